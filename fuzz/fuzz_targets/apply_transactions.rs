@@ -0,0 +1,103 @@
+//! Apply random transactions of the template runtime against a mock state.
+//!
+//! Each run starts from some random utxos in an opened block. Then it applies a sequence of
+//! transactions, either decoded from random bytes, or built around utxos that exist, so that they
+//! get past the executive's input checks and reach the constraint checkers. Applying must never
+//! panic, and a transaction that applies must have removed its inputs and stored its outputs.
+
+#![no_main]
+
+use arbitrary::Arbitrary;
+use libfuzzer_sys::fuzz_target;
+use parity_scale_codec::Decode;
+use tuxedo_core::types::{Input, InputMode, Output, OutputRef, TransactionVersion};
+use tuxedo_template_runtime::{Executive, OuterConstraintChecker, OuterVerifier, Transaction};
+use tuxedo_test_utils::{output_refs, utxo, BlockContext, MockState};
+
+#[derive(Arbitrary, Debug)]
+struct FuzzInput {
+    /// The height of the block that the transactions are applied in.
+    height: u16,
+    /// The utxos that exist before the first transaction.
+    utxos: Vec<(OutputRef, Output<OuterVerifier>)>,
+    /// The transactions to apply, in order.
+    extrinsics: Vec<Extrinsic>,
+}
+
+#[derive(Arbitrary, Debug)]
+enum Extrinsic {
+    /// Bytes that may or may not decode as a transaction.
+    Encoded(Vec<u8>),
+    /// A transaction whose inputs and peeks are picked among the utxos that exist, by index.
+    /// The constraint checker is decoded from random bytes.
+    Structured {
+        inputs: Vec<(u8, Vec<u8>, InputMode)>,
+        peeks: Vec<u8>,
+        outputs: Vec<Output<OuterVerifier>>,
+        checker: Vec<u8>,
+    },
+}
+
+impl Extrinsic {
+    /// The transaction, if there is one, given the output refs that have been seen so far.
+    fn transaction(self, known: &[OutputRef]) -> Option<Transaction> {
+        match self {
+            Extrinsic::Encoded(bytes) => Transaction::decode(&mut &bytes[..]).ok(),
+            Extrinsic::Structured {
+                inputs,
+                peeks,
+                outputs,
+                checker,
+            } => {
+                let pick = |i: u8| known.get(i as usize % known.len().max(1)).cloned();
+                Some(Transaction {
+                    version: TransactionVersion::LATEST,
+                    inputs: inputs
+                        .into_iter()
+                        .filter_map(|(i, redeemer, mode)| {
+                            pick(i).map(|output_ref| Input {
+                                output_ref,
+                                redeemer,
+                                mode,
+                            })
+                        })
+                        .collect(),
+                    peeks: peeks.into_iter().filter_map(pick).collect(),
+                    outputs,
+                    checker: OuterConstraintChecker::decode(&mut &checker[..]).ok()?,
+                    memo: None,
+                    valid_until: None,
+                })
+            }
+        }
+    }
+}
+
+fuzz_target!(|input: FuzzInput| {
+    let mut known: Vec<OutputRef> = input.utxos.iter().map(|(r, _)| r.clone()).collect();
+
+    let mut state = MockState::<OuterVerifier, OuterConstraintChecker>::default()
+        .with_block(BlockContext::at_height(u32::from(input.height).max(1)));
+    for (output_ref, output) in input.utxos {
+        state = state.with_utxo(output_ref, output);
+    }
+
+    state.build().execute_with(|| {
+        for extrinsic in input.extrinsics {
+            let Some(transaction) = extrinsic.transaction(&known) else {
+                continue;
+            };
+            let refs = output_refs(&transaction);
+
+            if Executive::apply_tuxedo_transaction(transaction.clone()).is_ok() {
+                for input in &transaction.inputs {
+                    assert!(utxo::<OuterVerifier>(&input.output_ref).is_none());
+                }
+                for (output_ref, output) in refs.iter().zip(&transaction.outputs) {
+                    assert_eq!(utxo::<OuterVerifier>(output_ref).as_ref(), Some(output));
+                }
+                known.extend(refs);
+            }
+        }
+    });
+});
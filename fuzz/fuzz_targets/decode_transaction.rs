@@ -0,0 +1,20 @@
+//! Decode random bytes as a transaction of the template runtime.
+//!
+//! Decoding must never panic, and whatever decodes must encode to something that decodes to the
+//! same transaction again. The length prefix of a transaction is skipped while decoding, so the
+//! re-encoded bytes may differ from the input, but the transaction they describe may not.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use parity_scale_codec::{Decode, Encode};
+use tuxedo_template_runtime::Transaction;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(transaction) = Transaction::decode(&mut &data[..]) {
+        let encoded = transaction.encode();
+        let decoded =
+            Transaction::decode(&mut &encoded[..]).expect("re-encoded transaction decodes");
+        assert_eq!(decoded, transaction);
+    }
+});
@@ -1,5 +1,6 @@
 use node_template_runtime::genesis::*;
 use sc_service::ChainType;
+use std::{fs::File, path::PathBuf};
 
 // The URL for the telemetry server.
 // const STAGING_TELEMETRY_URL: &str = "wss://telemetry.polkadot.io/submit/";
@@ -74,3 +75,77 @@ pub fn local_testnet_config() -> Result<ChainSpec, String> {
         None,
     ))
 }
+
+/// A live chain whose initial state is described by the given genesis sections.
+pub fn custom_config(name: &str, id: &str, sections: GenesisSections) -> ChainSpec {
+    ChainSpec::from_genesis(
+        // Name
+        name,
+        // ID
+        id,
+        ChainType::Live,
+        // TuxedoGenesisConfig
+        move || genesis_config(&sections),
+        // Bootnodes
+        vec![],
+        // Telemetry
+        None,
+        // Protocol ID
+        None,
+        None,
+        // Properties
+        None,
+        // Extensions
+        None,
+    )
+}
+
+/// Build a chain spec whose genesis utxos are declared in a JSON genesis file, so that custom
+/// networks can be launched without recompiling the runtime.
+#[derive(Debug, clap::Parser)]
+pub struct BuildGenesisSpecCmd {
+    /// The JSON genesis file that declares the initial state of each piece.
+    /// Pieces that it leaves out start with no utxos.
+    #[arg(required_unless_present = "template")]
+    pub genesis: Option<PathBuf>,
+
+    /// The human readable name of the chain.
+    #[arg(long, default_value = "Custom")]
+    pub name: String,
+
+    /// The id of the chain.
+    #[arg(long, default_value = "custom")]
+    pub id: String,
+
+    /// Output the genesis state as raw storage, the way that the node stores it.
+    #[arg(long)]
+    pub raw: bool,
+
+    /// Print the genesis file of the development chain, as a starting point for a custom one,
+    /// instead of building a chain spec.
+    #[arg(long)]
+    pub template: bool,
+}
+
+impl BuildGenesisSpecCmd {
+    /// Print the chain spec, or the template genesis file.
+    pub fn run(&self) -> sc_cli::Result<()> {
+        if self.template {
+            let template = serde_json::to_string_pretty(&development_genesis_sections())
+                .map_err(|e| e.to_string())?;
+            println!("{template}");
+            return Ok(());
+        }
+
+        let path = self
+            .genesis
+            .as_ref()
+            .expect("clap requires a genesis file unless printing the template; qed");
+        let sections: GenesisSections = serde_json::from_reader(File::open(path)?)
+            .map_err(|e| format!("Invalid genesis file: {e}"))?;
+
+        let spec = custom_config(&self.name, &self.id, sections);
+        println!("{}", spec.as_json(self.raw)?);
+        Ok(())
+    }
+}
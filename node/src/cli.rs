@@ -7,6 +7,22 @@ pub struct Cli {
 
     #[clap(flatten)]
     pub run: RunCmd,
+
+    /// Keep an index of the utxo set by owner, and serve it through the
+    /// `tuxedo_getUtxosByOwner` and `tuxedo_getBalance` RPC methods.
+    #[clap(long)]
+    pub index_owners: bool,
+
+    /// Keep an index of finalized transactions by hash, and serve them with proofs of
+    /// inclusion through the `tuxedo_getTransaction` RPC method.
+    #[clap(long)]
+    pub index_transactions: bool,
+
+    /// Seal a block as soon as a transaction enters the pool, and whenever the
+    /// `engine_createBlock` RPC method is called, instead of waiting for Aura slots.
+    /// Blocks are finalized as soon as they are sealed. Meant for a single development node.
+    #[clap(long)]
+    pub dev_instant_seal: bool,
 }
 
 #[derive(Debug, clap::Subcommand)]
@@ -18,6 +34,9 @@ pub enum Subcommand {
     /// Build a chain specification.
     BuildSpec(sc_cli::BuildSpecCmd),
 
+    /// Build a chain specification from a JSON file that declares the genesis utxos.
+    BuildGenesisSpec(crate::chain_spec::BuildGenesisSpecCmd),
+
     /// Validate blocks.
     CheckBlock(sc_cli::CheckBlockCmd),
 
@@ -27,9 +46,15 @@ pub enum Subcommand {
     /// Export the state of a given block into a chain spec.
     ExportState(sc_cli::ExportStateCmd),
 
+    /// Export the utxo set at a given block into a snapshot file.
+    ExportUtxos(crate::snapshot::ExportUtxosCmd),
+
     /// Import blocks.
     ImportBlocks(sc_cli::ImportBlocksCmd),
 
+    /// Bootstrap a fresh node from a utxo set snapshot.
+    ImportUtxos(crate::snapshot::ImportUtxosCmd),
+
     /// Remove the whole chain.
     PurgeChain(sc_cli::PurgeChainCmd),
 
@@ -39,10 +64,23 @@ pub enum Subcommand {
     /// Db meta columns information.
     ChainInfo(sc_cli::ChainInfoCmd),
 
+    /// Measure the weights of the runtime's verifiers and constraint checkers,
+    /// and print them as Rust constants.
+    #[cfg(feature = "runtime-benchmarks")]
+    Benchmark(BenchmarkCmd),
+
     /// Custom -- extend it as you wish.
     Custom(CustomCommand),
 }
 
+#[cfg(feature = "runtime-benchmarks")]
+#[derive(Debug, clap::Parser)]
+pub struct BenchmarkCmd {
+    /// How many times to run each case. The median time is reported.
+    #[clap(long, default_value_t = tuxedo_core::benchmarking::DEFAULT_REPEATS)]
+    pub repeats: u32,
+}
+
 #[derive(Debug, clap::Parser)]
 pub struct CustomCommand {
     /// The salt to use in the transaction. If none is supplied, a "random" one will be chosen
@@ -53,6 +53,7 @@ pub fn run() -> sc_cli::Result<()> {
             let runner = cli.create_runner(cmd)?;
             runner.sync_run(|config| cmd.run(config.chain_spec, config.network))
         }
+        Some(Subcommand::BuildGenesisSpec(cmd)) => cmd.run(),
         Some(Subcommand::CheckBlock(cmd)) => {
             let runner = cli.create_runner(cmd)?;
             runner.async_run(|config| {
@@ -87,6 +88,28 @@ pub fn run() -> sc_cli::Result<()> {
                 Ok((cmd.run(client, config.chain_spec), task_manager))
             })
         }
+        Some(Subcommand::ExportUtxos(cmd)) => {
+            let runner = cli.create_runner(cmd)?;
+            runner.async_run(|config| {
+                let PartialComponents {
+                    client,
+                    task_manager,
+                    ..
+                } = service::new_partial(&config)?;
+                Ok((cmd.run(client), task_manager))
+            })
+        }
+        Some(Subcommand::ImportUtxos(cmd)) => {
+            let runner = cli.create_runner(cmd)?;
+            runner.async_run(|config| {
+                let PartialComponents {
+                    client,
+                    task_manager,
+                    ..
+                } = service::new_partial(&config)?;
+                Ok((cmd.run(client), task_manager))
+            })
+        }
         Some(Subcommand::ImportBlocks(cmd)) => {
             let runner = cli.create_runner(cmd)?;
             runner.async_run(|config| {
@@ -127,13 +150,23 @@ pub fn run() -> sc_cli::Result<()> {
                 )
             })
         }
+        #[cfg(feature = "runtime-benchmarks")]
+        Some(Subcommand::Benchmark(cmd)) => {
+            let results = Runtime::benchmarks(cmd.repeats);
+            print!("{}", tuxedo_core::benchmarking::render(&results));
+            Ok(())
+        }
         Some(Subcommand::Custom(_)) => {
             todo!()
         }
         None => {
             let runner = cli.create_runner(&cli.run)?;
+            let index_owners = cli.index_owners;
+            let index_transactions = cli.index_transactions;
+            let instant_seal = cli.dev_instant_seal;
             runner.run_node_until_exit(|config| async move {
-                service::new_full(config).map_err(sc_cli::Error::Service)
+                service::new_full(config, index_owners, index_transactions, instant_seal)
+                    .map_err(sc_cli::Error::Service)
             })
         }
     }
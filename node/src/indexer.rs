@@ -0,0 +1,213 @@
+//! An optional index of the UTXO set by owner.
+//!
+//! Wallets normally find their coins by syncing every block and looking for outputs that they own.
+//! A node that runs with `--index-owners` does that work once for everybody, and serves the results
+//! through the `tuxedo_getUtxosByOwner` and `tuxedo_getBalance` RPC methods, so that light wallets
+//! can skip the sync.
+//!
+//! The index starts from the UTXO set in the state of the finalized block when the node starts,
+//! and then follows finality by replaying the transactions of each newly finalized block. Only
+//! finalized blocks are indexed, so the index never has to deal with reorgs, and it may lag the
+//! best block by a few blocks. An output belongs to every owner that its verifier names, so the
+//! outputs of a multisignature show up for each of its signatories.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    sync::{Arc, RwLock},
+};
+
+use crate::{service::FullClient, snapshot::read_utxo_storage};
+use futures::StreamExt;
+use jsonrpsee::{core::RpcResult, proc_macros::rpc};
+use node_template_runtime::{money::Coin, OuterVerifier, Output, Transaction};
+use parity_scale_codec::{Decode, DecodeAll, Encode};
+use sc_client_api::{BlockBackend, BlockchainEvents};
+use sp_blockchain::HeaderBackend;
+use sp_core::H256;
+use sp_runtime::traits::{BlakeTwo256, Block as BlockT, Hash, Header as HeaderT};
+use tuxedo_core::{
+    types::OutputRef,
+    verifier::{Ed25519SigCheck, Proxy, SigCheck, ThresholdMultiSignature},
+};
+
+/// The owners that the given verifier names.
+fn owners(verifier: &OuterVerifier) -> Vec<H256> {
+    match verifier {
+        OuterVerifier::SigCheck(SigCheck { owner_pubkey })
+        | OuterVerifier::Ed25519SigCheck(Ed25519SigCheck { owner_pubkey }) => vec![*owner_pubkey],
+        OuterVerifier::ThresholdMultiSignature(ThresholdMultiSignature { signatories, .. }) => {
+            signatories.clone()
+        }
+        OuterVerifier::Proxy(Proxy { owner, delegates }) => std::iter::once(*owner)
+            .chain(delegates.iter().map(|delegate| delegate.pubkey))
+            .collect(),
+        OuterVerifier::UpForGrabs(_) => Vec::new(),
+    }
+}
+
+/// The unspent outputs of the UTXO set, by owner.
+#[derive(Default)]
+pub struct OwnerIndex {
+    /// Every indexed output.
+    utxos: BTreeMap<OutputRef, Output>,
+    /// The refs of the outputs that each owner owns.
+    by_owner: BTreeMap<H256, BTreeSet<OutputRef>>,
+}
+
+impl OwnerIndex {
+    /// The unspent outputs that the given owner owns.
+    pub fn utxos(&self, owner: &H256) -> Vec<(OutputRef, Output)> {
+        self.by_owner
+            .get(owner)
+            .into_iter()
+            .flatten()
+            .filter_map(|output_ref| {
+                let output = self.utxos.get(output_ref)?;
+                Some((output_ref.clone(), output.clone()))
+            })
+            .collect()
+    }
+
+    /// The total value of the coins that the given owner owns.
+    pub fn balance(&self, owner: &H256) -> u128 {
+        self.utxos(owner)
+            .iter()
+            .filter_map(|(_, output)| output.payload.extract::<Coin<0>>().ok())
+            .fold(0, |total, coin| total.saturating_add(coin.0))
+    }
+
+    fn insert(&mut self, output_ref: OutputRef, output: Output) {
+        for owner in owners(&output.verifier) {
+            self.by_owner
+                .entry(owner)
+                .or_default()
+                .insert(output_ref.clone());
+        }
+        self.utxos.insert(output_ref, output);
+    }
+
+    fn remove(&mut self, output_ref: &OutputRef) {
+        let Some(output) = self.utxos.remove(output_ref) else {
+            return;
+        };
+        for owner in owners(&output.verifier) {
+            if let Some(refs) = self.by_owner.get_mut(&owner) {
+                refs.remove(output_ref);
+                if refs.is_empty() {
+                    self.by_owner.remove(&owner);
+                }
+            }
+        }
+    }
+
+    /// Update the index as the executive updates the UTXO set when it applies the transaction.
+    fn apply(&mut self, transaction: &Transaction) {
+        for input in &transaction.inputs {
+            self.remove(&input.output_ref);
+        }
+
+        let tx_hash = BlakeTwo256::hash_of(&transaction.encode());
+        for (index, output) in transaction.outputs.iter().enumerate() {
+            let output_ref = OutputRef {
+                tx_hash,
+                index: index as u32,
+            };
+            self.insert(output_ref, output.clone());
+        }
+    }
+}
+
+/// An owner index that is shared between the task that keeps it up to date and the RPC methods.
+pub type SharedOwnerIndex = Arc<RwLock<OwnerIndex>>;
+
+/// Build the index from the state of the finalized block, and then keep it up to date as more
+/// blocks are finalized. This runs until the node shuts down.
+pub async fn run(client: Arc<FullClient>, index: SharedOwnerIndex) {
+    // Subscribe before reading the state, so that no finalized block is missed in between.
+    let mut finality_notifications = client.finality_notification_stream();
+
+    let info = client.info();
+    let mut indexed_number = info.finalized_number;
+    match read_utxo_storage(&client, info.finalized_hash) {
+        Ok(pairs) => {
+            let mut index = index.write().expect("owner index lock is not poisoned");
+            for (key, value) in pairs {
+                let (Ok(output_ref), Ok(output)) = (
+                    OutputRef::decode_all(&mut &key[..]),
+                    Output::decode_all(&mut &value[..]),
+                ) else {
+                    continue;
+                };
+                index.insert(output_ref, output);
+            }
+        }
+        Err(e) => log::error!("Unable to read the utxo set to index it: {e}"),
+    }
+
+    while let Some(notification) = finality_notifications.next().await {
+        for hash in notification
+            .tree_route
+            .iter()
+            .chain(std::iter::once(&notification.hash))
+        {
+            let Ok(Some(block)) = client.block(*hash) else {
+                log::error!("Unable to fetch finalized block {hash} to index it");
+                continue;
+            };
+            let number = *block.block.header().number();
+            if number <= indexed_number {
+                continue;
+            }
+
+            let mut index = index.write().expect("owner index lock is not poisoned");
+            for extrinsic in block.block.extrinsics() {
+                if let Ok(transaction) = Transaction::decode(&mut &extrinsic.encode()[..]) {
+                    index.apply(&transaction);
+                }
+            }
+            indexed_number = number;
+        }
+    }
+}
+
+/// RPC methods for finding the utxos of an owner without syncing the chain.
+#[rpc(server, namespace = "tuxedo")]
+pub trait OwnerIndexApi {
+    /// The unspent outputs that the given public key owns, as of the last finalized block.
+    #[method(name = "getUtxosByOwner")]
+    fn get_utxos_by_owner(&self, owner: H256) -> RpcResult<Vec<(OutputRef, Output)>>;
+
+    /// The total value of the coins that the given public key owns, as of the last finalized block.
+    #[method(name = "getBalance")]
+    fn get_balance(&self, owner: H256) -> RpcResult<u128>;
+}
+
+/// Serves the owner index RPC methods from the shared index.
+pub struct OwnerIndexRpc {
+    index: SharedOwnerIndex,
+}
+
+impl OwnerIndexRpc {
+    /// Create new owner index RPC methods backed by the given index.
+    pub fn new(index: SharedOwnerIndex) -> Self {
+        Self { index }
+    }
+}
+
+impl OwnerIndexApiServer for OwnerIndexRpc {
+    fn get_utxos_by_owner(&self, owner: H256) -> RpcResult<Vec<(OutputRef, Output)>> {
+        Ok(self
+            .index
+            .read()
+            .expect("owner index lock is not poisoned")
+            .utxos(&owner))
+    }
+
+    fn get_balance(&self, owner: H256) -> RpcResult<u128> {
+        Ok(self
+            .index
+            .read()
+            .expect("owner index lock is not poisoned")
+            .balance(&owner))
+    }
+}
@@ -1,3 +1,6 @@
 pub mod chain_spec;
+pub mod indexer;
 pub mod rpc;
 pub mod service;
+pub mod snapshot;
+pub mod transaction_index;
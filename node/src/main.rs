@@ -4,8 +4,10 @@
 mod chain_spec;
 mod cli;
 mod command;
+mod indexer;
 mod rpc;
 mod service;
+mod snapshot;
 
 fn main() -> sc_cli::Result<()> {
     command::run()
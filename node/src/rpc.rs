@@ -7,12 +7,47 @@
 
 use std::sync::Arc;
 
-use jsonrpsee::RpcModule;
-use node_template_runtime::opaque::Block;
-use sc_transaction_pool_api::TransactionPool;
+use futures::{channel::mpsc, future, FutureExt, StreamExt};
+use jsonrpsee::{
+    core::RpcResult,
+    proc_macros::rpc,
+    types::{
+        error::{CallError, ErrorObject},
+        SubscriptionResult,
+    },
+    RpcModule, SubscriptionSink,
+};
+use node_template_runtime::{
+    opaque::Block, OuterConstraintChecker, OuterVerifier, Output, Transaction,
+};
+use parity_scale_codec::{Decode, Encode};
+use sc_client_api::{BlockBackend, ProofProvider};
+use sc_consensus_manual_seal::{
+    rpc::{ManualSeal, ManualSealApiServer},
+    EngineCommand,
+};
+use sc_rpc::SubscriptionTaskExecutor;
+use sc_transaction_pool_api::{
+    TransactionPool, TransactionSource, TransactionStatus as PoolTransactionStatus,
+};
 use sp_api::ProvideRuntimeApi;
 use sp_block_builder::BlockBuilder;
 use sp_blockchain::{Error as BlockChainError, HeaderBackend, HeaderMetadata};
+use sp_core::Bytes;
+use sp_runtime::{
+    traits::Block as BlockT, transaction_validity::TransactionPriority, OpaqueExtrinsic,
+};
+use tuxedo_core::{
+    events::{EventRecord, TuxedoEventsApi},
+    metadata::{RuntimeIntrospection, TuxedoIntrospectionApi},
+    types::{DetailedError, OutputRef, TransactionStatus, TuxedoDryRunApi},
+    utxo_set::{utxo_child_info, TuxedoUtxoApi, TuxedoUtxoProofApi, UtxoProof},
+};
+
+use crate::{
+    indexer::{OwnerIndexApiServer, OwnerIndexRpc, SharedOwnerIndex},
+    transaction_index::{SharedTransactionIndex, TransactionIndexApiServer, TransactionIndexRpc},
+};
 
 pub use sc_rpc_api::DenyUnsafe;
 
@@ -24,26 +59,403 @@ pub struct FullDeps<C, P> {
     pub pool: Arc<P>,
     /// Whether to deny unsafe calls
     pub deny_unsafe: DenyUnsafe,
+    /// Runs the tasks that feed subscriptions.
+    pub subscription_executor: SubscriptionTaskExecutor,
+    /// The index of the utxo set by owner, if the node keeps one.
+    pub owner_index: Option<SharedOwnerIndex>,
+    /// The index of finalized transactions by hash, if the node keeps one.
+    pub transaction_index: Option<SharedTransactionIndex>,
+    /// Sends commands to the instant seal task, if the node seals blocks that way.
+    pub command_sink: Option<mpsc::Sender<EngineCommand<<Block as BlockT>::Hash>>>,
 }
 
 /// Instantiate all full RPC extensions.
 pub fn create_full<C, P>(
-    _deps: FullDeps<C, P>,
+    deps: FullDeps<C, P>,
 ) -> Result<RpcModule<()>, Box<dyn std::error::Error + Send + Sync>>
 where
     C: ProvideRuntimeApi<Block>
         + HeaderBackend<Block>
         + HeaderMetadata<Block, Error = BlockChainError>
+        + ProofProvider<Block>
+        + BlockBackend<Block>
         + Send
         + Sync
         + 'static,
     C::Api: BlockBuilder<Block>,
-    P: TransactionPool + 'static,
+    C::Api: TuxedoEventsApi<Block>,
+    C::Api: TuxedoUtxoApi<Block, OuterVerifier>,
+    C::Api: TuxedoUtxoProofApi<Block>,
+    C::Api: TuxedoDryRunApi<Block, OuterVerifier, OuterConstraintChecker>,
+    C::Api: TuxedoIntrospectionApi<Block>,
+    P: TransactionPool<Block = Block> + 'static,
 {
-    let module = RpcModule::new(());
+    let mut module = RpcModule::new(());
+    module.merge(Events::new(deps.client.clone()).into_rpc())?;
+    module.merge(Utxos::new(deps.client.clone()).into_rpc())?;
+    module.merge(Introspection::new(deps.client.clone()).into_rpc())?;
+    module.merge(DryRun::new(deps.client.clone()).into_rpc())?;
+    if let Some(transaction_index) = deps.transaction_index {
+        module
+            .merge(TransactionIndexRpc::new(deps.client.clone(), transaction_index).into_rpc())?;
+    }
+    module.merge(Submit::new(deps.client, deps.pool, deps.subscription_executor).into_rpc())?;
+    if let Some(owner_index) = deps.owner_index {
+        module.merge(OwnerIndexRpc::new(owner_index).into_rpc())?;
+    }
+    if let Some(command_sink) = deps.command_sink {
+        module.merge(ManualSeal::new(command_sink).into_rpc())?;
+    }
     // Extend this RPC with a custom API by using the following syntax.
     // `YourRpcStruct` should have a reference to a client, which is needed
     // to call into the runtime.
     // `module.merge(YourRpcTrait::into_rpc(YourRpcStruct::new(ReferenceToClient, ...)))?;`
     Ok(module)
 }
+
+/// RPC methods for reading the events that transactions emit.
+#[rpc(server, namespace = "tuxedo")]
+pub trait EventsApi<BlockHash> {
+    /// The events emitted in the given block, or in the best block if none is given.
+    #[method(name = "events")]
+    fn events(&self, at: Option<BlockHash>) -> RpcResult<Vec<EventRecord>>;
+}
+
+/// Serves the events RPC methods by calling into the runtime.
+pub struct Events<C> {
+    client: Arc<C>,
+}
+
+impl<C> Events<C> {
+    /// Create new events RPC methods backed by the given client.
+    pub fn new(client: Arc<C>) -> Self {
+        Self { client }
+    }
+}
+
+impl<C> EventsApiServer<<Block as BlockT>::Hash> for Events<C>
+where
+    C: ProvideRuntimeApi<Block> + HeaderBackend<Block> + Send + Sync + 'static,
+    C::Api: TuxedoEventsApi<Block>,
+{
+    fn events(&self, at: Option<<Block as BlockT>::Hash>) -> RpcResult<Vec<EventRecord>> {
+        let at = at.unwrap_or_else(|| self.client.info().best_hash);
+        self.client
+            .runtime_api()
+            .events(at)
+            .map_err(|e| runtime_error("Unable to query events.", e))
+    }
+}
+
+/// RPC methods for reading the UTXO set.
+#[rpc(server, namespace = "tuxedo")]
+pub trait UtxoApi<BlockHash> {
+    /// The utxo at the given output ref, if it exists in the given block or the best block.
+    #[method(name = "utxo")]
+    fn utxo(&self, output_ref: OutputRef, at: Option<BlockHash>) -> RpcResult<Option<Output>>;
+
+    /// Whether a utxo exists at the given output ref in the given block or the best block.
+    #[method(name = "utxoExists")]
+    fn utxo_exists(&self, output_ref: OutputRef, at: Option<BlockHash>) -> RpcResult<bool>;
+
+    /// A proof that the utxo at the given output ref is, or is not, in the given block or the
+    /// best block. Light clients check it against the state root in that block's header.
+    #[method(name = "utxoProof")]
+    fn utxo_proof(&self, output_ref: OutputRef, at: Option<BlockHash>) -> RpcResult<UtxoProof>;
+}
+
+/// Serves the UTXO RPC methods by calling into the runtime.
+pub struct Utxos<C> {
+    client: Arc<C>,
+}
+
+impl<C> Utxos<C> {
+    /// Create new UTXO RPC methods backed by the given client.
+    pub fn new(client: Arc<C>) -> Self {
+        Self { client }
+    }
+}
+
+impl<C> UtxoApiServer<<Block as BlockT>::Hash> for Utxos<C>
+where
+    C: ProvideRuntimeApi<Block>
+        + HeaderBackend<Block>
+        + ProofProvider<Block>
+        + Send
+        + Sync
+        + 'static,
+    C::Api: TuxedoUtxoApi<Block, OuterVerifier>,
+    C::Api: TuxedoUtxoProofApi<Block>,
+{
+    fn utxo(
+        &self,
+        output_ref: OutputRef,
+        at: Option<<Block as BlockT>::Hash>,
+    ) -> RpcResult<Option<Output>> {
+        let at = at.unwrap_or_else(|| self.client.info().best_hash);
+        self.client
+            .runtime_api()
+            .utxo(at, output_ref)
+            .map_err(|e| runtime_error("Unable to query utxo.", e))
+    }
+
+    fn utxo_exists(
+        &self,
+        output_ref: OutputRef,
+        at: Option<<Block as BlockT>::Hash>,
+    ) -> RpcResult<bool> {
+        let at = at.unwrap_or_else(|| self.client.info().best_hash);
+        self.client
+            .runtime_api()
+            .utxo_exists(at, output_ref)
+            .map_err(|e| runtime_error("Unable to query utxo.", e))
+    }
+
+    fn utxo_proof(
+        &self,
+        output_ref: OutputRef,
+        at: Option<<Block as BlockT>::Hash>,
+    ) -> RpcResult<UtxoProof> {
+        let at = at.unwrap_or_else(|| self.client.info().best_hash);
+        // The runtime knows where in the utxo child trie the utxo would be kept, and the client
+        // can prove it, along with the path to the root of the child trie.
+        let key = self
+            .client
+            .runtime_api()
+            .utxo_storage_key(at, output_ref.clone())
+            .map_err(|e| runtime_error("Unable to query utxo.", e))?;
+        let proof = self
+            .client
+            .read_child_proof(at, &utxo_child_info(), &mut std::iter::once(&key[..]))
+            .map_err(|e| {
+                CallError::Custom(ErrorObject::owned(
+                    4,
+                    "Unable to prove utxo.",
+                    Some(e.to_string()),
+                ))
+            })?;
+
+        Ok(UtxoProof {
+            at,
+            output_ref,
+            nodes: proof.into_iter_nodes().collect(),
+        })
+    }
+}
+
+/// RPC methods for discovering what the runtime supports.
+#[rpc(server, namespace = "tuxedo")]
+pub trait IntrospectionApi<BlockHash> {
+    /// The verifiers, constraint checkers, and utxo types of the runtime at the given block or
+    /// the best block. The full types are described in the metadata of the same runtime.
+    #[method(name = "introspect")]
+    fn introspect(&self, at: Option<BlockHash>) -> RpcResult<RuntimeIntrospection>;
+}
+
+/// Serves the introspection RPC methods by calling into the runtime.
+pub struct Introspection<C> {
+    client: Arc<C>,
+}
+
+impl<C> Introspection<C> {
+    /// Create new introspection RPC methods backed by the given client.
+    pub fn new(client: Arc<C>) -> Self {
+        Self { client }
+    }
+}
+
+impl<C> IntrospectionApiServer<<Block as BlockT>::Hash> for Introspection<C>
+where
+    C: ProvideRuntimeApi<Block> + HeaderBackend<Block> + Send + Sync + 'static,
+    C::Api: TuxedoIntrospectionApi<Block>,
+{
+    fn introspect(&self, at: Option<<Block as BlockT>::Hash>) -> RpcResult<RuntimeIntrospection> {
+        let at = at.unwrap_or_else(|| self.client.info().best_hash);
+        self.client
+            .runtime_api()
+            .introspect(at)
+            .map_err(|e| runtime_error("Unable to introspect runtime.", e))
+    }
+}
+
+/// RPC methods for trying out transactions before submitting them.
+#[rpc(server, namespace = "tuxedo")]
+pub trait DryRunApi<BlockHash> {
+    /// Check the SCALE encoded transaction against the state of the given block or the best
+    /// block, without committing anything. Returns the priority the transaction would have,
+    /// or the reason that it would fail.
+    #[method(name = "dryRun")]
+    fn dry_run(
+        &self,
+        transaction: Bytes,
+        at: Option<BlockHash>,
+    ) -> RpcResult<Result<TransactionPriority, DetailedError>>;
+}
+
+/// Serves the dry run RPC methods by calling into the runtime.
+pub struct DryRun<C> {
+    client: Arc<C>,
+}
+
+impl<C> DryRun<C> {
+    /// Create new dry run RPC methods backed by the given client.
+    pub fn new(client: Arc<C>) -> Self {
+        Self { client }
+    }
+}
+
+impl<C> DryRunApiServer<<Block as BlockT>::Hash> for DryRun<C>
+where
+    C: ProvideRuntimeApi<Block> + HeaderBackend<Block> + Send + Sync + 'static,
+    C::Api: TuxedoDryRunApi<Block, OuterVerifier, OuterConstraintChecker>,
+{
+    fn dry_run(
+        &self,
+        transaction: Bytes,
+        at: Option<<Block as BlockT>::Hash>,
+    ) -> RpcResult<Result<TransactionPriority, DetailedError>> {
+        let transaction = Transaction::decode(&mut &transaction[..]).map_err(|e| {
+            CallError::Custom(ErrorObject::owned(
+                2,
+                "Unable to decode transaction.",
+                Some(e.to_string()),
+            ))
+        })?;
+        let at = at.unwrap_or_else(|| self.client.info().best_hash);
+        self.client
+            .runtime_api()
+            .dry_run(at, transaction)
+            .map_err(|e| runtime_error("Unable to dry run transaction.", e))
+    }
+}
+
+/// RPC methods for submitting transactions.
+#[rpc(server, namespace = "tuxedo")]
+pub trait SubmitApi {
+    /// Submit the SCALE encoded transaction to the pool, and follow it with a status update
+    /// whenever it moves along, until it is finalized or dropped.
+    #[subscription(
+        name = "submitAndWatch" => "transactionStatus",
+        unsubscribe = "unwatch",
+        item = TransactionStatus,
+    )]
+    fn submit_and_watch(&self, transaction: Bytes);
+}
+
+/// Serves the submission RPC methods by submitting to the pool.
+pub struct Submit<C, P> {
+    client: Arc<C>,
+    pool: Arc<P>,
+    executor: SubscriptionTaskExecutor,
+}
+
+impl<C, P> Submit<C, P> {
+    /// Create new submission RPC methods that submit to the given pool.
+    pub fn new(client: Arc<C>, pool: Arc<P>, executor: SubscriptionTaskExecutor) -> Self {
+        Self {
+            client,
+            pool,
+            executor,
+        }
+    }
+}
+
+impl<C, P> SubmitApiServer for Submit<C, P>
+where
+    C: HeaderBackend<Block> + Send + Sync + 'static,
+    P: TransactionPool<Block = Block> + 'static,
+{
+    fn submit_and_watch(
+        &self,
+        mut sink: SubscriptionSink,
+        transaction: Bytes,
+    ) -> SubscriptionResult {
+        // Make sure this is a Tuxedo transaction before the pool sees it.
+        let transaction = match Transaction::decode(&mut &transaction[..]) {
+            Ok(transaction) => transaction,
+            Err(e) => {
+                let _ = sink.reject(ErrorObject::owned(
+                    2,
+                    "Unable to decode transaction.",
+                    Some(e.to_string()),
+                ));
+                return Ok(());
+            }
+        };
+        let extrinsic = OpaqueExtrinsic::from_bytes(&transaction.encode())
+            .expect("a transaction is a valid opaque extrinsic; qed");
+
+        let best_hash = self.client.info().best_hash;
+        let submission =
+            self.pool
+                .submit_and_watch(best_hash, TransactionSource::External, extrinsic);
+        let client = self.client.clone();
+
+        let fut = async move {
+            let statuses = match submission.await {
+                Ok(statuses) => statuses,
+                Err(e) => {
+                    let _ = sink.reject(ErrorObject::owned(
+                        3,
+                        "Transaction was rejected.",
+                        Some(e.to_string()),
+                    ));
+                    return;
+                }
+            };
+            let statuses = statuses
+                .filter_map(move |status| future::ready(tuxedo_status(&*client, status)))
+                .boxed();
+            sink.pipe_from_stream(statuses).await;
+        };
+        self.executor
+            .spawn("tuxedo-submit-and-watch", Some("rpc"), fut.boxed());
+
+        Ok(())
+    }
+}
+
+/// Describe a status that the pool reports in Tuxedo's terms. Statuses that wallets do not
+/// care about, such as being broadcast to peers, are skipped.
+fn tuxedo_status<C, H>(
+    client: &C,
+    status: PoolTransactionStatus<H, <Block as BlockT>::Hash>,
+) -> Option<TransactionStatus>
+where
+    C: HeaderBackend<Block>,
+    H: std::fmt::Debug,
+{
+    let number = |hash| client.number(hash).ok().flatten().unwrap_or_default();
+
+    Some(match status {
+        PoolTransactionStatus::Future => TransactionStatus::InPool { ready: false },
+        PoolTransactionStatus::Ready => TransactionStatus::InPool { ready: true },
+        PoolTransactionStatus::Broadcast(_) => return None,
+        PoolTransactionStatus::InBlock((hash, _)) => TransactionStatus::InBlock {
+            hash,
+            number: number(hash),
+        },
+        PoolTransactionStatus::Retracted(hash) => TransactionStatus::Retracted { hash },
+        PoolTransactionStatus::Finalized((hash, _)) => TransactionStatus::Finalized {
+            hash,
+            number: number(hash),
+        },
+        PoolTransactionStatus::FinalityTimeout(_) => TransactionStatus::Dropped {
+            reason: String::from("its block was not finalized in time"),
+        },
+        PoolTransactionStatus::Usurped(by) => TransactionStatus::Dropped {
+            reason: format!("it was replaced by {by:?}"),
+        },
+        PoolTransactionStatus::Dropped => TransactionStatus::Dropped {
+            reason: String::from("the pool is full"),
+        },
+        PoolTransactionStatus::Invalid => TransactionStatus::Dropped {
+            reason: String::from("it became invalid"),
+        },
+    })
+}
+
+/// Report an error that occurred while calling into the runtime.
+fn runtime_error(message: &str, e: sp_api::ApiError) -> jsonrpsee::core::Error {
+    CallError::Custom(ErrorObject::owned(1, message, Some(e.to_string()))).into()
+}
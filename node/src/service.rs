@@ -1,15 +1,28 @@
 //! Service and ServiceFactory implementation. Specialized wrapper over substrate service.
 
-use crate::rpc;
-use node_template_runtime::{self, opaque::Block, RuntimeApi};
-use sc_client_api::BlockBackend;
-use sc_consensus_aura::{ImportQueueParams, SlotProportion, StartAuraParams};
+use crate::{indexer, rpc, transaction_index};
+use futures::{channel::mpsc, FutureExt, StreamExt};
+#[cfg(not(feature = "babe"))]
+use node_template_runtime::randomness_beacon;
+use node_template_runtime::{
+    self, opaque::Block, timestamp::TimestampConfig, Runtime, RuntimeApi, Transaction,
+};
+use parity_scale_codec::{Decode, Encode};
+use sc_client_api::{Backend, BlockBackend};
+use sc_consensus_aura::SlotProportion;
+#[cfg(not(feature = "babe"))]
+use sc_consensus_aura::{ImportQueueParams, StartAuraParams};
 use sc_consensus_grandpa::SharedVoterState;
+use sc_consensus_manual_seal::{EngineCommand, ManualSealParams};
 pub use sc_executor::NativeElseWasmExecutor;
 use sc_service::{error::Error as ServiceError, Configuration, TaskManager, WarpSyncParams};
 use sc_telemetry::{Telemetry, TelemetryWorker};
 use sc_transaction_pool_api::OffchainTransactionPoolFactory;
-use sp_consensus_aura::sr25519::AuthorityPair as AuraPair;
+#[cfg(not(feature = "babe"))]
+use sp_api::ProvideRuntimeApi;
+#[cfg(not(feature = "babe"))]
+use sp_consensus_aura::{sr25519::AuthorityPair as AuraPair, AuraApi};
+use sp_runtime::traits::Block as BlockT;
 use std::{sync::Arc, time::Duration};
 use tuxedo_core::genesis::TuxedoGenesisBlockBuilder;
 
@@ -32,6 +45,22 @@ pub(crate) type FullClient =
     sc_service::TFullClient<Block, RuntimeApi, NativeElseWasmExecutor<ExecutorDispatch>>;
 type FullBackend = sc_service::TFullBackend<Block>;
 type FullSelectChain = sc_consensus::LongestChain<FullBackend, Block>;
+type FullGrandpaBlockImport =
+    sc_consensus_grandpa::GrandpaBlockImport<FullBackend, Block, FullClient, FullSelectChain>;
+
+/// The block import that authored blocks go through. With the babe feature,
+/// BABE wraps the GRANDPA block import to track the epochs.
+#[cfg(not(feature = "babe"))]
+type ConsensusBlockImport = FullGrandpaBlockImport;
+#[cfg(feature = "babe")]
+type ConsensusBlockImport =
+    sc_consensus_babe::BabeBlockImport<Block, FullClient, FullGrandpaBlockImport>;
+
+/// What the block authoring task needs from the block import setup.
+#[cfg(not(feature = "babe"))]
+type ConsensusLink = sc_consensus_aura::SlotDuration;
+#[cfg(feature = "babe")]
+type ConsensusLink = sc_consensus_babe::BabeLink<Block>;
 
 /// The minimum period of blocks on which justifications will be
 /// imported and generated.
@@ -48,13 +77,9 @@ pub fn new_partial(
         sc_consensus::DefaultImportQueue<Block>,
         sc_transaction_pool::FullPool<Block, FullClient>,
         (
-            sc_consensus_grandpa::GrandpaBlockImport<
-                FullBackend,
-                Block,
-                FullClient,
-                FullSelectChain,
-            >,
+            ConsensusBlockImport,
             sc_consensus_grandpa::LinkHalf<Block, FullClient, FullSelectChain>,
+            ConsensusLink,
             Option<Telemetry>,
         ),
     >,
@@ -116,30 +141,76 @@ pub fn new_partial(
         telemetry.as_ref().map(|x| x.handle()),
     )?;
 
-    let slot_duration = sc_consensus_aura::slot_duration(&*client)?;
+    #[cfg(not(feature = "babe"))]
+    let (block_import, consensus_link, import_queue) = {
+        let slot_duration = sc_consensus_aura::slot_duration(&*client)?;
 
-    let import_queue =
-        sc_consensus_aura::import_queue::<AuraPair, _, _, _, _, _>(ImportQueueParams {
-            block_import: grandpa_block_import.clone(),
-            justification_import: Some(Box::new(grandpa_block_import.clone())),
-            client: client.clone(),
-            create_inherent_data_providers: move |_, ()| async move {
-                let timestamp = sp_timestamp::InherentDataProvider::from_system_time();
+        let import_queue = sc_consensus_aura::import_queue::<AuraPair, _, _, _, _, _>(
+            ImportQueueParams {
+                block_import: grandpa_block_import.clone(),
+                justification_import: Some(Box::new(grandpa_block_import.clone())),
+                client: client.clone(),
+                create_inherent_data_providers: move |_, ()| async move {
+                    let timestamp = sp_timestamp::InherentDataProvider::from_system_time();
 
-                let slot =
-					sp_consensus_aura::inherents::InherentDataProvider::from_timestamp_and_slot_duration(
-						*timestamp,
-						slot_duration,
-					);
+                    let slot =
+                        sp_consensus_aura::inherents::InherentDataProvider::from_timestamp_and_slot_duration(
+                            *timestamp,
+                            slot_duration,
+                        );
 
-                Ok((slot, timestamp))
+                    Ok((slot, timestamp))
+                },
+                spawner: &task_manager.spawn_essential_handle(),
+                registry: config.prometheus_registry(),
+                check_for_equivocation: Default::default(),
+                telemetry: telemetry.as_ref().map(|x| x.handle()),
+                compatibility_mode: Default::default(),
             },
-            spawner: &task_manager.spawn_essential_handle(),
-            registry: config.prometheus_registry(),
-            check_for_equivocation: Default::default(),
-            telemetry: telemetry.as_ref().map(|x| x.handle()),
-            compatibility_mode: Default::default(),
-        })?;
+        )?;
+
+        (grandpa_block_import, slot_duration, import_queue)
+    };
+
+    #[cfg(feature = "babe")]
+    let (block_import, consensus_link, import_queue) = {
+        let (babe_block_import, babe_link) = sc_consensus_babe::block_import(
+            sc_consensus_babe::configuration(&*client)?,
+            grandpa_block_import.clone(),
+            client.clone(),
+        )?;
+
+        let slot_duration = babe_link.config().slot_duration();
+
+        let (import_queue, _babe_worker_handle) = sc_consensus_babe::import_queue(
+            sc_consensus_babe::ImportQueueParams {
+                link: babe_link.clone(),
+                block_import: babe_block_import.clone(),
+                justification_import: Some(Box::new(grandpa_block_import)),
+                client: client.clone(),
+                select_chain: select_chain.clone(),
+                create_inherent_data_providers: move |_, ()| async move {
+                    let timestamp = sp_timestamp::InherentDataProvider::from_system_time();
+
+                    let slot =
+                        sp_consensus_babe::inherents::InherentDataProvider::from_timestamp_and_slot_duration(
+                            *timestamp,
+                            slot_duration,
+                        );
+
+                    Ok((slot, timestamp))
+                },
+                spawner: &task_manager.spawn_essential_handle(),
+                registry: config.prometheus_registry(),
+                telemetry: telemetry.as_ref().map(|x| x.handle()),
+                offchain_tx_pool_factory: OffchainTransactionPoolFactory::new(
+                    transaction_pool.clone(),
+                ),
+            },
+        )?;
+
+        (babe_block_import, babe_link, import_queue)
+    };
 
     Ok(sc_service::PartialComponents {
         client,
@@ -149,12 +220,44 @@ pub fn new_partial(
         keystore_container,
         select_chain,
         transaction_pool,
-        other: (grandpa_block_import, grandpa_link, telemetry),
+        other: (block_import, grandpa_link, consensus_link, telemetry),
     })
 }
 
-/// Builds a new service for a full client.
-pub fn new_full(config: Configuration) -> Result<TaskManager, ServiceError> {
+/// The timestamp for a block sealed on top of the given parent in instant seal mode.
+///
+/// Blocks may be sealed in quick succession, so when the current time is too close to the parent's
+/// timestamp for the timestamp piece to accept it, the block claims the earliest time that it
+/// accepts instead.
+fn instant_seal_timestamp(parent: &Block) -> sp_timestamp::InherentDataProvider {
+    let parent_time = parent
+        .extrinsics()
+        .iter()
+        .filter_map(|extrinsic| Transaction::decode(&mut &extrinsic.encode()[..]).ok())
+        .flat_map(|transaction| transaction.outputs)
+        .find_map(|output| {
+            output
+                .payload
+                .extract::<node_template_runtime::timestamp::Timestamp>()
+                .ok()
+        })
+        .map(|timestamp| timestamp.time)
+        .unwrap_or_default();
+
+    let earliest = parent_time + <Runtime as TimestampConfig>::MINIMUM_TIME_INTERVAL;
+    let now = sp_timestamp::Timestamp::current();
+    sp_timestamp::InherentDataProvider::new(now.max(earliest.into()))
+}
+
+/// Builds a new service for a full client. When asked to, it also indexes the utxo set by owner
+/// and finalized transactions by hash, and seals blocks instantly instead of in Aura slots, or
+/// BABE slots with the babe feature.
+pub fn new_full(
+    config: Configuration,
+    index_owners: bool,
+    index_transactions: bool,
+    instant_seal: bool,
+) -> Result<TaskManager, ServiceError> {
     let sc_service::PartialComponents {
         client,
         backend,
@@ -163,7 +266,7 @@ pub fn new_full(config: Configuration) -> Result<TaskManager, ServiceError> {
         keystore_container,
         select_chain,
         transaction_pool,
-        other: (block_import, grandpa_link, mut telemetry),
+        other: (block_import, grandpa_link, consensus_link, mut telemetry),
     } = new_partial(&config)?;
 
     let mut net_config = sc_network::config::FullNetworkConfiguration::new(&config.network);
@@ -200,22 +303,76 @@ pub fn new_full(config: Configuration) -> Result<TaskManager, ServiceError> {
             block_relay: None,
         })?;
 
+    if config.offchain_worker.enabled {
+        // Off-chain workers get local storage, HTTP requests, and a way to submit transactions.
+        task_manager.spawn_handle().spawn(
+            "offchain-workers-runner",
+            "offchain-worker",
+            sc_offchain::OffchainWorkers::new(sc_offchain::OffchainWorkerOptions {
+                runtime_api_provider: client.clone(),
+                is_validator: config.role.is_authority(),
+                keystore: Some(keystore_container.keystore()),
+                offchain_db: backend.offchain_storage(),
+                transaction_pool: Some(OffchainTransactionPoolFactory::new(
+                    transaction_pool.clone(),
+                )),
+                network_provider: network.clone(),
+                enable_http_requests: true,
+                custom_extensions: |_| vec![],
+            })
+            .run(client.clone(), task_manager.spawn_handle())
+            .boxed(),
+        );
+    }
+
     let role = config.role.clone();
     let force_authoring = config.force_authoring;
     let backoff_authoring_blocks: Option<()> = None;
     let name = config.network.node_name.clone();
-    let enable_grandpa = !config.disable_grandpa;
+    // Instantly sealed blocks are finalized as they are sealed.
+    let enable_grandpa = !config.disable_grandpa && !instant_seal;
     let prometheus_registry = config.prometheus_registry().cloned();
 
+    let owner_index = index_owners.then(|| {
+        let index = indexer::SharedOwnerIndex::default();
+        task_manager.spawn_handle().spawn(
+            "owner-indexer",
+            None,
+            indexer::run(client.clone(), index.clone()),
+        );
+        index
+    });
+
+    let transaction_index = index_transactions.then(|| {
+        let index = transaction_index::SharedTransactionIndex::default();
+        task_manager.spawn_handle().spawn(
+            "transaction-indexer",
+            None,
+            transaction_index::run(client.clone(), index.clone()),
+        );
+        index
+    });
+
+    let (command_sink, rpc_commands) = if instant_seal {
+        let (sink, stream) = mpsc::channel(1024);
+        (Some(sink), Some(stream))
+    } else {
+        (None, None)
+    };
+
     let rpc_extensions_builder = {
         let client = client.clone();
         let pool = transaction_pool.clone();
 
-        Box::new(move |deny_unsafe, _| {
+        Box::new(move |deny_unsafe, subscription_executor| {
             let deps = rpc::FullDeps {
                 client: client.clone(),
                 pool: pool.clone(),
                 deny_unsafe,
+                subscription_executor,
+                owner_index: owner_index.clone(),
+                transaction_index: transaction_index.clone(),
+                command_sink: command_sink.clone(),
             };
             rpc::create_full(deps).map_err(Into::into)
         })
@@ -236,7 +393,7 @@ pub fn new_full(config: Configuration) -> Result<TaskManager, ServiceError> {
         telemetry: telemetry.as_mut(),
     })?;
 
-    if role.is_authority() {
+    if let Some(rpc_commands) = rpc_commands {
         let proposer_factory = sc_basic_authorship::ProposerFactory::new(
             task_manager.spawn_handle(),
             client.clone(),
@@ -245,16 +402,152 @@ pub fn new_full(config: Configuration) -> Result<TaskManager, ServiceError> {
             telemetry.as_ref().map(|x| x.handle()),
         );
 
-        let slot_duration = sc_consensus_aura::slot_duration(&*client)?;
+        // Seal a block whenever a transaction is imported into the pool, as well as on request.
+        let pool_commands =
+            transaction_pool
+                .import_notification_stream()
+                .map(|_| EngineCommand::SealNewBlock {
+                    create_empty: false,
+                    finalize: true,
+                    parent_hash: None,
+                    sender: None,
+                });
+        let commands_stream = futures::stream::select(rpc_commands, pool_commands);
         let client_for_cidp = client.clone();
 
-        let aura = sc_consensus_aura::start_aura::<AuraPair, _, _, _, _, _, _, _, _, _, _>(
-            StartAuraParams {
-                slot_duration,
+        let instant_seal = sc_consensus_manual_seal::run_manual_seal(ManualSealParams {
+            block_import: client.clone(),
+            env: proposer_factory,
+            client,
+            pool: transaction_pool.clone(),
+            commands_stream,
+            select_chain,
+            // There is no Aura digest, so sealed blocks have no author and mint no block reward.
+            consensus_data_provider: None,
+            create_inherent_data_providers: move |parent_hash, ()| {
+                let maybe_parent_block = client_for_cidp.clone().block(parent_hash);
+
+                async move {
+                    let parent_block = maybe_parent_block?
+                        .ok_or(sp_blockchain::Error::UnknownBlock(parent_hash.to_string()))?
+                        .block;
+
+                    let timestamp = instant_seal_timestamp(&parent_block);
+                    let parent_idp =
+                        tuxedo_core::inherents::ParentBlockInherentDataProvider(parent_block);
+
+                    Ok((parent_idp, timestamp))
+                }
+            },
+        });
+
+        // the instant seal task is considered essential, i.e. if it
+        // fails we take down the service with it.
+        task_manager.spawn_essential_handle().spawn_blocking(
+            "instant-seal",
+            Some("block-authoring"),
+            instant_seal,
+        );
+    } else if role.is_authority() {
+        let proposer_factory = sc_basic_authorship::ProposerFactory::new(
+            task_manager.spawn_handle(),
+            client.clone(),
+            transaction_pool.clone(),
+            prometheus_registry.as_ref(),
+            telemetry.as_ref().map(|x| x.handle()),
+        );
+
+        let client_for_cidp = client.clone();
+
+        #[cfg(not(feature = "babe"))]
+        {
+            let slot_duration = consensus_link;
+            let keystore_for_cidp = keystore_container.keystore();
+
+            let aura = sc_consensus_aura::start_aura::<AuraPair, _, _, _, _, _, _, _, _, _, _>(
+                StartAuraParams {
+                    slot_duration,
+                    client,
+                    select_chain,
+                    block_import,
+                    proposer_factory,
+                    create_inherent_data_providers: move |parent_hash, ()| {
+                        let maybe_parent_block = client_for_cidp.clone().block(parent_hash);
+                        let maybe_authorities =
+                            client_for_cidp.runtime_api().authorities(parent_hash);
+                        let keystore = keystore_for_cidp.clone();
+
+                        async move {
+                            let parent_block = maybe_parent_block?
+                                .ok_or(sp_blockchain::Error::UnknownBlock(parent_hash.to_string()))?
+                                .block;
+
+                            let parent_idp =
+                                tuxedo_core::inherents::ParentBlockInherentDataProvider(
+                                    parent_block,
+                                );
+                            let timestamp = sp_timestamp::InherentDataProvider::from_system_time();
+
+                            let slot =
+                                sp_consensus_aura::inherents::InherentDataProvider::from_timestamp_and_slot_duration(
+                                    *timestamp,
+                                    slot_duration,
+                                );
+
+                            // Sign the randomness beacon's VRF input with the key of the authority
+                            // that is due to author this slot, if this node holds it.
+                            let authorities = maybe_authorities?;
+                            let slot_number = **slot;
+                            let author = slot_number
+                                .checked_rem(authorities.len() as u64)
+                                .and_then(|index| authorities.get(index as usize));
+                            let beacon = match author {
+                                Some(author) => randomness_beacon::InherentDataProvider::new(
+                                    &*keystore,
+                                    sp_core::crypto::key_types::AURA,
+                                    sp_core::H256::from_slice(author.as_ref()),
+                                    slot_number,
+                                    parent_hash,
+                                ),
+                                None => randomness_beacon::InherentDataProvider::empty(),
+                            };
+
+                            Ok((slot, parent_idp, timestamp, beacon))
+                        }
+                    },
+                    force_authoring,
+                    backoff_authoring_blocks,
+                    keystore: keystore_container.keystore(),
+                    sync_oracle: sync_service.clone(),
+                    justification_sync_link: sync_service.clone(),
+                    block_proposal_slot_portion: SlotProportion::new(2f32 / 3f32),
+                    max_block_proposal_slot_portion: None,
+                    telemetry: telemetry.as_ref().map(|x| x.handle()),
+                    compatibility_mode: Default::default(),
+                },
+            )?;
+
+            // the AURA authoring task is considered essential, i.e. if it
+            // fails we take down the service with it.
+            task_manager.spawn_essential_handle().spawn_blocking(
+                "aura",
+                Some("block-authoring"),
+                aura,
+            );
+        }
+
+        #[cfg(feature = "babe")]
+        {
+            let slot_duration = consensus_link.config().slot_duration();
+
+            let babe = sc_consensus_babe::start_babe(sc_consensus_babe::BabeParams {
+                keystore: keystore_container.keystore(),
                 client,
                 select_chain,
+                env: proposer_factory,
                 block_import,
-                proposer_factory,
+                sync_oracle: sync_service.clone(),
+                justification_sync_link: sync_service.clone(),
                 create_inherent_data_providers: move |parent_hash, ()| {
                     let maybe_parent_block = client_for_cidp.clone().block(parent_hash);
 
@@ -268,7 +561,7 @@ pub fn new_full(config: Configuration) -> Result<TaskManager, ServiceError> {
                         let timestamp = sp_timestamp::InherentDataProvider::from_system_time();
 
                         let slot =
-                            sp_consensus_aura::inherents::InherentDataProvider::from_timestamp_and_slot_duration(
+                            sp_consensus_babe::inherents::InherentDataProvider::from_timestamp_and_slot_duration(
                                 *timestamp,
                                 slot_duration,
                             );
@@ -278,21 +571,20 @@ pub fn new_full(config: Configuration) -> Result<TaskManager, ServiceError> {
                 },
                 force_authoring,
                 backoff_authoring_blocks,
-                keystore: keystore_container.keystore(),
-                sync_oracle: sync_service.clone(),
-                justification_sync_link: sync_service.clone(),
+                babe_link: consensus_link,
                 block_proposal_slot_portion: SlotProportion::new(2f32 / 3f32),
                 max_block_proposal_slot_portion: None,
                 telemetry: telemetry.as_ref().map(|x| x.handle()),
-                compatibility_mode: Default::default(),
-            },
-        )?;
-
-        // the AURA authoring task is considered essential, i.e. if it
-        // fails we take down the service with it.
-        task_manager
-            .spawn_essential_handle()
-            .spawn_blocking("aura", Some("block-authoring"), aura);
+            })?;
+
+            // the BABE authoring task is considered essential, i.e. if it
+            // fails we take down the service with it.
+            task_manager.spawn_essential_handle().spawn_blocking(
+                "babe-proposer",
+                Some("block-authoring"),
+                babe,
+            );
+        }
     }
 
     if enable_grandpa {
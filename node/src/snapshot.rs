@@ -0,0 +1,227 @@
+//! Snapshots of the UTXO set, for bootstrapping fresh nodes and for analysis off-chain.
+//!
+//! A snapshot holds the header of a block along with the entire state at that block. Almost all
+//! of a Tuxedo runtime's state is its transparent UTXO set, which is kept in its own child trie,
+//! where each utxo is stored under its encoded output ref. The rest of the state, such as the
+//! runtime code, is small, but it is needed to check the snapshot against the state root in the
+//! header.
+//!
+//! Importing a snapshot into a fresh node imports its block the way warp sync does: with the
+//! state, but without the body or any of the blocks before it. The node recomputes the state root
+//! from the snapshot's state and refuses the snapshot unless it matches the header. It then
+//! follows the chain from that block on. Check the hash that the import reports against a node
+//! that you trust, because the snapshot itself can not prove that its block is part of the chain.
+
+use std::{path::PathBuf, sync::Arc};
+
+use crate::service::FullClient;
+use node_template_runtime::{opaque::Block, Output};
+use parity_scale_codec::{Decode, DecodeAll, Encode};
+use sc_cli::{CliConfiguration, DatabaseParams, Error, PruningParams, SharedParams};
+use sc_client_api::StorageProvider;
+use sc_consensus::{
+    BlockImport, BlockImportParams, ForkChoiceStrategy, ImportResult, ImportedState, StateAction,
+    StorageChanges,
+};
+use sp_blockchain::HeaderBackend;
+use sp_consensus::BlockOrigin;
+use sp_core::{
+    storage::{well_known_keys::is_child_storage_key, StorageKey},
+    H256,
+};
+use sp_runtime::traits::{Block as BlockT, Header as HeaderT};
+use sp_state_machine::{KeyValueStates, KeyValueStorageLevel};
+use tuxedo_core::{types::OutputRef, utxo_set::utxo_child_info};
+
+/// The header of a block and the entire state at that block.
+#[derive(Encode, Decode)]
+pub struct UtxoSnapshot {
+    /// The header of the block whose state this is.
+    pub header: <Block as BlockT>::Header,
+    /// Every key and value in the top-level state, except for the roots of child tries, which
+    /// are recomputed from their contents.
+    pub storage: Vec<(Vec<u8>, Vec<u8>)>,
+    /// Every key and value in the utxo child trie.
+    pub utxo_storage: Vec<(Vec<u8>, Vec<u8>)>,
+}
+
+impl UtxoSnapshot {
+    /// The utxos in the snapshot's state.
+    pub fn utxos(&self) -> impl Iterator<Item = (OutputRef, Output)> + '_ {
+        self.utxo_storage.iter().filter_map(|(key, value)| {
+            Some((
+                OutputRef::decode_all(&mut &key[..]).ok()?,
+                Output::decode_all(&mut &value[..]).ok()?,
+            ))
+        })
+    }
+}
+
+/// Every key and value in the utxo child trie at the given block.
+pub fn read_utxo_storage(
+    client: &FullClient,
+    hash: H256,
+) -> sp_blockchain::Result<Vec<(Vec<u8>, Vec<u8>)>> {
+    let child_info = utxo_child_info();
+    client
+        .child_storage_keys(hash, child_info.clone(), None, None)?
+        .map(|key: StorageKey| {
+            let value = client
+                .child_storage(hash, &child_info, &key)?
+                .unwrap_or_default();
+            Ok((key.0, value.0))
+        })
+        .collect()
+}
+
+/// Export the UTXO set at a block to a snapshot file.
+#[derive(Debug, clap::Parser)]
+pub struct ExportUtxosCmd {
+    /// The file to write the snapshot to.
+    pub output: PathBuf,
+
+    /// The hash of the block whose utxo set to export. Defaults to the last finalized block.
+    /// The node must still have the state of that block.
+    #[arg(long, value_name = "HASH")]
+    pub at: Option<H256>,
+
+    #[allow(missing_docs)]
+    #[clap(flatten)]
+    pub shared_params: SharedParams,
+
+    #[allow(missing_docs)]
+    #[clap(flatten)]
+    pub database_params: DatabaseParams,
+
+    #[allow(missing_docs)]
+    #[clap(flatten)]
+    pub pruning_params: PruningParams,
+}
+
+impl ExportUtxosCmd {
+    /// Write the snapshot.
+    pub async fn run(&self, client: Arc<FullClient>) -> sc_cli::Result<()> {
+        let hash = self.at.unwrap_or_else(|| client.info().finalized_hash);
+        let header = client
+            .header(hash)?
+            .ok_or_else(|| Error::Input(format!("Unknown block {hash}")))?;
+        let storage = client
+            .storage_pairs(hash, None, None)?
+            .filter(|(key, _)| !is_child_storage_key(&key.0))
+            .map(|(key, value)| (key.0, value.0))
+            .collect();
+        let utxo_storage = read_utxo_storage(&client, hash)?;
+
+        let snapshot = UtxoSnapshot {
+            header,
+            storage,
+            utxo_storage,
+        };
+        std::fs::write(&self.output, snapshot.encode())?;
+
+        log::info!(
+            "Exported {} utxos at block #{} ({hash})",
+            snapshot.utxos().count(),
+            snapshot.header.number(),
+        );
+        Ok(())
+    }
+}
+
+impl CliConfiguration for ExportUtxosCmd {
+    fn shared_params(&self) -> &SharedParams {
+        &self.shared_params
+    }
+
+    fn database_params(&self) -> Option<&DatabaseParams> {
+        Some(&self.database_params)
+    }
+
+    fn pruning_params(&self) -> Option<&PruningParams> {
+        Some(&self.pruning_params)
+    }
+}
+
+/// Import a snapshot of the UTXO set into a fresh node.
+#[derive(Debug, clap::Parser)]
+pub struct ImportUtxosCmd {
+    /// The snapshot file to import.
+    pub input: PathBuf,
+
+    #[allow(missing_docs)]
+    #[clap(flatten)]
+    pub shared_params: SharedParams,
+
+    #[allow(missing_docs)]
+    #[clap(flatten)]
+    pub database_params: DatabaseParams,
+
+    #[allow(missing_docs)]
+    #[clap(flatten)]
+    pub pruning_params: PruningParams,
+}
+
+impl ImportUtxosCmd {
+    /// Import the snapshot's block along with its state.
+    pub async fn run(&self, client: Arc<FullClient>) -> sc_cli::Result<()> {
+        if client.info().best_number != 0 {
+            return Err(Error::Input(
+                "Snapshots can only be imported into a fresh node".into(),
+            ));
+        }
+
+        let snapshot = UtxoSnapshot::decode_all(&mut &std::fs::read(&self.input)?[..])
+            .map_err(|e| Error::Input(format!("Invalid snapshot: {e}")))?;
+        let hash = snapshot.header.hash();
+        let number = *snapshot.header.number();
+        let utxos = snapshot.utxos().count();
+
+        let mut params = BlockImportParams::new(BlockOrigin::File, snapshot.header);
+        params.state_action = StateAction::ApplyChanges(StorageChanges::Import(ImportedState {
+            block: hash,
+            state: KeyValueStates(vec![
+                KeyValueStorageLevel {
+                    state_root: Vec::new(),
+                    parent_storage_keys: Vec::new(),
+                    key_values: snapshot.storage,
+                },
+                KeyValueStorageLevel {
+                    state_root: Vec::new(),
+                    parent_storage_keys: vec![utxo_child_info()
+                        .prefixed_storage_key()
+                        .into_inner()],
+                    key_values: snapshot.utxo_storage,
+                },
+            ]),
+        }));
+        params.finalized = true;
+        params.fork_choice = Some(ForkChoiceStrategy::Custom(true));
+
+        // The client checks the imported state against the header's state root.
+        let mut importer = &*client;
+        match importer.import_block(params).await {
+            Ok(ImportResult::Imported(_)) => {
+                log::info!("Imported {utxos} utxos at block #{number} ({hash})");
+                Ok(())
+            }
+            Ok(result) => Err(Error::Input(format!(
+                "Unable to import snapshot: {result:?}"
+            ))),
+            Err(e) => Err(Error::Input(format!("Unable to import snapshot: {e}"))),
+        }
+    }
+}
+
+impl CliConfiguration for ImportUtxosCmd {
+    fn shared_params(&self) -> &SharedParams {
+        &self.shared_params
+    }
+
+    fn database_params(&self) -> Option<&DatabaseParams> {
+        Some(&self.database_params)
+    }
+
+    fn pruning_params(&self) -> Option<&PruningParams> {
+        Some(&self.pruning_params)
+    }
+}
@@ -0,0 +1,182 @@
+//! An optional index of finalized transactions by hash.
+//!
+//! A node that runs with `--index-transactions` remembers which block, and which position in that
+//! block, each finalized transaction is in. It serves them through the `tuxedo_getTransaction` RPC
+//! method along with a [`TransactionProof`] against the extrinsics root of the block. That lets a
+//! wallet prove a payment to a third party who trusts the header, without sharing its database.
+//!
+//! The index starts by reading every finalized block when the node starts, and then follows
+//! finality like the owner index does. Blocks whose bodies the node does not have, such as those
+//! before a warp sync or an imported snapshot, are skipped.
+
+use std::{
+    collections::BTreeMap,
+    sync::{Arc, RwLock},
+};
+
+use crate::service::FullClient;
+use futures::StreamExt;
+use jsonrpsee::{
+    core::RpcResult,
+    proc_macros::rpc,
+    types::error::{CallError, ErrorObject},
+};
+use node_template_runtime::{opaque::Block, Transaction};
+use parity_scale_codec::{Decode, Encode};
+use sc_client_api::{BlockBackend, BlockchainEvents};
+use sp_blockchain::HeaderBackend;
+use sp_core::H256;
+use sp_runtime::traits::{BlakeTwo256, Block as BlockT, Hash, Header as HeaderT};
+use tuxedo_core::inclusion::TransactionProof;
+
+/// Where finalized transactions are, by hash.
+#[derive(Default)]
+pub struct TransactionIndex {
+    /// The hash of the block that each transaction is in, and its position among the extrinsics.
+    locations: BTreeMap<H256, (H256, u32)>,
+}
+
+impl TransactionIndex {
+    /// The block that the transaction with the given hash is in, and its position in that block.
+    pub fn location(&self, tx_hash: &H256) -> Option<(H256, u32)> {
+        self.locations.get(tx_hash).copied()
+    }
+
+    /// Note where each extrinsic of the given block is.
+    fn index_block(&mut self, block: &Block) {
+        let block_hash = block.header().hash();
+        for (index, extrinsic) in block.extrinsics().iter().enumerate() {
+            let tx_hash = BlakeTwo256::hash(&extrinsic.encode());
+            self.locations.insert(tx_hash, (block_hash, index as u32));
+        }
+    }
+}
+
+/// A transaction index that is shared between the task that keeps it up to date and the RPC
+/// methods.
+pub type SharedTransactionIndex = Arc<RwLock<TransactionIndex>>;
+
+/// Index every finalized block, and then keep the index up to date as more blocks are finalized.
+/// This runs until the node shuts down.
+pub async fn run(client: Arc<FullClient>, index: SharedTransactionIndex) {
+    // Subscribe before reading the blocks, so that no finalized block is missed in between.
+    let mut finality_notifications = client.finality_notification_stream();
+
+    let mut indexed_number = client.info().finalized_number;
+    for number in 0..=indexed_number {
+        let Ok(Some(hash)) = client.hash(number) else {
+            continue;
+        };
+        let Ok(Some(block)) = client.block(hash) else {
+            log::debug!("Block {number} has no body to index");
+            continue;
+        };
+        index
+            .write()
+            .expect("transaction index lock is not poisoned")
+            .index_block(&block.block);
+    }
+
+    while let Some(notification) = finality_notifications.next().await {
+        for hash in notification
+            .tree_route
+            .iter()
+            .chain(std::iter::once(&notification.hash))
+        {
+            let Ok(Some(block)) = client.block(*hash) else {
+                log::error!("Unable to fetch finalized block {hash} to index it");
+                continue;
+            };
+            let number = *block.block.header().number();
+            if number <= indexed_number {
+                continue;
+            }
+
+            index
+                .write()
+                .expect("transaction index lock is not poisoned")
+                .index_block(&block.block);
+            indexed_number = number;
+        }
+    }
+}
+
+/// RPC methods for finding finalized transactions and proving that they are in their blocks.
+#[rpc(server, namespace = "tuxedo")]
+pub trait TransactionIndexApi {
+    /// The finalized transaction with the given hash, along with a proof that it is in its block.
+    /// The proof is checked against the extrinsics root in the header of that block.
+    #[method(name = "getTransaction")]
+    fn get_transaction(&self, tx_hash: H256) -> RpcResult<Option<(Transaction, TransactionProof)>>;
+}
+
+/// Serves the transaction index RPC methods from the shared index and the blocks of the client.
+pub struct TransactionIndexRpc<C> {
+    client: Arc<C>,
+    index: SharedTransactionIndex,
+}
+
+impl<C> TransactionIndexRpc<C> {
+    /// Create new transaction index RPC methods backed by the given client and index.
+    pub fn new(client: Arc<C>, index: SharedTransactionIndex) -> Self {
+        Self { client, index }
+    }
+}
+
+impl<C> TransactionIndexApiServer for TransactionIndexRpc<C>
+where
+    C: BlockBackend<Block> + Send + Sync + 'static,
+{
+    fn get_transaction(&self, tx_hash: H256) -> RpcResult<Option<(Transaction, TransactionProof)>> {
+        let Some((block_hash, index)) = self
+            .index
+            .read()
+            .expect("transaction index lock is not poisoned")
+            .location(&tx_hash)
+        else {
+            return Ok(None);
+        };
+
+        let block = self
+            .client
+            .block(block_hash)
+            .map_err(|e| {
+                CallError::Custom(ErrorObject::owned(
+                    5,
+                    "Unable to fetch block.",
+                    Some(e.to_string()),
+                ))
+            })?
+            .ok_or_else(|| {
+                CallError::Custom(ErrorObject::owned(
+                    5,
+                    "Unable to fetch block.",
+                    Some(format!("block {block_hash} is not known")),
+                ))
+            })?;
+        let extrinsics: Vec<Vec<u8>> = block
+            .block
+            .extrinsics()
+            .iter()
+            .map(Encode::encode)
+            .collect();
+
+        let transaction =
+            Transaction::decode(&mut &extrinsics[index as usize][..]).map_err(|e| {
+                CallError::Custom(ErrorObject::owned(
+                    2,
+                    "Unable to decode transaction.",
+                    Some(e.to_string()),
+                ))
+            })?;
+        let proof = TransactionProof::prove(block_hash, &extrinsics, index).ok_or_else(|| {
+            CallError::Custom(ErrorObject::owned(
+                4,
+                "Unable to prove transaction.",
+                None::<()>,
+            ))
+        })?;
+
+        Ok(Some((transaction, proof)))
+    }
+}
@@ -7,13 +7,30 @@
 
 use std::sync::Arc;
 
-use jsonrpsee::RpcModule;
-use parachain_template_runtime::opaque::Block;
+use jsonrpsee::{
+    core::RpcResult,
+    proc_macros::rpc,
+    types::error::{CallError, ErrorObject},
+    RpcModule,
+};
+use parachain_template_runtime::{
+    opaque::Block, OuterConstraintChecker, OuterVerifier, Output, Transaction,
+};
+use parity_scale_codec::Decode;
+use sc_client_api::ProofProvider;
 pub use sc_rpc::{DenyUnsafe, SubscriptionTaskExecutor};
 use sc_transaction_pool_api::TransactionPool;
 use sp_api::ProvideRuntimeApi;
 use sp_block_builder::BlockBuilder;
 use sp_blockchain::{Error as BlockChainError, HeaderBackend, HeaderMetadata};
+use sp_core::Bytes;
+use sp_runtime::{traits::Block as BlockT, transaction_validity::TransactionPriority};
+use tuxedo_core::{
+    events::{EventRecord, TuxedoEventsApi},
+    metadata::{RuntimeIntrospection, TuxedoIntrospectionApi},
+    types::{DetailedError, OutputRef, TuxedoDryRunApi},
+    utxo_set::{utxo_child_info, TuxedoUtxoApi, TuxedoUtxoProofApi, UtxoProof},
+};
 
 /// A type representing all RPC extensions.
 pub type RpcExtension = jsonrpsee::RpcModule<()>;
@@ -30,22 +47,253 @@ pub struct FullDeps<C, P> {
 
 /// Instantiate all RPC extensions.
 pub fn create_full<C, P>(
-    _deps: FullDeps<C, P>,
+    deps: FullDeps<C, P>,
 ) -> Result<RpcExtension, Box<dyn std::error::Error + Send + Sync>>
 where
     C: ProvideRuntimeApi<Block>
         + HeaderBackend<Block>
         + HeaderMetadata<Block, Error = BlockChainError>
+        + ProofProvider<Block>
         + Send
         + Sync
         + 'static,
     C::Api: BlockBuilder<Block>,
+    C::Api: TuxedoEventsApi<Block>,
+    C::Api: TuxedoUtxoApi<Block, OuterVerifier>,
+    C::Api: TuxedoUtxoProofApi<Block>,
+    C::Api: TuxedoDryRunApi<Block, OuterVerifier, OuterConstraintChecker>,
+    C::Api: TuxedoIntrospectionApi<Block>,
     P: TransactionPool + Sync + Send + 'static,
 {
-    let module = RpcModule::new(());
+    let mut module = RpcModule::new(());
+    module.merge(Events::new(deps.client.clone()).into_rpc())?;
+    module.merge(Utxos::new(deps.client.clone()).into_rpc())?;
+    module.merge(Introspection::new(deps.client.clone()).into_rpc())?;
+    module.merge(DryRun::new(deps.client).into_rpc())?;
     // Extend this RPC with a custom API by using the following syntax.
     // `YourRpcStruct` should have a reference to a client, which is needed
     // to call into the runtime.
     // `module.merge(YourRpcTrait::into_rpc(YourRpcStruct::new(ReferenceToClient, ...)))?;`
     Ok(module)
 }
+
+/// RPC methods for reading the events that transactions emit.
+#[rpc(server, namespace = "tuxedo")]
+pub trait EventsApi<BlockHash> {
+    /// The events emitted in the given block, or in the best block if none is given.
+    #[method(name = "events")]
+    fn events(&self, at: Option<BlockHash>) -> RpcResult<Vec<EventRecord>>;
+}
+
+/// Serves the events RPC methods by calling into the runtime.
+pub struct Events<C> {
+    client: Arc<C>,
+}
+
+impl<C> Events<C> {
+    /// Create new events RPC methods backed by the given client.
+    pub fn new(client: Arc<C>) -> Self {
+        Self { client }
+    }
+}
+
+impl<C> EventsApiServer<<Block as BlockT>::Hash> for Events<C>
+where
+    C: ProvideRuntimeApi<Block> + HeaderBackend<Block> + Send + Sync + 'static,
+    C::Api: TuxedoEventsApi<Block>,
+{
+    fn events(&self, at: Option<<Block as BlockT>::Hash>) -> RpcResult<Vec<EventRecord>> {
+        let at = at.unwrap_or_else(|| self.client.info().best_hash);
+        self.client
+            .runtime_api()
+            .events(at)
+            .map_err(|e| runtime_error("Unable to query events.", e))
+    }
+}
+
+/// RPC methods for reading the UTXO set.
+#[rpc(server, namespace = "tuxedo")]
+pub trait UtxoApi<BlockHash> {
+    /// The utxo at the given output ref, if it exists in the given block or the best block.
+    #[method(name = "utxo")]
+    fn utxo(&self, output_ref: OutputRef, at: Option<BlockHash>) -> RpcResult<Option<Output>>;
+
+    /// Whether a utxo exists at the given output ref in the given block or the best block.
+    #[method(name = "utxoExists")]
+    fn utxo_exists(&self, output_ref: OutputRef, at: Option<BlockHash>) -> RpcResult<bool>;
+
+    /// A proof that the utxo at the given output ref is, or is not, in the given block or the
+    /// best block. Light clients check it against the state root in that block's header.
+    #[method(name = "utxoProof")]
+    fn utxo_proof(&self, output_ref: OutputRef, at: Option<BlockHash>) -> RpcResult<UtxoProof>;
+}
+
+/// Serves the UTXO RPC methods by calling into the runtime.
+pub struct Utxos<C> {
+    client: Arc<C>,
+}
+
+impl<C> Utxos<C> {
+    /// Create new UTXO RPC methods backed by the given client.
+    pub fn new(client: Arc<C>) -> Self {
+        Self { client }
+    }
+}
+
+impl<C> UtxoApiServer<<Block as BlockT>::Hash> for Utxos<C>
+where
+    C: ProvideRuntimeApi<Block>
+        + HeaderBackend<Block>
+        + ProofProvider<Block>
+        + Send
+        + Sync
+        + 'static,
+    C::Api: TuxedoUtxoApi<Block, OuterVerifier>,
+    C::Api: TuxedoUtxoProofApi<Block>,
+{
+    fn utxo(
+        &self,
+        output_ref: OutputRef,
+        at: Option<<Block as BlockT>::Hash>,
+    ) -> RpcResult<Option<Output>> {
+        let at = at.unwrap_or_else(|| self.client.info().best_hash);
+        self.client
+            .runtime_api()
+            .utxo(at, output_ref)
+            .map_err(|e| runtime_error("Unable to query utxo.", e))
+    }
+
+    fn utxo_exists(
+        &self,
+        output_ref: OutputRef,
+        at: Option<<Block as BlockT>::Hash>,
+    ) -> RpcResult<bool> {
+        let at = at.unwrap_or_else(|| self.client.info().best_hash);
+        self.client
+            .runtime_api()
+            .utxo_exists(at, output_ref)
+            .map_err(|e| runtime_error("Unable to query utxo.", e))
+    }
+
+    fn utxo_proof(
+        &self,
+        output_ref: OutputRef,
+        at: Option<<Block as BlockT>::Hash>,
+    ) -> RpcResult<UtxoProof> {
+        let at = at.unwrap_or_else(|| self.client.info().best_hash);
+        // The runtime knows where in the utxo child trie the utxo would be kept, and the client
+        // can prove it, along with the path to the root of the child trie.
+        let key = self
+            .client
+            .runtime_api()
+            .utxo_storage_key(at, output_ref.clone())
+            .map_err(|e| runtime_error("Unable to query utxo.", e))?;
+        let proof = self
+            .client
+            .read_child_proof(at, &utxo_child_info(), &mut std::iter::once(&key[..]))
+            .map_err(|e| {
+                CallError::Custom(ErrorObject::owned(
+                    4,
+                    "Unable to prove utxo.",
+                    Some(e.to_string()),
+                ))
+            })?;
+
+        Ok(UtxoProof {
+            at,
+            output_ref,
+            nodes: proof.into_iter_nodes().collect(),
+        })
+    }
+}
+
+/// RPC methods for discovering what the runtime supports.
+#[rpc(server, namespace = "tuxedo")]
+pub trait IntrospectionApi<BlockHash> {
+    /// The verifiers, constraint checkers, and utxo types of the runtime at the given block or
+    /// the best block. The full types are described in the metadata of the same runtime.
+    #[method(name = "introspect")]
+    fn introspect(&self, at: Option<BlockHash>) -> RpcResult<RuntimeIntrospection>;
+}
+
+/// Serves the introspection RPC methods by calling into the runtime.
+pub struct Introspection<C> {
+    client: Arc<C>,
+}
+
+impl<C> Introspection<C> {
+    /// Create new introspection RPC methods backed by the given client.
+    pub fn new(client: Arc<C>) -> Self {
+        Self { client }
+    }
+}
+
+impl<C> IntrospectionApiServer<<Block as BlockT>::Hash> for Introspection<C>
+where
+    C: ProvideRuntimeApi<Block> + HeaderBackend<Block> + Send + Sync + 'static,
+    C::Api: TuxedoIntrospectionApi<Block>,
+{
+    fn introspect(&self, at: Option<<Block as BlockT>::Hash>) -> RpcResult<RuntimeIntrospection> {
+        let at = at.unwrap_or_else(|| self.client.info().best_hash);
+        self.client
+            .runtime_api()
+            .introspect(at)
+            .map_err(|e| runtime_error("Unable to introspect runtime.", e))
+    }
+}
+
+/// RPC methods for trying out transactions before submitting them.
+#[rpc(server, namespace = "tuxedo")]
+pub trait DryRunApi<BlockHash> {
+    /// Check the SCALE encoded transaction against the state of the given block or the best
+    /// block, without committing anything. Returns the priority the transaction would have,
+    /// or the reason that it would fail.
+    #[method(name = "dryRun")]
+    fn dry_run(
+        &self,
+        transaction: Bytes,
+        at: Option<BlockHash>,
+    ) -> RpcResult<Result<TransactionPriority, DetailedError>>;
+}
+
+/// Serves the dry run RPC methods by calling into the runtime.
+pub struct DryRun<C> {
+    client: Arc<C>,
+}
+
+impl<C> DryRun<C> {
+    /// Create new dry run RPC methods backed by the given client.
+    pub fn new(client: Arc<C>) -> Self {
+        Self { client }
+    }
+}
+
+impl<C> DryRunApiServer<<Block as BlockT>::Hash> for DryRun<C>
+where
+    C: ProvideRuntimeApi<Block> + HeaderBackend<Block> + Send + Sync + 'static,
+    C::Api: TuxedoDryRunApi<Block, OuterVerifier, OuterConstraintChecker>,
+{
+    fn dry_run(
+        &self,
+        transaction: Bytes,
+        at: Option<<Block as BlockT>::Hash>,
+    ) -> RpcResult<Result<TransactionPriority, DetailedError>> {
+        let transaction = Transaction::decode(&mut &transaction[..]).map_err(|e| {
+            CallError::Custom(ErrorObject::owned(
+                2,
+                "Unable to decode transaction.",
+                Some(e.to_string()),
+            ))
+        })?;
+        let at = at.unwrap_or_else(|| self.client.info().best_hash);
+        self.client
+            .runtime_api()
+            .dry_run(at, transaction)
+            .map_err(|e| runtime_error("Unable to dry run transaction.", e))
+    }
+}
+
+/// Report an error that occurred while calling into the runtime.
+fn runtime_error(message: &str, e: sp_api::ApiError) -> jsonrpsee::core::Error {
+    CallError::Custom(ErrorObject::owned(1, message, Some(e.to_string()))).into()
+}
@@ -1,6 +1,9 @@
 use proc_macro::TokenStream;
 use quote::quote;
-use syn::{parse_macro_input, Ident, ItemEnum};
+use syn::{
+    parse_macro_input, parse_quote, punctuated::Punctuated, Attribute, DeriveInput, Expr, Fields,
+    Ident, ItemEnum, ItemStruct, Token,
+};
 
 /// Automatically implements `From` for each type in an aggregate type enum.
 ///
@@ -8,6 +11,8 @@ use syn::{parse_macro_input, Ident, ItemEnum};
 /// And the type for each variant should be unique in the enum.
 ///
 /// The macro generates all the `From` implementations automatically.
+/// Variants that are feature gated with `#[cfg(...)]` only get their `From` implementations
+/// when they are compiled in.
 #[proc_macro_attribute]
 pub fn aggregate(_: TokenStream, body: TokenStream) -> TokenStream {
     let ast = parse_macro_input!(body as ItemEnum);
@@ -35,6 +40,12 @@ pub fn aggregate(_: TokenStream, body: TokenStream) -> TokenStream {
     let variants2 = variants.clone();
     let inner_types = variant_type_pairs.map(|(_v, t)| t);
     let inner_types2 = inner_types.clone();
+    // Feature gated variants only get their From impls when they are compiled in.
+    let cfgs = ast
+        .variants
+        .iter()
+        .map(|variant| cfg_attrs(&variant.attrs))
+        .collect::<Vec<_>>();
 
     let output = quote! {
         // First keep the original code in tact
@@ -42,6 +53,7 @@ pub fn aggregate(_: TokenStream, body: TokenStream) -> TokenStream {
 
         // Now write all the wrapping From impls
         #(
+            #(#cfgs)*
             impl From<#inner_types> for #outer_type {
                 fn from(b: #inner_types) -> Self {
                     Self::#variants(b)
@@ -51,6 +63,7 @@ pub fn aggregate(_: TokenStream, body: TokenStream) -> TokenStream {
 
         // Finally write all the un-wrapping From impls
         #(
+            #(#cfgs)*
             impl From<#outer_type> for #inner_types2 {
                 fn from(a: #outer_type) -> Self {
                     if let #outer_type::#variants2(b) = a {
@@ -76,6 +89,8 @@ pub fn tuxedo_verifier(_: TokenStream, body: TokenStream) -> TokenStream {
 
     let outer_type = ast.ident;
     let variants = ast.variants.into_iter().map(|v| v.ident);
+    let variants2 = variants.clone();
+    let variants3 = variants.clone();
 
     let output = quote! {
 
@@ -84,10 +99,37 @@ pub fn tuxedo_verifier(_: TokenStream, body: TokenStream) -> TokenStream {
         #original_code
 
         impl tuxedo_core::Verifier for #outer_type {
-            fn verify(&self, simplified_tx: &[u8], redeemer: &[u8]) -> bool {
+            fn verify(
+                &self,
+                simplified_tx: &[u8],
+                redeemer: &[u8],
+                context: &tuxedo_core::verifier::VerifierContext,
+            ) -> bool {
+                match self {
+                    #(
+                        Self::#variants(inner) => inner.verify(simplified_tx, redeemer, context),
+                    )*
+                }
+            }
+
+            fn verify_input(
+                &self,
+                payloads: &tuxedo_core::types::SigningPayloads,
+                index: usize,
+                redeemer: &[u8],
+                context: &tuxedo_core::verifier::VerifierContext,
+            ) -> bool {
                 match self {
                     #(
-                        Self::#variants(inner) => inner.verify(simplified_tx, redeemer),
+                        Self::#variants2(inner) => inner.verify_input(payloads, index, redeemer, context),
+                    )*
+                }
+            }
+
+            fn weight(&self) -> tuxedo_core::weights::Weight {
+                match self {
+                    #(
+                        Self::#variants3(inner) => tuxedo_core::Verifier::weight(inner),
                     )*
                 }
             }
@@ -102,33 +144,109 @@ pub fn tuxedo_verifier(_: TokenStream, body: TokenStream) -> TokenStream {
 ///
 /// It also declares an associated error type. The error type has a variant for each inner constraint checker,
 /// just like this original enum. however, the contained values in the error enum are of the corresponding types
-/// for the inner constraint checker.
+/// for the inner constraint checker. When an error is named for clients, it is attributed to the piece
+/// whose variant returned it, as in `Money::OutputsExceedInputs`.
+///
+/// Variants may be feature gated with `#[cfg(...)]`. When such a variant is compiled out, the macro puts a
+/// placeholder variant of the same name in its place, so that the SCALE indices of the following variants do
+/// not depend on which features are enabled. The placeholder rejects every transaction.
 #[proc_macro_attribute]
 pub fn tuxedo_constraint_checker(attrs: TokenStream, body: TokenStream) -> TokenStream {
     let ast = parse_macro_input!(body as ItemEnum);
     let verifier = parse_macro_input!(attrs as Ident);
-    let original_code = ast.clone();
 
-    let outer_type = ast.ident;
-    let variant_type_pairs = ast.variants.iter().map(|variant| {
+    let outer_type = ast.ident.clone();
+    let vis = ast.vis.clone();
+
+    // The placeholders need the same derives as the outer enum to be usable inside it.
+    let derives = ast
+        .attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("derive"))
+        .collect::<Vec<_>>();
+
+    // Rebuild the enum, pairing each feature gated variant with a placeholder for when it is compiled out.
+    let mut outer_enum = ast.clone();
+    outer_enum.variants = Punctuated::new();
+    let mut placeholders = Vec::new();
+    let mut variants = Vec::new();
+    let mut inner_types = Vec::new();
+    let mut cfgs = Vec::new();
+    for variant in ast.variants.iter() {
         // Make sure there is only a single field, and if not, give a helpful error
         assert!(
             variant.fields.len() == 1,
             "Each variant must have a single unnamed field"
         );
-        (
-            variant.ident.clone(),
-            variant
-                .fields
-                .iter()
-                .next()
-                .expect("exactly one field per variant")
-                .ty
-                .clone(),
-        )
-    });
-    let variants = variant_type_pairs.clone().map(|(v, _t)| v);
-    let inner_types = variant_type_pairs.map(|(_v, t)| t);
+        let inner_type = variant
+            .fields
+            .iter()
+            .next()
+            .expect("exactly one field per variant")
+            .ty
+            .clone();
+        let variant_cfgs = cfg_attrs(&variant.attrs);
+
+        outer_enum.variants.push(variant.clone());
+        variants.push(variant.ident.clone());
+        inner_types.push(inner_type);
+        cfgs.push(variant_cfgs.clone());
+
+        if variant_cfgs.is_empty() {
+            continue;
+        }
+
+        let predicates = variant_cfgs.iter().map(|attr| {
+            attr.meta
+                .require_list()
+                .expect("cfg attributes take a predicate")
+                .tokens
+                .clone()
+        });
+        let not_cfg: Attribute = parse_quote!(#[cfg(not(all(#(#predicates),*)))]);
+
+        let variant_name = &variant.ident;
+        let placeholder = Ident::new(
+            &format!("{}{}Placeholder", outer_type, variant_name),
+            variant_name.span(),
+        );
+        let placeholder_doc = format!(
+            "A placeholder for the `{}` variant of `{}` when it is compiled out. It rejects every transaction.",
+            variant_name, outer_type,
+        );
+        placeholders.push(quote! {
+            #[doc = #placeholder_doc]
+            ///
+            /// This type is generated by the `#[tuxedo_constraint_checker]` macro.
+            #not_cfg
+            #(#derives)*
+            #vis struct #placeholder;
+
+            #not_cfg
+            impl tuxedo_core::SimpleConstraintChecker for #placeholder {
+                type Error = &'static str;
+
+                fn check(
+                    &self,
+                    _input_data: &[tuxedo_core::dynamic_typing::DynamicallyTypedData],
+                    _peeks: &[tuxedo_core::dynamic_typing::DynamicallyTypedData],
+                    _output_data: &[tuxedo_core::dynamic_typing::DynamicallyTypedData],
+                    _context: &tuxedo_core::CheckerContext,
+                ) -> Result<TransactionPriority, Self::Error> {
+                    Err("This constraint checker is not available in this runtime")
+                }
+            }
+        });
+
+        outer_enum.variants.push(parse_quote! {
+            #[doc = #placeholder_doc]
+            #not_cfg
+            #variant_name(#placeholder)
+        });
+        variants.push(variant_name.clone());
+        inner_types.push(parse_quote!(#placeholder));
+        cfgs.push(vec![not_cfg]);
+    }
 
     // Set up the names of the new associated types.
     let mut error_type_name = outer_type.to_string();
@@ -139,25 +257,12 @@ pub fn tuxedo_constraint_checker(attrs: TokenStream, body: TokenStream) -> Token
     inherent_hooks_name.push_str("InherentHooks");
     let inherent_hooks = Ident::new(&inherent_hooks_name, outer_type.span());
 
-    let vis = ast.vis;
-
-    // TODO there must be a better way to do this, right?
-    let inner_types2 = inner_types.clone();
-    let inner_types3 = inner_types.clone();
-    let inner_types4 = inner_types.clone();
-    let inner_types6 = inner_types.clone();
-    let inner_types7 = inner_types.clone();
-    let variants2 = variants.clone();
-    let variants3 = variants.clone();
-    let variants4 = variants.clone();
-    let variants5 = variants.clone();
-    let variants6 = variants.clone();
-
     let output = quote! {
         // Preserve the original enum, and write the From impls
         #[tuxedo_core::aggregate]
-        #original_code
+        #outer_enum
 
+        #(#placeholders)*
 
         /// This type is generated by the `#[tuxedo_constraint_checker]` macro.
         /// It is a combined error type for the errors of each individual checker.
@@ -166,6 +271,7 @@ pub fn tuxedo_constraint_checker(attrs: TokenStream, body: TokenStream) -> Token
         #[derive(Debug)]
         #vis enum #error_type {
             #(
+                #(#cfgs)*
                 #variants(<#inner_types as tuxedo_core::ConstraintChecker<#verifier>>::Error),
             )*
         }
@@ -177,7 +283,8 @@ pub fn tuxedo_constraint_checker(attrs: TokenStream, body: TokenStream) -> Token
         #[derive(Debug, scale_info::TypeInfo)]
         #vis enum #inherent_hooks {
             #(
-                #variants2(<#inner_types2 as tuxedo_core::ConstraintChecker<#verifier>>::InherentHooks),
+                #(#cfgs)*
+                #variants(<#inner_types as tuxedo_core::ConstraintChecker<#verifier>>::InherentHooks),
             )*
         }
 
@@ -191,19 +298,20 @@ pub fn tuxedo_constraint_checker(attrs: TokenStream, body: TokenStream) -> Token
                 let mut all_inherents = Vec::new();
 
                 #(
+                    #(#cfgs)*
                     {
                         // Filter the previous inherents down to just the ones that came from this piece
                         let previous_inherents = previous_inherents
                             .iter()
                             .filter_map(|(tx, hash)| {
                                 match tx.checker {
-                                    #outer_type::#variants3(ref inner_checker) => Some((tx.transform::<#inner_types3>(), *hash )),
+                                    #outer_type::#variants(ref inner_checker) => Some((tx.transform::<#inner_types>(), *hash )),
                                     _ => None,
                                 }
                             })
                             .collect();
 
-                        let inherents = <#inner_types3 as tuxedo_core::ConstraintChecker<#verifier>>::InherentHooks::create_inherents(authoring_inherent_data, previous_inherents)
+                        let inherents = <#inner_types as tuxedo_core::ConstraintChecker<#verifier>>::InherentHooks::create_inherents(authoring_inherent_data, previous_inherents)
                             .iter()
                             .map(|tx| tx.transform::<#outer_type>())
                             .collect::<Vec<_>>();
@@ -221,22 +329,25 @@ pub fn tuxedo_constraint_checker(attrs: TokenStream, body: TokenStream) -> Token
                 result: &mut sp_inherents::CheckInherentsResult,
             ) {
                 #(
-                    let relevant_inherents: Vec<tuxedo_core::types::Transaction<#verifier, #inner_types4>> = inherents
-                        .iter()
-                        .filter_map(|tx| {
-                            match tx.checker {
-                                #outer_type::#variants4(ref inner_checker) => Some(tx.transform::<#inner_types4>()),
-                                _ => None,
-                            }
-                        })
-                        .collect();
-
-                    <#inner_types4 as tuxedo_core::ConstraintChecker<#verifier>>::InherentHooks::check_inherents(importing_inherent_data, relevant_inherents, result);
-
-                    // According to https://paritytech.github.io/polkadot-sdk/master/sp_inherents/struct.CheckInherentsResult.html
-                    // "When a fatal error occurs, all other errors are removed and the implementation needs to abort checking inherents."
-                    if result.fatal_error() {
-                        return;
+                    #(#cfgs)*
+                    {
+                        let relevant_inherents: Vec<tuxedo_core::types::Transaction<#verifier, #inner_types>> = inherents
+                            .iter()
+                            .filter_map(|tx| {
+                                match tx.checker {
+                                    #outer_type::#variants(ref inner_checker) => Some(tx.transform::<#inner_types>()),
+                                    _ => None,
+                                }
+                            })
+                            .collect();
+
+                        <#inner_types as tuxedo_core::ConstraintChecker<#verifier>>::InherentHooks::check_inherents(importing_inherent_data, relevant_inherents, result);
+
+                        // According to https://paritytech.github.io/polkadot-sdk/master/sp_inherents/struct.CheckInherentsResult.html
+                        // "When a fatal error occurs, all other errors are removed and the implementation needs to abort checking inherents."
+                        if result.fatal_error() {
+                            return;
+                        }
                     }
                 )*
             }
@@ -246,19 +357,47 @@ pub fn tuxedo_constraint_checker(attrs: TokenStream, body: TokenStream) -> Token
                 let mut all_transactions: Vec<tuxedo_core::types::Transaction<#verifier, #outer_type>> = Vec::new();
 
                 #(
-                    let transactions =
-                        <<#inner_types6 as tuxedo_core::ConstraintChecker<#verifier>>::InherentHooks as tuxedo_core::inherents::InherentInternal<#verifier, #inner_types6>>::genesis_transactions();
-                    all_transactions.extend(
-                        transactions
-                            .into_iter()
-                            .map(|tx| tx.transform::<#outer_type>())
-                            .collect::<Vec<_>>()
-                    );
+                    #(#cfgs)*
+                    {
+                        let transactions =
+                            <<#inner_types as tuxedo_core::ConstraintChecker<#verifier>>::InherentHooks as tuxedo_core::inherents::InherentInternal<#verifier, #inner_types>>::genesis_transactions();
+                        all_transactions.extend(
+                            transactions
+                                .into_iter()
+                                .map(|tx| tx.transform::<#outer_type>())
+                                .collect::<Vec<_>>()
+                        );
+                    }
                 )*
 
                 all_transactions
             }
 
+            fn identifiers() -> Vec<sp_inherents::InherentIdentifier> {
+                let mut all_identifiers = Vec::new();
+
+                #(
+                    #(#cfgs)*
+                    all_identifiers.extend(
+                        <<#inner_types as tuxedo_core::ConstraintChecker<#verifier>>::InherentHooks as tuxedo_core::inherents::InherentInternal<#verifier, #inner_types>>::identifiers()
+                    );
+                )*
+
+                all_identifiers
+            }
+
+            fn identify(
+                inherent: &tuxedo_core::types::Transaction<#verifier, #outer_type>,
+            ) -> Option<sp_inherents::InherentIdentifier> {
+                // Each inherent belongs to the piece whose constraint checker it uses.
+                match inherent.checker {
+                    #(
+                        #(#cfgs)*
+                        #outer_type::#variants(_) => <<#inner_types as tuxedo_core::ConstraintChecker<#verifier>>::InherentHooks as tuxedo_core::inherents::InherentInternal<#verifier, #inner_types>>::identify(&inherent.transform::<#inner_types>()),
+                    )*
+                }
+            }
+
         }
 
         impl tuxedo_core::ConstraintChecker<#verifier> for #outer_type {
@@ -271,10 +410,12 @@ pub fn tuxedo_constraint_checker(attrs: TokenStream, body: TokenStream) -> Token
                 inputs: &[tuxedo_core::types::Output<#verifier>],
                 peeks: &[tuxedo_core::types::Output<#verifier>],
                 outputs: &[tuxedo_core::types::Output<#verifier>],
+                context: &tuxedo_core::CheckerContext,
             ) -> Result<TransactionPriority, Self::Error> {
                 match self {
                     #(
-                        Self::#variants5(inner) => inner.check(inputs, peeks, outputs).map_err(|e| Self::Error::#variants5(e)),
+                        #(#cfgs)*
+                        Self::#variants(inner) => inner.check(inputs, peeks, outputs, context).map_err(|e| Self::Error::#variants(e)),
                     )*
                 }
             }
@@ -282,12 +423,191 @@ pub fn tuxedo_constraint_checker(attrs: TokenStream, body: TokenStream) -> Token
             fn is_inherent(&self) -> bool {
                 match self {
                     #(
-                        Self::#variants6(inner) => <#inner_types7 as tuxedo_core::ConstraintChecker<#verifier>>::is_inherent(inner),
+                        #(#cfgs)*
+                        Self::#variants(inner) => <#inner_types as tuxedo_core::ConstraintChecker<#verifier>>::is_inherent(inner),
                     )*
                 }
 
             }
 
+            fn weight(&self) -> tuxedo_core::weights::Weight {
+                match self {
+                    #(
+                        #(#cfgs)*
+                        Self::#variants(inner) => <#inner_types as tuxedo_core::ConstraintChecker<#verifier>>::weight(inner),
+                    )*
+                }
+            }
+
+            fn reclaims(&self) -> bool {
+                match self {
+                    #(
+                        #(#cfgs)*
+                        Self::#variants(inner) => <#inner_types as tuxedo_core::ConstraintChecker<#verifier>>::reclaims(inner),
+                    )*
+                }
+            }
+
+            fn name_error(error: &Self::Error) -> tuxedo_core::types::CheckerErrorName {
+                // Each error is attributed to the piece whose checker returned it.
+                match error {
+                    #(
+                        #(#cfgs)*
+                        #error_type::#variants(inner) => <#inner_types as tuxedo_core::ConstraintChecker<#verifier>>::name_error(inner).in_piece(stringify!(#variants)),
+                    )*
+                }
+            }
+
+        }
+    };
+
+    output.into()
+}
+
+/// The `#[cfg(...)]` attributes among the given attributes.
+fn cfg_attrs(attrs: &[Attribute]) -> Vec<Attribute> {
+    attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("cfg"))
+        .cloned()
+        .collect()
+}
+
+/// This macro treats the supplied struct as an aggregate genesis configuration. Each field is the
+/// genesis section of a single piece, and must implement `tuxedo_core::genesis::GenesisSection`.
+///
+/// The macro takes the outer verifier and the outer constraint checker as arguments, as in
+/// `#[tuxedo_genesis_config(OuterVerifier, OuterConstraintChecker)]`. It implements `GenesisSection`
+/// for the struct by chaining the sections' transactions, and their storage entries, in field order.
+/// It also writes a `build` method that places the inherents' genesis transactions first, and wraps
+/// everything up in a `TuxedoGenesisConfig` along with the wasm binary.
+///
+/// The struct keeps its own derives, so it is up to the runtime to derive `Serialize`, `Deserialize`,
+/// and `Default` as it sees fit.
+#[proc_macro_attribute]
+pub fn tuxedo_genesis_config(attrs: TokenStream, body: TokenStream) -> TokenStream {
+    let ast = parse_macro_input!(body as ItemStruct);
+    let args = parse_macro_input!(attrs with Punctuated::<Ident, Token![,]>::parse_terminated);
+    let original_code = ast.clone();
+
+    assert!(
+        args.len() == 2,
+        "Expected the outer verifier and the outer constraint checker as arguments"
+    );
+    let verifier = &args[0];
+    let checker = &args[1];
+
+    let outer_type = ast.ident;
+    let Fields::Named(fields) = ast.fields else {
+        panic!("Each genesis section must be a named field");
+    };
+    let sections = fields
+        .named
+        .into_iter()
+        .map(|field| field.ident.expect("named fields have an ident"))
+        .collect::<Vec<_>>();
+
+    let output = quote! {
+        #original_code
+
+        impl tuxedo_core::genesis::GenesisSection<#verifier, #checker> for #outer_type {
+            fn genesis_transactions(&self) -> Vec<tuxedo_core::types::Transaction<#verifier, #checker>> {
+                let mut all_transactions = Vec::new();
+
+                #(
+                    all_transactions.extend(
+                        tuxedo_core::genesis::GenesisSection::<#verifier, #checker>::genesis_transactions(&self.#sections)
+                    );
+                )*
+
+                all_transactions
+            }
+
+            fn genesis_storage(&self) -> Vec<(Vec<u8>, Vec<u8>)> {
+                let mut all_storage = Vec::new();
+
+                #(
+                    all_storage.extend(
+                        tuxedo_core::genesis::GenesisSection::<#verifier, #checker>::genesis_storage(&self.#sections)
+                    );
+                )*
+
+                all_storage
+            }
+        }
+
+        impl #outer_type {
+            /// Build the complete genesis configuration for the given wasm binary.
+            ///
+            /// The genesis transactions of the inherents come first, followed by those of each section.
+            pub fn build(&self, wasm_binary: Vec<u8>) -> tuxedo_core::genesis::TuxedoGenesisConfig<#verifier, #checker> {
+                let mut genesis_transactions =
+                    <<#checker as tuxedo_core::ConstraintChecker<#verifier>>::InherentHooks as tuxedo_core::inherents::InherentInternal<#verifier, #checker>>::genesis_transactions();
+                genesis_transactions.extend(
+                    tuxedo_core::genesis::GenesisSection::<#verifier, #checker>::genesis_transactions(self)
+                );
+
+                tuxedo_core::genesis::TuxedoGenesisConfig::new(wasm_binary, genesis_transactions)
+                    .with_storage(tuxedo_core::genesis::GenesisSection::<#verifier, #checker>::genesis_storage(self))
+            }
+        }
+    };
+
+    output.into()
+}
+
+/// Implements `tuxedo_core::dynamic_typing::UtxoData` for the type.
+///
+/// The type id is derived from a hash of the crate name and the type name, so no id has to be
+/// picked by hand. The hash also covers the values of any const generic parameters, so that,
+/// for example, `Token<0>` and `Token<1>` get different ids. Type parameters are not covered,
+/// and they must be bounded in the type's where clause so that the type implements `Encode`
+/// and `Decode`.
+///
+/// A type can choose its own id with `#[utxo_data(type_id = ...)]`, which takes any constant
+/// expression. This is how types that were stored before the derive existed keep their ids.
+///
+/// Changing the crate or type name changes the derived id, and makes existing UTXOs of the type
+/// unreadable. Types that are renamed should keep their old id with the attribute.
+#[proc_macro_derive(UtxoData, attributes(utxo_data))]
+pub fn derive_utxo_data(input: TokenStream) -> TokenStream {
+    let ast = parse_macro_input!(input as DeriveInput);
+
+    let mut type_id: Option<Expr> = None;
+    for attr in ast
+        .attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("utxo_data"))
+    {
+        let parsed = attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("type_id") {
+                type_id = Some(meta.value()?.parse()?);
+                Ok(())
+            } else {
+                Err(meta.error("expected `type_id = ...`"))
+            }
+        });
+        if let Err(e) = parsed {
+            return e.to_compile_error().into();
+        }
+    }
+
+    let name = &ast.ident;
+    let type_id = type_id.unwrap_or_else(|| {
+        let const_params = ast.generics.const_params().map(|param| &param.ident);
+        parse_quote! {
+            tuxedo_core::dynamic_typing::type_id_from_name(
+                concat!(env!("CARGO_PKG_NAME"), "::", stringify!(#name)),
+                &[ #( #const_params as u64, )* ],
+            )
+        }
+    });
+
+    let (impl_generics, type_generics, where_clause) = ast.generics.split_for_impl();
+
+    let output = quote! {
+        impl #impl_generics tuxedo_core::dynamic_typing::UtxoData for #name #type_generics #where_clause {
+            const TYPE_ID: [u8; 4] = #type_id;
         }
     };
 
@@ -0,0 +1,89 @@
+//! The ages of outputs, which let pieces reclaim old outputs that nobody spends.
+//!
+//! Runtimes may opt into tracking ages by setting [`TRACK_AGES`](crate::weights::WeightConfig::TRACK_AGES).
+//! Then the executive remembers the block height at which every output is created, and tells the
+//! constraint checker how old each input is in the [`CheckerContext`](crate::CheckerContext).
+//! Outputs in the genesis state, and outputs created before the runtime started tracking ages,
+//! have no known age.
+//!
+//! Ages are what a piece needs to offer a reclaim policy, such as letting anyone remove dust coins
+//! that nobody has touched for a long time. Such inputs are spent with
+//! [`InputMode::Reclaim`](crate::types::InputMode::Reclaim), which skips their verifiers. Only
+//! constraint checkers that [reclaim](crate::ConstraintChecker::reclaims) may be called with them,
+//! and those checkers are responsible for making sure that each one may really be reclaimed.
+
+use crate::types::OutputRef;
+use parity_scale_codec::{Decode, Encode};
+use sp_std::vec::Vec;
+
+/// The storage prefix under which the block height that each output was created at is kept.
+pub const CREATED_PREFIX: &[u8] = b"created";
+
+fn key(output_ref: &OutputRef) -> Vec<u8> {
+    let mut key = CREATED_PREFIX.to_vec();
+    key.extend(output_ref.encode());
+    key
+}
+
+/// The block height at which the given output was created, if it is known.
+pub fn created_at(output_ref: &OutputRef) -> Option<u32> {
+    sp_io::storage::get(&key(output_ref)).and_then(|d| u32::decode(&mut &*d).ok())
+}
+
+/// How many blocks before the given height the given output was created, if it is known.
+pub fn age(output_ref: &OutputRef, block_height: u32) -> Option<u32> {
+    created_at(output_ref).map(|created| block_height.saturating_sub(created))
+}
+
+/// Note that the given output was created at the given block height.
+pub(crate) fn note(output_ref: &OutputRef, block_height: u32) {
+    sp_io::storage::set(&key(output_ref), &block_height.encode());
+}
+
+/// Forget when an output that is being removed was created.
+pub(crate) fn forget(output_ref: &OutputRef) {
+    sp_io::storage::clear(&key(output_ref));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sp_core::H256;
+    use sp_io::TestExternalities;
+
+    fn output_ref() -> OutputRef {
+        OutputRef {
+            tx_hash: H256::repeat_byte(1),
+            index: 0,
+        }
+    }
+
+    #[test]
+    fn unknown_outputs_have_no_age() {
+        TestExternalities::default().execute_with(|| {
+            assert_eq!(created_at(&output_ref()), None);
+            assert_eq!(age(&output_ref(), 10), None);
+        });
+    }
+
+    #[test]
+    fn age_counts_blocks_since_creation() {
+        TestExternalities::default().execute_with(|| {
+            note(&output_ref(), 4);
+
+            assert_eq!(created_at(&output_ref()), Some(4));
+            assert_eq!(age(&output_ref(), 10), Some(6));
+            assert_eq!(age(&output_ref(), 4), Some(0));
+        });
+    }
+
+    #[test]
+    fn forgotten_outputs_have_no_age() {
+        TestExternalities::default().execute_with(|| {
+            note(&output_ref(), 4);
+            forget(&output_ref());
+
+            assert_eq!(created_at(&output_ref()), None);
+        });
+    }
+}
@@ -0,0 +1,270 @@
+//! A harness for measuring the weight of constraint checkers and verifiers, in the spirit of
+//! FRAME's benchmarking.
+//!
+//! Each piece describes the worst case inputs for its constraint checkers as [`CheckerCase`]s,
+//! and verifiers are described by [`VerifierCase`]s. The harness runs every case many times in
+//! fresh test externalities, holding only the storage that the case needs, and reports the median
+//! execution time as its weight, along with the number of bytes that are read from storage on the
+//! case's behalf, which is what the case adds to a parachain's proof of validity.
+//!
+//! The results can be rendered as weight constants with [`render`]. Pieces include the rendered
+//! file, and return its constants from `ConstraintChecker::weight` and `Verifier::weight`.
+//!
+//! The measured times depend on the machine that runs the benchmarks, so they should be run on
+//! reference hardware, in release mode.
+
+use crate::{
+    types::{Output, OutputRef},
+    verifier::{SigCheck, UpForGrabs, VerifierContext},
+    weights::Weight,
+    CheckerContext, ConstraintChecker, Verifier,
+};
+use parity_scale_codec::Encode;
+use sp_core::{sr25519, Pair, H256};
+use sp_io::TestExternalities;
+use std::time::Instant;
+
+/// The number of times each case runs when no other number is given.
+pub const DEFAULT_REPEATS: u32 = 50;
+
+/// The measured cost of a single case.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BenchmarkResult {
+    /// The name of the case that was measured.
+    pub name: String,
+    /// The median execution time, in picoseconds.
+    pub weight: Weight,
+    /// The number of bytes read from storage for this case.
+    pub proof_size: u64,
+}
+
+/// The worst case inputs for a constraint checker.
+pub struct CheckerCase<V, C> {
+    /// A unique name for the case, such as `money_spend`.
+    pub name: &'static str,
+    pub checker: C,
+    pub inputs: Vec<Output<V>>,
+    pub peeks: Vec<Output<V>>,
+    pub outputs: Vec<Output<V>>,
+    pub context: CheckerContext,
+    /// Raw storage entries that the checker reads, written before each run.
+    pub storage: Vec<(Vec<u8>, Vec<u8>)>,
+}
+
+/// The worst case inputs for a verifier.
+pub struct VerifierCase<V> {
+    /// A unique name for the case, such as `sig_check`.
+    pub name: &'static str,
+    pub verifier: V,
+    pub simplified_tx: Vec<u8>,
+    pub redeemer: Vec<u8>,
+    pub context: VerifierContext,
+}
+
+/// Run the closure the given number of times, each time in fresh externalities that hold the
+/// given storage, and return the median time it took in picoseconds.
+fn median_time(repeats: u32, storage: &[(Vec<u8>, Vec<u8>)], mut f: impl FnMut()) -> Weight {
+    let mut times: Vec<u128> = (0..repeats.max(1))
+        .map(|_| {
+            TestExternalities::default().execute_with(|| {
+                for (key, value) in storage {
+                    sp_io::storage::set(key, value);
+                }
+                let start = Instant::now();
+                f();
+                start.elapsed().as_nanos()
+            })
+        })
+        .collect();
+    times.sort_unstable();
+
+    let nanos = times[times.len() / 2];
+    Weight::try_from(nanos.saturating_mul(1_000)).unwrap_or(Weight::MAX)
+}
+
+/// The number of bytes that the executive reads from storage to fetch the given utxos.
+fn read_size<V: Encode>(utxos: &[Output<V>]) -> u64 {
+    let key_size = OutputRef {
+        tx_hash: H256::zero(),
+        index: 0,
+    }
+    .encoded_size();
+
+    utxos
+        .iter()
+        .map(|utxo| (key_size + utxo.encoded_size()) as u64)
+        .sum()
+}
+
+/// Measure a constraint checker. The checker must accept the case; a benchmark of the
+/// error path would underestimate the weight.
+pub fn bench_checker<V, C>(case: &CheckerCase<V, C>, repeats: u32) -> BenchmarkResult
+where
+    V: Verifier,
+    C: ConstraintChecker<V>,
+{
+    let weight = median_time(repeats, &case.storage, || {
+        let result = case
+            .checker
+            .check(&case.inputs, &case.peeks, &case.outputs, &case.context);
+        assert!(result.is_ok(), "case {} must pass: {:?}", case.name, result);
+    });
+
+    BenchmarkResult {
+        name: case.name.into(),
+        weight,
+        proof_size: read_size(&case.inputs)
+            + read_size(&case.peeks)
+            + case
+                .storage
+                .iter()
+                .map(|(key, value)| (key.len() + value.len()) as u64)
+                .sum::<u64>(),
+    }
+}
+
+/// Measure a verifier. The verifier must accept the case.
+/// Verifiers do not read storage, so they add nothing to the proof size.
+pub fn bench_verifier<V: Verifier>(case: &VerifierCase<V>, repeats: u32) -> BenchmarkResult {
+    let weight = median_time(repeats, &[], || {
+        let verified = case
+            .verifier
+            .verify(&case.simplified_tx, &case.redeemer, &case.context);
+        assert!(verified, "case {} must pass", case.name);
+    });
+
+    BenchmarkResult {
+        name: case.name.into(),
+        weight,
+        proof_size: 0,
+    }
+}
+
+/// Measure the verifiers in Tuxedo core whose cost does not depend on how they are configured.
+pub fn bench_core_verifiers(repeats: u32) -> Vec<BenchmarkResult> {
+    let pair = sr25519::Pair::from_seed(&[1; 32]);
+    let simplified_tx = vec![7; 256];
+    let sig_check = VerifierCase {
+        name: "sig_check",
+        verifier: SigCheck::new(pair.public()),
+        redeemer: pair.sign(&simplified_tx).as_ref().to_vec(),
+        simplified_tx: simplified_tx.clone(),
+        context: VerifierContext::default(),
+    };
+    let up_for_grabs = VerifierCase {
+        name: "up_for_grabs",
+        verifier: UpForGrabs,
+        simplified_tx,
+        redeemer: Vec::new(),
+        context: VerifierContext::default(),
+    };
+
+    vec![
+        bench_verifier(&sig_check, repeats),
+        bench_verifier(&up_for_grabs, repeats),
+    ]
+}
+
+/// The name of the constant that holds the weight of the named case.
+fn constant_name(name: &str) -> String {
+    let name: String = name
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_uppercase()
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    format!("{name}_WEIGHT")
+}
+
+/// Render the results as a Rust source file of weight constants.
+pub fn render(results: &[BenchmarkResult]) -> String {
+    let mut rendered = String::from(
+        "//! Weights measured by the Tuxedo benchmarking harness. Do not edit by hand.\n\n\
+         use tuxedo_core::weights::Weight;\n",
+    );
+    for result in results {
+        rendered.push_str(&format!(
+            "\n/// Reads {} bytes from storage.\npub const {}: Weight = {};\n",
+            result.proof_size,
+            constant_name(&result.name),
+            result.weight,
+        ));
+    }
+    rendered
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{constraint_checker::testing::TestConstraintChecker, verifier::TestVerifier};
+
+    #[test]
+    fn checker_case_counts_reads() {
+        let input: Output<TestVerifier> = Output {
+            payload: crate::dynamic_typing::testing::Bogus.into(),
+            verifier: TestVerifier { verifies: true },
+        };
+        let case = CheckerCase {
+            name: "test",
+            checker: TestConstraintChecker {
+                checks: true,
+                inherent: false,
+            },
+            inputs: vec![input.clone()],
+            peeks: vec![input.clone()],
+            outputs: vec![input.clone()],
+            context: CheckerContext::default(),
+            storage: Vec::new(),
+        };
+
+        let result = bench_checker(&case, 3);
+
+        assert_eq!(result.name, "test");
+        assert_eq!(result.proof_size, 2 * (36 + input.encoded_size() as u64));
+    }
+
+    #[test]
+    #[should_panic]
+    fn failing_checker_case_panics() {
+        let case: CheckerCase<TestVerifier, _> = CheckerCase {
+            name: "test",
+            checker: TestConstraintChecker {
+                checks: false,
+                inherent: false,
+            },
+            inputs: Vec::new(),
+            peeks: Vec::new(),
+            outputs: Vec::new(),
+            context: CheckerContext::default(),
+            storage: Vec::new(),
+        };
+
+        bench_checker(&case, 1);
+    }
+
+    #[test]
+    fn core_verifiers_pass() {
+        let results = bench_core_verifiers(1);
+
+        assert_eq!(results.len(), 2);
+        assert!(results.iter().all(|r| r.proof_size == 0));
+    }
+
+    #[test]
+    fn render_names_constants() {
+        let results = vec![BenchmarkResult {
+            name: "money_spend".into(),
+            weight: 1_234,
+            proof_size: 56,
+        }];
+
+        let rendered = render(&results);
+
+        assert!(rendered.contains("/// Reads 56 bytes from storage.\n"));
+        assert!(rendered.contains("pub const MONEY_SPEND_WEIGHT: Weight = 1234;\n"));
+    }
+}
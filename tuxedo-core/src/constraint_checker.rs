@@ -3,15 +3,60 @@
 //! Constraint Checkers do not typically calculate the correct final state, but rather determine whether the
 //! proposed final state (as specified by the output set) meets the necessary constraints.
 
-use sp_std::{fmt::Debug, vec::Vec};
+use sp_std::{fmt::Debug, ops::Range, vec::Vec};
 
-use crate::{dynamic_typing::DynamicallyTypedData, inherents::InherentInternal, types::Output};
+use crate::{
+    dynamic_typing::DynamicallyTypedData,
+    inherents::InherentInternal,
+    types::{CheckerErrorName, Output},
+    weights::{Weight, DEFAULT_CHECKER_WEIGHT},
+};
 use parity_scale_codec::{Decode, Encode};
-use sp_runtime::transaction_validity::TransactionPriority;
+use scale_info::TypeInfo;
+use serde::{Deserialize, Serialize};
+use sp_runtime::{transaction_validity::TransactionPriority, Digest};
+
+/// Information about the block that a transaction is checked in, and about the parts of the
+/// transaction that are not passed to the constraint checker directly.
+///
+/// While a block is being built or imported, this describes that block. In the pool there is no
+/// such block, so the height is that of the block after the one that noted the best known time,
+/// and the digest is empty.
+#[derive(Encode, Decode, Debug, Default, PartialEq, Eq, Clone, TypeInfo)]
+pub struct CheckerContext {
+    /// The height of the block.
+    pub block_height: u32,
+    /// The digest of the block, as it was when the block was opened.
+    /// This is where consensus engines note information such as the block author.
+    pub digest: Digest,
+    /// The redeemers of the transaction's inputs, in the same order as the inputs that are
+    /// passed to the constraint checker.
+    pub redeemers: Vec<Vec<u8>>,
+    /// The encoded verifiers of the transaction's inputs, in the same order as the inputs.
+    ///
+    /// These let a [`SimpleConstraintChecker`] compare owners, for example to make sure that
+    /// change goes back to the sender. Checkers that need to look inside verifiers should
+    /// implement the full [`ConstraintChecker`] instead, which is given the verifiers themselves.
+    pub input_verifiers: Vec<Vec<u8>>,
+    /// The encoded verifiers of the transaction's outputs, in the same order as the outputs.
+    pub output_verifiers: Vec<Vec<u8>>,
+    /// The transaction's memo, if it carries one. Most checkers ignore it.
+    pub memo: Option<Vec<u8>>,
+    /// Whether each of the transaction's inputs is reclaimed rather than consumed, in the same
+    /// order as the inputs. Reclaimed inputs did not have to satisfy their verifiers.
+    pub reclaimed: Vec<bool>,
+    /// How many blocks ago each of the transaction's inputs was created, in the same order as the
+    /// inputs. An age is `None` when it is not known, for example when the runtime does not track
+    /// ages. See the [`ages`](crate::ages) module.
+    pub input_ages: Vec<Option<u32>>,
+}
 
 /// A simplified constraint checker that a transaction can choose to call.
 /// Checks whether the input and output data from a transaction meets the codified constraints.
 ///
+/// Only the payloads are passed directly. The encoded verifiers of the inputs and outputs, and
+/// the redeemers of the inputs, are available in the [`CheckerContext`] for checkers that need them.
+///
 /// Additional transient information may be passed to the constraint checker by including it in the fields
 /// of the constraint checker struct itself. Information passed in this way does not come from state, nor
 /// is it stored in state.
@@ -20,12 +65,26 @@ pub trait SimpleConstraintChecker: Debug + Encode + Decode + Clone {
     type Error: Debug;
 
     /// The actual check validation logic
+    ///
+    /// On success, returns the value that the transaction burns. The executive takes the
+    /// transaction's fee out of it, and whatever is left over is a tip for the block author.
     fn check(
         &self,
         input_data: &[DynamicallyTypedData],
         peek_data: &[DynamicallyTypedData],
         output_data: &[DynamicallyTypedData],
+        context: &CheckerContext,
     ) -> Result<TransactionPriority, Self::Error>;
+
+    /// The weight of the check. Checkers that do more than a little arithmetic should override this.
+    fn weight(&self) -> Weight {
+        DEFAULT_CHECKER_WEIGHT
+    }
+
+    /// Whether this checker may be called with reclaimed inputs. See [`ConstraintChecker::reclaims`].
+    fn reclaims(&self) -> bool {
+        false
+    }
 }
 
 /// A single constraint checker that a transaction can choose to call. Checks whether the input
@@ -47,16 +106,44 @@ pub trait ConstraintChecker<V>: Debug + Encode + Decode + Clone {
     type InherentHooks: InherentInternal<V, Self>;
 
     /// The actual check validation logic
+    ///
+    /// On success, returns the value that the transaction burns. The executive takes the
+    /// transaction's fee out of it, and whatever is left over is a tip for the block author.
     fn check(
         &self,
         inputs: &[Output<V>],
         peeks: &[Output<V>],
         outputs: &[Output<V>],
+        context: &CheckerContext,
     ) -> Result<TransactionPriority, Self::Error>;
 
     /// Tells whether this extrinsic is an inherent or not.
     /// If you return true here, you must provide the correct inherent hooks above.
     fn is_inherent(&self) -> bool;
+
+    /// The weight of the check. Checkers that do more than a little arithmetic should override this.
+    fn weight(&self) -> Weight {
+        DEFAULT_CHECKER_WEIGHT
+    }
+
+    /// Whether this checker may be called with inputs that are reclaimed without satisfying their
+    /// verifiers. See [`InputMode::Reclaim`](crate::types::InputMode::Reclaim).
+    ///
+    /// A checker that returns true here takes the verifiers' place. It must check every input that
+    /// the context marks as reclaimed, and only accept those that its piece's policy lets anyone
+    /// take. By default, checkers do not reclaim, and the executive refuses reclaimed inputs.
+    fn reclaims(&self) -> bool {
+        false
+    }
+
+    /// Name the given error for clients, such as wallets that dry run their transactions.
+    ///
+    /// By default the error is named after its debug representation and not attributed to any piece.
+    /// The outer constraint checker that the `tuxedo_constraint_checker` macro aggregates attributes
+    /// each error to the piece it came from.
+    fn name_error(error: &Self::Error) -> CheckerErrorName {
+        CheckerErrorName::from_debug(error)
+    }
 }
 
 // This blanket implementation makes it so that any type that chooses to
@@ -73,6 +160,7 @@ impl<T: SimpleConstraintChecker, V> ConstraintChecker<V> for T {
         inputs: &[Output<V>],
         peeks: &[Output<V>],
         outputs: &[Output<V>],
+        context: &CheckerContext,
     ) -> Result<TransactionPriority, Self::Error> {
         // Extract the input data
         let input_data: Vec<DynamicallyTypedData> =
@@ -87,20 +175,139 @@ impl<T: SimpleConstraintChecker, V> ConstraintChecker<V> for T {
             outputs.iter().map(|o| o.payload.clone()).collect();
 
         // Call the simple constraint checker
-        SimpleConstraintChecker::check(self, &input_data, &peek_data, &output_data)
+        SimpleConstraintChecker::check(self, &input_data, &peek_data, &output_data, context)
     }
 
     fn is_inherent(&self) -> bool {
         false
     }
+
+    fn weight(&self) -> Weight {
+        SimpleConstraintChecker::weight(self)
+    }
+
+    fn reclaims(&self) -> bool {
+        SimpleConstraintChecker::reclaims(self)
+    }
+}
+
+/// A part of a transaction, given by the positions of its inputs, peeks, and outputs, that one
+/// constraint checker hands to another to check.
+///
+/// Pieces often need logic that another piece already provides. For example, a marketplace that
+/// sells kitties for coins must check its payment the same way the money piece checks a spend.
+/// Rather than reimplement coin accounting, its checker checks its own part of the transaction
+/// and delegates the payment to the money piece's checker.
+///
+/// A checker that delegates should add the delegate's weight to its own.
+#[derive(Debug, Default, PartialEq, Eq, Clone)]
+pub struct Delegation {
+    /// The positions of the delegated inputs, in the order the delegate sees them.
+    pub inputs: Vec<usize>,
+    /// The positions of the delegated peeks, in the order the delegate sees them.
+    pub peeks: Vec<usize>,
+    /// The positions of the delegated outputs, in the order the delegate sees them.
+    pub outputs: Vec<usize>,
+}
+
+/// An error from checking a delegated part of a transaction.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum DelegationError<E> {
+    /// The delegation names an input, peek, or output that the transaction does not have.
+    OutOfRange,
+    /// The delegate rejected its part of the transaction.
+    Rejected(E),
+}
+
+impl Delegation {
+    /// Delegate contiguous ranges of the inputs, peeks, and outputs.
+    pub fn ranges(inputs: Range<usize>, peeks: Range<usize>, outputs: Range<usize>) -> Self {
+        Self {
+            inputs: inputs.collect(),
+            peeks: peeks.collect(),
+            outputs: outputs.collect(),
+        }
+    }
+
+    /// Check the delegated part of a transaction with another constraint checker.
+    ///
+    /// The delegate is given only the delegated inputs, peeks, and outputs. Everything that the
+    /// context says about each input and output is narrowed to match, so the delegate checks its part exactly as
+    /// if it were a transaction of its own. On success, returns the value that part burns.
+    pub fn check<V: Clone, C: ConstraintChecker<V>>(
+        &self,
+        checker: &C,
+        inputs: &[Output<V>],
+        peeks: &[Output<V>],
+        outputs: &[Output<V>],
+        context: &CheckerContext,
+    ) -> Result<TransactionPriority, DelegationError<C::Error>> {
+        let sub_context = CheckerContext {
+            block_height: context.block_height,
+            digest: context.digest.clone(),
+            redeemers: narrow(&context.redeemers, &self.inputs),
+            input_verifiers: narrow(&context.input_verifiers, &self.inputs),
+            output_verifiers: narrow(&context.output_verifiers, &self.outputs),
+            memo: context.memo.clone(),
+            reclaimed: narrow(&context.reclaimed, &self.inputs),
+            input_ages: narrow(&context.input_ages, &self.inputs),
+        };
+
+        checker
+            .check(
+                &select(inputs, &self.inputs)?,
+                &select(peeks, &self.peeks)?,
+                &select(outputs, &self.outputs)?,
+                &sub_context,
+            )
+            .map_err(DelegationError::Rejected)
+    }
+}
+
+/// Pick the items at the given positions, failing if any of them is missing.
+fn select<T: Clone, E>(items: &[T], positions: &[usize]) -> Result<Vec<T>, DelegationError<E>> {
+    positions
+        .iter()
+        .map(|&i| items.get(i).cloned().ok_or(DelegationError::OutOfRange))
+        .collect()
+}
+
+/// Pick the context entries at the given positions. The context may not describe every input
+/// and output, for example in unit tests, so missing entries are skipped rather than an error.
+fn narrow<T: Clone>(items: &[T], positions: &[usize]) -> Vec<T> {
+    positions
+        .iter()
+        .filter_map(|&i| items.get(i).cloned())
+        .collect()
+}
+
+/// The constraint checker of the transaction that creates the outputs declared directly in the
+/// genesis configuration, rather than minted by a piece's own genesis section.
+///
+/// Genesis transactions are never checked, so this checker only labels that transaction.
+/// It rejects every transaction after genesis, so nobody can use it to create outputs from nothing.
+#[derive(
+    Serialize, Deserialize, Encode, Decode, Debug, Default, PartialEq, Eq, Clone, TypeInfo,
+)]
+pub struct GenesisOnly;
+
+impl SimpleConstraintChecker for GenesisOnly {
+    type Error = &'static str;
+
+    fn check(
+        &self,
+        _input_data: &[DynamicallyTypedData],
+        _peeks: &[DynamicallyTypedData],
+        _output_data: &[DynamicallyTypedData],
+        _context: &CheckerContext,
+    ) -> Result<TransactionPriority, Self::Error> {
+        Err("Outputs can only be declared in the genesis block")
+    }
 }
 
 /// Utilities for writing constraint-checker-related unit tests
 #[cfg(test)]
 pub mod testing {
-    use scale_info::TypeInfo;
-    use serde::{Deserialize, Serialize};
-
     use super::*;
     use crate::{types::Output, verifier::TestVerifier};
 
@@ -123,6 +330,7 @@ pub mod testing {
             _input_data: &[Output<TestVerifier>],
             _peek_data: &[Output<TestVerifier>],
             _output_data: &[Output<TestVerifier>],
+            _context: &CheckerContext,
         ) -> Result<TransactionPriority, ()> {
             if self.checks {
                 Ok(0)
@@ -142,7 +350,7 @@ pub mod testing {
             checks: true,
             inherent: false,
         }
-        .check(&[], &[], &[]);
+        .check(&[], &[], &[], &CheckerContext::default());
         assert_eq!(result, Ok(0));
     }
 
@@ -152,7 +360,142 @@ pub mod testing {
             checks: false,
             inherent: false,
         }
-        .check(&[], &[], &[]);
+        .check(&[], &[], &[], &CheckerContext::default());
         assert_eq!(result, Err(()));
     }
+
+    #[test]
+    fn genesis_only_checker_fails() {
+        let result =
+            SimpleConstraintChecker::check(&GenesisOnly, &[], &[], &[], &CheckerContext::default());
+        assert!(result.is_err());
+    }
+
+    /// A small piece of data for the delegation tests.
+    #[derive(Encode, Decode, Debug, PartialEq, Eq, Clone)]
+    struct Byte(u8);
+
+    impl crate::dynamic_typing::UtxoData for Byte {
+        const TYPE_ID: [u8; 4] = *b"byte";
+    }
+
+    /// A checker that burns the sum of its input bytes less the sum of its output bytes,
+    /// and insists on a redeemer for every input and a verifier for every output.
+    #[derive(Encode, Decode, Debug, Clone)]
+    struct ByteSpend;
+
+    impl SimpleConstraintChecker for ByteSpend {
+        type Error = &'static str;
+
+        fn check(
+            &self,
+            input_data: &[DynamicallyTypedData],
+            _peek_data: &[DynamicallyTypedData],
+            output_data: &[DynamicallyTypedData],
+            context: &CheckerContext,
+        ) -> Result<TransactionPriority, Self::Error> {
+            if context.redeemers.len() != input_data.len()
+                || context.output_verifiers.len() != output_data.len()
+            {
+                return Err("context does not match the transaction");
+            }
+            let sum = |data: &[DynamicallyTypedData]| {
+                data.iter()
+                    .map(|d| d.extract::<Byte>().map(|b| b.0 as u64))
+                    .sum::<Result<u64, _>>()
+                    .map_err(|_| "not a byte")
+            };
+            sum(input_data)?
+                .checked_sub(sum(output_data)?)
+                .ok_or("outputs exceed inputs")
+        }
+    }
+
+    fn byte(b: u8) -> Output<TestVerifier> {
+        Output {
+            payload: Byte(b).into(),
+            verifier: TestVerifier { verifies: true },
+        }
+    }
+
+    fn bogus() -> Output<TestVerifier> {
+        Output {
+            payload: crate::dynamic_typing::testing::Bogus.into(),
+            verifier: TestVerifier { verifies: true },
+        }
+    }
+
+    /// A context with one redeemer and verifier per input, and one verifier per output.
+    fn context_for(inputs: usize, outputs: usize) -> CheckerContext {
+        CheckerContext {
+            redeemers: (0..inputs as u8).map(|i| vec![i]).collect(),
+            input_verifiers: (0..inputs as u8).map(|i| vec![i]).collect(),
+            output_verifiers: (0..outputs as u8).map(|i| vec![i]).collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn delegation_checks_only_the_delegated_part() {
+        let inputs = vec![byte(5), bogus(), byte(7)];
+        let outputs = vec![bogus(), byte(9)];
+        let delegation = Delegation {
+            inputs: vec![0, 2],
+            peeks: vec![],
+            outputs: vec![1],
+        };
+
+        let result = delegation.check(
+            &ByteSpend,
+            &inputs,
+            &[],
+            &outputs,
+            &context_for(inputs.len(), outputs.len()),
+        );
+        assert_eq!(result, Ok(3));
+    }
+
+    #[test]
+    fn delegation_with_ranges_works() {
+        let inputs = vec![bogus(), byte(5), byte(7)];
+        let outputs = vec![bogus(), byte(9), byte(1)];
+        let delegation = Delegation::ranges(1..3, 0..0, 1..3);
+
+        let result = delegation.check(
+            &ByteSpend,
+            &inputs,
+            &[],
+            &outputs,
+            &context_for(inputs.len(), outputs.len()),
+        );
+        assert_eq!(result, Ok(2));
+    }
+
+    #[test]
+    fn delegation_passes_on_the_delegates_error() {
+        let inputs = vec![byte(5)];
+        let outputs = vec![byte(6)];
+        let delegation = Delegation::ranges(0..1, 0..0, 0..1);
+
+        let result = delegation.check(
+            &ByteSpend,
+            &inputs,
+            &[],
+            &outputs,
+            &context_for(inputs.len(), outputs.len()),
+        );
+        assert_eq!(
+            result,
+            Err(DelegationError::Rejected("outputs exceed inputs"))
+        );
+    }
+
+    #[test]
+    fn delegation_out_of_range_fails() {
+        let inputs = vec![byte(5)];
+        let delegation = Delegation::ranges(0..2, 0..0, 0..0);
+
+        let result = delegation.check(&ByteSpend, &inputs, &[], &[], &context_for(inputs.len(), 0));
+        assert_eq!(result, Err(DelegationError::OutOfRange));
+    }
 }
@@ -0,0 +1,326 @@
+//! Assembling a runtime from a list of verifiers and pieces.
+//!
+//! Every Tuxedo runtime declares the same handful of items: an outer verifier and an outer
+//! constraint checker that aggregate its verifiers and pieces, the genesis sections of the pieces
+//! that need initial state, a description of its types for clients, and the type aliases that tie
+//! them all to the executive. The [`construct_tuxedo_runtime`](crate::construct_tuxedo_runtime)
+//! macro writes all of them from the lists alone, so that adding a piece to a runtime is one line.
+
+/// Declare a runtime's aggregate types, genesis configuration, metadata, and type aliases.
+///
+/// ```ignore
+/// tuxedo_core::construct_tuxedo_runtime! {
+///     runtime: Runtime,
+///     version: Version,
+///     pool_policy: (),
+///     verifiers: {
+///         SigCheck(SigCheck),
+///         UpForGrabs(UpForGrabs),
+///     },
+///     pieces: {
+///         /// Checks monetary transactions
+///         Money(money::MoneyConstraintChecker<0, Runtime>),
+///         #[cfg(feature = "kitties")]
+///         FreeKitty(kitties::FreeKittyConstraintChecker<Runtime>),
+///     },
+///     utxo_types: [money::Coin<0>],
+///     describe_utxo_types: [poe::describe_utxo_types],
+///     genesis: {
+///         money: money::genesis::MoneyGenesisConfig<0, OuterVerifier, Runtime>,
+///     },
+///     opaque: {},
+/// }
+/// ```
+///
+/// This declares:
+/// - the runtime struct itself,
+/// - `OuterVerifier`, aggregated with `tuxedo_verifier`, which wallets can also use to collect
+///   signatures because every verifier must implement `pstt::SignatureRedeemer`,
+/// - `OuterConstraintChecker`, aggregated with `tuxedo_constraint_checker`,
+/// - `GenesisSections` and `RuntimeGenesisConfig`, when the `std` feature is enabled,
+/// - a `metadata` function on the runtime that registers the listed UTXO types, and calls the
+///   listed functions to register the types that pieces keep private,
+/// - the `Transaction`, `BlockNumber`, `Header`, `Block`, `Executive`, and `Output` aliases,
+/// - an `opaque` module with the opaque `Block` and `Hash` types, plus any items given under
+///   `opaque`, such as the session keys.
+///
+/// The version and the pool policy fill in the matching parameters of the executive. The
+/// executive's weight config and off-chain workers default to `()`, and may be given with
+/// `weights: ...,` and `offchain_workers: ...,` right after the pool policy.
+///
+/// Attributes written before `verifiers` are added to `OuterVerifier`, for example to derive
+/// `arbitrary::Arbitrary` for fuzzing.
+///
+/// Verifiers, pieces, and UTXO types may be feature gated with `#[cfg(...)]`. Genesis sections
+/// may not, because every section is part of the genesis file's format.
+///
+/// The generated code derives the SCALE codec, `TypeInfo`, and serde traits, so the runtime must
+/// depend on `parity-scale-codec`, `scale-info`, `serde`, `sp-core`, `sp-inherents`, `sp-runtime`,
+/// and `sp-std`, as runtimes do anyway. Like the aggregation macros it uses, it also expects
+/// `Vec` and `TransactionPriority` to be in scope.
+#[macro_export]
+macro_rules! construct_tuxedo_runtime {
+    (@or [] $default:ty) => { $default };
+    (@or [$given:ty] $default:ty) => { $given };
+
+    (
+        runtime: $runtime:ident,
+        version: $version:ty,
+        pool_policy: $pool_policy:ty,
+        $( weights: $weights:ty, )?
+        $( offchain_workers: $offchain_workers:ty, )?
+        $(#[$verifiers_attr:meta])*
+        verifiers: {
+            $( $(#[$verifier_attr:meta])* $verifier:ident($verifier_ty:ty) ),* $(,)?
+        },
+        pieces: {
+            $( $(#[$piece_attr:meta])* $piece:ident($piece_ty:ty) ),* $(,)?
+        },
+        utxo_types: [ $( $(#[$utxo_attr:meta])* $utxo_ty:ty ),* $(,)? ],
+        describe_utxo_types: [ $( $(#[$describe_attr:meta])* $describe:path ),* $(,)? ],
+        genesis: {
+            $( $(#[$section_attr:meta])* $section:ident: $section_ty:ty ),* $(,)?
+        },
+        opaque: { $( $opaque_item:item )* } $(,)?
+    ) => {
+        /// The runtime, which ties this runtime's pieces to the executive and the runtime APIs.
+        #[derive(
+            ::parity_scale_codec::Encode,
+            ::parity_scale_codec::Decode,
+            PartialEq,
+            Eq,
+            Clone,
+            ::scale_info::TypeInfo,
+        )]
+        pub struct $runtime;
+
+        /// A verifier checks that an individual input can be consumed, for example that it is
+        /// signed properly. This is every kind of verifier that this runtime accepts.
+        #[derive(
+            ::serde::Serialize,
+            ::serde::Deserialize,
+            ::parity_scale_codec::Encode,
+            ::parity_scale_codec::Decode,
+            Debug,
+            PartialEq,
+            Eq,
+            Clone,
+            ::scale_info::TypeInfo,
+        )]
+        $(#[$verifiers_attr])*
+        #[$crate::tuxedo_verifier]
+        pub enum OuterVerifier {
+            $( $(#[$verifier_attr])* $verifier($verifier_ty), )*
+        }
+
+        // Lets wallets collect signatures for any of this runtime's verifiers in a partially
+        // signed transaction.
+        #[cfg(feature = "std")]
+        impl $crate::pstt::SignatureRedeemer for OuterVerifier {
+            #[allow(unused_doc_comments)]
+            fn signers(&self) -> ::sp_std::vec::Vec<::sp_core::H256> {
+                match self {
+                    $( $(#[$verifier_attr])* Self::$verifier(v) => $crate::pstt::SignatureRedeemer::signers(v), )*
+                }
+            }
+
+            #[allow(unused_doc_comments)]
+            fn assemble_redeemer(
+                &self,
+                signatures: &::std::collections::BTreeMap<::sp_core::H256, ::sp_std::vec::Vec<u8>>,
+            ) -> Option<::sp_std::vec::Vec<u8>> {
+                match self {
+                    $( $(#[$verifier_attr])* Self::$verifier(v) => {
+                        $crate::pstt::SignatureRedeemer::assemble_redeemer(v, signatures)
+                    } )*
+                }
+            }
+        }
+
+        /// A constraint checker is a piece of logic that can be used to check a transaction.
+        /// This is every constraint checker that this runtime's pieces provide.
+        #[derive(
+            ::serde::Serialize,
+            ::serde::Deserialize,
+            ::parity_scale_codec::Encode,
+            ::parity_scale_codec::Decode,
+            Debug,
+            PartialEq,
+            Eq,
+            Clone,
+            ::scale_info::TypeInfo,
+        )]
+        #[$crate::tuxedo_constraint_checker(OuterVerifier)]
+        pub enum OuterConstraintChecker {
+            $( $(#[$piece_attr])* $piece($piece_ty), )*
+        }
+
+        /// The genesis sections of each piece in this runtime that needs some initial state.
+        /// Pieces that only need inherents at genesis do not need a section here.
+        ///
+        /// The sections can also be read from a JSON genesis file, in which each section is keyed
+        /// by its field name. Sections that the file leaves out start empty, and unknown sections
+        /// are refused.
+        #[cfg(feature = "std")]
+        #[derive(::serde::Serialize, ::serde::Deserialize, Default)]
+        #[serde(default, deny_unknown_fields)]
+        #[$crate::tuxedo_genesis_config(OuterVerifier, OuterConstraintChecker)]
+        pub struct GenesisSections {
+            $( $(#[$section_attr])* pub $section: $section_ty, )*
+        }
+
+        /// Helper type for the ChainSpec.
+        #[cfg(feature = "std")]
+        pub type RuntimeGenesisConfig =
+            $crate::genesis::TuxedoGenesisConfig<OuterVerifier, OuterConstraintChecker>;
+
+        impl $runtime {
+            /// A description of this runtime's types for clients
+            pub fn metadata() -> $crate::metadata::TuxedoMetadata {
+                let builder = $crate::metadata::MetadataBuilder::new::<
+                    OuterVerifier,
+                    OuterConstraintChecker,
+                >();
+                $(
+                    $(#[$utxo_attr])*
+                    let builder = builder.with_utxo_type::<$utxo_ty>();
+                )*
+                $(
+                    $(#[$describe_attr])*
+                    let builder = builder.with_piece($describe);
+                )*
+                builder.build()
+            }
+        }
+
+        /// Opaque types. These are used by the CLI to instantiate machinery that don't need to
+        /// know the specifics of the runtime. They can then be made to be agnostic over specific
+        /// formats of data like extrinsics, allowing for them to continue syncing the network
+        /// through upgrades to even the core data structures.
+        pub mod opaque {
+            use super::*;
+
+            /// Opaque block type.
+            pub type Block = ::sp_runtime::generic::Block<Header, ::sp_runtime::OpaqueExtrinsic>;
+            /// Opaque block hash type.
+            pub type Hash =
+                <::sp_runtime::traits::BlakeTwo256 as ::sp_runtime::traits::Hash>::Output;
+
+            $( $opaque_item )*
+        }
+
+        pub type Transaction = $crate::types::Transaction<OuterVerifier, OuterConstraintChecker>;
+        pub type BlockNumber = u32;
+        pub type Header =
+            ::sp_runtime::generic::Header<BlockNumber, ::sp_runtime::traits::BlakeTwo256>;
+        pub type Block = ::sp_runtime::generic::Block<Header, Transaction>;
+        pub type Executive = $crate::Executive<
+            Block,
+            OuterVerifier,
+            OuterConstraintChecker,
+            $crate::construct_tuxedo_runtime!(@or [$($weights)?] ()),
+            $crate::utxo_set::TransparentUtxoSet<OuterVerifier>,
+            $crate::construct_tuxedo_runtime!(@or [$($offchain_workers)?] ()),
+            // The state checks that try-runtime runs
+            ($crate::try_runtime::UtxosDecode<OuterVerifier>,),
+            $version,
+            $pool_policy,
+        >;
+        pub type Output = $crate::types::Output<OuterVerifier>;
+
+        impl ::sp_runtime::traits::GetNodeBlockType for $runtime {
+            type NodeBlock = opaque::Block;
+        }
+
+        impl ::sp_runtime::traits::GetRuntimeBlockType for $runtime {
+            type RuntimeBlock = Block;
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        dynamic_typing::UtxoData,
+        verifier::{TestVerifier, UpForGrabs},
+        GenesisOnly,
+    };
+    use parity_scale_codec::{Decode, Encode};
+    use scale_info::TypeInfo;
+    use sp_runtime::transaction_validity::TransactionPriority;
+
+    /// A simple type that can be stored in the UTXO set.
+    #[derive(Encode, Decode, TypeInfo)]
+    struct Counter(u32);
+
+    impl UtxoData for Counter {
+        const TYPE_ID: [u8; 4] = *b"cntr";
+    }
+
+    // The tests only use some of the declared items.
+    #[allow(dead_code)]
+    mod runtime {
+        use super::*;
+
+        crate::construct_tuxedo_runtime! {
+            runtime: Runtime,
+            version: (),
+            pool_policy: (),
+            verifiers: {
+                Test(TestVerifier),
+                UpForGrabs(UpForGrabs),
+            },
+            pieces: {
+                #[cfg(any())]
+                Absent(GenesisOnly),
+                GenesisOnly(GenesisOnly),
+            },
+            utxo_types: [
+                Counter,
+                // Types that are compiled out are never even resolved.
+                #[cfg(any())]
+                NotAType,
+            ],
+            describe_utxo_types: [],
+            genesis: {},
+            opaque: {},
+        }
+    }
+    use runtime::*;
+
+    #[test]
+    fn verifiers_and_pieces_are_aggregated() {
+        assert_eq!(
+            OuterVerifier::from(UpForGrabs),
+            OuterVerifier::UpForGrabs(UpForGrabs)
+        );
+        assert_eq!(
+            OuterConstraintChecker::from(GenesisOnly),
+            OuterConstraintChecker::GenesisOnly(GenesisOnly)
+        );
+    }
+
+    #[test]
+    fn compiled_out_pieces_keep_their_index() {
+        // The placeholder of the absent piece holds index zero.
+        assert_eq!(OuterConstraintChecker::from(GenesisOnly).encode(), vec![1]);
+    }
+
+    #[test]
+    fn metadata_describes_the_listed_types() {
+        let metadata = Runtime::metadata();
+        let type_ids = metadata
+            .utxo_types
+            .iter()
+            .map(|utxo_type| utxo_type.type_id)
+            .collect::<Vec<_>>();
+
+        assert_eq!(type_ids, vec![Counter::TYPE_ID]);
+    }
+
+    #[test]
+    fn genesis_has_no_transactions_without_sections() {
+        let config = GenesisSections::default().build(Vec::new());
+        assert!(config.get_transaction(0).is_none());
+    }
+}
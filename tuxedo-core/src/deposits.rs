@@ -0,0 +1,91 @@
+//! Storage deposits that discourage bloating the UTXO set.
+//!
+//! Runtimes may opt into deposits by setting a
+//! [`DEPOSIT_PER_BYTE`](crate::weights::WeightConfig::DEPOSIT_PER_BYTE). Then every output that a
+//! transaction creates locks a deposit proportional to its encoded size. Like the fee, the deposit
+//! is paid from the value that the transaction burns. When the output is later consumed or evicted,
+//! its deposit is refunded to the transaction that removes it, which may spend it on its own fee,
+//! deposits, and tip. This makes cleaning up old outputs worthwhile for anyone who is allowed to.
+//!
+//! The executive remembers the deposit that each output locked, so the refund is always exactly
+//! what was locked, even when the runtime changes the deposit in the meantime. Inherents neither
+//! lock deposits nor collect refunds, so outputs that inherents create, and outputs in the genesis
+//! state, have no deposit to refund.
+
+use crate::types::OutputRef;
+use parity_scale_codec::{Decode, Encode};
+use sp_std::vec::Vec;
+
+/// The storage prefix under which the deposit locked by each output is kept.
+pub const DEPOSIT_PREFIX: &[u8] = b"deposit";
+
+fn key(output_ref: &OutputRef) -> Vec<u8> {
+    let mut key = DEPOSIT_PREFIX.to_vec();
+    key.extend(output_ref.encode());
+    key
+}
+
+/// The deposit that the given output locked when it was created.
+pub fn locked(output_ref: &OutputRef) -> u128 {
+    sp_io::storage::get(&key(output_ref))
+        .and_then(|d| u128::decode(&mut &*d).ok())
+        .unwrap_or_default()
+}
+
+/// Note that the given output locked a deposit.
+pub(crate) fn lock(output_ref: &OutputRef, deposit: u128) {
+    // Don't touch storage when nothing was locked.
+    if deposit == 0 {
+        return;
+    }
+    sp_io::storage::set(&key(output_ref), &deposit.encode());
+}
+
+/// Forget the deposit of an output that is being removed, and return it.
+pub(crate) fn release(output_ref: &OutputRef) -> u128 {
+    let deposit = locked(output_ref);
+    if deposit > 0 {
+        sp_io::storage::clear(&key(output_ref));
+    }
+    deposit
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sp_core::H256;
+    use sp_io::TestExternalities;
+
+    fn output_ref() -> OutputRef {
+        OutputRef {
+            tx_hash: H256::repeat_byte(1),
+            index: 0,
+        }
+    }
+
+    #[test]
+    fn locked_defaults_to_zero() {
+        TestExternalities::default().execute_with(|| {
+            assert_eq!(locked(&output_ref()), 0);
+        });
+    }
+
+    #[test]
+    fn release_returns_locked_deposit() {
+        TestExternalities::default().execute_with(|| {
+            lock(&output_ref(), 7);
+            assert_eq!(locked(&output_ref()), 7);
+
+            assert_eq!(release(&output_ref()), 7);
+            assert_eq!(locked(&output_ref()), 0);
+        });
+    }
+
+    #[test]
+    fn zero_deposits_do_not_touch_storage() {
+        TestExternalities::default().execute_with(|| {
+            lock(&output_ref(), 0);
+            assert!(!sp_io::storage::exists(&key(&output_ref())));
+        });
+    }
+}
@@ -0,0 +1,116 @@
+//! Logs that transactions add to the digest of the block that includes them.
+//!
+//! Some consensus engines learn about changes from logs in the block header, rather than from the
+//! runtime's state. For example, GRANDPA only switches to a new authority set when a header carries
+//! a scheduled change log. Constraint checkers add such logs by calling [`deposit_log`] while they
+//! check a transaction.
+//!
+//! While authoring, the executive appends the deposited logs to the header when it closes the block.
+//! While importing, it checks that the header carries exactly the logs that the block's transactions
+//! deposited, apart from the pre-runtime and seal logs that the consensus engine adds itself.
+//!
+//! Checking a transaction in the pool does not keep any of its storage changes, so depositing logs
+//! there does nothing.
+
+use parity_scale_codec::{Decode, Encode};
+use sp_runtime::{ConsensusEngineId, DigestItem};
+use sp_std::vec::Vec;
+
+/// A transient storage key that holds the logs deposited so far in the current block.
+/// This key is cleared before the end of the block.
+const DIGEST_LOGS_KEY: &[u8] = b"digest_logs";
+
+/// The logs that have been deposited so far in the current block.
+pub fn deposited_logs() -> Vec<DigestItem> {
+    sp_io::storage::get(DIGEST_LOGS_KEY)
+        .and_then(|d| Vec::<DigestItem>::decode(&mut &*d).ok())
+        .unwrap_or_default()
+}
+
+/// Add a log to the digest of the current block.
+pub fn deposit_log(log: DigestItem) {
+    let mut logs = deposited_logs();
+    logs.push(log);
+    sp_io::storage::set(DIGEST_LOGS_KEY, &logs.encode());
+}
+
+/// Whether a consensus log for the given engine has been deposited so far in the current block.
+pub fn deposited_by(engine: ConsensusEngineId) -> bool {
+    deposited_logs()
+        .iter()
+        .any(|log| matches!(log.as_consensus(), Some((id, _)) if id == engine))
+}
+
+/// Take the logs deposited in the block out of storage. This is called by the executive when the
+/// block is finished.
+pub(crate) fn take() -> Vec<DigestItem> {
+    let logs = deposited_logs();
+    sp_io::storage::clear(DIGEST_LOGS_KEY);
+    logs
+}
+
+/// Whether a log in the header of an imported block must have been deposited by its transactions.
+/// Only the consensus engine's own pre-runtime and seal logs are not.
+pub(crate) fn is_deposited(log: &DigestItem) -> bool {
+    !matches!(log, DigestItem::PreRuntime(..) | DigestItem::Seal(..))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sp_io::TestExternalities;
+
+    #[test]
+    fn nothing_deposited_by_default() {
+        TestExternalities::default().execute_with(|| {
+            assert!(deposited_logs().is_empty());
+        });
+    }
+
+    #[test]
+    fn deposited_logs_are_kept_in_order() {
+        TestExternalities::default().execute_with(|| {
+            deposit_log(DigestItem::Consensus(*b"test", vec![1]));
+            deposit_log(DigestItem::Other(vec![2]));
+
+            assert_eq!(
+                deposited_logs(),
+                vec![
+                    DigestItem::Consensus(*b"test", vec![1]),
+                    DigestItem::Other(vec![2])
+                ]
+            );
+        });
+    }
+
+    #[test]
+    fn deposited_by_matches_consensus_logs_of_engine() {
+        TestExternalities::default().execute_with(|| {
+            deposit_log(DigestItem::Other(vec![1]));
+            assert!(!deposited_by(*b"test"));
+
+            deposit_log(DigestItem::Consensus(*b"othr", vec![2]));
+            assert!(!deposited_by(*b"test"));
+
+            deposit_log(DigestItem::Consensus(*b"test", vec![3]));
+            assert!(deposited_by(*b"test"));
+        });
+    }
+
+    #[test]
+    fn take_clears_logs() {
+        TestExternalities::default().execute_with(|| {
+            deposit_log(DigestItem::Other(vec![2]));
+
+            assert_eq!(take(), vec![DigestItem::Other(vec![2])]);
+            assert!(deposited_logs().is_empty());
+        });
+    }
+
+    #[test]
+    fn consensus_engine_logs_are_not_deposited() {
+        assert!(!is_deposited(&DigestItem::PreRuntime(*b"aura", vec![])));
+        assert!(!is_deposited(&DigestItem::Seal(*b"aura", vec![])));
+        assert!(is_deposited(&DigestItem::Consensus(*b"FRNK", vec![])));
+    }
+}
@@ -28,6 +28,17 @@
 //! against the type into which the data is being decoded. Currently this read-time checking
 //! is the job of the piece developer, although that may be able to improve in the future.
 //!
+//! # Versioning
+//!
+//! Pieces evolve, and the structs they store in UTXOs change with them. Without care, UTXOs
+//! stored before an upgrade no longer decode afterwards, and are bricked forever. Types that
+//! expect to change can opt into a version byte convention by implementing [`VersionedUtxoData`].
+//! Their encoded data starts with a single version byte, followed by the SCALE encoding of that
+//! version of the type. Extraction decodes the current version directly, and hands data of any
+//! older version to the type's [`upgrade`](VersionedUtxoData::upgrade) hook, which decodes the old
+//! struct and converts it. A type should adopt the convention before any of its data is stored,
+//! because data stored without a version byte cannot be told apart from versioned data.
+//!
 //! # Comparison with `sp_std::any`
 //!
 //! The Rust standard library, and also the `sp-std` crate offer utilities for dynamic typing
@@ -37,7 +48,8 @@
 //! ## In favor of `sp_std::any`
 //!
 //! * The compiler guarantees unique type ids for every type, whereas this utility
-//!   requires the developer to avoid collisions (hopefully macros can improve this slightly)
+//!   requires the developer to avoid collisions (the `assert_unique_type_ids` macro
+//!   catches them at compile time, but only for the types it is given)
 //! * Using that crate would be less code for Tuxedo developers to maintain
 //!
 //! ## In favor of this custom utility
@@ -51,25 +63,190 @@ use scale_info::TypeInfo;
 use serde::{Deserialize, Serialize};
 use sp_std::vec::Vec;
 
+pub use aggregator::UtxoData;
+
 /// A piece of encoded data with a type id associated
 /// Strongly typed data can be extracted
 #[derive(Serialize, Deserialize, Encode, Decode, Debug, PartialEq, Eq, Clone, TypeInfo)]
+#[cfg_attr(feature = "fuzzing", derive(arbitrary::Arbitrary))]
 pub struct DynamicallyTypedData {
     pub data: Vec<u8>,
     pub type_id: [u8; 4],
 }
 
 /// A trait that must be implemented for any data that can be contained in a UTXO.
+///
+/// Most types should derive it with `#[derive(UtxoData)]`, which derives a type id from the crate
+/// and type names, so that no id has to be picked by hand. The instances of a type with different
+/// const generic parameters get different ids. A type can still choose its own id with the
+/// `#[utxo_data(type_id = ...)]` attribute, which takes any constant expression.
 /// It is not recommended to implement this trait directly for primitive types, but rather to
 /// use the newtype pattern: https://doc.rust-lang.org/book/ch19-04-advanced-types.html.
 /// Using a new type allows strong type disambiguation between bespoke use-cases in which
 /// the same primitive may be stored.
 pub trait UtxoData: Encode + Decode {
-    //TODO Not great that it is up to the runtime dev to enforce uniqueness
-    // Maybe macros can help... Doesn't frame somehow pass info about the string in construct runtime to the pallet-level storage items?
-    /// A unique identifier for this type. For now choosing this value and making sure it
-    /// really is unique is the problem of the developer. Ideally this would be better.
+    /// A unique identifier for this type. Choosing this value is the problem of the piece developer.
+    /// Runtimes should make sure that the types of all their pieces really have unique ids with
+    /// [`assert_unique_type_ids`](crate::assert_unique_type_ids).
     const TYPE_ID: [u8; 4];
+
+    /// Encode this value as the data of a UTXO.
+    ///
+    /// By default this is the value's SCALE encoding. Types that implement [`VersionedUtxoData`]
+    /// should override this with [`encode_versioned`].
+    fn encode_payload(&self) -> Vec<u8> {
+        self.encode()
+    }
+
+    /// Decode a value from the data of a UTXO whose type id matches this type.
+    ///
+    /// By default this is SCALE decoding. Types that implement [`VersionedUtxoData`]
+    /// should override this with [`decode_versioned`].
+    fn decode_payload(data: &[u8]) -> Result<Self, DynamicTypingError> {
+        Self::decode(&mut &data[..]).map_err(|_| DynamicTypingError::DecodingFailed)
+    }
+}
+
+/// A type of UTXO data that is stored with a version byte, so that its struct can change
+/// without bricking the UTXOs that were stored before the change.
+///
+/// Implementing this trait is not enough on its own. The type's [`UtxoData`] implementation
+/// must also route its payload through the version byte convention:
+///
+/// ```ignore
+/// impl UtxoData for Thing {
+///     const TYPE_ID: [u8; 4] = *b"thng";
+///
+///     fn encode_payload(&self) -> Vec<u8> {
+///         encode_versioned(self)
+///     }
+///
+///     fn decode_payload(data: &[u8]) -> Result<Self, DynamicTypingError> {
+///         decode_versioned(data)
+///     }
+/// }
+///
+/// impl VersionedUtxoData for Thing {
+///     const VERSION: u8 = 1;
+///
+///     fn upgrade(version: u8, data: &[u8]) -> Result<Self, DynamicTypingError> {
+///         match version {
+///             0 => ThingV0::decode(&mut &data[..])
+///                 .map(Into::into)
+///                 .map_err(|_| DynamicTypingError::DecodingFailed),
+///             _ => Err(DynamicTypingError::UnknownVersion),
+///         }
+///     }
+/// }
+/// ```
+pub trait VersionedUtxoData: UtxoData {
+    /// The version of this type's current struct. It should be increased each time the struct
+    /// changes, and an upgrade from the previous version should be added at the same time.
+    const VERSION: u8;
+
+    /// Decode data that was stored by an older version of this type, and convert it to the
+    /// current version. The version byte has already been stripped from the data.
+    ///
+    /// By default no older versions are known.
+    fn upgrade(version: u8, data: &[u8]) -> Result<Self, DynamicTypingError> {
+        let _ = (version, data);
+        Err(DynamicTypingError::UnknownVersion)
+    }
+}
+
+/// Encode a value with the version byte convention: its current version followed by its SCALE encoding.
+pub fn encode_versioned<T: VersionedUtxoData>(value: &T) -> Vec<u8> {
+    let mut data = sp_std::vec![T::VERSION];
+    value.encode_to(&mut data);
+    data
+}
+
+/// Decode data that follows the version byte convention. Data of the current version is decoded
+/// directly, and data of any other version is passed to the type's upgrade hook.
+pub fn decode_versioned<T: VersionedUtxoData>(data: &[u8]) -> Result<T, DynamicTypingError> {
+    let (&version, rest) = data
+        .split_first()
+        .ok_or(DynamicTypingError::DecodingFailed)?;
+
+    if version == T::VERSION {
+        T::decode(&mut &rest[..]).map_err(|_| DynamicTypingError::DecodingFailed)
+    } else {
+        T::upgrade(version, rest)
+    }
+}
+
+/// Derive a type id by hashing a name along with some salt values.
+///
+/// The name is hashed with 32 bit FNV-1a, and then the little endian bytes of each salt value are
+/// mixed in. `#[derive(UtxoData)]` passes the crate and type names as in `"kitties::KittyData"`,
+/// and the type's const generic parameters as the salt.
+pub const fn type_id_from_name(name: &str, salt: &[u64]) -> [u8; 4] {
+    const OFFSET_BASIS: u32 = 0x811c_9dc5;
+    const PRIME: u32 = 0x0100_0193;
+
+    let name = name.as_bytes();
+    let mut hash = OFFSET_BASIS;
+    let mut i = 0;
+    while i < name.len() {
+        hash ^= name[i] as u32;
+        hash = hash.wrapping_mul(PRIME);
+        i += 1;
+    }
+
+    let mut i = 0;
+    while i < salt.len() {
+        let bytes = salt[i].to_le_bytes();
+        let mut j = 0;
+        while j < bytes.len() {
+            hash ^= bytes[j] as u32;
+            hash = hash.wrapping_mul(PRIME);
+            j += 1;
+        }
+        i += 1;
+    }
+
+    hash.to_be_bytes()
+}
+
+/// Whether all the given type ids are different from one another.
+///
+/// This is a `const fn` so that collisions can be caught at compile time.
+/// See [`assert_unique_type_ids`](crate::assert_unique_type_ids).
+pub const fn type_ids_are_unique(type_ids: &[[u8; 4]]) -> bool {
+    let mut i = 0;
+    while i < type_ids.len() {
+        let mut j = i + 1;
+        while j < type_ids.len() {
+            let (a, b) = (type_ids[i], type_ids[j]);
+            if a[0] == b[0] && a[1] == b[1] && a[2] == b[2] && a[3] == b[3] {
+                return false;
+            }
+            j += 1;
+        }
+        i += 1;
+    }
+    true
+}
+
+/// Fail compilation if any two of the given `UtxoData` types share a `TYPE_ID`.
+///
+/// Colliding type ids would let data of one type be extracted as the other, which is exactly
+/// the attack that dynamic typing is meant to prevent. Runtimes should list every type that
+/// their pieces store in the UTXO set.
+///
+/// ```ignore
+/// tuxedo_core::assert_unique_type_ids!(money::Coin<0>, kitties::KittyData);
+/// ```
+#[macro_export]
+macro_rules! assert_unique_type_ids {
+    ( $( $t:ty ),* $(,)? ) => {
+        const _: () = assert!(
+            $crate::dynamic_typing::type_ids_are_unique(&[
+                $( <$t as $crate::dynamic_typing::UtxoData>::TYPE_ID, )*
+            ]),
+            "Two UtxoData types share a TYPE_ID",
+        );
+    };
 }
 
 impl DynamicallyTypedData {
@@ -79,7 +256,7 @@ impl DynamicallyTypedData {
         // The first four bytes represent the type id that that was encoded. If they match the type
         // we are trying to decode into, we continue, otherwise we error out.
         if self.type_id == <T as UtxoData>::TYPE_ID {
-            T::decode(&mut &self.data[..]).map_err(|_| DynamicTypingError::DecodingFailed)
+            T::decode_payload(&self.data)
         } else {
             Err(DynamicTypingError::WrongType)
         }
@@ -93,6 +270,8 @@ pub enum DynamicTypingError {
     WrongType,
     /// Although the types matched, the data could not be decoded with the SCALE codec.
     DecodingFailed,
+    /// The data was stored by a version of its type that it cannot be upgraded from.
+    UnknownVersion,
 }
 
 impl sp_std::fmt::Display for DynamicTypingError {
@@ -103,6 +282,10 @@ impl sp_std::fmt::Display for DynamicTypingError {
                 f,
                 "failed to decode dynamically typed data with scale codec"
             ),
+            Self::UnknownVersion => write!(
+                f,
+                "dynamically typed data has a version that cannot be upgraded"
+            ),
         }
     }
 }
@@ -126,7 +309,7 @@ impl std::error::Error for DynamicTypingError {}
 impl<T: UtxoData> From<T> for DynamicallyTypedData {
     fn from(value: T) -> Self {
         Self {
-            data: value.encode(),
+            data: value.encode_payload(),
             type_id: T::TYPE_ID,
         }
     }
@@ -163,6 +346,123 @@ mod tests {
         const TYPE_ID: [u8; 4] = *b"byte";
     }
 
+    /// The first version of a versioned type, before it gained a second field.
+    #[derive(Encode, Decode, Debug, PartialEq, Eq, Clone)]
+    struct CounterV0(u8);
+
+    /// The current version of a versioned type. Used to test upgrades.
+    #[derive(Encode, Decode, Debug, PartialEq, Eq, Clone)]
+    struct Counter {
+        count: u8,
+        step: u8,
+    }
+
+    impl UtxoData for Counter {
+        const TYPE_ID: [u8; 4] = *b"cntr";
+
+        fn encode_payload(&self) -> Vec<u8> {
+            encode_versioned(self)
+        }
+
+        fn decode_payload(data: &[u8]) -> Result<Self, DynamicTypingError> {
+            decode_versioned(data)
+        }
+    }
+
+    impl VersionedUtxoData for Counter {
+        const VERSION: u8 = 1;
+
+        fn upgrade(version: u8, data: &[u8]) -> Result<Self, DynamicTypingError> {
+            match version {
+                0 => CounterV0::decode(&mut &data[..])
+                    .map(|old| Counter {
+                        count: old.0,
+                        step: 1,
+                    })
+                    .map_err(|_| DynamicTypingError::DecodingFailed),
+                _ => Err(DynamicTypingError::UnknownVersion),
+            }
+        }
+    }
+
+    /// A type whose id is derived from its name.
+    #[derive(Encode, Decode, UtxoData)]
+    struct Derived;
+
+    /// A type whose instances get different derived ids.
+    #[derive(Encode, Decode, UtxoData)]
+    struct Salted<const ID: u8>;
+
+    /// A type that picks its own id.
+    #[derive(Encode, Decode, Debug, PartialEq, UtxoData)]
+    #[utxo_data(type_id = *b"ovrd")]
+    struct Overridden;
+
+    #[test]
+    fn type_id_from_name_is_fnv1a() {
+        assert_eq!(type_id_from_name("", &[]), 0x811c_9dc5u32.to_be_bytes());
+        assert_eq!(type_id_from_name("a", &[]), 0xe40c_292cu32.to_be_bytes());
+    }
+
+    #[test]
+    fn derived_type_id_hashes_crate_and_type_name() {
+        assert_eq!(
+            Derived::TYPE_ID,
+            type_id_from_name("tuxedo-core::Derived", &[])
+        );
+    }
+
+    #[test]
+    fn derived_type_id_covers_const_generics() {
+        assert_eq!(
+            Salted::<1>::TYPE_ID,
+            type_id_from_name("tuxedo-core::Salted", &[1])
+        );
+        assert_ne!(Salted::<0>::TYPE_ID, Salted::<1>::TYPE_ID);
+    }
+
+    #[test]
+    fn derived_type_id_can_be_overridden() {
+        assert_eq!(Overridden::TYPE_ID, *b"ovrd");
+    }
+
+    #[test]
+    fn derived_utxo_data_extracts() {
+        let dynamically_typed: DynamicallyTypedData = Derived.into();
+
+        assert!(dynamically_typed.extract::<Derived>().is_ok());
+        assert_eq!(
+            dynamically_typed.extract::<Overridden>(),
+            Err(DynamicTypingError::WrongType)
+        );
+    }
+
+    #[test]
+    fn distinct_type_ids_are_unique() {
+        assert!(type_ids_are_unique(&[]));
+        assert!(type_ids_are_unique(&[Byte::TYPE_ID, Bogus::TYPE_ID]));
+    }
+
+    #[test]
+    fn repeated_type_ids_are_not_unique() {
+        assert!(!type_ids_are_unique(&[
+            Byte::TYPE_ID,
+            Bogus::TYPE_ID,
+            *b"byte"
+        ]));
+    }
+
+    // Fails to compile if the ids collide.
+    crate::assert_unique_type_ids!(
+        Byte,
+        Bogus,
+        Counter,
+        Derived,
+        Salted<0>,
+        Salted<1>,
+        Overridden
+    );
+
     #[test]
     fn extract_works() {
         let original_b = Byte(4);
@@ -196,6 +496,63 @@ mod tests {
         assert_eq!(extracted_b, Err(DynamicTypingError::DecodingFailed));
     }
 
+    #[test]
+    fn versioned_data_starts_with_version_byte() {
+        let counter = Counter { count: 3, step: 2 };
+        let dynamically_typed: DynamicallyTypedData = counter.clone().into();
+
+        assert_eq!(dynamically_typed.data, vec![1, 3, 2]);
+        assert_eq!(dynamically_typed.extract::<Counter>(), Ok(counter));
+    }
+
+    #[test]
+    fn versioned_extract_upgrades_old_version() {
+        let mut data = vec![0];
+        data.extend(CounterV0(7).encode());
+        let dynamically_typed = DynamicallyTypedData {
+            data,
+            type_id: Counter::TYPE_ID,
+        };
+
+        assert_eq!(
+            dynamically_typed.extract::<Counter>(),
+            Ok(Counter { count: 7, step: 1 })
+        );
+    }
+
+    #[test]
+    fn versioned_extract_unknown_version() {
+        let dynamically_typed = DynamicallyTypedData {
+            data: vec![9, 3, 2],
+            type_id: Counter::TYPE_ID,
+        };
+
+        assert_eq!(
+            dynamically_typed.extract::<Counter>(),
+            Err(DynamicTypingError::UnknownVersion)
+        );
+    }
+
+    #[test]
+    fn versioned_extract_without_version_byte_fails() {
+        let dynamically_typed = DynamicallyTypedData {
+            data: Vec::new(),
+            type_id: Counter::TYPE_ID,
+        };
+
+        assert_eq!(
+            dynamically_typed.extract::<Counter>(),
+            Err(DynamicTypingError::DecodingFailed)
+        );
+    }
+
+    #[test]
+    fn unversioned_data_is_plain_scale() {
+        let dynamically_typed: DynamicallyTypedData = Byte(4).into();
+
+        assert_eq!(dynamically_typed.data, vec![4]);
+    }
+
     #[test]
     fn display_wrong_type_error() {
         let actual = format!("{}", DynamicTypingError::WrongType);
@@ -211,4 +568,12 @@ mod tests {
 
         assert_eq!(actual, expected);
     }
+
+    #[test]
+    fn display_unknown_version_error() {
+        let actual = format!("{}", DynamicTypingError::UnknownVersion);
+        let expected = String::from("dynamically typed data has a version that cannot be upgraded");
+
+        assert_eq!(actual, expected);
+    }
 }
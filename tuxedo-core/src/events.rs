@@ -0,0 +1,187 @@
+//! Events that transactions emit so that wallets and indexers can follow what happened on chain
+//! without re-interpreting every transaction themselves.
+//!
+//! Constraint checkers emit events while they check a transaction by calling [`deposit`]. When
+//! the executive applies the transaction, it collects those events and stores them along with the
+//! hash of the transaction that emitted them. Like FRAME's system events, only the events of the
+//! most recent block are kept in storage. To learn the events of an older block, query the state
+//! of that block, for example through the [`TuxedoEventsApi`] runtime API.
+//!
+//! Checking a transaction in the pool, or in a unit test, does not collect any events, so
+//! depositing them there does nothing. This keeps constraint checkers free to be called anywhere.
+//!
+//! Like UTXO data, each event type is tagged with a four byte id so that it can be decoded safely.
+
+use crate::dynamic_typing::DynamicTypingError;
+use parity_scale_codec::{Decode, Encode};
+use scale_info::TypeInfo;
+use serde::{Deserialize, Serialize};
+use sp_core::H256;
+use sp_std::vec::Vec;
+
+/// The storage key that holds the events emitted in the most recent block.
+pub const EVENTS_KEY: &[u8] = b"events";
+
+/// A trait that must be implemented for any event that a piece emits.
+pub trait TuxedoEvent: Encode + Decode {
+    /// A unique identifier for this event type. Like the type ids of UTXO data,
+    /// making sure it really is unique is the problem of the developer.
+    const EVENT_ID: [u8; 4];
+}
+
+/// An encoded event with its type id.
+#[derive(Serialize, Deserialize, Encode, Decode, Debug, PartialEq, Eq, Clone, TypeInfo)]
+pub struct Event {
+    pub event_id: [u8; 4],
+    pub data: Vec<u8>,
+}
+
+impl Event {
+    /// Extracts a strongly typed event, iff this is an event of the type specified.
+    pub fn extract<E: TuxedoEvent>(&self) -> Result<E, DynamicTypingError> {
+        if self.event_id == E::EVENT_ID {
+            E::decode(&mut &self.data[..]).map_err(|_| DynamicTypingError::DecodingFailed)
+        } else {
+            Err(DynamicTypingError::WrongType)
+        }
+    }
+}
+
+impl<E: TuxedoEvent> From<E> for Event {
+    fn from(event: E) -> Self {
+        Self {
+            event_id: E::EVENT_ID,
+            data: event.encode(),
+        }
+    }
+}
+
+/// An event along with the transaction that emitted it.
+#[derive(Serialize, Deserialize, Encode, Decode, Debug, PartialEq, Eq, Clone, TypeInfo)]
+pub struct EventRecord {
+    pub tx_hash: H256,
+    pub event: Event,
+}
+
+environmental::environmental!(collector: Vec<Event>);
+
+/// Emit an event. This does nothing unless the events are being collected.
+pub fn deposit<E: TuxedoEvent>(event: E) {
+    collector::with(|events| events.push(event.into()));
+}
+
+/// Run the given closure, and collect all the events that it deposits.
+pub fn collect<R>(f: impl FnOnce() -> R) -> (R, Vec<Event>) {
+    let mut events = Vec::new();
+    let result = collector::using(&mut events, f);
+    (result, events)
+}
+
+/// The events that have been emitted so far in the current block.
+///
+/// When called against the state of a finished block, these are all the events of that block.
+pub fn block_events() -> Vec<EventRecord> {
+    sp_io::storage::get(EVENTS_KEY)
+        .and_then(|d| Vec::<EventRecord>::decode(&mut &*d).ok())
+        .unwrap_or_default()
+}
+
+/// Store the events emitted by the transaction with the given hash.
+pub(crate) fn note(tx_hash: H256, events: Vec<Event>) {
+    // Don't touch storage for transactions that emit nothing.
+    if events.is_empty() {
+        return;
+    }
+    let mut records = block_events();
+    records.extend(
+        events
+            .into_iter()
+            .map(|event| EventRecord { tx_hash, event }),
+    );
+    sp_io::storage::set(EVENTS_KEY, &records.encode());
+}
+
+/// Forget the events of the previous block. This is called by the executive at the beginning
+/// of each block.
+pub(crate) fn clear() {
+    sp_io::storage::clear(EVENTS_KEY);
+}
+
+sp_api::decl_runtime_apis! {
+    /// An API for reading the events emitted in a block.
+    pub trait TuxedoEventsApi {
+        /// All the events emitted in the block, in the order they were emitted.
+        fn events() -> Vec<EventRecord>;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sp_io::TestExternalities;
+
+    #[derive(Encode, Decode, Debug, PartialEq, Eq)]
+    struct Ping(u8);
+
+    impl TuxedoEvent for Ping {
+        const EVENT_ID: [u8; 4] = *b"ping";
+    }
+
+    #[derive(Encode, Decode, Debug, PartialEq, Eq)]
+    struct Pong(u8);
+
+    impl TuxedoEvent for Pong {
+        const EVENT_ID: [u8; 4] = *b"pong";
+    }
+
+    #[test]
+    fn deposit_without_collecting_does_nothing() {
+        deposit(Ping(1));
+    }
+
+    #[test]
+    fn collect_gathers_deposits_in_order() {
+        let (result, events) = collect(|| {
+            deposit(Ping(1));
+            deposit(Pong(2));
+            3
+        });
+
+        assert_eq!(result, 3);
+        assert_eq!(events, vec![Ping(1).into(), Pong(2).into()]);
+    }
+
+    #[test]
+    fn extract_checks_event_id() {
+        let event: Event = Ping(1).into();
+
+        assert_eq!(event.extract::<Ping>(), Ok(Ping(1)));
+        assert_eq!(event.extract::<Pong>(), Err(DynamicTypingError::WrongType));
+    }
+
+    #[test]
+    fn note_appends_records() {
+        TestExternalities::default().execute_with(|| {
+            let first = H256::repeat_byte(1);
+            let second = H256::repeat_byte(2);
+            note(first, vec![Ping(1).into()]);
+            note(second, Vec::new());
+            note(second, vec![Pong(2).into()]);
+
+            let expected = vec![
+                EventRecord {
+                    tx_hash: first,
+                    event: Ping(1).into(),
+                },
+                EventRecord {
+                    tx_hash: second,
+                    event: Pong(2).into(),
+                },
+            ];
+            assert_eq!(block_events(), expected);
+
+            clear();
+            assert!(block_events().is_empty());
+        });
+    }
+}
@@ -5,38 +5,80 @@
 //!
 //! It does all the reusable verification of UTXO transactions such as checking that there
 //! are no duplicate inputs, and that the verifiers are satisfied.
+//!
+//! A transaction may consume or peek at outputs that an earlier transaction in the same block
+//! creates. Transactions are applied in order, so those outputs are in the utxo set by the time
+//! it is applied. In the pool, every transaction provides a tag for each of its outputs and
+//! requires a tag for each of its inputs and peeks that are not in the utxo set yet. This way
+//! the pool holds on to, and gossips, a dependent transaction until its parent lands, and block
+//! authors include the parent first. Payments can be chained without waiting a block per hop.
+//!
+//! The executive also keeps track of the weight that the block has used so far. A transaction
+//! that would take the block past the configured maximum is refused with `ExhaustsResources`,
+//! which tells the block author that the block is full, so that it stops filling it by weight
+//! and not only by length.
 
+#[cfg(feature = "try-runtime")]
+use crate::try_runtime::{TryRuntimeWeight, TryStateSelect, UpgradeCheckSelect};
 use crate::{
-    constraint_checker::ConstraintChecker,
-    ensure,
+    ages,
+    constraint_checker::{CheckerContext, ConstraintChecker},
+    deposits, digest, ensure, events, fees,
     inherents::{InherentInternal, PARENT_INHERENT_IDENTIFIER},
-    types::{DispatchResult, OutputRef, Transaction, UtxoError},
-    utxo_set::TransparentUtxoSet,
-    verifier::Verifier,
-    EXTRINSIC_KEY, HEADER_KEY, LOG_TARGET,
+    offchain::{OffchainContext, OffchainWorker},
+    pool::PoolPolicy,
+    try_runtime::TryStateHooks,
+    types::{DispatchResult, InputMode, Output, OutputRef, SigningDomain, Transaction, UtxoError},
+    utxo_set::{TransparentUtxoSet, UtxoSet},
+    verifier::{best_time, Verifier, VerifierContext},
+    weights::{self, Weight, WeightConfig, READ_WEIGHT, WRITE_WEIGHT},
+    AWAITING_GENESIS_HASH_KEY, EXTRINSIC_KEY, GENESIS_HASH_KEY, HEADER_KEY, LOG_TARGET,
+    MINTED_PREFIX,
 };
 use log::debug;
 use parity_scale_codec::{Decode, Encode};
 use sp_api::{BlockT, HashT, HeaderT, TransactionValidity};
-use sp_core::H256;
+use sp_core::{Get, H256};
 use sp_inherents::{CheckInherentsResult, InherentData};
 use sp_runtime::{
-    traits::BlakeTwo256,
+    traits::{BlakeTwo256, UniqueSaturatedInto},
     transaction_validity::{
-        InvalidTransaction, TransactionLongevity, TransactionSource, TransactionValidityError,
-        ValidTransaction,
+        InvalidTransaction, TransactionLongevity, TransactionPriority, TransactionSource,
+        TransactionValidityError, ValidTransaction,
     },
     ApplyExtrinsicResult, StateVersion,
 };
 use sp_std::marker::PhantomData;
 use sp_std::{collections::btree_set::BTreeSet, vec::Vec};
+use sp_version::RuntimeVersion;
 
-/// The executive. Each runtime is encouraged to make a type alias called `Executive` that fills
-/// in the proper generic types.
-pub struct Executive<B, V, C>(PhantomData<(B, V, C)>);
+/// Inputs, by transaction hash and input index, whose redeemers were already verified by the
+/// parallel pass of `execute_block`.
+#[cfg(feature = "parallel-verification")]
+environmental::environmental!(preverified: BTreeSet<(H256, u32)>);
 
-impl<B: BlockT<Extrinsic = Transaction<V, C>>, V: Verifier, C: ConstraintChecker<V>>
-    Executive<B, V, C>
+/// The executive. Each runtime is encouraged to make a type alias called `Executive` that fills
+/// in the proper generic types. Runtimes that do not configure weights and fees may leave out the
+/// weight config, in which case no fees are charged. Runtimes that do not configure a utxo set use
+/// the [`TransparentUtxoSet`]. Runtimes whose pieces have off-chain workers list them next, then
+/// the state checks that try-runtime runs. The next parameter supplies the runtime version, whose
+/// spec and transaction versions are part of every signing payload. The last one is the policy
+/// that adjusts how the pool orders transactions.
+pub struct Executive<B, V, C, W = (), U = TransparentUtxoSet<V>, O = (), S = (), R = (), P = ()>(
+    PhantomData<(B, V, C, W, U, O, S, R, P)>,
+);
+
+impl<
+        B: BlockT<Extrinsic = Transaction<V, C>>,
+        V: Verifier,
+        C: ConstraintChecker<V>,
+        W: WeightConfig,
+        U: UtxoSet<V>,
+        O: OffchainWorker<V, C>,
+        S: TryStateHooks,
+        R: Get<RuntimeVersion>,
+        P: PoolPolicy<V, C>,
+    > Executive<B, V, C, W, U, O, S, R, P>
 {
     /// Does pool-style validation of a tuxedo transaction.
     /// Does not commit anything to storage.
@@ -45,43 +87,240 @@ impl<B: BlockT<Extrinsic = Transaction<V, C>>, V: Verifier, C: ConstraintChecker
     pub fn validate_tuxedo_transaction(
         transaction: &Transaction<V, C>,
     ) -> Result<ValidTransaction, UtxoError<C::Error>> {
+        Self::validate_and_weigh(transaction).map(|(valid_transaction, _, _)| valid_transaction)
+    }
+
+    /// Checks a transaction against the current state as if it were applied in the next block,
+    /// and returns its priority. Nothing is committed to storage. Unlike pool validation,
+    /// this fails when any of the inputs are missing.
+    pub fn dry_run(
+        transaction: &Transaction<V, C>,
+    ) -> Result<TransactionPriority, UtxoError<C::Error>> {
+        let valid_transaction = Self::validate_tuxedo_transaction(transaction)?;
+        ensure!(
+            valid_transaction.requires.is_empty(),
+            UtxoError::MissingInput
+        );
+
+        Ok(valid_transaction.priority)
+    }
+
+    /// The weight of a transaction whose inputs are the given utxos. Besides the verifiers and the
+    /// constraint checker, this includes the storage accesses that the executive makes: it reads
+    /// every input, peek, and output, and it writes every input and output. Mints also read and
+    /// write their mark. When deposits are enabled, it also reads and releases the deposit of
+    /// every input, and locks the deposit of every output. Likewise, when ages are tracked, it
+    /// reads and forgets the age of every input, and notes the age of every output.
+    fn weigh(transaction: &Transaction<V, C>, input_utxos: &[Output<V>]) -> Weight {
+        let mark = usize::from(Self::is_mint(transaction));
+        let (deposit_reads, deposit_writes) = if W::DEPOSIT_PER_BYTE > 0 {
+            (
+                transaction.inputs.len(),
+                transaction.inputs.len() + transaction.outputs.len(),
+            )
+        } else {
+            (0, 0)
+        };
+        let (age_reads, age_writes) = if W::TRACK_AGES {
+            (
+                transaction.inputs.len(),
+                transaction.inputs.len() + transaction.outputs.len(),
+            )
+        } else {
+            (0, 0)
+        };
+        let reads = transaction.inputs.len()
+            + transaction.peeks.len()
+            + transaction.outputs.len()
+            + mark
+            + deposit_reads
+            + age_reads;
+        let writes = transaction.inputs.len()
+            + transaction.outputs.len()
+            + mark
+            + deposit_writes
+            + age_writes;
+
+        input_utxos
+            .iter()
+            .map(|utxo| utxo.verifier.weight())
+            .fold(transaction.checker.weight(), Weight::saturating_add)
+            .saturating_add(READ_WEIGHT.saturating_mul(reads as Weight))
+            .saturating_add(WRITE_WEIGHT.saturating_mul(writes as Weight))
+    }
+
+    /// Whether the given transaction creates outputs without consuming anything. Applying such a
+    /// transaction again would recreate the same output refs, even after the first ones were
+    /// consumed, so the executive marks it and only ever applies it once. Inherents are exempt,
+    /// because the block author creates them. Pieces make their inherents unique, for example by
+    /// including the block height.
+    fn is_mint(transaction: &Transaction<V, C>) -> bool {
+        transaction.inputs.is_empty()
+            && !transaction.outputs.is_empty()
+            && !transaction.checker.is_inherent()
+    }
+
+    /// The storage key that marks the mint with the given hash as applied.
+    fn mint_key(tx_hash: H256) -> Vec<u8> {
+        [MINTED_PREFIX, tx_hash.as_bytes()].concat()
+    }
+
+    /// Whether the redeemer of the given input was already verified by the parallel pass of
+    /// `execute_block`. This is never the case in the pool.
+    #[cfg(feature = "parallel-verification")]
+    fn is_preverified(tx_hash: H256, index: usize) -> bool {
+        preverified::with(|set| set.contains(&(tx_hash, index as u32))).unwrap_or(false)
+    }
+
+    /// Without parallel verification, every redeemer is verified when its transaction is applied.
+    #[cfg(not(feature = "parallel-verification"))]
+    fn is_preverified(_tx_hash: H256, _index: usize) -> bool {
+        false
+    }
+
+    /// Verify the redeemers of all the given transactions' inputs that are currently in the utxo
+    /// set on many threads at once, and return the ones that passed.
+    ///
+    /// Failures are not reported here. They are simply verified again, and reported, when the
+    /// transaction is applied.
+    #[cfg(feature = "parallel-verification")]
+    fn verify_in_parallel(transactions: &[Transaction<V, C>]) -> BTreeSet<(H256, u32)> {
+        use rayon::prelude::*;
+
+        let context = Self::verifier_context();
+        let domain = Self::signing_domain();
+        let payloads: Vec<_> = transactions
+            .iter()
+            .map(|transaction| transaction.signing_payloads(&domain))
+            .collect();
+
+        // Only this thread has access to storage, so read all the inputs first.
+        let mut jobs = Vec::new();
+        for (transaction, payloads) in transactions.iter().zip(&payloads) {
+            let tx_hash = BlakeTwo256::hash_of(&transaction.encode());
+            for (index, input) in transaction.inputs.iter().enumerate() {
+                // Reclaimed inputs have no redeemers to verify.
+                if input.mode == InputMode::Reclaim {
+                    continue;
+                }
+                if let Some(input_utxo) = U::peek_utxo(&input.output_ref) {
+                    jobs.push((
+                        (tx_hash, index as u32),
+                        input_utxo.verifier,
+                        payloads,
+                        &input.redeemer,
+                    ));
+                }
+            }
+        }
+
+        jobs.into_par_iter()
+            .filter(|((_, index), verifier, payloads, redeemer)| {
+                verifier.verify_input(payloads, *index as usize, redeemer, &context)
+            })
+            .map(|(key, ..)| key)
+            .collect()
+    }
+
+    /// Does the work of `validate_tuxedo_transaction`, and also returns the transaction's weight
+    /// and the tip that it pays on top of its fee.
+    fn validate_and_weigh(
+        transaction: &Transaction<V, C>,
+    ) -> Result<(ValidTransaction, Weight, u128), UtxoError<C::Error>> {
         debug!(
             target: LOG_TARGET,
             "validating tuxedo transaction",
         );
 
+        // Make sure the transaction is within the runtime's limits before doing any real work.
+        // Inherents are created by the block author, so they are not limited.
+        if !transaction.checker.is_inherent() {
+            ensure!(
+                transaction.inputs.len() <= W::MAXIMUM_INPUTS as usize
+                    && transaction.peeks.len() <= W::MAXIMUM_PEEKS as usize
+                    && transaction.outputs.len() <= W::MAXIMUM_OUTPUTS as usize
+                    && transaction.encoded_size() <= W::MAXIMUM_TRANSACTION_LENGTH as usize
+                    && transaction.memo.as_ref().map_or(0, Vec::len)
+                        <= W::MAXIMUM_MEMO_LENGTH as usize,
+                UtxoError::ExceedsTransactionLimits
+            );
+        }
+
+        // Make sure the transaction has not expired. In the pool, the height is that of the next
+        // block, so the pool drops the transaction once it can no longer be included.
+        let context = Self::verifier_context();
+        if let Some(valid_until) = transaction.valid_until {
+            ensure!(context.block_height <= valid_until, UtxoError::Expired);
+        }
+        let longevity = transaction
+            .valid_until
+            .map_or(TransactionLongevity::max_value(), |valid_until| {
+                u64::from(valid_until - context.block_height) + 1
+            });
+
         // Make sure there are no duplicate inputs
         // Duplicate peeks are allowed, although they are inefficient and wallets should not create such transactions
         {
-            let input_set: BTreeSet<_> = transaction.inputs.iter().map(|o| o.encode()).collect();
+            let input_set: BTreeSet<_> = transaction
+                .inputs
+                .iter()
+                .map(|i| i.output_ref.encode())
+                .collect();
             ensure!(
                 input_set.len() == transaction.inputs.len(),
                 UtxoError::DuplicateInput
             );
         }
 
-        // Build the stripped transaction (with the redeemers stripped) and encode it
-        // This will be passed to the verifiers
-        let mut stripped = transaction.clone();
-        for input in stripped.inputs.iter_mut() {
-            input.redeemer = Vec::new();
-        }
-        let stripped_encoded = stripped.encode();
+        // Encode the parts of the transaction (with the redeemers stripped) that signatures may
+        // commit to, along with the signing domain. These will be passed to the verifiers
+        let payloads = transaction.signing_payloads(&Self::signing_domain());
+        let tx_hash = BlakeTwo256::hash_of(&transaction.encode());
 
         // Check that the verifiers of all inputs are satisfied
-        // Keep a Vec of the input utxos for passing to the constraint checker
+        // Keep a Vec of the input utxos, and one of their redeemers, for passing to the constraint checker
         // Keep track of any missing inputs for use in the tagged transaction pool
+        // Missing evictions and reclaims are skipped. They are not required, because nothing needs to happen to them.
         let mut input_utxos = Vec::new();
+        let mut redeemers = Vec::new();
+        let mut reclaimed = Vec::new();
+        let mut input_ages = Vec::new();
+        let mut refunded: u128 = 0;
         let mut missing_inputs = Vec::new();
-        for input in transaction.inputs.iter() {
-            if let Some(input_utxo) = TransparentUtxoSet::<V>::peek_utxo(&input.output_ref) {
-                ensure!(
-                    input_utxo
-                        .verifier
-                        .verify(&stripped_encoded, &input.redeemer),
-                    UtxoError::VerifierError
-                );
+        for (index, input) in transaction.inputs.iter().enumerate() {
+            if let Some(input_utxo) = U::peek_utxo(&input.output_ref) {
+                let is_reclaimed = input.mode == InputMode::Reclaim;
+                if is_reclaimed {
+                    // A reclaimed input skips its verifier, so the constraint checker must vouch for it.
+                    ensure!(transaction.checker.reclaims(), UtxoError::VerifierError);
+                } else {
+                    ensure!(
+                        Self::is_preverified(tx_hash, index)
+                            || input_utxo.verifier.verify_input(
+                                &payloads,
+                                index,
+                                &input.redeemer,
+                                &context
+                            ),
+                        UtxoError::VerifierError
+                    );
+                }
                 input_utxos.push(input_utxo);
+                redeemers.push(input.redeemer.clone());
+                reclaimed.push(is_reclaimed);
+                input_ages.push(if W::TRACK_AGES {
+                    ages::age(&input.output_ref, context.block_height)
+                } else {
+                    None
+                });
+                if W::DEPOSIT_PER_BYTE > 0 {
+                    refunded = refunded.saturating_add(deposits::locked(&input.output_ref));
+                }
+            } else if matches!(input.mode, InputMode::Evict | InputMode::Reclaim) {
+                debug!(
+                    target: LOG_TARGET,
+                    "Skipping {:?} of missing input {:?}", input.mode, input.output_ref
+                );
             } else {
                 missing_inputs.push(input.output_ref.clone().encode());
             }
@@ -92,7 +331,7 @@ impl<B: BlockT<Extrinsic = Transaction<V, C>>, V: Verifier, C: ConstraintChecker
         // Use the same vec as previously to keep track of missing peeks
         let mut peek_utxos = Vec::new();
         for output_ref in transaction.peeks.iter() {
-            if let Some(peek_utxo) = TransparentUtxoSet::<V>::peek_utxo(output_ref) {
+            if let Some(peek_utxo) = U::peek_utxo(output_ref) {
                 peek_utxos.push(peek_utxo);
             } else {
                 missing_inputs.push(output_ref.encode());
@@ -100,7 +339,6 @@ impl<B: BlockT<Extrinsic = Transaction<V, C>>, V: Verifier, C: ConstraintChecker
         }
 
         // Make sure no outputs already exist in storage
-        let tx_hash = BlakeTwo256::hash_of(&transaction.encode());
         for index in 0..transaction.outputs.len() {
             let output_ref = OutputRef {
                 tx_hash,
@@ -113,11 +351,17 @@ impl<B: BlockT<Extrinsic = Transaction<V, C>>, V: Verifier, C: ConstraintChecker
             );
 
             ensure!(
-                TransparentUtxoSet::<V>::peek_utxo(&output_ref).is_none(),
+                U::peek_utxo(&output_ref).is_none(),
                 UtxoError::PreExistingOutput
             );
         }
 
+        // Make sure a mint's outputs did not exist before either
+        ensure!(
+            !(Self::is_mint(transaction) && sp_io::storage::exists(&Self::mint_key(tx_hash))),
+            UtxoError::PreExistingOutput
+        );
+
         // Calculate the tx-pool tags provided by this transaction, which
         // are just the encoded OutputRefs
         let provides = (0..transaction.outputs.len())
@@ -130,6 +374,13 @@ impl<B: BlockT<Extrinsic = Transaction<V, C>>, V: Verifier, C: ConstraintChecker
             })
             .collect::<Vec<_>>();
 
+        // Make sure the transaction could fit in a block at all
+        let weight = Self::weigh(transaction, &input_utxos);
+        ensure!(
+            weight <= W::MAXIMUM_BLOCK_WEIGHT,
+            UtxoError::ExhaustsBlockWeight
+        );
+
         // If any of the inputs are missing, we cannot make any more progress
         // If they are all present, we may proceed to call the constraint checker
         if !missing_inputs.is_empty() {
@@ -137,29 +388,71 @@ impl<B: BlockT<Extrinsic = Transaction<V, C>>, V: Verifier, C: ConstraintChecker
                 target: LOG_TARGET,
                 "Transaction is valid but still has missing inputs. Returning early.",
             );
-            return Ok(ValidTransaction {
+            let valid_transaction = ValidTransaction {
                 requires: missing_inputs,
                 provides,
                 priority: 0,
-                longevity: TransactionLongevity::max_value(),
+                longevity,
                 propagate: true,
-            });
+            };
+            return Ok((valid_transaction, weight, 0));
         }
 
-        // Call the constraint checker
-        transaction
+        // Call the constraint checker. Besides the block, the context describes the parts of
+        // the transaction that simple constraint checkers are not given directly.
+        let checker_context = CheckerContext {
+            redeemers,
+            input_verifiers: input_utxos.iter().map(|u| u.verifier.encode()).collect(),
+            output_verifiers: transaction
+                .outputs
+                .iter()
+                .map(|o| o.verifier.encode())
+                .collect(),
+            memo: transaction.memo.clone(),
+            reclaimed,
+            input_ages,
+            ..Self::checker_context()
+        };
+        let surplus = transaction
             .checker
-            .check(&input_utxos, &peek_utxos, &transaction.outputs)
+            .check(
+                &input_utxos,
+                &peek_utxos,
+                &transaction.outputs,
+                &checker_context,
+            )
             .map_err(UtxoError::ConstraintCheckerError)?;
 
-        // Return the valid transaction
-        Ok(ValidTransaction {
+        // Make sure the value that the transaction burns, along with the deposits that its inputs
+        // refund, pays its fee and the deposits of its outputs. Whatever is left over is a tip.
+        // Inherents are created by the block author and pay neither fees nor deposits.
+        let (available, required) = if transaction.checker.is_inherent() {
+            (u128::from(surplus), 0)
+        } else {
+            let fee = weights::fee::<W>(weight, transaction.encoded_size());
+            let locked = transaction
+                .outputs
+                .iter()
+                .map(|output| weights::deposit::<W>(output.encoded_size()))
+                .fold(0, u128::saturating_add);
+            (
+                u128::from(surplus).saturating_add(refunded),
+                fee.saturating_add(locked),
+            )
+        };
+        ensure!(available >= required, UtxoError::InsufficientFee);
+        let tip = available - required;
+
+        // Return the valid transaction, prioritized by how much it tips for the weight it uses,
+        // unless the runtime's pool policy says otherwise
+        let valid_transaction = ValidTransaction {
             requires: Vec::new(),
             provides,
-            priority: 0,
-            longevity: TransactionLongevity::max_value(),
+            priority: P::priority(transaction, weights::priority(tip, weight)),
+            longevity,
             propagate: true,
-        })
+        };
+        Ok((valid_transaction, weight, tip))
     }
 
     /// Does full verification and application of tuxedo transactions.
@@ -173,7 +466,9 @@ impl<B: BlockT<Extrinsic = Transaction<V, C>>, V: Verifier, C: ConstraintChecker
 
         // Re-do the pre-checks. These should have been done in the pool, but we can't
         // guarantee that foreign nodes to these checks faithfully, so we need to check on-chain.
-        let valid_transaction = Self::validate_tuxedo_transaction(&transaction)?;
+        // Collect the events that the constraint checker emits along the way.
+        let (validity, emitted_events) = events::collect(|| Self::validate_and_weigh(&transaction));
+        let (valid_transaction, weight, tip) = validity?;
 
         // If there are still missing inputs, we cannot execute this,
         // although it would be valid in the pool
@@ -182,12 +477,72 @@ impl<B: BlockT<Extrinsic = Transaction<V, C>>, V: Verifier, C: ConstraintChecker
             UtxoError::MissingInput
         );
 
+        // Make sure there is enough weight left in the block
+        ensure!(
+            weights::block_weight().saturating_add(weight) <= W::MAXIMUM_BLOCK_WEIGHT,
+            UtxoError::ExhaustsBlockWeight
+        );
+        weights::note(weight);
+
+        // Note the fee and tip paid by this transaction so they can be collected later.
+        // Inherents are created by the block author and do not pay fees.
+        if !transaction.checker.is_inherent() {
+            let fee = weights::fee::<W>(weight, transaction.encoded_size());
+            fees::note(fee);
+            fees::note_tip(tip);
+        }
+
         // At this point, all validation is complete, so we can commit the storage changes.
+        let tx_hash = BlakeTwo256::hash_of(&transaction.encode());
+        if Self::is_mint(&transaction) {
+            sp_io::storage::set(&Self::mint_key(tx_hash), &[]);
+        }
+        if W::DEPOSIT_PER_BYTE > 0 {
+            // The deposits of the removed outputs helped pay for this transaction, and the new
+            // outputs lock their own.
+            for input in &transaction.inputs {
+                deposits::release(&input.output_ref);
+            }
+            if !transaction.checker.is_inherent() {
+                for (index, output) in transaction.outputs.iter().enumerate() {
+                    let output_ref = OutputRef {
+                        tx_hash,
+                        index: index as u32,
+                    };
+                    deposits::lock(&output_ref, weights::deposit::<W>(output.encoded_size()));
+                }
+            }
+        }
+        if W::TRACK_AGES {
+            let block_height = Self::verifier_context().block_height;
+            for input in &transaction.inputs {
+                ages::forget(&input.output_ref);
+            }
+            for index in 0..transaction.outputs.len() {
+                let output_ref = OutputRef {
+                    tx_hash,
+                    index: index as u32,
+                };
+                ages::note(&output_ref, block_height);
+            }
+        }
+        events::note(tx_hash, emitted_events);
         Self::update_storage(transaction);
 
         Ok(())
     }
 
+    /// Apply an extrinsic while executing a block, in which every extrinsic must be valid.
+    fn apply_in_block(extrinsic: &Transaction<V, C>) {
+        match Self::apply_tuxedo_transaction(extrinsic.clone()) {
+            Ok(()) => debug!(
+                target: LOG_TARGET,
+                "Successfully executed extrinsic: {:?}", extrinsic
+            ),
+            Err(e) => panic!("{:?}", e),
+        }
+    }
+
     /// Helper function to update the utxo set according to the given transaction.
     /// This function does absolutely no validation. It assumes that the transaction
     /// has already passed validation. Changes proposed by the transaction are written
@@ -195,7 +550,7 @@ impl<B: BlockT<Extrinsic = Transaction<V, C>>, V: Verifier, C: ConstraintChecker
     fn update_storage(transaction: Transaction<V, C>) {
         // Remove verified UTXOs
         for input in &transaction.inputs {
-            TransparentUtxoSet::<V>::consume_utxo(&input.output_ref);
+            U::consume_utxo(&input.output_ref);
         }
 
         debug!(
@@ -208,7 +563,64 @@ impl<B: BlockT<Extrinsic = Transaction<V, C>>, V: Verifier, C: ConstraintChecker
                 tx_hash: BlakeTwo256::hash_of(&transaction.encode()),
                 index: index as u32,
             };
-            TransparentUtxoSet::<V>::store_utxo(output_ref, output);
+            U::store_utxo(output_ref, output);
+        }
+    }
+
+    /// The chain and runtime that the redeemers of transactions must be made for.
+    pub fn signing_domain() -> SigningDomain {
+        let version = R::get();
+        SigningDomain {
+            genesis_hash: Self::genesis_hash(),
+            spec_version: version.spec_version,
+            transaction_version: version.transaction_version,
+        }
+    }
+
+    /// The hash of the genesis block. It is noted when the first block is opened, so it is zero
+    /// until then. Chains that were past their first block before the genesis hash was noted have
+    /// no record of it, so it is zero for them too.
+    pub fn genesis_hash() -> H256 {
+        sp_io::storage::get(GENESIS_HASH_KEY)
+            .and_then(|d| H256::decode(&mut &*d).ok())
+            .unwrap_or_default()
+    }
+
+    /// The context in which verifiers are checked.
+    ///
+    /// While a block is being built or imported, this describes that block. In the pool there is
+    /// no such block, so we assume the transaction will be included in the block right after the
+    /// one that noted the best known time.
+    fn verifier_context() -> VerifierContext {
+        let (timestamp, noted_in) = best_time().unwrap_or_default();
+        let block_height = sp_io::storage::get(HEADER_KEY)
+            .and_then(|d| B::Header::decode(&mut &*d).ok())
+            .map(|header| (*header.number()).unique_saturated_into())
+            .unwrap_or(noted_in.saturating_add(1));
+
+        VerifierContext {
+            block_height,
+            timestamp,
+        }
+    }
+
+    /// The context in which constraint checkers are checked.
+    ///
+    /// Like the verifier context, this describes the block being built or imported. In the pool,
+    /// it describes the next block, whose digest is not known yet.
+    fn checker_context() -> CheckerContext {
+        let header = sp_io::storage::get(HEADER_KEY).and_then(|d| B::Header::decode(&mut &*d).ok());
+
+        match header {
+            Some(header) => CheckerContext {
+                block_height: (*header.number()).unique_saturated_into(),
+                digest: header.digest().clone(),
+                ..Default::default()
+            },
+            None => CheckerContext {
+                block_height: Self::verifier_context().block_height,
+                ..Default::default()
+            },
         }
     }
 
@@ -223,6 +635,32 @@ impl<B: BlockT<Extrinsic = Transaction<V, C>>, V: Verifier, C: ConstraintChecker
             .number()
     }
 
+    /// A helper function that allows tuxedo runtimes to read the current block's parent hash.
+    /// This is useful as a source of entropy that is already known when transactions are built.
+    pub fn parent_hash() -> <B as BlockT>::Hash
+    where
+        B::Header: HeaderT,
+    {
+        *sp_io::storage::get(HEADER_KEY)
+            .and_then(|d| B::Header::decode(&mut &*d).ok())
+            .expect("A header is always stored at the beginning of the block")
+            .parent_hash()
+    }
+
+    /// A helper function that allows tuxedo runtimes to read the current block's digest.
+    /// This is useful, for example, to find the block author from the consensus engine's
+    /// pre-runtime digest.
+    pub fn block_digest() -> sp_runtime::Digest
+    where
+        B::Header: HeaderT,
+    {
+        sp_io::storage::get(HEADER_KEY)
+            .and_then(|d| B::Header::decode(&mut &*d).ok())
+            .expect("A header is always stored at the beginning of the block")
+            .digest()
+            .clone()
+    }
+
     // These next three methods are for the block authoring workflow.
     // Open the block, apply zero or more extrinsics, close the block
 
@@ -235,6 +673,24 @@ impl<B: BlockT<Extrinsic = Transaction<V, C>>, V: Verifier, C: ConstraintChecker
         // Store the transient partial header for updating at the end of the block.
         // This will be removed from storage before the end of the block.
         sp_io::storage::set(HEADER_KEY, &header.encode());
+
+        Self::note_genesis_hash(header);
+
+        // The fees paid in the previous block may now be collected.
+        fees::roll_over();
+
+        // The events of the previous block are no longer kept.
+        events::clear();
+    }
+
+    /// The parent of the first block is the genesis block. Its hash can't be part of the
+    /// genesis state, because it depends on that state, so it is noted when the first block is
+    /// opened or executed instead.
+    fn note_genesis_hash(header: &<B as BlockT>::Header) {
+        if UniqueSaturatedInto::<u32>::unique_saturated_into(*header.number()) == 1 {
+            sp_io::storage::set(GENESIS_HASH_KEY, &header.parent_hash().encode());
+            sp_io::storage::clear(AWAITING_GENESIS_HASH_KEY);
+        }
     }
 
     pub fn apply_extrinsic(extrinsic: <B as BlockT>::Extrinsic) -> ApplyExtrinsicResult {
@@ -252,8 +708,14 @@ impl<B: BlockT<Extrinsic = Transaction<V, C>>, V: Verifier, C: ConstraintChecker
         sp_io::storage::set(EXTRINSIC_KEY, &extrinsics.encode());
 
         // Now actually
-        Self::apply_tuxedo_transaction(extrinsic)
-            .map_err(|_| TransactionValidityError::Invalid(InvalidTransaction::Custom(0)))?;
+        Self::apply_tuxedo_transaction(extrinsic).map_err(|e| match e {
+            // Let the block author know that the block is full
+            UtxoError::ExhaustsBlockWeight => {
+                TransactionValidityError::Invalid(InvalidTransaction::ExhaustsResources)
+            }
+            UtxoError::Expired => TransactionValidityError::Invalid(InvalidTransaction::Stale),
+            _ => TransactionValidityError::Invalid(InvalidTransaction::Custom(0)),
+        })?;
 
         Ok(Ok(()))
     }
@@ -266,6 +728,12 @@ impl<B: BlockT<Extrinsic = Transaction<V, C>>, V: Verifier, C: ConstraintChecker
         // the header itself contains the state root, so it cannot be inside the state (circular
         // dependency..). Make sure in execute block path we have the same rule.
         sp_io::storage::clear(HEADER_KEY);
+        weights::clear();
+
+        // The logs that transactions deposited go in the header, after the pre-runtime logs.
+        for log in digest::take() {
+            header.digest_mut().push(log);
+        }
 
         let extrinsics = sp_io::storage::get(EXTRINSIC_KEY)
             .and_then(|d| <Vec<Vec<u8>>>::decode(&mut &*d).ok())
@@ -289,6 +757,12 @@ impl<B: BlockT<Extrinsic = Transaction<V, C>>, V: Verifier, C: ConstraintChecker
     // This one is for the Core api. It is used to import blocks authored by foreign nodes.
 
     pub fn execute_block(block: B) {
+        Self::execute_block_with(block, true);
+    }
+
+    /// Execute the block, and return the weight that it used. The state root check may only be
+    /// skipped when trying out a runtime whose state roots are expected to differ.
+    fn execute_block_with(block: B, check_state_root: bool) -> Weight {
         debug!(
             target: LOG_TARGET,
             "Entering execute_block. block: {:?}", block
@@ -299,43 +773,74 @@ impl<B: BlockT<Extrinsic = Transaction<V, C>>, V: Verifier, C: ConstraintChecker
         // be cleared before the end of the block
         sp_io::storage::set(HEADER_KEY, &block.header().encode());
 
+        // Importing nodes note the genesis hash just like the author did.
+        Self::note_genesis_hash(block.header());
+
+        // The fees paid in the previous block may now be collected.
+        fees::roll_over();
+
+        // The events of the previous block are no longer kept.
+        events::clear();
+
         // Tuxedo requires that inherents are at the beginning (and soon end) of the
-        // block and not scattered throughout. We use this flag to enforce that.
-        let mut finished_with_opening_inherents = false;
-
-        // Apply each extrinsic
-        for extrinsic in block.extrinsics() {
-            // Enforce that inherents are in the right place
-            let current_tx_is_inherent = extrinsic.checker.is_inherent();
-            if current_tx_is_inherent && finished_with_opening_inherents {
-                panic!("Tried to execute opening inherent after switching to non-inherents.");
-            }
-            if !current_tx_is_inherent && !finished_with_opening_inherents {
-                // This is the first non-inherent, so we update our flag and continue.
-                finished_with_opening_inherents = true;
-            }
+        // block and not scattered throughout. So we apply the opening inherents first.
+        let extrinsics = block.extrinsics();
+        let opening_inherents = extrinsics
+            .iter()
+            .take_while(|extrinsic| extrinsic.checker.is_inherent())
+            .count();
+        let (inherents, transactions) = extrinsics.split_at(opening_inherents);
+        for extrinsic in inherents {
+            Self::apply_in_block(extrinsic);
+        }
 
-            match Self::apply_tuxedo_transaction(extrinsic.clone()) {
-                Ok(()) => debug!(
-                    target: LOG_TARGET,
-                    "Successfully executed extrinsic: {:?}", extrinsic
-                ),
-                Err(e) => panic!("{:?}", e),
+        // Then we apply the rest, enforcing that there are no more inherents among them.
+        let apply_transactions = || {
+            for extrinsic in transactions {
+                if extrinsic.checker.is_inherent() {
+                    panic!("Tried to execute opening inherent after switching to non-inherents.");
+                }
+                Self::apply_in_block(extrinsic);
             }
-        }
+        };
+
+        // The inherents have set up the context in which verifiers are checked, so natively,
+        // we can verify the rest of the block's redeemers in parallel before applying it.
+        #[cfg(feature = "parallel-verification")]
+        preverified::using(
+            &mut Self::verify_in_parallel(transactions),
+            apply_transactions,
+        );
+        #[cfg(not(feature = "parallel-verification"))]
+        apply_transactions();
 
-        // Clear the transient header out of storage
+        // Clear the transient header and block weight out of storage
+        let block_weight = weights::block_weight();
         sp_io::storage::clear(HEADER_KEY);
+        weights::clear();
+
+        // Check that the header carries exactly the logs that the transactions deposited
+        let carried_logs: Vec<_> = block
+            .header()
+            .digest()
+            .logs()
+            .iter()
+            .filter(|log| digest::is_deposited(log))
+            .cloned()
+            .collect();
+        assert_eq!(carried_logs, digest::take(), "digest logs mismatch");
 
         // Check state root
-        let raw_state_root = &sp_io::storage::root(StateVersion::V1)[..];
-        let state_root =
-            <<B as BlockT>::Header as HeaderT>::Hash::decode(&mut &raw_state_root[..]).unwrap();
-        assert_eq!(
-            *block.header().state_root(),
-            state_root,
-            "state root mismatch"
-        );
+        if check_state_root {
+            let raw_state_root = &sp_io::storage::root(StateVersion::V1)[..];
+            let state_root =
+                <<B as BlockT>::Header as HeaderT>::Hash::decode(&mut &raw_state_root[..]).unwrap();
+            assert_eq!(
+                *block.header().state_root(),
+                state_root,
+                "state root mismatch"
+            );
+        }
 
         // Print state for quick debugging
         // let mut key = vec![];
@@ -365,6 +870,8 @@ impl<B: BlockT<Extrinsic = Transaction<V, C>>, V: Verifier, C: ConstraintChecker
             extrinsics_root,
             "extrinsics root mismatch"
         );
+
+        block_weight
     }
 
     // This one is the pool api. It is used to make preliminary checks in the transaction pool
@@ -382,6 +889,14 @@ impl<B: BlockT<Extrinsic = Transaction<V, C>>, V: Verifier, C: ConstraintChecker
             block_hash
         );
 
+        // The genesis hash is only noted once the first block is opened. Until then, the pool
+        // validates against the genesis state, so the block we are given is the genesis block.
+        // Any other block is never taken for the genesis block, even if no hash was noted.
+        // Nothing written to storage while validating is kept.
+        if sp_io::storage::exists(AWAITING_GENESIS_HASH_KEY) {
+            sp_io::storage::set(GENESIS_HASH_KEY, &block_hash.encode());
+        }
+
         // Inherents are not permitted in the pool. They only come from the block author.
         // We perform this check here rather than in the `validate_tuxedo_transaction` helper,
         // because that helper is called again during on-chain execution. Inherents are valid
@@ -391,15 +906,39 @@ impl<B: BlockT<Extrinsic = Transaction<V, C>>, V: Verifier, C: ConstraintChecker
         } else {
             // TODO, we need a good way to map our UtxoError into the supposedly generic InvalidTransaction
             // https://paritytech.github.io/substrate/master/sp_runtime/transaction_validity/enum.InvalidTransaction.html
-            // For now, I just make them all custom zero, and log the error variant
-            Self::validate_tuxedo_transaction(&tx).map_err(|e| {
-                log::warn!(
-                    target: LOG_TARGET,
-                    "Tuxedo Transaction did not validate (in the pool): {:?}",
-                    e,
-                );
-                TransactionValidityError::Invalid(InvalidTransaction::Custom(0))
-            })
+            // For now, I just make them all custom zero, except expiry, and log the error variant
+            Self::validate_tuxedo_transaction(&tx)
+                .map_err(|e| {
+                    log::warn!(
+                        target: LOG_TARGET,
+                        "Tuxedo Transaction did not validate (in the pool): {:?}",
+                        e,
+                    );
+                    match e {
+                        UtxoError::Expired => {
+                            TransactionValidityError::Invalid(InvalidTransaction::Stale)
+                        }
+                        _ => TransactionValidityError::Invalid(InvalidTransaction::Custom(0)),
+                    }
+                })
+                .and_then(|valid_transaction| {
+                    // Transactions that are still missing inputs have not been checked, so their
+                    // priority means nothing yet. They are held to the minimum once the pool revalidates them.
+                    let minimum = P::minimum_priority(&tx);
+                    if valid_transaction.requires.is_empty() && valid_transaction.priority < minimum
+                    {
+                        log::warn!(
+                            target: LOG_TARGET,
+                            "Tuxedo Transaction priority {} is below the pool's minimum of {}",
+                            valid_transaction.priority,
+                            minimum,
+                        );
+                        return Err(TransactionValidityError::Invalid(
+                            InvalidTransaction::Payment,
+                        ));
+                    }
+                    Ok(valid_transaction)
+                })
         };
 
         debug!(target: LOG_TARGET, "Validation result: {:?}", r);
@@ -442,7 +981,14 @@ impl<B: BlockT<Extrinsic = Transaction<V, C>>, V: Verifier, C: ConstraintChecker
         );
 
         // Call into constraint checker's own inherent hooks to create the actual transactions
-        C::InherentHooks::create_inherents(&data, previous_blocks_inherents)
+        let mut inherents = C::InherentHooks::create_inherents(&data, previous_blocks_inherents);
+
+        // Order the inherents by their identifiers. This way each inherent's position is deterministic
+        // and does not depend on the order in which the runtime happens to list its pieces.
+        // The sort is stable, so multiple inherents from a single piece keep their relative order.
+        inherents.sort_by_key(|tx| C::InherentHooks::identify(tx));
+
+        inherents
     }
 
     pub fn check_inherents(block: B, data: InherentData) -> sp_inherents::CheckInherentsResult {
@@ -458,6 +1004,8 @@ impl<B: BlockT<Extrinsic = Transaction<V, C>>, V: Verifier, C: ConstraintChecker
         // (TODO revise this logic once that is implemented.)
         // At this off-chain pre-check stage, we assume that requirement is upheld.
         // It will be verified later once we are executing on-chain.
+        // Each piece finds and checks its own inherents wherever they are among the inherents,
+        // so the checks do not depend on the order of the inherents.
         let inherents: Vec<Transaction<V, C>> = block
             .extrinsics()
             .iter()
@@ -469,6 +1017,68 @@ impl<B: BlockT<Extrinsic = Transaction<V, C>>, V: Verifier, C: ConstraintChecker
 
         result
     }
+
+    // This one is the off-chain worker api. It is called by the node after importing a block.
+
+    pub fn offchain_worker(header: &<B as BlockT>::Header)
+    where
+        <B as BlockT>::Hash: Into<H256>,
+    {
+        debug!(
+            target: LOG_TARGET,
+            "Entering offchain_worker. header: {:?}", header
+        );
+
+        let context = OffchainContext {
+            block_height: (*header.number()).unique_saturated_into(),
+            block_hash: header.hash().into(),
+        };
+
+        O::offchain_worker(&context);
+    }
+
+    // These last two are for the try-runtime api. They are never called on a real chain.
+
+    #[cfg(feature = "try-runtime")]
+    pub fn try_runtime_upgrade(checks: UpgradeCheckSelect) -> (TryRuntimeWeight, TryRuntimeWeight) {
+        debug!(
+            target: LOG_TARGET,
+            "Entering try_runtime_upgrade. checks: {:?}", checks
+        );
+
+        // Tuxedo has no migrations that run on upgrade, so the upgrade itself weighs nothing.
+        // The state checks still run so that the new runtime can check the state it inherits.
+        if checks.try_state() {
+            S::try_state(0, &TryStateSelect::All)
+                .unwrap_or_else(|e| panic!("State checks failed after the upgrade: {}", e));
+        }
+
+        (TryRuntimeWeight::default(), W::MAXIMUM_BLOCK_WEIGHT.into())
+    }
+
+    #[cfg(feature = "try-runtime")]
+    pub fn try_execute_block(
+        block: B,
+        state_root_check: bool,
+        signature_check: bool,
+        select: TryStateSelect,
+    ) -> TryRuntimeWeight {
+        // Redeemers are part of what makes a Tuxedo transaction valid, so they are always checked.
+        if !signature_check {
+            log::warn!(
+                target: LOG_TARGET,
+                "Tuxedo always checks redeemers. Ignoring the request to skip signature checks."
+            );
+        }
+
+        let block_height = (*block.header().number()).unique_saturated_into();
+        let weight = Self::execute_block_with(block, state_root_check);
+
+        S::try_state(block_height, &select)
+            .unwrap_or_else(|e| panic!("State checks failed after executing the block: {}", e));
+
+        weight.into()
+    }
 }
 
 #[cfg(test)]
@@ -478,10 +1088,12 @@ mod tests {
     use sp_runtime::transaction_validity::ValidTransactionBuilder;
 
     use crate::{
-        constraint_checker::testing::TestConstraintChecker,
-        dynamic_typing::{testing::Bogus, UtxoData},
-        types::{Input, Output},
-        verifier::TestVerifier,
+        constraint_checker::{testing::TestConstraintChecker, SimpleConstraintChecker},
+        dynamic_typing::{testing::Bogus, DynamicallyTypedData, UtxoData},
+        types::{Input, Output, TransactionVersion},
+        utxo_set::{utxo_child_info, UTXO_CHILD_TRIE},
+        verifier::{note_time, TestVerifier},
+        weights::DEFAULT_CHECKER_WEIGHT,
     };
 
     use super::*;
@@ -491,6 +1103,262 @@ mod tests {
     pub type TestBlock = sp_runtime::generic::Block<TestHeader, TestTransaction>;
     pub type TestExecutive = Executive<TestBlock, TestVerifier, TestConstraintChecker>;
 
+    /// A weight config that charges a fee of one for every transaction,
+    /// and only leaves room for a single empty transaction in each block.
+    pub struct TightConfig;
+
+    impl WeightConfig for TightConfig {
+        const MAXIMUM_BLOCK_WEIGHT: Weight = DEFAULT_CHECKER_WEIGHT * 3 / 2;
+
+        fn weight_to_fee(_weight: Weight) -> u128 {
+            1
+        }
+    }
+
+    pub type TightExecutive =
+        Executive<TestBlock, TestVerifier, TestConstraintChecker, TightConfig>;
+
+    /// A weight config that only allows tiny transactions.
+    pub struct SmallConfig;
+
+    impl WeightConfig for SmallConfig {
+        const MAXIMUM_INPUTS: u32 = 1;
+        const MAXIMUM_PEEKS: u32 = 1;
+        const MAXIMUM_OUTPUTS: u32 = 1;
+        const MAXIMUM_TRANSACTION_LENGTH: u32 = 100;
+        const MAXIMUM_MEMO_LENGTH: u32 = 8;
+    }
+
+    pub type SmallExecutive =
+        Executive<TestBlock, TestVerifier, TestConstraintChecker, SmallConfig>;
+
+    /// A weight config that charges no fees, but requires a deposit of one for every byte.
+    pub struct DepositConfig;
+
+    impl WeightConfig for DepositConfig {
+        const DEPOSIT_PER_BYTE: u128 = 1;
+    }
+
+    pub type DepositExecutive =
+        Executive<TestBlock, TestVerifier, TestConstraintChecker, DepositConfig>;
+
+    /// A utxo set that keeps every utxo under a prefix, to show that the executive
+    /// does not depend on how the set is laid out in storage.
+    pub struct PrefixedUtxoSet;
+
+    impl PrefixedUtxoSet {
+        fn key(output_ref: &OutputRef) -> Vec<u8> {
+            [b"utxo".as_slice(), &output_ref.encode()].concat()
+        }
+    }
+
+    impl UtxoSet<TestVerifier> for PrefixedUtxoSet {
+        fn peek_utxo(output_ref: &OutputRef) -> Option<Output<TestVerifier>> {
+            sp_io::storage::get(&Self::key(output_ref)).and_then(|d| Output::decode(&mut &*d).ok())
+        }
+
+        fn consume_utxo(output_ref: &OutputRef) -> Option<Output<TestVerifier>> {
+            let maybe_output = Self::peek_utxo(output_ref);
+            sp_io::storage::clear(&Self::key(output_ref));
+            maybe_output
+        }
+
+        fn store_utxo(output_ref: OutputRef, output: &Output<TestVerifier>) {
+            sp_io::storage::set(&Self::key(&output_ref), &output.encode());
+        }
+    }
+
+    pub type PrefixedExecutive =
+        Executive<TestBlock, TestVerifier, TestConstraintChecker, (), PrefixedUtxoSet>;
+
+    /// An off-chain worker that leaves the context it was called with in storage.
+    pub struct RecordingWorker;
+
+    impl OffchainWorker<TestVerifier, TestConstraintChecker> for RecordingWorker {
+        fn offchain_worker(context: &OffchainContext) {
+            sp_io::storage::set(
+                b"offchain",
+                &(context.block_height, context.block_hash).encode(),
+            );
+        }
+    }
+
+    pub type OffchainExecutive = Executive<
+        TestBlock,
+        TestVerifier,
+        TestConstraintChecker,
+        (),
+        TransparentUtxoSet<TestVerifier>,
+        RecordingWorker,
+    >;
+
+    /// A runtime version for testing that signatures commit to it.
+    pub struct TestVersion;
+
+    impl Get<RuntimeVersion> for TestVersion {
+        fn get() -> RuntimeVersion {
+            RuntimeVersion {
+                spec_version: 7,
+                transaction_version: 3,
+                ..Default::default()
+            }
+        }
+    }
+
+    pub type VersionedExecutive = Executive<
+        TestBlock,
+        TestVerifier,
+        TestConstraintChecker,
+        (),
+        TransparentUtxoSet<TestVerifier>,
+        (),
+        (),
+        TestVersion,
+    >;
+
+    /// A pool policy that puts inherents first and everything else last.
+    pub struct InherentsFirst;
+
+    impl PoolPolicy<TestVerifier, TestConstraintChecker> for InherentsFirst {
+        fn priority(
+            transaction: &TestTransaction,
+            _priority: TransactionPriority,
+        ) -> TransactionPriority {
+            if transaction.checker.inherent {
+                TransactionPriority::MAX
+            } else {
+                0
+            }
+        }
+    }
+
+    /// A pool policy that refuses transactions that do not tip at all.
+    pub struct TipsRequired;
+
+    impl PoolPolicy<TestVerifier, TestConstraintChecker> for TipsRequired {
+        fn priority(
+            _transaction: &TestTransaction,
+            priority: TransactionPriority,
+        ) -> TransactionPriority {
+            priority
+        }
+
+        fn minimum_priority(_transaction: &TestTransaction) -> TransactionPriority {
+            1
+        }
+    }
+
+    pub type TipsRequiredExecutive = Executive<
+        TestBlock,
+        TestVerifier,
+        TestConstraintChecker,
+        (),
+        TransparentUtxoSet<TestVerifier>,
+        (),
+        (),
+        (),
+        TipsRequired,
+    >;
+
+    pub type PolicyExecutive = Executive<
+        TestBlock,
+        TestVerifier,
+        TestConstraintChecker,
+        (),
+        TransparentUtxoSet<TestVerifier>,
+        (),
+        (),
+        (),
+        InherentsFirst,
+    >;
+
+    /// A simple constraint checker that requires the last output to go back to the owner of
+    /// the first input, and the first input to be redeemed with the given redeemer.
+    #[derive(
+        serde::Serialize,
+        serde::Deserialize,
+        Encode,
+        Decode,
+        Debug,
+        Clone,
+        PartialEq,
+        Eq,
+        scale_info::TypeInfo,
+    )]
+    pub struct ChangeToSender(Vec<u8>);
+
+    impl SimpleConstraintChecker for ChangeToSender {
+        type Error = ();
+
+        fn check(
+            &self,
+            _input_data: &[DynamicallyTypedData],
+            _peek_data: &[DynamicallyTypedData],
+            _output_data: &[DynamicallyTypedData],
+            context: &CheckerContext,
+        ) -> Result<TransactionPriority, ()> {
+            ensure!(
+                context.output_verifiers.last() == context.input_verifiers.first(),
+                ()
+            );
+            ensure!(context.redeemers.first() == Some(&self.0), ());
+            Ok(0)
+        }
+    }
+
+    pub type ChangeBlock =
+        sp_runtime::generic::Block<TestHeader, Transaction<TestVerifier, ChangeToSender>>;
+    pub type ChangeExecutive = Executive<ChangeBlock, TestVerifier, ChangeToSender>;
+
+    /// A weight config that tracks the ages of outputs.
+    pub struct AgesConfig;
+
+    impl WeightConfig for AgesConfig {
+        const TRACK_AGES: bool = true;
+    }
+
+    pub type AgesExecutive = Executive<TestBlock, TestVerifier, TestConstraintChecker, AgesConfig>;
+
+    /// A simple constraint checker that reclaims inputs that are at least the given number of
+    /// blocks old.
+    #[derive(
+        serde::Serialize,
+        serde::Deserialize,
+        Encode,
+        Decode,
+        Debug,
+        Clone,
+        PartialEq,
+        Eq,
+        scale_info::TypeInfo,
+    )]
+    pub struct ReclaimOld(u32);
+
+    impl SimpleConstraintChecker for ReclaimOld {
+        type Error = ();
+
+        fn check(
+            &self,
+            _input_data: &[DynamicallyTypedData],
+            _peek_data: &[DynamicallyTypedData],
+            _output_data: &[DynamicallyTypedData],
+            context: &CheckerContext,
+        ) -> Result<TransactionPriority, ()> {
+            for (reclaimed, age) in context.reclaimed.iter().zip(&context.input_ages) {
+                ensure!(!reclaimed || age.is_some_and(|age| age >= self.0), ());
+            }
+            Ok(0)
+        }
+
+        fn reclaims(&self) -> bool {
+            true
+        }
+    }
+
+    pub type ReclaimBlock =
+        sp_runtime::generic::Block<TestHeader, Transaction<TestVerifier, ReclaimOld>>;
+    pub type ReclaimExecutive = Executive<ReclaimBlock, TestVerifier, ReclaimOld, AgesConfig>;
+
     /// Construct a mock OutputRef from a transaction number and index in that transaction.
     ///
     /// When setting up tests, it is often useful to have some Utxos in the storage
@@ -511,6 +1379,7 @@ mod tests {
         inputs: Vec<Input>,
         peeks: Vec<OutputRef>,
         outputs: Vec<Output<TestVerifier>>,
+        valid_until: Option<u32>,
     }
 
     impl TestTransactionBuilder {
@@ -529,12 +1398,20 @@ mod tests {
             self
         }
 
+        fn with_valid_until(mut self, valid_until: u32) -> Self {
+            self.valid_until = Some(valid_until);
+            self
+        }
+
         fn build(self, checks: bool, inherent: bool) -> TestTransaction {
             TestTransaction {
+                version: TransactionVersion::LATEST,
                 inputs: self.inputs,
                 peeks: self.peeks,
                 outputs: self.outputs,
                 checker: TestConstraintChecker { checks, inherent },
+                memo: None,
+                valid_until: self.valid_until,
             }
         }
     }
@@ -616,7 +1493,7 @@ mod tests {
 
             // Write all the utxos
             for (output_ref, output) in self.utxos {
-                ext.insert(output_ref.encode(), output.encode());
+                ext.insert_child(utxo_child_info(), output_ref.encode(), output.encode());
             }
 
             // Write the pre-header
@@ -653,6 +1530,7 @@ mod tests {
                 let input = Input {
                     output_ref,
                     redeemer: Vec::new(),
+                    mode: InputMode::Consume,
                 };
 
                 let tx = TestTransactionBuilder::default()
@@ -719,6 +1597,7 @@ mod tests {
             let input = Input {
                 output_ref: output_ref.clone(),
                 redeemer: Vec::new(),
+                mode: InputMode::Consume,
             };
 
             let tx = TestTransactionBuilder::default()
@@ -755,9 +1634,101 @@ mod tests {
     }
 
     #[test]
-    fn validate_with_duplicate_input_fails() {
-        let output_ref = mock_output_ref(0, 0);
-
+    fn validate_with_missing_eviction_works() {
+        ExternalityBuilder::default().build().execute_with(|| {
+            let input = Input {
+                output_ref: mock_output_ref(0, 0),
+                redeemer: Vec::new(),
+                mode: InputMode::Evict,
+            };
+
+            let tx = TestTransactionBuilder::default()
+                .with_input(input)
+                .build(true, false);
+
+            let vt = TestExecutive::validate_tuxedo_transaction(&tx).unwrap();
+
+            // Nothing is required, because there is nothing left to evict
+            let expected_result = ValidTransactionBuilder::default().into();
+
+            assert_eq!(vt, expected_result);
+        });
+    }
+
+    #[test]
+    fn validate_with_present_eviction_checks_verifier() {
+        let output_ref = mock_output_ref(0, 0);
+
+        ExternalityBuilder::default()
+            .with_utxo(output_ref.clone(), Bogus, false)
+            .build()
+            .execute_with(|| {
+                let input = Input {
+                    output_ref,
+                    redeemer: Vec::new(),
+                    mode: InputMode::Evict,
+                };
+
+                let tx = TestTransactionBuilder::default()
+                    .with_input(input)
+                    .build(true, false);
+
+                let result = TestExecutive::validate_tuxedo_transaction(&tx);
+
+                assert_eq!(result, Err(UtxoError::VerifierError));
+            });
+    }
+
+    #[test]
+    fn validate_with_missing_reclaim_works() {
+        ExternalityBuilder::default().build().execute_with(|| {
+            let input = Input {
+                output_ref: mock_output_ref(0, 0),
+                redeemer: Vec::new(),
+                mode: InputMode::Reclaim,
+            };
+
+            let tx = TestTransactionBuilder::default()
+                .with_input(input)
+                .build(true, false);
+
+            let vt = TestExecutive::validate_tuxedo_transaction(&tx).unwrap();
+
+            // Nothing is required, because there is nothing left to reclaim
+            let expected_result = ValidTransactionBuilder::default().into();
+
+            assert_eq!(vt, expected_result);
+        });
+    }
+
+    #[test]
+    fn validate_with_reclaim_by_checker_that_does_not_reclaim_fails() {
+        let output_ref = mock_output_ref(0, 0);
+
+        ExternalityBuilder::default()
+            .with_utxo(output_ref.clone(), Bogus, true)
+            .build()
+            .execute_with(|| {
+                let input = Input {
+                    output_ref,
+                    redeemer: Vec::new(),
+                    mode: InputMode::Reclaim,
+                };
+
+                let tx = TestTransactionBuilder::default()
+                    .with_input(input)
+                    .build(true, false);
+
+                let result = TestExecutive::validate_tuxedo_transaction(&tx);
+
+                assert_eq!(result, Err(UtxoError::VerifierError));
+            });
+    }
+
+    #[test]
+    fn validate_with_duplicate_input_fails() {
+        let output_ref = mock_output_ref(0, 0);
+
         ExternalityBuilder::default()
             .with_utxo(output_ref.clone(), Bogus, false)
             .build()
@@ -765,6 +1736,7 @@ mod tests {
                 let input = Input {
                     output_ref,
                     redeemer: Vec::new(),
+                    mode: InputMode::Consume,
                 };
 
                 let tx = TestTransactionBuilder::default()
@@ -778,6 +1750,35 @@ mod tests {
             });
     }
 
+    #[test]
+    fn validate_with_duplicate_input_in_different_modes_fails() {
+        let output_ref = mock_output_ref(0, 0);
+
+        ExternalityBuilder::default()
+            .with_utxo(output_ref.clone(), Bogus, true)
+            .build()
+            .execute_with(|| {
+                let consumed = Input {
+                    output_ref,
+                    redeemer: Vec::new(),
+                    mode: InputMode::Consume,
+                };
+                let evicted = Input {
+                    mode: InputMode::Evict,
+                    ..consumed.clone()
+                };
+
+                let tx = TestTransactionBuilder::default()
+                    .with_input(consumed)
+                    .with_input(evicted)
+                    .build(true, false);
+
+                let result = TestExecutive::validate_tuxedo_transaction(&tx);
+
+                assert_eq!(result, Err(UtxoError::DuplicateInput));
+            });
+    }
+
     #[test]
     fn validate_with_duplicate_peek_works() {
         // Peeking at the same input twice is considered valid. However, wallets should do their best
@@ -796,118 +1797,1041 @@ mod tests {
 
                 let vt = TestExecutive::validate_tuxedo_transaction(&tx).unwrap();
 
-                let expected_result = ValidTransactionBuilder::default().into();
+                let expected_result = ValidTransactionBuilder::default().into();
+
+                assert_eq!(vt, expected_result);
+            });
+    }
+
+    #[test]
+    fn validate_with_unsatisfied_verifier_fails() {
+        let output_ref = mock_output_ref(0, 0);
+
+        ExternalityBuilder::default()
+            .with_utxo(output_ref.clone(), Bogus, false)
+            .build()
+            .execute_with(|| {
+                let input = Input {
+                    output_ref,
+                    redeemer: Vec::new(),
+                    mode: InputMode::Consume,
+                };
+
+                let tx = TestTransactionBuilder::default()
+                    .with_input(input)
+                    .build(true, false);
+
+                let result = TestExecutive::validate_tuxedo_transaction(&tx);
+
+                assert_eq!(result, Err(UtxoError::VerifierError));
+            });
+    }
+
+    #[test]
+    fn verifier_context_uses_current_block() {
+        ExternalityBuilder::default()
+            .with_pre_header(H256::zero(), 5)
+            .build()
+            .execute_with(|| {
+                note_time(1_000, 4);
+
+                let expected = VerifierContext {
+                    block_height: 5,
+                    timestamp: 1_000,
+                };
+                assert_eq!(TestExecutive::verifier_context(), expected);
+            });
+    }
+
+    #[test]
+    fn verifier_context_in_pool_uses_next_block() {
+        ExternalityBuilder::default().build().execute_with(|| {
+            note_time(1_000, 4);
+
+            let expected = VerifierContext {
+                block_height: 5,
+                timestamp: 1_000,
+            };
+            assert_eq!(TestExecutive::verifier_context(), expected);
+        });
+    }
+
+    #[test]
+    fn checker_context_uses_current_block() {
+        ExternalityBuilder::default()
+            .with_pre_header(H256::zero(), 5)
+            .build()
+            .execute_with(|| {
+                let expected = CheckerContext {
+                    block_height: 5,
+                    ..Default::default()
+                };
+                assert_eq!(TestExecutive::checker_context(), expected);
+            });
+    }
+
+    #[test]
+    fn checker_context_in_pool_uses_next_block() {
+        ExternalityBuilder::default().build().execute_with(|| {
+            note_time(1_000, 4);
+
+            assert_eq!(TestExecutive::checker_context().block_height, 5);
+        });
+    }
+
+    #[test]
+    fn validate_before_expiry_works() {
+        ExternalityBuilder::default().build().execute_with(|| {
+            note_time(1_000, 4);
+
+            // The transaction may be included in blocks 5, 6, and 7.
+            let tx = TestTransactionBuilder::default()
+                .with_valid_until(7)
+                .build(true, false);
+
+            let vt = TestExecutive::validate_tuxedo_transaction(&tx).unwrap();
+
+            let expected_result = ValidTransactionBuilder::default().longevity(3).into();
+
+            assert_eq!(vt, expected_result);
+        });
+    }
+
+    #[test]
+    fn validate_after_expiry_fails() {
+        ExternalityBuilder::default().build().execute_with(|| {
+            note_time(1_000, 4);
+
+            let tx = TestTransactionBuilder::default()
+                .with_valid_until(4)
+                .build(true, false);
+
+            let vt = TestExecutive::validate_tuxedo_transaction(&tx);
+
+            assert_eq!(vt, Err(UtxoError::Expired));
+        });
+    }
+
+    #[test]
+    fn apply_at_expiry_works() {
+        ExternalityBuilder::default()
+            .with_pre_header(H256::zero(), 5)
+            .build()
+            .execute_with(|| {
+                let tx = TestTransactionBuilder::default()
+                    .with_valid_until(5)
+                    .build(true, false);
+
+                let vt = TestExecutive::apply_tuxedo_transaction(tx);
+
+                assert_eq!(vt, Ok(()));
+            });
+    }
+
+    #[test]
+    fn apply_after_expiry_fails() {
+        ExternalityBuilder::default()
+            .with_pre_header(H256::zero(), 5)
+            .build()
+            .execute_with(|| {
+                let tx = TestTransactionBuilder::default()
+                    .with_valid_until(4)
+                    .build(true, false);
+
+                let vt = TestExecutive::apply_tuxedo_transaction(tx);
+
+                assert_eq!(vt, Err(UtxoError::Expired));
+            });
+    }
+
+    #[test]
+    fn validate_with_pre_existing_output_fails() {
+        // This test requires a transaction to create an output at a location where
+        // an output already exists. This could happen in the wild when two transactions
+        // don't have inputs and have the same outputs. I initially couldn't think of how
+        // this could happen.
+
+        // First we create the transaction that will be submitted in the test.
+        let output = Output {
+            payload: Bogus.into(),
+            verifier: TestVerifier { verifies: false },
+        };
+        let tx = TestTransactionBuilder::default()
+            .with_output(output)
+            .build(true, false);
+
+        // Now calculate the output ref that the transaction creates so we can pre-populate the state.
+        let tx_hash = BlakeTwo256::hash_of(&tx.encode());
+        let output_ref = OutputRef { tx_hash, index: 0 };
+
+        ExternalityBuilder::default()
+            .with_utxo(output_ref, Bogus, false)
+            .build()
+            .execute_with(|| {
+                let result = TestExecutive::validate_tuxedo_transaction(&tx);
+                assert_eq!(result, Err(UtxoError::PreExistingOutput));
+            });
+    }
+
+    #[test]
+    fn validate_with_constraint_error_fails() {
+        ExternalityBuilder::default().build().execute_with(|| {
+            let tx = TestTransactionBuilder::default().build(false, false);
+
+            let vt = TestExecutive::validate_tuxedo_transaction(&tx);
+
+            assert_eq!(vt, Err(UtxoError::ConstraintCheckerError(())));
+        });
+    }
+
+    #[test]
+    fn dry_run_returns_priority() {
+        ExternalityBuilder::default().build().execute_with(|| {
+            let tx = TestTransactionBuilder::default().build(true, false);
+
+            assert_eq!(TestExecutive::dry_run(&tx), Ok(0));
+        });
+    }
+
+    #[test]
+    fn pool_policy_overrides_priority() {
+        ExternalityBuilder::default().build().execute_with(|| {
+            let inherent = TestTransactionBuilder::default().build(true, true);
+            let extrinsic = TestTransactionBuilder::default().build(true, false);
+
+            assert_eq!(
+                PolicyExecutive::dry_run(&inherent),
+                Ok(TransactionPriority::MAX)
+            );
+            assert_eq!(PolicyExecutive::dry_run(&extrinsic), Ok(0));
+        });
+    }
+
+    #[test]
+    fn pool_refuses_transactions_below_minimum_priority() {
+        ExternalityBuilder::default().build().execute_with(|| {
+            let tx = TestTransactionBuilder::default().build(true, false);
+
+            assert_eq!(
+                TipsRequiredExecutive::validate_transaction(
+                    TransactionSource::External,
+                    tx,
+                    H256::zero(),
+                ),
+                Err(TransactionValidityError::Invalid(
+                    InvalidTransaction::Payment
+                ))
+            );
+        });
+    }
+
+    #[test]
+    fn minimum_priority_waits_for_missing_inputs() {
+        ExternalityBuilder::default().build().execute_with(|| {
+            let input = Input {
+                output_ref: mock_output_ref(0, 0),
+                redeemer: Vec::new(),
+                mode: InputMode::Consume,
+            };
+            let tx = TestTransactionBuilder::default()
+                .with_input(input)
+                .build(true, false);
+
+            let valid = TipsRequiredExecutive::validate_transaction(
+                TransactionSource::External,
+                tx,
+                H256::zero(),
+            )
+            .expect("a transaction that waits for inputs is not held to the minimum yet");
+            assert!(!valid.requires.is_empty());
+        });
+    }
+
+    #[test]
+    fn dry_run_with_missing_input_fails() {
+        ExternalityBuilder::default().build().execute_with(|| {
+            let input = Input {
+                output_ref: mock_output_ref(0, 0),
+                redeemer: Vec::new(),
+                mode: InputMode::Consume,
+            };
+            let tx = TestTransactionBuilder::default()
+                .with_input(input)
+                .build(true, false);
+
+            assert_eq!(TestExecutive::dry_run(&tx), Err(UtxoError::MissingInput));
+        });
+    }
+
+    #[test]
+    fn apply_empty_works() {
+        ExternalityBuilder::default().build().execute_with(|| {
+            let tx = TestTransactionBuilder::default().build(true, false);
+
+            let vt = TestExecutive::apply_tuxedo_transaction(tx);
+
+            assert_eq!(vt, Ok(()));
+        });
+    }
+
+    #[test]
+    fn apply_with_missing_input_fails() {
+        ExternalityBuilder::default().build().execute_with(|| {
+            let output_ref = mock_output_ref(0, 0);
+            let input = Input {
+                output_ref: output_ref.clone(),
+                redeemer: Vec::new(),
+                mode: InputMode::Consume,
+            };
+
+            let tx = TestTransactionBuilder::default()
+                .with_input(input)
+                .build(true, false);
+
+            let vt = TestExecutive::apply_tuxedo_transaction(tx);
+
+            assert_eq!(vt, Err(UtxoError::MissingInput));
+        });
+    }
+
+    #[test]
+    fn apply_with_missing_peek_fails() {
+        ExternalityBuilder::default().build().execute_with(|| {
+            let output_ref = mock_output_ref(0, 0);
+
+            let tx = TestTransactionBuilder::default()
+                .with_peek(output_ref)
+                .build(true, false);
+
+            let vt = TestExecutive::apply_tuxedo_transaction(tx);
+
+            assert_eq!(vt, Err(UtxoError::MissingInput));
+        });
+    }
+
+    #[test]
+    fn validate_without_fee_fails() {
+        ExternalityBuilder::default().build().execute_with(|| {
+            let tx = TestTransactionBuilder::default().build(true, false);
+
+            let vt = TightExecutive::validate_tuxedo_transaction(&tx);
+
+            assert_eq!(vt, Err(UtxoError::InsufficientFee));
+        });
+    }
+
+    #[test]
+    fn validate_inherent_without_fee_works() {
+        ExternalityBuilder::default().build().execute_with(|| {
+            let tx = TestTransactionBuilder::default().build(true, true);
+
+            let vt = TightExecutive::validate_tuxedo_transaction(&tx);
+
+            assert!(vt.is_ok());
+        });
+    }
+
+    #[test]
+    fn validate_heavier_than_block_fails() {
+        ExternalityBuilder::default().build().execute_with(|| {
+            let output = Output {
+                payload: Bogus.into(),
+                verifier: TestVerifier { verifies: false },
+            };
+            let tx = TestTransactionBuilder::default()
+                .with_output(output)
+                .build(true, true);
+
+            let vt = TightExecutive::validate_tuxedo_transaction(&tx);
+
+            assert_eq!(vt, Err(UtxoError::ExhaustsBlockWeight));
+        });
+    }
+
+    fn bogus_output() -> Output<TestVerifier> {
+        Output {
+            payload: Bogus.into(),
+            verifier: TestVerifier { verifies: true },
+        }
+    }
+
+    #[test]
+    fn validate_without_deposit_fails() {
+        ExternalityBuilder::default().build().execute_with(|| {
+            let tx = TestTransactionBuilder::default()
+                .with_output(bogus_output())
+                .build(true, false);
+
+            let vt = DepositExecutive::validate_tuxedo_transaction(&tx);
+
+            assert_eq!(vt, Err(UtxoError::InsufficientFee));
+        });
+    }
+
+    #[test]
+    fn refunded_deposit_pays_for_new_output() {
+        let input_ref = mock_output_ref(0, 0);
+        ExternalityBuilder::default()
+            .with_utxo(input_ref.clone(), Bogus, true)
+            .build()
+            .execute_with(|| {
+                let deposit = weights::deposit::<DepositConfig>(bogus_output().encoded_size());
+                deposits::lock(&input_ref, deposit);
+                let tx = TestTransactionBuilder::default()
+                    .with_input(Input {
+                        output_ref: input_ref.clone(),
+                        redeemer: Vec::new(),
+                        mode: InputMode::Consume,
+                    })
+                    .with_output(bogus_output())
+                    .build(true, false);
+                let output_ref = OutputRef {
+                    tx_hash: BlakeTwo256::hash_of(&tx.encode()),
+                    index: 0,
+                };
+
+                assert_eq!(DepositExecutive::apply_tuxedo_transaction(tx), Ok(()));
+
+                assert_eq!(deposits::locked(&input_ref), 0);
+                assert_eq!(deposits::locked(&output_ref), deposit);
+            });
+    }
+
+    #[test]
+    fn leftover_refund_is_a_tip() {
+        let input_ref = mock_output_ref(0, 0);
+        ExternalityBuilder::default()
+            .with_utxo(input_ref.clone(), Bogus, true)
+            .build()
+            .execute_with(|| {
+                deposits::lock(&input_ref, 5);
+                let tx = TestTransactionBuilder::default()
+                    .with_input(Input {
+                        output_ref: input_ref,
+                        redeemer: Vec::new(),
+                        mode: InputMode::Consume,
+                    })
+                    .build(true, false);
+
+                assert_eq!(DepositExecutive::apply_tuxedo_transaction(tx), Ok(()));
+
+                assert_eq!(fees::pending_tips(), 5);
+            });
+    }
+
+    #[test]
+    fn inherents_lock_no_deposits() {
+        ExternalityBuilder::default().build().execute_with(|| {
+            let tx = TestTransactionBuilder::default()
+                .with_output(bogus_output())
+                .build(true, true);
+            let output_ref = OutputRef {
+                tx_hash: BlakeTwo256::hash_of(&tx.encode()),
+                index: 0,
+            };
+
+            assert_eq!(DepositExecutive::apply_tuxedo_transaction(tx), Ok(()));
+
+            assert_eq!(deposits::locked(&output_ref), 0);
+        });
+    }
+
+    #[test]
+    fn simple_checker_sees_verifiers_and_redeemers() {
+        let sender = TestVerifier { verifies: true };
+        let stranger = TestVerifier { verifies: false };
+        ExternalityBuilder::default()
+            .with_utxo(mock_output_ref(0, 0), Bogus, true)
+            .build()
+            .execute_with(|| {
+                let transaction = |change_owner: &TestVerifier, redeemer: Vec<u8>| Transaction {
+                    version: TransactionVersion::LATEST,
+                    inputs: vec![Input {
+                        output_ref: mock_output_ref(0, 0),
+                        redeemer,
+                        mode: InputMode::Consume,
+                    }],
+                    peeks: Vec::new(),
+                    outputs: vec![Output {
+                        payload: Bogus.into(),
+                        verifier: change_owner.clone(),
+                    }],
+                    checker: ChangeToSender(vec![7]),
+                    memo: None,
+                    valid_until: None,
+                };
+
+                assert!(ChangeExecutive::validate_tuxedo_transaction(&transaction(
+                    &sender,
+                    vec![7]
+                ))
+                .is_ok());
+                assert_eq!(
+                    ChangeExecutive::validate_tuxedo_transaction(&transaction(&stranger, vec![7])),
+                    Err(UtxoError::ConstraintCheckerError(()))
+                );
+                assert_eq!(
+                    ChangeExecutive::validate_tuxedo_transaction(&transaction(&sender, vec![8])),
+                    Err(UtxoError::ConstraintCheckerError(()))
+                );
+            });
+    }
+
+    /// A transaction that reclaims the given output with the [`ReclaimOld`] checker.
+    fn reclaim(output_ref: OutputRef, minimum_age: u32) -> Transaction<TestVerifier, ReclaimOld> {
+        Transaction {
+            version: TransactionVersion::LATEST,
+            inputs: vec![Input {
+                output_ref,
+                redeemer: Vec::new(),
+                mode: InputMode::Reclaim,
+            }],
+            peeks: Vec::new(),
+            outputs: Vec::new(),
+            checker: ReclaimOld(minimum_age),
+            memo: None,
+            valid_until: None,
+        }
+    }
+
+    #[test]
+    fn reclaim_skips_verifier() {
+        let output_ref = mock_output_ref(0, 0);
+        ExternalityBuilder::default()
+            .with_utxo(output_ref.clone(), Bogus, false)
+            .with_pre_header(H256::zero(), 10)
+            .build()
+            .execute_with(|| {
+                ages::note(&output_ref, 4);
+
+                assert_eq!(
+                    ReclaimExecutive::apply_tuxedo_transaction(reclaim(output_ref.clone(), 6)),
+                    Ok(())
+                );
+                assert!(!TransparentUtxoSet::<TestVerifier>::utxo_exists(
+                    &output_ref
+                ));
+            });
+    }
+
+    #[test]
+    fn reclaim_too_young_fails() {
+        let output_ref = mock_output_ref(0, 0);
+        ExternalityBuilder::default()
+            .with_utxo(output_ref.clone(), Bogus, false)
+            .with_pre_header(H256::zero(), 10)
+            .build()
+            .execute_with(|| {
+                ages::note(&output_ref, 5);
+
+                assert_eq!(
+                    ReclaimExecutive::validate_tuxedo_transaction(&reclaim(output_ref, 6)),
+                    Err(UtxoError::ConstraintCheckerError(()))
+                );
+            });
+    }
+
+    #[test]
+    fn reclaim_of_unknown_age_fails() {
+        let output_ref = mock_output_ref(0, 0);
+        ExternalityBuilder::default()
+            .with_utxo(output_ref.clone(), Bogus, false)
+            .with_pre_header(H256::zero(), 10)
+            .build()
+            .execute_with(|| {
+                assert_eq!(
+                    ReclaimExecutive::validate_tuxedo_transaction(&reclaim(output_ref, 0)),
+                    Err(UtxoError::ConstraintCheckerError(()))
+                );
+            });
+    }
+
+    #[test]
+    fn apply_tracks_ages() {
+        let input_ref = mock_output_ref(0, 0);
+        ExternalityBuilder::default()
+            .with_utxo(input_ref.clone(), Bogus, true)
+            .with_pre_header(H256::zero(), 7)
+            .build()
+            .execute_with(|| {
+                ages::note(&input_ref, 2);
+                let tx = TestTransactionBuilder::default()
+                    .with_input(Input {
+                        output_ref: input_ref.clone(),
+                        redeemer: Vec::new(),
+                        mode: InputMode::Consume,
+                    })
+                    .with_output(bogus_output())
+                    .build(true, false);
+                let output_ref = OutputRef {
+                    tx_hash: BlakeTwo256::hash_of(&tx.encode()),
+                    index: 0,
+                };
+
+                assert_eq!(AgesExecutive::apply_tuxedo_transaction(tx), Ok(()));
+
+                assert_eq!(ages::created_at(&input_ref), None);
+                assert_eq!(ages::created_at(&output_ref), Some(7));
+            });
+    }
+
+    #[test]
+    fn untracked_ages_are_not_noted() {
+        ExternalityBuilder::default()
+            .with_pre_header(H256::zero(), 7)
+            .build()
+            .execute_with(|| {
+                let tx = TestTransactionBuilder::default()
+                    .with_output(bogus_output())
+                    .build(true, false);
+                let output_ref = OutputRef {
+                    tx_hash: BlakeTwo256::hash_of(&tx.encode()),
+                    index: 0,
+                };
+
+                assert_eq!(TestExecutive::apply_tuxedo_transaction(tx), Ok(()));
+
+                assert_eq!(ages::created_at(&output_ref), None);
+            });
+    }
+
+    #[test]
+    fn validate_within_transaction_limits_works() {
+        ExternalityBuilder::default().build().execute_with(|| {
+            let tx = TestTransactionBuilder::default()
+                .with_peek(mock_output_ref(0, 0))
+                .with_output(bogus_output())
+                .build(true, false);
+
+            let vt = SmallExecutive::validate_tuxedo_transaction(&tx);
+
+            assert!(vt.is_ok());
+        });
+    }
+
+    #[test]
+    fn validate_with_too_many_inputs_fails() {
+        ExternalityBuilder::default().build().execute_with(|| {
+            let input = |index| Input {
+                output_ref: mock_output_ref(0, index),
+                redeemer: Vec::new(),
+                mode: InputMode::Consume,
+            };
+            let tx = TestTransactionBuilder::default()
+                .with_input(input(0))
+                .with_input(input(1))
+                .build(true, false);
+
+            let vt = SmallExecutive::validate_tuxedo_transaction(&tx);
+
+            assert_eq!(vt, Err(UtxoError::ExceedsTransactionLimits));
+        });
+    }
+
+    #[test]
+    fn validate_with_too_many_peeks_fails() {
+        ExternalityBuilder::default().build().execute_with(|| {
+            let tx = TestTransactionBuilder::default()
+                .with_peek(mock_output_ref(0, 0))
+                .with_peek(mock_output_ref(0, 1))
+                .build(true, false);
+
+            let vt = SmallExecutive::validate_tuxedo_transaction(&tx);
+
+            assert_eq!(vt, Err(UtxoError::ExceedsTransactionLimits));
+        });
+    }
+
+    #[test]
+    fn validate_with_too_many_outputs_fails() {
+        ExternalityBuilder::default().build().execute_with(|| {
+            let tx = TestTransactionBuilder::default()
+                .with_output(bogus_output())
+                .with_output(bogus_output())
+                .build(true, false);
+
+            let vt = SmallExecutive::validate_tuxedo_transaction(&tx);
+
+            assert_eq!(vt, Err(UtxoError::ExceedsTransactionLimits));
+        });
+    }
+
+    #[test]
+    fn validate_too_long_transaction_fails() {
+        ExternalityBuilder::default().build().execute_with(|| {
+            let input = Input {
+                output_ref: mock_output_ref(0, 0),
+                redeemer: vec![0; 100],
+                mode: InputMode::Consume,
+            };
+            let tx = TestTransactionBuilder::default()
+                .with_input(input)
+                .build(true, false);
+
+            let vt = SmallExecutive::validate_tuxedo_transaction(&tx);
+
+            assert_eq!(vt, Err(UtxoError::ExceedsTransactionLimits));
+        });
+    }
+
+    #[test]
+    fn validate_with_too_long_memo_fails() {
+        ExternalityBuilder::default().build().execute_with(|| {
+            let short = Transaction {
+                memo: Some(vec![0; 8]),
+                ..TestTransactionBuilder::default().build(true, false)
+            };
+            let long = Transaction {
+                memo: Some(vec![0; 9]),
+                ..short.clone()
+            };
+
+            assert!(SmallExecutive::validate_tuxedo_transaction(&short).is_ok());
+            assert_eq!(
+                SmallExecutive::validate_tuxedo_transaction(&long),
+                Err(UtxoError::ExceedsTransactionLimits)
+            );
+        });
+    }
+
+    #[test]
+    fn inherents_are_not_limited() {
+        ExternalityBuilder::default().build().execute_with(|| {
+            let tx = TestTransactionBuilder::default()
+                .with_output(bogus_output())
+                .with_output(bogus_output())
+                .build(true, true);
+
+            let vt = SmallExecutive::validate_tuxedo_transaction(&tx);
+
+            assert!(vt.is_ok());
+        });
+    }
+
+    #[test]
+    fn apply_beyond_transaction_limits_fails() {
+        ExternalityBuilder::default().build().execute_with(|| {
+            let tx = TestTransactionBuilder::default()
+                .with_output(bogus_output())
+                .with_output(bogus_output())
+                .build(true, false);
+
+            assert_eq!(
+                SmallExecutive::apply_tuxedo_transaction(tx),
+                Err(UtxoError::ExceedsTransactionLimits)
+            );
+        });
+    }
+
+    #[test]
+    fn apply_notes_block_weight() {
+        ExternalityBuilder::default().build().execute_with(|| {
+            let tx = TestTransactionBuilder::default().build(true, false);
+
+            TestExecutive::apply_tuxedo_transaction(tx).unwrap();
+
+            assert_eq!(weights::block_weight(), DEFAULT_CHECKER_WEIGHT);
+        });
+    }
+
+    #[test]
+    fn apply_beyond_block_weight_fails() {
+        ExternalityBuilder::default().build().execute_with(|| {
+            let tx = TestTransactionBuilder::default().build(true, true);
+
+            assert_eq!(TightExecutive::apply_tuxedo_transaction(tx.clone()), Ok(()));
+            assert_eq!(
+                TightExecutive::apply_tuxedo_transaction(tx),
+                Err(UtxoError::ExhaustsBlockWeight)
+            );
+        });
+    }
+
+    #[test]
+    fn chained_transaction_requires_what_parent_provides() {
+        ExternalityBuilder::default().build().execute_with(|| {
+            let parent = TestTransactionBuilder::default()
+                .with_output(bogus_output())
+                .build(true, false);
+            let parent_output = OutputRef {
+                tx_hash: BlakeTwo256::hash_of(&parent.encode()),
+                index: 0,
+            };
+            let child = TestTransactionBuilder::default()
+                .with_input(Input {
+                    output_ref: parent_output.clone(),
+                    redeemer: Vec::new(),
+                    mode: InputMode::Consume,
+                })
+                .build(true, false);
+
+            let parent_validity = TestExecutive::validate_tuxedo_transaction(&parent).unwrap();
+            let child_validity = TestExecutive::validate_tuxedo_transaction(&child).unwrap();
+
+            assert_eq!(parent_validity.provides, vec![parent_output.encode()]);
+            assert_eq!(child_validity.requires, parent_validity.provides);
+        });
+    }
+
+    #[test]
+    fn apply_spends_output_created_earlier_in_block() {
+        ExternalityBuilder::default().build().execute_with(|| {
+            let parent = TestTransactionBuilder::default()
+                .with_output(bogus_output())
+                .build(true, false);
+            let parent_output = OutputRef {
+                tx_hash: BlakeTwo256::hash_of(&parent.encode()),
+                index: 0,
+            };
+            let child = TestTransactionBuilder::default()
+                .with_input(Input {
+                    output_ref: parent_output.clone(),
+                    redeemer: Vec::new(),
+                    mode: InputMode::Consume,
+                })
+                .build(true, false);
+
+            assert_eq!(TestExecutive::apply_tuxedo_transaction(parent), Ok(()));
+            assert_eq!(TestExecutive::apply_tuxedo_transaction(child), Ok(()));
+
+            assert!(TransparentUtxoSet::<TestVerifier>::peek_utxo(&parent_output).is_none());
+        });
+    }
+
+    #[test]
+    fn apply_peeks_output_created_earlier_in_block() {
+        ExternalityBuilder::default().build().execute_with(|| {
+            let parent = TestTransactionBuilder::default()
+                .with_output(bogus_output())
+                .build(true, false);
+            let parent_output = OutputRef {
+                tx_hash: BlakeTwo256::hash_of(&parent.encode()),
+                index: 0,
+            };
+            let child = TestTransactionBuilder::default()
+                .with_peek(parent_output.clone())
+                .build(true, false);
+
+            assert_eq!(
+                TestExecutive::apply_tuxedo_transaction(child.clone()),
+                Err(UtxoError::MissingInput)
+            );
+            assert_eq!(TestExecutive::apply_tuxedo_transaction(parent), Ok(()));
+            assert_eq!(TestExecutive::apply_tuxedo_transaction(child), Ok(()));
+
+            assert!(TransparentUtxoSet::<TestVerifier>::peek_utxo(&parent_output).is_some());
+        });
+    }
+
+    #[test]
+    fn identical_mint_is_rejected_after_its_outputs_are_consumed() {
+        ExternalityBuilder::default().build().execute_with(|| {
+            let mint = TestTransactionBuilder::default()
+                .with_output(bogus_output())
+                .build(true, false);
+            let minted = OutputRef {
+                tx_hash: BlakeTwo256::hash_of(&mint.encode()),
+                index: 0,
+            };
+            let spend = TestTransactionBuilder::default()
+                .with_input(Input {
+                    output_ref: minted,
+                    redeemer: Vec::new(),
+                    mode: InputMode::Consume,
+                })
+                .build(true, false);
+
+            assert_eq!(
+                TestExecutive::apply_tuxedo_transaction(mint.clone()),
+                Ok(())
+            );
+            assert_eq!(TestExecutive::apply_tuxedo_transaction(spend), Ok(()));
+
+            assert_eq!(
+                TestExecutive::validate_tuxedo_transaction(&mint),
+                Err(UtxoError::PreExistingOutput)
+            );
+            assert_eq!(
+                TestExecutive::apply_tuxedo_transaction(mint),
+                Err(UtxoError::PreExistingOutput)
+            );
+        });
+    }
+
+    #[test]
+    fn inherent_mints_are_not_marked() {
+        ExternalityBuilder::default().build().execute_with(|| {
+            let mint = TestTransactionBuilder::default()
+                .with_output(bogus_output())
+                .build(true, true);
+            let tx_hash = BlakeTwo256::hash_of(&mint.encode());
+
+            assert_eq!(TestExecutive::apply_tuxedo_transaction(mint), Ok(()));
+
+            assert!(!sp_io::storage::exists(&TestExecutive::mint_key(tx_hash)));
+        });
+    }
+
+    #[test]
+    fn apply_with_missing_eviction_works() {
+        ExternalityBuilder::default().build().execute_with(|| {
+            let input = Input {
+                output_ref: mock_output_ref(0, 0),
+                redeemer: Vec::new(),
+                mode: InputMode::Evict,
+            };
+
+            let tx = TestTransactionBuilder::default()
+                .with_input(input)
+                .build(true, false);
 
-                assert_eq!(vt, expected_result);
-            });
+            assert_eq!(TestExecutive::apply_tuxedo_transaction(tx), Ok(()));
+        });
     }
 
     #[test]
-    fn validate_with_unsatisfied_verifier_fails() {
+    fn apply_with_present_eviction_consumes_it() {
         let output_ref = mock_output_ref(0, 0);
 
         ExternalityBuilder::default()
-            .with_utxo(output_ref.clone(), Bogus, false)
+            .with_utxo(output_ref.clone(), Bogus, true)
             .build()
             .execute_with(|| {
                 let input = Input {
-                    output_ref,
+                    output_ref: output_ref.clone(),
                     redeemer: Vec::new(),
+                    mode: InputMode::Evict,
                 };
 
                 let tx = TestTransactionBuilder::default()
                     .with_input(input)
                     .build(true, false);
 
-                let result = TestExecutive::validate_tuxedo_transaction(&tx);
-
-                assert_eq!(result, Err(UtxoError::VerifierError));
-            });
-    }
-
-    #[test]
-    fn validate_with_pre_existing_output_fails() {
-        // This test requires a transaction to create an output at a location where
-        // an output already exists. This could happen in the wild when two transactions
-        // don't have inputs and have the same outputs. I initially couldn't think of how
-        // this could happen.
-
-        // First we create the transaction that will be submitted in the test.
-        let output = Output {
-            payload: Bogus.into(),
-            verifier: TestVerifier { verifies: false },
-        };
-        let tx = TestTransactionBuilder::default()
-            .with_output(output)
-            .build(true, false);
-
-        // Now calculate the output ref that the transaction creates so we can pre-populate the state.
-        let tx_hash = BlakeTwo256::hash_of(&tx.encode());
-        let output_ref = OutputRef { tx_hash, index: 0 };
-
-        ExternalityBuilder::default()
-            .with_utxo(output_ref, Bogus, false)
-            .build()
-            .execute_with(|| {
-                let result = TestExecutive::validate_tuxedo_transaction(&tx);
-                assert_eq!(result, Err(UtxoError::PreExistingOutput));
+                assert_eq!(TestExecutive::apply_tuxedo_transaction(tx), Ok(()));
+                assert!(!TransparentUtxoSet::<TestVerifier>::utxo_exists(
+                    &output_ref
+                ));
             });
     }
 
     #[test]
-    fn validate_with_constraint_error_fails() {
+    fn offchain_worker_gets_block_context() {
         ExternalityBuilder::default().build().execute_with(|| {
-            let tx = TestTransactionBuilder::default().build(false, false);
+            let header = TestHeader {
+                parent_hash: H256::repeat_byte(1),
+                number: 6,
+                state_root: H256::zero(),
+                extrinsics_root: H256::zero(),
+                digest: Default::default(),
+            };
 
-            let vt = TestExecutive::validate_tuxedo_transaction(&tx);
+            OffchainExecutive::offchain_worker(&header);
 
-            assert_eq!(vt, Err(UtxoError::ConstraintCheckerError(())));
+            assert_eq!(
+                sp_io::storage::get(b"offchain").map(|d| d.to_vec()),
+                Some((6u32, header.hash()).encode())
+            );
         });
     }
 
     #[test]
-    fn apply_empty_works() {
+    fn custom_utxo_set_is_used() {
         ExternalityBuilder::default().build().execute_with(|| {
-            let tx = TestTransactionBuilder::default().build(true, false);
+            let output = Output {
+                payload: Bogus.into(),
+                verifier: TestVerifier { verifies: true },
+            };
+            let tx = TestTransactionBuilder::default()
+                .with_output(output.clone())
+                .build(true, false);
+            let output_ref = OutputRef {
+                tx_hash: BlakeTwo256::hash_of(&tx.encode()),
+                index: 0,
+            };
 
-            let vt = TestExecutive::apply_tuxedo_transaction(tx);
+            assert_eq!(PrefixedExecutive::apply_tuxedo_transaction(tx), Ok(()));
 
-            assert_eq!(vt, Ok(()));
+            // The output is only where the custom set put it
+            assert_eq!(PrefixedUtxoSet::peek_utxo(&output_ref), Some(output));
+            assert!(!TransparentUtxoSet::<TestVerifier>::utxo_exists(
+                &output_ref
+            ));
+
+            // So it can be spent through the custom set, but not through the default one
+            let spend = TestTransactionBuilder::default()
+                .with_input(Input {
+                    output_ref: output_ref.clone(),
+                    redeemer: Vec::new(),
+                    mode: InputMode::Consume,
+                })
+                .build(true, false);
+            assert_eq!(
+                TestExecutive::apply_tuxedo_transaction(spend.clone()),
+                Err(UtxoError::MissingInput)
+            );
+            assert_eq!(PrefixedExecutive::apply_tuxedo_transaction(spend), Ok(()));
+            assert_eq!(PrefixedUtxoSet::peek_utxo(&output_ref), None);
         });
     }
 
+    #[cfg(feature = "parallel-verification")]
     #[test]
-    fn apply_with_missing_input_fails() {
-        ExternalityBuilder::default().build().execute_with(|| {
-            let output_ref = mock_output_ref(0, 0);
-            let input = Input {
-                output_ref: output_ref.clone(),
-                redeemer: Vec::new(),
-            };
+    fn verify_in_parallel_reports_passing_inputs() {
+        let passing = mock_output_ref(0, 0);
+        let failing = mock_output_ref(0, 1);
 
-            let tx = TestTransactionBuilder::default()
-                .with_input(input)
-                .build(true, false);
+        ExternalityBuilder::default()
+            .with_utxo(passing.clone(), Bogus, true)
+            .with_utxo(failing.clone(), Bogus, false)
+            .build()
+            .execute_with(|| {
+                let input = |output_ref| Input {
+                    output_ref,
+                    redeemer: Vec::new(),
+                    mode: InputMode::Consume,
+                };
+                let tx = TestTransactionBuilder::default()
+                    .with_input(input(passing))
+                    .with_input(input(failing))
+                    .with_input(input(mock_output_ref(1, 0)))
+                    .build(true, false);
+                let tx_hash = BlakeTwo256::hash_of(&tx.encode());
 
-            let vt = TestExecutive::apply_tuxedo_transaction(tx);
+                let preverified = TestExecutive::verify_in_parallel(&[tx]);
 
-            assert_eq!(vt, Err(UtxoError::MissingInput));
-        });
+                assert_eq!(preverified, BTreeSet::from([(tx_hash, 0)]));
+            });
     }
 
+    #[cfg(feature = "parallel-verification")]
     #[test]
-    fn apply_with_missing_peek_fails() {
-        ExternalityBuilder::default().build().execute_with(|| {
-            let output_ref = mock_output_ref(0, 0);
+    fn preverified_inputs_are_not_verified_again() {
+        let output_ref = mock_output_ref(0, 0);
 
-            let tx = TestTransactionBuilder::default()
-                .with_peek(output_ref)
-                .build(true, false);
+        ExternalityBuilder::default()
+            .with_utxo(output_ref.clone(), Bogus, false)
+            .build()
+            .execute_with(|| {
+                let tx = TestTransactionBuilder::default()
+                    .with_input(Input {
+                        output_ref,
+                        redeemer: Vec::new(),
+                        mode: InputMode::Consume,
+                    })
+                    .build(true, false);
+                let tx_hash = BlakeTwo256::hash_of(&tx.encode());
 
-            let vt = TestExecutive::apply_tuxedo_transaction(tx);
+                let result = preverified::using(&mut BTreeSet::from([(tx_hash, 0)]), || {
+                    TestExecutive::validate_tuxedo_transaction(&tx)
+                });
+                assert!(result.is_ok());
 
-            assert_eq!(vt, Err(UtxoError::MissingInput));
-        });
+                // Outside of block execution, the verifier is checked as usual
+                assert_eq!(
+                    TestExecutive::validate_tuxedo_transaction(&tx),
+                    Err(UtxoError::VerifierError)
+                );
+            });
     }
 
     #[test]
@@ -921,6 +2845,7 @@ mod tests {
                 let input = Input {
                     output_ref: output_ref.clone(),
                     redeemer: Vec::new(),
+                    mode: InputMode::Consume,
                 };
 
                 let tx = TestTransactionBuilder::default()
@@ -931,7 +2856,9 @@ mod tests {
                 TestExecutive::update_storage(tx);
 
                 // Check whether the Input is still in storage
-                assert!(!sp_io::storage::exists(&output_ref.encode()));
+                assert!(!TransparentUtxoSet::<TestVerifier>::utxo_exists(
+                    &output_ref
+                ));
             });
     }
 
@@ -954,7 +2881,8 @@ mod tests {
             TestExecutive::update_storage(tx);
 
             // Check whether the Output has been written to storage and the proper value is stored
-            let stored_bytes = sp_io::storage::get(&output_ref.encode()).unwrap();
+            let stored_bytes =
+                sp_io::default_child_storage::get(UTXO_CHILD_TRIE, &output_ref.encode()).unwrap();
             let stored_value = Output::decode(&mut &stored_bytes[..]).unwrap();
             assert_eq!(stored_value, output);
         });
@@ -984,6 +2912,114 @@ mod tests {
         });
     }
 
+    #[test]
+    fn open_block_notes_genesis_hash_in_first_block() {
+        let header = TestHeader {
+            parent_hash: H256::repeat_byte(5),
+            number: 1,
+            state_root: H256::repeat_byte(6),
+            extrinsics_root: H256::repeat_byte(7),
+            digest: Default::default(),
+        };
+
+        ExternalityBuilder::default().build().execute_with(|| {
+            sp_io::storage::set(AWAITING_GENESIS_HASH_KEY, &[]);
+            assert_eq!(TestExecutive::genesis_hash(), H256::zero());
+
+            TestExecutive::open_block(&header);
+
+            assert_eq!(TestExecutive::genesis_hash(), H256::repeat_byte(5));
+            assert!(!sp_io::storage::exists(AWAITING_GENESIS_HASH_KEY));
+        });
+    }
+
+    #[test]
+    fn open_block_leaves_genesis_hash_in_later_blocks() {
+        let header = TestHeader {
+            parent_hash: H256::repeat_byte(5),
+            number: 5,
+            state_root: H256::repeat_byte(6),
+            extrinsics_root: H256::repeat_byte(7),
+            digest: Default::default(),
+        };
+
+        ExternalityBuilder::default().build().execute_with(|| {
+            sp_io::storage::set(GENESIS_HASH_KEY, &H256::repeat_byte(1).encode());
+
+            TestExecutive::open_block(&header);
+
+            assert_eq!(TestExecutive::genesis_hash(), H256::repeat_byte(1));
+        });
+    }
+
+    #[test]
+    fn signing_domain_includes_genesis_hash_and_versions() {
+        ExternalityBuilder::default().build().execute_with(|| {
+            sp_io::storage::set(GENESIS_HASH_KEY, &H256::repeat_byte(1).encode());
+
+            let expected = SigningDomain {
+                genesis_hash: H256::repeat_byte(1),
+                spec_version: 7,
+                transaction_version: 3,
+            };
+            assert_eq!(VersionedExecutive::signing_domain(), expected);
+        });
+    }
+
+    #[test]
+    fn validate_transaction_at_genesis_uses_the_given_block_as_genesis() {
+        ExternalityBuilder::default().build().execute_with(|| {
+            sp_io::storage::set(AWAITING_GENESIS_HASH_KEY, &[]);
+            let tx = TestTransactionBuilder::default().build(true, false);
+
+            let _ = TestExecutive::validate_transaction(
+                TransactionSource::External,
+                tx,
+                H256::repeat_byte(9),
+            );
+
+            assert_eq!(TestExecutive::genesis_hash(), H256::repeat_byte(9));
+        });
+    }
+
+    #[test]
+    fn validate_transaction_after_genesis_never_uses_the_given_block_as_genesis() {
+        ExternalityBuilder::default().build().execute_with(|| {
+            let tx = TestTransactionBuilder::default().build(true, false);
+
+            let _ = TestExecutive::validate_transaction(
+                TransactionSource::External,
+                tx,
+                H256::repeat_byte(9),
+            );
+
+            assert_eq!(TestExecutive::genesis_hash(), H256::zero());
+        });
+    }
+
+    #[test]
+    fn open_block_forgets_previous_events() {
+        let header = TestHeader {
+            parent_hash: H256::repeat_byte(5),
+            number: 5,
+            state_root: H256::repeat_byte(6),
+            extrinsics_root: H256::repeat_byte(7),
+            digest: Default::default(),
+        };
+
+        ExternalityBuilder::default().build().execute_with(|| {
+            let event = events::Event {
+                event_id: *b"test",
+                data: Vec::new(),
+            };
+            events::note(H256::repeat_byte(1), vec![event]);
+
+            TestExecutive::open_block(&header);
+
+            assert!(events::block_events().is_empty());
+        });
+    }
+
     #[test]
     fn apply_valid_extrinsic_work() {
         ExternalityBuilder::default().build().execute_with(|| {
@@ -1023,6 +3059,21 @@ mod tests {
         });
     }
 
+    #[test]
+    fn apply_extrinsic_beyond_block_weight_exhausts_resources() {
+        ExternalityBuilder::default().build().execute_with(|| {
+            let tx = TestTransactionBuilder::default().build(true, true);
+
+            assert_eq!(TightExecutive::apply_extrinsic(tx.clone()), Ok(Ok(())));
+            assert_eq!(
+                TightExecutive::apply_extrinsic(tx),
+                Err(TransactionValidityError::Invalid(
+                    InvalidTransaction::ExhaustsResources
+                ))
+            );
+        });
+    }
+
     #[test]
     fn close_block_works() {
         let parent_hash = H256::repeat_byte(5);
@@ -1057,6 +3108,22 @@ mod tests {
             });
     }
 
+    #[test]
+    fn close_block_appends_deposited_logs() {
+        let log = sp_runtime::DigestItem::Consensus(*b"test", vec![1, 2, 3]);
+        ExternalityBuilder::default()
+            .with_pre_header(H256::repeat_byte(5), 6)
+            .build()
+            .execute_with(|| {
+                digest::deposit_log(log.clone());
+
+                let returned_header = TestExecutive::close_block();
+
+                assert_eq!(returned_header.digest.logs(), &[log]);
+                assert!(digest::deposited_logs().is_empty());
+            });
+    }
+
     #[test]
     fn execute_empty_block_works() {
         ExternalityBuilder::default().build().execute_with(|| {
@@ -1079,6 +3146,56 @@ mod tests {
         });
     }
 
+    #[test]
+    fn execute_first_block_notes_genesis_hash_like_its_author() {
+        let pre_header = TestHeader {
+            parent_hash: H256::repeat_byte(5),
+            number: 1,
+            state_root: H256::zero(),
+            extrinsics_root: H256::zero(),
+            digest: Default::default(),
+        };
+
+        let header = TestExternalities::default().execute_with(|| {
+            TestExecutive::open_block(&pre_header);
+            TestExecutive::close_block()
+        });
+
+        TestExternalities::default().execute_with(|| {
+            TestExecutive::execute_block(TestBlock {
+                header,
+                extrinsics: Vec::new(),
+            });
+
+            assert_eq!(TestExecutive::genesis_hash(), H256::repeat_byte(5));
+        });
+    }
+
+    #[test]
+    #[should_panic(expected = "digest logs mismatch")]
+    fn execute_block_with_undeposited_log_fails() {
+        ExternalityBuilder::default().build().execute_with(|| {
+            let b = TestBlock {
+                header: TestHeader {
+                    parent_hash: H256::zero(),
+                    number: 6,
+                    state_root: array_bytes::hex_n_into_unchecked(
+                        "858174d563f845dbb4959ea64816bd8409e48cc7e65db8aa455bc98d61d24071",
+                    ),
+                    extrinsics_root: array_bytes::hex_n_into_unchecked(
+                        "03170a2e7597b7b7e3d84c05391d139a62b157e78786d8c082f29dcf4c111314",
+                    ),
+                    digest: sp_runtime::Digest {
+                        logs: vec![sp_runtime::DigestItem::Consensus(*b"test", vec![1])],
+                    },
+                },
+                extrinsics: Vec::new(),
+            };
+
+            TestExecutive::execute_block(b);
+        });
+    }
+
     #[test]
     fn execute_block_with_transaction_works() {
         ExternalityBuilder::default().build().execute_with(|| {
@@ -1145,6 +3262,73 @@ mod tests {
         });
     }
 
+    /// An empty block at height six whose state root is wrong.
+    #[cfg(feature = "try-runtime")]
+    fn block_with_wrong_state_root() -> TestBlock {
+        TestBlock {
+            header: TestHeader {
+                parent_hash: H256::zero(),
+                number: 6,
+                state_root: H256::zero(),
+                extrinsics_root: array_bytes::hex_n_into_unchecked(
+                    "03170a2e7597b7b7e3d84c05391d139a62b157e78786d8c082f29dcf4c111314",
+                ),
+                digest: Default::default(),
+            },
+            extrinsics: Vec::new(),
+        }
+    }
+
+    #[cfg(feature = "try-runtime")]
+    #[test]
+    fn try_execute_block_can_skip_state_root_check() {
+        ExternalityBuilder::default().build().execute_with(|| {
+            let weight = TestExecutive::try_execute_block(
+                block_with_wrong_state_root(),
+                false,
+                true,
+                TryStateSelect::All,
+            );
+
+            assert_eq!(weight, TryRuntimeWeight::default());
+        });
+    }
+
+    /// A state check that always fails.
+    #[cfg(feature = "try-runtime")]
+    pub struct FailingCheck;
+
+    #[cfg(feature = "try-runtime")]
+    impl crate::try_runtime::TryState for FailingCheck {
+        const NAME: &'static str = "failing";
+
+        fn try_state() -> Result<(), &'static str> {
+            Err("always fails")
+        }
+    }
+
+    #[cfg(feature = "try-runtime")]
+    #[test]
+    #[should_panic(expected = "State checks failed after executing the block: always fails")]
+    fn try_execute_block_runs_state_checks() {
+        ExternalityBuilder::default().build().execute_with(|| {
+            Executive::<
+                TestBlock,
+                TestVerifier,
+                TestConstraintChecker,
+                (),
+                TransparentUtxoSet<TestVerifier>,
+                (),
+                (FailingCheck,),
+            >::try_execute_block(
+                block_with_wrong_state_root(),
+                false,
+                true,
+                TryStateSelect::All,
+            );
+        });
+    }
+
     #[test]
     #[should_panic(expected = "extrinsics root mismatch")]
     fn execute_block_extrinsic_root_mismatch() {
@@ -0,0 +1,181 @@
+//! Tracking of the fees and tips paid by transactions so that they can be redistributed.
+//!
+//! By convention, a Tuxedo constraint checker reports the value that a transaction burns
+//! (its inputs minus its outputs) from `check`. Of that surplus, the executive takes the fee that
+//! the transaction must pay (see the [`weights`](crate::weights) module), and the rest is a tip.
+//! Checkers that destroy value on purpose, such as an explicit burn, report no surplus for it,
+//! because whatever they report is paid back out.
+//! The executive adds the fee and the tip of every non-inherent transaction it applies to separate
+//! running totals of pending fees and pending tips in storage.
+//!
+//! When the next block opens, the executive rolls the pending totals over so that they become
+//! collectible for the duration of that block. Pieces that redistribute fees, such as a treasury
+//! or a block author reward, each collect their own share of the collectible fees through an
+//! inherent. Tips are meant for the block author, although a runtime may share them with a
+//! treasury too. Fees and tips that nobody collects remain burned when the following block rolls
+//! over.
+
+use parity_scale_codec::{Decode, Encode};
+
+/// The storage key that holds the total fees paid so far in the current block.
+pub const PENDING_FEES_KEY: &[u8] = b"pending_fees";
+
+/// The storage key that holds the total fees paid in the previous block.
+pub const COLLECTIBLE_FEES_KEY: &[u8] = b"collectible_fees";
+
+/// The storage key that holds the total tips paid so far in the current block.
+pub const PENDING_TIPS_KEY: &[u8] = b"pending_tips";
+
+/// The storage key that holds the total tips paid in the previous block.
+pub const COLLECTIBLE_TIPS_KEY: &[u8] = b"collectible_tips";
+
+fn get(key: &[u8]) -> u128 {
+    sp_io::storage::get(key)
+        .and_then(|d| u128::decode(&mut &*d).ok())
+        .unwrap_or_default()
+}
+
+/// The total fees that have been paid so far in the current block.
+///
+/// When called from off-chain checks against the parent state, these
+/// are the fees that will become collectible in the block being checked.
+pub fn pending() -> u128 {
+    get(PENDING_FEES_KEY)
+}
+
+fn add(key: &[u8], value: u128) {
+    // Don't touch storage when nothing was paid.
+    if value == 0 {
+        return;
+    }
+    let total = get(key).saturating_add(value);
+    sp_io::storage::set(key, &total.encode());
+}
+
+/// Add the fees paid by a transaction to the pending total.
+pub fn note(fee: u128) {
+    add(PENDING_FEES_KEY, fee)
+}
+
+/// The total tips that have been paid so far in the current block.
+pub fn pending_tips() -> u128 {
+    get(PENDING_TIPS_KEY)
+}
+
+/// Add the tip paid by a transaction to the pending total.
+pub fn note_tip(tip: u128) {
+    add(PENDING_TIPS_KEY, tip)
+}
+
+/// The total fees that were paid in the previous block and may be collected in this one.
+pub fn collectible() -> u128 {
+    get(COLLECTIBLE_FEES_KEY)
+}
+
+/// The total tips that were paid in the previous block and may be collected in this one.
+pub fn collectible_tips() -> u128 {
+    get(COLLECTIBLE_TIPS_KEY)
+}
+
+fn roll(pending_key: &[u8], collectible_key: &[u8]) {
+    let total = get(pending_key);
+    sp_io::storage::clear(pending_key);
+    if total == 0 {
+        sp_io::storage::clear(collectible_key);
+    } else {
+        sp_io::storage::set(collectible_key, &total.encode());
+    }
+}
+
+/// Make the pending fees and tips collectible, and start new pending totals from zero.
+///
+/// Any fees or tips that were collectible but not collected are burned.
+/// This is called by the executive at the beginning of each block.
+pub fn roll_over() {
+    roll(PENDING_FEES_KEY, COLLECTIBLE_FEES_KEY);
+    roll(PENDING_TIPS_KEY, COLLECTIBLE_TIPS_KEY);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sp_io::TestExternalities;
+
+    #[test]
+    fn pending_defaults_to_zero() {
+        TestExternalities::default().execute_with(|| {
+            assert_eq!(pending(), 0);
+        });
+    }
+
+    #[test]
+    fn note_accumulates() {
+        TestExternalities::default().execute_with(|| {
+            note(3);
+            note(4);
+            assert_eq!(pending(), 7);
+        });
+    }
+
+    #[test]
+    fn note_keeps_amounts_beyond_priorities() {
+        TestExternalities::default().execute_with(|| {
+            let large = u128::from(u64::MAX) + 1;
+            note(large);
+            note_tip(large);
+            assert_eq!(pending(), large);
+            assert_eq!(pending_tips(), large);
+        });
+    }
+
+    #[test]
+    fn free_transactions_do_not_touch_storage() {
+        TestExternalities::default().execute_with(|| {
+            note(0);
+            assert!(!sp_io::storage::exists(PENDING_FEES_KEY));
+        });
+    }
+
+    #[test]
+    fn roll_over_makes_pending_collectible() {
+        TestExternalities::default().execute_with(|| {
+            note(5);
+            roll_over();
+            assert_eq!(pending(), 0);
+            assert_eq!(collectible(), 5);
+        });
+    }
+
+    #[test]
+    fn tips_are_tracked_apart_from_fees() {
+        TestExternalities::default().execute_with(|| {
+            note(5);
+            note_tip(2);
+            note_tip(0);
+            assert_eq!(pending(), 5);
+            assert_eq!(pending_tips(), 2);
+
+            roll_over();
+            assert_eq!(collectible(), 5);
+            assert_eq!(collectible_tips(), 2);
+
+            roll_over();
+            assert_eq!(collectible_tips(), 0);
+            assert!(!sp_io::storage::exists(COLLECTIBLE_TIPS_KEY));
+        });
+    }
+
+    #[test]
+    fn roll_over_burns_uncollected_fees() {
+        TestExternalities::default().execute_with(|| {
+            note(5);
+            roll_over();
+            note(3);
+            roll_over();
+            assert_eq!(collectible(), 3);
+            roll_over();
+            assert_eq!(collectible(), 0);
+            assert!(!sp_io::storage::exists(COLLECTIBLE_FEES_KEY));
+        });
+    }
+}
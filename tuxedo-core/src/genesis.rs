@@ -2,15 +2,20 @@
 
 use crate::{
     ensure,
-    types::{Output, OutputRef, Transaction},
-    ConstraintChecker, Verifier, EXTRINSIC_KEY, LOG_TARGET,
+    support_macros::DefaultNoBound,
+    types::{Output, OutputRef, Transaction, TransactionVersion},
+    utxo_set::{utxo_child_info, UTXO_CHILD_TRIE},
+    ConstraintChecker, GenesisOnly, Verifier, AWAITING_GENESIS_HASH_KEY, EXTRINSIC_KEY, LOG_TARGET,
 };
 use parity_scale_codec::{Decode, Encode};
 use sc_chain_spec::BuildGenesisBlock;
 use sc_client_api::backend::{Backend, BlockImportOperation};
 use sc_executor::RuntimeVersionOf;
 use serde::{Deserialize, Serialize};
-use sp_core::{storage::Storage, traits::CodeExecutor};
+use sp_core::{
+    storage::{Storage, StorageChild},
+    traits::CodeExecutor,
+};
 use sp_runtime::{
     traits::{BlakeTwo256, Block as BlockT, Hash as HashT, Header as HeaderT, Zero},
     BuildStorage,
@@ -67,10 +72,7 @@ impl<'a, Block: BlockT, B: Backend<Block>, E: RuntimeVersionOf + CodeExecutor>
         let state_version =
             sc_chain_spec::resolve_state_version_from_wasm(&genesis_storage, &self.executor)?;
 
-        let extrinsics = match genesis_storage.top.remove(crate::EXTRINSIC_KEY) {
-            Some(v) => <Vec<<Block as BlockT>::Extrinsic>>::decode(&mut &v[..]).unwrap_or_default(),
-            None => Vec::new(),
-        };
+        let extrinsics = take_genesis_extrinsics::<Block>(&mut genesis_storage);
 
         let extrinsics_root =
             <<<Block as BlockT>::Header as HeaderT>::Hashing as HashT>::ordered_trie_root(
@@ -103,6 +105,104 @@ impl<'a, Block: BlockT, B: Backend<Block>, E: RuntimeVersionOf + CodeExecutor>
     }
 }
 
+/// Take the genesis extrinsics out of the genesis storage, where [`TuxedoGenesisConfig`] put them.
+/// They belong in the genesis block, but they are not part of the genesis state.
+pub fn take_genesis_extrinsics<Block: BlockT>(storage: &mut Storage) -> Vec<Block::Extrinsic> {
+    match storage.top.remove(EXTRINSIC_KEY) {
+        Some(v) => <Vec<<Block as BlockT>::Extrinsic>>::decode(&mut &v[..]).unwrap_or_default(),
+        None => Vec::new(),
+    }
+}
+
+/// A piece's own section of the genesis configuration.
+///
+/// Pieces that need some initial state, such as coins or kitties, provide a serde-able type that
+/// implements this trait. Runtimes then collect the sections of all their pieces into a single
+/// struct with the [`tuxedo_genesis_config`](crate::tuxedo_genesis_config) macro, rather than
+/// listing raw genesis transactions by hand.
+pub trait GenesisSection<V, C> {
+    /// The transactions that create this section's initial state.
+    /// Just like any other genesis transactions, they must not contain any inputs or peeks.
+    fn genesis_transactions(&self) -> Vec<Transaction<V, C>>;
+
+    /// Raw storage entries, as keys and values, that this section's initial state needs besides
+    /// the outputs of its transactions. Genesis transactions are never executed, so this is how a
+    /// section sets up any storage that its piece would otherwise write while checking them.
+    ///
+    /// Default is none.
+    fn genesis_storage(&self) -> Vec<(Vec<u8>, Vec<u8>)> {
+        Vec::new()
+    }
+}
+
+/// The reference to the output at the given index of the given genesis transaction.
+///
+/// Genesis outputs are referenced just like any other outputs: by the hash of the encoded
+/// transaction that created them, and their index among that transaction's outputs.
+pub fn genesis_output_ref<V, C>(tx: &Transaction<V, C>, index: u32) -> OutputRef
+where
+    Transaction<V, C>: Encode,
+{
+    OutputRef {
+        tx_hash: BlakeTwo256::hash_of(&tx.encode()),
+        index,
+    }
+}
+
+/// Outputs of any piece, with any verifier, declared directly in the genesis configuration.
+///
+/// This is for initial state that no piece's own genesis section covers, such as issuer
+/// capabilities or proofs of existence. The outputs are stored exactly as declared, without any
+/// piece checking them, so make sure each payload is valid for its piece.
+///
+/// All the outputs are created by a single transaction whose checker is [`GenesisOnly`], in the order
+/// they are declared. So the output at position `i` is found at [`GenesisOutputs::output_ref`]`(i)`.
+#[derive(Serialize, Deserialize, DefaultNoBound)]
+pub struct GenesisOutputs<V> {
+    /// The declared outputs.
+    pub outputs: Vec<Output<V>>,
+}
+
+impl<V: Clone> GenesisOutputs<V> {
+    /// Create a genesis section with the given outputs.
+    pub fn new(outputs: Vec<Output<V>>) -> Self {
+        Self { outputs }
+    }
+
+    /// The transaction that creates the declared outputs, if any are declared.
+    pub fn transaction<C: From<GenesisOnly>>(&self) -> Option<Transaction<V, C>> {
+        (!self.outputs.is_empty()).then(|| Transaction {
+            version: TransactionVersion::LATEST,
+            inputs: Vec::new(),
+            peeks: Vec::new(),
+            outputs: self.outputs.clone(),
+            checker: GenesisOnly.into(),
+            memo: None,
+            valid_until: None,
+        })
+    }
+
+    /// The reference to the declared output at the given position, in a runtime whose outer
+    /// constraint checker is `C`.
+    pub fn output_ref<C: From<GenesisOnly>>(&self, index: u32) -> Option<OutputRef>
+    where
+        Transaction<V, C>: Encode,
+    {
+        let tx = self.transaction::<C>()?;
+        ((index as usize) < tx.outputs.len()).then(|| genesis_output_ref(&tx, index))
+    }
+}
+
+impl<V, C> GenesisSection<V, C> for GenesisOutputs<V>
+where
+    V: Clone,
+    C: From<GenesisOnly>,
+{
+    fn genesis_transactions(&self) -> Vec<Transaction<V, C>> {
+        self.transaction().into_iter().collect()
+    }
+}
+
 #[derive(Serialize, Deserialize)]
 /// The `TuxedoGenesisConfig` struct is used to configure the genesis state of the runtime.
 /// It expects the wasm binary and a list of transactions to be included in the genesis block, and stored along with their outputs.
@@ -111,6 +211,8 @@ impl<'a, Block: BlockT, B: Backend<Block>, E: RuntimeVersionOf + CodeExecutor>
 pub struct TuxedoGenesisConfig<V, C> {
     wasm_binary: Vec<u8>,
     genesis_transactions: Vec<Transaction<V, C>>,
+    #[serde(default)]
+    genesis_storage: Vec<(Vec<u8>, Vec<u8>)>,
 }
 
 impl<V, C> TuxedoGenesisConfig<V, C> {
@@ -120,9 +222,16 @@ impl<V, C> TuxedoGenesisConfig<V, C> {
         Self {
             wasm_binary,
             genesis_transactions,
+            genesis_storage: Vec::new(),
         }
     }
 
+    /// Add raw storage entries to the genesis state, such as those of the genesis sections.
+    pub fn with_storage(mut self, genesis_storage: Vec<(Vec<u8>, Vec<u8>)>) -> Self {
+        self.genesis_storage.extend(genesis_storage);
+        self
+    }
+
     pub fn get_transaction(&self, i: usize) -> Option<&Transaction<V, C>> {
         self.genesis_transactions.get(i)
     }
@@ -138,6 +247,11 @@ where
     /// Assimilate the storage into the genesis block.
     /// This is done by inserting the genesis extrinsics into the genesis block, along with their outputs.
     fn assimilate_storage(&self, storage: &mut Storage) -> Result<(), String> {
+        // The raw entries go first, so that they can not replace any of the special keys below.
+        for (key, value) in self.genesis_storage.iter() {
+            storage.top.insert(key.clone(), value.clone());
+        }
+
         // The wasm binary is stored under a special key.
         storage.top.insert(
             sp_storage::well_known_keys::CODE.into(),
@@ -149,6 +263,12 @@ where
             .top
             .insert(EXTRINSIC_KEY.to_vec(), self.genesis_transactions.encode());
 
+        // The genesis hash depends on the genesis state, so it can not be part of it. Instead we
+        // mark the state as the genesis state, until the first block notes the hash.
+        storage
+            .top
+            .insert(AWAITING_GENESIS_HASH_KEY.to_vec(), Vec::new());
+
         let mut finished_with_opening_inherents = false;
 
         for tx in self.genesis_transactions.iter() {
@@ -168,14 +288,21 @@ where
                 tx.inputs.is_empty() && tx.peeks.is_empty(),
                 "Genesis transactions must not have any inputs or peeks."
             );
-            // Insert the outputs into the storage.
+            // Insert the outputs into the utxo child trie.
+            let utxos = storage
+                .children_default
+                .entry(UTXO_CHILD_TRIE.to_vec())
+                .or_insert_with(|| StorageChild {
+                    data: Default::default(),
+                    child_info: utxo_child_info(),
+                });
             let tx_hash = BlakeTwo256::hash_of(&tx.encode());
             for (index, utxo) in tx.outputs.iter().enumerate() {
                 let output_ref = OutputRef {
                     tx_hash,
                     index: index as u32,
                 };
-                storage.top.insert(output_ref.encode(), utxo.encode());
+                utxos.data.insert(output_ref.encode(), utxo.encode());
             }
         }
 
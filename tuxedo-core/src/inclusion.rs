@@ -0,0 +1,168 @@
+//! Proofs that a transaction is in a block.
+//!
+//! Every header commits to the extrinsics of its block through the extrinsics root. That is the
+//! root of a trie which maps the compact encoded position of each extrinsic to its encoding. A
+//! [`TransactionProof`] holds the trie nodes on the path to one transaction, so anybody who trusts
+//! the header can check that the transaction is in the block without downloading the block.
+//!
+//! Wallets use these to prove to third parties that they made a payment, without sharing anything
+//! else about their history.
+
+use crate::types::Transaction;
+use parity_scale_codec::{Compact, Decode, Encode};
+use scale_info::TypeInfo;
+use serde::{Deserialize, Serialize};
+use sp_core::H256;
+use sp_runtime::traits::BlakeTwo256;
+use sp_std::vec::Vec;
+use sp_trie::LayoutV0;
+
+/// A proof that a transaction is among the extrinsics of some block.
+#[derive(Serialize, Deserialize, Encode, Decode, Debug, PartialEq, Eq, Clone, TypeInfo)]
+pub struct TransactionProof {
+    /// The hash of the block that the transaction is in.
+    pub block_hash: H256,
+    /// The position of the transaction among the extrinsics of the block.
+    pub index: u32,
+    /// The trie nodes on the path from the extrinsics root to the transaction, in compact form.
+    pub nodes: Vec<Vec<u8>>,
+}
+
+/// Reasons that a [`TransactionProof`] may not prove anything.
+#[derive(Serialize, Deserialize, Encode, Decode, Debug, PartialEq, Eq, Clone, TypeInfo)]
+pub enum TransactionProofError {
+    /// The nodes do not lead from the extrinsics root to the transaction at the proven position,
+    /// so the proof is either for another block, another transaction, or incomplete.
+    InvalidProof,
+}
+
+impl TransactionProof {
+    /// Prove that the extrinsic at the given index is among the encoded extrinsics of the block
+    /// with the given hash. Returns `None` if the block has no extrinsic at that index.
+    #[cfg(feature = "std")]
+    pub fn prove(block_hash: H256, extrinsics: &[Vec<u8>], index: u32) -> Option<Self> {
+        use sp_trie::TrieMut;
+
+        if index as usize >= extrinsics.len() {
+            return None;
+        }
+
+        // Rebuild the trie that the extrinsics root is the root of.
+        let mut db = sp_trie::MemoryDB::<BlakeTwo256>::default();
+        let mut root = H256::default();
+        {
+            let mut trie = sp_trie::trie_types::TrieDBMutBuilderV0::new(&mut db, &mut root).build();
+            for (position, extrinsic) in extrinsics.iter().enumerate() {
+                trie.insert(&Compact(position as u32).encode(), extrinsic)
+                    .ok()?;
+            }
+        }
+
+        let nodes = sp_trie::generate_trie_proof::<LayoutV0<BlakeTwo256>, _, _, _>(
+            &db,
+            root,
+            &[Compact(index).encode()],
+        )
+        .ok()?;
+
+        Some(Self {
+            block_hash,
+            index,
+            nodes,
+        })
+    }
+
+    /// Check that the given transaction is in the block, against the extrinsics root of the block
+    /// that this proof is for, which is usually read from a finalized header.
+    pub fn verify<V: Encode, C: Encode>(
+        &self,
+        transaction: &Transaction<V, C>,
+        extrinsics_root: &H256,
+    ) -> Result<(), TransactionProofError> {
+        let item = (Compact(self.index).encode(), Some(transaction.encode()));
+        sp_trie::verify_trie_proof::<LayoutV0<BlakeTwo256>, _, _, _>(
+            extrinsics_root,
+            &self.nodes,
+            &[item],
+        )
+        .map_err(|_| TransactionProofError::InvalidProof)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        constraint_checker::testing::TestConstraintChecker,
+        transaction_builder::TransactionBuilder, verifier::TestVerifier,
+    };
+    use sp_runtime::{traits::Hash, StateVersion};
+
+    type TestTransaction = Transaction<TestVerifier, TestConstraintChecker>;
+
+    /// A transaction that differs from the others by its memo.
+    fn transaction(memo: u8) -> TestTransaction {
+        TransactionBuilder::new(TestConstraintChecker {
+            checks: true,
+            inherent: false,
+        })
+        .memo(vec![memo])
+        .build()
+    }
+
+    /// The encodings of a block's worth of transactions, and their extrinsics root.
+    fn block() -> (Vec<Vec<u8>>, H256) {
+        let extrinsics: Vec<Vec<u8>> = (0..10).map(|memo| transaction(memo).encode()).collect();
+        let root = BlakeTwo256::ordered_trie_root(extrinsics.clone(), StateVersion::V0);
+        (extrinsics, root)
+    }
+
+    #[test]
+    fn proof_of_included_transaction_works() {
+        let (extrinsics, root) = block();
+        let proof = TransactionProof::prove(H256::zero(), &extrinsics, 3).unwrap();
+
+        assert_eq!(proof.verify(&transaction(3), &root), Ok(()));
+    }
+
+    #[test]
+    fn proof_against_other_root_fails() {
+        let (extrinsics, _) = block();
+        let proof = TransactionProof::prove(H256::zero(), &extrinsics, 3).unwrap();
+
+        assert_eq!(
+            proof.verify(&transaction(3), &H256::repeat_byte(2)),
+            Err(TransactionProofError::InvalidProof)
+        );
+    }
+
+    #[test]
+    fn proof_for_other_transaction_does_not_prove_it() {
+        let (extrinsics, root) = block();
+        let proof = TransactionProof::prove(H256::zero(), &extrinsics, 3).unwrap();
+
+        assert_eq!(
+            proof.verify(&transaction(4), &root),
+            Err(TransactionProofError::InvalidProof)
+        );
+    }
+
+    #[test]
+    fn proof_with_other_index_fails() {
+        let (extrinsics, root) = block();
+        let mut proof = TransactionProof::prove(H256::zero(), &extrinsics, 3).unwrap();
+        proof.index = 4;
+
+        assert_eq!(
+            proof.verify(&transaction(3), &root),
+            Err(TransactionProofError::InvalidProof)
+        );
+    }
+
+    #[test]
+    fn proving_missing_extrinsic_fails() {
+        let (extrinsics, _) = block();
+
+        assert_eq!(TransactionProof::prove(H256::zero(), &extrinsics, 10), None);
+    }
+}
@@ -29,6 +29,12 @@
 //! This module provides an inherent data provider that does just this. Any Tuxedo runtime that uses inherents (At least ones
 //! that update environmental data), needs to include this foundational previous block inherent data provider
 //! so that the Tuxedo executive can scrape it to find the output references of the previous inherent transactions.
+//!
+//! # Multiple inherents
+//!
+//! Each inherent-capable piece registers an [`InherentIdentifier`] through its inherent hooks. The executive orders
+//! the inherents of each authored block by these identifiers, so their positions are deterministic. When checking
+//! and scraping inherents, each piece finds its own inherents by identifier, so nothing depends on their positions.
 
 use parity_scale_codec::Encode;
 use scale_info::TypeInfo;
@@ -121,6 +127,14 @@ pub trait TuxedoInherent<V, C: ConstraintChecker<V>>: Sized {
     fn genesis_transactions() -> Vec<Transaction<V, C>> {
         Vec::new()
     }
+
+    fn identifiers() -> Vec<InherentIdentifier> {
+        Vec::new()
+    }
+
+    fn identify(_: &Transaction<V, C>) -> Option<InherentIdentifier> {
+        None
+    }
 }
 
 /// Almost identical to TuxedoInherent, but allows returning multiple extrinsics
@@ -152,6 +166,16 @@ pub trait InherentInternal<V, C: ConstraintChecker<V>>: Sized {
     /// Return the genesis transactions that are required for the inherents.
     #[cfg(feature = "std")]
     fn genesis_transactions() -> Vec<Transaction<V, C>>;
+
+    /// The identifiers of all the inherents that these hooks are responsible for.
+    fn identifiers() -> Vec<InherentIdentifier>;
+
+    /// The identifier of the inherent that the given inherent extrinsic belongs to,
+    /// or `None` if these hooks are not responsible for it.
+    ///
+    /// The executive orders the inherents in a block by this identifier, and
+    /// off-chain code uses it to find a particular inherent without relying on its position.
+    fn identify(inherent: &Transaction<V, C>) -> Option<InherentIdentifier>;
 }
 
 /// An adapter to transform structured Tuxedo inherents into the more general and powerful
@@ -186,7 +210,7 @@ impl<V: Verifier, C: ConstraintChecker<V>, T: TuxedoInherent<V, C> + 'static> In
         if inherents.is_empty() {
             results
                 .put_error(
-                    *b"12345678",
+                    T::INHERENT_IDENTIFIER,
                     &MakeFatalError::from(
                         "Tuxedo inherent expected exactly one inherent extrinsic but found zero",
                     ),
@@ -195,7 +219,7 @@ impl<V: Verifier, C: ConstraintChecker<V>, T: TuxedoInherent<V, C> + 'static> In
             return;
         } else if inherents.len() > 1 {
             results
-                .put_error(*b"12345678", &MakeFatalError::from("Tuxedo inherent expected exactly one inherent extrinsic but found multiple"))
+                .put_error(T::INHERENT_IDENTIFIER, &MakeFatalError::from("Tuxedo inherent expected exactly one inherent extrinsic but found multiple"))
                 .expect("Should be able to put an error.");
             return;
         }
@@ -207,6 +231,17 @@ impl<V: Verifier, C: ConstraintChecker<V>, T: TuxedoInherent<V, C> + 'static> In
     fn genesis_transactions() -> Vec<Transaction<V, C>> {
         <T as TuxedoInherent<V, C>>::genesis_transactions()
     }
+
+    fn identifiers() -> Vec<InherentIdentifier> {
+        vec![T::INHERENT_IDENTIFIER]
+    }
+
+    fn identify(inherent: &Transaction<V, C>) -> Option<InherentIdentifier> {
+        inherent
+            .checker
+            .is_inherent()
+            .then_some(T::INHERENT_IDENTIFIER)
+    }
 }
 
 impl<V, C: ConstraintChecker<V>> InherentInternal<V, C> for () {
@@ -234,4 +269,106 @@ impl<V, C: ConstraintChecker<V>> InherentInternal<V, C> for () {
     fn genesis_transactions() -> Vec<Transaction<V, C>> {
         Vec::new()
     }
+
+    fn identifiers() -> Vec<InherentIdentifier> {
+        Vec::new()
+    }
+
+    fn identify(_: &Transaction<V, C>) -> Option<InherentIdentifier> {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        constraint_checker::testing::TestConstraintChecker, types::TransactionVersion,
+        verifier::TestVerifier,
+    };
+
+    const TEST_IDENTIFIER: InherentIdentifier = *b"testinhr";
+
+    struct TestInherent;
+
+    impl TuxedoInherent<TestVerifier, TestConstraintChecker> for TestInherent {
+        type Error = MakeFatalError<()>;
+
+        const INHERENT_IDENTIFIER: InherentIdentifier = TEST_IDENTIFIER;
+
+        fn create_inherent(
+            _: &InherentData,
+            (previous_inherent, _): (Transaction<TestVerifier, TestConstraintChecker>, H256),
+        ) -> Transaction<TestVerifier, TestConstraintChecker> {
+            previous_inherent
+        }
+
+        fn check_inherent(
+            _: &InherentData,
+            _: Transaction<TestVerifier, TestConstraintChecker>,
+            _: &mut CheckInherentsResult,
+        ) {
+        }
+    }
+
+    type Hooks = TuxedoInherentAdapter<TestInherent>;
+
+    fn transaction(inherent: bool) -> Transaction<TestVerifier, TestConstraintChecker> {
+        Transaction {
+            version: TransactionVersion::LATEST,
+            inputs: Vec::new(),
+            peeks: Vec::new(),
+            outputs: Vec::new(),
+            checker: TestConstraintChecker {
+                checks: true,
+                inherent,
+            },
+            memo: None,
+            valid_until: None,
+        }
+    }
+
+    #[test]
+    fn adapter_registers_its_identifier() {
+        assert_eq!(
+            <Hooks as InherentInternal<_, TestConstraintChecker>>::identifiers(),
+            vec![TEST_IDENTIFIER]
+        );
+    }
+
+    #[test]
+    fn adapter_identifies_only_inherents() {
+        assert_eq!(Hooks::identify(&transaction(true)), Some(TEST_IDENTIFIER));
+        assert_eq!(Hooks::identify(&transaction(false)), None);
+    }
+
+    #[test]
+    fn adapter_reports_missing_inherent_under_its_identifier() {
+        let mut result = CheckInherentsResult::new();
+
+        <Hooks as InherentInternal<TestVerifier, TestConstraintChecker>>::check_inherents(
+            &InherentData::new(),
+            Vec::new(),
+            &mut result,
+        );
+
+        assert!(result.fatal_error());
+        assert_eq!(
+            result.into_errors().map(|(id, _)| id).collect::<Vec<_>>(),
+            vec![TEST_IDENTIFIER]
+        );
+    }
+
+    #[test]
+    fn unit_hooks_register_nothing() {
+        assert!(
+            <() as InherentInternal<TestVerifier, TestConstraintChecker>>::identifiers().is_empty()
+        );
+        assert_eq!(
+            <() as InherentInternal<TestVerifier, TestConstraintChecker>>::identify(&transaction(
+                true
+            )),
+            None
+        );
+    }
 }
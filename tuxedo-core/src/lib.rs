@@ -5,22 +5,54 @@
 
 #![cfg_attr(not(feature = "std"), no_std)]
 
+extern crate alloc;
+
+// Lets the code generated by this crate's own derive macros name it as `tuxedo_core`.
+extern crate self as tuxedo_core;
+
 pub mod dynamic_typing;
+mod construct_runtime;
 mod executive;
 
+pub mod ages;
 pub mod constraint_checker;
+pub mod deposits;
+pub mod digest;
+pub mod events;
+pub mod fees;
+pub mod inclusion;
 pub mod inherents;
+pub mod metadata;
+pub mod offchain;
+pub mod pool;
 pub mod support_macros;
 pub mod traits;
+pub mod try_runtime;
 pub mod types;
 pub mod utxo_set;
 pub mod verifier;
+pub mod weights;
 
 #[cfg(feature = "std")]
 pub mod genesis;
+#[cfg(feature = "std")]
+pub mod pstt;
+#[cfg(feature = "std")]
+pub mod transaction_builder;
+
+#[cfg(all(feature = "std", feature = "runtime-benchmarks"))]
+pub mod benchmarking;
 
-pub use aggregator::{aggregate, tuxedo_constraint_checker, tuxedo_verifier};
-pub use constraint_checker::{ConstraintChecker, SimpleConstraintChecker};
+#[cfg(feature = "wasm-sandbox")]
+pub mod wasm_sandbox;
+
+pub use aggregator::{
+    aggregate, tuxedo_constraint_checker, tuxedo_genesis_config, tuxedo_verifier,
+};
+pub use constraint_checker::{
+    CheckerContext, ConstraintChecker, Delegation, DelegationError, GenesisOnly,
+    SimpleConstraintChecker,
+};
 pub use executive::Executive;
 pub use verifier::Verifier;
 
@@ -34,3 +66,15 @@ const HEADER_KEY: &[u8] = b"header"; // 686561646572
 /// A transient storage key that will hold the list of extrinsics that have been applied so far.
 /// This key is cleared before the end of the block.
 const EXTRINSIC_KEY: &[u8] = b"extrinsics";
+
+/// A storage key that holds the hash of the genesis block. It is written when the first block
+/// is opened, and never changes after that. Wallets read it to learn the signing domain.
+pub const GENESIS_HASH_KEY: &[u8] = b"genesis_hash";
+
+/// A storage key that the genesis config writes, and that is cleared when the first block notes
+/// the genesis hash. While it is present, the state is the genesis state.
+pub const AWAITING_GENESIS_HASH_KEY: &[u8] = b"awaiting_genesis_hash";
+
+/// A storage prefix under which the executive marks the hashes of transactions that created
+/// outputs without consuming any inputs, so that they can not be applied again.
+const MINTED_PREFIX: &[u8] = b"minted";
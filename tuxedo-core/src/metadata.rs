@@ -0,0 +1,352 @@
+//! Runtime metadata for Tuxedo runtimes.
+//!
+//! FRAME's metadata describes pallets, calls, and storage items, none of which exist in Tuxedo.
+//! Instead, Tuxedo metadata describes the types that a client needs in order to build and decode
+//! transactions and UTXOs: the runtime's outer verifier, its outer constraint checker, and every
+//! type that may be stored in the UTXO set along with the type id that it is tagged with. It also
+//! describes [`DetailedError`], which is how the dry run API reports failures. It reports
+//! constraint checker errors by name, along with the variant of the outer constraint checker that
+//! returned them. All of these are described by their `TypeInfo` in a single portable type registry.
+//!
+//! The encoded metadata starts with [`METADATA_MAGIC`] and [`METADATA_VERSION`], so that clients
+//! can tell it apart from FRAME metadata, which starts with `meta`.
+//!
+//! Clients that only want to know what a chain supports, such as explorers, do not need to walk
+//! the type registry themselves. The [`TuxedoIntrospectionApi`] serves a [`RuntimeIntrospection`],
+//! which lists the variants of the outer verifier and constraint checker, and the type ids of the
+//! utxo types, along with the fields of each.
+
+use crate::{dynamic_typing::UtxoData, types::DetailedError, ConstraintChecker, Verifier};
+use alloc::string::String;
+use parity_scale_codec::{Decode, Encode};
+use scale_info::{
+    form::PortableForm, meta_type, Field, PortableRegistry, Registry, TypeDef, TypeInfo,
+};
+use serde::{Deserialize, Serialize};
+use sp_core::OpaqueMetadata;
+use sp_std::vec::Vec;
+
+/// The bytes that all encoded Tuxedo metadata starts with.
+pub const METADATA_MAGIC: [u8; 4] = *b"tuxm";
+
+/// The version of the metadata format described in this module.
+pub const METADATA_VERSION: u32 = 2;
+
+/// A type that may be stored in the UTXO set.
+#[derive(Encode, Debug, Clone, PartialEq, Eq)]
+pub struct UtxoTypeMetadata {
+    /// The type id that the data is tagged with in storage.
+    pub type_id: [u8; 4],
+    /// The id of the data's type in the registry.
+    #[codec(compact)]
+    pub ty: u32,
+}
+
+/// Everything that a client needs to know about the types of a Tuxedo runtime.
+#[derive(Encode, Debug, Clone, PartialEq, Eq)]
+pub struct TuxedoMetadata {
+    /// All the types that are described below, and all the types that they are made of.
+    pub types: PortableRegistry,
+    /// The id of the outer verifier's type in the registry.
+    #[codec(compact)]
+    pub verifier: u32,
+    /// The id of the outer constraint checker's type in the registry.
+    #[codec(compact)]
+    pub constraint_checker: u32,
+    /// The id of the [`DetailedError`] type in the registry.
+    #[codec(compact)]
+    pub detailed_error: u32,
+    /// All the types that may be stored in the UTXO set.
+    pub utxo_types: Vec<UtxoTypeMetadata>,
+}
+
+impl TuxedoMetadata {
+    /// Encode the metadata after the magic bytes and the version, so that it can be served
+    /// through the `Metadata` runtime API.
+    pub fn into_opaque(self) -> OpaqueMetadata {
+        OpaqueMetadata::new((METADATA_MAGIC, METADATA_VERSION, self).encode())
+    }
+
+    /// Summarize what the runtime supports, so that it can be served through the
+    /// [`TuxedoIntrospectionApi`].
+    pub fn introspect(&self) -> RuntimeIntrospection {
+        let utxo_types = self
+            .utxo_types
+            .iter()
+            .map(|utxo_type| {
+                let ty = self.types.resolve(utxo_type.ty);
+                UtxoTypeDescription {
+                    type_id: utxo_type.type_id,
+                    path: ty.map(|ty| ty.path.segments.join("::")).unwrap_or_default(),
+                    fields: match ty.map(|ty| &ty.type_def) {
+                        Some(TypeDef::Composite(composite)) => describe_fields(&composite.fields),
+                        _ => Vec::new(),
+                    },
+                    variants: self.describe_variants(utxo_type.ty),
+                }
+            })
+            .collect();
+
+        RuntimeIntrospection {
+            metadata_version: METADATA_VERSION,
+            verifiers: self.describe_variants(self.verifier),
+            constraint_checkers: self.describe_variants(self.constraint_checker),
+            utxo_types,
+        }
+    }
+
+    /// The variants of the enum with the given id in the registry. Other types have none.
+    fn describe_variants(&self, id: u32) -> Vec<VariantDescription> {
+        match self.types.resolve(id).map(|ty| &ty.type_def) {
+            Some(TypeDef::Variant(def)) => def
+                .variants
+                .iter()
+                .map(|variant| VariantDescription {
+                    name: variant.name.clone(),
+                    index: variant.index,
+                    fields: describe_fields(&variant.fields),
+                })
+                .collect(),
+            _ => Vec::new(),
+        }
+    }
+}
+
+fn describe_fields(fields: &[Field<PortableForm>]) -> Vec<FieldDescription> {
+    fields
+        .iter()
+        .map(|field| FieldDescription {
+            name: field.name.clone(),
+            type_name: field.type_name.clone(),
+            ty: field.ty.id,
+        })
+        .collect()
+}
+
+/// A field of a variant or of a utxo type.
+#[derive(Serialize, Deserialize, Encode, Decode, Debug, Clone, PartialEq, Eq, TypeInfo)]
+pub struct FieldDescription {
+    /// The name of the field, unless it is part of a tuple struct or variant.
+    pub name: Option<String>,
+    /// The type of the field as it is written in the source, such as `Coin<0>`.
+    pub type_name: Option<String>,
+    /// The id of the field's type in the registry of the full [`TuxedoMetadata`].
+    pub ty: u32,
+}
+
+/// A variant of the outer verifier, the outer constraint checker, or a utxo type.
+#[derive(Serialize, Deserialize, Encode, Decode, Debug, Clone, PartialEq, Eq, TypeInfo)]
+pub struct VariantDescription {
+    /// The name of the variant, such as `SigCheck` or `Money`.
+    pub name: String,
+    /// The index that the variant is encoded with.
+    pub index: u8,
+    /// The fields that the variant carries.
+    pub fields: Vec<FieldDescription>,
+}
+
+/// A type that may be stored in the UTXO set.
+#[derive(Serialize, Deserialize, Encode, Decode, Debug, Clone, PartialEq, Eq, TypeInfo)]
+pub struct UtxoTypeDescription {
+    /// The type id that the data is tagged with in storage.
+    pub type_id: [u8; 4],
+    /// The full path of the type, such as `money::Coin`.
+    pub path: String,
+    /// The fields of the type, if it is a struct.
+    pub fields: Vec<FieldDescription>,
+    /// The variants of the type, if it is an enum.
+    pub variants: Vec<VariantDescription>,
+}
+
+/// What a Tuxedo runtime supports, summarized from its [`TuxedoMetadata`].
+#[derive(Serialize, Deserialize, Encode, Decode, Debug, Clone, PartialEq, Eq, TypeInfo)]
+pub struct RuntimeIntrospection {
+    /// The version of the full metadata, which describes every type that is referred to here.
+    pub metadata_version: u32,
+    /// The variants of the outer verifier, or none if it is not an enum.
+    pub verifiers: Vec<VariantDescription>,
+    /// The variants of the outer constraint checker, or none if it is not an enum.
+    pub constraint_checkers: Vec<VariantDescription>,
+    /// All the types that may be stored in the UTXO set.
+    pub utxo_types: Vec<UtxoTypeDescription>,
+}
+
+sp_api::decl_runtime_apis! {
+    /// An API for discovering what a runtime supports, without decoding its full metadata.
+    pub trait TuxedoIntrospectionApi {
+        /// The verifiers, constraint checkers, and utxo types of the runtime.
+        fn introspect() -> RuntimeIntrospection;
+    }
+}
+
+/// Collects the types of a runtime into its [`TuxedoMetadata`].
+///
+/// There is no way to discover all the `UtxoData` types of a runtime automatically,
+/// so each runtime registers the types that its pieces store.
+pub struct MetadataBuilder {
+    registry: Registry,
+    verifier: u32,
+    constraint_checker: u32,
+    detailed_error: u32,
+    utxo_types: Vec<UtxoTypeMetadata>,
+}
+
+impl MetadataBuilder {
+    /// Start describing a runtime with the given outer verifier and outer constraint checker.
+    pub fn new<V, C>() -> Self
+    where
+        V: Verifier + TypeInfo + 'static,
+        C: ConstraintChecker<V> + TypeInfo + 'static,
+    {
+        let mut registry = Registry::new();
+        let verifier = registry.register_type(&meta_type::<V>()).id;
+        let constraint_checker = registry.register_type(&meta_type::<C>()).id;
+        let detailed_error = registry.register_type(&meta_type::<DetailedError>()).id;
+
+        Self {
+            registry,
+            verifier,
+            constraint_checker,
+            detailed_error,
+            utxo_types: Vec::new(),
+        }
+    }
+
+    /// Describe a type that may be stored in the UTXO set.
+    ///
+    /// Panics if a different type with the same type id has already been described. Building the
+    /// metadata thus doubles as a self-check that covers the types that pieces keep private, which
+    /// the compile-time [`assert_unique_type_ids`](crate::assert_unique_type_ids) cannot name.
+    pub fn with_utxo_type<T: UtxoData + TypeInfo + 'static>(mut self) -> Self {
+        let ty = self.registry.register_type(&meta_type::<T>()).id;
+        assert!(
+            self.utxo_types
+                .iter()
+                .all(|existing| existing.type_id != T::TYPE_ID || existing.ty == ty),
+            "Two UtxoData types share the TYPE_ID {:?}",
+            T::TYPE_ID,
+        );
+        self.utxo_types.push(UtxoTypeMetadata {
+            type_id: T::TYPE_ID,
+            ty,
+        });
+        self
+    }
+
+    /// Let a piece describe the types that it stores. This way pieces can describe types
+    /// that they do not make public.
+    pub fn with_piece(self, describe: impl FnOnce(Self) -> Self) -> Self {
+        describe(self)
+    }
+
+    /// Finish describing the runtime.
+    pub fn build(self) -> TuxedoMetadata {
+        TuxedoMetadata {
+            types: self.registry.into(),
+            verifier: self.verifier,
+            constraint_checker: self.constraint_checker,
+            detailed_error: self.detailed_error,
+            utxo_types: self.utxo_types,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{constraint_checker::testing::TestConstraintChecker, verifier::TestVerifier};
+    use parity_scale_codec::Decode;
+
+    /// A simple type that can be stored in the UTXO set.
+    #[derive(Encode, Decode, Debug, PartialEq, Eq, Clone, TypeInfo)]
+    struct Counter(u32);
+
+    impl UtxoData for Counter {
+        const TYPE_ID: [u8; 4] = *b"cntr";
+    }
+
+    fn test_metadata() -> TuxedoMetadata {
+        MetadataBuilder::new::<TestVerifier, TestConstraintChecker>()
+            .with_utxo_type::<Counter>()
+            .build()
+    }
+
+    /// The name of the type with the given id in the registry.
+    fn type_name(metadata: &TuxedoMetadata, id: u32) -> String {
+        let ty = metadata.types.resolve(id).expect("type is in the registry");
+        ty.path.ident().expect("type has a name")
+    }
+
+    #[test]
+    fn outer_types_are_described() {
+        let metadata = test_metadata();
+
+        assert_eq!(type_name(&metadata, metadata.verifier), "TestVerifier");
+        assert_eq!(
+            type_name(&metadata, metadata.constraint_checker),
+            "TestConstraintChecker"
+        );
+        assert_eq!(
+            type_name(&metadata, metadata.detailed_error),
+            "DetailedError"
+        );
+    }
+
+    #[test]
+    fn utxo_types_are_described() {
+        let metadata = test_metadata();
+
+        assert_eq!(metadata.utxo_types.len(), 1);
+        assert_eq!(metadata.utxo_types[0].type_id, *b"cntr");
+        assert_eq!(type_name(&metadata, metadata.utxo_types[0].ty), "Counter");
+    }
+
+    /// A different type that collides with `Counter`.
+    #[derive(Encode, Decode, Debug, PartialEq, Eq, Clone, TypeInfo)]
+    struct Impostor(u32);
+
+    impl UtxoData for Impostor {
+        const TYPE_ID: [u8; 4] = *b"cntr";
+    }
+
+    #[test]
+    #[should_panic(expected = "share the TYPE_ID")]
+    fn colliding_utxo_types_panic() {
+        MetadataBuilder::new::<TestVerifier, TestConstraintChecker>()
+            .with_utxo_type::<Counter>()
+            .with_utxo_type::<Impostor>();
+    }
+
+    #[test]
+    fn introspection_describes_utxo_types() {
+        let introspection = test_metadata().introspect();
+
+        assert_eq!(introspection.metadata_version, METADATA_VERSION);
+        assert_eq!(introspection.utxo_types.len(), 1);
+        let counter = &introspection.utxo_types[0];
+        assert_eq!(counter.type_id, *b"cntr");
+        assert!(counter.path.ends_with("Counter"));
+        assert_eq!(counter.fields.len(), 1);
+        assert_eq!(counter.fields[0].name, None);
+        assert_eq!(counter.fields[0].type_name.as_deref(), Some("u32"));
+        assert!(counter.variants.is_empty());
+    }
+
+    #[test]
+    fn introspection_of_structs_has_no_variants() {
+        // Neither the test verifier nor the test constraint checker is an enum.
+        let introspection = test_metadata().introspect();
+
+        assert!(introspection.verifiers.is_empty());
+        assert!(introspection.constraint_checkers.is_empty());
+    }
+
+    #[test]
+    fn opaque_metadata_is_prefixed() {
+        let opaque = test_metadata().into_opaque();
+        let mut encoded = &opaque[..];
+
+        assert_eq!(<[u8; 4]>::decode(&mut encoded).unwrap(), METADATA_MAGIC);
+        assert_eq!(u32::decode(&mut encoded).unwrap(), METADATA_VERSION);
+    }
+}
@@ -0,0 +1,108 @@
+//! Off-chain workers for Tuxedo pieces.
+//!
+//! After a node imports a block, it may call into the runtime's `OffchainWorkerApi`. The executive
+//! answers by calling the [`OffchainWorker`] hooks that the runtime configured, in order. Unlike
+//! on-chain logic, off-chain workers may take as long as they like, are not deterministic, and
+//! have access to host functions that the block execution does not have. The most useful are:
+//!
+//! * Local storage, through `sp_io::offchain::local_storage_get` and friends. It is private
+//!   to the node, and persists between runs of the workers.
+//! * HTTP requests, through `sp_runtime::offchain::http`. This is how, for example, an oracle
+//!   piece would fetch prices from the outside world.
+//!
+//! Workers never change the chain's state directly. Instead, they submit transactions to the
+//! node's pool with [`submit_transaction`]. These are regular transactions that are validated
+//! like any other, so a worker usually submits transactions for its own piece's constraint
+//! checker, protected by a verifier that anyone can satisfy.
+
+use crate::types::Transaction;
+use parity_scale_codec::Encode;
+use sp_core::H256;
+
+/// Information about the block that the off-chain workers are running after.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct OffchainContext {
+    /// The height of the block that was just imported.
+    pub block_height: u32,
+    /// The hash of the block that was just imported.
+    pub block_hash: H256,
+}
+
+/// Off-chain logic that a piece runs after each block is imported.
+///
+/// The runtime lists the workers of its pieces in its executive, as a tuple if there are several.
+/// The unit type has no workers, and is the default.
+pub trait OffchainWorker<V, C> {
+    /// Run the off-chain logic for the block described by the context.
+    fn offchain_worker(context: &OffchainContext);
+}
+
+impl<V, C> OffchainWorker<V, C> for () {
+    fn offchain_worker(_context: &OffchainContext) {}
+}
+
+macro_rules! impl_offchain_worker_for_tuples {
+    ( $( $worker:ident ),+ ) => {
+        impl<V, C, $( $worker: OffchainWorker<V, C> ),+> OffchainWorker<V, C> for ( $( $worker, )+ ) {
+            fn offchain_worker(context: &OffchainContext) {
+                $( <$worker as OffchainWorker<V, C>>::offchain_worker(context); )+
+            }
+        }
+    };
+}
+
+impl_offchain_worker_for_tuples!(A);
+impl_offchain_worker_for_tuples!(A, B);
+impl_offchain_worker_for_tuples!(A, B, C1);
+impl_offchain_worker_for_tuples!(A, B, C1, D);
+impl_offchain_worker_for_tuples!(A, B, C1, D, E);
+impl_offchain_worker_for_tuples!(A, B, C1, D, E, F);
+impl_offchain_worker_for_tuples!(A, B, C1, D, E, F, G);
+impl_offchain_worker_for_tuples!(A, B, C1, D, E, F, G, H);
+
+/// Submit a transaction to the node's transaction pool from inside an off-chain worker.
+///
+/// This only works while an off-chain worker is running. The transaction is validated by the pool
+/// like any other, so an error here means that the pool rejected it.
+#[allow(clippy::result_unit_err)]
+pub fn submit_transaction<V: Encode, C: Encode>(transaction: Transaction<V, C>) -> Result<(), ()> {
+    sp_io::offchain::submit_transaction(transaction.encode())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+
+    thread_local! {
+        static CALLS: RefCell<Vec<(&'static str, u32)>> = RefCell::new(Vec::new());
+    }
+
+    struct First;
+
+    impl OffchainWorker<(), ()> for First {
+        fn offchain_worker(context: &OffchainContext) {
+            CALLS.with(|calls| calls.borrow_mut().push(("first", context.block_height)));
+        }
+    }
+
+    struct Second;
+
+    impl OffchainWorker<(), ()> for Second {
+        fn offchain_worker(context: &OffchainContext) {
+            CALLS.with(|calls| calls.borrow_mut().push(("second", context.block_height)));
+        }
+    }
+
+    #[test]
+    fn tuple_workers_run_in_order() {
+        let context = OffchainContext {
+            block_height: 7,
+            ..Default::default()
+        };
+
+        <(First, Second) as OffchainWorker<(), ()>>::offchain_worker(&context);
+
+        CALLS.with(|calls| assert_eq!(*calls.borrow(), vec![("first", 7), ("second", 7)]));
+    }
+}
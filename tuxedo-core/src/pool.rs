@@ -0,0 +1,256 @@
+//! Policies that adjust the order in which the transaction pool includes transactions.
+//!
+//! By default the executive prioritizes every transaction by the tip that it pays per second of
+//! weight (see [`weights::priority`](crate::weights::priority)). Runtimes sometimes know better
+//! than that. A runtime may want runtime upgrades to jump the queue, or may want the free
+//! transactions of a toy piece to wait until the paying transactions are in.
+//!
+//! The runtime configures a [`PoolPolicy`] in its executive. The policy sees each fully checked
+//! transaction along with the priority that the executive gave it, and returns the priority that
+//! the pool uses instead. The policy only changes the order of transactions. The fee and the tip
+//! that a transaction pays stay the same.
+//!
+//! A policy may also set a minimum priority for each transaction, below which the pool refuses it.
+//! On a public network this keeps free operations, such as creating amoebas or spending coins
+//! without a surplus, from flooding the pool. The minimum is only enforced in the pool, so block
+//! authors can still include cheaper transactions of their own.
+//!
+//! Priority is not the only thing that decides the order though. Each transaction provides a tag
+//! for every output it creates, and requires one for every input and peek that is not in the utxo
+//! set yet. The node's pool only hands a transaction to the block author after the ones that
+//! provide its tags, so a parent and the child that spends its outputs can land in the same block.
+//! Authors that build blocks from a plain list of transactions instead, such as simulations, get
+//! the same guarantee from [`dependency_order`].
+
+use crate::types::{OutputRef, Transaction};
+use parity_scale_codec::Encode;
+use sp_runtime::{
+    traits::{BlakeTwo256, Hash},
+    transaction_validity::TransactionPriority,
+};
+use sp_std::{
+    collections::{btree_map::BTreeMap, btree_set::BTreeSet},
+    vec::Vec,
+};
+
+/// Adjusts the priority that the pool gives transactions, for example by constraint checker.
+///
+/// The unit type keeps the priority that the executive computed, and is the default.
+pub trait PoolPolicy<V, C> {
+    /// The priority of the given transaction, which the executive prioritized at `priority`.
+    fn priority(
+        transaction: &Transaction<V, C>,
+        priority: TransactionPriority,
+    ) -> TransactionPriority;
+
+    /// The least priority, as adjusted by this policy, that the pool accepts the given
+    /// transaction at. By default every transaction is accepted.
+    fn minimum_priority(_transaction: &Transaction<V, C>) -> TransactionPriority {
+        0
+    }
+}
+
+impl<V, C> PoolPolicy<V, C> for () {
+    fn priority(
+        _transaction: &Transaction<V, C>,
+        priority: TransactionPriority,
+    ) -> TransactionPriority {
+        priority
+    }
+}
+
+/// Order the given transactions so that each one comes after the transactions that create the
+/// outputs it consumes or peeks at. Otherwise the order is kept, so transactions that do not depend
+/// on each other stay in the order they were given in, for example by priority.
+///
+/// Transactions whose dependencies are not among the given ones keep their place. They either
+/// depend on utxos that are already in the set, or they will fail to apply.
+pub fn dependency_order<V, C>(transactions: Vec<Transaction<V, C>>) -> Vec<Transaction<V, C>>
+where
+    Transaction<V, C>: Encode,
+{
+    // Which transaction creates each output. Transactions that appear twice can only be applied
+    // once anyway, so the first one is their creator.
+    let hashes: Vec<_> = transactions
+        .iter()
+        .map(|transaction| BlakeTwo256::hash_of(&transaction.encode()))
+        .collect();
+    let mut creators = BTreeMap::new();
+    for (creator, transaction) in transactions.iter().enumerate() {
+        for index in 0..transaction.outputs.len() {
+            let output_ref = OutputRef {
+                tx_hash: hashes[creator],
+                index: index as u32,
+            };
+            creators.entry(output_ref).or_insert(creator);
+        }
+    }
+
+    // How many of its parents each transaction is still waiting for, and the children that are
+    // waiting for each transaction.
+    let mut waiting_for = Vec::with_capacity(transactions.len());
+    let mut children = BTreeMap::<usize, Vec<usize>>::new();
+    for (child, transaction) in transactions.iter().enumerate() {
+        let parents: BTreeSet<_> = transaction
+            .inputs
+            .iter()
+            .map(|input| &input.output_ref)
+            .chain(transaction.peeks.iter())
+            .filter_map(|output_ref| creators.get(output_ref).copied())
+            .filter(|parent| *parent != child)
+            .collect();
+        waiting_for.push(parents.len());
+        for parent in parents {
+            children.entry(parent).or_default().push(child);
+        }
+    }
+
+    // Repeatedly take the earliest transaction that is not waiting for anything.
+    let mut ready: BTreeSet<_> = (0..transactions.len())
+        .filter(|i| waiting_for[*i] == 0)
+        .collect();
+    let mut order = Vec::with_capacity(transactions.len());
+    while let Some(next) = ready.pop_first() {
+        order.push(next);
+        for child in children.remove(&next).unwrap_or_default() {
+            waiting_for[child] -= 1;
+            if waiting_for[child] == 0 {
+                ready.insert(child);
+            }
+        }
+    }
+
+    let mut slots: Vec<_> = transactions.into_iter().map(Some).collect();
+    order.into_iter().filter_map(|i| slots[i].take()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        constraint_checker::testing::TestConstraintChecker,
+        dynamic_typing::testing::Bogus,
+        types::{Input, InputMode, TransactionVersion},
+        verifier::TestVerifier,
+    };
+
+    type TestTransaction = Transaction<TestVerifier, TestConstraintChecker>;
+
+    /// A transaction that consumes and peeks at the given output refs, and creates a single
+    /// output, which is distinguished by its verifier.
+    fn tx(inputs: &[&OutputRef], peeks: &[&OutputRef], verifies: bool) -> TestTransaction {
+        Transaction {
+            version: TransactionVersion::LATEST,
+            inputs: inputs
+                .iter()
+                .map(|output_ref| Input {
+                    output_ref: (*output_ref).clone(),
+                    redeemer: Vec::new(),
+                    mode: InputMode::Consume,
+                })
+                .collect(),
+            peeks: peeks
+                .iter()
+                .map(|output_ref| (*output_ref).clone())
+                .collect(),
+            outputs: vec![(Bogus, TestVerifier { verifies }).into()],
+            checker: TestConstraintChecker {
+                checks: true,
+                inherent: false,
+            },
+            memo: None,
+            valid_until: None,
+        }
+    }
+
+    fn output_of(transaction: &TestTransaction) -> OutputRef {
+        OutputRef {
+            tx_hash: BlakeTwo256::hash_of(&transaction.encode()),
+            index: 0,
+        }
+    }
+
+    fn on_chain(byte: u8) -> OutputRef {
+        OutputRef {
+            tx_hash: sp_core::H256::repeat_byte(byte),
+            index: 0,
+        }
+    }
+
+    #[test]
+    fn unit_policy_keeps_priority() {
+        let tx = Transaction {
+            version: TransactionVersion::LATEST,
+            inputs: Vec::new(),
+            peeks: Vec::new(),
+            outputs: Vec::new(),
+            checker: TestConstraintChecker {
+                checks: true,
+                inherent: false,
+            },
+            memo: None,
+            valid_until: None,
+        };
+
+        assert_eq!(
+            <() as PoolPolicy<TestVerifier, TestConstraintChecker>>::priority(&tx, 7),
+            7
+        );
+        assert_eq!(
+            <() as PoolPolicy<TestVerifier, TestConstraintChecker>>::minimum_priority(&tx),
+            0
+        );
+    }
+
+    #[test]
+    fn independent_transactions_keep_their_order() {
+        let first = tx(&[&on_chain(1)], &[], true);
+        let second = tx(&[&on_chain(2)], &[], true);
+
+        assert_eq!(
+            dependency_order(vec![second.clone(), first.clone()]),
+            vec![second, first]
+        );
+    }
+
+    #[test]
+    fn children_follow_their_parents() {
+        let parent = tx(&[&on_chain(1)], &[], true);
+        let child = tx(&[&output_of(&parent)], &[], true);
+        let grandchild = tx(&[&output_of(&child)], &[], true);
+
+        assert_eq!(
+            dependency_order(vec![grandchild.clone(), child.clone(), parent.clone()]),
+            vec![parent, child, grandchild]
+        );
+    }
+
+    #[test]
+    fn peeks_are_dependencies_too() {
+        let parent = tx(&[&on_chain(1)], &[], true);
+        let peeker = tx(&[], &[&output_of(&parent)], true);
+
+        assert_eq!(
+            dependency_order(vec![peeker.clone(), parent.clone()]),
+            vec![parent, peeker]
+        );
+    }
+
+    #[test]
+    fn children_wait_for_all_of_their_parents() {
+        let first = tx(&[&on_chain(1)], &[], true);
+        let second = tx(&[&on_chain(2)], &[], true);
+        let child = tx(&[&output_of(&first)], &[&output_of(&second)], true);
+        let unrelated = tx(&[&on_chain(3)], &[], false);
+
+        assert_eq!(
+            dependency_order(vec![
+                first.clone(),
+                child.clone(),
+                unrelated.clone(),
+                second.clone()
+            ]),
+            vec![first, unrelated, second, child]
+        );
+    }
+}
@@ -0,0 +1,722 @@
+//! Partially signed Tuxedo transactions (PSTTs), an interchange format for transactions that
+//! are signed by several parties, or on a different machine than the one that built them.
+//!
+//! A PSTT carries an unsigned transaction, the signing domain of the chain it is meant for, and
+//! some metadata about each input. It is passed between the following roles, which may all be
+//! played by the same wallet, or by different ones.
+//!
+//! 1. The creator builds the transaction and wraps it with [`PartiallySignedTransaction::new`].
+//! 2. An updater, which can read the chain, resolves each input to the output that it consumes
+//!    with [`resolve_input`](PartiallySignedTransaction::resolve_input). This tells signers what
+//!    they are spending, and who has to sign for it.
+//! 3. Each signer adds their signatures with [`sign_with`](PartiallySignedTransaction::sign_with)
+//!    or [`add_signature`](PartiallySignedTransaction::add_signature). Signers never need to
+//!    reach the chain, so their keys can stay offline.
+//! 4. A combiner merges the PSTTs that different signers returned with
+//!    [`combine`](PartiallySignedTransaction::combine).
+//! 5. The finalizer assembles the collected signatures into redeemers with
+//!    [`finalize`](PartiallySignedTransaction::finalize), and gets a transaction to submit.
+//!
+//! Transactions that several parties build together, such as CoinJoins, start as one PSTT per
+//! party with only that party's inputs and outputs. A coordinator merges them with
+//! [`join`](PartiallySignedTransaction::join) before anyone signs, and every party then checks
+//! that the joined transaction still contains their outputs before signing their own inputs.
+//!
+//! PSTTs are exchanged as the bytes from [`to_bytes`](PartiallySignedTransaction::to_bytes),
+//! which are the SCALE encoding of the PSTT behind a short header.
+
+use crate::{
+    types::{Output, OutputRef, SigningDomain, Transaction},
+    verifier::{
+        Ed25519SigCheck, Proxy, ProxyRedeemer, ProxyScope, SigCheck, SignatureAndIndex,
+        TestVerifier, ThresholdMultiSignature, UpForGrabs,
+    },
+};
+use parity_scale_codec::{Decode, Encode};
+use sp_core::{sr25519, Pair, H256};
+use std::collections::BTreeMap;
+
+/// The bytes that every encoded PSTT begins with, so that tools can tell PSTTs from other data.
+pub const PSTT_MAGIC: [u8; 4] = *b"pstt";
+
+/// The version of the PSTT format. It follows the magic bytes, and is bumped whenever the
+/// encoding changes.
+pub const PSTT_VERSION: u8 = 1;
+
+/// Verifiers whose redeemers are made of signatures, so that a PSTT can collect a signature from
+/// each signer separately, and assemble the redeemer once it has enough of them.
+pub trait SignatureRedeemer {
+    /// The public keys whose signatures count towards this verifier's redeemer.
+    fn signers(&self) -> Vec<H256>;
+
+    /// Assemble a redeemer from the signatures collected so far, keyed by their signer.
+    /// Returns `None` while there are not enough signatures.
+    fn assemble_redeemer(&self, signatures: &BTreeMap<H256, Vec<u8>>) -> Option<Vec<u8>>;
+}
+
+impl SignatureRedeemer for SigCheck {
+    fn signers(&self) -> Vec<H256> {
+        vec![self.owner_pubkey]
+    }
+
+    fn assemble_redeemer(&self, signatures: &BTreeMap<H256, Vec<u8>>) -> Option<Vec<u8>> {
+        signatures.get(&self.owner_pubkey).cloned()
+    }
+}
+
+impl SignatureRedeemer for Ed25519SigCheck {
+    fn signers(&self) -> Vec<H256> {
+        vec![self.owner_pubkey]
+    }
+
+    fn assemble_redeemer(&self, signatures: &BTreeMap<H256, Vec<u8>>) -> Option<Vec<u8>> {
+        signatures.get(&self.owner_pubkey).cloned()
+    }
+}
+
+impl SignatureRedeemer for ThresholdMultiSignature {
+    fn signers(&self) -> Vec<H256> {
+        self.signatories.clone()
+    }
+
+    fn assemble_redeemer(&self, signatures: &BTreeMap<H256, Vec<u8>>) -> Option<Vec<u8>> {
+        let redeemer: Vec<SignatureAndIndex> = self
+            .signatories
+            .iter()
+            .enumerate()
+            .filter_map(|(index, signatory)| {
+                let signature =
+                    sr25519::Signature::try_from(&signatures.get(signatory)?[..]).ok()?;
+                Some(SignatureAndIndex {
+                    signature,
+                    index: index as u8,
+                })
+            })
+            .collect();
+
+        (redeemer.len() >= self.threshold as usize).then(|| redeemer.encode())
+    }
+}
+
+impl<S: ProxyScope> SignatureRedeemer for Proxy<S> {
+    fn signers(&self) -> Vec<H256> {
+        let mut signers = vec![self.owner];
+        signers.extend(self.delegates.iter().map(|delegate| delegate.pubkey));
+        signers
+    }
+
+    /// The owner's signature is preferred. Otherwise the first delegate that signed is used,
+    /// so a delegate must not sign transactions outside of their scope.
+    fn assemble_redeemer(&self, signatures: &BTreeMap<H256, Vec<u8>>) -> Option<Vec<u8>> {
+        let signature_of =
+            |key: &H256| sr25519::Signature::try_from(&signatures.get(key)?[..]).ok();
+
+        if let Some(signature) = signature_of(&self.owner) {
+            return Some(ProxyRedeemer::Owner(signature).encode());
+        }
+        self.delegates
+            .iter()
+            .enumerate()
+            .find_map(|(index, delegate)| {
+                Some(ProxyRedeemer::Delegate {
+                    index: index as u8,
+                    signature: signature_of(&delegate.pubkey)?,
+                })
+            })
+            .map(|redeemer| redeemer.encode())
+    }
+}
+
+impl SignatureRedeemer for UpForGrabs {
+    fn signers(&self) -> Vec<H256> {
+        Vec::new()
+    }
+
+    fn assemble_redeemer(&self, _signatures: &BTreeMap<H256, Vec<u8>>) -> Option<Vec<u8>> {
+        Some(Vec::new())
+    }
+}
+
+impl SignatureRedeemer for TestVerifier {
+    fn signers(&self) -> Vec<H256> {
+        Vec::new()
+    }
+
+    fn assemble_redeemer(&self, _signatures: &BTreeMap<H256, Vec<u8>>) -> Option<Vec<u8>> {
+        Some(Vec::new())
+    }
+}
+
+/// Errors that can occur while working with a PSTT.
+#[derive(Debug, PartialEq, Eq)]
+pub enum PsttError {
+    /// The bytes do not begin with the PSTT header, are of an unknown version, or do not decode.
+    Malformed,
+    /// The transaction has no input at the given index.
+    NoSuchInput(usize),
+    /// The combined PSTTs are not for the same transaction on the same chain, or the joined
+    /// PSTTs do not share a chain, constraint checker, version, memo, and expiry.
+    DifferentTransactions,
+    /// The joined PSTTs already carry signatures, which joining would invalidate.
+    AlreadySigned,
+    /// The combined PSTTs resolved the input at the given index to different outputs.
+    ConflictingUtxos(usize),
+    /// The key is not one of the signers of the input at the given index.
+    UnexpectedSigner(usize),
+    /// The input at the given index has not been resolved, so it is not known who must sign it.
+    UnresolvedInput(usize),
+    /// The input at the given index does not have enough signatures for its verifier yet.
+    MissingSignatures(usize),
+}
+
+/// What a PSTT knows about one of its transaction's inputs.
+#[derive(Encode, Decode, Debug, PartialEq, Eq, Clone)]
+pub struct InputMetadata<V> {
+    /// The output that the input consumes, once an updater has resolved it. Its payload tells
+    /// signers what they are spending, and its verifier tells them who must sign.
+    pub utxo: Option<Output<V>>,
+    /// The signatures collected so far, keyed by the public key that made them.
+    pub signatures: BTreeMap<H256, Vec<u8>>,
+    /// A complete redeemer for the input, for verifiers whose redeemers are not only signatures,
+    /// such as hash locks. When present, it is used as is and the signatures are ignored.
+    pub redeemer: Option<Vec<u8>>,
+}
+
+impl<V> Default for InputMetadata<V> {
+    fn default() -> Self {
+        Self {
+            utxo: None,
+            signatures: BTreeMap::new(),
+            redeemer: None,
+        }
+    }
+}
+
+/// A transaction that is still being signed, along with what its signers need to know.
+#[derive(Encode, Decode, Debug, PartialEq, Eq, Clone)]
+pub struct PartiallySignedTransaction<V, C> {
+    /// The transaction, with every redeemer empty.
+    pub transaction: Transaction<V, C>,
+    /// The chain that the transaction is signed for.
+    pub domain: SigningDomain,
+    /// The metadata of each input, in the same order as the transaction's inputs.
+    pub inputs: Vec<InputMetadata<V>>,
+}
+
+impl<V, C> PartiallySignedTransaction<V, C>
+where
+    V: SignatureRedeemer + Encode + Decode + Clone + PartialEq,
+    C: Encode + Decode + Clone,
+{
+    /// Wrap the given transaction, to be signed for the chain described by the given domain.
+    ///
+    /// Any redeemers that the transaction already has are moved into the metadata of their inputs.
+    pub fn new(mut transaction: Transaction<V, C>, domain: SigningDomain) -> Self {
+        let inputs = transaction
+            .inputs
+            .iter_mut()
+            .map(|input| InputMetadata {
+                redeemer: (!input.redeemer.is_empty())
+                    .then(|| core::mem::take(&mut input.redeemer)),
+                ..Default::default()
+            })
+            .collect();
+
+        Self {
+            transaction,
+            domain,
+            inputs,
+        }
+    }
+
+    /// The payload that every signer signs.
+    pub fn signing_payload(&self) -> Vec<u8> {
+        self.transaction.signing_payload(&self.domain)
+    }
+
+    /// The output refs of the inputs that have not been resolved yet, along with their index.
+    pub fn unresolved_inputs(&self) -> Vec<(usize, OutputRef)> {
+        self.transaction
+            .inputs
+            .iter()
+            .zip(&self.inputs)
+            .enumerate()
+            .filter(|(_, (_, metadata))| metadata.utxo.is_none())
+            .map(|(index, (input, _))| (index, input.output_ref.clone()))
+            .collect()
+    }
+
+    /// Note the output that the input at the given index consumes, as read from the chain.
+    pub fn resolve_input(&mut self, index: usize, utxo: Output<V>) -> Result<(), PsttError> {
+        self.input_mut(index)?.utxo = Some(utxo);
+        Ok(())
+    }
+
+    /// Set a complete redeemer for the input at the given index.
+    pub fn set_redeemer(&mut self, index: usize, redeemer: Vec<u8>) -> Result<(), PsttError> {
+        self.input_mut(index)?.redeemer = Some(redeemer);
+        Ok(())
+    }
+
+    /// Add a signature by the given key to the input at the given index.
+    ///
+    /// The input must already be resolved, so that the key can be checked against its signers.
+    pub fn add_signature(
+        &mut self,
+        index: usize,
+        signer: H256,
+        signature: Vec<u8>,
+    ) -> Result<(), PsttError> {
+        let input = self.input_mut(index)?;
+        let utxo = input
+            .utxo
+            .as_ref()
+            .ok_or(PsttError::UnresolvedInput(index))?;
+        if !utxo.verifier.signers().contains(&signer) {
+            return Err(PsttError::UnexpectedSigner(index));
+        }
+
+        input.signatures.insert(signer, signature);
+        Ok(())
+    }
+
+    /// Sign every resolved input that the given sr25519 key is a signer of.
+    /// Returns the indices of the inputs that were signed.
+    pub fn sign_with(&mut self, pair: &sr25519::Pair) -> Vec<usize> {
+        let signer = H256::from(pair.public().0);
+        let signature = pair.sign(&self.signing_payload()).0.to_vec();
+
+        let mut signed = Vec::new();
+        for (index, input) in self.inputs.iter_mut().enumerate() {
+            let is_signer = input
+                .utxo
+                .as_ref()
+                .is_some_and(|utxo| utxo.verifier.signers().contains(&signer));
+            if is_signer {
+                input.signatures.insert(signer, signature.clone());
+                signed.push(index);
+            }
+        }
+        signed
+    }
+
+    /// Merge what another PSTT of the same transaction knows into this one.
+    ///
+    /// Resolved inputs, signatures, and redeemers that only the other PSTT has are added.
+    /// Where both have one, this PSTT's is kept. The resolved outputs must agree.
+    pub fn combine(&mut self, other: Self) -> Result<(), PsttError> {
+        if self.domain != other.domain || self.signing_payload() != other.signing_payload() {
+            return Err(PsttError::DifferentTransactions);
+        }
+
+        for (index, (mine, theirs)) in self.inputs.iter_mut().zip(other.inputs).enumerate() {
+            match (&mine.utxo, theirs.utxo) {
+                (Some(utxo), Some(other_utxo)) if *utxo != other_utxo => {
+                    return Err(PsttError::ConflictingUtxos(index))
+                }
+                (None, other_utxo) => mine.utxo = other_utxo,
+                _ => (),
+            }
+            for (signer, signature) in theirs.signatures {
+                mine.signatures.entry(signer).or_insert(signature);
+            }
+            if mine.redeemer.is_none() {
+                mine.redeemer = theirs.redeemer;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Add the inputs, peeks, and outputs of another PSTT after those of this one, so that
+    /// several parties can each contribute their part of a collaborative transaction.
+    ///
+    /// Both PSTTs must be for the same chain, with the same constraint checker, version, memo, and
+    /// expiry.
+    /// Joining changes the signing payload, so neither may have collected any signatures yet.
+    pub fn join(&mut self, other: Self) -> Result<(), PsttError> {
+        let is_signed = |pstt: &Self| pstt.inputs.iter().any(|input| !input.signatures.is_empty());
+        if is_signed(self) || is_signed(&other) {
+            return Err(PsttError::AlreadySigned);
+        }
+        if self.domain != other.domain
+            || self.transaction.version != other.transaction.version
+            || self.transaction.memo != other.transaction.memo
+            || self.transaction.valid_until != other.transaction.valid_until
+            || self.transaction.checker.encode() != other.transaction.checker.encode()
+        {
+            return Err(PsttError::DifferentTransactions);
+        }
+
+        self.transaction.inputs.extend(other.transaction.inputs);
+        self.transaction.peeks.extend(other.transaction.peeks);
+        self.transaction.outputs.extend(other.transaction.outputs);
+        self.inputs.extend(other.inputs);
+        Ok(())
+    }
+
+    /// Whether every input has a redeemer, or enough signatures to assemble one.
+    pub fn is_complete(&self) -> bool {
+        (0..self.inputs.len()).all(|index| self.redeemer(index).is_ok())
+    }
+
+    /// Assemble the redeemer of every input, and return the transaction, ready to be submitted.
+    pub fn finalize(self) -> Result<Transaction<V, C>, PsttError> {
+        let redeemers = (0..self.inputs.len())
+            .map(|index| self.redeemer(index))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let mut transaction = self.transaction;
+        for (input, redeemer) in transaction.inputs.iter_mut().zip(redeemers) {
+            input.redeemer = redeemer;
+        }
+        Ok(transaction)
+    }
+
+    /// Encode this PSTT, behind the magic bytes and the version, to be written to a file or sent
+    /// to another party.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = PSTT_MAGIC.to_vec();
+        bytes.push(PSTT_VERSION);
+        self.encode_to(&mut bytes);
+        bytes
+    }
+
+    /// Decode a PSTT from the bytes that [`to_bytes`](Self::to_bytes) produced.
+    ///
+    /// The number of inputs' metadata must match the number of inputs of the transaction.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, PsttError> {
+        let encoded = bytes
+            .strip_prefix(&PSTT_MAGIC[..])
+            .and_then(|rest| rest.strip_prefix(&[PSTT_VERSION]))
+            .ok_or(PsttError::Malformed)?;
+        let pstt = Self::decode(&mut &encoded[..]).map_err(|_| PsttError::Malformed)?;
+
+        if pstt.inputs.len() != pstt.transaction.inputs.len() {
+            return Err(PsttError::Malformed);
+        }
+        Ok(pstt)
+    }
+
+    fn input_mut(&mut self, index: usize) -> Result<&mut InputMetadata<V>, PsttError> {
+        self.inputs
+            .get_mut(index)
+            .ok_or(PsttError::NoSuchInput(index))
+    }
+
+    fn redeemer(&self, index: usize) -> Result<Vec<u8>, PsttError> {
+        let input = &self.inputs[index];
+        if let Some(redeemer) = &input.redeemer {
+            return Ok(redeemer.clone());
+        }
+
+        input
+            .utxo
+            .as_ref()
+            .ok_or(PsttError::UnresolvedInput(index))?
+            .verifier
+            .assemble_redeemer(&input.signatures)
+            .ok_or(PsttError::MissingSignatures(index))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        constraint_checker::testing::TestConstraintChecker,
+        dynamic_typing::testing::Bogus,
+        transaction_builder::TransactionBuilder,
+        verifier::{Verifier, VerifierContext},
+    };
+
+    /// A verifier with the signature-based variants that these tests need.
+    #[derive(Encode, Decode, Debug, PartialEq, Eq, Clone)]
+    enum TestOuterVerifier {
+        SigCheck(SigCheck),
+        ThresholdMultiSignature(ThresholdMultiSignature),
+    }
+
+    impl SignatureRedeemer for TestOuterVerifier {
+        fn signers(&self) -> Vec<H256> {
+            match self {
+                Self::SigCheck(v) => v.signers(),
+                Self::ThresholdMultiSignature(v) => v.signers(),
+            }
+        }
+
+        fn assemble_redeemer(&self, signatures: &BTreeMap<H256, Vec<u8>>) -> Option<Vec<u8>> {
+            match self {
+                Self::SigCheck(v) => v.assemble_redeemer(signatures),
+                Self::ThresholdMultiSignature(v) => v.assemble_redeemer(signatures),
+            }
+        }
+    }
+
+    type TestPstt = PartiallySignedTransaction<TestOuterVerifier, TestConstraintChecker>;
+
+    fn alice() -> sr25519::Pair {
+        sr25519::Pair::from_seed(&[1; 32])
+    }
+
+    fn bob() -> sr25519::Pair {
+        sr25519::Pair::from_seed(&[2; 32])
+    }
+
+    fn key(pair: &sr25519::Pair) -> H256 {
+        H256::from(pair.public().0)
+    }
+
+    fn domain() -> SigningDomain {
+        SigningDomain {
+            genesis_hash: H256::repeat_byte(3),
+            spec_version: 1,
+            transaction_version: 1,
+        }
+    }
+
+    fn output_ref(index: u32) -> OutputRef {
+        OutputRef {
+            tx_hash: H256::repeat_byte(9),
+            index,
+        }
+    }
+
+    fn owned_by(verifier: TestOuterVerifier) -> Output<TestOuterVerifier> {
+        Output {
+            payload: Bogus.into(),
+            verifier,
+        }
+    }
+
+    /// A PSTT whose first input is owned by Alice, and whose second is owned by a 2-of-2
+    /// multisig of Alice and Bob. Both inputs are resolved.
+    fn resolved_pstt() -> TestPstt {
+        let transaction = TransactionBuilder::new(TestConstraintChecker {
+            checks: true,
+            inherent: false,
+        })
+        .input(output_ref(0), Vec::new())
+        .input(output_ref(1), Vec::new())
+        .output(
+            Bogus,
+            TestOuterVerifier::SigCheck(SigCheck::new(key(&bob()))),
+        )
+        .build();
+
+        let mut pstt = TestPstt::new(transaction, domain());
+        pstt.resolve_input(
+            0,
+            owned_by(TestOuterVerifier::SigCheck(SigCheck::new(key(&alice())))),
+        )
+        .unwrap();
+        pstt.resolve_input(
+            1,
+            owned_by(TestOuterVerifier::ThresholdMultiSignature(
+                ThresholdMultiSignature::new(2, vec![key(&alice()), key(&bob())]),
+            )),
+        )
+        .unwrap();
+        pstt
+    }
+
+    #[test]
+    fn new_moves_redeemers_into_metadata() {
+        let transaction = TransactionBuilder::<TestOuterVerifier, _>::new(TestConstraintChecker {
+            checks: true,
+            inherent: false,
+        })
+        .input(output_ref(0), vec![1, 2, 3])
+        .input(output_ref(1), Vec::new())
+        .build();
+
+        let pstt = TestPstt::new(transaction, domain());
+
+        assert!(pstt
+            .transaction
+            .inputs
+            .iter()
+            .all(|i| i.redeemer.is_empty()));
+        assert_eq!(pstt.inputs[0].redeemer, Some(vec![1, 2, 3]));
+        assert_eq!(pstt.inputs[1].redeemer, None);
+        assert_eq!(
+            pstt.unresolved_inputs(),
+            vec![(0, output_ref(0)), (1, output_ref(1))]
+        );
+    }
+
+    #[test]
+    fn signers_sign_separately_and_combine() {
+        let unsigned = resolved_pstt();
+        let mut from_alice = unsigned.clone();
+        let mut from_bob = unsigned.clone();
+
+        assert_eq!(from_alice.sign_with(&alice()), vec![0, 1]);
+        assert_eq!(from_bob.sign_with(&bob()), vec![1]);
+        assert!(!from_alice.is_complete());
+
+        from_alice.combine(from_bob).unwrap();
+        assert!(from_alice.is_complete());
+
+        let payload = unsigned.signing_payload();
+        let transaction = from_alice.finalize().unwrap();
+        assert!(SigCheck::new(key(&alice())).verify(
+            &payload,
+            &transaction.inputs[0].redeemer,
+            &VerifierContext::default()
+        ));
+        assert!(
+            ThresholdMultiSignature::new(2, vec![key(&alice()), key(&bob())]).verify(
+                &payload,
+                &transaction.inputs[1].redeemer,
+                &VerifierContext::default()
+            )
+        );
+    }
+
+    #[test]
+    fn join_appends_each_part() {
+        let part = |index, pair: &sr25519::Pair| {
+            let owner = TestOuterVerifier::SigCheck(SigCheck::new(key(pair)));
+            let transaction = TransactionBuilder::new(TestConstraintChecker {
+                checks: true,
+                inherent: false,
+            })
+            .input(output_ref(index), Vec::new())
+            .output(Bogus, owner.clone())
+            .build();
+            let mut pstt = TestPstt::new(transaction, domain());
+            pstt.resolve_input(0, owned_by(owner)).unwrap();
+            pstt
+        };
+        let mut joined = part(0, &alice());
+        joined.join(part(1, &bob())).unwrap();
+
+        assert_eq!(joined.transaction.inputs[1].output_ref, output_ref(1));
+        assert_eq!(joined.transaction.outputs.len(), 2);
+        assert_eq!(joined.inputs.len(), 2);
+
+        let mut from_alice = joined.clone();
+        assert_eq!(from_alice.sign_with(&alice()), vec![0]);
+        assert_eq!(joined.sign_with(&bob()), vec![1]);
+        joined.combine(from_alice).unwrap();
+        assert!(joined.is_complete());
+    }
+
+    #[test]
+    fn join_after_signing_fails() {
+        let mut signed = resolved_pstt();
+        signed.sign_with(&alice());
+
+        assert_eq!(
+            resolved_pstt().join(signed.clone()),
+            Err(PsttError::AlreadySigned)
+        );
+        assert_eq!(signed.join(resolved_pstt()), Err(PsttError::AlreadySigned));
+    }
+
+    #[test]
+    fn join_with_different_checker_fails() {
+        let transaction = TransactionBuilder::new(TestConstraintChecker {
+            checks: false,
+            inherent: false,
+        })
+        .input(output_ref(2), Vec::new())
+        .build();
+
+        assert_eq!(
+            resolved_pstt().join(TestPstt::new(transaction, domain())),
+            Err(PsttError::DifferentTransactions)
+        );
+    }
+
+    #[test]
+    fn finalize_needs_every_signature() {
+        let mut pstt = resolved_pstt();
+        pstt.sign_with(&alice());
+
+        assert_eq!(pstt.finalize(), Err(PsttError::MissingSignatures(1)));
+    }
+
+    #[test]
+    fn finalize_needs_resolved_inputs() {
+        let transaction = TransactionBuilder::<TestOuterVerifier, _>::new(TestConstraintChecker {
+            checks: true,
+            inherent: false,
+        })
+        .input(output_ref(0), Vec::new())
+        .build();
+
+        let mut pstt = TestPstt::new(transaction, domain());
+        assert!(pstt.sign_with(&alice()).is_empty());
+        assert_eq!(pstt.finalize(), Err(PsttError::UnresolvedInput(0)));
+    }
+
+    #[test]
+    fn only_signers_can_add_signatures() {
+        let mut pstt = resolved_pstt();
+
+        assert_eq!(
+            pstt.add_signature(0, key(&bob()), vec![0; 64]),
+            Err(PsttError::UnexpectedSigner(0))
+        );
+        assert_eq!(
+            pstt.add_signature(2, key(&alice()), vec![0; 64]),
+            Err(PsttError::NoSuchInput(2))
+        );
+    }
+
+    #[test]
+    fn combining_different_transactions_fails() {
+        let mut pstt = resolved_pstt();
+        let mut other = resolved_pstt();
+        other.domain.spec_version = 2;
+
+        assert_eq!(pstt.combine(other), Err(PsttError::DifferentTransactions));
+    }
+
+    #[test]
+    fn combining_conflicting_utxos_fails() {
+        let mut pstt = resolved_pstt();
+        let mut other = resolved_pstt();
+        other
+            .resolve_input(
+                0,
+                owned_by(TestOuterVerifier::SigCheck(SigCheck::new(key(&bob())))),
+            )
+            .unwrap();
+
+        assert_eq!(pstt.combine(other), Err(PsttError::ConflictingUtxos(0)));
+    }
+
+    #[test]
+    fn bytes_round_trip() {
+        let mut pstt = resolved_pstt();
+        pstt.sign_with(&alice());
+
+        let bytes = pstt.to_bytes();
+
+        assert!(bytes.starts_with(&PSTT_MAGIC));
+        assert_eq!(TestPstt::from_bytes(&bytes), Ok(pstt));
+    }
+
+    #[test]
+    fn from_bytes_refuses_other_data() {
+        let bytes = resolved_pstt().to_bytes();
+        let mut other_version = bytes.clone();
+        other_version[PSTT_MAGIC.len()] = PSTT_VERSION + 1;
+
+        assert_eq!(
+            TestPstt::from_bytes(&bytes[PSTT_MAGIC.len()..]),
+            Err(PsttError::Malformed)
+        );
+        assert_eq!(
+            TestPstt::from_bytes(&other_version),
+            Err(PsttError::Malformed)
+        );
+        assert_eq!(
+            TestPstt::from_bytes(&bytes[..bytes.len() - 1]),
+            Err(PsttError::Malformed)
+        );
+    }
+}
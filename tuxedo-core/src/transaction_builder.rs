@@ -0,0 +1,323 @@
+//! A builder for transactions, so that wallets, tests, and other tools do not need to assemble
+//! transactions, or the payloads that verifiers check redeemers against, by hand.
+//!
+//! ```ignore
+//! let transaction = TransactionBuilder::new(MoneyConstraintChecker::Spend)
+//!     .input(coin_ref, Vec::new())
+//!     .output(Coin::<0>(90), SigCheck::new(bob))
+//!     .sign_with(&alice, &domain)
+//!     .build();
+//! ```
+
+use crate::{
+    dynamic_typing::DynamicallyTypedData,
+    types::{
+        Input, InputMode, Output, OutputRef, SigningDomain, SigningScope, Transaction,
+        TransactionVersion,
+    },
+    verifier::ScopedSignature,
+};
+use parity_scale_codec::Encode;
+use sp_core::{sr25519, Pair};
+
+/// Builds a [`Transaction`] one piece at a time.
+pub struct TransactionBuilder<V, C> {
+    transaction: Transaction<V, C>,
+}
+
+impl<V: Encode + Clone, C: Encode + Clone> TransactionBuilder<V, C> {
+    /// Start building a transaction that is checked by the given constraint checker.
+    pub fn new(checker: C) -> Self {
+        Self {
+            transaction: Transaction {
+                version: TransactionVersion::LATEST,
+                inputs: Vec::new(),
+                peeks: Vec::new(),
+                outputs: Vec::new(),
+                checker,
+                memo: None,
+                valid_until: None,
+            },
+        }
+    }
+
+    /// Encode the transaction in the given version instead of [`TransactionVersion::LATEST`].
+    pub fn version(mut self, version: TransactionVersion) -> Self {
+        self.transaction.version = version;
+        self
+    }
+
+    /// Attach a memo to the transaction. Memos need [`TransactionVersion::V2`] or later,
+    /// so an earlier version is raised to it.
+    pub fn memo(mut self, memo: Vec<u8>) -> Self {
+        self.transaction.version = self.transaction.version.max(TransactionVersion::V2);
+        self.transaction.memo = Some(memo);
+        self
+    }
+
+    /// Make the transaction invalid in blocks after the given height. Expiries need
+    /// [`TransactionVersion::V3`] or later, so an earlier version is raised to it.
+    pub fn valid_until(mut self, height: u32) -> Self {
+        self.transaction.version = self.transaction.version.max(TransactionVersion::V3);
+        self.transaction.valid_until = Some(height);
+        self
+    }
+
+    /// Consume the given output. Inputs that will be signed later with
+    /// [`sign_with`](Self::sign_with) should be given an empty redeemer.
+    pub fn input(self, output_ref: OutputRef, redeemer: Vec<u8>) -> Self {
+        self.with_input(output_ref, redeemer, InputMode::Consume)
+    }
+
+    /// Evict the given output if it is still present. See [`InputMode::Evict`].
+    pub fn evict(self, output_ref: OutputRef, redeemer: Vec<u8>) -> Self {
+        self.with_input(output_ref, redeemer, InputMode::Evict)
+    }
+
+    /// Reclaim the given output if it is still present, without a redeemer.
+    /// See [`InputMode::Reclaim`].
+    pub fn reclaim(self, output_ref: OutputRef) -> Self {
+        self.with_input(output_ref, Vec::new(), InputMode::Reclaim)
+    }
+
+    fn with_input(mut self, output_ref: OutputRef, redeemer: Vec<u8>, mode: InputMode) -> Self {
+        self.transaction.inputs.push(Input {
+            output_ref,
+            redeemer,
+            mode,
+        });
+        self
+    }
+
+    /// Read the given output without consuming it.
+    pub fn peek(mut self, output_ref: OutputRef) -> Self {
+        self.transaction.peeks.push(output_ref);
+        self
+    }
+
+    /// Create a new output with the given payload, protected by the given verifier.
+    pub fn output(
+        mut self,
+        payload: impl Into<DynamicallyTypedData>,
+        verifier: impl Into<V>,
+    ) -> Self {
+        self.transaction.outputs.push(Output {
+            payload: payload.into(),
+            verifier: verifier.into(),
+        });
+        self
+    }
+
+    /// The payload that the verifiers of this transaction's inputs check their redeemers against
+    /// on the chain described by the given domain.
+    ///
+    /// It covers the whole transaction except the redeemers, so it must only be signed once
+    /// every input, peek, and output has been added.
+    pub fn signing_payload(&self, domain: &SigningDomain) -> Vec<u8> {
+        self.transaction.signing_payload(domain)
+    }
+
+    /// Sign the transaction with the given sr25519 key for the chain described by the given domain,
+    /// as a [`SigCheck`](crate::verifier::SigCheck) expects, and use the signature as the redeemer
+    /// of every input that does not have one yet. Reclaimed inputs need no redeemer, so they are
+    /// left alone.
+    ///
+    /// Inputs that are owned by different keys can be signed by calling this once per key, after
+    /// giving the inputs of the other keys a redeemer.
+    pub fn sign_with(mut self, pair: &sr25519::Pair, domain: &SigningDomain) -> Self {
+        let signature = pair.sign(&self.signing_payload(domain));
+        for input in self.transaction.inputs.iter_mut() {
+            if input.redeemer.is_empty() && input.mode != InputMode::Reclaim {
+                input.redeemer = signature.as_ref().to_vec();
+            }
+        }
+        self
+    }
+
+    /// Sign only the given scope of the transaction with the given sr25519 key for the chain
+    /// described by the given domain, and use the signature as the redeemer of the input at the
+    /// given index, as a [`SigCheck`](crate::verifier::SigCheck) expects.
+    ///
+    /// Unlike [`sign_with`](Self::sign_with), the signature stays valid when inputs or outputs
+    /// outside of its scope are added later.
+    ///
+    /// Panics if there is no input at the index, or no output at the index when the scope
+    /// covers the matching output.
+    pub fn sign_input_with_scope(
+        mut self,
+        index: usize,
+        scope: SigningScope,
+        pair: &sr25519::Pair,
+        domain: &SigningDomain,
+    ) -> Self {
+        let payload = self
+            .transaction
+            .signing_payloads(domain)
+            .scoped(index, scope)
+            .expect("the scope only covers inputs and outputs that exist");
+        let signature = pair.sign(&payload);
+        self.transaction.inputs[index].redeemer = match scope {
+            SigningScope::ALL => signature.as_ref().to_vec(),
+            scope => ScopedSignature { signature, scope }.encode(),
+        };
+        self
+    }
+
+    /// Finish building the transaction.
+    pub fn build(self) -> Transaction<V, C> {
+        self.transaction
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        constraint_checker::testing::TestConstraintChecker,
+        dynamic_typing::testing::Bogus,
+        types::{InputScope, OutputScope},
+        verifier::{SigCheck, TestVerifier, Verifier, VerifierContext},
+    };
+
+    fn checker() -> TestConstraintChecker {
+        TestConstraintChecker {
+            checks: true,
+            inherent: false,
+        }
+    }
+
+    fn output_ref(index: u32) -> OutputRef {
+        OutputRef {
+            tx_hash: Default::default(),
+            index,
+        }
+    }
+
+    #[test]
+    fn builds_the_same_transaction_as_by_hand() {
+        let verifier = TestVerifier { verifies: true };
+        let built = TransactionBuilder::new(checker())
+            .input(output_ref(0), vec![1])
+            .evict(output_ref(1), Vec::new())
+            .reclaim(output_ref(3))
+            .peek(output_ref(2))
+            .output(Bogus, verifier.clone())
+            .build();
+
+        let by_hand = Transaction {
+            version: TransactionVersion::LATEST,
+            inputs: vec![
+                Input {
+                    output_ref: output_ref(0),
+                    redeemer: vec![1],
+                    mode: InputMode::Consume,
+                },
+                Input {
+                    output_ref: output_ref(1),
+                    redeemer: Vec::new(),
+                    mode: InputMode::Evict,
+                },
+                Input {
+                    output_ref: output_ref(3),
+                    redeemer: Vec::new(),
+                    mode: InputMode::Reclaim,
+                },
+            ],
+            peeks: vec![output_ref(2)],
+            outputs: vec![Output {
+                payload: Bogus.into(),
+                verifier,
+            }],
+            checker: checker(),
+            memo: None,
+            valid_until: None,
+        };
+        assert_eq!(built, by_hand);
+    }
+
+    #[test]
+    fn memo_raises_the_version() {
+        let built = TransactionBuilder::<TestVerifier, _>::new(checker())
+            .version(TransactionVersion::V1)
+            .memo(b"invoice 42".to_vec())
+            .build();
+
+        assert_eq!(built.version, TransactionVersion::V2);
+        assert_eq!(built.memo, Some(b"invoice 42".to_vec()));
+    }
+
+    #[test]
+    fn expiry_raises_the_version() {
+        let built = TransactionBuilder::<TestVerifier, _>::new(checker())
+            .version(TransactionVersion::V1)
+            .valid_until(100)
+            .build();
+
+        assert_eq!(built.version, TransactionVersion::V3);
+        assert_eq!(built.valid_until, Some(100));
+    }
+
+    #[test]
+    fn signing_payload_ignores_redeemers() {
+        let unsigned = TransactionBuilder::<TestVerifier, _>::new(checker())
+            .input(output_ref(0), Vec::new())
+            .signing_payload(&SigningDomain::default());
+        let signed = TransactionBuilder::<TestVerifier, _>::new(checker())
+            .input(output_ref(0), vec![1, 2, 3])
+            .signing_payload(&SigningDomain::default());
+
+        assert_eq!(unsigned, signed);
+    }
+
+    #[test]
+    fn sign_with_satisfies_sig_check() {
+        let alice = sr25519::Pair::from_seed(&[1; 32]);
+        let bob = sr25519::Pair::from_seed(&[2; 32]);
+        let builder = TransactionBuilder::<SigCheck, _>::new(checker())
+            .input(output_ref(0), Vec::new())
+            .input(output_ref(1), vec![7])
+            .output(Bogus, SigCheck::new(bob.public()));
+        let domain = SigningDomain {
+            genesis_hash: [3; 32].into(),
+            spec_version: 1,
+            transaction_version: 1,
+        };
+        let payload = builder.signing_payload(&domain);
+
+        let transaction = builder.sign_with(&alice, &domain).build();
+
+        assert!(SigCheck::new(alice.public()).verify(
+            &payload,
+            &transaction.inputs[0].redeemer,
+            &VerifierContext::default()
+        ));
+        // Inputs that already had a redeemer are left alone
+        assert_eq!(transaction.inputs[1].redeemer, vec![7]);
+    }
+
+    #[test]
+    fn scoped_signature_survives_new_inputs_and_outputs() {
+        let alice = sr25519::Pair::from_seed(&[1; 32]);
+        let bob = sr25519::Pair::from_seed(&[2; 32]);
+        let domain = SigningDomain::default();
+        let scope = SigningScope {
+            inputs: InputScope::ThisInput,
+            outputs: OutputScope::MatchingOutput,
+        };
+
+        let transaction = TransactionBuilder::<SigCheck, _>::new(checker())
+            .input(output_ref(0), Vec::new())
+            .output(Bogus, SigCheck::new(alice.public()))
+            .sign_input_with_scope(0, scope, &alice, &domain)
+            .input(output_ref(1), vec![7])
+            .output(Bogus, SigCheck::new(bob.public()))
+            .build();
+
+        assert!(SigCheck::new(alice.public()).verify_input(
+            &transaction.signing_payloads(&domain),
+            0,
+            &transaction.inputs[0].redeemer,
+            &VerifierContext::default()
+        ));
+    }
+}
@@ -0,0 +1,272 @@
+//! Support for the try-runtime CLI, which lets operators test wasm upgrades and block execution
+//! against snapshots of a live chain before enacting them.
+//!
+//! The CLI calls into the runtime through an api called `TryRuntime`. FRAME declares that api
+//! with types from FRAME crates, so Tuxedo declares its own [`TryRuntime`] api with the same name,
+//! the same methods, and types that encode the same way. This way the CLI works with Tuxedo
+//! runtimes unchanged.
+//!
+//! Besides executing blocks, try-runtime runs sanity checks of the state. Pieces provide these
+//! by implementing [`TryState`], and runtimes list them in their executive. The executive only
+//! runs them, and only exposes the try-runtime entry points, with the `try-runtime` feature.
+
+use crate::{types::Output, utxo_set::UTXO_CHILD_TRIE};
+use parity_scale_codec::{Decode, Encode};
+use scale_info::TypeInfo;
+use sp_std::{marker::PhantomData, vec::Vec};
+
+/// Which checks to run around a runtime upgrade. Encoded like FRAME's `UpgradeCheckSelect`.
+#[derive(Encode, Decode, Debug, Clone, Copy, PartialEq, Eq, TypeInfo)]
+pub enum UpgradeCheckSelect {
+    /// Run no checks.
+    None,
+    /// Run all checks.
+    All,
+    /// Run the checks before and after the migrations. Tuxedo has no migrations, so this runs nothing.
+    PreAndPost,
+    /// Run the state checks.
+    TryState,
+}
+
+impl UpgradeCheckSelect {
+    /// Whether the state checks should run.
+    pub fn try_state(&self) -> bool {
+        matches!(self, Self::All | Self::TryState)
+    }
+}
+
+/// Which state checks to run after executing a block. Encoded like FRAME's `TryStateSelect`.
+#[derive(Encode, Decode, Debug, Clone, PartialEq, Eq, TypeInfo)]
+pub enum TryStateSelect {
+    /// Run no checks.
+    None,
+    /// Run all checks.
+    All,
+    /// Run this many checks, starting from a different one at each block height.
+    RoundRobin(u32),
+    /// Run only the checks with these names.
+    Only(Vec<Vec<u8>>),
+}
+
+/// The weight reported back to the CLI. Encoded like `sp_weights::Weight`.
+///
+/// Tuxedo weights only measure time, so the proof size is always zero.
+#[derive(Encode, Decode, Debug, Default, Clone, Copy, PartialEq, Eq, TypeInfo)]
+pub struct TryRuntimeWeight {
+    #[codec(compact)]
+    pub ref_time: u64,
+    #[codec(compact)]
+    pub proof_size: u64,
+}
+
+impl From<crate::weights::Weight> for TryRuntimeWeight {
+    fn from(ref_time: crate::weights::Weight) -> Self {
+        Self {
+            ref_time,
+            proof_size: 0,
+        }
+    }
+}
+
+/// A piece's sanity checks of the state. They may be as slow as they need to be, because they
+/// only ever run in try-runtime, never on a real chain.
+pub trait TryState {
+    /// The name by which [`TryStateSelect::Only`] selects this check.
+    const NAME: &'static str;
+
+    /// Check the state, and describe the first problem found, if any.
+    fn try_state() -> Result<(), &'static str>;
+}
+
+/// The state checks of a runtime, as configured in its executive.
+///
+/// Implemented for the unit type, which has no checks, and for tuples of [`TryState`]s.
+pub trait TryStateHooks {
+    /// Run the selected checks at the given block height, in order, stopping at the first failure.
+    fn try_state(block_height: u32, select: &TryStateSelect) -> Result<(), &'static str>;
+}
+
+impl TryStateHooks for () {
+    fn try_state(_block_height: u32, _select: &TryStateSelect) -> Result<(), &'static str> {
+        Ok(())
+    }
+}
+
+macro_rules! impl_try_state_hooks_for_tuples {
+    ( $( $check:ident ),+ ) => {
+        impl<$( $check: TryState ),+> TryStateHooks for ( $( $check, )+ ) {
+            fn try_state(block_height: u32, select: &TryStateSelect) -> Result<(), &'static str> {
+                let checks: &[(&str, fn() -> Result<(), &'static str>)] =
+                    &[ $( ($check::NAME, $check::try_state) ),+ ];
+                run_selected(checks, block_height, select)
+            }
+        }
+    };
+}
+
+impl_try_state_hooks_for_tuples!(A);
+impl_try_state_hooks_for_tuples!(A, B);
+impl_try_state_hooks_for_tuples!(A, B, C);
+impl_try_state_hooks_for_tuples!(A, B, C, D);
+impl_try_state_hooks_for_tuples!(A, B, C, D, E);
+impl_try_state_hooks_for_tuples!(A, B, C, D, E, F);
+impl_try_state_hooks_for_tuples!(A, B, C, D, E, F, G);
+impl_try_state_hooks_for_tuples!(A, B, C, D, E, F, G, H);
+
+/// Run the selected checks among the given named checks.
+fn run_selected(
+    checks: &[(&str, fn() -> Result<(), &'static str>)],
+    block_height: u32,
+    select: &TryStateSelect,
+) -> Result<(), &'static str> {
+    match select {
+        TryStateSelect::None => Ok(()),
+        TryStateSelect::All => checks.iter().try_for_each(|(_, check)| check()),
+        TryStateSelect::RoundRobin(count) => {
+            let start = block_height as usize % checks.len();
+            checks
+                .iter()
+                .cycle()
+                .skip(start)
+                .take((*count as usize).min(checks.len()))
+                .try_for_each(|(_, check)| check())
+        }
+        TryStateSelect::Only(names) => checks
+            .iter()
+            .filter(|(name, _)| names.iter().any(|n| n.as_slice() == name.as_bytes()))
+            .try_for_each(|(_, check)| check()),
+    }
+}
+
+/// Checks that every value in the [`UTXO_CHILD_TRIE`] decodes as an [`Output`]. This catches
+/// migrations and upgrades that leave the utxo set undecodable.
+///
+/// It only applies to runtimes that use the [`TransparentUtxoSet`](crate::utxo_set::TransparentUtxoSet).
+pub struct UtxosDecode<V>(PhantomData<V>);
+
+impl<V: Decode> TryState for UtxosDecode<V> {
+    const NAME: &'static str = "utxos-decode";
+
+    fn try_state() -> Result<(), &'static str> {
+        let mut key = Vec::new();
+        while let Some(next) = sp_io::default_child_storage::next_key(UTXO_CHILD_TRIE, &key) {
+            let value =
+                sp_io::default_child_storage::get(UTXO_CHILD_TRIE, &next).unwrap_or_default();
+            Output::<V>::decode(&mut &value[..]).map_err(|_| "A utxo does not decode")?;
+            key = next;
+        }
+        Ok(())
+    }
+}
+
+sp_api::decl_runtime_apis! {
+    /// The api that the try-runtime CLI calls. It matches FRAME's api of the same name.
+    pub trait TryRuntime {
+        /// Pretend that the runtime was just upgraded, and run the selected checks.
+        /// Returns the weight of the upgrade and the maximum block weight.
+        fn on_runtime_upgrade(checks: UpgradeCheckSelect) -> (TryRuntimeWeight, TryRuntimeWeight);
+
+        /// Execute the given block, optionally skipping the state root check, and run the
+        /// selected state checks afterwards. Returns the weight of the block.
+        fn execute_block(
+            block: Block,
+            state_root_check: bool,
+            signature_check: bool,
+            try_state: TryStateSelect,
+        ) -> TryRuntimeWeight;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::OutputRef;
+    use std::cell::RefCell;
+
+    thread_local! {
+        static RAN: RefCell<Vec<&'static str>> = RefCell::new(Vec::new());
+    }
+
+    macro_rules! check {
+        ( $name:ident, $result:expr ) => {
+            struct $name;
+
+            impl TryState for $name {
+                const NAME: &'static str = stringify!($name);
+
+                fn try_state() -> Result<(), &'static str> {
+                    RAN.with(|ran| ran.borrow_mut().push(Self::NAME));
+                    $result
+                }
+            }
+        };
+    }
+
+    check!(First, Ok(()));
+    check!(Second, Ok(()));
+    check!(Third, Ok(()));
+    check!(Broken, Err("broken"));
+
+    type Checks = (First, Second, Third);
+
+    fn ran(block_height: u32, select: TryStateSelect) -> Vec<&'static str> {
+        RAN.with(|ran| ran.borrow_mut().clear());
+        assert_eq!(Checks::try_state(block_height, &select), Ok(()));
+        RAN.with(|ran| ran.borrow().clone())
+    }
+
+    #[test]
+    fn none_runs_nothing() {
+        assert!(ran(0, TryStateSelect::None).is_empty());
+    }
+
+    #[test]
+    fn all_runs_everything_in_order() {
+        assert_eq!(
+            ran(0, TryStateSelect::All),
+            vec!["First", "Second", "Third"]
+        );
+    }
+
+    #[test]
+    fn round_robin_rotates_with_the_block_height() {
+        assert_eq!(
+            ran(2, TryStateSelect::RoundRobin(2)),
+            vec!["Third", "First"]
+        );
+    }
+
+    #[test]
+    fn only_runs_the_named_checks() {
+        assert_eq!(
+            ran(0, TryStateSelect::Only(vec![b"Second".to_vec()])),
+            vec!["Second"]
+        );
+    }
+
+    #[test]
+    fn failures_stop_the_checks() {
+        assert_eq!(
+            <(First, Broken, Second)>::try_state(0, &TryStateSelect::All),
+            Err("broken")
+        );
+    }
+
+    #[test]
+    fn undecodable_utxo_is_caught() {
+        sp_io::TestExternalities::default().execute_with(|| {
+            assert_eq!(UtxosDecode::<u8>::try_state(), Ok(()));
+
+            let output_ref = OutputRef {
+                tx_hash: Default::default(),
+                index: 0,
+            };
+            sp_io::default_child_storage::set(UTXO_CHILD_TRIE, &output_ref.encode(), &[]);
+
+            assert_eq!(
+                UtxosDecode::<u8>::try_state(),
+                Err("A utxo does not decode")
+            );
+        });
+    }
+}
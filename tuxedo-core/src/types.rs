@@ -1,15 +1,29 @@
 //! The common types that will be used across a Tuxedo runtime, and not specific to any one piece
 
 use crate::{dynamic_typing::DynamicallyTypedData, ConstraintChecker, Verifier};
+use alloc::{format, string::String};
 use parity_scale_codec::{Decode, Encode};
 use scale_info::TypeInfo;
 use serde::{Deserialize, Serialize};
 use sp_core::H256;
-use sp_runtime::traits::Extrinsic;
-use sp_std::vec::Vec;
+use sp_runtime::{traits::Extrinsic, transaction_validity::TransactionPriority};
+use sp_std::{fmt::Debug, vec::Vec};
 
 /// A reference to a output that is expected to exist in the state.
-#[derive(Serialize, Deserialize, Encode, Decode, Debug, PartialEq, Eq, Clone, TypeInfo)]
+///
+/// An output is referred to by the hash of the transaction that created it, and its index among
+/// that transaction's outputs. Two transactions that encode the same would create outputs with
+/// the same refs, so the executive never applies the same transaction twice:
+/// * A transaction that consumes inputs can not be applied again, because its inputs are gone.
+/// * A transaction that consumes nothing, such as a mint, is marked when it is applied, and
+///   is rejected with [`UtxoError::PreExistingOutput`] from then on. Identical mints must
+///   differ somehow, for example in their payloads or constraint checkers, to both be applied.
+/// * Inherents are not marked. The pieces that create them make them unique, for example by
+///   including the block height.
+#[derive(
+    Serialize, Deserialize, Encode, Decode, Debug, PartialEq, Eq, PartialOrd, Ord, Clone, TypeInfo,
+)]
+#[cfg_attr(feature = "fuzzing", derive(arbitrary::Arbitrary))]
 pub struct OutputRef {
     /// A hash of the transaction that created this output
     pub tx_hash: H256,
@@ -29,11 +43,17 @@ pub struct OutputRef {
 ///    For example, that the total output value of a cryptocurrency transaction does not exceed its
 ///    input value. Or that a cryptokitty was created with the correct genetic material from its parents.
 ///
-/// In the future, there may be additional notions of peeks (inputs that are not consumed)
-/// and evictions (inputs that are forcefully consumed.)
-/// Existing state to be read and consumed from storage
+/// Besides the inputs that it consumes, a transaction may peek at existing state without consuming
+/// it. An input may also be an eviction (see [`InputMode`]), which consumes the utxo if it is
+/// still present, and is skipped if it is already gone.
+///
+/// Every transaction carries the [`TransactionVersion`] of the format that it is encoded in.
 #[derive(Serialize, Deserialize, Default, Debug, PartialEq, Eq, Clone, TypeInfo)]
+#[cfg_attr(feature = "fuzzing", derive(arbitrary::Arbitrary))]
 pub struct Transaction<V, C> {
+    /// The format that the transaction is encoded in
+    #[serde(default)]
+    pub version: TransactionVersion,
     /// Existing pieces of state to be read and consumed from storage
     pub inputs: Vec<Input>,
     /// Existing state to be read, but not consumed, from storage
@@ -42,6 +62,20 @@ pub struct Transaction<V, C> {
     pub outputs: Vec<Output<V>>,
     /// Which piece of constraint checking logic is used to determine whether this transaction is valid
     pub checker: C,
+    /// An optional note, such as a payment reference for an exchange or an invoice. Signatures
+    /// cover it, so it can not be stripped or changed. Constraint checkers ignore it, unless they
+    /// read it from the [`CheckerContext`](crate::CheckerContext).
+    ///
+    /// Only [`TransactionVersion::V2`] and later can carry a memo.
+    #[serde(default)]
+    pub memo: Option<Vec<u8>>,
+    /// The last block height at which the transaction may be included, if any. After that, it can
+    /// never be applied, so a signed transaction can be handed out with a deadline, for example
+    /// as a bid in an auction or one side of a swap.
+    ///
+    /// Only [`TransactionVersion::V3`] and later can expire.
+    #[serde(default)]
+    pub valid_until: Option<u32>,
 }
 
 impl<V: Clone, C: Clone> Transaction<V, C> {
@@ -50,11 +84,220 @@ impl<V: Clone, C: Clone> Transaction<V, C> {
     /// of constraint checker. This is useful when moving up and down the aggregation tree.
     pub fn transform<D: From<C>>(&self) -> Transaction<V, D> {
         Transaction {
+            version: self.version,
             inputs: self.inputs.clone(),
             peeks: self.peeks.clone(),
             outputs: self.outputs.clone(),
             checker: self.checker.clone().into(),
+            memo: self.memo.clone(),
+            valid_until: self.valid_until,
+        }
+    }
+}
+
+/// The format that a [`Transaction`] is encoded in.
+///
+/// Transactions in the legacy format, which predates versioning, start with their inputs. All
+/// later versions start with [`TRANSACTION_VERSION_MARKER`] followed by the version, which
+/// decoding dispatches on. The marker can not start a valid list of inputs, so both kinds can be
+/// told apart, and wallets that only know the legacy format keep working.
+///
+/// New fields, such as a mortality or a memo, are introduced in new versions. Each version encodes
+/// exactly the fields that it supports, so the hash of a transaction, and the payload that its
+/// signatures cover, never change once it is built.
+#[derive(
+    Serialize,
+    Deserialize,
+    Encode,
+    Decode,
+    Debug,
+    Default,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Clone,
+    Copy,
+    TypeInfo,
+)]
+#[cfg_attr(feature = "fuzzing", derive(arbitrary::Arbitrary))]
+pub enum TransactionVersion {
+    /// The original format, without a marker or version.
+    #[default]
+    Legacy,
+    /// The first versioned format, with the same fields as the legacy one.
+    V1,
+    /// Adds the memo, which is encoded after the constraint checker.
+    V2,
+    /// Adds the expiry, which is encoded after the memo.
+    V3,
+}
+
+impl TransactionVersion {
+    /// The version that new transactions are built in.
+    pub const LATEST: Self = Self::V3;
+
+    /// Whether transactions in this version can carry a memo.
+    pub fn supports_memo(&self) -> bool {
+        *self >= Self::V2
+    }
+
+    /// Whether transactions in this version can expire.
+    pub fn supports_expiry(&self) -> bool {
+        *self >= Self::V3
+    }
+}
+
+/// The byte that encoded transactions in any format but the legacy one start with. In the legacy
+/// format this would be the first byte of the compact length of the inputs, but no `u32` is
+/// encoded this way, so no legacy transaction starts with it.
+pub const TRANSACTION_VERSION_MARKER: u8 = 0xff;
+
+/// The chain and runtime that a signature is meant for. It is part of every signing payload,
+/// so a signature can not be replayed on another chain, such as a fork or a test network that
+/// shares the same output refs, nor after a runtime upgrade that changes the transaction format.
+#[derive(
+    Serialize, Deserialize, Encode, Decode, Debug, Default, PartialEq, Eq, Clone, Copy, TypeInfo,
+)]
+pub struct SigningDomain {
+    /// The hash of the chain's genesis block.
+    pub genesis_hash: H256,
+    /// The spec version of the runtime.
+    pub spec_version: u32,
+    /// The transaction version of the runtime.
+    pub transaction_version: u32,
+}
+
+impl<V: Clone + Encode, C: Clone + Encode> Transaction<V, C> {
+    /// The encoded transaction with all of its redeemers stripped, followed by the encoded
+    /// signing domain. This is the payload that verifiers check redeemers against, so it is
+    /// what wallets sign.
+    pub fn signing_payload(&self, domain: &SigningDomain) -> Vec<u8> {
+        let mut stripped = self.clone();
+        for input in stripped.inputs.iter_mut() {
+            input.redeemer = Vec::new();
+        }
+        let mut payload = stripped.encode();
+        domain.encode_to(&mut payload);
+        payload
+    }
+
+    /// The encoded parts of the transaction from which the signing payload of any input and
+    /// [`SigningScope`] can be built. The executive passes them to every verifier.
+    pub fn signing_payloads(&self, domain: &SigningDomain) -> SigningPayloads {
+        let memo = self.memo.as_ref().filter(|_| self.version.supports_memo());
+        let valid_until = self.valid_until.filter(|_| self.version.supports_expiry());
+
+        SigningPayloads {
+            all: self.signing_payload(domain),
+            inputs: self
+                .inputs
+                .iter()
+                .map(|input| {
+                    Input {
+                        redeemer: Vec::new(),
+                        ..input.clone()
+                    }
+                    .encode()
+                })
+                .collect(),
+            peeks: self.peeks.encode(),
+            outputs: self.outputs.iter().map(Encode::encode).collect(),
+            common: (self.version, &self.checker, memo, valid_until, domain).encode(),
+        }
+    }
+}
+
+/// Which inputs a signature commits to.
+#[derive(Serialize, Deserialize, Encode, Decode, Debug, PartialEq, Eq, Clone, Copy, TypeInfo)]
+#[cfg_attr(feature = "fuzzing", derive(arbitrary::Arbitrary))]
+pub enum InputScope {
+    /// Every input and peek, so none can be added or removed.
+    All,
+    /// Only the input being consumed, so others can add inputs and peeks of their own.
+    ThisInput,
+}
+
+/// Which outputs a signature commits to.
+#[derive(Serialize, Deserialize, Encode, Decode, Debug, PartialEq, Eq, Clone, Copy, TypeInfo)]
+#[cfg_attr(feature = "fuzzing", derive(arbitrary::Arbitrary))]
+pub enum OutputScope {
+    /// Every output, so none can be added or removed.
+    All,
+    /// Only the output at the same index as the input being consumed. There must be one.
+    MatchingOutput,
+}
+
+/// The parts of a transaction that a signature commits to, like the SIGHASH flags of Bitcoin.
+///
+/// A signature with a narrower scope than [`SigningScope::ALL`] stays valid when others add
+/// inputs or outputs outside of it. That lets several parties fund one transaction, or a third
+/// party bump its fee. Every signature commits to the version, the constraint checker, the memo,
+/// the expiry, and the signing domain, whatever its scope.
+#[derive(Serialize, Deserialize, Encode, Decode, Debug, PartialEq, Eq, Clone, Copy, TypeInfo)]
+#[cfg_attr(feature = "fuzzing", derive(arbitrary::Arbitrary))]
+pub struct SigningScope {
+    /// The inputs that the signature commits to.
+    pub inputs: InputScope,
+    /// The outputs that the signature commits to.
+    pub outputs: OutputScope,
+}
+
+impl SigningScope {
+    /// The whole transaction, whose payload is the plain [`Transaction::signing_payload`].
+    pub const ALL: Self = Self {
+        inputs: InputScope::All,
+        outputs: OutputScope::All,
+    };
+}
+
+/// The encoded parts of a transaction, with its redeemers stripped, that the signing payload of
+/// any input and [`SigningScope`] is built from. See [`Transaction::signing_payloads`].
+#[derive(Debug, Default, PartialEq, Eq, Clone)]
+pub struct SigningPayloads {
+    /// The payload that commits to the whole transaction.
+    all: Vec<u8>,
+    /// Each encoded input.
+    inputs: Vec<Vec<u8>>,
+    /// The encoded peeks.
+    peeks: Vec<u8>,
+    /// Each encoded output.
+    outputs: Vec<Vec<u8>>,
+    /// The encoded version, constraint checker, memo, expiry, and signing domain.
+    common: Vec<u8>,
+}
+
+impl SigningPayloads {
+    /// The payload that commits to the whole transaction. It is the same for every input.
+    pub fn all(&self) -> &[u8] {
+        &self.all
+    }
+
+    /// The payload that a signature with the given scope commits to when it consumes the input
+    /// at the given index.
+    ///
+    /// Payloads of narrower scopes start with the scope, so a signature of one scope is never
+    /// valid for another. Returns `None` if there is no such input, or no matching output.
+    pub fn scoped(&self, index: usize, scope: SigningScope) -> Option<Vec<u8>> {
+        self.inputs.get(index)?;
+        if scope == SigningScope::ALL {
+            return Some(self.all.clone());
+        }
+
+        let mut payload = scope.encode();
+        match scope.inputs {
+            InputScope::All => {
+                self.inputs.encode_to(&mut payload);
+                payload.extend_from_slice(&self.peeks);
+            }
+            InputScope::ThisInput => self.inputs[index].encode_to(&mut payload),
         }
+        match scope.outputs {
+            OutputScope::All => self.outputs.encode_to(&mut payload),
+            OutputScope::MatchingOutput => self.outputs.get(index)?.encode_to(&mut payload),
+        }
+        payload.extend_from_slice(&self.common);
+        Some(payload)
     }
 }
 
@@ -62,19 +305,43 @@ impl<V: Clone, C: Clone> Transaction<V, C> {
 // so that its encoding is the same as an opaque Vec<u8>.
 impl<V: Encode, C: Encode> Encode for Transaction<V, C> {
     fn encode_to<T: parity_scale_codec::Output + ?Sized>(&self, dest: &mut T) {
+        let version = match self.version {
+            TransactionVersion::Legacy => Vec::new(),
+            version => (TRANSACTION_VERSION_MARKER, version).encode(),
+        };
         let inputs = self.inputs.encode();
         let peeks = self.peeks.encode();
         let outputs = self.outputs.encode();
         let checker = self.checker.encode();
+        // Earlier versions have no room for a memo, so it is left out of their encoding.
+        let memo = if self.version.supports_memo() {
+            self.memo.encode()
+        } else {
+            Vec::new()
+        };
+        let valid_until = if self.version.supports_expiry() {
+            self.valid_until.encode()
+        } else {
+            Vec::new()
+        };
 
-        let total_len = (inputs.len() + outputs.len() + peeks.len() + checker.len()) as u32;
+        let total_len = (version.len()
+            + inputs.len()
+            + outputs.len()
+            + peeks.len()
+            + checker.len()
+            + memo.len()
+            + valid_until.len()) as u32;
         let size = parity_scale_codec::Compact::<u32>(total_len).encode();
 
         dest.write(&size);
+        dest.write(&version);
         dest.write(&inputs);
         dest.write(&peeks);
         dest.write(&outputs);
         dest.write(&checker);
+        dest.write(&memo);
+        dest.write(&valid_until);
     }
 }
 
@@ -85,20 +352,73 @@ impl<V: Decode, C: Decode> Decode for Transaction<V, C> {
         // Throw away the length of the vec. We just want the bytes.
         <parity_scale_codec::Compact<u32>>::skip(input)?;
 
+        // Only versioned transactions start with the marker. In legacy transactions, the byte
+        // that was read instead belongs to the inputs, so it is put back.
+        let first = input.read_byte()?;
+        let (version, first) = if first == TRANSACTION_VERSION_MARKER {
+            match TransactionVersion::decode(input)? {
+                TransactionVersion::Legacy => {
+                    return Err("Legacy transactions do not have a version".into())
+                }
+                version => (version, None),
+            }
+        } else {
+            (TransactionVersion::Legacy, Some(first))
+        };
+        let input = &mut PutBack { first, input };
+
+        // Every version has the fields of the legacy format. Later versions add theirs after them.
         let inputs = <Vec<Input>>::decode(input)?;
         let peeks = <Vec<OutputRef>>::decode(input)?;
         let outputs = <Vec<Output<V>>>::decode(input)?;
         let checker = C::decode(input)?;
+        let memo = if version.supports_memo() {
+            <Option<Vec<u8>>>::decode(input)?
+        } else {
+            None
+        };
+        let valid_until = if version.supports_expiry() {
+            <Option<u32>>::decode(input)?
+        } else {
+            None
+        };
 
         Ok(Transaction {
+            version,
             inputs,
             peeks,
             outputs,
             checker,
+            memo,
+            valid_until,
         })
     }
 }
 
+/// A codec input with a byte that was already read from it put back in front.
+struct PutBack<'a, I> {
+    first: Option<u8>,
+    input: &'a mut I,
+}
+
+impl<I: parity_scale_codec::Input> parity_scale_codec::Input for PutBack<'_, I> {
+    fn remaining_len(&mut self) -> Result<Option<usize>, parity_scale_codec::Error> {
+        let put_back = self.first.is_some() as usize;
+        Ok(self.input.remaining_len()?.map(|len| len + put_back))
+    }
+
+    fn read(&mut self, into: &mut [u8]) -> Result<(), parity_scale_codec::Error> {
+        match (self.first, into.split_first_mut()) {
+            (Some(first), Some((head, tail))) => {
+                *head = first;
+                self.first = None;
+                self.input.read(tail)
+            }
+            _ => self.input.read(into),
+        }
+    }
+}
+
 // We must implement this Extrinsic trait to use our Transaction type as the Block's Transaction type
 // See https://paritytech.github.io/substrate/master/sp_runtime/traits/trait.Block.html#associatedtype.Extrinsic
 //
@@ -140,18 +460,50 @@ where
 
 /// A reference the a utxo that will be consumed along with proof that it may be consumed
 #[derive(Serialize, Deserialize, Encode, Decode, Debug, PartialEq, Eq, Clone, TypeInfo)]
+#[cfg_attr(feature = "fuzzing", derive(arbitrary::Arbitrary))]
 pub struct Input {
     /// a reference to the output being consumed
     pub output_ref: OutputRef,
     // Eg the signature
     pub redeemer: Vec<u8>,
+    /// What to do if the output is not in the utxo set
+    #[serde(default)]
+    pub mode: InputMode,
+}
+
+/// How the executive treats an input that is not in the utxo set.
+#[derive(
+    Serialize, Deserialize, Encode, Decode, Debug, Default, PartialEq, Eq, Clone, Copy, TypeInfo,
+)]
+#[cfg_attr(feature = "fuzzing", derive(arbitrary::Arbitrary))]
+pub enum InputMode {
+    /// The input must exist. If it is missing, the transaction is invalid for now, and waits in
+    /// the pool until some other transaction creates the input.
+    #[default]
+    Consume,
+    /// The input is evicted if it exists. If it is missing, the input is skipped, and it is not
+    /// passed to the constraint checker.
+    ///
+    /// This is useful for cleanup and dispute transactions that race with other transactions that
+    /// may remove the same utxo first. An evicted input must still satisfy its verifier when it
+    /// is present.
+    Evict,
+    /// The input is reclaimed if it exists, without satisfying its verifier. If it is missing, the
+    /// input is skipped, like an eviction.
+    ///
+    /// Only constraint checkers that [reclaim](crate::ConstraintChecker::reclaims) may be called
+    /// with reclaimed inputs, and they must make sure that each one may be taken from its owner,
+    /// for example because it is dust that nobody has spent in a long time. See the
+    /// [`ages`](crate::ages) module.
+    Reclaim,
 }
 
 #[derive(Debug, PartialEq, Eq)]
 pub enum UtxoError<ConstraintCheckerError> {
     /// This transaction defines the same input multiple times
     DuplicateInput,
-    /// This transaction defines an output that already existed in the UTXO set
+    /// This transaction defines an output that already exists in the UTXO set, or that it
+    /// already created before
     PreExistingOutput,
     /// The constraint checker errored.
     ConstraintCheckerError(ConstraintCheckerError),
@@ -161,6 +513,147 @@ pub enum UtxoError<ConstraintCheckerError> {
     VerifierError,
     /// One or more of the inputs required by this transaction is not present in the UTXO set
     MissingInput,
+    /// The value burned by this transaction does not cover its fee and the deposits of its outputs
+    InsufficientFee,
+    /// This transaction is too heavy to fit in the block
+    ExhaustsBlockWeight,
+    /// This transaction has more inputs, peeks, or outputs, or more bytes,
+    /// than the runtime allows in a single transaction
+    ExceedsTransactionLimits,
+    /// This transaction is only valid until an earlier block height than this block's
+    Expired,
+}
+
+/// The name of a constraint checker error, so that clients can show it without knowing its type,
+/// for example as `Money::OutputsExceedInputs`.
+#[derive(Serialize, Deserialize, Encode, Decode, Debug, PartialEq, Eq, Clone, TypeInfo)]
+pub struct CheckerErrorName {
+    /// The name of the piece whose constraint checker errored. This is the name of the variant of
+    /// the outer constraint checker, or empty when the checker is not aggregated.
+    pub piece: String,
+    /// The name of the error, without any of the values that it carries.
+    pub error: String,
+    /// The full debug representation of the error, including the values that it carries.
+    pub details: String,
+}
+
+impl CheckerErrorName {
+    /// Name an error that does not belong to any particular piece after its debug representation.
+    ///
+    /// The name is the debug representation up to the first field, so `Overflow { by: 3 }` is
+    /// named `Overflow`. Errors that are strings are named by the whole string.
+    pub fn from_debug<E: Debug>(error: &E) -> Self {
+        let details = format!("{:?}", error);
+        let error = if details.starts_with('"') {
+            String::from(details.trim_matches('"'))
+        } else {
+            let end = details.find(['(', '{', ' ']).unwrap_or(details.len());
+            String::from(&details[..end])
+        };
+
+        Self {
+            piece: String::new(),
+            error,
+            details,
+        }
+    }
+
+    /// Attribute this error to the given piece.
+    pub fn in_piece(self, piece: &str) -> Self {
+        Self {
+            piece: String::from(piece),
+            ..self
+        }
+    }
+}
+
+impl sp_std::fmt::Display for CheckerErrorName {
+    fn fmt(&self, f: &mut sp_std::fmt::Formatter) -> sp_std::fmt::Result {
+        if self.piece.is_empty() {
+            write!(f, "{}", self.error)
+        } else {
+            write!(f, "{}::{}", self.piece, self.error)
+        }
+    }
+}
+
+/// A [`UtxoError`] that can be reported outside of the runtime, for example to show a wallet
+/// why its transaction would fail. Constraint checker errors are not required to be encodable,
+/// so they are reported by name, as given by [`ConstraintChecker::name_error`].
+#[derive(Serialize, Deserialize, Encode, Decode, Debug, PartialEq, Eq, Clone, TypeInfo)]
+pub enum DetailedError {
+    /// This transaction defines the same input multiple times
+    DuplicateInput,
+    /// This transaction defines an output that already exists in the UTXO set, or that it
+    /// already created before
+    PreExistingOutput,
+    /// The constraint checker errored with the named error.
+    ConstraintCheckerError(CheckerErrorName),
+    /// The Verifier errored.
+    VerifierError,
+    /// One or more of the inputs required by this transaction is not present in the UTXO set
+    MissingInput,
+    /// The value burned by this transaction does not cover its fee and the deposits of its outputs
+    InsufficientFee,
+    /// This transaction is too heavy to fit in the block
+    ExhaustsBlockWeight,
+    /// This transaction has more inputs, peeks, or outputs, or more bytes,
+    /// than the runtime allows in a single transaction
+    ExceedsTransactionLimits,
+    /// This transaction is only valid until an earlier block height than this block's
+    Expired,
+}
+
+impl DetailedError {
+    /// Describe an error that the executive returned for a transaction that uses the
+    /// constraint checker `C`.
+    pub fn new<V, C: ConstraintChecker<V>>(error: UtxoError<C::Error>) -> Self {
+        match error {
+            UtxoError::DuplicateInput => Self::DuplicateInput,
+            UtxoError::PreExistingOutput => Self::PreExistingOutput,
+            UtxoError::ConstraintCheckerError(e) => Self::ConstraintCheckerError(C::name_error(&e)),
+            UtxoError::VerifierError => Self::VerifierError,
+            UtxoError::MissingInput => Self::MissingInput,
+            UtxoError::InsufficientFee => Self::InsufficientFee,
+            UtxoError::ExhaustsBlockWeight => Self::ExhaustsBlockWeight,
+            UtxoError::ExceedsTransactionLimits => Self::ExceedsTransactionLimits,
+            UtxoError::Expired => Self::Expired,
+        }
+    }
+}
+
+/// The status of a transaction that was submitted through a node's `tuxedo_submitAndWatch`
+/// RPC method. Nodes report a new status whenever the transaction moves along.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub enum TransactionStatus {
+    /// The transaction is in the pool. It is either ready to be included in a block, or it is
+    /// waiting for some of its inputs to be created.
+    InPool { ready: bool },
+    /// The transaction was included in the given block.
+    InBlock { hash: H256, number: u32 },
+    /// The block that included the transaction was retracted in a reorg. The transaction is
+    /// back in the pool, or it will be reported as dropped.
+    Retracted { hash: H256 },
+    /// The block that included the transaction was finalized. This is the last status.
+    Finalized { hash: H256, number: u32 },
+    /// The transaction will never be included. This is the last status.
+    Dropped { reason: String },
+}
+
+impl TransactionStatus {
+    /// Whether no more statuses will follow this one.
+    pub fn is_final(&self) -> bool {
+        matches!(self, Self::Finalized { .. } | Self::Dropped { .. })
+    }
+}
+
+sp_api::decl_runtime_apis! {
+    /// An API for trying out transactions before submitting them.
+    pub trait TuxedoDryRunApi<V: Verifier, C: ConstraintChecker<V>> {
+        /// Check the transaction against the current state as if it were included in the next
+        /// block, without committing anything. Returns the priority the transaction would have.
+        fn dry_run(transaction: Transaction<V, C>) -> Result<TransactionPriority, DetailedError>;
+    }
 }
 
 /// The Result of dispatching a UTXO transaction.
@@ -171,6 +664,7 @@ pub type DispatchResult<VerifierError> = Result<(), UtxoError<VerifierError>>;
 /// In a cryptocurrency, the data represents a single coin. In Tuxedo, the type of
 /// the contained data is generic.
 #[derive(Serialize, Deserialize, Encode, Decode, Debug, PartialEq, Eq, Clone, TypeInfo)]
+#[cfg_attr(feature = "fuzzing", derive(arbitrary::Arbitrary))]
 pub struct Output<V> {
     pub payload: DynamicallyTypedData,
     pub verifier: V,
@@ -201,6 +695,275 @@ pub mod tests {
 
     use super::*;
 
+    fn versioned_tx(
+        version: TransactionVersion,
+    ) -> Transaction<TestVerifier, TestConstraintChecker> {
+        Transaction {
+            version,
+            inputs: vec![Input {
+                output_ref: OutputRef {
+                    tx_hash: H256::repeat_byte(1),
+                    index: 2,
+                },
+                redeemer: vec![3],
+                mode: InputMode::Consume,
+            }],
+            peeks: Vec::new(),
+            outputs: Vec::new(),
+            checker: TestConstraintChecker {
+                checks: true,
+                inherent: false,
+            },
+            memo: None,
+            valid_until: None,
+        }
+    }
+
+    #[test]
+    fn legacy_encoding_is_unversioned() {
+        let tx = versioned_tx(TransactionVersion::Legacy);
+        let body = (&tx.inputs, &tx.peeks, &tx.outputs, &tx.checker).encode();
+
+        assert_eq!(tx.encode(), body.encode());
+        assert_eq!(Transaction::decode(&mut &tx.encode()[..]).unwrap(), tx);
+    }
+
+    #[test]
+    fn versioned_encoding_starts_with_marker() {
+        let tx = versioned_tx(TransactionVersion::V1);
+        let body = (
+            TRANSACTION_VERSION_MARKER,
+            TransactionVersion::V1,
+            &tx.inputs,
+            &tx.peeks,
+            &tx.outputs,
+            &tx.checker,
+        )
+            .encode();
+
+        assert_eq!(tx.encode(), body.encode());
+        assert_eq!(Transaction::decode(&mut &tx.encode()[..]).unwrap(), tx);
+    }
+
+    #[test]
+    fn versions_encode_differently() {
+        assert_ne!(
+            versioned_tx(TransactionVersion::Legacy).encode(),
+            versioned_tx(TransactionVersion::V1).encode()
+        );
+    }
+
+    #[test]
+    fn encoding_is_opaque_in_every_version() {
+        for version in [
+            TransactionVersion::Legacy,
+            TransactionVersion::V1,
+            TransactionVersion::V2,
+            TransactionVersion::V3,
+        ] {
+            let encoded = Transaction {
+                memo: version.supports_memo().then(|| b"invoice 42".to_vec()),
+                valid_until: version.supports_expiry().then_some(100),
+                ..versioned_tx(version)
+            }
+            .encode();
+            let opaque = <Vec<u8>>::decode(&mut &encoded[..]).unwrap();
+
+            assert_eq!(opaque.encode(), encoded);
+        }
+    }
+
+    #[test]
+    fn memo_is_encoded_after_the_checker() {
+        let tx = Transaction {
+            memo: Some(b"invoice 42".to_vec()),
+            ..versioned_tx(TransactionVersion::V2)
+        };
+        let body = (
+            TRANSACTION_VERSION_MARKER,
+            TransactionVersion::V2,
+            &tx.inputs,
+            &tx.peeks,
+            &tx.outputs,
+            &tx.checker,
+            &tx.memo,
+        )
+            .encode();
+
+        assert_eq!(tx.encode(), body.encode());
+        assert_eq!(Transaction::decode(&mut &tx.encode()[..]).unwrap(), tx);
+    }
+
+    #[test]
+    fn expiry_is_encoded_after_the_memo() {
+        let tx = Transaction {
+            memo: Some(b"invoice 42".to_vec()),
+            valid_until: Some(100),
+            ..versioned_tx(TransactionVersion::V3)
+        };
+        let body = (
+            TRANSACTION_VERSION_MARKER,
+            TransactionVersion::V3,
+            &tx.inputs,
+            &tx.peeks,
+            &tx.outputs,
+            &tx.checker,
+            &tx.memo,
+            &tx.valid_until,
+        )
+            .encode();
+
+        assert_eq!(tx.encode(), body.encode());
+        assert_eq!(Transaction::decode(&mut &tx.encode()[..]).unwrap(), tx);
+    }
+
+    #[test]
+    fn expiry_is_not_encoded_before_v3() {
+        let tx = Transaction {
+            valid_until: Some(100),
+            ..versioned_tx(TransactionVersion::V2)
+        };
+
+        assert_eq!(tx.encode(), versioned_tx(TransactionVersion::V2).encode());
+    }
+
+    #[test]
+    fn memo_is_not_encoded_before_v2() {
+        let tx = Transaction {
+            memo: Some(b"invoice 42".to_vec()),
+            ..versioned_tx(TransactionVersion::V1)
+        };
+
+        assert_eq!(tx.encode(), versioned_tx(TransactionVersion::V1).encode());
+    }
+
+    #[test]
+    fn signing_payload_commits_to_the_memo() {
+        let domain = SigningDomain::default();
+        let with_memo = |memo: Option<&[u8]>| {
+            Transaction {
+                memo: memo.map(<[u8]>::to_vec),
+                ..versioned_tx(TransactionVersion::V2)
+            }
+            .signing_payload(&domain)
+        };
+
+        assert_ne!(with_memo(None), with_memo(Some(b"invoice 42")));
+        assert_ne!(
+            with_memo(Some(b"invoice 42")),
+            with_memo(Some(b"invoice 43"))
+        );
+    }
+
+    #[test]
+    fn whole_transaction_scope_is_the_signing_payload() {
+        let tx = versioned_tx(TransactionVersion::V2);
+        let domain = SigningDomain::default();
+        let payloads = tx.signing_payloads(&domain);
+
+        assert_eq!(payloads.all(), tx.signing_payload(&domain));
+        assert_eq!(
+            payloads.scoped(0, SigningScope::ALL),
+            Some(tx.signing_payload(&domain))
+        );
+    }
+
+    #[test]
+    fn scoped_payloads_commit_to_the_memo() {
+        let scope = SigningScope {
+            inputs: InputScope::ThisInput,
+            outputs: OutputScope::All,
+        };
+        let with_memo = |memo: &[u8]| {
+            Transaction {
+                memo: Some(memo.to_vec()),
+                ..versioned_tx(TransactionVersion::V2)
+            }
+            .signing_payloads(&SigningDomain::default())
+            .scoped(0, scope)
+        };
+
+        assert_ne!(with_memo(b"invoice 42"), with_memo(b"invoice 43"));
+    }
+
+    #[test]
+    fn scoped_payload_needs_the_input_and_matching_output() {
+        let payloads = versioned_tx(TransactionVersion::V2).signing_payloads(&Default::default());
+        let matching = SigningScope {
+            inputs: InputScope::All,
+            outputs: OutputScope::MatchingOutput,
+        };
+
+        assert_eq!(payloads.scoped(1, SigningScope::ALL), None);
+        assert_eq!(payloads.scoped(0, matching), None);
+    }
+
+    #[test]
+    fn unknown_version_fails_to_decode() {
+        let tx = versioned_tx(TransactionVersion::V1);
+        let mut body = (TRANSACTION_VERSION_MARKER, 9u8).encode();
+        body.extend(&tx.encode()[3..]);
+
+        assert!(Transaction::<TestVerifier, TestConstraintChecker>::decode(
+            &mut &body.encode()[..]
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn marked_legacy_version_fails_to_decode() {
+        let tx = versioned_tx(TransactionVersion::V1);
+        let mut body = (TRANSACTION_VERSION_MARKER, TransactionVersion::Legacy).encode();
+        body.extend(&tx.encode()[3..]);
+
+        assert!(Transaction::<TestVerifier, TestConstraintChecker>::decode(
+            &mut &body.encode()[..]
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn signing_payload_commits_to_the_domain() {
+        let tx: Transaction<TestVerifier, TestConstraintChecker> = Transaction {
+            version: TransactionVersion::LATEST,
+            inputs: Vec::new(),
+            peeks: Vec::new(),
+            outputs: Vec::new(),
+            checker: TestConstraintChecker {
+                checks: true,
+                inherent: false,
+            },
+            memo: None,
+            valid_until: None,
+        };
+        let domain = SigningDomain {
+            genesis_hash: H256::repeat_byte(1),
+            spec_version: 2,
+            transaction_version: 3,
+        };
+        let other_chain = SigningDomain {
+            genesis_hash: H256::repeat_byte(4),
+            ..domain
+        };
+        let other_version = SigningDomain {
+            transaction_version: 4,
+            ..domain
+        };
+
+        assert_ne!(
+            tx.signing_payload(&domain),
+            tx.signing_payload(&other_chain)
+        );
+        assert_ne!(
+            tx.signing_payload(&domain),
+            tx.signing_payload(&other_version)
+        );
+        assert_eq!(
+            tx.signing_payload(&domain),
+            [tx.encode(), domain.encode()].concat()
+        );
+    }
+
     #[test]
     fn extrinsic_no_signed_payload() {
         let checker = TestConstraintChecker {
@@ -208,10 +971,13 @@ pub mod tests {
             inherent: false,
         };
         let tx: Transaction<TestVerifier, TestConstraintChecker> = Transaction {
+            version: TransactionVersion::LATEST,
             inputs: Vec::new(),
             peeks: Vec::new(),
             outputs: Vec::new(),
             checker,
+            memo: None,
+            valid_until: None,
         };
         let e = Transaction::new(tx.clone(), None).unwrap();
 
@@ -226,10 +992,13 @@ pub mod tests {
             inherent: false,
         };
         let tx: Transaction<TestVerifier, TestConstraintChecker> = Transaction {
+            version: TransactionVersion::LATEST,
             inputs: Vec::new(),
             peeks: Vec::new(),
             outputs: Vec::new(),
             checker,
+            memo: None,
+            valid_until: None,
         };
         let e = Transaction::new(tx.clone(), Some(())).unwrap();
 
@@ -244,14 +1013,67 @@ pub mod tests {
             inherent: true,
         };
         let tx: Transaction<TestVerifier, TestConstraintChecker> = Transaction {
+            version: TransactionVersion::LATEST,
             inputs: Vec::new(),
             peeks: Vec::new(),
             outputs: Vec::new(),
             checker,
+            memo: None,
+            valid_until: None,
         };
         let e = Transaction::new(tx.clone(), Some(())).unwrap();
 
         assert_eq!(e, tx);
         assert_eq!(e.is_signed(), Some(false));
     }
+
+    #[derive(Debug)]
+    struct TooManyKitties;
+
+    #[derive(Debug)]
+    #[allow(dead_code)]
+    enum KittyError {
+        Overflow { by: u32 },
+        Named(&'static str),
+    }
+
+    #[test]
+    fn detailed_error_names_checker_error() {
+        let error = UtxoError::ConstraintCheckerError(());
+
+        assert_eq!(
+            DetailedError::new::<TestVerifier, TestConstraintChecker>(error),
+            DetailedError::ConstraintCheckerError(CheckerErrorName {
+                piece: String::new(),
+                error: String::from("()"),
+                details: String::from("()"),
+            })
+        );
+    }
+
+    #[test]
+    fn checker_error_named_after_debug_representation() {
+        let name = CheckerErrorName::from_debug(&TooManyKitties);
+        assert_eq!(name.error, "TooManyKitties");
+
+        let name = CheckerErrorName::from_debug(&KittyError::Overflow { by: 3 });
+        assert_eq!(name.error, "Overflow");
+        assert_eq!(name.details, "Overflow { by: 3 }");
+
+        let name = CheckerErrorName::from_debug(&KittyError::Named("x"));
+        assert_eq!(name.error, "Named");
+
+        let name = CheckerErrorName::from_debug(&"Not available");
+        assert_eq!(name.error, "Not available");
+    }
+
+    #[test]
+    fn checker_error_name_displays_piece() {
+        let name = CheckerErrorName::from_debug(&TooManyKitties);
+        assert_eq!(name.to_string(), "TooManyKitties");
+        assert_eq!(
+            name.in_piece("Kitties").to_string(),
+            "Kitties::TooManyKitties"
+        );
+    }
 }
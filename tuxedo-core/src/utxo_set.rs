@@ -1,8 +1,18 @@
-//! For now this is a higher level description of the default UTXO set features which Tuxedo
-//! chooses to use. Future UTXO sets could take a different form especially
-//! if being used for Zero-Knowledge. In the future it may likely be abstracted into a trait
-//! to support various UTXO set types.
+//! The UTXO set is where the executive reads the inputs and peeks of transactions from, and writes
+//! their outputs to.
 //!
+//! Tuxedo's default is the [`TransparentUtxoSet`], which stores each utxo in plain sight, keyed by
+//! its encoded `OutputRef`. Other sets, such as commitment accumulators for privacy-preserving
+//! chains, can be plugged into the executive by implementing [`UtxoSet`].
+//!
+//! The transparent set lives in its own child trie rather than among the top-level storage keys.
+//! Only the root of that child trie is in the main trie, so proofs of the other state, such as the
+//! runtime code, stay small no matter how large the set grows, and the whole set can be iterated
+//! or snapshotted without touching anything else.
+//!
+//! Because the transparent set keeps each utxo under its own storage key, a node can prove whether
+//! a utxo is in the set with an ordinary storage proof. A [`UtxoProof`] lets light clients and
+//! bridges check that against the state root of a header they trust, without syncing any state.
 
 use crate::{
     types::{Output, OutputRef},
@@ -10,35 +20,290 @@ use crate::{
     LOG_TARGET,
 };
 use parity_scale_codec::{Decode, Encode};
-use sp_std::marker::PhantomData;
+use scale_info::TypeInfo;
+use serde::{Deserialize, Serialize};
+use sp_core::H256;
+use sp_runtime::traits::BlakeTwo256;
+use sp_std::{marker::PhantomData, vec::Vec};
+use sp_storage::ChildInfo;
+use sp_trie::{LayoutV1, StorageProof};
+
+/// A storage model for utxos.
+pub trait UtxoSet<V> {
+    /// Fetch a utxo from the set.
+    fn peek_utxo(output_ref: &OutputRef) -> Option<Output<V>>;
+
+    /// Whether a utxo is in the set.
+    fn utxo_exists(output_ref: &OutputRef) -> bool {
+        Self::peek_utxo(output_ref).is_some()
+    }
+
+    /// Consume a utxo from the set, returning it if it was there.
+    fn consume_utxo(output_ref: &OutputRef) -> Option<Output<V>>;
+
+    /// Add a utxo into the set.
+    fn store_utxo(output_ref: OutputRef, output: &Output<V>);
+}
+
+/// The name of the child trie that the [`TransparentUtxoSet`] is stored in.
+pub const UTXO_CHILD_TRIE: &[u8] = b"tuxedo_utxos";
 
+/// The child trie that the [`TransparentUtxoSet`] is stored in. Nodes need this to read the set
+/// or prove what is in it.
+pub fn utxo_child_info() -> ChildInfo {
+    ChildInfo::new_default(UTXO_CHILD_TRIE)
+}
+
+/// The default utxo set, which stores each utxo in plain sight in the [`UTXO_CHILD_TRIE`],
+/// keyed by its encoded `OutputRef`.
 pub struct TransparentUtxoSet<Verifier>(PhantomData<Verifier>);
 
-impl<V: Verifier> TransparentUtxoSet<V> {
-    /// Fetch a utxo from the set.
-    pub fn peek_utxo(output_ref: &OutputRef) -> Option<Output<V>> {
-        sp_io::storage::get(&output_ref.encode()).and_then(|d| Output::decode(&mut &*d).ok())
+impl<V> TransparentUtxoSet<V> {
+    /// The key within the [`UTXO_CHILD_TRIE`] that the utxo with the given output ref is kept at.
+    pub fn storage_key(output_ref: &OutputRef) -> Vec<u8> {
+        output_ref.encode()
+    }
+}
+
+impl<V: Verifier> UtxoSet<V> for TransparentUtxoSet<V> {
+    fn peek_utxo(output_ref: &OutputRef) -> Option<Output<V>> {
+        sp_io::default_child_storage::get(UTXO_CHILD_TRIE, &Self::storage_key(output_ref))
+            .and_then(|d| Output::decode(&mut &*d).ok())
     }
 
-    /// Consume a Utxo from the set.
-    pub fn consume_utxo(output_ref: &OutputRef) -> Option<Output<V>> {
+    /// This does not need to decode the utxo.
+    fn utxo_exists(output_ref: &OutputRef) -> bool {
+        sp_io::default_child_storage::exists(UTXO_CHILD_TRIE, &Self::storage_key(output_ref))
+    }
+
+    fn consume_utxo(output_ref: &OutputRef) -> Option<Output<V>> {
         // TODO do we even need to read the stored value here? The only place we call this
         // is from `update_storage` and we don't use the value there.
         let maybe_output = Self::peek_utxo(output_ref);
-        sp_io::storage::clear(&output_ref.encode());
+        sp_io::default_child_storage::clear(UTXO_CHILD_TRIE, &Self::storage_key(output_ref));
         maybe_output
     }
 
-    /// Add a utxo into the set.
     /// This will overwrite any utxo that already exists at this OutputRef. It should never be the
     /// case that there are collisions though. Right??
-    pub fn store_utxo(output_ref: OutputRef, output: &Output<V>) {
-        let key = output_ref.encode();
+    fn store_utxo(output_ref: OutputRef, output: &Output<V>) {
+        let key = Self::storage_key(&output_ref);
         log::debug!(
             target: LOG_TARGET,
             "Storing UTXO at key: {:?}",
             sp_core::hexdisplay::HexDisplay::from(&key)
         );
-        sp_io::storage::set(&key, &output.encode());
+        sp_io::default_child_storage::set(UTXO_CHILD_TRIE, &key, &output.encode());
+    }
+}
+
+sp_api::decl_runtime_apis! {
+    /// An API for reading the UTXO set, so that wallets and explorers do not need to know
+    /// how it is laid out in storage.
+    pub trait TuxedoUtxoApi<V: Verifier> {
+        /// The utxo at the given output ref, if it exists.
+        fn utxo(output_ref: OutputRef) -> Option<Output<V>>;
+        /// Whether a utxo exists at the given output ref.
+        fn utxo_exists(output_ref: OutputRef) -> bool;
+    }
+
+    /// An API for proving what is in the UTXO set.
+    ///
+    /// A runtime never sees the trie nodes that its state is made of, so it can not build the
+    /// proof itself. Instead it tells the node which storage key to prove, and the node proves
+    /// that key's value, or its absence, against the state root of the block.
+    pub trait TuxedoUtxoProofApi {
+        /// The key within the [`UTXO_CHILD_TRIE`] that the utxo with the given output ref is kept at,
+        /// whether or not it exists.
+        fn utxo_storage_key(output_ref: OutputRef) -> Vec<u8>;
+    }
+}
+
+/// A proof that a utxo is, or is not, in the [`TransparentUtxoSet`] of some block.
+#[derive(Serialize, Deserialize, Encode, Decode, Debug, PartialEq, Eq, Clone, TypeInfo)]
+pub struct UtxoProof {
+    /// The hash of the block whose state this proof is against.
+    pub at: H256,
+    /// The output ref of the utxo that this proof is about.
+    pub output_ref: OutputRef,
+    /// The trie nodes on the path from the state root to the root of the [`UTXO_CHILD_TRIE`],
+    /// and from there to the utxo's storage key.
+    pub nodes: Vec<Vec<u8>>,
+}
+
+/// Reasons that a [`UtxoProof`] may not prove anything.
+#[derive(Serialize, Deserialize, Encode, Decode, Debug, PartialEq, Eq, Clone, TypeInfo)]
+pub enum UtxoProofError {
+    /// The nodes do not lead from the state root to the utxo's storage key, so the proof is
+    /// either for another block or incomplete. This is also the case when the root of the
+    /// child trie that the proof leads to is malformed.
+    InvalidProof,
+    /// The proof is valid, but the value it proves is not an output with the expected verifier.
+    BadlyEncodedUtxo,
+}
+
+impl UtxoProof {
+    /// Check this proof against the state root of the block it is for, which is usually read
+    /// from a finalized header.
+    ///
+    /// Returns the utxo if it is in the set, or `None` if the proof shows that it is not.
+    pub fn verify<V: Verifier>(
+        &self,
+        state_root: &H256,
+    ) -> Result<Option<Output<V>>, UtxoProofError> {
+        let db = StorageProof::new(self.nodes.iter().cloned()).into_memory_db::<BlakeTwo256>();
+
+        // First find the root of the utxo child trie in the main trie. Before the first utxo is
+        // stored, there is no child trie at all, so nothing is in the set.
+        let child_root_key = utxo_child_info().prefixed_storage_key().into_inner();
+        let child_root = match sp_trie::read_trie_value::<LayoutV1<BlakeTwo256>, _>(
+            &db,
+            state_root,
+            &child_root_key,
+            None,
+            None,
+        )
+        .map_err(|_| UtxoProofError::InvalidProof)?
+        {
+            Some(encoded) => {
+                H256::decode(&mut &encoded[..]).map_err(|_| UtxoProofError::InvalidProof)?
+            }
+            None => return Ok(None),
+        };
+
+        let key = TransparentUtxoSet::<V>::storage_key(&self.output_ref);
+        let value = sp_trie::read_trie_value::<LayoutV1<BlakeTwo256>, _>(
+            &db,
+            &child_root,
+            &key,
+            None,
+            None,
+        )
+        .map_err(|_| UtxoProofError::InvalidProof)?;
+
+        value
+            .map(|encoded| {
+                Output::decode(&mut &encoded[..]).map_err(|_| UtxoProofError::BadlyEncodedUtxo)
+            })
+            .transpose()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{dynamic_typing::testing::Bogus, verifier::TestVerifier};
+    use sp_runtime::StateVersion;
+
+    fn output_ref(index: u32) -> OutputRef {
+        OutputRef {
+            tx_hash: H256::repeat_byte(1),
+            index,
+        }
+    }
+
+    fn utxo() -> Output<TestVerifier> {
+        (Bogus, TestVerifier { verifies: true }).into()
+    }
+
+    /// Put a utxo in the set and prove the given output ref. Returns the state root and the proof.
+    fn prove(proven: OutputRef) -> (H256, UtxoProof) {
+        let mut ext = sp_io::TestExternalities::default();
+        ext.execute_with(|| {
+            TransparentUtxoSet::<TestVerifier>::store_utxo(output_ref(0), &utxo());
+            // Some more utxos so the trie has some branches
+            for index in 1..10 {
+                TransparentUtxoSet::<TestVerifier>::store_utxo(output_ref(index * 2), &utxo());
+            }
+        });
+        ext.commit_all().unwrap();
+
+        let state_root =
+            ext.execute_with(|| H256::from_slice(&sp_io::storage::root(StateVersion::V1)));
+        let key = TransparentUtxoSet::<TestVerifier>::storage_key(&proven);
+        let (_, proof) =
+            ext.execute_and_prove(|| sp_io::default_child_storage::get(UTXO_CHILD_TRIE, &key));
+
+        let proof = UtxoProof {
+            at: H256::zero(),
+            output_ref: proven,
+            nodes: proof.into_iter_nodes().collect(),
+        };
+        (state_root, proof)
+    }
+
+    #[test]
+    fn proof_of_existing_utxo_works() {
+        let (state_root, proof) = prove(output_ref(0));
+
+        assert_eq!(proof.verify::<TestVerifier>(&state_root), Ok(Some(utxo())));
+    }
+
+    #[test]
+    fn proof_of_missing_utxo_works() {
+        let (state_root, proof) = prove(output_ref(1));
+
+        assert_eq!(proof.verify::<TestVerifier>(&state_root), Ok(None));
+    }
+
+    #[test]
+    fn proof_against_other_root_fails() {
+        let (_, proof) = prove(output_ref(0));
+
+        assert_eq!(
+            proof.verify::<TestVerifier>(&H256::repeat_byte(2)),
+            Err(UtxoProofError::InvalidProof)
+        );
+    }
+
+    #[test]
+    fn proof_for_other_output_ref_does_not_prove_it() {
+        let (state_root, mut proof) = prove(output_ref(0));
+        proof.output_ref = output_ref(4);
+
+        assert_eq!(
+            proof.verify::<TestVerifier>(&state_root),
+            Err(UtxoProofError::InvalidProof)
+        );
+    }
+
+    #[test]
+    fn proof_without_nodes_fails() {
+        let (state_root, mut proof) = prove(output_ref(0));
+        proof.nodes.clear();
+
+        assert_eq!(
+            proof.verify::<TestVerifier>(&state_root),
+            Err(UtxoProofError::InvalidProof)
+        );
+    }
+
+    #[test]
+    fn proof_before_any_utxo_is_stored_works() {
+        let mut ext = sp_io::TestExternalities::default();
+        let state_root =
+            ext.execute_with(|| H256::from_slice(&sp_io::storage::root(StateVersion::V1)));
+        let key = TransparentUtxoSet::<TestVerifier>::storage_key(&output_ref(0));
+        let (_, proof) =
+            ext.execute_and_prove(|| sp_io::default_child_storage::get(UTXO_CHILD_TRIE, &key));
+        let proof = UtxoProof {
+            at: H256::zero(),
+            output_ref: output_ref(0),
+            nodes: proof.into_iter_nodes().collect(),
+        };
+
+        assert_eq!(proof.verify::<TestVerifier>(&state_root), Ok(None));
+    }
+
+    #[test]
+    fn utxos_are_not_stored_in_the_main_trie() {
+        sp_io::TestExternalities::default().execute_with(|| {
+            TransparentUtxoSet::<TestVerifier>::store_utxo(output_ref(0), &utxo());
+
+            assert!(TransparentUtxoSet::<TestVerifier>::utxo_exists(
+                &output_ref(0)
+            ));
+            assert!(!sp_io::storage::exists(&output_ref(0).encode()));
+        });
     }
 }
@@ -5,26 +5,89 @@
 //! with Tuxedo core, but downstream developers are expected to create their own as well.
 //!
 
-use parity_scale_codec::{Decode, Encode};
+use parity_scale_codec::{Decode, DecodeAll, Encode};
 use scale_info::TypeInfo;
 use serde::{Deserialize, Serialize};
+use sp_core::ed25519;
 use sp_core::sr25519::{Public, Signature};
-use sp_core::H256;
+use sp_core::{H160, H256};
 use sp_std::collections::btree_map::BTreeMap;
 use sp_std::collections::btree_set::BTreeSet;
 use sp_std::fmt::Debug;
+use sp_std::vec;
 use sp_std::vec::Vec;
 
+use crate::types::{SigningPayloads, SigningScope};
+use crate::weights::{Weight, SIGNATURE_WEIGHT};
+
 /// A means of checking that an output can be verified (aka spent). This check is made on a
 /// per-output basis and neither knows nor cares anything about the validation logic that will
 /// be applied to the transaction as a whole. Nonetheless, in order to avoid malleability, we
-/// we take the entire stripped and serialized transaction as a parameter.
-pub trait Verifier: Debug + Encode + Decode + Clone {
-    fn verify(&self, simplified_tx: &[u8], redeemer: &[u8]) -> bool;
+/// we take the entire stripped and serialized transaction as a parameter. It is followed by the
+/// [`SigningDomain`](crate::types::SigningDomain), so signatures are only valid on one chain.
+///
+/// Verifiers also receive some information about the state of the chain, so that spending
+/// conditions like timelocks and expiries are possible.
+///
+/// Verifiers must not read or write storage. When the `parallel-verification` feature is enabled,
+/// the executive verifies the inputs of a block on many threads at once, where there is no storage.
+pub trait Verifier: Debug + Encode + Decode + Clone + Send + Sync {
+    fn verify(&self, simplified_tx: &[u8], redeemer: &[u8], context: &VerifierContext) -> bool;
+
+    /// Verify the input at the given index of a transaction, given the signing payloads of all
+    /// its [`SigningScope`]s. This is what the executive calls.
+    ///
+    /// Verifiers whose redeemers may commit to only part of the transaction, like [`SigCheck`],
+    /// override this. By default, the redeemer is checked against the whole transaction with
+    /// [`verify`](Self::verify).
+    fn verify_input(
+        &self,
+        payloads: &SigningPayloads,
+        _index: usize,
+        redeemer: &[u8],
+        context: &VerifierContext,
+    ) -> bool {
+        self.verify(payloads.all(), redeemer, context)
+    }
+
+    /// The weight of the verification. Verifiers that do expensive work such as checking
+    /// signatures should override this.
+    fn weight(&self) -> Weight {
+        0
+    }
+}
+
+/// Information about the chain that is given to verifiers along with the transaction.
+#[derive(Encode, Decode, Debug, Default, PartialEq, Eq, Clone, Copy, TypeInfo)]
+pub struct VerifierContext {
+    /// The height of the block into which the transaction is being included.
+    pub block_height: u32,
+    /// The best known timestamp in milliseconds since the unix epoch, or zero if the chain does
+    /// not know the time.
+    pub timestamp: u64,
+}
+
+/// A storage key that holds the best known timestamp along with the height of the block that
+/// noted it. Unlike the header, this key is not transient. It is kept up to date with
+/// [`note_time`] by whichever piece knows the time, typically the timestamp piece.
+pub const BEST_TIME_KEY: &[u8] = b"best_time";
+
+/// Note a new best known timestamp, which was set in the block at the given height.
+pub fn note_time(time: u64, block: u32) {
+    sp_io::storage::set(BEST_TIME_KEY, &(time, block).encode());
+}
+
+/// The best known timestamp and the height of the block that noted it, if any.
+pub fn best_time() -> Option<(u64, u32)> {
+    sp_io::storage::get(BEST_TIME_KEY).and_then(|d| <(u64, u32)>::decode(&mut &*d).ok())
 }
 
 /// A typical verifier that checks an sr25519 signature
+///
+/// The redeemer is usually the bare signature of the whole transaction. It may also be a
+/// [`ScopedSignature`] instead, whose signature only commits to part of the transaction.
 #[derive(Serialize, Deserialize, Encode, Decode, Debug, PartialEq, Eq, Clone, TypeInfo)]
+#[cfg_attr(feature = "fuzzing", derive(arbitrary::Arbitrary))]
 pub struct SigCheck {
     pub owner_pubkey: H256,
 }
@@ -38,7 +101,7 @@ impl SigCheck {
 }
 
 impl Verifier for SigCheck {
-    fn verify(&self, simplified_tx: &[u8], redeemer: &[u8]) -> bool {
+    fn verify(&self, simplified_tx: &[u8], redeemer: &[u8], _context: &VerifierContext) -> bool {
         let sig = match Signature::try_from(redeemer) {
             Ok(s) => s,
             Err(_) => return false,
@@ -46,25 +109,317 @@ impl Verifier for SigCheck {
 
         sp_io::crypto::sr25519_verify(&sig, simplified_tx, &Public::from_h256(self.owner_pubkey))
     }
+
+    fn verify_input(
+        &self,
+        payloads: &SigningPayloads,
+        index: usize,
+        redeemer: &[u8],
+        context: &VerifierContext,
+    ) -> bool {
+        let Ok(ScopedSignature { signature, scope }) =
+            ScopedSignature::decode_all(&mut &redeemer[..])
+        else {
+            return self.verify(payloads.all(), redeemer, context);
+        };
+
+        // The whole transaction is only signed with a bare signature, so that there is
+        // exactly one redeemer for each signature, and nobody can change the transaction's hash.
+        if scope == SigningScope::ALL {
+            return false;
+        }
+        let Some(payload) = payloads.scoped(index, scope) else {
+            return false;
+        };
+
+        sp_io::crypto::sr25519_verify(&signature, &payload, &Public::from_h256(self.owner_pubkey))
+    }
+
+    fn weight(&self) -> Weight {
+        SIGNATURE_WEIGHT
+    }
+}
+
+/// The redeemer for a [`SigCheck`] whose signature only commits to the given scope of the
+/// transaction. It is signed over [`SigningPayloads::scoped`].
+#[derive(Serialize, Deserialize, Encode, Decode, Debug, PartialEq, Eq, Clone, TypeInfo)]
+pub struct ScopedSignature {
+    /// The signature of the scoped payload
+    pub signature: Signature,
+    /// The parts of the transaction that the signature commits to. It is never
+    /// [`SigningScope::ALL`], which takes a bare signature instead.
+    pub scope: SigningScope,
+}
+
+/// A member of a [`WeightedThresholdMultiSignature`] along with their voting power.
+#[derive(Serialize, Deserialize, Encode, Decode, Debug, PartialEq, Eq, Clone, TypeInfo)]
+#[cfg_attr(feature = "fuzzing", derive(arbitrary::Arbitrary))]
+pub struct WeightedSignatory {
+    /// The signatory's sr25519 public key
+    pub pubkey: H256,
+    /// How much the signatory's signature counts towards the threshold
+    pub weight: u32,
+}
+
+/// A weighted threshold multisignature. Like a `ThresholdMultiSignature`, except that each
+/// signatory carries a weight, and a valid redeemer must supply valid signatures from signatories
+/// whose weights add up to at least the threshold. If the threshold is greater than the total
+/// weight of the signatories, the input can never be consumed.
+///
+/// The redeemer is the same as for a `ThresholdMultiSignature`: a list of [`SignatureAndIndex`].
+#[derive(Serialize, Deserialize, Encode, Decode, Debug, PartialEq, Eq, Clone, TypeInfo)]
+#[cfg_attr(feature = "fuzzing", derive(arbitrary::Arbitrary))]
+pub struct WeightedThresholdMultiSignature {
+    /// The minimum total weight of valid signatures needed to consume this input
+    pub threshold: u64,
+    /// All the member signatories and their weights. This should include no duplicates
+    pub signatories: Vec<WeightedSignatory>,
+}
+
+impl WeightedThresholdMultiSignature {
+    pub fn new(threshold: u64, signatories: Vec<WeightedSignatory>) -> Self {
+        WeightedThresholdMultiSignature {
+            threshold,
+            signatories,
+        }
+    }
+
+    pub fn has_duplicate_signatories(&self) -> bool {
+        let set: BTreeSet<_> = self.signatories.iter().map(|s| s.pubkey).collect();
+        set.len() < self.signatories.len()
+    }
+}
+
+impl Verifier for WeightedThresholdMultiSignature {
+    fn verify(&self, simplified_tx: &[u8], redeemer: &[u8], _context: &VerifierContext) -> bool {
+        if self.has_duplicate_signatories() {
+            return false;
+        }
+
+        let sigs = match Vec::<SignatureAndIndex>::decode(&mut &redeemer[..]) {
+            Ok(s) => s,
+            Err(_) => return false,
+        };
+
+        // Each signatory may only be counted once
+        let indices: BTreeSet<u8> = sigs.iter().map(|sig| sig.index).collect();
+        if indices.len() < sigs.len() {
+            return false;
+        }
+
+        let mut total_weight: u64 = 0;
+        for sig in &sigs {
+            let signatory = match self.signatories.get(sig.index as usize) {
+                Some(s) => s,
+                None => return false,
+            };
+            if !sp_io::crypto::sr25519_verify(
+                &sig.signature,
+                simplified_tx,
+                &Public::from_h256(signatory.pubkey),
+            ) {
+                return false;
+            }
+            total_weight += u64::from(signatory.weight);
+        }
+
+        total_weight >= self.threshold
+    }
+
+    fn weight(&self) -> Weight {
+        SIGNATURE_WEIGHT.saturating_mul(self.signatories.len() as Weight)
+    }
+}
+
+/// Pay to public key hash. A verifier that stores only the hash of the owner's sr25519 public key.
+///
+/// This keeps outputs small and does not reveal the owner's key until they spend the output.
+/// A valid redeemer is an encoded [`PubkeyAndSignature`] whose public key hashes to the stored hash.
+#[allow(clippy::upper_case_acronyms)]
+#[derive(Serialize, Deserialize, Encode, Decode, Debug, PartialEq, Eq, Clone, TypeInfo)]
+#[cfg_attr(feature = "fuzzing", derive(arbitrary::Arbitrary))]
+pub struct P2PKH {
+    /// The blake2-256 hash of the owner's public key
+    pub owner_pubkey_hash: H256,
+}
+
+impl P2PKH {
+    /// Create a verifier for the owner with the given public key. Only its hash is stored.
+    pub fn new<T: Into<H256>>(owner_pubkey: T) -> Self {
+        P2PKH {
+            owner_pubkey_hash: sp_io::hashing::blake2_256(owner_pubkey.into().as_bytes()).into(),
+        }
+    }
+}
+
+/// The redeemer for a [`P2PKH`] verifier. The full public key is only revealed at spend time.
+#[derive(Serialize, Deserialize, Encode, Decode, Debug, PartialEq, Eq, Clone, TypeInfo)]
+pub struct PubkeyAndSignature {
+    /// The owner's public key
+    pub pubkey: H256,
+    /// The owner's signature over the simplified transaction
+    pub signature: Signature,
+}
+
+impl Verifier for P2PKH {
+    fn verify(&self, simplified_tx: &[u8], redeemer: &[u8], _context: &VerifierContext) -> bool {
+        let PubkeyAndSignature { pubkey, signature } =
+            match PubkeyAndSignature::decode(&mut &redeemer[..]) {
+                Ok(r) => r,
+                Err(_) => return false,
+            };
+
+        if H256::from(sp_io::hashing::blake2_256(pubkey.as_bytes())) != self.owner_pubkey_hash {
+            return false;
+        }
+
+        sp_io::crypto::sr25519_verify(&signature, simplified_tx, &Public::from_h256(pubkey))
+    }
+
+    fn weight(&self) -> Weight {
+        SIGNATURE_WEIGHT
+    }
+}
+
+/// A verifier that checks an ed25519 signature. This allows UTXOs to be owned by
+/// keys from tooling that is built around ed25519 rather than sr25519.
+#[derive(Serialize, Deserialize, Encode, Decode, Debug, PartialEq, Eq, Clone, TypeInfo)]
+#[cfg_attr(feature = "fuzzing", derive(arbitrary::Arbitrary))]
+pub struct Ed25519SigCheck {
+    pub owner_pubkey: H256,
+}
+
+impl Ed25519SigCheck {
+    pub fn new<T: Into<H256>>(value: T) -> Self {
+        Ed25519SigCheck {
+            owner_pubkey: value.into(),
+        }
+    }
+}
+
+impl Verifier for Ed25519SigCheck {
+    fn verify(&self, simplified_tx: &[u8], redeemer: &[u8], _context: &VerifierContext) -> bool {
+        let sig = match ed25519::Signature::try_from(redeemer) {
+            Ok(s) => s,
+            Err(_) => return false,
+        };
+
+        sp_io::crypto::ed25519_verify(
+            &sig,
+            simplified_tx,
+            &ed25519::Public::from_raw(self.owner_pubkey.0),
+        )
+    }
+
+    fn weight(&self) -> Weight {
+        SIGNATURE_WEIGHT
+    }
+}
+
+/// The hash that Ethereum wallets sign when asked to sign the given simplified transaction.
+///
+/// The transaction is hashed with keccak-256, and that hash is signed as a message following
+/// EIP-191 (`personal_sign`), so existing wallets and hardware devices can produce the signature.
+pub fn ethereum_signing_hash(simplified_tx: &[u8]) -> [u8; 32] {
+    let mut message = b"\x19Ethereum Signed Message:\n32".to_vec();
+    message.extend_from_slice(&sp_io::hashing::keccak_256(simplified_tx));
+    sp_io::hashing::keccak_256(&message)
+}
+
+/// Half the order of the secp256k1 curve. Signatures whose `s` is above it are rejected, because
+/// negating `s` and flipping the recovery id gives a second valid signature from the same key.
+const SECP256K1_HALF_ORDER: [u8; 32] = [
+    0x7f, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+    0x5d, 0x57, 0x6e, 0x73, 0x57, 0xa4, 0x50, 0x1d, 0xdf, 0xe9, 0x2f, 0x46, 0x68, 0x1b, 0x20, 0xa0,
+];
+
+/// A verifier that checks a secp256k1 ECDSA signature from an Ethereum account.
+///
+/// Only the 20 byte address is stored. The redeemer is a 65 byte recoverable signature
+/// over the [`ethereum_signing_hash`] of the transaction. The public key is recovered from the
+/// signature, and it must hash to the owner's address in the same way Ethereum addresses do.
+///
+/// Each signature has exactly one accepted encoding, so that nobody but the signer can change the
+/// redeemer, and with it the hash of the transaction. The recovery id must be offset by 27, as
+/// Ethereum wallets produce it, and `s` must be in the lower half of the curve order.
+#[derive(Serialize, Deserialize, Encode, Decode, Debug, PartialEq, Eq, Clone, TypeInfo)]
+#[cfg_attr(feature = "fuzzing", derive(arbitrary::Arbitrary))]
+pub struct EthereumSigCheck {
+    pub owner_address: H160,
+}
+
+impl EthereumSigCheck {
+    pub fn new<T: Into<H160>>(value: T) -> Self {
+        EthereumSigCheck {
+            owner_address: value.into(),
+        }
+    }
+}
+
+impl Verifier for EthereumSigCheck {
+    fn verify(&self, simplified_tx: &[u8], redeemer: &[u8], _context: &VerifierContext) -> bool {
+        let mut sig: [u8; 65] = match redeemer.try_into() {
+            Ok(s) => s,
+            Err(_) => return false,
+        };
+        sig[64] = match sig[64] {
+            27 | 28 => sig[64] - 27,
+            _ => return false,
+        };
+        // Byte arrays compare lexicographically, which is numerically for big endian integers.
+        if sig[32..64] > SECP256K1_HALF_ORDER[..] {
+            return false;
+        }
+
+        let pubkey = match sp_io::crypto::secp256k1_ecdsa_recover(
+            &sig,
+            &ethereum_signing_hash(simplified_tx),
+        ) {
+            Ok(p) => p,
+            Err(_) => return false,
+        };
+
+        H160::from_slice(&sp_io::hashing::keccak_256(&pubkey)[12..]) == self.owner_address
+    }
+
+    fn weight(&self) -> Weight {
+        SIGNATURE_WEIGHT
+    }
 }
 
 /// A simple verifier that allows anyone to consume an output at any time
 #[derive(
     Serialize, Deserialize, Encode, Decode, Debug, PartialEq, Eq, Clone, TypeInfo, Default,
 )]
+#[cfg_attr(feature = "fuzzing", derive(arbitrary::Arbitrary))]
 pub struct UpForGrabs;
 
 impl Verifier for UpForGrabs {
-    fn verify(&self, _simplified_tx: &[u8], _redeemer: &[u8]) -> bool {
+    fn verify(&self, _simplified_tx: &[u8], _redeemer: &[u8], _context: &VerifierContext) -> bool {
         true
     }
 }
 
+/// A verifier that never allows an output to be consumed. Outputs that carry data, rather than
+/// value, may be locked with it to prove that they can never be spent.
+#[derive(
+    Serialize, Deserialize, Encode, Decode, Debug, PartialEq, Eq, Clone, TypeInfo, Default,
+)]
+#[cfg_attr(feature = "fuzzing", derive(arbitrary::Arbitrary))]
+pub struct Unspendable;
+
+impl Verifier for Unspendable {
+    fn verify(&self, _simplified_tx: &[u8], _redeemer: &[u8], _context: &VerifierContext) -> bool {
+        false
+    }
+}
+
 /// A Threshold multisignature. Some number of member signatories collectively own inputs
 /// guarded by this verifier. A valid redeemer must supply valid signatures by at least
 /// `threshold` of the signatories. If the threshold is greater than the number of signatories
 /// the input can never be consumed.
 #[derive(Serialize, Deserialize, Encode, Decode, Debug, PartialEq, Eq, Clone, TypeInfo)]
+#[cfg_attr(feature = "fuzzing", derive(arbitrary::Arbitrary))]
 pub struct ThresholdMultiSignature {
     /// The minimum number of valid signatures needed to consume this input
     pub threshold: u8,
@@ -88,7 +443,7 @@ impl ThresholdMultiSignature {
     }
 }
 
-#[derive(Serialize, Deserialize, Encode, Decode, Debug, PartialEq, Eq, Clone)]
+#[derive(Serialize, Deserialize, Encode, Decode, Debug, PartialEq, Eq, Clone, TypeInfo)]
 /// Combination of a signature plus and index so that the signer can specify which
 /// index this signature pertains too of the available signatories for a `ThresholdMultiSignature`
 pub struct SignatureAndIndex {
@@ -99,7 +454,7 @@ pub struct SignatureAndIndex {
 }
 
 impl Verifier for ThresholdMultiSignature {
-    fn verify(&self, simplified_tx: &[u8], redeemer: &[u8]) -> bool {
+    fn verify(&self, simplified_tx: &[u8], redeemer: &[u8], _context: &VerifierContext) -> bool {
         if self.has_duplicate_signatories() {
             return false;
         }
@@ -115,7 +470,9 @@ impl Verifier for ThresholdMultiSignature {
 
         {
             // Check range of indicies
-            let index_out_of_bounds = sigs.iter().any(|sig| sig.index as usize >= sigs.len());
+            let index_out_of_bounds = sigs
+                .iter()
+                .any(|sig| sig.index as usize >= self.signatories.len());
             if index_out_of_bounds {
                 return false;
             }
@@ -132,248 +489,2005 @@ impl Verifier for ThresholdMultiSignature {
             }
         }
 
-        let valid_sigs: Vec<_> = sigs
+        let valid_sigs = sigs
             .iter()
-            .map(|sig| {
+            .filter(|sig| {
                 sp_io::crypto::sr25519_verify(
                     &sig.signature,
                     simplified_tx,
                     &Public::from_h256(self.signatories[sig.index as usize]),
-                );
+                )
             })
-            .collect();
+            .count();
 
-        valid_sigs.len() >= self.threshold.into()
+        valid_sigs >= self.threshold.into()
+    }
+
+    fn weight(&self) -> Weight {
+        SIGNATURE_WEIGHT.saturating_mul(self.signatories.len() as Weight)
     }
 }
 
-/// A testing verifier that passes or depending on the enclosed
-/// boolean value.
-#[cfg(feature = "std")]
+/// A hash lock, optionally combined with another verifier. The output can be consumed by
+/// revealing a preimage of the hash, as long as the inner verifier is also satisfied.
+///
+/// The hash is SHA-256 so that the same secret can lock outputs on other chains, as in atomic
+/// swaps. Once a preimage is revealed, for example in the transaction pool, anyone can use it.
+/// So a standalone hash lock (whose inner verifier is `UpForGrabs`) should only guard outputs
+/// that may go to whoever reveals the preimage first. Combine it with a signature check
+/// to make sure only the intended recipient can consume the output.
 #[derive(Serialize, Deserialize, Encode, Decode, Debug, PartialEq, Eq, Clone, TypeInfo)]
-pub struct TestVerifier {
-    /// Whether the verifier should pass
-    pub verifies: bool,
+#[cfg_attr(feature = "fuzzing", derive(arbitrary::Arbitrary))]
+pub struct HashLock<V = UpForGrabs> {
+    /// The SHA-256 hash whose preimage must be revealed
+    pub hash_lock: H256,
+    /// The verifier that must also be satisfied
+    pub inner: V,
 }
 
-#[cfg(feature = "std")]
-impl Verifier for TestVerifier {
-    fn verify(&self, _simplified_tx: &[u8], _redeemer: &[u8]) -> bool {
-        self.verifies
+impl HashLock<UpForGrabs> {
+    /// A standalone hash lock that anyone who knows the preimage can consume.
+    pub fn new(hash_lock: H256) -> Self {
+        HashLock {
+            hash_lock,
+            inner: UpForGrabs,
+        }
     }
 }
 
-#[cfg(test)]
-mod test {
-    use super::*;
-    use sp_core::{crypto::Pair as _, sr25519::Pair};
+impl<V: Verifier> HashLock<V> {
+    /// A hash lock that can only be consumed if the inner verifier is also satisfied.
+    pub fn with_inner(hash_lock: H256, inner: V) -> Self {
+        HashLock { hash_lock, inner }
+    }
+}
 
-    /// Generate a bunch of test keypairs
-    fn generate_n_pairs(n: u8) -> Vec<Pair> {
-        let mut seed = [0u8; 32];
-        let mut pairs = Vec::new();
+/// The redeemer for a [`HashLock`] verifier.
+#[derive(Serialize, Deserialize, Encode, Decode, Debug, PartialEq, Eq, Clone, TypeInfo)]
+pub struct PreimageAndRedeemer {
+    /// The preimage of the hash lock
+    pub preimage: Vec<u8>,
+    /// The redeemer that is passed on to the inner verifier
+    pub inner: Vec<u8>,
+}
 
-        // We generate the pairs from sequential seeds. Just changing the last byte of the seed each time.
-        for i in 0..n {
-            seed[31] = i;
+impl<V: Verifier> HashLock<V> {
+    /// The redeemer for the inner verifier, if the given redeemer reveals the preimage.
+    fn unlock(&self, redeemer: &[u8]) -> Option<Vec<u8>> {
+        let PreimageAndRedeemer { preimage, inner } =
+            PreimageAndRedeemer::decode(&mut &redeemer[..]).ok()?;
 
-            let pair = Pair::from_seed(&seed);
-            pairs.push(pair);
-        }
+        (H256(sp_io::hashing::sha2_256(&preimage)) == self.hash_lock).then_some(inner)
+    }
+}
 
-        pairs
+impl<V: Verifier> Verifier for HashLock<V> {
+    fn verify(&self, simplified_tx: &[u8], redeemer: &[u8], context: &VerifierContext) -> bool {
+        self.unlock(redeemer)
+            .is_some_and(|inner| self.inner.verify(simplified_tx, &inner, context))
     }
 
-    #[test]
-    fn up_for_grabs_always_verifies() {
-        assert!(UpForGrabs.verify(&[], &[]))
+    fn verify_input(
+        &self,
+        payloads: &SigningPayloads,
+        index: usize,
+        redeemer: &[u8],
+        context: &VerifierContext,
+    ) -> bool {
+        self.unlock(redeemer)
+            .is_some_and(|inner| self.inner.verify_input(payloads, index, &inner, context))
     }
 
-    #[test]
-    fn sig_check_with_good_sig() {
-        let pair = Pair::from_seed(&[0u8; 32]);
-        let simplified_tx = b"hello world".as_slice();
-        let sig = pair.sign(simplified_tx);
-        let redeemer: &[u8] = sig.as_ref();
+    fn weight(&self) -> Weight {
+        self.inner.weight()
+    }
+}
 
-        let sig_check = SigCheck {
-            owner_pubkey: pair.public().into(),
-        };
+/// A timelock that wraps another verifier. The output can not be consumed before the chain
+/// reaches the `not_before` block height. After that, it can be consumed whenever the inner
+/// verifier is satisfied, and the redeemer is passed straight through to it.
+#[derive(Serialize, Deserialize, Encode, Decode, Debug, PartialEq, Eq, Clone, TypeInfo)]
+#[cfg_attr(feature = "fuzzing", derive(arbitrary::Arbitrary))]
+pub struct HeightLock<V> {
+    /// The verifier that must be satisfied once the lock has expired
+    pub inner: V,
+    /// The first block height at which the output can be consumed
+    pub not_before: u32,
+}
 
-        assert!(sig_check.verify(simplified_tx, redeemer));
+impl<V: Verifier> HeightLock<V> {
+    pub fn new(inner: V, not_before: u32) -> Self {
+        HeightLock { inner, not_before }
     }
+}
 
-    #[test]
-    fn threshold_multisig_with_enough_sigs_passes() {
-        let threshold = 2;
-        let pairs = generate_n_pairs(threshold);
+impl<V: Verifier> Verifier for HeightLock<V> {
+    fn verify(&self, simplified_tx: &[u8], redeemer: &[u8], context: &VerifierContext) -> bool {
+        context.block_height >= self.not_before
+            && self.inner.verify(simplified_tx, redeemer, context)
+    }
 
-        let signatories: Vec<H256> = pairs.iter().map(|p| H256::from(p.public())).collect();
+    fn verify_input(
+        &self,
+        payloads: &SigningPayloads,
+        index: usize,
+        redeemer: &[u8],
+        context: &VerifierContext,
+    ) -> bool {
+        context.block_height >= self.not_before
+            && self.inner.verify_input(payloads, index, redeemer, context)
+    }
 
-        let simplified_tx = b"hello_world".as_slice();
-        let sigs: Vec<_> = pairs
-            .iter()
-            .enumerate()
-            .map(|(i, p)| SignatureAndIndex {
-                signature: p.sign(simplified_tx),
-                index: i.try_into().unwrap(),
-            })
-            .collect();
+    fn weight(&self) -> Weight {
+        self.inner.weight()
+    }
+}
 
-        let redeemer: &[u8] = &sigs.encode()[..];
-        let threshold_multisig = ThresholdMultiSignature {
-            threshold,
-            signatories,
-        };
+/// An expiry that wraps another verifier. Until the chain reaches the `expires_at` block height,
+/// the output can only be consumed when the inner verifier is satisfied, and the redeemer is passed
+/// straight through to it. From then on, anyone may consume it.
+///
+/// This lets the creator of an output mark it as temporary, so that anyone may clean it up and
+/// keep the value if its owner never does.
+#[derive(Serialize, Deserialize, Encode, Decode, Debug, PartialEq, Eq, Clone, TypeInfo)]
+#[cfg_attr(feature = "fuzzing", derive(arbitrary::Arbitrary))]
+pub struct Expiring<V> {
+    /// The verifier that must be satisfied until the output expires
+    pub inner: V,
+    /// The first block height at which anyone can consume the output
+    pub expires_at: u32,
+}
 
-        assert!(threshold_multisig.verify(simplified_tx, redeemer));
+impl<V: Verifier> Expiring<V> {
+    pub fn new(inner: V, expires_at: u32) -> Self {
+        Expiring { inner, expires_at }
     }
+}
 
-    #[test]
-    fn threshold_multisig_not_enough_sigs_fails() {
-        let threshold = 3;
-        let pairs = generate_n_pairs(threshold);
+impl<V: Verifier> Verifier for Expiring<V> {
+    fn verify(&self, simplified_tx: &[u8], redeemer: &[u8], context: &VerifierContext) -> bool {
+        context.block_height >= self.expires_at
+            || self.inner.verify(simplified_tx, redeemer, context)
+    }
 
-        let signatories: Vec<H256> = pairs.iter().map(|p| H256::from(p.public())).collect();
+    fn verify_input(
+        &self,
+        payloads: &SigningPayloads,
+        index: usize,
+        redeemer: &[u8],
+        context: &VerifierContext,
+    ) -> bool {
+        context.block_height >= self.expires_at
+            || self.inner.verify_input(payloads, index, redeemer, context)
+    }
 
-        let simplified_tx = b"hello_world".as_slice();
-        let sigs: Vec<_> = pairs
-            .iter()
-            .take(threshold as usize - 1)
-            .enumerate()
-            .map(|(i, p)| SignatureAndIndex {
-                signature: p.sign(simplified_tx),
-                index: i.try_into().unwrap(),
-            })
-            .collect();
+    fn weight(&self) -> Weight {
+        self.inner.weight()
+    }
+}
 
-        let redeemer: &[u8] = &sigs.encode()[..];
-        let threshold_multisig = ThresholdMultiSignature {
-            threshold,
-            signatories,
-        };
+/// The kinds of transactions that a delegate of a [`Proxy`] may sign.
+///
+/// Verifiers can not decode the transaction that they are checking, because it is generic over
+/// the runtime's verifiers and constraint checkers. So each runtime defines its own scopes, and
+/// decodes its own transaction type from the bytes that the verifier received.
+pub trait ProxyScope: Debug + Encode + Decode + Clone + PartialEq + Send + Sync {
+    /// Whether a delegate with this scope may sign the given transaction on behalf of the proxy.
+    ///
+    /// The transaction is passed exactly as the verifier received it, that is the stripped
+    /// transaction followed by the signing domain.
+    fn allows(&self, proxy: &Proxy<Self>, simplified_tx: &[u8]) -> bool;
+}
+
+/// A key that may sign for a [`Proxy`], but only within its scope.
+#[derive(Serialize, Deserialize, Encode, Decode, Debug, PartialEq, Eq, Clone, TypeInfo)]
+#[cfg_attr(feature = "fuzzing", derive(arbitrary::Arbitrary))]
+pub struct Delegate<S> {
+    /// The delegate's sr25519 public key
+    pub pubkey: H256,
+    /// The kinds of transactions that the delegate may sign
+    pub scope: S,
+}
+
+/// An owner key along with some delegate keys. The owner may sign any transaction, while each
+/// delegate may only sign the transactions that its [`ProxyScope`] allows.
+#[derive(Serialize, Deserialize, Encode, Decode, Debug, PartialEq, Eq, Clone, TypeInfo)]
+#[cfg_attr(feature = "fuzzing", derive(arbitrary::Arbitrary))]
+pub struct Proxy<S> {
+    /// The owner's sr25519 public key
+    pub owner: H256,
+    /// The delegates that may sign within their scopes
+    pub delegates: Vec<Delegate<S>>,
+}
 
-        assert!(!threshold_multisig.verify(simplified_tx, redeemer));
+impl<S: ProxyScope> Proxy<S> {
+    pub fn new<T: Into<H256>>(owner: T, delegates: Vec<Delegate<S>>) -> Self {
+        Proxy {
+            owner: owner.into(),
+            delegates,
+        }
     }
+}
 
-    #[test]
-    fn threshold_multisig_extra_sigs_still_passes() {
-        let threshold = 2;
-        let pairs = generate_n_pairs(threshold + 1);
+/// The redeemer for a [`Proxy`] verifier, which indicates who signed.
+#[derive(Serialize, Deserialize, Encode, Decode, Debug, PartialEq, Eq, Clone, TypeInfo)]
+pub enum ProxyRedeemer {
+    /// The owner's signature over the transaction
+    Owner(Signature),
+    /// The signature of the delegate at the given index over the transaction
+    Delegate { index: u8, signature: Signature },
+}
 
-        let signatories: Vec<H256> = pairs.iter().map(|p| H256::from(p.public())).collect();
+impl<S: ProxyScope> Verifier for Proxy<S> {
+    fn verify(&self, simplified_tx: &[u8], redeemer: &[u8], _context: &VerifierContext) -> bool {
+        let redeemer = match ProxyRedeemer::decode(&mut &redeemer[..]) {
+            Ok(r) => r,
+            Err(_) => return false,
+        };
 
-        let simplified_tx = b"hello_world".as_slice();
-        let sigs: Vec<_> = pairs
-            .iter()
-            .enumerate()
-            .map(|(i, p)| SignatureAndIndex {
-                signature: p.sign(simplified_tx),
-                index: i.try_into().unwrap(),
-            })
-            .collect();
+        match redeemer {
+            ProxyRedeemer::Owner(signature) => sp_io::crypto::sr25519_verify(
+                &signature,
+                simplified_tx,
+                &Public::from_h256(self.owner),
+            ),
+            ProxyRedeemer::Delegate { index, signature } => {
+                let delegate = match self.delegates.get(index as usize) {
+                    Some(d) => d,
+                    None => return false,
+                };
+                delegate.scope.allows(self, simplified_tx)
+                    && sp_io::crypto::sr25519_verify(
+                        &signature,
+                        simplified_tx,
+                        &Public::from_h256(delegate.pubkey),
+                    )
+            }
+        }
+    }
 
-        let redeemer: &[u8] = &sigs.encode()[..];
-        let threshold_multisig = ThresholdMultiSignature {
+    fn weight(&self) -> Weight {
+        SIGNATURE_WEIGHT
+    }
+}
+
+/// The most items that a [`Script`]'s stack may hold at any time.
+pub const MAX_SCRIPT_STACK: usize = 64;
+
+/// A single operation in a [`Script`].
+///
+/// Booleans are represented on the stack like in Bitcoin script. False is the empty item, true is
+/// `[1]`, and any item with a non-zero byte is considered true.
+#[derive(Serialize, Deserialize, Encode, Decode, Debug, PartialEq, Eq, Clone, TypeInfo)]
+#[cfg_attr(feature = "fuzzing", derive(arbitrary::Arbitrary))]
+pub enum Op {
+    /// Push the given data onto the stack.
+    Push(Vec<u8>),
+    /// Duplicate the top item of the stack.
+    Dup,
+    /// Remove the top item of the stack.
+    Drop,
+    /// Replace the top item with its SHA-256 hash.
+    Sha256,
+    /// Replace the top item with its blake2-256 hash.
+    Blake2,
+    /// Replace the top two items with whether they are equal.
+    Equal,
+    /// Pop a public key, then a signature, and push whether it is a valid sr25519 signature by
+    /// that key over the simplified transaction.
+    CheckSig,
+    /// Push whether the chain has reached the given block height.
+    CheckHeight(u32),
+    /// Replace the top two items with whether they are both true.
+    And,
+    /// Replace the top two items with whether either of them is true.
+    Or,
+    /// Replace the top item with its negation.
+    Not,
+    /// Pop the top item, and fail the script immediately unless it is true.
+    Verify,
+}
+
+fn script_bool(value: bool) -> Vec<u8> {
+    if value {
+        vec![1]
+    } else {
+        Vec::new()
+    }
+}
+
+fn script_truth(item: &[u8]) -> bool {
+    item.iter().any(|byte| *byte != 0)
+}
+
+impl Op {
+    /// Apply this operation to the stack. Returns `None` if the script fails.
+    fn apply(
+        &self,
+        stack: &mut Vec<Vec<u8>>,
+        simplified_tx: &[u8],
+        context: &VerifierContext,
+    ) -> Option<()> {
+        match self {
+            Op::Push(data) => stack.push(data.clone()),
+            Op::Dup => {
+                let top = stack.last()?.clone();
+                stack.push(top);
+            }
+            Op::Drop => {
+                stack.pop()?;
+            }
+            Op::Sha256 => {
+                let item = stack.pop()?;
+                stack.push(sp_io::hashing::sha2_256(&item).to_vec());
+            }
+            Op::Blake2 => {
+                let item = stack.pop()?;
+                stack.push(sp_io::hashing::blake2_256(&item).to_vec());
+            }
+            Op::Equal => {
+                let (a, b) = (stack.pop()?, stack.pop()?);
+                stack.push(script_bool(a == b));
+            }
+            Op::CheckSig => {
+                let pubkey = stack.pop()?;
+                let sig = stack.pop()?;
+                let valid = match (Public::try_from(&pubkey[..]), Signature::try_from(&sig[..])) {
+                    (Ok(pubkey), Ok(sig)) => {
+                        sp_io::crypto::sr25519_verify(&sig, simplified_tx, &pubkey)
+                    }
+                    _ => false,
+                };
+                stack.push(script_bool(valid));
+            }
+            Op::CheckHeight(height) => stack.push(script_bool(context.block_height >= *height)),
+            Op::And => {
+                let (a, b) = (stack.pop()?, stack.pop()?);
+                stack.push(script_bool(script_truth(&a) && script_truth(&b)));
+            }
+            Op::Or => {
+                let (a, b) = (stack.pop()?, stack.pop()?);
+                stack.push(script_bool(script_truth(&a) || script_truth(&b)));
+            }
+            Op::Not => {
+                let item = stack.pop()?;
+                stack.push(script_bool(!script_truth(&item)));
+            }
+            Op::Verify => {
+                if !script_truth(&stack.pop()?) {
+                    return None;
+                }
+            }
+        }
+
+        (stack.len() <= MAX_SCRIPT_STACK).then_some(())
+    }
+}
+
+/// A verifier whose spending conditions are expressed as a small script, so that new kinds of
+/// conditions do not require new verifiers and runtime upgrades.
+///
+/// The redeemer is an encoded list of items that are pushed onto the stack in order. Then each
+/// operation is applied once, in order. There are no jumps or loops, so every script terminates.
+/// The output can be consumed if the script does not fail, and leaves a true item on top of
+/// the stack.
+#[derive(Serialize, Deserialize, Encode, Decode, Debug, PartialEq, Eq, Clone, TypeInfo)]
+#[cfg_attr(feature = "fuzzing", derive(arbitrary::Arbitrary))]
+pub struct Script {
+    /// The operations that make up the script
+    pub ops: Vec<Op>,
+}
+
+impl Script {
+    pub fn new(ops: Vec<Op>) -> Self {
+        Script { ops }
+    }
+}
+
+impl Verifier for Script {
+    fn verify(&self, simplified_tx: &[u8], redeemer: &[u8], context: &VerifierContext) -> bool {
+        let mut stack = match Vec::<Vec<u8>>::decode(&mut &redeemer[..]) {
+            Ok(s) => s,
+            Err(_) => return false,
+        };
+        if stack.len() > MAX_SCRIPT_STACK {
+            return false;
+        }
+
+        for op in &self.ops {
+            if op.apply(&mut stack, simplified_tx, context).is_none() {
+                return false;
+            }
+        }
+
+        stack.last().is_some_and(|top| script_truth(top))
+    }
+
+    fn weight(&self) -> Weight {
+        let signatures = self.ops.iter().filter(|op| **op == Op::CheckSig).count();
+        SIGNATURE_WEIGHT.saturating_mul(signatures as Weight)
+    }
+}
+
+/// The redeemer for a [`BlsMultiSignature`] verifier.
+#[cfg(feature = "bls")]
+#[derive(Serialize, Deserialize, Encode, Decode, Debug, PartialEq, Eq, Clone, TypeInfo)]
+pub struct BlsSignersAndSignature {
+    /// The indices of the signatories who signed, in ascending order
+    pub signers: Vec<u16>,
+    /// The serialized aggregate of all the signers' signatures over the simplified transaction
+    pub signature: Vec<u8>,
+}
+
+/// A threshold multisignature using aggregated BLS12-381 signatures. Unlike
+/// `ThresholdMultiSignature`, the redeemer contains only a single signature, no matter how many
+/// signatories signed, which keeps approvals by large committees compact.
+///
+/// Signatures are aggregated assuming each signatory has proven possession of their key. So before
+/// locking anything to a committee, its members must check each other's proofs of possession.
+/// Otherwise a member could choose a key that lets them forge the signatures of the others.
+#[cfg(feature = "bls")]
+#[derive(Serialize, Deserialize, Encode, Decode, Debug, PartialEq, Eq, Clone, TypeInfo)]
+#[cfg_attr(feature = "fuzzing", derive(arbitrary::Arbitrary))]
+pub struct BlsMultiSignature {
+    /// The minimum number of signers needed to consume this input
+    pub threshold: u16,
+    /// The serialized BLS12-381 public keys of all the member signatories
+    pub signatories: Vec<Vec<u8>>,
+}
+
+#[cfg(feature = "bls")]
+impl BlsMultiSignature {
+    /// The context that BLS signatures over Tuxedo transactions are made in.
+    pub const SIGNING_CONTEXT: &'static [u8] = b"tuxedo";
+
+    /// The weight of verifying an aggregated signature, which is dominated by two pairings.
+    pub const VERIFICATION_WEIGHT: Weight = 1_500_000_000;
+
+    pub fn new(threshold: u16, signatories: Vec<Vec<u8>>) -> Self {
+        BlsMultiSignature {
             threshold,
             signatories,
+        }
+    }
+}
+
+#[cfg(feature = "bls")]
+impl Verifier for BlsMultiSignature {
+    fn verify(&self, simplified_tx: &[u8], redeemer: &[u8], _context: &VerifierContext) -> bool {
+        use w3f_bls::{
+            Message, PublicKey, SerializableToBytes, Signature as BlsSignature,
+            SignatureAggregatorAssumingPoP, Signed, TinyBLS381,
+        };
+
+        let BlsSignersAndSignature { signers, signature } =
+            match BlsSignersAndSignature::decode(&mut &redeemer[..]) {
+                Ok(r) => r,
+                Err(_) => return false,
+            };
+
+        // Strictly ascending indices can not contain duplicates
+        if signers.len() < self.threshold.into() || signers.windows(2).any(|w| w[0] >= w[1]) {
+            return false;
+        }
+
+        let signature = match BlsSignature::<TinyBLS381>::from_bytes(&signature) {
+            Ok(s) => s,
+            Err(_) => return false,
         };
 
-        assert!(threshold_multisig.verify(simplified_tx, redeemer));
+        let mut aggregator = SignatureAggregatorAssumingPoP::<TinyBLS381>::new(Message::new(
+            Self::SIGNING_CONTEXT,
+            simplified_tx,
+        ));
+        aggregator.add_signature(&signature);
+        for index in signers {
+            let public = match self
+                .signatories
+                .get(index as usize)
+                .map(|key| PublicKey::<TinyBLS381>::from_bytes(key))
+            {
+                Some(Ok(p)) => p,
+                _ => return false,
+            };
+            aggregator.add_publickey(&public);
+        }
+
+        (&aggregator).verify()
     }
 
-    #[test]
-    fn threshold_multisig_replay_sig_attack_fails() {
-        let threshold = 2;
-        let pairs = generate_n_pairs(threshold);
+    fn weight(&self) -> Weight {
+        Self::VERIFICATION_WEIGHT
+    }
+}
 
-        let signatories: Vec<H256> = pairs.iter().map(|p| H256::from(p.public())).collect();
+/// The most fuel that a [`WasmVerifier`] program may consume. Roughly, each executed Wasm
+/// instruction consumes one unit of fuel.
+#[cfg(feature = "wasm-verifier")]
+pub const WASM_VERIFIER_FUEL: u64 = 1_000_000;
+
+/// The largest [`WasmVerifier`] program, in bytes, that may be run.
+#[cfg(feature = "wasm-verifier")]
+pub const WASM_VERIFIER_MAX_CODE_SIZE: usize = 16 * 1024;
+
+/// A verifier whose spending condition is a small Wasm program, giving users programmable custody
+/// without new verifiers and runtime upgrades.
+///
+/// The program is executed in the [`wasm_sandbox`](crate::wasm_sandbox), so its size, memory and
+/// fuel are limited. A program that is too large can never be consumed. It must export a
+/// function `verify` that takes no parameters and returns an `i32`. The output can be consumed if
+/// it returns `1`. To read the simplified transaction and the redeemer, the program may import
+/// these functions from the `env` module, and must then export its `memory`:
+/// * `tx_len() -> i32` and `read_tx(ptr: i32)`, which writes the whole transaction at `ptr`.
+/// * `redeemer_len() -> i32` and `read_redeemer(ptr: i32)`, which does the same for the redeemer.
+///
+/// The [`VerifierContext`] is available through `block_height() -> i32` and `timestamp() -> i64`.
+#[cfg(feature = "wasm-verifier")]
+#[derive(Serialize, Deserialize, Encode, Decode, Debug, PartialEq, Eq, Clone, TypeInfo)]
+#[cfg_attr(feature = "fuzzing", derive(arbitrary::Arbitrary))]
+pub struct WasmVerifier {
+    /// The Wasm program that decides whether the output can be consumed
+    pub code: Vec<u8>,
+}
 
-        let simplified_tx = b"hello_world".as_slice();
+#[cfg(feature = "wasm-verifier")]
+impl WasmVerifier {
+    pub fn new(code: Vec<u8>) -> Self {
+        WasmVerifier { code }
+    }
 
-        let sigs: Vec<SignatureAndIndex> = vec![
-            SignatureAndIndex {
-                signature: pairs[0].sign(simplified_tx),
-                index: 0.try_into().unwrap(),
-            },
-            SignatureAndIndex {
-                signature: pairs[0].sign(simplified_tx),
-                index: 0.try_into().unwrap(),
+    /// Run the program. Returns `None` if it could not be run to completion.
+    fn run(&self, simplified_tx: &[u8], redeemer: &[u8], context: &VerifierContext) -> Option<i32> {
+        use crate::wasm_sandbox::{self, Host};
+        use wasmi::Caller;
+
+        /// The data that the program can read through the host functions
+        struct Inputs {
+            tx: Vec<u8>,
+            redeemer: Vec<u8>,
+            context: VerifierContext,
+        }
+
+        let inputs = Inputs {
+            tx: simplified_tx.to_vec(),
+            redeemer: redeemer.to_vec(),
+            context: *context,
+        };
+        wasm_sandbox::run(
+            &self.code,
+            WASM_VERIFIER_MAX_CODE_SIZE,
+            WASM_VERIFIER_FUEL,
+            inputs,
+            "verify",
+            |linker| {
+                linker
+                    .func_wrap("env", "tx_len", |caller: Caller<'_, Host<Inputs>>| {
+                        caller.data().data.tx.len() as i32
+                    })
+                    .ok()?
+                    .func_wrap(
+                        "env",
+                        "read_tx",
+                        |mut caller: Caller<'_, Host<Inputs>>, ptr: i32| {
+                            wasm_sandbox::write(&mut caller, ptr, |inputs| &inputs.tx)
+                        },
+                    )
+                    .ok()?
+                    .func_wrap("env", "redeemer_len", |caller: Caller<'_, Host<Inputs>>| {
+                        caller.data().data.redeemer.len() as i32
+                    })
+                    .ok()?
+                    .func_wrap(
+                        "env",
+                        "read_redeemer",
+                        |mut caller: Caller<'_, Host<Inputs>>, ptr: i32| {
+                            wasm_sandbox::write(&mut caller, ptr, |inputs| &inputs.redeemer)
+                        },
+                    )
+                    .ok()?
+                    .func_wrap("env", "block_height", |caller: Caller<'_, Host<Inputs>>| {
+                        caller.data().data.context.block_height as i32
+                    })
+                    .ok()?
+                    .func_wrap("env", "timestamp", |caller: Caller<'_, Host<Inputs>>| {
+                        caller.data().data.context.timestamp as i64
+                    })
+                    .ok()?;
+                Some(())
             },
-        ];
+        )
+    }
+}
 
-        let redeemer: &[u8] = &sigs.encode()[..];
-        let threshold_multisig = ThresholdMultiSignature {
-            threshold,
-            signatories,
+#[cfg(feature = "wasm-verifier")]
+impl Verifier for WasmVerifier {
+    fn verify(&self, simplified_tx: &[u8], redeemer: &[u8], context: &VerifierContext) -> bool {
+        self.run(simplified_tx, redeemer, context) == Some(1)
+    }
+
+    fn weight(&self) -> Weight {
+        // Programs that are too large are rejected before they are compiled.
+        let code_size = self.code.len().min(WASM_VERIFIER_MAX_CODE_SIZE);
+        crate::wasm_sandbox::weight(code_size, WASM_VERIFIER_FUEL)
+    }
+}
+
+/// A testing verifier that passes or depending on the enclosed
+/// boolean value.
+#[cfg(feature = "std")]
+#[derive(Serialize, Deserialize, Encode, Decode, Debug, PartialEq, Eq, Clone, TypeInfo)]
+#[cfg_attr(feature = "fuzzing", derive(arbitrary::Arbitrary))]
+pub struct TestVerifier {
+    /// Whether the verifier should pass
+    pub verifies: bool,
+}
+
+#[cfg(feature = "std")]
+impl Verifier for TestVerifier {
+    fn verify(&self, _simplified_tx: &[u8], _redeemer: &[u8], _context: &VerifierContext) -> bool {
+        self.verifies
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{
+        constraint_checker::testing::TestConstraintChecker,
+        dynamic_typing::testing::Bogus,
+        transaction_builder::TransactionBuilder,
+        types::{InputScope, OutputRef, OutputScope},
+    };
+    use sp_core::{crypto::Pair as _, ecdsa, sr25519::Pair};
+    use sp_io::TestExternalities;
+
+    /// The mock context is always at block height ten, and does not know the time.
+    const CONTEXT: VerifierContext = VerifierContext {
+        block_height: 10,
+        timestamp: 0,
+    };
+
+    #[test]
+    fn best_time_starts_unknown() {
+        TestExternalities::default().execute_with(|| {
+            assert_eq!(best_time(), None);
+        });
+    }
+
+    #[test]
+    fn note_time_works() {
+        TestExternalities::default().execute_with(|| {
+            note_time(1_000, 1);
+            note_time(3_000, 2);
+            assert_eq!(best_time(), Some((3_000, 2)));
+        });
+    }
+
+    /// Generate a bunch of test keypairs
+    fn generate_n_pairs(n: u8) -> Vec<Pair> {
+        let mut seed = [0u8; 32];
+        let mut pairs = Vec::new();
+
+        // We generate the pairs from sequential seeds. Just changing the last byte of the seed each time.
+        for i in 0..n {
+            seed[31] = i;
+
+            let pair = Pair::from_seed(&seed);
+            pairs.push(pair);
+        }
+
+        pairs
+    }
+
+    #[test]
+    fn up_for_grabs_always_verifies() {
+        assert!(UpForGrabs.verify(&[], &[], &CONTEXT))
+    }
+
+    #[test]
+    fn unspendable_never_verifies() {
+        assert!(!Unspendable.verify(&[], &[], &CONTEXT))
+    }
+
+    #[test]
+    fn sig_check_with_good_sig() {
+        let pair = Pair::from_seed(&[0u8; 32]);
+        let simplified_tx = b"hello world".as_slice();
+        let sig = pair.sign(simplified_tx);
+        let redeemer: &[u8] = sig.as_ref();
+
+        let sig_check = SigCheck {
+            owner_pubkey: pair.public().into(),
         };
 
-        assert!(!threshold_multisig.verify(simplified_tx, redeemer));
+        assert!(sig_check.verify(simplified_tx, redeemer, &CONTEXT));
+    }
+
+    /// The signing payloads of a transaction with the given number of inputs, and an output for
+    /// each of the given owners.
+    fn payloads(inputs: u32, owners: &[u8]) -> SigningPayloads {
+        let mut builder = TransactionBuilder::<SigCheck, _>::new(TestConstraintChecker {
+            checks: true,
+            inherent: false,
+        });
+        for index in 0..inputs {
+            builder = builder.input(
+                OutputRef {
+                    tx_hash: H256::zero(),
+                    index,
+                },
+                Vec::new(),
+            );
+        }
+        for owner in owners {
+            builder = builder.output(Bogus, SigCheck::new(H256::repeat_byte(*owner)));
+        }
+        builder.build().signing_payloads(&Default::default())
+    }
+
+    /// A redeemer that signs the given scope of the first input of the given payloads.
+    fn scoped_redeemer(pair: &Pair, payloads: &SigningPayloads, scope: SigningScope) -> Vec<u8> {
+        ScopedSignature {
+            signature: pair.sign(&payloads.scoped(0, scope).unwrap()),
+            scope,
+        }
+        .encode()
     }
 
+    const THIS_INPUT_ALL_OUTPUTS: SigningScope = SigningScope {
+        inputs: InputScope::ThisInput,
+        outputs: OutputScope::All,
+    };
+
+    const ALL_INPUTS_MATCHING_OUTPUT: SigningScope = SigningScope {
+        inputs: InputScope::All,
+        outputs: OutputScope::MatchingOutput,
+    };
+
     #[test]
-    fn threshold_multisig_has_duplicate_signatories_fails() {
-        let threshold = 2;
-        let pairs = generate_n_pairs(threshold);
+    fn sig_check_with_bare_sig_covers_whole_transaction() {
+        let pair = Pair::from_seed(&[0u8; 32]);
+        let sig_check = SigCheck::new(pair.public());
+        let signed = payloads(1, &[1]);
+        let redeemer = pair.sign(signed.all());
 
-        let signatories: Vec<H256> =
-            vec![H256::from(pairs[0].public()), H256::from(pairs[0].public())];
+        assert!(sig_check.verify_input(&signed, 0, redeemer.as_ref(), &CONTEXT));
+        assert!(!sig_check.verify_input(&payloads(2, &[1]), 0, redeemer.as_ref(), &CONTEXT));
+    }
 
-        let simplified_tx = b"hello_world".as_slice();
+    #[test]
+    fn sig_check_for_this_input_allows_new_inputs() {
+        let pair = Pair::from_seed(&[0u8; 32]);
+        let sig_check = SigCheck::new(pair.public());
+        let redeemer = scoped_redeemer(&pair, &payloads(1, &[1]), THIS_INPUT_ALL_OUTPUTS);
 
-        let sigs: Vec<_> = pairs
-            .iter()
-            .enumerate()
-            .map(|(i, p)| SignatureAndIndex {
-                signature: p.sign(simplified_tx),
-                index: i.try_into().unwrap(),
-            })
-            .collect();
-        let redeemer: &[u8] = &sigs.encode()[..];
+        assert!(sig_check.verify_input(&payloads(3, &[1]), 0, &redeemer, &CONTEXT));
+        assert!(!sig_check.verify_input(&payloads(3, &[1, 2]), 0, &redeemer, &CONTEXT));
+    }
 
-        let threshold_multisig = ThresholdMultiSignature {
-            threshold,
-            signatories,
+    #[test]
+    fn sig_check_for_matching_output_allows_new_outputs() {
+        let pair = Pair::from_seed(&[0u8; 32]);
+        let sig_check = SigCheck::new(pair.public());
+        let redeemer = scoped_redeemer(&pair, &payloads(1, &[1]), ALL_INPUTS_MATCHING_OUTPUT);
+
+        assert!(sig_check.verify_input(&payloads(1, &[1, 2]), 0, &redeemer, &CONTEXT));
+        assert!(!sig_check.verify_input(&payloads(1, &[2, 1]), 0, &redeemer, &CONTEXT));
+        assert!(!sig_check.verify_input(&payloads(2, &[1, 2]), 0, &redeemer, &CONTEXT));
+    }
+
+    #[test]
+    fn sig_check_with_scope_swapped_fails() {
+        let pair = Pair::from_seed(&[0u8; 32]);
+        let signed = payloads(1, &[1]);
+        let ScopedSignature { signature, .. } = ScopedSignature::decode(
+            &mut &scoped_redeemer(&pair, &signed, THIS_INPUT_ALL_OUTPUTS)[..],
+        )
+        .unwrap();
+        let redeemer = ScopedSignature {
+            signature,
+            scope: ALL_INPUTS_MATCHING_OUTPUT,
         };
 
-        assert!(!threshold_multisig.verify(simplified_tx, redeemer));
+        assert!(!SigCheck::new(pair.public()).verify_input(
+            &signed,
+            0,
+            &redeemer.encode(),
+            &CONTEXT
+        ));
     }
 
     #[test]
-    fn threshold_multisig_bogus_redeemer_encoding_fails() {
-        use crate::dynamic_typing::testing::Bogus;
+    fn sig_check_with_scoped_all_fails() {
+        // The whole transaction takes a bare signature, so that the redeemer is not malleable.
+        let pair = Pair::from_seed(&[0u8; 32]);
+        let signed = payloads(1, &[1]);
+        let redeemer = ScopedSignature {
+            signature: pair.sign(signed.all()),
+            scope: SigningScope::ALL,
+        };
 
-        let bogus = Bogus;
+        assert!(!SigCheck::new(pair.public()).verify_input(
+            &signed,
+            0,
+            &redeemer.encode(),
+            &CONTEXT
+        ));
+    }
 
-        let threshold_multisig = ThresholdMultiSignature {
-            threshold: 3,
-            signatories: vec![],
+    #[test]
+    fn sig_check_without_matching_output_fails() {
+        let pair = Pair::from_seed(&[0u8; 32]);
+        let redeemer = scoped_redeemer(&pair, &payloads(1, &[1]), ALL_INPUTS_MATCHING_OUTPUT);
+
+        assert!(!SigCheck::new(pair.public()).verify_input(
+            &payloads(1, &[]),
+            0,
+            &redeemer,
+            &CONTEXT
+        ));
+    }
+
+    #[test]
+    fn p2pkh_with_good_sig() {
+        let pair = Pair::from_seed(&[0u8; 32]);
+        let simplified_tx = b"hello world".as_slice();
+        let redeemer = PubkeyAndSignature {
+            pubkey: pair.public().into(),
+            signature: pair.sign(simplified_tx),
         };
 
-        assert!(!threshold_multisig.verify(b"bogus_message".as_slice(), bogus.encode().as_slice()))
+        let p2pkh = P2PKH::new(pair.public());
+
+        assert!(p2pkh.verify(simplified_tx, &redeemer.encode(), &CONTEXT));
     }
 
     #[test]
-    fn sig_check_with_bad_sig() {
+    fn p2pkh_does_not_store_pubkey() {
+        let pair = Pair::from_seed(&[0u8; 32]);
+        let p2pkh = P2PKH::new(pair.public());
+
+        assert_ne!(p2pkh.owner_pubkey_hash, H256::from(pair.public()));
+    }
+
+    #[test]
+    fn p2pkh_with_wrong_pubkey() {
+        let owner = Pair::from_seed(&[0u8; 32]);
+        let thief = Pair::from_seed(&[1u8; 32]);
         let simplified_tx = b"hello world".as_slice();
-        let redeemer = b"bogus_signature".as_slice();
+        let redeemer = PubkeyAndSignature {
+            pubkey: thief.public().into(),
+            signature: thief.sign(simplified_tx),
+        };
 
-        let sig_check = SigCheck {
-            owner_pubkey: H256::zero(),
+        let p2pkh = P2PKH::new(owner.public());
+
+        assert!(!p2pkh.verify(simplified_tx, &redeemer.encode(), &CONTEXT));
+    }
+
+    #[test]
+    fn p2pkh_with_bad_sig() {
+        let owner = Pair::from_seed(&[0u8; 32]);
+        let thief = Pair::from_seed(&[1u8; 32]);
+        let simplified_tx = b"hello world".as_slice();
+        let redeemer = PubkeyAndSignature {
+            pubkey: owner.public().into(),
+            signature: thief.sign(simplified_tx),
         };
 
-        assert!(!sig_check.verify(simplified_tx, redeemer));
+        let p2pkh = P2PKH::new(owner.public());
+
+        assert!(!p2pkh.verify(simplified_tx, &redeemer.encode(), &CONTEXT));
+    }
+
+    #[test]
+    fn p2pkh_bogus_redeemer_encoding_fails() {
+        let p2pkh = P2PKH::new(H256::zero());
+
+        assert!(!p2pkh.verify(b"hello world".as_slice(), b"bogus".as_slice(), &CONTEXT));
+    }
+
+    #[test]
+    fn ed25519_sig_check_with_good_sig() {
+        let pair = ed25519::Pair::from_seed(&[0u8; 32]);
+        let simplified_tx = b"hello world".as_slice();
+        let sig = pair.sign(simplified_tx);
+        let redeemer: &[u8] = sig.as_ref();
+
+        let sig_check = Ed25519SigCheck::new(pair.public().0);
+
+        assert!(sig_check.verify(simplified_tx, redeemer, &CONTEXT));
+    }
+
+    #[test]
+    fn ed25519_sig_check_with_bad_sig() {
+        let simplified_tx = b"hello world".as_slice();
+        let redeemer = b"bogus_signature".as_slice();
+
+        let sig_check = Ed25519SigCheck::new(H256::zero());
+
+        assert!(!sig_check.verify(simplified_tx, redeemer, &CONTEXT));
+    }
+
+    #[test]
+    fn ed25519_sig_check_with_sr25519_sig() {
+        // Both schemes use 32 byte keys and 64 byte signatures, but they do not mix.
+        let pair = Pair::from_seed(&[0u8; 32]);
+        let simplified_tx = b"hello world".as_slice();
+        let sig = pair.sign(simplified_tx);
+        let redeemer: &[u8] = sig.as_ref();
+
+        let sig_check = Ed25519SigCheck::new(pair.public());
+
+        assert!(!sig_check.verify(simplified_tx, redeemer, &CONTEXT));
+    }
+
+    /// A well known Ethereum test account and its address
+    fn ethereum_account() -> (ecdsa::Pair, H160) {
+        let seed: [u8; 32] = array_bytes::hex_n_into_unchecked(
+            "4c0883a69102937d6231471b5dbb6204fe5129617082792ae468d01a3f362318",
+        );
+        let address = array_bytes::hex_n_into_unchecked("2c7536e3605d9c16a7a3d7b1898e529396a65c23");
+        (ecdsa::Pair::from_seed(&seed), address)
+    }
+
+    /// Sign the simplified transaction the way Ethereum wallets do, with the recovery id offset by 27
+    fn ethereum_sign(pair: &ecdsa::Pair, simplified_tx: &[u8]) -> [u8; 65] {
+        let mut sig = pair.sign_prehashed(&ethereum_signing_hash(simplified_tx)).0;
+        sig[64] += 27;
+        sig
+    }
+
+    #[test]
+    fn ethereum_sig_check_with_good_sig() {
+        let (pair, address) = ethereum_account();
+        let simplified_tx = b"hello world".as_slice();
+        let redeemer = ethereum_sign(&pair, simplified_tx);
+
+        let sig_check = EthereumSigCheck::new(address);
+
+        assert!(sig_check.verify(simplified_tx, &redeemer, &CONTEXT));
+    }
+
+    #[test]
+    fn ethereum_sig_check_with_raw_recovery_id_fails() {
+        let (pair, address) = ethereum_account();
+        let simplified_tx = b"hello world".as_slice();
+        let mut redeemer = ethereum_sign(&pair, simplified_tx);
+        redeemer[64] -= 27;
+
+        let sig_check = EthereumSigCheck::new(address);
+
+        assert!(!sig_check.verify(simplified_tx, &redeemer, &CONTEXT));
+    }
+
+    #[test]
+    fn ethereum_sig_check_with_high_s_fails() {
+        // The order of the secp256k1 curve
+        let order: [u8; 32] = array_bytes::hex_n_into_unchecked(
+            "fffffffffffffffffffffffffffffffebaaedce6af48a03bbfd25e8cd0364141",
+        );
+        let (pair, address) = ethereum_account();
+        let simplified_tx = b"hello world".as_slice();
+        let mut redeemer = ethereum_sign(&pair, simplified_tx);
+
+        // Replace s with order - s, and flip the recovery id to match.
+        let mut borrow = 0i16;
+        for i in (0..32).rev() {
+            let diff = order[i] as i16 - redeemer[32 + i] as i16 - borrow;
+            borrow = (diff < 0) as i16;
+            redeemer[32 + i] = diff.rem_euclid(256) as u8;
+        }
+        redeemer[64] ^= 1;
+
+        let sig_check = EthereumSigCheck::new(address);
+
+        assert!(!sig_check.verify(simplified_tx, &redeemer, &CONTEXT));
+    }
+
+    #[test]
+    fn ethereum_sig_check_with_wrong_signer() {
+        let (_, address) = ethereum_account();
+        let pair = ecdsa::Pair::from_seed(&[1u8; 32]);
+        let simplified_tx = b"hello world".as_slice();
+        let redeemer = ethereum_sign(&pair, simplified_tx);
+
+        let sig_check = EthereumSigCheck::new(address);
+
+        assert!(!sig_check.verify(simplified_tx, &redeemer, &CONTEXT));
+    }
+
+    #[test]
+    fn ethereum_sig_check_with_bad_sig() {
+        let (_, address) = ethereum_account();
+        let simplified_tx = b"hello world".as_slice();
+        let redeemer = b"bogus_signature".as_slice();
+
+        let sig_check = EthereumSigCheck::new(address);
+
+        assert!(!sig_check.verify(simplified_tx, redeemer, &CONTEXT));
+    }
+
+    #[test]
+    fn threshold_multisig_with_enough_sigs_passes() {
+        let threshold = 2;
+        let pairs = generate_n_pairs(threshold);
+
+        let signatories: Vec<H256> = pairs.iter().map(|p| H256::from(p.public())).collect();
+
+        let simplified_tx = b"hello_world".as_slice();
+        let sigs: Vec<_> = pairs
+            .iter()
+            .enumerate()
+            .map(|(i, p)| SignatureAndIndex {
+                signature: p.sign(simplified_tx),
+                index: i.try_into().unwrap(),
+            })
+            .collect();
+
+        let redeemer: &[u8] = &sigs.encode()[..];
+        let threshold_multisig = ThresholdMultiSignature {
+            threshold,
+            signatories,
+        };
+
+        assert!(threshold_multisig.verify(simplified_tx, redeemer, &CONTEXT));
+    }
+
+    #[test]
+    fn threshold_multisig_not_enough_sigs_fails() {
+        let threshold = 3;
+        let pairs = generate_n_pairs(threshold);
+
+        let signatories: Vec<H256> = pairs.iter().map(|p| H256::from(p.public())).collect();
+
+        let simplified_tx = b"hello_world".as_slice();
+        let sigs: Vec<_> = pairs
+            .iter()
+            .take(threshold as usize - 1)
+            .enumerate()
+            .map(|(i, p)| SignatureAndIndex {
+                signature: p.sign(simplified_tx),
+                index: i.try_into().unwrap(),
+            })
+            .collect();
+
+        let redeemer: &[u8] = &sigs.encode()[..];
+        let threshold_multisig = ThresholdMultiSignature {
+            threshold,
+            signatories,
+        };
+
+        assert!(!threshold_multisig.verify(simplified_tx, redeemer, &CONTEXT));
+    }
+
+    #[test]
+    fn threshold_multisig_extra_sigs_still_passes() {
+        let threshold = 2;
+        let pairs = generate_n_pairs(threshold + 1);
+
+        let signatories: Vec<H256> = pairs.iter().map(|p| H256::from(p.public())).collect();
+
+        let simplified_tx = b"hello_world".as_slice();
+        let sigs: Vec<_> = pairs
+            .iter()
+            .enumerate()
+            .map(|(i, p)| SignatureAndIndex {
+                signature: p.sign(simplified_tx),
+                index: i.try_into().unwrap(),
+            })
+            .collect();
+
+        let redeemer: &[u8] = &sigs.encode()[..];
+        let threshold_multisig = ThresholdMultiSignature {
+            threshold,
+            signatories,
+        };
+
+        assert!(threshold_multisig.verify(simplified_tx, redeemer, &CONTEXT));
+    }
+
+    #[test]
+    fn threshold_multisig_wrong_sigs_fail() {
+        let threshold = 2;
+        let pairs = generate_n_pairs(threshold);
+
+        let signatories: Vec<H256> = pairs.iter().map(|p| H256::from(p.public())).collect();
+
+        let simplified_tx = b"hello_world".as_slice();
+        // Enough signatures at the right indices, but over some other message.
+        let sigs: Vec<_> = pairs
+            .iter()
+            .enumerate()
+            .map(|(i, p)| SignatureAndIndex {
+                signature: p.sign(b"something_else"),
+                index: i.try_into().unwrap(),
+            })
+            .collect();
+
+        let redeemer: &[u8] = &sigs.encode()[..];
+        let threshold_multisig = ThresholdMultiSignature {
+            threshold,
+            signatories,
+        };
+
+        assert!(!threshold_multisig.verify(simplified_tx, redeemer, &CONTEXT));
+    }
+
+    #[test]
+    fn threshold_multisig_one_wrong_sig_below_threshold_fails() {
+        let threshold = 2;
+        let pairs = generate_n_pairs(threshold);
+
+        let signatories: Vec<H256> = pairs.iter().map(|p| H256::from(p.public())).collect();
+
+        let simplified_tx = b"hello_world".as_slice();
+        let sigs = vec![
+            SignatureAndIndex {
+                signature: pairs[0].sign(simplified_tx),
+                index: 0,
+            },
+            SignatureAndIndex {
+                signature: pairs[0].sign(simplified_tx),
+                index: 1,
+            },
+        ];
+
+        let redeemer: &[u8] = &sigs.encode()[..];
+        let threshold_multisig = ThresholdMultiSignature {
+            threshold,
+            signatories,
+        };
+
+        assert!(!threshold_multisig.verify(simplified_tx, redeemer, &CONTEXT));
+    }
+
+    #[test]
+    fn threshold_multisig_index_out_of_range_fails() {
+        let threshold = 2;
+        let pairs = generate_n_pairs(threshold);
+
+        let signatories: Vec<H256> = pairs.iter().map(|p| H256::from(p.public())).collect();
+
+        let simplified_tx = b"hello_world".as_slice();
+        // Three signatures, so the last index is within the redeemer, but not the signatories.
+        let sigs: Vec<_> = (0..3)
+            .map(|i| SignatureAndIndex {
+                signature: pairs[0].sign(simplified_tx),
+                index: i,
+            })
+            .collect();
+
+        let redeemer: &[u8] = &sigs.encode()[..];
+        let threshold_multisig = ThresholdMultiSignature {
+            threshold,
+            signatories,
+        };
+
+        assert!(!threshold_multisig.verify(simplified_tx, redeemer, &CONTEXT));
+    }
+
+    #[test]
+    fn threshold_multisig_replay_sig_attack_fails() {
+        let threshold = 2;
+        let pairs = generate_n_pairs(threshold);
+
+        let signatories: Vec<H256> = pairs.iter().map(|p| H256::from(p.public())).collect();
+
+        let simplified_tx = b"hello_world".as_slice();
+
+        let sigs: Vec<SignatureAndIndex> = vec![
+            SignatureAndIndex {
+                signature: pairs[0].sign(simplified_tx),
+                index: 0.try_into().unwrap(),
+            },
+            SignatureAndIndex {
+                signature: pairs[0].sign(simplified_tx),
+                index: 0.try_into().unwrap(),
+            },
+        ];
+
+        let redeemer: &[u8] = &sigs.encode()[..];
+        let threshold_multisig = ThresholdMultiSignature {
+            threshold,
+            signatories,
+        };
+
+        assert!(!threshold_multisig.verify(simplified_tx, redeemer, &CONTEXT));
+    }
+
+    #[test]
+    fn threshold_multisig_has_duplicate_signatories_fails() {
+        let threshold = 2;
+        let pairs = generate_n_pairs(threshold);
+
+        let signatories: Vec<H256> =
+            vec![H256::from(pairs[0].public()), H256::from(pairs[0].public())];
+
+        let simplified_tx = b"hello_world".as_slice();
+
+        let sigs: Vec<_> = pairs
+            .iter()
+            .enumerate()
+            .map(|(i, p)| SignatureAndIndex {
+                signature: p.sign(simplified_tx),
+                index: i.try_into().unwrap(),
+            })
+            .collect();
+        let redeemer: &[u8] = &sigs.encode()[..];
+
+        let threshold_multisig = ThresholdMultiSignature {
+            threshold,
+            signatories,
+        };
+
+        assert!(!threshold_multisig.verify(simplified_tx, redeemer, &CONTEXT));
+    }
+
+    #[test]
+    fn threshold_multisig_bogus_redeemer_encoding_fails() {
+        use crate::dynamic_typing::testing::Bogus;
+
+        let bogus = Bogus;
+
+        let threshold_multisig = ThresholdMultiSignature {
+            threshold: 3,
+            signatories: vec![],
+        };
+
+        assert!(!threshold_multisig.verify(
+            b"bogus_message".as_slice(),
+            bogus.encode().as_slice(),
+            &CONTEXT
+        ))
+    }
+
+    /// A board where the first member has as much weight as the other two combined
+    fn weighted_board(threshold: u64, pairs: &[Pair]) -> WeightedThresholdMultiSignature {
+        let weights = [2, 1, 1];
+        let signatories = pairs
+            .iter()
+            .zip(weights)
+            .map(|(p, weight)| WeightedSignatory {
+                pubkey: p.public().into(),
+                weight,
+            })
+            .collect();
+        WeightedThresholdMultiSignature::new(threshold, signatories)
+    }
+
+    fn weighted_redeemer(pairs: &[Pair], signers: &[u8], simplified_tx: &[u8]) -> Vec<u8> {
+        signers
+            .iter()
+            .map(|&i| SignatureAndIndex {
+                signature: pairs[i as usize].sign(simplified_tx),
+                index: i,
+            })
+            .collect::<Vec<_>>()
+            .encode()
+    }
+
+    #[test]
+    fn weighted_multisig_heavy_signer_passes() {
+        let pairs = generate_n_pairs(3);
+        let simplified_tx = b"hello_world".as_slice();
+        let redeemer = weighted_redeemer(&pairs, &[0], simplified_tx);
+
+        assert!(weighted_board(2, &pairs).verify(simplified_tx, &redeemer, &CONTEXT));
+    }
+
+    #[test]
+    fn weighted_multisig_light_signers_together_pass() {
+        let pairs = generate_n_pairs(3);
+        let simplified_tx = b"hello_world".as_slice();
+        let redeemer = weighted_redeemer(&pairs, &[1, 2], simplified_tx);
+
+        assert!(weighted_board(2, &pairs).verify(simplified_tx, &redeemer, &CONTEXT));
+    }
+
+    #[test]
+    fn weighted_multisig_not_enough_weight_fails() {
+        let pairs = generate_n_pairs(3);
+        let simplified_tx = b"hello_world".as_slice();
+        let redeemer = weighted_redeemer(&pairs, &[1, 2], simplified_tx);
+
+        assert!(!weighted_board(3, &pairs).verify(simplified_tx, &redeemer, &CONTEXT));
+    }
+
+    #[test]
+    fn weighted_multisig_replay_sig_attack_fails() {
+        let pairs = generate_n_pairs(3);
+        let simplified_tx = b"hello_world".as_slice();
+        let redeemer = weighted_redeemer(&pairs, &[1, 1], simplified_tx);
+
+        assert!(!weighted_board(2, &pairs).verify(simplified_tx, &redeemer, &CONTEXT));
+    }
+
+    #[test]
+    fn weighted_multisig_bad_sig_fails() {
+        let pairs = generate_n_pairs(3);
+        let simplified_tx = b"hello_world".as_slice();
+        let redeemer = vec![SignatureAndIndex {
+            signature: pairs[1].sign(simplified_tx),
+            index: 0,
+        }]
+        .encode();
+
+        assert!(!weighted_board(1, &pairs).verify(simplified_tx, &redeemer, &CONTEXT));
+    }
+
+    #[test]
+    fn weighted_multisig_index_out_of_bounds_fails() {
+        let pairs = generate_n_pairs(3);
+        let simplified_tx = b"hello_world".as_slice();
+        let redeemer = weighted_redeemer(&pairs, &[2], simplified_tx);
+
+        assert!(!weighted_board(1, &pairs[..2]).verify(simplified_tx, &redeemer, &CONTEXT));
+    }
+
+    #[test]
+    fn weighted_multisig_has_duplicate_signatories_fails() {
+        let pairs = generate_n_pairs(1);
+        let simplified_tx = b"hello_world".as_slice();
+        let redeemer = weighted_redeemer(&pairs, &[0], simplified_tx);
+        let board = weighted_board(1, &[pairs[0].clone(), pairs[0].clone()]);
+
+        assert!(!board.verify(simplified_tx, &redeemer, &CONTEXT));
+    }
+
+    #[test]
+    fn sig_check_with_bad_sig() {
+        let simplified_tx = b"hello world".as_slice();
+        let redeemer = b"bogus_signature".as_slice();
+
+        let sig_check = SigCheck {
+            owner_pubkey: H256::zero(),
+        };
+
+        assert!(!sig_check.verify(simplified_tx, redeemer, &CONTEXT));
+    }
+
+    fn hash_lock_redeemer(preimage: &[u8], inner: &[u8]) -> Vec<u8> {
+        PreimageAndRedeemer {
+            preimage: preimage.to_vec(),
+            inner: inner.to_vec(),
+        }
+        .encode()
+    }
+
+    #[test]
+    fn hash_lock_with_preimage_passes() {
+        let lock = HashLock::new(H256(sp_io::hashing::sha2_256(b"secret")));
+        assert!(lock.verify(&[], &hash_lock_redeemer(b"secret", &[]), &CONTEXT));
+    }
+
+    #[test]
+    fn hash_lock_with_wrong_preimage_fails() {
+        let lock = HashLock::new(H256(sp_io::hashing::sha2_256(b"secret")));
+        assert!(!lock.verify(&[], &hash_lock_redeemer(b"guess", &[]), &CONTEXT));
+    }
+
+    #[test]
+    fn hash_lock_bogus_redeemer_encoding_fails() {
+        let lock = HashLock::new(H256(sp_io::hashing::sha2_256(b"secret")));
+        assert!(!lock.verify(&[], b"secret".as_slice(), &CONTEXT));
+    }
+
+    #[test]
+    fn hash_lock_with_sig_check_passes() {
+        let pair = Pair::from_seed(&[0u8; 32]);
+        let simplified_tx = b"hello world".as_slice();
+        let sig = pair.sign(simplified_tx);
+
+        let lock = HashLock::with_inner(
+            H256(sp_io::hashing::sha2_256(b"secret")),
+            SigCheck::new(pair.public()),
+        );
+
+        let redeemer = hash_lock_redeemer(b"secret", sig.as_ref());
+
+        assert!(lock.verify(simplified_tx, &redeemer, &CONTEXT));
+    }
+
+    #[test]
+    fn hash_lock_with_sig_check_requires_sig() {
+        let pair = Pair::from_seed(&[0u8; 32]);
+        let simplified_tx = b"hello world".as_slice();
+
+        let lock = HashLock::with_inner(
+            H256(sp_io::hashing::sha2_256(b"secret")),
+            SigCheck::new(pair.public()),
+        );
+
+        let redeemer = hash_lock_redeemer(b"secret", b"bogus_signature");
+
+        assert!(!lock.verify(simplified_tx, &redeemer, &CONTEXT));
+    }
+
+    #[test]
+    fn height_lock_after_expiry_passes() {
+        let lock = HeightLock::new(UpForGrabs, 10);
+        assert!(lock.verify(&[], &[], &CONTEXT));
+    }
+
+    #[test]
+    fn height_lock_before_expiry_fails() {
+        let lock = HeightLock::new(UpForGrabs, 11);
+        assert!(!lock.verify(&[], &[], &CONTEXT));
+    }
+
+    #[test]
+    fn height_lock_requires_inner_verifier() {
+        let lock = HeightLock::new(TestVerifier { verifies: false }, 5);
+        assert!(!lock.verify(&[], &[], &CONTEXT));
+    }
+
+    #[test]
+    fn height_lock_passes_redeemer_through() {
+        let pair = Pair::from_seed(&[0u8; 32]);
+        let simplified_tx = b"hello world".as_slice();
+        let sig = pair.sign(simplified_tx);
+        let redeemer: &[u8] = sig.as_ref();
+
+        let lock = HeightLock::new(SigCheck::new(pair.public()), 5);
+
+        assert!(lock.verify(simplified_tx, redeemer, &CONTEXT));
+    }
+
+    #[test]
+    fn height_lock_passes_scoped_redeemer_through() {
+        let pair = Pair::from_seed(&[0u8; 32]);
+        let redeemer = scoped_redeemer(&pair, &payloads(1, &[1]), THIS_INPUT_ALL_OUTPUTS);
+
+        let lock = HeightLock::new(SigCheck::new(pair.public()), 5);
+
+        assert!(lock.verify_input(&payloads(2, &[1]), 0, &redeemer, &CONTEXT));
+    }
+
+    #[test]
+    fn expiring_after_expiry_passes_for_anyone() {
+        let expiring = Expiring::new(TestVerifier { verifies: false }, 10);
+        assert!(expiring.verify(&[], &[], &CONTEXT));
+    }
+
+    #[test]
+    fn expiring_before_expiry_requires_inner_verifier() {
+        let expiring = Expiring::new(TestVerifier { verifies: false }, 11);
+        assert!(!expiring.verify(&[], &[], &CONTEXT));
+    }
+
+    #[test]
+    fn expiring_passes_redeemer_through() {
+        let pair = Pair::from_seed(&[0u8; 32]);
+        let simplified_tx = b"hello world".as_slice();
+        let sig = pair.sign(simplified_tx);
+        let redeemer: &[u8] = sig.as_ref();
+
+        let expiring = Expiring::new(SigCheck::new(pair.public()), 11);
+
+        assert!(expiring.verify(simplified_tx, redeemer, &CONTEXT));
+    }
+
+    /// A scope that allows the transactions that start with the given byte.
+    #[derive(Encode, Decode, Debug, PartialEq, Eq, Clone)]
+    struct StartsWith(u8);
+
+    impl ProxyScope for StartsWith {
+        fn allows(&self, _proxy: &Proxy<Self>, simplified_tx: &[u8]) -> bool {
+            simplified_tx.first() == Some(&self.0)
+        }
+    }
+
+    /// A proxy owned by the first pair, whose delegate is the second pair and may only sign
+    /// transactions that start with `h`.
+    fn proxy(pairs: &[Pair]) -> Proxy<StartsWith> {
+        Proxy::new(
+            pairs[0].public(),
+            vec![Delegate {
+                pubkey: pairs[1].public().into(),
+                scope: StartsWith(b'h'),
+            }],
+        )
+    }
+
+    #[test]
+    fn proxy_owner_may_sign_anything() {
+        let pairs = generate_n_pairs(2);
+        let simplified_tx = b"goodbye world".as_slice();
+        let redeemer = ProxyRedeemer::Owner(pairs[0].sign(simplified_tx)).encode();
+
+        assert!(proxy(&pairs).verify(simplified_tx, &redeemer, &CONTEXT));
+    }
+
+    #[test]
+    fn proxy_delegate_within_scope_passes() {
+        let pairs = generate_n_pairs(2);
+        let simplified_tx = b"hello world".as_slice();
+        let redeemer = ProxyRedeemer::Delegate {
+            index: 0,
+            signature: pairs[1].sign(simplified_tx),
+        }
+        .encode();
+
+        assert!(proxy(&pairs).verify(simplified_tx, &redeemer, &CONTEXT));
+    }
+
+    #[test]
+    fn proxy_delegate_outside_scope_fails() {
+        let pairs = generate_n_pairs(2);
+        let simplified_tx = b"goodbye world".as_slice();
+        let redeemer = ProxyRedeemer::Delegate {
+            index: 0,
+            signature: pairs[1].sign(simplified_tx),
+        }
+        .encode();
+
+        assert!(!proxy(&pairs).verify(simplified_tx, &redeemer, &CONTEXT));
+    }
+
+    #[test]
+    fn proxy_delegate_with_wrong_index_fails() {
+        let pairs = generate_n_pairs(2);
+        let simplified_tx = b"hello world".as_slice();
+        let redeemer = ProxyRedeemer::Delegate {
+            index: 1,
+            signature: pairs[1].sign(simplified_tx),
+        }
+        .encode();
+
+        assert!(!proxy(&pairs).verify(simplified_tx, &redeemer, &CONTEXT));
+    }
+
+    #[test]
+    fn proxy_owner_signature_as_delegate_fails() {
+        let pairs = generate_n_pairs(2);
+        let simplified_tx = b"hello world".as_slice();
+        let redeemer = ProxyRedeemer::Delegate {
+            index: 0,
+            signature: pairs[0].sign(simplified_tx),
+        }
+        .encode();
+
+        assert!(!proxy(&pairs).verify(simplified_tx, &redeemer, &CONTEXT));
+    }
+
+    #[test]
+    fn proxy_bogus_redeemer_fails() {
+        let pairs = generate_n_pairs(2);
+        assert!(!proxy(&pairs).verify(b"hello world", b"bogus", &CONTEXT));
+    }
+
+    #[test]
+    fn empty_script_with_true_redeemer_passes() {
+        let script = Script::new(vec![]);
+        assert!(script.verify(&[], &vec![vec![1u8]].encode(), &CONTEXT));
+    }
+
+    #[test]
+    fn empty_script_with_empty_redeemer_fails() {
+        let script = Script::new(vec![]);
+        assert!(!script.verify(&[], &Vec::<Vec<u8>>::new().encode(), &CONTEXT));
+    }
+
+    #[test]
+    fn script_bogus_redeemer_encoding_fails() {
+        let script = Script::new(vec![Op::Push(vec![1])]);
+        assert!(!script.verify(&[], b"bogus".as_slice(), &CONTEXT));
+    }
+
+    /// A script that works like pay to public key hash
+    fn p2pkh_script(pair: &Pair) -> Script {
+        Script::new(vec![
+            Op::Dup,
+            Op::Blake2,
+            Op::Push(sp_io::hashing::blake2_256(pair.public().as_ref()).to_vec()),
+            Op::Equal,
+            Op::Verify,
+            Op::CheckSig,
+        ])
+    }
+
+    #[test]
+    fn script_sig_check_passes() {
+        let pair = Pair::from_seed(&[0u8; 32]);
+        let simplified_tx = b"hello world".as_slice();
+        let sig = pair.sign(simplified_tx);
+        let redeemer = vec![sig.as_ref().to_vec(), pair.public().as_ref().to_vec()];
+
+        assert!(p2pkh_script(&pair).verify(simplified_tx, &redeemer.encode(), &CONTEXT));
+    }
+
+    #[test]
+    fn script_sig_check_with_wrong_key_fails() {
+        let owner = Pair::from_seed(&[0u8; 32]);
+        let thief = Pair::from_seed(&[1u8; 32]);
+        let simplified_tx = b"hello world".as_slice();
+        let sig = thief.sign(simplified_tx);
+        let redeemer = vec![sig.as_ref().to_vec(), thief.public().as_ref().to_vec()];
+
+        assert!(!p2pkh_script(&owner).verify(simplified_tx, &redeemer.encode(), &CONTEXT));
+    }
+
+    #[test]
+    fn script_sig_check_with_bad_sig_fails() {
+        let pair = Pair::from_seed(&[0u8; 32]);
+        let simplified_tx = b"hello world".as_slice();
+        let redeemer = vec![b"bogus".to_vec(), pair.public().as_ref().to_vec()];
+
+        assert!(!p2pkh_script(&pair).verify(simplified_tx, &redeemer.encode(), &CONTEXT));
+    }
+
+    /// A script that can be consumed with the preimage of a hash, or by anyone after height ten.
+    fn hash_or_timeout_script(timeout: u32) -> Script {
+        Script::new(vec![
+            Op::Sha256,
+            Op::Push(sp_io::hashing::sha2_256(b"secret").to_vec()),
+            Op::Equal,
+            Op::CheckHeight(timeout),
+            Op::Or,
+        ])
+    }
+
+    #[test]
+    fn script_hash_lock_passes() {
+        let redeemer = vec![b"secret".to_vec()];
+        assert!(hash_or_timeout_script(11).verify(&[], &redeemer.encode(), &CONTEXT));
+    }
+
+    #[test]
+    fn script_wrong_preimage_before_timeout_fails() {
+        let redeemer = vec![b"guess".to_vec()];
+        assert!(!hash_or_timeout_script(11).verify(&[], &redeemer.encode(), &CONTEXT));
+    }
+
+    #[test]
+    fn script_wrong_preimage_after_timeout_passes() {
+        let redeemer = vec![b"guess".to_vec()];
+        assert!(hash_or_timeout_script(10).verify(&[], &redeemer.encode(), &CONTEXT));
+    }
+
+    #[test]
+    fn script_popping_empty_stack_fails() {
+        let script = Script::new(vec![Op::Push(vec![1]), Op::And]);
+        assert!(!script.verify(&[], &Vec::<Vec<u8>>::new().encode(), &CONTEXT));
+    }
+
+    #[test]
+    fn script_verify_stops_on_false() {
+        let script = Script::new(vec![Op::Push(vec![]), Op::Verify, Op::Push(vec![1])]);
+        assert!(!script.verify(&[], &Vec::<Vec<u8>>::new().encode(), &CONTEXT));
+    }
+
+    #[test]
+    fn script_not_and_drop_work() {
+        let script = Script::new(vec![Op::Push(vec![0, 0]), Op::Dup, Op::Drop, Op::Not]);
+        assert!(script.verify(&[], &Vec::<Vec<u8>>::new().encode(), &CONTEXT));
+    }
+
+    #[test]
+    fn script_overflowing_stack_fails() {
+        let script = Script::new(vec![Op::Dup; MAX_SCRIPT_STACK]);
+        assert!(!script.verify(&[], &vec![vec![1u8]].encode(), &CONTEXT));
+    }
+
+    #[cfg(feature = "bls")]
+    mod bls {
+        use super::*;
+        use w3f_bls::{Message, SecretKeyVT, SerializableToBytes, Signed, TinyBLS381};
+
+        fn bls_keys(n: u8) -> Vec<SecretKeyVT<TinyBLS381>> {
+            (0..n).map(|i| SecretKeyVT::from_seed(&[i; 32])).collect()
+        }
+
+        fn committee(threshold: u16, keys: &[SecretKeyVT<TinyBLS381>]) -> BlsMultiSignature {
+            let signatories = keys.iter().map(|k| k.into_public().to_bytes()).collect();
+            BlsMultiSignature::new(threshold, signatories)
+        }
+
+        /// The aggregate signature of the keys at the given indices, encoded as a redeemer
+        fn redeemer(keys: &[SecretKeyVT<TinyBLS381>], signers: Vec<u16>, tx: &[u8]) -> Vec<u8> {
+            let message = Message::new(BlsMultiSignature::SIGNING_CONTEXT, tx);
+            let mut aggregator = w3f_bls::SignatureAggregatorAssumingPoP::new(message.clone());
+            for index in &signers {
+                aggregator.add_signature(&keys[*index as usize].sign(&message));
+            }
+            let signature = (&aggregator).signature().to_bytes();
+
+            BlsSignersAndSignature { signers, signature }.encode()
+        }
+
+        #[test]
+        fn bls_with_enough_sigs_passes() {
+            let keys = bls_keys(3);
+            let simplified_tx = b"hello world".as_slice();
+
+            assert!(committee(2, &keys).verify(
+                simplified_tx,
+                &redeemer(&keys, vec![0, 2], simplified_tx),
+                &CONTEXT
+            ));
+        }
+
+        #[test]
+        fn bls_with_all_sigs_passes() {
+            let keys = bls_keys(3);
+            let simplified_tx = b"hello world".as_slice();
+
+            assert!(committee(2, &keys).verify(
+                simplified_tx,
+                &redeemer(&keys, vec![0, 1, 2], simplified_tx),
+                &CONTEXT
+            ));
+        }
+
+        #[test]
+        fn bls_not_enough_sigs_fails() {
+            let keys = bls_keys(3);
+            let simplified_tx = b"hello world".as_slice();
+
+            assert!(!committee(2, &keys).verify(
+                simplified_tx,
+                &redeemer(&keys, vec![1], simplified_tx),
+                &CONTEXT
+            ));
+        }
+
+        #[test]
+        fn bls_repeated_signer_fails() {
+            let keys = bls_keys(3);
+            let simplified_tx = b"hello world".as_slice();
+
+            assert!(!committee(2, &keys).verify(
+                simplified_tx,
+                &redeemer(&keys, vec![1, 1], simplified_tx),
+                &CONTEXT
+            ));
+        }
+
+        #[test]
+        fn bls_wrong_signers_claimed_fails() {
+            let keys = bls_keys(3);
+            let simplified_tx = b"hello world".as_slice();
+            let mut redeemer = BlsSignersAndSignature::decode(
+                &mut &redeemer(&keys, vec![0, 1], simplified_tx)[..],
+            )
+            .unwrap();
+            redeemer.signers = vec![0, 2];
+
+            assert!(!committee(2, &keys).verify(simplified_tx, &redeemer.encode(), &CONTEXT));
+        }
+
+        #[test]
+        fn bls_signer_out_of_bounds_fails() {
+            let keys = bls_keys(3);
+            let simplified_tx = b"hello world".as_slice();
+
+            assert!(!committee(1, &keys[..2]).verify(
+                simplified_tx,
+                &redeemer(&keys, vec![2], simplified_tx),
+                &CONTEXT
+            ));
+        }
+
+        #[test]
+        fn bls_bogus_redeemer_encoding_fails() {
+            let keys = bls_keys(3);
+
+            assert!(!committee(1, &keys).verify(
+                b"hello world".as_slice(),
+                b"bogus".as_slice(),
+                &CONTEXT
+            ));
+        }
+    }
+
+    #[cfg(feature = "wasm-verifier")]
+    mod wasm {
+        use super::*;
+
+        fn wasm_verifier(wat: &str) -> WasmVerifier {
+            WasmVerifier::new(wat::parse_str(wat).unwrap())
+        }
+
+        /// A program that passes if the redeemer is the single byte 42.
+        const ANSWER: &str = r#"
+            (module
+                (import "env" "redeemer_len" (func $redeemer_len (result i32)))
+                (import "env" "read_redeemer" (func $read_redeemer (param i32)))
+                (memory (export "memory") 1)
+                (func (export "verify") (result i32)
+                    (if (i32.ne (call $redeemer_len) (i32.const 1))
+                        (then (return (i32.const 0))))
+                    (call $read_redeemer (i32.const 0))
+                    (i32.eq (i32.load8_u (i32.const 0)) (i32.const 42))))
+        "#;
+
+        /// A program that passes if the redeemer is the same as the transaction.
+        const ECHO: &str = r#"
+            (module
+                (import "env" "tx_len" (func $tx_len (result i32)))
+                (import "env" "read_tx" (func $read_tx (param i32)))
+                (import "env" "redeemer_len" (func $redeemer_len (result i32)))
+                (import "env" "read_redeemer" (func $read_redeemer (param i32)))
+                (memory (export "memory") 1)
+                (func (export "verify") (result i32)
+                    (local $i i32)
+                    (if (i32.ne (call $tx_len) (call $redeemer_len))
+                        (then (return (i32.const 0))))
+                    (call $read_tx (i32.const 0))
+                    (call $read_redeemer (i32.const 1024))
+                    (block $done
+                        (loop $next
+                            (br_if $done (i32.ge_u (local.get $i) (call $tx_len)))
+                            (if (i32.ne
+                                    (i32.load8_u (local.get $i))
+                                    (i32.load8_u (i32.add (local.get $i) (i32.const 1024))))
+                                (then (return (i32.const 0))))
+                            (local.set $i (i32.add (local.get $i) (i32.const 1)))
+                            (br $next)))
+                    (i32.const 1)))
+        "#;
+
+        #[test]
+        fn wasm_returning_one_passes() {
+            let verifier =
+                wasm_verifier(r#"(module (func (export "verify") (result i32) i32.const 1))"#);
+            assert!(verifier.verify(&[], &[], &CONTEXT));
+        }
+
+        #[test]
+        fn wasm_returning_zero_fails() {
+            let verifier =
+                wasm_verifier(r#"(module (func (export "verify") (result i32) i32.const 0))"#);
+            assert!(!verifier.verify(&[], &[], &CONTEXT));
+        }
+
+        #[test]
+        fn wasm_reading_redeemer_works() {
+            let verifier = wasm_verifier(ANSWER);
+            assert!(verifier.verify(&[], &[42], &CONTEXT));
+            assert!(!verifier.verify(&[], &[41], &CONTEXT));
+            assert!(!verifier.verify(&[], &[42, 42], &CONTEXT));
+        }
+
+        #[test]
+        fn wasm_reading_tx_works() {
+            let verifier = wasm_verifier(ECHO);
+            assert!(verifier.verify(b"hello world", b"hello world", &CONTEXT));
+            assert!(!verifier.verify(b"hello world", b"hello there", &CONTEXT));
+        }
+
+        #[test]
+        fn wasm_reading_context_works() {
+            let verifier = wasm_verifier(
+                r#"(module
+                    (import "env" "block_height" (func $block_height (result i32)))
+                    (import "env" "timestamp" (func $timestamp (result i64)))
+                    (func (export "verify") (result i32)
+                        (i32.and
+                            (i32.ge_u (call $block_height) (i32.const 10))
+                            (i64.ge_u (call $timestamp) (i64.const 5000)))))"#,
+            );
+            let context = VerifierContext {
+                block_height: 10,
+                timestamp: 5_000,
+            };
+            assert!(verifier.verify(&[], &[], &context));
+            assert!(!verifier.verify(&[], &[], &CONTEXT));
+        }
+
+        #[test]
+        fn wasm_running_out_of_fuel_fails() {
+            let verifier = wasm_verifier(
+                r#"(module (func (export "verify") (result i32) (loop $l (br $l)) i32.const 1))"#,
+            );
+            assert!(!verifier.verify(&[], &[], &CONTEXT));
+        }
+
+        #[test]
+        fn wasm_without_verify_function_fails() {
+            let verifier =
+                wasm_verifier(r#"(module (func (export "check") (result i32) i32.const 1))"#);
+            assert!(!verifier.verify(&[], &[], &CONTEXT));
+        }
+
+        #[test]
+        fn wasm_importing_unknown_function_fails() {
+            let verifier = wasm_verifier(
+                r#"(module
+                    (import "env" "steal" (func $steal))
+                    (func (export "verify") (result i32) i32.const 1))"#,
+            );
+            assert!(!verifier.verify(&[], &[], &CONTEXT));
+        }
+
+        #[test]
+        fn wasm_bogus_code_fails() {
+            assert!(!WasmVerifier::new(b"bogus".to_vec()).verify(&[], &[], &CONTEXT));
+        }
+
+        #[test]
+        fn wasm_declaring_large_memory_fails() {
+            let verifier = wasm_verifier(
+                r#"(module
+                    (memory (export "memory") 65536)
+                    (func (export "verify") (result i32) i32.const 1))"#,
+            );
+            assert!(!verifier.verify(&[], &[], &CONTEXT));
+        }
+
+        #[test]
+        fn wasm_growing_memory_past_limit_fails() {
+            let verifier = wasm_verifier(
+                r#"(module
+                    (memory (export "memory") 1)
+                    (func (export "verify") (result i32)
+                        (i32.ne (memory.grow (i32.const 1024)) (i32.const -1))))"#,
+            );
+            assert!(!verifier.verify(&[], &[], &CONTEXT));
+        }
+
+        #[test]
+        fn wasm_declaring_large_table_fails() {
+            let verifier = wasm_verifier(
+                r#"(module
+                    (table 1000000 funcref)
+                    (func (export "verify") (result i32) i32.const 1))"#,
+            );
+            assert!(!verifier.verify(&[], &[], &CONTEXT));
+        }
+
+        /// Pad the program with a custom section, which does not change what it does, so that
+        /// its code has the given size.
+        fn padded(wat: &str, size: usize) -> WasmVerifier {
+            let mut code = wat::parse_str(wat).unwrap();
+            // The section id and a section length of up to three bytes, which is enough for
+            // lengths below 2^21, and the empty name of the section.
+            let payload = size - code.len() - 5;
+            let mut length = payload + 1;
+            code.push(0);
+            for _ in 0..2 {
+                code.push((length & 0x7f) as u8 | 0x80);
+                length >>= 7;
+            }
+            code.push(length as u8);
+            code.push(0);
+            code.extend(vec![0; payload]);
+            WasmVerifier::new(code)
+        }
+
+        #[test]
+        fn wasm_at_code_size_limit_passes() {
+            let wat = r#"(module (func (export "verify") (result i32) i32.const 1))"#;
+            let verifier = padded(wat, WASM_VERIFIER_MAX_CODE_SIZE);
+            assert_eq!(verifier.code.len(), WASM_VERIFIER_MAX_CODE_SIZE);
+            assert!(verifier.verify(&[], &[], &CONTEXT));
+        }
+
+        #[test]
+        fn wasm_above_code_size_limit_fails() {
+            let wat = r#"(module (func (export "verify") (result i32) i32.const 1))"#;
+            let verifier = padded(wat, WASM_VERIFIER_MAX_CODE_SIZE + 1);
+            assert!(!verifier.verify(&[], &[], &CONTEXT));
+        }
+
+        #[test]
+        fn wasm_weight_grows_with_code_size() {
+            let small = WasmVerifier::new(vec![0; 10]);
+            let large = WasmVerifier::new(vec![0; 1_000]);
+            assert!(small.weight() < large.weight());
+        }
     }
 
     #[test]
     fn test_verifier_passes() {
-        let result = TestVerifier { verifies: true }.verify(&[], &[]);
+        let result = TestVerifier { verifies: true }.verify(&[], &[], &CONTEXT);
         assert!(result);
     }
 
     #[test]
     fn test_verifier_fails() {
-        let result = TestVerifier { verifies: false }.verify(&[], &[]);
+        let result = TestVerifier { verifies: false }.verify(&[], &[], &CONTEXT);
         assert!(!result);
     }
 }
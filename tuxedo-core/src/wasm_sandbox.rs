@@ -0,0 +1,105 @@
+//! A sandbox for the small Wasm programs that some verifiers and constraint checkers run.
+//!
+//! Anyone may write these programs, and every validating node runs them, so all that a program
+//! may cost is bounded before it starts. The code is limited in size before it is compiled. Its
+//! memory and tables are limited when it is instantiated and whenever it grows them, so declaring
+//! a huge memory fails instead of allocating it. Once it runs, the interpreter charges fuel for
+//! each instruction and stops the program when the fuel runs out.
+
+use sp_std::vec::Vec;
+use wasmi::{
+    Caller, Config, Engine, Extern, Linker, Module, Store, StoreLimits, StoreLimitsBuilder,
+};
+
+use crate::weights::Weight;
+
+/// The most pages of linear memory, of 64 KiB each, that a program may have.
+pub const MAX_MEMORY_PAGES: usize = 16;
+
+/// The most elements that a program's table may have.
+pub const MAX_TABLE_ELEMENTS: u32 = 1_024;
+
+/// The weight of each unit of fuel that a program may consume.
+pub const FUEL_WEIGHT: Weight = 5_000;
+
+/// The weight of each byte of code that is compiled before a program runs.
+pub const CODE_BYTE_WEIGHT: Weight = 50_000;
+
+/// The weight of running a program of the given size with the given fuel.
+pub fn weight(code_size: usize, fuel: u64) -> Weight {
+    (code_size as Weight)
+        .saturating_mul(CODE_BYTE_WEIGHT)
+        .saturating_add(fuel.saturating_mul(FUEL_WEIGHT))
+}
+
+/// The data that a program may read through its host functions, along with the limits of the
+/// store that it runs in.
+pub struct Host<T> {
+    /// The data that the host functions read
+    pub data: T,
+    limits: StoreLimits,
+}
+
+/// The engine that programs are compiled and run with, which meters fuel.
+pub fn engine() -> Engine {
+    let mut config = Config::default();
+    config.consume_fuel(true);
+    Engine::new(&config)
+}
+
+/// Compile the given code, which checks that it is valid Wasm and no longer than `max_code_size`.
+pub fn compile(engine: &Engine, code: &[u8], max_code_size: usize) -> Option<Module> {
+    if code.len() > max_code_size {
+        return None;
+    }
+    Module::new(engine, code).ok()
+}
+
+/// Write some of the host data into the program's memory at the given pointer.
+pub fn write<T>(caller: &mut Caller<'_, Host<T>>, ptr: i32, read: fn(&T) -> &[u8]) {
+    let data: Vec<u8> = read(&caller.data().data).to_vec();
+    if let Some(memory) = caller.get_export("memory").and_then(Extern::into_memory) {
+        // If the program points outside its memory, it simply reads nothing.
+        let _ = memory.write(caller, ptr as usize, &data);
+    }
+}
+
+/// Run a program's exported function `entry`, which takes no parameters and returns an `i32`.
+///
+/// The program's host functions are added to the linker by `link`, and read `data`. Returns `None`
+/// if the program is too large, can not be instantiated within the limits, or does not run to
+/// completion.
+pub fn run<T>(
+    code: &[u8],
+    max_code_size: usize,
+    fuel: u64,
+    data: T,
+    entry: &str,
+    link: impl FnOnce(&mut Linker<Host<T>>) -> Option<()>,
+) -> Option<i32> {
+    let engine = engine();
+    let module = compile(&engine, code, max_code_size)?;
+
+    let limits = StoreLimitsBuilder::new()
+        .memory_size(MAX_MEMORY_PAGES * 64 * 1024)
+        .table_elements(MAX_TABLE_ELEMENTS)
+        .instances(1)
+        .memories(1)
+        .tables(1)
+        .build();
+    let mut store = Store::new(&engine, Host { data, limits });
+    store.limiter(|host| &mut host.limits);
+    store.add_fuel(fuel).ok()?;
+
+    let mut linker = <Linker<Host<T>>>::new(&engine);
+    link(&mut linker)?;
+
+    let instance = linker
+        .instantiate(&mut store, &module)
+        .ok()?
+        .start(&mut store)
+        .ok()?;
+    let entry = instance.get_typed_func::<(), i32>(&store, entry).ok()?;
+
+    entry.call(&mut store, ()).ok()
+}
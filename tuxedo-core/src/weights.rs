@@ -0,0 +1,208 @@
+//! Accounting for the resources that transactions use, and charging fees for them.
+//!
+//! Weight measures how long a transaction takes to execute. Verifiers and constraint checkers each
+//! declare the weight of their own logic, and the executive adds the weight of the storage accesses
+//! that it makes on the transaction's behalf. Like FRAME's ref time, weight is measured in
+//! picoseconds on reference hardware. The executive makes sure that the transactions in a block
+//! never use more than the block's weight limit together.
+//!
+//! Every transaction that is not an inherent must also pay a fee that depends on its weight and
+//! its encoded length. By convention, a constraint checker reports the value that a transaction
+//! burns (its inputs minus its outputs) from `check`, so the fee is paid by that surplus. Whatever
+//! is left over is a tip, and the transaction is prioritized by how much it tips for the weight
+//! that it uses. See the [`fees`](crate::fees) module for how fees and tips are then redistributed.
+//! Runtimes may also require new outputs to lock a deposit, which is paid from the same surplus.
+//! See the [`deposits`](crate::deposits) module.
+
+use parity_scale_codec::{Decode, Encode};
+use sp_runtime::transaction_validity::TransactionPriority;
+
+/// An amount of execution time, in picoseconds on reference hardware.
+pub type Weight = u64;
+
+/// The weight of one second of execution.
+pub const WEIGHT_PER_SECOND: Weight = 1_000_000_000_000;
+
+/// The weight of reading a single value from storage.
+pub const READ_WEIGHT: Weight = 25_000_000;
+
+/// The weight of writing or clearing a single value in storage.
+pub const WRITE_WEIGHT: Weight = 100_000_000;
+
+/// The weight of verifying a single signature.
+pub const SIGNATURE_WEIGHT: Weight = 50_000_000;
+
+/// The weight of a constraint checker that does not declare its own.
+pub const DEFAULT_CHECKER_WEIGHT: Weight = 10_000_000;
+
+/// A transient storage key that holds the weight used so far in the current block.
+/// This key is cleared before the end of the block.
+pub const BLOCK_WEIGHT_KEY: &[u8] = b"block_weight";
+
+/// Options to configure weights, fees, and the shape of transactions in your runtime.
+///
+/// The default configuration, which is implemented for `()`, charges no fees.
+pub trait WeightConfig {
+    /// The most weight that the transactions in a single block may use together.
+    ///
+    /// Default is one second, which leaves plenty of time to propagate blocks
+    /// with three second block times.
+    const MAXIMUM_BLOCK_WEIGHT: Weight = WEIGHT_PER_SECOND;
+
+    /// The most inputs that a single transaction may have.
+    ///
+    /// This and the following limits keep a single gigantic transaction from stalling block
+    /// import. They do not apply to inherents, which only the block author can include.
+    const MAXIMUM_INPUTS: u32 = 1_024;
+
+    /// The most peeks that a single transaction may have.
+    const MAXIMUM_PEEKS: u32 = 1_024;
+
+    /// The most outputs that a single transaction may have.
+    const MAXIMUM_OUTPUTS: u32 = 1_024;
+
+    /// The longest that a single transaction may be when encoded, in bytes.
+    ///
+    /// Default is half a mebibyte.
+    const MAXIMUM_TRANSACTION_LENGTH: u32 = 512 * 1_024;
+
+    /// The longest memo that a single transaction may carry, in bytes.
+    const MAXIMUM_MEMO_LENGTH: u32 = 256;
+
+    /// The deposit that a new output locks for every byte of its encoded size. It is refunded when
+    /// the output is removed. See the [`deposits`](crate::deposits) module.
+    ///
+    /// Default is zero, which disables deposits.
+    const DEPOSIT_PER_BYTE: u128 = 0;
+
+    /// Whether the executive remembers the block height at which every output is created, so that
+    /// constraint checkers know how old their inputs are. See the [`ages`](crate::ages) module.
+    ///
+    /// Default is false, which saves a storage write for every output.
+    const TRACK_AGES: bool = false;
+
+    /// The fee for using the given weight.
+    fn weight_to_fee(_weight: Weight) -> u128 {
+        0
+    }
+
+    /// The fee for a transaction of the given encoded length, in bytes.
+    fn length_to_fee(_length: usize) -> u128 {
+        0
+    }
+}
+
+impl WeightConfig for () {}
+
+/// The fee that a transaction with the given weight and encoded length must pay.
+pub fn fee<W: WeightConfig>(weight: Weight, length: usize) -> u128 {
+    W::weight_to_fee(weight).saturating_add(W::length_to_fee(length))
+}
+
+/// The deposit that an output of the given encoded length, in bytes, must lock.
+pub fn deposit<W: WeightConfig>(length: usize) -> u128 {
+    W::DEPOSIT_PER_BYTE.saturating_mul(length as u128)
+}
+
+/// The priority of a transaction that uses the given weight and pays the given tip on top of its fee.
+///
+/// Blocks are limited by weight, so this is the tip per second of weight rather than the tip alone.
+/// That way a heavy transaction must tip more than a light one to be included first.
+pub fn priority(tip: u128, weight: Weight) -> TransactionPriority {
+    let tip_per_second = tip.saturating_mul(WEIGHT_PER_SECOND.into()) / u128::from(weight.max(1));
+    tip_per_second
+        .try_into()
+        .unwrap_or(TransactionPriority::MAX)
+}
+
+/// The weight that has been used so far in the current block.
+pub fn block_weight() -> Weight {
+    sp_io::storage::get(BLOCK_WEIGHT_KEY)
+        .and_then(|d| Weight::decode(&mut &*d).ok())
+        .unwrap_or_default()
+}
+
+/// Note that some weight has been used in the current block.
+pub(crate) fn note(weight: Weight) {
+    let total = block_weight().saturating_add(weight);
+    sp_io::storage::set(BLOCK_WEIGHT_KEY, &total.encode());
+}
+
+/// Forget the weight used in the block. This is called when the block is finished.
+pub(crate) fn clear() {
+    sp_io::storage::clear(BLOCK_WEIGHT_KEY);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sp_io::TestExternalities;
+
+    /// Charges one per million weight, and ten per byte.
+    struct TestConfig;
+
+    impl WeightConfig for TestConfig {
+        fn weight_to_fee(weight: Weight) -> u128 {
+            (weight / 1_000_000) as u128
+        }
+
+        fn length_to_fee(length: usize) -> u128 {
+            10 * length as u128
+        }
+    }
+
+    #[test]
+    fn default_config_charges_nothing() {
+        assert_eq!(fee::<()>(SIGNATURE_WEIGHT, 1_000), 0);
+    }
+
+    #[test]
+    fn fee_includes_weight_and_length() {
+        assert_eq!(fee::<TestConfig>(SIGNATURE_WEIGHT, 7), 120);
+    }
+
+    #[test]
+    fn default_config_requires_no_deposit() {
+        assert_eq!(deposit::<()>(1_000), 0);
+    }
+
+    #[test]
+    fn deposit_is_proportional_to_length() {
+        struct DepositConfig;
+        impl WeightConfig for DepositConfig {
+            const DEPOSIT_PER_BYTE: u128 = 3;
+        }
+
+        assert_eq!(deposit::<DepositConfig>(7), 21);
+    }
+
+    #[test]
+    fn priority_is_tip_per_second() {
+        assert_eq!(priority(3, WEIGHT_PER_SECOND / 2), 6);
+        assert_eq!(priority(0, READ_WEIGHT), 0);
+    }
+
+    #[test]
+    fn heavier_transactions_need_bigger_tips() {
+        assert!(priority(10, READ_WEIGHT) > priority(10, WRITE_WEIGHT));
+        assert_eq!(priority(1, READ_WEIGHT), priority(4, WRITE_WEIGHT));
+    }
+
+    #[test]
+    fn priority_saturates() {
+        assert_eq!(priority(u128::MAX, 1), TransactionPriority::MAX);
+        assert_eq!(priority(1, 0), WEIGHT_PER_SECOND);
+    }
+
+    #[test]
+    fn block_weight_accumulates() {
+        TestExternalities::default().execute_with(|| {
+            assert_eq!(block_weight(), 0);
+            note(READ_WEIGHT);
+            note(WRITE_WEIGHT);
+            assert_eq!(block_weight(), READ_WEIGHT + WRITE_WEIGHT);
+            clear();
+            assert_eq!(block_weight(), 0);
+        });
+    }
+}
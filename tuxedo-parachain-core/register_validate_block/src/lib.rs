@@ -5,7 +5,7 @@ use proc_macro2::Span;
 use proc_macro_crate::{crate_name, FoundCrate};
 use syn::{
     parse::{Parse, ParseStream},
-    Error, Ident, Token,
+    Error, Ident, Token, Type,
 };
 
 /// Provides an identifier that is a safe way to refer to the crate tuxedo_core within the macro
@@ -23,22 +23,44 @@ struct RegisterValidateBlockInput {
     pub verifier: Ident,
     _comma2: Token![,],
     pub constraint_checker: Ident,
+    pub weight_config: Option<Type>,
+    pub utxo_set: Option<Type>,
+    pub runtime_version: Option<Type>,
 }
 
 impl Parse for RegisterValidateBlockInput {
     fn parse(input: ParseStream) -> syn::Result<Self> {
-        let parsed = Self {
+        let mut parsed = Self {
             block: input.parse()?,
             _comma1: input.parse()?,
             verifier: input.parse()?,
             _comma2: input.parse()?,
             constraint_checker: input.parse()?,
+            weight_config: None,
+            utxo_set: None,
+            runtime_version: None,
         };
 
+        // The weight config is optional, and the runtime charges no fees without it.
+        if input.parse::<Option<Token![,]>>()?.is_some() && !input.is_empty() {
+            parsed.weight_config = Some(input.parse()?);
+        }
+
+        // The utxo set is optional too, and the runtime uses the transparent utxo set without it.
+        if input.parse::<Option<Token![,]>>()?.is_some() && !input.is_empty() {
+            parsed.utxo_set = Some(input.parse()?);
+        }
+
+        // So is the runtime version. Without it, signatures commit to the default versions.
+        if input.parse::<Option<Token![,]>>()?.is_some() && !input.is_empty() {
+            parsed.runtime_version = Some(input.parse()?);
+            input.parse::<Option<Token![,]>>()?;
+        }
+
         if !input.is_empty() {
             return Err(Error::new(
                 input.span(),
-                "Expected exactly three parameters: Block, Verifier, ConstraintChecker.",
+                "Expected three to six parameters: Block, Verifier, ConstraintChecker, and optionally WeightConfig, UtxoSet, and RuntimeVersion.",
             ));
         }
 
@@ -59,6 +81,10 @@ pub fn register_validate_block(input: proc_macro::TokenStream) -> proc_macro::To
     let block = input.block.clone();
     let verifier = input.verifier.clone();
     let constraint_checker = input.constraint_checker.clone();
+    let weight_config = match input.weight_config {
+        Some(weight_config) => quote::quote! { #weight_config },
+        None => quote::quote! { () },
+    };
 
     // A way to refer to the tuxedo_parachain_core crate from within the macro.
     let crate_ = match crate_() {
@@ -66,6 +92,16 @@ pub fn register_validate_block(input: proc_macro::TokenStream) -> proc_macro::To
         Err(e) => return e.into_compile_error().into(),
     };
 
+    let utxo_set = match input.utxo_set {
+        Some(utxo_set) => quote::quote! { #utxo_set },
+        None => quote::quote! { #crate_::tuxedo_core::utxo_set::TransparentUtxoSet<#verifier> },
+    };
+
+    let runtime_version = match input.runtime_version {
+        Some(runtime_version) => quote::quote! { #runtime_version },
+        None => quote::quote! { () },
+    };
+
     //TODO We need to check inherents. At least the timestamp one, and maybe also the parachain one?
     // https://github.com/Off-Narrative-Labs/Tuxedo/issues/144
     // But I think the parachain one is handled already.
@@ -114,6 +150,9 @@ pub fn register_validate_block(input: proc_macro::TokenStream) -> proc_macro::To
                         #block,
                         #verifier,
                         #constraint_checker,
+                        #weight_config,
+                        #utxo_set,
+                        #runtime_version,
                     >(params);
 
                     // Step 3: Write the return value back into the shared memory
@@ -1,26 +1,32 @@
 //! Tuxedo's implementation of the CollectCollationInfoApi.
-//! It is pretty basic and just returns the encoded ehader along with some empty data.
-//! It will get more complex and interesting when we start to support XCM or parachain runtime upgrades.
+//! It is pretty basic and returns the encoded header along with the outbound messages and the number of
+//! processed downward messages. It will get more complex and interesting when we start to support
+//! parachain runtime upgrades.
 
 use cumulus_primitives_core::{relay_chain::HeadData, CollationInfo};
 use parity_scale_codec::Encode;
 use sp_api::BlockT;
-use sp_std::vec::Vec;
 use tuxedo_core::Executive;
 
-use crate::{GetRelayParentNumberStorage, RelayParentNumberStorage};
+use crate::{
+    DownwardMessagesStorage, GetDownwardMessagesStorage, GetRelayParentNumberStorage,
+    OutboundHorizontalMessages, RelayParentNumberStorage, UpwardMessages,
+};
 
 /// An extension trait that allows us to implement more methods on tuxedo-core's executive.
 pub trait ParachainExecutiveExtension<Header> {
     fn collect_collation_info(header: &Header) -> cumulus_primitives_core::CollationInfo;
 }
 
-impl<B: BlockT, V, C> ParachainExecutiveExtension<B::Header> for Executive<B, V, C> {
+impl<B: BlockT, V, C, W, U, O, S, R, P> ParachainExecutiveExtension<B::Header>
+    for Executive<B, V, C, W, U, O, S, R, P>
+{
     fn collect_collation_info(header: &B::Header) -> cumulus_primitives_core::CollationInfo {
-        // The implementation here is simple. Most of the fields are related to xcm and parachain runtime upgrades,
-        // neither or which are supported in the PoC, so they are left blank.
+        // The implementation here is simple. Parachain runtime upgrades are not supported
+        // in the PoC, so that field is left blank.
 
-        // Get the relay parent number out of storage so we can advance the hrmp watermark
+        // Get the relay parent number out of storage so we can advance the hrmp watermark.
+        // Every inbound horizontal message up to the relay parent is delivered in the same block.
         let hrmp_watermark = RelayParentNumberStorage::get();
 
         // The final field allows us to specify head data. We will do the boring / standard / default / original
@@ -30,10 +36,11 @@ impl<B: BlockT, V, C> ParachainExecutiveExtension<B::Header> for Executive<B, V,
         // https://github.com/paritytech/cumulus/pull/825 and https://github.com/paritytech/cumulus/pull/882
         // and https://substrate.stackexchange.com/q/10522/372
         CollationInfo {
-            upward_messages: Vec::new(),
-            horizontal_messages: Vec::new(),
+            upward_messages: UpwardMessages::get(),
+            horizontal_messages: OutboundHorizontalMessages::get(),
             new_validation_code: None,
-            processed_downward_messages: 0,
+            // Every downward message that the relay chain delivers is processed in the same block.
+            processed_downward_messages: DownwardMessagesStorage::get().len() as u32,
             hrmp_watermark,
             head_data: HeadData(header.encode()),
         }
@@ -8,7 +8,9 @@
 //!
 //! It's primary jobs are to
 //! * Manage transiet storage details for the parachain inherent, specifically the relay
-//!   parent block number.
+//!   parent block number, the relay chain state proof, the downward messages, and the horizontal
+//!   messaging state.
+//! * Collect the upward and horizontal messages that pieces send.
 //! * Provide collation information to the client side collator service.
 //! * Implement the `validate_block` funtion required by relay chain validators.
 //!   This task is achieved through the `register_validate_block!` macro.
@@ -26,8 +28,14 @@ pub mod validate_block;
 
 mod collation_api;
 mod relay_state_snapshot;
+mod unincluded_segment;
 pub use collation_api::ParachainExecutiveExtension;
 use parity_scale_codec::{Decode, Encode};
+pub use unincluded_segment::{
+    Ancestor, GetUnincludedSegmentStorage, MockUnincludedSegmentStorage,
+    SetUnincludedSegmentStorage, UnincludedSegment, UnincludedSegmentError,
+    UnincludedSegmentStorage,
+};
 
 #[cfg(not(feature = "std"))]
 #[doc(hidden)]
@@ -54,7 +62,16 @@ pub use sp_std;
 /// accidental version mismatches.
 pub use tuxedo_core;
 
+use cumulus_primitives_core::{
+    relay_chain, AbridgedHrmpChannel, InboundDownwardMessage, InboundHrmpMessage,
+    OutboundHrmpMessage, ParaId, UpwardMessage,
+};
 use cumulus_primitives_parachain_inherent::ParachainInherentData;
+use relay_state_snapshot::RelayChainStateProof;
+use sp_core::H256;
+use sp_runtime::traits::{BlakeTwo256, Hash};
+use sp_std::{collections::btree_map::BTreeMap, vec::Vec};
+use sp_trie::StorageProof;
 use tuxedo_core::{
     dynamic_typing::UtxoData,
     support_macros::{CloneNoBound, DebugNoBound},
@@ -103,6 +120,352 @@ impl SetRelayParentNumberStorage for MockRelayParentNumberStorage {
     fn set(_new_parent_number: u32) {}
 }
 
+/// Reasons that an entry of the relay chain state can not be read.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RelayStateError {
+    /// No relay chain state proof was noted in the current block.
+    Unavailable,
+    /// The proof does not prove the relay parent storage root.
+    RootMismatch,
+    /// The proof does not cover the entry.
+    NotInProof,
+}
+
+/// An abstraction over reading entries of the relay parent's state.
+/// This allows it to be mocked during tests and not require actual externalities.
+pub trait ReadRelayState {
+    /// The raw value of the given relay chain storage entry, or `None` if the entry is absent
+    /// on the relay chain.
+    fn read(key: &[u8]) -> Result<Option<Vec<u8>>, RelayStateError>;
+}
+
+/// An abstraction over setting the ambiently available relay chain state proof.
+/// This allows it to be mocked during tests and not require actual externalities.
+pub trait SetRelayStateProofStorage {
+    fn set(relay_parent_storage_root: relay_chain::Hash, proof: StorageProof);
+}
+
+/// A public interface for noting the relay chain state proof and reading entries from it. The proof
+/// is set by the parachain piece, and may be read by any other piece.
+///
+/// The proof only covers the entries that the collator chose to include, which are the well known
+/// keys such as the relay chain's randomness and active configuration. Reading any other entry
+/// fails with [`RelayStateError::NotInProof`]. In validate_block, the storage root is checked
+/// against the relay parent's, so entries that can be read are the relay chain's actual state.
+pub enum RelayStateProofStorage {}
+
+impl ReadRelayState for RelayStateProofStorage {
+    fn read(key: &[u8]) -> Result<Option<Vec<u8>>, RelayStateError> {
+        let (storage_root, proof): (relay_chain::Hash, StorageProof) =
+            sp_io::storage::get(RELAY_STATE_PROOF_KEY)
+                .and_then(|encoded| Decode::decode(&mut &encoded[..]).ok())
+                .ok_or(RelayStateError::Unavailable)?;
+
+        // The para id is only used when reading the messaging state, so any will do.
+        RelayChainStateProof::new(ParaId::default(), storage_root, proof)
+            .map_err(|_| RelayStateError::RootMismatch)?
+            .read_raw_entry(key)
+            .map_err(|_| RelayStateError::NotInProof)
+    }
+}
+
+impl SetRelayStateProofStorage for RelayStateProofStorage {
+    fn set(relay_parent_storage_root: relay_chain::Hash, proof: StorageProof) {
+        sp_io::storage::set(
+            RELAY_STATE_PROOF_KEY,
+            &(relay_parent_storage_root, proof).encode(),
+        );
+    }
+}
+
+/// A mock version of the RelayStateProofStorage that can be used in tests without externalities.
+pub enum MockRelayStateProofStorage {}
+
+impl SetRelayStateProofStorage for MockRelayStateProofStorage {
+    fn set(_relay_parent_storage_root: relay_chain::Hash, _proof: StorageProof) {}
+}
+
+/// A transient storage key that will hold the downward messages that the relay chain delivers
+/// to the current parachain block. This data enters the parachain through the parachain inherent.
+const DOWNWARD_MESSAGES_KEY: &[u8] = b"downward_messages";
+
+/// A storage key that holds the messages that the current parachain block sends up to the relay
+/// chain, along with the relay parent number of that block.
+const UPWARD_MESSAGES_KEY: &[u8] = b"upward_messages";
+
+/// A transient storage key that will hold the relay chain state proof of the current block, along
+/// with the relay parent storage root that it proves. This data enters the parachain through the
+/// parachain inherent.
+const RELAY_STATE_PROOF_KEY: &[u8] = b"relay_state_proof";
+
+/// A transient storage key that will hold the horizontal messaging state of the current block.
+/// This data enters the parachain through the parachain inherent.
+const HORIZONTAL_MESSAGING_KEY: &[u8] = b"horizontal_messaging";
+
+/// A storage key that holds the messages that the current parachain block sends to other
+/// parachains, along with the relay parent number of that block.
+const OUTBOUND_HORIZONTAL_MESSAGES_KEY: &[u8] = b"outbound_horizontal_messages";
+
+/// An abstraction over reading the ambiently available downward messages.
+/// This allows it to be mocked during tests and not require actual externalities.
+pub trait GetDownwardMessagesStorage {
+    fn get() -> Vec<InboundDownwardMessage>;
+}
+
+/// An abstraction over setting the ambiently available downward messages.
+/// This allows it to be mocked during tests and not require actual externalities.
+pub trait SetDownwardMessagesStorage {
+    fn set(messages: Vec<InboundDownwardMessage>);
+}
+
+/// A public interface for accessing and mutating the downward messages of the current block.
+/// These are set by the parachain piece, and may be processed by any other piece.
+///
+/// The messages are not yet checked against the message queue chain in the relay chain state proof.
+/// Like the rest of the parachain inherent, they are trusted as the collator provided them.
+pub enum DownwardMessagesStorage {}
+
+impl GetDownwardMessagesStorage for DownwardMessagesStorage {
+    fn get() -> Vec<InboundDownwardMessage> {
+        sp_io::storage::get(DOWNWARD_MESSAGES_KEY)
+            .and_then(|encoded| Decode::decode(&mut &encoded[..]).ok())
+            .unwrap_or_default()
+    }
+}
+
+impl SetDownwardMessagesStorage for DownwardMessagesStorage {
+    fn set(messages: Vec<InboundDownwardMessage>) {
+        sp_io::storage::set(DOWNWARD_MESSAGES_KEY, &messages.encode());
+    }
+}
+
+/// A mock version of the DownwardMessagesStorage that can be used in tests without externalities.
+pub enum MockDownwardMessagesStorage {}
+
+impl SetDownwardMessagesStorage for MockDownwardMessagesStorage {
+    fn set(_messages: Vec<InboundDownwardMessage>) {}
+}
+
+/// Identifies the current parachain block by its parent's head hash, which the parachain piece
+/// records in the unincluded segment. Before the parachain inherent runs, this is the zero hash.
+fn current_block() -> H256 {
+    UnincludedSegmentStorage::get()
+        .current()
+        .map(|ancestor| ancestor.parent_head_hash)
+        .unwrap_or_default()
+}
+
+/// An abstraction over sending a message up to the relay chain.
+/// This allows it to be mocked during tests and not require actual externalities.
+pub trait SendUpwardMessage {
+    fn send(message: UpwardMessage);
+}
+
+/// The messages that the current parachain block sends up to the relay chain. These are
+/// reported to the collator through the collation API, and to the relay chain in validate_block.
+///
+/// The messages are stored along with the parent hash of the block that sent them, so the messages
+/// of earlier blocks are ignored and never need to be cleaned up. With async backing several blocks
+/// may share a relay parent, so the relay parent number would not tell them apart.
+pub enum UpwardMessages {}
+
+impl UpwardMessages {
+    /// The messages that the current block sends up to the relay chain.
+    pub fn get() -> Vec<UpwardMessage> {
+        let block = current_block();
+        sp_io::storage::get(UPWARD_MESSAGES_KEY)
+            .and_then(|encoded| <(H256, Vec<UpwardMessage>)>::decode(&mut &encoded[..]).ok())
+            .filter(|(sent_by, _)| *sent_by == block)
+            .map(|(_, messages)| messages)
+            .unwrap_or_default()
+    }
+}
+
+impl SendUpwardMessage for UpwardMessages {
+    fn send(message: UpwardMessage) {
+        let mut messages = Self::get();
+        messages.push(message);
+        sp_io::storage::set(UPWARD_MESSAGES_KEY, &(current_block(), messages).encode());
+    }
+}
+
+/// The horizontal messaging state of the current parachain block.
+#[derive(Encode, Decode, Default, Clone, Debug, PartialEq, Eq)]
+pub struct HorizontalMessagingState {
+    /// The messages that other parachains sent to this one, by sender.
+    pub inbound: BTreeMap<ParaId, Vec<InboundHrmpMessage>>,
+    /// The channels that this parachain can send messages through, sorted by recipient.
+    pub egress_channels: Vec<(ParaId, AbridgedHrmpChannel)>,
+}
+
+/// Read the channels that this parachain can send messages through from the relay chain state
+/// proof in the parachain inherent data.
+///
+/// A proof that can not be read gives no channels, so no messages can be sent in that block.
+pub fn read_egress_channels(
+    para_id: u32,
+    data: &ParachainInherentData,
+) -> Vec<(ParaId, AbridgedHrmpChannel)> {
+    let snapshot = RelayChainStateProof::new(
+        para_id.into(),
+        data.validation_data.relay_parent_storage_root,
+        data.relay_chain_state.clone(),
+    )
+    .and_then(|proof| {
+        let host_config = proof.read_abridged_host_configuration()?;
+        proof.read_messaging_state_snapshot(&host_config)
+    });
+
+    match snapshot {
+        Ok(snapshot) => snapshot.egress_channels,
+        Err(e) => {
+            log::warn!(
+                "Unable to read the egress channels from the relay chain state proof: {:?}",
+                e
+            );
+            Vec::new()
+        }
+    }
+}
+
+/// Read the hash of the latest parachain head that the relay chain included from the relay chain
+/// state proof in the parachain inherent data. This is used to prune the unincluded segment.
+///
+/// A proof that can not be read gives no included head, so nothing is pruned in that block.
+pub fn read_included_head_hash(para_id: u32, data: &ParachainInherentData) -> Option<H256> {
+    let head = RelayChainStateProof::new(
+        para_id.into(),
+        data.validation_data.relay_parent_storage_root,
+        data.relay_chain_state.clone(),
+    )
+    .and_then(|proof| proof.read_included_para_head());
+
+    match head {
+        Ok(head) => Some(BlakeTwo256::hash(&head.0)),
+        Err(e) => {
+            log::warn!(
+                "Unable to read the included head from the relay chain state proof: {:?}",
+                e
+            );
+            None
+        }
+    }
+}
+
+/// An abstraction over reading the ambiently available horizontal messaging state.
+/// This allows it to be mocked during tests and not require actual externalities.
+pub trait GetHorizontalMessagingStorage {
+    fn get() -> HorizontalMessagingState;
+}
+
+/// An abstraction over setting the ambiently available horizontal messaging state.
+/// This allows it to be mocked during tests and not require actual externalities.
+pub trait SetHorizontalMessagingStorage {
+    fn set(state: HorizontalMessagingState);
+}
+
+/// A public interface for accessing and mutating the horizontal messaging state of the current
+/// block. This is set by the parachain piece, and may be used by any other piece.
+///
+/// Like the downward messages, the inbound messages are not yet checked against the message queue
+/// chains in the relay chain state proof. The egress channels are read from the proof itself.
+pub enum HorizontalMessagingStorage {}
+
+impl GetHorizontalMessagingStorage for HorizontalMessagingStorage {
+    fn get() -> HorizontalMessagingState {
+        sp_io::storage::get(HORIZONTAL_MESSAGING_KEY)
+            .and_then(|encoded| Decode::decode(&mut &encoded[..]).ok())
+            .unwrap_or_default()
+    }
+}
+
+impl SetHorizontalMessagingStorage for HorizontalMessagingStorage {
+    fn set(state: HorizontalMessagingState) {
+        sp_io::storage::set(HORIZONTAL_MESSAGING_KEY, &state.encode());
+    }
+}
+
+/// A mock version of the HorizontalMessagingStorage that can be used in tests without externalities.
+pub enum MockHorizontalMessagingStorage {}
+
+impl SetHorizontalMessagingStorage for MockHorizontalMessagingStorage {
+    fn set(_state: HorizontalMessagingState) {}
+}
+
+/// Reasons that a horizontal message can not be sent.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HorizontalMessageError {
+    /// There is no open channel to the recipient.
+    NoChannel,
+    /// The message is larger than the channel allows.
+    MessageTooLarge,
+    /// The channel has no room for another message.
+    ChannelFull,
+    /// The block already sends a message to the recipient. The relay chain accepts at most one
+    /// message per recipient in each block.
+    RecipientBusy,
+}
+
+/// An abstraction over sending a message to another parachain.
+/// This allows it to be mocked during tests and not require actual externalities.
+pub trait SendHorizontalMessage {
+    fn send(message: OutboundHrmpMessage) -> Result<(), HorizontalMessageError>;
+}
+
+/// The messages that the current parachain block sends to other parachains. These are reported
+/// to the collator through the collation API, and to the relay chain in validate_block.
+///
+/// Messages are only accepted when they fit in an open channel, because the relay chain rejects
+/// any block whose messages do not. The messages are kept sorted by recipient, as the relay chain
+/// requires. Like the upward messages, they are stored along with the parent hash of the block that
+/// sent them, so the messages of earlier blocks are ignored.
+pub enum OutboundHorizontalMessages {}
+
+impl OutboundHorizontalMessages {
+    /// The messages that the current block sends to other parachains, sorted by recipient.
+    pub fn get() -> Vec<OutboundHrmpMessage> {
+        let block = current_block();
+        sp_io::storage::get(OUTBOUND_HORIZONTAL_MESSAGES_KEY)
+            .and_then(|encoded| <(H256, Vec<OutboundHrmpMessage>)>::decode(&mut &encoded[..]).ok())
+            .filter(|(sent_by, _)| *sent_by == block)
+            .map(|(_, messages)| messages)
+            .unwrap_or_default()
+    }
+}
+
+impl SendHorizontalMessage for OutboundHorizontalMessages {
+    fn send(message: OutboundHrmpMessage) -> Result<(), HorizontalMessageError> {
+        let channels = HorizontalMessagingStorage::get().egress_channels;
+        let (_, channel) = channels
+            .iter()
+            .find(|(recipient, _)| *recipient == message.recipient)
+            .ok_or(HorizontalMessageError::NoChannel)?;
+
+        let size = message.data.len() as u32;
+        if size > channel.max_message_size {
+            return Err(HorizontalMessageError::MessageTooLarge);
+        }
+        if channel.msg_count >= channel.max_capacity
+            || channel.total_size.saturating_add(size) > channel.max_total_size
+        {
+            return Err(HorizontalMessageError::ChannelFull);
+        }
+
+        let mut messages = Self::get();
+        let index = match messages.binary_search_by_key(&message.recipient, |m| m.recipient) {
+            Ok(_) => return Err(HorizontalMessageError::RecipientBusy),
+            Err(index) => index,
+        };
+        messages.insert(index, message);
+        sp_io::storage::set(
+            OUTBOUND_HORIZONTAL_MESSAGES_KEY,
+            &(current_block(), messages).encode(),
+        );
+
+        Ok(())
+    }
+}
+
 /// Basically the same as
 /// [`ValidationParams`](polkadot_parachain_primitives::primitives::ValidationParams), but a little
 /// bit optimized for our use case here.
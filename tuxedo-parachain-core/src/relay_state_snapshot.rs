@@ -18,8 +18,8 @@
 //! This is copied entirely from Cumulus. Some of it will liekly be removed in the future, and
 //! perhaps some added as well.
 //!
-//! For now it is mostly unused and is kept here in preparation for a fully secure validate_block
-//! implementation and XCM support.
+//! For now it is only used to read the egress HRMP channels and the entries that constraint checkers
+//! peek at, and the rest is kept here in preparation for a fully secure validate_block implementation.
 
 // These warnings should be unsuppressed after validate_block is complete and XCM is working.
 // FIXME https://github.com/Off-Narrative-Labs/Tuxedo/issues/149
@@ -392,4 +392,14 @@ impl RelayChainStateProof {
     {
         read_optional_entry(&self.trie_backend, key).map_err(Error::ReadOptionalEntry)
     }
+
+    /// Read the raw value of an optional entry given by the key, without decoding it.
+    ///
+    /// Returns `Err` in case the backend can't return the value under the specific key, for
+    /// example because the proof does not cover it.
+    pub fn read_raw_entry(&self, key: &[u8]) -> Result<Option<Vec<u8>>, Error> {
+        self.trie_backend
+            .storage(key)
+            .map_err(|_| Error::ReadOptionalEntry(ReadEntryErr::Proof))
+    }
 }
@@ -0,0 +1,241 @@
+//! The unincluded segment is the chain of parachain blocks that have been built but are not yet
+//! included in the relay chain.
+//!
+//! With synchronous backing, each parachain block is included in the relay chain before the next one
+//! is built on top of it, so the segment never holds more than the block being built. Async backing
+//! lets collators build ahead, on blocks that are still waiting to be included, and to build more than
+//! one block on the same relay parent. The parachain piece advances the segment in every block, and
+//! rejects the block if the segment grows beyond its capacity, or if too many of its blocks share a
+//! relay parent.
+//!
+//! Each entry of the segment notes the hash of its block's parent head, rather than of its block's own
+//! head, because a block can not know its own hash while it is executing. The blocks that the relay
+//! chain has included are pruned from the segment by the included head that the relay chain state
+//! proof reports.
+
+use parity_scale_codec::{Decode, Encode};
+use sp_core::H256;
+use sp_std::vec::Vec;
+
+/// A storage key that holds the unincluded segment as of the current block.
+const UNINCLUDED_SEGMENT_KEY: &[u8] = b"unincluded_segment";
+
+/// A parachain block in the unincluded segment.
+#[derive(Encode, Decode, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Ancestor {
+    /// The number of the relay chain block that the parachain block was built on.
+    pub relay_parent_number: u32,
+    /// The hash of the parachain block's parent head.
+    pub parent_head_hash: H256,
+}
+
+/// Reasons that a block can not be added to the unincluded segment.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnincludedSegmentError {
+    /// The block's relay parent is older than that of the block before it.
+    RelayParentDecreased,
+    /// Too many blocks of the segment were built on the same relay parent.
+    VelocityExceeded,
+    /// The segment already holds as many blocks as it may.
+    SegmentFull,
+}
+
+/// The parachain blocks that have been built but are not yet included in the relay chain, oldest first.
+#[derive(Encode, Decode, Clone, Debug, Default, PartialEq, Eq)]
+pub struct UnincludedSegment(pub Vec<Ancestor>);
+
+impl UnincludedSegment {
+    /// Add the current block to the segment, after pruning the blocks that the relay chain included.
+    ///
+    /// The included head hash is the hash of the latest parachain head that the relay chain included,
+    /// if it is known. The segment may hold at most `capacity` blocks, including the current one, and
+    /// at most `velocity` of them may share a relay parent.
+    pub fn advance(
+        &mut self,
+        block: Ancestor,
+        included_head_hash: Option<H256>,
+        capacity: u32,
+        velocity: u32,
+    ) -> Result<(), UnincludedSegmentError> {
+        if let Some(included) = included_head_hash {
+            if included == block.parent_head_hash {
+                // The current block builds directly on the included head.
+                self.0.clear();
+            } else if let Some(child) = self
+                .0
+                .iter()
+                .position(|ancestor| ancestor.parent_head_hash == included)
+            {
+                // Everything before the child of the included block is included.
+                self.0.drain(..child);
+            }
+        }
+
+        if let Some(last) = self.0.last() {
+            if block.relay_parent_number < last.relay_parent_number {
+                return Err(UnincludedSegmentError::RelayParentDecreased);
+            }
+        }
+
+        let same_relay_parent = self
+            .0
+            .iter()
+            .filter(|ancestor| ancestor.relay_parent_number == block.relay_parent_number)
+            .count();
+        if same_relay_parent >= velocity as usize {
+            return Err(UnincludedSegmentError::VelocityExceeded);
+        }
+        if self.0.len() >= capacity as usize {
+            return Err(UnincludedSegmentError::SegmentFull);
+        }
+
+        self.0.push(block);
+        Ok(())
+    }
+
+    /// The current block, which is the last one in the segment.
+    pub fn current(&self) -> Option<&Ancestor> {
+        self.0.last()
+    }
+}
+
+/// An abstraction over reading the unincluded segment as of the current block.
+/// This allows it to be mocked during tests and not require actual externalities.
+pub trait GetUnincludedSegmentStorage {
+    fn get() -> UnincludedSegment;
+}
+
+/// An abstraction over setting the unincluded segment as of the current block.
+/// This allows it to be mocked during tests and not require actual externalities.
+pub trait SetUnincludedSegmentStorage {
+    fn set(segment: UnincludedSegment);
+}
+
+/// A public interface for accessing and mutating the unincluded segment. This is expected to be
+/// advanced by the parachain piece in every block.
+pub enum UnincludedSegmentStorage {}
+
+impl GetUnincludedSegmentStorage for UnincludedSegmentStorage {
+    fn get() -> UnincludedSegment {
+        sp_io::storage::get(UNINCLUDED_SEGMENT_KEY)
+            .and_then(|encoded| Decode::decode(&mut &encoded[..]).ok())
+            .unwrap_or_default()
+    }
+}
+
+impl SetUnincludedSegmentStorage for UnincludedSegmentStorage {
+    fn set(segment: UnincludedSegment) {
+        sp_io::storage::set(UNINCLUDED_SEGMENT_KEY, &segment.encode());
+    }
+}
+
+/// A mock version of the UnincludedSegmentStorage that can be used in tests without externalities.
+/// The segment is always empty.
+pub enum MockUnincludedSegmentStorage {}
+
+impl GetUnincludedSegmentStorage for MockUnincludedSegmentStorage {
+    fn get() -> UnincludedSegment {
+        UnincludedSegment::default()
+    }
+}
+
+impl SetUnincludedSegmentStorage for MockUnincludedSegmentStorage {
+    fn set(_segment: UnincludedSegment) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn block(relay_parent_number: u32, parent: u8) -> Ancestor {
+        Ancestor {
+            relay_parent_number,
+            parent_head_hash: H256::repeat_byte(parent),
+        }
+    }
+
+    #[test]
+    fn sync_backing_replaces_included_parent() {
+        let mut segment = UnincludedSegment(vec![block(1, 1)]);
+
+        assert_eq!(
+            segment.advance(block(2, 2), Some(H256::repeat_byte(2)), 1, 1),
+            Ok(())
+        );
+        assert_eq!(segment, UnincludedSegment(vec![block(2, 2)]));
+    }
+
+    #[test]
+    fn sync_backing_rejects_unincluded_parent() {
+        let mut segment = UnincludedSegment(vec![block(1, 1)]);
+
+        assert_eq!(
+            segment.advance(block(2, 2), Some(H256::repeat_byte(1)), 1, 1),
+            Err(UnincludedSegmentError::SegmentFull)
+        );
+    }
+
+    #[test]
+    fn async_backing_builds_ahead() {
+        let mut segment = UnincludedSegment(vec![block(1, 1), block(2, 2)]);
+
+        assert_eq!(
+            segment.advance(block(3, 3), Some(H256::repeat_byte(1)), 3, 1),
+            Ok(())
+        );
+        assert_eq!(
+            segment,
+            UnincludedSegment(vec![block(1, 1), block(2, 2), block(3, 3)])
+        );
+    }
+
+    #[test]
+    fn pruning_drops_included_blocks() {
+        // The block with parent 2 is included, so its child with parent 3 is the oldest left.
+        let mut segment = UnincludedSegment(vec![block(1, 1), block(2, 2), block(3, 3)]);
+
+        assert_eq!(
+            segment.advance(block(4, 4), Some(H256::repeat_byte(3)), 3, 1),
+            Ok(())
+        );
+        assert_eq!(segment, UnincludedSegment(vec![block(3, 3), block(4, 4)]));
+    }
+
+    #[test]
+    fn full_segment_is_rejected() {
+        let mut segment = UnincludedSegment(vec![block(1, 1), block(2, 2)]);
+
+        assert_eq!(
+            segment.advance(block(3, 3), None, 2, 1),
+            Err(UnincludedSegmentError::SegmentFull)
+        );
+    }
+
+    #[test]
+    fn velocity_allows_blocks_on_same_relay_parent() {
+        let mut segment = UnincludedSegment(vec![block(5, 1)]);
+
+        assert_eq!(segment.advance(block(5, 2), None, 3, 2), Ok(()));
+        assert_eq!(
+            segment.advance(block(5, 3), None, 3, 2),
+            Err(UnincludedSegmentError::VelocityExceeded)
+        );
+    }
+
+    #[test]
+    fn decreasing_relay_parent_is_rejected() {
+        let mut segment = UnincludedSegment(vec![block(5, 1)]);
+
+        assert_eq!(
+            segment.advance(block(4, 2), None, 3, 2),
+            Err(UnincludedSegmentError::RelayParentDecreased)
+        );
+    }
+
+    #[test]
+    fn current_is_last_block() {
+        let segment = UnincludedSegment(vec![block(1, 1), block(2, 2)]);
+
+        assert_eq!(segment.current(), Some(&block(2, 2)));
+    }
+}
@@ -1,26 +1,36 @@
 //! The actual implementation of the validate block functionality.
 
 use super::{
-    trie_cache, GetRelayParentNumberStorage, MemoryOptimizedValidationParams,
-    ParachainInherentDataUtxo, RelayParentNumberStorage,
+    trie_cache, DownwardMessagesStorage, GetDownwardMessagesStorage, GetRelayParentNumberStorage,
+    MemoryOptimizedValidationParams, OutboundHorizontalMessages, ParachainInherentDataUtxo,
+    RelayParentNumberStorage, UpwardMessages,
 };
 use cumulus_primitives_core::{
     relay_chain::Hash as RHash, ParachainBlockData, PersistedValidationData,
 };
-use cumulus_primitives_parachain_inherent::ParachainInherentData;
+use cumulus_primitives_parachain_inherent::{
+    ParachainInherentData, INHERENT_IDENTIFIER as PARACHAIN_INHERENT_IDENTIFIER,
+};
 use polkadot_parachain_primitives::primitives::{
     HeadData, RelayChainBlockNumber, ValidationResult,
 };
-use tuxedo_core::{types::Transaction, ConstraintChecker, Executive, Verifier};
+use tuxedo_core::{
+    inherents::InherentInternal, types::Transaction, utxo_set::UtxoSet, weights::WeightConfig,
+    ConstraintChecker, Executive, Verifier,
+};
 
 use parity_scale_codec::Encode;
 use scale_info::TypeInfo;
-use sp_core::storage::{ChildInfo, StateVersion};
+use sp_core::{
+    storage::{ChildInfo, StateVersion},
+    Get,
+};
 use sp_externalities::{set_and_run_with_externalities, Externalities};
 use sp_io::KillStorageResult;
 use sp_runtime::traits::{Block as BlockT, Extrinsic, HashingFor, Header as HeaderT};
 use sp_std::prelude::*;
 use sp_trie::MemoryDB;
+use sp_version::RuntimeVersion;
 
 type TrieBackend<B> = sp_state_machine::TrieBackend<
     MemoryDB<HashingFor<B>>,
@@ -61,7 +71,7 @@ fn with_externalities<F: FnOnce(&mut dyn Externalities) -> R, R>(f: F) -> R {
 /// ensuring that the final storage root matches the storage root in the header of the block. In the
 /// end we return back the [`ValidationResult`] with all the required information for the validator.
 #[doc(hidden)]
-pub fn validate_block<B, V, C>(
+pub fn validate_block<B, V, C, W, U, R>(
     MemoryOptimizedValidationParams {
         block_data,
         parent_head,
@@ -74,6 +84,9 @@ where
     Transaction<V, C>: Extrinsic,
     V: TypeInfo + Verifier + 'static,
     C: TypeInfo + ConstraintChecker<V> + 'static, // + Into<SetParachainInfo<V>>,
+    W: WeightConfig,
+    U: UtxoSet<V>,
+    R: Get<RuntimeVersion>,
 {
     sp_runtime::runtime_logger::RuntimeLogger::init();
     log::info!(target: "tuxvb", "🕵️🕵️🕵️🕵️Entering validate_block implementation");
@@ -195,12 +208,11 @@ where
         log::info!(target: "tuxvb", "🕵️🕵️🕵️🕵️ In the run_with_externalities closure");
         let head_data = HeadData(block.header().encode());
 
-        Executive::<B, V, C>::execute_block(block);
+        Executive::<B, V, C, W, U, (), (), R>::execute_block(block);
 
         log::info!(target: "tuxvb", "🕵️🕵️🕵️🕵️ returned from execute block");
 
-        // TODO Once we support XCM, we will need to gather some messaging state stuff here
-        // Seems like we could call the existing collect_collation_info api to get this information here
+        // TODO Seems like we could call the existing collect_collation_info api to get this information here
         // instead of FRAME's approach of tightly coupling to pallet parachain system.
         // That would mean less duplicated code as well as a more flexible validate block macro.
 
@@ -210,9 +222,13 @@ where
         ValidationResult {
             head_data,
             new_validation_code: None,
-            upward_messages: Default::default(),
-            processed_downward_messages: 0,
-            horizontal_messages: Default::default(),
+            upward_messages: UpwardMessages::get().try_into().expect(
+                "Number of upward messages should not be greater than `MAX_UPWARD_MESSAGE_NUM`",
+            ),
+            processed_downward_messages: DownwardMessagesStorage::get().len() as u32,
+            horizontal_messages: OutboundHorizontalMessages::get()
+                .try_into()
+                .expect("Number of horizontal messages should not be greater than `MAX_HORIZONTAL_MESSAGE_NUM`"),
             hrmp_watermark,
         }
     })
@@ -220,9 +236,9 @@ where
 
 /// Extract the [`ParachainInherentData`] from a parachain block.
 /// The data has to be extracted from the extrinsics themselves.
-/// I want the runtime to expose a method to do this, and I also want it to
-/// be nice and flexible by searching for the right transactions.
-/// For now I have a hacky implementation that assumes the parachain inherent is last
+///
+/// The parachain inherent is found by its inherent identifier, as reported by the runtime's
+/// inherent hooks, so it does not matter where among the inherents it appears.
 fn extract_parachain_inherent_data<B, V, C>(block: &B) -> ParachainInherentData
 where
     B: BlockT<Extrinsic = Transaction<V, C>>,
@@ -231,45 +247,19 @@ where
     V: TypeInfo + Verifier + 'static,
     C: TypeInfo + ConstraintChecker<V> + 'static,
 {
-    // The commented stuff is Basti's algo.
-    // It is nicer than my hack because it searches the transactions,
-    // But it is still not good enough because it lived right here in this file as
-    // opposed to with the runtime.
-    // FIXME https://github.com/Off-Narrative-Labs/Tuxedo/issues/146
-
-    // One idea from github.com/Off-Narrative-Labs/Tuxedo/pull/130#discussion_r1408250978
-    // is to find the inehrent based o nthe dynamic type of the output.
-    // This is a reason to keep dynamic typing which is discussed in
-    // https://github.com/Off-Narrative-Labs/Tuxedo/issues/153
-
-    // block
-    // 	.extrinsics()
-    // 	.iter()
-    // 	// Inherents are at the front of the block and are unsigned.
-    // 	//
-    // 	// If `is_signed` is returning `None`, we keep it safe and assume that it is "signed".
-    // 	// We are searching for unsigned transactions anyway.
-    // 	.take_while(|e| !e.is_signed().unwrap_or(true))
-    // 	.filter_map(|e| e.call().is_sub_type())
-    // 	.find_map(|c| match c {
-    // 		crate::Call::set_validation_data { data: validation_data } => Some(validation_data),
-    // 		_ => None,
-    // 	})
-    // 	.expect("Could not find `set_validation_data` inherent")
-
     block
         .extrinsics()
         .iter()
+        // Inherents are at the front of the block and are unsigned.
         .take_while(|&e| !e.is_signed().unwrap_or(true))
-        .collect::<Vec<_>>()
-        .last()
-        .expect("There should be at least one inherent extrinsic which is the parachain inherent.")
+        .find(|&e| C::InherentHooks::identify(e) == Some(PARACHAIN_INHERENT_IDENTIFIER))
+        .expect("There should be exactly one parachain inherent among the inherent extrinsics.")
         .outputs
-        .get(0)
-        .expect("Parachain inherent should be first and should have exactly one output.")
+        .first()
+        .expect("Parachain inherent should have exactly one output.")
         .payload
         .extract::<ParachainInherentDataUtxo>()
-        .expect("Should decode to proper type based on the position in the block.")
+        .expect("Parachain inherent output should decode to the parachain inherent data.")
         .into()
 }
 
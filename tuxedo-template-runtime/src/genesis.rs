@@ -1,64 +1,78 @@
 //! Helper module to build a genesis configuration for the template runtime.
 
-use super::{
-    kitties::{KittyData, Parent},
-    money::Coin,
-    OuterConstraintChecker, OuterConstraintCheckerInherentHooks, OuterVerifier, WASM_BINARY,
+pub use super::{GenesisSections, RuntimeGenesisConfig};
+
+use super::{ANDREW_PUB_KEY_BYTES, SHAWN_PUB_KEY_BYTES, WASM_BINARY};
+use kitties::{
+    genesis::{GenesisKitty, KittiesGenesisConfig},
+    Parent,
 };
-use hex_literal::hex;
+use money::genesis::MoneyGenesisConfig;
+use runtime_upgrade::UpgradeKey;
 use tuxedo_core::{
-    inherents::InherentInternal,
+    genesis::GenesisOutputs,
     verifier::{SigCheck, ThresholdMultiSignature, UpForGrabs},
 };
 
-/// Helper type for the ChainSpec.
-pub type RuntimeGenesisConfig =
-    tuxedo_core::genesis::TuxedoGenesisConfig<OuterVerifier, OuterConstraintChecker>;
-
-const SHAWN_PUB_KEY_BYTES: [u8; 32] =
-    hex!("d2bf4b844dfefd6772a8843e669f943408966a977e3ae2af1dd78e0f55f4df67");
-const ANDREW_PUB_KEY_BYTES: [u8; 32] =
-    hex!("baa81e58b1b4d053c2e86d93045765036f9d265c7dfe8b9693bbc2c0f048d93a");
-
-pub fn development_genesis_config() -> RuntimeGenesisConfig {
-    let signatories = vec![SHAWN_PUB_KEY_BYTES.into(), ANDREW_PUB_KEY_BYTES.into()];
-
-    // The inherents are computed using the appropriate method, and placed before the extrinsics.
-    let mut genesis_transactions = OuterConstraintCheckerInherentHooks::genesis_transactions();
-
-    genesis_transactions.extend([
-        // Money Transactions
-        Coin::<0>::mint(100, SigCheck::new(SHAWN_PUB_KEY_BYTES)),
-        Coin::<0>::mint(100, ThresholdMultiSignature::new(1, signatories)),
-        // Kitty Transactions
-        KittyData::mint(Parent::mom(), b"mother", UpForGrabs),
-        KittyData::mint(Parent::dad(), b"father", UpForGrabs),
-        // TODO: Initial Transactions for Existence
-    ]);
-
-    RuntimeGenesisConfig::new(
+/// Build the genesis configuration of this runtime from the given sections.
+pub fn genesis_config(sections: &GenesisSections) -> RuntimeGenesisConfig {
+    sections.build(
         WASM_BINARY
             .expect("Runtime WASM binary must exist.")
             .to_vec(),
-        genesis_transactions,
     )
 }
 
+pub fn development_genesis_config() -> RuntimeGenesisConfig {
+    genesis_config(&development_genesis_sections())
+}
+
+/// The initial state of development chains.
+///
+/// Shawn and Andrew, the two development accounts, hold the runtime upgrade key together. Both of
+/// them must sign to upgrade the runtime with it. Otherwise an upgrade needs a referendum.
+pub fn development_genesis_sections() -> GenesisSections {
+    let signatories = vec![SHAWN_PUB_KEY_BYTES.into(), ANDREW_PUB_KEY_BYTES.into()];
+    let upgrade_key_holders = ThresholdMultiSignature::new(2, signatories.clone());
+
+    GenesisSections {
+        money: MoneyGenesisConfig::new(vec![
+            (100, SigCheck::new(SHAWN_PUB_KEY_BYTES).into()),
+            (100, ThresholdMultiSignature::new(1, signatories).into()),
+        ]),
+        kitties: KittiesGenesisConfig::new(vec![
+            GenesisKitty {
+                parent: Parent::mom(),
+                dna_preimage: "mother".into(),
+                verifier: UpForGrabs.into(),
+            },
+            GenesisKitty {
+                parent: Parent::dad(),
+                dna_preimage: "father".into(),
+                verifier: UpForGrabs.into(),
+            },
+        ]),
+        outputs: GenesisOutputs::new(vec![(UpgradeKey, upgrade_key_holders).into()]),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
-    use crate::OuterVerifier;
+    use crate::{OuterConstraintChecker, OuterConstraintCheckerInherentHooks, OuterVerifier};
     use parity_scale_codec::{Decode, Encode};
     use sp_api::HashT;
-    use sp_core::testing::SR25519;
+    use sp_core::{testing::SR25519, H256};
     use sp_keystore::{testing::MemoryKeystore, Keystore, KeystoreExt};
     use sp_runtime::{traits::BlakeTwo256, BuildStorage};
     use std::sync::Arc;
     use tuxedo_core::{
         dynamic_typing::{DynamicallyTypedData, UtxoData},
+        genesis::GenesisSection,
         inherents::InherentInternal,
         types::{Output, OutputRef},
+        utxo_set::UTXO_CHILD_TRIE,
     };
 
     // other random account generated with subkey
@@ -82,18 +96,17 @@ mod tests {
 
         let signatories = vec![shawn_pub_key_bytes.into(), andrew_pub_key_bytes.into()];
 
-        let mut genesis_transactions = OuterConstraintCheckerInherentHooks::genesis_transactions();
-        genesis_transactions.extend([
-            // Money Transactions
-            Coin::<0>::mint(100, SigCheck::new(shawn_pub_key_bytes)),
-            Coin::<0>::mint(100, ThresholdMultiSignature::new(1, signatories)),
-        ]);
-
-        RuntimeGenesisConfig::new(
+        GenesisSections {
+            money: MoneyGenesisConfig::new(vec![
+                (100, SigCheck::new(shawn_pub_key_bytes).into()),
+                (100, ThresholdMultiSignature::new(1, signatories).into()),
+            ]),
+            ..Default::default()
+        }
+        .build(
             WASM_BINARY
                 .expect("Runtime WASM binary must exist.")
                 .to_vec(),
-            genesis_transactions,
         )
     }
 
@@ -143,7 +156,8 @@ mod tests {
             };
 
             let encoded_utxo =
-                sp_io::storage::get(&output_ref.encode()).expect("Retrieve Genesis UTXO");
+                sp_io::default_child_storage::get(UTXO_CHILD_TRIE, &output_ref.encode())
+                    .expect("Retrieve Genesis UTXO");
             let utxo = Output::decode(&mut &encoded_utxo[..]).expect("Can Decode UTXO correctly");
             assert_eq!(utxo, genesis_utxo);
         })
@@ -187,9 +201,160 @@ mod tests {
             };
 
             let encoded_utxo =
-                sp_io::storage::get(&output_ref.encode()).expect("Retrieve Genesis MultiSig UTXO");
+                sp_io::default_child_storage::get(UTXO_CHILD_TRIE, &output_ref.encode())
+                    .expect("Retrieve Genesis MultiSig UTXO");
             let utxo = Output::decode(&mut &encoded_utxo[..]).expect("Can Decode UTXO correctly");
             assert_eq!(utxo, genesis_multi_sig_utxo);
         })
     }
+
+    #[test]
+    fn genesis_writes_total_issuance() {
+        new_test_ext().execute_with(|| {
+            assert_eq!(money::total_issuance(0), Some(200));
+        })
+    }
+
+    #[test]
+    fn genesis_sections_read_from_json() {
+        let json = r#"{
+            "money": {
+                "coins": [
+                    [42, { "SigCheck": { "owner_pubkey": "0xd2bf4b844dfefd6772a8843e669f943408966a977e3ae2af1dd78e0f55f4df67" } }]
+                ]
+            }
+        }"#;
+        let sections: GenesisSections = serde_json::from_str(json).unwrap();
+
+        assert_eq!(
+            sections.money.coins,
+            vec![(42, SigCheck::new(SHAWN_PUB_KEY_BYTES).into())]
+        );
+        // The file leaves the kitties out, so there are none.
+        assert!(sections.kitties.kitties.is_empty());
+    }
+
+    /// A proof of existence claim, built by hand because the PoE piece keeps its claim type private.
+    fn poe_claim_output() -> Output<OuterVerifier> {
+        Output {
+            payload: DynamicallyTypedData {
+                data: (H256::repeat_byte(7), 0u32, Option::<H256>::None).encode(),
+                type_id: *b"poe_",
+            },
+            verifier: SigCheck::new(SHAWN_PUB_KEY_BYTES).into(),
+        }
+    }
+
+    fn kitty_output() -> Output<OuterVerifier> {
+        let kitty = kitties::KittyData {
+            parent: Parent::mom(),
+            free_breedings: 2,
+            dna: kitties::KittyDNA(H256::repeat_byte(3)),
+            num_breedings: 0,
+            name: *b"tabi",
+            last_bred: None,
+        };
+        (kitty, UpForGrabs.into()).into()
+    }
+
+    #[test]
+    fn genesis_outputs_are_stored_at_derived_refs() {
+        let sections = GenesisSections {
+            outputs: GenesisOutputs::new(vec![poe_claim_output(), kitty_output()]),
+            ..Default::default()
+        };
+        let outputs = &sections.outputs;
+        let storage = genesis_config(&sections).build_storage().unwrap();
+
+        sp_io::TestExternalities::from(storage).execute_with(|| {
+            for (index, expected) in outputs.outputs.iter().enumerate() {
+                let output_ref = outputs
+                    .output_ref::<OuterConstraintChecker>(index as u32)
+                    .unwrap();
+                let encoded_utxo =
+                    sp_io::default_child_storage::get(UTXO_CHILD_TRIE, &output_ref.encode())
+                        .expect("Retrieve genesis output");
+                let utxo = Output::decode(&mut &encoded_utxo[..]).unwrap();
+                assert_eq!(&utxo, expected);
+            }
+        });
+        assert_eq!(outputs.output_ref::<OuterConstraintChecker>(2), None);
+    }
+
+    #[test]
+    fn genesis_outputs_share_one_transaction() {
+        let outputs = GenesisOutputs::new(vec![poe_claim_output(), poe_claim_output()]);
+        let tx = outputs.transaction::<OuterConstraintChecker>().unwrap();
+
+        assert_eq!(
+            tx.checker,
+            OuterConstraintChecker::GenesisOnly(Default::default())
+        );
+        // Identical outputs are still told apart by their index.
+        assert_eq!(
+            outputs.output_ref::<OuterConstraintChecker>(0),
+            Some(OutputRef {
+                tx_hash: BlakeTwo256::hash_of(&tx.encode()),
+                index: 0
+            })
+        );
+        assert_ne!(
+            outputs.output_ref::<OuterConstraintChecker>(0),
+            outputs.output_ref::<OuterConstraintChecker>(1)
+        );
+    }
+
+    #[test]
+    fn no_genesis_outputs_means_no_transaction() {
+        let outputs = GenesisOutputs::<OuterVerifier>::default();
+
+        assert_eq!(outputs.transaction::<OuterConstraintChecker>(), None);
+        assert_eq!(
+            GenesisSection::<OuterVerifier, OuterConstraintChecker>::genesis_transactions(&outputs),
+            vec![]
+        );
+    }
+
+    #[test]
+    fn genesis_outputs_read_from_json() {
+        let json = r#"{
+            "outputs": {
+                "outputs": [
+                    {
+                        "payload": { "data": [42, 0, 0, 0], "type_id": [112, 111, 101, 95] },
+                        "verifier": { "UpForGrabs": null }
+                    }
+                ]
+            }
+        }"#;
+        let sections: GenesisSections = serde_json::from_str(json).unwrap();
+
+        assert_eq!(
+            sections.outputs.outputs,
+            vec![Output {
+                payload: DynamicallyTypedData {
+                    data: vec![42, 0, 0, 0],
+                    type_id: *b"poe_",
+                },
+                verifier: UpForGrabs.into(),
+            }]
+        );
+    }
+
+    #[test]
+    fn genesis_sections_refuse_unknown_pieces() {
+        let json = r#"{ "monies": { "coins": [] } }"#;
+        assert!(serde_json::from_str::<GenesisSections>(json).is_err());
+    }
+
+    #[test]
+    fn development_genesis_sections_round_trip_through_json() {
+        let json = serde_json::to_string(&development_genesis_sections()).unwrap();
+        let sections: GenesisSections = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(
+            sections.genesis_transactions(),
+            development_genesis_sections().genesis_transactions()
+        );
+    }
 }
@@ -12,20 +12,22 @@ include!(concat!(env!("OUT_DIR"), "/wasm_binary.rs"));
 #[cfg(feature = "std")]
 pub mod genesis;
 
+#[cfg(all(feature = "std", not(feature = "parachain")))]
+pub mod simulation;
+
 use parity_scale_codec::{Decode, Encode};
 use scale_info::TypeInfo;
 use serde::{Deserialize, Serialize};
 use sp_consensus_aura::sr25519::AuthorityId as AuraId;
-use sp_consensus_grandpa::AuthorityId as GrandpaId;
 
 use sp_api::impl_runtime_apis;
-use sp_core::OpaqueMetadata;
+use sp_core::{OpaqueMetadata, H256};
 use sp_inherents::InherentData;
 use sp_runtime::{
     create_runtime_str, impl_opaque_keys,
-    traits::{BlakeTwo256, Block as BlockT},
+    traits::Block as BlockT,
     transaction_validity::{TransactionPriority, TransactionSource, TransactionValidity},
-    ApplyExtrinsicResult, BoundToRuntimeAppPublic,
+    ApplyExtrinsicResult, BoundToRuntimeAppPublic, Percent,
 };
 use sp_std::prelude::*;
 
@@ -34,50 +36,19 @@ use sp_version::NativeVersion;
 use sp_version::RuntimeVersion;
 
 use tuxedo_core::{
-    tuxedo_constraint_checker, tuxedo_verifier,
-    types::Transaction as TuxedoTransaction,
-    verifier::{SigCheck, ThresholdMultiSignature, UpForGrabs},
+    metadata::METADATA_VERSION,
+    verifier::{Ed25519SigCheck, Proxy, ProxyScope, SigCheck, ThresholdMultiSignature, UpForGrabs},
 };
 
 pub use amoeba;
+pub use block_reward;
+pub use coinjoin;
 pub use kitties;
 pub use money;
 pub use poe;
+pub use randomness_beacon;
 pub use runtime_upgrade;
-
-/// Opaque types. These are used by the CLI to instantiate machinery that don't need to know
-/// the specifics of the runtime. They can then be made to be agnostic over specific formats
-/// of data like extrinsics, allowing for them to continue syncing the network through upgrades
-/// to even the core data structures.
-pub mod opaque {
-    use super::*;
-
-    /// Opaque block type.
-    pub type Block = sp_runtime::generic::Block<Header, sp_runtime::OpaqueExtrinsic>;
-    /// Opaque block hash type.
-    pub type Hash = <BlakeTwo256 as sp_api::HashT>::Output;
-
-    // This part is necessary for generating session keys in the runtime
-    impl_opaque_keys! {
-        pub struct SessionKeys {
-            pub aura: AuraAppPublic,
-            pub grandpa: GrandpaAppPublic,
-        }
-    }
-
-    // Typically these are not implemented manually, but rather for the pallet associated with the
-    // keys. Here we are not using the pallets, and these implementations are trivial, so we just
-    // re-write them.
-    pub struct AuraAppPublic;
-    impl BoundToRuntimeAppPublic for AuraAppPublic {
-        type Public = AuraId;
-    }
-
-    pub struct GrandpaAppPublic;
-    impl BoundToRuntimeAppPublic for GrandpaAppPublic {
-        type Public = sp_consensus_grandpa::AuthorityId;
-    }
-}
+pub use timestamp;
 
 /// This runtime version.
 #[sp_version::runtime_version]
@@ -92,6 +63,15 @@ pub const VERSION: RuntimeVersion = RuntimeVersion {
     state_version: 1,
 };
 
+/// Supplies this runtime's version to the executive, so that signatures commit to it.
+pub struct Version;
+
+impl sp_core::Get<RuntimeVersion> for Version {
+    fn get() -> RuntimeVersion {
+        VERSION
+    }
+}
+
 /// The version information used to identify this runtime when compiled natively.
 #[cfg(feature = "std")]
 pub fn native_version() -> NativeVersion {
@@ -101,44 +81,238 @@ pub fn native_version() -> NativeVersion {
     }
 }
 
-pub type Transaction = TuxedoTransaction<OuterVerifier, OuterConstraintChecker>;
-pub type BlockNumber = u32;
-pub type Header = sp_runtime::generic::Header<BlockNumber, BlakeTwo256>;
-pub type Block = sp_runtime::generic::Block<Header, Transaction>;
-pub type Executive = tuxedo_core::Executive<Block, OuterVerifier, OuterConstraintChecker>;
-pub type Output = tuxedo_core::types::Output<OuterVerifier>;
+/// Orders this runtime's transactions in the pool. Runtime upgrades go first, so that a busy pool
+/// does not hold them up. Everything else is ordered by the tip that it pays per weight.
+pub struct PoolPolicy;
 
-impl sp_runtime::traits::GetNodeBlockType for Runtime {
-    type NodeBlock = opaque::Block;
+impl tuxedo_core::pool::PoolPolicy<OuterVerifier, OuterConstraintChecker> for PoolPolicy {
+    fn priority(transaction: &Transaction, priority: TransactionPriority) -> TransactionPriority {
+        match transaction.checker {
+            OuterConstraintChecker::RuntimeUpgrade(_) => TransactionPriority::MAX,
+            _ => priority,
+        }
+    }
 }
 
-impl sp_runtime::traits::GetRuntimeBlockType for Runtime {
-    type RuntimeBlock = Block;
+/// The kinds of transactions that a delegate of a [`Proxy`] may sign in this runtime.
+#[derive(Serialize, Deserialize, Encode, Decode, Debug, PartialEq, Eq, Clone, TypeInfo)]
+#[cfg_attr(feature = "fuzzing", derive(arbitrary::Arbitrary))]
+pub enum DelegateScope {
+    /// Any transaction at all
+    Any,
+    /// Money transfers that send less than the given amount away from the proxy.
+    /// Change paid back to the same proxy does not count towards the limit.
+    TransfersBelow(u128),
+    /// Proof of existence claims only
+    PoeClaimsOnly,
+}
+
+impl ProxyScope for DelegateScope {
+    fn allows(&self, proxy: &Proxy<Self>, simplified_tx: &[u8]) -> bool {
+        // Decoding ignores the signing domain that follows the transaction.
+        let transaction = match Transaction::decode(&mut &simplified_tx[..]) {
+            Ok(t) => t,
+            Err(_) => return false,
+        };
+
+        match self {
+            DelegateScope::Any => true,
+            DelegateScope::TransfersBelow(limit) => {
+                if transaction.checker
+                    != OuterConstraintChecker::Money(money::MoneyConstraintChecker::Spend)
+                {
+                    return false;
+                }
+                let change = OuterVerifier::Proxy(proxy.clone());
+                let mut sent: u128 = 0;
+                for output in transaction.outputs {
+                    if output.verifier == change {
+                        continue;
+                    }
+                    match output.payload.extract::<money::Coin<0>>() {
+                        Ok(coin) => sent = sent.saturating_add(coin.0),
+                        Err(_) => return false,
+                    }
+                }
+                sent < *limit
+            }
+            DelegateScope::PoeClaimsOnly => {
+                matches!(transaction.checker, OuterConstraintChecker::PoeClaim(_))
+            }
+        }
+    }
 }
 
 /// The Aura slot duration. When things are working well, this will also be the block time.
 const BLOCK_TIME: u64 = 3000;
 
-/// A verifier checks that an individual input can be consumed. For example that it is signed properly
-/// To begin playing, we will have two kinds. A simple signature check, and an anyone-can-consume check.
-#[derive(Serialize, Deserialize, Encode, Decode, Debug, PartialEq, Eq, Clone, TypeInfo)]
-#[tuxedo_verifier]
-pub enum OuterVerifier {
-    SigCheck(SigCheck),
-    UpForGrabs(UpForGrabs),
-    ThresholdMultiSignature(ThresholdMultiSignature),
+impl money::MoneyConfig for Runtime {
+    // Any non-zero coin is allowed for now.
+    // Raise this to keep dust out of the UTXO set.
+    const MINIMUM_COIN_VALUE: u128 = 1;
+    // Keep an on-chain supply figure for explorers. It starts from the genesis coins and counts
+    // the block rewards too.
+    // The relay chain coins share this configuration, but they are minted and burned by
+    // the reserve transfer pieces, so plain relay coin mints and burns are refused for
+    // lack of an accumulator.
+    const TRACK_ISSUANCE: bool = true;
 }
 
-impl poe::PoeConfig for Runtime {
+impl block_reward::BlockRewardConfig for Runtime {
     fn block_height() -> u32 {
         Executive::block_height()
     }
+
+    fn block_author() -> Option<H256> {
+        Self::consensus_author()
+    }
+
+    // The genesis supply is only a few hundred tokens, so start
+    // the reward small enough not to drown it out immediately.
+    const INITIAL_REWARD: u128 = 10;
 }
 
-impl timestamp::TimestampConfig for Runtime {
+/// The public key of Shawn, one of the two development accounts.
+const SHAWN_PUB_KEY_BYTES: [u8; 32] =
+    hex_literal::hex!("d2bf4b844dfefd6772a8843e669f943408966a977e3ae2af1dd78e0f55f4df67");
+
+/// The public key of Andrew, the other development account.
+const ANDREW_PUB_KEY_BYTES: [u8; 32] =
+    hex_literal::hex!("baa81e58b1b4d053c2e86d93045765036f9d265c7dfe8b9693bbc2c0f048d93a");
+
+// The template charges no fees, so all that transactions leave over is tips. The author reward
+// pays out four fifths of them, and the treasury collects the rest.
+impl author_reward::AuthorRewardConfig for Runtime {
+    fn block_height() -> u32 {
+        Executive::block_height()
+    }
+
+    fn block_author() -> Option<H256> {
+        Self::consensus_author()
+    }
+
+    const TIP_SHARE: Percent = Percent::from_percent(80);
+}
+
+impl treasury::TreasuryConfig for Runtime {
+    fn block_height() -> u32 {
+        Executive::block_height()
+    }
+
+    // Both development accounts must sign to spend from the treasury without a referendum.
+    fn council() -> ThresholdMultiSignature {
+        ThresholdMultiSignature::new(
+            2,
+            vec![SHAWN_PUB_KEY_BYTES.into(), ANDREW_PUB_KEY_BYTES.into()],
+        )
+    }
+
+    const TIP_SHARE: Percent = Percent::from_percent(20);
+}
+
+impl governance::GovernanceConfig for Runtime {
     fn block_height() -> u32 {
         Executive::block_height()
     }
+
+    // The genesis supply is only a few hundred tokens, so a referendum
+    // needs at least half of it in favor to pass.
+    const APPROVAL_THRESHOLD: u128 = 100;
+}
+
+impl runtime_upgrade::RuntimeUpgradeConfig for Runtime {
+    // Upgrades need either the upgrade key, which the development genesis locks behind a
+    // multisignature of both development accounts, or a referendum that passed for the new wasm.
+    const UPGRADE_KEY: bool = true;
+    const GOVERNANCE: bool = true;
+}
+
+impl aura_authorities::AuraAuthoritiesConfig for Runtime {
+    fn genesis_authorities() -> Vec<H256> {
+        Self::genesis_aura_authorities()
+    }
+}
+
+impl grandpa_authorities::GrandpaAuthoritiesConfig for Runtime {
+    fn genesis_authorities() -> Vec<(H256, u64)> {
+        Self::genesis_grandpa_authorities()
+    }
+
+    fn voting_key(authority: H256) -> H256 {
+        session_keys::active_key(session_keys::Engine::Grandpa, authority)
+    }
+
+    // A published GRANDPA session key also schedules a change.
+    fn change_scheduled() -> bool {
+        tuxedo_core::digest::deposited_by(sp_consensus_grandpa::GRANDPA_ENGINE_ID)
+    }
+}
+
+impl session_keys::SessionKeysConfig for Runtime {
+    fn block_height() -> u32 {
+        Executive::block_height()
+    }
+
+    fn aura_authorities() -> Vec<H256> {
+        Self::aura_identities()
+    }
+
+    fn grandpa_authorities() -> Vec<(H256, u64)> {
+        grandpa_authorities::authority_set::<Runtime>().authorities
+    }
+
+    // A governance rotation of the GRANDPA authorities also schedules a change.
+    fn grandpa_change_scheduled() -> bool {
+        tuxedo_core::digest::deposited_by(sp_consensus_grandpa::GRANDPA_ENGINE_ID)
+    }
+}
+
+#[cfg(feature = "babe")]
+impl babe_epochs::BabeEpochsConfig for Runtime {
+    fn block_digest() -> sp_runtime::Digest {
+        Executive::block_digest()
+    }
+
+    // BABE uses the same sr25519 keys as Aura, so governance rotates them through the Aura
+    // authorities piece. A rotation takes effect in the epoch after next.
+    fn authorities() -> Vec<H256> {
+        aura_authorities::authorities::<Runtime>()
+    }
+}
+
+impl kitties::KittyConfig for Runtime {
+    fn block_entropy() -> H256 {
+        Executive::parent_hash()
+    }
+
+    fn block_height() -> u32 {
+        Executive::block_height()
+    }
+}
+
+impl poe::PoeConfig for Runtime {}
+
+impl coinjoin::CoinJoinConfig for Runtime {}
+
+impl timestamp::TimestampConfig for Runtime {}
+
+impl randomness_beacon::RandomnessBeaconConfig for Runtime {
+    // Only the standalone node's Aura authors sign the VRF. Instantly sealed blocks
+    // have no Aura digest, so they carry the randomness over.
+    #[cfg(not(any(feature = "babe", feature = "parachain")))]
+    fn author_and_slot() -> Option<(H256, u64)> {
+        Some((Self::aura_author()?, Self::aura_slot()?))
+    }
+
+    // BABE and parachain blocks always carry the randomness over.
+    #[cfg(any(feature = "babe", feature = "parachain"))]
+    fn author_and_slot() -> Option<(H256, u64)> {
+        None
+    }
+
+    fn parent_hash() -> H256 {
+        Executive::parent_hash()
+    }
 }
 
 #[cfg(feature = "parachain")]
@@ -146,120 +320,292 @@ impl parachain_piece::ParachainPieceConfig for Runtime {
     // Use the para ID 2_000 which is the first available in the rococo-local runtime.
     // This is the default value, so this could be omitted, but explicit is better.
     const PARA_ID: u32 = 2_000;
+    // The collator node uses the basic collator, so stick with synchronous backing.
+    const BLOCK_PROCESSING_VELOCITY: u32 = 1;
+    const UNINCLUDED_SEGMENT_CAPACITY: u32 = 1;
 
     type SetRelayParentNumberStorage = tuxedo_parachain_core::RelayParentNumberStorage;
+    type SetDownwardMessagesStorage = tuxedo_parachain_core::DownwardMessagesStorage;
+    type SetHorizontalMessagingStorage = tuxedo_parachain_core::HorizontalMessagingStorage;
+    type SetRelayStateProofStorage = tuxedo_parachain_core::RelayStateProofStorage;
+    type ReadRelayState = tuxedo_parachain_core::RelayStateProofStorage;
+    type UnincludedSegmentStorage = tuxedo_parachain_core::UnincludedSegmentStorage;
 }
 
-// Observation: For some applications, it will be invalid to simply delete
-// a UTXO without any further processing. Therefore, we explicitly include
-// AmoebaDeath and PoeRevoke on an application-specific basis
+#[cfg(feature = "parachain")]
+impl collator_selection::CollatorSelectionConfig for Runtime {
+    fn block_height() -> u32 {
+        Executive::block_height()
+    }
 
-// The macro doesn't understand conditional compilation flags inside, so we have to
-// feature gate the entire thing, and repeat it twice. I remember this was a problem
-// with frame's construct_runtime! as well.
+    // The well-known collators keep the parachain going until candidates register.
+    fn invulnerables() -> Vec<H256> {
+        Self::genesis_aura_authorities()
+    }
+
+    // The genesis supply is only a few hundred tokens, so keep the bond within reach.
+    const MIN_BOND: u128 = 20;
+}
 
-/// A constraint checker is a piece of logic that can be used to check a transaction.
-/// For any given Tuxedo runtime there is a finite set of such constraint checkers.
-/// For example, this may check that input token values exceed output token values.
-#[derive(Serialize, Deserialize, Encode, Decode, Debug, PartialEq, Eq, Clone, TypeInfo)]
-#[tuxedo_constraint_checker(OuterVerifier)]
 #[cfg(feature = "parachain")]
-pub enum OuterConstraintChecker {
-    /// Checks monetary transactions in a basic fungible cryptocurrency
-    Money(money::MoneyConstraintChecker<0>),
-    /// Checks Free Kitty transactions
-    FreeKittyConstraintChecker(kitties::FreeKittyConstraintChecker),
-    /// Checks that an amoeba can split into two new amoebas
-    AmoebaMitosis(amoeba::AmoebaMitosis),
-    /// Checks that a single amoeba is simply removed from the state
-    AmoebaDeath(amoeba::AmoebaDeath),
-    /// Checks that a single amoeba is simply created from the void... and it is good
-    AmoebaCreation(amoeba::AmoebaCreation),
-    /// Checks that new valid proofs of existence are claimed
-    PoeClaim(poe::PoeClaim<Runtime>),
-    /// Checks that proofs of existence are revoked.
-    PoeRevoke(poe::PoeRevoke),
-    /// Checks that one winning claim came earlier than all the other claims, and thus
-    /// the losing claims can be removed from storage.
-    PoeDispute(poe::PoeDispute),
-    /// Set the block's timestamp via an inherent extrinsic.
-    SetTimestamp(timestamp::SetTimestamp<Runtime>),
-    /// Upgrade the Wasm Runtime
-    RuntimeUpgrade(runtime_upgrade::RuntimeUpgrade),
-
-    // TODO This one is last for now so that I can write a hacky algorithm to scrape
-    // the inherent data and assume it is last.
-    /// Set some parachain related information via an inherent extrinsic.
-    ParachainInfo(parachain_piece::SetParachainInfo<Runtime>),
+impl xcm_transfer::XcmTransferConfig for Runtime {
+    type GetRelayParentNumber = tuxedo_parachain_core::RelayParentNumberStorage;
+    type GetDownwardMessages = tuxedo_parachain_core::DownwardMessagesStorage;
+    type SendUpwardMessage = tuxedo_parachain_core::UpwardMessages;
 }
 
-/// A constraint checker is a piece of logic that can be used to check a transaction.
-/// For any given Tuxedo runtime there is a finite set of such constraint checkers.
-/// For example, this may check that input token values exceed output token values.
-#[derive(Serialize, Deserialize, Encode, Decode, Debug, PartialEq, Eq, Clone, TypeInfo)]
-#[tuxedo_constraint_checker(OuterVerifier)]
-#[cfg(not(feature = "parachain"))]
-pub enum OuterConstraintChecker {
-    /// Checks monetary transactions in a basic fungible cryptocurrency
-    Money(money::MoneyConstraintChecker<0>),
-    /// Checks Free Kitty transactions
-    FreeKittyConstraintChecker(kitties::FreeKittyConstraintChecker),
-    /// Checks that an amoeba can split into two new amoebas
-    AmoebaMitosis(amoeba::AmoebaMitosis),
-    /// Checks that a single amoeba is simply removed from the state
-    AmoebaDeath(amoeba::AmoebaDeath),
-    /// Checks that a single amoeba is simply created from the void... and it is good
-    AmoebaCreation(amoeba::AmoebaCreation),
-    /// Checks that new valid proofs of existence are claimed
-    PoeClaim(poe::PoeClaim<Runtime>),
-    /// Checks that proofs of existence are revoked.
-    PoeRevoke(poe::PoeRevoke),
-    /// Checks that one winning claim came earlier than all the other claims, and thus
-    /// the losing claims can be removed from storage.
-    PoeDispute(poe::PoeDispute),
-    /// Set the block's timestamp via an inherent extrinsic.
-    SetTimestamp(timestamp::SetTimestamp<Runtime>),
-    /// Upgrade the Wasm Runtime
-    RuntimeUpgrade(runtime_upgrade::RuntimeUpgrade),
-
-    /// A Dummy Constraint Checker to make the encoding compatible with the parachain.
-    /// This does nothing.
-    ParachainInfo(DummyParachainInfo),
+#[cfg(feature = "parachain")]
+impl hrmp_messaging::HrmpMessagingConfig for Runtime {
+    type GetRelayParentNumber = tuxedo_parachain_core::RelayParentNumberStorage;
+    type GetHorizontalMessaging = tuxedo_parachain_core::HorizontalMessagingStorage;
+    type SendHorizontalMessage = tuxedo_parachain_core::OutboundHorizontalMessages;
 }
 
-#[derive(
-    Serialize, Deserialize, Encode, Decode, Debug, Default, PartialEq, Eq, Clone, TypeInfo,
-)]
-/// A Dummy constraint checker that does nothing. It is only present to make the
-/// Parachain and non-parahcain OuterConstraintCheckers scale compatible
-pub struct DummyParachainInfo;
+// Assemble the runtime from its verifiers and pieces. This declares the runtime itself, the outer
+// verifier and constraint checker, the genesis sections, the metadata, and the type aliases that
+// the node and the executive use.
+//
+// Observation: For some applications, it will be invalid to simply delete
+// a UTXO without any further processing. Therefore, we explicitly include
+// AmoebaDeath and PoeRevoke on an application-specific basis
+tuxedo_core::construct_tuxedo_runtime! {
+    runtime: Runtime,
+    version: Version,
+    pool_policy: PoolPolicy,
+
+    // Signatures may be sr25519, which is what the template wallet uses, or ed25519.
+    #[cfg_attr(feature = "fuzzing", derive(arbitrary::Arbitrary))]
+    verifiers: {
+        SigCheck(SigCheck),
+        UpForGrabs(UpForGrabs),
+        ThresholdMultiSignature(ThresholdMultiSignature),
+        Ed25519SigCheck(Ed25519SigCheck),
+        Proxy(Proxy<DelegateScope>),
+    },
 
-impl tuxedo_core::SimpleConstraintChecker for DummyParachainInfo {
-    type Error = ();
+    pieces: {
+        /// Checks monetary transactions in a basic fungible cryptocurrency
+        Money(money::MoneyConstraintChecker<0, Runtime>),
+        /// Checks Free Kitty transactions
+        FreeKittyConstraintChecker(kitties::FreeKittyConstraintChecker<Runtime>),
+        /// Checks that an amoeba can split into two new amoebas
+        AmoebaMitosis(amoeba::AmoebaMitosis),
+        /// Checks that two amoebas can fuse into one new amoeba
+        AmoebaFusion(amoeba::AmoebaFusion),
+        /// Checks that a single amoeba is simply removed from the state
+        AmoebaDeath(amoeba::AmoebaDeath),
+        /// Checks that a single amoeba is simply created from the void... and it is good
+        AmoebaCreation(amoeba::AmoebaCreation),
+        /// Checks that new valid proofs of existence are claimed
+        PoeClaim(poe::PoeClaim<Runtime>),
+        /// Checks that a valid batch of proofs of existence is claimed under a single Merkle root
+        PoeBatchClaim(poe::PoeBatchClaim<Runtime>),
+        /// Checks that proofs of existence are revoked.
+        PoeRevoke(poe::PoeRevoke),
+        /// Checks that proofs of existence are transferred to new owners unchanged.
+        PoeTransfer(poe::PoeTransfer),
+        /// Checks that proofs of existence are replaced by claims to their next revisions
+        PoeRevise(poe::PoeRevise<Runtime>),
+        /// Checks that one winning claim came earlier than all the other claims, and thus
+        /// the losing claims can be removed from storage.
+        PoeDispute(poe::PoeDispute),
+        /// Set the block's timestamp via an inherent extrinsic.
+        SetTimestamp(timestamp::SetTimestamp<Runtime>),
+        /// Reward the block's author with newly issued coins via an inherent extrinsic.
+        MintBlockReward(block_reward::MintBlockReward<Runtime, 0>),
+        /// Upgrade the Wasm Runtime
+        RuntimeUpgrade(runtime_upgrade::RuntimeUpgrade<Runtime, 0>),
+        /// Open a referendum on some payload
+        Propose(governance::Propose<Runtime, 0>),
+        /// Lock coins to vote on an open referendum
+        CastVote(governance::CastVote<Runtime, 0>),
+        /// Close a referendum once voting ends, enacting its payload if it passed
+        Tally(governance::Tally<Runtime, 0>),
+        /// Unlock the coins of votes once voting ends
+        UnlockVotes(governance::UnlockVotes<Runtime, 0>),
+        /// Rotate the Aura authorities to a set that governance enacted
+        RotateAuraAuthorities(aura_authorities::RotateAuthorities<Runtime, 0>),
+        /// Rotate the GRANDPA authorities to a set that governance enacted
+        RotateGrandpaAuthorities(grandpa_authorities::RotateAuthorities<Runtime, 0>),
+        /// Publish a new Aura or GRANDPA session key for an authority
+        PublishSessionKey(session_keys::PublishSessionKey<Runtime>),
+        /// Labels the genesis transaction that creates the outputs declared in the genesis config.
+        /// It rejects every transaction after genesis.
+        GenesisOnly(tuxedo_core::GenesisOnly),
 
-    fn check(
-        &self,
-        _input_data: &[tuxedo_core::dynamic_typing::DynamicallyTypedData],
-        _peeks: &[tuxedo_core::dynamic_typing::DynamicallyTypedData],
-        _output_data: &[tuxedo_core::dynamic_typing::DynamicallyTypedData],
-    ) -> Result<TransactionPriority, ()> {
-        Ok(0)
-    }
+        /// Set some parachain related information via an inherent extrinsic.
+        /// Without the parachain feature, the macro puts a placeholder here so the encoding stays compatible.
+        #[cfg(feature = "parachain")]
+        ParachainInfo(parachain_piece::SetParachainInfo<Runtime>),
+        /// Mint the relay chain coins that reserve transfers deposited via an inherent extrinsic.
+        #[cfg(feature = "parachain")]
+        ReceiveReserveTransfers(xcm_transfer::ReceiveReserveTransfers<Runtime>),
+        /// Burn relay chain coins and send the same amount back to the relay chain.
+        #[cfg(feature = "parachain")]
+        BurnAndSend(xcm_transfer::BurnAndSend<Runtime>),
+        /// Checks transfers of the relay chain coins on this parachain.
+        #[cfg(feature = "parachain")]
+        RelayMoney(money::MoneyConstraintChecker<{ xcm_transfer::RELAY }, Runtime>),
+        /// Deliver the messages that other parachains sent to this one via an inherent extrinsic.
+        #[cfg(feature = "parachain")]
+        ReceiveHorizontalMessages(hrmp_messaging::ReceiveMessages<Runtime>),
+        /// Remove delivered messages from the state once their lifetime is over.
+        #[cfg(feature = "parachain")]
+        RetireHorizontalMessages(hrmp_messaging::RetireMessages<Runtime>),
+        /// Send a message to another parachain, paid for with coins.
+        #[cfg(feature = "parachain")]
+        SendHorizontalMessage(hrmp_messaging::SendMessage<Runtime, 0>),
+        /// Select the collators at the start of each session via an inherent extrinsic.
+        #[cfg(feature = "parachain")]
+        AdvanceCollatorSession(collator_selection::AdvanceSession<Runtime>),
+        /// Bond coins to register as a collator candidate.
+        #[cfg(feature = "parachain")]
+        RegisterCollatorCandidate(collator_selection::RegisterCandidate<Runtime, 0>),
+        /// Retire a collator candidacy so that later sessions no longer select it.
+        #[cfg(feature = "parachain")]
+        RetireCollatorCandidate(collator_selection::RetireCandidate<Runtime, 0>),
+        /// Withdraw the bond of a retired candidacy once its session has ended.
+        #[cfg(feature = "parachain")]
+        WithdrawCollatorBond(collator_selection::WithdrawBond<Runtime, 0>),
+
+        /// Advance the BABE epochs and their randomness via an inherent extrinsic.
+        #[cfg(feature = "babe")]
+        AdvanceBabeEpoch(babe_epochs::AdvanceEpoch<Runtime>),
+
+        /// Remove timestamps that are old enough that no transaction needs to peek at them anymore.
+        CleanUpTimestamp(timestamp::CleanUpTimestamp<Runtime>),
+        /// Reveal the block author's VRF randomness via an inherent extrinsic.
+        RevealRandomness(randomness_beacon::RevealRandomness<Runtime>),
+        /// Remove randomness beacons that are old enough that no transaction needs to peek at them anymore.
+        CleanUpRandomness(randomness_beacon::CleanUpBeacons<Runtime>),
+        /// Checks that several participants spend their coins together into mixed outputs of a
+        /// common denomination, each paying for their own.
+        CoinJoin(coinjoin::CoinJoin<Runtime, 0>),
+        /// Reward the previous block's author with their share of the tips paid in their block via an inherent extrinsic.
+        RewardAuthor(author_reward::RewardAuthor<Runtime, 0>),
+        /// Deposit the treasury's share of the previous block's tips via an inherent extrinsic.
+        FundTreasury(treasury::FundTreasury<Runtime, 0>),
+        /// Propose spends from the treasury.
+        ProposeTreasurySpend(treasury::ProposeSpend<0>),
+        /// Pay out treasury spends that the council or a referendum approved.
+        ApproveTreasurySpend(treasury::ApproveSpend<Runtime, 0>),
+    },
+
+    utxo_types: [
+        money::Coin<0>,
+        money::Issuance<0>,
+        kitties::KittyData,
+        amoeba::AmoebaDetails,
+        timestamp::Timestamp,
+        randomness_beacon::Beacon,
+        governance::Proposal<0>,
+        governance::Vote<0>,
+        governance::Enacted<0>,
+        treasury::TreasuryFunds<0>,
+        treasury::SpendProposal<OuterVerifier, 0>,
+        aura_authorities::AuthoritySet,
+        grandpa_authorities::GrandpaAuthoritySet,
+        session_keys::Registration,
+        #[cfg(feature = "parachain")]
+        tuxedo_parachain_core::ParachainInherentDataUtxo,
+        #[cfg(feature = "parachain")]
+        parachain_piece::RelayStorageEntry,
+        #[cfg(feature = "parachain")]
+        money::Coin<{ xcm_transfer::RELAY }>,
+        #[cfg(feature = "parachain")]
+        hrmp_messaging::InboundMessage,
+        #[cfg(feature = "parachain")]
+        collator_selection::Candidacy<0>,
+        #[cfg(feature = "parachain")]
+        collator_selection::Retiring<0>,
+        #[cfg(feature = "parachain")]
+        collator_selection::Session,
+        #[cfg(feature = "babe")]
+        babe_epochs::EpochState,
+    ],
+
+    // Pieces that keep some of their UTXO types private describe them themselves.
+    describe_utxo_types: [
+        poe::describe_utxo_types,
+        runtime_upgrade::describe_utxo_types,
+    ],
+
+    genesis: {
+        /// The initial coins.
+        money: money::genesis::MoneyGenesisConfig<0, OuterVerifier, Runtime>,
+        /// The initial kitties.
+        kitties: kitties::genesis::KittiesGenesisConfig<OuterVerifier, Runtime>,
+        /// Outputs of any other piece, such as proofs of existence, declared as they are stored.
+        outputs: tuxedo_core::genesis::GenesisOutputs<OuterVerifier>,
+    },
+
+    opaque: {
+        // This part is necessary for generating session keys in the runtime
+        #[cfg(not(feature = "babe"))]
+        impl_opaque_keys! {
+            pub struct SessionKeys {
+                pub aura: AuraAppPublic,
+                pub grandpa: GrandpaAppPublic,
+            }
+        }
+
+        // With the babe feature, blocks are authored with BABE keys instead of Aura keys.
+        #[cfg(feature = "babe")]
+        impl_opaque_keys! {
+            pub struct SessionKeys {
+                pub babe: BabeAppPublic,
+                pub grandpa: GrandpaAppPublic,
+            }
+        }
+
+        // Typically these are not implemented manually, but rather for the pallet associated with the
+        // keys. Here we are not using the pallets, and these implementations are trivial, so we just
+        // re-write them.
+        pub struct AuraAppPublic;
+        impl BoundToRuntimeAppPublic for AuraAppPublic {
+            type Public = AuraId;
+        }
+
+        pub struct GrandpaAppPublic;
+        impl BoundToRuntimeAppPublic for GrandpaAppPublic {
+            type Public = sp_consensus_grandpa::AuthorityId;
+        }
+
+        #[cfg(feature = "babe")]
+        pub struct BabeAppPublic;
+        #[cfg(feature = "babe")]
+        impl BoundToRuntimeAppPublic for BabeAppPublic {
+            type Public = sp_consensus_babe::AuthorityId;
+        }
+    },
 }
 
-/// The main struct in this module.
-#[derive(Encode, Decode, PartialEq, Eq, Clone, TypeInfo)]
-pub struct Runtime;
+// Every public type that the pieces of this runtime store in the UTXO set. Types that pieces keep
+// private are checked when the metadata is built instead.
+tuxedo_core::assert_unique_type_ids!(
+    money::Coin<0>,
+    money::Issuance<0>,
+    kitties::KittyData,
+    amoeba::AmoebaDetails,
+    timestamp::Timestamp,
+    randomness_beacon::Beacon,
+    governance::Proposal<0>,
+    governance::Vote<0>,
+    governance::Enacted<0>,
+    aura_authorities::AuthoritySet,
+    grandpa_authorities::GrandpaAuthoritySet,
+    session_keys::Registration,
+);
 
 // Here we hard-code consensus authority IDs for the well-known identities that work with the CLI flags
 // Such as `--alice`, `--bob`, etc. Only Alice is enabled by default which makes things work nicely
 // in a `--dev` node. You may enable more authorities to test more interesting networks, or replace
-// these IDs entirely.
+// these IDs entirely. The Aura authorities can also be rotated on chain through governance, while
+// a parachain selects its collators on chain from bonded candidates and keeps these as invulnerables.
 impl Runtime {
-    /// Aura authority IDs
-    fn aura_authorities() -> Vec<AuraId> {
+    /// Aura authority IDs before governance rotates them
+    fn genesis_aura_authorities() -> Vec<H256> {
         use hex_literal::hex;
-        use sp_application_crypto::ByteArray;
 
         [
             // Alice
@@ -275,16 +621,75 @@ impl Runtime {
             // Ferdie
             // hex!("1cbd2d43530a44705ad088af313e18f80b53ef16b36177cd4b77b846f2a5f07c"),
         ]
-        .iter()
-        .map(|hex| AuraId::from_slice(hex.as_ref()).expect("Valid Aura authority hex was provided"))
+        .into_iter()
+        .map(H256::from)
         .collect()
     }
 
-    ///Grandpa Authority IDs - All equally weighted
-    fn grandpa_authorities() -> sp_consensus_grandpa::AuthorityList {
-        use hex_literal::hex;
+    /// The identities of the Aura authorities, as currently stored on chain. A parachain's
+    /// authorities are the collators that the current session selected.
+    fn aura_identities() -> Vec<H256> {
+        #[cfg(not(feature = "parachain"))]
+        let identities = aura_authorities::authorities::<Runtime>();
+        #[cfg(feature = "parachain")]
+        let identities = collator_selection::collators::<Runtime>();
+
+        identities
+    }
+
+    /// Aura authority IDs. Each authority authors with the session key that it published,
+    /// or with its identity if it has not published one.
+    fn aura_authorities() -> Vec<AuraId> {
+        use sp_application_crypto::ByteArray;
+
+        session_keys::aura_authorities::<Runtime>()
+            .iter()
+            .map(|key| AuraId::from_slice(key.as_ref()).expect("Aura authorities are 32 bytes"))
+            .collect()
+    }
+
+    /// The slot of the current block, according to the block's Aura pre-runtime digest.
+    #[cfg(not(feature = "babe"))]
+    fn aura_slot() -> Option<u64> {
+        let slot = Executive::block_digest().convert_first(|item| {
+            item.pre_runtime_try_to::<sp_consensus_aura::Slot>(&sp_consensus_aura::AURA_ENGINE_ID)
+        })?;
+        Some(*slot)
+    }
+
+    /// The key that authored the current block, according to the slot in the block's
+    /// pre-runtime digest. This is the authority's session key if it published one.
+    #[cfg(not(any(feature = "babe", feature = "parachain")))]
+    fn aura_author() -> Option<H256> {
         use sp_application_crypto::ByteArray;
 
+        let slot = Self::aura_slot()?;
+        let authorities = Self::aura_authorities();
+        let index = slot.checked_rem(authorities.len() as u64)?;
+        let author = authorities.get(index as usize)?;
+        Some(H256::from_slice(author.as_slice()))
+    }
+
+    /// The identity of the current block's author, according to the consensus engine's
+    /// pre-runtime digest. Rewards go to the identity rather than to the session key.
+    #[cfg(not(feature = "babe"))]
+    fn consensus_author() -> Option<H256> {
+        let slot = Self::aura_slot()?;
+        let identities = Self::aura_identities();
+        let index = slot.checked_rem(identities.len() as u64)?;
+        identities.get(index as usize).copied()
+    }
+
+    /// The author of the current block, according to the consensus engine's pre-runtime digest.
+    #[cfg(feature = "babe")]
+    fn consensus_author() -> Option<H256> {
+        babe_epochs::block_author::<Runtime>()
+    }
+
+    /// Grandpa Authority IDs before governance rotates them - All equally weighted
+    fn genesis_grandpa_authorities() -> Vec<(H256, u64)> {
+        use hex_literal::hex;
+
         [
             // Alice
             hex!("88dc3417d5058ec4b4503e0c12ea1a0a89be200fe98922423d4334014fa6b0ee"),
@@ -299,16 +704,18 @@ impl Runtime {
             // Ferdie
             // hex!("568cb4a574c6d178feb39c27dfc8b3f789e5f5423e19c71633c748b9acf086b5"),
         ]
-        .iter()
-        .map(|hex| {
-            (
-                GrandpaId::from_slice(hex.as_ref())
-                    .expect("Valid Grandpa authority hex was provided"),
-                1,
-            )
-        })
+        .into_iter()
+        .map(|hex| (H256::from(hex), 1))
         .collect()
     }
+
+    /// Measure the weights of this runtime's verifiers and constraint checkers.
+    #[cfg(feature = "runtime-benchmarks")]
+    pub fn benchmarks(repeats: u32) -> Vec<tuxedo_core::benchmarking::BenchmarkResult> {
+        let mut results = tuxedo_core::benchmarking::bench_core_verifiers(repeats);
+        results.extend(money::benchmarking::bench::<0, Runtime>(repeats));
+        results
+    }
 }
 
 impl_runtime_apis! {
@@ -359,18 +766,51 @@ impl_runtime_apis! {
         }
     }
 
-    // Tuxedo does not yet support metadata
+    // Tuxedo serves its own metadata format rather than FRAME's
     impl sp_api::Metadata<Block> for Runtime {
         fn metadata() -> OpaqueMetadata {
-            OpaqueMetadata::new(Default::default())
+            Runtime::metadata().into_opaque()
         }
 
-        fn metadata_at_version(_version: u32) -> Option<OpaqueMetadata> {
-            None
+        fn metadata_at_version(version: u32) -> Option<OpaqueMetadata> {
+            (version == METADATA_VERSION).then(|| Runtime::metadata().into_opaque())
         }
 
         fn metadata_versions() -> sp_std::vec::Vec<u32> {
-            Default::default()
+            vec![METADATA_VERSION]
+        }
+    }
+
+    impl tuxedo_core::metadata::TuxedoIntrospectionApi<Block> for Runtime {
+        fn introspect() -> tuxedo_core::metadata::RuntimeIntrospection {
+            Runtime::metadata().introspect()
+        }
+    }
+
+    #[cfg(feature = "try-runtime")]
+    impl tuxedo_core::try_runtime::TryRuntime<Block> for Runtime {
+        fn on_runtime_upgrade(
+            checks: tuxedo_core::try_runtime::UpgradeCheckSelect,
+        ) -> (
+            tuxedo_core::try_runtime::TryRuntimeWeight,
+            tuxedo_core::try_runtime::TryRuntimeWeight,
+        ) {
+            Executive::try_runtime_upgrade(checks)
+        }
+
+        fn execute_block(
+            block: Block,
+            state_root_check: bool,
+            signature_check: bool,
+            select: tuxedo_core::try_runtime::TryStateSelect,
+        ) -> tuxedo_core::try_runtime::TryRuntimeWeight {
+            Executive::try_execute_block(block, state_root_check, signature_check, select)
+        }
+    }
+
+    impl sp_offchain::OffchainWorkerApi<Block> for Runtime {
+        fn offchain_worker(header: &<Block as BlockT>::Header) {
+            Executive::offchain_worker(header)
         }
     }
 
@@ -396,13 +836,50 @@ impl_runtime_apis! {
         }
     }
 
+    #[cfg(feature = "babe")]
+    impl sp_consensus_babe::BabeApi<Block> for Runtime {
+        fn configuration() -> sp_consensus_babe::BabeConfiguration {
+            babe_epochs::configuration::<Runtime>(BLOCK_TIME)
+        }
+
+        fn current_epoch_start() -> sp_consensus_babe::Slot {
+            babe_epochs::current_epoch_start::<Runtime>()
+        }
+
+        fn current_epoch() -> sp_consensus_babe::Epoch {
+            babe_epochs::current_epoch::<Runtime>()
+        }
+
+        fn next_epoch() -> sp_consensus_babe::Epoch {
+            babe_epochs::next_epoch::<Runtime>()
+        }
+
+        fn generate_key_ownership_proof(
+            _slot: sp_consensus_babe::Slot,
+            _authority_id: sp_consensus_babe::AuthorityId,
+        ) -> Option<sp_consensus_babe::OpaqueKeyOwnershipProof> {
+            None
+        }
+
+        fn submit_report_equivocation_unsigned_extrinsic(
+            _equivocation_proof: sp_consensus_babe::EquivocationProof<<Block as BlockT>::Header>,
+            _key_owner_proof: sp_consensus_babe::OpaqueKeyOwnershipProof,
+        ) -> Option<()> {
+            None
+        }
+    }
+
     impl sp_consensus_grandpa::GrandpaApi<Block> for Runtime {
         fn grandpa_authorities() -> sp_consensus_grandpa::AuthorityList {
-            Self::grandpa_authorities()
+            session_keys::grandpa_voters::<Runtime>()
         }
 
+        // Every published GRANDPA key schedules a new set for the voters, on top of the
+        // rotations by governance. At most one of them lands in each block, so this counts the
+        // changes that the voters saw, which warp syncing nodes rely on to pick up the set.
         fn current_set_id() -> sp_consensus_grandpa::SetId {
-            0u64
+            grandpa_authorities::authority_set::<Runtime>().set_id
+                + session_keys::grandpa_key_changes()
         }
 
         fn submit_report_equivocation_unsigned_extrinsic(
@@ -423,6 +900,62 @@ impl_runtime_apis! {
         }
     }
 
+    impl poe::PoeApi<Block> for Runtime {
+        fn verify_inclusion(
+            root: H256,
+            leaves: u32,
+            document: H256,
+            proof: poe::MerkleProof,
+        ) -> bool {
+            poe::verify_inclusion(root, leaves, document, &proof)
+        }
+    }
+
+    impl money::MoneyApi<Block> for Runtime {
+        fn total_issuance(id: u8) -> Option<u128> {
+            money::total_issuance(id)
+        }
+    }
+
+    impl kitties::KittiesApi<Block> for Runtime {
+        fn kitty_traits(dna: kitties::KittyDNA) -> kitties::KittyTraits {
+            dna.traits()
+        }
+    }
+
+    impl tuxedo_core::events::TuxedoEventsApi<Block> for Runtime {
+        fn events() -> Vec<tuxedo_core::events::EventRecord> {
+            tuxedo_core::events::block_events()
+        }
+    }
+
+    impl tuxedo_core::types::TuxedoDryRunApi<Block, OuterVerifier, OuterConstraintChecker> for Runtime {
+        fn dry_run(
+            transaction: Transaction,
+        ) -> Result<TransactionPriority, tuxedo_core::types::DetailedError> {
+            Executive::dry_run(&transaction)
+                .map_err(tuxedo_core::types::DetailedError::new::<OuterVerifier, OuterConstraintChecker>)
+        }
+    }
+
+    impl tuxedo_core::utxo_set::TuxedoUtxoApi<Block, OuterVerifier> for Runtime {
+        fn utxo(output_ref: tuxedo_core::types::OutputRef) -> Option<Output> {
+            use tuxedo_core::utxo_set::UtxoSet;
+            tuxedo_core::utxo_set::TransparentUtxoSet::<OuterVerifier>::peek_utxo(&output_ref)
+        }
+
+        fn utxo_exists(output_ref: tuxedo_core::types::OutputRef) -> bool {
+            use tuxedo_core::utxo_set::UtxoSet;
+            tuxedo_core::utxo_set::TransparentUtxoSet::<OuterVerifier>::utxo_exists(&output_ref)
+        }
+    }
+
+    impl tuxedo_core::utxo_set::TuxedoUtxoProofApi<Block> for Runtime {
+        fn utxo_storage_key(output_ref: tuxedo_core::types::OutputRef) -> Vec<u8> {
+            tuxedo_core::utxo_set::TransparentUtxoSet::<OuterVerifier>::storage_key(&output_ref)
+        }
+    }
+
     #[cfg(feature = "parachain")]
     impl cumulus_primitives_core::CollectCollationInfo<Block> for Runtime {
         fn collect_collation_info(header: &<Block as BlockT>::Header) -> cumulus_primitives_core::CollationInfo {
@@ -434,4 +967,129 @@ impl_runtime_apis! {
 
 // Register the `validate_block` function that Polkadot validators will call to verify this parachain block.
 #[cfg(feature = "parachain")]
-tuxedo_parachain_core::register_validate_block!(Block, OuterVerifier, OuterConstraintChecker);
+tuxedo_parachain_core::register_validate_block!(
+    Block,
+    OuterVerifier,
+    OuterConstraintChecker,
+    (),
+    tuxedo_core::utxo_set::TransparentUtxoSet<OuterVerifier>,
+    Version,
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tuxedo_core::{inherents::InherentInternal, types::TransactionVersion, ConstraintChecker};
+
+    #[test]
+    fn utxo_types_have_unique_ids() {
+        // Panics if two of the described types share a type id.
+        Runtime::metadata();
+    }
+
+    #[test]
+    fn tips_are_shared_out_exactly_once() {
+        use author_reward::AuthorRewardConfig;
+        use treasury::TreasuryConfig;
+
+        let author = <Runtime as AuthorRewardConfig>::TIP_SHARE.deconstruct();
+        let treasury = <Runtime as TreasuryConfig>::TIP_SHARE.deconstruct();
+        assert_eq!(author + treasury, 100);
+    }
+
+    #[test]
+    fn introspection_lists_the_runtime_pieces() {
+        use tuxedo_core::dynamic_typing::UtxoData;
+        let introspection = Runtime::metadata().introspect();
+
+        assert!(introspection.verifiers.iter().any(|v| v.name == "SigCheck"));
+        assert!(introspection
+            .constraint_checkers
+            .iter()
+            .any(|c| c.name == "Money"));
+        assert!(introspection
+            .utxo_types
+            .iter()
+            .any(|t| t.type_id == money::Coin::<0>::TYPE_ID));
+    }
+
+    #[test]
+    fn checker_errors_are_named_after_their_piece() {
+        let error =
+            OuterConstraintCheckerError::Money(money::ConstraintCheckerError::OutputsExceedInputs);
+        let name = <OuterConstraintChecker as ConstraintChecker<OuterVerifier>>::name_error(&error);
+
+        assert_eq!(name.piece, "Money");
+        assert_eq!(name.error, "OutputsExceedInputs");
+        assert_eq!(name.to_string(), "Money::OutputsExceedInputs");
+    }
+
+    #[test]
+    fn runtime_upgrades_go_first_in_the_pool() {
+        use tuxedo_core::pool::PoolPolicy as _;
+
+        let transaction = |checker| Transaction {
+            version: TransactionVersion::LATEST,
+            inputs: Vec::new(),
+            peeks: Vec::new(),
+            outputs: Vec::new(),
+            checker,
+            memo: None,
+            valid_until: None,
+        };
+        let upgrade = runtime_upgrade::RuntimeUpgrade::<Runtime, 0>::new(Vec::new());
+
+        assert_eq!(
+            PoolPolicy::priority(&transaction(upgrade.into()), 5),
+            TransactionPriority::MAX
+        );
+        assert_eq!(
+            PoolPolicy::priority(&transaction(amoeba::AmoebaCreation.into()), 5),
+            5
+        );
+    }
+
+    #[test]
+    fn delegate_scopes_follow_the_transaction() {
+        let proxy = Proxy::new(
+            H256::repeat_byte(1),
+            vec![tuxedo_core::verifier::Delegate {
+                pubkey: H256::repeat_byte(2),
+                scope: DelegateScope::TransfersBelow(100),
+            }],
+        );
+        let payment = |sent: u128, change: u128| {
+            Transaction {
+                version: TransactionVersion::LATEST,
+                inputs: Vec::new(),
+                peeks: Vec::new(),
+                outputs: vec![
+                    (
+                        money::Coin::<0>(sent),
+                        SigCheck::new(H256::repeat_byte(3)).into(),
+                    )
+                        .into(),
+                    (money::Coin::<0>(change), proxy.clone().into()).into(),
+                ],
+                checker: money::MoneyConstraintChecker::Spend.into(),
+                memo: None,
+                valid_until: None,
+            }
+            .signing_payload(&Default::default())
+        };
+
+        assert!(DelegateScope::TransfersBelow(100).allows(&proxy, &payment(99, 500)));
+        assert!(!DelegateScope::TransfersBelow(100).allows(&proxy, &payment(100, 0)));
+        assert!(!DelegateScope::PoeClaimsOnly.allows(&proxy, &payment(1, 0)));
+        assert!(DelegateScope::Any.allows(&proxy, &payment(1_000, 0)));
+    }
+
+    #[test]
+    fn inherent_identifiers_are_unique() {
+        let mut identifiers = OuterConstraintCheckerInherentHooks::identifiers();
+        let registered = identifiers.len();
+        identifiers.sort();
+        identifiers.dedup();
+        assert_eq!(identifiers.len(), registered);
+    }
+}
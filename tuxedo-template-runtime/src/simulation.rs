@@ -0,0 +1,599 @@
+//! Simulate chains of the template runtime natively, block by block.
+//!
+//! A [`Simulation`] authors blocks the way a node does: it opens each block, applies the
+//! inherents and the given transactions, and closes it. Then it imports the block on a fresh
+//! copy of the parent's state, just like any other node would, so every block is both authored
+//! and checked. Blocks may be built on any known block, so tests can create forks and watch the
+//! longest chain become the best one. Finalizing a block prunes every fork that does not
+//! include it.
+//!
+//! Everything after genesis is deterministic. Each block's timestamp is one slot after its
+//! parent's, and under Aura its author is the one assigned to that slot. This lets tests of
+//! inherents and of protocols that span many blocks run in CI without a node. The genesis
+//! timestamp is taken from the clock, so two simulations only share a genesis block when one is
+//! cloned from the other. A clone is like another node that has synced the same blocks.
+//!
+//! Parachain builds are not supported, because their inherents need data from a relay chain.
+
+use super::{
+    genesis::{genesis_config, GenesisSections},
+    Block, Executive, Header, Transaction, BLOCK_TIME,
+};
+use parity_scale_codec::{Decode, Encode};
+use sp_core::{
+    storage::{well_known_keys::is_child_storage_key, Storage, StorageChild},
+    H256,
+};
+use sp_inherents::InherentData;
+use sp_io::TestExternalities;
+use sp_runtime::{
+    traits::{BlakeTwo256, Block as BlockT, Hash as HashT, Header as HeaderT},
+    transaction_validity::TransactionValidityError,
+    BuildStorage, Digest, StateVersion,
+};
+use std::collections::{BTreeMap, HashMap};
+use tuxedo_core::{
+    genesis::take_genesis_extrinsics,
+    inherents::PARENT_INHERENT_IDENTIFIER,
+    pool::dependency_order,
+    utxo_set::{utxo_child_info, UTXO_CHILD_TRIE},
+    verifier::best_time,
+};
+
+/// Errors that a [`Simulation`] may return.
+#[derive(Debug, PartialEq, Eq)]
+pub enum SimulationError {
+    /// The block is not known. It was never authored or imported, or it was pruned when a block
+    /// on another fork was finalized.
+    UnknownBlock,
+    /// The block's parent is not known.
+    UnknownParent,
+    /// The block does not descend from the finalized block.
+    NotDescendantOfFinalized,
+}
+
+/// A block that was authored in a [`Simulation`].
+#[derive(Debug)]
+pub struct AuthoredBlock {
+    /// The hash of the block.
+    pub hash: H256,
+    /// The block itself, including the inherents.
+    pub block: Block,
+    /// The transactions that were left out of the block, and why.
+    pub rejected: Vec<(Transaction, TransactionValidityError)>,
+}
+
+/// A block that the simulation knows of, along with the state after it.
+#[derive(Clone)]
+struct KnownBlock {
+    block: Block,
+    state: Storage,
+    timestamp: u64,
+}
+
+/// A chain of the template runtime that is built and imported natively.
+#[derive(Clone)]
+pub struct Simulation {
+    blocks: HashMap<H256, KnownBlock>,
+    genesis_hash: H256,
+    best_hash: H256,
+    finalized_hash: H256,
+}
+
+impl Simulation {
+    /// Start a chain from the genesis block that the given sections describe.
+    pub fn new(sections: &GenesisSections) -> Self {
+        let mut storage = genesis_config(sections)
+            .build_storage()
+            .expect("the genesis configuration builds its storage");
+        let extrinsics = take_genesis_extrinsics::<Block>(&mut storage);
+
+        let extrinsics_root = BlakeTwo256::ordered_trie_root(
+            extrinsics.iter().map(Encode::encode).collect(),
+            StateVersion::V0,
+        );
+        let state_root = TestExternalities::new(storage.clone()).execute_with(|| storage_root());
+        let header = Header::new(
+            0,
+            extrinsics_root,
+            state_root,
+            H256::zero(),
+            Digest::default(),
+        );
+        // Genesis transactions are not executed, so the genesis time is only noted in state
+        // once the first block is. Until then, it is in the timestamp piece's genesis output.
+        let timestamp = extrinsics
+            .iter()
+            .flat_map(|transaction| &transaction.outputs)
+            .find_map(|output| output.payload.extract::<timestamp::Timestamp>().ok())
+            .map(|genesis_timestamp| genesis_timestamp.time)
+            .unwrap_or_default();
+
+        let block = Block::new(header, extrinsics);
+        let genesis_hash = block.hash();
+
+        let mut blocks = HashMap::new();
+        blocks.insert(
+            genesis_hash,
+            KnownBlock {
+                block,
+                state: storage,
+                timestamp,
+            },
+        );
+
+        Self {
+            blocks,
+            genesis_hash,
+            best_hash: genesis_hash,
+            finalized_hash: genesis_hash,
+        }
+    }
+
+    /// The hash of the genesis block.
+    pub fn genesis_hash(&self) -> H256 {
+        self.genesis_hash
+    }
+
+    /// The hash of the best block, which is the highest one known.
+    /// When several blocks are equally high, the first one to be imported is the best.
+    pub fn best_hash(&self) -> H256 {
+        self.best_hash
+    }
+
+    /// The height of the best block.
+    pub fn best_number(&self) -> u32 {
+        self.blocks[&self.best_hash].block.header.number
+    }
+
+    /// The hash of the last finalized block.
+    pub fn finalized_hash(&self) -> H256 {
+        self.finalized_hash
+    }
+
+    /// The block with the given hash, if it is known.
+    pub fn block(&self, hash: &H256) -> Option<&Block> {
+        self.blocks.get(hash).map(|known| &known.block)
+    }
+
+    /// Author a block on top of the best block.
+    pub fn author_block(&mut self, transactions: Vec<Transaction>) -> AuthoredBlock {
+        self.author_block_on(self.best_hash, transactions)
+            .expect("the best block is always known")
+    }
+
+    /// Author the given number of empty blocks on top of the best block, and return their hashes.
+    pub fn author_empty_blocks(&mut self, count: u32) -> Vec<H256> {
+        (0..count)
+            .map(|_| self.author_block(Vec::new()).hash)
+            .collect()
+    }
+
+    /// Author a block on top of the given parent, which may be on any fork.
+    ///
+    /// The inherents go first, followed by every given transaction that is valid at that point.
+    /// Like the node's pool, the simulation puts transactions after the ones whose outputs they
+    /// spend, so chains of dependent transactions can be given in any order.
+    /// The invalid ones are left out of the block and returned along with it.
+    /// The block is then imported, so it may become the best block.
+    pub fn author_block_on(
+        &mut self,
+        parent_hash: H256,
+        transactions: Vec<Transaction>,
+    ) -> Result<AuthoredBlock, SimulationError> {
+        let parent = self
+            .blocks
+            .get(&parent_hash)
+            .ok_or(SimulationError::UnknownParent)?;
+
+        let timestamp = parent.timestamp + BLOCK_TIME;
+        let header = Header::new(
+            parent.block.header.number + 1,
+            H256::zero(),
+            H256::zero(),
+            parent_hash,
+            pre_digest(timestamp),
+        );
+
+        let mut inherent_data = InherentData::new();
+        inherent_data
+            .put_data(sp_timestamp::INHERENT_IDENTIFIER, &timestamp)
+            .expect("the timestamp is not in the inherent data yet");
+        inherent_data
+            .put_data(PARENT_INHERENT_IDENTIFIER, &parent.block)
+            .expect("the parent block is not in the inherent data yet");
+
+        let (block, rejected) = TestExternalities::new(parent.state.clone()).execute_with(|| {
+            Executive::open_block(&header);
+
+            let mut extrinsics = Vec::new();
+            for inherent in Executive::inherent_extrinsics(inherent_data.clone()) {
+                Executive::apply_extrinsic(inherent.clone())
+                    .expect("the runtime's own inherents are valid")
+                    .expect("the runtime's own inherents dispatch successfully");
+                extrinsics.push(inherent);
+            }
+
+            // Like the block builder, roll back any transaction that fails to apply,
+            // so that it leaves no trace in the block.
+            let mut rejected = Vec::new();
+            for transaction in dependency_order(transactions) {
+                sp_io::storage::start_transaction();
+                match Executive::apply_extrinsic(transaction.clone()) {
+                    Ok(_) => {
+                        sp_io::storage::commit_transaction();
+                        extrinsics.push(transaction);
+                    }
+                    Err(e) => {
+                        sp_io::storage::rollback_transaction();
+                        rejected.push((transaction, e));
+                    }
+                }
+            }
+
+            let header = Executive::close_block();
+            (Block::new(header, extrinsics), rejected)
+        });
+
+        let result = Executive::check_inherents(block.clone(), inherent_data);
+        assert!(
+            result.ok(),
+            "the block's inherents should pass their own checks"
+        );
+
+        let hash = self.import_block(block.clone())?;
+        Ok(AuthoredBlock {
+            hash,
+            block,
+            rejected,
+        })
+    }
+
+    /// Import a block, such as one that another simulation authored.
+    ///
+    /// The block is executed on its parent's state, which panics if the block is invalid,
+    /// just like it does in the runtime. The block becomes the best one if it is higher
+    /// than the current best block.
+    pub fn import_block(&mut self, block: Block) -> Result<H256, SimulationError> {
+        let parent_hash = block.header.parent_hash;
+        if !self.blocks.contains_key(&parent_hash) {
+            return Err(SimulationError::UnknownParent);
+        }
+        if !self.is_ancestor(&self.finalized_hash, &parent_hash) {
+            return Err(SimulationError::NotDescendantOfFinalized);
+        }
+
+        let mut ext = TestExternalities::new(self.blocks[&parent_hash].state.clone());
+        let (state, timestamp) = ext.execute_with(|| {
+            Executive::execute_block(block.clone());
+            let timestamp = best_time().map(|(time, _)| time).unwrap_or_default();
+            (storage_snapshot(), timestamp)
+        });
+
+        let hash = block.hash();
+        let number = block.header.number;
+        self.blocks.insert(
+            hash,
+            KnownBlock {
+                block,
+                state,
+                timestamp,
+            },
+        );
+        if number > self.best_number() {
+            self.best_hash = hash;
+        }
+
+        Ok(hash)
+    }
+
+    /// Finalize the given block, and prune every block that neither is its ancestor
+    /// nor descends from it.
+    pub fn finalize(&mut self, hash: H256) -> Result<(), SimulationError> {
+        if !self.blocks.contains_key(&hash) {
+            return Err(SimulationError::UnknownBlock);
+        }
+        if !self.is_ancestor(&self.finalized_hash, &hash) {
+            return Err(SimulationError::NotDescendantOfFinalized);
+        }
+
+        let pruned: Vec<H256> = self
+            .blocks
+            .keys()
+            .filter(|known| !self.is_ancestor(known, &hash) && !self.is_ancestor(&hash, known))
+            .copied()
+            .collect();
+        for known in pruned {
+            self.blocks.remove(&known);
+        }
+        self.finalized_hash = hash;
+
+        // The best block may have been on a pruned fork.
+        if !self.blocks.contains_key(&self.best_hash) {
+            self.best_hash = self
+                .blocks
+                .iter()
+                .max_by_key(|(hash, known)| (known.block.header.number, **hash))
+                .map(|(hash, _)| *hash)
+                .expect("the finalized block is always known");
+        }
+
+        Ok(())
+    }
+
+    /// Run the given closure on the state after the given block, for example to look up utxos.
+    /// Any changes the closure makes are discarded.
+    pub fn read_state<R>(&self, hash: &H256, f: impl FnOnce() -> R) -> Result<R, SimulationError> {
+        let known = self.blocks.get(hash).ok_or(SimulationError::UnknownBlock)?;
+        Ok(TestExternalities::new(known.state.clone()).execute_with(f))
+    }
+
+    /// Run the given closure on the state after the best block.
+    pub fn read_best_state<R>(&self, f: impl FnOnce() -> R) -> R {
+        self.read_state(&self.best_hash, f)
+            .expect("the best block is always known")
+    }
+
+    /// Whether `ancestor` is `block` itself or one of its known ancestors.
+    fn is_ancestor(&self, ancestor: &H256, block: &H256) -> bool {
+        let mut current = *block;
+        loop {
+            if current == *ancestor {
+                return true;
+            }
+            match self.blocks.get(&current) {
+                Some(known) if known.block.header.number > 0 => {
+                    current = known.block.header.parent_hash;
+                }
+                _ => return false,
+            }
+        }
+    }
+}
+
+/// The digest that a block at the given time is opened with.
+/// Under Aura, this names the slot, from which the runtime knows the block's author.
+#[cfg(not(feature = "babe"))]
+fn pre_digest(timestamp: u64) -> Digest {
+    let slot = sp_consensus_aura::Slot::from(timestamp / BLOCK_TIME);
+    Digest {
+        logs: vec![sp_runtime::DigestItem::PreRuntime(
+            sp_consensus_aura::AURA_ENGINE_ID,
+            slot.encode(),
+        )],
+    }
+}
+
+/// The digest that a block at the given time is opened with.
+/// Under BABE, blocks are authored without a pre-runtime digest, so they have no author.
+#[cfg(feature = "babe")]
+fn pre_digest(_timestamp: u64) -> Digest {
+    Digest::default()
+}
+
+/// The root of the current state, as the executive computes it.
+fn storage_root() -> H256 {
+    H256::decode(&mut &sp_io::storage::root(StateVersion::V1)[..])
+        .expect("the storage root is a hash")
+}
+
+/// Every key and value in the current state, including the utxo child trie.
+fn storage_snapshot() -> Storage {
+    let mut top = BTreeMap::new();
+    let mut next = sp_io::storage::next_key(&[]);
+    while let Some(key) = next {
+        let value = sp_io::storage::get(&key).expect("the key was just found in storage");
+        next = sp_io::storage::next_key(&key);
+        // The roots of child tries are recomputed from their contents.
+        if !is_child_storage_key(&key) {
+            top.insert(key, value.to_vec());
+        }
+    }
+
+    let mut utxos = BTreeMap::new();
+    let mut next = sp_io::default_child_storage::next_key(UTXO_CHILD_TRIE, &[]);
+    while let Some(key) = next {
+        let value = sp_io::default_child_storage::get(UTXO_CHILD_TRIE, &key)
+            .expect("the key was just found in storage");
+        next = sp_io::default_child_storage::next_key(UTXO_CHILD_TRIE, &key);
+        utxos.insert(key, value);
+    }
+
+    Storage {
+        top,
+        children_default: [(
+            UTXO_CHILD_TRIE.to_vec(),
+            StorageChild {
+                data: utxos,
+                child_info: utxo_child_info(),
+            },
+        )]
+        .into(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{genesis::development_genesis_sections, OuterConstraintChecker, Runtime};
+    use money::{Coin, MoneyConstraintChecker};
+    use tuxedo_core::{
+        genesis::GenesisOutputs,
+        types::{Input, InputMode, OutputRef, TransactionVersion},
+        utxo_set::{TransparentUtxoSet, UtxoSet},
+        verifier::UpForGrabs,
+    };
+
+    /// The development genesis, plus a coin that anyone can spend.
+    fn sections_with_free_coin() -> (GenesisSections, OutputRef) {
+        let outputs = GenesisOutputs::new(vec![(Coin::<0>(100), UpForGrabs).into()]);
+        let free_coin = outputs
+            .output_ref::<OuterConstraintChecker>(0)
+            .expect("there is one output");
+        let sections = GenesisSections {
+            outputs,
+            ..development_genesis_sections()
+        };
+
+        (sections, free_coin)
+    }
+
+    fn spend(input: OutputRef, amounts: &[u128]) -> Transaction {
+        Transaction {
+            version: TransactionVersion::LATEST,
+            inputs: vec![Input {
+                output_ref: input,
+                redeemer: Vec::new(),
+                mode: InputMode::Consume,
+            }],
+            peeks: Vec::new(),
+            outputs: amounts
+                .iter()
+                .map(|amount| (Coin::<0>(*amount), UpForGrabs).into())
+                .collect(),
+            checker: MoneyConstraintChecker::<0, Runtime>::Spend.into(),
+            memo: None,
+            valid_until: None,
+        }
+    }
+
+    fn coin_at(sim: &Simulation, hash: &H256, output_ref: &OutputRef) -> Option<Coin<0>> {
+        sim.read_state(hash, || {
+            TransparentUtxoSet::<crate::OuterVerifier>::peek_utxo(output_ref)
+        })
+        .unwrap()
+        .and_then(|output| output.payload.extract::<Coin<0>>().ok())
+    }
+
+    #[test]
+    fn empty_blocks_advance_height_and_time() {
+        let mut sim = Simulation::new(&development_genesis_sections());
+
+        let first = sim.author_block(Vec::new()).hash;
+        let (first_time, _) = sim.read_state(&first, best_time).unwrap().unwrap();
+        sim.author_empty_blocks(2);
+
+        assert_eq!(sim.best_number(), 3);
+        assert_eq!(
+            sim.read_best_state(best_time),
+            Some((first_time + 2 * BLOCK_TIME, 3))
+        );
+    }
+
+    #[test]
+    fn transactions_are_included_and_applied() {
+        let (sections, free_coin) = sections_with_free_coin();
+        let mut sim = Simulation::new(&sections);
+        let transfer = spend(free_coin.clone(), &[60, 40]);
+        let new_coin = OutputRef {
+            tx_hash: BlakeTwo256::hash_of(&transfer.encode()),
+            index: 1,
+        };
+
+        let authored = sim.author_block(vec![transfer.clone()]);
+
+        assert!(authored.rejected.is_empty());
+        assert!(authored.block.extrinsics.contains(&transfer));
+        assert_eq!(coin_at(&sim, &authored.hash, &free_coin), None);
+        assert_eq!(coin_at(&sim, &authored.hash, &new_coin), Some(Coin(40)));
+    }
+
+    #[test]
+    fn dependent_transactions_land_in_the_same_block() {
+        let (sections, free_coin) = sections_with_free_coin();
+        let mut sim = Simulation::new(&sections);
+        let parent = spend(free_coin, &[100]);
+        let child = spend(
+            OutputRef {
+                tx_hash: BlakeTwo256::hash_of(&parent.encode()),
+                index: 0,
+            },
+            &[100],
+        );
+
+        let authored = sim.author_block(vec![child.clone(), parent.clone()]);
+
+        assert!(authored.rejected.is_empty());
+        let position = |tx: &Transaction| authored.block.extrinsics.iter().position(|e| e == tx);
+        assert!(position(&parent).unwrap() < position(&child).unwrap());
+    }
+
+    #[test]
+    fn invalid_transactions_are_left_out() {
+        let (sections, free_coin) = sections_with_free_coin();
+        let mut sim = Simulation::new(&sections);
+        let overspend = spend(free_coin.clone(), &[200]);
+
+        let authored = sim.author_block(vec![overspend.clone()]);
+
+        assert_eq!(authored.rejected.len(), 1);
+        assert_eq!(authored.rejected[0].0, overspend);
+        assert!(!authored.block.extrinsics.contains(&overspend));
+        assert_eq!(coin_at(&sim, &authored.hash, &free_coin), Some(Coin(100)));
+    }
+
+    #[test]
+    fn longer_fork_becomes_best() {
+        let (sections, free_coin) = sections_with_free_coin();
+        let mut sim = Simulation::new(&sections);
+        let genesis = sim.genesis_hash();
+
+        let a1 = sim
+            .author_block(vec![spend(free_coin.clone(), &[100])])
+            .hash;
+        assert_eq!(sim.best_hash(), a1);
+
+        // A fork of the same height does not take over.
+        let b1 = sim.author_block_on(genesis, Vec::new()).unwrap().hash;
+        assert_eq!(sim.best_hash(), a1);
+
+        // Once it is longer, it does, and the transfer from the other fork is undone.
+        let b2 = sim.author_block_on(b1, Vec::new()).unwrap().hash;
+        assert_eq!(sim.best_hash(), b2);
+        assert_eq!(coin_at(&sim, &b2, &free_coin), Some(Coin(100)));
+    }
+
+    #[test]
+    fn finalizing_prunes_other_forks() {
+        let mut sim = Simulation::new(&development_genesis_sections());
+        let genesis = sim.genesis_hash();
+        let a1 = sim.author_block(Vec::new()).hash;
+        let b1 = sim.author_block_on(genesis, Vec::new()).unwrap().hash;
+        let b2 = sim.author_block_on(b1, Vec::new()).unwrap().hash;
+        assert_eq!(sim.best_hash(), b2);
+
+        sim.finalize(a1).unwrap();
+
+        assert_eq!(sim.finalized_hash(), a1);
+        assert_eq!(sim.best_hash(), a1);
+        assert!(sim.block(&b1).is_none());
+        assert!(sim.block(&b2).is_none());
+        assert!(sim.block(&genesis).is_some());
+        assert_eq!(
+            sim.author_block_on(b2, Vec::new()).unwrap_err(),
+            SimulationError::UnknownParent
+        );
+        assert_eq!(
+            sim.author_block_on(genesis, Vec::new()).unwrap_err(),
+            SimulationError::NotDescendantOfFinalized
+        );
+        assert_eq!(
+            sim.finalize(genesis),
+            Err(SimulationError::NotDescendantOfFinalized)
+        );
+    }
+
+    #[test]
+    fn clones_import_authored_blocks() {
+        let mut author = Simulation::new(&development_genesis_sections());
+        let mut importer = author.clone();
+
+        for _ in 0..2 {
+            let authored = author.author_block(Vec::new());
+            assert_eq!(importer.import_block(authored.block), Ok(authored.hash));
+        }
+
+        assert_eq!(importer.best_hash(), author.best_hash());
+        assert_eq!(importer.best_number(), 2);
+    }
+}
@@ -0,0 +1,272 @@
+//! Helpers for testing Tuxedo pieces without a full runtime.
+//!
+//! Unit tests call a piece's constraint checker directly, with payloads they made up. That leaves
+//! out everything the executive does around it: finding the inputs in the utxo set, checking their
+//! verifiers, and storing the outputs where later transactions can find them. This crate lets
+//! pieces test whole transactions, and sequences of them, against the real executive instead.
+//!
+//! - [`MockState`] builds test externalities with some utxos already in the [`TransparentUtxoSet`],
+//!   and optionally a block that is already opened.
+//! - [`BlockContext`] describes that block: its height, parent, digest, and timestamp.
+//! - [`apply`] runs a full [`Transaction`] through the executive, and returns the references to
+//!   the outputs that it created, so that the next transaction can consume them.
+//!
+//! ```ignore
+//! MockState::<TestVerifier, MoneyConstraintChecker<0>>::default()
+//!     .with_payload(mock_output_ref(0, 0), Coin::<0>(10), TestVerifier { verifies: true })
+//!     .build()
+//!     .execute_with(|| {
+//!         let tx = Transaction {
+//!             version: TransactionVersion::LATEST,
+//!             inputs: vec![consume(mock_output_ref(0, 0))],
+//!             peeks: vec![],
+//!             outputs: vec![(Coin::<0>(9), TestVerifier { verifies: true }).into()],
+//!             checker: MoneyConstraintChecker::Spend,
+//!             memo: None,
+//!             valid_until: None,
+//!         };
+//!         let refs = apply(tx).unwrap();
+//!         assert_eq!(payload::<Coin<0>, TestVerifier>(&refs[0]), Some(Coin(9)));
+//!     });
+//! ```
+
+use parity_scale_codec::Encode;
+use sp_core::H256;
+use sp_runtime::{
+    generic,
+    traits::{BlakeTwo256, Block as BlockT, Hash as HashT},
+    transaction_validity::TransactionPriority,
+    ConsensusEngineId, Digest, DigestItem,
+};
+use std::marker::PhantomData;
+use tuxedo_core::{
+    dynamic_typing::UtxoData,
+    types::{Input, InputMode, Output, OutputRef, Transaction, TransactionVersion, UtxoError},
+    utxo_set::{TransparentUtxoSet, UtxoSet},
+    verifier::note_time,
+    ConstraintChecker, Executive, Verifier,
+};
+
+pub use sp_io::TestExternalities;
+
+#[cfg(test)]
+mod tests;
+
+/// The header of the blocks that the mock executive works with.
+pub type TestHeader = generic::Header<u32, BlakeTwo256>;
+
+/// A block of transactions with the given verifier and constraint checker.
+pub type TestBlock<V, C> = generic::Block<TestHeader, Transaction<V, C>>;
+
+/// The executive that the helpers in this crate apply transactions through. It charges no fees
+/// and keeps the utxos in the [`TransparentUtxoSet`].
+pub type TestExecutive<V, C> = Executive<TestBlock<V, C>, V, C>;
+
+/// Construct a mock OutputRef from a transaction number and index in that transaction.
+///
+/// Utxos that a test puts in place before it begins were not created by any real transaction,
+/// so they have no real OutputRefs either. This constructs one from a "transaction number" and
+/// an output index, both of which are simple u32s.
+pub fn mock_output_ref(tx_num: u32, index: u32) -> OutputRef {
+    OutputRef {
+        tx_hash: H256::from_low_u64_le(tx_num as u64),
+        index,
+    }
+}
+
+/// An input that consumes the given output with an empty redeemer.
+/// This is enough for verifiers that do not look at the redeemer, such as `TestVerifier`.
+pub fn consume(output_ref: OutputRef) -> Input {
+    Input {
+        output_ref,
+        redeemer: Vec::new(),
+        mode: InputMode::Consume,
+    }
+}
+
+/// The block that a test runs in.
+///
+/// By default this is the first block after genesis, with an empty digest and no timestamp.
+#[derive(Clone, Debug)]
+pub struct BlockContext {
+    height: u32,
+    parent_hash: H256,
+    digest: Digest,
+    timestamp: Option<u64>,
+}
+
+impl Default for BlockContext {
+    fn default() -> Self {
+        Self {
+            height: 1,
+            parent_hash: H256::zero(),
+            digest: Digest::default(),
+            timestamp: None,
+        }
+    }
+}
+
+impl BlockContext {
+    /// A block at the given height.
+    pub fn at_height(height: u32) -> Self {
+        Self {
+            height,
+            ..Default::default()
+        }
+    }
+
+    /// Set the hash of the block's parent.
+    pub fn with_parent_hash(mut self, parent_hash: H256) -> Self {
+        self.parent_hash = parent_hash;
+        self
+    }
+
+    /// Add a pre-runtime digest item, such as the one that a consensus engine uses to note the
+    /// block's author.
+    pub fn with_pre_runtime(mut self, engine_id: ConsensusEngineId, data: Vec<u8>) -> Self {
+        self.digest.push(DigestItem::PreRuntime(engine_id, data));
+        self
+    }
+
+    /// Note the given time, in milliseconds, as the best known time in this block, as the
+    /// timestamp piece would. Verifiers that depend on time read it from here.
+    pub fn with_timestamp(mut self, timestamp: u64) -> Self {
+        self.timestamp = Some(timestamp);
+        self
+    }
+
+    /// The partial header that the block is opened with.
+    pub fn header(&self) -> TestHeader {
+        TestHeader {
+            parent_hash: self.parent_hash,
+            number: self.height,
+            state_root: H256::zero(),
+            extrinsics_root: H256::zero(),
+            digest: self.digest.clone(),
+        }
+    }
+}
+
+/// Builds test externalities in which some utxos, and optionally an opened block, are already
+/// in place.
+pub struct MockState<V, C> {
+    utxos: Vec<(OutputRef, Output<V>)>,
+    storage: Vec<(Vec<u8>, Vec<u8>)>,
+    block: Option<BlockContext>,
+    _checker: PhantomData<C>,
+}
+
+impl<V, C> Default for MockState<V, C> {
+    fn default() -> Self {
+        Self {
+            utxos: Vec::new(),
+            storage: Vec::new(),
+            block: None,
+            _checker: PhantomData,
+        }
+    }
+}
+
+impl<V, C> MockState<V, C>
+where
+    V: Verifier,
+    C: ConstraintChecker<V>,
+    TestBlock<V, C>: BlockT<Extrinsic = Transaction<V, C>>,
+{
+    /// Put the given output in the utxo set.
+    ///
+    /// See [`mock_output_ref`] for a convenient way to construct the output ref.
+    pub fn with_utxo(mut self, output_ref: OutputRef, output: Output<V>) -> Self {
+        self.utxos.push((output_ref, output));
+        self
+    }
+
+    /// Put an output with the given payload and verifier in the utxo set.
+    pub fn with_payload<T: UtxoData>(self, output_ref: OutputRef, payload: T, verifier: V) -> Self {
+        self.with_utxo(output_ref, (payload, verifier).into())
+    }
+
+    /// Write a raw value to storage, for pieces that keep some state outside the utxo set.
+    pub fn with_storage(mut self, key: &[u8], value: Vec<u8>) -> Self {
+        self.storage.push((key.to_vec(), value));
+        self
+    }
+
+    /// Open the given block before the test begins, so that transactions are applied in it.
+    ///
+    /// Without a block, transactions are checked like in the pool, where the block height is
+    /// that of the block after the one that noted the best known time.
+    pub fn with_block(mut self, block: BlockContext) -> Self {
+        self.block = Some(block);
+        self
+    }
+
+    /// Build the test externalities.
+    pub fn build(self) -> TestExternalities {
+        let mut ext = TestExternalities::default();
+
+        ext.execute_with(|| {
+            for (key, value) in &self.storage {
+                sp_io::storage::set(key, value);
+            }
+            for (output_ref, output) in self.utxos {
+                TransparentUtxoSet::<V>::store_utxo(output_ref, &output);
+            }
+            if let Some(block) = self.block {
+                TestExecutive::<V, C>::open_block(&block.header());
+                if let Some(timestamp) = block.timestamp {
+                    note_time(timestamp, block.height);
+                }
+            }
+        });
+
+        ext
+    }
+}
+
+/// The references to the outputs that the given transaction creates.
+pub fn output_refs<V, C>(transaction: &Transaction<V, C>) -> Vec<OutputRef>
+where
+    Transaction<V, C>: Encode,
+{
+    let tx_hash = BlakeTwo256::hash_of(&transaction.encode());
+    (0..transaction.outputs.len() as u32)
+        .map(|index| OutputRef { tx_hash, index })
+        .collect()
+}
+
+/// Apply the given transaction through the executive, just like a block would.
+///
+/// On success, returns the references to the outputs it created, in order.
+pub fn apply<V, C>(transaction: Transaction<V, C>) -> Result<Vec<OutputRef>, UtxoError<C::Error>>
+where
+    V: Verifier,
+    C: ConstraintChecker<V>,
+    TestBlock<V, C>: BlockT<Extrinsic = Transaction<V, C>>,
+{
+    let refs = output_refs(&transaction);
+    TestExecutive::<V, C>::apply_tuxedo_transaction(transaction).map(|()| refs)
+}
+
+/// Check the given transaction as if it were applied in the current block, without committing
+/// anything to storage. Returns the transaction's priority.
+pub fn dry_run<V, C>(
+    transaction: &Transaction<V, C>,
+) -> Result<TransactionPriority, UtxoError<C::Error>>
+where
+    V: Verifier,
+    C: ConstraintChecker<V>,
+    TestBlock<V, C>: BlockT<Extrinsic = Transaction<V, C>>,
+{
+    TestExecutive::<V, C>::dry_run(transaction)
+}
+
+/// The utxo at the given output ref, if it is in the utxo set.
+pub fn utxo<V: Verifier>(output_ref: &OutputRef) -> Option<Output<V>> {
+    TransparentUtxoSet::<V>::peek_utxo(output_ref)
+}
+
+/// The payload of the utxo at the given output ref, if it is in the utxo set and has the given type.
+pub fn payload<T: UtxoData, V: Verifier>(output_ref: &OutputRef) -> Option<T> {
+    utxo::<V>(output_ref).and_then(|output| output.payload.extract::<T>().ok())
+}
@@ -0,0 +1,214 @@
+//! Tests for the test helpers themselves
+
+use super::*;
+use parity_scale_codec::Decode;
+use scale_info::TypeInfo;
+use serde::{Deserialize, Serialize};
+use tuxedo_core::{
+    dynamic_typing::DynamicallyTypedData, verifier::TestVerifier, CheckerContext,
+    SimpleConstraintChecker,
+};
+
+/// A payload for testing.
+#[derive(Encode, Decode, Debug, PartialEq, Eq, Clone)]
+struct Marble(u32);
+
+impl UtxoData for Marble {
+    const TYPE_ID: [u8; 4] = *b"mrbl";
+}
+
+/// A checker that only passes in a block at the height enclosed, so tests can see the context.
+/// Without an opened block, transactions are checked at height one.
+#[derive(Serialize, Deserialize, Encode, Decode, Debug, PartialEq, Eq, Clone, TypeInfo)]
+struct AtHeight(u32);
+
+impl SimpleConstraintChecker for AtHeight {
+    type Error = ();
+
+    fn check(
+        &self,
+        _input_data: &[DynamicallyTypedData],
+        _peek_data: &[DynamicallyTypedData],
+        _output_data: &[DynamicallyTypedData],
+        context: &CheckerContext,
+    ) -> Result<TransactionPriority, ()> {
+        if context.block_height == self.0 {
+            Ok(0)
+        } else {
+            Err(())
+        }
+    }
+}
+
+type TestState = MockState<TestVerifier, AtHeight>;
+
+fn marble(value: u32, verifies: bool) -> Output<TestVerifier> {
+    (Marble(value), TestVerifier { verifies }).into()
+}
+
+fn transaction(
+    inputs: Vec<Input>,
+    outputs: Vec<Output<TestVerifier>>,
+    height: u32,
+) -> Transaction<TestVerifier, AtHeight> {
+    Transaction {
+        version: TransactionVersion::LATEST,
+        inputs,
+        peeks: Vec::new(),
+        outputs,
+        checker: AtHeight(height),
+        memo: None,
+        valid_until: None,
+    }
+}
+
+#[test]
+fn mock_state_stores_utxos() {
+    TestState::default()
+        .with_utxo(mock_output_ref(0, 0), marble(1, true))
+        .with_payload(
+            mock_output_ref(0, 1),
+            Marble(2),
+            TestVerifier { verifies: true },
+        )
+        .build()
+        .execute_with(|| {
+            assert_eq!(
+                utxo::<TestVerifier>(&mock_output_ref(0, 0)),
+                Some(marble(1, true))
+            );
+            assert_eq!(
+                payload::<Marble, TestVerifier>(&mock_output_ref(0, 1)),
+                Some(Marble(2))
+            );
+            assert_eq!(utxo::<TestVerifier>(&mock_output_ref(1, 0)), None);
+        });
+}
+
+#[test]
+fn mock_state_writes_raw_storage() {
+    TestState::default()
+        .with_storage(b"key", vec![1, 2, 3])
+        .build()
+        .execute_with(|| {
+            assert_eq!(
+                sp_io::storage::get(b"key").map(|v| v.to_vec()),
+                Some(vec![1, 2, 3])
+            );
+        });
+}
+
+#[test]
+fn apply_consumes_inputs_and_stores_outputs() {
+    TestState::default()
+        .with_utxo(mock_output_ref(0, 0), marble(1, true))
+        .build()
+        .execute_with(|| {
+            let tx = transaction(
+                vec![consume(mock_output_ref(0, 0))],
+                vec![marble(2, true), marble(3, false)],
+                1,
+            );
+            let expected_refs = output_refs(&tx);
+
+            let refs = apply(tx).unwrap();
+
+            assert_eq!(refs, expected_refs);
+            assert_eq!(utxo::<TestVerifier>(&mock_output_ref(0, 0)), None);
+            assert_eq!(payload::<Marble, TestVerifier>(&refs[0]), Some(Marble(2)));
+            assert_eq!(payload::<Marble, TestVerifier>(&refs[1]), Some(Marble(3)));
+        });
+}
+
+#[test]
+fn applied_outputs_can_be_spent_next() {
+    TestState::default()
+        .with_utxo(mock_output_ref(0, 0), marble(1, true))
+        .build()
+        .execute_with(|| {
+            let first = transaction(
+                vec![consume(mock_output_ref(0, 0))],
+                vec![marble(2, true)],
+                1,
+            );
+            let refs = apply(first).unwrap();
+
+            let second = transaction(vec![consume(refs[0].clone())], vec![marble(3, true)], 1);
+            let refs = apply(second).unwrap();
+
+            assert_eq!(payload::<Marble, TestVerifier>(&refs[0]), Some(Marble(3)));
+        });
+}
+
+#[test]
+fn apply_with_missing_input_fails() {
+    TestState::default().build().execute_with(|| {
+        let tx = transaction(vec![consume(mock_output_ref(0, 0))], vec![], 1);
+
+        assert_eq!(apply(tx), Err(UtxoError::MissingInput));
+    });
+}
+
+#[test]
+fn apply_with_failing_verifier_fails() {
+    TestState::default()
+        .with_utxo(mock_output_ref(0, 0), marble(1, false))
+        .build()
+        .execute_with(|| {
+            let tx = transaction(vec![consume(mock_output_ref(0, 0))], vec![], 1);
+
+            assert_eq!(apply(tx), Err(UtxoError::VerifierError));
+            assert!(utxo::<TestVerifier>(&mock_output_ref(0, 0)).is_some());
+        });
+}
+
+#[test]
+fn apply_with_failing_checker_fails() {
+    TestState::default()
+        .with_utxo(mock_output_ref(0, 0), marble(1, true))
+        .build()
+        .execute_with(|| {
+            let tx = transaction(
+                vec![consume(mock_output_ref(0, 0))],
+                vec![marble(2, true)],
+                2,
+            );
+            let refs = output_refs(&tx);
+
+            assert_eq!(apply(tx), Err(UtxoError::ConstraintCheckerError(())));
+            assert_eq!(utxo::<TestVerifier>(&refs[0]), None);
+        });
+}
+
+#[test]
+fn block_context_is_seen_by_checkers() {
+    TestState::default()
+        .with_utxo(mock_output_ref(0, 0), marble(1, true))
+        .with_block(BlockContext::at_height(7).with_timestamp(5_000))
+        .build()
+        .execute_with(|| {
+            let tx = transaction(vec![consume(mock_output_ref(0, 0))], vec![], 7);
+            assert!(dry_run(&tx).is_ok());
+
+            let tx = transaction(vec![consume(mock_output_ref(0, 0))], vec![], 1);
+            assert_eq!(dry_run(&tx), Err(UtxoError::ConstraintCheckerError(())));
+
+            assert_eq!(tuxedo_core::verifier::best_time(), Some((5_000, 7)));
+            assert_eq!(TestExecutive::<TestVerifier, AtHeight>::block_height(), 7);
+        });
+}
+
+#[test]
+fn block_context_digest_is_in_the_header() {
+    let context = BlockContext::default().with_pre_runtime(*b"test", vec![4, 2]);
+
+    TestState::default()
+        .with_block(context)
+        .build()
+        .execute_with(|| {
+            assert_eq!(
+                TestExecutive::<TestVerifier, AtHeight>::block_digest().logs,
+                vec![DigestItem::PreRuntime(*b"test", vec![4, 2])]
+            );
+        });
+}
@@ -0,0 +1,80 @@
+//! A persistent address book that maps human readable labels to public keys.
+//!
+//! Labels may be used anywhere the wallet CLI expects a recipient public key.
+//! The address book lives in its own tree in the wallet's sled database.
+
+use anyhow::anyhow;
+use parity_scale_codec::{Decode, Encode};
+use sled::Db;
+use sp_core::H256;
+
+/// The identifier for the address book tree in the db.
+const ADDRESS_BOOK: &str = "address_book";
+
+/// Add an entry to the address book, replacing any existing entry with the same label.
+pub fn add(db: &Db, label: &str, pubkey: H256) -> anyhow::Result<()> {
+    // A label that parses as a public key would be ambiguous when resolving.
+    if crate::h256_from_string(label).is_ok() {
+        return Err(anyhow!(
+            "Label {label} looks like a public key. Choose another label."
+        ));
+    }
+
+    let tree = db.open_tree(ADDRESS_BOOK)?;
+    tree.insert(label.as_bytes(), pubkey.encode())?;
+
+    Ok(())
+}
+
+/// Remove an entry from the address book. Returns whether the label was present.
+pub fn remove(db: &Db, label: &str) -> anyhow::Result<bool> {
+    let tree = db.open_tree(ADDRESS_BOOK)?;
+    Ok(tree.remove(label.as_bytes())?.is_some())
+}
+
+/// Get all the entries in the address book sorted by label.
+pub fn list(db: &Db) -> anyhow::Result<Vec<(String, H256)>> {
+    let tree = db.open_tree(ADDRESS_BOOK)?;
+
+    tree.iter()
+        .map(|raw_data| {
+            let (label_ivec, pubkey_ivec) = raw_data?;
+            let label = String::from_utf8(label_ivec.to_vec())?;
+            let pubkey = H256::decode(&mut &pubkey_ivec[..])?;
+            Ok((label, pubkey))
+        })
+        .collect()
+}
+
+/// Look up the public key associated with a label.
+pub fn get(db: &Db, label: &str) -> anyhow::Result<Option<H256>> {
+    let tree = db.open_tree(ADDRESS_BOOK)?;
+    let Some(ivec) = tree.get(label.as_bytes())? else {
+        return Ok(None);
+    };
+
+    Ok(Some(H256::decode(&mut &ivec[..])?))
+}
+
+/// Find the label of a public key, if it is in the address book.
+pub fn label_of(db: &Db, pubkey: &H256) -> anyhow::Result<Option<String>> {
+    Ok(list(db)?
+        .into_iter()
+        .find_map(|(label, p)| (&p == pubkey).then_some(label)))
+}
+
+/// Interpret a user supplied string as a hex encoded public key, an address book label,
+/// or a name registered with the name service, in that order.
+pub fn resolve(db: &Db, label_or_pubkey: &str) -> anyhow::Result<H256> {
+    if let Ok(pubkey) = crate::h256_from_string(label_or_pubkey) {
+        return Ok(pubkey);
+    }
+
+    if let Some(pubkey) = get(db, label_or_pubkey)? {
+        return Ok(pubkey);
+    }
+
+    crate::indexer::resolve_name(db, label_or_pubkey)?.ok_or(anyhow!(
+        "{label_or_pubkey} is neither a public key, a label in the address book, nor a registered name"
+    ))
+}
@@ -1,10 +1,8 @@
 //! Toy off-chain process to create an amoeba and perform mitosis on it
 
-use crate::rpc::fetch_storage;
+use crate::rpc::{fetch_storage, node_submit_and_watch};
 
-use std::{thread::sleep, time::Duration};
-
-use jsonrpsee::{core::client::ClientT, http_client::HttpClient, rpc_params};
+use jsonrpsee::http_client::HttpClient;
 use parity_scale_codec::Encode;
 use runtime::{
     amoeba::{AmoebaCreation, AmoebaDetails, AmoebaMitosis},
@@ -12,25 +10,25 @@ use runtime::{
 };
 use sp_runtime::traits::{BlakeTwo256, Hash};
 use tuxedo_core::{
-    types::{Input, Output, OutputRef},
+    transaction_builder::TransactionBuilder,
+    types::{OutputRef, TransactionStatus},
     verifier::UpForGrabs,
 };
 
-pub async fn amoeba_demo(client: &HttpClient) -> anyhow::Result<()> {
+/// Whether the transaction has made it into a block, so that its outputs are in storage.
+fn in_block(status: &TransactionStatus) -> bool {
+    matches!(status, TransactionStatus::InBlock { .. })
+}
+
+pub async fn amoeba_demo(client: &HttpClient, endpoint: &str) -> anyhow::Result<()> {
     // Construct a simple amoeba spawning transaction (no signature required)
     let eve = AmoebaDetails {
         generation: 0,
         four_bytes: *b"eve_",
     };
-    let spawn_tx = Transaction {
-        inputs: Vec::new(),
-        peeks: Vec::new(),
-        outputs: vec![Output {
-            payload: eve.into(),
-            verifier: UpForGrabs.into(),
-        }],
-        checker: AmoebaCreation.into(),
-    };
+    let spawn_tx: Transaction = TransactionBuilder::new(AmoebaCreation.into())
+        .output(eve, UpForGrabs)
+        .build();
 
     // Calculate the OutputRef which also serves as the storage location
     let eve_ref = OutputRef {
@@ -38,14 +36,9 @@ pub async fn amoeba_demo(client: &HttpClient) -> anyhow::Result<()> {
         index: 0,
     };
 
-    // Send the transaction
-    let spawn_hex = hex::encode(spawn_tx.encode());
-    let params = rpc_params![spawn_hex];
-    let spawn_response: Result<String, _> = client.request("author_submitExtrinsic", params).await;
-    println!("Node's response to spawn transaction: {:?}", spawn_response);
-
-    // Wait a few seconds to make sure a block has been authored.
-    sleep(Duration::from_secs(3));
+    // Send the transaction, and wait for it to be included in a block.
+    let spawn_status = node_submit_and_watch(endpoint, &spawn_tx, in_block).await?;
+    println!("Node's response to spawn transaction: {:?}", spawn_status);
 
     // Check that the amoeba is in storage and print its details
     let eve_from_storage: AmoebaDetails = fetch_storage::<OuterVerifier>(&eve_ref, client)
@@ -63,24 +56,11 @@ pub async fn amoeba_demo(client: &HttpClient) -> anyhow::Result<()> {
         generation: 1,
         four_bytes: *b"able",
     };
-    let mitosis_tx = Transaction {
-        inputs: vec![Input {
-            output_ref: eve_ref,
-            redeemer: Vec::new(),
-        }],
-        peeks: Vec::new(),
-        outputs: vec![
-            Output {
-                payload: cain.into(),
-                verifier: UpForGrabs.into(),
-            },
-            Output {
-                payload: able.into(),
-                verifier: UpForGrabs.into(),
-            },
-        ],
-        checker: AmoebaMitosis.into(),
-    };
+    let mitosis_tx: Transaction = TransactionBuilder::new(AmoebaMitosis.into())
+        .input(eve_ref, Vec::new())
+        .output(cain, UpForGrabs)
+        .output(able, UpForGrabs)
+        .build();
 
     // Calculate the two OutputRefs for the daughters
     let cain_ref = OutputRef {
@@ -92,19 +72,13 @@ pub async fn amoeba_demo(client: &HttpClient) -> anyhow::Result<()> {
         index: 1,
     };
 
-    // Send the mitosis transaction
-    let mitosis_hex = hex::encode(mitosis_tx.encode());
-    let params = rpc_params![mitosis_hex];
-    let mitosis_response: Result<String, _> =
-        client.request("author_submitExtrinsic", params).await;
+    // Send the mitosis transaction, and wait for it to be included in a block.
+    let mitosis_status = node_submit_and_watch(endpoint, &mitosis_tx, in_block).await?;
     println!(
         "Node's response to mitosis transaction: {:?}",
-        mitosis_response
+        mitosis_status
     );
 
-    // Wait a few seconds to make sure a block has been authored.
-    sleep(Duration::from_secs(3));
-
     // Check that the daughters are in storage and print their details
     let cain_from_storage: AmoebaDetails = fetch_storage::<OuterVerifier>(&cain_ref, client)
         .await?
@@ -0,0 +1,206 @@
+//! Encrypted backup and restore of the wallet.
+//!
+//! A backup contains everything that cannot be recovered by simply re-syncing with a node.
+//! That is the keystore, the address book, and the transactions that this wallet submitted but that have not
+//! yet landed in a block (whose inputs are locked). Everything else in the local database is
+//! rebuilt by the regular sync after a restore.
+//!
+//! ## File Format
+//!
+//! MAGIC (8 bytes) | salt (16 bytes) | nonce (12 bytes) | ciphertext
+//!
+//! The plaintext is a SCALE encoded [`WalletBackup`]. It is encrypted with ChaCha20-Poly1305
+//! using a key derived from the user's password with PBKDF2-HMAC-SHA256.
+
+use std::path::Path;
+
+use anyhow::anyhow;
+use chacha20poly1305::{aead::Aead, ChaCha20Poly1305, Key, KeyInit, Nonce};
+use parity_scale_codec::{Decode, Encode};
+use rand::RngCore;
+use runtime::Transaction;
+use sha2::Sha256;
+use sled::Db;
+use sp_core::H256;
+
+use crate::{address_book, sync};
+
+/// Identifies a file as a Tuxedo wallet backup, including the format version.
+const MAGIC: &[u8; 8] = b"TUXBAK01";
+
+/// Length of the random salt used in key derivation.
+const SALT_LEN: usize = 16;
+
+/// Length of the ChaCha20-Poly1305 nonce.
+const NONCE_LEN: usize = 12;
+
+/// Number of PBKDF2 rounds used to derive the encryption key from the password.
+const PBKDF2_ROUNDS: u32 = 100_000;
+
+/// The essential wallet state that is stored in a backup.
+#[derive(Debug, Encode, Decode)]
+struct WalletBackup {
+    /// The raw keystore files as (file name, contents) pairs.
+    keystore_files: Vec<(String, Vec<u8>)>,
+    /// Transactions this wallet submitted that were not yet in a block at backup time.
+    pending_transactions: Vec<Transaction>,
+    /// The labelled entries in the address book.
+    address_book: Vec<(String, H256)>,
+}
+
+/// Write an encrypted backup of the wallet to the given file.
+pub fn backup(db: &Db, keystore_path: &Path, file: &Path) -> anyhow::Result<()> {
+    let mut keystore_files = Vec::new();
+    for entry in std::fs::read_dir(keystore_path)? {
+        let entry = entry?;
+        if !entry.file_type()?.is_file() {
+            continue;
+        }
+        let name = entry
+            .file_name()
+            .into_string()
+            .map_err(|_| anyhow!("Keystore contains a file with a non-utf8 name"))?;
+        keystore_files.push((name, std::fs::read(entry.path())?));
+    }
+
+    let pending_transactions = sync::get_pending_transactions(db)?
+        .into_iter()
+        .map(|(_, tx)| tx)
+        .collect();
+
+    let backup = WalletBackup {
+        keystore_files,
+        pending_transactions,
+        address_book: address_book::list(db)?,
+    };
+
+    let password = read_password()?;
+    std::fs::write(file, encrypt(&backup.encode(), &password)?)?;
+
+    println!(
+        "Backed up {} keys and {} pending transactions to {}",
+        backup.keystore_files.len(),
+        backup.pending_transactions.len(),
+        file.to_string_lossy()
+    );
+
+    Ok(())
+}
+
+/// Restore the wallet from an encrypted backup file.
+///
+/// Keys are written into the keystore, overwriting any existing key with the same public key.
+/// Pending transactions are tracked again until the next sync reconciles them.
+pub fn restore(db: &Db, keystore_path: &Path, file: &Path) -> anyhow::Result<()> {
+    let password = read_password()?;
+    let plaintext = decrypt(&std::fs::read(file)?, &password)?;
+    let backup = WalletBackup::decode(&mut &plaintext[..])?;
+
+    std::fs::create_dir_all(keystore_path)?;
+    for (name, contents) in &backup.keystore_files {
+        // Be paranoid about path traversal in a file that came from elsewhere.
+        if Path::new(name).file_name() != Some(name.as_ref()) {
+            return Err(anyhow!(
+                "Backup contains an invalid keystore file name: {name}"
+            ));
+        }
+        std::fs::write(keystore_path.join(name), contents)?;
+    }
+
+    for tx in &backup.pending_transactions {
+        sync::add_pending_transaction(db, tx)?;
+    }
+
+    for (label, pubkey) in &backup.address_book {
+        address_book::add(db, label, *pubkey)?;
+    }
+
+    println!(
+        "Restored {} keys and {} pending transactions from {}",
+        backup.keystore_files.len(),
+        backup.pending_transactions.len(),
+        file.to_string_lossy()
+    );
+
+    Ok(())
+}
+
+/// Prompt the user for the backup password on stdin.
+fn read_password() -> anyhow::Result<String> {
+    println!("Enter the backup password:");
+    let mut password = String::new();
+    std::io::stdin().read_line(&mut password)?;
+
+    Ok(password.trim_end_matches(['\r', '\n']).to_string())
+}
+
+/// Derive a symmetric encryption key from a password and salt.
+fn derive_key(password: &str, salt: &[u8]) -> Key {
+    let mut key = Key::default();
+    pbkdf2::pbkdf2_hmac::<Sha256>(password.as_bytes(), salt, PBKDF2_ROUNDS, &mut key);
+    key
+}
+
+/// Encrypt the plaintext with the password producing the complete contents of a backup file.
+fn encrypt(plaintext: &[u8], password: &str) -> anyhow::Result<Vec<u8>> {
+    let mut salt = [0u8; SALT_LEN];
+    let mut nonce = [0u8; NONCE_LEN];
+    rand::thread_rng().fill_bytes(&mut salt);
+    rand::thread_rng().fill_bytes(&mut nonce);
+
+    let cipher = ChaCha20Poly1305::new(&derive_key(password, &salt));
+    let ciphertext = cipher
+        .encrypt(Nonce::from_slice(&nonce), plaintext)
+        .map_err(|_| anyhow!("Failed to encrypt wallet backup"))?;
+
+    let mut file_contents =
+        Vec::with_capacity(MAGIC.len() + SALT_LEN + NONCE_LEN + ciphertext.len());
+    file_contents.extend_from_slice(MAGIC);
+    file_contents.extend_from_slice(&salt);
+    file_contents.extend_from_slice(&nonce);
+    file_contents.extend_from_slice(&ciphertext);
+
+    Ok(file_contents)
+}
+
+/// Decrypt the complete contents of a backup file with the password.
+fn decrypt(file_contents: &[u8], password: &str) -> anyhow::Result<Vec<u8>> {
+    let header_len = MAGIC.len() + SALT_LEN + NONCE_LEN;
+    if file_contents.len() < header_len || !file_contents.starts_with(MAGIC) {
+        return Err(anyhow!("Not a Tuxedo wallet backup file"));
+    }
+
+    let salt = &file_contents[MAGIC.len()..MAGIC.len() + SALT_LEN];
+    let nonce = &file_contents[MAGIC.len() + SALT_LEN..header_len];
+    let ciphertext = &file_contents[header_len..];
+
+    let cipher = ChaCha20Poly1305::new(&derive_key(password, salt));
+    cipher
+        .decrypt(Nonce::from_slice(nonce), ciphertext)
+        .map_err(|_| anyhow!("Failed to decrypt wallet backup. Is the password correct?"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encrypt_decrypt_round_trip() {
+        let plaintext = b"some wallet state".to_vec();
+        let encrypted = encrypt(&plaintext, "hunter2").unwrap();
+
+        assert_eq!(decrypt(&encrypted, "hunter2").unwrap(), plaintext);
+    }
+
+    #[test]
+    fn decrypt_with_wrong_password_fails() {
+        let encrypted = encrypt(b"some wallet state", "hunter2").unwrap();
+
+        assert!(decrypt(&encrypted, "hunter3").is_err());
+    }
+
+    #[test]
+    fn decrypt_garbage_fails() {
+        assert!(decrypt(b"definitely not a backup", "hunter2").is_err());
+    }
+}
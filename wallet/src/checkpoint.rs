@@ -0,0 +1,193 @@
+//! Signed state checkpoints for fast wallet sync.
+//!
+//! Syncing from genesis requires replaying every block. A checkpoint instead captures the
+//! best block and the relevant UTXOs at some height so that a fresh wallet can start from
+//! there. Checkpoints are signed by a key that the user trusts, and the block hash they
+//! claim is always checked against the node before the checkpoint is used.
+
+use std::path::Path;
+
+use anyhow::anyhow;
+use jsonrpsee::http_client::HttpClient;
+use parity_scale_codec::{Decode, Encode};
+use runtime::{OuterVerifier, Output};
+use sc_keystore::LocalKeystore;
+use sled::Db;
+use sp_core::{
+    sr25519::{Pair, Public, Signature},
+    Pair as PairT, H256,
+};
+use tuxedo_core::types::OutputRef;
+
+use crate::{indexer, rpc, sync};
+
+/// A snapshot of the UTXOs relevant to some keys as of a particular block.
+#[derive(Debug, Encode, Decode)]
+pub struct Checkpoint {
+    /// The height of the block the snapshot was taken at.
+    pub height: u32,
+    /// The hash of the block the snapshot was taken at.
+    pub block_hash: H256,
+    /// The unspent outputs as of the end of that block.
+    pub outputs: Vec<(OutputRef, Output)>,
+}
+
+/// A checkpoint along with the key that signed it and the signature.
+/// This is what is written to checkpoint files.
+#[derive(Debug, Encode, Decode)]
+pub struct SignedCheckpoint {
+    pub checkpoint: Checkpoint,
+    pub signer: H256,
+    pub signature: Signature,
+}
+
+impl SignedCheckpoint {
+    /// Read a signed checkpoint from a file and make sure it is signed by the expected key.
+    pub fn read_and_verify(file: &Path, expected_signer: &H256) -> anyhow::Result<Checkpoint> {
+        let bytes = std::fs::read(file)?;
+        let signed = Self::decode(&mut &bytes[..])?;
+
+        if &signed.signer != expected_signer {
+            return Err(anyhow!(
+                "Checkpoint is signed by {:?}, not the trusted signer {expected_signer:?}",
+                signed.signer
+            ));
+        }
+
+        let public = Public::from_h256(signed.signer);
+        if !Pair::verify(&signed.signature, signed.checkpoint.encode(), &public) {
+            return Err(anyhow!("Checkpoint signature is invalid"));
+        }
+
+        Ok(signed.checkpoint)
+    }
+
+    /// Write the signed checkpoint to a file.
+    ///
+    /// The checkpoint is written to a temporary file first and then moved into place,
+    /// so the file always holds a complete checkpoint even if the wallet is interrupted.
+    pub fn write(&self, file: &Path) -> anyhow::Result<()> {
+        let tmp = file.with_extension("tmp");
+        std::fs::write(&tmp, self.encode())?;
+        std::fs::rename(&tmp, file)?;
+
+        Ok(())
+    }
+}
+
+/// Initialize a fresh database from a checkpoint instead of from genesis.
+///
+/// The checkpoint's block hash must match the hash the node reports at the same height.
+pub async fn initialize_from_checkpoint<F: Fn(&OuterVerifier) -> bool>(
+    db: &Db,
+    client: &HttpClient,
+    checkpoint: Checkpoint,
+    filter: &F,
+    indexers: &[Box<dyn indexer::OutputIndexer>],
+    max_in_flight: u32,
+) -> anyhow::Result<()> {
+    let node_hash = rpc::node_get_block_hash(checkpoint.height, client).await?;
+    if node_hash != Some(checkpoint.block_hash) {
+        return Err(anyhow!(
+            "Checkpoint block {:?} at height {} does not match the node's block {node_hash:?}",
+            checkpoint.block_hash,
+            checkpoint.height
+        ));
+    }
+
+    sync::initialize_at_height(
+        db,
+        client,
+        checkpoint.height,
+        checkpoint.block_hash,
+        max_in_flight,
+    )
+    .await?;
+
+    for (output_ref, output) in checkpoint.outputs {
+        if !filter(&output.verifier) {
+            continue;
+        }
+        for indexer in indexers {
+            if indexer.index(db, &output_ref, &output, checkpoint.height)? {
+                break;
+            }
+        }
+    }
+
+    log::info!(
+        "Initialized wallet from checkpoint at height {}",
+        checkpoint.height
+    );
+
+    Ok(())
+}
+
+/// Create a checkpoint of the wallet's current best block and unspent outputs,
+/// sign it with a key from the keystore, and write it to a file.
+pub async fn export_checkpoint(
+    db: &Db,
+    client: &HttpClient,
+    keystore: &LocalKeystore,
+    signer: H256,
+    file: &Path,
+) -> anyhow::Result<()> {
+    let signed = create_checkpoint(db, client, keystore, signer).await?;
+    signed.write(file)?;
+
+    println!(
+        "Wrote checkpoint of {} outputs at height {} to {}",
+        signed.checkpoint.outputs.len(),
+        signed.checkpoint.height,
+        file.to_string_lossy()
+    );
+
+    Ok(())
+}
+
+/// Create a checkpoint of the wallet's current best block and unspent outputs,
+/// and sign it with a key from the keystore.
+///
+/// The outputs themselves are fetched from the node's storage, so the wallet should be
+/// synced with the node when this is called.
+pub async fn create_checkpoint(
+    db: &Db,
+    client: &HttpClient,
+    keystore: &LocalKeystore,
+    signer: H256,
+) -> anyhow::Result<SignedCheckpoint> {
+    let height = sync::height(db)?.ok_or(anyhow!("Cannot checkpoint an uninitialized database"))?;
+    let block_hash = sync::get_block_hash(db, height)?.ok_or(anyhow!(
+        "No block hash at the best height. DB is inconsistent."
+    ))?;
+
+    let output_refs = sync::get_unspent_outputs(db)?
+        .into_iter()
+        .map(|(output_ref, ..)| output_ref)
+        .chain(
+            indexer::get_kitties(db)?
+                .into_iter()
+                .map(|(output_ref, ..)| output_ref),
+        );
+
+    let mut outputs = Vec::new();
+    for output_ref in output_refs {
+        let output = rpc::fetch_storage::<OuterVerifier>(&output_ref, client).await?;
+        outputs.push((output_ref, output));
+    }
+
+    let checkpoint = Checkpoint {
+        height,
+        block_hash,
+        outputs,
+    };
+
+    let signature =
+        crate::keystore::sign_with(keystore, &Public::from_h256(signer), &checkpoint.encode())?;
+
+    Ok(SignedCheckpoint {
+        checkpoint,
+        signer,
+        signature: Signature::decode(&mut &signature[..])?,
+    })
+}
@@ -4,19 +4,24 @@
 
 use std::path::PathBuf;
 
-use clap::{ArgAction::Append, Args, Parser, Subcommand};
+use clap::{ArgAction::Append, Args, Parser, Subcommand, ValueEnum};
 use sp_core::H256;
 use tuxedo_core::types::OutputRef;
 
-use crate::{h256_from_string, keystore::SHAWN_PUB_KEY, output_ref_from_string, DEFAULT_ENDPOINT};
+use crate::{
+    h256_from_string, keystore::SHAWN_PUB_KEY, output_ref_from_string, DEFAULT_ENDPOINT,
+    DEFAULT_SYNC_CONCURRENCY,
+};
 
 /// The wallet's main CLI struct
 #[derive(Debug, Parser)]
 #[command(about, version)]
 pub struct Cli {
-    #[arg(long, short, default_value_t = DEFAULT_ENDPOINT.to_string())]
-    /// RPC endpoint of the node that this wallet will connect to.
-    pub endpoint: String,
+    #[arg(long, short, verbatim_doc_comment, action = Append, default_values_t = [DEFAULT_ENDPOINT.to_string()])]
+    /// RPC endpoint of a node that this wallet will connect to.
+    /// Repeat it to give several nodes. The wallet syncs with the one that has finalized the most
+    /// blocks, fails over to the others when it stops responding, and checks that they all agree.
+    pub endpoint: Vec<String>,
 
     #[arg(long, short)]
     /// Path where the wallet data is stored. Default value is platform specific.
@@ -27,6 +32,31 @@ pub struct Cli {
     /// The wallet will use the latest data it had previously synced.
     pub no_sync: bool,
 
+    #[arg(long, default_value_t = DEFAULT_SYNC_CONCURRENCY)]
+    /// Maximum number of blocks to fetch from the node concurrently while syncing.
+    pub sync_concurrency: u32,
+
+    #[arg(long, verbatim_doc_comment)]
+    /// Initialize a fresh wallet from a signed checkpoint file instead of syncing from genesis.
+    /// The checkpoint's block is checked against the node. Ignored if the database already exists.
+    pub checkpoint: Option<PathBuf>,
+
+    #[arg(long, value_parser = h256_from_string)]
+    /// The public key that checkpoints must be signed by to be trusted.
+    pub checkpoint_signer: Option<H256>,
+
+    #[arg(long, verbatim_doc_comment, default_value_t = 1)]
+    /// Minimum number of confirmations an output needs before it is considered spendable.
+    /// Outputs with fewer confirmations are reported as pending and never selected for spending.
+    /// An output in the best block has one confirmation.
+    pub min_confirmations: u32,
+
+    #[arg(long, verbatim_doc_comment)]
+    /// Only keep this many of the most recent blocks in the local database.
+    /// Older block bodies and spent output records are discarded after each sync.
+    /// Re-orgs deeper than this depth cannot be handled. By default nothing is pruned.
+    pub prune: Option<u32>,
+
     #[arg(long)]
     /// A temporary directory will be created to store the configuration and will be deleted at the end of the process.
     /// path will be ignored if this is set.
@@ -37,10 +67,30 @@ pub struct Cli {
     /// The keystore will contain the development key Shawn.
     pub dev: bool,
 
+    #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+    /// How command results are printed. Logs are always written to stderr.
+    pub output: OutputFormat,
+
     #[command(subcommand)]
     pub command: Option<Command>,
 }
 
+/// The formats in which the wallet can print command results
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    /// Human readable text.
+    Text,
+    /// Structured JSON, suitable for scripts and other tools.
+    Json,
+}
+
+/// The formats in which the wallet can export transaction history
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ExportFormat {
+    /// Comma separated values, with a header row.
+    Csv,
+}
+
 /// The tasks supported by the wallet
 #[derive(Debug, Subcommand)]
 pub enum Command {
@@ -98,6 +148,133 @@ pub enum Command {
 
     /// Show the complete list of UTXOs known to the wallet.
     ShowAllOutputs,
+
+    /// Manage the address book of labelled public keys.
+    /// Labels can be used in place of public keys when specifying recipients.
+    #[command(subcommand, verbatim_doc_comment)]
+    Address(AddressCommand),
+
+    /// Suggest a tip for spending coins based on the transactions in the node's pool.
+    EstimateTip,
+
+    /// Write a checkpoint of the wallet's best block and unspent outputs to a file,
+    /// signed by a key in the keystore. Other wallets can start from it with --checkpoint.
+    #[command(verbatim_doc_comment)]
+    ExportCheckpoint {
+        /// Path of the checkpoint file to create.
+        file: PathBuf,
+        /// Public key of the keystore key that signs the checkpoint.
+        #[arg(long, short, value_parser = h256_from_string, default_value = SHAWN_PUB_KEY)]
+        signer: H256,
+    },
+
+    /// Write a password-encrypted backup of the keystore and essential wallet state to a file.
+    /// Synced chain data is not included; it is recovered by syncing after a restore.
+    #[command(verbatim_doc_comment)]
+    Backup {
+        /// Path of the backup file to create.
+        file: PathBuf,
+    },
+
+    /// Restore the keystore and essential wallet state from an encrypted backup file.
+    Restore {
+        /// Path of the backup file to restore from.
+        file: PathBuf,
+    },
+
+    /// Create, sign, combine, and finalize partially signed transactions (PSTTs),
+    /// which let several wallets sign a transaction together.
+    #[command(subcommand, verbatim_doc_comment)]
+    Pstt(PsttCommand),
+
+    /// Mix coins with other wallets in a CoinJoin, either through a coordinator or as one.
+    /// The mixed output goes to a fresh key in the keystore unless a recipient is given.
+    #[command(verbatim_doc_comment)]
+    Coinjoin(CoinjoinArgs),
+
+    /// Decode a transaction, show what it spends and creates, and dry run it against the node.
+    /// When it would fail, report which verifier or constraint checker fails it.
+    #[command(verbatim_doc_comment)]
+    VerifyTx(VerifyTxArgs),
+
+    /// Synchronize the wallet with the node and report the height it reached.
+    /// With --follow, keep it synchronized as new blocks arrive until interrupted.
+    #[command(verbatim_doc_comment)]
+    Sync(SyncArgs),
+
+    /// Export the transaction history of a key, as found in the blocks in the local database.
+    /// Blocks that have been pruned cannot be exported.
+    #[command(verbatim_doc_comment)]
+    Export(ExportArgs),
+
+    /// Open an interactive terminal interface that keeps the wallet synced with the node
+    /// while showing balances and recent transactions, and guides you through sending coins.
+    /// The --no-sync flag is ignored, since the interface syncs continuously.
+    #[command(verbatim_doc_comment)]
+    Tui,
+}
+
+/// The partially signed transaction operations
+#[derive(Debug, Subcommand)]
+pub enum PsttCommand {
+    /// Create a PSTT that spends the given coins, with its inputs resolved from the node.
+    Create(CreatePsttArgs),
+
+    /// Resolve the inputs of a PSTT that are not resolved yet, from the node's storage.
+    Update {
+        /// Path of the PSTT file, which is updated in place.
+        file: PathBuf,
+    },
+
+    /// Sign every input of a PSTT that a key in the keystore is a signer of.
+    Sign {
+        /// Path of the PSTT file, which is updated in place.
+        file: PathBuf,
+    },
+
+    /// Combine the signatures in several copies of the same PSTT into one.
+    Combine {
+        /// Paths of the PSTT files to combine.
+        #[arg(required = true)]
+        files: Vec<PathBuf>,
+        /// Path of the combined PSTT file to create.
+        #[arg(long)]
+        into: PathBuf,
+    },
+
+    /// Show what a PSTT spends and creates, and who has signed it so far.
+    Show {
+        /// Path of the PSTT file.
+        file: PathBuf,
+    },
+
+    /// Assemble the signatures of a PSTT into redeemers, and submit the transaction.
+    Finalize {
+        /// Path of the PSTT file.
+        file: PathBuf,
+    },
+}
+
+/// The address book operations
+#[derive(Debug, Subcommand)]
+pub enum AddressCommand {
+    /// Add a labelled public key to the address book, replacing any entry with the same label.
+    Add {
+        /// The label to refer to this address by.
+        label: String,
+        /// The hex encoded public key.
+        #[arg(value_parser = h256_from_string)]
+        pub_key: H256,
+    },
+
+    /// Remove a labelled public key from the address book.
+    Remove {
+        /// The label of the entry to remove.
+        label: String,
+    },
+
+    /// Show all the entries in the address book.
+    List,
 }
 
 #[derive(Debug, Args)]
@@ -114,13 +291,154 @@ pub struct SpendArgs {
 
     // https://docs.rs/clap/latest/clap/_derive/_cookbook/typed_derive/index.html
     // shows how to specify a custom parsing function
-    /// Hex encoded address (sr25519 pubkey) of the recipient.
-    #[arg(long, short, verbatim_doc_comment, value_parser = h256_from_string, default_value = SHAWN_PUB_KEY)]
-    pub recipient: H256,
+    /// Hex encoded address (sr25519 pubkey) of the recipient, its label in the address book,
+    /// or a name registered with the name service.
+    #[arg(long, short, verbatim_doc_comment, default_value = SHAWN_PUB_KEY)]
+    pub recipient: String,
 
     // The `action = Append` allows us to accept the same value multiple times.
     /// An output amount. For the transaction to be valid, the outputs must add up to less than the sum of the inputs.
     /// The wallet will not enforce this and will gladly send an invalid which will then be rejected by the node.
     #[arg(long, short, verbatim_doc_comment, action = Append)]
     pub output_amount: Vec<u128>,
+
+    /// The amount to burn as a tip to prioritize this transaction. Any other input value
+    /// is returned as a change output to the owner of the first input.
+    /// When omitted, no change output is created and the entire surplus is burned.
+    /// See the `estimate-tip` command for a suggested value.
+    #[arg(long, verbatim_doc_comment)]
+    pub tip: Option<u128>,
+
+    /// A memo to attach to the transaction, such as a payment reference.
+    /// It is signed along with the rest of the transaction, and shown in the exported history.
+    #[arg(long, verbatim_doc_comment)]
+    pub memo: Option<String>,
+
+    /// Follow the transaction until it is finalized or dropped, reporting each status.
+    /// By default the wallet only waits until the transaction enters the pool.
+    #[arg(long, verbatim_doc_comment)]
+    pub watch: bool,
+}
+
+#[derive(Debug, Args)]
+pub struct CreatePsttArgs {
+    /// Path of the PSTT file to create.
+    pub file: PathBuf,
+
+    /// An input to be consumed by this transaction. This argument may be specified multiple times.
+    /// They must all be coins, but they do not need to be owned by keys in this wallet.
+    #[arg(long, short, required = true, verbatim_doc_comment, value_parser = output_ref_from_string)]
+    pub input: Vec<OutputRef>,
+
+    /// Hex encoded address (sr25519 pubkey) of the recipient, its label in the address book,
+    /// or a name registered with the name service.
+    #[arg(long, short, verbatim_doc_comment, default_value = SHAWN_PUB_KEY)]
+    pub recipient: String,
+
+    /// An output amount. The outputs must add up to less than the sum of the inputs,
+    /// and the difference is a tip for the block author.
+    #[arg(long, short, verbatim_doc_comment, action = Append)]
+    pub output_amount: Vec<u128>,
+}
+
+#[derive(Debug, Args)]
+pub struct CoinjoinArgs {
+    /// Address of the coordinator to take part through, such as 127.0.0.1:9955.
+    #[arg(
+        long,
+        required_unless_present = "coordinate",
+        conflicts_with = "coordinate"
+    )]
+    pub coordinator: Option<String>,
+
+    /// Coordinate a CoinJoin instead, listening for participants on this address.
+    #[arg(long)]
+    pub coordinate: Option<String>,
+
+    /// The number of participants that the coordinator waits for.
+    /// It includes the coordinator itself when it takes part with some inputs.
+    #[arg(long, verbatim_doc_comment, default_value_t = 3)]
+    pub participants: usize,
+
+    /// The value of every mixed output. All participants must use the same one.
+    #[arg(long, short)]
+    pub denomination: u128,
+
+    /// A coin to mix. This argument may be specified multiple times.
+    /// They must all be owned by the same key in this wallet, which receives any change.
+    /// A coordinator that does not take part itself needs none.
+    #[arg(long, short, verbatim_doc_comment, value_parser = output_ref_from_string)]
+    pub input: Vec<OutputRef>,
+
+    /// Hex encoded address (sr25519 pubkey) of the mixed output's recipient, its label in the
+    /// address book, or a name registered with the name service.
+    /// A fresh key is generated when omitted.
+    #[arg(long, short, verbatim_doc_comment)]
+    pub recipient: Option<String>,
+
+    /// This wallet's share of the fee, which goes to the block author.
+    #[arg(long, default_value_t = 0)]
+    pub fee: u128,
+}
+
+#[derive(Debug, Args)]
+pub struct VerifyTxArgs {
+    /// The hex encoded transaction.
+    #[arg(required_unless_present = "file", conflicts_with = "file")]
+    pub transaction: Option<String>,
+
+    /// Read the transaction from this file instead, either encoded or hex encoded.
+    #[arg(long)]
+    pub file: Option<PathBuf>,
+}
+
+#[derive(Debug, Args)]
+pub struct SyncArgs {
+    /// Keep syncing new blocks as the node imports them, until interrupted with Ctrl-C.
+    #[arg(long)]
+    pub follow: bool,
+
+    /// Seconds to wait between checks for new blocks while following.
+    #[arg(long, default_value_t = 6)]
+    pub interval: u64,
+
+    /// Seconds between flushes of the database to disk while following.
+    #[arg(long, default_value_t = 30)]
+    pub flush_interval: u64,
+
+    /// While following, keep a signed checkpoint of the wallet in this file.
+    /// A fresh wallet can start from it with --checkpoint instead of syncing from genesis.
+    #[arg(long, verbatim_doc_comment)]
+    pub checkpoint_file: Option<PathBuf>,
+
+    /// Number of blocks to sync between rewrites of the checkpoint file.
+    #[arg(long, default_value_t = 100)]
+    pub checkpoint_every: u32,
+
+    /// Public key of the keystore key that signs the checkpoints.
+    #[arg(long, short, value_parser = h256_from_string, default_value = SHAWN_PUB_KEY)]
+    pub signer: H256,
+}
+
+#[derive(Debug, Args)]
+pub struct ExportArgs {
+    /// The format of the exported history.
+    #[arg(long, value_enum, default_value_t = ExportFormat::Csv)]
+    pub format: ExportFormat,
+
+    /// Public key whose history is exported. Its coins must be tracked by this wallet.
+    #[arg(long, short, value_parser = h256_from_string, default_value = SHAWN_PUB_KEY)]
+    pub key: H256,
+
+    /// The first block of the range to export.
+    #[arg(long, default_value_t = 0)]
+    pub from: u32,
+
+    /// The last block of the range to export. Defaults to the wallet's best block.
+    #[arg(long)]
+    pub to: Option<u32>,
+
+    /// Path of the file to write. The history is printed to stdout when omitted.
+    #[arg(long)]
+    pub file: Option<PathBuf>,
 }
@@ -0,0 +1,468 @@
+//! Wallet features related to CoinJoins, which mix the coins of several wallets in one transaction.
+//!
+//! One wallet coordinates the CoinJoin. It listens for participants, each of which sends a PSTT
+//! with its own inputs, its mixed output, and its change. The coordinator joins these parts into
+//! one PSTT, shuffles its inputs and outputs, and sends it back to every participant. Each
+//! participant checks that the joined transaction still pays it and spends none of its other
+//! coins, signs its own inputs, and returns the PSTT. Once every participant has signed, the
+//! coordinator finalizes and submits the transaction, and sends it to the participants so they can
+//! track it. The coordinator may take part with its own coins as well.
+//!
+//! Messages are exchanged over plain TCP, one hex-encoded message per line. The coordinator learns
+//! which mixed output belongs to which participant, so it must be trusted not to reveal that.
+
+use crate::{
+    cli::{CoinjoinArgs, OutputFormat},
+    pstt::{self, Pstt},
+    rpc::{node_get_signing_domain, node_submit_and_watch},
+    sync,
+};
+
+use anyhow::anyhow;
+use jsonrpsee::http_client::HttpClient;
+use parity_scale_codec::{Decode, Encode};
+use rand::seq::SliceRandom;
+use runtime::{
+    coinjoin::CoinJoin, money::Coin, OuterConstraintChecker, OuterVerifier, Transaction,
+};
+use sc_keystore::LocalKeystore;
+use sled::Db;
+use sp_core::H256;
+use sp_runtime::traits::{BlakeTwo256, Hash};
+use std::time::Duration;
+use tokio::{
+    io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader},
+    net::{TcpListener, TcpStream},
+};
+use tuxedo_core::{
+    transaction_builder::TransactionBuilder,
+    types::{OutputRef, TransactionStatus},
+    verifier::SigCheck,
+};
+
+/// The longest line that either side reads, so a misbehaving peer can not exhaust our memory.
+const MAX_MESSAGE_LENGTH: u64 = 1 << 20;
+
+/// How long the coordinator waits for every participant to sign the joined PSTT.
+const SIGNING_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// Take part in a CoinJoin through a coordinator, or coordinate one, and print the coins it
+/// creates for this wallet.
+pub async fn coinjoin(
+    db: &Db,
+    client: &HttpClient,
+    endpoint: &str,
+    keystore: &LocalKeystore,
+    args: CoinjoinArgs,
+    min_confirmations: u32,
+    format: OutputFormat,
+) -> anyhow::Result<()> {
+    // Build this wallet's part first, so that bad arguments fail before any connection is made.
+    let part = match args.input.is_empty() {
+        true => None,
+        false => Some(build_part(db, client, keystore, &args, min_confirmations).await?),
+    };
+
+    let transaction = match (&args.coordinate, &args.coordinator, &part) {
+        (Some(listen), _, _) => {
+            coordinate(db, client, endpoint, keystore, listen, &args, part.as_ref()).await?
+        }
+        (None, Some(coordinator), Some(part)) => {
+            participate(db, keystore, coordinator, part.clone()).await?
+        }
+        (None, Some(_), None) => Err(anyhow!("a participant needs at least one input"))?,
+        (None, None, _) => Err(anyhow!("either a coordinator or --coordinate is required"))?,
+    };
+    let tx_hash = <BlakeTwo256 as Hash>::hash_of(&transaction.encode());
+
+    // Only report the outputs of this wallet's part, since it doesn't know whom the others pay.
+    let mut created = Vec::new();
+    if let Some(part) = &part {
+        for (output_ref, amount, verifier) in own_outputs(part, &transaction, tx_hash) {
+            if format == OutputFormat::Json {
+                created.push(serde_json::json!({
+                    "output_ref": hex::encode(output_ref.encode()),
+                    "amount": amount,
+                    "verifier": verifier,
+                }));
+            } else {
+                print!(
+                    "Created {:?} worth {amount}. ",
+                    hex::encode(output_ref.encode())
+                );
+                crate::pretty_print_verifier(&verifier);
+            }
+        }
+    }
+
+    if format == OutputFormat::Json {
+        crate::print_json(serde_json::json!({
+            "tx_hash": tx_hash,
+            "inputs": transaction.inputs.len(),
+            "outputs": created,
+        }));
+    } else {
+        println!(
+            "Submitted CoinJoin {tx_hash:?} with {} inputs",
+            transaction.inputs.len()
+        );
+    }
+
+    Ok(())
+}
+
+/// Build this wallet's part of a CoinJoin, with its inputs resolved from the node.
+///
+/// All the inputs must be owned by the same key, because the CoinJoin piece tells participants
+/// apart by the owners of their inputs. They must cover the denomination and the fee, and anything
+/// left over comes back to that key as change.
+async fn build_part(
+    db: &Db,
+    client: &HttpClient,
+    keystore: &LocalKeystore,
+    args: &CoinjoinArgs,
+    min_confirmations: u32,
+) -> anyhow::Result<Pstt> {
+    let best_height = sync::height(db)?.unwrap_or_default();
+
+    let mut owner = None;
+    let mut total_input_amount: u128 = 0;
+    for output_ref in &args.input {
+        let (owner_pubkey, amount, creation_height, asset_id) = sync::get_unspent(db, output_ref)?
+            .ok_or(anyhow!(
+                "user-specified output ref not found in local database"
+            ))?;
+        if asset_id != 0 {
+            return Err(anyhow!(
+                "user-specified output ref is not a coin of asset 0"
+            ));
+        }
+        if *owner.get_or_insert(owner_pubkey) != owner_pubkey {
+            return Err(anyhow!(
+                "all the inputs of a CoinJoin participant must be owned by the same key"
+            ));
+        }
+        if !sync::is_confirmed(creation_height, best_height, min_confirmations) {
+            return Err(anyhow!("user-specified output ref is not confirmed yet"));
+        }
+        if sync::is_in_flight(db, output_ref)? {
+            return Err(anyhow!(
+                "user-specified output ref is already spent by a pending transaction"
+            ));
+        }
+        total_input_amount = total_input_amount
+            .checked_add(amount)
+            .ok_or(anyhow!("the inputs are worth too much"))?;
+    }
+    let owner = owner.ok_or(anyhow!("a participant needs at least one input"))?;
+
+    let change = total_input_amount
+        .checked_sub(args.denomination.saturating_add(args.fee))
+        .ok_or(anyhow!(
+            "the inputs are worth {total_input_amount}, which does not cover the denomination and the fee"
+        ))?;
+
+    // A mixed output that goes back to the owner of the inputs would be counted as change.
+    let recipient = match &args.recipient {
+        Some(recipient) => crate::address_book::resolve(db, recipient)?,
+        None => crate::keystore::generate_fresh_key(keystore)?,
+    };
+    if recipient == owner {
+        return Err(anyhow!(
+            "the mixed output must not go to the key that owns the inputs"
+        ));
+    }
+
+    let mut builder = TransactionBuilder::<OuterVerifier, _>::new(
+        OuterConstraintChecker::CoinJoin(CoinJoin::new(args.denomination)),
+    );
+    for output_ref in &args.input {
+        builder = builder.input(output_ref.clone(), Vec::new());
+    }
+    builder = builder.output(Coin::<0>::new(args.denomination), SigCheck::new(recipient));
+    if change > 0 {
+        builder = builder.output(Coin::<0>::new(change), SigCheck::new(owner));
+    }
+
+    let domain = node_get_signing_domain(client).await?;
+    let mut part = Pstt::new(builder.build(), domain);
+    pstt::resolve_inputs(&mut part, client).await?;
+
+    Ok(part)
+}
+
+/// Take part in the CoinJoin of the coordinator at the given address, and return the transaction
+/// that the coordinator submitted.
+async fn participate(
+    db: &Db,
+    keystore: &LocalKeystore,
+    coordinator: &str,
+    part: Pstt,
+) -> anyhow::Result<Transaction> {
+    let mut connection = Connection(BufReader::new(TcpStream::connect(coordinator).await?));
+    connection.send(&part.to_bytes()).await?;
+    println!(
+        "Registered with the coordinator at {coordinator}, waiting for the other participants"
+    );
+
+    let mut joined = Pstt::from_bytes(&connection.receive().await?)
+        .map_err(|e| anyhow!("the coordinator sent an invalid PSTT: {e:?}"))?;
+    check_joined(db, &part, &joined)?;
+    let unsigned = joined.transaction.clone();
+
+    let signatures = pstt::sign_inputs(keystore, &mut joined)?;
+    connection.send(&joined.to_bytes()).await?;
+    log::info!("Sent {signatures} signatures to the coordinator");
+
+    // The coordinator only sends the transaction back once the node accepted it.
+    let transaction = Transaction::decode(&mut &connection.receive().await?[..])?;
+    let mut stripped = transaction.clone();
+    for input in &mut stripped.inputs {
+        input.redeemer = Vec::new();
+    }
+    if stripped.encode() != unsigned.encode() {
+        return Err(anyhow!(
+            "the coordinator submitted a different transaction than the one this wallet signed"
+        ));
+    }
+
+    // Remember the transaction so its inputs aren't double spent before it lands in a block.
+    sync::add_pending_transaction(db, &transaction)?;
+    Ok(transaction)
+}
+
+/// Make sure that the joined PSTT that a coordinator sent is safe for this wallet to sign.
+///
+/// The signature covers the whole transaction, so it would be valid for any other input of the
+/// same key too. Therefore the joined PSTT must not spend any coin of this wallet beyond the part.
+fn check_joined(db: &Db, part: &Pstt, joined: &Pstt) -> anyhow::Result<()> {
+    if joined.domain != part.domain
+        || joined.transaction.version != part.transaction.version
+        || joined.transaction.memo != part.transaction.memo
+        || joined.transaction.valid_until != part.transaction.valid_until
+        || joined.transaction.checker.encode() != part.transaction.checker.encode()
+    {
+        return Err(anyhow!(
+            "the coordinator changed the CoinJoin itself, not just joined it"
+        ));
+    }
+    if !joined.transaction.peeks.is_empty() {
+        return Err(anyhow!("the coordinator added peeks to the CoinJoin"));
+    }
+
+    for input in &joined.transaction.inputs {
+        let is_ours = part
+            .transaction
+            .inputs
+            .iter()
+            .any(|own| own.output_ref == input.output_ref);
+        if !is_ours && sync::get_unspent(db, &input.output_ref)?.is_some() {
+            return Err(anyhow!(
+                "the coordinator added another coin of this wallet to the CoinJoin"
+            ));
+        }
+    }
+    for own in &part.transaction.inputs {
+        if !joined
+            .transaction
+            .inputs
+            .iter()
+            .any(|input| input.output_ref == own.output_ref)
+        {
+            return Err(anyhow!("the coordinator left out an input of this wallet"));
+        }
+    }
+
+    // Each of our outputs must be there, as many times as we created it.
+    let mut outputs = joined.transaction.outputs.clone();
+    for own in &part.transaction.outputs {
+        let index = outputs
+            .iter()
+            .position(|output| output == own)
+            .ok_or(anyhow!("the coordinator left out an output of this wallet"))?;
+        outputs.swap_remove(index);
+    }
+
+    Ok(())
+}
+
+/// Coordinate a CoinJoin, listening for participants on the given address, and return the
+/// transaction once the node accepted it.
+///
+/// When this wallet takes part as well, its part counts towards the number of participants.
+async fn coordinate(
+    db: &Db,
+    client: &HttpClient,
+    endpoint: &str,
+    keystore: &LocalKeystore,
+    listen: &str,
+    args: &CoinjoinArgs,
+    part: Option<&Pstt>,
+) -> anyhow::Result<Transaction> {
+    let domain = node_get_signing_domain(client).await?;
+    let mut joined = Pstt::new(
+        TransactionBuilder::<OuterVerifier, _>::new(OuterConstraintChecker::CoinJoin(
+            CoinJoin::new(args.denomination),
+        ))
+        .build(),
+        domain,
+    );
+    if let Some(part) = part {
+        add_part(&mut joined, part.clone())?;
+    }
+
+    let listener = TcpListener::bind(listen).await?;
+    println!(
+        "Coordinating a CoinJoin of {} on {listen}, waiting for {} participants",
+        args.denomination, args.participants
+    );
+
+    let mut connections = Vec::new();
+    while connections.len() + usize::from(part.is_some()) < args.participants {
+        let (stream, peer) = listener.accept().await?;
+        let mut connection = Connection(BufReader::new(stream));
+        let registered = connection.receive().await.and_then(|bytes| {
+            let part = Pstt::from_bytes(&bytes).map_err(|e| anyhow!("invalid PSTT: {e:?}"))?;
+            add_part(&mut joined, part)
+        });
+        match registered {
+            Ok(()) => {
+                log::info!("{peer} registered for the CoinJoin");
+                connections.push(connection);
+            }
+            Err(e) => log::warn!("Rejected the part that {peer} sent: {e}"),
+        }
+    }
+
+    // Shuffle the joined PSTT, so its order does not reveal which outputs belong to which inputs.
+    let mut rng = rand::thread_rng();
+    let mut inputs: Vec<_> = joined
+        .transaction
+        .inputs
+        .drain(..)
+        .zip(joined.inputs.drain(..))
+        .collect();
+    inputs.shuffle(&mut rng);
+    (joined.transaction.inputs, joined.inputs) = inputs.into_iter().unzip();
+    joined.transaction.outputs.shuffle(&mut rng);
+
+    // Let every participant sign at the same time, and then collect their signatures.
+    for connection in &mut connections {
+        connection.send(&joined.to_bytes()).await?;
+    }
+    let mut signed = joined.clone();
+    for connection in &mut connections {
+        let bytes = tokio::time::timeout(SIGNING_TIMEOUT, connection.receive())
+            .await
+            .map_err(|_| anyhow!("a participant did not sign the CoinJoin in time"))??;
+        let theirs = Pstt::from_bytes(&bytes)
+            .map_err(|e| anyhow!("a participant sent an invalid PSTT: {e:?}"))?;
+        signed
+            .combine(theirs)
+            .map_err(|e| anyhow!("a participant signed a different CoinJoin: {e:?}"))?;
+    }
+    if part.is_some() {
+        pstt::sign_inputs(keystore, &mut signed)?;
+    }
+
+    let transaction = signed
+        .finalize()
+        .map_err(|e| anyhow!("the CoinJoin is still missing signatures: {e:?}"))?;
+    let status = node_submit_and_watch(endpoint, &transaction, |_| true).await;
+    log::info!("Node's response to the CoinJoin: {:?}", status);
+    if !matches!(&status, Ok(status) if !matches!(status, TransactionStatus::Dropped { .. })) {
+        return Err(anyhow!("the node did not accept the CoinJoin"));
+    }
+
+    if part.is_some() {
+        sync::add_pending_transaction(db, &transaction)?;
+    }
+    for connection in &mut connections {
+        if let Err(e) = connection.send(&transaction.encode()).await {
+            log::warn!("Could not send the submitted CoinJoin to a participant: {e}");
+        }
+    }
+
+    Ok(transaction)
+}
+
+/// Join a participant's part into the CoinJoin, unless it spends an input that is already in it.
+fn add_part(joined: &mut Pstt, part: Pstt) -> anyhow::Result<()> {
+    if part.transaction.inputs.iter().any(|input| {
+        joined
+            .transaction
+            .inputs
+            .iter()
+            .any(|other| other.output_ref == input.output_ref)
+    }) {
+        return Err(anyhow!(
+            "it spends an input that is already in the CoinJoin"
+        ));
+    }
+
+    joined
+        .join(part)
+        .map_err(|e| anyhow!("it does not fit the CoinJoin: {e:?}"))
+}
+
+/// Find the outputs of this wallet's part in the submitted transaction, along with their values
+/// and verifiers.
+fn own_outputs(
+    part: &Pstt,
+    transaction: &Transaction,
+    tx_hash: H256,
+) -> Vec<(OutputRef, u128, OuterVerifier)> {
+    let mut claimed = vec![false; transaction.outputs.len()];
+    let mut found = Vec::new();
+    for own in &part.transaction.outputs {
+        let Some(index) = (0..transaction.outputs.len())
+            .find(|&index| !claimed[index] && transaction.outputs[index] == *own)
+        else {
+            continue;
+        };
+        claimed[index] = true;
+
+        let amount = own
+            .payload
+            .extract::<Coin<0>>()
+            .map(|coin| coin.0)
+            .unwrap_or_default();
+        let output_ref = OutputRef {
+            tx_hash,
+            index: index as u32,
+        };
+        found.push((output_ref, amount, own.verifier.clone()));
+    }
+
+    found
+}
+
+/// A connection between a coordinator and a participant, which exchange one hex-encoded message
+/// per line.
+struct Connection(BufReader<TcpStream>);
+
+impl Connection {
+    /// Send a message to the other side.
+    async fn send(&mut self, message: &[u8]) -> anyhow::Result<()> {
+        let mut line = hex::encode(message);
+        line.push('\n');
+        self.0.write_all(line.as_bytes()).await?;
+        self.0.flush().await?;
+        Ok(())
+    }
+
+    /// Wait for the next message from the other side.
+    async fn receive(&mut self) -> anyhow::Result<Vec<u8>> {
+        let mut line = String::new();
+        (&mut self.0)
+            .take(MAX_MESSAGE_LENGTH)
+            .read_line(&mut line)
+            .await?;
+        let Some(line) = line.strip_suffix('\n') else {
+            return Err(anyhow!(
+                "the connection was closed or the message is too long"
+            ));
+        };
+        Ok(hex::decode(line)?)
+    }
+}
@@ -0,0 +1,155 @@
+//! Working with several nodes at once.
+//!
+//! The wallet may be given more than one node endpoint. When it starts, it checks that each one
+//! responds and serves the same chain, and ranks the healthy ones by the height of their finalized
+//! block. It syncs with the best one, and when that one stops responding mid-sync, it carries on
+//! with the next one from the last block that it applied.
+//!
+//! Once synced, the wallet compares its blocks with the finalized blocks of the other endpoints.
+//! Two honest nodes never disagree about a finalized block, so a mismatch means that a node is
+//! lying to the wallet, and the wallet refuses to go on.
+
+use std::cmp::Reverse;
+
+use anyhow::{anyhow, bail};
+use futures::future::join_all;
+use jsonrpsee::http_client::{HttpClient, HttpClientBuilder};
+use runtime::OuterVerifier;
+use sled::Db;
+use sp_core::H256;
+
+use crate::{indexer::OutputIndexer, rpc, sync};
+
+/// A node endpoint that responded to the health check.
+pub struct Endpoint {
+    /// The http url of the endpoint.
+    pub url: String,
+    /// A client connected to the endpoint.
+    pub client: HttpClient,
+    /// The hash of the node's genesis block.
+    pub genesis_hash: H256,
+    /// The height of the node's finalized block at the time of the health check.
+    pub finalized_height: u32,
+}
+
+/// Check the health of each endpoint, and return the healthy ones with the best first.
+///
+/// An endpoint is healthy when it reports its genesis block and its finalized block. The best
+/// endpoint is the one with the highest finalized block, and ties keep the order they were given in.
+/// All healthy endpoints must serve the same chain.
+pub async fn rank(urls: &[String]) -> anyhow::Result<Vec<Endpoint>> {
+    let checks = urls.iter().map(|url| async move {
+        let client = HttpClientBuilder::default().build(url)?;
+        let genesis_hash = rpc::node_get_block_hash(0, &client)
+            .await?
+            .ok_or(anyhow!("node has no genesis block"))?;
+        let finalized_height = rpc::node_get_finalized_height(&client).await?;
+        anyhow::Ok(Endpoint {
+            url: url.clone(),
+            client,
+            genesis_hash,
+            finalized_height,
+        })
+    });
+
+    let mut healthy = Vec::new();
+    for (url, result) in urls.iter().zip(join_all(checks).await) {
+        match result {
+            Ok(endpoint) => {
+                log::debug!(
+                    "Endpoint {url} is finalized to height {}",
+                    endpoint.finalized_height
+                );
+                healthy.push(endpoint);
+            }
+            Err(e) => log::warn!("Skipping endpoint {url}, which failed the health check: {e}"),
+        }
+    }
+    healthy.sort_by_key(|endpoint| Reverse(endpoint.finalized_height));
+
+    let best = healthy
+        .first()
+        .ok_or(anyhow!("None of the endpoints passed the health check"))?;
+    if let Some(other) = healthy.iter().find(|e| e.genesis_hash != best.genesis_hash) {
+        bail!(
+            "Endpoints {} and {} serve different chains, with genesis blocks {:?} and {:?}",
+            best.url,
+            other.url,
+            best.genesis_hash,
+            other.genesis_hash
+        );
+    }
+
+    Ok(healthy)
+}
+
+/// Whether an error came from talking to a node, rather than from the wallet itself.
+fn is_rpc_error(e: &anyhow::Error) -> bool {
+    e.downcast_ref::<jsonrpsee::core::Error>().is_some()
+}
+
+/// Synchronize the local database with the first of the endpoints that keeps responding.
+///
+/// The sync applies blocks one at a time, so when an endpoint stops responding, the next one picks
+/// up from the last block that was applied. Errors that do not come from the node are returned
+/// right away. Returns the index of the endpoint that finished the sync.
+pub(crate) async fn synchronize<F: Fn(&OuterVerifier) -> bool>(
+    db: &Db,
+    endpoints: &[Endpoint],
+    filter: &F,
+    indexers: &[Box<dyn OutputIndexer>],
+    max_in_flight: u32,
+) -> anyhow::Result<usize> {
+    let mut last_error = None;
+    for (index, endpoint) in endpoints.iter().enumerate() {
+        match sync::synchronize(db, &endpoint.client, filter, indexers, max_in_flight).await {
+            Ok(()) => return Ok(index),
+            Err(e) if is_rpc_error(&e) => {
+                log::warn!("Endpoint {} failed while syncing: {e}", endpoint.url);
+                last_error = Some(e);
+            }
+            Err(e) => return Err(e),
+        }
+    }
+
+    Err(last_error.unwrap_or_else(|| anyhow!("There are no endpoints to sync with")))
+}
+
+/// Compare the blocks that the wallet synced from the active endpoint with the finalized blocks of
+/// the other endpoints.
+///
+/// Each comparison is made at the highest height that both endpoints had finalized, so honest
+/// nodes always agree. Endpoints that do not respond are skipped.
+pub(crate) async fn cross_check(
+    db: &Db,
+    endpoints: &[Endpoint],
+    active: usize,
+) -> anyhow::Result<()> {
+    let Some(synced) = sync::height(db)? else {
+        return Ok(());
+    };
+    let active_height = endpoints[active].finalized_height;
+
+    for (index, other) in endpoints.iter().enumerate() {
+        if index == active {
+            continue;
+        }
+        let height = synced.min(active_height).min(other.finalized_height);
+        let Some(ours) = sync::get_block_hash(db, height)? else {
+            continue;
+        };
+
+        match rpc::node_get_block_hash(height, &other.client).await {
+            Ok(Some(theirs)) if theirs == ours => {
+                log::debug!("Endpoint {} agrees on block {ours:?} at height {height}", other.url);
+            }
+            Ok(theirs) => bail!(
+                "Endpoint {} reports block {theirs:?} at height {height}, but the wallet synced block {ours:?}. A node is lying.",
+                other.url
+            ),
+            Err(e) => log::warn!("Could not cross-check with endpoint {}: {e}", other.url),
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,110 @@
+//! Keeping the wallet synchronized with the node for as long as it runs.
+//!
+//! Each wallet command normally syncs once when it starts. In follow mode, the wallet instead
+//! keeps syncing new blocks as the node imports them, until it is interrupted. The database is
+//! flushed to disk periodically, so little work is lost if the process is killed, and the next
+//! run resumes from wherever the database left off.
+//!
+//! Optionally, the wallet also keeps a signed checkpoint file up to date. A fresh wallet can be
+//! started from that file with `--checkpoint` rather than syncing from genesis.
+
+use std::time::Duration;
+
+use anyhow::anyhow;
+use jsonrpsee::http_client::HttpClient;
+use runtime::OuterVerifier;
+use sc_keystore::LocalKeystore;
+use sled::Db;
+use tokio::time::MissedTickBehavior;
+
+use crate::{
+    checkpoint,
+    cli::SyncArgs,
+    indexer::OutputIndexer,
+    sync::{self, SyncOptions},
+};
+
+/// Keep the database synchronized with the node until the user presses Ctrl-C.
+///
+/// Failing to reach the node is not fatal. The wallet logs the failure and tries again
+/// at the next interval.
+pub async fn follow<F: Fn(&OuterVerifier) -> bool>(
+    db: &Db,
+    client: &HttpClient,
+    keystore: &LocalKeystore,
+    filter: &F,
+    indexers: &[Box<dyn OutputIndexer>],
+    options: &SyncOptions,
+    args: SyncArgs,
+) -> anyhow::Result<()> {
+    // Find out about a missing signing key now, rather than at the first checkpoint.
+    if args.checkpoint_file.is_some() && !crate::keystore::has_key(keystore, &args.signer) {
+        return Err(anyhow!(
+            "Checkpoint signer {:?} is not in the keystore",
+            args.signer
+        ));
+    }
+
+    let mut poll = tokio::time::interval(Duration::from_secs(args.interval.max(1)));
+    poll.set_missed_tick_behavior(MissedTickBehavior::Delay);
+    let mut flush = tokio::time::interval(Duration::from_secs(args.flush_interval.max(1)));
+    flush.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+    // The same signal future is polled throughout, so a Ctrl-C that arrives in the middle
+    // of a sync is noticed as soon as that sync finishes.
+    let shutdown = tokio::signal::ctrl_c();
+    tokio::pin!(shutdown);
+
+    let mut last_checkpoint: Option<u32> = None;
+    log::info!("Following the node. Press Ctrl-C to stop.");
+
+    loop {
+        tokio::select! {
+            result = &mut shutdown => {
+                result?;
+                break;
+            }
+            _ = poll.tick() => {
+                if let Err(e) = sync::catch_up(db, client, filter, indexers, options).await {
+                    log::warn!("Unable to sync with the node: {e}");
+                    continue;
+                }
+
+                let height = sync::height(db)?.unwrap_or_default();
+                log::debug!("Wallet database synchronized with node to height {height}");
+
+                let Some(file) = &args.checkpoint_file else {
+                    continue;
+                };
+                if let Some(last) = last_checkpoint {
+                    if height < last.saturating_add(args.checkpoint_every) {
+                        continue;
+                    }
+                }
+                match checkpoint::create_checkpoint(db, client, keystore, args.signer).await {
+                    Ok(signed) => {
+                        signed.write(file)?;
+                        log::info!(
+                            "Wrote checkpoint at height {height} to {}",
+                            file.to_string_lossy()
+                        );
+                        last_checkpoint = Some(height);
+                    }
+                    Err(e) => log::warn!("Unable to create a checkpoint: {e}"),
+                }
+            }
+            _ = flush.tick() => {
+                let bytes = db.flush_async().await?;
+                log::debug!("Flushed {bytes} bytes of the wallet database to disk");
+            }
+        }
+    }
+
+    db.flush_async().await?;
+    log::info!(
+        "Stopped following the node at height {}",
+        sync::height(db)?.unwrap_or_default()
+    );
+
+    Ok(())
+}
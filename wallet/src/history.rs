@@ -0,0 +1,324 @@
+//! The wallet's transaction history, reconstructed from the blocks in the local database.
+//!
+//! The wallet does not keep a separate record of its transactions. Instead, it finds them in
+//! the blocks that it stores, and it looks up the coins they consumed in the spent table.
+//! So history is only available for blocks that have not been pruned, and only for keys
+//! whose coins the wallet tracks.
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    io::Write,
+};
+
+use anyhow::anyhow;
+use parity_scale_codec::Encode;
+use runtime::{timestamp::Timestamp, Block, OuterVerifier, Transaction};
+use sled::Db;
+use sp_core::H256;
+use sp_runtime::traits::{BlakeTwo256, Hash};
+use tuxedo_core::verifier::SigCheck;
+
+use crate::{
+    cli::{ExportArgs, ExportFormat},
+    indexer::extract_coin,
+    sync,
+};
+
+/// How a transaction moves the coins of one asset in or out of a set of keys.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub(crate) struct AssetFlow {
+    /// The net change in the keys' holdings of the asset.
+    pub change: i128,
+    /// The other owners involved. These are the recipients when the keys' holdings
+    /// decrease, and the known senders otherwise.
+    pub counterparties: BTreeSet<H256>,
+}
+
+/// A transaction's effect on the holdings of one asset, as one line of history.
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) struct HistoryEntry {
+    pub height: u32,
+    /// The time of the block in milliseconds since the unix epoch, if it noted one.
+    pub timestamp: Option<u64>,
+    pub tx_hash: H256,
+    pub asset_id: u8,
+    pub flow: AssetFlow,
+    /// The memo that the transaction carries, if any.
+    pub memo: Option<Vec<u8>>,
+}
+
+/// Find how a transaction moves coins in and out of the keys that `is_ours` accepts,
+/// for each asset whose coins it creates or consumes for those keys.
+///
+/// Empty if the transaction does not touch any of their coins.
+pub(crate) fn coin_flows<F: Fn(&H256) -> bool>(
+    db: &Db,
+    tx: &Transaction,
+    is_ours: &F,
+) -> anyhow::Result<BTreeMap<u8, AssetFlow>> {
+    let mut flows = BTreeMap::<u8, AssetFlow>::new();
+    let mut recipients = BTreeMap::<u8, BTreeSet<H256>>::new();
+    let mut senders = BTreeMap::<u8, BTreeSet<H256>>::new();
+
+    for output in &tx.outputs {
+        let (Some((asset_id, amount)), OuterVerifier::SigCheck(SigCheck { owner_pubkey })) =
+            (extract_coin(&output.payload), &output.verifier)
+        else {
+            continue;
+        };
+        if is_ours(owner_pubkey) {
+            flows.entry(asset_id).or_default().change += amount as i128;
+        } else {
+            recipients
+                .entry(asset_id)
+                .or_default()
+                .insert(*owner_pubkey);
+        }
+    }
+
+    for input in &tx.inputs {
+        // Coins consumed by a transaction in a block are in the spent table, while
+        // coins consumed by a pending transaction are still in the unspent table.
+        let record = match sync::get_spent(db, &input.output_ref)? {
+            Some(record) => Some(record),
+            None => sync::get_unspent(db, &input.output_ref)?,
+        };
+        let Some((owner, amount, _, asset_id)) = record else {
+            continue;
+        };
+        if is_ours(&owner) {
+            flows.entry(asset_id).or_default().change -= amount as i128;
+        } else {
+            senders.entry(asset_id).or_default().insert(owner);
+        }
+    }
+
+    for (asset_id, flow) in flows.iter_mut() {
+        let others = if flow.change < 0 {
+            recipients.remove(asset_id)
+        } else {
+            senders.remove(asset_id)
+        };
+        flow.counterparties = others.unwrap_or_default();
+    }
+
+    Ok(flows)
+}
+
+/// The time that a block noted in its timestamp inherent, if it has one.
+fn block_timestamp(block: &Block) -> Option<u64> {
+    block
+        .extrinsics
+        .iter()
+        .flat_map(|tx| &tx.outputs)
+        .find_map(|output| output.payload.extract::<Timestamp>().ok())
+        .map(|timestamp| timestamp.time)
+}
+
+/// Collect the history of the keys that `is_ours` accepts over an inclusive range of blocks.
+///
+/// Fails if any block in the range is not in the local database.
+fn history<F: Fn(&H256) -> bool>(
+    db: &Db,
+    is_ours: &F,
+    from: u32,
+    to: u32,
+) -> anyhow::Result<Vec<HistoryEntry>> {
+    let mut entries = Vec::new();
+
+    for height in from..=to {
+        let hash = sync::get_block_hash(db, height)?
+            .ok_or(anyhow!("The wallet has no block at height {height}"))?;
+        let block = sync::get_block(db, hash)?.ok_or(anyhow!(
+            "Block {height} has been pruned from the wallet database"
+        ))?;
+        let timestamp = block_timestamp(&block);
+
+        for tx in &block.extrinsics {
+            let tx_hash = BlakeTwo256::hash_of(&tx.encode());
+            for (asset_id, flow) in coin_flows(db, tx, is_ours)? {
+                entries.push(HistoryEntry {
+                    height,
+                    timestamp,
+                    tx_hash,
+                    asset_id,
+                    flow,
+                    memo: tx.memo.clone(),
+                });
+            }
+        }
+    }
+
+    Ok(entries)
+}
+
+/// Export the history of a key over a range of blocks, to a file or to stdout.
+pub fn export(db: &Db, args: ExportArgs) -> anyhow::Result<()> {
+    let best = sync::height(db)?.ok_or(anyhow!("Cannot export from an uninitialized database"))?;
+    let to = args.to.unwrap_or(best).min(best);
+    if args.from > to {
+        return Err(anyhow!(
+            "The range starts at block {} but the wallet is only synced to block {to}",
+            args.from
+        ));
+    }
+
+    let entries = history(db, &|key: &H256| key == &args.key, args.from, to)?;
+
+    let mut out: Box<dyn Write> = match &args.file {
+        Some(file) => Box::new(std::fs::File::create(file)?),
+        None => Box::new(std::io::stdout()),
+    };
+    match args.format {
+        ExportFormat::Csv => write_csv(&mut out, &entries)?,
+    }
+    out.flush()?;
+
+    if let Some(file) = &args.file {
+        log::info!(
+            "Exported {} entries to {}",
+            entries.len(),
+            file.to_string_lossy()
+        );
+    }
+
+    Ok(())
+}
+
+/// Write history entries as CSV, one row per transaction and asset, with a header row.
+fn write_csv(out: &mut impl Write, entries: &[HistoryEntry]) -> anyhow::Result<()> {
+    writeln!(
+        out,
+        "block,timestamp,direction,asset,amount,counterparty,tx_hash,memo"
+    )?;
+
+    for entry in entries {
+        let timestamp = entry.timestamp.map(format_timestamp).unwrap_or_default();
+        let direction = match entry.flow.change {
+            c if c > 0 => "in",
+            c if c < 0 => "out",
+            _ => "self",
+        };
+        // Several counterparties are separated by semicolons so they stay in a single field.
+        let counterparty = entry
+            .flow
+            .counterparties
+            .iter()
+            .map(|key| format!("0x{}", hex::encode(key)))
+            .collect::<Vec<_>>()
+            .join(";");
+        let memo = entry.memo.as_deref().map(format_memo).unwrap_or_default();
+
+        writeln!(
+            out,
+            "{},{timestamp},{direction},{},{},{counterparty},0x{},{memo}",
+            entry.height,
+            entry.asset_id,
+            entry.flow.change.unsigned_abs(),
+            hex::encode(entry.tx_hash)
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Format a memo as a single CSV field. Text is quoted, and anything else is hex encoded.
+fn format_memo(memo: &[u8]) -> String {
+    match std::str::from_utf8(memo) {
+        Ok(text) => format!("\"{}\"", text.replace('"', "\"\"")),
+        Err(_) => format!("0x{}", hex::encode(memo)),
+    }
+}
+
+/// Format milliseconds since the unix epoch as an ISO 8601 UTC time, to the second.
+fn format_timestamp(millis: u64) -> String {
+    let secs = millis / 1000;
+    let (days, time) = (secs / 86_400, secs % 86_400);
+
+    // Convert days since the epoch to a civil date. The calendar is shifted to start in
+    // March, so that leap days fall at the end of the year. See
+    // http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+    let z = days + 719_468;
+    let era = z / 146_097;
+    let day_of_era = z % 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 {
+        shifted_month + 3
+    } else {
+        shifted_month - 9
+    };
+    let year = year_of_era + era * 400 + u64::from(month <= 2);
+
+    format!(
+        "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}Z",
+        time / 3_600,
+        time % 3_600 / 60,
+        time % 60
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn format_timestamp_works() {
+        assert_eq!(format_timestamp(0), "1970-01-01T00:00:00Z");
+        assert_eq!(format_timestamp(1_700_000_000_000), "2023-11-14T22:13:20Z");
+        assert_eq!(format_timestamp(951_782_400_000), "2000-02-29T00:00:00Z");
+        assert_eq!(format_timestamp(1_709_251_199_999), "2024-02-29T23:59:59Z");
+    }
+
+    #[test]
+    fn format_memo_works() {
+        assert_eq!(format_memo(b"invoice 42"), "\"invoice 42\"");
+        assert_eq!(format_memo(b"say \"hi\""), "\"say \"\"hi\"\"\"");
+        assert_eq!(format_memo(&[0xff, 0x00]), "0xff00");
+    }
+
+    #[test]
+    fn write_csv_works() {
+        let entries = vec![
+            HistoryEntry {
+                height: 3,
+                timestamp: Some(1_700_000_000_000),
+                tx_hash: H256::repeat_byte(1),
+                asset_id: 0,
+                flow: AssetFlow {
+                    change: -70,
+                    counterparties: [H256::repeat_byte(2), H256::repeat_byte(3)].into(),
+                },
+                memo: Some(b"invoice \"42\", paid".to_vec()),
+            },
+            HistoryEntry {
+                height: 4,
+                timestamp: None,
+                tx_hash: H256::repeat_byte(4),
+                asset_id: 1,
+                flow: AssetFlow {
+                    change: 5,
+                    counterparties: BTreeSet::new(),
+                },
+                memo: None,
+            },
+        ];
+
+        let mut out = Vec::new();
+        write_csv(&mut out, &entries).unwrap();
+
+        let expected = format!(
+            "block,timestamp,direction,asset,amount,counterparty,tx_hash,memo\n\
+             3,2023-11-14T22:13:20Z,out,0,70,0x{};0x{},0x{},\"invoice \"\"42\"\", paid\"\n\
+             4,,in,1,5,,0x{},\n",
+            "02".repeat(32),
+            "03".repeat(32),
+            "01".repeat(32),
+            "04".repeat(32),
+        );
+        assert_eq!(String::from_utf8(out).unwrap(), expected);
+    }
+}
@@ -0,0 +1,251 @@
+//! Pluggable indexing of outputs during wallet sync.
+//!
+//! The sync logic itself knows nothing about the types of data stored in UTXOs. Instead, every
+//! output that the wallet considers relevant is offered to a list of [`OutputIndexer`]s that
+//! is registered at startup. Each indexer decodes the output types it understands and persists
+//! them in its own trees. Pieces that want wallet support can provide their own indexer.
+
+use name_service::NameRecord;
+use parity_scale_codec::{Decode, Encode};
+use runtime::{kitties::KittyData, OuterVerifier, Output};
+use sled::Db;
+use sp_core::H256;
+use tuxedo_core::{
+    dynamic_typing::DynamicallyTypedData,
+    types::OutputRef,
+    verifier::{SigCheck, UpForGrabs},
+};
+
+use crate::sync;
+
+/// Decodes and persists specific kinds of outputs as the wallet syncs.
+///
+/// Spent and re-org related hooks are called for every input and output regardless of which
+/// indexer stored it, so implementations should quietly ignore output refs they don't know.
+pub trait OutputIndexer {
+    /// Decode and store the output if this indexer understands it.
+    ///
+    /// Returns whether the output was stored. Once an indexer stores an output, it is not
+    /// offered to any later indexers.
+    fn index(
+        &self,
+        db: &Db,
+        output_ref: &OutputRef,
+        output: &Output,
+        height: u32,
+    ) -> anyhow::Result<bool>;
+
+    /// Mark an output as spent.
+    fn spend(&self, db: &Db, output_ref: &OutputRef) -> anyhow::Result<()>;
+
+    /// Mark a previously spent output as unspent again. Used when rolling back a block.
+    fn unspend(&self, db: &Db, output_ref: &OutputRef) -> anyhow::Result<()>;
+
+    /// Drop all record of an unspent output. Used when rolling back a block.
+    fn remove(&self, db: &Db, output_ref: &OutputRef) -> anyhow::Result<()>;
+
+    /// Discard the record of a spent output. Used when pruning old blocks.
+    fn forget_spent(&self, _db: &Db, _output_ref: &OutputRef) -> anyhow::Result<()> {
+        Ok(())
+    }
+}
+
+/// The indexers that the template wallet registers by default.
+pub fn default_indexers() -> Vec<Box<dyn OutputIndexer>> {
+    vec![
+        Box::new(MoneyIndexer),
+        Box::new(KittyIndexer),
+        Box::new(NameIndexer),
+    ]
+}
+
+/// Decode a coin of any asset from a UTXO payload.
+///
+/// The money piece is instantiated once per asset and gives each `Coin<ID>` the type id
+/// `coi<ID>`, so the asset id is the last byte of the type id.
+/// Returns the asset id and the coin's value, or None if the payload is not a coin.
+pub fn extract_coin(payload: &DynamicallyTypedData) -> Option<(u8, u128)> {
+    let [b'c', b'o', b'i', asset_id] = payload.type_id else {
+        return None;
+    };
+    let amount = u128::decode(&mut &payload.data[..]).ok()?;
+
+    Some((asset_id, amount))
+}
+
+/// Indexes simple coins, of any asset, owned by a single signature.
+pub struct MoneyIndexer;
+
+impl OutputIndexer for MoneyIndexer {
+    fn index(
+        &self,
+        db: &Db,
+        output_ref: &OutputRef,
+        output: &Output,
+        height: u32,
+    ) -> anyhow::Result<bool> {
+        let (Some((asset_id, amount)), OuterVerifier::SigCheck(SigCheck { owner_pubkey })) =
+            (extract_coin(&output.payload), &output.verifier)
+        else {
+            return Ok(false);
+        };
+
+        sync::add_unspent_output(db, output_ref, owner_pubkey, &amount, height, asset_id)?;
+        Ok(true)
+    }
+
+    fn spend(&self, db: &Db, output_ref: &OutputRef) -> anyhow::Result<()> {
+        sync::spend_output(db, output_ref)
+    }
+
+    fn unspend(&self, db: &Db, output_ref: &OutputRef) -> anyhow::Result<()> {
+        sync::unspend_output(db, output_ref)
+    }
+
+    fn remove(&self, db: &Db, output_ref: &OutputRef) -> anyhow::Result<()> {
+        sync::remove_unspent_output(db, output_ref)
+    }
+
+    fn forget_spent(&self, db: &Db, output_ref: &OutputRef) -> anyhow::Result<()> {
+        sync::forget_spent_output(db, output_ref)
+    }
+}
+
+/// The identifier for the tree of owned kitties in the db.
+const KITTIES: &str = "kitties";
+
+/// The identifier for the tree of spent kitties in the db.
+const SPENT_KITTIES: &str = "spent_kitties";
+
+/// Indexes kitties owned by a single signature.
+///
+/// Kitties are stored as output_ref => (owner_pubkey, kitty, creation_height).
+pub struct KittyIndexer;
+
+impl OutputIndexer for KittyIndexer {
+    fn index(
+        &self,
+        db: &Db,
+        output_ref: &OutputRef,
+        output: &Output,
+        height: u32,
+    ) -> anyhow::Result<bool> {
+        let (Ok(kitty), OuterVerifier::SigCheck(SigCheck { owner_pubkey })) =
+            (output.payload.extract::<KittyData>(), &output.verifier)
+        else {
+            return Ok(false);
+        };
+
+        let kitties_tree = db.open_tree(KITTIES)?;
+        kitties_tree.insert(output_ref.encode(), (owner_pubkey, kitty, height).encode())?;
+        Ok(true)
+    }
+
+    fn spend(&self, db: &Db, output_ref: &OutputRef) -> anyhow::Result<()> {
+        move_record(db, KITTIES, SPENT_KITTIES, output_ref)
+    }
+
+    fn unspend(&self, db: &Db, output_ref: &OutputRef) -> anyhow::Result<()> {
+        move_record(db, SPENT_KITTIES, KITTIES, output_ref)
+    }
+
+    fn remove(&self, db: &Db, output_ref: &OutputRef) -> anyhow::Result<()> {
+        db.open_tree(KITTIES)?.remove(output_ref.encode())?;
+        Ok(())
+    }
+
+    fn forget_spent(&self, db: &Db, output_ref: &OutputRef) -> anyhow::Result<()> {
+        db.open_tree(SPENT_KITTIES)?.remove(output_ref.encode())?;
+        Ok(())
+    }
+}
+
+/// Get all the unspent kitties known to the wallet along with their owners.
+pub fn get_kitties(db: &Db) -> anyhow::Result<Vec<(OutputRef, H256, KittyData)>> {
+    let kitties_tree = db.open_tree(KITTIES)?;
+
+    kitties_tree
+        .iter()
+        .map(|raw_data| {
+            let (output_ref_ivec, kitty_ivec) = raw_data?;
+            let output_ref = OutputRef::decode(&mut &output_ref_ivec[..])?;
+            let (owner, kitty, _height) = <(H256, KittyData, u32)>::decode(&mut &kitty_ivec[..])?;
+            Ok((output_ref, owner, kitty))
+        })
+        .collect()
+}
+
+/// The identifier for the tree of name records in the db.
+const NAMES: &str = "names";
+
+/// The identifier for the tree of spent name records in the db.
+const SPENT_NAMES: &str = "spent_names";
+
+/// Indexes the records of the name service so that names can be resolved when sending.
+///
+/// Name records are public and protected by `UpForGrabs`, so the wallet only sees them if its
+/// sync filter accepts `UpForGrabs` outputs. They are stored as output_ref => record.
+pub struct NameIndexer;
+
+impl OutputIndexer for NameIndexer {
+    fn index(
+        &self,
+        db: &Db,
+        output_ref: &OutputRef,
+        output: &Output,
+        _height: u32,
+    ) -> anyhow::Result<bool> {
+        let (Ok(record), OuterVerifier::UpForGrabs(UpForGrabs)) =
+            (output.payload.extract::<NameRecord<0>>(), &output.verifier)
+        else {
+            return Ok(false);
+        };
+
+        let names_tree = db.open_tree(NAMES)?;
+        names_tree.insert(output_ref.encode(), record.encode())?;
+        Ok(true)
+    }
+
+    fn spend(&self, db: &Db, output_ref: &OutputRef) -> anyhow::Result<()> {
+        move_record(db, NAMES, SPENT_NAMES, output_ref)
+    }
+
+    fn unspend(&self, db: &Db, output_ref: &OutputRef) -> anyhow::Result<()> {
+        move_record(db, SPENT_NAMES, NAMES, output_ref)
+    }
+
+    fn remove(&self, db: &Db, output_ref: &OutputRef) -> anyhow::Result<()> {
+        db.open_tree(NAMES)?.remove(output_ref.encode())?;
+        Ok(())
+    }
+
+    fn forget_spent(&self, db: &Db, output_ref: &OutputRef) -> anyhow::Result<()> {
+        db.open_tree(SPENT_NAMES)?.remove(output_ref.encode())?;
+        Ok(())
+    }
+}
+
+/// Look up the key that a registered name resolves to, ignoring expired names.
+pub fn resolve_name(db: &Db, name: &str) -> anyhow::Result<Option<H256>> {
+    let height = sync::height(db)?.unwrap_or_default();
+
+    for raw_data in db.open_tree(NAMES)?.iter() {
+        let (_, record_ivec) = raw_data?;
+        let record = NameRecord::<0>::decode(&mut &record_ivec[..])?;
+        if record.name == name.as_bytes() && !record.is_expired(height) {
+            return Ok(Some(record.target));
+        }
+    }
+
+    Ok(None)
+}
+
+/// Move a record from one tree to another, if it is present.
+fn move_record(db: &Db, from: &str, to: &str, output_ref: &OutputRef) -> anyhow::Result<()> {
+    let Some(ivec) = db.open_tree(from)?.remove(output_ref.encode())? else {
+        return Ok(());
+    };
+    db.open_tree(to)?.insert(output_ref.encode(), ivec)?;
+
+    Ok(())
+}
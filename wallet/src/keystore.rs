@@ -7,7 +7,8 @@ use parity_scale_codec::Encode;
 use sc_keystore::LocalKeystore;
 use sp_core::{
     crypto::Pair as PairT,
-    sr25519::{Pair, Public},
+    ed25519,
+    sr25519::{Pair, Public, Signature},
     H256,
 };
 use sp_keystore::Keystore;
@@ -40,9 +41,32 @@ pub fn sign_with(
     public: &Public,
     message: &[u8],
 ) -> anyhow::Result<Vec<u8>> {
-    let sig = keystore
+    Ok(signature_with(keystore, public, message)?.encode())
+}
+
+/// Like [`sign_with`], but returns the signature itself rather than its encoding, so that it can
+/// be wrapped in a larger redeemer.
+pub fn signature_with(
+    keystore: &LocalKeystore,
+    public: &Public,
+    message: &[u8],
+) -> anyhow::Result<Signature> {
+    keystore
         .sr25519_sign(KEY_TYPE, public, message)?
-        .ok_or(anyhow!("Key doesn't exist in keystore"))?;
+        .ok_or(anyhow!("Key doesn't exist in keystore"))
+}
+
+/// Sign a given message with the ed25519 private key that corresponds to the given public key.
+///
+/// Returns an error if the keystore itself errors, or does not contain the requested key.
+pub fn ed25519_sign_with(
+    keystore: &LocalKeystore,
+    public: &ed25519::Public,
+    message: &[u8],
+) -> anyhow::Result<Vec<u8>> {
+    let sig = keystore
+        .ed25519_sign(KEY_TYPE, public, message)?
+        .ok_or(anyhow!("Ed25519 key doesn't exist in keystore"))?;
 
     Ok(sig.encode())
 }
@@ -72,6 +96,16 @@ pub fn generate_key(keystore: &LocalKeystore, password: Option<String>) -> anyho
     Ok(())
 }
 
+/// Generate a new key from system entropy, insert it into the keystore, and return its public key.
+///
+/// Unlike [`generate_key`], nothing is printed, so the key is only as safe as the keystore.
+pub fn generate_fresh_key(keystore: &LocalKeystore) -> anyhow::Result<H256> {
+    let public = keystore
+        .sr25519_generate_new(KEY_TYPE, None)
+        .map_err(|e| anyhow!("Error generating key: {e}"))?;
+    Ok(H256::from_slice(public.as_ref()))
+}
+
 /// Check whether a specific key is in the keystore
 pub fn has_key(keystore: &LocalKeystore, pubkey: &H256) -> bool {
     keystore.has_keys(&[(pubkey.encode(), KEY_TYPE)])
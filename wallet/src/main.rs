@@ -1,7 +1,6 @@
 //! A simple CLI wallet. For now it is a toy just to start testing things out.
 
 use clap::Parser;
-use jsonrpsee::http_client::HttpClientBuilder;
 use parity_scale_codec::{Decode, Encode};
 use runtime::OuterVerifier;
 use std::path::PathBuf;
@@ -9,19 +8,33 @@ use tuxedo_core::{types::OutputRef, verifier::*};
 
 use sp_core::H256;
 
+mod address_book;
 mod amoeba;
+mod backup;
+mod checkpoint;
 mod cli;
+mod coinjoin;
+mod endpoints;
+mod follow;
+mod history;
+mod indexer;
 mod keystore;
 mod money;
 mod output_filter;
+mod pstt;
 mod rpc;
 mod sync;
+mod tui;
+mod verify_tx;
 
-use cli::{Cli, Command};
+use cli::{AddressCommand, Cli, Command, OutputFormat, PsttCommand};
 
 /// The default RPC endpoint for the wallet to connect to
 const DEFAULT_ENDPOINT: &str = "http://localhost:9944";
 
+/// The default number of blocks the wallet will fetch concurrently while syncing
+const DEFAULT_SYNC_CONCURRENCY: u32 = 16;
+
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
     env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info")).init();
@@ -50,13 +63,14 @@ async fn main() -> anyhow::Result<()> {
 
     // Setup jsonrpsee and endpoint-related information.
     // https://github.com/paritytech/jsonrpsee/blob/master/examples/examples/http.rs
-    let client = HttpClientBuilder::default().build(cli.endpoint)?;
+    // Until the sync fails over to another endpoint, the wallet talks to the best one.
+    let endpoints = endpoints::rank(&cli.endpoint).await?;
+    let mut active = 0;
+    let client = &endpoints[active].client;
 
     // Read node's genesis block.
-    let node_genesis_hash = rpc::node_get_block_hash(0, &client)
-        .await?
-        .expect("node should be able to return some genesis hash");
-    let node_genesis_block = rpc::node_get_block(node_genesis_hash, &client)
+    let node_genesis_hash = endpoints[active].genesis_hash;
+    let node_genesis_block = rpc::node_get_block(node_genesis_hash, client)
         .await?
         .expect("node should be able to return some genesis block");
     log::debug!("Node's Genesis block::{:?}", node_genesis_hash);
@@ -70,23 +84,63 @@ async fn main() -> anyhow::Result<()> {
 
     // The filter function that will determine whether the local database should track a given utxo
     // is based on whether that utxo is privately owned by a key that is in our keystore.
+    // Public `UpForGrabs` outputs are also tracked so that indexers can pick up shared records
+    // such as registered names.
     let keystore_filter = |v: &OuterVerifier| -> bool {
         matches![
             v,
             OuterVerifier::SigCheck(SigCheck { owner_pubkey }) if crate::keystore::has_key(&keystore, owner_pubkey)
-        ]
+        ] || matches!(v, OuterVerifier::UpForGrabs(_))
     };
 
-    if !sled::Db::was_recovered(&db) {
+    // The indexers that decode and store the relevant outputs as the wallet syncs.
+    let indexers = indexer::default_indexers();
+
+    if !sled::Db::was_recovered(&db) && cli.checkpoint.is_some() {
+        // This is a new instance, and the user asked to start from a checkpoint rather than genesis.
+        let file = cli.checkpoint.as_ref().expect("just checked it is some");
+        let signer = cli.checkpoint_signer.ok_or(anyhow::anyhow!(
+            "--checkpoint requires a --checkpoint-signer"
+        ))?;
+        let checkpoint = checkpoint::SignedCheckpoint::read_and_verify(file, &signer)?;
+        checkpoint::initialize_from_checkpoint(
+            &db,
+            client,
+            checkpoint,
+            &keystore_filter,
+            &indexers,
+            cli.sync_concurrency,
+        )
+        .await?;
+    } else if !sled::Db::was_recovered(&db) {
         // This is a new instance, so we need to apply the genesis block to the database.
-        sync::apply_block(&db, node_genesis_block, node_genesis_hash, &keystore_filter).await?;
+        sync::apply_block(
+            &db,
+            node_genesis_block,
+            node_genesis_hash,
+            &keystore_filter,
+            &indexers,
+        )
+        .await?;
     }
 
     // Synchronize the wallet with attached node unless instructed otherwise.
-    if cli.no_sync {
+    // The terminal interface syncs on its own so that it can show the progress.
+    if matches!(cli.command, Some(Command::Tui)) {
+        log::debug!("Leaving the sync to the terminal interface.");
+    } else if cli.no_sync {
         log::warn!("Skipping sync with node. Using previously synced information.")
     } else {
-        sync::synchronize(&db, &client, &keystore_filter).await?;
+        active = endpoints::synchronize(
+            &db,
+            &endpoints,
+            &keystore_filter,
+            &indexers,
+            cli.sync_concurrency,
+        )
+        .await?;
+        endpoints::cross_check(&db, &endpoints, active).await?;
+        sync::reconcile_pending(&db, &endpoints[active].client).await?;
 
         log::info!(
             "Wallet database synchronized with node to height {:?}",
@@ -94,41 +148,89 @@ async fn main() -> anyhow::Result<()> {
         );
     }
 
+    // Discard old blocks if the user asked for a pruned database.
+    if let Some(depth) = cli.prune {
+        sync::prune(&db, depth, &indexers)?;
+    }
+
+    let sync_options = sync::SyncOptions {
+        sync_concurrency: cli.sync_concurrency,
+        min_confirmations: cli.min_confirmations,
+        prune: cli.prune,
+    };
+
+    let endpoint = &endpoints[active].url;
+    let client = &endpoints[active].client;
+
     // Dispatch to proper subcommand
     match cli.command {
-        Some(Command::AmoebaDemo) => amoeba::amoeba_demo(&client).await,
+        Some(Command::AmoebaDemo) => amoeba::amoeba_demo(client, endpoint).await,
         // Command::MultiSigDemo => multi_sig::multi_sig_demo(&client).await,
         Some(Command::VerifyCoin { output_ref }) => {
-            println!("Details of coin {}:", hex::encode(output_ref.encode()));
-
-            // Print the details from storage
             let (coin_from_storage, verifier_from_storage) =
-                money::get_coin_from_storage(&output_ref, &client).await?;
-            print!("Found in storage.  Value: {}, ", coin_from_storage.0);
-            pretty_print_verifier(&verifier_from_storage);
-
-            // Print the details from the local db
-            match sync::get_unspent(&db, &output_ref)? {
-                Some((owner, amount)) => {
-                    println!("Found in local db. Value: {amount}, owned by {owner}");
-                }
-                None => {
-                    println!("Not found in local db");
+                money::get_coin_from_storage(&output_ref, client).await?;
+            let local = sync::get_unspent(&db, &output_ref)?;
+
+            if cli.output == OutputFormat::Json {
+                print_json(serde_json::json!({
+                    "output_ref": hex::encode(output_ref.encode()),
+                    "storage": {
+                        "value": coin_from_storage.0,
+                        "verifier": verifier_from_storage,
+                    },
+                    "local": local.map(|(owner, amount, creation_height, asset_id)| serde_json::json!({
+                        "asset_id": asset_id,
+                        "value": amount,
+                        "owner": owner,
+                        "height": creation_height,
+                    })),
+                }));
+            } else {
+                println!("Details of coin {}:", hex::encode(output_ref.encode()));
+
+                // Print the details from storage
+                print!("Found in storage.  Value: {}, ", coin_from_storage.0);
+                pretty_print_verifier(&verifier_from_storage);
+
+                // Print the details from the local db
+                match local {
+                    Some((owner, amount, creation_height, asset_id)) => {
+                        println!("Found in local db. Asset: {asset_id}, Value: {amount}, owned by {owner}, created at height {creation_height}");
+                    }
+                    None => {
+                        println!("Not found in local db");
+                    }
                 }
             }
 
             Ok(())
         }
-        Some(Command::SpendCoins(args)) => money::spend_coins(&db, &client, &keystore, args).await,
+        Some(Command::SpendCoins(args)) => {
+            money::spend_coins(
+                &db,
+                client,
+                endpoint,
+                &keystore,
+                args,
+                cli.min_confirmations,
+                cli.output,
+            )
+            .await
+        }
         Some(Command::InsertKey { seed }) => crate::keystore::insert_key(&keystore, &seed),
         Some(Command::GenerateKey { password }) => {
             crate::keystore::generate_key(&keystore, password)?;
             Ok(())
         }
         Some(Command::ShowKeys) => {
-            crate::keystore::get_keys(&keystore)?.for_each(|pubkey| {
-                println!("key: 0x{}", hex::encode(pubkey));
-            });
+            let keys = crate::keystore::get_keys(&keystore)?
+                .map(|pubkey| format!("0x{}", hex::encode(pubkey)));
+
+            if cli.output == OutputFormat::Json {
+                print_json(serde_json::json!(keys.collect::<Vec<_>>()));
+            } else {
+                keys.for_each(|key| println!("key: {key}"));
+            }
 
             Ok(())
         }
@@ -148,24 +250,188 @@ async fn main() -> anyhow::Result<()> {
             }
         }
         Some(Command::ShowBalance) => {
-            println!("Balance Summary");
-            let mut total = 0;
-            let balances = sync::get_balances(&db)?;
-            for (account, balance) in balances {
-                total += balance;
-                println!("{account}: {balance}");
+            let balances: Vec<_> = sync::get_balances(&db, cli.min_confirmations)?.collect();
+            let incoming: Vec<_> = sync::get_unconfirmed_incoming(&db, &keystore_filter)?.collect();
+
+            if cli.output == OutputFormat::Json {
+                print_json(serde_json::json!({
+                    "balances": balances
+                        .iter()
+                        .map(|((account, asset_id), (balance, pending))| serde_json::json!({
+                            "account": account,
+                            "asset_id": asset_id,
+                            "spendable": balance,
+                            "pending": pending,
+                        }))
+                        .collect::<Vec<_>>(),
+                    "unconfirmed_incoming": incoming
+                        .iter()
+                        .map(|((account, asset_id), amount)| serde_json::json!({
+                            "account": account,
+                            "asset_id": asset_id,
+                            "amount": amount,
+                        }))
+                        .collect::<Vec<_>>(),
+                }));
+            } else {
+                // Totals are only meaningful within a single asset.
+                let mut totals = std::collections::BTreeMap::<u8, (u128, u128, u128)>::new();
+
+                println!("Balance Summary");
+                for ((account, asset_id), (balance, pending)) in balances {
+                    let (total, total_pending, _) = totals.entry(asset_id).or_default();
+                    *total += balance;
+                    *total_pending += pending;
+                    let account = display_account(&db, &account)?;
+                    println!("{account} asset {asset_id}: {balance} (pending: {pending})");
+                }
+
+                for ((account, asset_id), amount) in incoming {
+                    let (_, _, total_incoming) = totals.entry(asset_id).or_default();
+                    *total_incoming += amount;
+                    let account = display_account(&db, &account)?;
+                    println!("{account} asset {asset_id}: {amount} unconfirmed incoming");
+                }
+
+                println!("--------------------");
+                for (asset_id, (total, total_pending, total_incoming)) in totals {
+                    println!("asset {asset_id} total: {total} (pending: {total_pending}, unconfirmed: {total_incoming})");
+                }
             }
-            println!("--------------------");
-            println!("total      : {total}");
 
             Ok(())
         }
         Some(Command::ShowAllOutputs) => {
-            println!("###### Unspent outputs ###########");
-            sync::print_unspent_tree(&db)?;
+            if cli.output == OutputFormat::Json {
+                let outputs = sync::get_unspent_outputs(&db)?
+                    .into_iter()
+                    .map(|(output_ref, owner, amount, creation_height, asset_id)| {
+                        serde_json::json!({
+                            "output_ref": hex::encode(output_ref.encode()),
+                            "owner": owner,
+                            "asset_id": asset_id,
+                            "amount": amount,
+                            "height": creation_height,
+                        })
+                    })
+                    .collect::<Vec<_>>();
+                let kitties = indexer::get_kitties(&db)?
+                    .into_iter()
+                    .map(|(output_ref, owner, kitty)| {
+                        serde_json::json!({
+                            "output_ref": hex::encode(output_ref.encode()),
+                            "owner": owner,
+                            "kitty": kitty,
+                        })
+                    })
+                    .collect::<Vec<_>>();
+                print_json(serde_json::json!({
+                    "coins": outputs,
+                    "kitties": kitties,
+                }));
+            } else {
+                println!("###### Unspent outputs ###########");
+                sync::print_unspent_tree(&db)?;
+
+                println!("###### Kitties ###########");
+                for (output_ref, owner, kitty) in indexer::get_kitties(&db)? {
+                    println!(
+                        "{}: owner {owner:?}, dna {:?}",
+                        hex::encode(output_ref.encode()),
+                        kitty.dna.0
+                    );
+                }
+            }
 
             Ok(())
         }
+        Some(Command::Address(AddressCommand::Add { label, pub_key })) => {
+            address_book::add(&db, &label, pub_key)
+        }
+        Some(Command::Address(AddressCommand::Remove { label })) => {
+            if !address_book::remove(&db, &label)? {
+                println!("No address book entry labelled {label}");
+            }
+            Ok(())
+        }
+        Some(Command::Address(AddressCommand::List)) => {
+            let entries = address_book::list(&db)?;
+            if cli.output == OutputFormat::Json {
+                print_json(serde_json::json!(entries
+                    .iter()
+                    .map(|(label, pubkey)| serde_json::json!({
+                        "label": label,
+                        "pubkey": pubkey,
+                    }))
+                    .collect::<Vec<_>>()));
+            } else {
+                for (label, pubkey) in entries {
+                    println!("{label}: {pubkey:?}");
+                }
+            }
+            Ok(())
+        }
+        Some(Command::EstimateTip) => money::estimate_tip(client, cli.output).await,
+        Some(Command::ExportCheckpoint { file, signer }) => {
+            checkpoint::export_checkpoint(&db, client, &keystore, signer, &file).await
+        }
+        Some(Command::Backup { file }) => backup::backup(&db, &keystore_path, &file),
+        Some(Command::Restore { file }) => backup::restore(&db, &keystore_path, &file),
+        Some(Command::Pstt(PsttCommand::Create(args))) => pstt::create(&db, client, args).await,
+        Some(Command::Pstt(PsttCommand::Update { file })) => pstt::update(client, &file).await,
+        Some(Command::Pstt(PsttCommand::Sign { file })) => pstt::sign(&keystore, &file),
+        Some(Command::Pstt(PsttCommand::Combine { files, into })) => pstt::combine(&files, &into),
+        Some(Command::Pstt(PsttCommand::Show { file })) => pstt::show(&file, cli.output),
+        Some(Command::Pstt(PsttCommand::Finalize { file })) => {
+            pstt::finalize(&db, endpoint, &file, cli.output).await
+        }
+        Some(Command::Coinjoin(args)) => {
+            coinjoin::coinjoin(
+                &db,
+                client,
+                endpoint,
+                &keystore,
+                args,
+                cli.min_confirmations,
+                cli.output,
+            )
+            .await
+        }
+        Some(Command::VerifyTx(args)) => verify_tx::verify_tx(client, args, cli.output).await,
+        Some(Command::Sync(args)) if args.follow => {
+            follow::follow(
+                &db,
+                client,
+                &keystore,
+                &keystore_filter,
+                &indexers,
+                &sync_options,
+                args,
+            )
+            .await
+        }
+        Some(Command::Sync(_)) => {
+            let height = sync::height(&db)?.unwrap_or_default();
+            if cli.output == OutputFormat::Json {
+                print_json(serde_json::json!({ "height": height }));
+            } else {
+                println!("Wallet is synchronized to height {height}");
+            }
+            Ok(())
+        }
+        Some(Command::Export(args)) => history::export(&db, args),
+        Some(Command::Tui) => {
+            tui::run(
+                &db,
+                client,
+                endpoint,
+                &keystore,
+                &keystore_filter,
+                &indexers,
+                sync_options,
+            )
+            .await
+        }
         None => {
             log::info!("No Wallet Command invoked. Exiting.");
             Ok(())
@@ -208,11 +474,7 @@ fn output_ref_from_string(s: &str) -> Result<OutputRef, clap::Error> {
 
 /// Takes a string and checks for a 0x prefix. Returns a string without a 0x prefix.
 fn strip_0x_prefix(s: &str) -> &str {
-    if &s[..2] == "0x" {
-        &s[2..]
-    } else {
-        s
-    }
+    s.strip_prefix("0x").unwrap_or(s)
 }
 
 /// Generate a plaform-specific temporary directory for the wallet
@@ -240,6 +502,22 @@ fn default_data_path() -> PathBuf {
         .into()
 }
 
+/// Utility to print a JSON value to stdout for the `--output json` mode
+pub(crate) fn print_json(value: serde_json::Value) {
+    println!(
+        "{}",
+        serde_json::to_string_pretty(&value).expect("JSON values always serialize")
+    );
+}
+
+/// Utility to display an account along with its address book label, if it has one
+fn display_account(db: &sled::Db, account: &H256) -> anyhow::Result<String> {
+    Ok(match address_book::label_of(db, account)? {
+        Some(label) => format!("{account} ({label})"),
+        None => format!("{account}"),
+    })
+}
+
 /// Utility to pretty print an outer verifier
 pub fn pretty_print_verifier(v: &OuterVerifier) {
     match v {
@@ -258,5 +536,19 @@ pub fn pretty_print_verifier(v: &OuterVerifier) {
                 string_sigs, multi_sig.threshold
             );
         }
+        OuterVerifier::Ed25519SigCheck(sig_check) => {
+            println! {"owned by ed25519 key {}", sig_check.owner_pubkey}
+        }
+        OuterVerifier::Proxy(proxy) => {
+            let delegates: Vec<_> = proxy
+                .delegates
+                .iter()
+                .map(|delegate| format!("{} ({:?})", delegate.pubkey, delegate.scope))
+                .collect();
+            println!(
+                "owned by {} through a proxy, with delegates {:?}",
+                proxy.owner, delegates
+            );
+        }
     }
 }
@@ -1,47 +1,128 @@
 //! Wallet features related to spending money and checking balances.
 
-use crate::{cli::SpendArgs, rpc::fetch_storage, sync};
+use crate::{
+    cli::{OutputFormat, SpendArgs},
+    rpc::{fetch_storage, node_get_signing_domain, node_submit_and_watch},
+    sync,
+};
 
 use anyhow::anyhow;
-use jsonrpsee::{core::client::ClientT, http_client::HttpClient, rpc_params};
+use jsonrpsee::http_client::HttpClient;
 use parity_scale_codec::Encode;
 use runtime::{
     money::{Coin, MoneyConstraintChecker},
-    OuterConstraintChecker, OuterVerifier, Transaction,
+    DelegateScope, OuterConstraintChecker, OuterVerifier, Transaction,
 };
 use sc_keystore::LocalKeystore;
 use sled::Db;
-use sp_core::sr25519::Public;
+use sp_core::{ed25519, sr25519::Public, H256};
 use sp_runtime::traits::{BlakeTwo256, Hash};
 use tuxedo_core::{
-    types::{Input, Output, OutputRef},
-    verifier::SigCheck,
+    types::{Input, InputMode, Output, OutputRef, TransactionStatus, TransactionVersion},
+    verifier::{
+        Ed25519SigCheck, Proxy, ProxyRedeemer, SigCheck, SignatureAndIndex, ThresholdMultiSignature,
+    },
 };
 
-/// Create and send a transaction that spends coins on the network
+/// Create and send a transaction that spends coins on the network, and print the coins it creates
 pub async fn spend_coins(
     db: &Db,
     client: &HttpClient,
+    endpoint: &str,
     keystore: &LocalKeystore,
     args: SpendArgs,
+    min_confirmations: u32,
+    format: OutputFormat,
 ) -> anyhow::Result<()> {
+    let receipt = submit_spend(
+        db,
+        client,
+        endpoint,
+        keystore,
+        args,
+        min_confirmations,
+        |status| {
+            if format == OutputFormat::Text {
+                println!("Transaction status: {status:?}");
+            }
+        },
+    )
+    .await?;
+
+    // Print new output refs for user to check later
+    let mut created = Vec::new();
+    for (new_coin_ref, amount, verifier) in &receipt.outputs {
+        if format == OutputFormat::Json {
+            created.push(serde_json::json!({
+                "output_ref": hex::encode(new_coin_ref.encode()),
+                "amount": amount,
+                "verifier": verifier,
+            }));
+        } else {
+            print!(
+                "Created {:?} worth {amount}. ",
+                hex::encode(new_coin_ref.encode())
+            );
+            crate::pretty_print_verifier(verifier);
+        }
+    }
+
+    if format == OutputFormat::Json {
+        crate::print_json(serde_json::json!({
+            "tx_hash": receipt.tx_hash,
+            "accepted": receipt.accepted,
+            "outputs": created,
+        }));
+    }
+
+    Ok(())
+}
+
+/// The outcome of a spend transaction that was submitted to the node
+pub struct SpendReceipt {
+    /// The hash of the submitted transaction.
+    pub tx_hash: H256,
+    /// Whether the node accepted the transaction into its pool.
+    pub accepted: bool,
+    /// The coins that the transaction creates, along with their values and verifiers.
+    pub outputs: Vec<(OutputRef, u128, OuterVerifier)>,
+}
+
+/// Create and send a transaction that spends coins on the network without printing anything.
+///
+/// When `args.watch` is set, each status that the node reports is passed to `on_status`
+/// until the transaction is finalized or dropped.
+pub async fn submit_spend(
+    db: &Db,
+    client: &HttpClient,
+    endpoint: &str,
+    keystore: &LocalKeystore,
+    args: SpendArgs,
+    min_confirmations: u32,
+    mut on_status: impl FnMut(&TransactionStatus),
+) -> anyhow::Result<SpendReceipt> {
     log::debug!("The args are:: {:?}", args);
 
     // Construct a template Transaction to push coins into later
     let mut transaction = Transaction {
+        version: TransactionVersion::LATEST,
         inputs: Vec::new(),
         peeks: Vec::new(),
         outputs: Vec::new(),
         checker: OuterConstraintChecker::Money(MoneyConstraintChecker::Spend),
+        memo: args.memo.map(String::into_bytes),
+        valid_until: None,
     };
 
+    let recipient = crate::address_book::resolve(db, &args.recipient)?;
+
     // Construct each output and then push to the transactions
     let mut total_output_amount = 0;
     for amount in &args.output_amount {
         let output = Output {
             payload: Coin::<0>::new(*amount).into(),
             verifier: OuterVerifier::SigCheck(SigCheck {
-                owner_pubkey: args.recipient,
+                owner_pubkey: recipient,
             }),
         };
         total_output_amount += amount;
@@ -53,26 +134,62 @@ pub async fn spend_coins(
     let mut total_input_amount = 0;
     let mut all_input_refs = args.input;
     for output_ref in &all_input_refs {
-        let (_owner_pubkey, amount) = sync::get_unspent(db, output_ref)?.ok_or(anyhow!(
-            "user-specified output ref not found in local database"
-        ))?;
+        let (_owner_pubkey, amount, _creation_height, asset_id) =
+            sync::get_unspent(db, output_ref)?.ok_or(anyhow!(
+                "user-specified output ref not found in local database"
+            ))?;
+        if asset_id != 0 {
+            return Err(anyhow!(
+                "user-specified output ref is not a coin of asset 0"
+            ));
+        }
         total_input_amount += amount;
     }
     //TODO filtering on a specific sender
 
+    // When a tip is requested, the inputs must cover it too.
+    let required_input_amount = total_output_amount + args.tip.unwrap_or_default();
+
     // If the supplied inputs are not valuable enough to cover the output amount
     // we select the rest arbitrarily from the local db. (In many cases, this will be all the inputs.)
-    if total_input_amount < total_output_amount {
-        match sync::get_arbitrary_unspent_set(db, total_output_amount - total_input_amount)? {
+    if total_input_amount < required_input_amount {
+        match sync::get_arbitrary_unspent_set(
+            db,
+            required_input_amount - total_input_amount,
+            0,
+            min_confirmations,
+        )? {
             Some(more_inputs) => {
+                for output_ref in &more_inputs {
+                    let (_owner_pubkey, amount, _creation_height, _asset_id) =
+                        sync::get_unspent(db, output_ref)?
+                            .ok_or(anyhow!("selected output ref not found in local database"))?;
+                    total_input_amount += amount;
+                }
                 all_input_refs.extend(more_inputs);
             }
             None => Err(anyhow!(
-                "Not enough value in database to construct transaction"
+                "Not enough confirmed value in database to construct transaction"
             ))?,
         }
     }
 
+    // With an explicit tip, only the tip is left over and the rest comes back to us as change.
+    // Without one, the entire surplus is left over and goes to the block author.
+    if let Some(tip) = args.tip {
+        let change = total_input_amount - total_output_amount - tip;
+        if change > 0 {
+            let (change_owner, _, _, _) = sync::get_unspent(db, &all_input_refs[0])?
+                .ok_or(anyhow!("input not found in local database"))?;
+            transaction.outputs.push(Output {
+                payload: Coin::<0>::new(change).into(),
+                verifier: OuterVerifier::SigCheck(SigCheck {
+                    owner_pubkey: change_owner,
+                }),
+            });
+        }
+    }
+
     // Make sure each input decodes and is still present in the node's storage,
     // and then push to transaction.
     for output_ref in &all_input_refs {
@@ -80,11 +197,14 @@ pub async fn spend_coins(
         transaction.inputs.push(Input {
             output_ref: output_ref.clone(),
             redeemer: vec![], // We will sign the total transaction so this should be empty
+            mode: InputMode::Consume,
         });
     }
 
-    // Keep a copy of the stripped encoded transaction for signing purposes
-    let stripped_encoded_transaction = transaction.clone().encode();
+    // Keep a copy of the stripped encoded transaction for signing purposes.
+    // It commits to the chain and runtime version, so fetch those from the node.
+    let domain = node_get_signing_domain(client).await?;
+    let stripped_encoded_transaction = transaction.signing_payload(&domain);
 
     // Iterate back through the inputs, signing, and putting the signatures in place.
     for input in &mut transaction.inputs {
@@ -98,40 +218,124 @@ pub async fn spend_coins(
                 crate::keystore::sign_with(keystore, &public, &stripped_encoded_transaction)?
             }
             OuterVerifier::UpForGrabs(_) => Vec::new(),
-            OuterVerifier::ThresholdMultiSignature(_) => todo!(),
+            OuterVerifier::ThresholdMultiSignature(multi_sig) => {
+                threshold_redeemer(keystore, &multi_sig, &stripped_encoded_transaction)?
+            }
+            OuterVerifier::Ed25519SigCheck(Ed25519SigCheck { owner_pubkey }) => {
+                let public = ed25519::Public::from_raw(owner_pubkey.0);
+                crate::keystore::ed25519_sign_with(
+                    keystore,
+                    &public,
+                    &stripped_encoded_transaction,
+                )?
+            }
+            OuterVerifier::Proxy(proxy) => {
+                proxy_redeemer(keystore, &proxy, &stripped_encoded_transaction)?
+            }
         };
 
         // insert the proof
         input.redeemer = redeemer;
     }
 
-    // Send the transaction
-    let genesis_spend_hex = hex::encode(transaction.encode());
-    let params = rpc_params![genesis_spend_hex];
-    let genesis_spend_response: Result<String, _> =
-        client.request("author_submitExtrinsic", params).await;
-    log::info!(
-        "Node's response to spend transaction: {:?}",
-        genesis_spend_response
-    );
+    // Send the transaction, and follow it for as long as the user asked to.
+    let watch = args.watch;
+    let status = node_submit_and_watch(endpoint, &transaction, |status| {
+        if watch {
+            on_status(status);
+        }
+        !watch
+    })
+    .await;
+    log::info!("Node's response to spend transaction: {:?}", status);
+    let accepted =
+        matches!(&status, Ok(status) if !matches!(status, TransactionStatus::Dropped { .. }));
+
+    // Remember the transaction so its inputs aren't double spent before it lands in a block.
+    if accepted {
+        sync::add_pending_transaction(db, &transaction)?;
+    }
 
-    // Print new output refs for user to check later
     let tx_hash = <BlakeTwo256 as Hash>::hash_of(&transaction.encode());
-    for (i, output) in transaction.outputs.iter().enumerate() {
+    let mut outputs = Vec::new();
+    for (i, output) in transaction.outputs.into_iter().enumerate() {
         let new_coin_ref = OutputRef {
             tx_hash,
             index: i as u32,
         };
         let amount = output.payload.extract::<Coin<0>>()?.0;
+        outputs.push((new_coin_ref, amount, output.verifier));
+    }
 
-        print!(
-            "Created {:?} worth {amount}. ",
-            hex::encode(new_coin_ref.encode())
-        );
-        crate::pretty_print_verifier(&output.verifier);
+    Ok(SpendReceipt {
+        tx_hash,
+        accepted,
+        outputs,
+    })
+}
+
+/// Sign for a coin held by a threshold multisignature with every signatory key in the keystore.
+///
+/// The other signatories can not add their signatures to a transaction that this wallet submits, so
+/// the keystore must hold at least as many of the keys as the threshold.
+fn threshold_redeemer(
+    keystore: &LocalKeystore,
+    multi_sig: &ThresholdMultiSignature,
+    message: &[u8],
+) -> anyhow::Result<Vec<u8>> {
+    let mut sigs = Vec::new();
+    for (index, signatory) in multi_sig.signatories.iter().enumerate() {
+        if !crate::keystore::has_key(keystore, signatory) {
+            continue;
+        }
+        let public = Public::from_h256(*signatory);
+        sigs.push(SignatureAndIndex {
+            signature: crate::keystore::signature_with(keystore, &public, message)?,
+            index: u8::try_from(index)?,
+        });
     }
 
-    Ok(())
+    if sigs.len() < multi_sig.threshold.into() {
+        return Err(anyhow!(
+            "The keystore holds {} of the signatories' keys, but {} signatures are needed",
+            sigs.len(),
+            multi_sig.threshold
+        ));
+    }
+    Ok(sigs.encode())
+}
+
+/// Sign for a coin held by a proxy with whichever of its keys the keystore holds.
+///
+/// The owner's key is preferred, because it may sign anything. Otherwise the first delegate in the
+/// keystore signs, and the node decides whether the transaction is within that delegate's scope.
+fn proxy_redeemer(
+    keystore: &LocalKeystore,
+    proxy: &Proxy<DelegateScope>,
+    message: &[u8],
+) -> anyhow::Result<Vec<u8>> {
+    if crate::keystore::has_key(keystore, &proxy.owner) {
+        let public = Public::from_h256(proxy.owner);
+        let signature = crate::keystore::signature_with(keystore, &public, message)?;
+        return Ok(ProxyRedeemer::Owner(signature).encode());
+    }
+
+    let (index, delegate) = proxy
+        .delegates
+        .iter()
+        .enumerate()
+        .find(|(_, delegate)| crate::keystore::has_key(keystore, &delegate.pubkey))
+        .ok_or(anyhow!(
+            "Neither the owner nor any delegate of proxy {} is in the keystore",
+            proxy.owner
+        ))?;
+    let public = Public::from_h256(delegate.pubkey);
+    let signature = crate::keystore::signature_with(keystore, &public, message)?;
+    Ok(ProxyRedeemer::Delegate {
+        index: u8::try_from(index)?,
+        signature,
+    }
+    .encode())
 }
 
 /// Given an output ref, fetch the details about this coin from the node's
@@ -145,3 +349,71 @@ pub async fn get_coin_from_storage(
 
     Ok((coin_in_storage, utxo.verifier))
 }
+
+/// Suggest a tip for a spend transaction by looking at the money transactions currently
+/// waiting in the node's pool.
+///
+/// Transactions are prioritized by the tip they pay for each unit of weight they use. In the
+/// Money piece, the tip is the surplus of a transaction's inputs over its outputs, less its fee.
+/// Spends of a similar size weigh about the same, so to be included promptly, a transaction should
+/// tip at least as much as the transactions it is competing with.
+pub async fn estimate_tip(client: &HttpClient, format: OutputFormat) -> anyhow::Result<()> {
+    let mut tips = Vec::new();
+
+    for tx in crate::rpc::node_get_pending_transactions(client).await? {
+        if !matches!(
+            tx.checker,
+            OuterConstraintChecker::Money(MoneyConstraintChecker::Spend)
+        ) {
+            continue;
+        }
+
+        let mut input_value = 0u128;
+        for input in &tx.inputs {
+            // The input may have been consumed since we fetched the pool. Just skip the transaction.
+            let Ok((coin, _)) = get_coin_from_storage(&input.output_ref, client).await else {
+                input_value = 0;
+                break;
+            };
+            input_value = input_value.saturating_add(coin.0);
+        }
+
+        let output_value = tx
+            .outputs
+            .iter()
+            .filter_map(|o| o.payload.extract::<Coin<0>>().ok())
+            .fold(0u128, |acc, coin| acc.saturating_add(coin.0));
+
+        if input_value >= output_value && input_value > 0 {
+            tips.push(input_value - output_value);
+        }
+    }
+
+    tips.sort_unstable();
+    let suggested = tips.last().map_or(0, |max| max.saturating_add(1));
+
+    if format == OutputFormat::Json {
+        crate::print_json(serde_json::json!({
+            "pending": tips.len(),
+            "min": tips.first(),
+            "median": tips.get(tips.len() / 2),
+            "max": tips.last(),
+            "suggested": suggested,
+        }));
+    } else if tips.is_empty() {
+        println!(
+            "No competing money transactions in the pool. A tip of 0 should be included promptly."
+        );
+    } else {
+        println!("Pending money transactions: {}", tips.len());
+        println!(
+            "Tips - min: {}, median: {}, max: {}",
+            tips[0],
+            tips[tips.len() / 2],
+            tips[tips.len() - 1]
+        );
+        println!("Suggested tip for timely inclusion: {suggested}");
+    }
+
+    Ok(())
+}
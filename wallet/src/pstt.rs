@@ -0,0 +1,267 @@
+//! Wallet features related to partially signed transactions (PSTTs).
+//!
+//! A PSTT lets several wallets sign one transaction, for example to spend coins owned by a
+//! multisig. One wallet creates it, each signer signs their own copy, and any wallet combines
+//! the copies and finalizes the transaction once it has enough signatures.
+
+use crate::{
+    cli::{CreatePsttArgs, OutputFormat},
+    rpc::{fetch_storage, node_get_signing_domain, node_submit_and_watch},
+    sync,
+};
+
+use anyhow::anyhow;
+use jsonrpsee::http_client::HttpClient;
+use parity_scale_codec::Encode;
+use runtime::{
+    money::{Coin, MoneyConstraintChecker},
+    OuterConstraintChecker, OuterVerifier,
+};
+use sc_keystore::LocalKeystore;
+use sled::Db;
+use sp_core::sr25519::Public;
+use sp_runtime::traits::{BlakeTwo256, Hash};
+use std::path::Path;
+use tuxedo_core::{
+    pstt::{PartiallySignedTransaction, SignatureRedeemer},
+    transaction_builder::TransactionBuilder,
+    types::TransactionStatus,
+    verifier::SigCheck,
+};
+
+/// A partially signed transaction of the template runtime.
+pub type Pstt = PartiallySignedTransaction<OuterVerifier, OuterConstraintChecker>;
+
+/// Read a PSTT from the given file.
+pub fn read(file: &Path) -> anyhow::Result<Pstt> {
+    Pstt::from_bytes(&std::fs::read(file)?)
+        .map_err(|e| anyhow!("{} is not a valid PSTT: {e:?}", file.display()))
+}
+
+/// Write a PSTT to the given file, replacing anything that was there.
+pub fn write(pstt: &Pstt, file: &Path) -> anyhow::Result<()> {
+    std::fs::write(file, pstt.to_bytes())?;
+    Ok(())
+}
+
+/// Create a PSTT that spends the given coins, and write it to a file for the signers.
+///
+/// Unlike `spend-coins`, the inputs are not chosen from the local database, because they are
+/// usually owned by keys that this wallet does not track. The inputs are resolved right away.
+pub async fn create(db: &Db, client: &HttpClient, args: CreatePsttArgs) -> anyhow::Result<()> {
+    if args.input.is_empty() {
+        return Err(anyhow!("a PSTT needs at least one input"));
+    }
+
+    let recipient = crate::address_book::resolve(db, &args.recipient)?;
+
+    let mut builder = TransactionBuilder::<OuterVerifier, _>::new(OuterConstraintChecker::Money(
+        MoneyConstraintChecker::Spend,
+    ));
+    for output_ref in args.input {
+        builder = builder.input(output_ref, Vec::new());
+    }
+    for amount in args.output_amount {
+        builder = builder.output(Coin::<0>::new(amount), SigCheck::new(recipient));
+    }
+
+    let domain = node_get_signing_domain(client).await?;
+    let mut pstt = Pstt::new(builder.build(), domain);
+    resolve_inputs(&mut pstt, client).await?;
+
+    write(&pstt, &args.file)?;
+    println!("Created PSTT at {}", args.file.display());
+    Ok(())
+}
+
+/// Resolve the inputs of the PSTT in the given file that are not resolved yet, from the node's storage.
+pub async fn update(client: &HttpClient, file: &Path) -> anyhow::Result<()> {
+    let mut pstt = read(file)?;
+    let resolved = resolve_inputs(&mut pstt, client).await?;
+    write(&pstt, file)?;
+
+    println!("Resolved {resolved} inputs");
+    Ok(())
+}
+
+/// Sign every input of the PSTT in the given file that a key in the keystore is a signer of.
+///
+/// Signing does not read the chain, so it only covers inputs that were already resolved.
+pub fn sign(keystore: &LocalKeystore, file: &Path) -> anyhow::Result<()> {
+    let mut pstt = read(file)?;
+    let signatures = sign_inputs(keystore, &mut pstt)?;
+    write(&pstt, file)?;
+
+    println!("Added {signatures} signatures");
+    Ok(())
+}
+
+/// Sign every resolved input of the PSTT that a key in the keystore is a signer of.
+/// Returns how many signatures were added.
+pub(crate) fn sign_inputs(keystore: &LocalKeystore, pstt: &mut Pstt) -> anyhow::Result<usize> {
+    let payload = pstt.signing_payload();
+
+    let mut signatures = 0;
+    for index in 0..pstt.inputs.len() {
+        let Some(utxo) = pstt.inputs[index].utxo.clone() else {
+            log::warn!("Input {index} is not resolved, so it can not be signed yet");
+            continue;
+        };
+
+        for signer in utxo.verifier.signers() {
+            if !crate::keystore::has_key(keystore, &signer) {
+                continue;
+            }
+            let signature =
+                crate::keystore::sign_with(keystore, &Public::from_h256(signer), &payload)?;
+            pstt.add_signature(index, signer, signature)
+                .map_err(|e| anyhow!("{e:?}"))?;
+            signatures += 1;
+        }
+    }
+
+    Ok(signatures)
+}
+
+/// Combine the PSTTs in the given files, which must all be for the same transaction,
+/// and write the result to another file.
+pub fn combine(files: &[std::path::PathBuf], into: &Path) -> anyhow::Result<()> {
+    let (first, rest) = files
+        .split_first()
+        .ok_or(anyhow!("there are no PSTTs to combine"))?;
+
+    let mut pstt = read(first)?;
+    for file in rest {
+        pstt.combine(read(file)?)
+            .map_err(|e| anyhow!("can not combine {}: {e:?}", file.display()))?;
+    }
+    write(&pstt, into)?;
+
+    let complete = if pstt.is_complete() {
+        "it is ready to be finalized"
+    } else {
+        "it still needs more signatures"
+    };
+    println!(
+        "Combined {} PSTTs into {}, {complete}",
+        files.len(),
+        into.display()
+    );
+    Ok(())
+}
+
+/// Show what the PSTT in the given file spends and creates, and who has signed it.
+pub fn show(file: &Path, format: OutputFormat) -> anyhow::Result<()> {
+    let pstt = read(file)?;
+
+    if format == OutputFormat::Json {
+        crate::print_json(serde_json::json!({
+            "domain": pstt.domain,
+            "complete": pstt.is_complete(),
+            "inputs": pstt
+                .transaction
+                .inputs
+                .iter()
+                .zip(&pstt.inputs)
+                .map(|(input, metadata)| serde_json::json!({
+                    "output_ref": hex::encode(input.output_ref.encode()),
+                    "verifier": metadata.utxo.as_ref().map(|utxo| &utxo.verifier),
+                    "value": metadata.utxo.as_ref().and_then(|utxo| utxo.payload.extract::<Coin<0>>().ok()).map(|coin| coin.0),
+                    "signers": metadata.signatures.keys().collect::<Vec<_>>(),
+                    "has_redeemer": metadata.redeemer.is_some(),
+                }))
+                .collect::<Vec<_>>(),
+            "outputs": pstt
+                .transaction
+                .outputs
+                .iter()
+                .map(|output| serde_json::json!({
+                    "value": output.payload.extract::<Coin<0>>().ok().map(|coin| coin.0),
+                    "verifier": output.verifier,
+                }))
+                .collect::<Vec<_>>(),
+        }));
+        return Ok(());
+    }
+
+    println!("###### Inputs ###########");
+    for (input, metadata) in pstt.transaction.inputs.iter().zip(&pstt.inputs) {
+        print!("{}: ", hex::encode(input.output_ref.encode()));
+        match &metadata.utxo {
+            Some(utxo) => {
+                if let Ok(coin) = utxo.payload.extract::<Coin<0>>() {
+                    print!("worth {} ", coin.0);
+                }
+                crate::pretty_print_verifier(&utxo.verifier);
+            }
+            None => println!("not resolved"),
+        }
+        for signer in metadata.signatures.keys() {
+            println!("    signed by {signer}");
+        }
+    }
+
+    println!("###### Outputs ###########");
+    for output in &pstt.transaction.outputs {
+        if let Ok(coin) = output.payload.extract::<Coin<0>>() {
+            print!("worth {} ", coin.0);
+        }
+        crate::pretty_print_verifier(&output.verifier);
+    }
+
+    if pstt.is_complete() {
+        println!("The PSTT is ready to be finalized");
+    } else {
+        println!("The PSTT still needs more signatures");
+    }
+    Ok(())
+}
+
+/// Finalize the PSTT in the given file, and submit the transaction to the node.
+pub async fn finalize(
+    db: &Db,
+    endpoint: &str,
+    file: &Path,
+    format: OutputFormat,
+) -> anyhow::Result<()> {
+    let transaction = read(file)?
+        .finalize()
+        .map_err(|e| anyhow!("can not finalize the PSTT yet: {e:?}"))?;
+
+    let status = node_submit_and_watch(endpoint, &transaction, |_| true).await;
+    log::info!("Node's response to the finalized transaction: {:?}", status);
+    let accepted =
+        matches!(&status, Ok(status) if !matches!(status, TransactionStatus::Dropped { .. }));
+
+    // Remember the transaction so its inputs aren't double spent before it lands in a block.
+    if accepted {
+        sync::add_pending_transaction(db, &transaction)?;
+    }
+
+    let tx_hash = <BlakeTwo256 as Hash>::hash_of(&transaction.encode());
+    if format == OutputFormat::Json {
+        crate::print_json(serde_json::json!({
+            "tx_hash": tx_hash,
+            "accepted": accepted,
+        }));
+    } else if accepted {
+        println!("Submitted transaction {tx_hash:?}");
+    } else {
+        println!("The node did not accept transaction {tx_hash:?}");
+    }
+
+    Ok(())
+}
+
+/// Resolve every unresolved input of the PSTT from the node's storage.
+/// Returns how many inputs were resolved.
+pub(crate) async fn resolve_inputs(pstt: &mut Pstt, client: &HttpClient) -> anyhow::Result<usize> {
+    let unresolved = pstt.unresolved_inputs();
+    for (index, output_ref) in &unresolved {
+        let utxo = fetch_storage::<OuterVerifier>(output_ref, client).await?;
+        pstt.resolve_input(*index, utxo)
+            .map_err(|e| anyhow!("{e:?}"))?;
+    }
+
+    Ok(unresolved.len())
+}
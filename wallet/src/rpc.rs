@@ -3,13 +3,25 @@
 
 use crate::strip_0x_prefix;
 use anyhow::anyhow;
-use jsonrpsee::{core::client::ClientT, http_client::HttpClient, rpc_params};
+use futures::StreamExt;
+use jsonrpsee::{
+    core::client::{ClientT, SubscriptionClientT},
+    http_client::HttpClient,
+    rpc_params,
+    ws_client::WsClientBuilder,
+};
 use parity_scale_codec::{Decode, Encode};
-use runtime::{opaque::Block as OpaqueBlock, Block};
+use runtime::{opaque::Block as OpaqueBlock, Block, Transaction};
 use sp_core::H256;
+use sp_runtime::{
+    traits::{BlakeTwo256, Hash},
+    transaction_validity::TransactionPriority,
+};
 use tuxedo_core::{
-    types::{Output, OutputRef},
-    Verifier,
+    types::{DetailedError, Output, OutputRef, SigningDomain, TransactionStatus},
+    utxo_set::utxo_child_info,
+    verifier::{VerifierContext, BEST_TIME_KEY},
+    Verifier, AWAITING_GENESIS_HASH_KEY, GENESIS_HASH_KEY,
 };
 
 /// Typed helper to get the Node's block hash at a particular height
@@ -20,6 +32,101 @@ pub async fn node_get_block_hash(height: u32, client: &HttpClient) -> anyhow::Re
     Ok(maybe_hash)
 }
 
+/// The height of a block, given the header that the node returned for it
+fn header_number(header: serde_json::Value) -> anyhow::Result<u32> {
+    let number = header
+        .get("number")
+        .and_then(serde_json::Value::as_str)
+        .ok_or(anyhow!("node returned a header without a number"))?;
+    Ok(u32::from_str_radix(strip_0x_prefix(number), 16)?)
+}
+
+/// Typed helper to get the height of the Node's best block
+pub async fn node_get_best_height(client: &HttpClient) -> anyhow::Result<u32> {
+    let header: serde_json::Value = client.request("chain_getHeader", rpc_params![]).await?;
+    header_number(header)
+}
+
+/// Typed helper to get the height of the Node's finalized block
+pub async fn node_get_finalized_height(client: &HttpClient) -> anyhow::Result<u32> {
+    let hash: String = client
+        .request("chain_getFinalizedHead", rpc_params![])
+        .await?;
+    let header: serde_json::Value = client.request("chain_getHeader", rpc_params![hash]).await?;
+    header_number(header)
+}
+
+/// Typed helper to get the genesis hash that the runtime signs transactions for.
+///
+/// This is the hash that the runtime noted in storage. Before the first block there is none yet,
+/// and the runtime takes the genesis block's hash. Chains that were past their first block before
+/// the runtime noted it have no record of it, and the runtime uses zero, so we do too.
+async fn node_get_genesis_hash(client: &HttpClient) -> anyhow::Result<H256> {
+    let params = rpc_params![hex::encode(GENESIS_HASH_KEY)];
+    let rpc_response: Option<String> = client.request("state_getStorage", params).await?;
+    if let Some(hex) = rpc_response {
+        return Ok(H256::decode(&mut &hex::decode(strip_0x_prefix(&hex))?[..])?);
+    }
+
+    let params = rpc_params![hex::encode(AWAITING_GENESIS_HASH_KEY)];
+    let rpc_response: Option<String> = client.request("state_getStorage", params).await?;
+    if rpc_response.is_none() {
+        return Ok(H256::zero());
+    }
+
+    node_get_block_hash(0, client)
+        .await?
+        .ok_or(anyhow!("node has no genesis block"))
+}
+
+/// Typed helper to get the chain and runtime that the node's transactions must be signed for
+pub async fn node_get_signing_domain(client: &HttpClient) -> anyhow::Result<SigningDomain> {
+    let genesis_hash = node_get_genesis_hash(client).await?;
+
+    let version: serde_json::Value = client
+        .request("state_getRuntimeVersion", rpc_params![])
+        .await?;
+    let field = |name: &str| {
+        version
+            .get(name)
+            .and_then(serde_json::Value::as_u64)
+            .map(|v| v as u32)
+            .ok_or(anyhow!("runtime version has no {name}"))
+    };
+
+    Ok(SigningDomain {
+        genesis_hash,
+        spec_version: field("specVersion")?,
+        transaction_version: field("transactionVersion")?,
+    })
+}
+
+/// Typed helper to get the context that the node checks verifiers in, outside of any block.
+/// Like the node's pool, this assumes the next block is the one after the best known time.
+pub async fn node_get_verifier_context(client: &HttpClient) -> anyhow::Result<VerifierContext> {
+    let params = rpc_params![hex::encode(BEST_TIME_KEY)];
+    let rpc_response: Option<String> = client.request("state_getStorage", params).await?;
+
+    let (timestamp, noted_in) = match rpc_response {
+        Some(hex) => <(u64, u32)>::decode(&mut &hex::decode(strip_0x_prefix(&hex))?[..])?,
+        None => (0, 0),
+    };
+    Ok(VerifierContext {
+        block_height: noted_in.saturating_add(1),
+        timestamp,
+    })
+}
+
+/// Typed helper to check a transaction against the node's best block without submitting it.
+/// Returns the priority it would have, or the reason that it would fail.
+pub async fn node_dry_run(
+    transaction: &Transaction,
+    client: &HttpClient,
+) -> anyhow::Result<Result<TransactionPriority, DetailedError>> {
+    let params = rpc_params![hex::encode(transaction.encode()), None::<H256>];
+    Ok(client.request("tuxedo_dryRun", params).await?)
+}
+
 /// Typed helper to get the node's full block at a particular hash
 pub async fn node_get_block(hash: H256, client: &HttpClient) -> anyhow::Result<Option<Block>> {
     let s = hex::encode(hash.0);
@@ -43,14 +150,42 @@ pub async fn node_get_block(hash: H256, client: &HttpClient) -> anyhow::Result<O
     Ok(Some(structured_block))
 }
 
-/// Fetch an output from chain storage given an OutputRef
+/// Typed helper to get the hashes of all extrinsics in the node's transaction pool
+pub async fn node_get_pending_extrinsic_hashes(client: &HttpClient) -> anyhow::Result<Vec<H256>> {
+    Ok(node_get_pending_transactions(client)
+        .await?
+        .iter()
+        .map(|tx| BlakeTwo256::hash_of(&tx.encode()))
+        .collect())
+}
+
+/// Typed helper to get all the transactions in the node's transaction pool
+pub async fn node_get_pending_transactions(
+    client: &HttpClient,
+) -> anyhow::Result<Vec<Transaction>> {
+    let rpc_response: Vec<String> = client
+        .request("author_pendingExtrinsics", rpc_params![])
+        .await?;
+
+    rpc_response
+        .iter()
+        .map(|s| {
+            let bytes = hex::decode(strip_0x_prefix(s))?;
+            Ok(Transaction::decode(&mut &bytes[..])?)
+        })
+        .collect()
+}
+
+/// Fetch an output from the utxo child trie given an OutputRef
 pub async fn fetch_storage<V: Verifier>(
     output_ref: &OutputRef,
     client: &HttpClient,
 ) -> anyhow::Result<Output<V>> {
+    let child_hex = hex::encode(utxo_child_info().prefixed_storage_key().into_inner());
     let ref_hex = hex::encode(output_ref.encode());
-    let params = rpc_params![ref_hex];
-    let rpc_response: Result<Option<String>, _> = client.request("state_getStorage", params).await;
+    let params = rpc_params![child_hex, ref_hex];
+    let rpc_response: Result<Option<String>, _> =
+        client.request("childstate_getStorage", params).await;
 
     let response_hex = rpc_response?.ok_or(anyhow!("Data cannot be retrieved from storage"))?;
     let response_hex = strip_0x_prefix(&response_hex);
@@ -59,3 +194,37 @@ pub async fn fetch_storage<V: Verifier>(
 
     Ok(utxo)
 }
+
+/// Submit a transaction through the node's `tuxedo_submitAndWatch` subscription, and pass each
+/// status that the node reports to `done` until it returns true, or until the status is final.
+/// Returns the last status. Subscriptions need a websocket, so this connects to the node anew
+/// at the websocket equivalent of the given http endpoint.
+pub async fn node_submit_and_watch(
+    endpoint: &str,
+    transaction: &Transaction,
+    mut done: impl FnMut(&TransactionStatus) -> bool,
+) -> anyhow::Result<TransactionStatus> {
+    let endpoint = endpoint
+        .replacen("https://", "wss://", 1)
+        .replacen("http://", "ws://", 1);
+    let client = WsClientBuilder::default().build(endpoint).await?;
+
+    let params = rpc_params![hex::encode(transaction.encode())];
+    let mut statuses = client
+        .subscribe::<TransactionStatus, _>("tuxedo_submitAndWatch", params, "tuxedo_unwatch")
+        .await?;
+
+    let mut last = None;
+    while let Some(status) = statuses.next().await {
+        let status = status?;
+        let finished = done(&status) || status.is_final();
+        last = Some(status);
+        if finished {
+            break;
+        }
+    }
+
+    last.ok_or(anyhow!(
+        "node closed the subscription without reporting a status"
+    ))
+}
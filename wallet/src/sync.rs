@@ -8,13 +8,35 @@
 //! There are 4 tables in the database
 //! BlockHashes     block_number:u32 => block_hash:H256
 //! Blocks          block_hash:H256 => block:Block
-//! UnspentOutputs  output_ref => (owner_pubkey, amount)
-//! SpentOutputs    output_ref => (owner_pubkey, amount)
+//! UnspentOutputs  output_ref => (owner_pubkey, amount, creation_height, asset_id)
+//! SpentOutputs    output_ref => (owner_pubkey, amount, creation_height, asset_id)
+//! Pending         tx_hash:H256 => transaction:Transaction
+//! InFlight        output_ref => tx_hash:H256
+//!
+//! The unspent and spent tables above hold simple coins. Other kinds of outputs are stored
+//! by the [`OutputIndexer`]s that the wallet registers at startup, in trees of their own.
+//!
+//! ## Confirmations
+//!
+//! An output created at height `h` has `best_height - h + 1` confirmations. Outputs
+//! with fewer than the requested number of confirmations are considered pending. They
+//! are not selected for spending, and they are not counted in the spendable balance.
+//!
+//! ## Pruning
+//!
+//! By default every block is stored forever. When pruning is enabled, block bodies
+//! that are deeper than the configured depth are discarded along with the records of
+//! outputs that they spent. Block hashes are always kept. A pruned wallet can only
+//! handle re-orgs that are shallower than its pruning depth.
 
 use std::path::PathBuf;
 
-use crate::rpc;
+use crate::{
+    indexer::{extract_coin, OutputIndexer},
+    rpc,
+};
 use anyhow::anyhow;
+use futures::{stream, StreamExt, TryStreamExt};
 use parity_scale_codec::{Decode, Encode};
 use sled::Db;
 use sp_core::H256;
@@ -25,7 +47,7 @@ use tuxedo_core::{
 };
 
 use jsonrpsee::http_client::HttpClient;
-use runtime::{money::Coin, Block, OuterVerifier, Transaction};
+use runtime::{Block, OuterVerifier, Transaction};
 
 /// The identifier for the blocks tree in the db.
 const BLOCKS: &str = "blocks";
@@ -39,6 +61,14 @@ const UNSPENT: &str = "unspent";
 /// The identifier for the spent tree in the db.
 const SPENT: &str = "spent";
 
+/// The identifier for the pending tree in the db.
+/// It holds transactions that this wallet submitted but that are not yet in a block.
+const PENDING: &str = "pending";
+
+/// The identifier for the in-flight tree in the db.
+/// It maps each output consumed by a pending transaction to that transaction's hash.
+const IN_FLIGHT: &str = "in_flight";
+
 /// Open a database at the given location intended for the given genesis block.
 ///
 /// If the database is already populated, make sure it is based on the expected genesis
@@ -88,13 +118,51 @@ pub(crate) fn open_db(
     Ok(db)
 }
 
+/// Prepare a freshly opened database to start syncing at the given height instead of at genesis.
+///
+/// The block hashes of all ancestors are fetched from the node so that the database's notion
+/// of height stays consistent, but only the body of the block at the given height is stored.
+/// The caller is responsible for populating the UTXO trees as of this block.
+pub(crate) async fn initialize_at_height(
+    db: &Db,
+    client: &HttpClient,
+    height: u32,
+    block_hash: H256,
+    max_in_flight: u32,
+) -> anyhow::Result<()> {
+    let wallet_block_hashes_tree = db.open_tree(BLOCK_HASHES)?;
+    let wallet_blocks_tree = db.open_tree(BLOCKS)?;
+
+    let hashes: Vec<Option<H256>> = stream::iter(1..height)
+        .map(|h| rpc::node_get_block_hash(h, client))
+        .buffered(max_in_flight.max(1) as usize)
+        .try_collect()
+        .await?;
+    for (h, maybe_hash) in (1..height).zip(hashes) {
+        let hash = maybe_hash.ok_or(anyhow!("Node has no block hash at height {h}"))?;
+        wallet_block_hashes_tree.insert(h.encode(), hash.encode())?;
+    }
+
+    let block = rpc::node_get_block(block_hash, client)
+        .await?
+        .ok_or(anyhow!("Node does not have block {block_hash:?}"))?;
+    wallet_block_hashes_tree.insert(height.encode(), block_hash.encode())?;
+    wallet_blocks_tree.insert(block_hash.encode(), block.encode())?;
+
+    Ok(())
+}
+
 /// Synchronize the local database to the database of the running node.
 /// The wallet entirely trusts the data the node feeds it. In the bigger
 /// picture, that means run your own (light) node.
+///
+/// During the forward sync, up to `max_in_flight` blocks are fetched from the node concurrently.
 pub(crate) async fn synchronize<F: Fn(&OuterVerifier) -> bool>(
     db: &Db,
     client: &HttpClient,
     filter: &F,
+    indexers: &[Box<dyn OutputIndexer>],
+    max_in_flight: u32,
 ) -> anyhow::Result<()> {
     log::debug!("Synchronizing wallet with node.");
 
@@ -113,7 +181,7 @@ pub(crate) async fn synchronize<F: Fn(&OuterVerifier) -> bool>(
     while Some(wallet_hash) != node_hash {
         log::debug!("Divergence at height {height}. Node reports block: {node_hash:?}. Reverting wallet block: {wallet_hash:?}.");
 
-        unapply_highest_block(db).await?;
+        unapply_highest_block(db, indexers).await?;
 
         // Update for the next iteration
         height -= 1;
@@ -126,52 +194,137 @@ pub(crate) async fn synchronize<F: Fn(&OuterVerifier) -> bool>(
     // So we prepare our variables for forward syncing.
     log::debug!("Resyncing from common ancestor {node_hash:?} - {wallet_hash:?}");
     height += 1;
-    node_hash = rpc::node_get_block_hash(height, client).await?;
 
     // Now that we have checked for reorgs and rolled back any orphan blocks, we can go ahead and sync forward.
-    while let Some(hash) = node_hash {
-        log::debug!("Forward syncing height {height}, hash {hash:?}");
+    // Blocks are fetched in batches with a bounded number of requests in flight at once, but they are
+    // always applied to the local db strictly in order.
+    let max_in_flight = max_in_flight.max(1);
+    'forward: loop {
+        let batch: Vec<Option<(H256, Block)>> = stream::iter(height..height + max_in_flight)
+            .map(|h| fetch_block_at_height(h, client))
+            .buffered(max_in_flight as usize)
+            .try_collect()
+            .await?;
+
+        for maybe_block in batch {
+            // The first height that the node doesn't know about marks the end of the sync.
+            let Some((hash, block)) = maybe_block else {
+                break 'forward;
+            };
+            log::debug!("Forward syncing height {height}, hash {hash:?}");
+
+            // Apply the new block
+            apply_block(db, block, hash, filter, indexers).await?;
+
+            height += 1;
+        }
+    }
 
-        // Fetch the entire block in order to apply its transactions
-        let block = rpc::node_get_block(hash, client)
-            .await?
-            .expect("Node should be able to return a block whose hash it already returned");
+    log::debug!("Done with forward sync up to {}", height - 1);
 
-        // Apply the new block
-        apply_block(db, block, hash, filter).await?;
+    Ok(())
+}
 
-        height += 1;
+/// The settings from the command line that apply to every sync with the node.
+pub struct SyncOptions {
+    /// Maximum number of blocks to fetch from the node concurrently while syncing.
+    pub sync_concurrency: u32,
+    /// Minimum number of confirmations an output needs before it is considered spendable.
+    pub min_confirmations: u32,
+    /// Only keep this many of the most recent blocks in the local database.
+    pub prune: Option<u32>,
+}
 
-        node_hash = rpc::node_get_block_hash(height, client).await?;
-    }
+/// Synchronize with the node, drop any pending transactions that the node has dropped,
+/// and then prune old blocks if the options ask for it.
+///
+/// This is what long-running modes of the wallet do each time they check for new blocks.
+pub(crate) async fn catch_up<F: Fn(&OuterVerifier) -> bool>(
+    db: &Db,
+    client: &HttpClient,
+    filter: &F,
+    indexers: &[Box<dyn OutputIndexer>],
+    options: &SyncOptions,
+) -> anyhow::Result<()> {
+    synchronize(db, client, filter, indexers, options.sync_concurrency).await?;
+    reconcile_pending(db, client).await?;
 
-    log::debug!("Done with forward sync up to {}", height - 1);
+    if let Some(depth) = options.prune {
+        prune(db, depth, indexers)?;
+    }
 
     Ok(())
 }
 
-/// Gets the owner and amount associated with an output ref from the unspent table
+/// Fetch both the hash and the full block at the given height from the node.
+///
+/// None if the node does not know of a block at this height yet.
+async fn fetch_block_at_height(
+    height: u32,
+    client: &HttpClient,
+) -> anyhow::Result<Option<(H256, Block)>> {
+    let Some(hash) = rpc::node_get_block_hash(height, client).await? else {
+        return Ok(None);
+    };
+
+    // Fetch the entire block in order to apply its transactions
+    let block = rpc::node_get_block(hash, client)
+        .await?
+        .expect("Node should be able to return a block whose hash it already returned");
+
+    Ok(Some((hash, block)))
+}
+
+/// Gets the owner, amount, creation height, and asset id associated with an output ref from the unspent table
 ///
 /// Some if the output ref exists, None if it doesn't
-pub(crate) fn get_unspent(db: &Db, output_ref: &OutputRef) -> anyhow::Result<Option<(H256, u128)>> {
+pub(crate) fn get_unspent(
+    db: &Db,
+    output_ref: &OutputRef,
+) -> anyhow::Result<Option<(H256, u128, u32, u8)>> {
     let wallet_unspent_tree = db.open_tree(UNSPENT)?;
     let Some(ivec) = wallet_unspent_tree.get(output_ref.encode())? else {
         return Ok(None);
     };
 
-    Ok(Some(<(H256, u128)>::decode(&mut &ivec[..])?))
+    Ok(Some(<(H256, u128, u32, u8)>::decode(&mut &ivec[..])?))
+}
+
+/// Gets the owner, amount, creation height, and asset id associated with an output ref from the spent table
+///
+/// Some if this wallet owned the output and has not pruned its record, None otherwise
+pub(crate) fn get_spent(
+    db: &Db,
+    output_ref: &OutputRef,
+) -> anyhow::Result<Option<(H256, u128, u32, u8)>> {
+    let spent_tree = db.open_tree(SPENT)?;
+    let Some(ivec) = spent_tree.get(output_ref.encode())? else {
+        return Ok(None);
+    };
+
+    Ok(Some(<(H256, u128, u32, u8)>::decode(&mut &ivec[..])?))
+}
+
+/// Whether an output created at the given height has at least `min_confirmations`
+/// confirmations when the wallet is synced to `best_height`.
+pub(crate) fn is_confirmed(creation_height: u32, best_height: u32, min_confirmations: u32) -> bool {
+    best_height.saturating_sub(creation_height) + 1 >= min_confirmations
 }
 
 /// Picks an arbitrary set of unspent outputs from the database for spending.
 /// The set's token values must add up to at least the specified target value.
+/// Only coins of the given asset with at least `min_confirmations` confirmations are considered.
 ///
-/// The return value is None if the total value of the database is less than the target
+/// The return value is None if the total confirmed value of the database is less than the target
 /// It is Some(Vec![...]) when it is possible
 pub(crate) fn get_arbitrary_unspent_set(
     db: &Db,
     target: u128,
+    asset_id: u8,
+    min_confirmations: u32,
 ) -> anyhow::Result<Option<Vec<OutputRef>>> {
     let wallet_unspent_tree = db.open_tree(UNSPENT)?;
+    let best_height = height(db)?.unwrap_or_default();
 
     let mut total = 0u128;
     let mut keepers = Vec::new();
@@ -184,7 +337,22 @@ pub(crate) fn get_arbitrary_unspent_set(
 
         let (output_ref_ivec, owner_amount_ivec) = pair?;
         let output_ref = OutputRef::decode(&mut &output_ref_ivec[..])?;
-        let (_owner_pubkey, amount) = <(H256, u128)>::decode(&mut &owner_amount_ivec[..])?;
+        let (_owner_pubkey, amount, creation_height, coin_asset_id) =
+            <(H256, u128, u32, u8)>::decode(&mut &owner_amount_ivec[..])?;
+
+        if coin_asset_id != asset_id {
+            continue;
+        }
+
+        // Freshly received coins could still be re-orged away, so don't spend them yet.
+        if !is_confirmed(creation_height, best_height, min_confirmations) {
+            continue;
+        }
+
+        // Coins that a pending transaction already spends are not available either.
+        if is_in_flight(db, &output_ref)? {
+            continue;
+        }
 
         total += amount;
         keepers.push(output_ref);
@@ -227,6 +395,7 @@ pub(crate) async fn apply_block<F: Fn(&OuterVerifier) -> bool>(
     b: Block,
     block_hash: H256,
     filter: &F,
+    indexers: &[Box<dyn OutputIndexer>],
 ) -> anyhow::Result<()> {
     log::debug!("Applying Block {:?}, Block_Hash {:?}", b, block_hash);
     // Write the hash to the block_hashes table
@@ -238,74 +407,176 @@ pub(crate) async fn apply_block<F: Fn(&OuterVerifier) -> bool>(
     wallet_blocks_tree.insert(block_hash.encode(), b.encode())?;
 
     // Iterate through each transaction
+    let height = b.header.number;
     for tx in b.extrinsics {
-        apply_transaction(db, tx, filter).await?;
+        apply_transaction(db, tx, height, filter, indexers).await?;
     }
 
     Ok(())
 }
 
 /// Apply a single transaction to the local database
-/// The owner-specific tables are mappings from output_refs to coin amounts
+/// Each output that passes the filter is offered to the indexers in order until one of them stores it.
 async fn apply_transaction<F: Fn(&OuterVerifier) -> bool>(
     db: &Db,
     tx: Transaction,
+    height: u32,
     filter: &F,
+    indexers: &[Box<dyn OutputIndexer>],
 ) -> anyhow::Result<()> {
     let tx_hash = BlakeTwo256::hash_of(&tx.encode());
     log::debug!("syncing transaction {tx_hash:?}");
 
+    // If this is one of our own transactions, it is no longer pending.
+    remove_pending_transaction(db, &tx_hash)?;
+
     // Insert all new outputs
-    for (index, output) in tx
-        .outputs
-        .iter()
-        .filter(|o| filter(&o.verifier))
-        .enumerate()
-    {
-        // For now the wallet only supports simple coins, so skip anything else
-        let amount = match output.payload.extract::<Coin<0>>() {
-            Ok(Coin(amount)) => amount,
-            Err(_) => continue,
-        };
+    for (index, output) in tx.outputs.iter().enumerate() {
+        if !filter(&output.verifier) {
+            continue;
+        }
 
         let output_ref = OutputRef {
             tx_hash,
             index: index as u32,
         };
 
-        match output.verifier {
-            OuterVerifier::SigCheck(SigCheck { owner_pubkey }) => {
-                // Add it to the global unspent_outputs table
-                add_unspent_output(db, &output_ref, &owner_pubkey, &amount)?;
+        for indexer in indexers {
+            if indexer.index(db, &output_ref, output, height)? {
+                break;
             }
-            _ => return Err(anyhow!("{:?}", ())),
         }
     }
 
     log::debug!("about to spend all inputs");
     // Spend all the inputs
     for Input { output_ref, .. } in tx.inputs {
-        spend_output(db, &output_ref)?;
+        for indexer in indexers {
+            indexer.spend(db, &output_ref)?;
+        }
     }
 
     Ok(())
 }
 
+/// Record a transaction that this wallet has submitted to the node but that is not yet in a block.
+/// Its inputs are marked as in-flight so they are not selected for spending again.
+pub(crate) fn add_pending_transaction(db: &Db, tx: &Transaction) -> anyhow::Result<()> {
+    let pending_tree = db.open_tree(PENDING)?;
+    let in_flight_tree = db.open_tree(IN_FLIGHT)?;
+
+    let tx_hash = BlakeTwo256::hash_of(&tx.encode());
+    pending_tree.insert(tx_hash.encode(), tx.encode())?;
+    for Input { output_ref, .. } in &tx.inputs {
+        in_flight_tree.insert(output_ref.encode(), tx_hash.encode())?;
+    }
+
+    Ok(())
+}
+
+/// Stop tracking a pending transaction, releasing its in-flight inputs.
+///
+/// Returns whether the transaction was actually pending.
+pub(crate) fn remove_pending_transaction(db: &Db, tx_hash: &H256) -> anyhow::Result<bool> {
+    let pending_tree = db.open_tree(PENDING)?;
+    let in_flight_tree = db.open_tree(IN_FLIGHT)?;
+
+    let Some(ivec) = pending_tree.remove(tx_hash.encode())? else {
+        return Ok(false);
+    };
+    let tx = Transaction::decode(&mut &ivec[..])?;
+    for Input { output_ref, .. } in tx.inputs {
+        in_flight_tree.remove(output_ref.encode())?;
+    }
+
+    Ok(true)
+}
+
+/// Get all the transactions this wallet submitted that are not yet in a block.
+pub(crate) fn get_pending_transactions(db: &Db) -> anyhow::Result<Vec<(H256, Transaction)>> {
+    let pending_tree = db.open_tree(PENDING)?;
+
+    pending_tree
+        .iter()
+        .map(|raw_data| {
+            let (hash_ivec, tx_ivec) = raw_data?;
+            let hash = H256::decode(&mut &hash_ivec[..])?;
+            let tx = Transaction::decode(&mut &tx_ivec[..])?;
+            Ok((hash, tx))
+        })
+        .collect()
+}
+
+/// Whether the given output is consumed by one of this wallet's pending transactions.
+pub(crate) fn is_in_flight(db: &Db, output_ref: &OutputRef) -> anyhow::Result<bool> {
+    let in_flight_tree = db.open_tree(IN_FLIGHT)?;
+    Ok(in_flight_tree.contains_key(output_ref.encode())?)
+}
+
+/// Drop any pending transactions that the node no longer has in its pool.
+///
+/// This is meant to be called after syncing. Pending transactions that made it into a block
+/// have already been removed when the block was applied, so anything that is neither in a block
+/// nor in the pool has been dropped by the node and its inputs can be spent again.
+pub(crate) async fn reconcile_pending(db: &Db, client: &HttpClient) -> anyhow::Result<()> {
+    let pending = get_pending_transactions(db)?;
+    if pending.is_empty() {
+        return Ok(());
+    }
+
+    let pool = rpc::node_get_pending_extrinsic_hashes(client).await?;
+    for (tx_hash, _) in pending {
+        if !pool.contains(&tx_hash) {
+            log::info!("Pending transaction {tx_hash:?} was dropped from the node's pool.");
+            remove_pending_transaction(db, &tx_hash)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Sum the values of the coins that this wallet's pending transactions will create
+/// for addresses that pass the filter, per address and asset. This is typically incoming change.
+pub(crate) fn get_unconfirmed_incoming<F: Fn(&OuterVerifier) -> bool>(
+    db: &Db,
+    filter: &F,
+) -> anyhow::Result<impl Iterator<Item = ((H256, u8), u128)>> {
+    let mut incoming = std::collections::BTreeMap::<(H256, u8), u128>::new();
+
+    for (_, tx) in get_pending_transactions(db)? {
+        for output in tx.outputs.iter().filter(|o| filter(&o.verifier)) {
+            let (Some((asset_id, amount)), OuterVerifier::SigCheck(SigCheck { owner_pubkey })) =
+                (extract_coin(&output.payload), &output.verifier)
+            else {
+                continue;
+            };
+            *incoming.entry((*owner_pubkey, asset_id)).or_default() += amount;
+        }
+    }
+
+    Ok(incoming.into_iter())
+}
+
 /// Add a new output to the database updating all tables.
-fn add_unspent_output(
+pub(crate) fn add_unspent_output(
     db: &Db,
     output_ref: &OutputRef,
     owner_pubkey: &H256,
     amount: &u128,
+    creation_height: u32,
+    asset_id: u8,
 ) -> anyhow::Result<()> {
     let unspent_tree = db.open_tree(UNSPENT)?;
-    unspent_tree.insert(output_ref.encode(), (owner_pubkey, amount).encode())?;
+    unspent_tree.insert(
+        output_ref.encode(),
+        (owner_pubkey, amount, creation_height, asset_id).encode(),
+    )?;
 
     Ok(())
 }
 
 /// Remove an output from the database updating all tables.
-fn remove_unspent_output(db: &Db, output_ref: &OutputRef) -> anyhow::Result<()> {
+pub(crate) fn remove_unspent_output(db: &Db, output_ref: &OutputRef) -> anyhow::Result<()> {
     let unspent_tree = db.open_tree(UNSPENT)?;
 
     unspent_tree.remove(output_ref.encode())?;
@@ -315,39 +586,53 @@ fn remove_unspent_output(db: &Db, output_ref: &OutputRef) -> anyhow::Result<()>
 
 /// Mark an existing output as spent. This does not purge all record of the output from the db.
 /// It just moves the record from the unspent table to the spent table
-fn spend_output(db: &Db, output_ref: &OutputRef) -> anyhow::Result<()> {
+pub(crate) fn spend_output(db: &Db, output_ref: &OutputRef) -> anyhow::Result<()> {
     let unspent_tree = db.open_tree(UNSPENT)?;
     let spent_tree = db.open_tree(SPENT)?;
 
     let Some(ivec) = unspent_tree.remove(output_ref.encode())? else {
         return Ok(());
     };
-    let (owner, amount) = <(H256, u128)>::decode(&mut &ivec[..])?;
-    spent_tree.insert(output_ref.encode(), (owner, amount).encode())?;
+    let record = <(H256, u128, u32, u8)>::decode(&mut &ivec[..])?;
+    spent_tree.insert(output_ref.encode(), record.encode())?;
+
+    Ok(())
+}
+
+/// Discard the record of a spent output entirely.
+pub(crate) fn forget_spent_output(db: &Db, output_ref: &OutputRef) -> anyhow::Result<()> {
+    let spent_tree = db.open_tree(SPENT)?;
+    spent_tree.remove(output_ref.encode())?;
 
     Ok(())
 }
 
 /// Mark an output that was previously spent back as unspent.
-fn unspend_output(db: &Db, output_ref: &OutputRef) -> anyhow::Result<()> {
+pub(crate) fn unspend_output(db: &Db, output_ref: &OutputRef) -> anyhow::Result<()> {
     let unspent_tree = db.open_tree(UNSPENT)?;
     let spent_tree = db.open_tree(SPENT)?;
 
     let Some(ivec) = spent_tree.remove(output_ref.encode())? else {
         return Ok(());
     };
-    let (owner, amount) = <(H256, u128)>::decode(&mut &ivec[..])?;
-    unspent_tree.insert(output_ref.encode(), (owner, amount).encode())?;
+    let record = <(H256, u128, u32, u8)>::decode(&mut &ivec[..])?;
+    unspent_tree.insert(output_ref.encode(), record.encode())?;
 
     Ok(())
 }
 
 /// Run a transaction backwards against a database. Mark all of the Inputs
 /// as unspent, and drop all of the outputs.
-fn unapply_transaction(db: &Db, tx: &Transaction) -> anyhow::Result<()> {
+fn unapply_transaction(
+    db: &Db,
+    tx: &Transaction,
+    indexers: &[Box<dyn OutputIndexer>],
+) -> anyhow::Result<()> {
     // Loop through the inputs moving each from spent to unspent
     for Input { output_ref, .. } in &tx.inputs {
-        unspend_output(db, output_ref)?;
+        for indexer in indexers {
+            indexer.unspend(db, output_ref)?;
+        }
     }
 
     // Loop through the outputs pruning them from unspent and dropping all record
@@ -358,14 +643,19 @@ fn unapply_transaction(db: &Db, tx: &Transaction) -> anyhow::Result<()> {
             tx_hash,
             index: i as u32,
         };
-        remove_unspent_output(db, &output_ref)?;
+        for indexer in indexers {
+            indexer.remove(db, &output_ref)?;
+        }
     }
 
     Ok(())
 }
 
 /// Unapply the best block that the wallet currently knows about
-pub(crate) async fn unapply_highest_block(db: &Db) -> anyhow::Result<Block> {
+pub(crate) async fn unapply_highest_block(
+    db: &Db,
+    indexers: &[Box<dyn OutputIndexer>],
+) -> anyhow::Result<Block> {
     let wallet_blocks_tree = db.open_tree(BLOCKS)?;
     let wallet_block_hashes_tree = db.open_tree(BLOCK_HASHES)?;
 
@@ -383,7 +673,7 @@ pub(crate) async fn unapply_highest_block(db: &Db) -> anyhow::Result<Block> {
     // Take the block from the blocks table
     let Some(ivec) = wallet_blocks_tree.remove(hash.encode())? else {
         return Err(anyhow!(
-            "Block was not present in db but block hash was. DB is corrupted."
+            "Block was not present in db but block hash was. Either the re-org is deeper than the pruning depth or the DB is corrupted."
         ));
     };
 
@@ -391,12 +681,54 @@ pub(crate) async fn unapply_highest_block(db: &Db) -> anyhow::Result<Block> {
 
     // Loop through the transactions in reverse order calling unapply
     for tx in block.extrinsics.iter().rev() {
-        unapply_transaction(db, tx)?;
+        unapply_transaction(db, tx, indexers)?;
     }
 
     Ok(block)
 }
 
+/// Prune the local database so that only the most recent `depth` block bodies are kept.
+///
+/// Pruning a block discards its body as well as the spent records of every output that
+/// it consumed. Those records are only needed to roll the block back during a re-org.
+/// Block hashes are never pruned, so the wallet's height and genesis check are unaffected.
+pub(crate) fn prune(
+    db: &Db,
+    depth: u32,
+    indexers: &[Box<dyn OutputIndexer>],
+) -> anyhow::Result<()> {
+    let wallet_blocks_tree = db.open_tree(BLOCKS)?;
+
+    let best = height(db)?.ok_or(anyhow!("Cannot prune an uninitialized database"))?;
+
+    // Blocks are always pruned from the bottom up, so the retained bodies are
+    // exactly the highest ones. That tells us where the previous pruning stopped.
+    let lowest_retained = (best + 1).saturating_sub(wallet_blocks_tree.len() as u32);
+    let prune_below = (best + 1).saturating_sub(depth);
+
+    for height in lowest_retained..prune_below {
+        let hash = get_block_hash(db, height)?.ok_or(anyhow!(
+            "No block hash found at height {height}. DB is inconsistent."
+        ))?;
+
+        let Some(ivec) = wallet_blocks_tree.remove(hash.encode())? else {
+            continue;
+        };
+        let block = Block::decode(&mut &ivec[..])?;
+
+        for tx in block.extrinsics {
+            for Input { output_ref, .. } in tx.inputs {
+                for indexer in indexers {
+                    indexer.forget_spent(db, &output_ref)?;
+                }
+            }
+        }
+        log::debug!("Pruned block {hash:?} at height {height}");
+    }
+
+    Ok(())
+}
+
 /// Get the block height that the wallet is currently synced to
 ///
 /// None means the db is not yet initialized with a genesis block
@@ -423,35 +755,64 @@ pub(crate) fn print_block_hashes_tree(db: &Db) -> anyhow::Result<()> {
     Ok(())
 }
 
+/// Get every output in the unspent tree along with its owner, amount, creation height, and asset id.
+pub(crate) fn get_unspent_outputs(
+    db: &Db,
+) -> anyhow::Result<Vec<(OutputRef, H256, u128, u32, u8)>> {
+    let wallet_unspent_tree = db.open_tree(UNSPENT)?;
+
+    wallet_unspent_tree
+        .iter()
+        .map(|raw_data| {
+            let (output_ref_ivec, owner_amount_ivec) = raw_data?;
+            let output_ref = OutputRef::decode(&mut &output_ref_ivec[..])?;
+            let (owner_pubkey, amount, creation_height, asset_id) =
+                <(H256, u128, u32, u8)>::decode(&mut &owner_amount_ivec[..])?;
+            Ok((output_ref, owner_pubkey, amount, creation_height, asset_id))
+        })
+        .collect()
+}
+
 /// Debugging use. Print the entire unspent outputs tree.
 pub(crate) fn print_unspent_tree(db: &Db) -> anyhow::Result<()> {
     let wallet_unspent_tree = db.open_tree(UNSPENT)?;
     for x in wallet_unspent_tree.iter() {
         let (output_ref_ivec, owner_amount_ivec) = x?;
         let output_ref = hex::encode(output_ref_ivec);
-        let (owner_pubkey, amount) = <(H256, u128)>::decode(&mut &owner_amount_ivec[..])?;
+        let (owner_pubkey, amount, creation_height, asset_id) =
+            <(H256, u128, u32, u8)>::decode(&mut &owner_amount_ivec[..])?;
 
-        println!("{output_ref}: owner {owner_pubkey:?}, amount {amount}");
+        println!("{output_ref}: owner {owner_pubkey:?}, asset {asset_id}, amount {amount}, height {creation_height}");
     }
 
     Ok(())
 }
 
 /// Iterate the entire unspent set summing the values of the coins
-/// on a per-address basis.
-pub(crate) fn get_balances(db: &Db) -> anyhow::Result<impl Iterator<Item = (H256, u128)>> {
-    let mut balances = std::collections::HashMap::<H256, u128>::new();
+/// on a per-address, per-asset basis.
+///
+/// Each (address, asset id) pair maps to a `(spendable, pending)` pair where pending coins
+/// are those with fewer than `min_confirmations` confirmations. Results are sorted.
+pub(crate) fn get_balances(
+    db: &Db,
+    min_confirmations: u32,
+) -> anyhow::Result<impl Iterator<Item = ((H256, u8), (u128, u128))>> {
+    let mut balances = std::collections::BTreeMap::<(H256, u8), (u128, u128)>::new();
 
     let wallet_unspent_tree = db.open_tree(UNSPENT)?;
+    let best_height = height(db)?.unwrap_or_default();
 
     for raw_data in wallet_unspent_tree.iter() {
         let (_output_ref_ivec, owner_amount_ivec) = raw_data?;
-        let (owner, amount) = <(H256, u128)>::decode(&mut &owner_amount_ivec[..])?;
-
-        balances
-            .entry(owner)
-            .and_modify(|old| *old += amount)
-            .or_insert(amount);
+        let (owner, amount, creation_height, asset_id) =
+            <(H256, u128, u32, u8)>::decode(&mut &owner_amount_ivec[..])?;
+
+        let (spendable, pending) = balances.entry((owner, asset_id)).or_default();
+        if is_confirmed(creation_height, best_height, min_confirmations) {
+            *spendable += amount;
+        } else {
+            *pending += amount;
+        }
     }
 
     Ok(balances.into_iter())
@@ -0,0 +1,617 @@
+//! An interactive terminal interface for the wallet.
+//!
+//! Unlike the one-shot commands, which each open the database, sync, and exit, the terminal
+//! interface keeps the database open and syncs with the node every few seconds in the background.
+//! It shows the sync progress, the balance of each asset, and the wallet's recent transactions,
+//! and it guides the user through sending coins.
+//!
+//! Logs would draw over the interface, so they are silenced while it is open.
+
+use std::{cell::Cell, collections::BTreeMap, io::Stdout, time::Duration};
+
+use crossterm::{
+    event::{Event, EventStream, KeyCode, KeyEvent, KeyEventKind, KeyModifiers},
+    execute,
+    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
+};
+use futures::StreamExt;
+use jsonrpsee::http_client::HttpClient;
+use parity_scale_codec::Encode;
+use ratatui::{
+    backend::CrosstermBackend,
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Gauge, List, ListItem, Paragraph},
+    Frame, Terminal,
+};
+use runtime::{OuterVerifier, Transaction};
+use sc_keystore::LocalKeystore;
+use sled::Db;
+use sp_core::H256;
+use sp_runtime::traits::{BlakeTwo256, Hash};
+
+use crate::{
+    address_book,
+    cli::SpendArgs,
+    history,
+    indexer::OutputIndexer,
+    keystore, money, rpc,
+    sync::{self, SyncOptions},
+};
+
+/// How long to wait after one sync with the node finishes before starting the next one.
+const SYNC_INTERVAL: Duration = Duration::from_secs(3);
+
+/// How often the interface re-reads the local database, so that sync progress shows as it happens.
+const REFRESH_INTERVAL: Duration = Duration::from_millis(500);
+
+/// How many of the most recent blocks are searched for the wallet's transactions.
+const RECENT_BLOCKS: u32 = 100;
+
+/// The most transactions that are shown in the recent transactions list.
+const MAX_RECENT: usize = 50;
+
+/// The balance of a single asset, summed over all of the wallet's keys.
+#[derive(Default)]
+struct AssetBalance {
+    spendable: u128,
+    pending: u128,
+    unconfirmed: u128,
+}
+
+/// A transaction that creates or consumes coins owned by the wallet's keys.
+struct Activity {
+    /// The height of the block that includes the transaction, or None if it is still pending.
+    height: Option<u32>,
+    tx_hash: H256,
+    /// The net change in the wallet's holdings of each asset that the transaction touches.
+    changes: BTreeMap<u8, i128>,
+}
+
+/// The steps of the guided send flow, in order.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum SendStep {
+    Recipient,
+    Amount,
+    Tip,
+    Confirm,
+}
+
+/// The state of the guided send flow.
+struct SendForm {
+    step: SendStep,
+    recipient: String,
+    resolved: H256,
+    amount: String,
+    tip: String,
+    error: Option<String>,
+}
+
+impl SendForm {
+    fn new() -> Self {
+        Self {
+            step: SendStep::Recipient,
+            recipient: String::new(),
+            resolved: H256::zero(),
+            amount: String::new(),
+            tip: String::new(),
+            error: None,
+        }
+    }
+
+    /// The text field that the current step edits, if any.
+    fn field(&mut self) -> Option<&mut String> {
+        match self.step {
+            SendStep::Recipient => Some(&mut self.recipient),
+            SendStep::Amount => Some(&mut self.amount),
+            SendStep::Tip => Some(&mut self.tip),
+            SendStep::Confirm => None,
+        }
+    }
+
+    /// Check the current step's input and move on to the next step.
+    fn advance(&mut self, db: &Db) {
+        let checked = match self.step {
+            SendStep::Recipient => address_book::resolve(db, self.recipient.trim())
+                .map(|resolved| {
+                    self.resolved = resolved;
+                    SendStep::Amount
+                })
+                .map_err(|e| e.to_string()),
+            SendStep::Amount => match self.amount.trim().parse::<u128>() {
+                Ok(amount) if amount > 0 => Ok(SendStep::Tip),
+                _ => Err("The amount must be a positive whole number".to_string()),
+            },
+            SendStep::Tip => match self.tip.trim() {
+                "" => Ok(SendStep::Confirm),
+                tip => tip
+                    .parse::<u128>()
+                    .map(|_| SendStep::Confirm)
+                    .map_err(|_| "The tip must be a whole number, or blank".to_string()),
+            },
+            SendStep::Confirm => Ok(SendStep::Confirm),
+        };
+
+        match checked {
+            Ok(step) => {
+                self.step = step;
+                self.error = None;
+            }
+            Err(e) => self.error = Some(e),
+        }
+    }
+
+    /// The spend that the completed form describes.
+    fn spend_args(&self) -> SpendArgs {
+        SpendArgs {
+            input: Vec::new(),
+            recipient: format!("0x{}", hex::encode(self.resolved)),
+            output_amount: vec![self.amount.trim().parse().unwrap_or_default()],
+            tip: self.tip.trim().parse().ok(),
+            memo: None,
+            watch: false,
+        }
+    }
+}
+
+/// Everything the interface displays.
+#[derive(Default)]
+struct App {
+    local_height: u32,
+    balances: BTreeMap<u8, AssetBalance>,
+    recent: Vec<Activity>,
+    /// The best block hash and number of pending transactions that `recent` was found at.
+    /// Searching for recent transactions is only repeated when either of these changes.
+    recent_at: Option<(Option<H256>, usize)>,
+    sync_error: Option<String>,
+    message: Option<String>,
+    send: Option<SendForm>,
+    quit: bool,
+}
+
+impl App {
+    /// Re-read the balances and recent transactions from the local database.
+    fn refresh<F: Fn(&OuterVerifier) -> bool>(
+        &mut self,
+        db: &Db,
+        keystore: &LocalKeystore,
+        filter: &F,
+        min_confirmations: u32,
+    ) -> anyhow::Result<()> {
+        self.local_height = sync::height(db)?.unwrap_or_default();
+
+        self.balances.clear();
+        for ((_, asset_id), (spendable, pending)) in sync::get_balances(db, min_confirmations)? {
+            let balance = self.balances.entry(asset_id).or_default();
+            balance.spendable += spendable;
+            balance.pending += pending;
+        }
+        for ((_, asset_id), amount) in sync::get_unconfirmed_incoming(db, filter)? {
+            self.balances.entry(asset_id).or_default().unconfirmed += amount;
+        }
+
+        let recent_at = Some((
+            sync::get_block_hash(db, self.local_height)?,
+            sync::get_pending_transactions(db)?.len(),
+        ));
+        if recent_at != self.recent_at {
+            self.recent = recent_activity(db, keystore)?;
+            self.recent_at = recent_at;
+        }
+
+        Ok(())
+    }
+
+    /// React to a key press. Returns the spend to submit once the user confirms one.
+    fn handle_key(&mut self, key: KeyEvent, db: &Db) -> Option<SpendArgs> {
+        if key.modifiers.contains(KeyModifiers::CONTROL) && key.code == KeyCode::Char('c') {
+            self.quit = true;
+            return None;
+        }
+
+        let Some(form) = &mut self.send else {
+            match key.code {
+                KeyCode::Char('q') | KeyCode::Esc => self.quit = true,
+                KeyCode::Char('s') => {
+                    self.send = Some(SendForm::new());
+                    self.message = None;
+                }
+                _ => {}
+            }
+            return None;
+        };
+
+        match (form.step, key.code) {
+            (_, KeyCode::Esc) | (SendStep::Confirm, KeyCode::Char('n')) => self.send = None,
+            (SendStep::Confirm, KeyCode::Enter | KeyCode::Char('y')) => {
+                let args = form.spend_args();
+                self.send = None;
+                return Some(args);
+            }
+            (_, KeyCode::Enter) => form.advance(db),
+            (_, KeyCode::Backspace) => {
+                if let Some(field) = form.field() {
+                    field.pop();
+                }
+            }
+            (_, KeyCode::Char(c)) => {
+                if let Some(field) = form.field() {
+                    field.push(c);
+                }
+            }
+            _ => {}
+        }
+
+        None
+    }
+}
+
+/// Restores the terminal and the log level when the interface closes, even if it fails.
+struct TerminalGuard {
+    log_level: log::LevelFilter,
+}
+
+impl TerminalGuard {
+    fn enter() -> anyhow::Result<(Self, Terminal<CrosstermBackend<Stdout>>)> {
+        let guard = Self {
+            log_level: log::max_level(),
+        };
+        log::set_max_level(log::LevelFilter::Off);
+
+        enable_raw_mode()?;
+        let mut stdout = std::io::stdout();
+        execute!(stdout, EnterAlternateScreen)?;
+        let terminal = Terminal::new(CrosstermBackend::new(stdout))?;
+
+        Ok((guard, terminal))
+    }
+}
+
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        let _ = disable_raw_mode();
+        let _ = execute!(std::io::stdout(), LeaveAlternateScreen);
+        log::set_max_level(self.log_level);
+    }
+}
+
+/// Run the terminal interface until the user quits.
+pub async fn run<F: Fn(&OuterVerifier) -> bool>(
+    db: &Db,
+    client: &HttpClient,
+    endpoint: &str,
+    keystore: &LocalKeystore,
+    filter: &F,
+    indexers: &[Box<dyn OutputIndexer>],
+    options: SyncOptions,
+) -> anyhow::Result<()> {
+    let (_guard, mut terminal) = TerminalGuard::enter()?;
+
+    let mut app = App::default();
+    app.refresh(db, keystore, filter, options.min_confirmations)?;
+
+    let node_height = Cell::new(None);
+    let mut round = Box::pin(sync_round(
+        db,
+        client,
+        filter,
+        indexers,
+        &options,
+        &node_height,
+        Duration::ZERO,
+    ));
+    let mut ticker = tokio::time::interval(REFRESH_INTERVAL);
+    let mut events = EventStream::new();
+
+    while !app.quit {
+        terminal.draw(|frame| draw(frame, &app, node_height.get()))?;
+
+        tokio::select! {
+            result = &mut round => {
+                app.sync_error = result.err().map(|e| e.to_string());
+                app.refresh(db, keystore, filter, options.min_confirmations)?;
+                round = Box::pin(sync_round(
+                    db,
+                    client,
+                    filter,
+                    indexers,
+                    &options,
+                    &node_height,
+                    SYNC_INTERVAL,
+                ));
+            }
+            _ = ticker.tick() => app.refresh(db, keystore, filter, options.min_confirmations)?,
+            event = events.next() => match event {
+                Some(Ok(Event::Key(key))) if key.kind == KeyEventKind::Press => {
+                    let Some(args) = app.handle_key(key, db) else {
+                        continue;
+                    };
+
+                    app.message = Some("Submitting transaction...".to_string());
+                    terminal.draw(|frame| draw(frame, &app, node_height.get()))?;
+
+                    let receipt = money::submit_spend(
+                        db,
+                        client,
+                        endpoint,
+                        keystore,
+                        args,
+                        options.min_confirmations,
+                        |_| {},
+                    )
+                    .await;
+                    app.message = Some(match receipt {
+                        Ok(receipt) if receipt.accepted => {
+                            format!("Sent transaction {:?}", receipt.tx_hash)
+                        }
+                        Ok(receipt) => {
+                            format!("The node did not accept transaction {:?}", receipt.tx_hash)
+                        }
+                        Err(e) => format!("Unable to send: {e}"),
+                    });
+                    app.refresh(db, keystore, filter, options.min_confirmations)?;
+                }
+                Some(Ok(_)) => {}
+                Some(Err(e)) => return Err(e.into()),
+                None => break,
+            },
+        }
+    }
+
+    Ok(())
+}
+
+/// Wait for the given delay, and then bring the local database up to date with the node.
+///
+/// The node's best height is published through `node_height` before syncing
+/// so the interface can show how far along the sync is.
+async fn sync_round<F: Fn(&OuterVerifier) -> bool>(
+    db: &Db,
+    client: &HttpClient,
+    filter: &F,
+    indexers: &[Box<dyn OutputIndexer>],
+    options: &SyncOptions,
+    node_height: &Cell<Option<u32>>,
+    delay: Duration,
+) -> anyhow::Result<()> {
+    tokio::time::sleep(delay).await;
+
+    node_height.set(Some(rpc::node_get_best_height(client).await?));
+    sync::catch_up(db, client, filter, indexers, options).await
+}
+
+/// Find the wallet's pending transactions and the transactions in recent blocks that
+/// touch its coins, newest first.
+fn recent_activity(db: &Db, keystore: &LocalKeystore) -> anyhow::Result<Vec<Activity>> {
+    let mut recent = Vec::new();
+
+    for (tx_hash, tx) in sync::get_pending_transactions(db)? {
+        recent.push(Activity {
+            height: None,
+            tx_hash,
+            changes: coin_changes(db, keystore, &tx)?,
+        });
+    }
+
+    let best = sync::height(db)?.unwrap_or_default();
+    for height in (best.saturating_sub(RECENT_BLOCKS - 1)..=best).rev() {
+        let Some(hash) = sync::get_block_hash(db, height)? else {
+            continue;
+        };
+        // Older blocks have been pruned, so there is nothing more to find.
+        let Some(block) = sync::get_block(db, hash)? else {
+            break;
+        };
+
+        for tx in block.extrinsics.iter().rev() {
+            let changes = coin_changes(db, keystore, tx)?;
+            if !changes.is_empty() {
+                recent.push(Activity {
+                    height: Some(height),
+                    tx_hash: BlakeTwo256::hash_of(&tx.encode()),
+                    changes,
+                });
+            }
+        }
+    }
+
+    recent.truncate(MAX_RECENT);
+    Ok(recent)
+}
+
+/// The net change in the wallet's holdings of each asset whose coins the transaction
+/// creates or consumes. Empty if the transaction does not touch any of the wallet's coins.
+fn coin_changes(
+    db: &Db,
+    keystore: &LocalKeystore,
+    tx: &Transaction,
+) -> anyhow::Result<BTreeMap<u8, i128>> {
+    let is_ours = |key: &H256| keystore::has_key(keystore, key);
+
+    Ok(history::coin_flows(db, tx, &is_ours)?
+        .into_iter()
+        .map(|(asset_id, flow)| (asset_id, flow.change))
+        .collect())
+}
+
+/// Draw the whole interface.
+fn draw(frame: &mut Frame, app: &App, node_height: Option<u32>) {
+    let rows = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(3),
+            Constraint::Min(5),
+            Constraint::Length(1),
+        ])
+        .split(frame.size());
+    let columns = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([Constraint::Percentage(35), Constraint::Percentage(65)])
+        .split(rows[1]);
+
+    draw_sync(frame, rows[0], app, node_height);
+    draw_balances(frame, columns[0], app);
+    draw_recent(frame, columns[1], app);
+
+    let help = match &app.message {
+        Some(message) => format!(" s: send  q: quit | {message}"),
+        None => " s: send  q: quit".to_string(),
+    };
+    frame.render_widget(Paragraph::new(help), rows[2]);
+
+    if let Some(form) = &app.send {
+        draw_send_form(frame, form);
+    }
+}
+
+/// Draw the progress of the local database towards the node's best block.
+fn draw_sync(frame: &mut Frame, area: Rect, app: &App, node_height: Option<u32>) {
+    let (ratio, label) = match node_height {
+        Some(node_height) if node_height > 0 => (
+            (app.local_height as f64 / node_height as f64).min(1.0),
+            format!("block {} of {node_height}", app.local_height),
+        ),
+        _ => (0.0, format!("block {}, waiting for node", app.local_height)),
+    };
+    let label = match &app.sync_error {
+        Some(e) => format!("{label} - last sync failed: {e}"),
+        None => label,
+    };
+    let color = if app.sync_error.is_some() {
+        Color::Red
+    } else {
+        Color::Green
+    };
+
+    let gauge = Gauge::default()
+        .block(Block::default().title(" Sync ").borders(Borders::ALL))
+        .gauge_style(Style::default().fg(color))
+        .ratio(ratio)
+        .label(label);
+    frame.render_widget(gauge, area);
+}
+
+/// Draw the balance of each asset.
+fn draw_balances(frame: &mut Frame, area: Rect, app: &App) {
+    let items: Vec<_> = app
+        .balances
+        .iter()
+        .map(|(asset_id, balance)| {
+            ListItem::new(vec![
+                Line::from(Span::styled(
+                    format!("asset {asset_id}: {}", balance.spendable),
+                    Style::default().add_modifier(Modifier::BOLD),
+                )),
+                Line::from(format!(
+                    "  pending {}, unconfirmed {}",
+                    balance.pending, balance.unconfirmed
+                )),
+            ])
+        })
+        .collect();
+
+    let list = List::new(items).block(Block::default().title(" Balances ").borders(Borders::ALL));
+    frame.render_widget(list, area);
+}
+
+/// Draw the wallet's recent transactions, newest first.
+fn draw_recent(frame: &mut Frame, area: Rect, app: &App) {
+    let items: Vec<_> = app
+        .recent
+        .iter()
+        .map(|activity| {
+            let height = match activity.height {
+                Some(height) => format!("#{height:<8}"),
+                None => "pending  ".to_string(),
+            };
+            let changes = activity
+                .changes
+                .iter()
+                .map(|(asset_id, change)| {
+                    let color = if *change < 0 {
+                        Color::Red
+                    } else {
+                        Color::Green
+                    };
+                    Span::styled(
+                        format!(" {change:+} (asset {asset_id})"),
+                        Style::default().fg(color),
+                    )
+                })
+                .collect::<Vec<_>>();
+
+            let mut spans = vec![Span::raw(format!("{height} {:?}", activity.tx_hash))];
+            spans.extend(changes);
+            ListItem::new(Line::from(spans))
+        })
+        .collect();
+
+    let list = List::new(items).block(
+        Block::default()
+            .title(" Recent transactions ")
+            .borders(Borders::ALL),
+    );
+    frame.render_widget(list, area);
+}
+
+/// Draw the guided send flow in a window over the rest of the interface.
+fn draw_send_form(frame: &mut Frame, form: &SendForm) {
+    let area = centered(frame.size(), 70, 10);
+
+    let field = |step: SendStep, label: &str, value: &str| {
+        let style = if form.step == step {
+            Style::default().add_modifier(Modifier::BOLD)
+        } else {
+            Style::default()
+        };
+        Line::from(Span::styled(format!("{label}: {value}"), style))
+    };
+
+    let hint = match form.step {
+        SendStep::Recipient => "A public key, or a label from the address book.",
+        SendStep::Amount => "The number of coins of asset 0 to send.",
+        SendStep::Tip => "Change is returned to you. Leave blank to burn all change as a tip.",
+        SendStep::Confirm => "Press y or enter to send, n or esc to cancel.",
+    };
+
+    let mut lines = vec![
+        field(SendStep::Recipient, "Recipient", &form.recipient),
+        field(SendStep::Amount, "Amount", &form.amount),
+        field(SendStep::Tip, "Tip", &form.tip),
+        Line::from(""),
+        Line::from(hint),
+    ];
+    if form.step == SendStep::Confirm {
+        lines.insert(
+            3,
+            Line::from(format!("Sending to 0x{}", hex::encode(form.resolved))),
+        );
+    }
+    if let Some(error) = &form.error {
+        lines.push(Line::from(Span::styled(
+            error.clone(),
+            Style::default().fg(Color::Red),
+        )));
+    }
+
+    let window = Paragraph::new(lines).block(
+        Block::default()
+            .title(" Send coins (esc to cancel) ")
+            .borders(Borders::ALL),
+    );
+    frame.render_widget(Clear, area);
+    frame.render_widget(window, area);
+}
+
+/// A rectangle of the given width percentage and height, centered within the area.
+fn centered(area: Rect, percent_x: u16, height: u16) -> Rect {
+    let width = area.width * percent_x / 100;
+    let height = height.min(area.height);
+    Rect {
+        x: area.x + (area.width - width) / 2,
+        y: area.y + (area.height - height) / 2,
+        width,
+        height,
+    }
+}
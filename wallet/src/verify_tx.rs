@@ -0,0 +1,222 @@
+//! Checking a transaction that somebody else built, before signing, submitting, or trusting it.
+//!
+//! The transaction is decoded against the template runtime's types, and its inputs and peeks are
+//! resolved from the node's storage, so that it can be shown in human readable form. It is then
+//! dry run against the node's best block. When a verifier would fail, the node only says that
+//! one did, so the wallet checks each input's verifier itself to tell which.
+
+use crate::{
+    cli::{OutputFormat, VerifyTxArgs},
+    rpc::{fetch_storage, node_dry_run, node_get_signing_domain, node_get_verifier_context},
+    strip_0x_prefix,
+};
+
+use anyhow::anyhow;
+use jsonrpsee::http_client::HttpClient;
+use parity_scale_codec::{DecodeAll, Encode};
+use runtime::{money::Coin, OuterVerifier, Transaction};
+use sp_runtime::traits::{BlakeTwo256, Hash};
+use tuxedo_core::{
+    types::{DetailedError, Output},
+    Verifier,
+};
+
+/// Decode, resolve, and dry run a transaction, and report why it would fail, if it would.
+pub async fn verify_tx(
+    client: &HttpClient,
+    args: VerifyTxArgs,
+    format: OutputFormat,
+) -> anyhow::Result<()> {
+    let bytes = match (args.transaction, args.file) {
+        (Some(hex), _) => hex::decode(strip_0x_prefix(hex.trim()))?,
+        (None, Some(file)) => read_file(&std::fs::read(&file)?),
+        (None, None) => return Err(anyhow!("give a hex encoded transaction or a --file")),
+    };
+    let transaction = Transaction::decode_all(&mut &bytes[..])
+        .map_err(|e| anyhow!("not a transaction of this runtime: {e}"))?;
+    let tx_hash = BlakeTwo256::hash_of(&transaction.encode());
+
+    // Inputs and peeks that are not in the node's storage are reported as missing.
+    let mut input_utxos = Vec::new();
+    for input in &transaction.inputs {
+        input_utxos.push(
+            fetch_storage::<OuterVerifier>(&input.output_ref, client)
+                .await
+                .ok(),
+        );
+    }
+    let mut peek_utxos = Vec::new();
+    for output_ref in &transaction.peeks {
+        peek_utxos.push(
+            fetch_storage::<OuterVerifier>(output_ref, client)
+                .await
+                .ok(),
+        );
+    }
+
+    let result = node_dry_run(&transaction, client).await?;
+    let failing_inputs = match result {
+        Err(DetailedError::VerifierError) => {
+            failing_verifiers(client, &transaction, &input_utxos).await?
+        }
+        _ => Vec::new(),
+    };
+
+    if format == OutputFormat::Json {
+        let utxo_json = |utxo: &Option<Output<OuterVerifier>>| {
+            serde_json::json!({
+                "found": utxo.is_some(),
+                "value": utxo.as_ref().and_then(coin_value),
+                "verifier": utxo.as_ref().map(|utxo| &utxo.verifier),
+            })
+        };
+        crate::print_json(serde_json::json!({
+            "hash": format!("{tx_hash:?}"),
+            "version": format!("{:?}", transaction.version),
+            "checker": format!("{:?}", transaction.checker),
+            "memo": transaction.memo.as_ref().map(|memo| String::from_utf8_lossy(memo)),
+            "valid_until": transaction.valid_until,
+            "inputs": transaction
+                .inputs
+                .iter()
+                .zip(&input_utxos)
+                .map(|(input, utxo)| serde_json::json!({
+                    "output_ref": hex::encode(input.output_ref.encode()),
+                    "mode": format!("{:?}", input.mode),
+                    "utxo": utxo_json(utxo),
+                }))
+                .collect::<Vec<_>>(),
+            "peeks": transaction
+                .peeks
+                .iter()
+                .zip(&peek_utxos)
+                .map(|(output_ref, utxo)| serde_json::json!({
+                    "output_ref": hex::encode(output_ref.encode()),
+                    "utxo": utxo_json(utxo),
+                }))
+                .collect::<Vec<_>>(),
+            "outputs": transaction
+                .outputs
+                .iter()
+                .map(|output| serde_json::json!({
+                    "value": coin_value(output),
+                    "verifier": output.verifier,
+                }))
+                .collect::<Vec<_>>(),
+            "valid": result.is_ok(),
+            "priority": result.as_ref().ok(),
+            "error": result.as_ref().err(),
+            "failing_inputs": failing_inputs,
+        }));
+        return Ok(());
+    }
+
+    println!("Transaction {tx_hash:?}");
+    println!(
+        "Version {:?}, checked by {:?}",
+        transaction.version, transaction.checker
+    );
+    if let Some(memo) = &transaction.memo {
+        println!("Memo: {}", String::from_utf8_lossy(memo));
+    }
+    if let Some(valid_until) = transaction.valid_until {
+        println!("Valid until block {valid_until}");
+    }
+
+    println!("###### Inputs ###########");
+    for (input, utxo) in transaction.inputs.iter().zip(&input_utxos) {
+        print!(
+            "{} ({:?}): ",
+            hex::encode(input.output_ref.encode()),
+            input.mode
+        );
+        print_utxo(utxo);
+    }
+
+    println!("###### Peeks ###########");
+    for (output_ref, utxo) in transaction.peeks.iter().zip(&peek_utxos) {
+        print!("{}: ", hex::encode(output_ref.encode()));
+        print_utxo(utxo);
+    }
+
+    println!("###### Outputs ###########");
+    for output in &transaction.outputs {
+        if let Some(value) = coin_value(output) {
+            print!("worth {value} ");
+        }
+        crate::pretty_print_verifier(&output.verifier);
+    }
+
+    match result {
+        Ok(priority) => println!("The transaction is valid, with priority {priority}"),
+        Err(DetailedError::ConstraintCheckerError(name)) => {
+            println!(
+                "The constraint checker would fail with {name}: {}",
+                name.details
+            )
+        }
+        Err(DetailedError::VerifierError) if !failing_inputs.is_empty() => {
+            for index in failing_inputs {
+                println!("The verifier of input {index} would fail");
+            }
+        }
+        Err(DetailedError::MissingInput) => {
+            println!("Some inputs or peeks are not in the node's storage (yet)")
+        }
+        Err(e) => println!("The transaction would fail with {e:?}"),
+    }
+
+    Ok(())
+}
+
+/// A transaction file holds either the encoded transaction, or the same in hex.
+fn read_file(contents: &[u8]) -> Vec<u8> {
+    std::str::from_utf8(contents)
+        .ok()
+        .and_then(|text| hex::decode(strip_0x_prefix(text.trim())).ok())
+        .unwrap_or_else(|| contents.to_vec())
+}
+
+/// The value of the output, if it is a coin.
+fn coin_value(output: &Output<OuterVerifier>) -> Option<u128> {
+    output.payload.extract::<Coin<0>>().ok().map(|coin| coin.0)
+}
+
+/// Print what a resolved input or peek is, or that it was not found.
+fn print_utxo(utxo: &Option<Output<OuterVerifier>>) {
+    match utxo {
+        Some(utxo) => {
+            if let Some(value) = coin_value(utxo) {
+                print!("worth {value} ");
+            }
+            crate::pretty_print_verifier(&utxo.verifier);
+        }
+        None => println!("not found in the node's storage"),
+    }
+}
+
+/// The indices of the inputs whose verifiers are not satisfied, when checked in the same context
+/// that the node's dry run checked them in. Inputs that were not found can not be checked.
+async fn failing_verifiers(
+    client: &HttpClient,
+    transaction: &Transaction,
+    input_utxos: &[Option<Output<OuterVerifier>>],
+) -> anyhow::Result<Vec<usize>> {
+    let domain = node_get_signing_domain(client).await?;
+    let context = node_get_verifier_context(client).await?;
+    let payloads = transaction.signing_payloads(&domain);
+
+    Ok(transaction
+        .inputs
+        .iter()
+        .zip(input_utxos)
+        .enumerate()
+        .filter_map(|(index, (input, utxo))| {
+            let utxo = utxo.as_ref()?;
+            (!utxo
+                .verifier
+                .verify_input(&payloads, index, &input.redeemer, &context))
+            .then_some(index)
+        })
+        .collect())
+}
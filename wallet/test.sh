@@ -1,6 +1,6 @@
 # Integration tests for the Template Wallet.
 # Requires a `cargo build` to be run before.
 
-./target/debug/node-template --dev &
+./target/debug/node-template --dev --dev-instant-seal &
 sleep 20 &&
 ./target/debug/tuxedo-template-wallet --dev
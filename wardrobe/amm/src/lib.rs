@@ -0,0 +1,420 @@
+//! A constant product automated market maker (AMM) between `Coin<A>` and `Coin<B>`.
+//!
+//! A [`Pool`] UTXO holds reserves of both assets. Liquidity providers deposit both assets into
+//! the pool in proportion to its current reserves and receive [`LpShare`] tokens representing
+//! their portion of the pool. They can later burn those shares to withdraw their portion of
+//! the reserves. Traders swap one asset for the other against the pool, and the product of the
+//! reserves, after deducting a small fee from the deposited side, must never decrease. The fee
+//! stays in the pool, so it accrues to the liquidity providers.
+//!
+//! Every transaction in this piece consumes the pool as its first input and re-creates it as its
+//! first output. Pool outputs should be protected by the `UpForGrabs` verifier so that anyone can
+//! trade against them. The constraint checkers guarantee that nobody can take value out of the
+//! pool without paying for it. All value in a transaction is accounted for, and whatever the
+//! trader or liquidity provider leaves over, in either asset, pays the transaction's fee and tip.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use money::Coin;
+use parity_scale_codec::{Decode, Encode};
+use scale_info::TypeInfo;
+use serde::{Deserialize, Serialize};
+use sp_core::U256;
+use sp_runtime::transaction_validity::TransactionPriority;
+use sp_std::prelude::*;
+use tuxedo_core::{
+    dynamic_typing::{DynamicallyTypedData, UtxoData},
+    ensure, CheckerContext, SimpleConstraintChecker,
+};
+
+#[cfg(test)]
+mod tests;
+
+/// The swap fee, in thousandths of the amount deposited into the pool.
+pub const FEE_PER_THOUSAND: u128 = 3;
+
+/// A liquidity pool holding reserves of `Coin<A>` and `Coin<B>`.
+#[derive(
+    Serialize,
+    Deserialize,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Clone,
+    Encode,
+    Decode,
+    Hash,
+    Debug,
+    TypeInfo,
+)]
+pub struct Pool<const A: u8, const B: u8> {
+    /// The amount of A held by the pool.
+    pub reserve_a: u128,
+    /// The amount of B held by the pool.
+    pub reserve_b: u128,
+    /// The total number of LP shares in circulation for this pool.
+    pub total_shares: u128,
+}
+
+impl<const A: u8, const B: u8> UtxoData for Pool<A, B> {
+    const TYPE_ID: [u8; 4] = [b'a', b'm', A, B];
+}
+
+/// Some shares of the liquidity in the `Coin<A>` / `Coin<B>` pool.
+#[derive(
+    Serialize,
+    Deserialize,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Clone,
+    Encode,
+    Decode,
+    Hash,
+    Debug,
+    TypeInfo,
+)]
+pub struct LpShare<const A: u8, const B: u8>(pub u128);
+
+impl<const A: u8, const B: u8> UtxoData for LpShare<A, B> {
+    const TYPE_ID: [u8; 4] = [b'l', b'p', A, B];
+}
+
+/// Errors that can occur when checking AMM transactions.
+#[derive(
+    Serialize,
+    Deserialize,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Clone,
+    Encode,
+    Decode,
+    Hash,
+    Debug,
+    TypeInfo,
+)]
+pub enum ConstraintCheckerError {
+    /// An input data has the wrong type.
+    BadlyTypedInput,
+    /// An output data has the wrong type.
+    BadlyTypedOutput,
+    /// The pool was not consumed as the first input.
+    MissingPoolInput,
+    /// The pool was not re-created as the first output.
+    MissingPoolOutput,
+    /// A pool, coin, or share with zero value was created. This is not allowed because it wastes state space.
+    ZeroValue,
+    /// The value consumed or created by this transaction overflows the value type.
+    ValueOverflow,
+    /// More value was created than was consumed.
+    OutputsExceedInputs,
+    /// The change in the pool's total shares does not match the shares created and destroyed.
+    SharesNotConserved,
+    /// The pool's reserves changed in a direction that this operation does not allow.
+    WrongDirection,
+    /// Liquidity providers were given more shares than their deposit is worth.
+    TooManySharesMinted,
+    /// Liquidity providers withdrew more reserves than their shares are worth.
+    TooMuchWithdrawn,
+    /// The swap would decrease the product of the reserves after fees.
+    InvariantViolated,
+}
+
+/// The net movement of a single asset within a transaction.
+#[derive(Default)]
+struct Flow {
+    /// Total coins consumed.
+    coins_in: u128,
+    /// Total coins created.
+    coins_out: u128,
+    /// The pool's reserve before the transaction.
+    reserve_before: u128,
+    /// The pool's reserve after the transaction.
+    reserve_after: u128,
+}
+
+impl Flow {
+    /// Make sure the asset is conserved, and return the amount left over.
+    fn burned(&self) -> Result<u128, ConstraintCheckerError> {
+        let total_in = self
+            .coins_in
+            .checked_add(self.reserve_before)
+            .ok_or(ConstraintCheckerError::ValueOverflow)?;
+        let total_out = self
+            .coins_out
+            .checked_add(self.reserve_after)
+            .ok_or(ConstraintCheckerError::ValueOverflow)?;
+        total_in
+            .checked_sub(total_out)
+            .ok_or(ConstraintCheckerError::OutputsExceedInputs)
+    }
+
+    /// The amount deposited into the pool, or zero if the reserve decreased.
+    fn deposited(&self) -> u128 {
+        self.reserve_after.saturating_sub(self.reserve_before)
+    }
+
+    /// The amount withdrawn from the pool, or zero if the reserve increased.
+    fn withdrawn(&self) -> u128 {
+        self.reserve_before.saturating_sub(self.reserve_after)
+    }
+}
+
+/// Everything that moved in a transaction, sorted by type.
+struct Flows<const A: u8, const B: u8> {
+    a: Flow,
+    b: Flow,
+    shares_in: u128,
+    shares_out: u128,
+    pool_before: Option<Pool<A, B>>,
+    pool_after: Pool<A, B>,
+}
+
+impl<const A: u8, const B: u8> Flows<A, B> {
+    /// Sort the inputs and outputs into flows of each asset, shares, and the pool.
+    ///
+    /// When `creating` is true, no pool is consumed. Otherwise the first input must be the pool.
+    /// In either case the first output must be the pool.
+    fn new(
+        input_data: &[DynamicallyTypedData],
+        output_data: &[DynamicallyTypedData],
+        creating: bool,
+    ) -> Result<Self, ConstraintCheckerError> {
+        let mut inputs = input_data.iter();
+        let pool_before = if creating {
+            None
+        } else {
+            let pool = inputs
+                .next()
+                .and_then(|input| input.extract::<Pool<A, B>>().ok())
+                .ok_or(ConstraintCheckerError::MissingPoolInput)?;
+            Some(pool)
+        };
+
+        let mut outputs = output_data.iter();
+        let pool_after = outputs
+            .next()
+            .and_then(|output| output.extract::<Pool<A, B>>().ok())
+            .ok_or(ConstraintCheckerError::MissingPoolOutput)?;
+        ensure!(
+            pool_after.reserve_a > 0 && pool_after.reserve_b > 0 && pool_after.total_shares > 0,
+            ConstraintCheckerError::ZeroValue
+        );
+
+        let mut flows = Self {
+            a: Flow {
+                reserve_before: pool_before.as_ref().map_or(0, |p| p.reserve_a),
+                reserve_after: pool_after.reserve_a,
+                ..Default::default()
+            },
+            b: Flow {
+                reserve_before: pool_before.as_ref().map_or(0, |p| p.reserve_b),
+                reserve_after: pool_after.reserve_b,
+                ..Default::default()
+            },
+            shares_in: 0,
+            shares_out: 0,
+            pool_before,
+            pool_after,
+        };
+
+        for input in inputs {
+            let (total, value) = flows
+                .classify(input)
+                .ok_or(ConstraintCheckerError::BadlyTypedInput)?;
+            let total = match total {
+                Total::A => &mut flows.a.coins_in,
+                Total::B => &mut flows.b.coins_in,
+                Total::Shares => &mut flows.shares_in,
+            };
+            *total = total
+                .checked_add(value)
+                .ok_or(ConstraintCheckerError::ValueOverflow)?;
+        }
+
+        for output in outputs {
+            let (total, value) = flows
+                .classify(output)
+                .ok_or(ConstraintCheckerError::BadlyTypedOutput)?;
+            ensure!(value > 0, ConstraintCheckerError::ZeroValue);
+            let total = match total {
+                Total::A => &mut flows.a.coins_out,
+                Total::B => &mut flows.b.coins_out,
+                Total::Shares => &mut flows.shares_out,
+            };
+            *total = total
+                .checked_add(value)
+                .ok_or(ConstraintCheckerError::ValueOverflow)?;
+        }
+
+        Ok(flows)
+    }
+
+    /// Determine which total a piece of data contributes to, and its value.
+    /// Pools are rejected here because there may only be one on each side.
+    fn classify(&self, data: &DynamicallyTypedData) -> Option<(Total, u128)> {
+        if let Ok(coin) = data.extract::<Coin<A>>() {
+            Some((Total::A, coin.0))
+        } else if let Ok(coin) = data.extract::<Coin<B>>() {
+            Some((Total::B, coin.0))
+        } else if let Ok(shares) = data.extract::<LpShare<A, B>>() {
+            Some((Total::Shares, shares.0))
+        } else {
+            None
+        }
+    }
+
+    /// The pool's total shares before the transaction.
+    fn shares_before(&self) -> u128 {
+        self.pool_before.as_ref().map_or(0, |p| p.total_shares)
+    }
+
+    /// Make sure the change in total shares matches the shares created and destroyed,
+    /// and return the total amount left over across both assets.
+    fn check_conservation(&self) -> Result<u128, ConstraintCheckerError> {
+        let shares_before = self
+            .shares_before()
+            .checked_add(self.shares_out)
+            .ok_or(ConstraintCheckerError::ValueOverflow)?;
+        let shares_after = self
+            .pool_after
+            .total_shares
+            .checked_add(self.shares_in)
+            .ok_or(ConstraintCheckerError::ValueOverflow)?;
+        ensure!(
+            shares_before == shares_after,
+            ConstraintCheckerError::SharesNotConserved
+        );
+
+        Ok(self.a.burned()?.saturating_add(self.b.burned()?))
+    }
+}
+
+/// Which running total a piece of data contributes to.
+enum Total {
+    A,
+    B,
+    Shares,
+}
+
+/// Multiply two values without overflowing.
+fn mul(x: u128, y: u128) -> U256 {
+    U256::from(x) * U256::from(y)
+}
+
+/// The constraint checkers for the `Coin<A>` / `Coin<B>` liquidity pool.
+#[derive(
+    Serialize,
+    Deserialize,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Clone,
+    Encode,
+    Decode,
+    Hash,
+    Debug,
+    TypeInfo,
+)]
+pub enum Amm<const A: u8, const B: u8> {
+    /// Create a new pool with some initial liquidity.
+    ///
+    /// Inputs are `Coin<A>`s and `Coin<B>`s. The first output is the new pool and the remaining
+    /// outputs are the initial LP shares, and optionally coin change. The creator chooses the
+    /// initial number of shares and the initial reserves set the initial price.
+    CreatePool,
+    /// Deposit both assets into the pool in exchange for newly minted LP shares.
+    ///
+    /// The shares minted are worth no more than the smaller of the two deposits relative to
+    /// the existing reserves.
+    AddLiquidity,
+    /// Burn LP shares in exchange for a proportional amount of both reserves.
+    RemoveLiquidity,
+    /// Swap one asset for the other.
+    ///
+    /// The total shares must not change, and the product of the reserves, after deducting
+    /// the fee from the deposited side, must not decrease.
+    Swap,
+}
+
+impl<const A: u8, const B: u8> SimpleConstraintChecker for Amm<A, B> {
+    type Error = ConstraintCheckerError;
+
+    fn check(
+        &self,
+        input_data: &[DynamicallyTypedData],
+        _peeks: &[DynamicallyTypedData],
+        output_data: &[DynamicallyTypedData],
+        _context: &CheckerContext,
+    ) -> Result<TransactionPriority, Self::Error> {
+        let flows = Flows::<A, B>::new(input_data, output_data, self == &Self::CreatePool)?;
+        let burned = flows.check_conservation()?;
+
+        let before = flows.pool_before.as_ref();
+        let after = &flows.pool_after;
+        match self {
+            Self::CreatePool => {
+                // Conservation is all there is to check for a brand new pool.
+            }
+            Self::AddLiquidity => {
+                let before = before.ok_or(ConstraintCheckerError::MissingPoolInput)?;
+                ensure!(
+                    flows.a.withdrawn() == 0 && flows.b.withdrawn() == 0,
+                    ConstraintCheckerError::WrongDirection
+                );
+                let minted = after
+                    .total_shares
+                    .checked_sub(before.total_shares)
+                    .ok_or(ConstraintCheckerError::WrongDirection)?;
+                ensure!(
+                    mul(minted, before.reserve_a) <= mul(flows.a.deposited(), before.total_shares)
+                        && mul(minted, before.reserve_b)
+                            <= mul(flows.b.deposited(), before.total_shares),
+                    ConstraintCheckerError::TooManySharesMinted
+                );
+            }
+            Self::RemoveLiquidity => {
+                let before = before.ok_or(ConstraintCheckerError::MissingPoolInput)?;
+                ensure!(
+                    flows.a.deposited() == 0 && flows.b.deposited() == 0,
+                    ConstraintCheckerError::WrongDirection
+                );
+                let redeemed = before
+                    .total_shares
+                    .checked_sub(after.total_shares)
+                    .ok_or(ConstraintCheckerError::WrongDirection)?;
+                ensure!(
+                    mul(flows.a.withdrawn(), before.total_shares)
+                        <= mul(redeemed, before.reserve_a)
+                        && mul(flows.b.withdrawn(), before.total_shares)
+                            <= mul(redeemed, before.reserve_b),
+                    ConstraintCheckerError::TooMuchWithdrawn
+                );
+            }
+            Self::Swap => {
+                let before = before.ok_or(ConstraintCheckerError::MissingPoolInput)?;
+                ensure!(
+                    after.total_shares == before.total_shares,
+                    ConstraintCheckerError::SharesNotConserved
+                );
+
+                // Deduct the fee from whatever was deposited, then compare the products.
+                let adjusted = |flow: &Flow| {
+                    mul(flow.reserve_after, 1000) - mul(flow.deposited(), FEE_PER_THOUSAND)
+                };
+                ensure!(
+                    adjusted(&flows.a) * adjusted(&flows.b)
+                        >= mul(before.reserve_a, before.reserve_b) * U256::from(1_000_000u32),
+                    ConstraintCheckerError::InvariantViolated
+                );
+            }
+        }
+
+        Ok(burned.try_into().unwrap_or(TransactionPriority::MAX))
+    }
+}
@@ -0,0 +1,284 @@
+//! Unit tests for the AMM piece
+
+use super::*;
+use tuxedo_core::dynamic_typing::testing::Bogus;
+
+type Market = Amm<0, 1>;
+
+fn pool(reserve_a: u128, reserve_b: u128, total_shares: u128) -> DynamicallyTypedData {
+    Pool::<0, 1> {
+        reserve_a,
+        reserve_b,
+        total_shares,
+    }
+    .into()
+}
+
+fn coin_a(value: u128) -> DynamicallyTypedData {
+    Coin::<0>(value).into()
+}
+
+fn coin_b(value: u128) -> DynamicallyTypedData {
+    Coin::<1>(value).into()
+}
+
+fn shares(value: u128) -> DynamicallyTypedData {
+    LpShare::<0, 1>(value).into()
+}
+
+fn check(
+    checker: Market,
+    inputs: Vec<DynamicallyTypedData>,
+    outputs: Vec<DynamicallyTypedData>,
+) -> Result<TransactionPriority, ConstraintCheckerError> {
+    SimpleConstraintChecker::check(&checker, &inputs, &[], &outputs, &Default::default())
+}
+
+#[test]
+fn create_pool_works() {
+    let inputs = vec![coin_a(1000), coin_b(2000)];
+    let outputs = vec![pool(1000, 2000, 100), shares(100)];
+
+    assert_eq!(check(Market::CreatePool, inputs, outputs), Ok(0));
+}
+
+#[test]
+fn create_pool_with_change_and_burn_works() {
+    let inputs = vec![coin_a(1100), coin_b(2000)];
+    let outputs = vec![pool(1000, 1990, 100), shares(100), coin_a(95)];
+
+    assert_eq!(check(Market::CreatePool, inputs, outputs), Ok(15));
+}
+
+#[test]
+fn create_pool_with_empty_reserve_fails() {
+    let inputs = vec![coin_a(1000)];
+    let outputs = vec![pool(1000, 0, 100), shares(100)];
+
+    assert_eq!(
+        check(Market::CreatePool, inputs, outputs),
+        Err(ConstraintCheckerError::ZeroValue)
+    );
+}
+
+#[test]
+fn create_pool_exceeding_inputs_fails() {
+    let inputs = vec![coin_a(1000), coin_b(1000)];
+    let outputs = vec![pool(1000, 2000, 100), shares(100)];
+
+    assert_eq!(
+        check(Market::CreatePool, inputs, outputs),
+        Err(ConstraintCheckerError::OutputsExceedInputs)
+    );
+}
+
+#[test]
+fn create_pool_with_unbacked_shares_fails() {
+    let inputs = vec![coin_a(1000), coin_b(2000)];
+    let outputs = vec![pool(1000, 2000, 100), shares(150)];
+
+    assert_eq!(
+        check(Market::CreatePool, inputs, outputs),
+        Err(ConstraintCheckerError::SharesNotConserved)
+    );
+}
+
+#[test]
+fn create_pool_without_pool_output_fails() {
+    let inputs = vec![coin_a(1000), coin_b(2000)];
+    let outputs = vec![shares(100)];
+
+    assert_eq!(
+        check(Market::CreatePool, inputs, outputs),
+        Err(ConstraintCheckerError::MissingPoolOutput)
+    );
+}
+
+#[test]
+fn create_second_pool_in_same_transaction_fails() {
+    let inputs = vec![coin_a(2000), coin_b(4000)];
+    let outputs = vec![pool(1000, 2000, 100), pool(1000, 2000, 100), shares(100)];
+
+    assert_eq!(
+        check(Market::CreatePool, inputs, outputs),
+        Err(ConstraintCheckerError::BadlyTypedOutput)
+    );
+}
+
+#[test]
+fn add_liquidity_works() {
+    let inputs = vec![pool(1000, 2000, 100), coin_a(100), coin_b(200)];
+    let outputs = vec![pool(1100, 2200, 110), shares(10)];
+
+    assert_eq!(check(Market::AddLiquidity, inputs, outputs), Ok(0));
+}
+
+#[test]
+fn add_liquidity_with_unbalanced_deposit_mints_for_smaller_side() {
+    let inputs = vec![pool(1000, 2000, 100), coin_a(100), coin_b(300)];
+    let outputs = vec![pool(1100, 2300, 110), shares(10)];
+
+    assert_eq!(check(Market::AddLiquidity, inputs, outputs), Ok(0));
+}
+
+#[test]
+fn add_liquidity_minting_too_many_shares_fails() {
+    let inputs = vec![pool(1000, 2000, 100), coin_a(100), coin_b(200)];
+    let outputs = vec![pool(1100, 2200, 111), shares(11)];
+
+    assert_eq!(
+        check(Market::AddLiquidity, inputs, outputs),
+        Err(ConstraintCheckerError::TooManySharesMinted)
+    );
+}
+
+#[test]
+fn add_liquidity_without_pool_input_fails() {
+    let inputs = vec![coin_a(100), coin_b(200)];
+    let outputs = vec![pool(100, 200, 10), shares(10)];
+
+    assert_eq!(
+        check(Market::AddLiquidity, inputs, outputs),
+        Err(ConstraintCheckerError::MissingPoolInput)
+    );
+}
+
+#[test]
+fn add_liquidity_while_withdrawing_fails() {
+    let inputs = vec![pool(1000, 2000, 100), coin_a(200)];
+    let outputs = vec![pool(1200, 1900, 100), coin_b(100)];
+
+    assert_eq!(
+        check(Market::AddLiquidity, inputs, outputs),
+        Err(ConstraintCheckerError::WrongDirection)
+    );
+}
+
+#[test]
+fn remove_liquidity_works() {
+    let inputs = vec![pool(1000, 2000, 100), shares(10)];
+    let outputs = vec![pool(900, 1800, 90), coin_a(100), coin_b(200)];
+
+    assert_eq!(check(Market::RemoveLiquidity, inputs, outputs), Ok(0));
+}
+
+#[test]
+fn remove_liquidity_with_share_change_works() {
+    let inputs = vec![pool(1000, 2000, 100), shares(15)];
+    let outputs = vec![pool(900, 1800, 90), coin_a(100), coin_b(200), shares(5)];
+
+    assert_eq!(check(Market::RemoveLiquidity, inputs, outputs), Ok(0));
+}
+
+#[test]
+fn remove_liquidity_withdrawing_too_much_fails() {
+    let inputs = vec![pool(1000, 2000, 100), shares(10)];
+    let outputs = vec![pool(899, 1800, 90), coin_a(101), coin_b(200)];
+
+    assert_eq!(
+        check(Market::RemoveLiquidity, inputs, outputs),
+        Err(ConstraintCheckerError::TooMuchWithdrawn)
+    );
+}
+
+#[test]
+fn remove_liquidity_without_burning_shares_fails() {
+    let inputs = vec![pool(1000, 2000, 100)];
+    let outputs = vec![pool(900, 1800, 90), coin_a(100), coin_b(200)];
+
+    assert_eq!(
+        check(Market::RemoveLiquidity, inputs, outputs),
+        Err(ConstraintCheckerError::SharesNotConserved)
+    );
+}
+
+#[test]
+fn swap_a_for_b_works() {
+    let inputs = vec![pool(1000, 1000, 100), coin_a(100)];
+    let outputs = vec![pool(1100, 910, 100), coin_b(90)];
+
+    assert_eq!(check(Market::Swap, inputs, outputs), Ok(0));
+}
+
+#[test]
+fn swap_b_for_a_works() {
+    let inputs = vec![pool(1000, 1000, 100), coin_b(100)];
+    let outputs = vec![pool(910, 1100, 100), coin_a(90)];
+
+    assert_eq!(check(Market::Swap, inputs, outputs), Ok(0));
+}
+
+#[test]
+fn swap_ignoring_fee_fails() {
+    // Without the fee, 1100 * 909 would just preserve the product.
+    let inputs = vec![pool(1000, 1000, 100), coin_a(100)];
+    let outputs = vec![pool(1100, 909, 100), coin_b(91)];
+
+    assert_eq!(
+        check(Market::Swap, inputs, outputs),
+        Err(ConstraintCheckerError::InvariantViolated)
+    );
+}
+
+#[test]
+fn swap_draining_pool_fails() {
+    let inputs = vec![pool(1000, 1000, 100), coin_a(1)];
+    let outputs = vec![pool(1001, 1, 100), coin_b(999)];
+
+    assert_eq!(
+        check(Market::Swap, inputs, outputs),
+        Err(ConstraintCheckerError::InvariantViolated)
+    );
+}
+
+#[test]
+fn swap_taking_more_than_reserve_change_fails() {
+    let inputs = vec![pool(1000, 1000, 100), coin_a(100)];
+    let outputs = vec![pool(1100, 910, 100), coin_b(100)];
+
+    assert_eq!(
+        check(Market::Swap, inputs, outputs),
+        Err(ConstraintCheckerError::OutputsExceedInputs)
+    );
+}
+
+#[test]
+fn swap_minting_shares_fails() {
+    let inputs = vec![pool(1000, 1000, 100), coin_a(100)];
+    let outputs = vec![pool(1100, 910, 110), coin_b(90), shares(10)];
+
+    assert_eq!(
+        check(Market::Swap, inputs, outputs),
+        Err(ConstraintCheckerError::SharesNotConserved)
+    );
+}
+
+#[test]
+fn swap_with_bogus_input_fails() {
+    let inputs = vec![pool(1000, 1000, 100), Bogus.into()];
+    let outputs = vec![pool(1000, 1000, 100)];
+
+    assert_eq!(
+        check(Market::Swap, inputs, outputs),
+        Err(ConstraintCheckerError::BadlyTypedInput)
+    );
+}
+
+#[test]
+fn swap_with_wrong_pool_type_fails() {
+    let inputs = vec![
+        Pool::<0, 2> {
+            reserve_a: 1000,
+            reserve_b: 1000,
+            total_shares: 100,
+        }
+        .into(),
+        coin_a(100),
+    ];
+    let outputs = vec![pool(1100, 910, 100), coin_b(90)];
+
+    assert_eq!(
+        check(Market::Swap, inputs, outputs),
+        Err(ConstraintCheckerError::MissingPoolInput)
+    );
+}
@@ -7,6 +7,8 @@
 //!    is consumed, and nothing new is created.
 //! 3. An existing amoeba can undergo mitosis. Mitosis is a process that consumes the
 //!    mother amoeba and creates, in its place two new daughter amoebas.
+//! 4. Two existing amoebas can undergo fusion. Fusion is the opposite of mitosis: it consumes
+//!    two parent amoebas and creates, in their place, a single fused amoeba.
 
 #![cfg_attr(not(feature = "std"), no_std)]
 
@@ -16,14 +18,16 @@ use serde::{Deserialize, Serialize};
 use sp_runtime::transaction_validity::TransactionPriority;
 use tuxedo_core::{
     dynamic_typing::{DynamicallyTypedData, UtxoData},
-    ensure, SimpleConstraintChecker,
+    ensure, CheckerContext, SimpleConstraintChecker,
 };
 
 #[cfg(test)]
 mod tests;
 
 /// An amoeba tracked by our simple Amoeba APP
-#[derive(Serialize, Deserialize, Encode, Decode, Debug, PartialEq, Eq, Clone)]
+#[derive(
+    Serialize, Deserialize, Encode, Decode, Debug, PartialEq, Eq, Clone, TypeInfo, UtxoData,
+)]
 pub struct AmoebaDetails {
     /// How many generations after the original Eve Amoeba this one is.
     /// When going through mitosis, this number must increase by 1 each time.
@@ -33,10 +37,6 @@ pub struct AmoebaDetails {
     pub four_bytes: [u8; 4],
 }
 
-impl UtxoData for AmoebaDetails {
-    const TYPE_ID: [u8; 4] = *b"amoe";
-}
-
 /// Reasons that the amoeba constraint checkers may fail
 #[derive(Debug, Eq, PartialEq)]
 pub enum ConstraintCheckerError {
@@ -68,6 +68,12 @@ pub enum ConstraintCheckerError {
     /// Amoeba mitosis requires exactly one mother amoeba to be consumed.
     /// Consuming any more or fewer than that is invalid.
     WrongNumberOfMothers,
+    /// Amoeba fusion requires exactly two parent amoebas to be consumed.
+    /// Consuming any more or fewer than that is invalid.
+    WrongNumberOfFusingAmoebas,
+    /// Amoeba fusion requires exactly one fused amoeba to be created.
+    /// Creating more or fewer than that is invalid.
+    WrongNumberOfFusedAmoebas,
     // Now we get on to the actual amoeba-specific errors
     /// The daughters did not have to right generation based on the mother.
     WrongGeneration,
@@ -89,6 +95,7 @@ impl SimpleConstraintChecker for AmoebaMitosis {
         input_data: &[DynamicallyTypedData],
         _peeks: &[DynamicallyTypedData],
         output_data: &[DynamicallyTypedData],
+        _context: &CheckerContext,
     ) -> Result<TransactionPriority, ConstraintCheckerError> {
         // Make sure there is exactly one mother.
         ensure!(
@@ -132,6 +139,55 @@ impl SimpleConstraintChecker for AmoebaMitosis {
     }
 }
 
+/// A constraint checker for the process of amoeba fusion
+/// The fusion is valid is the following criteria are met
+/// 1. There are exactly two parent amoebas.
+/// 2. There is exactly one fused amoeba.
+/// 3. The fused amoeba has a generation one higher than its older parent.
+#[derive(Serialize, Deserialize, Encode, Decode, Debug, PartialEq, Eq, Clone, TypeInfo)]
+pub struct AmoebaFusion;
+
+impl SimpleConstraintChecker for AmoebaFusion {
+    type Error = ConstraintCheckerError;
+
+    fn check(
+        &self,
+        input_data: &[DynamicallyTypedData],
+        _peeks: &[DynamicallyTypedData],
+        output_data: &[DynamicallyTypedData],
+        _context: &CheckerContext,
+    ) -> Result<TransactionPriority, ConstraintCheckerError> {
+        // Make sure there are exactly two parents.
+        ensure!(
+            input_data.len() == 2,
+            ConstraintCheckerError::WrongNumberOfFusingAmoebas
+        );
+        let first_parent = input_data[0]
+            .extract::<AmoebaDetails>()
+            .map_err(|_| ConstraintCheckerError::BadlyTypedInput)?;
+        let second_parent = input_data[1]
+            .extract::<AmoebaDetails>()
+            .map_err(|_| ConstraintCheckerError::BadlyTypedInput)?;
+
+        // Make sure there is exactly one fused amoeba.
+        ensure!(
+            output_data.len() == 1,
+            ConstraintCheckerError::WrongNumberOfFusedAmoebas
+        );
+        let fused = output_data[0]
+            .extract::<AmoebaDetails>()
+            .map_err(|_| ConstraintCheckerError::BadlyTypedOutput)?;
+
+        // Make sure the generation is correct
+        ensure!(
+            fused.generation == first_parent.generation.max(second_parent.generation) + 1,
+            ConstraintCheckerError::WrongGeneration
+        );
+
+        Ok(0)
+    }
+}
+
 /// A constraint checker for simple death of an amoeba.
 ///
 /// Any amoeba can be killed by providing it as the sole input to this constraint checker. No
@@ -147,6 +203,7 @@ impl SimpleConstraintChecker for AmoebaDeath {
         input_data: &[DynamicallyTypedData],
         _peeks: &[DynamicallyTypedData],
         output_data: &[DynamicallyTypedData],
+        _context: &CheckerContext,
     ) -> Result<TransactionPriority, Self::Error> {
         // Make sure there is a single victim
         ensure!(!input_data.is_empty(), ConstraintCheckerError::NoVictim);
@@ -186,6 +243,7 @@ impl SimpleConstraintChecker for AmoebaCreation {
         input_data: &[DynamicallyTypedData],
         _peeks: &[DynamicallyTypedData],
         output_data: &[DynamicallyTypedData],
+        _context: &CheckerContext,
     ) -> Result<TransactionPriority, Self::Error> {
         // Make sure there is a single created amoeba
         ensure!(
@@ -0,0 +1,329 @@
+//! Trustless exchange of `Coin<A>` for `Coin<B>`.
+//!
+//! A maker who wants to sell some `Coin<A>` locks them into a [`SwapOffer`] that records the
+//! price they want in `Coin<B>` and the verifier their payment should be locked to. Offers
+//! should be protected by the `UpForGrabs` verifier so that any taker may consume them. The
+//! constraint checker, rather than the verifier, guarantees that the maker is paid.
+//!
+//! A taker consumes the offer along with some of their own `Coin<B>`s. In the same transaction
+//! they receive the `Coin<A>`s and the maker receives the payment, so both legs of the trade
+//! settle together or not at all. Offers may be partially filled, in which case a reduced
+//! offer at the same price is re-created.
+//!
+//! Because offers are up for grabs, anyone may cancel an offer. However, the refund always goes
+//! to the maker's payout verifier, so the worst a griefer can do is force the maker to repost.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use money::Coin;
+use parity_scale_codec::{Decode, Encode};
+use scale_info::TypeInfo;
+use serde::{Deserialize, Serialize};
+use sp_runtime::transaction_validity::TransactionPriority;
+use sp_std::prelude::*;
+use tuxedo_core::{
+    dynamic_typing::UtxoData, ensure, types::Output, CheckerContext, ConstraintChecker, Verifier,
+};
+
+#[cfg(test)]
+mod tests;
+
+/// An open offer to sell `Coin<A>` in exchange for `Coin<B>`.
+///
+/// The price is expressed as a ratio: `price_b` units of B are asked for every `price_a` units of A.
+#[derive(
+    Serialize,
+    Deserialize,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Clone,
+    Encode,
+    Decode,
+    Hash,
+    Debug,
+    TypeInfo,
+)]
+pub struct SwapOffer<V, const A: u8, const B: u8> {
+    /// The amount of A that is still available in this offer.
+    pub amount: u128,
+    /// The A side of the price ratio.
+    pub price_a: u128,
+    /// The B side of the price ratio.
+    pub price_b: u128,
+    /// The verifier that payments and refunds must be locked to.
+    pub maker: V,
+}
+
+impl<V: Encode + Decode, const A: u8, const B: u8> UtxoData for SwapOffer<V, A, B> {
+    const TYPE_ID: [u8; 4] = [b's', b'w', A, B];
+}
+
+/// Errors that can occur when checking atomic swap transactions.
+#[derive(
+    Serialize,
+    Deserialize,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Clone,
+    Encode,
+    Decode,
+    Hash,
+    Debug,
+    TypeInfo,
+)]
+pub enum ConstraintCheckerError {
+    /// An input data has the wrong type.
+    BadlyTypedInput,
+    /// An output data has the wrong type.
+    BadlyTypedOutput,
+    /// Wrong number of inputs were provided to the constraint checker.
+    WrongNumberInputs,
+    /// Wrong number of outputs were provided to the constraint checker.
+    WrongNumberOutputs,
+    /// An offer or coin with zero value was created. This is not allowed because it wastes state space.
+    ZeroValue,
+    /// An offer's price has a zero on one side of the ratio.
+    InvalidPrice,
+    /// The value consumed or created by this transaction overflows the value type.
+    ValueOverflow,
+    /// More value was created than was consumed.
+    OutputsExceedInputs,
+    /// The amount of A taken plus the amount left in the offer does not equal the amount originally offered.
+    OfferNotConserved,
+    /// The reduced offer created by a partial fill differs from the original in something other than its amount.
+    RemainderMismatch,
+    /// The maker was not paid enough to satisfy the offer's price.
+    Underpaid,
+    /// A refund was locked to some verifier other than the maker's.
+    RefundToWrongVerifier,
+}
+
+/// The constraint checkers for trading `Coin<A>` for `Coin<B>`.
+#[derive(
+    Serialize,
+    Deserialize,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Clone,
+    Encode,
+    Decode,
+    Hash,
+    Debug,
+    TypeInfo,
+)]
+pub enum AtomicSwap<const A: u8, const B: u8> {
+    /// Lock some `Coin<A>` into new offers.
+    ///
+    /// All inputs are `Coin<A>`s. Outputs are offers, and optionally `Coin<A>` change.
+    /// The maker pays the transaction's fee and tip with whatever `Coin<A>` is left over.
+    MakeOffer,
+    /// Take some or all of an offer.
+    ///
+    /// The first input is the offer and all other inputs are the taker's `Coin<B>`s.
+    /// The outputs are the `Coin<A>`s that the taker receives, the `Coin<B>`s that the maker
+    /// receives and any `Coin<B>` change for the taker, as well as at most one reduced offer
+    /// if the offer is only partially filled. The taker pays the transaction's fee and tip
+    /// with whatever `Coin<B>` is left over.
+    TakeOffer,
+    /// Cancel an offer and refund the remaining `Coin<A>` to the maker.
+    CancelOffer,
+}
+
+impl<const A: u8, const B: u8> AtomicSwap<A, B> {
+    /// Sum the value of some coins, making sure they are all `Coin<ID>`s with non-zero value.
+    fn sum_coins<V, const ID: u8>(
+        coins: &[&Output<V>],
+        error: ConstraintCheckerError,
+    ) -> Result<u128, ConstraintCheckerError> {
+        coins.iter().try_fold(0u128, |total, coin| {
+            let value = coin
+                .payload
+                .extract::<Coin<ID>>()
+                .map_err(|_| error.clone())?
+                .0;
+            ensure!(value > 0, ConstraintCheckerError::ZeroValue);
+            total
+                .checked_add(value)
+                .ok_or(ConstraintCheckerError::ValueOverflow)
+        })
+    }
+}
+
+impl<V: Verifier + PartialEq, const A: u8, const B: u8> ConstraintChecker<V> for AtomicSwap<A, B> {
+    type Error = ConstraintCheckerError;
+    type InherentHooks = ();
+
+    fn check(
+        &self,
+        inputs: &[Output<V>],
+        _peeks: &[Output<V>],
+        outputs: &[Output<V>],
+        _context: &CheckerContext,
+    ) -> Result<TransactionPriority, Self::Error> {
+        match self {
+            Self::MakeOffer => {
+                let inputs: Vec<_> = inputs.iter().collect();
+                let total_input =
+                    Self::sum_coins::<V, A>(&inputs, ConstraintCheckerError::BadlyTypedInput)?;
+
+                let mut offered: u128 = 0;
+                let mut change = Vec::new();
+                for output in outputs {
+                    if let Ok(offer) = output.payload.extract::<SwapOffer<V, A, B>>() {
+                        ensure!(offer.amount > 0, ConstraintCheckerError::ZeroValue);
+                        ensure!(
+                            offer.price_a > 0 && offer.price_b > 0,
+                            ConstraintCheckerError::InvalidPrice
+                        );
+                        offered = offered
+                            .checked_add(offer.amount)
+                            .ok_or(ConstraintCheckerError::ValueOverflow)?;
+                    } else {
+                        change.push(output);
+                    }
+                }
+                ensure!(offered > 0, ConstraintCheckerError::WrongNumberOutputs);
+
+                let total_output =
+                    Self::sum_coins::<V, A>(&change, ConstraintCheckerError::BadlyTypedOutput)?
+                        .checked_add(offered)
+                        .ok_or(ConstraintCheckerError::ValueOverflow)?;
+                ensure!(
+                    total_output <= total_input,
+                    ConstraintCheckerError::OutputsExceedInputs
+                );
+
+                let burned = total_input - total_output;
+                Ok(burned.try_into().unwrap_or(TransactionPriority::MAX))
+            }
+            Self::TakeOffer => {
+                ensure!(
+                    !inputs.is_empty(),
+                    ConstraintCheckerError::WrongNumberInputs
+                );
+                let offer = inputs[0]
+                    .payload
+                    .extract::<SwapOffer<V, A, B>>()
+                    .map_err(|_| ConstraintCheckerError::BadlyTypedInput)?;
+                let taker_coins: Vec<_> = inputs[1..].iter().collect();
+                let taker_b =
+                    Self::sum_coins::<V, B>(&taker_coins, ConstraintCheckerError::BadlyTypedInput)?;
+
+                // Sort the outputs into what the taker receives, what the maker receives,
+                // the taker's change, and the reduced offer.
+                let mut a_outputs = Vec::new();
+                let mut payments = Vec::new();
+                let mut b_change = Vec::new();
+                let mut remainder = None;
+                for output in outputs {
+                    if output.payload.type_id == <Coin<A> as UtxoData>::TYPE_ID {
+                        a_outputs.push(output);
+                    } else if output.payload.type_id == <Coin<B> as UtxoData>::TYPE_ID {
+                        if output.verifier == offer.maker {
+                            payments.push(output);
+                        } else {
+                            b_change.push(output);
+                        }
+                    } else {
+                        let new_offer = output
+                            .payload
+                            .extract::<SwapOffer<V, A, B>>()
+                            .map_err(|_| ConstraintCheckerError::BadlyTypedOutput)?;
+                        ensure!(
+                            remainder.is_none(),
+                            ConstraintCheckerError::WrongNumberOutputs
+                        );
+                        ensure!(
+                            output.verifier == inputs[0].verifier,
+                            ConstraintCheckerError::RemainderMismatch
+                        );
+                        remainder = Some(new_offer);
+                    }
+                }
+
+                // Make sure the A side of the trade is conserved.
+                let taken =
+                    Self::sum_coins::<V, A>(&a_outputs, ConstraintCheckerError::BadlyTypedOutput)?;
+                ensure!(taken > 0, ConstraintCheckerError::WrongNumberOutputs);
+                let left = match remainder {
+                    Some(new_offer) => {
+                        ensure!(
+                            new_offer.price_a == offer.price_a
+                                && new_offer.price_b == offer.price_b
+                                && new_offer.maker == offer.maker,
+                            ConstraintCheckerError::RemainderMismatch
+                        );
+                        ensure!(new_offer.amount > 0, ConstraintCheckerError::ZeroValue);
+                        new_offer.amount
+                    }
+                    None => 0,
+                };
+                ensure!(
+                    taken.checked_add(left) == Some(offer.amount),
+                    ConstraintCheckerError::OfferNotConserved
+                );
+
+                // Make sure the maker is paid at least their asking price.
+                let paid =
+                    Self::sum_coins::<V, B>(&payments, ConstraintCheckerError::BadlyTypedOutput)?;
+                let paid_scaled = paid
+                    .checked_mul(offer.price_a)
+                    .ok_or(ConstraintCheckerError::ValueOverflow)?;
+                let asked_scaled = taken
+                    .checked_mul(offer.price_b)
+                    .ok_or(ConstraintCheckerError::ValueOverflow)?;
+                ensure!(
+                    paid_scaled >= asked_scaled,
+                    ConstraintCheckerError::Underpaid
+                );
+
+                // Make sure the B side does not create money.
+                let total_b_output =
+                    Self::sum_coins::<V, B>(&b_change, ConstraintCheckerError::BadlyTypedOutput)?
+                        .checked_add(paid)
+                        .ok_or(ConstraintCheckerError::ValueOverflow)?;
+                ensure!(
+                    total_b_output <= taker_b,
+                    ConstraintCheckerError::OutputsExceedInputs
+                );
+
+                let burned = taker_b - total_b_output;
+                Ok(burned.try_into().unwrap_or(TransactionPriority::MAX))
+            }
+            Self::CancelOffer => {
+                ensure!(inputs.len() == 1, ConstraintCheckerError::WrongNumberInputs);
+                let offer = inputs[0]
+                    .payload
+                    .extract::<SwapOffer<V, A, B>>()
+                    .map_err(|_| ConstraintCheckerError::BadlyTypedInput)?;
+
+                for output in outputs {
+                    ensure!(
+                        output.verifier == offer.maker,
+                        ConstraintCheckerError::RefundToWrongVerifier
+                    );
+                }
+                let refunds: Vec<_> = outputs.iter().collect();
+                let refunded =
+                    Self::sum_coins::<V, A>(&refunds, ConstraintCheckerError::BadlyTypedOutput)?;
+                ensure!(
+                    refunded <= offer.amount,
+                    ConstraintCheckerError::OutputsExceedInputs
+                );
+
+                let burned = offer.amount - refunded;
+                Ok(burned.try_into().unwrap_or(TransactionPriority::MAX))
+            }
+        }
+    }
+
+    fn is_inherent(&self) -> bool {
+        false
+    }
+}
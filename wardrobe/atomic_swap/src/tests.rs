@@ -0,0 +1,242 @@
+//! Unit tests for the Atomic Swap piece
+
+use super::*;
+use sp_core::H256;
+use tuxedo_core::{dynamic_typing::testing::Bogus, verifier::SigCheck};
+
+type Swap = AtomicSwap<0, 1>;
+type Offer = SwapOffer<SigCheck, 0, 1>;
+
+fn maker() -> SigCheck {
+    SigCheck::new(H256::repeat_byte(1))
+}
+
+fn taker() -> SigCheck {
+    SigCheck::new(H256::repeat_byte(2))
+}
+
+/// An offer selling `amount` of asset 0 at two units of asset 1 each.
+fn offer(amount: u128) -> Offer {
+    Offer {
+        amount,
+        price_a: 1,
+        price_b: 2,
+        maker: maker(),
+    }
+}
+
+fn check(
+    checker: Swap,
+    inputs: Vec<Output<SigCheck>>,
+    outputs: Vec<Output<SigCheck>>,
+) -> Result<TransactionPriority, ConstraintCheckerError> {
+    ConstraintChecker::<SigCheck>::check(&checker, &inputs, &[], &outputs, &Default::default())
+}
+
+#[test]
+fn make_offer_works() {
+    let inputs = vec![(Coin::<0>(100), maker()).into()];
+    let outputs = vec![(offer(60), maker()).into(), (Coin::<0>(30), maker()).into()];
+
+    assert_eq!(check(Swap::MakeOffer, inputs, outputs), Ok(10));
+}
+
+#[test]
+fn make_offer_exceeding_inputs_fails() {
+    let inputs = vec![(Coin::<0>(100), maker()).into()];
+    let outputs = vec![(offer(60), maker()).into(), (Coin::<0>(50), maker()).into()];
+
+    assert_eq!(
+        check(Swap::MakeOffer, inputs, outputs),
+        Err(ConstraintCheckerError::OutputsExceedInputs)
+    );
+}
+
+#[test]
+fn make_offer_with_zero_price_fails() {
+    let inputs = vec![(Coin::<0>(100), maker()).into()];
+    let mut bad_offer = offer(100);
+    bad_offer.price_a = 0;
+    let outputs = vec![(bad_offer, maker()).into()];
+
+    assert_eq!(
+        check(Swap::MakeOffer, inputs, outputs),
+        Err(ConstraintCheckerError::InvalidPrice)
+    );
+}
+
+#[test]
+fn make_offer_with_wrong_asset_fails() {
+    let inputs = vec![(Coin::<1>(100), maker()).into()];
+    let outputs = vec![(offer(100), maker()).into()];
+
+    assert_eq!(
+        check(Swap::MakeOffer, inputs, outputs),
+        Err(ConstraintCheckerError::BadlyTypedInput)
+    );
+}
+
+#[test]
+fn make_no_offer_fails() {
+    let inputs = vec![(Coin::<0>(100), maker()).into()];
+    let outputs = vec![(Coin::<0>(100), taker()).into()];
+
+    assert_eq!(
+        check(Swap::MakeOffer, inputs, outputs),
+        Err(ConstraintCheckerError::WrongNumberOutputs)
+    );
+}
+
+#[test]
+fn take_whole_offer_works() {
+    let inputs = vec![(offer(10), maker()).into(), (Coin::<1>(25), taker()).into()];
+    let outputs = vec![
+        (Coin::<0>(10), taker()).into(),
+        (Coin::<1>(20), maker()).into(),
+        (Coin::<1>(4), taker()).into(),
+    ];
+
+    assert_eq!(check(Swap::TakeOffer, inputs, outputs), Ok(1));
+}
+
+#[test]
+fn take_partial_offer_works() {
+    let inputs = vec![(offer(10), maker()).into(), (Coin::<1>(8), taker()).into()];
+    let outputs = vec![
+        (Coin::<0>(4), taker()).into(),
+        (Coin::<1>(8), maker()).into(),
+        (offer(6), maker()).into(),
+    ];
+
+    assert_eq!(check(Swap::TakeOffer, inputs, outputs), Ok(0));
+}
+
+#[test]
+fn take_offer_underpaying_fails() {
+    let inputs = vec![(offer(10), maker()).into(), (Coin::<1>(25), taker()).into()];
+    let outputs = vec![
+        (Coin::<0>(10), taker()).into(),
+        (Coin::<1>(19), maker()).into(),
+        (Coin::<1>(6), taker()).into(),
+    ];
+
+    assert_eq!(
+        check(Swap::TakeOffer, inputs, outputs),
+        Err(ConstraintCheckerError::Underpaid)
+    );
+}
+
+#[test]
+fn take_offer_paying_wrong_verifier_fails() {
+    let inputs = vec![(offer(10), maker()).into(), (Coin::<1>(20), taker()).into()];
+    let outputs = vec![
+        (Coin::<0>(10), taker()).into(),
+        (Coin::<1>(20), taker()).into(),
+    ];
+
+    assert_eq!(
+        check(Swap::TakeOffer, inputs, outputs),
+        Err(ConstraintCheckerError::Underpaid)
+    );
+}
+
+#[test]
+fn take_offer_creating_b_fails() {
+    let inputs = vec![(offer(10), maker()).into(), (Coin::<1>(15), taker()).into()];
+    let outputs = vec![
+        (Coin::<0>(10), taker()).into(),
+        (Coin::<1>(20), maker()).into(),
+    ];
+
+    assert_eq!(
+        check(Swap::TakeOffer, inputs, outputs),
+        Err(ConstraintCheckerError::OutputsExceedInputs)
+    );
+}
+
+#[test]
+fn take_offer_without_remainder_fails() {
+    let inputs = vec![(offer(10), maker()).into(), (Coin::<1>(8), taker()).into()];
+    let outputs = vec![
+        (Coin::<0>(4), taker()).into(),
+        (Coin::<1>(8), maker()).into(),
+    ];
+
+    assert_eq!(
+        check(Swap::TakeOffer, inputs, outputs),
+        Err(ConstraintCheckerError::OfferNotConserved)
+    );
+}
+
+#[test]
+fn take_offer_changing_price_fails() {
+    let inputs = vec![(offer(10), maker()).into(), (Coin::<1>(8), taker()).into()];
+    let mut cheaper = offer(6);
+    cheaper.price_b = 1;
+    let outputs = vec![
+        (Coin::<0>(4), taker()).into(),
+        (Coin::<1>(8), maker()).into(),
+        (cheaper, maker()).into(),
+    ];
+
+    assert_eq!(
+        check(Swap::TakeOffer, inputs, outputs),
+        Err(ConstraintCheckerError::RemainderMismatch)
+    );
+}
+
+#[test]
+fn take_offer_changing_remainder_verifier_fails() {
+    let inputs = vec![(offer(10), maker()).into(), (Coin::<1>(8), taker()).into()];
+    let outputs = vec![
+        (Coin::<0>(4), taker()).into(),
+        (Coin::<1>(8), maker()).into(),
+        (offer(6), taker()).into(),
+    ];
+
+    assert_eq!(
+        check(Swap::TakeOffer, inputs, outputs),
+        Err(ConstraintCheckerError::RemainderMismatch)
+    );
+}
+
+#[test]
+fn take_non_offer_fails() {
+    let inputs = vec![(Bogus, maker()).into(), (Coin::<1>(8), taker()).into()];
+    let outputs = vec![(Coin::<0>(4), taker()).into()];
+
+    assert_eq!(
+        check(Swap::TakeOffer, inputs, outputs),
+        Err(ConstraintCheckerError::BadlyTypedInput)
+    );
+}
+
+#[test]
+fn cancel_offer_works() {
+    let inputs = vec![(offer(10), maker()).into()];
+    let outputs = vec![(Coin::<0>(10), maker()).into()];
+
+    assert_eq!(check(Swap::CancelOffer, inputs, outputs), Ok(0));
+}
+
+#[test]
+fn cancel_offer_refunding_wrong_verifier_fails() {
+    let inputs = vec![(offer(10), maker()).into()];
+    let outputs = vec![(Coin::<0>(10), taker()).into()];
+
+    assert_eq!(
+        check(Swap::CancelOffer, inputs, outputs),
+        Err(ConstraintCheckerError::RefundToWrongVerifier)
+    );
+}
+
+#[test]
+fn cancel_offer_refunding_too_much_fails() {
+    let inputs = vec![(offer(10), maker()).into()];
+    let outputs = vec![(Coin::<0>(11), maker()).into()];
+
+    assert_eq!(
+        check(Swap::CancelOffer, inputs, outputs),
+        Err(ConstraintCheckerError::OutputsExceedInputs)
+    );
+}
@@ -0,0 +1,339 @@
+//! Attestations that issuers make about the holders of keys, such as the outcome of a KYC check.
+//!
+//! An issuer first registers an [`Issuer`] record, which is owned by their key. To attest a claim
+//! about a subject, the issuer consumes their record in an [`Issue`] transaction, and creates the
+//! updated record along with the [`Attestation`]s. Consuming the record proves that the issuer
+//! signed the transaction, and makes sure that it can not be replayed to bring back an attestation
+//! that was revoked. Claims are only stored as hashes, so the details of the claim stay off chain.
+//!
+//! Attestations remain owned by their issuer. They are valid until their expiry height, or until the
+//! issuer revokes them by consuming them in a [`Revoke`] transaction, whichever comes first.
+//!
+//! Subjects present their attestations by peeking at them in the transactions of other pieces.
+//! Those pieces decide which issuers and claims they trust, and check the peeks with
+//! [`is_attested`]. For example a permissioned asset could only allow transfers to owners who
+//! present an attestation that they passed a KYC check by a trusted issuer.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use core::marker::PhantomData;
+
+use parity_scale_codec::{Decode, Encode};
+use scale_info::TypeInfo;
+use serde::{Deserialize, Serialize};
+use sp_core::H256;
+use sp_runtime::transaction_validity::TransactionPriority;
+use sp_std::prelude::*;
+use tuxedo_core::{
+    dynamic_typing::{DynamicallyTypedData, UtxoData},
+    ensure,
+    support_macros::{CloneNoBound, DebugNoBound, DefaultNoBound},
+    types::Output,
+    verifier::SigCheck,
+    CheckerContext, ConstraintChecker, SimpleConstraintChecker, Verifier,
+};
+
+#[cfg(test)]
+mod tests;
+
+/// Configuration items for the attestation piece when it is
+/// instantiated in a concrete runtime.
+pub trait AttestationConfig {
+    /// A means of getting the current block height.
+    /// Probably this will be the Tuxedo Executive
+    fn block_height() -> u32;
+}
+
+/// The record of an issuer, which they consume to issue attestations.
+#[derive(
+    Serialize,
+    Deserialize,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Clone,
+    Encode,
+    Decode,
+    Hash,
+    Debug,
+    TypeInfo,
+)]
+pub struct Issuer {
+    /// The issuer's sr25519 public key, which owns the record
+    pub pubkey: H256,
+    /// The number of attestations issued so far
+    pub issued: u64,
+}
+
+impl UtxoData for Issuer {
+    const TYPE_ID: [u8; 4] = *b"issr";
+}
+
+/// An issuer's attestation of a claim about a subject.
+#[derive(
+    Serialize,
+    Deserialize,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Clone,
+    Encode,
+    Decode,
+    Hash,
+    Debug,
+    TypeInfo,
+)]
+pub struct Attestation {
+    /// The issuer's sr25519 public key, which owns the attestation
+    pub issuer: H256,
+    /// The serial number of the attestation among all those of the issuer
+    pub serial: u64,
+    /// The key that the claim is about
+    pub subject: H256,
+    /// The hash of the claim
+    pub claim: H256,
+    /// The block height from which the attestation is no longer valid
+    pub expires_at: u32,
+}
+
+impl Attestation {
+    /// Whether the attestation is still valid at the given block height.
+    pub fn is_valid_at(&self, block_height: u32) -> bool {
+        block_height < self.expires_at
+    }
+}
+
+impl UtxoData for Attestation {
+    const TYPE_ID: [u8; 4] = *b"attn";
+}
+
+/// Whether the peeked data includes a valid attestation of the claim about the subject by one of
+/// the given issuers. Pieces that require attestations call this from their constraint checkers.
+pub fn is_attested<'a, T: AttestationConfig>(
+    peeks: impl IntoIterator<Item = &'a DynamicallyTypedData>,
+    subject: &H256,
+    claim: &H256,
+    issuers: &[H256],
+) -> bool {
+    let block_height = T::block_height();
+    peeks
+        .into_iter()
+        .filter_map(|data| data.extract::<Attestation>().ok())
+        .any(|attestation| {
+            attestation.subject == *subject
+                && attestation.claim == *claim
+                && issuers.contains(&attestation.issuer)
+                && attestation.is_valid_at(block_height)
+        })
+}
+
+/// Errors that can occur when issuing or revoking attestations.
+#[derive(
+    Serialize,
+    Deserialize,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Clone,
+    Encode,
+    Decode,
+    Hash,
+    Debug,
+    TypeInfo,
+)]
+pub enum ConstraintCheckerError {
+    /// An input data has the wrong type.
+    BadlyTypedInput,
+    /// An output data has the wrong type.
+    BadlyTypedOutput,
+    /// Wrong number of inputs were provided to the constraint checker.
+    WrongNumberInputs,
+    /// Wrong number of outputs were provided to the constraint checker.
+    WrongNumberOutputs,
+    /// A new issuer record must not have issued anything yet.
+    IssuerNotNew,
+    /// The issuer record or an attestation is not owned by the issuer.
+    NotOwnedByIssuer,
+    /// The updated issuer record does not count the new attestations.
+    IssuerModified,
+    /// An issuance must create at least one attestation.
+    NoAttestations,
+    /// An attestation is issued by someone other than the owner of the consumed record,
+    /// or its serial number is not the next one.
+    WrongAttestation,
+    /// An attestation expires before it is created.
+    AlreadyExpired,
+    /// A revocation must consume at least one attestation.
+    NothingToRevoke,
+}
+
+/// A constraint checker to register a new issuer.
+///
+/// There are no inputs, and the single output is the new record, owned by the issuer's key.
+#[derive(
+    Serialize, Deserialize, Encode, Decode, Debug, Default, PartialEq, Eq, Clone, Copy, TypeInfo,
+)]
+pub struct RegisterIssuer;
+
+impl<V: Verifier + PartialEq + From<SigCheck>> ConstraintChecker<V> for RegisterIssuer {
+    type Error = ConstraintCheckerError;
+    type InherentHooks = ();
+
+    fn check(
+        &self,
+        inputs: &[Output<V>],
+        _peeks: &[Output<V>],
+        outputs: &[Output<V>],
+        _context: &CheckerContext,
+    ) -> Result<TransactionPriority, Self::Error> {
+        ensure!(inputs.is_empty(), ConstraintCheckerError::WrongNumberInputs);
+        ensure!(
+            outputs.len() == 1,
+            ConstraintCheckerError::WrongNumberOutputs
+        );
+        let issuer = outputs[0]
+            .payload
+            .extract::<Issuer>()
+            .map_err(|_| ConstraintCheckerError::BadlyTypedOutput)?;
+        ensure!(issuer.issued == 0, ConstraintCheckerError::IssuerNotNew);
+        ensure!(
+            outputs[0].verifier == SigCheck::new(issuer.pubkey).into(),
+            ConstraintCheckerError::NotOwnedByIssuer
+        );
+
+        Ok(0)
+    }
+
+    fn is_inherent(&self) -> bool {
+        false
+    }
+}
+
+/// A constraint checker to issue attestations.
+///
+/// The single input is the issuer's record. The first output is the updated record, and every
+/// other output is an attestation by the issuer, numbered in sequence and owned by the issuer.
+#[derive(
+    Serialize,
+    Deserialize,
+    Encode,
+    Decode,
+    DebugNoBound,
+    DefaultNoBound,
+    PartialEq,
+    Eq,
+    CloneNoBound,
+    TypeInfo,
+)]
+#[scale_info(skip_type_params(T))]
+pub struct Issue<T>(PhantomData<T>);
+
+impl<T: AttestationConfig, V: Verifier + PartialEq + From<SigCheck>> ConstraintChecker<V>
+    for Issue<T>
+{
+    type Error = ConstraintCheckerError;
+    type InherentHooks = ();
+
+    fn check(
+        &self,
+        inputs: &[Output<V>],
+        _peeks: &[Output<V>],
+        outputs: &[Output<V>],
+        _context: &CheckerContext,
+    ) -> Result<TransactionPriority, Self::Error> {
+        ensure!(inputs.len() == 1, ConstraintCheckerError::WrongNumberInputs);
+        let issuer = inputs[0]
+            .payload
+            .extract::<Issuer>()
+            .map_err(|_| ConstraintCheckerError::BadlyTypedInput)?;
+        let owned_by_issuer: V = SigCheck::new(issuer.pubkey).into();
+        ensure!(
+            inputs[0].verifier == owned_by_issuer,
+            ConstraintCheckerError::NotOwnedByIssuer
+        );
+
+        let (record, attestations) = outputs
+            .split_first()
+            .ok_or(ConstraintCheckerError::WrongNumberOutputs)?;
+        ensure!(
+            !attestations.is_empty(),
+            ConstraintCheckerError::NoAttestations
+        );
+        let updated = record
+            .payload
+            .extract::<Issuer>()
+            .map_err(|_| ConstraintCheckerError::BadlyTypedOutput)?;
+        ensure!(
+            record.verifier == owned_by_issuer
+                && updated.pubkey == issuer.pubkey
+                && updated.issued == issuer.issued.saturating_add(attestations.len() as u64),
+            ConstraintCheckerError::IssuerModified
+        );
+
+        let block_height = T::block_height();
+        for (serial, output) in (issuer.issued..).zip(attestations) {
+            let attestation = output
+                .payload
+                .extract::<Attestation>()
+                .map_err(|_| ConstraintCheckerError::BadlyTypedOutput)?;
+            ensure!(
+                attestation.issuer == issuer.pubkey && attestation.serial == serial,
+                ConstraintCheckerError::WrongAttestation
+            );
+            ensure!(
+                output.verifier == owned_by_issuer,
+                ConstraintCheckerError::NotOwnedByIssuer
+            );
+            ensure!(
+                attestation.is_valid_at(block_height),
+                ConstraintCheckerError::AlreadyExpired
+            );
+        }
+
+        Ok(0)
+    }
+
+    fn is_inherent(&self) -> bool {
+        false
+    }
+}
+
+/// A constraint checker to revoke attestations.
+///
+/// Every input is an attestation, and there are no outputs. Because attestations are owned by
+/// their issuer, only the issuer can revoke them. Expired attestations can be removed the same way.
+#[derive(
+    Serialize, Deserialize, Encode, Decode, Debug, Default, PartialEq, Eq, Clone, Copy, TypeInfo,
+)]
+pub struct Revoke;
+
+impl SimpleConstraintChecker for Revoke {
+    type Error = ConstraintCheckerError;
+
+    fn check(
+        &self,
+        input_data: &[DynamicallyTypedData],
+        _peeks: &[DynamicallyTypedData],
+        output_data: &[DynamicallyTypedData],
+        _context: &CheckerContext,
+    ) -> Result<TransactionPriority, Self::Error> {
+        ensure!(
+            !input_data.is_empty(),
+            ConstraintCheckerError::NothingToRevoke
+        );
+        ensure!(
+            output_data.is_empty(),
+            ConstraintCheckerError::WrongNumberOutputs
+        );
+        for input in input_data {
+            input
+                .extract::<Attestation>()
+                .map_err(|_| ConstraintCheckerError::BadlyTypedInput)?;
+        }
+
+        Ok(0)
+    }
+}
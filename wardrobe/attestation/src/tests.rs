@@ -0,0 +1,289 @@
+//! Unit tests for the Attestation piece
+
+use super::*;
+use tuxedo_core::{dynamic_typing::testing::Bogus, tuxedo_verifier, verifier::UpForGrabs};
+
+/// A verifier that can represent issuers as well as unprotected outputs.
+#[derive(Serialize, Deserialize, Encode, Decode, Debug, PartialEq, Eq, Clone, TypeInfo)]
+#[tuxedo_verifier]
+pub enum TestVerifier {
+    SigCheck(SigCheck),
+    UpForGrabs(UpForGrabs),
+}
+
+/// A mock config that always reports block ten.
+pub struct AtBlockTen;
+
+impl AttestationConfig for AtBlockTen {
+    fn block_height() -> u32 {
+        10
+    }
+}
+
+fn issuer_key() -> H256 {
+    H256::repeat_byte(1)
+}
+
+fn subject() -> H256 {
+    H256::repeat_byte(2)
+}
+
+fn kyc() -> H256 {
+    H256::repeat_byte(3)
+}
+
+fn owned_by_issuer<T: UtxoData>(data: T) -> Output<TestVerifier> {
+    (data, SigCheck::new(issuer_key()).into()).into()
+}
+
+fn record(issued: u64) -> Issuer {
+    Issuer {
+        pubkey: issuer_key(),
+        issued,
+    }
+}
+
+fn attestation(serial: u64, expires_at: u32) -> Attestation {
+    Attestation {
+        issuer: issuer_key(),
+        serial,
+        subject: subject(),
+        claim: kyc(),
+        expires_at,
+    }
+}
+
+fn revoke(
+    input_data: &[DynamicallyTypedData],
+    output_data: &[DynamicallyTypedData],
+) -> Result<TransactionPriority, ConstraintCheckerError> {
+    SimpleConstraintChecker::check(&Revoke, input_data, &[], output_data, &Default::default())
+}
+
+fn issue(
+    inputs: &[Output<TestVerifier>],
+    outputs: &[Output<TestVerifier>],
+) -> Result<TransactionPriority, ConstraintCheckerError> {
+    Issue::<AtBlockTen>::default().check(inputs, &[], outputs, &Default::default())
+}
+
+#[test]
+fn register_issuer_works() {
+    assert_eq!(
+        RegisterIssuer.check(&[], &[], &[owned_by_issuer(record(0))], &Default::default()),
+        Ok(0)
+    );
+}
+
+#[test]
+fn register_issuer_not_owned_by_issuer_fails() {
+    let output: Output<TestVerifier> = (record(0), UpForGrabs.into()).into();
+
+    assert_eq!(
+        RegisterIssuer.check(&[], &[], &[output], &Default::default()),
+        Err(ConstraintCheckerError::NotOwnedByIssuer)
+    );
+}
+
+#[test]
+fn register_issuer_with_history_fails() {
+    assert_eq!(
+        RegisterIssuer.check(&[], &[], &[owned_by_issuer(record(5))], &Default::default()),
+        Err(ConstraintCheckerError::IssuerNotNew)
+    );
+}
+
+#[test]
+fn issue_works() {
+    let inputs = vec![owned_by_issuer(record(4))];
+    let outputs = vec![
+        owned_by_issuer(record(6)),
+        owned_by_issuer(attestation(4, 20)),
+        owned_by_issuer(attestation(5, 11)),
+    ];
+
+    assert_eq!(issue(&inputs, &outputs), Ok(0));
+}
+
+#[test]
+fn issue_without_counting_fails() {
+    let inputs = vec![owned_by_issuer(record(4))];
+    let outputs = vec![
+        owned_by_issuer(record(4)),
+        owned_by_issuer(attestation(4, 20)),
+    ];
+
+    assert_eq!(
+        issue(&inputs, &outputs),
+        Err(ConstraintCheckerError::IssuerModified)
+    );
+}
+
+#[test]
+fn issue_out_of_sequence_fails() {
+    let inputs = vec![owned_by_issuer(record(4))];
+    let outputs = vec![
+        owned_by_issuer(record(5)),
+        owned_by_issuer(attestation(3, 20)),
+    ];
+
+    assert_eq!(
+        issue(&inputs, &outputs),
+        Err(ConstraintCheckerError::WrongAttestation)
+    );
+}
+
+#[test]
+fn issue_for_other_issuer_fails() {
+    let inputs = vec![owned_by_issuer(record(0))];
+    let outputs = vec![
+        owned_by_issuer(record(1)),
+        owned_by_issuer(Attestation {
+            issuer: H256::repeat_byte(9),
+            ..attestation(0, 20)
+        }),
+    ];
+
+    assert_eq!(
+        issue(&inputs, &outputs),
+        Err(ConstraintCheckerError::WrongAttestation)
+    );
+}
+
+#[test]
+fn issue_attestation_not_owned_by_issuer_fails() {
+    let inputs = vec![owned_by_issuer(record(0))];
+    let outputs = vec![
+        owned_by_issuer(record(1)),
+        (attestation(0, 20), SigCheck::new(subject()).into()).into(),
+    ];
+
+    assert_eq!(
+        issue(&inputs, &outputs),
+        Err(ConstraintCheckerError::NotOwnedByIssuer)
+    );
+}
+
+#[test]
+fn issue_from_unowned_record_fails() {
+    let inputs = vec![(record(0), UpForGrabs.into()).into()];
+    let outputs = vec![
+        owned_by_issuer(record(1)),
+        owned_by_issuer(attestation(0, 20)),
+    ];
+
+    assert_eq!(
+        issue(&inputs, &outputs),
+        Err(ConstraintCheckerError::NotOwnedByIssuer)
+    );
+}
+
+#[test]
+fn issue_expired_fails() {
+    let inputs = vec![owned_by_issuer(record(0))];
+    let outputs = vec![
+        owned_by_issuer(record(1)),
+        owned_by_issuer(attestation(0, 10)),
+    ];
+
+    assert_eq!(
+        issue(&inputs, &outputs),
+        Err(ConstraintCheckerError::AlreadyExpired)
+    );
+}
+
+#[test]
+fn issue_nothing_fails() {
+    let inputs = vec![owned_by_issuer(record(0))];
+    let outputs = vec![owned_by_issuer(record(0))];
+
+    assert_eq!(
+        issue(&inputs, &outputs),
+        Err(ConstraintCheckerError::NoAttestations)
+    );
+}
+
+#[test]
+fn revoke_works() {
+    let input_data = vec![attestation(0, 20).into(), attestation(1, 5).into()];
+
+    assert_eq!(revoke(&input_data, &[]), Ok(0));
+}
+
+#[test]
+fn revoke_something_else_fails() {
+    let input_data = vec![attestation(0, 20).into(), Bogus.into()];
+
+    assert_eq!(
+        revoke(&input_data, &[]),
+        Err(ConstraintCheckerError::BadlyTypedInput)
+    );
+}
+
+#[test]
+fn revoke_with_outputs_fails() {
+    let input_data = vec![attestation(0, 20).into()];
+    let output_data = vec![attestation(0, 30).into()];
+
+    assert_eq!(
+        revoke(&input_data, &output_data),
+        Err(ConstraintCheckerError::WrongNumberOutputs)
+    );
+}
+
+#[test]
+fn revoke_nothing_fails() {
+    assert_eq!(
+        revoke(&[], &[]),
+        Err(ConstraintCheckerError::NothingToRevoke)
+    );
+}
+
+#[test]
+fn presented_attestation_is_attested() {
+    let peeks: Vec<DynamicallyTypedData> = vec![Bogus.into(), attestation(0, 20).into()];
+
+    assert!(is_attested::<AtBlockTen>(
+        &peeks,
+        &subject(),
+        &kyc(),
+        &[issuer_key()]
+    ));
+}
+
+#[test]
+fn attestation_must_match_presentation() {
+    let peeks: Vec<DynamicallyTypedData> = vec![attestation(0, 20).into()];
+    let untrusted = H256::repeat_byte(9);
+
+    assert!(!is_attested::<AtBlockTen>(
+        &peeks,
+        &untrusted,
+        &kyc(),
+        &[issuer_key()]
+    ));
+    assert!(!is_attested::<AtBlockTen>(
+        &peeks,
+        &subject(),
+        &untrusted,
+        &[issuer_key()]
+    ));
+    assert!(!is_attested::<AtBlockTen>(
+        &peeks,
+        &subject(),
+        &kyc(),
+        &[untrusted]
+    ));
+}
+
+#[test]
+fn expired_attestation_is_not_attested() {
+    let peeks: Vec<DynamicallyTypedData> = vec![attestation(0, 10).into()];
+
+    assert!(!is_attested::<AtBlockTen>(
+        &peeks,
+        &subject(),
+        &kyc(),
+        &[issuer_key()]
+    ));
+}
@@ -0,0 +1,793 @@
+//! English and Dutch auctions of arbitrary items for `Coin<ID>`.
+//!
+//! A seller opens an auction by consuming the item, such as a kitty or an NFT, and storing it in
+//! an [`Auction`] along with the reserve price and the end height. Auctions and bids are protected
+//! by the `UpForGrabs` verifier so that anyone can drive them forward. The constraint checkers
+//! make sure that the coins and the item always end up with the right keys.
+//!
+//! In an English auction, bidders escrow coins in [`Bid`]s. Every bid must beat the current
+//! highest bid, which the auction keeps track of. This way, the auction always knows its winner,
+//! and settlement cannot skip over a higher bid. After the end height, anyone may settle the
+//! auction, which pays the seller, transfers the item to the winner, and refunds any losing bids
+//! that are included. Losing bids can also be refunded on their own as soon as they are outbid.
+//! If nobody settles the auction within the settlement period, all bids become refundable and
+//! the seller may reclaim the item.
+//!
+//! In a Dutch auction, the price falls linearly from a starting price to the reserve price over
+//! the course of the auction. The first buyer to pay the current price gets the item right away.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use core::marker::PhantomData;
+
+use money::Coin;
+use parity_scale_codec::{Decode, Encode};
+use scale_info::TypeInfo;
+use serde::{Deserialize, Serialize};
+use sp_core::H256;
+use sp_runtime::{
+    traits::{BlakeTwo256, Hash},
+    transaction_validity::TransactionPriority,
+};
+use sp_std::prelude::*;
+use tuxedo_core::{
+    dynamic_typing::{DynamicallyTypedData, UtxoData},
+    ensure,
+    support_macros::{CloneNoBound, DebugNoBound, DefaultNoBound},
+    types::Output,
+    verifier::{SigCheck, UpForGrabs},
+    CheckerContext, ConstraintChecker, Verifier,
+};
+
+#[cfg(test)]
+mod tests;
+
+/// Configuration items for the auction piece when it is
+/// instantiated in a concrete runtime.
+pub trait AuctionConfig {
+    /// A means of getting the current block height.
+    /// Probably this will be the Tuxedo Executive
+    fn block_height() -> u32;
+
+    /// The number of blocks after an English auction ends during which it may be settled.
+    /// After this period, bids are refundable and the seller may reclaim the item.
+    ///
+    /// Default is roughly one day with 6 second block times.
+    const SETTLEMENT_PERIOD: u32 = 14_400;
+}
+
+/// The rules by which the winner of an auction is decided.
+#[derive(
+    Serialize,
+    Deserialize,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Clone,
+    Encode,
+    Decode,
+    Hash,
+    Debug,
+    TypeInfo,
+)]
+pub enum AuctionKind {
+    /// Bidders outbid each other, and the highest bid at the end height wins.
+    English {
+        /// The highest bid so far, if any, as the bidder's key and the amount.
+        highest: Option<(H256, u128)>,
+    },
+    /// The price falls from the starting price to the reserve, and the first buyer wins.
+    Dutch {
+        /// The price at the start height.
+        start_price: u128,
+    },
+}
+
+/// An item up for auction.
+#[derive(Serialize, Deserialize, PartialEq, Eq, Clone, Encode, Decode, Debug, TypeInfo)]
+pub struct Auction<const ID: u8> {
+    /// The key that receives the proceeds of the auction.
+    pub seller: H256,
+    /// The item being sold.
+    pub item: DynamicallyTypedData,
+    /// The lowest price at which the item may be sold.
+    pub reserve: u128,
+    /// The block height at which the auction opened.
+    pub start: u32,
+    /// The block height at which the auction closes.
+    pub end: u32,
+    /// Whether this is an English or Dutch auction, and its current state.
+    pub kind: AuctionKind,
+}
+
+impl<const ID: u8> Auction<ID> {
+    /// The id of this auction, which bids refer to.
+    ///
+    /// The id does not depend on the current highest bid, so it stays the same as bids come in.
+    pub fn id(&self) -> H256 {
+        let kind = match self.kind {
+            AuctionKind::English { .. } => AuctionKind::English { highest: None },
+            AuctionKind::Dutch { start_price } => AuctionKind::Dutch { start_price },
+        };
+        BlakeTwo256::hash_of(&(
+            self.seller,
+            &self.item,
+            self.reserve,
+            self.start,
+            self.end,
+            kind,
+        ))
+    }
+
+    /// The current highest bid, if this is an English auction that has received any bids.
+    pub fn highest_bid(&self) -> Option<(H256, u128)> {
+        match self.kind {
+            AuctionKind::English { highest } => highest,
+            AuctionKind::Dutch { .. } => None,
+        }
+    }
+
+    /// The price of the item at the given height, if this is a Dutch auction.
+    ///
+    /// The price falls linearly from the starting price at the start height
+    /// to the reserve at the end height.
+    pub fn dutch_price(&self, height: u32) -> Option<u128> {
+        let AuctionKind::Dutch { start_price } = self.kind else {
+            return None;
+        };
+        let duration = self.end.saturating_sub(self.start);
+        if duration == 0 {
+            return Some(self.reserve);
+        }
+        let elapsed = height.saturating_sub(self.start).min(duration);
+
+        let drop = start_price.saturating_sub(self.reserve);
+        let dropped = drop.saturating_mul(elapsed.into()) / u128::from(duration);
+        Some(start_price.saturating_sub(dropped).max(self.reserve))
+    }
+}
+
+impl<const ID: u8> UtxoData for Auction<ID> {
+    const TYPE_ID: [u8; 4] = [b'a', b'u', b'c', ID];
+}
+
+/// Coins escrowed as a bid in an English auction.
+#[derive(
+    Serialize,
+    Deserialize,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Clone,
+    Encode,
+    Decode,
+    Hash,
+    Debug,
+    TypeInfo,
+)]
+pub struct Bid<const ID: u8> {
+    /// The id of the auction that this bid was placed in.
+    pub auction: H256,
+    /// The key that placed the bid and receives the item or the refund.
+    pub bidder: H256,
+    /// The value escrowed in this bid.
+    pub amount: u128,
+    /// The block height from which this bid may be refunded even if it won,
+    /// because the auction was not settled in time.
+    pub refundable_at: u32,
+}
+
+impl<const ID: u8> UtxoData for Bid<ID> {
+    const TYPE_ID: [u8; 4] = [b'b', b'i', b'd', ID];
+}
+
+/// Errors that can occur when checking auction transactions.
+#[derive(
+    Serialize,
+    Deserialize,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Clone,
+    Encode,
+    Decode,
+    Hash,
+    Debug,
+    TypeInfo,
+)]
+pub enum ConstraintCheckerError {
+    /// An input data has the wrong type.
+    BadlyTypedInput,
+    /// An output data has the wrong type.
+    BadlyTypedOutput,
+    /// Wrong number of inputs were provided to the constraint checker.
+    WrongNumberInputs,
+    /// Wrong number of outputs were provided to the constraint checker.
+    WrongNumberOutputs,
+    /// A coin with zero value was created. This is not allowed because it wastes state space.
+    ZeroValue,
+    /// The value consumed or created by this transaction overflows the value type.
+    ValueOverflow,
+    /// More value was created than was consumed.
+    OutputsExceedInputs,
+    /// The auctioned item is not the item that was consumed.
+    ItemMismatch,
+    /// A new auction does not start at the current block, or ends before it starts.
+    InvalidSchedule,
+    /// A new English auction already has bids.
+    AlreadyBid,
+    /// A Dutch auction starts below its reserve price.
+    StartBelowReserve,
+    /// The auction or bid was locked to a verifier other than `UpForGrabs`.
+    NotUpForGrabs,
+    /// The operation requires an English auction.
+    NotEnglish,
+    /// The operation requires a Dutch auction.
+    NotDutch,
+    /// The auction has already ended.
+    AuctionEnded,
+    /// The auction has not ended yet.
+    AuctionNotEnded,
+    /// The settlement period has passed, so the auction can no longer be settled.
+    SettlementPeriodOver,
+    /// The bid is below the reserve or does not beat the current highest bid.
+    BidTooLow,
+    /// The auction was changed in some way other than recording the new highest bid.
+    AuctionModified,
+    /// The bid does not match the auction or the bid recorded as highest.
+    BidMismatch,
+    /// The auction has no bids to settle.
+    NoBids,
+    /// The bid has not been outbid and the auction may still be settled.
+    NotRefundable,
+    /// The auction received bids and may still be settled, so the item cannot be reclaimed.
+    NotReclaimable,
+    /// The payment is less than the current price of the item.
+    Underpaid,
+    /// The item, proceeds, or refund was locked to some verifier other than the expected key's.
+    PaidToWrongVerifier,
+}
+
+/// Make sure an output is the given coin locked to the given key.
+fn check_payment<V: Verifier + PartialEq + From<SigCheck>, const ID: u8>(
+    output: &Output<V>,
+    amount: u128,
+    recipient: H256,
+) -> Result<(), ConstraintCheckerError> {
+    let coin = output
+        .payload
+        .extract::<Coin<ID>>()
+        .map_err(|_| ConstraintCheckerError::BadlyTypedOutput)?;
+    ensure!(coin.0 == amount, ConstraintCheckerError::Underpaid);
+    ensure!(
+        output.verifier == SigCheck::new(recipient).into(),
+        ConstraintCheckerError::PaidToWrongVerifier
+    );
+    Ok(())
+}
+
+/// Sum the values of some `Coin<ID>` inputs.
+fn input_value<V, const ID: u8>(inputs: &[Output<V>]) -> Result<u128, ConstraintCheckerError> {
+    inputs.iter().try_fold(0u128, |total, input| {
+        let coin = input
+            .payload
+            .extract::<Coin<ID>>()
+            .map_err(|_| ConstraintCheckerError::BadlyTypedInput)?;
+        total
+            .checked_add(coin.0)
+            .ok_or(ConstraintCheckerError::ValueOverflow)
+    })
+}
+
+/// Sum the values of some `Coin<ID>` outputs, none of which may be zero.
+fn output_value<V, const ID: u8>(outputs: &[Output<V>]) -> Result<u128, ConstraintCheckerError> {
+    outputs.iter().try_fold(0u128, |total, output| {
+        let coin = output
+            .payload
+            .extract::<Coin<ID>>()
+            .map_err(|_| ConstraintCheckerError::BadlyTypedOutput)?;
+        ensure!(coin.0 > 0, ConstraintCheckerError::ZeroValue);
+        total
+            .checked_add(coin.0)
+            .ok_or(ConstraintCheckerError::ValueOverflow)
+    })
+}
+
+/// Extract an auction from an input.
+fn extract_auction<V, const ID: u8>(
+    input: Option<&Output<V>>,
+) -> Result<Auction<ID>, ConstraintCheckerError> {
+    input
+        .ok_or(ConstraintCheckerError::WrongNumberInputs)?
+        .payload
+        .extract::<Auction<ID>>()
+        .map_err(|_| ConstraintCheckerError::BadlyTypedInput)
+}
+
+/// A constraint checker that puts an item up for auction.
+///
+/// The only input is the item, and the only output is the auction holding it.
+/// The auction must open at the current block.
+#[derive(
+    Serialize,
+    Deserialize,
+    Encode,
+    Decode,
+    DebugNoBound,
+    DefaultNoBound,
+    PartialEq,
+    Eq,
+    CloneNoBound,
+    TypeInfo,
+)]
+#[scale_info(skip_type_params(T))]
+pub struct OpenAuction<T, const ID: u8>(PhantomData<T>);
+
+impl<T: AuctionConfig, V: Verifier + PartialEq + From<UpForGrabs>, const ID: u8>
+    ConstraintChecker<V> for OpenAuction<T, ID>
+{
+    type Error = ConstraintCheckerError;
+    type InherentHooks = ();
+
+    fn check(
+        &self,
+        inputs: &[Output<V>],
+        _peeks: &[Output<V>],
+        outputs: &[Output<V>],
+        _context: &CheckerContext,
+    ) -> Result<TransactionPriority, Self::Error> {
+        ensure!(inputs.len() == 1, ConstraintCheckerError::WrongNumberInputs);
+        ensure!(
+            outputs.len() == 1,
+            ConstraintCheckerError::WrongNumberOutputs
+        );
+        let auction = outputs[0]
+            .payload
+            .extract::<Auction<ID>>()
+            .map_err(|_| ConstraintCheckerError::BadlyTypedOutput)?;
+        ensure!(
+            outputs[0].verifier == UpForGrabs.into(),
+            ConstraintCheckerError::NotUpForGrabs
+        );
+
+        ensure!(
+            auction.item == inputs[0].payload,
+            ConstraintCheckerError::ItemMismatch
+        );
+        ensure!(
+            auction.start == T::block_height() && auction.end >= auction.start,
+            ConstraintCheckerError::InvalidSchedule
+        );
+        match auction.kind {
+            AuctionKind::English { highest } => {
+                ensure!(highest.is_none(), ConstraintCheckerError::AlreadyBid)
+            }
+            AuctionKind::Dutch { start_price } => ensure!(
+                start_price >= auction.reserve,
+                ConstraintCheckerError::StartBelowReserve
+            ),
+        }
+
+        Ok(0)
+    }
+
+    fn is_inherent(&self) -> bool {
+        false
+    }
+}
+
+/// A constraint checker that places a bid in an English auction.
+///
+/// The first input is the auction and the remaining inputs are the bidder's `Coin<ID>`s. The
+/// first output is the auction with the new highest bid recorded, the second is the bid, and the
+/// remaining outputs are optionally `Coin<ID>` change. The bidder pays the transaction's fee and
+/// tip with whatever coins are neither bid nor returned as change.
+#[derive(
+    Serialize,
+    Deserialize,
+    Encode,
+    Decode,
+    DebugNoBound,
+    DefaultNoBound,
+    PartialEq,
+    Eq,
+    CloneNoBound,
+    TypeInfo,
+)]
+#[scale_info(skip_type_params(T))]
+pub struct PlaceBid<T, const ID: u8>(PhantomData<T>);
+
+impl<T: AuctionConfig, V: Verifier + PartialEq + From<UpForGrabs>, const ID: u8>
+    ConstraintChecker<V> for PlaceBid<T, ID>
+{
+    type Error = ConstraintCheckerError;
+    type InherentHooks = ();
+
+    fn check(
+        &self,
+        inputs: &[Output<V>],
+        _peeks: &[Output<V>],
+        outputs: &[Output<V>],
+        _context: &CheckerContext,
+    ) -> Result<TransactionPriority, Self::Error> {
+        let old = extract_auction::<V, ID>(inputs.first())?;
+        ensure!(
+            outputs.len() >= 2,
+            ConstraintCheckerError::WrongNumberOutputs
+        );
+        let new = outputs[0]
+            .payload
+            .extract::<Auction<ID>>()
+            .map_err(|_| ConstraintCheckerError::BadlyTypedOutput)?;
+        let bid = outputs[1]
+            .payload
+            .extract::<Bid<ID>>()
+            .map_err(|_| ConstraintCheckerError::BadlyTypedOutput)?;
+        ensure!(
+            outputs[0].verifier == UpForGrabs.into() && outputs[1].verifier == UpForGrabs.into(),
+            ConstraintCheckerError::NotUpForGrabs
+        );
+
+        let AuctionKind::English { highest } = old.kind else {
+            return Err(ConstraintCheckerError::NotEnglish);
+        };
+        ensure!(
+            T::block_height() < old.end,
+            ConstraintCheckerError::AuctionEnded
+        );
+        ensure!(
+            bid.amount >= old.reserve && highest.map_or(true, |(_, amount)| bid.amount > amount),
+            ConstraintCheckerError::BidTooLow
+        );
+        ensure!(
+            bid.auction == old.id()
+                && bid.refundable_at == old.end.saturating_add(T::SETTLEMENT_PERIOD),
+            ConstraintCheckerError::BidMismatch
+        );
+        ensure!(
+            new == Auction {
+                kind: AuctionKind::English {
+                    highest: Some((bid.bidder, bid.amount)),
+                },
+                ..old
+            },
+            ConstraintCheckerError::AuctionModified
+        );
+
+        let total_input = input_value::<V, ID>(&inputs[1..])?;
+        let total_output = output_value::<V, ID>(&outputs[2..])?
+            .checked_add(bid.amount)
+            .ok_or(ConstraintCheckerError::ValueOverflow)?;
+        ensure!(
+            total_output <= total_input,
+            ConstraintCheckerError::OutputsExceedInputs
+        );
+
+        let burned = total_input - total_output;
+        Ok(burned.try_into().unwrap_or(TransactionPriority::MAX))
+    }
+
+    fn is_inherent(&self) -> bool {
+        false
+    }
+}
+
+/// A constraint checker that settles an English auction after it ends.
+///
+/// The first input is the auction, the second is the winning bid, and any remaining inputs are
+/// losing bids in the same auction. The first output is the item, locked to the winner. The second
+/// output pays the winning bid to the seller. The remaining outputs refund the losing bids, in
+/// the same order as the inputs.
+#[derive(
+    Serialize,
+    Deserialize,
+    Encode,
+    Decode,
+    DebugNoBound,
+    DefaultNoBound,
+    PartialEq,
+    Eq,
+    CloneNoBound,
+    TypeInfo,
+)]
+#[scale_info(skip_type_params(T))]
+pub struct Settle<T, const ID: u8>(PhantomData<T>);
+
+impl<T: AuctionConfig, V: Verifier + PartialEq + From<SigCheck>, const ID: u8> ConstraintChecker<V>
+    for Settle<T, ID>
+{
+    type Error = ConstraintCheckerError;
+    type InherentHooks = ();
+
+    fn check(
+        &self,
+        inputs: &[Output<V>],
+        _peeks: &[Output<V>],
+        outputs: &[Output<V>],
+        _context: &CheckerContext,
+    ) -> Result<TransactionPriority, Self::Error> {
+        let auction = extract_auction::<V, ID>(inputs.first())?;
+        ensure!(inputs.len() >= 2, ConstraintCheckerError::WrongNumberInputs);
+        ensure!(
+            outputs.len() == inputs.len(),
+            ConstraintCheckerError::WrongNumberOutputs
+        );
+
+        let AuctionKind::English { highest } = auction.kind else {
+            return Err(ConstraintCheckerError::NotEnglish);
+        };
+        let (winner, price) = highest.ok_or(ConstraintCheckerError::NoBids)?;
+        let height = T::block_height();
+        ensure!(
+            height >= auction.end,
+            ConstraintCheckerError::AuctionNotEnded
+        );
+        ensure!(
+            height < auction.end.saturating_add(T::SETTLEMENT_PERIOD),
+            ConstraintCheckerError::SettlementPeriodOver
+        );
+
+        // Check the winning bid, and transfer the item and the proceeds
+        let id = auction.id();
+        let winning_bid = inputs[1]
+            .payload
+            .extract::<Bid<ID>>()
+            .map_err(|_| ConstraintCheckerError::BadlyTypedInput)?;
+        ensure!(
+            winning_bid.auction == id
+                && winning_bid.bidder == winner
+                && winning_bid.amount == price,
+            ConstraintCheckerError::BidMismatch
+        );
+        ensure!(
+            outputs[0].payload == auction.item,
+            ConstraintCheckerError::ItemMismatch
+        );
+        ensure!(
+            outputs[0].verifier == SigCheck::new(winner).into(),
+            ConstraintCheckerError::PaidToWrongVerifier
+        );
+        check_payment::<V, ID>(&outputs[1], price, auction.seller)?;
+
+        // Refund the losing bids
+        for (input, output) in inputs[2..].iter().zip(&outputs[2..]) {
+            let bid = input
+                .payload
+                .extract::<Bid<ID>>()
+                .map_err(|_| ConstraintCheckerError::BadlyTypedInput)?;
+            ensure!(bid.auction == id, ConstraintCheckerError::BidMismatch);
+            check_payment::<V, ID>(output, bid.amount, bid.bidder)?;
+        }
+
+        Ok(0)
+    }
+
+    fn is_inherent(&self) -> bool {
+        false
+    }
+}
+
+/// A constraint checker that refunds bids that can no longer win.
+///
+/// A bid can be refunded once it has been outbid, which is proven by peeking at the auction,
+/// or once the settlement period has passed. All inputs are bids, and each output refunds the
+/// bid at the same position. Any peeked auction must be the one that all the bids were placed in.
+#[derive(
+    Serialize,
+    Deserialize,
+    Encode,
+    Decode,
+    DebugNoBound,
+    DefaultNoBound,
+    PartialEq,
+    Eq,
+    CloneNoBound,
+    TypeInfo,
+)]
+#[scale_info(skip_type_params(T))]
+pub struct RefundBids<T, const ID: u8>(PhantomData<T>);
+
+impl<T: AuctionConfig, V: Verifier + PartialEq + From<SigCheck>, const ID: u8> ConstraintChecker<V>
+    for RefundBids<T, ID>
+{
+    type Error = ConstraintCheckerError;
+    type InherentHooks = ();
+
+    fn check(
+        &self,
+        inputs: &[Output<V>],
+        peeks: &[Output<V>],
+        outputs: &[Output<V>],
+        _context: &CheckerContext,
+    ) -> Result<TransactionPriority, Self::Error> {
+        ensure!(
+            !inputs.is_empty(),
+            ConstraintCheckerError::WrongNumberInputs
+        );
+        ensure!(
+            outputs.len() == inputs.len(),
+            ConstraintCheckerError::WrongNumberOutputs
+        );
+        let auction = peeks
+            .first()
+            .map(|peek| {
+                peek.payload
+                    .extract::<Auction<ID>>()
+                    .map_err(|_| ConstraintCheckerError::BadlyTypedInput)
+            })
+            .transpose()?;
+
+        let height = T::block_height();
+        for (input, output) in inputs.iter().zip(outputs) {
+            let bid = input
+                .payload
+                .extract::<Bid<ID>>()
+                .map_err(|_| ConstraintCheckerError::BadlyTypedInput)?;
+            let outbid = match &auction {
+                Some(auction) => {
+                    ensure!(
+                        bid.auction == auction.id(),
+                        ConstraintCheckerError::BidMismatch
+                    );
+                    auction
+                        .highest_bid()
+                        .is_some_and(|(_, amount)| amount > bid.amount)
+                }
+                None => false,
+            };
+            ensure!(
+                outbid || height >= bid.refundable_at,
+                ConstraintCheckerError::NotRefundable
+            );
+            check_payment::<V, ID>(output, bid.amount, bid.bidder)?;
+        }
+
+        Ok(0)
+    }
+
+    fn is_inherent(&self) -> bool {
+        false
+    }
+}
+
+/// A constraint checker that buys the item in a Dutch auction at the current price.
+///
+/// The first input is the auction and the remaining inputs are the buyer's `Coin<ID>`s. The first
+/// output is the item, locked to whatever verifier the buyer chooses. The second output pays the
+/// current price to the seller. The remaining outputs are optionally `Coin<ID>` change. The buyer
+/// pays the transaction's fee and tip with whatever coins are left over.
+#[derive(
+    Serialize,
+    Deserialize,
+    Encode,
+    Decode,
+    DebugNoBound,
+    DefaultNoBound,
+    PartialEq,
+    Eq,
+    CloneNoBound,
+    TypeInfo,
+)]
+#[scale_info(skip_type_params(T))]
+pub struct Buy<T, const ID: u8>(PhantomData<T>);
+
+impl<T: AuctionConfig, V: Verifier + PartialEq + From<SigCheck>, const ID: u8> ConstraintChecker<V>
+    for Buy<T, ID>
+{
+    type Error = ConstraintCheckerError;
+    type InherentHooks = ();
+
+    fn check(
+        &self,
+        inputs: &[Output<V>],
+        _peeks: &[Output<V>],
+        outputs: &[Output<V>],
+        _context: &CheckerContext,
+    ) -> Result<TransactionPriority, Self::Error> {
+        let auction = extract_auction::<V, ID>(inputs.first())?;
+        ensure!(
+            outputs.len() >= 2,
+            ConstraintCheckerError::WrongNumberOutputs
+        );
+
+        let height = T::block_height();
+        let price = auction
+            .dutch_price(height)
+            .ok_or(ConstraintCheckerError::NotDutch)?;
+        ensure!(height < auction.end, ConstraintCheckerError::AuctionEnded);
+        ensure!(
+            outputs[0].payload == auction.item,
+            ConstraintCheckerError::ItemMismatch
+        );
+        let paid = outputs[1]
+            .payload
+            .extract::<Coin<ID>>()
+            .map_err(|_| ConstraintCheckerError::BadlyTypedOutput)?;
+        ensure!(paid.0 >= price, ConstraintCheckerError::Underpaid);
+        check_payment::<V, ID>(&outputs[1], paid.0, auction.seller)?;
+
+        let total_input = input_value::<V, ID>(&inputs[1..])?;
+        let total_output = output_value::<V, ID>(&outputs[1..])?;
+        ensure!(
+            total_output <= total_input,
+            ConstraintCheckerError::OutputsExceedInputs
+        );
+
+        let burned = total_input - total_output;
+        Ok(burned.try_into().unwrap_or(TransactionPriority::MAX))
+    }
+
+    fn is_inherent(&self) -> bool {
+        false
+    }
+}
+
+/// A constraint checker that returns an unsold item to the seller.
+///
+/// An item can be reclaimed once the auction has ended without any bids, or once
+/// the settlement period has passed without the auction being settled. The only input is
+/// the auction and the only output is the item, locked to the seller.
+#[derive(
+    Serialize,
+    Deserialize,
+    Encode,
+    Decode,
+    DebugNoBound,
+    DefaultNoBound,
+    PartialEq,
+    Eq,
+    CloneNoBound,
+    TypeInfo,
+)]
+#[scale_info(skip_type_params(T))]
+pub struct Reclaim<T, const ID: u8>(PhantomData<T>);
+
+impl<T: AuctionConfig, V: Verifier + PartialEq + From<SigCheck>, const ID: u8> ConstraintChecker<V>
+    for Reclaim<T, ID>
+{
+    type Error = ConstraintCheckerError;
+    type InherentHooks = ();
+
+    fn check(
+        &self,
+        inputs: &[Output<V>],
+        _peeks: &[Output<V>],
+        outputs: &[Output<V>],
+        _context: &CheckerContext,
+    ) -> Result<TransactionPriority, Self::Error> {
+        ensure!(inputs.len() == 1, ConstraintCheckerError::WrongNumberInputs);
+        ensure!(
+            outputs.len() == 1,
+            ConstraintCheckerError::WrongNumberOutputs
+        );
+        let auction = extract_auction::<V, ID>(inputs.first())?;
+
+        let height = T::block_height();
+        ensure!(
+            height >= auction.end,
+            ConstraintCheckerError::AuctionNotEnded
+        );
+        ensure!(
+            auction.highest_bid().is_none()
+                || height >= auction.end.saturating_add(T::SETTLEMENT_PERIOD),
+            ConstraintCheckerError::NotReclaimable
+        );
+        ensure!(
+            outputs[0].payload == auction.item,
+            ConstraintCheckerError::ItemMismatch
+        );
+        ensure!(
+            outputs[0].verifier == SigCheck::new(auction.seller).into(),
+            ConstraintCheckerError::PaidToWrongVerifier
+        );
+
+        Ok(0)
+    }
+
+    fn is_inherent(&self) -> bool {
+        false
+    }
+}
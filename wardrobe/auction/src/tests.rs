@@ -0,0 +1,594 @@
+//! Unit tests for the Auction piece
+
+use super::*;
+use sp_core::{crypto::Pair as _, sr25519::Pair};
+use tuxedo_core::{dynamic_typing::testing::Bogus, tuxedo_verifier};
+
+/// A verifier that can represent auctions as well as the keys of the participants.
+#[derive(Serialize, Deserialize, Encode, Decode, Debug, PartialEq, Eq, Clone, TypeInfo)]
+#[tuxedo_verifier]
+pub enum TestVerifier {
+    SigCheck(SigCheck),
+    UpForGrabs(UpForGrabs),
+}
+
+/// A mock config that always reports the same block height and has a short settlement period.
+pub struct AtHeight<const H: u32>;
+
+impl<const H: u32> AuctionConfig for AtHeight<H> {
+    fn block_height() -> u32 {
+        H
+    }
+
+    const SETTLEMENT_PERIOD: u32 = 10;
+}
+
+/// An item to put up for auction.
+#[derive(Encode, Decode, PartialEq, Eq, Debug)]
+pub struct Painting(u32);
+
+impl UtxoData for Painting {
+    const TYPE_ID: [u8; 4] = *b"pntg";
+}
+
+fn seller() -> H256 {
+    Pair::from_seed(&[1u8; 32]).public().into()
+}
+
+fn alice() -> H256 {
+    Pair::from_seed(&[2u8; 32]).public().into()
+}
+
+fn bob() -> H256 {
+    Pair::from_seed(&[3u8; 32]).public().into()
+}
+
+fn owned_by(key: H256) -> TestVerifier {
+    SigCheck::new(key).into()
+}
+
+fn up_for_grabs() -> TestVerifier {
+    UpForGrabs.into()
+}
+
+fn coin(value: u128, owner: H256) -> Output<TestVerifier> {
+    (Coin::<0>(value), owned_by(owner)).into()
+}
+
+/// An English auction running from block 10 to block 20 with a reserve of 100.
+fn english(highest: Option<(H256, u128)>) -> Auction<0> {
+    Auction {
+        seller: seller(),
+        item: Painting(1).into(),
+        reserve: 100,
+        start: 10,
+        end: 20,
+        kind: AuctionKind::English { highest },
+    }
+}
+
+/// A Dutch auction running from block 10 to block 20, with the price falling from 200 to 100.
+fn dutch() -> Auction<0> {
+    Auction {
+        kind: AuctionKind::Dutch { start_price: 200 },
+        ..english(None)
+    }
+}
+
+fn bid(bidder: H256, amount: u128) -> Bid<0> {
+    Bid {
+        auction: english(None).id(),
+        bidder,
+        amount,
+        refundable_at: 30,
+    }
+}
+
+fn grabbable<T: UtxoData>(data: T) -> Output<TestVerifier> {
+    (data, up_for_grabs()).into()
+}
+
+fn check<C: ConstraintChecker<TestVerifier>>(
+    checker: C,
+    inputs: Vec<Output<TestVerifier>>,
+    peeks: Vec<Output<TestVerifier>>,
+    outputs: Vec<Output<TestVerifier>>,
+) -> Result<TransactionPriority, C::Error> {
+    checker.check(&inputs, &peeks, &outputs, &Default::default())
+}
+
+#[test]
+fn open_english_auction_works() {
+    let inputs = vec![(Painting(1), owned_by(seller())).into()];
+    let outputs = vec![grabbable(english(None))];
+
+    assert_eq!(
+        check(
+            OpenAuction::<AtHeight<10>, 0>::default(),
+            inputs,
+            vec![],
+            outputs
+        ),
+        Ok(0)
+    );
+}
+
+#[test]
+fn open_dutch_auction_works() {
+    let inputs = vec![(Painting(1), owned_by(seller())).into()];
+    let outputs = vec![grabbable(dutch())];
+
+    assert_eq!(
+        check(
+            OpenAuction::<AtHeight<10>, 0>::default(),
+            inputs,
+            vec![],
+            outputs
+        ),
+        Ok(0)
+    );
+}
+
+#[test]
+fn open_auction_with_other_item_fails() {
+    let inputs = vec![(Painting(2), owned_by(seller())).into()];
+    let outputs = vec![grabbable(english(None))];
+
+    assert_eq!(
+        check(
+            OpenAuction::<AtHeight<10>, 0>::default(),
+            inputs,
+            vec![],
+            outputs
+        ),
+        Err(ConstraintCheckerError::ItemMismatch)
+    );
+}
+
+#[test]
+fn open_backdated_auction_fails() {
+    let inputs = vec![(Painting(1), owned_by(seller())).into()];
+    let outputs = vec![grabbable(english(None))];
+
+    assert_eq!(
+        check(
+            OpenAuction::<AtHeight<11>, 0>::default(),
+            inputs,
+            vec![],
+            outputs
+        ),
+        Err(ConstraintCheckerError::InvalidSchedule)
+    );
+}
+
+#[test]
+fn open_auction_with_fake_bid_fails() {
+    let inputs = vec![(Painting(1), owned_by(seller())).into()];
+    let outputs = vec![grabbable(english(Some((alice(), 1000))))];
+
+    assert_eq!(
+        check(
+            OpenAuction::<AtHeight<10>, 0>::default(),
+            inputs,
+            vec![],
+            outputs
+        ),
+        Err(ConstraintCheckerError::AlreadyBid)
+    );
+}
+
+#[test]
+fn open_dutch_auction_below_reserve_fails() {
+    let cheap = Auction {
+        kind: AuctionKind::Dutch { start_price: 99 },
+        ..english(None)
+    };
+    let inputs = vec![(Painting(1), owned_by(seller())).into()];
+    let outputs = vec![grabbable(cheap)];
+
+    assert_eq!(
+        check(
+            OpenAuction::<AtHeight<10>, 0>::default(),
+            inputs,
+            vec![],
+            outputs
+        ),
+        Err(ConstraintCheckerError::StartBelowReserve)
+    );
+}
+
+#[test]
+fn open_auction_locked_to_seller_fails() {
+    let inputs = vec![(Painting(1), owned_by(seller())).into()];
+    let outputs = vec![(english(None), owned_by(seller())).into()];
+
+    assert_eq!(
+        check(
+            OpenAuction::<AtHeight<10>, 0>::default(),
+            inputs,
+            vec![],
+            outputs
+        ),
+        Err(ConstraintCheckerError::NotUpForGrabs)
+    );
+}
+
+fn place_bid<const H: u32>(
+    highest: Option<(H256, u128)>,
+    amount: u128,
+) -> Result<TransactionPriority, ConstraintCheckerError> {
+    check(
+        PlaceBid::<AtHeight<H>, 0>::default(),
+        vec![grabbable(english(highest)), coin(200, bob())],
+        vec![],
+        vec![
+            grabbable(english(Some((bob(), amount)))),
+            grabbable(bid(bob(), amount)),
+            coin(195 - amount, bob()),
+        ],
+    )
+}
+
+#[test]
+fn first_bid_works() {
+    assert_eq!(place_bid::<15>(None, 100), Ok(5));
+}
+
+#[test]
+fn outbidding_works() {
+    assert_eq!(place_bid::<15>(Some((alice(), 120)), 121), Ok(5));
+}
+
+#[test]
+fn bid_below_reserve_fails() {
+    assert_eq!(
+        place_bid::<15>(None, 99),
+        Err(ConstraintCheckerError::BidTooLow)
+    );
+}
+
+#[test]
+fn matching_highest_bid_fails() {
+    assert_eq!(
+        place_bid::<15>(Some((alice(), 120)), 120),
+        Err(ConstraintCheckerError::BidTooLow)
+    );
+}
+
+#[test]
+fn bid_after_end_fails() {
+    assert_eq!(
+        place_bid::<20>(None, 100),
+        Err(ConstraintCheckerError::AuctionEnded)
+    );
+}
+
+#[test]
+fn bid_without_recording_it_fails() {
+    assert_eq!(
+        check(
+            PlaceBid::<AtHeight<15>, 0>::default(),
+            vec![grabbable(english(None)), coin(100, bob())],
+            vec![],
+            vec![grabbable(english(None)), grabbable(bid(bob(), 100))],
+        ),
+        Err(ConstraintCheckerError::AuctionModified)
+    );
+}
+
+#[test]
+fn bid_without_escrow_fails() {
+    assert_eq!(
+        check(
+            PlaceBid::<AtHeight<15>, 0>::default(),
+            vec![grabbable(english(None)), coin(50, bob())],
+            vec![],
+            vec![
+                grabbable(english(Some((bob(), 100)))),
+                grabbable(bid(bob(), 100))
+            ],
+        ),
+        Err(ConstraintCheckerError::OutputsExceedInputs)
+    );
+}
+
+#[test]
+fn bid_in_dutch_auction_fails() {
+    assert_eq!(
+        check(
+            PlaceBid::<AtHeight<15>, 0>::default(),
+            vec![grabbable(dutch()), coin(100, bob())],
+            vec![],
+            vec![grabbable(dutch()), grabbable(bid(bob(), 100))],
+        ),
+        Err(ConstraintCheckerError::NotEnglish)
+    );
+}
+
+fn settle<const H: u32>(
+    winner: H256,
+    outputs: Vec<Output<TestVerifier>>,
+) -> Result<TransactionPriority, ConstraintCheckerError> {
+    check(
+        Settle::<AtHeight<H>, 0>::default(),
+        vec![
+            grabbable(english(Some((bob(), 150)))),
+            grabbable(bid(winner, 150)),
+            grabbable(bid(alice(), 120)),
+        ],
+        vec![],
+        outputs,
+    )
+}
+
+fn settlement() -> Vec<Output<TestVerifier>> {
+    vec![
+        (Painting(1), owned_by(bob())).into(),
+        coin(150, seller()),
+        coin(120, alice()),
+    ]
+}
+
+#[test]
+fn settle_works() {
+    assert_eq!(settle::<20>(bob(), settlement()), Ok(0));
+}
+
+#[test]
+fn settle_before_end_fails() {
+    assert_eq!(
+        settle::<19>(bob(), settlement()),
+        Err(ConstraintCheckerError::AuctionNotEnded)
+    );
+}
+
+#[test]
+fn settle_after_settlement_period_fails() {
+    assert_eq!(
+        settle::<30>(bob(), settlement()),
+        Err(ConstraintCheckerError::SettlementPeriodOver)
+    );
+}
+
+#[test]
+fn settle_with_wrong_winner_fails() {
+    assert_eq!(
+        settle::<20>(alice(), settlement()),
+        Err(ConstraintCheckerError::BidMismatch)
+    );
+}
+
+#[test]
+fn settle_giving_item_to_loser_fails() {
+    let mut outputs = settlement();
+    outputs[0] = (Painting(1), owned_by(alice())).into();
+
+    assert_eq!(
+        settle::<20>(bob(), outputs),
+        Err(ConstraintCheckerError::PaidToWrongVerifier)
+    );
+}
+
+#[test]
+fn settle_underpaying_seller_fails() {
+    let mut outputs = settlement();
+    outputs[1] = coin(149, seller());
+
+    assert_eq!(
+        settle::<20>(bob(), outputs),
+        Err(ConstraintCheckerError::Underpaid)
+    );
+}
+
+#[test]
+fn settle_keeping_losing_bid_fails() {
+    let mut outputs = settlement();
+    outputs[2] = coin(120, bob());
+
+    assert_eq!(
+        settle::<20>(bob(), outputs),
+        Err(ConstraintCheckerError::PaidToWrongVerifier)
+    );
+}
+
+#[test]
+fn settle_without_bids_fails() {
+    assert_eq!(
+        check(
+            Settle::<AtHeight<20>, 0>::default(),
+            vec![grabbable(english(None)), grabbable(bid(bob(), 150))],
+            vec![],
+            vec![(Painting(1), owned_by(bob())).into(), coin(150, seller())],
+        ),
+        Err(ConstraintCheckerError::NoBids)
+    );
+}
+
+#[test]
+fn refund_outbid_bid_works() {
+    assert_eq!(
+        check(
+            RefundBids::<AtHeight<15>, 0>::default(),
+            vec![grabbable(bid(alice(), 120))],
+            vec![grabbable(english(Some((bob(), 150))))],
+            vec![coin(120, alice())],
+        ),
+        Ok(0)
+    );
+}
+
+#[test]
+fn refund_highest_bid_fails() {
+    assert_eq!(
+        check(
+            RefundBids::<AtHeight<15>, 0>::default(),
+            vec![grabbable(bid(bob(), 150))],
+            vec![grabbable(english(Some((bob(), 150))))],
+            vec![coin(150, bob())],
+        ),
+        Err(ConstraintCheckerError::NotRefundable)
+    );
+}
+
+#[test]
+fn refund_without_auction_fails() {
+    assert_eq!(
+        check(
+            RefundBids::<AtHeight<29>, 0>::default(),
+            vec![grabbable(bid(bob(), 150))],
+            vec![],
+            vec![coin(150, bob())],
+        ),
+        Err(ConstraintCheckerError::NotRefundable)
+    );
+}
+
+#[test]
+fn refund_after_settlement_period_works() {
+    assert_eq!(
+        check(
+            RefundBids::<AtHeight<30>, 0>::default(),
+            vec![grabbable(bid(bob(), 150))],
+            vec![],
+            vec![coin(150, bob())],
+        ),
+        Ok(0)
+    );
+}
+
+#[test]
+fn refund_peeking_other_auction_fails() {
+    let other = Auction {
+        reserve: 1,
+        ..english(Some((bob(), 150)))
+    };
+
+    assert_eq!(
+        check(
+            RefundBids::<AtHeight<15>, 0>::default(),
+            vec![grabbable(bid(alice(), 120))],
+            vec![grabbable(other)],
+            vec![coin(120, alice())],
+        ),
+        Err(ConstraintCheckerError::BidMismatch)
+    );
+}
+
+#[test]
+fn refund_to_someone_else_fails() {
+    assert_eq!(
+        check(
+            RefundBids::<AtHeight<30>, 0>::default(),
+            vec![grabbable(bid(alice(), 120))],
+            vec![],
+            vec![coin(120, bob())],
+        ),
+        Err(ConstraintCheckerError::PaidToWrongVerifier)
+    );
+}
+
+#[test]
+fn dutch_price_falls_linearly() {
+    assert_eq!(dutch().dutch_price(10), Some(200));
+    assert_eq!(dutch().dutch_price(15), Some(150));
+    assert_eq!(dutch().dutch_price(20), Some(100));
+    assert_eq!(dutch().dutch_price(25), Some(100));
+    assert_eq!(english(None).dutch_price(15), None);
+}
+
+fn buy<const H: u32>(price: u128) -> Result<TransactionPriority, ConstraintCheckerError> {
+    check(
+        Buy::<AtHeight<H>, 0>::default(),
+        vec![grabbable(dutch()), coin(200, bob())],
+        vec![],
+        vec![(Painting(1), owned_by(bob())).into(), coin(price, seller())],
+    )
+}
+
+#[test]
+fn buy_works() {
+    assert_eq!(buy::<15>(150), Ok(50));
+}
+
+#[test]
+fn buy_below_current_price_fails() {
+    assert_eq!(buy::<15>(149), Err(ConstraintCheckerError::Underpaid));
+}
+
+#[test]
+fn buy_after_end_fails() {
+    assert_eq!(buy::<20>(100), Err(ConstraintCheckerError::AuctionEnded));
+}
+
+#[test]
+fn buy_in_english_auction_fails() {
+    assert_eq!(
+        check(
+            Buy::<AtHeight<15>, 0>::default(),
+            vec![grabbable(english(None)), coin(200, bob())],
+            vec![],
+            vec![(Painting(1), owned_by(bob())).into(), coin(200, seller())],
+        ),
+        Err(ConstraintCheckerError::NotDutch)
+    );
+}
+
+#[test]
+fn buy_other_item_fails() {
+    assert_eq!(
+        check(
+            Buy::<AtHeight<15>, 0>::default(),
+            vec![grabbable(dutch()), coin(200, bob())],
+            vec![],
+            vec![(Bogus, owned_by(bob())).into(), coin(150, seller())],
+        ),
+        Err(ConstraintCheckerError::ItemMismatch)
+    );
+}
+
+fn reclaim<const H: u32>(
+    auction: Auction<0>,
+    owner: H256,
+) -> Result<TransactionPriority, ConstraintCheckerError> {
+    check(
+        Reclaim::<AtHeight<H>, 0>::default(),
+        vec![grabbable(auction)],
+        vec![],
+        vec![(Painting(1), owned_by(owner)).into()],
+    )
+}
+
+#[test]
+fn reclaim_unsold_item_works() {
+    assert_eq!(reclaim::<20>(english(None), seller()), Ok(0));
+    assert_eq!(reclaim::<20>(dutch(), seller()), Ok(0));
+}
+
+#[test]
+fn reclaim_before_end_fails() {
+    assert_eq!(
+        reclaim::<19>(english(None), seller()),
+        Err(ConstraintCheckerError::AuctionNotEnded)
+    );
+}
+
+#[test]
+fn reclaim_during_settlement_period_fails() {
+    assert_eq!(
+        reclaim::<29>(english(Some((bob(), 150))), seller()),
+        Err(ConstraintCheckerError::NotReclaimable)
+    );
+}
+
+#[test]
+fn reclaim_unsettled_auction_works() {
+    assert_eq!(reclaim::<30>(english(Some((bob(), 150))), seller()), Ok(0));
+}
+
+#[test]
+fn reclaim_to_someone_else_fails() {
+    assert_eq!(
+        reclaim::<20>(english(None), bob()),
+        Err(ConstraintCheckerError::PaidToWrongVerifier)
+    );
+}
@@ -0,0 +1,228 @@
+//! On-chain Aura authorities that governance can rotate without a runtime upgrade.
+//!
+//! The current authorities live in an [`AuthoritySet`] UTXO. Governance rotates them by enacting a
+//! referendum whose payload is the hash of the new set, and then submitting a [`RotateAuthorities`]
+//! transaction that consumes the enacted outcome together with the current set, and creates the new set.
+//!
+//! The runtime's `AuraApi` can not look up a UTXO without knowing its output ref, so the checker also
+//! writes the new set to a well-known storage key as a side effect, and [`authorities`] reads it from
+//! there. This is similar to how the runtime upgrade piece writes the new wasm to the `:code` key.
+//!
+//! Until governance rotates them for the first time, the authorities are the ones that the runtime
+//! configures in [`AuraAuthoritiesConfig::genesis_authorities`], and they are not in the UTXO set.
+//! The first rotation therefore consumes only the enacted outcome. Every later rotation must also
+//! consume the current set.
+//!
+//! A rotation takes effect from the block after the one that includes it, because Aura reads the
+//! authorities from the parent block's state.
+//!
+//! ## Hack Warning
+//!
+//! Like the timestamp piece, this piece abuses the UpForGrabs verifier to protect the authority set,
+//! and relies on the constraint checker to make sure that only governance can replace it.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use core::marker::PhantomData;
+
+use governance::Enacted;
+use parity_scale_codec::{Decode, Encode};
+use scale_info::TypeInfo;
+use serde::{Deserialize, Serialize};
+use sp_core::H256;
+use sp_runtime::{
+    traits::{BlakeTwo256, Hash},
+    transaction_validity::TransactionPriority,
+};
+use sp_std::{collections::btree_set::BTreeSet, vec::Vec};
+use tuxedo_core::{
+    dynamic_typing::UtxoData,
+    ensure,
+    support_macros::{CloneNoBound, DebugNoBound, DefaultNoBound},
+    types::Output,
+    verifier::UpForGrabs,
+    CheckerContext, ConstraintChecker, Verifier,
+};
+
+#[cfg(test)]
+mod tests;
+
+/// The storage key that holds the current authority set once governance has rotated it.
+const AUTHORITIES_KEY: &[u8] = b"aura_authorities";
+
+/// Configuration items for the Aura authorities piece when it is
+/// instantiated in a concrete runtime.
+pub trait AuraAuthoritiesConfig {
+    /// The authorities before governance rotates them for the first time.
+    fn genesis_authorities() -> Vec<H256>;
+}
+
+/// The sr25519 public keys of the Aura authorities, in the order that they author in.
+#[derive(
+    Serialize,
+    Deserialize,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Clone,
+    Encode,
+    Decode,
+    Hash,
+    Debug,
+    TypeInfo,
+)]
+pub struct AuthoritySet {
+    /// The public keys of the authorities.
+    pub authorities: Vec<H256>,
+}
+
+impl AuthoritySet {
+    /// The payload that a governance referendum must enact to approve rotating to this set.
+    pub fn governance_payload(&self) -> H256 {
+        BlakeTwo256::hash_of(self)
+    }
+}
+
+impl UtxoData for AuthoritySet {
+    const TYPE_ID: [u8; 4] = *b"aura";
+}
+
+/// The authority set that governance last rotated to, if any.
+fn rotated_authorities() -> Option<AuthoritySet> {
+    sp_io::storage::get(AUTHORITIES_KEY).and_then(|d| AuthoritySet::decode(&mut &*d).ok())
+}
+
+/// The current Aura authorities. These are the ones that governance last rotated to,
+/// or the genesis authorities if governance has not rotated them yet.
+pub fn authorities<T: AuraAuthoritiesConfig>() -> Vec<H256> {
+    rotated_authorities()
+        .map(|set| set.authorities)
+        .unwrap_or_else(T::genesis_authorities)
+}
+
+/// Errors that can occur when rotating the authorities.
+#[derive(
+    Serialize,
+    Deserialize,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Clone,
+    Encode,
+    Decode,
+    Hash,
+    Debug,
+    TypeInfo,
+)]
+pub enum ConstraintCheckerError {
+    /// An input data has the wrong type.
+    BadlyTypedInput,
+    /// An output data has the wrong type.
+    BadlyTypedOutput,
+    /// Wrong number of inputs were provided to the constraint checker.
+    WrongNumberInputs,
+    /// Wrong number of outputs were provided to the constraint checker.
+    WrongNumberOutputs,
+    /// The rotation is not backed by an enacted referendum outcome as the first input.
+    NotApproved,
+    /// The enacted referendum outcome approves some other payload than the new authority set.
+    WrongGovernanceOutcome,
+    /// The consumed authority set is not the current one.
+    InputMismatch,
+    /// The new authority set is not protected by the `UpForGrabs` verifier, so it could not be rotated again.
+    SetNotUpForGrabs,
+    /// The new authority set is empty, which would stop block production.
+    NoAuthorities,
+    /// The new authority set lists an authority more than once.
+    DuplicateAuthority,
+}
+
+/// A constraint checker that rotates the Aura authorities with the approval of governance.
+///
+/// The first input is an enacted referendum outcome whose payload is the hash of the new set.
+/// The second input is the current authority set, unless the genesis authorities are still in force.
+/// The single output is the new authority set, which must be up for grabs.
+#[derive(
+    Serialize,
+    Deserialize,
+    Encode,
+    Decode,
+    DebugNoBound,
+    DefaultNoBound,
+    PartialEq,
+    Eq,
+    CloneNoBound,
+    TypeInfo,
+)]
+#[scale_info(skip_type_params(T))]
+pub struct RotateAuthorities<T, const ID: u8>(PhantomData<T>);
+
+impl<T: AuraAuthoritiesConfig, V: Verifier + PartialEq + From<UpForGrabs>, const ID: u8>
+    ConstraintChecker<V> for RotateAuthorities<T, ID>
+{
+    type Error = ConstraintCheckerError;
+    type InherentHooks = ();
+
+    fn check(
+        &self,
+        inputs: &[Output<V>],
+        _peeks: &[Output<V>],
+        outputs: &[Output<V>],
+        _context: &CheckerContext,
+    ) -> Result<TransactionPriority, Self::Error> {
+        ensure!(
+            outputs.len() == 1,
+            ConstraintCheckerError::WrongNumberOutputs
+        );
+        let new_set = outputs[0]
+            .payload
+            .extract::<AuthoritySet>()
+            .map_err(|_| ConstraintCheckerError::BadlyTypedOutput)?;
+        ensure!(
+            outputs[0].verifier == UpForGrabs.into(),
+            ConstraintCheckerError::SetNotUpForGrabs
+        );
+        ensure!(
+            !new_set.authorities.is_empty(),
+            ConstraintCheckerError::NoAuthorities
+        );
+        let distinct: BTreeSet<_> = new_set.authorities.iter().collect();
+        ensure!(
+            distinct.len() == new_set.authorities.len(),
+            ConstraintCheckerError::DuplicateAuthority
+        );
+
+        let outcome = inputs
+            .first()
+            .and_then(|input| input.payload.extract::<Enacted<ID>>().ok())
+            .ok_or(ConstraintCheckerError::NotApproved)?;
+        ensure!(
+            outcome.payload == new_set.governance_payload(),
+            ConstraintCheckerError::WrongGovernanceOutcome
+        );
+
+        // Once governance has rotated the authorities, the current set is in the UTXO set and must be consumed.
+        match rotated_authorities() {
+            Some(current) => {
+                ensure!(inputs.len() == 2, ConstraintCheckerError::WrongNumberInputs);
+                let consumed = inputs[1]
+                    .payload
+                    .extract::<AuthoritySet>()
+                    .map_err(|_| ConstraintCheckerError::BadlyTypedInput)?;
+                ensure!(consumed == current, ConstraintCheckerError::InputMismatch);
+            }
+            None => ensure!(inputs.len() == 1, ConstraintCheckerError::WrongNumberInputs),
+        }
+
+        // SIDE EFFECT: Write the new authorities to storage so that the runtime API can find them
+        sp_io::storage::set(AUTHORITIES_KEY, &new_set.encode());
+
+        Ok(0)
+    }
+
+    fn is_inherent(&self) -> bool {
+        false
+    }
+}
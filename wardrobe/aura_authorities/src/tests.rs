@@ -0,0 +1,195 @@
+//! Unit tests for the Aura Authorities piece
+
+use super::*;
+use sp_io::TestExternalities;
+use tuxedo_core::{tuxedo_verifier, verifier::SigCheck};
+
+/// A verifier that can represent the authority set and ordinary users.
+#[derive(Serialize, Deserialize, Encode, Decode, Debug, PartialEq, Eq, Clone, TypeInfo)]
+#[tuxedo_verifier]
+pub enum TestVerifier {
+    SigCheck(SigCheck),
+    UpForGrabs(UpForGrabs),
+}
+
+/// The mock config starts with Alice as the only authority.
+pub struct TestConfig;
+
+impl AuraAuthoritiesConfig for TestConfig {
+    fn genesis_authorities() -> Vec<H256> {
+        vec![alice()]
+    }
+}
+
+type Rotate = RotateAuthorities<TestConfig, 0>;
+
+fn alice() -> H256 {
+    H256::repeat_byte(1)
+}
+
+fn bob() -> H256 {
+    H256::repeat_byte(2)
+}
+
+fn set(authorities: Vec<H256>) -> AuthoritySet {
+    AuthoritySet { authorities }
+}
+
+fn set_output(authorities: Vec<H256>) -> Output<TestVerifier> {
+    (set(authorities), UpForGrabs.into()).into()
+}
+
+fn approval(authorities: Vec<H256>) -> Output<TestVerifier> {
+    let payload = set(authorities).governance_payload();
+    (Enacted::<0> { payload }, UpForGrabs.into()).into()
+}
+
+fn rotate(
+    inputs: Vec<Output<TestVerifier>>,
+    outputs: Vec<Output<TestVerifier>>,
+) -> Result<TransactionPriority, ConstraintCheckerError> {
+    Rotate::default().check(&inputs, &[], &outputs, &Default::default())
+}
+
+#[test]
+fn genesis_authorities_are_current_before_rotation() {
+    TestExternalities::default().execute_with(|| {
+        assert_eq!(authorities::<TestConfig>(), vec![alice()]);
+    });
+}
+
+#[test]
+fn first_rotation_works() {
+    TestExternalities::default().execute_with(|| {
+        assert_eq!(
+            rotate(
+                vec![approval(vec![alice(), bob()])],
+                vec![set_output(vec![alice(), bob()])]
+            ),
+            Ok(0)
+        );
+        assert_eq!(authorities::<TestConfig>(), vec![alice(), bob()]);
+    });
+}
+
+#[test]
+fn later_rotation_consumes_current_set() {
+    TestExternalities::default().execute_with(|| {
+        rotate(
+            vec![approval(vec![alice(), bob()])],
+            vec![set_output(vec![alice(), bob()])],
+        )
+        .unwrap();
+
+        assert_eq!(
+            rotate(
+                vec![approval(vec![bob()]), set_output(vec![alice(), bob()])],
+                vec![set_output(vec![bob()])]
+            ),
+            Ok(0)
+        );
+        assert_eq!(authorities::<TestConfig>(), vec![bob()]);
+    });
+}
+
+#[test]
+fn later_rotation_without_current_set_fails() {
+    TestExternalities::default().execute_with(|| {
+        rotate(
+            vec![approval(vec![alice(), bob()])],
+            vec![set_output(vec![alice(), bob()])],
+        )
+        .unwrap();
+
+        assert_eq!(
+            rotate(vec![approval(vec![bob()])], vec![set_output(vec![bob()])]),
+            Err(ConstraintCheckerError::WrongNumberInputs)
+        );
+    });
+}
+
+#[test]
+fn later_rotation_consuming_stale_set_fails() {
+    TestExternalities::default().execute_with(|| {
+        rotate(
+            vec![approval(vec![alice(), bob()])],
+            vec![set_output(vec![alice(), bob()])],
+        )
+        .unwrap();
+
+        assert_eq!(
+            rotate(
+                vec![approval(vec![bob()]), set_output(vec![alice()])],
+                vec![set_output(vec![bob()])]
+            ),
+            Err(ConstraintCheckerError::InputMismatch)
+        );
+        assert_eq!(authorities::<TestConfig>(), vec![alice(), bob()]);
+    });
+}
+
+#[test]
+fn rotation_without_approval_fails() {
+    TestExternalities::default().execute_with(|| {
+        assert_eq!(
+            rotate(vec![], vec![set_output(vec![bob()])]),
+            Err(ConstraintCheckerError::NotApproved)
+        );
+        assert_eq!(authorities::<TestConfig>(), vec![alice()]);
+    });
+}
+
+#[test]
+fn rotation_with_other_approval_fails() {
+    TestExternalities::default().execute_with(|| {
+        assert_eq!(
+            rotate(vec![approval(vec![alice()])], vec![set_output(vec![bob()])]),
+            Err(ConstraintCheckerError::WrongGovernanceOutcome)
+        );
+    });
+}
+
+#[test]
+fn rotation_to_owned_set_fails() {
+    TestExternalities::default().execute_with(|| {
+        let owned = (set(vec![bob()]), SigCheck::new(bob()).into()).into();
+
+        assert_eq!(
+            rotate(vec![approval(vec![bob()])], vec![owned]),
+            Err(ConstraintCheckerError::SetNotUpForGrabs)
+        );
+    });
+}
+
+#[test]
+fn rotation_to_empty_set_fails() {
+    TestExternalities::default().execute_with(|| {
+        assert_eq!(
+            rotate(vec![approval(vec![])], vec![set_output(vec![])]),
+            Err(ConstraintCheckerError::NoAuthorities)
+        );
+    });
+}
+
+#[test]
+fn rotation_with_duplicate_authority_fails() {
+    TestExternalities::default().execute_with(|| {
+        assert_eq!(
+            rotate(
+                vec![approval(vec![bob(), bob()])],
+                vec![set_output(vec![bob(), bob()])]
+            ),
+            Err(ConstraintCheckerError::DuplicateAuthority)
+        );
+    });
+}
+
+#[test]
+fn rotation_without_new_set_fails() {
+    TestExternalities::default().execute_with(|| {
+        assert_eq!(
+            rotate(vec![approval(vec![bob()])], vec![]),
+            Err(ConstraintCheckerError::WrongNumberOutputs)
+        );
+    });
+}
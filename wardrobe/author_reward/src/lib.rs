@@ -0,0 +1,299 @@
+//! Rewards block authors with a share of the fees, and by default all of the tips, paid by the
+//! transactions in their blocks.
+//!
+//! Tuxedo inherents are applied at the beginning of a block, before any of the block's fees
+//! have been paid. So the fees of each block are rewarded one block later. Once per block, the
+//! block author includes a `RewardAuthor` inherent that:
+//! * Mints a [`Coin`] worth the configured shares of the fees and the tips paid in the previous
+//!   block (see `tuxedo_core::fees`) to the author of the previous block.
+//! * Records the author of the current block, so that the next block can reward them in turn.
+//!
+//! Rewards that are too small to be valid coins under the money piece's configuration are not
+//! paid, and remain burned.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use core::marker::PhantomData;
+
+use money::{Coin, MoneyConfig};
+use parity_scale_codec::{Decode, Encode};
+use scale_info::TypeInfo;
+use serde::{Deserialize, Serialize};
+use sp_core::H256;
+use sp_inherents::{CheckInherentsResult, InherentData, MakeFatalError};
+use sp_runtime::{transaction_validity::TransactionPriority, Percent};
+use sp_std::{vec, vec::Vec};
+use tuxedo_core::{
+    ensure, fees,
+    inherents::{TuxedoInherent, TuxedoInherentAdapter},
+    support_macros::{CloneNoBound, DebugNoBound, DefaultNoBound},
+    types::{Output, Transaction, TransactionVersion},
+    verifier::SigCheck,
+    CheckerContext, ConstraintChecker, Verifier,
+};
+
+#[cfg(test)]
+mod tests;
+
+/// The storage key that holds the author of the previous block, who is owed its fees.
+pub const PREVIOUS_AUTHOR_KEY: &[u8] = b"previous_author";
+
+/// Configuration items for the author reward piece when it is
+/// instantiated in a concrete runtime.
+pub trait AuthorRewardConfig: MoneyConfig {
+    /// A means of getting the current block height.
+    /// Probably this will be the Tuxedo Executive
+    fn block_height() -> u32;
+
+    /// A means of getting the public key of the current block's author, if there is one.
+    /// Probably this will look up the slot's authority from the Aura pre-runtime digest.
+    fn block_author() -> Option<H256>;
+
+    /// The share of the fees paid in a block that is rewarded to its author.
+    ///
+    /// Default is 80 percent, which redistributes all the fees when combined with
+    /// the treasury's default share.
+    const FEE_SHARE: Percent = Percent::from_percent(80);
+
+    /// The share of the tips paid in a block that is rewarded to its author.
+    ///
+    /// Default is all of them. Runtimes that also fund a treasury from tips should lower it by
+    /// the treasury's share.
+    const TIP_SHARE: Percent = Percent::from_percent(100);
+}
+
+/// The author of the previous block, if it had one.
+pub fn previous_author() -> Option<H256> {
+    sp_io::storage::get(PREVIOUS_AUTHOR_KEY).and_then(|d| H256::decode(&mut &*d).ok())
+}
+
+/// Errors that can occur when checking author rewards.
+#[derive(
+    Serialize,
+    Deserialize,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Clone,
+    Encode,
+    Decode,
+    Hash,
+    Debug,
+    TypeInfo,
+)]
+pub enum ConstraintCheckerError {
+    /// Inputs were provided to the constraint checker. Rewards are created from the void.
+    WrongNumberInputs,
+    /// The wrong number of outputs was provided to the constraint checker.
+    WrongNumberOutputs,
+    /// The block height noted in the inherent does not match the block into which it was inserted.
+    WrongHeight,
+    /// The author noted in the inherent is not the author of the block into which it was inserted.
+    WrongAuthor,
+    /// The reward is not a coin.
+    BadlyTypedOutput,
+    /// The reward is worth a different amount than the author's share of the fees.
+    WrongRewardAmount,
+    /// The reward is not locked to the previous block's author.
+    WrongRecipient,
+}
+
+/// A constraint checker that rewards the previous block's author with their shares of its fees
+/// and tips.
+///
+/// This is expected to be performed through an inherent, and to happen exactly once per block.
+/// Fees and tips are paid in native coins (see `money::surplus`), so `ID` is expected to be the
+/// money piece's `NATIVE_ID`.
+///
+/// There are no inputs. If there is a reward to pay, the only output is a coin worth the
+/// reward, locked to the previous block's author. Otherwise there are no outputs. The block
+/// height is part of the checker so that two rewards of the same amount to the same author
+/// do not have the same output references.
+#[derive(
+    Serialize,
+    Deserialize,
+    Encode,
+    Decode,
+    DebugNoBound,
+    DefaultNoBound,
+    PartialEq,
+    Eq,
+    CloneNoBound,
+    TypeInfo,
+)]
+#[scale_info(skip_type_params(T))]
+pub struct RewardAuthor<T, const ID: u8> {
+    /// The block in which the reward is paid.
+    pub block: u32,
+    /// The author of that block, who will be rewarded in the next one.
+    pub author: Option<H256>,
+    _phantom: PhantomData<T>,
+}
+
+impl<T, const ID: u8> RewardAuthor<T, ID> {
+    pub fn new(block: u32, author: Option<H256>) -> Self {
+        Self {
+            block,
+            author,
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<T: AuthorRewardConfig, const ID: u8> RewardAuthor<T, ID> {
+    /// The reward that is owed for the given fees and tips, and the author it is owed to.
+    ///
+    /// There is no reward if the fees were paid in a block without an author,
+    /// or if the author's share is too small to make a valid coin.
+    pub fn reward_for(fees: u128, tips: u128) -> Option<(H256, u128)> {
+        let amount = (T::FEE_SHARE * fees).saturating_add(T::TIP_SHARE * tips);
+        let author = previous_author()?;
+        (amount > 0 && amount >= T::MINIMUM_COIN_VALUE).then_some((author, amount))
+    }
+
+    /// The reward that is owed for the fees and tips that are currently collectible.
+    pub fn expected_reward() -> Option<(H256, u128)> {
+        Self::reward_for(fees::collectible(), fees::collectible_tips())
+    }
+
+    /// The output that pays the given reward.
+    fn reward_output<V: From<SigCheck>>((author, amount): (H256, u128)) -> Output<V> {
+        Output {
+            payload: Coin::<ID>(amount).into(),
+            verifier: SigCheck::new(author).into(),
+        }
+    }
+}
+
+impl<T: AuthorRewardConfig + 'static, V: Verifier + PartialEq + From<SigCheck>, const ID: u8>
+    ConstraintChecker<V> for RewardAuthor<T, ID>
+{
+    type Error = ConstraintCheckerError;
+    type InherentHooks = TuxedoInherentAdapter<Self>;
+
+    fn check(
+        &self,
+        input_data: &[Output<V>],
+        _peek_data: &[Output<V>],
+        output_data: &[Output<V>],
+        _context: &CheckerContext,
+    ) -> Result<TransactionPriority, Self::Error> {
+        ensure!(
+            input_data.is_empty(),
+            ConstraintCheckerError::WrongNumberInputs
+        );
+        ensure!(
+            self.block == T::block_height(),
+            ConstraintCheckerError::WrongHeight
+        );
+        ensure!(
+            self.author == T::block_author(),
+            ConstraintCheckerError::WrongAuthor
+        );
+
+        match Self::expected_reward() {
+            None => ensure!(
+                output_data.is_empty(),
+                ConstraintCheckerError::WrongNumberOutputs
+            ),
+            Some((author, amount)) => {
+                ensure!(
+                    output_data.len() == 1,
+                    ConstraintCheckerError::WrongNumberOutputs
+                );
+                let reward = output_data[0]
+                    .payload
+                    .extract::<Coin<ID>>()
+                    .map_err(|_| ConstraintCheckerError::BadlyTypedOutput)?;
+                ensure!(
+                    reward.0 == amount,
+                    ConstraintCheckerError::WrongRewardAmount
+                );
+                ensure!(
+                    output_data[0].verifier == SigCheck::new(author).into(),
+                    ConstraintCheckerError::WrongRecipient
+                );
+            }
+        }
+
+        // SIDE EFFECT: Remember who authored this block, so they can be rewarded in the next one.
+        match self.author {
+            Some(author) => sp_io::storage::set(PREVIOUS_AUTHOR_KEY, &author.encode()),
+            None => sp_io::storage::clear(PREVIOUS_AUTHOR_KEY),
+        }
+
+        Ok(0)
+    }
+
+    fn is_inherent(&self) -> bool {
+        true
+    }
+}
+
+impl<V: Verifier + PartialEq + From<SigCheck>, T: AuthorRewardConfig + 'static, const ID: u8>
+    TuxedoInherent<V, Self> for RewardAuthor<T, ID>
+{
+    type Error = MakeFatalError<ConstraintCheckerError>;
+    const INHERENT_IDENTIFIER: sp_inherents::InherentIdentifier = *b"author__";
+
+    fn create_inherent(
+        _authoring_inherent_data: &InherentData,
+        _previous_inherent: (Transaction<V, Self>, H256),
+    ) -> Transaction<V, Self> {
+        // Inherents are applied at the beginning of the block, so the collectible fees
+        // are the same now as they will be when this transaction is applied.
+        let outputs = Self::expected_reward()
+            .map(Self::reward_output)
+            .into_iter()
+            .collect();
+
+        Transaction {
+            version: TransactionVersion::LATEST,
+            inputs: Vec::new(),
+            peeks: Vec::new(),
+            outputs,
+            checker: Self::new(T::block_height(), T::block_author()),
+            memo: None,
+            valid_until: None,
+        }
+    }
+
+    fn check_inherent(
+        _importing_inherent_data: &InherentData,
+        inherent: Transaction<V, Self>,
+        result: &mut CheckInherentsResult,
+    ) {
+        // Inherents are checked against the parent block's state, in which its fees are
+        // still pending, and its author is already recorded. So we can validate the reward
+        // before executing the block.
+        let expected: Vec<Output<V>> = Self::reward_for(fees::pending(), fees::pending_tips())
+            .map(Self::reward_output)
+            .into_iter()
+            .collect();
+
+        if inherent.outputs != expected {
+            result
+                .put_error(
+                    Self::INHERENT_IDENTIFIER,
+                    &MakeFatalError::from(ConstraintCheckerError::WrongRewardAmount),
+                )
+                .expect("Should be able to put an error.");
+        }
+    }
+
+    #[cfg(feature = "std")]
+    fn genesis_transactions() -> Vec<Transaction<V, Self>> {
+        // The inherent must appear in every block, including genesis, so that
+        // block authors always find a previous inherent to build on.
+        vec![Transaction {
+            version: TransactionVersion::LATEST,
+            inputs: Vec::new(),
+            peeks: Vec::new(),
+            outputs: Vec::new(),
+            checker: Self::default(),
+            memo: None,
+            valid_until: None,
+        }]
+    }
+}
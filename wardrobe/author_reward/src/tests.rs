@@ -0,0 +1,348 @@
+//! Unit tests for the Author Reward piece
+
+use super::*;
+use sp_io::TestExternalities;
+use tuxedo_core::{tuxedo_verifier, verifier::UpForGrabs};
+
+/// A verifier that can represent block authors and the void.
+#[derive(Serialize, Deserialize, Encode, Decode, Debug, PartialEq, Eq, Clone, TypeInfo)]
+#[tuxedo_verifier]
+pub enum TestVerifier {
+    SigCheck(SigCheck),
+    UpForGrabs(UpForGrabs),
+}
+
+/// The mock config always says the block number is seven, and that it was authored by Bob.
+/// Coins must be worth at least ten.
+pub struct TestConfig;
+
+impl MoneyConfig for TestConfig {
+    const MINIMUM_COIN_VALUE: u128 = 10;
+}
+
+impl AuthorRewardConfig for TestConfig {
+    fn block_height() -> u32 {
+        7
+    }
+
+    fn block_author() -> Option<H256> {
+        Some(bob())
+    }
+}
+
+/// Like the mock config, but a fifth of the tips are left for a treasury.
+pub struct SharesTips;
+
+impl MoneyConfig for SharesTips {
+    const MINIMUM_COIN_VALUE: u128 = 10;
+}
+
+impl AuthorRewardConfig for SharesTips {
+    fn block_height() -> u32 {
+        7
+    }
+
+    fn block_author() -> Option<H256> {
+        Some(bob())
+    }
+
+    const TIP_SHARE: Percent = Percent::from_percent(80);
+}
+
+type Reward = RewardAuthor<TestConfig, 0>;
+
+fn alice() -> H256 {
+    H256::repeat_byte(1)
+}
+
+fn bob() -> H256 {
+    H256::repeat_byte(2)
+}
+
+fn coin(value: u128, owner: H256) -> Output<TestVerifier> {
+    (Coin::<0>(value), SigCheck::new(owner)).into()
+}
+
+/// Set up the previous block as authored by Alice, with the given fees paid in it.
+fn previous_block(paid: u128) {
+    sp_io::storage::set(PREVIOUS_AUTHOR_KEY, &alice().encode());
+    fees::note(paid);
+    fees::roll_over();
+}
+
+fn reward(
+    outputs: Vec<Output<TestVerifier>>,
+) -> Result<TransactionPriority, ConstraintCheckerError> {
+    Reward::new(7, Some(bob())).check(&[], &[], &outputs, &Default::default())
+}
+
+#[test]
+fn reward_author_works() {
+    TestExternalities::default().execute_with(|| {
+        previous_block(100);
+
+        assert_eq!(reward(vec![coin(80, alice())]), Ok(0));
+        // Bob will be rewarded in the next block
+        assert_eq!(previous_author(), Some(bob()));
+    });
+}
+
+#[test]
+fn reward_author_includes_all_tips() {
+    TestExternalities::default().execute_with(|| {
+        sp_io::storage::set(PREVIOUS_AUTHOR_KEY, &alice().encode());
+        fees::note(100);
+        fees::note_tip(15);
+        fees::roll_over();
+
+        assert_eq!(reward(vec![coin(95, alice())]), Ok(0));
+    });
+}
+
+#[test]
+fn tips_alone_are_rewarded() {
+    TestExternalities::default().execute_with(|| {
+        sp_io::storage::set(PREVIOUS_AUTHOR_KEY, &alice().encode());
+        fees::note_tip(15);
+        fees::roll_over();
+
+        assert_eq!(reward(vec![coin(15, alice())]), Ok(0));
+    });
+}
+
+#[test]
+fn tips_shared_with_a_treasury_are_left_out() {
+    TestExternalities::default().execute_with(|| {
+        sp_io::storage::set(PREVIOUS_AUTHOR_KEY, &alice().encode());
+        fees::note(100);
+        fees::note_tip(50);
+        fees::roll_over();
+
+        assert_eq!(
+            RewardAuthor::<SharesTips, 0>::new(7, Some(bob())).check(
+                &[],
+                &[],
+                &[coin(120, alice())],
+                &Default::default()
+            ),
+            Ok(0)
+        );
+    });
+}
+
+#[test]
+fn reward_author_without_fees_works() {
+    TestExternalities::default().execute_with(|| {
+        previous_block(0);
+
+        assert_eq!(reward(vec![]), Ok(0));
+        assert_eq!(previous_author(), Some(bob()));
+    });
+}
+
+#[test]
+fn dust_reward_is_not_paid() {
+    TestExternalities::default().execute_with(|| {
+        // Eighty percent of ten is below the minimum coin value
+        previous_block(10);
+
+        assert_eq!(reward(vec![]), Ok(0));
+        assert_eq!(
+            reward(vec![coin(8, alice())]),
+            Err(ConstraintCheckerError::WrongNumberOutputs)
+        );
+    });
+}
+
+#[test]
+fn reward_without_previous_author_is_not_paid() {
+    TestExternalities::default().execute_with(|| {
+        fees::note(100);
+        fees::roll_over();
+
+        assert_eq!(reward(vec![]), Ok(0));
+    });
+}
+
+#[test]
+fn reward_with_wrong_amount_fails() {
+    TestExternalities::default().execute_with(|| {
+        previous_block(100);
+
+        assert_eq!(
+            reward(vec![coin(100, alice())]),
+            Err(ConstraintCheckerError::WrongRewardAmount)
+        );
+        // Nothing was recorded
+        assert_eq!(previous_author(), Some(alice()));
+    });
+}
+
+#[test]
+fn reward_to_current_author_fails() {
+    TestExternalities::default().execute_with(|| {
+        previous_block(100);
+
+        assert_eq!(
+            reward(vec![coin(80, bob())]),
+            Err(ConstraintCheckerError::WrongRecipient)
+        );
+    });
+}
+
+#[test]
+fn reward_missing_fails() {
+    TestExternalities::default().execute_with(|| {
+        previous_block(100);
+
+        assert_eq!(
+            reward(vec![]),
+            Err(ConstraintCheckerError::WrongNumberOutputs)
+        );
+    });
+}
+
+#[test]
+fn reward_badly_typed_fails() {
+    TestExternalities::default().execute_with(|| {
+        previous_block(100);
+
+        assert_eq!(
+            reward(vec![(money::Coin::<1>(80), SigCheck::new(alice())).into()]),
+            Err(ConstraintCheckerError::BadlyTypedOutput)
+        );
+    });
+}
+
+#[test]
+fn reward_with_inputs_fails() {
+    TestExternalities::default().execute_with(|| {
+        previous_block(100);
+        let outputs = vec![coin(80, alice())];
+
+        assert_eq!(
+            Reward::new(7, Some(bob())).check(
+                &[coin(5, bob())],
+                &[],
+                &outputs,
+                &Default::default()
+            ),
+            Err(ConstraintCheckerError::WrongNumberInputs)
+        );
+    });
+}
+
+#[test]
+fn reward_at_wrong_height_fails() {
+    TestExternalities::default().execute_with(|| {
+        let checker = Reward::new(6, Some(bob()));
+
+        assert_eq!(
+            ConstraintChecker::<TestVerifier>::check(&checker, &[], &[], &[], &Default::default()),
+            Err(ConstraintCheckerError::WrongHeight)
+        );
+    });
+}
+
+#[test]
+fn reward_noting_wrong_author_fails() {
+    TestExternalities::default().execute_with(|| {
+        let checker = Reward::new(7, Some(alice()));
+
+        assert_eq!(
+            ConstraintChecker::<TestVerifier>::check(&checker, &[], &[], &[], &Default::default()),
+            Err(ConstraintCheckerError::WrongAuthor)
+        );
+    });
+}
+
+fn previous_inherent() -> (Transaction<TestVerifier, Reward>, H256) {
+    let tx = Transaction {
+        version: TransactionVersion::LATEST,
+        inputs: Vec::new(),
+        peeks: Vec::new(),
+        outputs: Vec::new(),
+        checker: Reward::new(6, Some(alice())),
+        memo: None,
+        valid_until: None,
+    };
+    (tx, H256::zero())
+}
+
+#[test]
+fn created_inherent_is_valid() {
+    TestExternalities::default().execute_with(|| {
+        previous_block(1_000);
+
+        let tx = <Reward as TuxedoInherent<TestVerifier, _>>::create_inherent(
+            &InherentData::new(),
+            previous_inherent(),
+        );
+
+        assert_eq!(tx.outputs, vec![coin(800, alice())]);
+        assert_eq!(
+            tx.checker.check(&[], &[], &tx.outputs, &Default::default()),
+            Ok(0)
+        );
+    });
+}
+
+#[test]
+fn check_inherent_accepts_correct_reward() {
+    TestExternalities::default().execute_with(|| {
+        // The parent state, before the block under check rolls its fees over
+        sp_io::storage::set(PREVIOUS_AUTHOR_KEY, &alice().encode());
+        fees::note(1_000);
+
+        let (mut tx, _) = previous_inherent();
+        tx.outputs = vec![coin(800, alice())];
+        let mut result = CheckInherentsResult::new();
+        <Reward as TuxedoInherent<TestVerifier, _>>::check_inherent(
+            &InherentData::new(),
+            tx,
+            &mut result,
+        );
+
+        assert!(result.ok());
+    });
+}
+
+#[test]
+fn check_inherent_counts_pending_tips() {
+    TestExternalities::default().execute_with(|| {
+        sp_io::storage::set(PREVIOUS_AUTHOR_KEY, &alice().encode());
+        fees::note(1_000);
+        fees::note_tip(50);
+
+        let (mut tx, _) = previous_inherent();
+        tx.outputs = vec![coin(850, alice())];
+        let mut result = CheckInherentsResult::new();
+        <Reward as TuxedoInherent<TestVerifier, _>>::check_inherent(
+            &InherentData::new(),
+            tx,
+            &mut result,
+        );
+
+        assert!(result.ok());
+    });
+}
+
+#[test]
+fn check_inherent_rejects_wrong_reward() {
+    TestExternalities::default().execute_with(|| {
+        sp_io::storage::set(PREVIOUS_AUTHOR_KEY, &alice().encode());
+        fees::note(1_000);
+
+        let (mut tx, _) = previous_inherent();
+        tx.outputs = vec![coin(1_000, alice())];
+        let mut result = CheckInherentsResult::new();
+        <Reward as TuxedoInherent<TestVerifier, _>>::check_inherent(
+            &InherentData::new(),
+            tx,
+            &mut result,
+        );
+
+        assert!(!result.ok());
+        assert!(result.fatal_error());
+    });
+}
@@ -0,0 +1,503 @@
+//! Tracks BABE epochs and their randomness on chain so that a Tuxedo runtime can offer BABE as an
+//! alternative to Aura.
+//!
+//! BABE divides time into epochs of a fixed number of slots. Each epoch has its own authorities and
+//! randomness, and the runtime must announce them one epoch in advance. This piece keeps the current
+//! and next epoch, along with the randomness that accumulates during the current epoch, in an
+//! [`EpochState`] UTXO.
+//!
+//! Once per block, the block author includes an [`AdvanceEpoch`] inherent that consumes the previous
+//! state and creates the next one. The transition is fully determined by the slot and VRF output in
+//! the block's BABE pre-runtime digest, so the constraint checker simply recomputes it. In the first
+//! block, and in the first block of every later epoch, the checker deposits a log that announces the
+//! next epoch to the BABE client, like FRAME's pallet babe does.
+//!
+//! Like the Aura authorities piece, the checker also writes the state to a well-known storage key as a
+//! side effect, so that the runtime's `BabeApi` can read it through [`epoch_state`].
+//!
+//! The randomness of an epoch is the hash of the VRF signatures of the blocks two epochs earlier.
+//! This is a simplification of pallet babe, which derives the randomness from the VRF outputs.
+//!
+//! ## Hack Warning
+//!
+//! Like the timestamp piece, this piece abuses the UpForGrabs verifier to protect the epoch state,
+//! and relies on the constraint checker to make sure that only the inherent can replace it.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use core::marker::PhantomData;
+
+use parity_scale_codec::{Decode, Encode};
+use scale_info::TypeInfo;
+use serde::{Deserialize, Serialize};
+use sp_consensus_babe::{
+    digests::{NextEpochDescriptor, PreDigest},
+    AllowedSlots, AuthorityId, BabeAuthorityWeight, BabeConfiguration, BabeEpochConfiguration,
+    ConsensusLog, Epoch, Randomness, Slot, BABE_ENGINE_ID,
+};
+use sp_core::{sr25519, H256};
+use sp_inherents::{CheckInherentsResult, InherentData};
+use sp_runtime::{
+    traits::{BlakeTwo256, Hash},
+    transaction_validity::TransactionPriority,
+    Digest, DigestItem,
+};
+use sp_std::{vec, vec::Vec};
+use tuxedo_core::{
+    digest,
+    dynamic_typing::UtxoData,
+    ensure,
+    inherents::{TuxedoInherent, TuxedoInherentAdapter},
+    support_macros::{CloneNoBound, DebugNoBound, DefaultNoBound},
+    types::{Input, InputMode, Output, OutputRef, Transaction, TransactionVersion},
+    verifier::UpForGrabs,
+    CheckerContext, ConstraintChecker, Verifier,
+};
+
+#[cfg(test)]
+mod tests;
+
+/// The storage key that holds the epoch state as of the latest block.
+const EPOCH_STATE_KEY: &[u8] = b"babe_epoch_state";
+
+/// The probability that a slot has a primary author, as a fraction.
+pub const PRIMARY_PROBABILITY: (u64, u64) = (1, 4);
+
+/// Configuration items for the BABE epochs piece when it is
+/// instantiated in a concrete runtime.
+pub trait BabeEpochsConfig {
+    /// A means of getting the digest of the current block.
+    /// Probably this will be the Tuxedo Executive
+    fn block_digest() -> Digest;
+
+    /// The sr25519 public keys of the authorities for the epochs that are announced from now on.
+    /// Probably these are the on-chain authorities from the Aura authorities piece, because
+    /// BABE uses the same kind of keys.
+    fn authorities() -> Vec<H256>;
+
+    /// The number of slots in each epoch. BABE does not allow this to change once the chain has started.
+    ///
+    /// Default is 200, which is ten minutes with three second slots.
+    const EPOCH_LENGTH: u64 = 200;
+}
+
+/// The authorities and randomness of a single BABE epoch.
+#[derive(
+    Serialize,
+    Deserialize,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Clone,
+    Encode,
+    Decode,
+    Hash,
+    Debug,
+    Default,
+    TypeInfo,
+)]
+pub struct EpochInfo {
+    /// The index of the epoch, counting from zero at the chain's first block.
+    pub index: u64,
+    /// The first slot of the epoch.
+    pub start_slot: u64,
+    /// The sr25519 public keys of the authorities. They all have the same weight.
+    pub authorities: Vec<H256>,
+    /// The randomness that the authorities use to claim slots.
+    pub randomness: Randomness,
+}
+
+impl EpochInfo {
+    /// The epoch in the form that BABE expects.
+    pub fn babe_epoch(&self, duration: u64) -> Epoch {
+        Epoch {
+            epoch_index: self.index,
+            start_slot: self.start_slot.into(),
+            duration,
+            authorities: babe_authorities(&self.authorities),
+            randomness: self.randomness,
+            config: epoch_configuration(),
+        }
+    }
+}
+
+/// The BABE epoch state as of some block.
+///
+/// The genesis state is the default one, whose next epoch has index zero. The first block
+/// replaces it with epoch zero, starting at that block's slot.
+#[derive(
+    Serialize,
+    Deserialize,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Clone,
+    Encode,
+    Decode,
+    Hash,
+    Debug,
+    Default,
+    TypeInfo,
+)]
+pub struct EpochState {
+    /// The epoch that the block belongs to.
+    pub current: EpochInfo,
+    /// The epoch after the current one, which has already been announced.
+    pub next: EpochInfo,
+    /// The randomness accumulated so far in the current epoch. It seeds the
+    /// randomness of the epoch after next.
+    pub accumulator: Randomness,
+}
+
+impl UtxoData for EpochState {
+    const TYPE_ID: [u8; 4] = *b"babe";
+}
+
+impl EpochState {
+    /// Whether the chain has produced its first block, which starts epoch zero.
+    pub fn is_started(&self) -> bool {
+        self.next.index > 0
+    }
+
+    /// The state in the first block, which starts epoch zero at the given slot.
+    fn first<T: BabeEpochsConfig>(slot: u64) -> Self {
+        let authorities = T::authorities();
+        Self {
+            current: EpochInfo {
+                index: 0,
+                start_slot: slot,
+                authorities: authorities.clone(),
+                randomness: Randomness::default(),
+            },
+            next: EpochInfo {
+                index: 1,
+                start_slot: slot.saturating_add(T::EPOCH_LENGTH),
+                authorities,
+                randomness: Randomness::default(),
+            },
+            accumulator: Randomness::default(),
+        }
+    }
+
+    /// The state in a block at the given slot, when this is the state in its parent.
+    ///
+    /// If the slot is in a later epoch, the announced next epoch becomes the current one, and the
+    /// epoch after it is announced with the authorities from the config and the accumulated
+    /// randomness. Should whole epochs pass without blocks, the announced epoch is carried over
+    /// to the later index, like the BABE client does.
+    fn advanced_to<T: BabeEpochsConfig>(&self, slot: u64) -> Self {
+        let elapsed = slot
+            .saturating_sub(self.current.start_slot)
+            .checked_div(T::EPOCH_LENGTH)
+            .unwrap_or_default();
+        if elapsed == 0 {
+            return self.clone();
+        }
+
+        let index = self.current.index + elapsed;
+        let start_slot = self.current.start_slot + elapsed * T::EPOCH_LENGTH;
+        Self {
+            current: EpochInfo {
+                index,
+                start_slot,
+                ..self.next.clone()
+            },
+            next: EpochInfo {
+                index: index + 1,
+                start_slot: start_slot + T::EPOCH_LENGTH,
+                authorities: T::authorities(),
+                randomness: BlakeTwo256::hash_of(&(self.accumulator, index + 1)).0,
+            },
+            accumulator: Randomness::default(),
+        }
+    }
+}
+
+/// The BABE pre-runtime digest of the current block, if it has one.
+fn pre_digest<T: BabeEpochsConfig>() -> Option<PreDigest> {
+    T::block_digest().convert_first(|item| item.pre_runtime_try_to::<PreDigest>(&BABE_ENGINE_ID))
+}
+
+/// The epoch state in a block with the given pre-runtime digest, when the
+/// given state is the one in its parent.
+///
+/// Blocks without a BABE pre-runtime digest leave the state unchanged.
+pub fn next_state<T: BabeEpochsConfig>(
+    previous: &EpochState,
+    pre_digest: Option<&PreDigest>,
+) -> EpochState {
+    let Some(pre_digest) = pre_digest else {
+        return previous.clone();
+    };
+
+    let slot = *pre_digest.slot();
+    let mut state = if previous.is_started() {
+        previous.advanced_to::<T>(slot)
+    } else {
+        EpochState::first::<T>(slot)
+    };
+
+    if let Some(vrf_signature) = pre_digest.vrf_signature() {
+        state.accumulator = BlakeTwo256::hash_of(&(state.accumulator, vrf_signature)).0;
+    }
+
+    state
+}
+
+/// The epoch state as of the latest block that advanced it, if any.
+fn stored_state() -> Option<EpochState> {
+    sp_io::storage::get(EPOCH_STATE_KEY).and_then(|d| EpochState::decode(&mut &*d).ok())
+}
+
+/// The current epoch state. Before the first block, this is the state that the first
+/// block would start at slot zero.
+pub fn epoch_state<T: BabeEpochsConfig>() -> EpochState {
+    stored_state()
+        .filter(EpochState::is_started)
+        .unwrap_or_else(|| EpochState::first::<T>(0))
+}
+
+/// The given sr25519 public keys as equally weighted BABE authorities.
+fn babe_authorities(keys: &[H256]) -> Vec<(AuthorityId, BabeAuthorityWeight)> {
+    keys.iter()
+        .map(|key| (AuthorityId::from(sr25519::Public::from_raw(key.0)), 1))
+        .collect()
+}
+
+/// The parameters that every epoch uses.
+pub fn epoch_configuration() -> BabeEpochConfiguration {
+    BabeEpochConfiguration {
+        c: PRIMARY_PROBABILITY,
+        allowed_slots: AllowedSlots::PrimaryAndSecondaryPlainSlots,
+    }
+}
+
+/// The configuration that the BABE client starts the chain with.
+pub fn configuration<T: BabeEpochsConfig>(slot_duration: u64) -> BabeConfiguration {
+    let first = epoch_state::<T>().current;
+    let epoch_configuration = epoch_configuration();
+    BabeConfiguration {
+        slot_duration,
+        epoch_length: T::EPOCH_LENGTH,
+        c: epoch_configuration.c,
+        authorities: babe_authorities(&first.authorities),
+        randomness: first.randomness,
+        allowed_slots: epoch_configuration.allowed_slots,
+    }
+}
+
+/// The first slot of the current epoch.
+pub fn current_epoch_start<T: BabeEpochsConfig>() -> Slot {
+    epoch_state::<T>().current.start_slot.into()
+}
+
+/// The current epoch in the form that BABE expects.
+pub fn current_epoch<T: BabeEpochsConfig>() -> Epoch {
+    epoch_state::<T>().current.babe_epoch(T::EPOCH_LENGTH)
+}
+
+/// The next epoch in the form that BABE expects.
+pub fn next_epoch<T: BabeEpochsConfig>() -> Epoch {
+    epoch_state::<T>().next.babe_epoch(T::EPOCH_LENGTH)
+}
+
+/// The BABE authority who authored the current block, according to the
+/// authority index in the block's pre-runtime digest.
+pub fn block_author<T: BabeEpochsConfig>() -> Option<H256> {
+    let pre_digest = pre_digest::<T>()?;
+    let state = stored_state().filter(EpochState::is_started);
+
+    // The stored state may be the parent's or this block's, depending on whether the epoch has
+    // advanced in this block already. Either way, the slot tells which epoch the author belongs to.
+    let authorities = match state {
+        None => T::authorities(),
+        Some(state) if *pre_digest.slot() >= state.next.start_slot => state.next.authorities,
+        Some(state) => state.current.authorities,
+    };
+    authorities
+        .get(pre_digest.authority_index() as usize)
+        .copied()
+}
+
+/// Errors that can occur when advancing the epoch state.
+#[derive(
+    Serialize,
+    Deserialize,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Clone,
+    Encode,
+    Decode,
+    Hash,
+    Debug,
+    TypeInfo,
+)]
+pub enum ConstraintCheckerError {
+    /// An input data has the wrong type.
+    BadlyTypedInput,
+    /// An output data has the wrong type.
+    BadlyTypedOutput,
+    /// Wrong number of inputs were provided to the constraint checker.
+    WrongNumberInputs,
+    /// Wrong number of outputs were provided to the constraint checker.
+    WrongNumberOutputs,
+    /// The new epoch state is not protected by the `UpForGrabs` verifier, so it could not be advanced again.
+    StateNotUpForGrabs,
+    /// The new epoch state does not follow from the previous one and the block's pre-runtime digest.
+    WrongState,
+}
+
+/// A constraint checker that advances the BABE epoch state.
+///
+/// This is expected to be performed through an inherent, and to happen exactly once per block.
+///
+/// The only input is the epoch state from the previous block. The only output is the
+/// epoch state for this block, which must be up for grabs.
+#[derive(
+    Serialize,
+    Deserialize,
+    Encode,
+    Decode,
+    DebugNoBound,
+    DefaultNoBound,
+    PartialEq,
+    Eq,
+    CloneNoBound,
+    TypeInfo,
+)]
+#[scale_info(skip_type_params(T))]
+pub struct AdvanceEpoch<T>(PhantomData<T>);
+
+impl<T: BabeEpochsConfig + 'static, V: Verifier + PartialEq + From<UpForGrabs>> ConstraintChecker<V>
+    for AdvanceEpoch<T>
+{
+    type Error = ConstraintCheckerError;
+    type InherentHooks = TuxedoInherentAdapter<Self>;
+
+    fn check(
+        &self,
+        input_data: &[Output<V>],
+        _peek_data: &[Output<V>],
+        output_data: &[Output<V>],
+        _context: &CheckerContext,
+    ) -> Result<TransactionPriority, Self::Error> {
+        ensure!(
+            input_data.len() == 1,
+            ConstraintCheckerError::WrongNumberInputs
+        );
+        let previous = input_data[0]
+            .payload
+            .extract::<EpochState>()
+            .map_err(|_| ConstraintCheckerError::BadlyTypedInput)?;
+
+        ensure!(
+            output_data.len() == 1,
+            ConstraintCheckerError::WrongNumberOutputs
+        );
+        let new_state = output_data[0]
+            .payload
+            .extract::<EpochState>()
+            .map_err(|_| ConstraintCheckerError::BadlyTypedOutput)?;
+        ensure!(
+            output_data[0].verifier == UpForGrabs.into(),
+            ConstraintCheckerError::StateNotUpForGrabs
+        );
+
+        ensure!(
+            new_state == next_state::<T>(&previous, pre_digest::<T>().as_ref()),
+            ConstraintCheckerError::WrongState
+        );
+
+        // SIDE EFFECT: Write the new state to storage so that the runtime API can find it
+        sp_io::storage::set(EPOCH_STATE_KEY, &new_state.encode());
+
+        // SIDE EFFECT: Announce the next epoch to the BABE client in the block's digest
+        if new_state.next.index != previous.next.index {
+            let announcement = ConsensusLog::NextEpochData(NextEpochDescriptor {
+                authorities: babe_authorities(&new_state.next.authorities),
+                randomness: new_state.next.randomness,
+            });
+            digest::deposit_log(DigestItem::Consensus(BABE_ENGINE_ID, announcement.encode()));
+        }
+
+        Ok(0)
+    }
+
+    fn is_inherent(&self) -> bool {
+        true
+    }
+}
+
+impl<V: Verifier + PartialEq + From<UpForGrabs>, T: BabeEpochsConfig + 'static>
+    TuxedoInherent<V, Self> for AdvanceEpoch<T>
+{
+    type Error = sp_inherents::MakeFatalError<()>;
+    const INHERENT_IDENTIFIER: sp_inherents::InherentIdentifier = *b"babe_epc";
+
+    fn create_inherent(
+        _authoring_inherent_data: &InherentData,
+        (previous_inherent, previous_id): (Transaction<V, Self>, H256),
+    ) -> Transaction<V, Self> {
+        let previous = previous_inherent.outputs[0]
+            .payload
+            .extract::<EpochState>()
+            .expect(
+                "The previous epoch inherent should have an output that decodes as an EpochState.",
+            );
+        let new_state = next_state::<T>(&previous, pre_digest::<T>().as_ref());
+
+        // There is always 1 output, so we know right where to find the previous state.
+        let input = Input {
+            output_ref: OutputRef {
+                tx_hash: previous_id,
+                index: 0,
+            },
+            redeemer: Vec::new(),
+            mode: InputMode::Consume,
+        };
+
+        Transaction {
+            version: TransactionVersion::LATEST,
+            inputs: vec![input],
+            peeks: Vec::new(),
+            outputs: vec![Output {
+                payload: new_state.into(),
+                verifier: UpForGrabs.into(),
+            }],
+            checker: Self::default(),
+            memo: None,
+            valid_until: None,
+        }
+    }
+
+    fn check_inherent(
+        _importing_inherent_data: &InherentData,
+        _inherent: Transaction<V, Self>,
+        _result: &mut CheckInherentsResult,
+    ) {
+        // The new state only depends on the block's pre-runtime digest, which is
+        // only known once the block is executing, so the constraint checker does all the checks.
+    }
+
+    #[cfg(feature = "std")]
+    fn genesis_transactions() -> Vec<Transaction<V, Self>> {
+        // The inherent must appear in every block, including genesis, so that
+        // block authors always find a previous state to advance.
+        vec![Transaction {
+            version: TransactionVersion::LATEST,
+            inputs: Vec::new(),
+            peeks: Vec::new(),
+            outputs: vec![Output {
+                payload: EpochState::default().into(),
+                verifier: UpForGrabs.into(),
+            }],
+            checker: Self::default(),
+            memo: None,
+            valid_until: None,
+        }]
+    }
+}
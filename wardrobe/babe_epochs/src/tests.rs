@@ -0,0 +1,253 @@
+//! Unit tests for the BABE Epochs piece
+
+use super::*;
+use sp_consensus_babe::digests::SecondaryPlainPreDigest;
+use sp_io::TestExternalities;
+use std::cell::RefCell;
+use tuxedo_core::{tuxedo_verifier, verifier::SigCheck};
+
+/// A verifier that can represent the epoch state and ordinary users.
+#[derive(Serialize, Deserialize, Encode, Decode, Debug, PartialEq, Eq, Clone, TypeInfo)]
+#[tuxedo_verifier]
+pub enum TestVerifier {
+    SigCheck(SigCheck),
+    UpForGrabs(UpForGrabs),
+}
+
+thread_local! {
+    static DIGEST: RefCell<Digest> = RefCell::new(Digest::default());
+    static AUTHORITIES: RefCell<Vec<H256>> = RefCell::new(vec![alice()]);
+}
+
+/// The mock config reads the block digest and the authorities from thread locals.
+/// Alice is the only authority unless a test says otherwise. Epochs are ten slots long.
+pub struct TestConfig;
+
+impl BabeEpochsConfig for TestConfig {
+    fn block_digest() -> Digest {
+        DIGEST.with(|d| d.borrow().clone())
+    }
+
+    fn authorities() -> Vec<H256> {
+        AUTHORITIES.with(|a| a.borrow().clone())
+    }
+
+    const EPOCH_LENGTH: u64 = 10;
+}
+
+fn alice() -> H256 {
+    H256::repeat_byte(1)
+}
+
+fn bob() -> H256 {
+    H256::repeat_byte(2)
+}
+
+fn pre_digest(authority_index: u32, slot: u64) -> PreDigest {
+    PreDigest::SecondaryPlain(SecondaryPlainPreDigest {
+        authority_index,
+        slot: slot.into(),
+    })
+}
+
+/// Make the current block one that the given authority authored in the given slot.
+fn set_pre_digest(authority_index: u32, slot: u64) {
+    let item = DigestItem::PreRuntime(BABE_ENGINE_ID, pre_digest(authority_index, slot).encode());
+    DIGEST.with(|d| *d.borrow_mut() = Digest { logs: vec![item] });
+}
+
+fn set_authorities(authorities: Vec<H256>) {
+    AUTHORITIES.with(|a| *a.borrow_mut() = authorities);
+}
+
+fn state_output(state: EpochState) -> Output<TestVerifier> {
+    (state, UpForGrabs.into()).into()
+}
+
+fn advance(
+    previous: EpochState,
+    new: EpochState,
+) -> Result<TransactionPriority, ConstraintCheckerError> {
+    AdvanceEpoch::<TestConfig>::default().check(
+        &[state_output(previous)],
+        &[],
+        &[state_output(new)],
+        &Default::default(),
+    )
+}
+
+/// The log that announces the given epoch.
+fn announcement(epoch: &EpochInfo) -> DigestItem {
+    let log = ConsensusLog::NextEpochData(NextEpochDescriptor {
+        authorities: babe_authorities(&epoch.authorities),
+        randomness: epoch.randomness,
+    });
+    DigestItem::Consensus(BABE_ENGINE_ID, log.encode())
+}
+
+#[test]
+fn first_block_starts_epoch_zero() {
+    let state = next_state::<TestConfig>(&EpochState::default(), Some(&pre_digest(0, 100)));
+
+    assert_eq!(state.current.index, 0);
+    assert_eq!(state.current.start_slot, 100);
+    assert_eq!(state.current.authorities, vec![alice()]);
+    assert_eq!(state.next.index, 1);
+    assert_eq!(state.next.start_slot, 110);
+}
+
+#[test]
+fn block_within_epoch_keeps_state() {
+    let first = next_state::<TestConfig>(&EpochState::default(), Some(&pre_digest(0, 100)));
+
+    assert_eq!(
+        next_state::<TestConfig>(&first, Some(&pre_digest(0, 109))),
+        first
+    );
+}
+
+#[test]
+fn block_without_pre_digest_keeps_state() {
+    let first = next_state::<TestConfig>(&EpochState::default(), Some(&pre_digest(0, 100)));
+
+    assert_eq!(next_state::<TestConfig>(&first, None), first);
+    assert_eq!(
+        next_state::<TestConfig>(&EpochState::default(), None),
+        EpochState::default()
+    );
+}
+
+#[test]
+fn block_in_next_epoch_rotates_and_announces_config_authorities() {
+    let first = next_state::<TestConfig>(&EpochState::default(), Some(&pre_digest(0, 100)));
+    set_authorities(vec![bob()]);
+
+    let second = next_state::<TestConfig>(&first, Some(&pre_digest(0, 112)));
+
+    assert_eq!(second.current.index, 1);
+    assert_eq!(second.current.start_slot, 110);
+    assert_eq!(second.current.authorities, vec![alice()]);
+    assert_eq!(second.next.index, 2);
+    assert_eq!(second.next.start_slot, 120);
+    assert_eq!(second.next.authorities, vec![bob()]);
+    assert_eq!(
+        second.next.randomness,
+        BlakeTwo256::hash_of(&(first.accumulator, 2u64)).0
+    );
+}
+
+#[test]
+fn skipped_epochs_carry_announced_epoch_over() {
+    let first = next_state::<TestConfig>(&EpochState::default(), Some(&pre_digest(0, 100)));
+
+    let later = next_state::<TestConfig>(&first, Some(&pre_digest(0, 135)));
+
+    assert_eq!(later.current.index, 3);
+    assert_eq!(later.current.start_slot, 130);
+    assert_eq!(later.current.authorities, first.next.authorities);
+    assert_eq!(later.next.index, 4);
+    assert_eq!(later.next.start_slot, 140);
+}
+
+#[test]
+fn first_block_announces_next_epoch() {
+    TestExternalities::default().execute_with(|| {
+        set_pre_digest(0, 100);
+        let first = next_state::<TestConfig>(&EpochState::default(), Some(&pre_digest(0, 100)));
+
+        assert_eq!(advance(EpochState::default(), first.clone()), Ok(0));
+        assert_eq!(epoch_state::<TestConfig>(), first);
+        assert_eq!(digest::deposited_logs(), vec![announcement(&first.next)]);
+    });
+}
+
+#[test]
+fn block_within_epoch_announces_nothing() {
+    TestExternalities::default().execute_with(|| {
+        let first = next_state::<TestConfig>(&EpochState::default(), Some(&pre_digest(0, 100)));
+        set_pre_digest(0, 105);
+
+        assert_eq!(advance(first.clone(), first), Ok(0));
+        assert!(digest::deposited_logs().is_empty());
+    });
+}
+
+#[test]
+fn advancing_to_wrong_state_fails() {
+    TestExternalities::default().execute_with(|| {
+        let first = next_state::<TestConfig>(&EpochState::default(), Some(&pre_digest(0, 100)));
+        set_pre_digest(0, 112);
+
+        assert_eq!(
+            advance(first.clone(), first),
+            Err(ConstraintCheckerError::WrongState)
+        );
+        assert!(digest::deposited_logs().is_empty());
+    });
+}
+
+#[test]
+fn advancing_to_owned_state_fails() {
+    TestExternalities::default().execute_with(|| {
+        set_pre_digest(0, 100);
+        let first = next_state::<TestConfig>(&EpochState::default(), Some(&pre_digest(0, 100)));
+        let owned = (first, SigCheck::new(alice()).into()).into();
+
+        assert_eq!(
+            AdvanceEpoch::<TestConfig>::default().check(
+                &[state_output(EpochState::default())],
+                &[],
+                &[owned],
+                &Default::default(),
+            ),
+            Err(ConstraintCheckerError::StateNotUpForGrabs)
+        );
+    });
+}
+
+#[test]
+fn advancing_without_previous_state_fails() {
+    TestExternalities::default().execute_with(|| {
+        assert_eq!(
+            AdvanceEpoch::<TestConfig>::default().check(
+                &[],
+                &[],
+                &[state_output(EpochState::default())],
+                &Default::default(),
+            ),
+            Err(ConstraintCheckerError::WrongNumberInputs)
+        );
+    });
+}
+
+#[test]
+fn block_author_comes_from_epoch_of_slot() {
+    TestExternalities::default().execute_with(|| {
+        set_authorities(vec![alice(), bob()]);
+        set_pre_digest(1, 100);
+        assert_eq!(block_author::<TestConfig>(), Some(bob()));
+
+        let first = next_state::<TestConfig>(&EpochState::default(), Some(&pre_digest(1, 100)));
+        advance(EpochState::default(), first).unwrap();
+        set_authorities(vec![alice()]);
+
+        // The stored state is still the parent's, but the slot is in the next epoch.
+        set_pre_digest(1, 110);
+        assert_eq!(block_author::<TestConfig>(), Some(bob()));
+
+        set_pre_digest(2, 110);
+        assert_eq!(block_author::<TestConfig>(), None);
+    });
+}
+
+#[test]
+fn api_reports_first_epoch_before_first_block() {
+    TestExternalities::default().execute_with(|| {
+        let config = configuration::<TestConfig>(3000);
+
+        assert_eq!(config.epoch_length, 10);
+        assert_eq!(config.authorities, babe_authorities(&[alice()]));
+        assert_eq!(current_epoch::<TestConfig>().epoch_index, 0);
+        assert_eq!(next_epoch::<TestConfig>().epoch_index, 1);
+    });
+}
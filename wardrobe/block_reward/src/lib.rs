@@ -0,0 +1,283 @@
+//! Issues new coins as a reward to the author of each block.
+//!
+//! Once per block, the block author includes a `MintBlockReward` inherent that mints a [`Coin`]
+//! to themselves. The reward follows a protocol-defined schedule: it starts at a configured
+//! initial value, and every decay period it shrinks to a configured share of its previous value.
+//! With the default configuration, the reward halves roughly once a year, so the total supply
+//! approaches a fixed cap. When the money piece tracks issuance, each reward is added to the total.
+//!
+//! Unlike the author's share of fees, the reward does not depend on the contents of the block,
+//! so it is paid in the same block it rewards.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use core::marker::PhantomData;
+
+use money::{Coin, MoneyConfig};
+use parity_scale_codec::{Decode, Encode};
+use scale_info::TypeInfo;
+use serde::{Deserialize, Serialize};
+use sp_core::H256;
+use sp_inherents::{CheckInherentsResult, InherentData, MakeFatalError};
+use sp_runtime::{transaction_validity::TransactionPriority, PerThing, Percent};
+use sp_std::{vec, vec::Vec};
+use tuxedo_core::{
+    ensure,
+    inherents::{TuxedoInherent, TuxedoInherentAdapter},
+    support_macros::{CloneNoBound, DebugNoBound, DefaultNoBound},
+    types::{Output, Transaction, TransactionVersion},
+    verifier::SigCheck,
+    CheckerContext, ConstraintChecker, Verifier,
+};
+
+#[cfg(test)]
+mod tests;
+
+/// Configuration items for the block reward piece when it is
+/// instantiated in a concrete runtime.
+pub trait BlockRewardConfig: MoneyConfig {
+    /// A means of getting the current block height.
+    /// Probably this will be the Tuxedo Executive
+    fn block_height() -> u32;
+
+    /// A means of getting the public key of the current block's author, if there is one.
+    /// Probably this will look up the slot's authority from the Aura pre-runtime digest.
+    fn block_author() -> Option<H256>;
+
+    /// The reward for each block in the first decay period.
+    ///
+    /// Default is 50.
+    const INITIAL_REWARD: u128 = 50;
+
+    /// The number of blocks after which the reward decays.
+    ///
+    /// Default is 10_512_000 which is about a year with three second blocks.
+    const DECAY_PERIOD: u32 = 10_512_000;
+
+    /// The share of the reward that remains after each decay period.
+    ///
+    /// Default is 50 percent, which halves the reward every period.
+    const RETAINED_SHARE: Percent = Percent::from_percent(50);
+}
+
+/// The reward for authoring the block at the given height.
+///
+/// Rewards that are too small to be valid coins under the money piece's configuration
+/// are zero. Once the reward reaches zero, it stays there.
+pub fn reward_at<T: BlockRewardConfig>(height: u32) -> u128 {
+    let periods = height.checked_div(T::DECAY_PERIOD).unwrap_or_default();
+
+    let mut reward = T::INITIAL_REWARD;
+    for _ in 0..periods {
+        if reward == 0 {
+            break;
+        }
+        // Round down so the reward is guaranteed to reach zero eventually.
+        reward = T::RETAINED_SHARE.mul_floor(reward);
+    }
+
+    if reward < T::MINIMUM_COIN_VALUE {
+        0
+    } else {
+        reward
+    }
+}
+
+/// Errors that can occur when checking block rewards.
+#[derive(
+    Serialize,
+    Deserialize,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Clone,
+    Encode,
+    Decode,
+    Hash,
+    Debug,
+    TypeInfo,
+)]
+pub enum ConstraintCheckerError {
+    /// Inputs were provided to the constraint checker. Rewards are created from the void.
+    WrongNumberInputs,
+    /// The wrong number of outputs was provided to the constraint checker.
+    WrongNumberOutputs,
+    /// The block height noted in the inherent does not match the block into which it was inserted.
+    WrongHeight,
+    /// The reward is not a coin.
+    BadlyTypedOutput,
+    /// The reward is worth a different amount than the schedule allows.
+    WrongRewardAmount,
+    /// The reward is not locked to the block's author.
+    WrongRecipient,
+    /// The reward could not be counted towards the money piece's total issuance.
+    Issuance(money::ConstraintCheckerError),
+}
+
+/// A constraint checker that mints the block reward to the block's author.
+///
+/// This is expected to be performed through an inherent, and to happen exactly once per block.
+///
+/// There are no inputs. If the block has an author and the reward is not zero, the only output is
+/// a coin worth the reward, locked to the author. Otherwise there are no outputs. The block height
+/// is part of the checker so that two rewards of the same amount to the same author do not have
+/// the same output references.
+#[derive(
+    Serialize,
+    Deserialize,
+    Encode,
+    Decode,
+    DebugNoBound,
+    DefaultNoBound,
+    PartialEq,
+    Eq,
+    CloneNoBound,
+    TypeInfo,
+)]
+#[scale_info(skip_type_params(T))]
+pub struct MintBlockReward<T, const ID: u8> {
+    /// The block whose author is rewarded.
+    pub block: u32,
+    _phantom: PhantomData<T>,
+}
+
+impl<T, const ID: u8> MintBlockReward<T, ID> {
+    pub fn new(block: u32) -> Self {
+        Self {
+            block,
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<T: BlockRewardConfig + 'static, V: Verifier + PartialEq + From<SigCheck>, const ID: u8>
+    ConstraintChecker<V> for MintBlockReward<T, ID>
+{
+    type Error = ConstraintCheckerError;
+    type InherentHooks = TuxedoInherentAdapter<Self>;
+
+    fn check(
+        &self,
+        input_data: &[Output<V>],
+        _peek_data: &[Output<V>],
+        output_data: &[Output<V>],
+        _context: &CheckerContext,
+    ) -> Result<TransactionPriority, Self::Error> {
+        ensure!(
+            input_data.is_empty(),
+            ConstraintCheckerError::WrongNumberInputs
+        );
+        ensure!(
+            self.block == T::block_height(),
+            ConstraintCheckerError::WrongHeight
+        );
+
+        let reward = reward_at::<T>(self.block);
+        match T::block_author().filter(|_| reward > 0) {
+            None => ensure!(
+                output_data.is_empty(),
+                ConstraintCheckerError::WrongNumberOutputs
+            ),
+            Some(author) => {
+                ensure!(
+                    output_data.len() == 1,
+                    ConstraintCheckerError::WrongNumberOutputs
+                );
+                let minted = output_data[0]
+                    .payload
+                    .extract::<Coin<ID>>()
+                    .map_err(|_| ConstraintCheckerError::BadlyTypedOutput)?;
+                ensure!(
+                    minted.0 == reward,
+                    ConstraintCheckerError::WrongRewardAmount
+                );
+                ensure!(
+                    output_data[0].verifier == SigCheck::new(author).into(),
+                    ConstraintCheckerError::WrongRecipient
+                );
+
+                // SIDE EFFECT: The reward is new money, so count it towards the total issuance.
+                money::note_minted::<ID, T>(reward).map_err(ConstraintCheckerError::Issuance)?;
+            }
+        }
+
+        Ok(0)
+    }
+
+    fn is_inherent(&self) -> bool {
+        true
+    }
+}
+
+impl<V: Verifier + PartialEq + From<SigCheck>, T: BlockRewardConfig + 'static, const ID: u8>
+    TuxedoInherent<V, Self> for MintBlockReward<T, ID>
+{
+    type Error = MakeFatalError<ConstraintCheckerError>;
+    const INHERENT_IDENTIFIER: sp_inherents::InherentIdentifier = *b"blk_rwrd";
+
+    fn create_inherent(
+        _authoring_inherent_data: &InherentData,
+        _previous_inherent: (Transaction<V, Self>, H256),
+    ) -> Transaction<V, Self> {
+        let block = T::block_height();
+        let reward = reward_at::<T>(block);
+        let outputs = T::block_author()
+            .filter(|_| reward > 0)
+            .map(|author| Output {
+                payload: Coin::<ID>(reward).into(),
+                verifier: SigCheck::new(author).into(),
+            })
+            .into_iter()
+            .collect();
+
+        Transaction {
+            version: TransactionVersion::LATEST,
+            inputs: Vec::new(),
+            peeks: Vec::new(),
+            outputs,
+            checker: Self::new(block),
+            memo: None,
+            valid_until: None,
+        }
+    }
+
+    fn check_inherent(
+        _importing_inherent_data: &InherentData,
+        inherent: Transaction<V, Self>,
+        result: &mut CheckInherentsResult,
+    ) {
+        // The author is only known once the block is executing, so the constraint checker
+        // makes that check. But we can make sure the amount follows the schedule already.
+        let reward = reward_at::<T>(inherent.checker.block);
+        let valid = inherent
+            .outputs
+            .iter()
+            .all(|output| output.payload.extract::<Coin<ID>>() == Ok(Coin(reward)));
+
+        if !valid {
+            result
+                .put_error(
+                    Self::INHERENT_IDENTIFIER,
+                    &MakeFatalError::from(ConstraintCheckerError::WrongRewardAmount),
+                )
+                .expect("Should be able to put an error.");
+        }
+    }
+
+    #[cfg(feature = "std")]
+    fn genesis_transactions() -> Vec<Transaction<V, Self>> {
+        // The inherent must appear in every block, including genesis, so that
+        // block authors always find a previous inherent to build on.
+        // Nobody authored the genesis block, so nobody is rewarded.
+        vec![Transaction {
+            version: TransactionVersion::LATEST,
+            inputs: Vec::new(),
+            peeks: Vec::new(),
+            outputs: Vec::new(),
+            checker: Self::default(),
+            memo: None,
+            valid_until: None,
+        }]
+    }
+}
@@ -0,0 +1,271 @@
+//! Unit tests for the Block Reward piece
+
+use super::*;
+use tuxedo_core::{tuxedo_verifier, verifier::UpForGrabs};
+
+/// A verifier that can represent block authors and the void.
+#[derive(Serialize, Deserialize, Encode, Decode, Debug, PartialEq, Eq, Clone, TypeInfo)]
+#[tuxedo_verifier]
+pub enum TestVerifier {
+    SigCheck(SigCheck),
+    UpForGrabs(UpForGrabs),
+}
+
+/// The mock config always says the block number is seven, and that it was authored by Alice.
+/// The reward starts at 100 and halves every ten blocks. Coins must be worth at least ten.
+pub struct TestConfig;
+
+impl MoneyConfig for TestConfig {
+    const MINIMUM_COIN_VALUE: u128 = 10;
+}
+
+impl BlockRewardConfig for TestConfig {
+    fn block_height() -> u32 {
+        7
+    }
+
+    fn block_author() -> Option<H256> {
+        Some(alice())
+    }
+
+    const INITIAL_REWARD: u128 = 100;
+    const DECAY_PERIOD: u32 = 10;
+}
+
+/// Like the test config, but nobody authored the block.
+pub struct NoAuthor;
+
+impl MoneyConfig for NoAuthor {}
+
+impl BlockRewardConfig for NoAuthor {
+    fn block_height() -> u32 {
+        7
+    }
+
+    fn block_author() -> Option<H256> {
+        None
+    }
+}
+
+type Reward = MintBlockReward<TestConfig, 0>;
+
+fn alice() -> H256 {
+    H256::repeat_byte(1)
+}
+
+fn bob() -> H256 {
+    H256::repeat_byte(2)
+}
+
+fn coin(value: u128, owner: H256) -> Output<TestVerifier> {
+    (Coin::<0>(value), SigCheck::new(owner)).into()
+}
+
+fn mint(outputs: Vec<Output<TestVerifier>>) -> Result<TransactionPriority, ConstraintCheckerError> {
+    Reward::new(7).check(&[], &[], &outputs, &Default::default())
+}
+
+#[test]
+fn reward_decays_every_period() {
+    assert_eq!(reward_at::<TestConfig>(0), 100);
+    assert_eq!(reward_at::<TestConfig>(9), 100);
+    assert_eq!(reward_at::<TestConfig>(10), 50);
+    assert_eq!(reward_at::<TestConfig>(25), 25);
+    assert_eq!(reward_at::<TestConfig>(30), 12);
+}
+
+#[test]
+fn reward_below_minimum_is_zero() {
+    assert_eq!(reward_at::<TestConfig>(40), 0);
+    assert_eq!(reward_at::<TestConfig>(u32::MAX), 0);
+}
+
+#[test]
+fn default_schedule_halves_yearly() {
+    assert_eq!(reward_at::<NoAuthor>(0), 50);
+    assert_eq!(reward_at::<NoAuthor>(10_511_999), 50);
+    assert_eq!(reward_at::<NoAuthor>(10_512_000), 25);
+}
+
+#[test]
+fn mint_reward_works() {
+    assert_eq!(mint(vec![coin(100, alice())]), Ok(0));
+}
+
+#[test]
+fn mint_wrong_amount_fails() {
+    assert_eq!(
+        mint(vec![coin(101, alice())]),
+        Err(ConstraintCheckerError::WrongRewardAmount)
+    );
+}
+
+#[test]
+fn mint_to_someone_else_fails() {
+    assert_eq!(
+        mint(vec![coin(100, bob())]),
+        Err(ConstraintCheckerError::WrongRecipient)
+    );
+}
+
+#[test]
+fn mint_nothing_fails() {
+    assert_eq!(
+        mint(vec![]),
+        Err(ConstraintCheckerError::WrongNumberOutputs)
+    );
+}
+
+#[test]
+fn mint_twice_fails() {
+    assert_eq!(
+        mint(vec![coin(100, alice()), coin(100, alice())]),
+        Err(ConstraintCheckerError::WrongNumberOutputs)
+    );
+}
+
+#[test]
+fn mint_badly_typed_fails() {
+    assert_eq!(
+        mint(vec![(Coin::<1>(100), SigCheck::new(alice())).into()]),
+        Err(ConstraintCheckerError::BadlyTypedOutput)
+    );
+}
+
+#[test]
+fn mint_with_inputs_fails() {
+    let outputs = vec![coin(100, alice())];
+
+    assert_eq!(
+        Reward::new(7).check(&[coin(5, alice())], &[], &outputs, &Default::default()),
+        Err(ConstraintCheckerError::WrongNumberInputs)
+    );
+}
+
+#[test]
+fn mint_at_wrong_height_fails() {
+    let outputs = vec![coin(100, alice())];
+
+    assert_eq!(
+        Reward::new(6).check(&[], &[], &outputs, &Default::default()),
+        Err(ConstraintCheckerError::WrongHeight)
+    );
+}
+
+#[test]
+fn no_reward_without_author() {
+    let checker = MintBlockReward::<NoAuthor, 0>::new(7);
+
+    assert_eq!(
+        ConstraintChecker::<TestVerifier>::check(&checker, &[], &[], &[], &Default::default()),
+        Ok(0)
+    );
+    assert_eq!(
+        checker.check(&[], &[], &[coin(50, alice())], &Default::default()),
+        Err(ConstraintCheckerError::WrongNumberOutputs)
+    );
+}
+
+/// Like the test config, but the money piece tracks issuance.
+pub struct Tracked;
+
+impl MoneyConfig for Tracked {
+    const MINIMUM_COIN_VALUE: u128 = 10;
+    const TRACK_ISSUANCE: bool = true;
+}
+
+impl BlockRewardConfig for Tracked {
+    fn block_height() -> u32 {
+        7
+    }
+
+    fn block_author() -> Option<H256> {
+        Some(alice())
+    }
+
+    const INITIAL_REWARD: u128 = 100;
+    const DECAY_PERIOD: u32 = 10;
+}
+
+#[test]
+fn tracked_reward_is_counted() {
+    sp_io::TestExternalities::default().execute_with(|| {
+        sp_io::storage::set(&[money::ISSUANCE_KEY, &[0]].concat(), &500u128.encode());
+        let checker = MintBlockReward::<Tracked, 0>::new(7);
+
+        assert_eq!(
+            checker.check(&[], &[], &[coin(100, alice())], &Default::default()),
+            Ok(0)
+        );
+        assert_eq!(money::total_issuance(0), Some(600));
+    })
+}
+
+#[test]
+fn tracked_reward_without_total_fails() {
+    sp_io::TestExternalities::default().execute_with(|| {
+        let checker = MintBlockReward::<Tracked, 0>::new(7);
+
+        assert_eq!(
+            checker.check(&[], &[], &[coin(100, alice())], &Default::default()),
+            Err(ConstraintCheckerError::Issuance(
+                money::ConstraintCheckerError::IssuanceMismatch
+            ))
+        );
+    })
+}
+
+fn previous_inherent() -> (Transaction<TestVerifier, Reward>, H256) {
+    let tx = Transaction {
+        version: TransactionVersion::LATEST,
+        inputs: Vec::new(),
+        peeks: Vec::new(),
+        outputs: vec![coin(100, alice())],
+        checker: Reward::new(6),
+        memo: None,
+        valid_until: None,
+    };
+    (tx, H256::zero())
+}
+
+#[test]
+fn created_inherent_is_valid() {
+    let tx = <Reward as TuxedoInherent<TestVerifier, _>>::create_inherent(
+        &InherentData::new(),
+        previous_inherent(),
+    );
+
+    assert_eq!(tx.outputs, vec![coin(100, alice())]);
+    assert_eq!(
+        tx.checker.check(&[], &[], &tx.outputs, &Default::default()),
+        Ok(0)
+    );
+}
+
+#[test]
+fn check_inherent_accepts_scheduled_reward() {
+    let (tx, _) = previous_inherent();
+    let mut result = CheckInherentsResult::new();
+    <Reward as TuxedoInherent<TestVerifier, _>>::check_inherent(
+        &InherentData::new(),
+        tx,
+        &mut result,
+    );
+
+    assert!(result.ok());
+}
+
+#[test]
+fn check_inherent_rejects_inflated_reward() {
+    let (mut tx, _) = previous_inherent();
+    tx.outputs = vec![coin(1_000, alice())];
+    let mut result = CheckInherentsResult::new();
+    <Reward as TuxedoInherent<TestVerifier, _>>::check_inherent(
+        &InherentData::new(),
+        tx,
+        &mut result,
+    );
+
+    assert!(!result.ok());
+    assert!(result.fatal_error());
+}
@@ -0,0 +1,599 @@
+//! Relays Bitcoin block headers, so that other pieces can check that a Bitcoin transaction was
+//! confirmed without trusting anyone to report it. This makes payments that are conditional on
+//! a Bitcoin payment possible, such as selling coins for BTC.
+//!
+//! The relay keeps one [`HeaderRecord`] for every header of the best Bitcoin chain that it knows
+//! of, starting from a checkpoint, and a single [`RelayTip`] that points at the best header. Both
+//! are protected by `UpForGrabs`, so anyone may submit headers.
+//!
+//! * [`BtcRelayConstraintChecker::SubmitHeaders`] extends the best chain. Every header must link
+//!   to the previous one, meet the difficulty that Bitcoin's retargeting rules require, and have
+//!   a hash that meets that difficulty.
+//! * [`BtcRelayConstraintChecker::Reorg`] switches to a fork with more work. It consumes the
+//!   records of the headers that the fork replaces, so only records of the best chain exist.
+//!
+//! Other pieces verify a transaction by peeking at the record of the block that contains it and
+//! at the tip, and calling [`verify_inclusion`] with a merkle proof of the transaction's id.
+//!
+//! A runtime starts the relay by declaring the tip and the record of the checkpoint in its genesis
+//! outputs. See [`HeaderRecord::checkpoint`]. On mainnet, the checkpoint must be the first block
+//! of a difficulty period so that the next retarget can be computed.
+//!
+//! Hashes are kept in Bitcoin's internal byte order, which is the reverse of how block explorers
+//! display them.
+//!
+//! ## Trust Warning
+//!
+//! Headers are checked for proof of work and difficulty, but not for the median time past or
+//! future timestamp rules, so a miner has a little more freedom with timestamps than on Bitcoin.
+//! Merkle proofs can not tell a 64 byte transaction from an inner node of the tree, so pieces
+//! should not accept transactions of that size.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use parity_scale_codec::{Decode, Encode};
+use scale_info::TypeInfo;
+use serde::{Deserialize, Serialize};
+use sp_core::{H256, U256};
+use sp_runtime::transaction_validity::TransactionPriority;
+use sp_std::prelude::*;
+use tuxedo_core::{
+    dynamic_typing::UtxoData, ensure, types::Output, verifier::UpForGrabs, CheckerContext,
+    ConstraintChecker, Verifier,
+};
+
+#[cfg(test)]
+mod tests;
+
+/// The number of blocks between difficulty retargets.
+pub const RETARGET_INTERVAL: u32 = 2016;
+
+/// The time, in seconds, that a difficulty period is meant to take.
+pub const TARGET_TIMESPAN: u32 = 14 * 24 * 60 * 60;
+
+/// The Bitcoin network whose headers are relayed.
+#[derive(
+    Serialize,
+    Deserialize,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Clone,
+    Copy,
+    Encode,
+    Decode,
+    Hash,
+    Debug,
+    TypeInfo,
+)]
+pub enum Network {
+    /// Bitcoin itself.
+    Mainnet,
+    /// A local test network, where the difficulty is minimal and never changes.
+    Regtest,
+}
+
+impl Network {
+    /// The lowest difficulty that headers may have, in compact form.
+    pub fn pow_limit_bits(&self) -> u32 {
+        match self {
+            Self::Mainnet => 0x1d00ffff,
+            Self::Regtest => 0x207fffff,
+        }
+    }
+
+    /// Whether the difficulty is retargeted every [`RETARGET_INTERVAL`] blocks.
+    pub fn retargets(&self) -> bool {
+        match self {
+            Self::Mainnet => true,
+            Self::Regtest => false,
+        }
+    }
+}
+
+/// A Bitcoin block header.
+#[derive(
+    Serialize,
+    Deserialize,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Clone,
+    Encode,
+    Decode,
+    Hash,
+    Debug,
+    TypeInfo,
+)]
+pub struct BlockHeader {
+    /// The block version, which miners also use for signalling.
+    pub version: i32,
+    /// The hash of the previous block's header.
+    pub prev_block_hash: H256,
+    /// The root of the merkle tree of the block's transaction ids.
+    pub merkle_root: H256,
+    /// The time the block was mined, in seconds since the Unix epoch.
+    pub time: u32,
+    /// The difficulty target of the block, in compact form.
+    pub bits: u32,
+    /// The nonce that the miner varied to meet the target.
+    pub nonce: u32,
+}
+
+impl BlockHeader {
+    /// Parse a header from the 80 bytes that Bitcoin serializes it as.
+    pub fn from_bytes(bytes: &[u8; 80]) -> Self {
+        let u32_at = |i: usize| u32::from_le_bytes(bytes[i..i + 4].try_into().expect("4 bytes"));
+        Self {
+            version: u32_at(0) as i32,
+            prev_block_hash: H256::from_slice(&bytes[4..36]),
+            merkle_root: H256::from_slice(&bytes[36..68]),
+            time: u32_at(68),
+            bits: u32_at(72),
+            nonce: u32_at(76),
+        }
+    }
+
+    /// Serialize the header the way Bitcoin does.
+    pub fn to_bytes(&self) -> [u8; 80] {
+        let mut bytes = [0u8; 80];
+        bytes[0..4].copy_from_slice(&self.version.to_le_bytes());
+        bytes[4..36].copy_from_slice(self.prev_block_hash.as_bytes());
+        bytes[36..68].copy_from_slice(self.merkle_root.as_bytes());
+        bytes[68..72].copy_from_slice(&self.time.to_le_bytes());
+        bytes[72..76].copy_from_slice(&self.bits.to_le_bytes());
+        bytes[76..80].copy_from_slice(&self.nonce.to_le_bytes());
+        bytes
+    }
+
+    /// The hash of the header, which identifies the block.
+    pub fn hash(&self) -> H256 {
+        sha256d(&self.to_bytes())
+    }
+}
+
+/// A header of the best Bitcoin chain that the relay knows of.
+#[derive(Serialize, Deserialize, Encode, Decode, Debug, PartialEq, Eq, Clone, TypeInfo)]
+pub struct HeaderRecord {
+    /// The height of the block in the Bitcoin chain.
+    pub height: u32,
+    /// The hash of the header.
+    pub hash: H256,
+    /// The root of the merkle tree of the block's transaction ids.
+    pub merkle_root: H256,
+    /// The time the block was mined, in seconds since the Unix epoch.
+    pub time: u32,
+    /// The difficulty target of the block, in compact form.
+    pub bits: u32,
+    /// The time of the first block in this block's difficulty period.
+    pub period_start_time: u32,
+    /// The work of the chain up to and including this block, counted from the checkpoint.
+    pub chain_work: U256,
+}
+
+impl UtxoData for HeaderRecord {
+    const TYPE_ID: [u8; 4] = *b"btch";
+}
+
+impl HeaderRecord {
+    /// The record of a trusted header at the given height, that the relay starts from.
+    ///
+    /// Work is counted from the checkpoint, so the checkpoint itself has none.
+    pub fn checkpoint(height: u32, header: &BlockHeader, period_start_time: u32) -> Self {
+        Self {
+            height,
+            hash: header.hash(),
+            merkle_root: header.merkle_root,
+            time: header.time,
+            bits: header.bits,
+            period_start_time,
+            chain_work: U256::zero(),
+        }
+    }
+}
+
+/// Points at the best header that the relay knows of.
+#[derive(Serialize, Deserialize, Encode, Decode, Debug, PartialEq, Eq, Clone, TypeInfo)]
+pub struct RelayTip {
+    /// The network whose headers are relayed.
+    pub network: Network,
+    /// A copy of the record of the best header.
+    pub best: HeaderRecord,
+}
+
+impl UtxoData for RelayTip {
+    const TYPE_ID: [u8; 4] = *b"btct";
+}
+
+/// A proof that a transaction id is in the merkle tree of a block.
+#[derive(
+    Serialize,
+    Deserialize,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Clone,
+    Encode,
+    Decode,
+    Hash,
+    Debug,
+    TypeInfo,
+)]
+pub struct InclusionProof {
+    /// The position of the transaction in the block.
+    pub index: u32,
+    /// The hashes next to the path from the transaction to the root, starting at the bottom.
+    pub siblings: Vec<H256>,
+}
+
+impl InclusionProof {
+    /// The merkle root that this proof leads to from the given transaction id.
+    pub fn merkle_root(&self, txid: H256) -> H256 {
+        self.siblings
+            .iter()
+            .enumerate()
+            .fold(txid, |node, (level, sibling)| {
+                if self.index.checked_shr(level as u32).unwrap_or(0) & 1 == 0 {
+                    sha256d(&[node.as_bytes(), sibling.as_bytes()].concat())
+                } else {
+                    sha256d(&[sibling.as_bytes(), node.as_bytes()].concat())
+                }
+            })
+    }
+}
+
+/// Reasons that relay constraint checks may fail
+#[derive(
+    Serialize,
+    Deserialize,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Clone,
+    Encode,
+    Decode,
+    Hash,
+    Debug,
+    TypeInfo,
+)]
+pub enum ConstraintCheckerError {
+    /// An input data has the wrong type.
+    BadlyTypedInput,
+    /// An output data has the wrong type.
+    BadlyTypedOutput,
+    /// A peeked data has the wrong type.
+    BadlyTypedPeek,
+    /// Wrong number of inputs were provided to the constraint checker.
+    WrongNumberInputs,
+    /// Wrong number of outputs were provided to the constraint checker.
+    WrongNumberOutputs,
+    /// Wrong number of peeks were provided to the constraint checker.
+    WrongNumberPeeks,
+    /// No headers were submitted.
+    NoHeaders,
+    /// A header does not link to the header before it.
+    Disconnected,
+    /// A header's difficulty is not the one that the retargeting rules require.
+    WrongDifficulty,
+    /// A header's difficulty can not be decoded, or is lower than the network allows.
+    InvalidDifficulty,
+    /// A header's hash does not meet its difficulty.
+    InsufficientWork,
+    /// The relayed chain is too long for the height to be counted.
+    HeightOverflow,
+    /// The new tip or the new records are not the ones that the headers make.
+    WrongOutputs,
+    /// The tip or a record is not up for grabs, so the relay could get stuck.
+    NotUpForGrabs,
+    /// The records that a reorg consumes are not exactly those above the fork point.
+    WrongStaleRecords,
+    /// The fork does not have more work than the chain it replaces.
+    NotEnoughWork,
+    /// The transaction is not in the block, according to the merkle proof.
+    NotIncluded,
+    /// The block does not have enough confirmations yet.
+    NotEnoughConfirmations,
+}
+
+/// The constraint checkers of the Bitcoin relay.
+#[derive(
+    Serialize,
+    Deserialize,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Clone,
+    Encode,
+    Decode,
+    Hash,
+    Debug,
+    TypeInfo,
+)]
+pub enum BtcRelayConstraintChecker {
+    /// Extend the best chain with some headers.
+    ///
+    /// The only input is the tip. The first output is the new tip, followed by the record of each
+    /// header in order.
+    SubmitHeaders {
+        /// The headers, oldest first, starting right after the current tip.
+        headers: Vec<BlockHeader>,
+    },
+    /// Switch to a fork of the best chain that has more work.
+    ///
+    /// The only peek is the record of the last header that both chains share. The first input is
+    /// the tip, followed by the records of every header above the fork point, in order. The first
+    /// output is the new tip, followed by the record of each header of the fork in order.
+    Reorg {
+        /// The headers of the fork, oldest first, starting right after the fork point.
+        headers: Vec<BlockHeader>,
+    },
+}
+
+impl<V: Verifier + PartialEq + From<UpForGrabs>> ConstraintChecker<V>
+    for BtcRelayConstraintChecker
+{
+    type Error = ConstraintCheckerError;
+    type InherentHooks = ();
+
+    fn check(
+        &self,
+        inputs: &[Output<V>],
+        peeks: &[Output<V>],
+        outputs: &[Output<V>],
+        _context: &CheckerContext,
+    ) -> Result<TransactionPriority, Self::Error> {
+        let tip_input = inputs
+            .first()
+            .ok_or(ConstraintCheckerError::WrongNumberInputs)?;
+        let tip = tip_input
+            .payload
+            .extract::<RelayTip>()
+            .map_err(|_| ConstraintCheckerError::BadlyTypedInput)?;
+
+        let records = match self {
+            Self::SubmitHeaders { headers } => {
+                ensure!(inputs.len() == 1, ConstraintCheckerError::WrongNumberInputs);
+                ensure!(peeks.is_empty(), ConstraintCheckerError::WrongNumberPeeks);
+
+                extend(tip.network, &tip.best, headers)?
+            }
+            Self::Reorg { headers } => {
+                ensure!(peeks.len() == 1, ConstraintCheckerError::WrongNumberPeeks);
+                let fork_point = peeks[0]
+                    .payload
+                    .extract::<HeaderRecord>()
+                    .map_err(|_| ConstraintCheckerError::BadlyTypedPeek)?;
+
+                // Every record above the fork point must be consumed, so that only records of
+                // the best chain remain.
+                let stale = inputs[1..]
+                    .iter()
+                    .map(|input| input.payload.extract::<HeaderRecord>())
+                    .collect::<Result<Vec<_>, _>>()
+                    .map_err(|_| ConstraintCheckerError::BadlyTypedInput)?;
+                ensure!(
+                    !stale.is_empty()
+                        && stale.iter().enumerate().all(|(i, record)| {
+                            Some(record.height) == fork_point.height.checked_add(i as u32 + 1)
+                        })
+                        && stale.last() == Some(&tip.best),
+                    ConstraintCheckerError::WrongStaleRecords
+                );
+
+                let records = extend(tip.network, &fork_point, headers)?;
+                ensure!(
+                    records.last().map(|best| best.chain_work) > Some(tip.best.chain_work),
+                    ConstraintCheckerError::NotEnoughWork
+                );
+                records
+            }
+        };
+
+        // The new tip and records must be exactly what the headers make, and up for grabs.
+        ensure!(
+            outputs.len() == records.len() + 1,
+            ConstraintCheckerError::WrongNumberOutputs
+        );
+        let new_tip = outputs[0]
+            .payload
+            .extract::<RelayTip>()
+            .map_err(|_| ConstraintCheckerError::BadlyTypedOutput)?;
+        ensure!(
+            new_tip.network == tip.network && records.last() == Some(&new_tip.best),
+            ConstraintCheckerError::WrongOutputs
+        );
+        for (output, record) in outputs[1..].iter().zip(&records) {
+            let created = output
+                .payload
+                .extract::<HeaderRecord>()
+                .map_err(|_| ConstraintCheckerError::BadlyTypedOutput)?;
+            ensure!(&created == record, ConstraintCheckerError::WrongOutputs);
+        }
+        ensure!(
+            outputs
+                .iter()
+                .all(|output| output.verifier == UpForGrabs.into()),
+            ConstraintCheckerError::NotUpForGrabs
+        );
+
+        Ok(0)
+    }
+
+    fn is_inherent(&self) -> bool {
+        false
+    }
+}
+
+/// Check that the transaction with the given id is in the block of the given record, and that the
+/// block has at least the given number of confirmations.
+///
+/// This is meant for pieces that accept Bitcoin payments. Such a piece peeks at the record of the
+/// block and at the relay's tip, and passes them here along with the proof from its checker.
+/// A block with one confirmation is the tip itself. Returns the record, so that the piece can
+/// check the block's time or height too.
+pub fn verify_inclusion<V>(
+    record: &Output<V>,
+    tip: &Output<V>,
+    txid: H256,
+    proof: &InclusionProof,
+    confirmations: u32,
+) -> Result<HeaderRecord, ConstraintCheckerError> {
+    let record = record
+        .payload
+        .extract::<HeaderRecord>()
+        .map_err(|_| ConstraintCheckerError::BadlyTypedPeek)?;
+    let tip = tip
+        .payload
+        .extract::<RelayTip>()
+        .map_err(|_| ConstraintCheckerError::BadlyTypedPeek)?;
+
+    let confirmed = tip
+        .best
+        .height
+        .checked_sub(record.height)
+        .map(|above| above.saturating_add(1));
+    ensure!(
+        confirmed >= Some(confirmations),
+        ConstraintCheckerError::NotEnoughConfirmations
+    );
+
+    // Each index has only one path, so indexes that are too large for the tree are refused.
+    ensure!(
+        proof
+            .index
+            .checked_shr(proof.siblings.len() as u32)
+            .unwrap_or(0)
+            == 0,
+        ConstraintCheckerError::NotIncluded
+    );
+    ensure!(
+        proof.merkle_root(txid) == record.merkle_root,
+        ConstraintCheckerError::NotIncluded
+    );
+
+    Ok(record)
+}
+
+/// The id of a transaction, given its serialization without witness data.
+pub fn txid(transaction: &[u8]) -> H256 {
+    sha256d(transaction)
+}
+
+/// Decode a difficulty target from its compact form, if it is a valid positive target.
+pub fn bits_to_target(bits: u32) -> Option<U256> {
+    let exponent = bits >> 24;
+    let mantissa = bits & 0x007f_ffff;
+    if bits & 0x0080_0000 != 0 || mantissa == 0 || exponent > 32 {
+        return None;
+    }
+
+    let target = if exponent <= 3 {
+        U256::from(mantissa >> (8 * (3 - exponent)))
+    } else {
+        U256::from(mantissa) << (8 * (exponent - 3))
+    };
+    (!target.is_zero()).then_some(target)
+}
+
+/// Encode a difficulty target in compact form, losing all but its most significant bits.
+pub fn target_to_bits(target: U256) -> u32 {
+    let mut size = (target.bits() as u32 + 7) / 8;
+    let mut mantissa = if size <= 3 {
+        target.low_u32() << (8 * (3 - size))
+    } else {
+        (target >> (8 * (size - 3))).low_u32()
+    };
+    // The mantissa's top bit is a sign bit, so positive targets must leave it clear.
+    if mantissa & 0x0080_0000 != 0 {
+        mantissa >>= 8;
+        size += 1;
+    }
+    mantissa | size << 24
+}
+
+/// The expected number of hashes needed to meet the given target.
+fn work(target: U256) -> U256 {
+    (!target / (target + 1)) + 1
+}
+
+/// The difficulty that the header after the given one must have.
+fn next_bits(network: Network, prev: &HeaderRecord) -> Result<u32, ConstraintCheckerError> {
+    let height = prev
+        .height
+        .checked_add(1)
+        .ok_or(ConstraintCheckerError::HeightOverflow)?;
+    if !network.retargets() || height % RETARGET_INTERVAL != 0 {
+        return Ok(prev.bits);
+    }
+
+    let timespan = prev
+        .time
+        .saturating_sub(prev.period_start_time)
+        .clamp(TARGET_TIMESPAN / 4, TARGET_TIMESPAN * 4);
+    let pow_limit = bits_to_target(network.pow_limit_bits()).expect("the pow limit is valid");
+    let target = bits_to_target(prev.bits).ok_or(ConstraintCheckerError::InvalidDifficulty)?
+        * U256::from(timespan)
+        / U256::from(TARGET_TIMESPAN);
+
+    Ok(target_to_bits(target.min(pow_limit)))
+}
+
+/// Check that the given headers extend the chain from the given record, and make their records.
+fn extend(
+    network: Network,
+    from: &HeaderRecord,
+    headers: &[BlockHeader],
+) -> Result<Vec<HeaderRecord>, ConstraintCheckerError> {
+    ensure!(!headers.is_empty(), ConstraintCheckerError::NoHeaders);
+    let pow_limit = bits_to_target(network.pow_limit_bits()).expect("the pow limit is valid");
+
+    let mut records: Vec<HeaderRecord> = Vec::with_capacity(headers.len());
+    for header in headers {
+        let prev = records.last().unwrap_or(from);
+        ensure!(
+            header.prev_block_hash == prev.hash,
+            ConstraintCheckerError::Disconnected
+        );
+        ensure!(
+            header.bits == next_bits(network, prev)?,
+            ConstraintCheckerError::WrongDifficulty
+        );
+
+        let target = bits_to_target(header.bits)
+            .filter(|target| *target <= pow_limit)
+            .ok_or(ConstraintCheckerError::InvalidDifficulty)?;
+        let hash = header.hash();
+        ensure!(
+            U256::from_little_endian(hash.as_bytes()) <= target,
+            ConstraintCheckerError::InsufficientWork
+        );
+
+        let height = prev.height + 1;
+        let period_start_time = if height % RETARGET_INTERVAL == 0 {
+            header.time
+        } else {
+            prev.period_start_time
+        };
+        records.push(HeaderRecord {
+            height,
+            hash,
+            merkle_root: header.merkle_root,
+            time: header.time,
+            bits: header.bits,
+            period_start_time,
+            chain_work: prev.chain_work.saturating_add(work(target)),
+        });
+    }
+
+    Ok(records)
+}
+
+/// Bitcoin's hash function, which is sha256 applied twice.
+fn sha256d(data: &[u8]) -> H256 {
+    H256(sp_io::hashing::sha2_256(&sp_io::hashing::sha2_256(data)))
+}
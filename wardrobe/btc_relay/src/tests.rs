@@ -0,0 +1,624 @@
+//! Unit tests for the Bitcoin Relay piece
+
+use super::*;
+use tuxedo_core::{dynamic_typing::testing::Bogus, tuxedo_verifier, verifier::SigCheck};
+
+/// A verifier that can represent the relay and ordinary users.
+#[derive(Serialize, Deserialize, Encode, Decode, Debug, PartialEq, Eq, Clone, TypeInfo)]
+#[tuxedo_verifier]
+pub enum TestVerifier {
+    SigCheck(SigCheck),
+    UpForGrabs(UpForGrabs),
+}
+
+/// The first three headers of Bitcoin mainnet.
+const MAINNET_HEADERS: [&str; 3] = [
+    "0100000000000000000000000000000000000000000000000000000000000000000000003ba3edfd7a7b12b27ac72c3e67768f617fc81bc3888a51323a9fb8aa4b1e5e4a29ab5f49ffff001d1dac2b7c",
+    "010000006fe28c0ab6f1b372c1a6a246ae63f74f931e8365e15a089c68d6190000000000982051fd1e4ba744bbbe680e1fee14677ba1a3c3540bf7b1cdb606e857233e0e61bc6649ffff001d01e36299",
+    "010000004860eb18bf1b1620e37e9490fc8a427514416fd75159ab86688e9a8300000000d5fdcc541e25de1c7a5addedf24858b8bb665c9f36ef744ee42c316022c90f9bb0bc6649ffff001d08d2bd61",
+];
+
+/// The hash of the mainnet genesis block, as block explorers display it.
+const GENESIS_HASH: &str = "000000000019d6689c085ae165831e934ff763ae46a2a6c172b3f1b60a8ce26f";
+
+fn from_hex(hex: &str) -> Vec<u8> {
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).unwrap())
+        .collect()
+}
+
+fn mainnet_header(height: usize) -> BlockHeader {
+    BlockHeader::from_bytes(&from_hex(MAINNET_HEADERS[height]).try_into().unwrap())
+}
+
+/// A regtest header on top of the given one, mined by trying nonces until one meets the target.
+fn mine(prev: H256, time: u32) -> BlockHeader {
+    let mut header = BlockHeader {
+        version: 4,
+        prev_block_hash: prev,
+        merkle_root: H256::repeat_byte(time as u8),
+        time,
+        bits: Network::Regtest.pow_limit_bits(),
+        nonce: 0,
+    };
+    let target = bits_to_target(header.bits).unwrap();
+    while U256::from_little_endian(header.hash().as_bytes()) > target {
+        header.nonce += 1;
+    }
+    header
+}
+
+/// A chain of regtest headers on top of the given one.
+fn mine_chain(prev: H256, first_time: u32, length: u32) -> Vec<BlockHeader> {
+    let mut headers: Vec<BlockHeader> = Vec::new();
+    for time in first_time..first_time + length {
+        let prev = headers.last().map(BlockHeader::hash).unwrap_or(prev);
+        headers.push(mine(prev, time));
+    }
+    headers
+}
+
+fn up_for_grabs() -> TestVerifier {
+    UpForGrabs.into()
+}
+
+fn tip(network: Network, best: &HeaderRecord) -> Output<TestVerifier> {
+    let tip = RelayTip {
+        network,
+        best: best.clone(),
+    };
+    (tip, up_for_grabs()).into()
+}
+
+fn record(record: &HeaderRecord) -> Output<TestVerifier> {
+    (record.clone(), up_for_grabs()).into()
+}
+
+/// The outputs that a submission of the given records must create.
+fn outputs(network: Network, records: &[HeaderRecord]) -> Vec<Output<TestVerifier>> {
+    let mut outputs = vec![tip(network, records.last().unwrap())];
+    outputs.extend(records.iter().map(record));
+    outputs
+}
+
+fn check(
+    checker: BtcRelayConstraintChecker,
+    inputs: Vec<Output<TestVerifier>>,
+    peeks: Vec<Output<TestVerifier>>,
+    outputs: Vec<Output<TestVerifier>>,
+) -> Result<TransactionPriority, ConstraintCheckerError> {
+    checker.check(&inputs, &peeks, &outputs, &Default::default())
+}
+
+/// The relay starting from the mainnet genesis block.
+fn mainnet_checkpoint() -> HeaderRecord {
+    let genesis = mainnet_header(0);
+    HeaderRecord::checkpoint(0, &genesis, genesis.time)
+}
+
+/// The relay starting from a regtest block at height 100.
+fn regtest_checkpoint() -> HeaderRecord {
+    HeaderRecord::checkpoint(100, &mine(H256::zero(), 1_000), 1_000)
+}
+
+#[test]
+fn header_serialization_round_trips() {
+    let bytes: [u8; 80] = from_hex(MAINNET_HEADERS[1]).try_into().unwrap();
+    assert_eq!(BlockHeader::from_bytes(&bytes).to_bytes(), bytes);
+}
+
+#[test]
+fn header_hash_matches_bitcoin() {
+    let mut displayed = mainnet_header(0).hash().0;
+    displayed.reverse();
+    assert_eq!(displayed.to_vec(), from_hex(GENESIS_HASH));
+    assert_eq!(mainnet_header(1).prev_block_hash, mainnet_header(0).hash());
+}
+
+#[test]
+fn compact_targets_round_trip() {
+    for bits in [0x1d00ffff, 0x1c05a3f4, 0x1b0404cb, 0x207fffff] {
+        assert_eq!(target_to_bits(bits_to_target(bits).unwrap()), bits);
+    }
+}
+
+#[test]
+fn negative_and_zero_targets_are_invalid() {
+    assert_eq!(bits_to_target(0x1d80ffff), None);
+    assert_eq!(bits_to_target(0x1d000000), None);
+    assert_eq!(bits_to_target(0x01003456), None);
+    assert_eq!(bits_to_target(0x2200ffff), None);
+}
+
+#[test]
+fn difficulty_one_has_expected_work() {
+    assert_eq!(
+        work(bits_to_target(0x1d00ffff).unwrap()),
+        U256::from(0x1_0001_0001u64)
+    );
+}
+
+/// A record of the last block of a mainnet difficulty period, for the retargeting vectors that
+/// Bitcoin Core tests with.
+fn end_of_period(height: u32, time: u32, bits: u32, period_start_time: u32) -> HeaderRecord {
+    HeaderRecord {
+        height,
+        hash: H256::zero(),
+        merkle_root: H256::zero(),
+        time,
+        bits,
+        period_start_time,
+        chain_work: U256::zero(),
+    }
+}
+
+#[test]
+fn retarget_works() {
+    let prev = end_of_period(32255, 1262152739, 0x1d00ffff, 1261130161);
+    assert_eq!(next_bits(Network::Mainnet, &prev), Ok(0x1d00d86a));
+}
+
+#[test]
+fn retarget_is_capped_at_pow_limit() {
+    let prev = end_of_period(2015, 1233061996, 0x1d00ffff, 1231006505);
+    assert_eq!(next_bits(Network::Mainnet, &prev), Ok(0x1d00ffff));
+}
+
+#[test]
+fn retarget_timespan_has_lower_limit() {
+    let prev = end_of_period(68543, 1279297671, 0x1c05a3f4, 1279008237);
+    assert_eq!(next_bits(Network::Mainnet, &prev), Ok(0x1c0168fd));
+}
+
+#[test]
+fn retarget_timespan_has_upper_limit() {
+    let prev = end_of_period(46367, 1269211443, 0x1c387f6f, 1263163443);
+    assert_eq!(next_bits(Network::Mainnet, &prev), Ok(0x1d00e1fd));
+}
+
+#[test]
+fn no_retarget_within_period() {
+    let prev = end_of_period(32254, 1262152739, 0x1d00ffff, 1261130161);
+    assert_eq!(next_bits(Network::Mainnet, &prev), Ok(0x1d00ffff));
+}
+
+#[test]
+fn regtest_never_retargets() {
+    let prev = end_of_period(2015, 1233061996, 0x207fffff, 1231006505);
+    assert_eq!(next_bits(Network::Regtest, &prev), Ok(0x207fffff));
+}
+
+#[test]
+fn submit_mainnet_headers_works() {
+    let checkpoint = mainnet_checkpoint();
+    let headers = vec![mainnet_header(1), mainnet_header(2)];
+    let records = extend(Network::Mainnet, &checkpoint, &headers).unwrap();
+
+    assert_eq!(records[1].height, 2);
+    assert_eq!(records[1].hash, mainnet_header(2).hash());
+    assert_eq!(records[1].chain_work, U256::from(0x2_0002_0002u64));
+    assert_eq!(
+        check(
+            BtcRelayConstraintChecker::SubmitHeaders { headers },
+            vec![tip(Network::Mainnet, &checkpoint)],
+            vec![],
+            outputs(Network::Mainnet, &records),
+        ),
+        Ok(0)
+    );
+}
+
+#[test]
+fn submit_no_headers_fails() {
+    let checkpoint = mainnet_checkpoint();
+    assert_eq!(
+        check(
+            BtcRelayConstraintChecker::SubmitHeaders { headers: vec![] },
+            vec![tip(Network::Mainnet, &checkpoint)],
+            vec![],
+            vec![tip(Network::Mainnet, &checkpoint)],
+        ),
+        Err(ConstraintCheckerError::NoHeaders)
+    );
+}
+
+#[test]
+fn submit_disconnected_header_fails() {
+    assert_eq!(
+        extend(
+            Network::Mainnet,
+            &mainnet_checkpoint(),
+            &[mainnet_header(2)]
+        ),
+        Err(ConstraintCheckerError::Disconnected)
+    );
+}
+
+#[test]
+fn submit_header_without_work_fails() {
+    let mut header = mainnet_header(1);
+    header.nonce += 1;
+
+    assert_eq!(
+        extend(Network::Mainnet, &mainnet_checkpoint(), &[header]),
+        Err(ConstraintCheckerError::InsufficientWork)
+    );
+}
+
+#[test]
+fn submit_header_with_wrong_difficulty_fails() {
+    let mut header = mainnet_header(1);
+    header.bits = 0x1d00fffe;
+
+    assert_eq!(
+        extend(Network::Mainnet, &mainnet_checkpoint(), &[header]),
+        Err(ConstraintCheckerError::WrongDifficulty)
+    );
+}
+
+#[test]
+fn submit_header_easier_than_pow_limit_fails() {
+    // Mainnet does not retarget within a period, so an easy checkpoint keeps its difficulty.
+    let mut checkpoint = mainnet_checkpoint();
+    checkpoint.bits = 0x2000ffff;
+    let mut header = mainnet_header(1);
+    header.bits = checkpoint.bits;
+    let target = bits_to_target(header.bits).unwrap();
+    while U256::from_little_endian(header.hash().as_bytes()) > target {
+        header.nonce += 1;
+    }
+
+    assert_eq!(
+        extend(Network::Mainnet, &checkpoint, &[header]),
+        Err(ConstraintCheckerError::InvalidDifficulty)
+    );
+}
+
+#[test]
+fn submit_with_wrong_tip_fails() {
+    let checkpoint = mainnet_checkpoint();
+    let headers = vec![mainnet_header(1), mainnet_header(2)];
+    let records = extend(Network::Mainnet, &checkpoint, &headers).unwrap();
+    let mut outputs = outputs(Network::Mainnet, &records);
+    outputs[0] = tip(Network::Mainnet, &records[0]);
+
+    assert_eq!(
+        check(
+            BtcRelayConstraintChecker::SubmitHeaders { headers },
+            vec![tip(Network::Mainnet, &checkpoint)],
+            vec![],
+            outputs,
+        ),
+        Err(ConstraintCheckerError::WrongOutputs)
+    );
+}
+
+#[test]
+fn submit_with_forged_record_fails() {
+    let checkpoint = mainnet_checkpoint();
+    let headers = vec![mainnet_header(1), mainnet_header(2)];
+    let records = extend(Network::Mainnet, &checkpoint, &headers).unwrap();
+    let mut forged = records[0].clone();
+    forged.merkle_root = H256::repeat_byte(1);
+    let mut outputs = outputs(Network::Mainnet, &records);
+    outputs[1] = record(&forged);
+
+    assert_eq!(
+        check(
+            BtcRelayConstraintChecker::SubmitHeaders { headers },
+            vec![tip(Network::Mainnet, &checkpoint)],
+            vec![],
+            outputs,
+        ),
+        Err(ConstraintCheckerError::WrongOutputs)
+    );
+}
+
+#[test]
+fn submit_with_owned_record_fails() {
+    let checkpoint = mainnet_checkpoint();
+    let headers = vec![mainnet_header(1)];
+    let records = extend(Network::Mainnet, &checkpoint, &headers).unwrap();
+    let mut outputs = outputs(Network::Mainnet, &records);
+    outputs[1].verifier = SigCheck::new(H256::repeat_byte(1)).into();
+
+    assert_eq!(
+        check(
+            BtcRelayConstraintChecker::SubmitHeaders { headers },
+            vec![tip(Network::Mainnet, &checkpoint)],
+            vec![],
+            outputs,
+        ),
+        Err(ConstraintCheckerError::NotUpForGrabs)
+    );
+}
+
+#[test]
+fn submit_without_tip_fails() {
+    let checkpoint = mainnet_checkpoint();
+    assert_eq!(
+        check(
+            BtcRelayConstraintChecker::SubmitHeaders {
+                headers: vec![mainnet_header(1)]
+            },
+            vec![record(&checkpoint)],
+            vec![],
+            vec![],
+        ),
+        Err(ConstraintCheckerError::BadlyTypedInput)
+    );
+}
+
+#[test]
+fn reorg_to_longer_fork_works() {
+    let checkpoint = regtest_checkpoint();
+    let main = extend(
+        Network::Regtest,
+        &checkpoint,
+        &mine_chain(checkpoint.hash, 2_000, 2),
+    )
+    .unwrap();
+    let fork_headers = mine_chain(checkpoint.hash, 3_000, 3);
+    let fork = extend(Network::Regtest, &checkpoint, &fork_headers).unwrap();
+
+    assert_eq!(
+        check(
+            BtcRelayConstraintChecker::Reorg {
+                headers: fork_headers
+            },
+            vec![
+                tip(Network::Regtest, &main[1]),
+                record(&main[0]),
+                record(&main[1])
+            ],
+            vec![record(&checkpoint)],
+            outputs(Network::Regtest, &fork),
+        ),
+        Ok(0)
+    );
+}
+
+#[test]
+fn reorg_to_fork_with_equal_work_fails() {
+    let checkpoint = regtest_checkpoint();
+    let main = extend(
+        Network::Regtest,
+        &checkpoint,
+        &mine_chain(checkpoint.hash, 2_000, 2),
+    )
+    .unwrap();
+    let fork_headers = mine_chain(checkpoint.hash, 3_000, 2);
+    let fork = extend(Network::Regtest, &checkpoint, &fork_headers).unwrap();
+
+    assert_eq!(
+        check(
+            BtcRelayConstraintChecker::Reorg {
+                headers: fork_headers
+            },
+            vec![
+                tip(Network::Regtest, &main[1]),
+                record(&main[0]),
+                record(&main[1])
+            ],
+            vec![record(&checkpoint)],
+            outputs(Network::Regtest, &fork),
+        ),
+        Err(ConstraintCheckerError::NotEnoughWork)
+    );
+}
+
+#[test]
+fn reorg_that_leaves_stale_records_fails() {
+    let checkpoint = regtest_checkpoint();
+    let main = extend(
+        Network::Regtest,
+        &checkpoint,
+        &mine_chain(checkpoint.hash, 2_000, 2),
+    )
+    .unwrap();
+    let fork_headers = mine_chain(checkpoint.hash, 3_000, 3);
+    let fork = extend(Network::Regtest, &checkpoint, &fork_headers).unwrap();
+
+    assert_eq!(
+        check(
+            BtcRelayConstraintChecker::Reorg {
+                headers: fork_headers
+            },
+            vec![tip(Network::Regtest, &main[1]), record(&main[1])],
+            vec![record(&checkpoint)],
+            outputs(Network::Regtest, &fork),
+        ),
+        Err(ConstraintCheckerError::WrongStaleRecords)
+    );
+}
+
+#[test]
+fn reorg_without_fork_point_fails() {
+    let checkpoint = regtest_checkpoint();
+    let main = extend(
+        Network::Regtest,
+        &checkpoint,
+        &mine_chain(checkpoint.hash, 2_000, 1),
+    )
+    .unwrap();
+    let fork_headers = mine_chain(checkpoint.hash, 3_000, 2);
+    let fork = extend(Network::Regtest, &checkpoint, &fork_headers).unwrap();
+
+    assert_eq!(
+        check(
+            BtcRelayConstraintChecker::Reorg {
+                headers: fork_headers
+            },
+            vec![tip(Network::Regtest, &main[0]), record(&main[0])],
+            vec![],
+            outputs(Network::Regtest, &fork),
+        ),
+        Err(ConstraintCheckerError::WrongNumberPeeks)
+    );
+}
+
+#[test]
+fn reorg_with_bogus_fork_point_fails() {
+    let checkpoint = regtest_checkpoint();
+    let main = extend(
+        Network::Regtest,
+        &checkpoint,
+        &mine_chain(checkpoint.hash, 2_000, 1),
+    )
+    .unwrap();
+
+    assert_eq!(
+        check(
+            BtcRelayConstraintChecker::Reorg {
+                headers: mine_chain(checkpoint.hash, 3_000, 2)
+            },
+            vec![tip(Network::Regtest, &main[0]), record(&main[0])],
+            vec![(Bogus, up_for_grabs()).into()],
+            vec![],
+        ),
+        Err(ConstraintCheckerError::BadlyTypedPeek)
+    );
+}
+
+/// Hash two nodes of a merkle tree together.
+fn parent(left: H256, right: H256) -> H256 {
+    sha256d(&[left.as_bytes(), right.as_bytes()].concat())
+}
+
+/// A mainnet tip two blocks above the genesis block.
+fn tip_at_two() -> Output<TestVerifier> {
+    let records = extend(
+        Network::Mainnet,
+        &mainnet_checkpoint(),
+        &[mainnet_header(1), mainnet_header(2)],
+    )
+    .unwrap();
+    tip(Network::Mainnet, &records[1])
+}
+
+#[test]
+fn genesis_coinbase_is_included() {
+    // The genesis block has a single transaction, so its id is the merkle root.
+    let checkpoint = mainnet_checkpoint();
+    let proof = InclusionProof {
+        index: 0,
+        siblings: vec![],
+    };
+
+    assert_eq!(
+        verify_inclusion(
+            &record(&checkpoint),
+            &tip_at_two(),
+            checkpoint.merkle_root,
+            &proof,
+            3
+        ),
+        Ok(checkpoint)
+    );
+}
+
+#[test]
+fn inclusion_needs_confirmations() {
+    let checkpoint = mainnet_checkpoint();
+    let proof = InclusionProof {
+        index: 0,
+        siblings: vec![],
+    };
+
+    assert_eq!(
+        verify_inclusion(
+            &record(&checkpoint),
+            &tip_at_two(),
+            checkpoint.merkle_root,
+            &proof,
+            4
+        ),
+        Err(ConstraintCheckerError::NotEnoughConfirmations)
+    );
+}
+
+#[test]
+fn inclusion_in_larger_tree_works() {
+    // Three transactions, so the last one is paired with itself.
+    let txids: Vec<H256> = (1..=3).map(|i| txid(&[i])).collect();
+    let left = parent(txids[0], txids[1]);
+    let right = parent(txids[2], txids[2]);
+    let mut block = mainnet_checkpoint();
+    block.merkle_root = parent(left, right);
+
+    let first = InclusionProof {
+        index: 0,
+        siblings: vec![txids[1], right],
+    };
+    let last = InclusionProof {
+        index: 2,
+        siblings: vec![txids[2], left],
+    };
+
+    for (txid, proof) in [(txids[0], first), (txids[2], last)] {
+        assert!(verify_inclusion(&record(&block), &tip_at_two(), txid, &proof, 1).is_ok());
+    }
+}
+
+#[test]
+fn inclusion_of_other_transaction_fails() {
+    let checkpoint = mainnet_checkpoint();
+    let proof = InclusionProof {
+        index: 0,
+        siblings: vec![],
+    };
+
+    assert_eq!(
+        verify_inclusion(
+            &record(&checkpoint),
+            &tip_at_two(),
+            txid(b"not the coinbase"),
+            &proof,
+            1
+        ),
+        Err(ConstraintCheckerError::NotIncluded)
+    );
+}
+
+#[test]
+fn inclusion_with_index_beyond_tree_fails() {
+    let checkpoint = mainnet_checkpoint();
+    let proof = InclusionProof {
+        index: 1,
+        siblings: vec![],
+    };
+
+    assert_eq!(
+        verify_inclusion(
+            &record(&checkpoint),
+            &tip_at_two(),
+            checkpoint.merkle_root,
+            &proof,
+            1
+        ),
+        Err(ConstraintCheckerError::NotIncluded)
+    );
+}
+
+#[test]
+fn inclusion_above_tip_fails() {
+    let checkpoint = mainnet_checkpoint();
+    let proof = InclusionProof {
+        index: 0,
+        siblings: vec![],
+    };
+    let mut future = checkpoint.clone();
+    future.height = 3;
+
+    assert_eq!(
+        verify_inclusion(
+            &record(&future),
+            &tip_at_two(),
+            checkpoint.merkle_root,
+            &proof,
+            0
+        ),
+        Err(ConstraintCheckerError::NotEnoughConfirmations)
+    );
+}
@@ -0,0 +1,177 @@
+//! CoinJoins, in which several users spend their coins together in a single transaction, so that
+//! observers can not tell which of the new coins belongs to whom.
+//!
+//! Every participant contributes some `Coin<ID>`s as inputs. In return, they receive one mixed
+//! output of the common denomination, owned by a fresh key, and any change back to the key that
+//! owns their inputs. All the mixed outputs have the same value, so they can not be linked to the
+//! inputs that paid for them. Change outputs remain linked to their owners.
+//!
+//! The [`CoinJoin`] constraint checker tells participants apart by the owners of the inputs, and
+//! makes sure that each of them pays for their own mixed output and share of the fee, so that no
+//! participant pays for another. Participants build their parts of the transaction separately,
+//! and a coordinator joins them with the PSTT format of Tuxedo core. Each participant signs only
+//! their own inputs, and only once they have seen that the joined transaction pays them.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use core::marker::PhantomData;
+
+use money::Coin;
+use parity_scale_codec::{Decode, Encode};
+use scale_info::TypeInfo;
+use serde::{Deserialize, Serialize};
+use sp_runtime::transaction_validity::TransactionPriority;
+use sp_std::{collections::btree_map::BTreeMap, prelude::*};
+use tuxedo_core::{
+    dynamic_typing::DynamicallyTypedData,
+    ensure,
+    support_macros::{CloneNoBound, DebugNoBound},
+    CheckerContext, SimpleConstraintChecker,
+};
+
+#[cfg(test)]
+mod tests;
+
+/// Configuration items for the CoinJoin piece when it is
+/// instantiated in a concrete runtime.
+pub trait CoinJoinConfig {
+    /// The fewest participants that a CoinJoin may have. Each participant hides among the others,
+    /// so a CoinJoin with too few of them does not hide much.
+    const MIN_PARTICIPANTS: usize = 3;
+}
+
+/// Errors that can occur when checking a CoinJoin.
+#[derive(
+    Serialize,
+    Deserialize,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Clone,
+    Encode,
+    Decode,
+    Hash,
+    Debug,
+    TypeInfo,
+)]
+pub enum ConstraintCheckerError {
+    /// An input data has the wrong type.
+    BadlyTypedInput,
+    /// An output data has the wrong type.
+    BadlyTypedOutput,
+    /// The denomination of the mixed outputs is zero.
+    ZeroDenomination,
+    /// The inputs are owned by fewer than the minimum number of participants.
+    TooFewParticipants,
+    /// A mixed output does not have the value of the denomination.
+    WrongDenomination,
+    /// There is not exactly one mixed output for every participant.
+    WrongNumberOfMixedOutputs,
+    /// A change output has zero value.
+    ZeroValue,
+    /// The value of a participant's inputs overflows.
+    ValueOverflow,
+    /// A participant's inputs do not cover their change and their mixed output.
+    ParticipantUnderpaid,
+}
+
+/// A constraint checker for CoinJoins.
+///
+/// Every input is a `Coin<ID>`, and the inputs with the same verifier belong to the same
+/// participant. Every output is a `Coin<ID>` as well. Outputs with the verifier of a participant
+/// are that participant's change. All the other outputs are mixed outputs, of which there is
+/// exactly one per participant, each worth the denomination.
+///
+/// Each participant's inputs must cover their change and one mixed output. Whatever is left over
+/// is their share of the transaction's fee and tip, which the participants pay together.
+#[derive(
+    Serialize, Deserialize, Encode, Decode, DebugNoBound, PartialEq, Eq, CloneNoBound, TypeInfo,
+)]
+#[scale_info(skip_type_params(T))]
+pub struct CoinJoin<T, const ID: u8> {
+    /// The value of every mixed output.
+    pub denomination: u128,
+    _phantom: PhantomData<T>,
+}
+
+impl<T, const ID: u8> CoinJoin<T, ID> {
+    /// A CoinJoin whose mixed outputs are worth the given denomination.
+    pub fn new(denomination: u128) -> Self {
+        Self {
+            denomination,
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<T: CoinJoinConfig, const ID: u8> SimpleConstraintChecker for CoinJoin<T, ID> {
+    type Error = ConstraintCheckerError;
+
+    fn check(
+        &self,
+        input_data: &[DynamicallyTypedData],
+        _peeks: &[DynamicallyTypedData],
+        output_data: &[DynamicallyTypedData],
+        context: &CheckerContext,
+    ) -> Result<TransactionPriority, Self::Error> {
+        ensure!(
+            self.denomination > 0,
+            ConstraintCheckerError::ZeroDenomination
+        );
+
+        // Sum the value that each participant contributes, keyed by the owner of their inputs.
+        let mut contributions = BTreeMap::<&[u8], u128>::new();
+        for (input, owner) in input_data.iter().zip(&context.input_verifiers) {
+            let coin = input
+                .extract::<Coin<ID>>()
+                .map_err(|_| ConstraintCheckerError::BadlyTypedInput)?;
+            let contribution = contributions.entry(owner.as_slice()).or_default();
+            *contribution = contribution
+                .checked_add(coin.0)
+                .ok_or(ConstraintCheckerError::ValueOverflow)?;
+        }
+        ensure!(
+            contributions.len() >= T::MIN_PARTICIPANTS,
+            ConstraintCheckerError::TooFewParticipants
+        );
+
+        // Take each participant's change out of their contribution, and count the mixed outputs.
+        let mut mixed_outputs = 0;
+        for (output, owner) in output_data.iter().zip(&context.output_verifiers) {
+            let coin = output
+                .extract::<Coin<ID>>()
+                .map_err(|_| ConstraintCheckerError::BadlyTypedOutput)?;
+            match contributions.get_mut(owner.as_slice()) {
+                Some(contribution) => {
+                    ensure!(coin.0 > 0, ConstraintCheckerError::ZeroValue);
+                    *contribution = contribution
+                        .checked_sub(coin.0)
+                        .ok_or(ConstraintCheckerError::ParticipantUnderpaid)?;
+                }
+                None => {
+                    ensure!(
+                        coin.0 == self.denomination,
+                        ConstraintCheckerError::WrongDenomination
+                    );
+                    mixed_outputs += 1;
+                }
+            }
+        }
+        ensure!(
+            mixed_outputs == contributions.len(),
+            ConstraintCheckerError::WrongNumberOfMixedOutputs
+        );
+
+        // What remains of each contribution after one mixed output is that participant's fee.
+        let mut burned: u128 = 0;
+        for contribution in contributions.values() {
+            let fee = contribution
+                .checked_sub(self.denomination)
+                .ok_or(ConstraintCheckerError::ParticipantUnderpaid)?;
+            burned = burned.saturating_add(fee);
+        }
+
+        Ok(burned.try_into().unwrap_or(TransactionPriority::MAX))
+    }
+}
@@ -0,0 +1,164 @@
+//! Unit tests for the CoinJoin piece
+
+use super::*;
+use tuxedo_core::dynamic_typing::testing::Bogus;
+
+/// A mock config that allows CoinJoins of only two participants, to keep the tests short.
+pub struct TestConfig;
+
+impl CoinJoinConfig for TestConfig {
+    const MIN_PARTICIPANTS: usize = 2;
+}
+
+/// The keys of the participants, which own their inputs and change.
+const ALICE: u8 = 1;
+const BOB: u8 = 2;
+const CHARLIE: u8 = 3;
+
+/// Fresh keys, which own the mixed outputs.
+const FRESH_1: u8 = 101;
+const FRESH_2: u8 = 102;
+const FRESH_3: u8 = 103;
+
+/// Check a CoinJoin with a denomination of ten, whose inputs and outputs are given as their
+/// owner and value. The encoded verifier of each is just the byte of its owner.
+fn join(
+    inputs: &[(u8, u128)],
+    outputs: &[(u8, u128)],
+) -> Result<TransactionPriority, ConstraintCheckerError> {
+    let input_data: Vec<DynamicallyTypedData> = inputs
+        .iter()
+        .map(|(_, value)| Coin::<0>(*value).into())
+        .collect();
+    let output_data: Vec<DynamicallyTypedData> = outputs
+        .iter()
+        .map(|(_, value)| Coin::<0>(*value).into())
+        .collect();
+    let context = CheckerContext {
+        input_verifiers: inputs.iter().map(|(owner, _)| vec![*owner]).collect(),
+        output_verifiers: outputs.iter().map(|(owner, _)| vec![*owner]).collect(),
+        ..Default::default()
+    };
+
+    CoinJoin::<TestConfig, 0>::new(10).check(&input_data, &[], &output_data, &context)
+}
+
+#[test]
+fn coinjoin_works() {
+    assert_eq!(
+        join(
+            &[(ALICE, 12), (BOB, 6), (BOB, 6), (CHARLIE, 11)],
+            &[
+                (FRESH_1, 10),
+                (ALICE, 1),
+                (FRESH_2, 10),
+                (FRESH_3, 10),
+                (BOB, 1),
+            ]
+        ),
+        Ok(3)
+    );
+}
+
+#[test]
+fn coinjoin_without_change_works() {
+    assert_eq!(
+        join(&[(ALICE, 10), (BOB, 10)], &[(FRESH_1, 10), (FRESH_2, 10)]),
+        Ok(0)
+    );
+}
+
+#[test]
+fn coinjoin_with_too_few_participants_fails() {
+    assert_eq!(
+        join(&[(ALICE, 10), (ALICE, 10)], &[(FRESH_1, 10), (FRESH_2, 10)]),
+        Err(ConstraintCheckerError::TooFewParticipants)
+    );
+}
+
+#[test]
+fn coinjoin_with_zero_denomination_fails() {
+    assert_eq!(
+        CoinJoin::<TestConfig, 0>::new(0).check(&[], &[], &[], &Default::default()),
+        Err(ConstraintCheckerError::ZeroDenomination)
+    );
+}
+
+#[test]
+fn coinjoin_with_wrong_denomination_fails() {
+    assert_eq!(
+        join(&[(ALICE, 10), (BOB, 10)], &[(FRESH_1, 10), (FRESH_2, 9)]),
+        Err(ConstraintCheckerError::WrongDenomination)
+    );
+}
+
+#[test]
+fn coinjoin_with_missing_mixed_output_fails() {
+    assert_eq!(
+        join(&[(ALICE, 10), (BOB, 10)], &[(FRESH_1, 10)]),
+        Err(ConstraintCheckerError::WrongNumberOfMixedOutputs)
+    );
+}
+
+#[test]
+fn coinjoin_with_extra_mixed_output_fails() {
+    assert_eq!(
+        join(
+            &[(ALICE, 20), (BOB, 10)],
+            &[(FRESH_1, 10), (FRESH_2, 10), (FRESH_3, 10)]
+        ),
+        Err(ConstraintCheckerError::WrongNumberOfMixedOutputs)
+    );
+}
+
+#[test]
+fn participant_paying_for_another_fails() {
+    // Alice pays enough for both mixed outputs, but Bob pays nothing towards his.
+    assert_eq!(
+        join(
+            &[(ALICE, 25), (BOB, 5)],
+            &[(FRESH_1, 10), (FRESH_2, 10), (ALICE, 5), (BOB, 5)]
+        ),
+        Err(ConstraintCheckerError::ParticipantUnderpaid)
+    );
+}
+
+#[test]
+fn change_beyond_contribution_fails() {
+    assert_eq!(
+        join(
+            &[(ALICE, 10), (BOB, 10)],
+            &[(FRESH_1, 10), (FRESH_2, 10), (BOB, 11)]
+        ),
+        Err(ConstraintCheckerError::ParticipantUnderpaid)
+    );
+}
+
+#[test]
+fn zero_change_fails() {
+    assert_eq!(
+        join(
+            &[(ALICE, 10), (BOB, 10)],
+            &[(FRESH_1, 10), (FRESH_2, 10), (BOB, 0)]
+        ),
+        Err(ConstraintCheckerError::ZeroValue)
+    );
+}
+
+#[test]
+fn coinjoin_with_something_else_fails() {
+    let context = CheckerContext {
+        input_verifiers: vec![vec![ALICE], vec![BOB]],
+        ..Default::default()
+    };
+
+    assert_eq!(
+        CoinJoin::<TestConfig, 0>::new(10).check(
+            &[Coin::<0>(10).into(), Bogus.into()],
+            &[],
+            &[],
+            &context
+        ),
+        Err(ConstraintCheckerError::BadlyTypedInput)
+    );
+}
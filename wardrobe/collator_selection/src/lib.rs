@@ -0,0 +1,630 @@
+//! Select a parachain's collators on chain from candidates who bond `Coin<ID>`s.
+//!
+//! Anyone can register a collator key as a candidate by bonding at least the configured minimum
+//! into a [`Candidacy`] UTXO. Time is divided into sessions of a fixed number of blocks. At the
+//! start of each session, the block author includes an [`AdvanceSession`] inherent that selects
+//! the collators for the whole session: the configured invulnerables, followed by the candidates
+//! with the largest bonds. In every other block, the inherent carries the [`Session`] over unchanged.
+//!
+//! To stop collating, a candidate retires its candidacy into a [`Retiring`] UTXO. It is no longer
+//! considered when the next session selects its collators, and it can withdraw its bond as regular
+//! coins once that session has started.
+//!
+//! The selection considers every registered candidate, but the runtime can not enumerate the UTXO
+//! set. So registering and retiring also keep a registry of candidates in a well-known storage key
+//! as a side effect. Likewise, the inherent writes the session to storage, so that the runtime's
+//! `AuraApi` can read the collators through [`collators`]. The new collators take effect from the
+//! block after the one that starts the session, because Aura reads the authorities from the parent
+//! block's state.
+//!
+//! ## Hack Warning
+//!
+//! Like the timestamp piece, this piece abuses the UpForGrabs verifier to protect the session,
+//! and relies on the constraint checker to make sure that only the inherent can replace it.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use core::marker::PhantomData;
+
+use money::{Coin, MoneyConfig};
+use parity_scale_codec::{Decode, Encode};
+use scale_info::TypeInfo;
+use serde::{Deserialize, Serialize};
+use sp_core::H256;
+use sp_inherents::{CheckInherentsResult, InherentData};
+use sp_runtime::transaction_validity::TransactionPriority;
+use sp_std::{collections::btree_map::BTreeMap, vec, vec::Vec};
+use tuxedo_core::{
+    dynamic_typing::{DynamicallyTypedData, UtxoData},
+    ensure,
+    inherents::{TuxedoInherent, TuxedoInherentAdapter},
+    support_macros::{CloneNoBound, DebugNoBound, DefaultNoBound},
+    types::{Input, InputMode, Output, OutputRef, Transaction, TransactionVersion},
+    verifier::UpForGrabs,
+    CheckerContext, ConstraintChecker, SimpleConstraintChecker, Verifier,
+};
+
+#[cfg(test)]
+mod tests;
+
+/// The storage key that holds the registered candidates and their bonds.
+const CANDIDATES_KEY: &[u8] = b"collator_candidates";
+
+/// The storage key that holds the session as of the latest block.
+const SESSION_KEY: &[u8] = b"collator_session";
+
+/// Configuration items for the collator selection piece when it is
+/// instantiated in a concrete runtime.
+pub trait CollatorSelectionConfig: MoneyConfig {
+    /// A means of getting the current block height.
+    /// Probably this will be the Tuxedo Executive
+    fn block_height() -> u32;
+
+    /// The sr25519 public keys of the collators that every session selects, whether they are
+    /// candidates or not. Until enough candidates register, these keep the parachain producing blocks.
+    fn invulnerables() -> Vec<H256>;
+
+    /// The smallest bond that a candidate may register with.
+    const MIN_BOND: u128;
+
+    /// The number of candidates that each session selects in addition to the invulnerables.
+    ///
+    /// Default is 10.
+    const MAX_CANDIDATES: u32 = 10;
+
+    /// The number of blocks in each session.
+    ///
+    /// Default is 600, which is about an hour with six second blocks.
+    const SESSION_LENGTH: u32 = 600;
+}
+
+/// A collator key that is registered as a candidate, along with the `Coin<ID>` value bonded to it.
+#[derive(
+    Serialize,
+    Deserialize,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Clone,
+    Encode,
+    Decode,
+    Hash,
+    Debug,
+    TypeInfo,
+)]
+pub struct Candidacy<const ID: u8> {
+    /// The sr25519 public key that the candidate collates with.
+    pub collator: H256,
+    /// The bonded value.
+    pub bond: u128,
+}
+
+impl<const ID: u8> UtxoData for Candidacy<ID> {
+    const TYPE_ID: [u8; 4] = [b'c', b'n', b'd', ID];
+}
+
+/// A retired candidacy, waiting for the session it retired in to end.
+#[derive(
+    Serialize,
+    Deserialize,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Clone,
+    Encode,
+    Decode,
+    Hash,
+    Debug,
+    TypeInfo,
+)]
+pub struct Retiring<const ID: u8> {
+    /// The sr25519 public key that the candidate collated with.
+    pub collator: H256,
+    /// The bonded value.
+    pub bond: u128,
+    /// The index of the session in which the candidacy retired.
+    pub session: u32,
+}
+
+impl<const ID: u8> UtxoData for Retiring<ID> {
+    const TYPE_ID: [u8; 4] = [b'r', b't', b'r', ID];
+}
+
+/// A session, along with the collators that it selected.
+#[derive(
+    Serialize,
+    Deserialize,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Clone,
+    Encode,
+    Decode,
+    Hash,
+    Debug,
+    Default,
+    TypeInfo,
+)]
+pub struct Session {
+    /// The index of the session, counting from zero at genesis.
+    pub index: u32,
+    /// The sr25519 public keys of the collators, in the order that they author in.
+    pub collators: Vec<H256>,
+}
+
+impl UtxoData for Session {
+    const TYPE_ID: [u8; 4] = *b"sesn";
+}
+
+/// The registered candidates and their bonds.
+pub fn candidates() -> BTreeMap<H256, u128> {
+    sp_io::storage::get(CANDIDATES_KEY)
+        .and_then(|d| BTreeMap::<H256, u128>::decode(&mut &*d).ok())
+        .unwrap_or_default()
+}
+
+fn set_candidates(candidates: &BTreeMap<H256, u128>) {
+    sp_io::storage::set(CANDIDATES_KEY, &candidates.encode());
+}
+
+/// The session that the latest block belongs to. Before the first session change,
+/// this is session zero, which selects only the invulnerables.
+pub fn session<T: CollatorSelectionConfig>() -> Session {
+    sp_io::storage::get(SESSION_KEY)
+        .and_then(|d| Session::decode(&mut &*d).ok())
+        .unwrap_or_else(|| Session {
+            index: 0,
+            collators: T::invulnerables(),
+        })
+}
+
+/// The collators of the current session.
+pub fn collators<T: CollatorSelectionConfig>() -> Vec<H256> {
+    session::<T>().collators
+}
+
+/// The collators that a new session selects from the given candidates. These are the invulnerables,
+/// followed by the candidates with the largest bonds. Ties go to the smaller key.
+pub fn select_collators<T: CollatorSelectionConfig>(
+    candidates: &BTreeMap<H256, u128>,
+) -> Vec<H256> {
+    let mut collators = T::invulnerables();
+
+    let mut ranked: Vec<_> = candidates
+        .iter()
+        .filter(|(collator, _)| !collators.contains(collator))
+        .collect();
+    ranked.sort_by(|(a, a_bond), (b, b_bond)| b_bond.cmp(a_bond).then(a.cmp(b)));
+
+    collators.extend(
+        ranked
+            .into_iter()
+            .take(T::MAX_CANDIDATES as usize)
+            .map(|(collator, _)| *collator),
+    );
+    collators
+}
+
+/// The session at the given block height, when the given session is the one in its parent.
+pub fn next_session<T: CollatorSelectionConfig>(
+    previous: &Session,
+    height: u32,
+    candidates: &BTreeMap<H256, u128>,
+) -> Session {
+    let index = height.checked_div(T::SESSION_LENGTH).unwrap_or_default();
+    if index == previous.index {
+        return previous.clone();
+    }
+
+    Session {
+        index,
+        collators: select_collators::<T>(candidates),
+    }
+}
+
+/// Errors that can occur when checking collator selection transactions.
+#[derive(
+    Serialize,
+    Deserialize,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Clone,
+    Encode,
+    Decode,
+    Hash,
+    Debug,
+    TypeInfo,
+)]
+pub enum ConstraintCheckerError {
+    /// An input data has the wrong type.
+    BadlyTypedInput,
+    /// An output data has the wrong type.
+    BadlyTypedOutput,
+    /// Wrong number of inputs were provided to the constraint checker.
+    WrongNumberInputs,
+    /// Wrong number of outputs were provided to the constraint checker.
+    WrongNumberOutputs,
+    /// A bond or coin with zero value was created. This is not allowed because it wastes state space.
+    ZeroValue,
+    /// The value consumed or created by this transaction overflows the value type.
+    ValueOverflow,
+    /// More value was created than was consumed.
+    OutputsExceedInputs,
+    /// The new candidacy bonds less than the minimum.
+    BondTooSmall,
+    /// The collator key is already registered as a candidate.
+    AlreadyCandidate,
+    /// The consumed candidacy is not registered.
+    NotCandidate,
+    /// The retiring output does not carry over the collator and bond of the candidacy,
+    /// or does not note the current session.
+    RetirementMismatch,
+    /// The session in which the candidacy retired has not ended yet, so the bond cannot be withdrawn.
+    StillCollating,
+    /// The new session is not protected by the `UpForGrabs` verifier, so it could not be advanced again.
+    SessionNotUpForGrabs,
+    /// The new session does not follow from the previous one and the registered candidates.
+    WrongSession,
+}
+
+/// Add a value to a running total, making sure it is non-zero and does not overflow.
+fn accumulate(total: u128, value: u128) -> Result<u128, ConstraintCheckerError> {
+    ensure!(value > 0, ConstraintCheckerError::ZeroValue);
+    total
+        .checked_add(value)
+        .ok_or(ConstraintCheckerError::ValueOverflow)
+}
+
+/// A constraint checker that registers a collator key as a candidate by bonding `Coin<ID>`s.
+///
+/// All inputs are `Coin<ID>`s. The first output is the new candidacy, and any further outputs
+/// are `Coin<ID>` change. Coins that are neither bonded nor returned as change are burned, and
+/// count towards the transaction's fee and tip when they are native (see `money::surplus`).
+#[derive(
+    Serialize,
+    Deserialize,
+    Encode,
+    Decode,
+    DebugNoBound,
+    DefaultNoBound,
+    PartialEq,
+    Eq,
+    CloneNoBound,
+    TypeInfo,
+)]
+#[scale_info(skip_type_params(T))]
+pub struct RegisterCandidate<T, const ID: u8>(PhantomData<T>);
+
+impl<T: CollatorSelectionConfig, const ID: u8> SimpleConstraintChecker
+    for RegisterCandidate<T, ID>
+{
+    type Error = ConstraintCheckerError;
+
+    fn check(
+        &self,
+        input_data: &[DynamicallyTypedData],
+        _peeks: &[DynamicallyTypedData],
+        output_data: &[DynamicallyTypedData],
+        _context: &CheckerContext,
+    ) -> Result<TransactionPriority, Self::Error> {
+        let mut total_input: u128 = 0;
+        for input in input_data {
+            let coin = input
+                .extract::<Coin<ID>>()
+                .map_err(|_| ConstraintCheckerError::BadlyTypedInput)?;
+            total_input = accumulate(total_input, coin.0)?;
+        }
+
+        let candidacy = output_data
+            .first()
+            .ok_or(ConstraintCheckerError::WrongNumberOutputs)?
+            .extract::<Candidacy<ID>>()
+            .map_err(|_| ConstraintCheckerError::BadlyTypedOutput)?;
+        ensure!(
+            candidacy.bond >= T::MIN_BOND,
+            ConstraintCheckerError::BondTooSmall
+        );
+        let mut total_output = accumulate(0, candidacy.bond)?;
+        for output in &output_data[1..] {
+            let coin = output
+                .extract::<Coin<ID>>()
+                .map_err(|_| ConstraintCheckerError::BadlyTypedOutput)?;
+            total_output = accumulate(total_output, coin.0)?;
+        }
+        ensure!(
+            total_output <= total_input,
+            ConstraintCheckerError::OutputsExceedInputs
+        );
+
+        let mut registered = candidates();
+        ensure!(
+            !registered.contains_key(&candidacy.collator),
+            ConstraintCheckerError::AlreadyCandidate
+        );
+
+        // SIDE EFFECT: Register the candidate so that the next session considers it
+        registered.insert(candidacy.collator, candidacy.bond);
+        set_candidates(&registered);
+
+        let burned = total_input - total_output;
+        Ok(money::surplus::<ID, T>(burned))
+    }
+}
+
+/// A constraint checker that retires a candidacy, so that later sessions no longer select it.
+///
+/// The only input is the candidacy. The only output is a retiring bond with the same collator
+/// and value, noting the current session.
+#[derive(
+    Serialize,
+    Deserialize,
+    Encode,
+    Decode,
+    DebugNoBound,
+    DefaultNoBound,
+    PartialEq,
+    Eq,
+    CloneNoBound,
+    TypeInfo,
+)]
+#[scale_info(skip_type_params(T))]
+pub struct RetireCandidate<T, const ID: u8>(PhantomData<T>);
+
+impl<T: CollatorSelectionConfig, const ID: u8> SimpleConstraintChecker for RetireCandidate<T, ID> {
+    type Error = ConstraintCheckerError;
+
+    fn check(
+        &self,
+        input_data: &[DynamicallyTypedData],
+        _peeks: &[DynamicallyTypedData],
+        output_data: &[DynamicallyTypedData],
+        _context: &CheckerContext,
+    ) -> Result<TransactionPriority, Self::Error> {
+        ensure!(
+            input_data.len() == 1,
+            ConstraintCheckerError::WrongNumberInputs
+        );
+        let candidacy = input_data[0]
+            .extract::<Candidacy<ID>>()
+            .map_err(|_| ConstraintCheckerError::BadlyTypedInput)?;
+
+        ensure!(
+            output_data.len() == 1,
+            ConstraintCheckerError::WrongNumberOutputs
+        );
+        let retiring = output_data[0]
+            .extract::<Retiring<ID>>()
+            .map_err(|_| ConstraintCheckerError::BadlyTypedOutput)?;
+        ensure!(
+            retiring
+                == Retiring {
+                    collator: candidacy.collator,
+                    bond: candidacy.bond,
+                    session: session::<T>().index,
+                },
+            ConstraintCheckerError::RetirementMismatch
+        );
+
+        let mut registered = candidates();
+        ensure!(
+            registered.get(&candidacy.collator) == Some(&candidacy.bond),
+            ConstraintCheckerError::NotCandidate
+        );
+
+        // SIDE EFFECT: Remove the candidate so that the next session no longer considers it
+        registered.remove(&candidacy.collator);
+        set_candidates(&registered);
+
+        Ok(0)
+    }
+}
+
+/// A constraint checker that withdraws retired bonds once their sessions have ended.
+///
+/// All inputs are retiring bonds from earlier sessions, and all outputs are `Coin<ID>`s.
+/// Whatever is not withdrawn into coins is left over in the same way as when registering.
+#[derive(
+    Serialize,
+    Deserialize,
+    Encode,
+    Decode,
+    DebugNoBound,
+    DefaultNoBound,
+    PartialEq,
+    Eq,
+    CloneNoBound,
+    TypeInfo,
+)]
+#[scale_info(skip_type_params(T))]
+pub struct WithdrawBond<T, const ID: u8>(PhantomData<T>);
+
+impl<T: CollatorSelectionConfig, const ID: u8> SimpleConstraintChecker for WithdrawBond<T, ID> {
+    type Error = ConstraintCheckerError;
+
+    fn check(
+        &self,
+        input_data: &[DynamicallyTypedData],
+        _peeks: &[DynamicallyTypedData],
+        output_data: &[DynamicallyTypedData],
+        _context: &CheckerContext,
+    ) -> Result<TransactionPriority, Self::Error> {
+        let current_session = session::<T>().index;
+
+        let mut total_input: u128 = 0;
+        for input in input_data {
+            let retiring = input
+                .extract::<Retiring<ID>>()
+                .map_err(|_| ConstraintCheckerError::BadlyTypedInput)?;
+            ensure!(
+                retiring.session < current_session,
+                ConstraintCheckerError::StillCollating
+            );
+            total_input = accumulate(total_input, retiring.bond)?;
+        }
+
+        let mut total_output: u128 = 0;
+        for output in output_data {
+            let coin = output
+                .extract::<Coin<ID>>()
+                .map_err(|_| ConstraintCheckerError::BadlyTypedOutput)?;
+            total_output = accumulate(total_output, coin.0)?;
+        }
+        ensure!(
+            total_output <= total_input,
+            ConstraintCheckerError::OutputsExceedInputs
+        );
+
+        let burned = total_input - total_output;
+        Ok(money::surplus::<ID, T>(burned))
+    }
+}
+
+/// A constraint checker that advances the session, selecting new collators when a session starts.
+///
+/// This is expected to be performed through an inherent, and to happen exactly once per block.
+///
+/// The only input is the session from the previous block. The only output is the
+/// session for this block, which must be up for grabs.
+#[derive(
+    Serialize,
+    Deserialize,
+    Encode,
+    Decode,
+    DebugNoBound,
+    DefaultNoBound,
+    PartialEq,
+    Eq,
+    CloneNoBound,
+    TypeInfo,
+)]
+#[scale_info(skip_type_params(T))]
+pub struct AdvanceSession<T>(PhantomData<T>);
+
+impl<T: CollatorSelectionConfig + 'static, V: Verifier + PartialEq + From<UpForGrabs>>
+    ConstraintChecker<V> for AdvanceSession<T>
+{
+    type Error = ConstraintCheckerError;
+    type InherentHooks = TuxedoInherentAdapter<Self>;
+
+    fn check(
+        &self,
+        input_data: &[Output<V>],
+        _peek_data: &[Output<V>],
+        output_data: &[Output<V>],
+        _context: &CheckerContext,
+    ) -> Result<TransactionPriority, Self::Error> {
+        ensure!(
+            input_data.len() == 1,
+            ConstraintCheckerError::WrongNumberInputs
+        );
+        let previous = input_data[0]
+            .payload
+            .extract::<Session>()
+            .map_err(|_| ConstraintCheckerError::BadlyTypedInput)?;
+
+        ensure!(
+            output_data.len() == 1,
+            ConstraintCheckerError::WrongNumberOutputs
+        );
+        let new_session = output_data[0]
+            .payload
+            .extract::<Session>()
+            .map_err(|_| ConstraintCheckerError::BadlyTypedOutput)?;
+        ensure!(
+            output_data[0].verifier == UpForGrabs.into(),
+            ConstraintCheckerError::SessionNotUpForGrabs
+        );
+
+        ensure!(
+            new_session == next_session::<T>(&previous, T::block_height(), &candidates()),
+            ConstraintCheckerError::WrongSession
+        );
+
+        // SIDE EFFECT: Write the new session to storage so that the runtime API can find its collators
+        sp_io::storage::set(SESSION_KEY, &new_session.encode());
+
+        Ok(0)
+    }
+
+    fn is_inherent(&self) -> bool {
+        true
+    }
+}
+
+impl<V: Verifier + PartialEq + From<UpForGrabs>, T: CollatorSelectionConfig + 'static>
+    TuxedoInherent<V, Self> for AdvanceSession<T>
+{
+    type Error = sp_inherents::MakeFatalError<()>;
+    const INHERENT_IDENTIFIER: sp_inherents::InherentIdentifier = *b"col_sesn";
+
+    fn create_inherent(
+        _authoring_inherent_data: &InherentData,
+        (previous_inherent, previous_id): (Transaction<V, Self>, H256),
+    ) -> Transaction<V, Self> {
+        let previous = previous_inherent.outputs[0]
+            .payload
+            .extract::<Session>()
+            .expect(
+                "The previous session inherent should have an output that decodes as a Session.",
+            );
+        let new_session = next_session::<T>(&previous, T::block_height(), &candidates());
+
+        // There is always 1 output, so we know right where to find the previous session.
+        let input = Input {
+            output_ref: OutputRef {
+                tx_hash: previous_id,
+                index: 0,
+            },
+            redeemer: Vec::new(),
+            mode: InputMode::Consume,
+        };
+
+        Transaction {
+            version: TransactionVersion::LATEST,
+            inputs: vec![input],
+            peeks: Vec::new(),
+            outputs: vec![Output {
+                payload: new_session.into(),
+                verifier: UpForGrabs.into(),
+            }],
+            checker: Self::default(),
+            memo: None,
+            valid_until: None,
+        }
+    }
+
+    fn check_inherent(
+        _importing_inherent_data: &InherentData,
+        _inherent: Transaction<V, Self>,
+        _result: &mut CheckInherentsResult,
+    ) {
+        // The new session only depends on on-chain data, so the constraint checker does all the checks.
+    }
+
+    #[cfg(feature = "std")]
+    fn genesis_transactions() -> Vec<Transaction<V, Self>> {
+        // The inherent must appear in every block, including genesis, so that
+        // block authors always find a previous session to advance.
+        vec![Transaction {
+            version: TransactionVersion::LATEST,
+            inputs: Vec::new(),
+            peeks: Vec::new(),
+            outputs: vec![Output {
+                payload: Session {
+                    index: 0,
+                    collators: T::invulnerables(),
+                }
+                .into(),
+                verifier: UpForGrabs.into(),
+            }],
+            checker: Self::default(),
+            memo: None,
+            valid_until: None,
+        }]
+    }
+}
@@ -0,0 +1,369 @@
+//! Unit tests for the Collator Selection piece
+
+use super::*;
+use sp_io::TestExternalities;
+use std::cell::RefCell;
+use tuxedo_core::{tuxedo_verifier, verifier::SigCheck, SimpleConstraintChecker};
+
+/// A verifier that can represent the session and ordinary users.
+#[derive(Serialize, Deserialize, Encode, Decode, Debug, PartialEq, Eq, Clone, TypeInfo)]
+#[tuxedo_verifier]
+pub enum TestVerifier {
+    SigCheck(SigCheck),
+    UpForGrabs(UpForGrabs),
+}
+
+thread_local! {
+    static BLOCK_HEIGHT: RefCell<u32> = RefCell::new(1);
+}
+
+/// The mock config reads the block height from a thread local. Alice is invulnerable,
+/// sessions are ten blocks long, and each session selects up to two candidates.
+pub struct TestConfig;
+
+impl MoneyConfig for TestConfig {}
+
+impl CollatorSelectionConfig for TestConfig {
+    fn block_height() -> u32 {
+        BLOCK_HEIGHT.with(|h| *h.borrow())
+    }
+
+    fn invulnerables() -> Vec<H256> {
+        vec![alice()]
+    }
+
+    const MIN_BOND: u128 = 10;
+    const MAX_CANDIDATES: u32 = 2;
+    const SESSION_LENGTH: u32 = 10;
+}
+
+fn set_block_height(height: u32) {
+    BLOCK_HEIGHT.with(|h| *h.borrow_mut() = height);
+}
+
+fn alice() -> H256 {
+    H256::repeat_byte(1)
+}
+
+fn bob() -> H256 {
+    H256::repeat_byte(2)
+}
+
+fn charlie() -> H256 {
+    H256::repeat_byte(3)
+}
+
+fn dave() -> H256 {
+    H256::repeat_byte(4)
+}
+
+fn coin(amount: u128) -> DynamicallyTypedData {
+    Coin::<0>(amount).into()
+}
+
+fn candidacy(collator: H256, bond: u128) -> DynamicallyTypedData {
+    Candidacy::<0> { collator, bond }.into()
+}
+
+fn retiring(collator: H256, bond: u128, session: u32) -> DynamicallyTypedData {
+    Retiring::<0> {
+        collator,
+        bond,
+        session,
+    }
+    .into()
+}
+
+fn register(collator: H256, bond: u128) {
+    SimpleConstraintChecker::check(
+        &RegisterCandidate::<TestConfig, 0>::default(),
+        &[coin(bond)],
+        &[],
+        &[candidacy(collator, bond)],
+        &Default::default(),
+    )
+    .unwrap();
+}
+
+fn session_output(index: u32, collators: Vec<H256>) -> Output<TestVerifier> {
+    (Session { index, collators }, UpForGrabs.into()).into()
+}
+
+fn advance(
+    previous: Output<TestVerifier>,
+    new: Output<TestVerifier>,
+) -> Result<TransactionPriority, ConstraintCheckerError> {
+    AdvanceSession::<TestConfig>::default().check(&[previous], &[], &[new], &Default::default())
+}
+
+#[test]
+fn register_works() {
+    TestExternalities::default().execute_with(|| {
+        assert_eq!(
+            SimpleConstraintChecker::check(
+                &RegisterCandidate::<TestConfig, 0>::default(),
+                &[coin(20), coin(5)],
+                &[],
+                &[candidacy(bob(), 15), coin(8)],
+                &Default::default(),
+            ),
+            Ok(2)
+        );
+        assert_eq!(candidates(), BTreeMap::from([(bob(), 15)]));
+    });
+}
+
+#[test]
+fn register_too_small_bond_fails() {
+    TestExternalities::default().execute_with(|| {
+        assert_eq!(
+            SimpleConstraintChecker::check(
+                &RegisterCandidate::<TestConfig, 0>::default(),
+                &[coin(9)],
+                &[],
+                &[candidacy(bob(), 9)],
+                &Default::default(),
+            ),
+            Err(ConstraintCheckerError::BondTooSmall)
+        );
+        assert!(candidates().is_empty());
+    });
+}
+
+#[test]
+fn register_more_than_inputs_fails() {
+    TestExternalities::default().execute_with(|| {
+        assert_eq!(
+            SimpleConstraintChecker::check(
+                &RegisterCandidate::<TestConfig, 0>::default(),
+                &[coin(10)],
+                &[],
+                &[candidacy(bob(), 10), coin(1)],
+                &Default::default(),
+            ),
+            Err(ConstraintCheckerError::OutputsExceedInputs)
+        );
+    });
+}
+
+#[test]
+fn register_twice_fails() {
+    TestExternalities::default().execute_with(|| {
+        register(bob(), 10);
+
+        assert_eq!(
+            SimpleConstraintChecker::check(
+                &RegisterCandidate::<TestConfig, 0>::default(),
+                &[coin(20)],
+                &[],
+                &[candidacy(bob(), 20)],
+                &Default::default(),
+            ),
+            Err(ConstraintCheckerError::AlreadyCandidate)
+        );
+        assert_eq!(candidates(), BTreeMap::from([(bob(), 10)]));
+    });
+}
+
+#[test]
+fn register_without_candidacy_fails() {
+    TestExternalities::default().execute_with(|| {
+        assert_eq!(
+            SimpleConstraintChecker::check(
+                &RegisterCandidate::<TestConfig, 0>::default(),
+                &[coin(10)],
+                &[],
+                &[coin(10)],
+                &Default::default(),
+            ),
+            Err(ConstraintCheckerError::BadlyTypedOutput)
+        );
+    });
+}
+
+#[test]
+fn selection_puts_invulnerables_first_then_largest_bonds() {
+    let candidates = BTreeMap::from([(bob(), 10), (charlie(), 30), (dave(), 20), (alice(), 50)]);
+
+    assert_eq!(
+        select_collators::<TestConfig>(&candidates),
+        vec![alice(), charlie(), dave()]
+    );
+}
+
+#[test]
+fn selection_breaks_ties_by_key() {
+    let candidates = BTreeMap::from([(dave(), 10), (charlie(), 10), (bob(), 10)]);
+
+    assert_eq!(
+        select_collators::<TestConfig>(&candidates),
+        vec![alice(), bob(), charlie()]
+    );
+}
+
+#[test]
+fn session_is_carried_over_within_session() {
+    let previous = Session {
+        index: 0,
+        collators: vec![alice()],
+    };
+    let candidates = BTreeMap::from([(bob(), 10)]);
+
+    assert_eq!(
+        next_session::<TestConfig>(&previous, 9, &candidates),
+        previous
+    );
+    assert_eq!(
+        next_session::<TestConfig>(&previous, 10, &candidates),
+        Session {
+            index: 1,
+            collators: vec![alice(), bob()]
+        }
+    );
+}
+
+#[test]
+fn advance_session_selects_candidates() {
+    TestExternalities::default().execute_with(|| {
+        register(bob(), 10);
+        set_block_height(10);
+
+        assert_eq!(
+            advance(
+                session_output(0, vec![alice()]),
+                session_output(1, vec![alice(), bob()])
+            ),
+            Ok(0)
+        );
+        assert_eq!(collators::<TestConfig>(), vec![alice(), bob()]);
+    });
+}
+
+#[test]
+fn advance_session_with_wrong_collators_fails() {
+    TestExternalities::default().execute_with(|| {
+        register(bob(), 10);
+        set_block_height(10);
+
+        assert_eq!(
+            advance(
+                session_output(0, vec![alice()]),
+                session_output(1, vec![alice()])
+            ),
+            Err(ConstraintCheckerError::WrongSession)
+        );
+        assert_eq!(collators::<TestConfig>(), vec![alice()]);
+    });
+}
+
+#[test]
+fn advance_session_to_owned_session_fails() {
+    TestExternalities::default().execute_with(|| {
+        let owned = (
+            Session {
+                index: 0,
+                collators: vec![alice()],
+            },
+            SigCheck::new(bob()).into(),
+        )
+            .into();
+
+        assert_eq!(
+            advance(session_output(0, vec![alice()]), owned),
+            Err(ConstraintCheckerError::SessionNotUpForGrabs)
+        );
+    });
+}
+
+#[test]
+fn retire_works() {
+    TestExternalities::default().execute_with(|| {
+        register(bob(), 10);
+
+        assert_eq!(
+            SimpleConstraintChecker::check(
+                &RetireCandidate::<TestConfig, 0>::default(),
+                &[candidacy(bob(), 10)],
+                &[],
+                &[retiring(bob(), 10, 0)],
+                &Default::default(),
+            ),
+            Ok(0)
+        );
+        assert!(candidates().is_empty());
+    });
+}
+
+#[test]
+fn retire_unregistered_candidacy_fails() {
+    TestExternalities::default().execute_with(|| {
+        assert_eq!(
+            SimpleConstraintChecker::check(
+                &RetireCandidate::<TestConfig, 0>::default(),
+                &[candidacy(bob(), 10)],
+                &[],
+                &[retiring(bob(), 10, 0)],
+                &Default::default(),
+            ),
+            Err(ConstraintCheckerError::NotCandidate)
+        );
+    });
+}
+
+#[test]
+fn retire_with_changed_bond_fails() {
+    TestExternalities::default().execute_with(|| {
+        register(bob(), 10);
+
+        assert_eq!(
+            SimpleConstraintChecker::check(
+                &RetireCandidate::<TestConfig, 0>::default(),
+                &[candidacy(bob(), 10)],
+                &[],
+                &[retiring(bob(), 20, 0)],
+                &Default::default(),
+            ),
+            Err(ConstraintCheckerError::RetirementMismatch)
+        );
+        assert_eq!(candidates(), BTreeMap::from([(bob(), 10)]));
+    });
+}
+
+#[test]
+fn withdraw_after_session_ends_works() {
+    TestExternalities::default().execute_with(|| {
+        set_block_height(10);
+        advance(
+            session_output(0, vec![alice()]),
+            session_output(1, vec![alice()]),
+        )
+        .unwrap();
+
+        assert_eq!(
+            SimpleConstraintChecker::check(
+                &WithdrawBond::<TestConfig, 0>::default(),
+                &[retiring(bob(), 10, 0)],
+                &[],
+                &[coin(9)],
+                &Default::default(),
+            ),
+            Ok(1)
+        );
+    });
+}
+
+#[test]
+fn withdraw_in_same_session_fails() {
+    TestExternalities::default().execute_with(|| {
+        assert_eq!(
+            SimpleConstraintChecker::check(
+                &WithdrawBond::<TestConfig, 0>::default(),
+                &[retiring(bob(), 10, 0)],
+                &[],
+                &[coin(10)],
+                &Default::default(),
+            ),
+            Err(ConstraintCheckerError::StillCollating)
+        );
+    });
+}
@@ -0,0 +1,193 @@
+//! A sanctioned way for applications to anchor arbitrary data on chain, like Bitcoin's `OP_RETURN`.
+//!
+//! A [`CarryData`] transaction spends `Coin<ID>`s to create one or more [`DataOutput`]s, each of
+//! which carries at most a configured number of bytes. The more data a transaction carries, the
+//! more of its coins it must leave over as its fee and tip, so that anchoring data is paid for by
+//! its size rather than hidden in the payloads of proofs of existence or coins.
+//!
+//! Data outputs must be locked by the [`Unspendable`] verifier, which never verifies. That makes
+//! it evident from the output alone that it can never be consumed, so wallets and indexers never
+//! mistake it for value. Because nobody can consume them, data outputs remain in the state for
+//! good. Runtimes that require deposits for new outputs keep their deposits forever as well.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use core::marker::PhantomData;
+
+use money::Coin;
+use parity_scale_codec::{Decode, Encode};
+use scale_info::TypeInfo;
+use serde::{Deserialize, Serialize};
+use sp_runtime::transaction_validity::TransactionPriority;
+use sp_std::prelude::*;
+use tuxedo_core::{
+    dynamic_typing::UtxoData,
+    ensure,
+    support_macros::{CloneNoBound, DebugNoBound, DefaultNoBound},
+    types::Output,
+    verifier::Unspendable,
+    CheckerContext, ConstraintChecker, Verifier,
+};
+
+#[cfg(test)]
+mod tests;
+
+/// Configuration items for the data carrier piece when it is
+/// instantiated in a concrete runtime.
+pub trait DataCarrierConfig {
+    /// The most bytes that a single data output may carry.
+    const MAX_DATA_LENGTH: usize = 80;
+
+    /// The value that must be left over for every byte of data carried.
+    const FEE_PER_BYTE: u128 = 1;
+}
+
+/// Arbitrary data anchored on chain by an application.
+#[derive(
+    Serialize,
+    Deserialize,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Clone,
+    Encode,
+    Decode,
+    Hash,
+    Debug,
+    TypeInfo,
+)]
+pub struct DataOutput {
+    /// The data being carried.
+    pub data: Vec<u8>,
+}
+
+impl UtxoData for DataOutput {
+    const TYPE_ID: [u8; 4] = *b"data";
+}
+
+/// Errors that can occur when creating data outputs.
+#[derive(
+    Serialize,
+    Deserialize,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Clone,
+    Encode,
+    Decode,
+    Hash,
+    Debug,
+    TypeInfo,
+)]
+pub enum ConstraintCheckerError {
+    /// An input data has the wrong type.
+    BadlyTypedInput,
+    /// An output data has the wrong type.
+    BadlyTypedOutput,
+    /// The transaction does not create any data outputs.
+    NoData,
+    /// A data output carries more than the maximum number of bytes.
+    DataTooLong,
+    /// A data output is not locked by the unspendable verifier.
+    DataNotUnspendable,
+    /// A change output has zero value.
+    ZeroValue,
+    /// The value of the inputs or outputs overflows.
+    ValueOverflow,
+    /// More value was created than was consumed.
+    OutputsExceedInputs,
+    /// Less than the fee for the carried data was left over.
+    InsufficientFee,
+}
+
+/// A constraint checker that anchors data on chain.
+///
+/// The inputs are `Coin<ID>`s that pay for the data. The outputs are the data outputs, which must
+/// be locked by the [`Unspendable`] verifier, along with any `Coin<ID>` change, in any order.
+/// At least [`DataCarrierConfig::FEE_PER_BYTE`] must be left over for every byte of data. All of
+/// the leftover value pays the transaction's fee and tip, so the block author collects it.
+#[derive(
+    Serialize,
+    Deserialize,
+    Encode,
+    Decode,
+    DebugNoBound,
+    DefaultNoBound,
+    PartialEq,
+    Eq,
+    CloneNoBound,
+    TypeInfo,
+)]
+#[scale_info(skip_type_params(T))]
+pub struct CarryData<T, const ID: u8>(PhantomData<T>);
+
+impl<T: DataCarrierConfig, V: Verifier + PartialEq + From<Unspendable>, const ID: u8>
+    ConstraintChecker<V> for CarryData<T, ID>
+{
+    type Error = ConstraintCheckerError;
+    type InherentHooks = ();
+
+    fn check(
+        &self,
+        inputs: &[Output<V>],
+        _peeks: &[Output<V>],
+        outputs: &[Output<V>],
+        _context: &CheckerContext,
+    ) -> Result<TransactionPriority, Self::Error> {
+        let total_input = inputs.iter().try_fold(0u128, |total, input| {
+            let coin = input
+                .payload
+                .extract::<Coin<ID>>()
+                .map_err(|_| ConstraintCheckerError::BadlyTypedInput)?;
+            total
+                .checked_add(coin.0)
+                .ok_or(ConstraintCheckerError::ValueOverflow)
+        })?;
+
+        let mut total_output: u128 = 0;
+        let mut data_length: u128 = 0;
+        let mut carries_data = false;
+        for output in outputs {
+            if let Ok(data_output) = output.payload.extract::<DataOutput>() {
+                ensure!(
+                    data_output.data.len() <= T::MAX_DATA_LENGTH,
+                    ConstraintCheckerError::DataTooLong
+                );
+                ensure!(
+                    output.verifier == Unspendable.into(),
+                    ConstraintCheckerError::DataNotUnspendable
+                );
+                data_length = data_length.saturating_add(data_output.data.len() as u128);
+                carries_data = true;
+            } else {
+                let coin = output
+                    .payload
+                    .extract::<Coin<ID>>()
+                    .map_err(|_| ConstraintCheckerError::BadlyTypedOutput)?;
+                ensure!(coin.0 > 0, ConstraintCheckerError::ZeroValue);
+                total_output = total_output
+                    .checked_add(coin.0)
+                    .ok_or(ConstraintCheckerError::ValueOverflow)?;
+            }
+        }
+        ensure!(carries_data, ConstraintCheckerError::NoData);
+
+        ensure!(
+            total_output <= total_input,
+            ConstraintCheckerError::OutputsExceedInputs
+        );
+        let burned = total_input - total_output;
+        ensure!(
+            burned >= data_length.saturating_mul(T::FEE_PER_BYTE),
+            ConstraintCheckerError::InsufficientFee
+        );
+
+        Ok(burned.try_into().unwrap_or(TransactionPriority::MAX))
+    }
+
+    fn is_inherent(&self) -> bool {
+        false
+    }
+}
@@ -0,0 +1,150 @@
+//! Unit tests for the Data Carrier piece
+
+use super::*;
+use sp_core::H256;
+use tuxedo_core::{dynamic_typing::testing::Bogus, tuxedo_verifier, verifier::SigCheck};
+
+/// A verifier that can represent coin owners as well as data outputs.
+#[derive(Serialize, Deserialize, Encode, Decode, Debug, PartialEq, Eq, Clone, TypeInfo)]
+#[tuxedo_verifier]
+pub enum TestVerifier {
+    SigCheck(SigCheck),
+    Unspendable(Unspendable),
+}
+
+/// A mock config with short data and a fee of two per byte.
+pub struct TestConfig;
+
+impl DataCarrierConfig for TestConfig {
+    const MAX_DATA_LENGTH: usize = 8;
+    const FEE_PER_BYTE: u128 = 2;
+}
+
+fn owned() -> TestVerifier {
+    SigCheck::new(H256::repeat_byte(1)).into()
+}
+
+fn coin(value: u128) -> Output<TestVerifier> {
+    (Coin::<0>(value), owned()).into()
+}
+
+fn data(bytes: &[u8]) -> Output<TestVerifier> {
+    (
+        DataOutput {
+            data: bytes.to_vec(),
+        },
+        Unspendable.into(),
+    )
+        .into()
+}
+
+fn carry(
+    inputs: &[Output<TestVerifier>],
+    outputs: &[Output<TestVerifier>],
+) -> Result<TransactionPriority, ConstraintCheckerError> {
+    CarryData::<TestConfig, 0>::default().check(inputs, &[], outputs, &Default::default())
+}
+
+#[test]
+fn carry_data_works() {
+    assert_eq!(carry(&[coin(12)], &[data(b"hello"), coin(1)]), Ok(11));
+}
+
+#[test]
+fn carry_data_in_many_outputs_works() {
+    assert_eq!(
+        carry(
+            &[coin(10), coin(10)],
+            &[coin(2), data(b"abc"), data(b"defg")]
+        ),
+        Ok(18)
+    );
+}
+
+#[test]
+fn carry_empty_data_is_free() {
+    assert_eq!(carry(&[], &[data(b"")]), Ok(0));
+}
+
+#[test]
+fn carry_nothing_fails() {
+    assert_eq!(
+        carry(&[coin(10)], &[coin(5)]),
+        Err(ConstraintCheckerError::NoData)
+    );
+}
+
+#[test]
+fn carry_too_much_data_fails() {
+    assert_eq!(
+        carry(&[coin(100)], &[data(b"too long!")]),
+        Err(ConstraintCheckerError::DataTooLong)
+    );
+}
+
+#[test]
+fn carry_spendable_data_fails() {
+    let spendable = (
+        DataOutput {
+            data: b"hello".to_vec(),
+        },
+        owned(),
+    )
+        .into();
+
+    assert_eq!(
+        carry(&[coin(10)], &[spendable]),
+        Err(ConstraintCheckerError::DataNotUnspendable)
+    );
+}
+
+#[test]
+fn carry_with_insufficient_fee_fails() {
+    assert_eq!(
+        carry(&[coin(10)], &[data(b"hello"), coin(1)]),
+        Err(ConstraintCheckerError::InsufficientFee)
+    );
+}
+
+#[test]
+fn carry_creating_value_fails() {
+    assert_eq!(
+        carry(&[coin(10)], &[data(b""), coin(11)]),
+        Err(ConstraintCheckerError::OutputsExceedInputs)
+    );
+}
+
+#[test]
+fn carry_with_zero_change_fails() {
+    assert_eq!(
+        carry(&[coin(10)], &[data(b"hello"), coin(0)]),
+        Err(ConstraintCheckerError::ZeroValue)
+    );
+}
+
+#[test]
+fn carry_paid_with_something_else_fails() {
+    let bogus = (Bogus, owned()).into();
+
+    assert_eq!(
+        carry(&[bogus], &[data(b"")]),
+        Err(ConstraintCheckerError::BadlyTypedInput)
+    );
+}
+
+#[test]
+fn carry_creating_something_else_fails() {
+    let bogus = (Bogus, owned()).into();
+
+    assert_eq!(
+        carry(&[coin(10)], &[data(b""), bogus]),
+        Err(ConstraintCheckerError::BadlyTypedOutput)
+    );
+}
+
+#[test]
+fn data_outputs_can_never_be_consumed() {
+    assert!(!data(b"hello")
+        .verifier
+        .verify(&[], &[], &Default::default()));
+}
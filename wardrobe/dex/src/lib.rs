@@ -0,0 +1,359 @@
+//! An on-chain order book exchange between `Coin<A>` and `Coin<B>`.
+//!
+//! Traders place limit [`Order`]s that lock up the coins they are selling and record the
+//! minimum price they will accept along with the verifier their proceeds should be locked to.
+//! The order book is simply the set of order UTXOs in state. Anyone may match a pair of
+//! compatible orders on opposite sides of the book, and both traders are guaranteed to receive
+//! at least their limit price. Orders may be partially filled, in which case a reduced order
+//! at the same price is re-created. Any spread between the two limit prices is left over as the
+//! matching transaction's tip for the block author, which rewards matching the most profitable
+//! orders first.
+//!
+//! Orders selling A for B are placed and cancelled through `Dex<A, B>`, while orders selling
+//! B for A are placed and cancelled through `Dex<B, A>`. Matching is done through `Dex<A, B>`
+//! with the order selling A first. A runtime that wants a market in both directions should
+//! include both instantiations.
+//!
+//! Order outputs should be protected by the `UpForGrabs` verifier so that anyone can match
+//! them. Because of this, anyone can also cancel an order. However, the refund always goes
+//! to the order's owner, so the worst a griefer can do is force the owner to re-place it.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use money::Coin;
+use parity_scale_codec::{Decode, Encode};
+use scale_info::TypeInfo;
+use serde::{Deserialize, Serialize};
+use sp_runtime::transaction_validity::TransactionPriority;
+use sp_std::prelude::*;
+use tuxedo_core::{
+    dynamic_typing::UtxoData, ensure, types::Output, CheckerContext, ConstraintChecker, Verifier,
+};
+
+#[cfg(test)]
+mod tests;
+
+/// A limit order to sell `Coin<SELL>` for `Coin<BUY>`.
+///
+/// The limit price is expressed as a ratio: the owner wants at least `price_buy` units of
+/// the bought asset for every `price_sell` units of the sold asset.
+#[derive(
+    Serialize,
+    Deserialize,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Clone,
+    Encode,
+    Decode,
+    Hash,
+    Debug,
+    TypeInfo,
+)]
+pub struct Order<V, const SELL: u8, const BUY: u8> {
+    /// The amount of the sold asset that is still available in this order.
+    pub amount: u128,
+    /// The sold side of the limit price ratio.
+    pub price_sell: u128,
+    /// The bought side of the limit price ratio.
+    pub price_buy: u128,
+    /// The verifier that proceeds and refunds must be locked to.
+    pub owner: V,
+}
+
+impl<V, const SELL: u8, const BUY: u8> Order<V, SELL, BUY> {
+    /// Check that the owner receives at least their limit price when
+    /// `sold` units of this order are exchanged for `received` units.
+    fn is_satisfied(&self, sold: u128, received: u128) -> Result<bool, ConstraintCheckerError> {
+        let received_scaled = received
+            .checked_mul(self.price_sell)
+            .ok_or(ConstraintCheckerError::ValueOverflow)?;
+        let asked_scaled = sold
+            .checked_mul(self.price_buy)
+            .ok_or(ConstraintCheckerError::ValueOverflow)?;
+        Ok(received_scaled >= asked_scaled)
+    }
+}
+
+impl<V: Encode + Decode, const SELL: u8, const BUY: u8> UtxoData for Order<V, SELL, BUY> {
+    const TYPE_ID: [u8; 4] = [b'o', b'r', SELL, BUY];
+}
+
+/// Errors that can occur when checking exchange transactions.
+#[derive(
+    Serialize,
+    Deserialize,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Clone,
+    Encode,
+    Decode,
+    Hash,
+    Debug,
+    TypeInfo,
+)]
+pub enum ConstraintCheckerError {
+    /// An input data has the wrong type.
+    BadlyTypedInput,
+    /// An output data has the wrong type.
+    BadlyTypedOutput,
+    /// Wrong number of inputs were provided to the constraint checker.
+    WrongNumberInputs,
+    /// Wrong number of outputs were provided to the constraint checker.
+    WrongNumberOutputs,
+    /// An order or coin with zero value was created. This is not allowed because it wastes state space.
+    ZeroValue,
+    /// An order's price has a zero on one side of the ratio.
+    InvalidPrice,
+    /// The value consumed or created by this transaction overflows the value type.
+    ValueOverflow,
+    /// More value was created than was consumed.
+    OutputsExceedInputs,
+    /// The reduced order created by a partial fill differs from the original in something other than its amount.
+    RemainderMismatch,
+    /// One of the matched orders would receive less than its limit price.
+    LimitPriceNotMet,
+    /// Nothing was exchanged between the matched orders.
+    NothingMatched,
+    /// Coins were locked to some verifier other than the owner who should receive them.
+    PaidToWrongVerifier,
+}
+
+/// Sum the value of some outputs, making sure they are all `Coin<ID>`s with non-zero value.
+fn sum_coins<'a, V: 'a, const ID: u8>(
+    coins: impl IntoIterator<Item = &'a Output<V>>,
+    error: ConstraintCheckerError,
+) -> Result<u128, ConstraintCheckerError> {
+    coins.into_iter().try_fold(0u128, |total, coin| {
+        let value = coin
+            .payload
+            .extract::<Coin<ID>>()
+            .map_err(|_| error.clone())?
+            .0;
+        ensure!(value > 0, ConstraintCheckerError::ZeroValue);
+        total
+            .checked_add(value)
+            .ok_or(ConstraintCheckerError::ValueOverflow)
+    })
+}
+
+/// Check that a reduced order matches the original in everything but its amount, and is
+/// protected by the same verifier. Returns the amount that was sold out of the original.
+fn check_remainder<V: PartialEq, const SELL: u8, const BUY: u8>(
+    original: &Order<V, SELL, BUY>,
+    original_verifier: &V,
+    remainder: Option<(Order<V, SELL, BUY>, &V)>,
+) -> Result<u128, ConstraintCheckerError> {
+    let left = match remainder {
+        Some((reduced, verifier)) => {
+            ensure!(
+                reduced.price_sell == original.price_sell
+                    && reduced.price_buy == original.price_buy
+                    && reduced.owner == original.owner
+                    && verifier == original_verifier,
+                ConstraintCheckerError::RemainderMismatch
+            );
+            ensure!(reduced.amount > 0, ConstraintCheckerError::ZeroValue);
+            reduced.amount
+        }
+        None => 0,
+    };
+
+    original
+        .amount
+        .checked_sub(left)
+        .ok_or(ConstraintCheckerError::OutputsExceedInputs)
+}
+
+/// The constraint checkers for the `Coin<A>` / `Coin<B>` market.
+#[derive(
+    Serialize,
+    Deserialize,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Clone,
+    Encode,
+    Decode,
+    Hash,
+    Debug,
+    TypeInfo,
+)]
+pub enum Dex<const A: u8, const B: u8> {
+    /// Lock some `Coin<A>` into new orders selling A for B.
+    ///
+    /// All inputs are `Coin<A>`s. Outputs are orders, and optionally `Coin<A>` change.
+    /// The seller pays the transaction's fee and tip with whatever `Coin<A>` is left over.
+    PlaceOrder,
+    /// Cancel an order selling A for B and refund the remaining `Coin<A>` to its owner.
+    CancelOrder,
+    /// Match an order selling A for B against an order selling B for A.
+    ///
+    /// The first input is the order selling A and the second is the order selling B.
+    /// The outputs are the `Coin<B>`s paid to the first order's owner, the `Coin<A>`s paid
+    /// to the second order's owner, and at most one reduced order for each side that is
+    /// only partially filled. Any value sold out of an order but not paid to the other
+    /// owner is the spread, which pays the transaction's fee and tip.
+    MatchOrders,
+}
+
+impl<V, const A: u8, const B: u8> ConstraintChecker<V> for Dex<A, B>
+where
+    V: Verifier + PartialEq,
+{
+    type Error = ConstraintCheckerError;
+    type InherentHooks = ();
+
+    fn check(
+        &self,
+        inputs: &[Output<V>],
+        _peeks: &[Output<V>],
+        outputs: &[Output<V>],
+        _context: &CheckerContext,
+    ) -> Result<TransactionPriority, Self::Error> {
+        match self {
+            Self::PlaceOrder => {
+                let total_input =
+                    sum_coins::<V, A>(inputs, ConstraintCheckerError::BadlyTypedInput)?;
+
+                let mut placed: u128 = 0;
+                let mut change = Vec::new();
+                for output in outputs {
+                    if let Ok(order) = output.payload.extract::<Order<V, A, B>>() {
+                        ensure!(order.amount > 0, ConstraintCheckerError::ZeroValue);
+                        ensure!(
+                            order.price_sell > 0 && order.price_buy > 0,
+                            ConstraintCheckerError::InvalidPrice
+                        );
+                        placed = placed
+                            .checked_add(order.amount)
+                            .ok_or(ConstraintCheckerError::ValueOverflow)?;
+                    } else {
+                        change.push(output);
+                    }
+                }
+                ensure!(placed > 0, ConstraintCheckerError::WrongNumberOutputs);
+
+                let total_output =
+                    sum_coins::<V, A>(change, ConstraintCheckerError::BadlyTypedOutput)?
+                        .checked_add(placed)
+                        .ok_or(ConstraintCheckerError::ValueOverflow)?;
+                ensure!(
+                    total_output <= total_input,
+                    ConstraintCheckerError::OutputsExceedInputs
+                );
+
+                let burned = total_input - total_output;
+                Ok(burned.try_into().unwrap_or(TransactionPriority::MAX))
+            }
+            Self::CancelOrder => {
+                ensure!(inputs.len() == 1, ConstraintCheckerError::WrongNumberInputs);
+                let order = inputs[0]
+                    .payload
+                    .extract::<Order<V, A, B>>()
+                    .map_err(|_| ConstraintCheckerError::BadlyTypedInput)?;
+
+                for output in outputs {
+                    ensure!(
+                        output.verifier == order.owner,
+                        ConstraintCheckerError::PaidToWrongVerifier
+                    );
+                }
+                let refunded =
+                    sum_coins::<V, A>(outputs, ConstraintCheckerError::BadlyTypedOutput)?;
+                ensure!(
+                    refunded <= order.amount,
+                    ConstraintCheckerError::OutputsExceedInputs
+                );
+
+                let burned = order.amount - refunded;
+                Ok(burned.try_into().unwrap_or(TransactionPriority::MAX))
+            }
+            Self::MatchOrders => {
+                ensure!(inputs.len() == 2, ConstraintCheckerError::WrongNumberInputs);
+                let sells_a = inputs[0]
+                    .payload
+                    .extract::<Order<V, A, B>>()
+                    .map_err(|_| ConstraintCheckerError::BadlyTypedInput)?;
+                let sells_b = inputs[1]
+                    .payload
+                    .extract::<Order<V, B, A>>()
+                    .map_err(|_| ConstraintCheckerError::BadlyTypedInput)?;
+
+                // Sort the outputs into payments and reduced orders.
+                let mut paid_a = Vec::new();
+                let mut paid_b = Vec::new();
+                let mut remainder_a = None;
+                let mut remainder_b = None;
+                for output in outputs {
+                    if output.payload.type_id == <Coin<A> as UtxoData>::TYPE_ID {
+                        ensure!(
+                            output.verifier == sells_b.owner,
+                            ConstraintCheckerError::PaidToWrongVerifier
+                        );
+                        paid_a.push(output);
+                    } else if output.payload.type_id == <Coin<B> as UtxoData>::TYPE_ID {
+                        ensure!(
+                            output.verifier == sells_a.owner,
+                            ConstraintCheckerError::PaidToWrongVerifier
+                        );
+                        paid_b.push(output);
+                    } else if let Ok(reduced) = output.payload.extract::<Order<V, A, B>>() {
+                        ensure!(
+                            remainder_a.is_none(),
+                            ConstraintCheckerError::WrongNumberOutputs
+                        );
+                        remainder_a = Some((reduced, &output.verifier));
+                    } else {
+                        let reduced = output
+                            .payload
+                            .extract::<Order<V, B, A>>()
+                            .map_err(|_| ConstraintCheckerError::BadlyTypedOutput)?;
+                        ensure!(
+                            remainder_b.is_none(),
+                            ConstraintCheckerError::WrongNumberOutputs
+                        );
+                        remainder_b = Some((reduced, &output.verifier));
+                    }
+                }
+
+                let sold_a = check_remainder(&sells_a, &inputs[0].verifier, remainder_a)?;
+                let sold_b = check_remainder(&sells_b, &inputs[1].verifier, remainder_b)?;
+                let received_a =
+                    sum_coins::<V, A>(paid_a, ConstraintCheckerError::BadlyTypedOutput)?;
+                let received_b =
+                    sum_coins::<V, B>(paid_b, ConstraintCheckerError::BadlyTypedOutput)?;
+
+                // Make sure something was actually exchanged.
+                ensure!(
+                    received_a > 0 && received_b > 0,
+                    ConstraintCheckerError::NothingMatched
+                );
+
+                // Make sure each side pays no more than was sold out of its order.
+                ensure!(
+                    received_a <= sold_a && received_b <= sold_b,
+                    ConstraintCheckerError::OutputsExceedInputs
+                );
+
+                // Make sure both owners get at least their limit price.
+                ensure!(
+                    sells_a.is_satisfied(sold_a, received_b)?
+                        && sells_b.is_satisfied(sold_b, received_a)?,
+                    ConstraintCheckerError::LimitPriceNotMet
+                );
+
+                let burned = (sold_a - received_a).saturating_add(sold_b - received_b);
+                Ok(burned.try_into().unwrap_or(TransactionPriority::MAX))
+            }
+        }
+    }
+
+    fn is_inherent(&self) -> bool {
+        false
+    }
+}
@@ -0,0 +1,236 @@
+//! Unit tests for the Dex piece
+
+use super::*;
+use sp_core::H256;
+use tuxedo_core::{dynamic_typing::testing::Bogus, verifier::SigCheck};
+
+type Market = Dex<0, 1>;
+
+fn alice() -> SigCheck {
+    SigCheck::new(H256::repeat_byte(1))
+}
+
+fn bob() -> SigCheck {
+    SigCheck::new(H256::repeat_byte(2))
+}
+
+/// Alice's order selling `amount` of asset 0 for at least two units of asset 1 each.
+fn sells_a(amount: u128) -> Order<SigCheck, 0, 1> {
+    Order {
+        amount,
+        price_sell: 1,
+        price_buy: 2,
+        owner: alice(),
+    }
+}
+
+/// Bob's order selling `amount` of asset 1 for at least one unit of asset 0 per three.
+fn sells_b(amount: u128) -> Order<SigCheck, 1, 0> {
+    Order {
+        amount,
+        price_sell: 3,
+        price_buy: 1,
+        owner: bob(),
+    }
+}
+
+fn check(
+    checker: Market,
+    inputs: Vec<Output<SigCheck>>,
+    outputs: Vec<Output<SigCheck>>,
+) -> Result<TransactionPriority, ConstraintCheckerError> {
+    ConstraintChecker::<SigCheck>::check(&checker, &inputs, &[], &outputs, &Default::default())
+}
+
+#[test]
+fn place_order_works() {
+    let inputs = vec![(Coin::<0>(100), alice()).into()];
+    let outputs = vec![
+        (sells_a(60), alice()).into(),
+        (Coin::<0>(39), alice()).into(),
+    ];
+
+    assert_eq!(check(Market::PlaceOrder, inputs, outputs), Ok(1));
+}
+
+#[test]
+fn place_order_with_zero_price_fails() {
+    let inputs = vec![(Coin::<0>(100), alice()).into()];
+    let mut order = sells_a(60);
+    order.price_buy = 0;
+    let outputs = vec![(order, alice()).into()];
+
+    assert_eq!(
+        check(Market::PlaceOrder, inputs, outputs),
+        Err(ConstraintCheckerError::InvalidPrice)
+    );
+}
+
+#[test]
+fn place_order_exceeding_inputs_fails() {
+    let inputs = vec![(Coin::<0>(50), alice()).into()];
+    let outputs = vec![(sells_a(60), alice()).into()];
+
+    assert_eq!(
+        check(Market::PlaceOrder, inputs, outputs),
+        Err(ConstraintCheckerError::OutputsExceedInputs)
+    );
+}
+
+#[test]
+fn place_order_selling_wrong_asset_fails() {
+    let inputs = vec![(Coin::<1>(100), alice()).into()];
+    let outputs = vec![(sells_a(60), alice()).into()];
+
+    assert_eq!(
+        check(Market::PlaceOrder, inputs, outputs),
+        Err(ConstraintCheckerError::BadlyTypedInput)
+    );
+}
+
+#[test]
+fn cancel_order_works() {
+    let inputs = vec![(sells_a(60), alice()).into()];
+    let outputs = vec![(Coin::<0>(60), alice()).into()];
+
+    assert_eq!(check(Market::CancelOrder, inputs, outputs), Ok(0));
+}
+
+#[test]
+fn cancel_order_refunding_wrong_verifier_fails() {
+    let inputs = vec![(sells_a(60), alice()).into()];
+    let outputs = vec![(Coin::<0>(60), bob()).into()];
+
+    assert_eq!(
+        check(Market::CancelOrder, inputs, outputs),
+        Err(ConstraintCheckerError::PaidToWrongVerifier)
+    );
+}
+
+#[test]
+fn match_full_fill_works() {
+    let inputs = vec![(sells_a(10), alice()).into(), (sells_b(30), bob()).into()];
+    let outputs = vec![
+        (Coin::<1>(30), alice()).into(),
+        (Coin::<0>(10), bob()).into(),
+    ];
+
+    assert_eq!(check(Market::MatchOrders, inputs, outputs), Ok(0));
+}
+
+#[test]
+fn match_burning_spread_works() {
+    let inputs = vec![(sells_a(10), alice()).into(), (sells_b(30), bob()).into()];
+    let outputs = vec![
+        (Coin::<1>(20), alice()).into(),
+        (Coin::<0>(10), bob()).into(),
+    ];
+
+    assert_eq!(check(Market::MatchOrders, inputs, outputs), Ok(10));
+}
+
+#[test]
+fn match_partial_fill_works() {
+    let inputs = vec![(sells_a(10), alice()).into(), (sells_b(12), bob()).into()];
+    let outputs = vec![
+        (Coin::<1>(12), alice()).into(),
+        (Coin::<0>(5), bob()).into(),
+        (sells_a(5), alice()).into(),
+    ];
+
+    assert_eq!(check(Market::MatchOrders, inputs, outputs), Ok(0));
+}
+
+#[test]
+fn match_below_limit_price_fails() {
+    let inputs = vec![(sells_a(10), alice()).into(), (sells_b(30), bob()).into()];
+    let outputs = vec![
+        (Coin::<1>(19), alice()).into(),
+        (Coin::<0>(10), bob()).into(),
+        (sells_b(11), bob()).into(),
+    ];
+
+    assert_eq!(
+        check(Market::MatchOrders, inputs, outputs),
+        Err(ConstraintCheckerError::LimitPriceNotMet)
+    );
+}
+
+#[test]
+fn match_without_remainder_paying_too_much_fails() {
+    let inputs = vec![(sells_a(10), alice()).into(), (sells_b(12), bob()).into()];
+    let outputs = vec![
+        (Coin::<1>(13), alice()).into(),
+        (Coin::<0>(5), bob()).into(),
+    ];
+
+    assert_eq!(
+        check(Market::MatchOrders, inputs, outputs),
+        Err(ConstraintCheckerError::OutputsExceedInputs)
+    );
+}
+
+#[test]
+fn match_changing_remainder_price_fails() {
+    let inputs = vec![(sells_a(10), alice()).into(), (sells_b(12), bob()).into()];
+    let mut reduced = sells_a(5);
+    reduced.price_buy = 1;
+    let outputs = vec![
+        (Coin::<1>(12), alice()).into(),
+        (Coin::<0>(5), bob()).into(),
+        (reduced, alice()).into(),
+    ];
+
+    assert_eq!(
+        check(Market::MatchOrders, inputs, outputs),
+        Err(ConstraintCheckerError::RemainderMismatch)
+    );
+}
+
+#[test]
+fn match_paying_wrong_owner_fails() {
+    let inputs = vec![(sells_a(10), alice()).into(), (sells_b(30), bob()).into()];
+    let outputs = vec![(Coin::<1>(30), bob()).into(), (Coin::<0>(10), bob()).into()];
+
+    assert_eq!(
+        check(Market::MatchOrders, inputs, outputs),
+        Err(ConstraintCheckerError::PaidToWrongVerifier)
+    );
+}
+
+#[test]
+fn match_same_side_orders_fails() {
+    let inputs = vec![(sells_a(10), alice()).into(), (sells_a(10), alice()).into()];
+    let outputs = vec![];
+
+    assert_eq!(
+        check(Market::MatchOrders, inputs, outputs),
+        Err(ConstraintCheckerError::BadlyTypedInput)
+    );
+}
+
+#[test]
+fn match_nothing_fails() {
+    let inputs = vec![(sells_a(10), alice()).into(), (sells_b(30), bob()).into()];
+    let outputs = vec![(sells_a(10), alice()).into(), (sells_b(30), bob()).into()];
+
+    assert_eq!(
+        check(Market::MatchOrders, inputs, outputs),
+        Err(ConstraintCheckerError::NothingMatched)
+    );
+}
+
+#[test]
+fn match_with_bogus_output_fails() {
+    let inputs = vec![(sells_a(10), alice()).into(), (sells_b(30), bob()).into()];
+    let outputs = vec![
+        (Coin::<1>(30), alice()).into(),
+        (Coin::<0>(10), bob()).into(),
+        (Bogus, bob()).into(),
+    ];
+
+    assert_eq!(
+        check(Market::MatchOrders, inputs, outputs),
+        Err(ConstraintCheckerError::BadlyTypedOutput)
+    );
+}
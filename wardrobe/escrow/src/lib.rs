@@ -0,0 +1,415 @@
+//! Escrowed payments in the tokens provided by the money piece.
+//!
+//! A buyer locks a payment into an [`Escrow`] that names the buyer, the seller, and an arbiter
+//! who both parties trust to settle disputes. The escrow can then be settled in three ways.
+//! 1. The buyer and seller agree on who gets the funds and both sign the decision. Typically
+//!    this releases the payment to the seller once the goods are delivered.
+//! 2. The arbiter decides who gets the funds and signs the decision alone.
+//! 3. The timeout passes without a settlement and the funds are refunded to the buyer.
+//!
+//! Escrow outputs should be protected by the `UpForGrabs` verifier. The constraint checkers,
+//! rather than the verifier, check the relevant signatures and guarantee the funds are paid to
+//! the right party. The signatures are passed as fields of the constraint checker and are
+//! made over the escrow's full terms along with the decision. Escrows with identical terms
+//! should be given distinct nonces so that a signature for one cannot be replayed on another.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use core::marker::PhantomData;
+
+use money::Coin;
+use parity_scale_codec::{Decode, Encode};
+use scale_info::TypeInfo;
+use serde::{Deserialize, Serialize};
+use sp_core::{
+    sr25519::{Public, Signature},
+    H256,
+};
+use sp_runtime::transaction_validity::TransactionPriority;
+use sp_std::prelude::*;
+use tuxedo_core::{
+    dynamic_typing::UtxoData,
+    ensure,
+    support_macros::{CloneNoBound, DebugNoBound, DefaultNoBound},
+    types::Output,
+    verifier::SigCheck,
+    CheckerContext, ConstraintChecker, Verifier,
+};
+
+#[cfg(test)]
+mod tests;
+
+/// Configuration items for the Escrow piece when it is
+/// instantiated in a concrete runtime.
+pub trait EscrowConfig {
+    /// A means of getting the current block height.
+    /// Probably this will be the Tuxedo Executive
+    fn block_height() -> u32;
+}
+
+/// One of the two parties to an escrowed payment.
+#[derive(
+    Serialize,
+    Deserialize,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Clone,
+    Encode,
+    Decode,
+    Hash,
+    Debug,
+    TypeInfo,
+)]
+pub enum Party {
+    Buyer,
+    Seller,
+}
+
+/// A payment of `Coin<ID>` held in escrow.
+#[derive(
+    Serialize,
+    Deserialize,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Clone,
+    Encode,
+    Decode,
+    Hash,
+    Debug,
+    TypeInfo,
+)]
+pub struct Escrow<const ID: u8> {
+    /// The escrowed value.
+    pub amount: u128,
+    /// The party paying. Refunds go to this key.
+    pub buyer: H256,
+    /// The party being paid. Releases go to this key.
+    pub seller: H256,
+    /// The party who settles disputes.
+    pub arbiter: H256,
+    /// The block height from which the buyer may reclaim the funds.
+    pub timeout: u32,
+    /// Distinguishes escrows that otherwise have identical terms.
+    pub nonce: u32,
+}
+
+impl<const ID: u8> Escrow<ID> {
+    /// The key of the given party.
+    pub fn key_of(&self, party: &Party) -> H256 {
+        match party {
+            Party::Buyer => self.buyer,
+            Party::Seller => self.seller,
+        }
+    }
+
+    /// The message that must be signed to award this escrow to the given party.
+    pub fn settlement_message(&self, award: &Party) -> Vec<u8> {
+        (b"escrow", self, award).encode()
+    }
+
+    /// Check that the given key signed the decision to award this escrow to the given party.
+    fn check_signature(&self, award: &Party, signer: H256, signature: &Signature) -> bool {
+        sp_io::crypto::sr25519_verify(
+            signature,
+            &self.settlement_message(award),
+            &Public::from_h256(signer),
+        )
+    }
+}
+
+impl<const ID: u8> UtxoData for Escrow<ID> {
+    const TYPE_ID: [u8; 4] = [b'e', b's', b'c', ID];
+}
+
+/// Errors that can occur when checking escrow transactions.
+#[derive(
+    Serialize,
+    Deserialize,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Clone,
+    Encode,
+    Decode,
+    Hash,
+    Debug,
+    TypeInfo,
+)]
+pub enum ConstraintCheckerError {
+    /// An input data has the wrong type.
+    BadlyTypedInput,
+    /// An output data has the wrong type.
+    BadlyTypedOutput,
+    /// Wrong number of inputs were provided to the constraint checker.
+    WrongNumberInputs,
+    /// The transaction does not lock anything in escrow.
+    NothingLocked,
+    /// An escrow or coin with zero value was created. This is not allowed because it wastes state space.
+    ZeroValue,
+    /// The value consumed or created by this transaction overflows the value type.
+    ValueOverflow,
+    /// More value was created than was consumed.
+    OutputsExceedInputs,
+    /// A new escrow has a timeout that has already passed.
+    TimeoutInPast,
+    /// The escrow has not timed out yet, so it cannot be unilaterally refunded.
+    NotExpired,
+    /// A required signature on the settlement decision is missing or invalid.
+    BadSignature,
+    /// The settled coins were locked to some verifier other than the awarded party's.
+    PaidToWrongVerifier,
+}
+
+/// Sum the value of some outputs, making sure they are all `Coin<ID>`s with non-zero value.
+fn sum_coins<'a, V: 'a, const ID: u8>(
+    coins: impl IntoIterator<Item = &'a Output<V>>,
+    error: ConstraintCheckerError,
+) -> Result<u128, ConstraintCheckerError> {
+    coins.into_iter().try_fold(0u128, |total, coin| {
+        let value = coin
+            .payload
+            .extract::<Coin<ID>>()
+            .map_err(|_| error.clone())?
+            .0;
+        ensure!(value > 0, ConstraintCheckerError::ZeroValue);
+        total
+            .checked_add(value)
+            .ok_or(ConstraintCheckerError::ValueOverflow)
+    })
+}
+
+/// Consume the single escrow input and pay its value out to the awarded party.
+/// Returns the escrow along with the amount that the awarded party leaves to pay the fee and tip.
+fn settle<V, const ID: u8>(
+    inputs: &[Output<V>],
+    outputs: &[Output<V>],
+    award: &Party,
+) -> Result<(Escrow<ID>, u128), ConstraintCheckerError>
+where
+    V: Verifier + PartialEq + From<SigCheck>,
+{
+    ensure!(inputs.len() == 1, ConstraintCheckerError::WrongNumberInputs);
+    let escrow = inputs[0]
+        .payload
+        .extract::<Escrow<ID>>()
+        .map_err(|_| ConstraintCheckerError::BadlyTypedInput)?;
+
+    let payee: V = SigCheck::new(escrow.key_of(award)).into();
+    for output in outputs {
+        ensure!(
+            output.verifier == payee,
+            ConstraintCheckerError::PaidToWrongVerifier
+        );
+    }
+    let paid = sum_coins::<V, ID>(outputs, ConstraintCheckerError::BadlyTypedOutput)?;
+    ensure!(
+        paid <= escrow.amount,
+        ConstraintCheckerError::OutputsExceedInputs
+    );
+
+    let burned = escrow.amount - paid;
+    Ok((escrow, burned))
+}
+
+/// A constraint checker that locks `Coin<ID>`s into new escrows.
+///
+/// All inputs are `Coin<ID>`s. Outputs are escrows, and optionally `Coin<ID>` change.
+/// The buyer pays the transaction's fee and tip with whatever is not escrowed or returned.
+#[derive(
+    Serialize,
+    Deserialize,
+    Encode,
+    Decode,
+    DebugNoBound,
+    DefaultNoBound,
+    PartialEq,
+    Eq,
+    CloneNoBound,
+    TypeInfo,
+)]
+#[scale_info(skip_type_params(T))]
+pub struct LockEscrow<T, const ID: u8>(PhantomData<T>);
+
+impl<T: EscrowConfig, V: Verifier, const ID: u8> ConstraintChecker<V> for LockEscrow<T, ID> {
+    type Error = ConstraintCheckerError;
+    type InherentHooks = ();
+
+    fn check(
+        &self,
+        inputs: &[Output<V>],
+        _peeks: &[Output<V>],
+        outputs: &[Output<V>],
+        _context: &CheckerContext,
+    ) -> Result<TransactionPriority, Self::Error> {
+        let total_input = sum_coins::<V, ID>(inputs, ConstraintCheckerError::BadlyTypedInput)?;
+
+        let mut locked: u128 = 0;
+        let mut change = Vec::new();
+        for output in outputs {
+            if let Ok(escrow) = output.payload.extract::<Escrow<ID>>() {
+                ensure!(escrow.amount > 0, ConstraintCheckerError::ZeroValue);
+                ensure!(
+                    escrow.timeout > T::block_height(),
+                    ConstraintCheckerError::TimeoutInPast
+                );
+                locked = locked
+                    .checked_add(escrow.amount)
+                    .ok_or(ConstraintCheckerError::ValueOverflow)?;
+            } else {
+                change.push(output);
+            }
+        }
+        ensure!(locked > 0, ConstraintCheckerError::NothingLocked);
+
+        let total_output = sum_coins::<V, ID>(change, ConstraintCheckerError::BadlyTypedOutput)?
+            .checked_add(locked)
+            .ok_or(ConstraintCheckerError::ValueOverflow)?;
+        ensure!(
+            total_output <= total_input,
+            ConstraintCheckerError::OutputsExceedInputs
+        );
+
+        let burned = total_input - total_output;
+        Ok(burned.try_into().unwrap_or(TransactionPriority::MAX))
+    }
+
+    fn is_inherent(&self) -> bool {
+        false
+    }
+}
+
+/// A constraint checker that settles an escrow by agreement between the buyer and the seller.
+///
+/// The only input is the escrow. All outputs are `Coin<ID>`s locked to the awarded party.
+#[derive(Serialize, Deserialize, Encode, Decode, Debug, PartialEq, Eq, Clone, TypeInfo)]
+pub struct MutualRelease<const ID: u8> {
+    /// The party that both the buyer and seller agree should receive the funds.
+    pub award: Party,
+    /// The buyer's signature over the settlement message.
+    pub buyer_signature: Signature,
+    /// The seller's signature over the settlement message.
+    pub seller_signature: Signature,
+}
+
+impl<V, const ID: u8> ConstraintChecker<V> for MutualRelease<ID>
+where
+    V: Verifier + PartialEq + From<SigCheck>,
+{
+    type Error = ConstraintCheckerError;
+    type InherentHooks = ();
+
+    fn check(
+        &self,
+        inputs: &[Output<V>],
+        _peeks: &[Output<V>],
+        outputs: &[Output<V>],
+        _context: &CheckerContext,
+    ) -> Result<TransactionPriority, Self::Error> {
+        let (escrow, burned) = settle::<V, ID>(inputs, outputs, &self.award)?;
+
+        ensure!(
+            escrow.check_signature(&self.award, escrow.buyer, &self.buyer_signature)
+                && escrow.check_signature(&self.award, escrow.seller, &self.seller_signature),
+            ConstraintCheckerError::BadSignature
+        );
+
+        Ok(burned.try_into().unwrap_or(TransactionPriority::MAX))
+    }
+
+    fn is_inherent(&self) -> bool {
+        false
+    }
+}
+
+/// A constraint checker that settles a disputed escrow by the arbiter's decision.
+///
+/// The only input is the escrow. All outputs are `Coin<ID>`s locked to the awarded party.
+#[derive(Serialize, Deserialize, Encode, Decode, Debug, PartialEq, Eq, Clone, TypeInfo)]
+pub struct ArbiterRelease<const ID: u8> {
+    /// The party that the arbiter decided should receive the funds.
+    pub award: Party,
+    /// The arbiter's signature over the settlement message.
+    pub arbiter_signature: Signature,
+}
+
+impl<V, const ID: u8> ConstraintChecker<V> for ArbiterRelease<ID>
+where
+    V: Verifier + PartialEq + From<SigCheck>,
+{
+    type Error = ConstraintCheckerError;
+    type InherentHooks = ();
+
+    fn check(
+        &self,
+        inputs: &[Output<V>],
+        _peeks: &[Output<V>],
+        outputs: &[Output<V>],
+        _context: &CheckerContext,
+    ) -> Result<TransactionPriority, Self::Error> {
+        let (escrow, burned) = settle::<V, ID>(inputs, outputs, &self.award)?;
+
+        ensure!(
+            escrow.check_signature(&self.award, escrow.arbiter, &self.arbiter_signature),
+            ConstraintCheckerError::BadSignature
+        );
+
+        Ok(burned.try_into().unwrap_or(TransactionPriority::MAX))
+    }
+
+    fn is_inherent(&self) -> bool {
+        false
+    }
+}
+
+/// A constraint checker that refunds an escrow to the buyer once it has timed out.
+///
+/// The only input is the escrow. All outputs are `Coin<ID>`s locked to the buyer.
+#[derive(
+    Serialize,
+    Deserialize,
+    Encode,
+    Decode,
+    DebugNoBound,
+    DefaultNoBound,
+    PartialEq,
+    Eq,
+    CloneNoBound,
+    TypeInfo,
+)]
+#[scale_info(skip_type_params(T))]
+pub struct TimeoutRefund<T, const ID: u8>(PhantomData<T>);
+
+impl<T, V, const ID: u8> ConstraintChecker<V> for TimeoutRefund<T, ID>
+where
+    T: EscrowConfig,
+    V: Verifier + PartialEq + From<SigCheck>,
+{
+    type Error = ConstraintCheckerError;
+    type InherentHooks = ();
+
+    fn check(
+        &self,
+        inputs: &[Output<V>],
+        _peeks: &[Output<V>],
+        outputs: &[Output<V>],
+        _context: &CheckerContext,
+    ) -> Result<TransactionPriority, Self::Error> {
+        let (escrow, burned) = settle::<V, ID>(inputs, outputs, &Party::Buyer)?;
+
+        ensure!(
+            T::block_height() >= escrow.timeout,
+            ConstraintCheckerError::NotExpired
+        );
+
+        Ok(burned.try_into().unwrap_or(TransactionPriority::MAX))
+    }
+
+    fn is_inherent(&self) -> bool {
+        false
+    }
+}
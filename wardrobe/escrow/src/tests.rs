@@ -0,0 +1,241 @@
+//! Unit tests for the Escrow piece
+
+use super::*;
+use sp_core::{crypto::Pair as _, sr25519::Pair};
+use tuxedo_core::dynamic_typing::testing::Bogus;
+
+/// A mock config that always reports the same block height.
+pub struct AtHeight<const H: u32>;
+
+impl<const H: u32> EscrowConfig for AtHeight<H> {
+    fn block_height() -> u32 {
+        H
+    }
+}
+
+fn buyer() -> Pair {
+    Pair::from_seed(&[1u8; 32])
+}
+
+fn seller() -> Pair {
+    Pair::from_seed(&[2u8; 32])
+}
+
+fn arbiter() -> Pair {
+    Pair::from_seed(&[3u8; 32])
+}
+
+fn owned_by(pair: &Pair) -> SigCheck {
+    SigCheck::new(pair.public())
+}
+
+/// An escrow that times out at block 100.
+fn escrow(amount: u128) -> Escrow<0> {
+    Escrow {
+        amount,
+        buyer: buyer().public().into(),
+        seller: seller().public().into(),
+        arbiter: arbiter().public().into(),
+        timeout: 100,
+        nonce: 0,
+    }
+}
+
+fn sign(pair: &Pair, escrow: &Escrow<0>, award: &Party) -> Signature {
+    pair.sign(&escrow.settlement_message(award))
+}
+
+fn check<C: ConstraintChecker<SigCheck>>(
+    checker: C,
+    inputs: Vec<Output<SigCheck>>,
+    outputs: Vec<Output<SigCheck>>,
+) -> Result<TransactionPriority, C::Error> {
+    checker.check(&inputs, &[], &outputs, &Default::default())
+}
+
+#[test]
+fn lock_works() {
+    let inputs = vec![(Coin::<0>(100), owned_by(&buyer())).into()];
+    let outputs = vec![
+        (escrow(60), SigCheck::new(H256::zero())).into(),
+        (Coin::<0>(40), owned_by(&buyer())).into(),
+    ];
+
+    assert_eq!(
+        check(LockEscrow::<AtHeight<50>, 0>::default(), inputs, outputs),
+        Ok(0)
+    );
+}
+
+#[test]
+fn lock_with_timeout_in_past_fails() {
+    let inputs = vec![(Coin::<0>(100), owned_by(&buyer())).into()];
+    let outputs = vec![(escrow(100), owned_by(&buyer())).into()];
+
+    assert_eq!(
+        check(LockEscrow::<AtHeight<100>, 0>::default(), inputs, outputs),
+        Err(ConstraintCheckerError::TimeoutInPast)
+    );
+}
+
+#[test]
+fn lock_exceeding_inputs_fails() {
+    let inputs = vec![(Coin::<0>(100), owned_by(&buyer())).into()];
+    let outputs = vec![(escrow(101), owned_by(&buyer())).into()];
+
+    assert_eq!(
+        check(LockEscrow::<AtHeight<50>, 0>::default(), inputs, outputs),
+        Err(ConstraintCheckerError::OutputsExceedInputs)
+    );
+}
+
+#[test]
+fn mutual_release_to_seller_works() {
+    let e = escrow(100);
+    let checker = MutualRelease::<0> {
+        award: Party::Seller,
+        buyer_signature: sign(&buyer(), &e, &Party::Seller),
+        seller_signature: sign(&seller(), &e, &Party::Seller),
+    };
+    let inputs = vec![(e, owned_by(&buyer())).into()];
+    let outputs = vec![(Coin::<0>(100), owned_by(&seller())).into()];
+
+    assert_eq!(check(checker, inputs, outputs), Ok(0));
+}
+
+#[test]
+fn mutual_release_missing_buyer_agreement_fails() {
+    let e = escrow(100);
+    let checker = MutualRelease::<0> {
+        award: Party::Seller,
+        buyer_signature: sign(&seller(), &e, &Party::Seller),
+        seller_signature: sign(&seller(), &e, &Party::Seller),
+    };
+    let inputs = vec![(e, owned_by(&buyer())).into()];
+    let outputs = vec![(Coin::<0>(100), owned_by(&seller())).into()];
+
+    assert_eq!(
+        check(checker, inputs, outputs),
+        Err(ConstraintCheckerError::BadSignature)
+    );
+}
+
+#[test]
+fn mutual_release_signatures_for_other_award_fails() {
+    let e = escrow(100);
+    let checker = MutualRelease::<0> {
+        award: Party::Seller,
+        buyer_signature: sign(&buyer(), &e, &Party::Buyer),
+        seller_signature: sign(&seller(), &e, &Party::Buyer),
+    };
+    let inputs = vec![(e, owned_by(&buyer())).into()];
+    let outputs = vec![(Coin::<0>(100), owned_by(&seller())).into()];
+
+    assert_eq!(
+        check(checker, inputs, outputs),
+        Err(ConstraintCheckerError::BadSignature)
+    );
+}
+
+#[test]
+fn mutual_release_to_wrong_verifier_fails() {
+    let e = escrow(100);
+    let checker = MutualRelease::<0> {
+        award: Party::Seller,
+        buyer_signature: sign(&buyer(), &e, &Party::Seller),
+        seller_signature: sign(&seller(), &e, &Party::Seller),
+    };
+    let inputs = vec![(e, owned_by(&buyer())).into()];
+    let outputs = vec![(Coin::<0>(100), owned_by(&arbiter())).into()];
+
+    assert_eq!(
+        check(checker, inputs, outputs),
+        Err(ConstraintCheckerError::PaidToWrongVerifier)
+    );
+}
+
+#[test]
+fn arbiter_release_to_buyer_works() {
+    let e = escrow(100);
+    let checker = ArbiterRelease::<0> {
+        award: Party::Buyer,
+        arbiter_signature: sign(&arbiter(), &e, &Party::Buyer),
+    };
+    let inputs = vec![(e, owned_by(&buyer())).into()];
+    let outputs = vec![(Coin::<0>(99), owned_by(&buyer())).into()];
+
+    assert_eq!(check(checker, inputs, outputs), Ok(1));
+}
+
+#[test]
+fn arbiter_release_signed_by_party_fails() {
+    let e = escrow(100);
+    let checker = ArbiterRelease::<0> {
+        award: Party::Seller,
+        arbiter_signature: sign(&seller(), &e, &Party::Seller),
+    };
+    let inputs = vec![(e, owned_by(&buyer())).into()];
+    let outputs = vec![(Coin::<0>(100), owned_by(&seller())).into()];
+
+    assert_eq!(
+        check(checker, inputs, outputs),
+        Err(ConstraintCheckerError::BadSignature)
+    );
+}
+
+#[test]
+fn arbiter_release_non_escrow_fails() {
+    let e = escrow(100);
+    let checker = ArbiterRelease::<0> {
+        award: Party::Seller,
+        arbiter_signature: sign(&arbiter(), &e, &Party::Seller),
+    };
+    let inputs = vec![(Bogus, owned_by(&buyer())).into()];
+    let outputs = vec![(Coin::<0>(100), owned_by(&seller())).into()];
+
+    assert_eq!(
+        check(checker, inputs, outputs),
+        Err(ConstraintCheckerError::BadlyTypedInput)
+    );
+}
+
+#[test]
+fn timeout_refund_works() {
+    let inputs = vec![(escrow(100), owned_by(&buyer())).into()];
+    let outputs = vec![(Coin::<0>(100), owned_by(&buyer())).into()];
+
+    assert_eq!(
+        check(
+            TimeoutRefund::<AtHeight<100>, 0>::default(),
+            inputs,
+            outputs
+        ),
+        Ok(0)
+    );
+}
+
+#[test]
+fn timeout_refund_before_timeout_fails() {
+    let inputs = vec![(escrow(100), owned_by(&buyer())).into()];
+    let outputs = vec![(Coin::<0>(100), owned_by(&buyer())).into()];
+
+    assert_eq!(
+        check(TimeoutRefund::<AtHeight<99>, 0>::default(), inputs, outputs),
+        Err(ConstraintCheckerError::NotExpired)
+    );
+}
+
+#[test]
+fn timeout_refund_to_seller_fails() {
+    let inputs = vec![(escrow(100), owned_by(&buyer())).into()];
+    let outputs = vec![(Coin::<0>(100), owned_by(&seller())).into()];
+
+    assert_eq!(
+        check(
+            TimeoutRefund::<AtHeight<100>, 0>::default(),
+            inputs,
+            outputs
+        ),
+        Err(ConstraintCheckerError::PaidToWrongVerifier)
+    );
+}
@@ -0,0 +1,433 @@
+//! Bridges an ERC-20 token from Ethereum by locking it in a contract there and minting wrapped
+//! [`Coin<ETH>`](Coin) here. A committee of relayers watches both chains and attests to what
+//! happened on the other side.
+//!
+//! * Deposits start on Ethereum, where a user locks tokens in the bridge contract and names an
+//!   sr25519 key as the recipient. Anyone may then register the deposit with
+//!   [`BridgeConstraintChecker::RegisterDeposit`], which creates a [`DepositNullifier`]. Once the
+//!   relayers see that the deposit is final, they consume its nullifier in a
+//!   [`BridgeConstraintChecker::Mint`] transaction, which mints the wrapped coins to the recipient.
+//! * Withdrawals start here, where a user burns wrapped coins with
+//!   [`BridgeConstraintChecker::Withdraw`]. This creates a [`WithdrawalReceipt`] that names the
+//!   Ethereum recipient and the amount. The relayers redeem the receipt on Ethereum, using its
+//!   `OutputRef` as the withdrawal's unique id, and later clear it from the utxo set with
+//!   [`BridgeConstraintChecker::ClearReceipts`].
+//!
+//! The committee itself is a [`RelayerCommittee`] utxo, usually protected by a
+//! `ThresholdMultiSignature` verifier that is configured at genesis. Minting and clearing receipts
+//! consume it and must recreate it unchanged, so only transactions that a threshold of relayers
+//! signed can do either. The relayers may change their membership with
+//! [`BridgeConstraintChecker::RotateCommittee`].
+//!
+//! ## Replay Protection
+//!
+//! Registering a deposit is a transaction with no inputs, whose only output is determined by the
+//! deposit's id. The executive remembers every such transaction that it has applied, and refuses
+//! to apply it again, even after the nullifier is consumed. Since the relayers can only mint by
+//! consuming the deposit's nullifier, each deposit is minted at most once.
+//!
+//! ## Trust Warning
+//!
+//! Nothing on this chain checks Ethereum's state, so this piece trusts a threshold of the relayers
+//! to only mint for deposits that really happened, and to redeem every withdrawal receipt.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use money::Coin;
+use parity_scale_codec::{Decode, Encode};
+use scale_info::TypeInfo;
+use serde::{Deserialize, Serialize};
+use sp_core::{H160, H256};
+use sp_runtime::transaction_validity::TransactionPriority;
+use sp_std::prelude::*;
+use tuxedo_core::{
+    dynamic_typing::UtxoData,
+    ensure,
+    events::{self, TuxedoEvent},
+    types::Output,
+    verifier::{SigCheck, UpForGrabs},
+    CheckerContext, ConstraintChecker, Verifier,
+};
+
+#[cfg(test)]
+mod tests;
+
+/// The coin id that a runtime is expected to use for the bridged token.
+pub const ETH: u8 = 2;
+
+/// The relayer committee that attests to deposits on Ethereum and redeems withdrawals there.
+///
+/// Who is on the committee is decided by this utxo's verifier, not its payload.
+#[derive(Serialize, Deserialize, Encode, Decode, Debug, PartialEq, Eq, Clone, TypeInfo)]
+pub struct RelayerCommittee<const ID: u8> {
+    /// The address of the ERC-20 token on Ethereum whose deposits this committee attests to.
+    pub token: H160,
+}
+
+impl<const ID: u8> UtxoData for RelayerCommittee<ID> {
+    const TYPE_ID: [u8; 4] = [b'e', b'b', b'c', ID];
+}
+
+/// Marks a deposit on Ethereum as registered, and is consumed when the deposit is minted.
+#[derive(Serialize, Deserialize, Encode, Decode, Debug, PartialEq, Eq, Clone, TypeInfo)]
+pub struct DepositNullifier<const ID: u8> {
+    /// The id that the bridge contract gave the deposit.
+    pub deposit_id: H256,
+}
+
+impl<const ID: u8> UtxoData for DepositNullifier<ID> {
+    const TYPE_ID: [u8; 4] = [b'e', b'b', b'n', ID];
+}
+
+/// A request to release some tokens on Ethereum, created by burning wrapped coins.
+#[derive(Serialize, Deserialize, Encode, Decode, Debug, PartialEq, Eq, Clone, TypeInfo)]
+pub struct WithdrawalReceipt<const ID: u8> {
+    /// The Ethereum address that receives the tokens.
+    pub eth_recipient: H160,
+    /// The amount of tokens to release.
+    pub amount: u128,
+}
+
+impl<const ID: u8> UtxoData for WithdrawalReceipt<ID> {
+    const TYPE_ID: [u8; 4] = [b'e', b'b', b'w', ID];
+}
+
+/// A deposit on Ethereum that the relayers attest to.
+#[derive(
+    Serialize,
+    Deserialize,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Clone,
+    Encode,
+    Decode,
+    Hash,
+    Debug,
+    TypeInfo,
+)]
+pub struct Deposit {
+    /// The id that the bridge contract gave the deposit.
+    pub id: H256,
+    /// The sr25519 key that the wrapped coins are minted to.
+    pub recipient: H256,
+    /// The amount of tokens that were locked.
+    pub amount: u128,
+}
+
+/// Emitted when wrapped coins are minted for a deposit.
+#[derive(Serialize, Deserialize, Encode, Decode, Debug, PartialEq, Eq, Clone, TypeInfo)]
+pub struct DepositMinted<const ID: u8> {
+    /// The id that the bridge contract gave the deposit.
+    pub deposit_id: H256,
+    /// The sr25519 key that the wrapped coins were minted to.
+    pub recipient: H256,
+    /// The value of the minted coin.
+    pub amount: u128,
+}
+
+impl<const ID: u8> TuxedoEvent for DepositMinted<ID> {
+    const EVENT_ID: [u8; 4] = [b'e', b'b', b'd', ID];
+}
+
+/// Emitted when wrapped coins are burned to withdraw tokens on Ethereum.
+///
+/// Relayers can watch for these instead of scanning the utxo set for receipts.
+#[derive(Serialize, Deserialize, Encode, Decode, Debug, PartialEq, Eq, Clone, TypeInfo)]
+pub struct WithdrawalRequested<const ID: u8> {
+    /// The Ethereum address that receives the tokens.
+    pub eth_recipient: H160,
+    /// The amount of tokens to release.
+    pub amount: u128,
+}
+
+impl<const ID: u8> TuxedoEvent for WithdrawalRequested<ID> {
+    const EVENT_ID: [u8; 4] = [b'e', b'b', b'w', ID];
+}
+
+/// Reasons that bridge constraint checks may fail
+#[derive(
+    Serialize,
+    Deserialize,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Clone,
+    Encode,
+    Decode,
+    Hash,
+    Debug,
+    TypeInfo,
+)]
+pub enum ConstraintCheckerError {
+    /// An input data has the wrong type.
+    BadlyTypedInput,
+    /// An output data has the wrong type.
+    BadlyTypedOutput,
+    /// Wrong number of inputs were provided to the constraint checker.
+    WrongNumberInputs,
+    /// Wrong number of outputs were provided to the constraint checker.
+    WrongNumberOutputs,
+    /// Bridge transactions do not peek at any utxos.
+    PeeksNotAllowed,
+    /// There is nothing for the transaction to mint, withdraw, or clear.
+    NothingToProcess,
+    /// A nullifier or receipt is not up for grabs, so it might never be consumed.
+    NotUpForGrabs,
+    /// The committee was not recreated exactly as it was consumed.
+    CommitteeNotPreserved,
+    /// A nullifier does not belong to the deposit that is minted in its place.
+    NullifierMismatch,
+    /// A minted coin's value or owner does not match its deposit.
+    MintMismatch,
+    /// A deposit, withdrawal, or coin with zero value was created. This is not allowed because it wastes state space.
+    ZeroValue,
+    /// The value consumed or created by this transaction overflows the value type.
+    ValueOverflow,
+    /// More value was created than was consumed.
+    OutputsExceedInputs,
+}
+
+/// The constraint checkers of the Ethereum bridge, for the wrapped token `Coin<ID>`.
+#[derive(
+    Serialize,
+    Deserialize,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Clone,
+    Encode,
+    Decode,
+    Hash,
+    Debug,
+    TypeInfo,
+)]
+pub enum BridgeConstraintChecker<const ID: u8> {
+    /// Register a deposit on Ethereum so that it can be minted.
+    ///
+    /// There are no inputs and a single output, which is the deposit's nullifier protected by
+    /// `UpForGrabs`. Anyone may register a deposit, but only the committee can mint it.
+    RegisterDeposit,
+    /// Mint wrapped coins for some registered deposits.
+    ///
+    /// The first input is the committee, followed by the nullifier of each deposit in order. The
+    /// first output recreates the committee, followed by one coin for each deposit in order,
+    /// protected by a `SigCheck` for the deposit's recipient.
+    Mint {
+        /// The deposits that the relayers attest to.
+        deposits: Vec<Deposit>,
+    },
+    /// Burn wrapped coins to withdraw tokens on Ethereum.
+    ///
+    /// All inputs are coins. The first output is the withdrawal receipt protected by
+    /// `UpForGrabs`, and all other outputs are coins for change. Any wrapped coins left over are
+    /// simply burned. They are not the native coin that fees and tips are paid in, so they do
+    /// not count towards the transaction's fee and tip.
+    Withdraw,
+    /// Remove the receipts of withdrawals that were redeemed on Ethereum.
+    ///
+    /// The first input is the committee, and all other inputs are receipts. The only output
+    /// recreates the committee.
+    ClearReceipts,
+    /// Change who is on the committee.
+    ///
+    /// The only input is the committee, and the only output recreates it with the same payload
+    /// and a new verifier.
+    RotateCommittee,
+}
+
+impl<const ID: u8> BridgeConstraintChecker<ID> {
+    /// Make sure the committee is the given input and is recreated as the given output. The
+    /// verifier is only compared when `same_verifier` is set.
+    fn check_committee<V: PartialEq>(
+        input: Option<&Output<V>>,
+        output: Option<&Output<V>>,
+        same_verifier: bool,
+    ) -> Result<(), ConstraintCheckerError> {
+        let input = input.ok_or(ConstraintCheckerError::WrongNumberInputs)?;
+        let output = output.ok_or(ConstraintCheckerError::WrongNumberOutputs)?;
+        let committee = input
+            .payload
+            .extract::<RelayerCommittee<ID>>()
+            .map_err(|_| ConstraintCheckerError::BadlyTypedInput)?;
+        let recreated = output
+            .payload
+            .extract::<RelayerCommittee<ID>>()
+            .map_err(|_| ConstraintCheckerError::BadlyTypedOutput)?;
+        ensure!(
+            committee == recreated && (!same_verifier || input.verifier == output.verifier),
+            ConstraintCheckerError::CommitteeNotPreserved
+        );
+        Ok(())
+    }
+
+    /// Sum the value of some coins, making sure they are all `Coin<ID>`s with non-zero value.
+    fn sum_coins<V>(
+        coins: &[Output<V>],
+        error: ConstraintCheckerError,
+    ) -> Result<u128, ConstraintCheckerError> {
+        coins.iter().try_fold(0u128, |total, coin| {
+            let value = coin
+                .payload
+                .extract::<Coin<ID>>()
+                .map_err(|_| error.clone())?
+                .0;
+            ensure!(value > 0, ConstraintCheckerError::ZeroValue);
+            total
+                .checked_add(value)
+                .ok_or(ConstraintCheckerError::ValueOverflow)
+        })
+    }
+}
+
+impl<V: Verifier + PartialEq + From<SigCheck> + From<UpForGrabs>, const ID: u8> ConstraintChecker<V>
+    for BridgeConstraintChecker<ID>
+{
+    type Error = ConstraintCheckerError;
+    type InherentHooks = ();
+
+    fn check(
+        &self,
+        inputs: &[Output<V>],
+        peeks: &[Output<V>],
+        outputs: &[Output<V>],
+        _context: &CheckerContext,
+    ) -> Result<TransactionPriority, Self::Error> {
+        ensure!(peeks.is_empty(), ConstraintCheckerError::PeeksNotAllowed);
+
+        match self {
+            Self::RegisterDeposit => {
+                ensure!(inputs.is_empty(), ConstraintCheckerError::WrongNumberInputs);
+                ensure!(
+                    outputs.len() == 1,
+                    ConstraintCheckerError::WrongNumberOutputs
+                );
+                outputs[0]
+                    .payload
+                    .extract::<DepositNullifier<ID>>()
+                    .map_err(|_| ConstraintCheckerError::BadlyTypedOutput)?;
+                ensure!(
+                    outputs[0].verifier == UpForGrabs.into(),
+                    ConstraintCheckerError::NotUpForGrabs
+                );
+
+                Ok(0)
+            }
+            Self::Mint { deposits } => {
+                ensure!(
+                    !deposits.is_empty(),
+                    ConstraintCheckerError::NothingToProcess
+                );
+                ensure!(
+                    inputs.len() == deposits.len() + 1,
+                    ConstraintCheckerError::WrongNumberInputs
+                );
+                ensure!(
+                    outputs.len() == deposits.len() + 1,
+                    ConstraintCheckerError::WrongNumberOutputs
+                );
+                Self::check_committee(inputs.first(), outputs.first(), true)?;
+
+                for ((deposit, nullifier), coin) in
+                    deposits.iter().zip(&inputs[1..]).zip(&outputs[1..])
+                {
+                    let nullifier = nullifier
+                        .payload
+                        .extract::<DepositNullifier<ID>>()
+                        .map_err(|_| ConstraintCheckerError::BadlyTypedInput)?;
+                    ensure!(
+                        nullifier.deposit_id == deposit.id,
+                        ConstraintCheckerError::NullifierMismatch
+                    );
+
+                    let value = coin
+                        .payload
+                        .extract::<Coin<ID>>()
+                        .map_err(|_| ConstraintCheckerError::BadlyTypedOutput)?
+                        .0;
+                    ensure!(deposit.amount > 0, ConstraintCheckerError::ZeroValue);
+                    ensure!(
+                        value == deposit.amount
+                            && coin.verifier == SigCheck::new(deposit.recipient).into(),
+                        ConstraintCheckerError::MintMismatch
+                    );
+
+                    events::deposit(DepositMinted::<ID> {
+                        deposit_id: deposit.id,
+                        recipient: deposit.recipient,
+                        amount: deposit.amount,
+                    });
+                }
+
+                Ok(0)
+            }
+            Self::Withdraw => {
+                let total_input = Self::sum_coins(inputs, ConstraintCheckerError::BadlyTypedInput)?;
+
+                let receipt_output = outputs
+                    .first()
+                    .ok_or(ConstraintCheckerError::WrongNumberOutputs)?;
+                let receipt = receipt_output
+                    .payload
+                    .extract::<WithdrawalReceipt<ID>>()
+                    .map_err(|_| ConstraintCheckerError::BadlyTypedOutput)?;
+                ensure!(receipt.amount > 0, ConstraintCheckerError::ZeroValue);
+                ensure!(
+                    receipt_output.verifier == UpForGrabs.into(),
+                    ConstraintCheckerError::NotUpForGrabs
+                );
+
+                let total_output =
+                    Self::sum_coins(&outputs[1..], ConstraintCheckerError::BadlyTypedOutput)?
+                        .checked_add(receipt.amount)
+                        .ok_or(ConstraintCheckerError::ValueOverflow)?;
+                ensure!(
+                    total_output <= total_input,
+                    ConstraintCheckerError::OutputsExceedInputs
+                );
+
+                events::deposit(WithdrawalRequested::<ID> {
+                    eth_recipient: receipt.eth_recipient,
+                    amount: receipt.amount,
+                });
+
+                // Leftover wrapped coins are burned, but they can't pay a native tip
+                Ok(0)
+            }
+            Self::ClearReceipts => {
+                ensure!(inputs.len() > 1, ConstraintCheckerError::NothingToProcess);
+                ensure!(
+                    outputs.len() == 1,
+                    ConstraintCheckerError::WrongNumberOutputs
+                );
+                Self::check_committee(inputs.first(), outputs.first(), true)?;
+
+                for receipt in &inputs[1..] {
+                    receipt
+                        .payload
+                        .extract::<WithdrawalReceipt<ID>>()
+                        .map_err(|_| ConstraintCheckerError::BadlyTypedInput)?;
+                }
+
+                Ok(0)
+            }
+            Self::RotateCommittee => {
+                ensure!(inputs.len() == 1, ConstraintCheckerError::WrongNumberInputs);
+                ensure!(
+                    outputs.len() == 1,
+                    ConstraintCheckerError::WrongNumberOutputs
+                );
+                Self::check_committee(inputs.first(), outputs.first(), false)?;
+
+                Ok(0)
+            }
+        }
+    }
+
+    fn is_inherent(&self) -> bool {
+        false
+    }
+}
@@ -0,0 +1,470 @@
+//! Unit tests for the Ethereum Bridge piece
+
+use super::*;
+use tuxedo_core::{
+    dynamic_typing::testing::Bogus, tuxedo_verifier, verifier::ThresholdMultiSignature,
+};
+
+/// A verifier that can represent the relayer committee, users, and nullifiers and receipts.
+#[derive(Serialize, Deserialize, Encode, Decode, Debug, PartialEq, Eq, Clone, TypeInfo)]
+#[tuxedo_verifier]
+pub enum TestVerifier {
+    SigCheck(SigCheck),
+    UpForGrabs(UpForGrabs),
+    ThresholdMultiSignature(ThresholdMultiSignature),
+}
+
+type Bridge = BridgeConstraintChecker<ETH>;
+
+fn token() -> H160 {
+    H160::repeat_byte(0xee)
+}
+
+fn alice() -> H256 {
+    H256::repeat_byte(1)
+}
+
+fn relayers() -> TestVerifier {
+    ThresholdMultiSignature::new(2, vec![H256::repeat_byte(7), H256::repeat_byte(8)]).into()
+}
+
+fn committee() -> Output<TestVerifier> {
+    (RelayerCommittee::<ETH> { token: token() }, relayers()).into()
+}
+
+fn nullifier(id: u8) -> Output<TestVerifier> {
+    let nullifier = DepositNullifier::<ETH> {
+        deposit_id: H256::repeat_byte(id),
+    };
+    (nullifier, TestVerifier::from(UpForGrabs)).into()
+}
+
+fn deposit(id: u8, amount: u128) -> Deposit {
+    Deposit {
+        id: H256::repeat_byte(id),
+        recipient: alice(),
+        amount,
+    }
+}
+
+fn coin(value: u128) -> Output<TestVerifier> {
+    (
+        Coin::<ETH>(value),
+        TestVerifier::from(SigCheck::new(alice())),
+    )
+        .into()
+}
+
+fn receipt(amount: u128) -> Output<TestVerifier> {
+    let receipt = WithdrawalReceipt::<ETH> {
+        eth_recipient: H160::repeat_byte(2),
+        amount,
+    };
+    (receipt, TestVerifier::from(UpForGrabs)).into()
+}
+
+fn check(
+    checker: Bridge,
+    inputs: Vec<Output<TestVerifier>>,
+    outputs: Vec<Output<TestVerifier>>,
+) -> Result<TransactionPriority, ConstraintCheckerError> {
+    checker.check(&inputs, &[], &outputs, &Default::default())
+}
+
+#[test]
+fn register_deposit_works() {
+    assert_eq!(
+        check(Bridge::RegisterDeposit, vec![], vec![nullifier(1)]),
+        Ok(0)
+    );
+}
+
+#[test]
+fn register_deposit_with_inputs_fails() {
+    assert_eq!(
+        check(Bridge::RegisterDeposit, vec![coin(5)], vec![nullifier(1)]),
+        Err(ConstraintCheckerError::WrongNumberInputs)
+    );
+}
+
+#[test]
+fn register_deposit_with_two_nullifiers_fails() {
+    assert_eq!(
+        check(
+            Bridge::RegisterDeposit,
+            vec![],
+            vec![nullifier(1), nullifier(2)]
+        ),
+        Err(ConstraintCheckerError::WrongNumberOutputs)
+    );
+}
+
+#[test]
+fn register_deposit_with_owned_nullifier_fails() {
+    let mut owned = nullifier(1);
+    owned.verifier = SigCheck::new(alice()).into();
+
+    assert_eq!(
+        check(Bridge::RegisterDeposit, vec![], vec![owned]),
+        Err(ConstraintCheckerError::NotUpForGrabs)
+    );
+}
+
+#[test]
+fn register_deposit_of_wrong_type_fails() {
+    assert_eq!(
+        check(
+            Bridge::RegisterDeposit,
+            vec![],
+            vec![(Bogus, TestVerifier::from(UpForGrabs)).into()]
+        ),
+        Err(ConstraintCheckerError::BadlyTypedOutput)
+    );
+}
+
+#[test]
+fn peeks_are_not_allowed() {
+    assert_eq!(
+        Bridge::RegisterDeposit.check(&[], &[committee()], &[nullifier(1)], &Default::default()),
+        Err(ConstraintCheckerError::PeeksNotAllowed)
+    );
+}
+
+#[test]
+fn mint_works() {
+    let (_, emitted) = events::collect(|| {
+        assert_eq!(
+            check(
+                Bridge::Mint {
+                    deposits: vec![deposit(1, 10), deposit(2, 20)]
+                },
+                vec![committee(), nullifier(1), nullifier(2)],
+                vec![committee(), coin(10), coin(20)],
+            ),
+            Ok(0)
+        );
+    });
+
+    let expected: Vec<events::Event> = vec![
+        DepositMinted::<ETH> {
+            deposit_id: H256::repeat_byte(1),
+            recipient: alice(),
+            amount: 10,
+        }
+        .into(),
+        DepositMinted::<ETH> {
+            deposit_id: H256::repeat_byte(2),
+            recipient: alice(),
+            amount: 20,
+        }
+        .into(),
+    ];
+    assert_eq!(emitted, expected);
+}
+
+#[test]
+fn mint_nothing_fails() {
+    assert_eq!(
+        check(
+            Bridge::Mint { deposits: vec![] },
+            vec![committee()],
+            vec![committee()],
+        ),
+        Err(ConstraintCheckerError::NothingToProcess)
+    );
+}
+
+#[test]
+fn mint_without_committee_fails() {
+    assert_eq!(
+        check(
+            Bridge::Mint {
+                deposits: vec![deposit(1, 10)]
+            },
+            vec![nullifier(2), nullifier(1)],
+            vec![committee(), coin(10)],
+        ),
+        Err(ConstraintCheckerError::BadlyTypedInput)
+    );
+}
+
+#[test]
+fn mint_with_changed_committee_fails() {
+    let mut changed = committee();
+    changed.verifier = SigCheck::new(alice()).into();
+
+    assert_eq!(
+        check(
+            Bridge::Mint {
+                deposits: vec![deposit(1, 10)]
+            },
+            vec![committee(), nullifier(1)],
+            vec![changed, coin(10)],
+        ),
+        Err(ConstraintCheckerError::CommitteeNotPreserved)
+    );
+}
+
+#[test]
+fn mint_without_nullifier_fails() {
+    assert_eq!(
+        check(
+            Bridge::Mint {
+                deposits: vec![deposit(1, 10)]
+            },
+            vec![committee()],
+            vec![committee(), coin(10)],
+        ),
+        Err(ConstraintCheckerError::WrongNumberInputs)
+    );
+}
+
+#[test]
+fn mint_with_wrong_nullifier_fails() {
+    assert_eq!(
+        check(
+            Bridge::Mint {
+                deposits: vec![deposit(1, 10)]
+            },
+            vec![committee(), nullifier(2)],
+            vec![committee(), coin(10)],
+        ),
+        Err(ConstraintCheckerError::NullifierMismatch)
+    );
+}
+
+#[test]
+fn mint_too_much_fails() {
+    assert_eq!(
+        check(
+            Bridge::Mint {
+                deposits: vec![deposit(1, 10)]
+            },
+            vec![committee(), nullifier(1)],
+            vec![committee(), coin(11)],
+        ),
+        Err(ConstraintCheckerError::MintMismatch)
+    );
+}
+
+#[test]
+fn mint_to_wrong_recipient_fails() {
+    let mut stolen = coin(10);
+    stolen.verifier = SigCheck::new(H256::repeat_byte(9)).into();
+
+    assert_eq!(
+        check(
+            Bridge::Mint {
+                deposits: vec![deposit(1, 10)]
+            },
+            vec![committee(), nullifier(1)],
+            vec![committee(), stolen],
+        ),
+        Err(ConstraintCheckerError::MintMismatch)
+    );
+}
+
+#[test]
+fn mint_zero_fails() {
+    assert_eq!(
+        check(
+            Bridge::Mint {
+                deposits: vec![deposit(1, 0)]
+            },
+            vec![committee(), nullifier(1)],
+            vec![committee(), coin(0)],
+        ),
+        Err(ConstraintCheckerError::ZeroValue)
+    );
+}
+
+#[test]
+fn withdraw_works() {
+    let (result, emitted) = events::collect(|| {
+        check(
+            Bridge::Withdraw,
+            vec![coin(10), coin(5)],
+            vec![receipt(12), coin(2)],
+        )
+    });
+
+    assert_eq!(result, Ok(0));
+    let expected = WithdrawalRequested::<ETH> {
+        eth_recipient: H160::repeat_byte(2),
+        amount: 12,
+    };
+    assert_eq!(emitted, vec![expected.into()]);
+}
+
+#[test]
+fn withdraw_more_than_burned_fails() {
+    assert_eq!(
+        check(Bridge::Withdraw, vec![coin(10)], vec![receipt(8), coin(3)]),
+        Err(ConstraintCheckerError::OutputsExceedInputs)
+    );
+}
+
+#[test]
+fn withdraw_without_receipt_fails() {
+    assert_eq!(
+        check(Bridge::Withdraw, vec![coin(10)], vec![coin(10)]),
+        Err(ConstraintCheckerError::BadlyTypedOutput)
+    );
+}
+
+#[test]
+fn withdraw_owned_receipt_fails() {
+    let mut owned = receipt(10);
+    owned.verifier = SigCheck::new(alice()).into();
+
+    assert_eq!(
+        check(Bridge::Withdraw, vec![coin(10)], vec![owned]),
+        Err(ConstraintCheckerError::NotUpForGrabs)
+    );
+}
+
+#[test]
+fn withdraw_zero_fails() {
+    assert_eq!(
+        check(Bridge::Withdraw, vec![coin(10)], vec![receipt(0)]),
+        Err(ConstraintCheckerError::ZeroValue)
+    );
+}
+
+#[test]
+fn withdraw_other_coins_fails() {
+    assert_eq!(
+        check(
+            Bridge::Withdraw,
+            vec![(Coin::<0>(10), TestVerifier::from(UpForGrabs)).into()],
+            vec![receipt(10)]
+        ),
+        Err(ConstraintCheckerError::BadlyTypedInput)
+    );
+}
+
+#[test]
+fn clear_receipts_works() {
+    assert_eq!(
+        check(
+            Bridge::ClearReceipts,
+            vec![committee(), receipt(10), receipt(20)],
+            vec![committee()],
+        ),
+        Ok(0)
+    );
+}
+
+#[test]
+fn clear_no_receipts_fails() {
+    assert_eq!(
+        check(Bridge::ClearReceipts, vec![committee()], vec![committee()]),
+        Err(ConstraintCheckerError::NothingToProcess)
+    );
+}
+
+#[test]
+fn clear_coins_as_receipts_fails() {
+    assert_eq!(
+        check(
+            Bridge::ClearReceipts,
+            vec![committee(), coin(10)],
+            vec![committee()],
+        ),
+        Err(ConstraintCheckerError::BadlyTypedInput)
+    );
+}
+
+#[test]
+fn rotate_committee_works() {
+    let mut rotated = committee();
+    rotated.verifier = ThresholdMultiSignature::new(1, vec![H256::repeat_byte(9)]).into();
+
+    assert_eq!(
+        check(Bridge::RotateCommittee, vec![committee()], vec![rotated]),
+        Ok(0)
+    );
+}
+
+#[test]
+fn rotate_committee_to_other_token_fails() {
+    let other = (
+        RelayerCommittee::<ETH> {
+            token: H160::repeat_byte(1),
+        },
+        relayers(),
+    )
+        .into();
+
+    assert_eq!(
+        check(Bridge::RotateCommittee, vec![committee()], vec![other]),
+        Err(ConstraintCheckerError::CommitteeNotPreserved)
+    );
+}
+
+// The tests below apply whole transactions through the executive, which is where replay
+// protection comes from.
+
+mod replays {
+    use super::*;
+    use tuxedo_core::types::{Transaction, TransactionVersion, UtxoError};
+    use tuxedo_test_utils::{apply, consume, mock_output_ref, payload, MockState};
+
+    type BridgeTransaction = Transaction<TestVerifier, Bridge>;
+
+    fn register(id: u8) -> BridgeTransaction {
+        Transaction {
+            version: TransactionVersion::LATEST,
+            inputs: vec![],
+            peeks: vec![],
+            outputs: vec![nullifier(id)],
+            checker: Bridge::RegisterDeposit,
+            memo: None,
+            valid_until: None,
+        }
+    }
+
+    #[test]
+    fn deposit_can_only_be_registered_once() {
+        MockState::<TestVerifier, Bridge>::default()
+            .build()
+            .execute_with(|| {
+                assert!(apply(register(1)).is_ok());
+                assert_eq!(apply(register(1)), Err(UtxoError::PreExistingOutput));
+                assert!(apply(register(2)).is_ok());
+            });
+    }
+
+    #[test]
+    fn minted_deposit_can_not_be_registered_again() {
+        // The committee is up for grabs here so the test does not need to sign for it.
+        let committee: Output<TestVerifier> = (
+            RelayerCommittee::<ETH> { token: token() },
+            TestVerifier::from(UpForGrabs),
+        )
+            .into();
+
+        MockState::<TestVerifier, Bridge>::default()
+            .with_utxo(mock_output_ref(0, 0), committee.clone())
+            .build()
+            .execute_with(|| {
+                let nullifier = apply(register(1)).unwrap()[0];
+
+                let mint = Transaction {
+                    version: TransactionVersion::LATEST,
+                    inputs: vec![consume(mock_output_ref(0, 0)), consume(nullifier)],
+                    peeks: vec![],
+                    outputs: vec![committee, coin(10)],
+                    checker: Bridge::Mint {
+                        deposits: vec![deposit(1, 10)],
+                    },
+                    memo: None,
+                    valid_until: None,
+                };
+                let refs = apply(mint).unwrap();
+                assert_eq!(payload::<Coin<ETH>, TestVerifier>(&refs[1]), Some(Coin(10)));
+
+                assert_eq!(apply(register(1)), Err(UtxoError::PreExistingOutput));
+            });
+    }
+}
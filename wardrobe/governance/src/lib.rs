@@ -0,0 +1,541 @@
+//! On-chain referenda in which `Coin<ID>` holders vote on proposals.
+//!
+//! A [`Proposal`] names some payload, such as the hash of a runtime wasm blob to upgrade to, and a
+//! block height at which voting ends. Until then, anyone may vote for or against it by locking
+//! coins into [`Vote`] UTXOs. Each vote is weighted by the coins it locks and by its conviction,
+//! and is counted on the proposal at the time it is cast. Because the tally lives in the proposal itself, every vote
+//! consumes and re-creates the proposal, so it is not possible to tally only a favorable subset
+//! of the votes.
+//!
+//! Voters who are willing to keep their coins locked beyond the end of voting may vote with
+//! conviction. Each level of conviction adds the locked value to the weight of the vote once more,
+//! and doubles the number of enactment periods that the coins stay locked for after voting ends.
+//! A vote without conviction counts its value once, and unlocks as soon as voting ends.
+//!
+//! Once voting has ended, the proposal is tallied. If it has enough support it is enacted, which
+//! creates an [`Enacted`] UTXO carrying the payload. Other pieces can then consume or peek at the
+//! enacted payload to authorize the action it describes. Regardless of the outcome, the voters
+//! may unlock their coins once their votes' lock periods have passed.
+//!
+//! Proposals should be protected by the `UpForGrabs` verifier so that anyone can vote on and
+//! tally them. Votes should be protected by the voter's own verifier.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use core::marker::PhantomData;
+
+use money::Coin;
+use parity_scale_codec::{Decode, Encode};
+use scale_info::TypeInfo;
+use serde::{Deserialize, Serialize};
+use sp_core::H256;
+use sp_runtime::transaction_validity::TransactionPriority;
+use sp_std::prelude::*;
+use tuxedo_core::{
+    dynamic_typing::{DynamicallyTypedData, UtxoData},
+    ensure,
+    support_macros::{CloneNoBound, DebugNoBound, DefaultNoBound},
+    CheckerContext, SimpleConstraintChecker,
+};
+
+#[cfg(test)]
+mod tests;
+
+/// Configuration items for the governance piece when it is
+/// instantiated in a concrete runtime.
+pub trait GovernanceConfig {
+    /// A means of getting the current block height.
+    /// Probably this will be the Tuxedo Executive
+    fn block_height() -> u32;
+
+    /// The minimum number of blocks that a proposal must be open for voting.
+    ///
+    /// Default is roughly one week with 6 second block times.
+    const VOTING_PERIOD: u32 = 100_800;
+
+    /// The minimum total weight of votes in favor for a proposal to be enacted.
+    /// Proposals must also have more weight in favor than against.
+    const APPROVAL_THRESHOLD: u128;
+
+    /// The number of blocks that votes with the lowest conviction stay locked after voting ends.
+    /// Each further level of conviction doubles the lock.
+    ///
+    /// Default is roughly two days with 6 second block times.
+    const ENACTMENT_PERIOD: u32 = 28_800;
+
+    /// The highest conviction that votes may be cast with.
+    const MAX_CONVICTION: u8 = 6;
+}
+
+/// A proposal being voted on by holders of `Coin<ID>`, along with its running tally.
+#[derive(
+    Serialize,
+    Deserialize,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Clone,
+    Encode,
+    Decode,
+    Hash,
+    Debug,
+    TypeInfo,
+)]
+pub struct Proposal<const ID: u8> {
+    /// The payload to enact if the proposal passes, for example the hash of a new runtime.
+    pub payload: H256,
+    /// The block height at which voting ends.
+    pub voting_ends: u32,
+    /// The total weight of votes in favor so far.
+    pub ayes: u128,
+    /// The total weight of votes against so far.
+    pub nays: u128,
+}
+
+impl<const ID: u8> Proposal<ID> {
+    /// Whether the votes so far are enough for the proposal to pass.
+    pub fn is_approved<T: GovernanceConfig>(&self) -> bool {
+        self.ayes >= T::APPROVAL_THRESHOLD && self.ayes > self.nays
+    }
+}
+
+impl<const ID: u8> UtxoData for Proposal<ID> {
+    const TYPE_ID: [u8; 4] = [b'p', b'r', b'p', ID];
+}
+
+/// Some `Coin<ID>` value locked as a vote on a proposal until voting ends,
+/// or longer if the vote was cast with conviction.
+#[derive(
+    Serialize,
+    Deserialize,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Clone,
+    Encode,
+    Decode,
+    Hash,
+    Debug,
+    TypeInfo,
+)]
+pub struct Vote<const ID: u8> {
+    /// The locked value.
+    pub amount: u128,
+    /// Whether this is a vote in favor of the proposal.
+    pub aye: bool,
+    /// The conviction that the vote was cast with. It multiplies the weight of the vote
+    /// in exchange for a longer lock.
+    pub conviction: u8,
+    /// The block height from which the value may be unlocked.
+    pub unlocks_at: u32,
+}
+
+impl<const ID: u8> Vote<ID> {
+    /// The weight of the vote, which is the locked value multiplied by one more than the
+    /// conviction. Returns `None` if the weight overflows.
+    pub fn weight(&self) -> Option<u128> {
+        self.amount.checked_mul(u128::from(self.conviction) + 1)
+    }
+
+    /// The number of blocks that a vote with the given conviction stays locked after voting ends.
+    ///
+    /// Votes without conviction unlock as soon as voting ends. Otherwise the lock is one
+    /// enactment period for the lowest conviction, and doubles with every level above it.
+    pub fn lock_period<T: GovernanceConfig>(conviction: u8) -> u32 {
+        match conviction {
+            0 => 0,
+            c => 2u32
+                .checked_pow(u32::from(c) - 1)
+                .map_or(u32::MAX, |periods| {
+                    periods.saturating_mul(T::ENACTMENT_PERIOD)
+                }),
+        }
+    }
+}
+
+impl<const ID: u8> UtxoData for Vote<ID> {
+    const TYPE_ID: [u8; 4] = [b'v', b'o', b't', ID];
+}
+
+/// The payload of a proposal that has passed a referendum.
+#[derive(
+    Serialize,
+    Deserialize,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Clone,
+    Encode,
+    Decode,
+    Hash,
+    Debug,
+    TypeInfo,
+)]
+pub struct Enacted<const ID: u8> {
+    /// The payload of the approved proposal.
+    pub payload: H256,
+}
+
+impl<const ID: u8> UtxoData for Enacted<ID> {
+    const TYPE_ID: [u8; 4] = [b'e', b'n', b'c', ID];
+}
+
+/// Errors that can occur when checking governance transactions.
+#[derive(
+    Serialize,
+    Deserialize,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Clone,
+    Encode,
+    Decode,
+    Hash,
+    Debug,
+    TypeInfo,
+)]
+pub enum ConstraintCheckerError {
+    /// An input data has the wrong type.
+    BadlyTypedInput,
+    /// An output data has the wrong type.
+    BadlyTypedOutput,
+    /// Wrong number of inputs were provided to the constraint checker.
+    WrongNumberInputs,
+    /// Wrong number of outputs were provided to the constraint checker.
+    WrongNumberOutputs,
+    /// A new proposal already has votes counted on it.
+    ProposalNotEmpty,
+    /// A new proposal closes before the minimum voting period has passed.
+    VotingPeriodTooShort,
+    /// The proposal was not consumed as the first input.
+    MissingProposalInput,
+    /// The proposal was not re-created as the first output.
+    MissingProposalOutput,
+    /// The proposal's payload or voting period was changed while voting.
+    ProposalChanged,
+    /// The transaction does not cast any votes.
+    NoVotes,
+    /// The votes counted on the proposal do not match the votes cast.
+    TallyMismatch,
+    /// A vote unlocks at a different height than the end of its lock period.
+    WrongUnlockHeight,
+    /// A vote was cast with a higher conviction than allowed.
+    ConvictionTooHigh,
+    /// A vote or coin with zero value was created. This is not allowed because it wastes state space.
+    ZeroValue,
+    /// The value consumed or created by this transaction overflows the value type.
+    ValueOverflow,
+    /// More value was created than was consumed.
+    OutputsExceedInputs,
+    /// Voting on the proposal has already ended.
+    VotingEnded,
+    /// Voting on the proposal has not ended yet.
+    VotingNotEnded,
+    /// The vote's lock period has not passed yet.
+    VoteStillLocked,
+    /// The enacted payload does not match the approved proposal.
+    PayloadMismatch,
+}
+
+/// A constraint checker that creates a new proposal.
+///
+/// There are no inputs, and the single output is a proposal with no votes whose voting period
+/// is at least the configured minimum.
+#[derive(
+    Serialize,
+    Deserialize,
+    Encode,
+    Decode,
+    DebugNoBound,
+    DefaultNoBound,
+    PartialEq,
+    Eq,
+    CloneNoBound,
+    TypeInfo,
+)]
+#[scale_info(skip_type_params(T))]
+pub struct Propose<T, const ID: u8>(PhantomData<T>);
+
+impl<T: GovernanceConfig, const ID: u8> SimpleConstraintChecker for Propose<T, ID> {
+    type Error = ConstraintCheckerError;
+
+    fn check(
+        &self,
+        input_data: &[DynamicallyTypedData],
+        _peeks: &[DynamicallyTypedData],
+        output_data: &[DynamicallyTypedData],
+        _context: &CheckerContext,
+    ) -> Result<TransactionPriority, Self::Error> {
+        ensure!(
+            input_data.is_empty(),
+            ConstraintCheckerError::WrongNumberInputs
+        );
+        ensure!(
+            output_data.len() == 1,
+            ConstraintCheckerError::WrongNumberOutputs
+        );
+        let proposal = output_data[0]
+            .extract::<Proposal<ID>>()
+            .map_err(|_| ConstraintCheckerError::BadlyTypedOutput)?;
+
+        ensure!(
+            proposal.ayes == 0 && proposal.nays == 0,
+            ConstraintCheckerError::ProposalNotEmpty
+        );
+        ensure!(
+            proposal.voting_ends >= T::block_height().saturating_add(T::VOTING_PERIOD),
+            ConstraintCheckerError::VotingPeriodTooShort
+        );
+
+        Ok(0)
+    }
+}
+
+/// A constraint checker that casts votes on a proposal by locking `Coin<ID>`s.
+///
+/// The first input is the proposal, and the remaining inputs are `Coin<ID>`s. The first output
+/// re-creates the proposal with the weights of the new votes added to its tally. The remaining
+/// outputs are the votes, which unlock when their lock period after the end of voting has passed,
+/// and optionally `Coin<ID>` change.
+/// Coins that are neither locked into votes nor returned as change pay the transaction's fee
+/// and tip.
+#[derive(
+    Serialize,
+    Deserialize,
+    Encode,
+    Decode,
+    DebugNoBound,
+    DefaultNoBound,
+    PartialEq,
+    Eq,
+    CloneNoBound,
+    TypeInfo,
+)]
+#[scale_info(skip_type_params(T))]
+pub struct CastVote<T, const ID: u8>(PhantomData<T>);
+
+impl<T: GovernanceConfig, const ID: u8> SimpleConstraintChecker for CastVote<T, ID> {
+    type Error = ConstraintCheckerError;
+
+    fn check(
+        &self,
+        input_data: &[DynamicallyTypedData],
+        _peeks: &[DynamicallyTypedData],
+        output_data: &[DynamicallyTypedData],
+        _context: &CheckerContext,
+    ) -> Result<TransactionPriority, Self::Error> {
+        let old_proposal = input_data
+            .first()
+            .and_then(|input| input.extract::<Proposal<ID>>().ok())
+            .ok_or(ConstraintCheckerError::MissingProposalInput)?;
+        let new_proposal = output_data
+            .first()
+            .and_then(|output| output.extract::<Proposal<ID>>().ok())
+            .ok_or(ConstraintCheckerError::MissingProposalOutput)?;
+        ensure!(
+            new_proposal.payload == old_proposal.payload
+                && new_proposal.voting_ends == old_proposal.voting_ends,
+            ConstraintCheckerError::ProposalChanged
+        );
+        ensure!(
+            T::block_height() < old_proposal.voting_ends,
+            ConstraintCheckerError::VotingEnded
+        );
+
+        let mut total_input: u128 = 0;
+        for input in &input_data[1..] {
+            let coin = input
+                .extract::<Coin<ID>>()
+                .map_err(|_| ConstraintCheckerError::BadlyTypedInput)?;
+            total_input = total_input
+                .checked_add(coin.0)
+                .ok_or(ConstraintCheckerError::ValueOverflow)?;
+        }
+
+        let mut ayes: u128 = 0;
+        let mut nays: u128 = 0;
+        let mut locked: u128 = 0;
+        let mut change: u128 = 0;
+        for output in &output_data[1..] {
+            if let Ok(vote) = output.extract::<Vote<ID>>() {
+                ensure!(vote.amount > 0, ConstraintCheckerError::ZeroValue);
+                ensure!(
+                    vote.conviction <= T::MAX_CONVICTION,
+                    ConstraintCheckerError::ConvictionTooHigh
+                );
+                ensure!(
+                    vote.unlocks_at
+                        == old_proposal
+                            .voting_ends
+                            .saturating_add(Vote::<ID>::lock_period::<T>(vote.conviction)),
+                    ConstraintCheckerError::WrongUnlockHeight
+                );
+                let weight = vote.weight().ok_or(ConstraintCheckerError::ValueOverflow)?;
+                let tally = if vote.aye { &mut ayes } else { &mut nays };
+                *tally = tally
+                    .checked_add(weight)
+                    .ok_or(ConstraintCheckerError::ValueOverflow)?;
+                locked = locked
+                    .checked_add(vote.amount)
+                    .ok_or(ConstraintCheckerError::ValueOverflow)?;
+            } else {
+                let coin = output
+                    .extract::<Coin<ID>>()
+                    .map_err(|_| ConstraintCheckerError::BadlyTypedOutput)?;
+                ensure!(coin.0 > 0, ConstraintCheckerError::ZeroValue);
+                change = change
+                    .checked_add(coin.0)
+                    .ok_or(ConstraintCheckerError::ValueOverflow)?;
+            }
+        }
+        ensure!(locked > 0, ConstraintCheckerError::NoVotes);
+
+        ensure!(
+            old_proposal.ayes.checked_add(ayes) == Some(new_proposal.ayes)
+                && old_proposal.nays.checked_add(nays) == Some(new_proposal.nays),
+            ConstraintCheckerError::TallyMismatch
+        );
+
+        let total_output = locked
+            .checked_add(change)
+            .ok_or(ConstraintCheckerError::ValueOverflow)?;
+        ensure!(
+            total_output <= total_input,
+            ConstraintCheckerError::OutputsExceedInputs
+        );
+
+        let burned = total_input - total_output;
+        Ok(burned.try_into().unwrap_or(TransactionPriority::MAX))
+    }
+}
+
+/// A constraint checker that tallies a proposal once voting has ended.
+///
+/// The only input is the proposal. If it was approved, the only output is the enacted payload.
+/// Otherwise the proposal is simply removed and there are no outputs.
+#[derive(
+    Serialize,
+    Deserialize,
+    Encode,
+    Decode,
+    DebugNoBound,
+    DefaultNoBound,
+    PartialEq,
+    Eq,
+    CloneNoBound,
+    TypeInfo,
+)]
+#[scale_info(skip_type_params(T))]
+pub struct Tally<T, const ID: u8>(PhantomData<T>);
+
+impl<T: GovernanceConfig, const ID: u8> SimpleConstraintChecker for Tally<T, ID> {
+    type Error = ConstraintCheckerError;
+
+    fn check(
+        &self,
+        input_data: &[DynamicallyTypedData],
+        _peeks: &[DynamicallyTypedData],
+        output_data: &[DynamicallyTypedData],
+        _context: &CheckerContext,
+    ) -> Result<TransactionPriority, Self::Error> {
+        ensure!(
+            input_data.len() == 1,
+            ConstraintCheckerError::WrongNumberInputs
+        );
+        let proposal = input_data[0]
+            .extract::<Proposal<ID>>()
+            .map_err(|_| ConstraintCheckerError::BadlyTypedInput)?;
+        ensure!(
+            T::block_height() >= proposal.voting_ends,
+            ConstraintCheckerError::VotingNotEnded
+        );
+
+        if proposal.is_approved::<T>() {
+            ensure!(
+                output_data.len() == 1,
+                ConstraintCheckerError::WrongNumberOutputs
+            );
+            let enacted = output_data[0]
+                .extract::<Enacted<ID>>()
+                .map_err(|_| ConstraintCheckerError::BadlyTypedOutput)?;
+            ensure!(
+                enacted.payload == proposal.payload,
+                ConstraintCheckerError::PayloadMismatch
+            );
+        } else {
+            ensure!(
+                output_data.is_empty(),
+                ConstraintCheckerError::WrongNumberOutputs
+            );
+        }
+
+        Ok(0)
+    }
+}
+
+/// A constraint checker that unlocks votes back into `Coin<ID>`s once their lock has expired.
+///
+/// All inputs are votes whose unlock height has been reached, and all outputs are `Coin<ID>`s.
+/// Whatever is not unlocked into coins pays the transaction's fee and tip.
+#[derive(
+    Serialize,
+    Deserialize,
+    Encode,
+    Decode,
+    DebugNoBound,
+    DefaultNoBound,
+    PartialEq,
+    Eq,
+    CloneNoBound,
+    TypeInfo,
+)]
+#[scale_info(skip_type_params(T))]
+pub struct UnlockVotes<T, const ID: u8>(PhantomData<T>);
+
+impl<T: GovernanceConfig, const ID: u8> SimpleConstraintChecker for UnlockVotes<T, ID> {
+    type Error = ConstraintCheckerError;
+
+    fn check(
+        &self,
+        input_data: &[DynamicallyTypedData],
+        _peeks: &[DynamicallyTypedData],
+        output_data: &[DynamicallyTypedData],
+        _context: &CheckerContext,
+    ) -> Result<TransactionPriority, Self::Error> {
+        let mut total_input: u128 = 0;
+        for input in input_data {
+            let vote = input
+                .extract::<Vote<ID>>()
+                .map_err(|_| ConstraintCheckerError::BadlyTypedInput)?;
+            ensure!(
+                T::block_height() >= vote.unlocks_at,
+                ConstraintCheckerError::VoteStillLocked
+            );
+            total_input = total_input
+                .checked_add(vote.amount)
+                .ok_or(ConstraintCheckerError::ValueOverflow)?;
+        }
+
+        let mut total_output: u128 = 0;
+        for output in output_data {
+            let coin = output
+                .extract::<Coin<ID>>()
+                .map_err(|_| ConstraintCheckerError::BadlyTypedOutput)?;
+            ensure!(coin.0 > 0, ConstraintCheckerError::ZeroValue);
+            total_output = total_output
+                .checked_add(coin.0)
+                .ok_or(ConstraintCheckerError::ValueOverflow)?;
+        }
+        ensure!(
+            total_output <= total_input,
+            ConstraintCheckerError::OutputsExceedInputs
+        );
+
+        let burned = total_input - total_output;
+        Ok(burned.try_into().unwrap_or(TransactionPriority::MAX))
+    }
+}
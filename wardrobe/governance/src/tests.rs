@@ -0,0 +1,369 @@
+//! Unit tests for the Governance piece
+
+use super::*;
+use tuxedo_core::dynamic_typing::testing::Bogus;
+use ConstraintCheckerError::*;
+
+/// The mock config always says the block number is ten.
+pub struct AlwaysBlockTen;
+
+impl GovernanceConfig for AlwaysBlockTen {
+    fn block_height() -> u32 {
+        10
+    }
+
+    const VOTING_PERIOD: u32 = 5;
+    const APPROVAL_THRESHOLD: u128 = 100;
+    const ENACTMENT_PERIOD: u32 = 3;
+    const MAX_CONVICTION: u8 = 3;
+}
+
+const PAYLOAD: H256 = H256::repeat_byte(9);
+
+fn proposal(voting_ends: u32, ayes: u128, nays: u128) -> DynamicallyTypedData {
+    Proposal::<0> {
+        payload: PAYLOAD,
+        voting_ends,
+        ayes,
+        nays,
+    }
+    .into()
+}
+
+fn vote(amount: u128, aye: bool, unlocks_at: u32) -> DynamicallyTypedData {
+    conviction_vote(amount, aye, 0, unlocks_at)
+}
+
+fn conviction_vote(
+    amount: u128,
+    aye: bool,
+    conviction: u8,
+    unlocks_at: u32,
+) -> DynamicallyTypedData {
+    Vote::<0> {
+        amount,
+        aye,
+        conviction,
+        unlocks_at,
+    }
+    .into()
+}
+
+fn coin(value: u128) -> DynamicallyTypedData {
+    Coin::<0>(value).into()
+}
+
+fn enacted(payload: H256) -> DynamicallyTypedData {
+    Enacted::<0> { payload }.into()
+}
+
+#[test]
+fn propose_works() {
+    let checker = Propose::<AlwaysBlockTen, 0>::default();
+    assert_eq!(
+        checker.check(&[], &[], &[proposal(15, 0, 0)], &Default::default()),
+        Ok(0)
+    );
+}
+
+#[test]
+fn propose_with_short_voting_period_fails() {
+    let checker = Propose::<AlwaysBlockTen, 0>::default();
+    assert_eq!(
+        checker.check(&[], &[], &[proposal(14, 0, 0)], &Default::default()),
+        Err(VotingPeriodTooShort)
+    );
+}
+
+#[test]
+fn propose_with_votes_fails() {
+    let checker = Propose::<AlwaysBlockTen, 0>::default();
+    assert_eq!(
+        checker.check(&[], &[], &[proposal(15, 1_000, 0)], &Default::default()),
+        Err(ProposalNotEmpty)
+    );
+}
+
+#[test]
+fn propose_bogus_output_fails() {
+    let checker = Propose::<AlwaysBlockTen, 0>::default();
+    assert_eq!(
+        checker.check(&[], &[], &[Bogus.into()], &Default::default()),
+        Err(BadlyTypedOutput)
+    );
+}
+
+#[test]
+fn cast_vote_works() {
+    let checker = CastVote::<AlwaysBlockTen, 0>::default();
+    let inputs = vec![proposal(15, 10, 20), coin(100)];
+    let outputs = vec![
+        proposal(15, 70, 50),
+        vote(60, true, 15),
+        vote(30, false, 15),
+        coin(5),
+    ];
+
+    assert_eq!(
+        checker.check(&inputs, &[], &outputs, &Default::default()),
+        Ok(5)
+    );
+}
+
+#[test]
+fn cast_vote_with_conviction_works() {
+    let checker = CastVote::<AlwaysBlockTen, 0>::default();
+    let inputs = vec![proposal(15, 10, 20), coin(100)];
+    let outputs = vec![
+        proposal(15, 130, 80),
+        conviction_vote(40, true, 2, 21),
+        conviction_vote(30, false, 1, 18),
+        coin(30),
+    ];
+
+    assert_eq!(
+        checker.check(&inputs, &[], &outputs, &Default::default()),
+        Ok(0)
+    );
+}
+
+#[test]
+fn lock_period_doubles_with_conviction() {
+    let lock_period = Vote::<0>::lock_period::<AlwaysBlockTen>;
+
+    assert_eq!(lock_period(0), 0);
+    assert_eq!(lock_period(1), 3);
+    assert_eq!(lock_period(2), 6);
+    assert_eq!(lock_period(3), 12);
+    assert_eq!(lock_period(255), u32::MAX);
+}
+
+#[test]
+fn cast_vote_with_conviction_unlocking_early_fails() {
+    let checker = CastVote::<AlwaysBlockTen, 0>::default();
+    let inputs = vec![proposal(15, 0, 0), coin(100)];
+    let outputs = vec![proposal(15, 300, 0), conviction_vote(100, true, 2, 18)];
+
+    assert_eq!(
+        checker.check(&inputs, &[], &outputs, &Default::default()),
+        Err(WrongUnlockHeight)
+    );
+}
+
+#[test]
+fn cast_vote_with_excessive_conviction_fails() {
+    let checker = CastVote::<AlwaysBlockTen, 0>::default();
+    let inputs = vec![proposal(15, 0, 0), coin(100)];
+    let outputs = vec![proposal(15, 500, 0), conviction_vote(100, true, 4, 39)];
+
+    assert_eq!(
+        checker.check(&inputs, &[], &outputs, &Default::default()),
+        Err(ConvictionTooHigh)
+    );
+}
+
+#[test]
+fn cast_vote_inflating_tally_fails() {
+    let checker = CastVote::<AlwaysBlockTen, 0>::default();
+    let inputs = vec![proposal(15, 0, 0), coin(100)];
+    let outputs = vec![proposal(15, 200, 0), vote(100, true, 15)];
+
+    assert_eq!(
+        checker.check(&inputs, &[], &outputs, &Default::default()),
+        Err(TallyMismatch)
+    );
+}
+
+#[test]
+fn cast_vote_exceeding_inputs_fails() {
+    let checker = CastVote::<AlwaysBlockTen, 0>::default();
+    let inputs = vec![proposal(15, 0, 0), coin(100)];
+    let outputs = vec![proposal(15, 200, 0), vote(200, true, 15)];
+
+    assert_eq!(
+        checker.check(&inputs, &[], &outputs, &Default::default()),
+        Err(OutputsExceedInputs)
+    );
+}
+
+#[test]
+fn cast_vote_unlocking_early_fails() {
+    let checker = CastVote::<AlwaysBlockTen, 0>::default();
+    let inputs = vec![proposal(15, 0, 0), coin(100)];
+    let outputs = vec![proposal(15, 100, 0), vote(100, true, 11)];
+
+    assert_eq!(
+        checker.check(&inputs, &[], &outputs, &Default::default()),
+        Err(WrongUnlockHeight)
+    );
+}
+
+#[test]
+fn cast_vote_after_voting_ends_fails() {
+    let checker = CastVote::<AlwaysBlockTen, 0>::default();
+    let inputs = vec![proposal(10, 0, 0), coin(100)];
+    let outputs = vec![proposal(10, 100, 0), vote(100, true, 10)];
+
+    assert_eq!(
+        checker.check(&inputs, &[], &outputs, &Default::default()),
+        Err(VotingEnded)
+    );
+}
+
+#[test]
+fn cast_vote_extending_voting_period_fails() {
+    let checker = CastVote::<AlwaysBlockTen, 0>::default();
+    let inputs = vec![proposal(15, 0, 0), coin(100)];
+    let outputs = vec![proposal(20, 100, 0), vote(100, true, 15)];
+
+    assert_eq!(
+        checker.check(&inputs, &[], &outputs, &Default::default()),
+        Err(ProposalChanged)
+    );
+}
+
+#[test]
+fn cast_vote_without_votes_fails() {
+    let checker = CastVote::<AlwaysBlockTen, 0>::default();
+    let inputs = vec![proposal(15, 0, 0), coin(100)];
+    let outputs = vec![proposal(15, 0, 0), coin(100)];
+
+    assert_eq!(
+        checker.check(&inputs, &[], &outputs, &Default::default()),
+        Err(NoVotes)
+    );
+}
+
+#[test]
+fn cast_vote_without_proposal_fails() {
+    let checker = CastVote::<AlwaysBlockTen, 0>::default();
+    let inputs = vec![coin(100)];
+    let outputs = vec![proposal(15, 100, 0), vote(100, true, 15)];
+
+    assert_eq!(
+        checker.check(&inputs, &[], &outputs, &Default::default()),
+        Err(MissingProposalInput)
+    );
+}
+
+#[test]
+fn tally_approved_proposal_works() {
+    let checker = Tally::<AlwaysBlockTen, 0>::default();
+    assert_eq!(
+        checker.check(
+            &[proposal(10, 100, 50)],
+            &[],
+            &[enacted(PAYLOAD)],
+            &Default::default()
+        ),
+        Ok(0)
+    );
+}
+
+#[test]
+fn tally_rejected_proposal_works() {
+    let checker = Tally::<AlwaysBlockTen, 0>::default();
+    assert_eq!(
+        checker.check(&[proposal(10, 100, 100)], &[], &[], &Default::default()),
+        Ok(0)
+    );
+}
+
+#[test]
+fn tally_below_threshold_cannot_enact() {
+    let checker = Tally::<AlwaysBlockTen, 0>::default();
+    assert_eq!(
+        checker.check(
+            &[proposal(10, 99, 0)],
+            &[],
+            &[enacted(PAYLOAD)],
+            &Default::default()
+        ),
+        Err(WrongNumberOutputs)
+    );
+}
+
+#[test]
+fn tally_approved_proposal_must_enact() {
+    let checker = Tally::<AlwaysBlockTen, 0>::default();
+    assert_eq!(
+        checker.check(&[proposal(10, 100, 0)], &[], &[], &Default::default()),
+        Err(WrongNumberOutputs)
+    );
+}
+
+#[test]
+fn tally_enacting_wrong_payload_fails() {
+    let checker = Tally::<AlwaysBlockTen, 0>::default();
+    assert_eq!(
+        checker.check(
+            &[proposal(10, 100, 0)],
+            &[],
+            &[enacted(H256::repeat_byte(1))],
+            &Default::default()
+        ),
+        Err(PayloadMismatch)
+    );
+}
+
+#[test]
+fn tally_before_voting_ends_fails() {
+    let checker = Tally::<AlwaysBlockTen, 0>::default();
+    assert_eq!(
+        checker.check(
+            &[proposal(11, 100, 0)],
+            &[],
+            &[enacted(PAYLOAD)],
+            &Default::default()
+        ),
+        Err(VotingNotEnded)
+    );
+}
+
+#[test]
+fn unlock_votes_works() {
+    let checker = UnlockVotes::<AlwaysBlockTen, 0>::default();
+    let inputs = vec![vote(60, true, 10), vote(40, false, 5)];
+    let outputs = vec![coin(99)];
+
+    assert_eq!(
+        checker.check(&inputs, &[], &outputs, &Default::default()),
+        Ok(1)
+    );
+}
+
+#[test]
+fn unlock_votes_before_voting_ends_fails() {
+    let checker = UnlockVotes::<AlwaysBlockTen, 0>::default();
+    let inputs = vec![vote(60, true, 11)];
+    let outputs = vec![coin(60)];
+
+    assert_eq!(
+        checker.check(&inputs, &[], &outputs, &Default::default()),
+        Err(VoteStillLocked)
+    );
+}
+
+#[test]
+fn unlock_conviction_vote_works() {
+    let checker = UnlockVotes::<AlwaysBlockTen, 0>::default();
+    let inputs = vec![conviction_vote(60, true, 1, 10)];
+    let outputs = vec![coin(60)];
+
+    assert_eq!(
+        checker.check(&inputs, &[], &outputs, &Default::default()),
+        Ok(0)
+    );
+}
+
+#[test]
+fn unlock_votes_exceeding_inputs_fails() {
+    let checker = UnlockVotes::<AlwaysBlockTen, 0>::default();
+    let inputs = vec![vote(60, true, 10)];
+    let outputs = vec![coin(61)];
+
+    assert_eq!(
+        checker.check(&inputs, &[], &outputs, &Default::default()),
+        Err(OutputsExceedInputs)
+    );
+}
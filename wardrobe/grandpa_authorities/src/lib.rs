@@ -0,0 +1,300 @@
+//! On-chain GRANDPA authorities that governance can rotate without a runtime upgrade.
+//!
+//! The current authorities and their set id live in a [`GrandpaAuthoritySet`] UTXO. Governance
+//! rotates them by enacting a referendum whose payload is the hash of the new set, and then
+//! submitting a [`RotateAuthorities`] transaction that consumes the enacted outcome together with
+//! the current set, and creates the new set with the next set id.
+//!
+//! Like the Aura authorities piece, the checker writes the new set to a well-known storage key as a
+//! side effect, so that the runtime's `GrandpaApi` can read it through [`authority_set`]. Until
+//! governance rotates them for the first time, the authorities are the ones that the runtime
+//! configures in [`GrandpaAuthoritiesConfig::genesis_authorities`], with set id zero, and they are
+//! not in the UTXO set. The first rotation therefore consumes only the enacted outcome.
+//!
+//! The GRANDPA voters do not read the authorities from the runtime's state. They learn about a new
+//! set from a scheduled change log in the header of the block that includes the rotation, which the
+//! checker deposits. The change has no delay, so the voters switch to the new set once that block
+//! is finalized, while the runtime's state reports the new set from that block onward.
+//!
+//! Nodes that warp sync do not replay the changes. They jump to a finalized block and take its set
+//! from the runtime's `GrandpaApi`, so the set id that the runtime reports must count exactly the
+//! changes that the voters saw. GRANDPA only reads one scheduled change from each block, so the
+//! checker refuses to rotate in a block that the runtime reports to
+//! [already schedule a change](GrandpaAuthoritiesConfig::change_scheduled), for example because an
+//! authority published a new session key in it.
+//!
+//! ## Hack Warning
+//!
+//! Like the timestamp piece, this piece abuses the UpForGrabs verifier to protect the authority set,
+//! and relies on the constraint checker to make sure that only governance can replace it.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use core::marker::PhantomData;
+
+use governance::Enacted;
+use parity_scale_codec::{Decode, Encode};
+use scale_info::TypeInfo;
+use serde::{Deserialize, Serialize};
+use sp_consensus_grandpa::{
+    AuthorityId, AuthorityList, AuthorityWeight, ConsensusLog, ScheduledChange, SetId,
+    GRANDPA_ENGINE_ID,
+};
+use sp_core::{ed25519, H256};
+use sp_runtime::{
+    traits::{BlakeTwo256, Hash},
+    transaction_validity::TransactionPriority,
+    DigestItem,
+};
+use sp_std::{collections::btree_set::BTreeSet, vec::Vec};
+use tuxedo_core::{
+    digest,
+    dynamic_typing::UtxoData,
+    ensure,
+    support_macros::{CloneNoBound, DebugNoBound, DefaultNoBound},
+    types::Output,
+    verifier::UpForGrabs,
+    CheckerContext, ConstraintChecker, Verifier,
+};
+
+#[cfg(test)]
+mod tests;
+
+/// The storage key that holds the current authority set once governance has rotated it.
+const AUTHORITIES_KEY: &[u8] = b"grandpa_authorities";
+
+/// Configuration items for the GRANDPA authorities piece when it is
+/// instantiated in a concrete runtime.
+pub trait GrandpaAuthoritiesConfig {
+    /// The ed25519 public keys and weights of the authorities before governance
+    /// rotates them for the first time.
+    fn genesis_authorities() -> Vec<(H256, AuthorityWeight)>;
+
+    /// The key that the given authority votes with. Runtimes that let authorities
+    /// publish session keys map them here, so that rotations schedule the keys that
+    /// the voters actually hold.
+    fn voting_key(authority: H256) -> H256 {
+        authority
+    }
+
+    /// Whether another piece, such as the session keys piece, already scheduled a change to
+    /// the GRANDPA voters in the current block. Rotations are refused in such blocks.
+    fn change_scheduled() -> bool {
+        false
+    }
+}
+
+/// The ed25519 public keys and weights of the GRANDPA authorities, along with the id of the set.
+#[derive(
+    Serialize,
+    Deserialize,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Clone,
+    Encode,
+    Decode,
+    Hash,
+    Debug,
+    TypeInfo,
+)]
+pub struct GrandpaAuthoritySet {
+    /// The id of the set. It goes up by one with each rotation.
+    pub set_id: SetId,
+    /// The public keys of the authorities, along with their voting weights.
+    pub authorities: Vec<(H256, AuthorityWeight)>,
+}
+
+impl GrandpaAuthoritySet {
+    /// The payload that a governance referendum must enact to approve rotating to this set.
+    pub fn governance_payload(&self) -> H256 {
+        BlakeTwo256::hash_of(self)
+    }
+
+    /// The authorities in the form that GRANDPA expects.
+    pub fn authority_list(&self) -> AuthorityList {
+        self.authorities
+            .iter()
+            .map(|(key, weight)| (AuthorityId::from(ed25519::Public::from_raw(key.0)), *weight))
+            .collect()
+    }
+}
+
+impl UtxoData for GrandpaAuthoritySet {
+    const TYPE_ID: [u8; 4] = *b"gran";
+}
+
+/// The authority set that governance last rotated to, if any.
+fn rotated_authority_set() -> Option<GrandpaAuthoritySet> {
+    sp_io::storage::get(AUTHORITIES_KEY).and_then(|d| GrandpaAuthoritySet::decode(&mut &*d).ok())
+}
+
+/// The current GRANDPA authority set. This is the one that governance last rotated to,
+/// or the genesis authorities with set id zero if governance has not rotated them yet.
+pub fn authority_set<T: GrandpaAuthoritiesConfig>() -> GrandpaAuthoritySet {
+    rotated_authority_set().unwrap_or_else(|| GrandpaAuthoritySet {
+        set_id: 0,
+        authorities: T::genesis_authorities(),
+    })
+}
+
+/// Errors that can occur when rotating the authorities.
+#[derive(
+    Serialize,
+    Deserialize,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Clone,
+    Encode,
+    Decode,
+    Hash,
+    Debug,
+    TypeInfo,
+)]
+pub enum ConstraintCheckerError {
+    /// An input data has the wrong type.
+    BadlyTypedInput,
+    /// An output data has the wrong type.
+    BadlyTypedOutput,
+    /// Wrong number of inputs were provided to the constraint checker.
+    WrongNumberInputs,
+    /// Wrong number of outputs were provided to the constraint checker.
+    WrongNumberOutputs,
+    /// The rotation is not backed by an enacted referendum outcome as the first input.
+    NotApproved,
+    /// The enacted referendum outcome approves some other payload than the new authority set.
+    WrongGovernanceOutcome,
+    /// The consumed authority set is not the current one.
+    InputMismatch,
+    /// The new authority set's id does not follow the current one.
+    WrongSetId,
+    /// The new authority set is not protected by the `UpForGrabs` verifier, so it could not be rotated again.
+    SetNotUpForGrabs,
+    /// The new authority set is empty, which would stop finality.
+    NoAuthorities,
+    /// The new authority set lists an authority more than once.
+    DuplicateAuthority,
+    /// An authority in the new set has no voting weight. GRANDPA does not accept such sets.
+    ZeroWeight,
+    /// The block already schedules a change to the GRANDPA voters, and GRANDPA only reads one.
+    ChangeAlreadyScheduled,
+}
+
+/// A constraint checker that rotates the GRANDPA authorities with the approval of governance.
+///
+/// The first input is an enacted referendum outcome whose payload is the hash of the new set.
+/// The second input is the current authority set, unless the genesis authorities are still in force.
+/// The single output is the new authority set, which must be up for grabs.
+#[derive(
+    Serialize,
+    Deserialize,
+    Encode,
+    Decode,
+    DebugNoBound,
+    DefaultNoBound,
+    PartialEq,
+    Eq,
+    CloneNoBound,
+    TypeInfo,
+)]
+#[scale_info(skip_type_params(T))]
+pub struct RotateAuthorities<T, const ID: u8>(PhantomData<T>);
+
+impl<T: GrandpaAuthoritiesConfig, V: Verifier + PartialEq + From<UpForGrabs>, const ID: u8>
+    ConstraintChecker<V> for RotateAuthorities<T, ID>
+{
+    type Error = ConstraintCheckerError;
+    type InherentHooks = ();
+
+    fn check(
+        &self,
+        inputs: &[Output<V>],
+        _peeks: &[Output<V>],
+        outputs: &[Output<V>],
+        _context: &CheckerContext,
+    ) -> Result<TransactionPriority, Self::Error> {
+        ensure!(
+            outputs.len() == 1,
+            ConstraintCheckerError::WrongNumberOutputs
+        );
+        let new_set = outputs[0]
+            .payload
+            .extract::<GrandpaAuthoritySet>()
+            .map_err(|_| ConstraintCheckerError::BadlyTypedOutput)?;
+        ensure!(
+            outputs[0].verifier == UpForGrabs.into(),
+            ConstraintCheckerError::SetNotUpForGrabs
+        );
+        ensure!(
+            !new_set.authorities.is_empty(),
+            ConstraintCheckerError::NoAuthorities
+        );
+        ensure!(
+            new_set.authorities.iter().all(|(_, weight)| *weight > 0),
+            ConstraintCheckerError::ZeroWeight
+        );
+        let distinct: BTreeSet<_> = new_set.authorities.iter().map(|(key, _)| key).collect();
+        ensure!(
+            distinct.len() == new_set.authorities.len(),
+            ConstraintCheckerError::DuplicateAuthority
+        );
+
+        let outcome = inputs
+            .first()
+            .and_then(|input| input.payload.extract::<Enacted<ID>>().ok())
+            .ok_or(ConstraintCheckerError::NotApproved)?;
+        ensure!(
+            outcome.payload == new_set.governance_payload(),
+            ConstraintCheckerError::WrongGovernanceOutcome
+        );
+
+        // Once governance has rotated the authorities, the current set is in the UTXO set and must be consumed.
+        let current = authority_set::<T>();
+        if rotated_authority_set().is_some() {
+            ensure!(inputs.len() == 2, ConstraintCheckerError::WrongNumberInputs);
+            let consumed = inputs[1]
+                .payload
+                .extract::<GrandpaAuthoritySet>()
+                .map_err(|_| ConstraintCheckerError::BadlyTypedInput)?;
+            ensure!(consumed == current, ConstraintCheckerError::InputMismatch);
+        } else {
+            ensure!(inputs.len() == 1, ConstraintCheckerError::WrongNumberInputs);
+        }
+        ensure!(
+            new_set.set_id == current.set_id + 1,
+            ConstraintCheckerError::WrongSetId
+        );
+        ensure!(
+            !T::change_scheduled(),
+            ConstraintCheckerError::ChangeAlreadyScheduled
+        );
+
+        // SIDE EFFECT: Write the new authorities to storage so that the runtime API can find them
+        sp_io::storage::set(AUTHORITIES_KEY, &new_set.encode());
+
+        // SIDE EFFECT: Schedule the change for the GRANDPA voters in the block's digest.
+        // Like the rest of the wardrobe, this assumes that block numbers are u32.
+        let voters = GrandpaAuthoritySet {
+            set_id: new_set.set_id,
+            authorities: new_set
+                .authorities
+                .iter()
+                .map(|(key, weight)| (T::voting_key(*key), *weight))
+                .collect(),
+        };
+        let change = ConsensusLog::<u32>::ScheduledChange(ScheduledChange {
+            next_authorities: voters.authority_list(),
+            delay: 0,
+        });
+        digest::deposit_log(DigestItem::Consensus(GRANDPA_ENGINE_ID, change.encode()));
+
+        Ok(0)
+    }
+
+    fn is_inherent(&self) -> bool {
+        false
+    }
+}
@@ -0,0 +1,307 @@
+//! Unit tests for the GRANDPA Authorities piece
+
+use super::*;
+use sp_io::TestExternalities;
+use tuxedo_core::{tuxedo_verifier, verifier::SigCheck};
+
+/// A verifier that can represent the authority set and ordinary users.
+#[derive(Serialize, Deserialize, Encode, Decode, Debug, PartialEq, Eq, Clone, TypeInfo)]
+#[tuxedo_verifier]
+pub enum TestVerifier {
+    SigCheck(SigCheck),
+    UpForGrabs(UpForGrabs),
+}
+
+/// The mock config starts with Alice as the only authority.
+pub struct TestConfig;
+
+impl GrandpaAuthoritiesConfig for TestConfig {
+    fn genesis_authorities() -> Vec<(H256, AuthorityWeight)> {
+        vec![(alice(), 1)]
+    }
+}
+
+type Rotate = RotateAuthorities<TestConfig, 0>;
+
+fn alice() -> H256 {
+    H256::repeat_byte(1)
+}
+
+fn bob() -> H256 {
+    H256::repeat_byte(2)
+}
+
+fn set(set_id: SetId, authorities: Vec<(H256, AuthorityWeight)>) -> GrandpaAuthoritySet {
+    GrandpaAuthoritySet {
+        set_id,
+        authorities,
+    }
+}
+
+fn set_output(set_id: SetId, authorities: Vec<(H256, AuthorityWeight)>) -> Output<TestVerifier> {
+    (set(set_id, authorities), UpForGrabs.into()).into()
+}
+
+fn approval(set_id: SetId, authorities: Vec<(H256, AuthorityWeight)>) -> Output<TestVerifier> {
+    let payload = set(set_id, authorities).governance_payload();
+    (Enacted::<0> { payload }, UpForGrabs.into()).into()
+}
+
+fn rotate(
+    inputs: Vec<Output<TestVerifier>>,
+    outputs: Vec<Output<TestVerifier>>,
+) -> Result<TransactionPriority, ConstraintCheckerError> {
+    Rotate::default().check(&inputs, &[], &outputs, &Default::default())
+}
+
+/// The log that schedules a change to the given authorities.
+fn scheduled_change(authorities: Vec<(H256, AuthorityWeight)>) -> DigestItem {
+    let change = ConsensusLog::<u32>::ScheduledChange(ScheduledChange {
+        next_authorities: set(0, authorities).authority_list(),
+        delay: 0,
+    });
+    DigestItem::Consensus(GRANDPA_ENGINE_ID, change.encode())
+}
+
+#[test]
+fn genesis_authorities_are_current_before_rotation() {
+    TestExternalities::default().execute_with(|| {
+        assert_eq!(authority_set::<TestConfig>(), set(0, vec![(alice(), 1)]));
+    });
+}
+
+#[test]
+fn first_rotation_works() {
+    TestExternalities::default().execute_with(|| {
+        let authorities = vec![(alice(), 1), (bob(), 2)];
+
+        assert_eq!(
+            rotate(
+                vec![approval(1, authorities.clone())],
+                vec![set_output(1, authorities.clone())]
+            ),
+            Ok(0)
+        );
+        assert_eq!(authority_set::<TestConfig>(), set(1, authorities.clone()));
+        assert_eq!(
+            digest::deposited_logs(),
+            vec![scheduled_change(authorities)]
+        );
+    });
+}
+
+#[test]
+fn later_rotation_consumes_current_set() {
+    TestExternalities::default().execute_with(|| {
+        let first = vec![(alice(), 1), (bob(), 1)];
+        rotate(vec![approval(1, first.clone())], vec![set_output(1, first.clone())]).unwrap();
+
+        assert_eq!(
+            rotate(
+                vec![approval(2, vec![(bob(), 1)]), set_output(1, first)],
+                vec![set_output(2, vec![(bob(), 1)])]
+            ),
+            Ok(0)
+        );
+        assert_eq!(authority_set::<TestConfig>(), set(2, vec![(bob(), 1)]));
+    });
+}
+
+#[test]
+fn later_rotation_without_current_set_fails() {
+    TestExternalities::default().execute_with(|| {
+        let first = vec![(alice(), 1), (bob(), 1)];
+        rotate(vec![approval(1, first.clone())], vec![set_output(1, first)]).unwrap();
+
+        assert_eq!(
+            rotate(
+                vec![approval(2, vec![(bob(), 1)])],
+                vec![set_output(2, vec![(bob(), 1)])]
+            ),
+            Err(ConstraintCheckerError::WrongNumberInputs)
+        );
+    });
+}
+
+#[test]
+fn later_rotation_consuming_stale_set_fails() {
+    TestExternalities::default().execute_with(|| {
+        let first = vec![(alice(), 1), (bob(), 1)];
+        rotate(vec![approval(1, first.clone())], vec![set_output(1, first)]).unwrap();
+
+        assert_eq!(
+            rotate(
+                vec![approval(2, vec![(bob(), 1)]), set_output(0, vec![(alice(), 1)])],
+                vec![set_output(2, vec![(bob(), 1)])]
+            ),
+            Err(ConstraintCheckerError::InputMismatch)
+        );
+    });
+}
+
+#[test]
+fn rotation_skipping_set_id_fails() {
+    TestExternalities::default().execute_with(|| {
+        assert_eq!(
+            rotate(
+                vec![approval(2, vec![(bob(), 1)])],
+                vec![set_output(2, vec![(bob(), 1)])]
+            ),
+            Err(ConstraintCheckerError::WrongSetId)
+        );
+        assert!(digest::deposited_logs().is_empty());
+    });
+}
+
+#[test]
+fn rotation_without_approval_fails() {
+    TestExternalities::default().execute_with(|| {
+        assert_eq!(
+            rotate(vec![], vec![set_output(1, vec![(bob(), 1)])]),
+            Err(ConstraintCheckerError::NotApproved)
+        );
+        assert_eq!(authority_set::<TestConfig>(), set(0, vec![(alice(), 1)]));
+    });
+}
+
+#[test]
+fn rotation_with_other_approval_fails() {
+    TestExternalities::default().execute_with(|| {
+        assert_eq!(
+            rotate(
+                vec![approval(1, vec![(alice(), 1)])],
+                vec![set_output(1, vec![(bob(), 1)])]
+            ),
+            Err(ConstraintCheckerError::WrongGovernanceOutcome)
+        );
+    });
+}
+
+#[test]
+fn rotation_to_owned_set_fails() {
+    TestExternalities::default().execute_with(|| {
+        let owned = (set(1, vec![(bob(), 1)]), SigCheck::new(bob()).into()).into();
+
+        assert_eq!(
+            rotate(vec![approval(1, vec![(bob(), 1)])], vec![owned]),
+            Err(ConstraintCheckerError::SetNotUpForGrabs)
+        );
+    });
+}
+
+#[test]
+fn rotation_to_empty_set_fails() {
+    TestExternalities::default().execute_with(|| {
+        assert_eq!(
+            rotate(vec![approval(1, vec![])], vec![set_output(1, vec![])]),
+            Err(ConstraintCheckerError::NoAuthorities)
+        );
+    });
+}
+
+#[test]
+fn rotation_with_zero_weight_fails() {
+    TestExternalities::default().execute_with(|| {
+        let authorities = vec![(alice(), 1), (bob(), 0)];
+
+        assert_eq!(
+            rotate(
+                vec![approval(1, authorities.clone())],
+                vec![set_output(1, authorities)]
+            ),
+            Err(ConstraintCheckerError::ZeroWeight)
+        );
+    });
+}
+
+#[test]
+fn rotation_with_duplicate_authority_fails() {
+    TestExternalities::default().execute_with(|| {
+        let authorities = vec![(bob(), 1), (bob(), 2)];
+
+        assert_eq!(
+            rotate(
+                vec![approval(1, authorities.clone())],
+                vec![set_output(1, authorities)]
+            ),
+            Err(ConstraintCheckerError::DuplicateAuthority)
+        );
+    });
+}
+
+/// A mock config where Alice votes with a session key.
+pub struct AliceSessionConfig;
+
+impl GrandpaAuthoritiesConfig for AliceSessionConfig {
+    fn genesis_authorities() -> Vec<(H256, AuthorityWeight)> {
+        vec![(alice(), 1)]
+    }
+
+    fn voting_key(authority: H256) -> H256 {
+        if authority == alice() {
+            H256::repeat_byte(3)
+        } else {
+            authority
+        }
+    }
+}
+
+#[test]
+fn rotation_schedules_voting_keys() {
+    TestExternalities::default().execute_with(|| {
+        let authorities = vec![(alice(), 1), (bob(), 2)];
+
+        assert_eq!(
+            RotateAuthorities::<AliceSessionConfig, 0>::default().check(
+                &[approval(1, authorities.clone())],
+                &[],
+                &[set_output(1, authorities.clone())],
+                &Default::default()
+            ),
+            Ok(0)
+        );
+        assert_eq!(authority_set::<AliceSessionConfig>(), set(1, authorities));
+        assert_eq!(
+            digest::deposited_logs(),
+            vec![scheduled_change(vec![
+                (H256::repeat_byte(3), 1),
+                (bob(), 2)
+            ])]
+        );
+    });
+}
+
+/// A mock config for a block where another piece already scheduled a change.
+pub struct ChangeScheduledConfig;
+
+impl GrandpaAuthoritiesConfig for ChangeScheduledConfig {
+    fn genesis_authorities() -> Vec<(H256, AuthorityWeight)> {
+        vec![(alice(), 1)]
+    }
+
+    fn change_scheduled() -> bool {
+        true
+    }
+}
+
+#[test]
+fn rotation_in_block_with_scheduled_change_fails() {
+    TestExternalities::default().execute_with(|| {
+        let authorities = vec![(alice(), 1), (bob(), 2)];
+
+        assert_eq!(
+            RotateAuthorities::<ChangeScheduledConfig, 0>::default().check(
+                &[approval(1, authorities.clone())],
+                &[],
+                &[set_output(1, authorities)],
+                &Default::default()
+            ),
+            Err(ConstraintCheckerError::ChangeAlreadyScheduled)
+        );
+        assert_eq!(
+            authority_set::<ChangeScheduledConfig>(),
+            set(0, vec![(alice(), 1)])
+        );
+        assert!(digest::deposited_logs().is_empty());
+    });
+}
@@ -0,0 +1,403 @@
+//! Send and receive messages between parachains over HRMP channels.
+//!
+//! This piece is the transport that cross-parachain application protocols build on. It does not
+//! interpret the messages. That is left to the application pieces.
+//!
+//! * Inbound messages are delivered as UTXOs. Once per block, the block author includes a
+//!   [`ReceiveMessages`] inherent that creates an [`InboundMessage`] for each message that another
+//!   parachain sent to this one. Application pieces peek at these messages to act on them.
+//! * Once the applications had their chance, messages are removed from the UTXO set by the dedicated
+//!   [`RetireMessages`] constraint checker. A message can only be retired once it is
+//!   [`MESSAGE_LIFETIME`](HrmpMessagingConfig::MESSAGE_LIFETIME) relay blocks old.
+//! * Outbound messages use the [`SendMessage`] constraint checker. It queues the message to be sent
+//!   with the block, and pays for it with coins. Any other piece may also queue messages through
+//!   its runtime's [`SendHorizontalMessage`] implementation.
+//!
+//! A message can only be sent through an open channel with room for it, and a block can only send
+//! one message to each recipient. These are the relay chain's rules, and a block that breaks them
+//! would be rejected, so transactions whose messages break them are invalid instead.
+//!
+//! ## Hack Warning
+//!
+//! Like the timestamp piece, this piece currently abuses the UpForGrabs verifier for the inbound
+//! messages. This should be replaced with an Unspendable verifier and an eviction workflow.
+//!
+//! ## Trust Warning
+//!
+//! The inbound messages are not yet checked against the message queue chains in the relay chain
+//! state proof, so this piece trusts the collator to report them faithfully.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use core::marker::PhantomData;
+
+use cumulus_primitives_core::{InboundHrmpMessage, OutboundHrmpMessage, ParaId};
+use cumulus_primitives_parachain_inherent::{
+    ParachainInherentData, INHERENT_IDENTIFIER as PARACHAIN_INHERENT_IDENTIFIER,
+};
+use money::{Coin, MoneyConfig};
+use parity_scale_codec::{Decode, Encode};
+use scale_info::TypeInfo;
+use serde::{Deserialize, Serialize};
+use sp_core::H256;
+use sp_inherents::{CheckInherentsResult, InherentData, MakeFatalError};
+use sp_runtime::transaction_validity::TransactionPriority;
+use sp_std::{collections::btree_map::BTreeMap, vec, vec::Vec};
+// We get all the Tuxedo core stuff through the re-export so we don't risk crossed versions.
+use tuxedo_parachain_core::{
+    tuxedo_core::{
+        dynamic_typing::{DynamicallyTypedData, UtxoData},
+        ensure,
+        inherents::{TuxedoInherent, TuxedoInherentAdapter},
+        support_macros::{CloneNoBound, DebugNoBound, DefaultNoBound},
+        types::{Output, Transaction, TransactionVersion},
+        verifier::UpForGrabs,
+        CheckerContext, ConstraintChecker, SimpleConstraintChecker, Verifier,
+    },
+    GetHorizontalMessagingStorage, GetRelayParentNumberStorage, HorizontalMessageError,
+    SendHorizontalMessage,
+};
+
+#[cfg(test)]
+mod tests;
+
+/// A piece-wide target for logging
+const LOG_TARGET: &str = "hrmp-messaging";
+
+/// Configuration items for the hrmp messaging piece when it is
+/// instantiated in a concrete runtime.
+pub trait HrmpMessagingConfig: MoneyConfig {
+    /// A means of getting the relay parent number of the current block.
+    /// Probably this will be `tuxedo_parachain_core::RelayParentNumberStorage`.
+    type GetRelayParentNumber: GetRelayParentNumberStorage;
+
+    /// A means of getting the horizontal messaging state of the current block.
+    /// Probably this will be `tuxedo_parachain_core::HorizontalMessagingStorage`.
+    type GetHorizontalMessaging: GetHorizontalMessagingStorage;
+
+    /// A means of sending messages to other parachains.
+    /// Probably this will be `tuxedo_parachain_core::OutboundHorizontalMessages`.
+    type SendHorizontalMessage: SendHorizontalMessage;
+
+    /// The number of relay blocks that an inbound message stays available to peek at
+    /// before it may be retired.
+    ///
+    /// Default is 14_400 which is about a day with six second relay blocks.
+    const MESSAGE_LIFETIME: u32 = 14_400;
+}
+
+/// A message that another parachain sent to this one.
+#[derive(Debug, Encode, Decode, PartialEq, Eq, Clone, TypeInfo)]
+pub struct InboundMessage {
+    /// The id of the parachain that sent the message.
+    pub sender: u32,
+    /// The relay block at which the message was sent.
+    pub sent_at: u32,
+    /// The message itself, which the application pieces interpret.
+    pub data: Vec<u8>,
+}
+
+impl UtxoData for InboundMessage {
+    const TYPE_ID: [u8; 4] = *b"hrmp";
+}
+
+/// The outputs that deliver the given inbound messages, by sender and then in the order that
+/// each sender sent them.
+pub fn deliveries<V: From<UpForGrabs>>(
+    inbound: &BTreeMap<ParaId, Vec<InboundHrmpMessage>>,
+) -> Vec<Output<V>> {
+    inbound
+        .iter()
+        .flat_map(|(sender, messages)| {
+            messages.iter().map(move |message| Output {
+                payload: InboundMessage {
+                    sender: (*sender).into(),
+                    sent_at: message.sent_at,
+                    data: message.data.clone(),
+                }
+                .into(),
+                verifier: UpForGrabs.into(),
+            })
+        })
+        .collect()
+}
+
+/// Errors that can occur when sending, receiving, or retiring messages.
+#[derive(Debug, Eq, PartialEq)]
+pub enum ConstraintCheckerError {
+    /// Inputs or peeks were provided while receiving messages. Received messages come from the void.
+    WrongNumberInputs,
+    /// The relay parent noted in the inherent does not match the block into which it was inserted.
+    WrongRelayParent,
+    /// The delivered messages do not match the block's inbound messages.
+    WrongDeliveries,
+    /// UTXO data has an unexpected type.
+    BadlyTyped,
+    /// No coins were consumed to pay for sending a message.
+    SpendingNothing,
+    /// The value of the change is more than the value of the consumed coins.
+    OutputsExceedInputs,
+    /// The value of the coins overflowed while being added up.
+    ValueOverflow,
+    /// No messages were retired.
+    RetiringNothing,
+    /// Outputs were provided while retiring messages. Retired messages are simply removed.
+    RetiringWithOutputs,
+    /// A message was retired before the end of its lifetime.
+    MessageNotExpired,
+    /// The message can not be sent.
+    SendFailed(HorizontalMessageError),
+}
+
+/// A constraint checker that delivers the messages that other parachains sent to this one.
+///
+/// This is expected to be performed through an inherent, and to happen exactly once per block.
+///
+/// There are no inputs. There is one output for each inbound message, by sender and then in the
+/// order that each sender sent them. The relay parent number is part of the checker so that two
+/// identical messages in different blocks do not have the same output references.
+#[derive(
+    Serialize,
+    Deserialize,
+    Encode,
+    Decode,
+    DebugNoBound,
+    DefaultNoBound,
+    PartialEq,
+    Eq,
+    CloneNoBound,
+    TypeInfo,
+)]
+#[scale_info(skip_type_params(T))]
+pub struct ReceiveMessages<T> {
+    /// The relay parent of the block whose inbound messages are delivered.
+    pub relay_parent_number: u32,
+    _phantom: PhantomData<T>,
+}
+
+impl<T> ReceiveMessages<T> {
+    pub fn new(relay_parent_number: u32) -> Self {
+        Self {
+            relay_parent_number,
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<T: HrmpMessagingConfig + 'static, V: Verifier + PartialEq + From<UpForGrabs>>
+    ConstraintChecker<V> for ReceiveMessages<T>
+{
+    type Error = ConstraintCheckerError;
+    type InherentHooks = TuxedoInherentAdapter<Self>;
+
+    fn check(
+        &self,
+        input_data: &[Output<V>],
+        peek_data: &[Output<V>],
+        output_data: &[Output<V>],
+        _context: &CheckerContext,
+    ) -> Result<TransactionPriority, Self::Error> {
+        ensure!(
+            input_data.is_empty() && peek_data.is_empty(),
+            ConstraintCheckerError::WrongNumberInputs
+        );
+        ensure!(
+            self.relay_parent_number == T::GetRelayParentNumber::get(),
+            ConstraintCheckerError::WrongRelayParent
+        );
+        ensure!(
+            output_data == deliveries::<V>(&T::GetHorizontalMessaging::get().inbound),
+            ConstraintCheckerError::WrongDeliveries
+        );
+
+        Ok(0)
+    }
+
+    fn is_inherent(&self) -> bool {
+        true
+    }
+}
+
+impl<V: Verifier + PartialEq + From<UpForGrabs>, T: HrmpMessagingConfig + 'static>
+    TuxedoInherent<V, Self> for ReceiveMessages<T>
+{
+    type Error = MakeFatalError<()>;
+    // This sorts after the parachain inherent, so the inbound messages are in storage by the time this runs.
+    const INHERENT_IDENTIFIER: sp_inherents::InherentIdentifier = *b"xcmp_rcv";
+
+    fn create_inherent(
+        authoring_inherent_data: &InherentData,
+        _previous_inherent: (Transaction<V, Self>, H256),
+    ) -> Transaction<V, Self> {
+        // The parachain inherent has not been applied yet, so read the messages from the inherent data.
+        let parachain_data: ParachainInherentData = authoring_inherent_data
+            .get_data(&PARACHAIN_INHERENT_IDENTIFIER)
+            .expect("Inherent data should decode properly")
+            .expect("Parachain inherent data should be present.");
+
+        let outputs = deliveries(&parachain_data.horizontal_messages);
+        log::debug!(
+            target: LOG_TARGET,
+            "Delivering {} inbound messages.",
+            outputs.len()
+        );
+
+        Transaction {
+            version: TransactionVersion::LATEST,
+            inputs: Vec::new(),
+            peeks: Vec::new(),
+            outputs,
+            checker: Self::new(parachain_data.validation_data.relay_parent_number),
+            memo: None,
+            valid_until: None,
+        }
+    }
+
+    fn check_inherent(
+        _importing_inherent_data: &InherentData,
+        _inherent: Transaction<V, Self>,
+        _result: &mut CheckInherentsResult,
+    ) {
+        // The deliveries are checked against the inbound messages on-chain,
+        // so no off-chain checks are required.
+    }
+
+    #[cfg(feature = "std")]
+    fn genesis_transactions() -> Vec<Transaction<V, Self>> {
+        // The inherent must appear in every block, including genesis, so that
+        // block authors always find a previous inherent to build on.
+        vec![Transaction {
+            version: TransactionVersion::LATEST,
+            inputs: Vec::new(),
+            peeks: Vec::new(),
+            outputs: Vec::new(),
+            checker: Self::default(),
+            memo: None,
+            valid_until: None,
+        }]
+    }
+}
+
+/// A constraint checker that removes inbound messages whose lifetime is over.
+///
+/// The inputs are the retired messages, and there are no outputs.
+#[derive(
+    Serialize,
+    Deserialize,
+    Encode,
+    Decode,
+    DebugNoBound,
+    DefaultNoBound,
+    PartialEq,
+    Eq,
+    CloneNoBound,
+    TypeInfo,
+)]
+#[scale_info(skip_type_params(T))]
+pub struct RetireMessages<T>(PhantomData<T>);
+
+impl<T: HrmpMessagingConfig> SimpleConstraintChecker for RetireMessages<T> {
+    type Error = ConstraintCheckerError;
+
+    fn check(
+        &self,
+        input_data: &[DynamicallyTypedData],
+        _peek_data: &[DynamicallyTypedData],
+        output_data: &[DynamicallyTypedData],
+        _context: &CheckerContext,
+    ) -> Result<TransactionPriority, Self::Error> {
+        ensure!(
+            !input_data.is_empty(),
+            ConstraintCheckerError::RetiringNothing
+        );
+        ensure!(
+            output_data.is_empty(),
+            ConstraintCheckerError::RetiringWithOutputs
+        );
+
+        let relay_parent_number = T::GetRelayParentNumber::get();
+        for input in input_data {
+            let message = input
+                .extract::<InboundMessage>()
+                .map_err(|_| ConstraintCheckerError::BadlyTyped)?;
+            ensure!(
+                message.sent_at.saturating_add(T::MESSAGE_LIFETIME) <= relay_parent_number,
+                ConstraintCheckerError::MessageNotExpired
+            );
+        }
+
+        Ok(0)
+    }
+}
+
+/// A constraint checker that sends a message to another parachain, paid for with coins.
+///
+/// The inputs are the coins that pay for the message, and the outputs are any change.
+/// Whatever value is left over is burned, and counts as the transaction's fee and tip when the
+/// coins are native (see `money::surplus`).
+#[derive(
+    Serialize, Deserialize, Encode, Decode, DebugNoBound, PartialEq, Eq, CloneNoBound, TypeInfo,
+)]
+#[scale_info(skip_type_params(T))]
+pub struct SendMessage<T, const ID: u8> {
+    /// The id of the parachain that receives the message.
+    pub recipient: u32,
+    /// The message itself.
+    pub data: Vec<u8>,
+    _phantom: PhantomData<T>,
+}
+
+impl<T, const ID: u8> SendMessage<T, ID> {
+    pub fn new(recipient: u32, data: Vec<u8>) -> Self {
+        Self {
+            recipient,
+            data,
+            _phantom: PhantomData,
+        }
+    }
+}
+
+/// The total value of the given coins.
+fn total_value<const ID: u8>(
+    data: &[DynamicallyTypedData],
+) -> Result<u128, ConstraintCheckerError> {
+    data.iter().try_fold(0u128, |total, coin| {
+        let value = coin
+            .extract::<Coin<ID>>()
+            .map_err(|_| ConstraintCheckerError::BadlyTyped)?
+            .0;
+        total
+            .checked_add(value)
+            .ok_or(ConstraintCheckerError::ValueOverflow)
+    })
+}
+
+impl<T: HrmpMessagingConfig, const ID: u8> SimpleConstraintChecker for SendMessage<T, ID> {
+    type Error = ConstraintCheckerError;
+
+    fn check(
+        &self,
+        input_data: &[DynamicallyTypedData],
+        _peek_data: &[DynamicallyTypedData],
+        output_data: &[DynamicallyTypedData],
+        _context: &CheckerContext,
+    ) -> Result<TransactionPriority, Self::Error> {
+        ensure!(
+            !input_data.is_empty(),
+            ConstraintCheckerError::SpendingNothing
+        );
+
+        let burned = total_value::<ID>(input_data)?
+            .checked_sub(total_value::<ID>(output_data)?)
+            .ok_or(ConstraintCheckerError::OutputsExceedInputs)?;
+
+        // SIDE EFFECT: Queue the message to be sent with the block
+        T::SendHorizontalMessage::send(OutboundHrmpMessage {
+            recipient: self.recipient.into(),
+            data: self.data.clone(),
+        })
+        .map_err(ConstraintCheckerError::SendFailed)?;
+
+        Ok(money::surplus::<ID, T>(burned))
+    }
+}
@@ -0,0 +1,373 @@
+//! Unit tests for the HRMP Messaging piece
+
+use super::*;
+use std::cell::RefCell;
+use tuxedo_parachain_core::{
+    tuxedo_core::{dynamic_typing::testing::Bogus, tuxedo_verifier, verifier::SigCheck},
+    HorizontalMessagingState,
+};
+
+/// A verifier that can represent owners and the void.
+#[derive(Serialize, Deserialize, Encode, Decode, Debug, PartialEq, Eq, Clone, TypeInfo)]
+#[tuxedo_verifier]
+pub enum TestVerifier {
+    SigCheck(SigCheck),
+    UpForGrabs(UpForGrabs),
+}
+
+thread_local! {
+    static SENT_MESSAGES: RefCell<Vec<OutboundHrmpMessage>> = RefCell::new(Vec::new());
+}
+
+/// The mock config says the relay parent is number 100, and that parachain 2_000 sent two
+/// messages and parachain 1_000 sent one. Only parachain 1_000 has a channel to send messages
+/// through, and sent messages are collected in a thread local. Messages live for ten relay blocks.
+pub struct TestConfig;
+
+impl GetRelayParentNumberStorage for TestConfig {
+    fn get() -> u32 {
+        100
+    }
+}
+
+impl GetHorizontalMessagingStorage for TestConfig {
+    fn get() -> HorizontalMessagingState {
+        HorizontalMessagingState {
+            inbound: BTreeMap::from([
+                (
+                    ParaId::from(2_000),
+                    vec![inbound(98, b"first"), inbound(99, b"second")],
+                ),
+                (ParaId::from(1_000), vec![inbound(99, b"hello")]),
+            ]),
+            egress_channels: Vec::new(),
+        }
+    }
+}
+
+impl SendHorizontalMessage for TestConfig {
+    fn send(message: OutboundHrmpMessage) -> Result<(), HorizontalMessageError> {
+        ensure!(
+            message.recipient == ParaId::from(1_000),
+            HorizontalMessageError::NoChannel
+        );
+        SENT_MESSAGES.with(|sent| sent.borrow_mut().push(message));
+        Ok(())
+    }
+}
+
+impl MoneyConfig for TestConfig {}
+
+impl HrmpMessagingConfig for TestConfig {
+    type GetRelayParentNumber = Self;
+    type GetHorizontalMessaging = Self;
+    type SendHorizontalMessage = Self;
+
+    const MESSAGE_LIFETIME: u32 = 10;
+}
+
+type Receive = ReceiveMessages<TestConfig>;
+type Retire = RetireMessages<TestConfig>;
+type Sender = SendMessage<TestConfig, 0>;
+
+fn inbound(sent_at: u32, data: &[u8]) -> InboundHrmpMessage {
+    InboundHrmpMessage {
+        sent_at,
+        data: data.to_vec(),
+    }
+}
+
+fn delivered(sender: u32, sent_at: u32, data: &[u8]) -> Output<TestVerifier> {
+    Output {
+        payload: message(sender, sent_at, data),
+        verifier: UpForGrabs.into(),
+    }
+}
+
+fn message(sender: u32, sent_at: u32, data: &[u8]) -> DynamicallyTypedData {
+    InboundMessage {
+        sender,
+        sent_at,
+        data: data.to_vec(),
+    }
+    .into()
+}
+
+fn take_sent_messages() -> Vec<OutboundHrmpMessage> {
+    SENT_MESSAGES.with(|sent| sent.take())
+}
+
+#[test]
+fn receive_happy_path() {
+    assert_eq!(
+        Receive::new(100).check(
+            &[],
+            &[],
+            &[
+                delivered(1_000, 99, b"hello"),
+                delivered(2_000, 98, b"first"),
+                delivered(2_000, 99, b"second"),
+            ],
+            &CheckerContext::default()
+        ),
+        Ok(0)
+    );
+}
+
+#[test]
+fn receive_with_inputs_fails() {
+    assert_eq!(
+        Receive::new(100).check(
+            &[delivered(3_000, 1, b"old")],
+            &[],
+            &[
+                delivered(1_000, 99, b"hello"),
+                delivered(2_000, 98, b"first"),
+                delivered(2_000, 99, b"second"),
+            ],
+            &CheckerContext::default()
+        ),
+        Err(ConstraintCheckerError::WrongNumberInputs)
+    );
+}
+
+#[test]
+fn receive_at_wrong_relay_parent_fails() {
+    assert_eq!(
+        Receive::new(99).check(
+            &[],
+            &[],
+            &[
+                delivered(1_000, 99, b"hello"),
+                delivered(2_000, 98, b"first"),
+                delivered(2_000, 99, b"second"),
+            ],
+            &CheckerContext::default()
+        ),
+        Err(ConstraintCheckerError::WrongRelayParent)
+    );
+}
+
+#[test]
+fn receive_missing_message_fails() {
+    assert_eq!(
+        Receive::new(100).check(
+            &[],
+            &[],
+            &[
+                delivered(1_000, 99, b"hello"),
+                delivered(2_000, 98, b"first"),
+            ],
+            &CheckerContext::default()
+        ),
+        Err(ConstraintCheckerError::WrongDeliveries)
+    );
+}
+
+#[test]
+fn receive_out_of_order_fails() {
+    assert_eq!(
+        Receive::new(100).check(
+            &[],
+            &[],
+            &[
+                delivered(1_000, 99, b"hello"),
+                delivered(2_000, 99, b"second"),
+                delivered(2_000, 98, b"first"),
+            ],
+            &CheckerContext::default()
+        ),
+        Err(ConstraintCheckerError::WrongDeliveries)
+    );
+}
+
+#[test]
+fn receive_forged_message_fails() {
+    assert_eq!(
+        Receive::new(100).check(
+            &[],
+            &[],
+            &[
+                delivered(1_000, 99, b"goodbye"),
+                delivered(2_000, 98, b"first"),
+                delivered(2_000, 99, b"second"),
+            ],
+            &CheckerContext::default()
+        ),
+        Err(ConstraintCheckerError::WrongDeliveries)
+    );
+}
+
+#[test]
+fn retire_expired_messages_works() {
+    assert_eq!(
+        SimpleConstraintChecker::check(
+            &Retire::default(),
+            &[message(1_000, 80, b"old"), message(2_000, 90, b"older")],
+            &[],
+            &[],
+            &CheckerContext::default()
+        ),
+        Ok(0)
+    );
+}
+
+#[test]
+fn retire_live_message_fails() {
+    assert_eq!(
+        SimpleConstraintChecker::check(
+            &Retire::default(),
+            &[message(1_000, 80, b"old"), message(2_000, 91, b"new")],
+            &[],
+            &[],
+            &CheckerContext::default()
+        ),
+        Err(ConstraintCheckerError::MessageNotExpired)
+    );
+}
+
+#[test]
+fn retire_nothing_fails() {
+    assert_eq!(
+        SimpleConstraintChecker::check(
+            &Retire::default(),
+            &[],
+            &[],
+            &[],
+            &CheckerContext::default()
+        ),
+        Err(ConstraintCheckerError::RetiringNothing)
+    );
+}
+
+#[test]
+fn retire_with_outputs_fails() {
+    assert_eq!(
+        SimpleConstraintChecker::check(
+            &Retire::default(),
+            &[message(1_000, 80, b"old")],
+            &[],
+            &[message(1_000, 100, b"new")],
+            &CheckerContext::default()
+        ),
+        Err(ConstraintCheckerError::RetiringWithOutputs)
+    );
+}
+
+#[test]
+fn retire_other_data_fails() {
+    assert_eq!(
+        SimpleConstraintChecker::check(
+            &Retire::default(),
+            &[Bogus.into()],
+            &[],
+            &[],
+            &CheckerContext::default()
+        ),
+        Err(ConstraintCheckerError::BadlyTyped)
+    );
+}
+
+#[test]
+fn send_happy_path() {
+    take_sent_messages();
+
+    assert_eq!(
+        SimpleConstraintChecker::check(
+            &Sender::new(1_000, b"hello".to_vec()),
+            &[Coin::<0>(10).into()],
+            &[],
+            &[Coin::<0>(7).into()],
+            &CheckerContext::default()
+        ),
+        Ok(3)
+    );
+    assert_eq!(
+        take_sent_messages(),
+        vec![OutboundHrmpMessage {
+            recipient: ParaId::from(1_000),
+            data: b"hello".to_vec(),
+        }]
+    );
+}
+
+#[test]
+fn send_paid_with_non_native_coins_has_no_priority() {
+    take_sent_messages();
+
+    assert_eq!(
+        SimpleConstraintChecker::check(
+            &SendMessage::<TestConfig, 1>::new(1_000, b"hello".to_vec()),
+            &[Coin::<1>(10).into()],
+            &[],
+            &[Coin::<1>(7).into()],
+            &CheckerContext::default()
+        ),
+        Ok(0)
+    );
+    assert_eq!(take_sent_messages().len(), 1);
+}
+
+#[test]
+fn send_without_channel_fails() {
+    take_sent_messages();
+
+    assert_eq!(
+        SimpleConstraintChecker::check(
+            &Sender::new(3_000, b"hello".to_vec()),
+            &[Coin::<0>(10).into()],
+            &[],
+            &[],
+            &CheckerContext::default()
+        ),
+        Err(ConstraintCheckerError::SendFailed(
+            HorizontalMessageError::NoChannel
+        ))
+    );
+    assert!(take_sent_messages().is_empty());
+}
+
+#[test]
+fn send_without_payment_fails() {
+    assert_eq!(
+        SimpleConstraintChecker::check(
+            &Sender::new(1_000, b"hello".to_vec()),
+            &[],
+            &[],
+            &[],
+            &CheckerContext::default()
+        ),
+        Err(ConstraintCheckerError::SpendingNothing)
+    );
+}
+
+#[test]
+fn send_with_too_much_change_fails() {
+    take_sent_messages();
+
+    assert_eq!(
+        SimpleConstraintChecker::check(
+            &Sender::new(1_000, b"hello".to_vec()),
+            &[Coin::<0>(10).into()],
+            &[],
+            &[Coin::<0>(11).into()],
+            &CheckerContext::default()
+        ),
+        Err(ConstraintCheckerError::OutputsExceedInputs)
+    );
+    assert!(take_sent_messages().is_empty());
+}
+
+#[test]
+fn send_paid_with_other_data_fails() {
+    assert_eq!(
+        SimpleConstraintChecker::check(
+            &Sender::new(1_000, b"hello".to_vec()),
+            &[Bogus.into()],
+            &[],
+            &[],
+            &CheckerContext::default()
+        ),
+        Err(ConstraintCheckerError::BadlyTyped)
+    );
+}
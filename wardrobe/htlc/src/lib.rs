@@ -0,0 +1,345 @@
+//! Hash time locked contracts (HTLCs) for the tokens provided by the money piece.
+//!
+//! An HTLC locks some `Coin<ID>` value under a hash and a timeout. Before the timeout, the
+//! recipient may claim the value by revealing a preimage of the hash. From the timeout onward,
+//! the value may instead be refunded to whoever locked it. Pairing HTLCs with the same hash
+//! on two chains allows trustless cross-chain swaps: revealing the preimage to claim on one chain
+//! lets the counterparty claim on the other.
+//!
+//! The hash is SHA-256 for compatibility with the HTLCs of other chains such as Bitcoin.
+//!
+//! HTLC outputs should be protected by the `UpForGrabs` verifier. The constraint checkers,
+//! rather than the verifier, guarantee that claims go to the recipient and refunds go to the
+//! original owner. This way revealing the preimage in the transaction pool does not allow
+//! anyone else to steal the funds.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use core::marker::PhantomData;
+
+use money::Coin;
+use parity_scale_codec::{Decode, Encode};
+use scale_info::TypeInfo;
+use serde::{Deserialize, Serialize};
+use sp_core::H256;
+use sp_runtime::transaction_validity::TransactionPriority;
+use sp_std::prelude::*;
+use tuxedo_core::{
+    dynamic_typing::UtxoData,
+    ensure,
+    support_macros::{CloneNoBound, DebugNoBound, DefaultNoBound},
+    types::Output,
+    CheckerContext, ConstraintChecker, Verifier,
+};
+
+#[cfg(test)]
+mod tests;
+
+/// Configuration items for the HTLC piece when it is
+/// instantiated in a concrete runtime.
+pub trait HtlcConfig {
+    /// A means of getting the current block height.
+    /// Probably this will be the Tuxedo Executive
+    fn block_height() -> u32;
+}
+
+/// Some `Coin<ID>` value locked under a hash and a timeout.
+#[derive(
+    Serialize,
+    Deserialize,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Clone,
+    Encode,
+    Decode,
+    Hash,
+    Debug,
+    TypeInfo,
+)]
+pub struct Htlc<V, const ID: u8> {
+    /// The locked value.
+    pub amount: u128,
+    /// The SHA-256 hash whose preimage unlocks the value for the recipient.
+    pub hash_lock: H256,
+    /// The block height from which the value can no longer be claimed and may be refunded instead.
+    pub timeout: u32,
+    /// The verifier that claimed coins must be locked to.
+    pub recipient: V,
+    /// The verifier that refunded coins must be locked to.
+    pub refund_to: V,
+}
+
+impl<V: Encode + Decode, const ID: u8> UtxoData for Htlc<V, ID> {
+    const TYPE_ID: [u8; 4] = [b'h', b't', b'l', ID];
+}
+
+/// Errors that can occur when checking HTLC transactions.
+#[derive(
+    Serialize,
+    Deserialize,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Clone,
+    Encode,
+    Decode,
+    Hash,
+    Debug,
+    TypeInfo,
+)]
+pub enum ConstraintCheckerError {
+    /// An input data has the wrong type.
+    BadlyTypedInput,
+    /// An output data has the wrong type.
+    BadlyTypedOutput,
+    /// Wrong number of inputs were provided to the constraint checker.
+    WrongNumberInputs,
+    /// The transaction does not lock anything in an HTLC.
+    NothingLocked,
+    /// An HTLC or coin with zero value was created. This is not allowed because it wastes state space.
+    ZeroValue,
+    /// The value consumed or created by this transaction overflows the value type.
+    ValueOverflow,
+    /// More value was created than was consumed.
+    OutputsExceedInputs,
+    /// A new HTLC has a timeout that has already passed.
+    TimeoutInPast,
+    /// The supplied preimage does not hash to the HTLC's hash lock.
+    WrongPreimage,
+    /// The HTLC has timed out, so it can no longer be claimed.
+    Expired,
+    /// The HTLC has not timed out yet, so it cannot be refunded.
+    NotExpired,
+    /// The unlocked coins were locked to the wrong verifier.
+    PaidToWrongVerifier,
+}
+
+/// Sum the value of some outputs, making sure they are all `Coin<ID>`s with non-zero value.
+fn sum_coins<'a, V: 'a, const ID: u8>(
+    coins: impl IntoIterator<Item = &'a Output<V>>,
+    error: ConstraintCheckerError,
+) -> Result<u128, ConstraintCheckerError> {
+    coins.into_iter().try_fold(0u128, |total, coin| {
+        let value = coin
+            .payload
+            .extract::<Coin<ID>>()
+            .map_err(|_| error.clone())?
+            .0;
+        ensure!(value > 0, ConstraintCheckerError::ZeroValue);
+        total
+            .checked_add(value)
+            .ok_or(ConstraintCheckerError::ValueOverflow)
+    })
+}
+
+/// Consume the single HTLC input and pay its value out to the given verifier.
+/// Returns the amount left over to pay the transaction's fee and tip.
+fn unlock<V: Verifier + PartialEq, const ID: u8>(
+    inputs: &[Output<V>],
+    outputs: &[Output<V>],
+    payee: impl Fn(&Htlc<V, ID>) -> &V,
+) -> Result<(Htlc<V, ID>, u128), ConstraintCheckerError> {
+    ensure!(inputs.len() == 1, ConstraintCheckerError::WrongNumberInputs);
+    let htlc = inputs[0]
+        .payload
+        .extract::<Htlc<V, ID>>()
+        .map_err(|_| ConstraintCheckerError::BadlyTypedInput)?;
+
+    for output in outputs {
+        ensure!(
+            &output.verifier == payee(&htlc),
+            ConstraintCheckerError::PaidToWrongVerifier
+        );
+    }
+    let paid = sum_coins::<V, ID>(outputs, ConstraintCheckerError::BadlyTypedOutput)?;
+    ensure!(
+        paid <= htlc.amount,
+        ConstraintCheckerError::OutputsExceedInputs
+    );
+
+    let burned = htlc.amount - paid;
+    Ok((htlc, burned))
+}
+
+/// A constraint checker that locks `Coin<ID>`s into new HTLCs.
+///
+/// All inputs are `Coin<ID>`s. Outputs are HTLCs, and optionally `Coin<ID>` change.
+/// Value that is neither locked nor returned as change pays the transaction's fee and tip.
+#[derive(
+    Serialize,
+    Deserialize,
+    Encode,
+    Decode,
+    DebugNoBound,
+    DefaultNoBound,
+    PartialEq,
+    Eq,
+    CloneNoBound,
+    TypeInfo,
+)]
+#[scale_info(skip_type_params(T))]
+pub struct LockHtlc<T, const ID: u8>(PhantomData<T>);
+
+impl<T: HtlcConfig, V: Verifier + PartialEq, const ID: u8> ConstraintChecker<V>
+    for LockHtlc<T, ID>
+{
+    type Error = ConstraintCheckerError;
+    type InherentHooks = ();
+
+    fn check(
+        &self,
+        inputs: &[Output<V>],
+        _peeks: &[Output<V>],
+        outputs: &[Output<V>],
+        _context: &CheckerContext,
+    ) -> Result<TransactionPriority, Self::Error> {
+        let total_input = sum_coins::<V, ID>(inputs, ConstraintCheckerError::BadlyTypedInput)?;
+
+        let mut locked: u128 = 0;
+        let mut change = Vec::new();
+        for output in outputs {
+            if let Ok(htlc) = output.payload.extract::<Htlc<V, ID>>() {
+                ensure!(htlc.amount > 0, ConstraintCheckerError::ZeroValue);
+                ensure!(
+                    htlc.timeout > T::block_height(),
+                    ConstraintCheckerError::TimeoutInPast
+                );
+                locked = locked
+                    .checked_add(htlc.amount)
+                    .ok_or(ConstraintCheckerError::ValueOverflow)?;
+            } else {
+                change.push(output);
+            }
+        }
+        ensure!(locked > 0, ConstraintCheckerError::NothingLocked);
+
+        let total_output = sum_coins::<V, ID>(change, ConstraintCheckerError::BadlyTypedOutput)?
+            .checked_add(locked)
+            .ok_or(ConstraintCheckerError::ValueOverflow)?;
+        ensure!(
+            total_output <= total_input,
+            ConstraintCheckerError::OutputsExceedInputs
+        );
+
+        let burned = total_input - total_output;
+        Ok(burned.try_into().unwrap_or(TransactionPriority::MAX))
+    }
+
+    fn is_inherent(&self) -> bool {
+        false
+    }
+}
+
+/// A constraint checker that claims an HTLC for its recipient by revealing the preimage.
+///
+/// The only input is the HTLC, and it must not have timed out yet. All outputs are `Coin<ID>`s
+/// locked to the HTLC's recipient.
+#[derive(
+    Serialize,
+    Deserialize,
+    Encode,
+    Decode,
+    DebugNoBound,
+    DefaultNoBound,
+    PartialEq,
+    Eq,
+    CloneNoBound,
+    TypeInfo,
+)]
+#[scale_info(skip_type_params(T))]
+pub struct ClaimHtlc<T, const ID: u8> {
+    /// The preimage of the HTLC's hash lock.
+    pub preimage: Vec<u8>,
+    _phantom: PhantomData<T>,
+}
+
+impl<T, const ID: u8> ClaimHtlc<T, ID> {
+    pub fn new(preimage: Vec<u8>) -> Self {
+        Self {
+            preimage,
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<T: HtlcConfig, V: Verifier + PartialEq, const ID: u8> ConstraintChecker<V>
+    for ClaimHtlc<T, ID>
+{
+    type Error = ConstraintCheckerError;
+    type InherentHooks = ();
+
+    fn check(
+        &self,
+        inputs: &[Output<V>],
+        _peeks: &[Output<V>],
+        outputs: &[Output<V>],
+        _context: &CheckerContext,
+    ) -> Result<TransactionPriority, Self::Error> {
+        let (htlc, burned) = unlock::<V, ID>(inputs, outputs, |htlc| &htlc.recipient)?;
+
+        ensure!(
+            T::block_height() < htlc.timeout,
+            ConstraintCheckerError::Expired
+        );
+        ensure!(
+            H256(sp_io::hashing::sha2_256(&self.preimage)) == htlc.hash_lock,
+            ConstraintCheckerError::WrongPreimage
+        );
+
+        Ok(burned.try_into().unwrap_or(TransactionPriority::MAX))
+    }
+
+    fn is_inherent(&self) -> bool {
+        false
+    }
+}
+
+/// A constraint checker that refunds a timed out HTLC to its original owner.
+///
+/// The only input is the HTLC, and it must have timed out. All outputs are `Coin<ID>`s
+/// locked to the HTLC's refund verifier.
+#[derive(
+    Serialize,
+    Deserialize,
+    Encode,
+    Decode,
+    DebugNoBound,
+    DefaultNoBound,
+    PartialEq,
+    Eq,
+    CloneNoBound,
+    TypeInfo,
+)]
+#[scale_info(skip_type_params(T))]
+pub struct RefundHtlc<T, const ID: u8>(PhantomData<T>);
+
+impl<T: HtlcConfig, V: Verifier + PartialEq, const ID: u8> ConstraintChecker<V>
+    for RefundHtlc<T, ID>
+{
+    type Error = ConstraintCheckerError;
+    type InherentHooks = ();
+
+    fn check(
+        &self,
+        inputs: &[Output<V>],
+        _peeks: &[Output<V>],
+        outputs: &[Output<V>],
+        _context: &CheckerContext,
+    ) -> Result<TransactionPriority, Self::Error> {
+        let (htlc, burned) = unlock::<V, ID>(inputs, outputs, |htlc| &htlc.refund_to)?;
+
+        ensure!(
+            T::block_height() >= htlc.timeout,
+            ConstraintCheckerError::NotExpired
+        );
+
+        Ok(burned.try_into().unwrap_or(TransactionPriority::MAX))
+    }
+
+    fn is_inherent(&self) -> bool {
+        false
+    }
+}
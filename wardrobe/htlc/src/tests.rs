@@ -0,0 +1,209 @@
+//! Unit tests for the HTLC piece
+
+use super::*;
+use tuxedo_core::{dynamic_typing::testing::Bogus, verifier::SigCheck};
+
+/// A mock config that always reports the same block height.
+pub struct AtHeight<const H: u32>;
+
+impl<const H: u32> HtlcConfig for AtHeight<H> {
+    fn block_height() -> u32 {
+        H
+    }
+}
+
+const PREIMAGE: &[u8] = b"super secret";
+
+fn recipient() -> SigCheck {
+    SigCheck::new(H256::repeat_byte(1))
+}
+
+fn owner() -> SigCheck {
+    SigCheck::new(H256::repeat_byte(2))
+}
+
+/// An HTLC for the test preimage that times out at block 100.
+fn htlc(amount: u128) -> Htlc<SigCheck, 0> {
+    Htlc {
+        amount,
+        hash_lock: H256(sp_io::hashing::sha2_256(PREIMAGE)),
+        timeout: 100,
+        recipient: recipient(),
+        refund_to: owner(),
+    }
+}
+
+fn check<C: ConstraintChecker<SigCheck>>(
+    checker: C,
+    inputs: Vec<Output<SigCheck>>,
+    outputs: Vec<Output<SigCheck>>,
+) -> Result<TransactionPriority, C::Error> {
+    checker.check(&inputs, &[], &outputs, &Default::default())
+}
+
+#[test]
+fn lock_works() {
+    let inputs = vec![(Coin::<0>(100), owner()).into()];
+    let outputs = vec![(htlc(60), owner()).into(), (Coin::<0>(39), owner()).into()];
+
+    assert_eq!(
+        check(LockHtlc::<AtHeight<50>, 0>::default(), inputs, outputs),
+        Ok(1)
+    );
+}
+
+#[test]
+fn lock_with_timeout_in_past_fails() {
+    let inputs = vec![(Coin::<0>(100), owner()).into()];
+    let outputs = vec![(htlc(100), owner()).into()];
+
+    assert_eq!(
+        check(LockHtlc::<AtHeight<100>, 0>::default(), inputs, outputs),
+        Err(ConstraintCheckerError::TimeoutInPast)
+    );
+}
+
+#[test]
+fn lock_exceeding_inputs_fails() {
+    let inputs = vec![(Coin::<0>(100), owner()).into()];
+    let outputs = vec![(htlc(101), owner()).into()];
+
+    assert_eq!(
+        check(LockHtlc::<AtHeight<50>, 0>::default(), inputs, outputs),
+        Err(ConstraintCheckerError::OutputsExceedInputs)
+    );
+}
+
+#[test]
+fn lock_nothing_fails() {
+    let inputs = vec![(Coin::<0>(100), owner()).into()];
+    let outputs = vec![(Coin::<0>(100), owner()).into()];
+
+    assert_eq!(
+        check(LockHtlc::<AtHeight<50>, 0>::default(), inputs, outputs),
+        Err(ConstraintCheckerError::NothingLocked)
+    );
+}
+
+#[test]
+fn claim_works() {
+    let inputs = vec![(htlc(100), owner()).into()];
+    let outputs = vec![(Coin::<0>(100), recipient()).into()];
+
+    assert_eq!(
+        check(
+            ClaimHtlc::<AtHeight<99>, 0>::new(PREIMAGE.to_vec()),
+            inputs,
+            outputs
+        ),
+        Ok(0)
+    );
+}
+
+#[test]
+fn claim_with_wrong_preimage_fails() {
+    let inputs = vec![(htlc(100), owner()).into()];
+    let outputs = vec![(Coin::<0>(100), recipient()).into()];
+
+    assert_eq!(
+        check(
+            ClaimHtlc::<AtHeight<99>, 0>::new(b"wrong guess".to_vec()),
+            inputs,
+            outputs
+        ),
+        Err(ConstraintCheckerError::WrongPreimage)
+    );
+}
+
+#[test]
+fn claim_after_timeout_fails() {
+    let inputs = vec![(htlc(100), owner()).into()];
+    let outputs = vec![(Coin::<0>(100), recipient()).into()];
+
+    assert_eq!(
+        check(
+            ClaimHtlc::<AtHeight<100>, 0>::new(PREIMAGE.to_vec()),
+            inputs,
+            outputs
+        ),
+        Err(ConstraintCheckerError::Expired)
+    );
+}
+
+#[test]
+fn claim_to_wrong_verifier_fails() {
+    let inputs = vec![(htlc(100), owner()).into()];
+    let outputs = vec![(Coin::<0>(100), owner()).into()];
+
+    assert_eq!(
+        check(
+            ClaimHtlc::<AtHeight<99>, 0>::new(PREIMAGE.to_vec()),
+            inputs,
+            outputs
+        ),
+        Err(ConstraintCheckerError::PaidToWrongVerifier)
+    );
+}
+
+#[test]
+fn claim_too_much_fails() {
+    let inputs = vec![(htlc(100), owner()).into()];
+    let outputs = vec![(Coin::<0>(101), recipient()).into()];
+
+    assert_eq!(
+        check(
+            ClaimHtlc::<AtHeight<99>, 0>::new(PREIMAGE.to_vec()),
+            inputs,
+            outputs
+        ),
+        Err(ConstraintCheckerError::OutputsExceedInputs)
+    );
+}
+
+#[test]
+fn claim_non_htlc_fails() {
+    let inputs = vec![(Bogus, owner()).into()];
+    let outputs = vec![(Coin::<0>(100), recipient()).into()];
+
+    assert_eq!(
+        check(
+            ClaimHtlc::<AtHeight<99>, 0>::new(PREIMAGE.to_vec()),
+            inputs,
+            outputs
+        ),
+        Err(ConstraintCheckerError::BadlyTypedInput)
+    );
+}
+
+#[test]
+fn refund_works() {
+    let inputs = vec![(htlc(100), owner()).into()];
+    let outputs = vec![(Coin::<0>(90), owner()).into()];
+
+    assert_eq!(
+        check(RefundHtlc::<AtHeight<100>, 0>::default(), inputs, outputs),
+        Ok(10)
+    );
+}
+
+#[test]
+fn refund_before_timeout_fails() {
+    let inputs = vec![(htlc(100), owner()).into()];
+    let outputs = vec![(Coin::<0>(100), owner()).into()];
+
+    assert_eq!(
+        check(RefundHtlc::<AtHeight<99>, 0>::default(), inputs, outputs),
+        Err(ConstraintCheckerError::NotExpired)
+    );
+}
+
+#[test]
+fn refund_to_wrong_verifier_fails() {
+    let inputs = vec![(htlc(100), owner()).into()];
+    let outputs = vec![(Coin::<0>(100), recipient()).into()];
+
+    assert_eq!(
+        check(RefundHtlc::<AtHeight<100>, 0>::default(), inputs, outputs),
+        Err(ConstraintCheckerError::PaidToWrongVerifier)
+    );
+}
@@ -0,0 +1,672 @@
+//! An on-chain identity registry backed by deposits of `Coin<ID>`.
+//!
+//! Anyone may register an [`Identity`] containing a display name and hashes of their contact
+//! details. Registering requires locking a deposit in the identity, which discourages spam. The
+//! configured registrars review identities and attach signed [`Judgement`]s to them. Judgements
+//! refer to the owner and registration height of the identity, so they continue to apply after
+//! the identity is updated. They also record the hash of the identity data that was reviewed, so
+//! that clients can tell whether the data has changed since.
+//!
+//! A registrar who finds an identity to be erroneous can have its deposit slashed. To prevent the
+//! owner from escaping a slash by withdrawing the deposit as soon as they see it coming, clearing
+//! an identity is a two step process. The owner first schedules the clearing, and can only
+//! withdraw the deposit once the clearing delay has passed.
+//!
+//! Identity outputs must be protected by the `UpForGrabs` verifier so that they can be slashed.
+//! The constraint checkers, rather than the verifier, check the owner's signature on updates.
+//! Signatures are passed as fields of the constraint checker.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use core::marker::PhantomData;
+
+use money::Coin;
+use parity_scale_codec::{Decode, Encode};
+use scale_info::TypeInfo;
+use serde::{Deserialize, Serialize};
+use sp_core::{
+    sr25519::{Public, Signature},
+    H256,
+};
+use sp_runtime::{
+    traits::{BlakeTwo256, Hash},
+    transaction_validity::TransactionPriority,
+};
+use sp_std::prelude::*;
+use tuxedo_core::{
+    dynamic_typing::{DynamicallyTypedData, UtxoData},
+    ensure,
+    support_macros::{CloneNoBound, DebugNoBound, DefaultNoBound},
+    types::Output,
+    verifier::{SigCheck, UpForGrabs},
+    CheckerContext, ConstraintChecker, SimpleConstraintChecker, Verifier,
+};
+
+#[cfg(test)]
+mod tests;
+
+/// Configuration items for the identity piece when it is
+/// instantiated in a concrete runtime.
+pub trait IdentityConfig {
+    /// A means of getting the current block height.
+    /// Probably this will be the Tuxedo Executive
+    fn block_height() -> u32;
+
+    /// The keys of the registrars who may judge identities.
+    fn registrars() -> Vec<H256>;
+
+    /// The minimum deposit that must be locked in an identity.
+    const MIN_DEPOSIT: u128 = 100;
+
+    /// The maximum length of a display name, in bytes.
+    const MAX_NAME_LENGTH: usize = 32;
+
+    /// The maximum number of contact hashes in an identity.
+    const MAX_CONTACTS: usize = 8;
+
+    /// The minimum number of blocks between scheduling the clearing of an identity and
+    /// withdrawing its deposit. This gives registrars time to slash erroneous identities.
+    ///
+    /// Default is roughly one day with 6 second block times.
+    const CLEARING_DELAY: u32 = 14_400;
+}
+
+/// An identity registered by the owner of a key, with a deposit of `Coin<ID>` locked in it.
+#[derive(
+    Serialize,
+    Deserialize,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Clone,
+    Encode,
+    Decode,
+    Hash,
+    Debug,
+    TypeInfo,
+)]
+pub struct Identity<const ID: u8> {
+    /// The key that owns this identity.
+    pub owner: H256,
+    /// The human readable name of the owner.
+    pub display_name: Vec<u8>,
+    /// Hashes of the owner's contact details, such as email or web addresses.
+    pub contacts: Vec<H256>,
+    /// The value locked in this identity.
+    pub deposit: u128,
+    /// The block height at which this identity was registered.
+    pub registered_at: u32,
+    /// The block height from which the identity may be cleared, if clearing has been scheduled.
+    pub clearing_at: Option<u32>,
+}
+
+impl<const ID: u8> Identity<ID> {
+    /// The hash of the identity data, which judgements refer to.
+    pub fn subject(&self) -> H256 {
+        BlakeTwo256::hash_of(self)
+    }
+
+    /// The message that the owner must sign to replace this identity with the new one.
+    pub fn update_message(&self, new: &Self) -> Vec<u8> {
+        (b"identity", self, new).encode()
+    }
+
+    /// Whether the given judgement was made about this identity, in any revision.
+    pub fn is_judged_by(&self, judgement: &Judgement<ID>) -> bool {
+        judgement.owner == self.owner && judgement.registered_at == self.registered_at
+    }
+
+    /// Make sure the display name and contacts are within the configured limits.
+    fn check_limits<T: IdentityConfig>(&self) -> Result<(), ConstraintCheckerError> {
+        ensure!(
+            self.display_name.len() <= T::MAX_NAME_LENGTH,
+            ConstraintCheckerError::NameTooLong
+        );
+        ensure!(
+            self.contacts.len() <= T::MAX_CONTACTS,
+            ConstraintCheckerError::TooManyContacts
+        );
+        Ok(())
+    }
+}
+
+impl<const ID: u8> UtxoData for Identity<ID> {
+    const TYPE_ID: [u8; 4] = [b'i', b'd', b'n', ID];
+}
+
+/// A registrar's opinion of an identity.
+#[derive(
+    Serialize,
+    Deserialize,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Clone,
+    Encode,
+    Decode,
+    Hash,
+    Debug,
+    TypeInfo,
+)]
+pub enum Verdict {
+    /// The identity looks reasonable, but has not been thoroughly checked.
+    Reasonable,
+    /// The identity has been checked and is known to be correct.
+    KnownGood,
+    /// The identity is wrong or malicious. Its deposit may be slashed.
+    Erroneous,
+}
+
+/// A registrar's signed judgement of an identity.
+#[derive(
+    Serialize,
+    Deserialize,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Clone,
+    Encode,
+    Decode,
+    Hash,
+    Debug,
+    TypeInfo,
+)]
+pub struct Judgement<const ID: u8> {
+    /// The owner of the judged identity.
+    pub owner: H256,
+    /// The block height at which the judged identity was registered.
+    pub registered_at: u32,
+    /// The hash of the identity data that was judged.
+    pub subject: H256,
+    /// The registrar who made the judgement.
+    pub registrar: H256,
+    /// The registrar's opinion.
+    pub verdict: Verdict,
+}
+
+impl<const ID: u8> Judgement<ID> {
+    /// The message that the registrar must sign to make this judgement.
+    pub fn signing_message(&self) -> Vec<u8> {
+        (b"judgement", self).encode()
+    }
+}
+
+impl<const ID: u8> UtxoData for Judgement<ID> {
+    const TYPE_ID: [u8; 4] = [b'j', b'd', b'g', ID];
+}
+
+/// Errors that can occur when checking identity transactions.
+#[derive(
+    Serialize,
+    Deserialize,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Clone,
+    Encode,
+    Decode,
+    Hash,
+    Debug,
+    TypeInfo,
+)]
+pub enum ConstraintCheckerError {
+    /// An input data has the wrong type.
+    BadlyTypedInput,
+    /// An output data has the wrong type.
+    BadlyTypedOutput,
+    /// A peeked data has the wrong type.
+    BadlyTypedPeek,
+    /// Wrong number of inputs were provided to the constraint checker.
+    WrongNumberInputs,
+    /// Wrong number of outputs were provided to the constraint checker.
+    WrongNumberOutputs,
+    /// No identity was peeked at, but one is required.
+    MissingIdentity,
+    /// A coin with zero value was created. This is not allowed because it wastes state space.
+    ZeroValue,
+    /// The value consumed or created by this transaction overflows the value type.
+    ValueOverflow,
+    /// More value was created than was consumed.
+    OutputsExceedInputs,
+    /// The identity's deposit is below the minimum.
+    DepositTooLow,
+    /// The display name is longer than allowed.
+    NameTooLong,
+    /// The identity has more contacts than allowed.
+    TooManyContacts,
+    /// A new identity does not record the current block as its registration height.
+    WrongRegistrationHeight,
+    /// A new identity already has clearing scheduled.
+    ClearingScheduled,
+    /// The identity is scheduled to be cleared sooner than the clearing delay allows.
+    ClearingTooSoon,
+    /// The identity has not been scheduled for clearing, or the clearing delay has not passed yet.
+    NotClearable,
+    /// An update changed the identity's owner, deposit, or registration height.
+    ImmutableFieldChanged,
+    /// The identity was locked to a verifier other than `UpForGrabs`.
+    IdentityNotUpForGrabs,
+    /// The owner's signature on the update is missing or invalid.
+    BadSignature,
+    /// The judgement was not made by one of the configured registrars.
+    UnknownRegistrar,
+    /// The judgement does not describe the identity it was made about.
+    JudgementMismatch,
+    /// Only erroneous identities can be slashed.
+    NotErroneous,
+    /// Erroneous judgements cannot be removed except by slashing.
+    CannotRemoveErroneous,
+    /// The refunded deposit was locked to some verifier other than the owner's.
+    PaidToWrongVerifier,
+}
+
+/// Check that the given key signed the given message.
+fn check_signature(signer: H256, message: &[u8], signature: &Signature) -> bool {
+    sp_io::crypto::sr25519_verify(signature, message, &Public::from_h256(signer))
+}
+
+/// A constraint checker that registers a new identity.
+///
+/// All inputs are `Coin<ID>`s. The first output is the new identity, which must lock at least
+/// the minimum deposit. The remaining outputs are optionally `Coin<ID>` change.
+/// Coins that are neither deposited nor returned as change pay the transaction's fee and tip.
+#[derive(
+    Serialize,
+    Deserialize,
+    Encode,
+    Decode,
+    DebugNoBound,
+    DefaultNoBound,
+    PartialEq,
+    Eq,
+    CloneNoBound,
+    TypeInfo,
+)]
+#[scale_info(skip_type_params(T))]
+pub struct Register<T, const ID: u8>(PhantomData<T>);
+
+impl<T: IdentityConfig, V: Verifier + PartialEq + From<UpForGrabs>, const ID: u8>
+    ConstraintChecker<V> for Register<T, ID>
+{
+    type Error = ConstraintCheckerError;
+    type InherentHooks = ();
+
+    fn check(
+        &self,
+        inputs: &[Output<V>],
+        _peeks: &[Output<V>],
+        outputs: &[Output<V>],
+        _context: &CheckerContext,
+    ) -> Result<TransactionPriority, Self::Error> {
+        let identity_output = outputs
+            .first()
+            .ok_or(ConstraintCheckerError::WrongNumberOutputs)?;
+        let identity = identity_output
+            .payload
+            .extract::<Identity<ID>>()
+            .map_err(|_| ConstraintCheckerError::BadlyTypedOutput)?;
+        ensure!(
+            identity_output.verifier == UpForGrabs.into(),
+            ConstraintCheckerError::IdentityNotUpForGrabs
+        );
+        identity.check_limits::<T>()?;
+        ensure!(
+            identity.deposit >= T::MIN_DEPOSIT,
+            ConstraintCheckerError::DepositTooLow
+        );
+        ensure!(
+            identity.registered_at == T::block_height(),
+            ConstraintCheckerError::WrongRegistrationHeight
+        );
+        ensure!(
+            identity.clearing_at.is_none(),
+            ConstraintCheckerError::ClearingScheduled
+        );
+
+        let mut total_input: u128 = 0;
+        for input in inputs {
+            let coin = input
+                .payload
+                .extract::<Coin<ID>>()
+                .map_err(|_| ConstraintCheckerError::BadlyTypedInput)?;
+            total_input = total_input
+                .checked_add(coin.0)
+                .ok_or(ConstraintCheckerError::ValueOverflow)?;
+        }
+
+        let mut total_output = identity.deposit;
+        for output in &outputs[1..] {
+            let coin = output
+                .payload
+                .extract::<Coin<ID>>()
+                .map_err(|_| ConstraintCheckerError::BadlyTypedOutput)?;
+            ensure!(coin.0 > 0, ConstraintCheckerError::ZeroValue);
+            total_output = total_output
+                .checked_add(coin.0)
+                .ok_or(ConstraintCheckerError::ValueOverflow)?;
+        }
+        ensure!(
+            total_output <= total_input,
+            ConstraintCheckerError::OutputsExceedInputs
+        );
+
+        let burned = total_input - total_output;
+        Ok(burned.try_into().unwrap_or(TransactionPriority::MAX))
+    }
+
+    fn is_inherent(&self) -> bool {
+        false
+    }
+}
+
+/// A constraint checker that updates an identity with the owner's signature.
+///
+/// The only input is the old identity and the only output is the new one. The display name,
+/// contacts, and scheduled clearing may change, but a newly scheduled clearing must respect the
+/// clearing delay. Setting the scheduled clearing to `None` cancels it.
+#[derive(
+    Serialize, Deserialize, Encode, Decode, DebugNoBound, PartialEq, Eq, CloneNoBound, TypeInfo,
+)]
+#[scale_info(skip_type_params(T))]
+pub struct UpdateIdentity<T, const ID: u8> {
+    /// The owner's signature over the old and new identities.
+    pub owner_signature: Signature,
+    _phantom: PhantomData<T>,
+}
+
+impl<T, const ID: u8> UpdateIdentity<T, ID> {
+    pub fn new(owner_signature: Signature) -> Self {
+        Self {
+            owner_signature,
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<T: IdentityConfig, V: Verifier + PartialEq + From<UpForGrabs>, const ID: u8>
+    ConstraintChecker<V> for UpdateIdentity<T, ID>
+{
+    type Error = ConstraintCheckerError;
+    type InherentHooks = ();
+
+    fn check(
+        &self,
+        inputs: &[Output<V>],
+        _peeks: &[Output<V>],
+        outputs: &[Output<V>],
+        _context: &CheckerContext,
+    ) -> Result<TransactionPriority, Self::Error> {
+        ensure!(inputs.len() == 1, ConstraintCheckerError::WrongNumberInputs);
+        ensure!(
+            outputs.len() == 1,
+            ConstraintCheckerError::WrongNumberOutputs
+        );
+        let old = inputs[0]
+            .payload
+            .extract::<Identity<ID>>()
+            .map_err(|_| ConstraintCheckerError::BadlyTypedInput)?;
+        let new = outputs[0]
+            .payload
+            .extract::<Identity<ID>>()
+            .map_err(|_| ConstraintCheckerError::BadlyTypedOutput)?;
+        ensure!(
+            outputs[0].verifier == UpForGrabs.into(),
+            ConstraintCheckerError::IdentityNotUpForGrabs
+        );
+
+        ensure!(
+            new.owner == old.owner
+                && new.deposit == old.deposit
+                && new.registered_at == old.registered_at,
+            ConstraintCheckerError::ImmutableFieldChanged
+        );
+        new.check_limits::<T>()?;
+        if let Some(clearing_at) = new.clearing_at {
+            // An already scheduled clearing may be kept as is. Otherwise it must respect the delay.
+            ensure!(
+                old.clearing_at == Some(clearing_at)
+                    || clearing_at >= T::block_height().saturating_add(T::CLEARING_DELAY),
+                ConstraintCheckerError::ClearingTooSoon
+            );
+        }
+
+        ensure!(
+            check_signature(old.owner, &old.update_message(&new), &self.owner_signature),
+            ConstraintCheckerError::BadSignature
+        );
+
+        Ok(0)
+    }
+
+    fn is_inherent(&self) -> bool {
+        false
+    }
+}
+
+/// A constraint checker that clears an identity and refunds its deposit to the owner.
+///
+/// The first input is the identity, whose scheduled clearing must have arrived. The remaining
+/// inputs are judgements about the identity that are cleaned up along with it. Erroneous
+/// judgements cannot be cleaned up this way. All outputs are `Coin<ID>`s locked to the owner.
+/// Whatever part of the deposit the owner does not take back pays the transaction's fee and tip.
+#[derive(
+    Serialize,
+    Deserialize,
+    Encode,
+    Decode,
+    DebugNoBound,
+    DefaultNoBound,
+    PartialEq,
+    Eq,
+    CloneNoBound,
+    TypeInfo,
+)]
+#[scale_info(skip_type_params(T))]
+pub struct ClearIdentity<T, const ID: u8>(PhantomData<T>);
+
+impl<T: IdentityConfig, V: Verifier + PartialEq + From<SigCheck>, const ID: u8> ConstraintChecker<V>
+    for ClearIdentity<T, ID>
+{
+    type Error = ConstraintCheckerError;
+    type InherentHooks = ();
+
+    fn check(
+        &self,
+        inputs: &[Output<V>],
+        _peeks: &[Output<V>],
+        outputs: &[Output<V>],
+        _context: &CheckerContext,
+    ) -> Result<TransactionPriority, Self::Error> {
+        let identity = inputs
+            .first()
+            .ok_or(ConstraintCheckerError::WrongNumberInputs)?
+            .payload
+            .extract::<Identity<ID>>()
+            .map_err(|_| ConstraintCheckerError::BadlyTypedInput)?;
+        ensure!(
+            identity
+                .clearing_at
+                .is_some_and(|clearing_at| T::block_height() >= clearing_at),
+            ConstraintCheckerError::NotClearable
+        );
+
+        for input in &inputs[1..] {
+            let judgement = input
+                .payload
+                .extract::<Judgement<ID>>()
+                .map_err(|_| ConstraintCheckerError::BadlyTypedInput)?;
+            ensure!(
+                identity.is_judged_by(&judgement),
+                ConstraintCheckerError::JudgementMismatch
+            );
+            ensure!(
+                judgement.verdict != Verdict::Erroneous,
+                ConstraintCheckerError::CannotRemoveErroneous
+            );
+        }
+
+        let owner: V = SigCheck::new(identity.owner).into();
+        let mut refunded: u128 = 0;
+        for output in outputs {
+            ensure!(
+                output.verifier == owner,
+                ConstraintCheckerError::PaidToWrongVerifier
+            );
+            let coin = output
+                .payload
+                .extract::<Coin<ID>>()
+                .map_err(|_| ConstraintCheckerError::BadlyTypedOutput)?;
+            ensure!(coin.0 > 0, ConstraintCheckerError::ZeroValue);
+            refunded = refunded
+                .checked_add(coin.0)
+                .ok_or(ConstraintCheckerError::ValueOverflow)?;
+        }
+        ensure!(
+            refunded <= identity.deposit,
+            ConstraintCheckerError::OutputsExceedInputs
+        );
+
+        let burned = identity.deposit - refunded;
+        Ok(burned.try_into().unwrap_or(TransactionPriority::MAX))
+    }
+
+    fn is_inherent(&self) -> bool {
+        false
+    }
+}
+
+/// A constraint checker that attaches a registrar's judgement to an identity.
+///
+/// There are no inputs. The first peek is the judged identity, and the only output is the
+/// judgement, which must be signed by one of the configured registrars.
+#[derive(
+    Serialize, Deserialize, Encode, Decode, DebugNoBound, PartialEq, Eq, CloneNoBound, TypeInfo,
+)]
+#[scale_info(skip_type_params(T))]
+pub struct Judge<T, const ID: u8> {
+    /// The registrar's signature over the judgement.
+    pub registrar_signature: Signature,
+    _phantom: PhantomData<T>,
+}
+
+impl<T, const ID: u8> Judge<T, ID> {
+    pub fn new(registrar_signature: Signature) -> Self {
+        Self {
+            registrar_signature,
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<T: IdentityConfig, const ID: u8> SimpleConstraintChecker for Judge<T, ID> {
+    type Error = ConstraintCheckerError;
+
+    fn check(
+        &self,
+        input_data: &[DynamicallyTypedData],
+        peek_data: &[DynamicallyTypedData],
+        output_data: &[DynamicallyTypedData],
+        _context: &CheckerContext,
+    ) -> Result<TransactionPriority, Self::Error> {
+        ensure!(
+            input_data.is_empty(),
+            ConstraintCheckerError::WrongNumberInputs
+        );
+        let identity = peek_data
+            .first()
+            .ok_or(ConstraintCheckerError::MissingIdentity)?
+            .extract::<Identity<ID>>()
+            .map_err(|_| ConstraintCheckerError::BadlyTypedPeek)?;
+
+        ensure!(
+            output_data.len() == 1,
+            ConstraintCheckerError::WrongNumberOutputs
+        );
+        let judgement = output_data[0]
+            .extract::<Judgement<ID>>()
+            .map_err(|_| ConstraintCheckerError::BadlyTypedOutput)?;
+
+        ensure!(
+            identity.is_judged_by(&judgement) && judgement.subject == identity.subject(),
+            ConstraintCheckerError::JudgementMismatch
+        );
+        ensure!(
+            T::registrars().contains(&judgement.registrar),
+            ConstraintCheckerError::UnknownRegistrar
+        );
+        ensure!(
+            check_signature(
+                judgement.registrar,
+                &judgement.signing_message(),
+                &self.registrar_signature
+            ),
+            ConstraintCheckerError::BadSignature
+        );
+
+        Ok(0)
+    }
+}
+
+/// A constraint checker that slashes the deposit of an erroneous identity.
+///
+/// The first input is the identity and the second is an erroneous judgement about it. There are
+/// no outputs. The whole deposit is left over, so it pays the transaction's fee and tip, and goes
+/// to the block author rather than to whoever reported the identity.
+#[derive(
+    Serialize,
+    Deserialize,
+    Encode,
+    Decode,
+    DebugNoBound,
+    DefaultNoBound,
+    PartialEq,
+    Eq,
+    CloneNoBound,
+    TypeInfo,
+)]
+pub struct Slash<const ID: u8>;
+
+impl<const ID: u8> SimpleConstraintChecker for Slash<ID> {
+    type Error = ConstraintCheckerError;
+
+    fn check(
+        &self,
+        input_data: &[DynamicallyTypedData],
+        _peeks: &[DynamicallyTypedData],
+        output_data: &[DynamicallyTypedData],
+        _context: &CheckerContext,
+    ) -> Result<TransactionPriority, Self::Error> {
+        ensure!(
+            input_data.len() == 2,
+            ConstraintCheckerError::WrongNumberInputs
+        );
+        ensure!(
+            output_data.is_empty(),
+            ConstraintCheckerError::WrongNumberOutputs
+        );
+        let identity = input_data[0]
+            .extract::<Identity<ID>>()
+            .map_err(|_| ConstraintCheckerError::BadlyTypedInput)?;
+        let judgement = input_data[1]
+            .extract::<Judgement<ID>>()
+            .map_err(|_| ConstraintCheckerError::BadlyTypedInput)?;
+
+        ensure!(
+            identity.is_judged_by(&judgement),
+            ConstraintCheckerError::JudgementMismatch
+        );
+        ensure!(
+            judgement.verdict == Verdict::Erroneous,
+            ConstraintCheckerError::NotErroneous
+        );
+
+        Ok(identity
+            .deposit
+            .try_into()
+            .unwrap_or(TransactionPriority::MAX))
+    }
+}
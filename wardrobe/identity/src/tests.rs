@@ -0,0 +1,462 @@
+//! Unit tests for the Identity piece
+
+use super::*;
+use sp_core::{crypto::Pair as _, sr25519::Pair};
+use tuxedo_core::{dynamic_typing::testing::Bogus, tuxedo_verifier};
+
+/// A verifier that can represent identities as well as their owners.
+#[derive(Serialize, Deserialize, Encode, Decode, Debug, PartialEq, Eq, Clone, TypeInfo)]
+#[tuxedo_verifier]
+pub enum TestVerifier {
+    SigCheck(SigCheck),
+    UpForGrabs(UpForGrabs),
+}
+
+/// A mock config that always reports the same block height and has a single registrar.
+pub struct AtHeight<const H: u32>;
+
+impl<const H: u32> IdentityConfig for AtHeight<H> {
+    fn block_height() -> u32 {
+        H
+    }
+
+    fn registrars() -> Vec<H256> {
+        vec![registrar().public().into()]
+    }
+
+    const CLEARING_DELAY: u32 = 10;
+}
+
+fn owner() -> Pair {
+    Pair::from_seed(&[1u8; 32])
+}
+
+fn registrar() -> Pair {
+    Pair::from_seed(&[2u8; 32])
+}
+
+fn owned_by(pair: &Pair) -> TestVerifier {
+    SigCheck::new(pair.public()).into()
+}
+
+fn up_for_grabs() -> TestVerifier {
+    UpForGrabs.into()
+}
+
+/// An identity registered at block 5.
+fn identity() -> Identity<0> {
+    Identity {
+        owner: owner().public().into(),
+        display_name: b"Alice".to_vec(),
+        contacts: vec![H256::repeat_byte(9)],
+        deposit: 100,
+        registered_at: 5,
+        clearing_at: None,
+    }
+}
+
+fn judgement(identity: &Identity<0>, verdict: Verdict) -> Judgement<0> {
+    Judgement {
+        owner: identity.owner,
+        registered_at: identity.registered_at,
+        subject: identity.subject(),
+        registrar: registrar().public().into(),
+        verdict,
+    }
+}
+
+fn coin(value: u128, verifier: TestVerifier) -> Output<TestVerifier> {
+    (Coin::<0>(value), verifier).into()
+}
+
+fn check<C: ConstraintChecker<TestVerifier>>(
+    checker: C,
+    inputs: Vec<Output<TestVerifier>>,
+    outputs: Vec<Output<TestVerifier>>,
+) -> Result<TransactionPriority, C::Error> {
+    checker.check(&inputs, &[], &outputs, &Default::default())
+}
+
+#[test]
+fn register_works() {
+    let inputs = vec![coin(150, owned_by(&owner()))];
+    let outputs = vec![
+        (identity(), up_for_grabs()).into(),
+        coin(40, owned_by(&owner())),
+    ];
+
+    assert_eq!(
+        check(Register::<AtHeight<5>, 0>::default(), inputs, outputs),
+        Ok(10)
+    );
+}
+
+#[test]
+fn register_with_small_deposit_fails() {
+    let mut cheap = identity();
+    cheap.deposit = 99;
+    let inputs = vec![coin(150, owned_by(&owner()))];
+    let outputs = vec![(cheap, up_for_grabs()).into()];
+
+    assert_eq!(
+        check(Register::<AtHeight<5>, 0>::default(), inputs, outputs),
+        Err(ConstraintCheckerError::DepositTooLow)
+    );
+}
+
+#[test]
+fn register_with_long_name_fails() {
+    let mut verbose = identity();
+    verbose.display_name = vec![b'a'; 33];
+    let inputs = vec![coin(150, owned_by(&owner()))];
+    let outputs = vec![(verbose, up_for_grabs()).into()];
+
+    assert_eq!(
+        check(Register::<AtHeight<5>, 0>::default(), inputs, outputs),
+        Err(ConstraintCheckerError::NameTooLong)
+    );
+}
+
+#[test]
+fn register_with_too_many_contacts_fails() {
+    let mut chatty = identity();
+    chatty.contacts = vec![H256::zero(); 9];
+    let inputs = vec![coin(150, owned_by(&owner()))];
+    let outputs = vec![(chatty, up_for_grabs()).into()];
+
+    assert_eq!(
+        check(Register::<AtHeight<5>, 0>::default(), inputs, outputs),
+        Err(ConstraintCheckerError::TooManyContacts)
+    );
+}
+
+#[test]
+fn register_backdated_fails() {
+    let inputs = vec![coin(150, owned_by(&owner()))];
+    let outputs = vec![(identity(), up_for_grabs()).into()];
+
+    assert_eq!(
+        check(Register::<AtHeight<6>, 0>::default(), inputs, outputs),
+        Err(ConstraintCheckerError::WrongRegistrationHeight)
+    );
+}
+
+#[test]
+fn register_locked_to_owner_fails() {
+    let inputs = vec![coin(150, owned_by(&owner()))];
+    let outputs = vec![(identity(), owned_by(&owner())).into()];
+
+    assert_eq!(
+        check(Register::<AtHeight<5>, 0>::default(), inputs, outputs),
+        Err(ConstraintCheckerError::IdentityNotUpForGrabs)
+    );
+}
+
+#[test]
+fn register_exceeding_inputs_fails() {
+    let inputs = vec![coin(90, owned_by(&owner()))];
+    let outputs = vec![(identity(), up_for_grabs()).into()];
+
+    assert_eq!(
+        check(Register::<AtHeight<5>, 0>::default(), inputs, outputs),
+        Err(ConstraintCheckerError::OutputsExceedInputs)
+    );
+}
+
+fn update(
+    old: Identity<0>,
+    new: Identity<0>,
+    signer: &Pair,
+) -> Result<TransactionPriority, ConstraintCheckerError> {
+    let signature = signer.sign(&old.update_message(&new));
+    check(
+        UpdateIdentity::<AtHeight<20>, 0>::new(signature),
+        vec![(old, up_for_grabs()).into()],
+        vec![(new, up_for_grabs()).into()],
+    )
+}
+
+#[test]
+fn update_works() {
+    let mut new = identity();
+    new.display_name = b"Alice Cooper".to_vec();
+
+    assert_eq!(update(identity(), new, &owner()), Ok(0));
+}
+
+#[test]
+fn update_signed_by_someone_else_fails() {
+    let mut new = identity();
+    new.display_name = b"Mallory".to_vec();
+
+    assert_eq!(
+        update(identity(), new, &registrar()),
+        Err(ConstraintCheckerError::BadSignature)
+    );
+}
+
+#[test]
+fn update_reducing_deposit_fails() {
+    let mut new = identity();
+    new.deposit = 1;
+
+    assert_eq!(
+        update(identity(), new, &owner()),
+        Err(ConstraintCheckerError::ImmutableFieldChanged)
+    );
+}
+
+#[test]
+fn schedule_clearing_works() {
+    let mut new = identity();
+    new.clearing_at = Some(30);
+
+    assert_eq!(update(identity(), new, &owner()), Ok(0));
+}
+
+#[test]
+fn schedule_clearing_too_soon_fails() {
+    let mut new = identity();
+    new.clearing_at = Some(29);
+
+    assert_eq!(
+        update(identity(), new, &owner()),
+        Err(ConstraintCheckerError::ClearingTooSoon)
+    );
+}
+
+#[test]
+fn keeping_scheduled_clearing_works() {
+    let mut old = identity();
+    old.clearing_at = Some(25);
+    let mut new = old.clone();
+    new.display_name = b"Alice Cooper".to_vec();
+
+    assert_eq!(update(old, new, &owner()), Ok(0));
+}
+
+/// An identity whose clearing was scheduled for block 30.
+fn clearing_identity() -> Identity<0> {
+    let mut identity = identity();
+    identity.clearing_at = Some(30);
+    identity
+}
+
+#[test]
+fn clear_works() {
+    let identity = clearing_identity();
+    let reasonable = judgement(&identity, Verdict::Reasonable);
+    let inputs = vec![
+        (identity, up_for_grabs()).into(),
+        (reasonable, up_for_grabs()).into(),
+    ];
+    let outputs = vec![coin(100, owned_by(&owner()))];
+
+    assert_eq!(
+        check(ClearIdentity::<AtHeight<30>, 0>::default(), inputs, outputs),
+        Ok(0)
+    );
+}
+
+#[test]
+fn clear_before_delay_fails() {
+    let inputs = vec![(clearing_identity(), up_for_grabs()).into()];
+    let outputs = vec![coin(100, owned_by(&owner()))];
+
+    assert_eq!(
+        check(ClearIdentity::<AtHeight<29>, 0>::default(), inputs, outputs),
+        Err(ConstraintCheckerError::NotClearable)
+    );
+}
+
+#[test]
+fn clear_without_scheduling_fails() {
+    let inputs = vec![(identity(), up_for_grabs()).into()];
+    let outputs = vec![coin(100, owned_by(&owner()))];
+
+    assert_eq!(
+        check(ClearIdentity::<AtHeight<30>, 0>::default(), inputs, outputs),
+        Err(ConstraintCheckerError::NotClearable)
+    );
+}
+
+#[test]
+fn clear_removing_erroneous_judgement_fails() {
+    let identity = clearing_identity();
+    let erroneous = judgement(&identity, Verdict::Erroneous);
+    let inputs = vec![
+        (identity, up_for_grabs()).into(),
+        (erroneous, up_for_grabs()).into(),
+    ];
+    let outputs = vec![coin(100, owned_by(&owner()))];
+
+    assert_eq!(
+        check(ClearIdentity::<AtHeight<30>, 0>::default(), inputs, outputs),
+        Err(ConstraintCheckerError::CannotRemoveErroneous)
+    );
+}
+
+#[test]
+fn clear_refunding_someone_else_fails() {
+    let inputs = vec![(clearing_identity(), up_for_grabs()).into()];
+    let outputs = vec![coin(100, owned_by(&registrar()))];
+
+    assert_eq!(
+        check(ClearIdentity::<AtHeight<30>, 0>::default(), inputs, outputs),
+        Err(ConstraintCheckerError::PaidToWrongVerifier)
+    );
+}
+
+fn judge(
+    identity: Identity<0>,
+    judgement: Judgement<0>,
+    signer: &Pair,
+) -> Result<TransactionPriority, ConstraintCheckerError> {
+    let checker = Judge::<AtHeight<20>, 0>::new(signer.sign(&judgement.signing_message()));
+    SimpleConstraintChecker::check(
+        &checker,
+        &[],
+        &[identity.into()],
+        &[judgement.into()],
+        &Default::default(),
+    )
+}
+
+#[test]
+fn judge_works() {
+    let judgement = judgement(&identity(), Verdict::KnownGood);
+
+    assert_eq!(judge(identity(), judgement, &registrar()), Ok(0));
+}
+
+#[test]
+fn judge_by_unknown_registrar_fails() {
+    let mut judgement = judgement(&identity(), Verdict::KnownGood);
+    judgement.registrar = owner().public().into();
+
+    assert_eq!(
+        judge(identity(), judgement, &owner()),
+        Err(ConstraintCheckerError::UnknownRegistrar)
+    );
+}
+
+#[test]
+fn judge_with_forged_signature_fails() {
+    let judgement = judgement(&identity(), Verdict::Erroneous);
+
+    assert_eq!(
+        judge(identity(), judgement, &owner()),
+        Err(ConstraintCheckerError::BadSignature)
+    );
+}
+
+#[test]
+fn judge_stale_data_fails() {
+    let judgement = judgement(&identity(), Verdict::KnownGood);
+    let mut updated = identity();
+    updated.display_name = b"Alice Cooper".to_vec();
+
+    assert_eq!(
+        judge(updated, judgement, &registrar()),
+        Err(ConstraintCheckerError::JudgementMismatch)
+    );
+}
+
+#[test]
+fn judge_without_identity_fails() {
+    let judgement = judgement(&identity(), Verdict::KnownGood);
+    let checker = Judge::<AtHeight<20>, 0>::new(registrar().sign(&judgement.signing_message()));
+
+    assert_eq!(
+        SimpleConstraintChecker::check(
+            &checker,
+            &[],
+            &[],
+            &[judgement.into()],
+            &Default::default()
+        ),
+        Err(ConstraintCheckerError::MissingIdentity)
+    );
+}
+
+#[test]
+fn slash_works() {
+    let erroneous = judgement(&identity(), Verdict::Erroneous);
+
+    assert_eq!(
+        SimpleConstraintChecker::check(
+            &Slash::<0>,
+            &[identity().into(), erroneous.into()],
+            &[],
+            &[],
+            &Default::default()
+        ),
+        Ok(100)
+    );
+}
+
+#[test]
+fn slash_after_update_works() {
+    let erroneous = judgement(&identity(), Verdict::Erroneous);
+    let mut updated = identity();
+    updated.display_name = b"Definitely Alice".to_vec();
+
+    assert_eq!(
+        SimpleConstraintChecker::check(
+            &Slash::<0>,
+            &[updated.into(), erroneous.into()],
+            &[],
+            &[],
+            &Default::default()
+        ),
+        Ok(100)
+    );
+}
+
+#[test]
+fn slash_good_identity_fails() {
+    let good = judgement(&identity(), Verdict::KnownGood);
+
+    assert_eq!(
+        SimpleConstraintChecker::check(
+            &Slash::<0>,
+            &[identity().into(), good.into()],
+            &[],
+            &[],
+            &Default::default()
+        ),
+        Err(ConstraintCheckerError::NotErroneous)
+    );
+}
+
+#[test]
+fn slash_other_identity_fails() {
+    let mut other = identity();
+    other.registered_at = 6;
+    let erroneous = judgement(&other, Verdict::Erroneous);
+
+    assert_eq!(
+        SimpleConstraintChecker::check(
+            &Slash::<0>,
+            &[identity().into(), erroneous.into()],
+            &[],
+            &[],
+            &Default::default()
+        ),
+        Err(ConstraintCheckerError::JudgementMismatch)
+    );
+}
+
+#[test]
+fn slash_with_bogus_judgement_fails() {
+    assert_eq!(
+        SimpleConstraintChecker::check(
+            &Slash::<0>,
+            &[identity().into(), Bogus.into()],
+            &[],
+            &[],
+            &Default::default()
+        ),
+        Err(ConstraintCheckerError::BadlyTypedInput)
+    );
+}
@@ -0,0 +1,44 @@
+//! The issuance piece's section of the genesis configuration.
+
+use crate::{IssuanceConstraintChecker, IssuerCapability};
+use serde::{Deserialize, Serialize};
+use tuxedo_core::{
+    genesis::GenesisSection,
+    support_macros::DefaultNoBound,
+    types::{Output, Transaction, TransactionVersion},
+    ConstraintChecker, Verifier,
+};
+
+/// The root issuer capability that exists at genesis, if any.
+///
+/// As described in the crate docs, there should be exactly one root capability per asset,
+/// so this is the usual way to create it.
+#[derive(Serialize, Deserialize, DefaultNoBound)]
+pub struct IssuanceGenesisConfig<const ID: u8, V> {
+    /// The verifier that protects the root capability.
+    pub issuer: Option<V>,
+}
+
+impl<const ID: u8, V, C> GenesisSection<V, C> for IssuanceGenesisConfig<ID, V>
+where
+    V: Verifier + Clone,
+    C: ConstraintChecker<V> + From<IssuanceConstraintChecker<ID>>,
+{
+    fn genesis_transactions(&self) -> Vec<Transaction<V, C>> {
+        self.issuer
+            .iter()
+            .map(|verifier| Transaction {
+                version: TransactionVersion::LATEST,
+                inputs: Vec::new(),
+                peeks: Vec::new(),
+                outputs: vec![Output {
+                    payload: IssuerCapability::<ID> { epoch: 0 }.into(),
+                    verifier: verifier.clone(),
+                }],
+                checker: IssuanceConstraintChecker::Issue.into(),
+                memo: None,
+                valid_until: None,
+            })
+            .collect()
+    }
+}
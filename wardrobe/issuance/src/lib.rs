@@ -0,0 +1,319 @@
+//! A permissioned issuance scheme for the tokens provided by the money piece.
+//!
+//! The money piece's own `Mint` checker lets anyone create coins from the void. This piece
+//! offers an alternative where minting new `Coin<ID>`s requires consuming and re-creating an
+//! [`IssuerCapability`] UTXO. Only whoever can satisfy the capability's verifier can expand
+//! the supply. Typically the capability is placed in the genesis state and the runtime does
+//! not include the money piece's `Mint` checker at all.
+//!
+//! The capability holder may delegate issuance rights to others by creating
+//! [`DelegatedIssuerCapability`] UTXOs. A delegate mints by consuming and re-creating their
+//! delegated capability while peeking at the root capability. Each delegation is tagged with
+//! the root capability's epoch at the time it was made. Revoking bumps the root's epoch, which
+//! invalidates all outstanding delegations at once without needing the delegates' signatures.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use money::Coin;
+use parity_scale_codec::{Decode, Encode};
+use scale_info::TypeInfo;
+use serde::{Deserialize, Serialize};
+use sp_runtime::transaction_validity::TransactionPriority;
+use sp_std::prelude::*;
+use tuxedo_core::{
+    dynamic_typing::{DynamicallyTypedData, UtxoData},
+    ensure, CheckerContext, SimpleConstraintChecker,
+};
+
+#[cfg(test)]
+mod tests;
+
+#[cfg(feature = "std")]
+pub mod genesis;
+
+/// The root authority to issue new `Coin<ID>`s.
+///
+/// There should be exactly one of these per asset, and it should be created at genesis.
+#[derive(
+    Serialize,
+    Deserialize,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Clone,
+    Encode,
+    Decode,
+    Hash,
+    Debug,
+    TypeInfo,
+)]
+pub struct IssuerCapability<const ID: u8> {
+    /// The current revocation epoch. Only delegations made in this epoch are valid.
+    pub epoch: u32,
+}
+
+impl<const ID: u8> UtxoData for IssuerCapability<ID> {
+    const TYPE_ID: [u8; 4] = [b'i', b's', b'c', ID];
+}
+
+/// The authority to issue new `Coin<ID>`s on behalf of the root capability holder.
+#[derive(
+    Serialize,
+    Deserialize,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Clone,
+    Encode,
+    Decode,
+    Hash,
+    Debug,
+    TypeInfo,
+)]
+pub struct DelegatedIssuerCapability<const ID: u8> {
+    /// The root capability's epoch when this delegation was made.
+    /// The delegation is only usable while the root is still in this epoch.
+    pub epoch: u32,
+}
+
+impl<const ID: u8> UtxoData for DelegatedIssuerCapability<ID> {
+    const TYPE_ID: [u8; 4] = [b'i', b'd', b'c', ID];
+}
+
+/// Errors that can occur when checking issuance transactions.
+#[derive(
+    Serialize,
+    Deserialize,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Clone,
+    Encode,
+    Decode,
+    Hash,
+    Debug,
+    TypeInfo,
+)]
+pub enum ConstraintCheckerError {
+    /// An input data has the wrong type.
+    BadlyTypedInput,
+    /// An output data has the wrong type.
+    BadlyTypedOutput,
+    /// A peek data has the wrong type.
+    BadlyTypedPeek,
+    /// Wrong number of inputs were provided to the constraint checker.
+    WrongNumberInputs,
+    /// Wrong number of outputs were provided to the constraint checker.
+    WrongNumberOutputs,
+    /// Wrong number of peeks were provided to the constraint checker.
+    WrongNumberPeeks,
+    /// The capability that was consumed was not re-created unchanged as the first output.
+    CapabilityNotPreserved,
+    /// The transaction does not issue or delegate anything, so it is a waste of processing power.
+    NothingCreated,
+    /// The transaction attempted to issue a coin with zero value. This is not allowed
+    /// because it wastes state space.
+    ZeroValueCoin,
+    /// A delegated capability was used or created for an epoch other than the root's current epoch.
+    /// Either it has been revoked, or it is being created incorrectly.
+    EpochMismatch,
+    /// The root capability's epoch must increase by exactly one when revoking.
+    /// The epoch counter is also not allowed to wrap around.
+    BadEpochIncrement,
+}
+
+/// The constraint checkers for permissioned issuance of `Coin<ID>`.
+#[derive(
+    Serialize,
+    Deserialize,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Clone,
+    Encode,
+    Decode,
+    Hash,
+    Debug,
+    TypeInfo,
+)]
+pub enum IssuanceConstraintChecker<const ID: u8> {
+    /// Mint new coins.
+    ///
+    /// The only input is either the root capability or a delegated capability, and it must be
+    /// re-created unchanged as the first output. When a delegated capability is used, the root
+    /// capability must be peeked at to prove the delegation has not been revoked.
+    /// All remaining outputs are the newly issued coins.
+    Issue,
+    /// Delegate issuance rights.
+    ///
+    /// The only input is the root capability, and it must be re-created unchanged as the first
+    /// output. All remaining outputs are new delegated capabilities in the root's current epoch.
+    Delegate,
+    /// Revoke all outstanding delegations.
+    ///
+    /// Consumes the root capability and re-creates it with its epoch incremented by one.
+    Revoke,
+    /// Destroy delegated capabilities. This is mostly useful for delegates to clean up
+    /// their capabilities after they have been revoked.
+    Renounce,
+}
+
+impl<const ID: u8> IssuanceConstraintChecker<ID> {
+    /// Check that the single input is the root capability, and that it is preserved as the first output.
+    /// Returns the root capability.
+    fn check_root_preserved(
+        input_data: &[DynamicallyTypedData],
+        output_data: &[DynamicallyTypedData],
+    ) -> Result<IssuerCapability<ID>, ConstraintCheckerError> {
+        ensure!(
+            input_data.len() == 1,
+            ConstraintCheckerError::WrongNumberInputs
+        );
+        let root = input_data[0]
+            .extract::<IssuerCapability<ID>>()
+            .map_err(|_| ConstraintCheckerError::BadlyTypedInput)?;
+
+        let preserved = output_data
+            .first()
+            .ok_or(ConstraintCheckerError::CapabilityNotPreserved)?
+            .extract::<IssuerCapability<ID>>()
+            .map_err(|_| ConstraintCheckerError::CapabilityNotPreserved)?;
+        ensure!(
+            preserved == root,
+            ConstraintCheckerError::CapabilityNotPreserved
+        );
+
+        Ok(root)
+    }
+}
+
+impl<const ID: u8> SimpleConstraintChecker for IssuanceConstraintChecker<ID> {
+    type Error = ConstraintCheckerError;
+
+    fn check(
+        &self,
+        input_data: &[DynamicallyTypedData],
+        peek_data: &[DynamicallyTypedData],
+        output_data: &[DynamicallyTypedData],
+        _context: &CheckerContext,
+    ) -> Result<TransactionPriority, Self::Error> {
+        match self {
+            Self::Issue => {
+                ensure!(
+                    input_data.len() == 1,
+                    ConstraintCheckerError::WrongNumberInputs
+                );
+
+                // Make sure the capability is either the root or a still-valid delegation,
+                // and that it is re-created unchanged.
+                let first_output = output_data
+                    .first()
+                    .ok_or(ConstraintCheckerError::CapabilityNotPreserved)?;
+                if let Ok(root) = input_data[0].extract::<IssuerCapability<ID>>() {
+                    ensure!(
+                        first_output.extract::<IssuerCapability<ID>>() == Ok(root),
+                        ConstraintCheckerError::CapabilityNotPreserved
+                    );
+                } else {
+                    let delegation = input_data[0]
+                        .extract::<DelegatedIssuerCapability<ID>>()
+                        .map_err(|_| ConstraintCheckerError::BadlyTypedInput)?;
+                    ensure!(
+                        first_output.extract::<DelegatedIssuerCapability<ID>>()
+                            == Ok(delegation.clone()),
+                        ConstraintCheckerError::CapabilityNotPreserved
+                    );
+
+                    ensure!(
+                        peek_data.len() == 1,
+                        ConstraintCheckerError::WrongNumberPeeks
+                    );
+                    let root = peek_data[0]
+                        .extract::<IssuerCapability<ID>>()
+                        .map_err(|_| ConstraintCheckerError::BadlyTypedPeek)?;
+                    ensure!(
+                        root.epoch == delegation.epoch,
+                        ConstraintCheckerError::EpochMismatch
+                    );
+                }
+
+                // Make sure at least one coin is issued, and that all the coins are valid.
+                let coins = &output_data[1..];
+                ensure!(!coins.is_empty(), ConstraintCheckerError::NothingCreated);
+                for coin in coins {
+                    let value = coin
+                        .extract::<Coin<ID>>()
+                        .map_err(|_| ConstraintCheckerError::BadlyTypedOutput)?
+                        .0;
+                    ensure!(value > 0, ConstraintCheckerError::ZeroValueCoin);
+                }
+
+                Ok(0)
+            }
+            Self::Delegate => {
+                let root = Self::check_root_preserved(input_data, output_data)?;
+
+                let delegations = &output_data[1..];
+                ensure!(
+                    !delegations.is_empty(),
+                    ConstraintCheckerError::NothingCreated
+                );
+                for delegation in delegations {
+                    let delegation = delegation
+                        .extract::<DelegatedIssuerCapability<ID>>()
+                        .map_err(|_| ConstraintCheckerError::BadlyTypedOutput)?;
+                    ensure!(
+                        delegation.epoch == root.epoch,
+                        ConstraintCheckerError::EpochMismatch
+                    );
+                }
+
+                Ok(0)
+            }
+            Self::Revoke => {
+                ensure!(
+                    input_data.len() == 1,
+                    ConstraintCheckerError::WrongNumberInputs
+                );
+                let old_root = input_data[0]
+                    .extract::<IssuerCapability<ID>>()
+                    .map_err(|_| ConstraintCheckerError::BadlyTypedInput)?;
+
+                ensure!(
+                    output_data.len() == 1,
+                    ConstraintCheckerError::WrongNumberOutputs
+                );
+                let new_root = output_data[0]
+                    .extract::<IssuerCapability<ID>>()
+                    .map_err(|_| ConstraintCheckerError::BadlyTypedOutput)?;
+                ensure!(
+                    old_root.epoch.checked_add(1) == Some(new_root.epoch),
+                    ConstraintCheckerError::BadEpochIncrement
+                );
+
+                Ok(0)
+            }
+            Self::Renounce => {
+                ensure!(
+                    !input_data.is_empty(),
+                    ConstraintCheckerError::WrongNumberInputs
+                );
+                ensure!(
+                    output_data.is_empty(),
+                    ConstraintCheckerError::WrongNumberOutputs
+                );
+                for input in input_data {
+                    input
+                        .extract::<DelegatedIssuerCapability<ID>>()
+                        .map_err(|_| ConstraintCheckerError::BadlyTypedInput)?;
+                }
+
+                Ok(0)
+            }
+        }
+    }
+}
@@ -0,0 +1,258 @@
+//! Unit tests for the Issuance piece
+
+use super::*;
+use tuxedo_core::dynamic_typing::testing::Bogus;
+
+type Checker = IssuanceConstraintChecker<0>;
+
+fn root(epoch: u32) -> DynamicallyTypedData {
+    IssuerCapability::<0> { epoch }.into()
+}
+
+fn delegation(epoch: u32) -> DynamicallyTypedData {
+    DelegatedIssuerCapability::<0> { epoch }.into()
+}
+
+#[test]
+fn issue_with_root_works() {
+    let input_data = vec![root(0)];
+    let output_data = vec![root(0), Coin::<0>(10).into(), Coin::<0>(5).into()];
+
+    assert_eq!(
+        Checker::Issue.check(&input_data, &[], &output_data, &Default::default()),
+        Ok(0)
+    );
+}
+
+#[test]
+fn issue_with_valid_delegation_works() {
+    let input_data = vec![delegation(3)];
+    let peek_data = vec![root(3)];
+    let output_data = vec![delegation(3), Coin::<0>(10).into()];
+
+    assert_eq!(
+        Checker::Issue.check(&input_data, &peek_data, &output_data, &Default::default()),
+        Ok(0)
+    );
+}
+
+#[test]
+fn issue_with_revoked_delegation_fails() {
+    let input_data = vec![delegation(3)];
+    let peek_data = vec![root(4)];
+    let output_data = vec![delegation(3), Coin::<0>(10).into()];
+
+    assert_eq!(
+        Checker::Issue.check(&input_data, &peek_data, &output_data, &Default::default()),
+        Err(ConstraintCheckerError::EpochMismatch)
+    );
+}
+
+#[test]
+fn issue_with_delegation_without_peeking_root_fails() {
+    let input_data = vec![delegation(3)];
+    let output_data = vec![delegation(3), Coin::<0>(10).into()];
+
+    assert_eq!(
+        Checker::Issue.check(&input_data, &[], &output_data, &Default::default()),
+        Err(ConstraintCheckerError::WrongNumberPeeks)
+    );
+}
+
+#[test]
+fn issue_without_capability_fails() {
+    let output_data = vec![Coin::<0>(10).into()];
+
+    assert_eq!(
+        Checker::Issue.check(&[], &[], &output_data, &Default::default()),
+        Err(ConstraintCheckerError::WrongNumberInputs)
+    );
+}
+
+#[test]
+fn issue_with_wrong_capability_type_fails() {
+    let input_data = vec![Bogus.into()];
+    let output_data = vec![Bogus.into(), Coin::<0>(10).into()];
+
+    assert_eq!(
+        Checker::Issue.check(&input_data, &[], &output_data, &Default::default()),
+        Err(ConstraintCheckerError::BadlyTypedInput)
+    );
+}
+
+#[test]
+fn issue_with_capability_for_other_asset_fails() {
+    let input_data = vec![IssuerCapability::<1> { epoch: 0 }.into()];
+    let output_data = vec![
+        IssuerCapability::<1> { epoch: 0 }.into(),
+        Coin::<0>(10).into(),
+    ];
+
+    assert_eq!(
+        Checker::Issue.check(&input_data, &[], &output_data, &Default::default()),
+        Err(ConstraintCheckerError::BadlyTypedInput)
+    );
+}
+
+#[test]
+fn issue_without_preserving_capability_fails() {
+    let input_data = vec![root(0)];
+    let output_data = vec![Coin::<0>(10).into()];
+
+    assert_eq!(
+        Checker::Issue.check(&input_data, &[], &output_data, &Default::default()),
+        Err(ConstraintCheckerError::CapabilityNotPreserved)
+    );
+}
+
+#[test]
+fn issue_modifying_capability_fails() {
+    let input_data = vec![root(0)];
+    let output_data = vec![root(1), Coin::<0>(10).into()];
+
+    assert_eq!(
+        Checker::Issue.check(&input_data, &[], &output_data, &Default::default()),
+        Err(ConstraintCheckerError::CapabilityNotPreserved)
+    );
+}
+
+#[test]
+fn issue_nothing_fails() {
+    let input_data = vec![root(0)];
+    let output_data = vec![root(0)];
+
+    assert_eq!(
+        Checker::Issue.check(&input_data, &[], &output_data, &Default::default()),
+        Err(ConstraintCheckerError::NothingCreated)
+    );
+}
+
+#[test]
+fn issue_zero_value_coin_fails() {
+    let input_data = vec![root(0)];
+    let output_data = vec![root(0), Coin::<0>(0).into()];
+
+    assert_eq!(
+        Checker::Issue.check(&input_data, &[], &output_data, &Default::default()),
+        Err(ConstraintCheckerError::ZeroValueCoin)
+    );
+}
+
+#[test]
+fn issue_coin_of_other_asset_fails() {
+    let input_data = vec![root(0)];
+    let output_data = vec![root(0), Coin::<1>(10).into()];
+
+    assert_eq!(
+        Checker::Issue.check(&input_data, &[], &output_data, &Default::default()),
+        Err(ConstraintCheckerError::BadlyTypedOutput)
+    );
+}
+
+#[test]
+fn delegate_works() {
+    let input_data = vec![root(2)];
+    let output_data = vec![root(2), delegation(2), delegation(2)];
+
+    assert_eq!(
+        Checker::Delegate.check(&input_data, &[], &output_data, &Default::default()),
+        Ok(0)
+    );
+}
+
+#[test]
+fn delegate_from_delegation_fails() {
+    let input_data = vec![delegation(2)];
+    let output_data = vec![delegation(2), delegation(2)];
+
+    assert_eq!(
+        Checker::Delegate.check(&input_data, &[], &output_data, &Default::default()),
+        Err(ConstraintCheckerError::BadlyTypedInput)
+    );
+}
+
+#[test]
+fn delegate_for_wrong_epoch_fails() {
+    let input_data = vec![root(2)];
+    let output_data = vec![root(2), delegation(1)];
+
+    assert_eq!(
+        Checker::Delegate.check(&input_data, &[], &output_data, &Default::default()),
+        Err(ConstraintCheckerError::EpochMismatch)
+    );
+}
+
+#[test]
+fn delegate_nothing_fails() {
+    let input_data = vec![root(2)];
+    let output_data = vec![root(2)];
+
+    assert_eq!(
+        Checker::Delegate.check(&input_data, &[], &output_data, &Default::default()),
+        Err(ConstraintCheckerError::NothingCreated)
+    );
+}
+
+#[test]
+fn revoke_works() {
+    let input_data = vec![root(2)];
+    let output_data = vec![root(3)];
+
+    assert_eq!(
+        Checker::Revoke.check(&input_data, &[], &output_data, &Default::default()),
+        Ok(0)
+    );
+}
+
+#[test]
+fn revoke_without_incrementing_epoch_fails() {
+    let input_data = vec![root(2)];
+    let output_data = vec![root(2)];
+
+    assert_eq!(
+        Checker::Revoke.check(&input_data, &[], &output_data, &Default::default()),
+        Err(ConstraintCheckerError::BadEpochIncrement)
+    );
+}
+
+#[test]
+fn revoke_with_epoch_overflow_fails() {
+    let input_data = vec![root(u32::MAX)];
+    let output_data = vec![root(0)];
+
+    assert_eq!(
+        Checker::Revoke.check(&input_data, &[], &output_data, &Default::default()),
+        Err(ConstraintCheckerError::BadEpochIncrement)
+    );
+}
+
+#[test]
+fn revoke_with_extra_outputs_fails() {
+    let input_data = vec![root(2)];
+    let output_data = vec![root(3), delegation(3)];
+
+    assert_eq!(
+        Checker::Revoke.check(&input_data, &[], &output_data, &Default::default()),
+        Err(ConstraintCheckerError::WrongNumberOutputs)
+    );
+}
+
+#[test]
+fn renounce_works() {
+    let input_data = vec![delegation(0), delegation(1)];
+
+    assert_eq!(
+        Checker::Renounce.check(&input_data, &[], &[], &Default::default()),
+        Ok(0)
+    );
+}
+
+#[test]
+fn renounce_root_fails() {
+    let input_data = vec![root(0)];
+
+    assert_eq!(
+        Checker::Renounce.check(&input_data, &[], &[], &Default::default()),
+        Err(ConstraintCheckerError::BadlyTypedInput)
+    );
+}
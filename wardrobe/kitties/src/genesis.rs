@@ -0,0 +1,58 @@
+//! The kitties piece's section of the genesis configuration.
+
+use crate::{FreeKittyConstraintChecker, KittyData, Parent};
+use serde::{Deserialize, Serialize};
+use sp_std::marker::PhantomData;
+use tuxedo_core::{
+    genesis::GenesisSection, support_macros::DefaultNoBound, types::Transaction, ConstraintChecker,
+    Verifier,
+};
+
+/// A single kitty that exists at genesis.
+#[derive(Serialize, Deserialize)]
+pub struct GenesisKitty<V> {
+    /// Whether the kitty is a mom or a dad.
+    pub parent: Parent,
+    /// The kitty's DNA is the hash of this preimage.
+    pub dna_preimage: String,
+    /// The verifier that protects the kitty.
+    pub verifier: V,
+}
+
+/// The kitties that exist at genesis. Each kitty is minted in its own transaction.
+#[derive(Serialize, Deserialize, DefaultNoBound)]
+pub struct KittiesGenesisConfig<V, T> {
+    /// The initial kitties.
+    pub kitties: Vec<GenesisKitty<V>>,
+    #[serde(skip)]
+    _config: PhantomData<T>,
+}
+
+impl<V, T> KittiesGenesisConfig<V, T> {
+    /// Create a genesis section with the given initial kitties.
+    pub fn new(kitties: Vec<GenesisKitty<V>>) -> Self {
+        Self {
+            kitties,
+            _config: PhantomData,
+        }
+    }
+}
+
+impl<V, C, T> GenesisSection<V, C> for KittiesGenesisConfig<V, T>
+where
+    V: Verifier + Clone,
+    C: ConstraintChecker<V> + From<FreeKittyConstraintChecker<T>>,
+{
+    fn genesis_transactions(&self) -> Vec<Transaction<V, C>> {
+        self.kitties
+            .iter()
+            .map(|kitty| {
+                KittyData::mint::<V, V, C, T>(
+                    kitty.parent.clone(),
+                    kitty.dna_preimage.as_bytes(),
+                    kitty.verifier.clone(),
+                )
+            })
+            .collect()
+    }
+}
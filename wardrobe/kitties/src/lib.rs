@@ -4,19 +4,39 @@
 //! 2.) Each Mom and Dad have some DNA and the child will have unique DNA combined from the both of them
 //!     Linkable back to the Mom and Dad
 //! 3.) The game also allows Kitties to have a cooling off period inbetween breeding before they can be bred again.
-//! 4.) A rest operation allows for a Mom Kitty and a Dad Kitty to be cooled off
+//! 4.) Each parent remembers the block height it last bred at. Once the runtime's configured
+//!     cooldown has passed since then, it is rested, and may breed again.
 //!
 //! In order to submit a valid transaction you must strutucture it as follows:
 //! 1.) Input must contain 1 mom and 1 dad
 //! 2.) Output must contain Mom, Dad, and newly created Child
-//! 3.) A child's DNA is calculated by:
-//!         BlakeTwo256::hash_of(MomDna, DadDna, MomCurrNumBreedings, DadCurrNumberBreedings)
+//! 3.) A child's DNA is a mix of its parents' genes. Each byte comes from either the Mom or the Dad,
+//!     and so does the child's gender, as selected by:
+//!         BlakeTwo256::hash_of(MomDna, DadDna, MomCurrNumBreedings, DadCurrNumberBreedings, BlockEntropy)
+//! 4.) A kitty cannot breed with itself.
+//! 5.) Occasionally, as decided by the same selector, one of the child's genes mutates into one
+//!     that neither parent has. See [`MUTATION_CHANCE`].
 //!
 //! There are a only a finite amount of free breedings available before it starts to cost money
 //! to breed kitties.
+//!
+//! Owners may rename their kitties with an `UpdateKittyName` transaction, which re-creates each
+//! kitty with a new name and proves that nothing else about it changed.
+//!
+//! Kitties can also be traded for `Coin<ID>`s from the money piece. An owner lists a kitty at a
+//! fixed price, which locks it up for grabs as a [`KittyListing`]. Anyone may then buy it with a
+//! `KittySale` transaction that pays the price to the seller, or the listing may be withdrawn,
+//! which returns the kitty to the seller.
+//!
+//! A kitty's DNA also decodes into its visible traits, such as its fur, its eyes, and how rare it
+//! is. Front-ends can query them through the [`KittiesApi`] runtime API rather than duplicating
+//! the gene math.
 
 #![cfg_attr(not(feature = "std"), no_std)]
 
+use core::marker::PhantomData;
+
+use money::{Coin, MoneyConstraintChecker};
 use parity_scale_codec::{Decode, Encode};
 use scale_info::TypeInfo;
 use serde::{Deserialize, Serialize};
@@ -29,28 +49,52 @@ use sp_std::prelude::*;
 use tuxedo_core::{
     dynamic_typing::{DynamicallyTypedData, UtxoData},
     ensure,
-    types::Transaction,
-    SimpleConstraintChecker, Verifier,
+    events::{self, TuxedoEvent},
+    support_macros::{CloneNoBound, DebugNoBound, DefaultNoBound},
+    types::{Output, Transaction, TransactionVersion},
+    verifier::UpForGrabs,
+    weights::{Weight, DEFAULT_CHECKER_WEIGHT},
+    CheckerContext, Delegation, DelegationError, SimpleConstraintChecker, Verifier,
 };
 
 #[cfg(test)]
 mod tests;
 
+#[cfg(feature = "std")]
+pub mod genesis;
+
+/// Configuration items for the kitties piece when it is
+/// instantiated in a concrete runtime.
+pub trait KittyConfig {
+    /// A source of entropy that is mixed into newly bred kitties' DNA.
+    /// Probably this will be the parent block hash, which is known to whoever builds
+    /// a breeding transaction. The transaction is only valid with the entropy it was built for.
+    fn block_entropy() -> H256;
+
+    /// A means of getting the current block height.
+    /// Probably this will be the Tuxedo Executive
+    fn block_height() -> u32;
+
+    /// The number of blocks that a parent must rest after breeding before it may breed again.
+    ///
+    /// Default is roughly ten minutes with 6 second block times.
+    const BREEDING_COOLDOWN: u32 = 100;
+}
+
 #[derive(
     Serialize,
     Deserialize,
-    PartialEq,
-    Eq,
-    PartialOrd,
-    Ord,
-    Clone,
     Encode,
     Decode,
-    Hash,
-    Debug,
+    DebugNoBound,
+    DefaultNoBound,
+    CloneNoBound,
+    PartialEq,
+    Eq,
     TypeInfo,
 )]
-pub struct FreeKittyConstraintChecker;
+#[scale_info(skip_type_params(T))]
+pub struct FreeKittyConstraintChecker<T>(PhantomData<T>);
 
 #[derive(
     Serialize,
@@ -159,23 +203,30 @@ pub struct KittyDNA(pub H256);
     Hash,
     Debug,
     TypeInfo,
+    UtxoData,
 )]
 pub struct KittyData {
     pub parent: Parent,
     pub free_breedings: u64, // Ignore in breed for money case
     pub dna: KittyDNA,
     pub num_breedings: u128,
+    /// A short name chosen by the kitty's owner. It has no effect on the game, and can be
+    /// changed at any time with the [`UpdateKittyName`] constraint checker.
+    pub name: [u8; 4],
+    /// The block height at which this kitty last bred, or `None` if it never has.
+    pub last_bred: Option<u32>,
 }
 
 impl KittyData {
     /// Create a mint transaction for a single Kitty.
-    pub fn mint<V, OV, OC>(parent: Parent, dna_preimage: &[u8], v: V) -> Transaction<OV, OC>
+    pub fn mint<V, OV, OC, T>(parent: Parent, dna_preimage: &[u8], v: V) -> Transaction<OV, OC>
     where
         V: Verifier,
         OV: Verifier + From<V>,
-        OC: tuxedo_core::ConstraintChecker<OV> + From<FreeKittyConstraintChecker>,
+        OC: tuxedo_core::ConstraintChecker<OV> + From<FreeKittyConstraintChecker<T>>,
     {
         Transaction {
+            version: TransactionVersion::LATEST,
             inputs: vec![],
             peeks: vec![],
             outputs: vec![(
@@ -187,7 +238,9 @@ impl KittyData {
                 v,
             )
                 .into()],
-            checker: FreeKittyConstraintChecker.into(),
+            checker: FreeKittyConstraintChecker(PhantomData).into(),
+            memo: None,
+            valid_until: None,
         }
     }
 }
@@ -199,12 +252,198 @@ impl Default for KittyData {
             free_breedings: 2,
             dna: KittyDNA(H256::from_slice(b"mom_kitty_1asdfasdfasdfasdfasdfa")),
             num_breedings: 3,
+            name: *b"kity",
+            last_bred: None,
         }
     }
 }
 
-impl UtxoData for KittyData {
-    const TYPE_ID: [u8; 4] = *b"Kitt";
+/// The chance, out of 256, that a newly bred child has a mutated gene.
+pub const MUTATION_CHANCE: u8 = 16;
+
+/// Mix two parents' genes into the DNA and gender of their child.
+///
+/// A selector is hashed from both parents and the block entropy. Each byte of the child's DNA
+/// is inherited from the mom or the dad according to the corresponding byte of the selector,
+/// and the selector's last byte also decides the child's gender.
+///
+/// Then, with a chance of [`MUTATION_CHANCE`] out of 256, a single gene chosen by the selector
+/// mutates by flipping some of its bits, so the child has at least one gene from neither parent.
+pub fn mix_genes(mom: &KittyData, dad: &KittyData, entropy: H256) -> (KittyDNA, Parent) {
+    let selector = BlakeTwo256::hash_of(&(
+        &mom.dna,
+        &dad.dna,
+        &mom.num_breedings,
+        &dad.num_breedings,
+        &entropy,
+    ));
+    let selector = selector.as_bytes();
+
+    let mut dna = [0u8; 32];
+    for (i, gene) in dna.iter_mut().enumerate() {
+        *gene = if selector[i] & 1 == 0 {
+            mom.dna.0.as_bytes()[i]
+        } else {
+            dad.dna.0.as_bytes()[i]
+        };
+    }
+
+    if selector[30] < MUTATION_CHANCE {
+        // Flipping at least the lowest bit guarantees the gene actually changes.
+        let gene = selector[29] as usize % dna.len();
+        dna[gene] ^= selector[28] | 1;
+    }
+
+    let gender = if selector[31] & 0x80 == 0 {
+        Parent::mom()
+    } else {
+        Parent::dad()
+    };
+
+    (KittyDNA(H256::from(dna)), gender)
+}
+
+/// The pattern of a kitty's fur, decided by the first gene.
+#[derive(
+    Serialize,
+    Deserialize,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Clone,
+    Copy,
+    Encode,
+    Decode,
+    Hash,
+    Debug,
+    TypeInfo,
+)]
+pub enum Fur {
+    Tabby,
+    Calico,
+    Tuxedo,
+    Siamese,
+    Ginger,
+    Black,
+    White,
+    Sphynx,
+}
+
+/// The color of a kitty's eyes, decided by the second gene.
+#[derive(
+    Serialize,
+    Deserialize,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Clone,
+    Copy,
+    Encode,
+    Decode,
+    Hash,
+    Debug,
+    TypeInfo,
+)]
+pub enum EyeColor {
+    Green,
+    Blue,
+    Amber,
+    Copper,
+    /// Each eye has a different color.
+    Odd,
+}
+
+/// How rare a kitty is, decided by the third gene.
+#[derive(
+    Serialize,
+    Deserialize,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Clone,
+    Copy,
+    Encode,
+    Decode,
+    Hash,
+    Debug,
+    TypeInfo,
+)]
+pub enum Rarity {
+    /// Three in four kitties are common.
+    Common,
+    /// About three in sixteen kitties are uncommon.
+    Uncommon,
+    /// About one in seventeen kitties is rare.
+    Rare,
+    /// One in 256 kitties is legendary.
+    Legendary,
+}
+
+/// The visible traits that a kitty's DNA decodes into.
+#[derive(
+    Serialize,
+    Deserialize,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Clone,
+    Encode,
+    Decode,
+    Hash,
+    Debug,
+    TypeInfo,
+)]
+pub struct KittyTraits {
+    pub fur: Fur,
+    pub eyes: EyeColor,
+    pub rarity: Rarity,
+}
+
+impl KittyDNA {
+    /// Decode this DNA into the traits it expresses.
+    pub fn traits(&self) -> KittyTraits {
+        let genes = self.0.as_bytes();
+
+        let fur = match genes[0] >> 5 {
+            0 => Fur::Tabby,
+            1 => Fur::Calico,
+            2 => Fur::Tuxedo,
+            3 => Fur::Siamese,
+            4 => Fur::Ginger,
+            5 => Fur::Black,
+            6 => Fur::White,
+            _ => Fur::Sphynx,
+        };
+
+        let eyes = match genes[1] {
+            0..=63 => EyeColor::Green,
+            64..=127 => EyeColor::Blue,
+            128..=191 => EyeColor::Amber,
+            192..=247 => EyeColor::Copper,
+            _ => EyeColor::Odd,
+        };
+
+        let rarity = match genes[2] {
+            0..=191 => Rarity::Common,
+            192..=239 => Rarity::Uncommon,
+            240..=254 => Rarity::Rare,
+            _ => Rarity::Legendary,
+        };
+
+        KittyTraits { fur, eyes, rarity }
+    }
+}
+
+sp_api::decl_runtime_apis! {
+    /// An API that lets front-ends learn about kitties without duplicating the gene math.
+    pub trait KittiesApi {
+        /// The traits that the given DNA decodes into.
+        fn kitty_traits(dna: KittyDNA) -> KittyTraits;
+    }
 }
 
 #[derive(
@@ -231,9 +470,9 @@ pub enum ConstraintCheckerError {
     TwoParentsDoNotExist,
     /// Incorrect number of outputs when it comes to breeding.
     NotEnoughFamilyMembers,
-    /// Mom has recently given birth and isnt ready to breed.
+    /// Mom has recently given birth and has not rested for the breeding cooldown yet.
     MomNotReadyYet,
-    /// Dad cannot breed because he is still too tired.
+    /// Dad cannot breed because he has not rested for the breeding cooldown yet.
     DadTooTired,
     /// Cannot have two moms when breeding.
     TwoMomsNotValid,
@@ -249,6 +488,8 @@ pub enum ConstraintCheckerError {
     NewParentDnaDoesntMatchOld,
     /// New parent Breedings has not incremented or is incorrect.
     NewParentNumberBreedingsIncorrect,
+    /// New parent does not remember that it last bred in the current block.
+    NewParentLastBredIncorrect,
     /// New child DNA is not correct given the protocol.
     NewChildDnaIncorrect,
     /// New child doesnt have the correct number of free breedings.
@@ -261,6 +502,30 @@ pub enum ConstraintCheckerError {
     TooManyBreedingsForKitty,
     /// Not enough free breedings available for these parents.
     NotEnoughFreeBreedings,
+    /// The mom and the dad are the same kitty. A kitty cannot breed with itself.
+    CannotBreedWithSelf,
+    /// New child's gender is not the one its genes decide.
+    NewChildGenderIncorrect,
+    /// Wrong number of inputs were provided to the constraint checker.
+    WrongNumberInputs,
+    /// Wrong number of outputs were provided to the constraint checker.
+    WrongNumberOutputs,
+    /// A kitty was listed for sale at no price.
+    ZeroPrice,
+    /// A listing does not hold the kitty it was created from, or is not payable to its owner.
+    ListingMismatch,
+    /// A listing was locked to a verifier other than `UpForGrabs`, so buyers could not consume it.
+    ListingNotUpForGrabs,
+    /// A withdrawn listing did not return exactly its kitty to the seller.
+    KittyNotReturned,
+    /// A sold kitty was not delivered unchanged as the first output.
+    KittyNotDelivered,
+    /// The seller was not paid the listed price as the second output.
+    SellerNotPaid,
+    /// The coins that pay for a sale are not a valid spend in the money piece.
+    Payment(money::ConstraintCheckerError),
+    /// A renamed kitty's DNA, parent status, breeding stats, or cooldown differ from the original's.
+    RenamedKittyMismatch,
 }
 
 trait Breed {
@@ -271,28 +536,36 @@ trait Breed {
     /// Error type for all Kitty errors.
     type Error: Into<ConstraintCheckerError>;
     /// Check if the two parents (Mom, Dad) proposed are capable of breeding.
-    fn can_breed(mom: &KittyData, dad: &KittyData) -> Result<(), Self::Error>;
+    fn can_breed<T: KittyConfig>(mom: &KittyData, dad: &KittyData) -> Result<(), Self::Error>;
     /// Checks if mom is in the correct state and capable of breeding.
-    fn check_mom_can_breed(mom: &KittyData) -> Result<(), Self::Error>;
+    fn check_mom_can_breed<T: KittyConfig>(mom: &KittyData) -> Result<(), Self::Error>;
     /// Checks if dad is in the correct state and capable of breeding.
-    fn check_dad_can_breed(dad: &KittyData) -> Result<(), Self::Error>;
+    fn check_dad_can_breed<T: KittyConfig>(dad: &KittyData) -> Result<(), Self::Error>;
     /// Makes sure each parent has a non-zero number of free breedings.
     fn check_free_breedings(mom: &KittyData, dad: &KittyData) -> Result<(), Self::Error>;
     /// Checks outputs which consists of (Mom, Dad, Child) is correctly formulated.
-    fn check_new_family(
+    fn check_new_family<T: KittyConfig>(
         old_mom: &KittyData,
         old_dad: &KittyData,
         new_family: &[DynamicallyTypedData],
+        entropy: H256,
     ) -> Result<(), Self::Error>;
     /// Checks if new mom matches the old ones DNA and changes state correctly.
-    fn check_new_mom(old_mom: &KittyData, new_mom: &KittyData) -> Result<(), Self::Error>;
+    fn check_new_mom<T: KittyConfig>(
+        old_mom: &KittyData,
+        new_mom: &KittyData,
+    ) -> Result<(), Self::Error>;
     /// Checks if new dad matches the old ones DNA and changes state correctly.
-    fn check_new_dad(old_dad: &KittyData, new_dad: &KittyData) -> Result<(), Self::Error>;
+    fn check_new_dad<T: KittyConfig>(
+        old_dad: &KittyData,
+        new_dad: &KittyData,
+    ) -> Result<(), Self::Error>;
     /// Checks if new child DNA is formulated correctly and is initialized to the proper state.
     fn check_child(
         new_mom: &KittyData,
         new_dad: &KittyData,
         child: &KittyData,
+        entropy: H256,
     ) -> Result<(), Self::Error>;
 }
 
@@ -304,23 +577,25 @@ impl Breed for KittyHelpers {
     /// Checks:
     ///     - Mom can breed
     ///     - Dad can breed
+    ///     - Mom and Dad are not the same kitty
     ///
-    fn can_breed(mom: &KittyData, dad: &KittyData) -> Result<(), Self::Error> {
-        Self::check_mom_can_breed(mom)?;
-        Self::check_dad_can_breed(dad)?;
+    fn can_breed<T: KittyConfig>(mom: &KittyData, dad: &KittyData) -> Result<(), Self::Error> {
+        Self::check_mom_can_breed::<T>(mom)?;
+        Self::check_dad_can_breed::<T>(dad)?;
+        ensure!(mom.dna != dad.dna, Self::Error::CannotBreedWithSelf);
         Self::check_free_breedings(mom, dad)?;
         Ok(())
     }
 
     /// Checks:
-    ///     - Mom is in `RearinToGo` state
+    ///     - Mom is in `RearinToGo` state, or has rested since she last bred
     ///     - Mom number of breedings is not maxed out
     ///
-    fn check_mom_can_breed(mom: &KittyData) -> Result<(), Self::Error> {
+    fn check_mom_can_breed<T: KittyConfig>(mom: &KittyData) -> Result<(), Self::Error> {
         match &mom.parent {
             Parent::Mom(status) => {
                 if let MomKittyStatus::HadBirthRecently = status {
-                    return Err(Self::Error::MomNotReadyYet);
+                    ensure!(has_rested::<T>(mom), Self::Error::MomNotReadyYet);
                 }
             }
             Parent::Dad(_) => return Err(Self::Error::TwoDadsNotValid),
@@ -332,14 +607,14 @@ impl Breed for KittyHelpers {
     }
 
     /// Checks:
-    ///     - Dad is in `RearinToGo` state
+    ///     - Dad is in `RearinToGo` state, or has rested since he last bred
     ///     - Dad number of breedings is not maxed out
     ///
-    fn check_dad_can_breed(dad: &KittyData) -> Result<(), Self::Error> {
+    fn check_dad_can_breed<T: KittyConfig>(dad: &KittyData) -> Result<(), Self::Error> {
         match &dad.parent {
             Parent::Dad(status) => {
                 if let DadKittyStatus::Tired = status {
-                    return Err(Self::Error::DadTooTired);
+                    ensure!(has_rested::<T>(dad), Self::Error::DadTooTired);
                 }
             }
             Parent::Mom(_) => return Err(Self::Error::TwoMomsNotValid),
@@ -362,19 +637,20 @@ impl Breed for KittyHelpers {
         Ok(())
     }
 
-    fn check_new_family(
+    fn check_new_family<T: KittyConfig>(
         old_mom: &KittyData,
         old_dad: &KittyData,
         new_family: &[DynamicallyTypedData],
+        entropy: H256,
     ) -> Result<(), Self::Error> {
         // Output Side
         ensure!(new_family.len() == 3, Self::Error::NotEnoughFamilyMembers);
         let new_mom = KittyData::try_from(&new_family[0])?;
         let new_dad = KittyData::try_from(&new_family[1])?;
         let child = KittyData::try_from(&new_family[2])?;
-        Self::check_new_mom(old_mom, &new_mom)?;
-        Self::check_new_dad(old_dad, &new_dad)?;
-        Self::check_child(&new_mom, &new_dad, &child)?;
+        Self::check_new_mom::<T>(old_mom, &new_mom)?;
+        Self::check_new_dad::<T>(old_dad, &new_dad)?;
+        Self::check_child(&new_mom, &new_dad, &child, entropy)?;
         Ok(())
     }
 
@@ -383,8 +659,12 @@ impl Breed for KittyHelpers {
     ///     - Mom has 1 less `free_breedings`
     ///     - Mom's DNA matches old Mom
     ///     - Mom's num breedings is incremented
+    ///     - Mom last bred in the current block
     ///
-    fn check_new_mom(old_mom: &KittyData, new_mom: &KittyData) -> Result<(), Self::Error> {
+    fn check_new_mom<T: KittyConfig>(
+        old_mom: &KittyData,
+        new_mom: &KittyData,
+    ) -> Result<(), Self::Error> {
         match &new_mom.parent {
             Parent::Mom(status) => {
                 if let MomKittyStatus::RearinToGo = status {
@@ -406,6 +686,10 @@ impl Breed for KittyHelpers {
             new_mom.dna == old_mom.dna,
             Self::Error::NewParentDnaDoesntMatchOld
         );
+        ensure!(
+            new_mom.last_bred == Some(T::block_height()),
+            Self::Error::NewParentLastBredIncorrect
+        );
 
         Ok(())
     }
@@ -415,8 +699,12 @@ impl Breed for KittyHelpers {
     ///     - Dad has 1 less `free_breedings`
     ///     - Dad's DNA matches old Dad
     ///     - Dad's num breedings is incremented
+    ///     - Dad last bred in the current block
     ///
-    fn check_new_dad(old_dad: &KittyData, new_dad: &KittyData) -> Result<(), Self::Error> {
+    fn check_new_dad<T: KittyConfig>(
+        old_dad: &KittyData,
+        new_dad: &KittyData,
+    ) -> Result<(), Self::Error> {
         match &new_dad.parent {
             Parent::Dad(status) => {
                 if let DadKittyStatus::RearinToGo = status {
@@ -438,39 +726,37 @@ impl Breed for KittyHelpers {
             new_dad.dna == old_dad.dna,
             Self::Error::NewParentDnaDoesntMatchOld
         );
+        ensure!(
+            new_dad.last_bred == Some(T::block_height()),
+            Self::Error::NewParentLastBredIncorrect
+        );
 
         Ok(())
     }
 
     /// Checks:
-    ///     - DNA formation correct -> `hash_of(mom_dna + dad_dna + mom_num_breedings + dad_num_breedings)
+    ///     - DNA formation correct -> `mix_genes(new_mom, new_dad, entropy)`
     ///     - Free breedings is correct given the trait implementation in this case 2
-    ///     - has non-zero bredings
+    ///     - has non-zero bredings, and has never bred
     ///     - If Mom is in RearinToGo
     ///     - If Dad is in RearinToGo
+    ///     - Gender is the one decided by `mix_genes`
     ///
     fn check_child(
         new_mom: &KittyData,
         new_dad: &KittyData,
         child: &KittyData,
+        entropy: H256,
     ) -> Result<(), Self::Error> {
-        let new_dna = BlakeTwo256::hash_of(&(
-            &new_mom.dna,
-            &new_dad.dna,
-            &new_mom.num_breedings,
-            &new_dad.num_breedings,
-        ));
+        let (new_dna, gender) = mix_genes(new_mom, new_dad, entropy);
 
-        ensure!(
-            child.dna == KittyDNA(new_dna),
-            Self::Error::NewChildDnaIncorrect,
-        );
+        ensure!(child.dna == new_dna, Self::Error::NewChildDnaIncorrect);
         ensure!(
             child.free_breedings == Self::NUM_FREE_BREEDINGS,
             Self::Error::NewChildFreeBreedingsIncorrect
         );
         ensure!(
-            child.num_breedings == 0,
+            child.num_breedings == 0 && child.last_bred.is_none(),
             Self::Error::NewChildHasNonZeroBreedings,
         );
 
@@ -486,10 +772,19 @@ impl Breed for KittyHelpers {
                 }
             }
         }
+        ensure!(child.parent == gender, Self::Error::NewChildGenderIncorrect);
         Ok(())
     }
 }
 
+/// Whether a parent that bred has rested for the configured cooldown since, and may breed again.
+/// A tired parent that does not remember when it last bred has not.
+fn has_rested<T: KittyConfig>(kitty: &KittyData) -> bool {
+    kitty
+        .last_bred
+        .is_some_and(|height| T::block_height() >= height.saturating_add(T::BREEDING_COOLDOWN))
+}
+
 impl TryFrom<&DynamicallyTypedData> for KittyData {
     type Error = ConstraintCheckerError;
     fn try_from(a: &DynamicallyTypedData) -> Result<Self, Self::Error> {
@@ -498,7 +793,19 @@ impl TryFrom<&DynamicallyTypedData> for KittyData {
     }
 }
 
-impl SimpleConstraintChecker for FreeKittyConstraintChecker {
+/// Emitted when two kitties breed.
+#[derive(Serialize, Deserialize, Encode, Decode, Debug, PartialEq, Eq, Clone, TypeInfo)]
+pub struct KittyBred {
+    pub mom: KittyDNA,
+    pub dad: KittyDNA,
+    pub child: KittyDNA,
+}
+
+impl TuxedoEvent for KittyBred {
+    const EVENT_ID: [u8; 4] = *b"kbrd";
+}
+
+impl<T: KittyConfig> SimpleConstraintChecker for FreeKittyConstraintChecker<T> {
     type Error = ConstraintCheckerError;
     /// Checks:
     ///     - `input_data` is of length 2
@@ -509,19 +816,309 @@ impl SimpleConstraintChecker for FreeKittyConstraintChecker {
         input_data: &[DynamicallyTypedData],
         _peeks: &[DynamicallyTypedData],
         output_data: &[DynamicallyTypedData],
+        _context: &CheckerContext,
     ) -> Result<TransactionPriority, Self::Error> {
         // Input must be a Mom and a Dad
         ensure!(input_data.len() == 2, Self::Error::TwoParentsDoNotExist);
 
         let mom = KittyData::try_from(&input_data[0])?;
         let dad = KittyData::try_from(&input_data[1])?;
-        KittyHelpers::can_breed(&mom, &dad)?;
+        KittyHelpers::can_breed::<T>(&mom, &dad)?;
 
         // Output must be Mom, Dad, Child
         ensure!(output_data.len() == 3, Self::Error::NotEnoughFamilyMembers);
 
-        KittyHelpers::check_new_family(&mom, &dad, output_data)?;
+        KittyHelpers::check_new_family::<T>(&mom, &dad, output_data, T::block_entropy())?;
+
+        let child = KittyData::try_from(&output_data[2])?;
+        events::deposit(KittyBred {
+            mom: mom.dna,
+            dad: dad.dna,
+            child: child.dna,
+        });
 
         Ok(0)
     }
 }
+
+/// A constraint checker that renames kitties.
+///
+/// Each input is a kitty, and each output is the kitty at the same index with a new name.
+/// Everything else about the kitty must stay the same, so renaming can never be used
+/// to alter its genes, reset its breeding stats, or skip its cooldown.
+#[derive(
+    Serialize, Deserialize, Encode, Decode, Debug, Default, PartialEq, Eq, Clone, TypeInfo,
+)]
+pub struct UpdateKittyName;
+
+impl SimpleConstraintChecker for UpdateKittyName {
+    type Error = ConstraintCheckerError;
+
+    fn check(
+        &self,
+        input_data: &[DynamicallyTypedData],
+        _peeks: &[DynamicallyTypedData],
+        output_data: &[DynamicallyTypedData],
+        _context: &CheckerContext,
+    ) -> Result<TransactionPriority, Self::Error> {
+        ensure!(!input_data.is_empty(), Self::Error::WrongNumberInputs);
+        ensure!(
+            input_data.len() == output_data.len(),
+            Self::Error::WrongNumberOutputs
+        );
+
+        for (input, output) in input_data.iter().zip(output_data) {
+            let original = KittyData::try_from(input)?;
+            let renamed = KittyData::try_from(output)?;
+            ensure!(
+                renamed.parent == original.parent
+                    && renamed.free_breedings == original.free_breedings
+                    && renamed.dna == original.dna
+                    && renamed.num_breedings == original.num_breedings
+                    && renamed.last_bred == original.last_bred,
+                Self::Error::RenamedKittyMismatch
+            );
+        }
+
+        Ok(0)
+    }
+}
+
+/// A kitty that is listed for sale at a fixed price of `Coin<ID>`s.
+#[derive(
+    Serialize,
+    Deserialize,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Clone,
+    Encode,
+    Decode,
+    Hash,
+    Debug,
+    TypeInfo,
+)]
+pub struct KittyListing<V, const ID: u8> {
+    /// The kitty for sale.
+    pub kitty: KittyData,
+    /// The price the buyer must pay.
+    pub price: u128,
+    /// The verifier that the kitty was locked to before it was listed.
+    /// The price is paid to it, and the kitty returns to it if the listing is withdrawn.
+    pub seller: V,
+}
+
+impl<V: Encode + Decode, const ID: u8> UtxoData for KittyListing<V, ID> {
+    const TYPE_ID: [u8; 4] = [b'k', b'l', b's', ID];
+}
+
+/// A constraint checker that lists kitties for sale.
+///
+/// Each input is a kitty, and each output is the listing of the input kitty at the same index.
+/// The listings are locked up for grabs so that any buyer can consume them, and they remember
+/// the kitty's previous verifier as the seller.
+#[derive(
+    Serialize,
+    Deserialize,
+    Encode,
+    Decode,
+    DebugNoBound,
+    DefaultNoBound,
+    PartialEq,
+    Eq,
+    CloneNoBound,
+    TypeInfo,
+)]
+pub struct ListKitty<const ID: u8>;
+
+impl<V: Verifier + PartialEq + From<UpForGrabs>, const ID: u8> tuxedo_core::ConstraintChecker<V>
+    for ListKitty<ID>
+{
+    type Error = ConstraintCheckerError;
+    type InherentHooks = ();
+
+    fn check(
+        &self,
+        inputs: &[Output<V>],
+        _peeks: &[Output<V>],
+        outputs: &[Output<V>],
+        _context: &CheckerContext,
+    ) -> Result<TransactionPriority, Self::Error> {
+        ensure!(!inputs.is_empty(), Self::Error::WrongNumberInputs);
+        ensure!(
+            inputs.len() == outputs.len(),
+            Self::Error::WrongNumberOutputs
+        );
+
+        for (input, output) in inputs.iter().zip(outputs) {
+            let kitty = KittyData::try_from(&input.payload)?;
+            let listing = output
+                .payload
+                .extract::<KittyListing<V, ID>>()
+                .map_err(|_| Self::Error::BadlyTyped)?;
+            ensure!(listing.price > 0, Self::Error::ZeroPrice);
+            ensure!(
+                listing.kitty == kitty && listing.seller == input.verifier,
+                Self::Error::ListingMismatch
+            );
+            ensure!(
+                output.verifier == UpForGrabs.into(),
+                Self::Error::ListingNotUpForGrabs
+            );
+        }
+
+        Ok(0)
+    }
+
+    fn is_inherent(&self) -> bool {
+        false
+    }
+}
+
+/// A constraint checker that withdraws kitty listings.
+///
+/// Each input is a listing, and each output is the listed kitty at the same index, locked to
+/// the seller again. Because listings are up for grabs, anyone may withdraw them, but the
+/// kitties can only ever go back to their sellers.
+#[derive(
+    Serialize,
+    Deserialize,
+    Encode,
+    Decode,
+    DebugNoBound,
+    DefaultNoBound,
+    PartialEq,
+    Eq,
+    CloneNoBound,
+    TypeInfo,
+)]
+pub struct DelistKitty<const ID: u8>;
+
+impl<V: Verifier + PartialEq, const ID: u8> tuxedo_core::ConstraintChecker<V> for DelistKitty<ID> {
+    type Error = ConstraintCheckerError;
+    type InherentHooks = ();
+
+    fn check(
+        &self,
+        inputs: &[Output<V>],
+        _peeks: &[Output<V>],
+        outputs: &[Output<V>],
+        _context: &CheckerContext,
+    ) -> Result<TransactionPriority, Self::Error> {
+        ensure!(!inputs.is_empty(), Self::Error::WrongNumberInputs);
+        ensure!(
+            inputs.len() == outputs.len(),
+            Self::Error::WrongNumberOutputs
+        );
+
+        for (input, output) in inputs.iter().zip(outputs) {
+            let listing = input
+                .payload
+                .extract::<KittyListing<V, ID>>()
+                .map_err(|_| Self::Error::BadlyTyped)?;
+            let kitty = KittyData::try_from(&output.payload)?;
+            ensure!(
+                kitty == listing.kitty && output.verifier == listing.seller,
+                Self::Error::KittyNotReturned
+            );
+        }
+
+        Ok(0)
+    }
+
+    fn is_inherent(&self) -> bool {
+        false
+    }
+}
+
+/// A constraint checker that buys a listed kitty with `Coin<ID>`s.
+///
+/// The first input is the listing and the remaining inputs are the buyer's coins. The first
+/// output is the kitty, locked to whichever verifier the buyer chooses. The second output pays
+/// the listed price to the seller, and the remaining outputs are the buyer's change. The coins
+/// are delegated to the money piece, which checks them as an ordinary spend, so the buyer's
+/// leftover coins pay the transaction's fee and tip.
+#[derive(
+    Serialize,
+    Deserialize,
+    Encode,
+    Decode,
+    DebugNoBound,
+    DefaultNoBound,
+    PartialEq,
+    Eq,
+    CloneNoBound,
+    TypeInfo,
+)]
+pub struct KittySale<const ID: u8>;
+
+impl<const ID: u8> KittySale<ID> {
+    /// The buyer's coins and the outputs they pay for, which are checked as a spend by the
+    /// money piece. Everything but the listing and the kitty.
+    fn payment<V>(inputs: &[Output<V>], outputs: &[Output<V>]) -> Delegation {
+        Delegation::ranges(1..inputs.len(), 0..0, 1..outputs.len())
+    }
+}
+
+impl From<DelegationError<money::ConstraintCheckerError>> for ConstraintCheckerError {
+    fn from(error: DelegationError<money::ConstraintCheckerError>) -> Self {
+        match error {
+            DelegationError::OutOfRange => Self::WrongNumberInputs,
+            DelegationError::Rejected(e) => Self::Payment(e),
+        }
+    }
+}
+
+impl<V: Verifier + PartialEq, const ID: u8> tuxedo_core::ConstraintChecker<V> for KittySale<ID> {
+    type Error = ConstraintCheckerError;
+    type InherentHooks = ();
+
+    fn check(
+        &self,
+        inputs: &[Output<V>],
+        peeks: &[Output<V>],
+        outputs: &[Output<V>],
+        context: &CheckerContext,
+    ) -> Result<TransactionPriority, Self::Error> {
+        let listing = inputs
+            .first()
+            .ok_or(Self::Error::WrongNumberInputs)?
+            .payload
+            .extract::<KittyListing<V, ID>>()
+            .map_err(|_| Self::Error::BadlyTyped)?;
+
+        ensure!(outputs.len() >= 2, Self::Error::WrongNumberOutputs);
+        ensure!(
+            KittyData::try_from(&outputs[0].payload)? == listing.kitty,
+            Self::Error::KittyNotDelivered
+        );
+        ensure!(
+            outputs[1].payload.extract::<Coin<ID>>() == Ok(Coin(listing.price))
+                && outputs[1].verifier == listing.seller,
+            Self::Error::SellerNotPaid
+        );
+
+        // The money piece checks that the buyer's coins cover the price and the change,
+        // and the surplus it reports pays the fee and tip of the whole sale.
+        Self::payment(inputs, outputs)
+            .check(
+                &MoneyConstraintChecker::<ID, ()>::Spend,
+                inputs,
+                peeks,
+                outputs,
+                context,
+            )
+            .map_err(Into::into)
+    }
+
+    fn weight(&self) -> Weight {
+        DEFAULT_CHECKER_WEIGHT.saturating_add(SimpleConstraintChecker::weight(
+            &MoneyConstraintChecker::<ID, ()>::Spend,
+        ))
+    }
+
+    fn is_inherent(&self) -> bool {
+        false
+    }
+}
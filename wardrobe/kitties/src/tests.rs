@@ -9,28 +9,46 @@ impl UtxoData for Bogus {
     const TYPE_ID: [u8; 4] = *b"bogs";
 }
 
+/// The mock config always provides the same block entropy, and says the block number is ten.
+pub struct TestConfig;
+
+impl KittyConfig for TestConfig {
+    fn block_entropy() -> H256 {
+        H256::repeat_byte(7)
+    }
+
+    fn block_height() -> u32 {
+        10
+    }
+
+    const BREEDING_COOLDOWN: u32 = 5;
+}
+
+type FreeKittyConstraintChecker = super::FreeKittyConstraintChecker<TestConfig>;
+
 impl KittyData {
     pub fn default_dad() -> Self {
         KittyData {
             parent: Parent::Dad(DadKittyStatus::RearinToGo),
+            dna: KittyDNA(H256::from_slice(b"dad_tomcat_2qwerqwerqwerqwerqwer")),
             ..Default::default()
         }
     }
 
     pub fn default_child() -> Self {
-        let mom = Self::default();
-        let dad = Self::default_dad();
+        let mut mom = Self::default();
+        mom.num_breedings += 1;
+        let mut dad = Self::default_dad();
+        dad.num_breedings += 1;
+        let (dna, parent) = mix_genes(&mom, &dad, TestConfig::block_entropy());
 
         KittyData {
-            parent: Parent::Mom(MomKittyStatus::RearinToGo),
+            parent,
             free_breedings: 2,
-            dna: KittyDNA(BlakeTwo256::hash_of(&(
-                mom.dna,
-                dad.dna,
-                mom.num_breedings + 1,
-                dad.num_breedings + 1,
-            ))),
+            dna,
             num_breedings: 0,
+            name: *b"kity",
+            last_bred: None,
         }
     }
 
@@ -39,11 +57,13 @@ impl KittyData {
         new_mom.parent = Parent::Mom(MomKittyStatus::HadBirthRecently);
         new_mom.num_breedings += 1;
         new_mom.free_breedings -= 1;
+        new_mom.last_bred = Some(10);
 
         let mut new_dad = KittyData::default_dad();
         new_dad.parent = Parent::Dad(DadKittyStatus::Tired);
         new_dad.num_breedings += 1;
         new_dad.free_breedings -= 1;
+        new_dad.last_bred = Some(10);
 
         let child = KittyData::default_child();
 
@@ -55,7 +75,7 @@ impl KittyData {
 fn breed_happy_path_works() {
     let new_family = KittyData::default_family();
     let result = FreeKittyConstraintChecker::check(
-        &FreeKittyConstraintChecker,
+        &FreeKittyConstraintChecker::default(),
         &[KittyData::default().into(), KittyData::default_dad().into()],
         &[], // no peeks
         &[
@@ -63,17 +83,45 @@ fn breed_happy_path_works() {
             new_family[1].clone().into(),
             new_family[2].clone().into(),
         ],
+        &Default::default(),
     );
     assert!(result.is_ok());
 }
 
+#[test]
+fn breed_emits_event() {
+    let new_family = KittyData::default_family();
+    let (result, emitted) = events::collect(|| {
+        FreeKittyConstraintChecker::check(
+            &FreeKittyConstraintChecker::default(),
+            &[KittyData::default().into(), KittyData::default_dad().into()],
+            &[], // no peeks
+            &[
+                new_family[0].clone().into(),
+                new_family[1].clone().into(),
+                new_family[2].clone().into(),
+            ],
+            &Default::default(),
+        )
+    });
+    assert!(result.is_ok());
+
+    let expected = KittyBred {
+        mom: KittyData::default().dna,
+        dad: KittyData::default_dad().dna,
+        child: KittyData::default_child().dna,
+    };
+    assert_eq!(emitted, vec![expected.into()]);
+}
+
 #[test]
 fn breed_wrong_input_type_fails() {
     let result = FreeKittyConstraintChecker::check(
-        &FreeKittyConstraintChecker,
+        &FreeKittyConstraintChecker::default(),
         &[Bogus.into(), Bogus.into()],
         &[], // no peeks
         &[],
+        &Default::default(),
     );
     assert_eq!(result, Err(ConstraintCheckerError::BadlyTyped));
 }
@@ -81,10 +129,11 @@ fn breed_wrong_input_type_fails() {
 #[test]
 fn breed_wrong_output_type_fails() {
     let result = FreeKittyConstraintChecker::check(
-        &FreeKittyConstraintChecker,
+        &FreeKittyConstraintChecker::default(),
         &[KittyData::default().into(), KittyData::default_dad().into()],
         &[], // no peeks
         &[Bogus.into(), Bogus.into(), Bogus.into()],
+        &Default::default(),
     );
     assert_eq!(result, Err(ConstraintCheckerError::BadlyTyped));
 }
@@ -92,10 +141,11 @@ fn breed_wrong_output_type_fails() {
 #[test]
 fn inputs_dont_contain_two_parents_fails() {
     let result = FreeKittyConstraintChecker::check(
-        &FreeKittyConstraintChecker,
+        &FreeKittyConstraintChecker::default(),
         &[KittyData::default().into()],
         &[], // no peeks
         &[],
+        &Default::default(),
     );
     assert_eq!(result, Err(ConstraintCheckerError::TwoParentsDoNotExist));
 }
@@ -103,10 +153,11 @@ fn inputs_dont_contain_two_parents_fails() {
 #[test]
 fn outputs_dont_contain_all_family_members_fails() {
     let result = FreeKittyConstraintChecker::check(
-        &FreeKittyConstraintChecker,
+        &FreeKittyConstraintChecker::default(),
         &[KittyData::default().into(), KittyData::default_dad().into()],
         &[], // no peeks
         &[KittyData::default().into()],
+        &Default::default(),
     );
     assert_eq!(result, Err(ConstraintCheckerError::NotEnoughFamilyMembers));
 }
@@ -114,13 +165,14 @@ fn outputs_dont_contain_all_family_members_fails() {
 #[test]
 fn breed_two_dads_fails() {
     let result = FreeKittyConstraintChecker::check(
-        &FreeKittyConstraintChecker,
+        &FreeKittyConstraintChecker::default(),
         &[
             KittyData::default_dad().into(),
             KittyData::default_dad().into(),
         ],
         &[], // no peeks
         &[KittyData::default().into()],
+        &Default::default(),
     );
     assert_eq!(result, Err(ConstraintCheckerError::TwoDadsNotValid));
 }
@@ -128,10 +180,11 @@ fn breed_two_dads_fails() {
 #[test]
 fn breed_two_moms_fails() {
     let result = FreeKittyConstraintChecker::check(
-        &FreeKittyConstraintChecker,
+        &FreeKittyConstraintChecker::default(),
         &[KittyData::default().into(), KittyData::default().into()],
         &[], // no peeks
         &[KittyData::default().into()],
+        &Default::default(),
     );
     assert_eq!(result, Err(ConstraintCheckerError::TwoMomsNotValid));
 }
@@ -139,10 +192,11 @@ fn breed_two_moms_fails() {
 #[test]
 fn first_input_not_mom_fails() {
     let result = FreeKittyConstraintChecker::check(
-        &FreeKittyConstraintChecker,
+        &FreeKittyConstraintChecker::default(),
         &[KittyData::default_dad().into(), KittyData::default().into()],
         &[], // no peeks
         &[],
+        &Default::default(),
     );
     assert_eq!(result, Err(ConstraintCheckerError::TwoDadsNotValid))
 }
@@ -150,7 +204,7 @@ fn first_input_not_mom_fails() {
 #[test]
 fn first_output_not_mom_fails() {
     let result = FreeKittyConstraintChecker::check(
-        &FreeKittyConstraintChecker,
+        &FreeKittyConstraintChecker::default(),
         &[KittyData::default().into(), KittyData::default_dad().into()],
         &[], // no peeks
         &[
@@ -158,6 +212,7 @@ fn first_output_not_mom_fails() {
             KittyData::default().into(),
             KittyData::default_child().into(),
         ],
+        &Default::default(),
     );
     assert_eq!(result, Err(ConstraintCheckerError::TwoDadsNotValid));
 }
@@ -168,10 +223,11 @@ fn breed_mom_when_she_gave_birth_recently_fails() {
     new_momma.parent = Parent::Mom(MomKittyStatus::HadBirthRecently);
 
     let result = FreeKittyConstraintChecker::check(
-        &FreeKittyConstraintChecker,
+        &FreeKittyConstraintChecker::default(),
         &[new_momma.into(), KittyData::default_dad().into()],
         &[], // no peeks
         &[],
+        &Default::default(),
     );
     assert_eq!(result, Err(ConstraintCheckerError::MomNotReadyYet));
 }
@@ -182,24 +238,107 @@ fn breed_dad_when_he_is_tired_fails() {
     tired_dadda.parent = Parent::Dad(DadKittyStatus::Tired);
 
     let result = FreeKittyConstraintChecker::check(
-        &FreeKittyConstraintChecker,
+        &FreeKittyConstraintChecker::default(),
+        &[KittyData::default().into(), tired_dadda.into()],
+        &[], // no peeks
+        &[],
+        &Default::default(),
+    );
+    assert_eq!(result, Err(ConstraintCheckerError::DadTooTired));
+}
+
+#[test]
+fn breed_mom_during_cooldown_fails() {
+    let mut new_momma = KittyData::default();
+    new_momma.parent = Parent::Mom(MomKittyStatus::HadBirthRecently);
+    new_momma.last_bred = Some(6);
+
+    let result = FreeKittyConstraintChecker::check(
+        &FreeKittyConstraintChecker::default(),
+        &[new_momma.into(), KittyData::default_dad().into()],
+        &[], // no peeks
+        &[],
+        &Default::default(),
+    );
+    assert_eq!(result, Err(ConstraintCheckerError::MomNotReadyYet));
+}
+
+#[test]
+fn breed_dad_during_cooldown_fails() {
+    let mut tired_dadda = KittyData::default_dad();
+    tired_dadda.parent = Parent::Dad(DadKittyStatus::Tired);
+    tired_dadda.last_bred = Some(6);
+
+    let result = FreeKittyConstraintChecker::check(
+        &FreeKittyConstraintChecker::default(),
         &[KittyData::default().into(), tired_dadda.into()],
         &[], // no peeks
         &[],
+        &Default::default(),
     );
     assert_eq!(result, Err(ConstraintCheckerError::DadTooTired));
 }
 
+#[test]
+fn breed_rested_parents_works() {
+    let mut mom = KittyData::default();
+    mom.parent = Parent::Mom(MomKittyStatus::HadBirthRecently);
+    mom.last_bred = Some(5);
+    let mut dad = KittyData::default_dad();
+    dad.parent = Parent::Dad(DadKittyStatus::Tired);
+    dad.last_bred = Some(5);
+
+    let mut new_family = KittyData::default_family();
+    new_family[0].last_bred = Some(10);
+    new_family[1].last_bred = Some(10);
+
+    let result = FreeKittyConstraintChecker::check(
+        &FreeKittyConstraintChecker::default(),
+        &[mom.into(), dad.into()],
+        &[], // no peeks
+        &[
+            new_family[0].clone().into(),
+            new_family[1].clone().into(),
+            new_family[2].clone().into(),
+        ],
+        &Default::default(),
+    );
+    assert!(result.is_ok());
+}
+
+#[test]
+fn new_parent_forgetting_breeding_height_fails() {
+    let mut new_family = KittyData::default_family();
+    new_family[0].last_bred = Some(9);
+
+    let result = FreeKittyConstraintChecker::check(
+        &FreeKittyConstraintChecker::default(),
+        &[KittyData::default().into(), KittyData::default_dad().into()],
+        &[], // no peeks
+        &[
+            new_family[0].clone().into(),
+            new_family[1].clone().into(),
+            new_family[2].clone().into(),
+        ],
+        &Default::default(),
+    );
+    assert_eq!(
+        result,
+        Err(ConstraintCheckerError::NewParentLastBredIncorrect)
+    );
+}
+
 #[test]
 fn check_mom_breedings_overflow_fails() {
     let mut test_mom = KittyData::default();
     test_mom.num_breedings = u128::MAX;
 
     let result = FreeKittyConstraintChecker::check(
-        &FreeKittyConstraintChecker,
+        &FreeKittyConstraintChecker::default(),
         &[test_mom.into(), KittyData::default_dad().into()],
         &[], // no peeks
         &[],
+        &Default::default(),
     );
     assert_eq!(
         result,
@@ -213,10 +352,11 @@ fn check_dad_breedings_overflow_fails() {
     test_dad.num_breedings = u128::MAX;
 
     let result = FreeKittyConstraintChecker::check(
-        &FreeKittyConstraintChecker,
+        &FreeKittyConstraintChecker::default(),
         &[KittyData::default().into(), test_dad.into()],
         &[], // no peeks
         &[],
+        &Default::default(),
     );
     assert_eq!(
         result,
@@ -230,10 +370,11 @@ fn check_mom_free_breedings_zero_fails() {
     test_mom.free_breedings = 0;
 
     let result = FreeKittyConstraintChecker::check(
-        &FreeKittyConstraintChecker,
+        &FreeKittyConstraintChecker::default(),
         &[test_mom.into(), KittyData::default_dad().into()],
         &[], // no peeks
         &[],
+        &Default::default(),
     );
     assert_eq!(result, Err(ConstraintCheckerError::NotEnoughFreeBreedings));
 }
@@ -244,10 +385,11 @@ fn check_dad_free_breedings_zero_fails() {
     test_dad.free_breedings = 0;
 
     let result = FreeKittyConstraintChecker::check(
-        &FreeKittyConstraintChecker,
+        &FreeKittyConstraintChecker::default(),
         &[KittyData::default().into(), test_dad.into()],
         &[], // no peeks
         &[],
+        &Default::default(),
     );
     assert_eq!(result, Err(ConstraintCheckerError::NotEnoughFreeBreedings));
 }
@@ -259,7 +401,7 @@ fn check_new_mom_free_breedings_incorrect_fails() {
     new_mom.free_breedings = 2;
 
     let result = FreeKittyConstraintChecker::check(
-        &FreeKittyConstraintChecker,
+        &FreeKittyConstraintChecker::default(),
         &[KittyData::default().into(), KittyData::default_dad().into()],
         &[], // no peeks
         &[
@@ -267,6 +409,7 @@ fn check_new_mom_free_breedings_incorrect_fails() {
             new_family[1].clone().into(),
             new_family[2].clone().into(),
         ],
+        &Default::default(),
     );
     assert_eq!(
         result,
@@ -281,7 +424,7 @@ fn check_new_dad_free_breedings_incorrect_fails() {
     new_dad.free_breedings = 2;
 
     let result = FreeKittyConstraintChecker::check(
-        &FreeKittyConstraintChecker,
+        &FreeKittyConstraintChecker::default(),
         &[KittyData::default().into(), KittyData::default_dad().into()],
         &[], // no peeks
         &[
@@ -289,6 +432,7 @@ fn check_new_dad_free_breedings_incorrect_fails() {
             new_dad.into(),
             new_family[2].clone().into(),
         ],
+        &Default::default(),
     );
     assert_eq!(
         result,
@@ -303,7 +447,7 @@ fn check_new_mom_num_breedings_incorrect_fails() {
     new_mom.num_breedings = 0;
 
     let result = FreeKittyConstraintChecker::check(
-        &FreeKittyConstraintChecker,
+        &FreeKittyConstraintChecker::default(),
         &[KittyData::default().into(), KittyData::default_dad().into()],
         &[], // no peeks
         &[
@@ -311,6 +455,7 @@ fn check_new_mom_num_breedings_incorrect_fails() {
             new_family[1].clone().into(),
             new_family[2].clone().into(),
         ],
+        &Default::default(),
     );
     assert_eq!(
         result,
@@ -325,7 +470,7 @@ fn check_new_dad_num_breedings_incorrect_fails() {
     new_dad.num_breedings = 0;
 
     let result = FreeKittyConstraintChecker::check(
-        &FreeKittyConstraintChecker,
+        &FreeKittyConstraintChecker::default(),
         &[KittyData::default().into(), KittyData::default_dad().into()],
         &[], // no peeks
         &[
@@ -333,6 +478,7 @@ fn check_new_dad_num_breedings_incorrect_fails() {
             new_dad.into(),
             new_family[2].clone().into(),
         ],
+        &Default::default(),
     );
     assert_eq!(
         result,
@@ -347,7 +493,7 @@ fn check_new_mom_dna_doesnt_match_old_fails() {
     new_mom.dna = KittyDNA(H256::from_slice(b"superkalifragislisticexpialadoci"));
 
     let result = FreeKittyConstraintChecker::check(
-        &FreeKittyConstraintChecker,
+        &FreeKittyConstraintChecker::default(),
         &[KittyData::default().into(), KittyData::default_dad().into()],
         &[], // no peeks
         &[
@@ -355,6 +501,7 @@ fn check_new_mom_dna_doesnt_match_old_fails() {
             new_family[1].clone().into(),
             new_family[2].clone().into(),
         ],
+        &Default::default(),
     );
     assert_eq!(
         result,
@@ -369,7 +516,7 @@ fn check_new_dad_dna_doesnt_match_old_fails() {
     new_dad.dna = KittyDNA(H256::from_slice(b"superkalifragislisticexpialadoci"));
 
     let result = FreeKittyConstraintChecker::check(
-        &FreeKittyConstraintChecker,
+        &FreeKittyConstraintChecker::default(),
         &[KittyData::default().into(), KittyData::default_dad().into()],
         &[], // no peeks
         &[
@@ -377,6 +524,7 @@ fn check_new_dad_dna_doesnt_match_old_fails() {
             new_dad.into(),
             new_family[2].clone().into(),
         ],
+        &Default::default(),
     );
     assert_eq!(
         result,
@@ -391,7 +539,7 @@ fn check_child_dna_incorrect_fails() {
     new_child.dna = KittyDNA(H256::zero());
 
     let result = FreeKittyConstraintChecker::check(
-        &FreeKittyConstraintChecker,
+        &FreeKittyConstraintChecker::default(),
         &[KittyData::default().into(), KittyData::default_dad().into()],
         &[], // no peeks
         &[
@@ -399,6 +547,7 @@ fn check_child_dna_incorrect_fails() {
             new_family[1].clone().into(),
             new_child.into(),
         ],
+        &Default::default(),
     );
     assert_eq!(result, Err(ConstraintCheckerError::NewChildDnaIncorrect));
 }
@@ -410,7 +559,7 @@ fn check_child_dad_parent_tired_fails() {
     new_child.parent = Parent::Dad(DadKittyStatus::Tired);
 
     let result = FreeKittyConstraintChecker::check(
-        &FreeKittyConstraintChecker,
+        &FreeKittyConstraintChecker::default(),
         &[KittyData::default().into(), KittyData::default_dad().into()],
         &[], // no peeks
         &[
@@ -418,6 +567,7 @@ fn check_child_dad_parent_tired_fails() {
             new_family[1].clone().into(),
             new_child.into(),
         ],
+        &Default::default(),
     );
     assert_eq!(
         result,
@@ -432,7 +582,7 @@ fn check_child_mom_parent_recently_gave_birth_fails() {
     new_child.parent = Parent::Mom(MomKittyStatus::HadBirthRecently);
 
     let result = FreeKittyConstraintChecker::check(
-        &FreeKittyConstraintChecker,
+        &FreeKittyConstraintChecker::default(),
         &[KittyData::default().into(), KittyData::default_dad().into()],
         &[], // no peeks
         &[
@@ -440,6 +590,7 @@ fn check_child_mom_parent_recently_gave_birth_fails() {
             new_family[1].clone().into(),
             new_child.into(),
         ],
+        &Default::default(),
     );
     assert_eq!(
         result,
@@ -454,7 +605,7 @@ fn check_child_free_breedings_incorrect_fails() {
     new_child.free_breedings = KittyHelpers::NUM_FREE_BREEDINGS + 1;
 
     let result = FreeKittyConstraintChecker::check(
-        &FreeKittyConstraintChecker,
+        &FreeKittyConstraintChecker::default(),
         &[KittyData::default().into(), KittyData::default_dad().into()],
         &[], // no peeks
         &[
@@ -462,6 +613,7 @@ fn check_child_free_breedings_incorrect_fails() {
             new_family[1].clone().into(),
             new_child.into(),
         ],
+        &Default::default(),
     );
     assert_eq!(
         result,
@@ -476,7 +628,7 @@ fn check_child_num_breedings_non_zero_fails() {
     new_child.num_breedings = 42;
 
     let result = FreeKittyConstraintChecker::check(
-        &FreeKittyConstraintChecker,
+        &FreeKittyConstraintChecker::default(),
         &[KittyData::default().into(), KittyData::default_dad().into()],
         &[], // no peeks
         &[
@@ -484,9 +636,528 @@ fn check_child_num_breedings_non_zero_fails() {
             new_family[1].clone().into(),
             new_child.into(),
         ],
+        &Default::default(),
     );
     assert_eq!(
         result,
         Err(ConstraintCheckerError::NewChildHasNonZeroBreedings)
     );
 }
+
+#[test]
+fn breed_with_self_fails() {
+    let mut clone = KittyData::default();
+    clone.parent = Parent::dad();
+
+    let result = FreeKittyConstraintChecker::check(
+        &FreeKittyConstraintChecker::default(),
+        &[KittyData::default().into(), clone.into()],
+        &[], // no peeks
+        &[],
+        &Default::default(),
+    );
+    assert_eq!(result, Err(ConstraintCheckerError::CannotBreedWithSelf));
+}
+
+#[test]
+fn check_child_gender_incorrect_fails() {
+    let new_family = KittyData::default_family();
+    let mut new_child = new_family[2].clone();
+    new_child.parent = match new_child.parent {
+        Parent::Mom(_) => Parent::dad(),
+        Parent::Dad(_) => Parent::mom(),
+    };
+
+    let result = FreeKittyConstraintChecker::check(
+        &FreeKittyConstraintChecker::default(),
+        &[KittyData::default().into(), KittyData::default_dad().into()],
+        &[], // no peeks
+        &[
+            new_family[0].clone().into(),
+            new_family[1].clone().into(),
+            new_child.into(),
+        ],
+        &Default::default(),
+    );
+    assert_eq!(result, Err(ConstraintCheckerError::NewChildGenderIncorrect));
+}
+
+/// The number of the child's genes that came from neither parent.
+fn mutations(mom: &KittyData, dad: &KittyData, child: &KittyDNA) -> usize {
+    (0..32)
+        .filter(|&i| {
+            let gene = child.0.as_bytes()[i];
+            gene != mom.dna.0.as_bytes()[i] && gene != dad.dna.0.as_bytes()[i]
+        })
+        .count()
+}
+
+#[test]
+fn child_inherits_every_gene_but_one_from_a_parent() {
+    let mom = KittyData::default();
+    let dad = KittyData::default_dad();
+
+    for byte in 0..=255 {
+        let (dna, _) = mix_genes(&mom, &dad, H256::repeat_byte(byte));
+        assert!(mutations(&mom, &dad, &dna) <= 1);
+    }
+}
+
+#[test]
+fn some_children_mutate() {
+    let mom = KittyData::default();
+    let dad = KittyData::default_dad();
+
+    let mutated = (0..=255)
+        .filter(|&byte| {
+            let (dna, _) = mix_genes(&mom, &dad, H256::repeat_byte(byte));
+            mutations(&mom, &dad, &dna) == 1
+        })
+        .count();
+
+    // Roughly one in sixteen children should mutate, but not all of them.
+    assert!(mutated > 0);
+    assert!(mutated < 64);
+}
+
+#[test]
+fn mutation_is_deterministic() {
+    let mom = KittyData::default();
+    let dad = KittyData::default_dad();
+
+    for byte in 0..=255 {
+        let entropy = H256::repeat_byte(byte);
+        assert_eq!(
+            mix_genes(&mom, &dad, entropy),
+            mix_genes(&mom, &dad, entropy)
+        );
+    }
+}
+
+#[test]
+fn block_entropy_changes_child() {
+    let mom = KittyData::default();
+    let dad = KittyData::default_dad();
+
+    assert_ne!(
+        mix_genes(&mom, &dad, H256::zero()),
+        mix_genes(&mom, &dad, H256::repeat_byte(1)),
+    );
+}
+
+fn dna_with_genes(fur: u8, eyes: u8, rarity: u8) -> KittyDNA {
+    let mut genes = [0u8; 32];
+    genes[0] = fur;
+    genes[1] = eyes;
+    genes[2] = rarity;
+    KittyDNA(H256::from(genes))
+}
+
+#[test]
+fn traits_decode_from_dna() {
+    assert_eq!(
+        dna_with_genes(0, 0, 0).traits(),
+        KittyTraits {
+            fur: Fur::Tabby,
+            eyes: EyeColor::Green,
+            rarity: Rarity::Common,
+        }
+    );
+    assert_eq!(
+        dna_with_genes(0x40, 128, 200).traits(),
+        KittyTraits {
+            fur: Fur::Tuxedo,
+            eyes: EyeColor::Amber,
+            rarity: Rarity::Uncommon,
+        }
+    );
+    assert_eq!(
+        dna_with_genes(255, 255, 255).traits(),
+        KittyTraits {
+            fur: Fur::Sphynx,
+            eyes: EyeColor::Odd,
+            rarity: Rarity::Legendary,
+        }
+    );
+}
+
+#[test]
+fn rarity_tiers_have_the_right_bounds() {
+    assert_eq!(dna_with_genes(0, 0, 191).traits().rarity, Rarity::Common);
+    assert_eq!(dna_with_genes(0, 0, 192).traits().rarity, Rarity::Uncommon);
+    assert_eq!(dna_with_genes(0, 0, 239).traits().rarity, Rarity::Uncommon);
+    assert_eq!(dna_with_genes(0, 0, 240).traits().rarity, Rarity::Rare);
+    assert_eq!(dna_with_genes(0, 0, 254).traits().rarity, Rarity::Rare);
+    assert_eq!(dna_with_genes(0, 0, 255).traits().rarity, Rarity::Legendary);
+}
+
+#[test]
+fn traits_only_depend_on_their_genes() {
+    let mut genes = [0xAB; 32];
+    let dna = KittyDNA(H256::from(genes));
+    genes[3..].fill(0);
+
+    assert_eq!(dna.traits(), KittyDNA(H256::from(genes)).traits());
+}
+
+fn renamed(kitty: &KittyData, name: &[u8; 4]) -> KittyData {
+    KittyData {
+        name: *name,
+        ..kitty.clone()
+    }
+}
+
+#[test]
+fn rename_kitty_works() {
+    let kitty = KittyData::default();
+    let result = UpdateKittyName.check(
+        &[kitty.clone().into()],
+        &[],
+        &[renamed(&kitty, b"tom_").into()],
+        &Default::default(),
+    );
+    assert_eq!(result, Ok(0));
+}
+
+#[test]
+fn rename_many_kitties_works() {
+    let mom = KittyData::default();
+    let dad = KittyData::default_dad();
+    let result = UpdateKittyName.check(
+        &[mom.clone().into(), dad.clone().into()],
+        &[],
+        &[renamed(&mom, b"mimi").into(), renamed(&dad, b"tom_").into()],
+        &Default::default(),
+    );
+    assert_eq!(result, Ok(0));
+}
+
+#[test]
+fn rename_no_kitties_fails() {
+    let result = UpdateKittyName.check(&[], &[], &[], &Default::default());
+    assert_eq!(result, Err(ConstraintCheckerError::WrongNumberInputs));
+}
+
+#[test]
+fn rename_dropping_kitty_fails() {
+    let kitty = KittyData::default();
+    let result = UpdateKittyName.check(&[kitty.into()], &[], &[], &Default::default());
+    assert_eq!(result, Err(ConstraintCheckerError::WrongNumberOutputs));
+}
+
+#[test]
+fn rename_changing_dna_fails() {
+    let kitty = KittyData::default();
+    let mut new_kitty = renamed(&kitty, b"tom_");
+    new_kitty.dna = KittyData::default_dad().dna;
+    let result = UpdateKittyName.check(
+        &[kitty.into()],
+        &[],
+        &[new_kitty.into()],
+        &Default::default(),
+    );
+    assert_eq!(result, Err(ConstraintCheckerError::RenamedKittyMismatch));
+}
+
+#[test]
+fn rename_resetting_breedings_fails() {
+    let kitty = KittyData::default();
+    let mut new_kitty = renamed(&kitty, b"tom_");
+    new_kitty.free_breedings += 1;
+    new_kitty.num_breedings = 0;
+    let result = UpdateKittyName.check(
+        &[kitty.into()],
+        &[],
+        &[new_kitty.into()],
+        &Default::default(),
+    );
+    assert_eq!(result, Err(ConstraintCheckerError::RenamedKittyMismatch));
+}
+
+#[test]
+fn rename_changing_parent_status_fails() {
+    let kitty = KittyData::default();
+    let mut new_kitty = renamed(&kitty, b"tom_");
+    new_kitty.parent = Parent::Mom(MomKittyStatus::HadBirthRecently);
+    let result = UpdateKittyName.check(
+        &[kitty.into()],
+        &[],
+        &[new_kitty.into()],
+        &Default::default(),
+    );
+    assert_eq!(result, Err(ConstraintCheckerError::RenamedKittyMismatch));
+}
+
+#[test]
+fn rename_skipping_cooldown_fails() {
+    let mut kitty = KittyData::default();
+    kitty.last_bred = Some(8);
+    let mut new_kitty = renamed(&kitty, b"tom_");
+    new_kitty.last_bred = None;
+    let result = UpdateKittyName.check(
+        &[kitty.into()],
+        &[],
+        &[new_kitty.into()],
+        &Default::default(),
+    );
+    assert_eq!(result, Err(ConstraintCheckerError::RenamedKittyMismatch));
+}
+
+#[test]
+fn rename_wrong_type_fails() {
+    let kitty = KittyData::default();
+    let result = UpdateKittyName.check(&[kitty.into()], &[], &[Bogus.into()], &Default::default());
+    assert_eq!(result, Err(ConstraintCheckerError::BadlyTyped));
+}
+
+/// A verifier that can represent kitty owners and listings.
+#[derive(Serialize, Deserialize, Encode, Decode, Debug, PartialEq, Eq, Clone, TypeInfo)]
+#[tuxedo_core::tuxedo_verifier]
+pub enum TestVerifier {
+    SigCheck(tuxedo_core::verifier::SigCheck),
+    UpForGrabs(UpForGrabs),
+}
+
+fn owner(byte: u8) -> TestVerifier {
+    tuxedo_core::verifier::SigCheck::new(H256::repeat_byte(byte)).into()
+}
+
+fn seller() -> TestVerifier {
+    owner(1)
+}
+
+fn buyer() -> TestVerifier {
+    owner(2)
+}
+
+fn listing(price: u128) -> KittyListing<TestVerifier, 0> {
+    KittyListing {
+        kitty: KittyData::default(),
+        price,
+        seller: seller(),
+    }
+}
+
+fn coin(value: u128, verifier: TestVerifier) -> Output<TestVerifier> {
+    (Coin::<0>(value), verifier).into()
+}
+
+fn check<C: tuxedo_core::ConstraintChecker<TestVerifier>>(
+    checker: &C,
+    inputs: Vec<Output<TestVerifier>>,
+    outputs: Vec<Output<TestVerifier>>,
+) -> Result<TransactionPriority, C::Error> {
+    checker.check(&inputs, &[], &outputs, &Default::default())
+}
+
+#[test]
+fn list_kitty_works() {
+    let result = check(
+        &ListKitty::<0>,
+        vec![(KittyData::default(), seller()).into()],
+        vec![(listing(10), UpForGrabs).into()],
+    );
+    assert_eq!(result, Ok(0));
+}
+
+#[test]
+fn list_kitty_at_zero_price_fails() {
+    let result = check(
+        &ListKitty::<0>,
+        vec![(KittyData::default(), seller()).into()],
+        vec![(listing(0), UpForGrabs).into()],
+    );
+    assert_eq!(result, Err(ConstraintCheckerError::ZeroPrice));
+}
+
+#[test]
+fn list_kitty_paying_someone_else_fails() {
+    let mut listing = listing(10);
+    listing.seller = buyer();
+
+    let result = check(
+        &ListKitty::<0>,
+        vec![(KittyData::default(), seller()).into()],
+        vec![(listing, UpForGrabs).into()],
+    );
+    assert_eq!(result, Err(ConstraintCheckerError::ListingMismatch));
+}
+
+#[test]
+fn list_different_kitty_fails() {
+    let result = check(
+        &ListKitty::<0>,
+        vec![(KittyData::default_dad(), seller()).into()],
+        vec![(listing(10), UpForGrabs).into()],
+    );
+    assert_eq!(result, Err(ConstraintCheckerError::ListingMismatch));
+}
+
+#[test]
+fn list_kitty_locked_away_fails() {
+    let result = check(
+        &ListKitty::<0>,
+        vec![(KittyData::default(), seller()).into()],
+        vec![(listing(10), seller()).into()],
+    );
+    assert_eq!(result, Err(ConstraintCheckerError::ListingNotUpForGrabs));
+}
+
+#[test]
+fn list_kitty_missing_listing_fails() {
+    let result = check(
+        &ListKitty::<0>,
+        vec![(KittyData::default(), seller()).into()],
+        vec![],
+    );
+    assert_eq!(result, Err(ConstraintCheckerError::WrongNumberOutputs));
+}
+
+#[test]
+fn delist_kitty_works() {
+    let result = check(
+        &DelistKitty::<0>,
+        vec![(listing(10), UpForGrabs).into()],
+        vec![(KittyData::default(), seller()).into()],
+    );
+    assert_eq!(result, Ok(0));
+}
+
+#[test]
+fn delist_kitty_to_someone_else_fails() {
+    let result = check(
+        &DelistKitty::<0>,
+        vec![(listing(10), UpForGrabs).into()],
+        vec![(KittyData::default(), buyer()).into()],
+    );
+    assert_eq!(result, Err(ConstraintCheckerError::KittyNotReturned));
+}
+
+#[test]
+fn buy_kitty_works() {
+    let result = check(
+        &KittySale::<0>,
+        vec![
+            (listing(10), UpForGrabs).into(),
+            coin(8, buyer()),
+            coin(7, buyer()),
+        ],
+        vec![
+            (KittyData::default(), buyer()).into(),
+            coin(10, seller()),
+            coin(4, buyer()),
+        ],
+    );
+    assert_eq!(result, Ok(1));
+}
+
+#[test]
+fn buy_kitty_underpaying_fails() {
+    let result = check(
+        &KittySale::<0>,
+        vec![(listing(10), UpForGrabs).into(), coin(15, buyer())],
+        vec![(KittyData::default(), buyer()).into(), coin(9, seller())],
+    );
+    assert_eq!(result, Err(ConstraintCheckerError::SellerNotPaid));
+}
+
+#[test]
+fn buy_kitty_paying_someone_else_fails() {
+    let result = check(
+        &KittySale::<0>,
+        vec![(listing(10), UpForGrabs).into(), coin(15, buyer())],
+        vec![(KittyData::default(), buyer()).into(), coin(10, buyer())],
+    );
+    assert_eq!(result, Err(ConstraintCheckerError::SellerNotPaid));
+}
+
+#[test]
+fn buy_kitty_without_enough_coins_fails() {
+    let result = check(
+        &KittySale::<0>,
+        vec![(listing(10), UpForGrabs).into(), coin(5, buyer())],
+        vec![(KittyData::default(), buyer()).into(), coin(10, seller())],
+    );
+    assert_eq!(
+        result,
+        Err(ConstraintCheckerError::Payment(
+            money::ConstraintCheckerError::OutputsExceedInputs
+        ))
+    );
+}
+
+#[test]
+fn buy_kitty_with_too_much_change_fails() {
+    let result = check(
+        &KittySale::<0>,
+        vec![(listing(10), UpForGrabs).into(), coin(15, buyer())],
+        vec![
+            (KittyData::default(), buyer()).into(),
+            coin(10, seller()),
+            coin(6, buyer()),
+        ],
+    );
+    assert_eq!(
+        result,
+        Err(ConstraintCheckerError::Payment(
+            money::ConstraintCheckerError::OutputsExceedInputs
+        ))
+    );
+}
+
+#[test]
+fn buy_different_kitty_fails() {
+    let result = check(
+        &KittySale::<0>,
+        vec![(listing(10), UpForGrabs).into(), coin(15, buyer())],
+        vec![
+            (KittyData::default_dad(), buyer()).into(),
+            coin(10, seller()),
+        ],
+    );
+    assert_eq!(result, Err(ConstraintCheckerError::KittyNotDelivered));
+}
+
+#[test]
+fn buy_kitty_without_listing_fails() {
+    let result = check(
+        &KittySale::<0>,
+        vec![coin(15, buyer())],
+        vec![(KittyData::default(), buyer()).into(), coin(10, seller())],
+    );
+    assert_eq!(result, Err(ConstraintCheckerError::BadlyTyped));
+}
+
+#[test]
+fn buy_kitty_with_bogus_payment_fails() {
+    let result = check(
+        &KittySale::<0>,
+        vec![(listing(10), UpForGrabs).into(), (Bogus, buyer()).into()],
+        vec![(KittyData::default(), buyer()).into(), coin(10, seller())],
+    );
+    assert_eq!(
+        result,
+        Err(ConstraintCheckerError::Payment(
+            money::ConstraintCheckerError::BadlyTyped
+        ))
+    );
+}
+
+#[test]
+fn buy_kitty_with_zero_value_change_fails() {
+    let result = check(
+        &KittySale::<0>,
+        vec![(listing(10), UpForGrabs).into(), coin(15, buyer())],
+        vec![
+            (KittyData::default(), buyer()).into(),
+            coin(10, seller()),
+            coin(0, buyer()),
+        ],
+    );
+    assert_eq!(
+        result,
+        Err(ConstraintCheckerError::Payment(
+            money::ConstraintCheckerError::ZeroValueCoin
+        ))
+    );
+}
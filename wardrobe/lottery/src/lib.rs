@@ -0,0 +1,508 @@
+//! Lotteries that pay their pot in `Coin<ID>` to a ticket drawn with the randomness beacon.
+//!
+//! Lotteries run in consecutive [`Round`]s. Players buy [`Ticket`]s for the current round with
+//! coins, which are added to the round's pot. Once the round closes at its configured height,
+//! anyone may draw it. The winning ticket is selected with the randomness revealed by the
+//! randomness beacon in the closing block, which nobody could know while tickets were on sale.
+//! Drawing pays the whole pot to the holder of the winning ticket and opens the next round.
+//!
+//! The first round is expected to be placed in the genesis block. Each draw creates the next
+//! round, so there is only ever one open round per lottery.
+//!
+//! Rounds and tickets are protected by the `UpForGrabs` verifier so that anyone can drive the
+//! lottery forward. The constraint checkers make sure that the pot always ends up with the
+//! holder of the winning ticket. Losing tickets can be discarded by anyone once their round has
+//! been drawn.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use core::marker::PhantomData;
+
+use money::Coin;
+use parity_scale_codec::{Decode, Encode};
+use randomness_beacon::Beacon;
+use scale_info::TypeInfo;
+use serde::{Deserialize, Serialize};
+use sp_core::H256;
+use sp_runtime::transaction_validity::TransactionPriority;
+use sp_std::prelude::*;
+use tuxedo_core::{
+    dynamic_typing::{DynamicallyTypedData, UtxoData},
+    ensure,
+    support_macros::{CloneNoBound, DebugNoBound, DefaultNoBound},
+    types::Output,
+    verifier::{SigCheck, UpForGrabs},
+    CheckerContext, ConstraintChecker, SimpleConstraintChecker, Verifier,
+};
+
+#[cfg(test)]
+mod tests;
+
+/// Configuration items for the lottery piece when it is
+/// instantiated in a concrete runtime.
+pub trait LotteryConfig {
+    /// A means of getting the current block height.
+    /// Probably this will be the Tuxedo Executive
+    fn block_height() -> u32;
+
+    /// The price of a single ticket.
+    const TICKET_PRICE: u128 = 10;
+
+    /// The number of blocks between the closing of one round and the closing of the next.
+    ///
+    /// Default is roughly one day with 6 second block times.
+    const ROUND_LENGTH: u32 = 14_400;
+}
+
+/// A single round of the lottery.
+#[derive(
+    Serialize,
+    Deserialize,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Clone,
+    Encode,
+    Decode,
+    Hash,
+    Debug,
+    TypeInfo,
+)]
+pub struct Round<const ID: u8> {
+    /// The sequence number of this round. Tickets refer to it.
+    pub number: u32,
+    /// The block height at which ticket sales stop and the round may be drawn.
+    pub close_height: u32,
+    /// The number of tickets sold so far. They are numbered from zero.
+    pub tickets_sold: u32,
+    /// The value that will be paid to the winner.
+    pub pot: u128,
+}
+
+impl<const ID: u8> Round<ID> {
+    pub fn new(number: u32, close_height: u32) -> Self {
+        Self {
+            number,
+            close_height,
+            tickets_sold: 0,
+            pot: 0,
+        }
+    }
+
+    /// The round that follows this one.
+    pub fn next<T: LotteryConfig>(&self) -> Self {
+        Self::new(
+            self.number.saturating_add(1),
+            self.close_height.saturating_add(T::ROUND_LENGTH),
+        )
+    }
+
+    /// The number of the ticket that wins this round, given the beacon from the closing block.
+    ///
+    /// Returns `None` if no tickets were sold.
+    pub fn winning_ticket(&self, beacon: &Beacon) -> Option<u32> {
+        if self.tickets_sold == 0 {
+            return None;
+        }
+        let randomness = beacon.derive(&(b"lottery", ID, self.number).encode());
+        let mut bytes = [0u8; 8];
+        bytes.copy_from_slice(&randomness.as_bytes()[..8]);
+        let index = u64::from_le_bytes(bytes) % u64::from(self.tickets_sold);
+        Some(index as u32)
+    }
+}
+
+impl<const ID: u8> UtxoData for Round<ID> {
+    const TYPE_ID: [u8; 4] = [b'l', b'o', b't', ID];
+}
+
+/// A ticket in a lottery round.
+#[derive(
+    Serialize,
+    Deserialize,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Clone,
+    Encode,
+    Decode,
+    Hash,
+    Debug,
+    TypeInfo,
+)]
+pub struct Ticket<const ID: u8> {
+    /// The number of the round that this ticket was bought in.
+    pub round: u32,
+    /// The number of this ticket within its round.
+    pub number: u32,
+    /// The key that receives the pot if this ticket wins.
+    pub holder: H256,
+}
+
+impl<const ID: u8> UtxoData for Ticket<ID> {
+    const TYPE_ID: [u8; 4] = [b't', b'k', b't', ID];
+}
+
+/// Errors that can occur when checking lottery transactions.
+#[derive(
+    Serialize,
+    Deserialize,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Clone,
+    Encode,
+    Decode,
+    Hash,
+    Debug,
+    TypeInfo,
+)]
+pub enum ConstraintCheckerError {
+    /// An input data has the wrong type.
+    BadlyTypedInput,
+    /// An output data has the wrong type.
+    BadlyTypedOutput,
+    /// The peeked data is not a randomness beacon or a round.
+    BadlyTypedPeek,
+    /// Wrong number of inputs were provided to the constraint checker.
+    WrongNumberInputs,
+    /// Wrong number of outputs were provided to the constraint checker.
+    WrongNumberOutputs,
+    /// Wrong number of peeks were provided to the constraint checker.
+    WrongNumberPeeks,
+    /// A coin with zero value was created. This is not allowed because it wastes state space.
+    ZeroValue,
+    /// The value consumed or created by this transaction overflows the value type.
+    ValueOverflow,
+    /// More value was created than was consumed.
+    OutputsExceedInputs,
+    /// A round or ticket was locked to a verifier other than `UpForGrabs`.
+    NotUpForGrabs,
+    /// No tickets were bought.
+    NoTickets,
+    /// The round has closed, so no more tickets may be bought.
+    RoundClosed,
+    /// The round has not closed yet, so it can not be drawn.
+    RoundNotClosed,
+    /// The round was changed in some way other than recording the sold tickets.
+    RoundModified,
+    /// A ticket does not belong to the round or is not numbered in sequence.
+    TicketMismatch,
+    /// The peeked beacon is not from the block in which the round closed.
+    WrongBeacon,
+    /// The ticket offered as the winner is not the one that was drawn.
+    NotTheWinner,
+    /// The next round is not the one that follows the drawn round.
+    WrongNextRound,
+    /// The pot was not paid in full to the holder of the winning ticket.
+    PaidToWrongVerifier,
+    /// The ticket's round has not been drawn yet.
+    RoundNotDrawn,
+}
+
+/// Extract a round from an input.
+fn extract_round<V, const ID: u8>(
+    input: Option<&Output<V>>,
+) -> Result<Round<ID>, ConstraintCheckerError> {
+    input
+        .ok_or(ConstraintCheckerError::WrongNumberInputs)?
+        .payload
+        .extract::<Round<ID>>()
+        .map_err(|_| ConstraintCheckerError::BadlyTypedInput)
+}
+
+/// A constraint checker that buys tickets in the current round.
+///
+/// The first input is the round and the remaining inputs are the player's `Coin<ID>`s. The first
+/// output is the round with the tickets recorded. It is followed by one or more tickets, numbered
+/// in sequence, and optionally `Coin<ID>` change. The player pays the transaction's fee and tip
+/// with whatever coins are left over after the ticket price and change, so it never enters the pot.
+#[derive(
+    Serialize,
+    Deserialize,
+    Encode,
+    Decode,
+    DebugNoBound,
+    DefaultNoBound,
+    PartialEq,
+    Eq,
+    CloneNoBound,
+    TypeInfo,
+)]
+#[scale_info(skip_type_params(T))]
+pub struct BuyTickets<T, const ID: u8>(PhantomData<T>);
+
+impl<T: LotteryConfig, V: Verifier + PartialEq + From<UpForGrabs>, const ID: u8>
+    ConstraintChecker<V> for BuyTickets<T, ID>
+{
+    type Error = ConstraintCheckerError;
+    type InherentHooks = ();
+
+    fn check(
+        &self,
+        inputs: &[Output<V>],
+        _peeks: &[Output<V>],
+        outputs: &[Output<V>],
+        _context: &CheckerContext,
+    ) -> Result<TransactionPriority, Self::Error> {
+        let old = extract_round::<V, ID>(inputs.first())?;
+        ensure!(
+            T::block_height() < old.close_height,
+            ConstraintCheckerError::RoundClosed
+        );
+        let new = outputs
+            .first()
+            .ok_or(ConstraintCheckerError::WrongNumberOutputs)?
+            .payload
+            .extract::<Round<ID>>()
+            .map_err(|_| ConstraintCheckerError::BadlyTypedOutput)?;
+        ensure!(
+            outputs[0].verifier == UpForGrabs.into(),
+            ConstraintCheckerError::NotUpForGrabs
+        );
+
+        // The tickets come right after the round, and the change after the tickets.
+        let mut tickets_sold = old.tickets_sold;
+        let mut change_outputs = &outputs[1..];
+        while let Some((output, rest)) = change_outputs.split_first() {
+            let Ok(ticket) = output.payload.extract::<Ticket<ID>>() else {
+                break;
+            };
+            ensure!(
+                ticket.round == old.number && ticket.number == tickets_sold,
+                ConstraintCheckerError::TicketMismatch
+            );
+            ensure!(
+                output.verifier == UpForGrabs.into(),
+                ConstraintCheckerError::NotUpForGrabs
+            );
+            tickets_sold = tickets_sold
+                .checked_add(1)
+                .ok_or(ConstraintCheckerError::ValueOverflow)?;
+            change_outputs = rest;
+        }
+        let bought = tickets_sold - old.tickets_sold;
+        ensure!(bought > 0, ConstraintCheckerError::NoTickets);
+
+        let cost = T::TICKET_PRICE
+            .checked_mul(bought.into())
+            .ok_or(ConstraintCheckerError::ValueOverflow)?;
+        let pot = old
+            .pot
+            .checked_add(cost)
+            .ok_or(ConstraintCheckerError::ValueOverflow)?;
+        ensure!(
+            new == Round {
+                tickets_sold,
+                pot,
+                ..old
+            },
+            ConstraintCheckerError::RoundModified
+        );
+
+        let mut total_input: u128 = 0;
+        for input in &inputs[1..] {
+            let coin = input
+                .payload
+                .extract::<Coin<ID>>()
+                .map_err(|_| ConstraintCheckerError::BadlyTypedInput)?;
+            total_input = total_input
+                .checked_add(coin.0)
+                .ok_or(ConstraintCheckerError::ValueOverflow)?;
+        }
+        let mut total_output = cost;
+        for output in change_outputs {
+            let coin = output
+                .payload
+                .extract::<Coin<ID>>()
+                .map_err(|_| ConstraintCheckerError::BadlyTypedOutput)?;
+            ensure!(coin.0 > 0, ConstraintCheckerError::ZeroValue);
+            total_output = total_output
+                .checked_add(coin.0)
+                .ok_or(ConstraintCheckerError::ValueOverflow)?;
+        }
+        ensure!(
+            total_output <= total_input,
+            ConstraintCheckerError::OutputsExceedInputs
+        );
+
+        let burned = total_input - total_output;
+        Ok(burned.try_into().unwrap_or(TransactionPriority::MAX))
+    }
+
+    fn is_inherent(&self) -> bool {
+        false
+    }
+}
+
+/// A constraint checker that draws a closed round and opens the next one.
+///
+/// The only peek is the randomness beacon from the block in which the round closed. The first
+/// input is the round and the first output is the next round. If any tickets were sold, the
+/// second input is the winning ticket, and the second output pays the pot to its holder. Any
+/// remaining inputs are losing tickets from the same round, which are discarded.
+#[derive(
+    Serialize,
+    Deserialize,
+    Encode,
+    Decode,
+    DebugNoBound,
+    DefaultNoBound,
+    PartialEq,
+    Eq,
+    CloneNoBound,
+    TypeInfo,
+)]
+#[scale_info(skip_type_params(T))]
+pub struct Draw<T, const ID: u8>(PhantomData<T>);
+
+impl<
+        T: LotteryConfig,
+        V: Verifier + PartialEq + From<UpForGrabs> + From<SigCheck>,
+        const ID: u8,
+    > ConstraintChecker<V> for Draw<T, ID>
+{
+    type Error = ConstraintCheckerError;
+    type InherentHooks = ();
+
+    fn check(
+        &self,
+        inputs: &[Output<V>],
+        peeks: &[Output<V>],
+        outputs: &[Output<V>],
+        _context: &CheckerContext,
+    ) -> Result<TransactionPriority, Self::Error> {
+        let round = extract_round::<V, ID>(inputs.first())?;
+        ensure!(
+            T::block_height() >= round.close_height,
+            ConstraintCheckerError::RoundNotClosed
+        );
+        ensure!(peeks.len() == 1, ConstraintCheckerError::WrongNumberPeeks);
+        let beacon = peeks[0]
+            .payload
+            .extract::<Beacon>()
+            .map_err(|_| ConstraintCheckerError::BadlyTypedPeek)?;
+        ensure!(
+            beacon.block == round.close_height,
+            ConstraintCheckerError::WrongBeacon
+        );
+
+        let next = outputs
+            .first()
+            .ok_or(ConstraintCheckerError::WrongNumberOutputs)?
+            .payload
+            .extract::<Round<ID>>()
+            .map_err(|_| ConstraintCheckerError::BadlyTypedOutput)?;
+        ensure!(
+            next == round.next::<T>(),
+            ConstraintCheckerError::WrongNextRound
+        );
+        ensure!(
+            outputs[0].verifier == UpForGrabs.into(),
+            ConstraintCheckerError::NotUpForGrabs
+        );
+
+        let Some(winning_number) = round.winning_ticket(&beacon) else {
+            ensure!(inputs.len() == 1, ConstraintCheckerError::WrongNumberInputs);
+            ensure!(
+                outputs.len() == 1,
+                ConstraintCheckerError::WrongNumberOutputs
+            );
+            return Ok(0);
+        };
+
+        // Check the winning ticket and pay the pot to its holder
+        ensure!(inputs.len() >= 2, ConstraintCheckerError::WrongNumberInputs);
+        ensure!(
+            outputs.len() == 2,
+            ConstraintCheckerError::WrongNumberOutputs
+        );
+        let winner = inputs[1]
+            .payload
+            .extract::<Ticket<ID>>()
+            .map_err(|_| ConstraintCheckerError::BadlyTypedInput)?;
+        ensure!(
+            winner.round == round.number,
+            ConstraintCheckerError::TicketMismatch
+        );
+        ensure!(
+            winner.number == winning_number,
+            ConstraintCheckerError::NotTheWinner
+        );
+        let payout = outputs[1]
+            .payload
+            .extract::<Coin<ID>>()
+            .map_err(|_| ConstraintCheckerError::BadlyTypedOutput)?;
+        ensure!(
+            payout.0 == round.pot && outputs[1].verifier == SigCheck::new(winner.holder).into(),
+            ConstraintCheckerError::PaidToWrongVerifier
+        );
+
+        // Discard the losing tickets
+        for input in &inputs[2..] {
+            let ticket = input
+                .payload
+                .extract::<Ticket<ID>>()
+                .map_err(|_| ConstraintCheckerError::BadlyTypedInput)?;
+            ensure!(
+                ticket.round == round.number,
+                ConstraintCheckerError::TicketMismatch
+            );
+        }
+
+        Ok(0)
+    }
+
+    fn is_inherent(&self) -> bool {
+        false
+    }
+}
+
+/// A constraint checker that discards losing tickets from rounds that have been drawn.
+///
+/// The only peek is a later round of the lottery, which proves that the tickets' rounds are over.
+/// All inputs are tickets and there are no outputs.
+#[derive(
+    Serialize, Deserialize, Encode, Decode, Debug, Default, PartialEq, Eq, Clone, TypeInfo,
+)]
+pub struct DiscardTickets<const ID: u8>;
+
+impl<const ID: u8> SimpleConstraintChecker for DiscardTickets<ID> {
+    type Error = ConstraintCheckerError;
+
+    fn check(
+        &self,
+        input_data: &[DynamicallyTypedData],
+        peeks: &[DynamicallyTypedData],
+        output_data: &[DynamicallyTypedData],
+        _context: &CheckerContext,
+    ) -> Result<TransactionPriority, Self::Error> {
+        ensure!(
+            !input_data.is_empty(),
+            ConstraintCheckerError::WrongNumberInputs
+        );
+        ensure!(
+            output_data.is_empty(),
+            ConstraintCheckerError::WrongNumberOutputs
+        );
+        ensure!(peeks.len() == 1, ConstraintCheckerError::WrongNumberPeeks);
+        let current = peeks[0]
+            .extract::<Round<ID>>()
+            .map_err(|_| ConstraintCheckerError::BadlyTypedPeek)?;
+
+        for input in input_data {
+            let ticket = input
+                .extract::<Ticket<ID>>()
+                .map_err(|_| ConstraintCheckerError::BadlyTypedInput)?;
+            ensure!(
+                ticket.round < current.number,
+                ConstraintCheckerError::RoundNotDrawn
+            );
+        }
+
+        Ok(0)
+    }
+}
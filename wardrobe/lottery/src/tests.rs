@@ -0,0 +1,368 @@
+//! Unit tests for the Lottery piece
+
+use super::*;
+use tuxedo_core::{dynamic_typing::testing::Bogus, tuxedo_verifier};
+
+/// A verifier that can represent the lottery as well as the players.
+#[derive(Serialize, Deserialize, Encode, Decode, Debug, PartialEq, Eq, Clone, TypeInfo)]
+#[tuxedo_verifier]
+pub enum TestVerifier {
+    SigCheck(SigCheck),
+    UpForGrabs(UpForGrabs),
+}
+
+/// A mock config that always reports the same block height.
+/// Tickets cost 10 and rounds close every 100 blocks.
+pub struct AtHeight<const H: u32>;
+
+impl<const H: u32> LotteryConfig for AtHeight<H> {
+    fn block_height() -> u32 {
+        H
+    }
+
+    const TICKET_PRICE: u128 = 10;
+    const ROUND_LENGTH: u32 = 100;
+}
+
+fn player(byte: u8) -> H256 {
+    H256::repeat_byte(byte)
+}
+
+fn up_for_grabs<T: UtxoData>(data: T) -> Output<TestVerifier> {
+    (data, UpForGrabs.into()).into()
+}
+
+fn owned_by<T: UtxoData>(data: T, owner: H256) -> Output<TestVerifier> {
+    (data, SigCheck::new(owner).into()).into()
+}
+
+fn ticket(round: u32, number: u32, holder: H256) -> Ticket<0> {
+    Ticket {
+        round,
+        number,
+        holder,
+    }
+}
+
+/// The third round, closing at block 50, with the given number of tickets sold.
+fn round(tickets_sold: u32) -> Round<0> {
+    Round {
+        number: 3,
+        close_height: 50,
+        tickets_sold,
+        pot: u128::from(tickets_sold) * 10,
+    }
+}
+
+fn beacon() -> Beacon {
+    Beacon::new(H256::repeat_byte(7), 50)
+}
+
+fn buy<const H: u32>(
+    inputs: &[Output<TestVerifier>],
+    outputs: &[Output<TestVerifier>],
+) -> Result<TransactionPriority, ConstraintCheckerError> {
+    BuyTickets::<AtHeight<H>, 0>::default().check(inputs, &[], outputs, &Default::default())
+}
+
+#[test]
+fn buy_tickets_works() {
+    let inputs = vec![up_for_grabs(round(2)), owned_by(Coin::<0>(25), player(1))];
+    let outputs = vec![
+        up_for_grabs(round(4)),
+        up_for_grabs(ticket(3, 2, player(1))),
+        up_for_grabs(ticket(3, 3, player(1))),
+        owned_by(Coin::<0>(4), player(1)),
+    ];
+
+    assert_eq!(buy::<49>(&inputs, &outputs), Ok(1));
+}
+
+#[test]
+fn buy_tickets_underpaid_fails() {
+    let inputs = vec![up_for_grabs(round(2)), owned_by(Coin::<0>(15), player(1))];
+    let outputs = vec![
+        up_for_grabs(round(4)),
+        up_for_grabs(ticket(3, 2, player(1))),
+        up_for_grabs(ticket(3, 3, player(1))),
+    ];
+
+    assert_eq!(
+        buy::<49>(&inputs, &outputs),
+        Err(ConstraintCheckerError::OutputsExceedInputs)
+    );
+}
+
+#[test]
+fn buy_tickets_after_close_fails() {
+    let inputs = vec![up_for_grabs(round(2)), owned_by(Coin::<0>(10), player(1))];
+    let outputs = vec![
+        up_for_grabs(round(3)),
+        up_for_grabs(ticket(3, 2, player(1))),
+    ];
+
+    assert_eq!(
+        buy::<50>(&inputs, &outputs),
+        Err(ConstraintCheckerError::RoundClosed)
+    );
+}
+
+#[test]
+fn buy_tickets_out_of_sequence_fails() {
+    let inputs = vec![up_for_grabs(round(2)), owned_by(Coin::<0>(10), player(1))];
+    let outputs = vec![
+        up_for_grabs(round(3)),
+        up_for_grabs(ticket(3, 0, player(1))),
+    ];
+
+    assert_eq!(
+        buy::<49>(&inputs, &outputs),
+        Err(ConstraintCheckerError::TicketMismatch)
+    );
+}
+
+#[test]
+fn buy_tickets_without_updating_pot_fails() {
+    let inputs = vec![up_for_grabs(round(2)), owned_by(Coin::<0>(10), player(1))];
+    let outputs = vec![
+        up_for_grabs(Round {
+            pot: 20,
+            ..round(3)
+        }),
+        up_for_grabs(ticket(3, 2, player(1))),
+    ];
+
+    assert_eq!(
+        buy::<49>(&inputs, &outputs),
+        Err(ConstraintCheckerError::RoundModified)
+    );
+}
+
+#[test]
+fn buy_no_tickets_fails() {
+    let inputs = vec![up_for_grabs(round(2)), owned_by(Coin::<0>(10), player(1))];
+    let outputs = vec![up_for_grabs(round(2)), owned_by(Coin::<0>(10), player(1))];
+
+    assert_eq!(
+        buy::<49>(&inputs, &outputs),
+        Err(ConstraintCheckerError::NoTickets)
+    );
+}
+
+#[test]
+fn buy_ticket_not_up_for_grabs_fails() {
+    let inputs = vec![up_for_grabs(round(2)), owned_by(Coin::<0>(10), player(1))];
+    let outputs = vec![
+        up_for_grabs(round(3)),
+        owned_by(ticket(3, 2, player(1)), player(1)),
+    ];
+
+    assert_eq!(
+        buy::<49>(&inputs, &outputs),
+        Err(ConstraintCheckerError::NotUpForGrabs)
+    );
+}
+
+#[test]
+fn winning_ticket_is_in_range() {
+    for tickets_sold in 1..20 {
+        let winner = round(tickets_sold).winning_ticket(&beacon()).unwrap();
+        assert!(winner < tickets_sold);
+    }
+    assert_eq!(round(0).winning_ticket(&beacon()), None);
+}
+
+#[test]
+fn draw_works() {
+    let winner = round(5).winning_ticket(&beacon()).unwrap();
+    let loser = (winner + 1) % 5;
+    let inputs = vec![
+        up_for_grabs(round(5)),
+        up_for_grabs(ticket(3, winner, player(1))),
+        up_for_grabs(ticket(3, loser, player(2))),
+    ];
+    let outputs = vec![
+        up_for_grabs(Round::<0>::new(4, 150)),
+        owned_by(Coin::<0>(50), player(1)),
+    ];
+
+    assert_eq!(
+        Draw::<AtHeight<60>, 0>::default().check(
+            &inputs,
+            &[up_for_grabs(beacon())],
+            &outputs,
+            &Default::default()
+        ),
+        Ok(0)
+    );
+}
+
+#[test]
+fn draw_without_tickets_opens_next_round() {
+    let inputs = vec![up_for_grabs(round(0))];
+    let outputs = vec![up_for_grabs(Round::<0>::new(4, 150))];
+
+    assert_eq!(
+        Draw::<AtHeight<60>, 0>::default().check(
+            &inputs,
+            &[up_for_grabs(beacon())],
+            &outputs,
+            &Default::default()
+        ),
+        Ok(0)
+    );
+}
+
+#[test]
+fn draw_wrong_ticket_fails() {
+    let winner = round(5).winning_ticket(&beacon()).unwrap();
+    let loser = (winner + 1) % 5;
+    let inputs = vec![
+        up_for_grabs(round(5)),
+        up_for_grabs(ticket(3, loser, player(2))),
+    ];
+    let outputs = vec![
+        up_for_grabs(Round::<0>::new(4, 150)),
+        owned_by(Coin::<0>(50), player(2)),
+    ];
+
+    assert_eq!(
+        Draw::<AtHeight<60>, 0>::default().check(
+            &inputs,
+            &[up_for_grabs(beacon())],
+            &outputs,
+            &Default::default()
+        ),
+        Err(ConstraintCheckerError::NotTheWinner)
+    );
+}
+
+#[test]
+fn draw_paying_someone_else_fails() {
+    let winner = round(5).winning_ticket(&beacon()).unwrap();
+    let inputs = vec![
+        up_for_grabs(round(5)),
+        up_for_grabs(ticket(3, winner, player(1))),
+    ];
+    let outputs = vec![
+        up_for_grabs(Round::<0>::new(4, 150)),
+        owned_by(Coin::<0>(50), player(2)),
+    ];
+
+    assert_eq!(
+        Draw::<AtHeight<60>, 0>::default().check(
+            &inputs,
+            &[up_for_grabs(beacon())],
+            &outputs,
+            &Default::default()
+        ),
+        Err(ConstraintCheckerError::PaidToWrongVerifier)
+    );
+}
+
+#[test]
+fn draw_before_close_fails() {
+    let inputs = vec![up_for_grabs(round(0))];
+    let outputs = vec![up_for_grabs(Round::<0>::new(4, 150))];
+
+    assert_eq!(
+        Draw::<AtHeight<49>, 0>::default().check(
+            &inputs,
+            &[up_for_grabs(beacon())],
+            &outputs,
+            &Default::default()
+        ),
+        Err(ConstraintCheckerError::RoundNotClosed)
+    );
+}
+
+#[test]
+fn draw_with_later_beacon_fails() {
+    let inputs = vec![up_for_grabs(round(0))];
+    let outputs = vec![up_for_grabs(Round::<0>::new(4, 150))];
+
+    assert_eq!(
+        Draw::<AtHeight<60>, 0>::default().check(
+            &inputs,
+            &[up_for_grabs(Beacon::new(H256::repeat_byte(7), 51))],
+            &outputs,
+            &Default::default()
+        ),
+        Err(ConstraintCheckerError::WrongBeacon)
+    );
+}
+
+#[test]
+fn draw_wrong_next_round_fails() {
+    let inputs = vec![up_for_grabs(round(0))];
+    let outputs = vec![up_for_grabs(Round::<0>::new(4, 51))];
+
+    assert_eq!(
+        Draw::<AtHeight<60>, 0>::default().check(
+            &inputs,
+            &[up_for_grabs(beacon())],
+            &outputs,
+            &Default::default()
+        ),
+        Err(ConstraintCheckerError::WrongNextRound)
+    );
+}
+
+#[test]
+fn draw_peeking_something_else_fails() {
+    let inputs = vec![up_for_grabs(round(0))];
+    let outputs = vec![up_for_grabs(Round::<0>::new(4, 150))];
+
+    assert_eq!(
+        Draw::<AtHeight<60>, 0>::default().check(
+            &inputs,
+            &[up_for_grabs(Bogus)],
+            &outputs,
+            &Default::default()
+        ),
+        Err(ConstraintCheckerError::BadlyTypedPeek)
+    );
+}
+
+fn discard(
+    input_data: &[DynamicallyTypedData],
+    current: Round<0>,
+) -> Result<TransactionPriority, ConstraintCheckerError> {
+    SimpleConstraintChecker::check(
+        &DiscardTickets::<0>,
+        input_data,
+        &[current.into()],
+        &[],
+        &Default::default(),
+    )
+}
+
+#[test]
+fn discard_tickets_works() {
+    let input_data = vec![
+        ticket(2, 0, player(1)).into(),
+        ticket(3, 4, player(2)).into(),
+    ];
+
+    assert_eq!(discard(&input_data, Round::new(4, 150)), Ok(0));
+}
+
+#[test]
+fn discard_tickets_of_open_round_fails() {
+    let input_data = vec![ticket(3, 4, player(2)).into()];
+
+    assert_eq!(
+        discard(&input_data, round(5)),
+        Err(ConstraintCheckerError::RoundNotDrawn)
+    );
+}
+
+#[test]
+fn discard_something_else_fails() {
+    let input_data = vec![Bogus.into()];
+
+    assert_eq!(
+        discard(&input_data, Round::new(4, 150)),
+        Err(ConstraintCheckerError::BadlyTypedInput)
+    );
+}
@@ -0,0 +1,114 @@
+//! Worst case transactions for benchmarking the money piece's constraint checkers.
+
+use super::*;
+use tuxedo_core::{
+    benchmarking::{bench_checker, BenchmarkResult, CheckerCase},
+    types::Output,
+    verifier::UpForGrabs,
+};
+
+/// The number of coins that the benchmarked transactions consume and create.
+/// Transactions with more coins than this are heavier than their measured weight.
+pub const MAX_COINS: usize = 100;
+
+/// Coins that are just big enough to not be dust.
+fn coins<const ID: u8, T: MoneyConfig>(count: usize) -> Vec<Output<UpForGrabs>> {
+    let value = T::MINIMUM_COIN_VALUE.max(1);
+    (0..count)
+        .map(|_| (Coin::<ID>(value), UpForGrabs).into())
+        .collect()
+}
+
+/// The issuance accumulator ahead of the given coins, if the runtime tracks issuance.
+fn with_issuance<const ID: u8, T: MoneyConfig>(
+    total: u128,
+    coins: Vec<Output<UpForGrabs>>,
+) -> Vec<Output<UpForGrabs>> {
+    let issuance = T::TRACK_ISSUANCE.then(|| (Issuance::<ID>(total), UpForGrabs).into());
+    issuance.into_iter().chain(coins).collect()
+}
+
+/// The total issuance in storage, if the runtime tracks issuance.
+fn issuance_storage<const ID: u8, T: MoneyConfig>(total: u128) -> Vec<(Vec<u8>, Vec<u8>)> {
+    T::TRACK_ISSUANCE
+        .then(|| (asset_key(ISSUANCE_KEY, ID), total.encode()))
+        .into_iter()
+        .collect()
+}
+
+/// The total value of the benchmarked coins.
+fn minted<T: MoneyConfig>() -> u128 {
+    T::MINIMUM_COIN_VALUE.max(1) * MAX_COINS as u128
+}
+
+/// Spend the most coins into the most coins.
+pub fn spend<const ID: u8, T: MoneyConfig>(
+) -> CheckerCase<UpForGrabs, MoneyConstraintChecker<ID, T>> {
+    CheckerCase {
+        name: "money_spend",
+        checker: MoneyConstraintChecker::Spend,
+        inputs: coins::<ID, T>(MAX_COINS),
+        peeks: Vec::new(),
+        outputs: coins::<ID, T>(MAX_COINS),
+        context: CheckerContext::default(),
+        storage: Vec::new(),
+    }
+}
+
+/// Mint the most coins.
+pub fn mint<const ID: u8, T: MoneyConfig>() -> CheckerCase<UpForGrabs, MoneyConstraintChecker<ID, T>>
+{
+    CheckerCase {
+        name: "money_mint",
+        checker: MoneyConstraintChecker::Mint,
+        inputs: with_issuance::<ID, T>(0, Vec::new()),
+        peeks: Vec::new(),
+        outputs: with_issuance::<ID, T>(minted::<T>(), coins::<ID, T>(MAX_COINS)),
+        context: CheckerContext::default(),
+        storage: issuance_storage::<ID, T>(0),
+    }
+}
+
+/// Burn the most coins.
+pub fn burn<const ID: u8, T: MoneyConfig>() -> CheckerCase<UpForGrabs, MoneyConstraintChecker<ID, T>>
+{
+    CheckerCase {
+        name: "money_burn",
+        checker: MoneyConstraintChecker::Burn,
+        inputs: with_issuance::<ID, T>(minted::<T>(), coins::<ID, T>(MAX_COINS)),
+        peeks: Vec::new(),
+        outputs: with_issuance::<ID, T>(0, Vec::new()),
+        context: CheckerContext::default(),
+        storage: issuance_storage::<ID, T>(minted::<T>()),
+    }
+}
+
+/// Measure all of the money piece's constraint checkers.
+pub fn bench<const ID: u8, T: MoneyConfig>(repeats: u32) -> Vec<BenchmarkResult> {
+    vec![
+        bench_checker(&spend::<ID, T>(), repeats),
+        bench_checker(&mint::<ID, T>(), repeats),
+        bench_checker(&burn::<ID, T>(), repeats),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn worst_cases_pass() {
+        assert_eq!(bench::<0, ()>(1).len(), 3);
+    }
+
+    struct Tracked;
+
+    impl MoneyConfig for Tracked {
+        const TRACK_ISSUANCE: bool = true;
+    }
+
+    #[test]
+    fn tracked_worst_cases_pass() {
+        assert_eq!(bench::<0, Tracked>(1).len(), 3);
+    }
+}
@@ -0,0 +1,80 @@
+//! The money piece's section of the genesis configuration.
+
+use crate::{asset_key, Coin, Issuance, MoneyConfig, MoneyConstraintChecker, ISSUANCE_KEY};
+use parity_scale_codec::Encode;
+use serde::{Deserialize, Serialize};
+use sp_std::marker::PhantomData;
+use tuxedo_core::{
+    genesis::GenesisSection,
+    support_macros::DefaultNoBound,
+    types::{Transaction, TransactionVersion},
+    verifier::UpForGrabs,
+    ConstraintChecker, Verifier,
+};
+
+/// The coins that exist at genesis. Each coin is minted in its own transaction.
+/// When the runtime tracks issuance, one more transaction creates the accumulator with their total,
+/// and the total is written to storage.
+#[derive(Serialize, Deserialize, DefaultNoBound)]
+pub struct MoneyGenesisConfig<const ID: u8, V, T = ()> {
+    /// The value of each initial coin, along with the verifier that protects it.
+    pub coins: Vec<(u128, V)>,
+    #[serde(skip)]
+    _config: PhantomData<T>,
+}
+
+impl<const ID: u8, V, T> MoneyGenesisConfig<ID, V, T> {
+    /// Create a genesis section with the given initial coins.
+    pub fn new(coins: Vec<(u128, V)>) -> Self {
+        Self {
+            coins,
+            _config: PhantomData,
+        }
+    }
+
+    /// The total value of the initial coins.
+    ///
+    /// Panics if it overflows, because such a genesis configuration is broken.
+    fn total(&self) -> u128 {
+        self.coins
+            .iter()
+            .try_fold(0u128, |total, (amount, _)| total.checked_add(*amount))
+            .expect("The total value of the genesis coins overflows.")
+    }
+}
+
+impl<const ID: u8, V, C, T> GenesisSection<V, C> for MoneyGenesisConfig<ID, V, T>
+where
+    V: Verifier + Clone + From<UpForGrabs>,
+    C: ConstraintChecker<V> + From<MoneyConstraintChecker<ID, T>>,
+    T: MoneyConfig,
+{
+    fn genesis_transactions(&self) -> Vec<Transaction<V, C>> {
+        let mut transactions: Vec<_> = self
+            .coins
+            .iter()
+            .map(|(amount, verifier)| Coin::<ID>::mint::<V, V, C, T>(*amount, verifier.clone()))
+            .collect();
+
+        if T::TRACK_ISSUANCE {
+            transactions.push(Transaction {
+                version: TransactionVersion::LATEST,
+                inputs: vec![],
+                peeks: vec![],
+                outputs: vec![(Issuance::<ID>(self.total()), UpForGrabs).into()],
+                checker: MoneyConstraintChecker::Mint.into(),
+                memo: None,
+                valid_until: None,
+            });
+        }
+
+        transactions
+    }
+
+    fn genesis_storage(&self) -> Vec<(Vec<u8>, Vec<u8>)> {
+        if !T::TRACK_ISSUANCE {
+            return Vec::new();
+        }
+        vec![(asset_key(ISSUANCE_KEY, ID), self.total().encode())]
+    }
+}
@@ -6,18 +6,26 @@ use parity_scale_codec::{Decode, Encode};
 use scale_info::TypeInfo;
 use serde::{Deserialize, Serialize};
 use sp_runtime::transaction_validity::TransactionPriority;
-use sp_std::prelude::*;
+use sp_std::{marker::PhantomData, prelude::*};
 use tuxedo_core::{
     dynamic_typing::{DynamicallyTypedData, UtxoData},
     ensure,
+    events::{self, TuxedoEvent},
+    support_macros::{CloneNoBound, DebugNoBound},
     traits::Cash,
-    types::Transaction,
-    SimpleConstraintChecker, Verifier,
+    types::{Transaction, TransactionVersion},
+    CheckerContext, SimpleConstraintChecker, Verifier,
 };
 
 #[cfg(test)]
 mod tests;
 
+#[cfg(feature = "runtime-benchmarks")]
+pub mod benchmarking;
+
+#[cfg(feature = "std")]
+pub mod genesis;
+
 impl<const ID: u8> Cash for Coin<ID> {
     fn value(&self) -> u128 {
         self.0
@@ -28,30 +36,90 @@ impl<const ID: u8> Cash for Coin<ID> {
 
 // use log::info;
 
-/// The main constraint checker for the money piece. Allows spending and minting tokens.
+/// Configuration items for the money piece when it is
+/// instantiated in a concrete runtime.
+pub trait MoneyConfig {
+    /// The smallest value that a newly created coin may hold.
+    ///
+    /// Coins below this value are dust. They cost as much state space as any other coin,
+    /// but are worth too little to ever be worth spending, so they bloat the UTXO set forever.
+    /// The default of one only forbids zero-value coins.
+    const MINIMUM_COIN_VALUE: u128 = 1;
+
+    /// Whether mints and burns must keep an [`Issuance`] accumulator up to date.
+    ///
+    /// When this is on, every mint and burn consumes the accumulator as its first input and
+    /// re-creates it, with the new total, as its first output. The total is also kept in storage,
+    /// starting from the genesis coins, so that the [`MoneyApi`] can read it without scanning the
+    /// UTXO set. Pieces that issue new coins on their own, such as block rewards, add them to the
+    /// total with [`note_minted`]. Pieces that only pay back what transactions burned, such as the
+    /// author reward, do not, and neither are fees and tips subtracted when spends burn them.
+    const TRACK_ISSUANCE: bool = false;
+
+    /// Coins worth less than this are dust that anyone may reclaim once it is old enough.
+    ///
+    /// Reclaiming lets the UTXO set shed coins that are too small for their owners to ever bother
+    /// spending, such as those left over from before the minimum was raised. It needs the runtime
+    /// to track the ages of outputs, see `tuxedo_core::ages`. The default of zero disables it.
+    const DUST_THRESHOLD: u128 = 0;
+
+    /// How many blocks a dust coin must go unspent before anyone may reclaim it.
+    const DUST_LIFETIME: u32 = 1_000_000;
+
+    /// The id of the coin that transactions pay their fees and tips in.
+    ///
+    /// The executive does not know which coin a checker's surplus is made of, and pieces such as
+    /// the author reward pay it back out in this coin. So checkers only report a surplus of this
+    /// coin, see [`surplus`], and any other coins they leave over are simply burned.
+    const NATIVE_ID: u8 = 0;
+}
+
+/// The unit type uses the default configuration, so that the money piece
+/// can be used without writing any configuration at all.
+impl MoneyConfig for () {}
+
+/// The main constraint checker for the money piece. Allows spending, burning, and minting tokens.
 #[derive(
-    Serialize,
-    Deserialize,
-    PartialEq,
-    Eq,
-    PartialOrd,
-    Ord,
-    Clone,
-    Encode,
-    Decode,
-    Hash,
-    Debug,
-    TypeInfo,
+    Serialize, Deserialize, PartialEq, Eq, CloneNoBound, Encode, Decode, DebugNoBound, TypeInfo,
 )]
-pub enum MoneyConstraintChecker<const ID: u8> {
+#[scale_info(skip_type_params(T))]
+pub enum MoneyConstraintChecker<const ID: u8, T = ()> {
     /// A typical spend transaction where some coins are consumed and others are created.
-    /// Input value must exceed output value. The difference is burned and reflected in the
-    /// transaction's priority.
+    /// Input value must exceed output value. The difference pays the transaction's fee, and the
+    /// rest is a tip for the block author. Every output must be worth at least the configured minimum.
+    ///
+    /// When the runtime requires storage deposits, the difference must also cover the deposits of
+    /// the new coins. The deposits of the spent coins are refunded towards the same costs, so a
+    /// spend that creates as many coins as it consumes pays no deposit overall.
     Spend,
     /// A mint transaction that creates no coins out of the void. In a real-world chain,
     /// this should be protected somehow, or not included at all. For now it is publicly
     /// available. I'm adding it to explore multiple validation paths in a single piece.
+    ///
+    /// When issuance is tracked, the accumulator is the only input, and it is re-created ahead of
+    /// the new coins holding the [`total_issuance`] with the minted value added.
     Mint,
+    /// An explicit burn where some coins are consumed and none are created.
+    /// This is the way to clean up dust coins whose total value is too small
+    /// to be respent into a coin that meets the minimum.
+    ///
+    /// The burned value is destroyed rather than left over as a tip, so a burn has no priority.
+    ///
+    /// When issuance is tracked, the accumulator is consumed ahead of the coins, and it is the only
+    /// output, re-created holding the [`total_issuance`] with the burned value taken away.
+    Burn,
+    /// A spend that may also reclaim dust coins that belong to somebody else.
+    ///
+    /// Reclaimed coins do not need their owners' signatures, but each must be worth less than the
+    /// configured dust threshold and must have gone unspent for the configured lifetime. Otherwise
+    /// this is just like a spend, so whoever cleans up the dust may keep it in new coins, or burn it
+    /// towards the transaction's fee, and the treasury's share of fees when there is one.
+    Reclaim,
+    /// Never constructed, and never encoded or decoded. It only carries the configuration type.
+    #[doc(hidden)]
+    #[codec(skip)]
+    #[serde(skip)]
+    _Config(PhantomData<T>),
 }
 
 /// A single coin in the fungible money system.
@@ -78,17 +146,20 @@ impl<const ID: u8> Coin<ID> {
     }
 
     /// Create a mint transaction for a single Coin.
-    pub fn mint<V, OV, OC>(amt: u128, v: V) -> Transaction<OV, OC>
+    pub fn mint<V, OV, OC, T>(amt: u128, v: V) -> Transaction<OV, OC>
     where
         V: Verifier,
         OV: Verifier + From<V>,
-        OC: tuxedo_core::ConstraintChecker<OV> + From<MoneyConstraintChecker<ID>>,
+        OC: tuxedo_core::ConstraintChecker<OV> + From<MoneyConstraintChecker<ID, T>>,
     {
         Transaction {
+            version: TransactionVersion::LATEST,
             inputs: vec![],
             peeks: vec![],
             outputs: vec![(Self::new(amt), v).into()],
             checker: MoneyConstraintChecker::Mint.into(),
+            memo: None,
+            valid_until: None,
         }
     }
 }
@@ -97,6 +168,105 @@ impl<const ID: u8> UtxoData for Coin<ID> {
     const TYPE_ID: [u8; 4] = [b'c', b'o', b'i', ID];
 }
 
+/// The total value of the coins that mints have created and burns have not destroyed.
+///
+/// There is a single accumulator per asset when the runtime tracks issuance. It is protected by
+/// the `UpForGrabs` verifier, and it is the money constraint checker that makes sure every mint
+/// and burn updates it honestly. Coins that other pieces mint are counted in storage rather than
+/// in the accumulator, so that they do not conflict with mints and burns. The next mint or burn
+/// folds them into the accumulator.
+#[derive(
+    Serialize,
+    Deserialize,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Clone,
+    Encode,
+    Decode,
+    Hash,
+    Debug,
+    TypeInfo,
+)]
+pub struct Issuance<const ID: u8>(pub u128);
+
+impl<const ID: u8> UtxoData for Issuance<ID> {
+    const TYPE_ID: [u8; 4] = [b'i', b's', b'u', ID];
+}
+
+/// The storage key under which the total issuance of each asset is kept.
+/// The asset's id is appended to it.
+pub const ISSUANCE_KEY: &[u8] = b"issuance";
+
+/// The storage key under which the value that other pieces minted since each asset's [`Issuance`]
+/// accumulator was last re-created is kept. The asset's id is appended to it.
+pub const UNCOUNTED_ISSUANCE_KEY: &[u8] = b"uncounted_issuance";
+
+/// The storage key of the given asset under the given prefix.
+pub(crate) fn asset_key(prefix: &[u8], id: u8) -> Vec<u8> {
+    [prefix, &[id]].concat()
+}
+
+fn get_value(key: &[u8]) -> Option<u128> {
+    sp_io::storage::get(key).and_then(|d| u128::decode(&mut &*d).ok())
+}
+
+/// The total issuance of the asset with the given id.
+///
+/// Returns `None` if issuance is not tracked.
+pub fn total_issuance(id: u8) -> Option<u128> {
+    get_value(&asset_key(ISSUANCE_KEY, id))
+}
+
+/// Note that some other piece minted coins worth `value` of the asset with the given id, outside
+/// of a mint transaction. Does nothing if the runtime does not track issuance.
+///
+/// The value is added to the total right away, and to the accumulator by the next mint or burn.
+pub fn note_minted<const ID: u8, T: MoneyConfig>(
+    value: u128,
+) -> Result<(), ConstraintCheckerError> {
+    if !T::TRACK_ISSUANCE {
+        return Ok(());
+    }
+    let total = total_issuance(ID).ok_or(ConstraintCheckerError::IssuanceMismatch)?;
+    let uncounted = get_value(&asset_key(UNCOUNTED_ISSUANCE_KEY, ID)).unwrap_or_default();
+
+    let total = total
+        .checked_add(value)
+        .ok_or(ConstraintCheckerError::ValueOverflow)?;
+    let uncounted = uncounted
+        .checked_add(value)
+        .ok_or(ConstraintCheckerError::ValueOverflow)?;
+
+    // SIDE EFFECT: Count the new coins.
+    sp_io::storage::set(&asset_key(ISSUANCE_KEY, ID), &total.encode());
+    sp_io::storage::set(&asset_key(UNCOUNTED_ISSUANCE_KEY, ID), &uncounted.encode());
+    Ok(())
+}
+
+sp_api::decl_runtime_apis! {
+    /// An API that lets explorers and other pieces learn the supply of an asset without scanning
+    /// the whole UTXO set.
+    pub trait MoneyApi {
+        /// The total issuance of the asset with the given id, if it is tracked.
+        fn total_issuance(id: u8) -> Option<u128>;
+    }
+}
+
+/// Emitted when coins are spent.
+#[derive(Serialize, Deserialize, Encode, Decode, Debug, PartialEq, Eq, Clone, TypeInfo)]
+pub struct CoinsTransferred<const ID: u8> {
+    /// The total value of the coins that were created.
+    pub value: u128,
+    /// The value that was burned, usually as a fee or as deposits for the new coins.
+    pub burned: u128,
+}
+
+impl<const ID: u8> TuxedoEvent for CoinsTransferred<ID> {
+    const EVENT_ID: [u8; 4] = [b'x', b'f', b'r', ID];
+}
+
 /// Errors that can occur when checking money transactions.
 #[derive(
     Serialize,
@@ -133,9 +303,130 @@ pub enum ConstraintCheckerError {
     /// The transaction attempted to create a coin with zero value. This is not allowed
     /// because it wastes state space.
     ZeroValueCoin,
+    /// The transaction attempted to create a coin worth less than the configured minimum.
+    /// Such dust is not allowed because it wastes state space. Burn it instead.
+    DustCoin,
+    /// The transaction attempts to burn coins, but also creates some.
+    /// Use a spend transaction instead.
+    BurningWithOutputs,
+    /// Issuance is tracked, but the mint or burn does not consume the issuance accumulator as its
+    /// first input and re-create it as its first output.
+    MissingIssuance,
+    /// The re-created issuance accumulator does not hold the previous total updated by the value
+    /// that was minted or burned.
+    WrongIssuance,
+    /// The consumed issuance accumulator, together with what other pieces minted since it was
+    /// re-created, does not add up to the total in storage, or a burn takes more than the total.
+    /// Either some coins were minted or burned without being counted, or the total was never
+    /// written at genesis.
+    IssuanceMismatch,
+    /// A reclaimed coin is not dust, or it has not gone unspent for long enough.
+    NotReclaimable,
+}
+
+/// Make sure a newly created coin is worth something, and is not dust.
+fn check_new_coin<T: MoneyConfig>(value: u128) -> Result<(), ConstraintCheckerError> {
+    ensure!(value > 0, ConstraintCheckerError::ZeroValueCoin);
+    ensure!(
+        value >= T::MINIMUM_COIN_VALUE,
+        ConstraintCheckerError::DustCoin
+    );
+    Ok(())
+}
+
+/// Make sure every reclaimed input is a dust coin that has gone unspent for long enough.
+fn check_reclaimed<const ID: u8, T: MoneyConfig>(
+    input_data: &[DynamicallyTypedData],
+    context: &CheckerContext,
+) -> Result<(), ConstraintCheckerError> {
+    for (index, input) in input_data.iter().enumerate() {
+        if !context.reclaimed.get(index).copied().unwrap_or(false) {
+            continue;
+        }
+        let value = input
+            .extract::<Coin<ID>>()
+            .map_err(|_| ConstraintCheckerError::BadlyTyped)?
+            .0;
+        let age = context.input_ages.get(index).copied().flatten();
+        ensure!(
+            value < T::DUST_THRESHOLD && age.is_some_and(|age| age >= T::DUST_LIFETIME),
+            ConstraintCheckerError::NotReclaimable
+        );
+    }
+    Ok(())
 }
 
-impl<const ID: u8> SimpleConstraintChecker for MoneyConstraintChecker<ID> {
+/// The surplus that a constraint checker reports when its transaction leaves `burned` worth of
+/// `Coin<ID>`s over.
+///
+/// Only [`MoneyConfig::NATIVE_ID`] coins pay fees and tips. Other coins are burned without any
+/// surplus, because the author would otherwise be paid the same value in native coins.
+pub fn surplus<const ID: u8, T: MoneyConfig>(burned: u128) -> TransactionPriority {
+    if ID == T::NATIVE_ID {
+        burned.try_into().unwrap_or(TransactionPriority::MAX)
+    } else {
+        0
+    }
+}
+
+/// Separate the issuance accumulator from the coins of a tracked mint or burn.
+///
+/// Returns the total that was consumed and the total that is re-created, followed by the
+/// remaining inputs and outputs.
+fn split_issuance<'a, const ID: u8>(
+    input_data: &'a [DynamicallyTypedData],
+    output_data: &'a [DynamicallyTypedData],
+) -> Result<
+    (
+        (u128, u128),
+        &'a [DynamicallyTypedData],
+        &'a [DynamicallyTypedData],
+    ),
+    ConstraintCheckerError,
+> {
+    let (old, input_data) = input_data
+        .split_first()
+        .ok_or(ConstraintCheckerError::MissingIssuance)?;
+    let (new, output_data) = output_data
+        .split_first()
+        .ok_or(ConstraintCheckerError::MissingIssuance)?;
+    let old = old
+        .extract::<Issuance<ID>>()
+        .map_err(|_| ConstraintCheckerError::MissingIssuance)?;
+    let new = new
+        .extract::<Issuance<ID>>()
+        .map_err(|_| ConstraintCheckerError::MissingIssuance)?;
+    Ok(((old.0, new.0), input_data, output_data))
+}
+
+/// Make sure the consumed accumulator agrees with the total in storage, and that the re-created
+/// one holds that total updated by the value that was minted and burned. Then store the new total.
+fn update_issuance<const ID: u8>(
+    (old, new): (u128, u128),
+    minted: u128,
+    burned: u128,
+) -> Result<(), ConstraintCheckerError> {
+    let total = total_issuance(ID).ok_or(ConstraintCheckerError::IssuanceMismatch)?;
+    let uncounted = get_value(&asset_key(UNCOUNTED_ISSUANCE_KEY, ID)).unwrap_or_default();
+    ensure!(
+        old.checked_add(uncounted) == Some(total),
+        ConstraintCheckerError::IssuanceMismatch
+    );
+
+    let expected = total
+        .checked_add(minted)
+        .ok_or(ConstraintCheckerError::ValueOverflow)?
+        .checked_sub(burned)
+        .ok_or(ConstraintCheckerError::IssuanceMismatch)?;
+    ensure!(new == expected, ConstraintCheckerError::WrongIssuance);
+
+    // SIDE EFFECT: Store the new total, which the accumulator now holds in full.
+    sp_io::storage::set(&asset_key(ISSUANCE_KEY, ID), &new.encode());
+    sp_io::storage::clear(&asset_key(UNCOUNTED_ISSUANCE_KEY, ID));
+    Ok(())
+}
+
+impl<const ID: u8, T: MoneyConfig> SimpleConstraintChecker for MoneyConstraintChecker<ID, T> {
     type Error = ConstraintCheckerError;
 
     fn check(
@@ -143,9 +434,25 @@ impl<const ID: u8> SimpleConstraintChecker for MoneyConstraintChecker<ID> {
         input_data: &[DynamicallyTypedData],
         _peeks: &[DynamicallyTypedData],
         output_data: &[DynamicallyTypedData],
+        context: &CheckerContext,
     ) -> Result<TransactionPriority, Self::Error> {
+        // Reclaimed coins skipped their verifiers, so make sure that anyone may take them.
+        if matches!(self, Self::Reclaim) {
+            check_reclaimed::<ID, T>(input_data, context)?;
+        }
+
+        // When issuance is tracked, mints and burns carry the accumulator ahead of their coins.
+        let (issuance, input_data, output_data) =
+            if T::TRACK_ISSUANCE && matches!(self, Self::Mint | Self::Burn) {
+                let (issuance, input_data, output_data) =
+                    split_issuance::<ID>(input_data, output_data)?;
+                (Some(issuance), input_data, output_data)
+            } else {
+                (None, input_data, output_data)
+            };
+
         match &self {
-            Self::Spend => {
+            Self::Spend | Self::Reclaim => {
                 // Check that we are consuming at least one input
                 ensure!(
                     !input_data.is_empty(),
@@ -171,7 +478,7 @@ impl<const ID: u8> SimpleConstraintChecker for MoneyConstraintChecker<ID> {
                         .extract::<Coin<ID>>()
                         .map_err(|_| ConstraintCheckerError::BadlyTyped)?
                         .0;
-                    ensure!(utxo_value > 0, ConstraintCheckerError::ZeroValueCoin);
+                    check_new_coin::<T>(utxo_value)?;
                     total_output_value = total_output_value
                         .checked_add(utxo_value)
                         .ok_or(ConstraintCheckerError::ValueOverflow)?;
@@ -182,14 +489,13 @@ impl<const ID: u8> SimpleConstraintChecker for MoneyConstraintChecker<ID> {
                     ConstraintCheckerError::OutputsExceedInputs
                 );
 
-                // Priority is based on how many token are burned
-                // Type stuff is kinda ugly. Maybe division would be better?
+                // Priority is based on how many native tokens are burned
                 let burned = total_input_value - total_output_value;
-                Ok(if burned < u64::max_value() as u128 {
-                    burned as u64
-                } else {
-                    u64::max_value()
-                })
+                events::deposit(CoinsTransferred::<ID> {
+                    value: total_output_value,
+                    burned,
+                });
+                Ok(surplus::<ID, T>(burned))
             }
             Self::Mint => {
                 // Make sure there are no inputs being consumed
@@ -205,17 +511,61 @@ impl<const ID: u8> SimpleConstraintChecker for MoneyConstraintChecker<ID> {
                 );
 
                 // Make sure the outputs are the right type
+                let mut total_output_value: u128 = 0;
                 for utxo in output_data {
                     let utxo_value = utxo
                         .extract::<Coin<ID>>()
                         .map_err(|_| ConstraintCheckerError::BadlyTyped)?
                         .0;
-                    ensure!(utxo_value > 0, ConstraintCheckerError::ZeroValueCoin);
+                    check_new_coin::<T>(utxo_value)?;
+                    total_output_value = total_output_value
+                        .checked_add(utxo_value)
+                        .ok_or(ConstraintCheckerError::ValueOverflow)?;
+                }
+
+                if let Some(issuance) = issuance {
+                    update_issuance::<ID>(issuance, total_output_value, 0)?;
                 }
 
                 // No priority for minting
                 Ok(0)
             }
+            Self::Burn => {
+                // Check that we are consuming at least one input
+                ensure!(
+                    !input_data.is_empty(),
+                    ConstraintCheckerError::SpendingNothing
+                );
+
+                // Make sure nothing is created
+                ensure!(
+                    output_data.is_empty(),
+                    ConstraintCheckerError::BurningWithOutputs
+                );
+
+                let mut total_input_value: u128 = 0;
+                for input in input_data {
+                    let utxo_value = input
+                        .extract::<Coin<ID>>()
+                        .map_err(|_| ConstraintCheckerError::BadlyTyped)?
+                        .0;
+                    total_input_value = total_input_value
+                        .checked_add(utxo_value)
+                        .ok_or(ConstraintCheckerError::ValueOverflow)?;
+                }
+
+                if let Some(issuance) = issuance {
+                    update_issuance::<ID>(issuance, 0, total_input_value)?;
+                }
+
+                // The burned coins are destroyed, so there is nothing left over to tip the author
+                Ok(0)
+            }
+            Self::_Config(_) => unreachable!("the configuration variant is never constructed"),
         }
     }
+
+    fn reclaims(&self) -> bool {
+        matches!(self, Self::Reclaim)
+    }
 }
@@ -1,7 +1,7 @@
 //! Unit tests for the Money piece
 
 use super::*;
-use tuxedo_core::dynamic_typing::testing::Bogus;
+use tuxedo_core::{dynamic_typing::testing::Bogus, events};
 
 #[test]
 fn spend_valid_transaction_work() {
@@ -10,11 +10,53 @@ fn spend_valid_transaction_work() {
     let expected_priority = 1u64;
 
     assert_eq!(
-        MoneyConstraintChecker::<0>::Spend.check(&input_data, &[], &output_data),
+        MoneyConstraintChecker::<0>::Spend.check(
+            &input_data,
+            &[],
+            &output_data,
+            &Default::default()
+        ),
         Ok(expected_priority),
     );
 }
 
+#[test]
+fn spend_of_non_native_coins_has_no_priority() {
+    let input_data = vec![Coin::<1>(5).into(), Coin::<1>(7).into()]; // total 12
+    let output_data = vec![Coin::<1>(10).into(), Coin::<1>(1).into()]; // total 11
+
+    assert_eq!(
+        MoneyConstraintChecker::<1>::Spend.check(
+            &input_data,
+            &[],
+            &output_data,
+            &Default::default()
+        ),
+        Ok(0),
+    );
+}
+
+#[test]
+fn spend_emits_transfer_event() {
+    let input_data = vec![Coin::<0>(5).into(), Coin::<0>(7).into()]; // total 12
+    let output_data = vec![Coin::<0>(10).into(), Coin::<0>(1).into()]; // total 11
+
+    let (_, emitted) = events::collect(|| {
+        MoneyConstraintChecker::<0>::Spend.check(
+            &input_data,
+            &[],
+            &output_data,
+            &Default::default(),
+        )
+    });
+
+    let expected = CoinsTransferred::<0> {
+        value: 11,
+        burned: 1,
+    };
+    assert_eq!(emitted, vec![expected.into()]);
+}
+
 #[test]
 fn spend_with_zero_value_output_fails() {
     let input_data = vec![Coin::<0>(5).into(), Coin::<0>(7).into()]; // total 12
@@ -25,7 +67,12 @@ fn spend_with_zero_value_output_fails() {
     ]; // total 11
 
     assert_eq!(
-        MoneyConstraintChecker::<0>::Spend.check(&input_data, &[], &output_data),
+        MoneyConstraintChecker::<0>::Spend.check(
+            &input_data,
+            &[],
+            &output_data,
+            &Default::default()
+        ),
         Err(ConstraintCheckerError::ZeroValueCoin),
     );
 }
@@ -37,7 +84,12 @@ fn spend_no_outputs_is_a_burn() {
     let expected_priority = 12u64;
 
     assert_eq!(
-        MoneyConstraintChecker::<0>::Spend.check(&input_data, &[], &output_data),
+        MoneyConstraintChecker::<0>::Spend.check(
+            &input_data,
+            &[],
+            &output_data,
+            &Default::default()
+        ),
         Ok(expected_priority),
     );
 }
@@ -48,7 +100,12 @@ fn spend_no_inputs_fails() {
     let output_data = vec![Coin::<0>(10).into(), Coin::<0>(1).into()];
 
     assert_eq!(
-        MoneyConstraintChecker::<0>::Spend.check(&input_data, &[], &output_data),
+        MoneyConstraintChecker::<0>::Spend.check(
+            &input_data,
+            &[],
+            &output_data,
+            &Default::default()
+        ),
         Err(ConstraintCheckerError::SpendingNothing),
     );
 }
@@ -59,7 +116,12 @@ fn spend_wrong_input_type_fails() {
     let output_data = vec![Coin::<0>(10).into(), Coin::<0>(1).into()];
 
     assert_eq!(
-        MoneyConstraintChecker::<0>::Spend.check(&input_data, &[], &output_data),
+        MoneyConstraintChecker::<0>::Spend.check(
+            &input_data,
+            &[],
+            &output_data,
+            &Default::default()
+        ),
         Err(ConstraintCheckerError::BadlyTyped),
     );
 }
@@ -70,7 +132,12 @@ fn spend_wrong_output_type_fails() {
     let output_data = vec![Bogus.into()];
 
     assert_eq!(
-        MoneyConstraintChecker::<0>::Spend.check(&input_data, &[], &output_data),
+        MoneyConstraintChecker::<0>::Spend.check(
+            &input_data,
+            &[],
+            &output_data,
+            &Default::default()
+        ),
         Err(ConstraintCheckerError::BadlyTyped),
     );
 }
@@ -81,7 +148,12 @@ fn spend_output_value_exceeds_input_value_fails() {
     let output_data = vec![Coin::<0>(5).into(), Coin::<0>(7).into()]; // total 12
 
     assert_eq!(
-        MoneyConstraintChecker::<0>::Spend.check(&input_data, &[], &output_data),
+        MoneyConstraintChecker::<0>::Spend.check(
+            &input_data,
+            &[],
+            &output_data,
+            &Default::default()
+        ),
         Err(ConstraintCheckerError::OutputsExceedInputs),
     );
 }
@@ -92,7 +164,12 @@ fn mint_valid_transaction_works() {
     let output_data = vec![Coin::<0>(10).into(), Coin::<0>(1).into()];
 
     assert_eq!(
-        MoneyConstraintChecker::<0>::Mint.check(&input_data, &[], &output_data),
+        MoneyConstraintChecker::<0>::Mint.check(
+            &input_data,
+            &[],
+            &output_data,
+            &Default::default()
+        ),
         Ok(0),
     );
 }
@@ -103,7 +180,12 @@ fn mint_with_zero_value_output_fails() {
     let output_data = vec![Coin::<0>(0).into()];
 
     assert_eq!(
-        MoneyConstraintChecker::<0>::Mint.check(&input_data, &[], &output_data),
+        MoneyConstraintChecker::<0>::Mint.check(
+            &input_data,
+            &[],
+            &output_data,
+            &Default::default()
+        ),
         Err(ConstraintCheckerError::ZeroValueCoin),
     );
 }
@@ -114,7 +196,12 @@ fn mint_with_inputs_fails() {
     let output_data = vec![Coin::<0>(10).into(), Coin::<0>(1).into()];
 
     assert_eq!(
-        MoneyConstraintChecker::<0>::Mint.check(&input_data, &[], &output_data),
+        MoneyConstraintChecker::<0>::Mint.check(
+            &input_data,
+            &[],
+            &output_data,
+            &Default::default()
+        ),
         Err(ConstraintCheckerError::MintingWithInputs),
     );
 }
@@ -125,7 +212,12 @@ fn mint_with_no_outputs_fails() {
     let output_data = vec![];
 
     assert_eq!(
-        MoneyConstraintChecker::<0>::Mint.check(&input_data, &[], &output_data),
+        MoneyConstraintChecker::<0>::Mint.check(
+            &input_data,
+            &[],
+            &output_data,
+            &Default::default()
+        ),
         Err(ConstraintCheckerError::MintingNothing),
     );
 }
@@ -136,7 +228,672 @@ fn mint_wrong_output_type_fails() {
     let output_data = vec![Coin::<0>(10).into(), Bogus.into()];
 
     assert_eq!(
-        MoneyConstraintChecker::<0>::Mint.check(&input_data, &[], &output_data),
+        MoneyConstraintChecker::<0>::Mint.check(
+            &input_data,
+            &[],
+            &output_data,
+            &Default::default()
+        ),
         Err(ConstraintCheckerError::BadlyTyped),
     );
 }
+
+/// A configuration with a dust limit, for testing.
+struct WithDustLimit;
+
+impl MoneyConfig for WithDustLimit {
+    const MINIMUM_COIN_VALUE: u128 = 5;
+}
+
+#[test]
+fn spend_at_dust_limit_works() {
+    let input_data = vec![Coin::<0>(12).into()];
+    let output_data = vec![Coin::<0>(5).into(), Coin::<0>(6).into()];
+
+    assert_eq!(
+        MoneyConstraintChecker::<0, WithDustLimit>::Spend.check(
+            &input_data,
+            &[],
+            &output_data,
+            &Default::default()
+        ),
+        Ok(1),
+    );
+}
+
+#[test]
+fn spend_creating_dust_fails() {
+    let input_data = vec![Coin::<0>(12).into()];
+    let output_data = vec![Coin::<0>(10).into(), Coin::<0>(2).into()];
+
+    assert_eq!(
+        MoneyConstraintChecker::<0, WithDustLimit>::Spend.check(
+            &input_data,
+            &[],
+            &output_data,
+            &Default::default()
+        ),
+        Err(ConstraintCheckerError::DustCoin),
+    );
+}
+
+#[test]
+fn mint_creating_dust_fails() {
+    let input_data = vec![];
+    let output_data = vec![Coin::<0>(4).into()];
+
+    assert_eq!(
+        MoneyConstraintChecker::<0, WithDustLimit>::Mint.check(
+            &input_data,
+            &[],
+            &output_data,
+            &Default::default()
+        ),
+        Err(ConstraintCheckerError::DustCoin),
+    );
+}
+
+#[test]
+fn burn_dust_works() {
+    let input_data = vec![Coin::<0>(2).into(), Coin::<0>(1).into()];
+    let output_data = vec![];
+
+    assert_eq!(
+        MoneyConstraintChecker::<0, WithDustLimit>::Burn.check(
+            &input_data,
+            &[],
+            &output_data,
+            &Default::default()
+        ),
+        Ok(0),
+    );
+}
+
+#[test]
+fn burn_with_outputs_fails() {
+    let input_data = vec![Coin::<0>(12).into()];
+    let output_data = vec![Coin::<0>(10).into()];
+
+    assert_eq!(
+        MoneyConstraintChecker::<0>::Burn.check(
+            &input_data,
+            &[],
+            &output_data,
+            &Default::default()
+        ),
+        Err(ConstraintCheckerError::BurningWithOutputs),
+    );
+}
+
+#[test]
+fn burn_no_inputs_fails() {
+    let input_data = vec![];
+    let output_data = vec![];
+
+    assert_eq!(
+        MoneyConstraintChecker::<0>::Burn.check(
+            &input_data,
+            &[],
+            &output_data,
+            &Default::default()
+        ),
+        Err(ConstraintCheckerError::SpendingNothing),
+    );
+}
+
+#[test]
+fn burn_wrong_input_type_fails() {
+    let input_data = vec![Bogus.into()];
+    let output_data = vec![];
+
+    assert_eq!(
+        MoneyConstraintChecker::<0>::Burn.check(
+            &input_data,
+            &[],
+            &output_data,
+            &Default::default()
+        ),
+        Err(ConstraintCheckerError::BadlyTyped),
+    );
+}
+
+/// A configuration that tracks issuance, for testing.
+struct Tracked;
+
+impl MoneyConfig for Tracked {
+    const TRACK_ISSUANCE: bool = true;
+}
+
+/// Write the total issuance of asset zero to storage, as genesis would.
+fn set_total_issuance(total: u128) {
+    sp_io::storage::set(&asset_key(ISSUANCE_KEY, 0), &total.encode());
+}
+
+#[test]
+fn tracked_mint_works() {
+    sp_io::TestExternalities::default().execute_with(|| {
+        set_total_issuance(100);
+        let input_data = vec![Issuance::<0>(100).into()];
+        let output_data = vec![
+            Issuance::<0>(115).into(),
+            Coin::<0>(10).into(),
+            Coin::<0>(5).into(),
+        ];
+
+        assert_eq!(
+            MoneyConstraintChecker::<0, Tracked>::Mint.check(
+                &input_data,
+                &[],
+                &output_data,
+                &Default::default()
+            ),
+            Ok(0),
+        );
+        assert_eq!(total_issuance(0), Some(115));
+        assert_eq!(total_issuance(1), None);
+    })
+}
+
+#[test]
+fn tracked_mint_without_issuance_fails() {
+    let input_data = vec![];
+    let output_data = vec![Coin::<0>(10).into()];
+
+    assert_eq!(
+        MoneyConstraintChecker::<0, Tracked>::Mint.check(
+            &input_data,
+            &[],
+            &output_data,
+            &Default::default()
+        ),
+        Err(ConstraintCheckerError::MissingIssuance),
+    );
+}
+
+#[test]
+fn tracked_mint_with_other_asset_issuance_fails() {
+    let input_data = vec![Issuance::<1>(100).into()];
+    let output_data = vec![Issuance::<1>(110).into(), Coin::<0>(10).into()];
+
+    assert_eq!(
+        MoneyConstraintChecker::<0, Tracked>::Mint.check(
+            &input_data,
+            &[],
+            &output_data,
+            &Default::default()
+        ),
+        Err(ConstraintCheckerError::MissingIssuance),
+    );
+}
+
+#[test]
+fn tracked_mint_with_wrong_total_fails() {
+    sp_io::TestExternalities::default().execute_with(|| {
+        set_total_issuance(100);
+        let input_data = vec![Issuance::<0>(100).into()];
+        let output_data = vec![Issuance::<0>(105).into(), Coin::<0>(10).into()];
+
+        assert_eq!(
+            MoneyConstraintChecker::<0, Tracked>::Mint.check(
+                &input_data,
+                &[],
+                &output_data,
+                &Default::default()
+            ),
+            Err(ConstraintCheckerError::WrongIssuance),
+        );
+    })
+}
+
+#[test]
+fn tracked_mint_without_total_in_storage_fails() {
+    sp_io::TestExternalities::default().execute_with(|| {
+        let input_data = vec![Issuance::<0>(100).into()];
+        let output_data = vec![Issuance::<0>(110).into(), Coin::<0>(10).into()];
+
+        assert_eq!(
+            MoneyConstraintChecker::<0, Tracked>::Mint.check(
+                &input_data,
+                &[],
+                &output_data,
+                &Default::default()
+            ),
+            Err(ConstraintCheckerError::IssuanceMismatch),
+        );
+    })
+}
+
+#[test]
+fn tracked_mint_with_drifted_total_fails() {
+    sp_io::TestExternalities::default().execute_with(|| {
+        set_total_issuance(120);
+        let input_data = vec![Issuance::<0>(100).into()];
+        let output_data = vec![Issuance::<0>(130).into(), Coin::<0>(10).into()];
+
+        assert_eq!(
+            MoneyConstraintChecker::<0, Tracked>::Mint.check(
+                &input_data,
+                &[],
+                &output_data,
+                &Default::default()
+            ),
+            Err(ConstraintCheckerError::IssuanceMismatch),
+        );
+    })
+}
+
+#[test]
+fn tracked_mint_folds_in_coins_minted_by_other_pieces() {
+    sp_io::TestExternalities::default().execute_with(|| {
+        set_total_issuance(100);
+        assert_eq!(note_minted::<0, Tracked>(20), Ok(()));
+        assert_eq!(total_issuance(0), Some(120));
+
+        let input_data = vec![Issuance::<0>(100).into()];
+        let output_data = vec![Issuance::<0>(130).into(), Coin::<0>(10).into()];
+
+        assert_eq!(
+            MoneyConstraintChecker::<0, Tracked>::Mint.check(
+                &input_data,
+                &[],
+                &output_data,
+                &Default::default()
+            ),
+            Ok(0),
+        );
+        assert_eq!(total_issuance(0), Some(130));
+        let uncounted_key = asset_key(UNCOUNTED_ISSUANCE_KEY, 0);
+        assert!(!sp_io::storage::exists(&uncounted_key));
+    })
+}
+
+#[test]
+fn note_minted_without_total_fails() {
+    sp_io::TestExternalities::default().execute_with(|| {
+        assert_eq!(
+            note_minted::<0, Tracked>(20),
+            Err(ConstraintCheckerError::IssuanceMismatch)
+        );
+    })
+}
+
+#[test]
+fn note_minted_untracked_does_nothing() {
+    sp_io::TestExternalities::default().execute_with(|| {
+        assert_eq!(note_minted::<0, ()>(20), Ok(()));
+        assert_eq!(total_issuance(0), None);
+    })
+}
+
+#[test]
+fn tracked_mint_with_other_inputs_fails() {
+    let input_data = vec![Issuance::<0>(100).into(), Coin::<0>(5).into()];
+    let output_data = vec![Issuance::<0>(110).into(), Coin::<0>(10).into()];
+
+    assert_eq!(
+        MoneyConstraintChecker::<0, Tracked>::Mint.check(
+            &input_data,
+            &[],
+            &output_data,
+            &Default::default()
+        ),
+        Err(ConstraintCheckerError::MintingWithInputs),
+    );
+}
+
+#[test]
+fn tracked_mint_overflowing_issuance_fails() {
+    sp_io::TestExternalities::default().execute_with(|| {
+        set_total_issuance(u128::MAX);
+        let input_data = vec![Issuance::<0>(u128::MAX).into()];
+        let output_data = vec![Issuance::<0>(u128::MAX).into(), Coin::<0>(1).into()];
+
+        assert_eq!(
+            MoneyConstraintChecker::<0, Tracked>::Mint.check(
+                &input_data,
+                &[],
+                &output_data,
+                &Default::default()
+            ),
+            Err(ConstraintCheckerError::ValueOverflow),
+        );
+    })
+}
+
+#[test]
+fn tracked_burn_works() {
+    sp_io::TestExternalities::default().execute_with(|| {
+        set_total_issuance(100);
+        let input_data = vec![
+            Issuance::<0>(100).into(),
+            Coin::<0>(10).into(),
+            Coin::<0>(5).into(),
+        ];
+        let output_data = vec![Issuance::<0>(85).into()];
+
+        assert_eq!(
+            MoneyConstraintChecker::<0, Tracked>::Burn.check(
+                &input_data,
+                &[],
+                &output_data,
+                &Default::default()
+            ),
+            Ok(0),
+        );
+        assert_eq!(total_issuance(0), Some(85));
+    })
+}
+
+#[test]
+fn tracked_burn_with_wrong_total_fails() {
+    sp_io::TestExternalities::default().execute_with(|| {
+        set_total_issuance(100);
+        let input_data = vec![Issuance::<0>(100).into(), Coin::<0>(10).into()];
+        let output_data = vec![Issuance::<0>(100).into()];
+
+        assert_eq!(
+            MoneyConstraintChecker::<0, Tracked>::Burn.check(
+                &input_data,
+                &[],
+                &output_data,
+                &Default::default()
+            ),
+            Err(ConstraintCheckerError::WrongIssuance),
+        );
+    })
+}
+
+#[test]
+fn tracked_burn_of_more_than_total_fails() {
+    sp_io::TestExternalities::default().execute_with(|| {
+        set_total_issuance(5);
+        let input_data = vec![Issuance::<0>(5).into(), Coin::<0>(10).into()];
+        let output_data = vec![Issuance::<0>(0).into()];
+
+        assert_eq!(
+            MoneyConstraintChecker::<0, Tracked>::Burn.check(
+                &input_data,
+                &[],
+                &output_data,
+                &Default::default()
+            ),
+            Err(ConstraintCheckerError::IssuanceMismatch),
+        );
+    })
+}
+
+#[test]
+fn tracked_burn_without_issuance_fails() {
+    let input_data = vec![Coin::<0>(10).into()];
+    let output_data = vec![];
+
+    assert_eq!(
+        MoneyConstraintChecker::<0, Tracked>::Burn.check(
+            &input_data,
+            &[],
+            &output_data,
+            &Default::default()
+        ),
+        Err(ConstraintCheckerError::MissingIssuance),
+    );
+}
+
+#[test]
+fn tracked_spend_ignores_issuance() {
+    let input_data = vec![Coin::<0>(12).into()];
+    let output_data = vec![Coin::<0>(11).into()];
+
+    assert_eq!(
+        MoneyConstraintChecker::<0, Tracked>::Spend.check(
+            &input_data,
+            &[],
+            &output_data,
+            &Default::default()
+        ),
+        Ok(1),
+    );
+}
+
+/// A config that lets anyone reclaim coins worth less than 10 once they are 100 blocks old.
+struct Reclaimable;
+
+impl MoneyConfig for Reclaimable {
+    const DUST_THRESHOLD: u128 = 10;
+    const DUST_LIFETIME: u32 = 100;
+}
+
+/// A context where the first input is the cleaner's own coin, and the rest are reclaimed dust of
+/// the given ages.
+fn reclaiming(ages: &[Option<u32>]) -> CheckerContext {
+    CheckerContext {
+        reclaimed: [false]
+            .into_iter()
+            .chain(ages.iter().map(|_| true))
+            .collect(),
+        input_ages: [Some(0)].into_iter().chain(ages.iter().copied()).collect(),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn reclaim_old_dust_works() {
+    let input_data = vec![
+        Coin::<0>(20).into(),
+        Coin::<0>(9).into(),
+        Coin::<0>(3).into(),
+    ];
+    let output_data = vec![Coin::<0>(30).into()];
+
+    assert_eq!(
+        MoneyConstraintChecker::<0, Reclaimable>::Reclaim.check(
+            &input_data,
+            &[],
+            &output_data,
+            &reclaiming(&[Some(100), Some(5_000)])
+        ),
+        Ok(2),
+    );
+}
+
+#[test]
+fn reclaim_coin_above_threshold_fails() {
+    let input_data = vec![Coin::<0>(20).into(), Coin::<0>(10).into()];
+
+    assert_eq!(
+        MoneyConstraintChecker::<0, Reclaimable>::Reclaim.check(
+            &input_data,
+            &[],
+            &[],
+            &reclaiming(&[Some(100)])
+        ),
+        Err(ConstraintCheckerError::NotReclaimable),
+    );
+}
+
+#[test]
+fn reclaim_young_dust_fails() {
+    let input_data = vec![Coin::<0>(20).into(), Coin::<0>(9).into()];
+
+    assert_eq!(
+        MoneyConstraintChecker::<0, Reclaimable>::Reclaim.check(
+            &input_data,
+            &[],
+            &[],
+            &reclaiming(&[Some(99)])
+        ),
+        Err(ConstraintCheckerError::NotReclaimable),
+    );
+}
+
+#[test]
+fn reclaim_dust_of_unknown_age_fails() {
+    let input_data = vec![Coin::<0>(20).into(), Coin::<0>(9).into()];
+
+    assert_eq!(
+        MoneyConstraintChecker::<0, Reclaimable>::Reclaim.check(
+            &input_data,
+            &[],
+            &[],
+            &reclaiming(&[None])
+        ),
+        Err(ConstraintCheckerError::NotReclaimable),
+    );
+}
+
+#[test]
+fn reclaim_is_disabled_by_default() {
+    let input_data = vec![Coin::<0>(20).into(), Coin::<0>(1).into()];
+
+    assert_eq!(
+        MoneyConstraintChecker::<0>::Reclaim.check(
+            &input_data,
+            &[],
+            &[],
+            &reclaiming(&[Some(u32::MAX)])
+        ),
+        Err(ConstraintCheckerError::NotReclaimable),
+    );
+}
+
+#[test]
+fn only_reclaim_reclaims() {
+    assert!(MoneyConstraintChecker::<0>::Reclaim.reclaims());
+    assert!(!MoneyConstraintChecker::<0>::Spend.reclaims());
+    assert!(!MoneyConstraintChecker::<0>::Burn.reclaims());
+}
+
+#[test]
+fn genesis_section_mints_each_coin() {
+    use tuxedo_core::{genesis::GenesisSection, verifier::UpForGrabs};
+
+    let config =
+        genesis::MoneyGenesisConfig::<0, UpForGrabs>::new(vec![(100, UpForGrabs), (5, UpForGrabs)]);
+    let transactions: Vec<Transaction<UpForGrabs, MoneyConstraintChecker<0>>> =
+        config.genesis_transactions();
+
+    assert_eq!(
+        transactions,
+        vec![
+            Coin::<0>::mint(100, UpForGrabs),
+            Coin::<0>::mint(5, UpForGrabs)
+        ]
+    );
+}
+
+#[test]
+fn tracked_genesis_section_creates_issuance() {
+    use tuxedo_core::{genesis::GenesisSection, verifier::UpForGrabs};
+
+    let config = genesis::MoneyGenesisConfig::<0, UpForGrabs, Tracked>::new(vec![
+        (100, UpForGrabs),
+        (5, UpForGrabs),
+    ]);
+    let transactions: Vec<Transaction<UpForGrabs, MoneyConstraintChecker<0, Tracked>>> =
+        config.genesis_transactions();
+
+    assert_eq!(transactions.len(), 3);
+    assert_eq!(
+        transactions[2].outputs,
+        vec![(Issuance::<0>(105), UpForGrabs).into()]
+    );
+    assert_eq!(
+        GenesisSection::<UpForGrabs, MoneyConstraintChecker<0, Tracked>>::genesis_storage(&config),
+        vec![(asset_key(ISSUANCE_KEY, 0), 105u128.encode())]
+    );
+}
+
+// The tests below apply whole transfers through the executive, so they also cover finding the
+// coins in the utxo set, checking who owns them, and storing the new coins.
+
+mod transfers {
+    use super::*;
+    use tuxedo_core::{
+        types::{OutputRef, UtxoError},
+        verifier::TestVerifier,
+    };
+    use tuxedo_test_utils::{
+        apply, consume, mock_output_ref, payload, utxo, MockState, TestExternalities,
+    };
+
+    type Spend = Transaction<TestVerifier, MoneyConstraintChecker<0>>;
+
+    const OWNED: TestVerifier = TestVerifier { verifies: true };
+    const NOT_OWNED: TestVerifier = TestVerifier { verifies: false };
+
+    fn state_with_coin(verifier: TestVerifier) -> TestExternalities {
+        MockState::<TestVerifier, MoneyConstraintChecker<0>>::default()
+            .with_payload(mock_output_ref(0, 0), Coin::<0>(10), verifier)
+            .build()
+    }
+
+    fn spend(inputs: Vec<OutputRef>, values: Vec<u128>) -> Spend {
+        Transaction {
+            version: TransactionVersion::LATEST,
+            inputs: inputs.into_iter().map(consume).collect(),
+            peeks: Vec::new(),
+            outputs: values
+                .into_iter()
+                .map(|value| (Coin::<0>(value), OWNED).into())
+                .collect(),
+            checker: MoneyConstraintChecker::Spend,
+            memo: None,
+            valid_until: None,
+        }
+    }
+
+    #[test]
+    fn transfer_works() {
+        state_with_coin(OWNED).execute_with(|| {
+            let refs = apply(spend(vec![mock_output_ref(0, 0)], vec![6, 3])).unwrap();
+
+            assert_eq!(utxo::<TestVerifier>(&mock_output_ref(0, 0)), None);
+            assert_eq!(payload::<Coin<0>, TestVerifier>(&refs[0]), Some(Coin(6)));
+            assert_eq!(payload::<Coin<0>, TestVerifier>(&refs[1]), Some(Coin(3)));
+        });
+    }
+
+    #[test]
+    fn received_coins_can_be_transferred_again() {
+        state_with_coin(OWNED).execute_with(|| {
+            let refs = apply(spend(vec![mock_output_ref(0, 0)], vec![6, 4])).unwrap();
+            let refs = apply(spend(refs, vec![10])).unwrap();
+
+            assert_eq!(payload::<Coin<0>, TestVerifier>(&refs[0]), Some(Coin(10)));
+        });
+    }
+
+    #[test]
+    fn transfer_of_someone_elses_coin_fails() {
+        state_with_coin(NOT_OWNED).execute_with(|| {
+            assert_eq!(
+                apply(spend(vec![mock_output_ref(0, 0)], vec![10])),
+                Err(UtxoError::VerifierError)
+            );
+            assert!(utxo::<TestVerifier>(&mock_output_ref(0, 0)).is_some());
+        });
+    }
+
+    #[test]
+    fn transfer_of_more_than_owned_fails() {
+        state_with_coin(OWNED).execute_with(|| {
+            assert_eq!(
+                apply(spend(vec![mock_output_ref(0, 0)], vec![6, 5])),
+                Err(UtxoError::ConstraintCheckerError(
+                    ConstraintCheckerError::OutputsExceedInputs
+                ))
+            );
+        });
+    }
+
+    #[test]
+    fn transfer_of_spent_coin_fails() {
+        state_with_coin(OWNED).execute_with(|| {
+            apply(spend(vec![mock_output_ref(0, 0)], vec![10])).unwrap();
+
+            assert_eq!(
+                apply(spend(vec![mock_output_ref(0, 0)], vec![9])),
+                Err(UtxoError::MissingInput)
+            );
+        });
+    }
+}
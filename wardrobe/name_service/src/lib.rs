@@ -0,0 +1,771 @@
+//! A name service that maps human readable names to public keys.
+//!
+//! Names are registered in two steps to prevent front-running. First the registrant publishes a
+//! [`NameCommitment`] that hides the name behind a salted hash. After at least one block, they
+//! reveal the name, salt, and owner to create the [`NameRecord`]. Someone watching the pool for
+//! reveals cannot take the name, because their own commitment would be too young to reveal.
+//!
+//! Each record locks a deposit of `Coin<ID>` and expires after the registration period unless it
+//! is renewed. Owners may transfer their names or point them at different keys. Once a name has
+//! expired and the grace period has passed, anyone may release it, refunding the deposit to the
+//! last owner and making the name available again.
+//!
+//! In a UTXO model, a checker cannot prove that a name has not been registered before by
+//! looking for it. Instead, all records form a linked list sorted by name, starting from a root
+//! record with an empty name that must be included in the genesis block (see [`NameRecord::root`]).
+//! Registering a name consumes and re-creates its predecessor in the list, which proves that the
+//! name is not taken. Releasing a name unlinks it from its predecessor in the same way.
+//!
+//! Records must be protected by the `UpForGrabs` verifier so that they can be linked and
+//! unlinked by others. The constraint checkers, rather than the verifier, check the owner's
+//! signature on transfers. Signatures are passed as fields of the constraint checker.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use core::marker::PhantomData;
+
+use money::Coin;
+use parity_scale_codec::{Decode, Encode};
+use scale_info::TypeInfo;
+use serde::{Deserialize, Serialize};
+use sp_core::{
+    sr25519::{Public, Signature},
+    H256,
+};
+use sp_runtime::{
+    traits::{BlakeTwo256, Hash},
+    transaction_validity::TransactionPriority,
+};
+use sp_std::prelude::*;
+use tuxedo_core::{
+    dynamic_typing::{DynamicallyTypedData, UtxoData},
+    ensure,
+    support_macros::{CloneNoBound, DebugNoBound, DefaultNoBound},
+    types::Output,
+    verifier::{SigCheck, UpForGrabs},
+    CheckerContext, ConstraintChecker, SimpleConstraintChecker, Verifier,
+};
+
+#[cfg(test)]
+mod tests;
+
+/// Configuration items for the name service piece when it is
+/// instantiated in a concrete runtime.
+pub trait NameServiceConfig {
+    /// A means of getting the current block height.
+    /// Probably this will be the Tuxedo Executive
+    fn block_height() -> u32;
+
+    /// The minimum deposit that must be locked in a name record.
+    const MIN_DEPOSIT: u128 = 100;
+
+    /// The value that must be left over to renew a name. Like any surplus, it pays the renewal's
+    /// fee and tip.
+    const RENEWAL_FEE: u128 = 10;
+
+    /// The maximum length of a name, in bytes.
+    const MAX_NAME_LENGTH: usize = 32;
+
+    /// The number of blocks that a registration or renewal lasts.
+    ///
+    /// Default is roughly one year with 6 second block times.
+    const REGISTRATION_PERIOD: u32 = 5_256_000;
+
+    /// The number of blocks after expiry during which the owner may still renew a name
+    /// before anyone else may release it.
+    ///
+    /// Default is roughly one week with 6 second block times.
+    const GRACE_PERIOD: u32 = 100_800;
+
+    /// The maximum number of blocks between committing to a name and revealing it.
+    ///
+    /// Default is roughly one day with 6 second block times.
+    const MAX_COMMITMENT_AGE: u32 = 14_400;
+}
+
+/// The salted hash that a registrant commits to before revealing the name they want.
+pub fn commitment(name: &[u8], owner: H256, salt: H256) -> H256 {
+    BlakeTwo256::hash_of(&(name, owner, salt))
+}
+
+/// A commitment to register a name that has not been revealed yet.
+#[derive(
+    Serialize,
+    Deserialize,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Clone,
+    Encode,
+    Decode,
+    Hash,
+    Debug,
+    TypeInfo,
+)]
+pub struct NameCommitment<const ID: u8> {
+    /// The salted hash of the name and its owner. See [`commitment`].
+    pub commitment: H256,
+    /// The block height at which the commitment was made.
+    pub committed_at: u32,
+}
+
+impl<const ID: u8> UtxoData for NameCommitment<ID> {
+    const TYPE_ID: [u8; 4] = [b'n', b'c', b'm', ID];
+}
+
+/// A registered name and the key that it resolves to.
+///
+/// Records form a linked list sorted by name. Each record knows the name that follows it.
+#[derive(
+    Serialize,
+    Deserialize,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Clone,
+    Encode,
+    Decode,
+    Hash,
+    Debug,
+    TypeInfo,
+)]
+pub struct NameRecord<const ID: u8> {
+    /// The registered name.
+    pub name: Vec<u8>,
+    /// The next registered name, in sorted order, if any.
+    pub next: Option<Vec<u8>>,
+    /// The key that owns this name and may transfer it.
+    pub owner: H256,
+    /// The key that this name resolves to.
+    pub target: H256,
+    /// The value locked in this record.
+    pub deposit: u128,
+    /// The block height at which this registration expires.
+    pub expires_at: u32,
+}
+
+impl<const ID: u8> NameRecord<ID> {
+    /// The root of the list of names, which must be included in the genesis block.
+    ///
+    /// The root has an empty name, so it sorts before every other record. It is owned
+    /// by nobody and never expires.
+    pub fn root() -> Self {
+        Self {
+            name: Vec::new(),
+            next: None,
+            owner: H256::zero(),
+            target: H256::zero(),
+            deposit: 0,
+            expires_at: u32::MAX,
+        }
+    }
+
+    /// Whether this record is the root of the list.
+    pub fn is_root(&self) -> bool {
+        self.name.is_empty()
+    }
+
+    /// Whether this record has expired and may no longer be resolved.
+    pub fn is_expired(&self, height: u32) -> bool {
+        height >= self.expires_at
+    }
+
+    /// Whether the grace period has passed and anyone may release this record.
+    pub fn is_releasable<T: NameServiceConfig>(&self, height: u32) -> bool {
+        height >= self.expires_at.saturating_add(T::GRACE_PERIOD)
+    }
+
+    /// The message that the owner must sign to replace this record with the new one.
+    pub fn transfer_message(&self, new: &Self) -> Vec<u8> {
+        (b"name", self, new).encode()
+    }
+
+    /// The message that the owner must sign to release this record before it expires.
+    pub fn release_message(&self) -> Vec<u8> {
+        (b"release", self).encode()
+    }
+}
+
+impl<const ID: u8> UtxoData for NameRecord<ID> {
+    const TYPE_ID: [u8; 4] = [b'n', b'a', b'm', ID];
+}
+
+/// Errors that can occur when checking name service transactions.
+#[derive(
+    Serialize,
+    Deserialize,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Clone,
+    Encode,
+    Decode,
+    Hash,
+    Debug,
+    TypeInfo,
+)]
+pub enum ConstraintCheckerError {
+    /// An input data has the wrong type.
+    BadlyTypedInput,
+    /// An output data has the wrong type.
+    BadlyTypedOutput,
+    /// Wrong number of inputs were provided to the constraint checker.
+    WrongNumberInputs,
+    /// Wrong number of outputs were provided to the constraint checker.
+    WrongNumberOutputs,
+    /// A coin with zero value was created. This is not allowed because it wastes state space.
+    ZeroValue,
+    /// The value consumed or created by this transaction overflows the value type.
+    ValueOverflow,
+    /// More value was created than was consumed.
+    OutputsExceedInputs,
+    /// A new commitment does not record the current block as its commitment height.
+    WrongCommitmentHeight,
+    /// The commitment was made in this block, so revealing it now would allow front-running.
+    CommitmentTooNew,
+    /// The commitment is older than the maximum commitment age.
+    CommitmentTooOld,
+    /// The revealed name, owner, and salt do not match the commitment.
+    CommitmentMismatch,
+    /// The name is empty, too long, or contains characters other than
+    /// lowercase ascii letters, digits, and hyphens.
+    InvalidName,
+    /// The new name does not belong directly after the predecessor, so it may already be taken.
+    NotAdjacent,
+    /// The predecessor was changed in some way other than linking or unlinking a name.
+    PredecessorModified,
+    /// The record's deposit is below the minimum.
+    DepositTooLow,
+    /// The record does not expire exactly one registration period after the current block.
+    WrongExpiry,
+    /// The name has expired and may no longer be transferred.
+    Expired,
+    /// The grace period has not passed, so only the owner may release the name.
+    NotReleasable,
+    /// Less than the renewal fee was left over.
+    InsufficientFee,
+    /// A change was made to the name, list link, deposit, or expiry of a record
+    /// that the transaction is not allowed to make.
+    ImmutableFieldChanged,
+    /// The record was locked to a verifier other than `UpForGrabs`.
+    RecordNotUpForGrabs,
+    /// The owner's signature is missing or invalid.
+    BadSignature,
+    /// The refunded deposit was locked to some verifier other than the owner's.
+    PaidToWrongVerifier,
+}
+
+/// Check that the given key signed the given message.
+fn check_signature(signer: H256, message: &[u8], signature: &Signature) -> bool {
+    sp_io::crypto::sr25519_verify(signature, message, &Public::from_h256(signer))
+}
+
+/// Make sure a name is non-empty, within the length limit, and uses only
+/// lowercase ascii letters, digits, and hyphens.
+fn check_name<T: NameServiceConfig>(name: &[u8]) -> Result<(), ConstraintCheckerError> {
+    ensure!(
+        !name.is_empty()
+            && name.len() <= T::MAX_NAME_LENGTH
+            && name
+                .iter()
+                .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || *c == b'-'),
+        ConstraintCheckerError::InvalidName
+    );
+    Ok(())
+}
+
+/// Extract a name record from an output and make sure it is protected by `UpForGrabs`.
+fn extract_record<V: Verifier + PartialEq + From<UpForGrabs>, const ID: u8>(
+    output: &Output<V>,
+) -> Result<NameRecord<ID>, ConstraintCheckerError> {
+    let record = output
+        .payload
+        .extract::<NameRecord<ID>>()
+        .map_err(|_| ConstraintCheckerError::BadlyTypedOutput)?;
+    ensure!(
+        output.verifier == UpForGrabs.into(),
+        ConstraintCheckerError::RecordNotUpForGrabs
+    );
+    Ok(record)
+}
+
+/// Sum the values of some `Coin<ID>` inputs.
+fn input_value<V, const ID: u8>(inputs: &[Output<V>]) -> Result<u128, ConstraintCheckerError> {
+    inputs.iter().try_fold(0u128, |total, input| {
+        let coin = input
+            .payload
+            .extract::<Coin<ID>>()
+            .map_err(|_| ConstraintCheckerError::BadlyTypedInput)?;
+        total
+            .checked_add(coin.0)
+            .ok_or(ConstraintCheckerError::ValueOverflow)
+    })
+}
+
+/// Sum the values of some `Coin<ID>` outputs, none of which may be zero.
+fn output_value<V, const ID: u8>(outputs: &[Output<V>]) -> Result<u128, ConstraintCheckerError> {
+    outputs.iter().try_fold(0u128, |total, output| {
+        let coin = output
+            .payload
+            .extract::<Coin<ID>>()
+            .map_err(|_| ConstraintCheckerError::BadlyTypedOutput)?;
+        ensure!(coin.0 > 0, ConstraintCheckerError::ZeroValue);
+        total
+            .checked_add(coin.0)
+            .ok_or(ConstraintCheckerError::ValueOverflow)
+    })
+}
+
+/// A constraint checker that commits to registering a name without revealing it.
+///
+/// There are no inputs, and the only output is the commitment. It may be protected by any
+/// verifier, and it is typically locked to the registrant.
+#[derive(
+    Serialize,
+    Deserialize,
+    Encode,
+    Decode,
+    DebugNoBound,
+    DefaultNoBound,
+    PartialEq,
+    Eq,
+    CloneNoBound,
+    TypeInfo,
+)]
+#[scale_info(skip_type_params(T))]
+pub struct Commit<T, const ID: u8>(PhantomData<T>);
+
+impl<T: NameServiceConfig, const ID: u8> SimpleConstraintChecker for Commit<T, ID> {
+    type Error = ConstraintCheckerError;
+
+    fn check(
+        &self,
+        input_data: &[DynamicallyTypedData],
+        _peeks: &[DynamicallyTypedData],
+        output_data: &[DynamicallyTypedData],
+        _context: &CheckerContext,
+    ) -> Result<TransactionPriority, Self::Error> {
+        ensure!(
+            input_data.is_empty(),
+            ConstraintCheckerError::WrongNumberInputs
+        );
+        ensure!(
+            output_data.len() == 1,
+            ConstraintCheckerError::WrongNumberOutputs
+        );
+        let commitment = output_data[0]
+            .extract::<NameCommitment<ID>>()
+            .map_err(|_| ConstraintCheckerError::BadlyTypedOutput)?;
+        ensure!(
+            commitment.committed_at == T::block_height(),
+            ConstraintCheckerError::WrongCommitmentHeight
+        );
+
+        Ok(0)
+    }
+}
+
+/// A constraint checker that reveals a commitment and registers the name.
+///
+/// The first input is the commitment and the second is the record that precedes the new name
+/// in the list. The remaining inputs are `Coin<ID>`s to pay the deposit. The first output is the
+/// predecessor, now linked to the new name, and the second is the new record. The remaining
+/// outputs are optionally `Coin<ID>` change. Coins that are neither deposited nor returned as
+/// change pay the transaction's fee and tip.
+#[derive(
+    Serialize, Deserialize, Encode, Decode, DebugNoBound, PartialEq, Eq, CloneNoBound, TypeInfo,
+)]
+#[scale_info(skip_type_params(T))]
+pub struct Register<T, const ID: u8> {
+    /// The salt that was used to hide the name in the commitment.
+    pub salt: H256,
+    _phantom: PhantomData<T>,
+}
+
+impl<T, const ID: u8> Register<T, ID> {
+    pub fn new(salt: H256) -> Self {
+        Self {
+            salt,
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<T: NameServiceConfig, V: Verifier + PartialEq + From<UpForGrabs>, const ID: u8>
+    ConstraintChecker<V> for Register<T, ID>
+{
+    type Error = ConstraintCheckerError;
+    type InherentHooks = ();
+
+    fn check(
+        &self,
+        inputs: &[Output<V>],
+        _peeks: &[Output<V>],
+        outputs: &[Output<V>],
+        _context: &CheckerContext,
+    ) -> Result<TransactionPriority, Self::Error> {
+        ensure!(inputs.len() >= 2, ConstraintCheckerError::WrongNumberInputs);
+        ensure!(
+            outputs.len() >= 2,
+            ConstraintCheckerError::WrongNumberOutputs
+        );
+        let commitment = inputs[0]
+            .payload
+            .extract::<NameCommitment<ID>>()
+            .map_err(|_| ConstraintCheckerError::BadlyTypedInput)?;
+        let old_predecessor = inputs[1]
+            .payload
+            .extract::<NameRecord<ID>>()
+            .map_err(|_| ConstraintCheckerError::BadlyTypedInput)?;
+        let new_predecessor = extract_record::<V, ID>(&outputs[0])?;
+        let record = extract_record::<V, ID>(&outputs[1])?;
+
+        // Check the commitment
+        let height = T::block_height();
+        ensure!(
+            commitment.committed_at < height,
+            ConstraintCheckerError::CommitmentTooNew
+        );
+        ensure!(
+            height - commitment.committed_at <= T::MAX_COMMITMENT_AGE,
+            ConstraintCheckerError::CommitmentTooOld
+        );
+        ensure!(
+            commitment.commitment == self::commitment(&record.name, record.owner, self.salt),
+            ConstraintCheckerError::CommitmentMismatch
+        );
+
+        // Check that the name is free by linking it in after its predecessor
+        check_name::<T>(&record.name)?;
+        ensure!(
+            old_predecessor.name < record.name
+                && old_predecessor
+                    .next
+                    .as_ref()
+                    .map_or(true, |next| &record.name < next),
+            ConstraintCheckerError::NotAdjacent
+        );
+        ensure!(
+            new_predecessor
+                == NameRecord {
+                    next: Some(record.name.clone()),
+                    ..old_predecessor.clone()
+                },
+            ConstraintCheckerError::PredecessorModified
+        );
+        ensure!(
+            record.next == old_predecessor.next,
+            ConstraintCheckerError::NotAdjacent
+        );
+
+        // Check the new record's terms
+        ensure!(
+            record.deposit >= T::MIN_DEPOSIT,
+            ConstraintCheckerError::DepositTooLow
+        );
+        ensure!(
+            record.expires_at == height.saturating_add(T::REGISTRATION_PERIOD),
+            ConstraintCheckerError::WrongExpiry
+        );
+
+        // Check that the deposit is paid
+        let total_input = input_value::<V, ID>(&inputs[2..])?;
+        let total_output = output_value::<V, ID>(&outputs[2..])?
+            .checked_add(record.deposit)
+            .ok_or(ConstraintCheckerError::ValueOverflow)?;
+        ensure!(
+            total_output <= total_input,
+            ConstraintCheckerError::OutputsExceedInputs
+        );
+
+        let burned = total_input - total_output;
+        Ok(burned.try_into().unwrap_or(TransactionPriority::MAX))
+    }
+
+    fn is_inherent(&self) -> bool {
+        false
+    }
+}
+
+/// A constraint checker that renews a name for another registration period.
+///
+/// Anyone may renew a name by paying the renewal fee. A name can be renewed once it is within
+/// one registration period of expiring, including during the grace period, but not once it has
+/// been released.
+///
+/// The first input is the record and the remaining inputs are `Coin<ID>`s to pay the fee. The
+/// first output is the renewed record and the remaining outputs are optionally `Coin<ID>` change.
+/// At least the renewal fee must be left over. It is not destroyed, but pays the transaction's
+/// fee, and whatever remains is a tip for the block author.
+#[derive(
+    Serialize,
+    Deserialize,
+    Encode,
+    Decode,
+    DebugNoBound,
+    DefaultNoBound,
+    PartialEq,
+    Eq,
+    CloneNoBound,
+    TypeInfo,
+)]
+#[scale_info(skip_type_params(T))]
+pub struct Renew<T, const ID: u8>(PhantomData<T>);
+
+impl<T: NameServiceConfig, V: Verifier + PartialEq + From<UpForGrabs>, const ID: u8>
+    ConstraintChecker<V> for Renew<T, ID>
+{
+    type Error = ConstraintCheckerError;
+    type InherentHooks = ();
+
+    fn check(
+        &self,
+        inputs: &[Output<V>],
+        _peeks: &[Output<V>],
+        outputs: &[Output<V>],
+        _context: &CheckerContext,
+    ) -> Result<TransactionPriority, Self::Error> {
+        let old = inputs
+            .first()
+            .ok_or(ConstraintCheckerError::WrongNumberInputs)?
+            .payload
+            .extract::<NameRecord<ID>>()
+            .map_err(|_| ConstraintCheckerError::BadlyTypedInput)?;
+        let new = extract_record::<V, ID>(
+            outputs
+                .first()
+                .ok_or(ConstraintCheckerError::WrongNumberOutputs)?,
+        )?;
+        ensure!(!old.is_root(), ConstraintCheckerError::InvalidName);
+
+        // Renewal extends the existing registration, so that renewing early loses nothing.
+        // Renewing too far in advance would let names be held indefinitely for a single deposit.
+        let height = T::block_height();
+        ensure!(
+            old.expires_at <= height.saturating_add(T::REGISTRATION_PERIOD),
+            ConstraintCheckerError::WrongExpiry
+        );
+        ensure!(
+            new == NameRecord {
+                expires_at: old
+                    .expires_at
+                    .max(height)
+                    .saturating_add(T::REGISTRATION_PERIOD),
+                ..old
+            },
+            ConstraintCheckerError::ImmutableFieldChanged
+        );
+
+        let total_input = input_value::<V, ID>(&inputs[1..])?;
+        let total_output = output_value::<V, ID>(&outputs[1..])?;
+        ensure!(
+            total_output <= total_input,
+            ConstraintCheckerError::OutputsExceedInputs
+        );
+        let burned = total_input - total_output;
+        ensure!(
+            burned >= T::RENEWAL_FEE,
+            ConstraintCheckerError::InsufficientFee
+        );
+
+        Ok(burned.try_into().unwrap_or(TransactionPriority::MAX))
+    }
+
+    fn is_inherent(&self) -> bool {
+        false
+    }
+}
+
+/// A constraint checker that transfers a name to a new owner or points it at a new key.
+///
+/// The only input is the old record and the only output is the new one. Only the owner and
+/// target may change, and the name must not have expired.
+#[derive(
+    Serialize, Deserialize, Encode, Decode, DebugNoBound, PartialEq, Eq, CloneNoBound, TypeInfo,
+)]
+#[scale_info(skip_type_params(T))]
+pub struct Transfer<T, const ID: u8> {
+    /// The current owner's signature over the old and new records.
+    pub owner_signature: Signature,
+    _phantom: PhantomData<T>,
+}
+
+impl<T, const ID: u8> Transfer<T, ID> {
+    pub fn new(owner_signature: Signature) -> Self {
+        Self {
+            owner_signature,
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<T: NameServiceConfig, V: Verifier + PartialEq + From<UpForGrabs>, const ID: u8>
+    ConstraintChecker<V> for Transfer<T, ID>
+{
+    type Error = ConstraintCheckerError;
+    type InherentHooks = ();
+
+    fn check(
+        &self,
+        inputs: &[Output<V>],
+        _peeks: &[Output<V>],
+        outputs: &[Output<V>],
+        _context: &CheckerContext,
+    ) -> Result<TransactionPriority, Self::Error> {
+        ensure!(inputs.len() == 1, ConstraintCheckerError::WrongNumberInputs);
+        ensure!(
+            outputs.len() == 1,
+            ConstraintCheckerError::WrongNumberOutputs
+        );
+        let old = inputs[0]
+            .payload
+            .extract::<NameRecord<ID>>()
+            .map_err(|_| ConstraintCheckerError::BadlyTypedInput)?;
+        let new = extract_record::<V, ID>(&outputs[0])?;
+
+        ensure!(
+            !old.is_expired(T::block_height()),
+            ConstraintCheckerError::Expired
+        );
+        ensure!(
+            new == NameRecord {
+                owner: new.owner,
+                target: new.target,
+                ..old.clone()
+            },
+            ConstraintCheckerError::ImmutableFieldChanged
+        );
+        ensure!(
+            check_signature(
+                old.owner,
+                &old.transfer_message(&new),
+                &self.owner_signature
+            ),
+            ConstraintCheckerError::BadSignature
+        );
+
+        Ok(0)
+    }
+
+    fn is_inherent(&self) -> bool {
+        false
+    }
+}
+
+/// A constraint checker that releases a name and refunds its deposit to the owner.
+///
+/// Anyone may release a name once its grace period has passed. Before then, the owner may
+/// release it early by providing a signature.
+///
+/// The first input is the predecessor of the released record in the list, and the second is the
+/// released record. The first output is the predecessor, now linked to the released record's
+/// successor. The remaining outputs are `Coin<ID>`s locked to the owner. Whatever part of the
+/// deposit the owner does not take back pays the transaction's fee and tip.
+#[derive(
+    Serialize,
+    Deserialize,
+    Encode,
+    Decode,
+    DebugNoBound,
+    DefaultNoBound,
+    PartialEq,
+    Eq,
+    CloneNoBound,
+    TypeInfo,
+)]
+#[scale_info(skip_type_params(T))]
+pub struct Release<T, const ID: u8> {
+    /// The owner's signature over the released record, if releasing it early.
+    pub owner_signature: Option<Signature>,
+    _phantom: PhantomData<T>,
+}
+
+impl<T, const ID: u8> Release<T, ID> {
+    pub fn new(owner_signature: Option<Signature>) -> Self {
+        Self {
+            owner_signature,
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<
+        T: NameServiceConfig,
+        V: Verifier + PartialEq + From<UpForGrabs> + From<SigCheck>,
+        const ID: u8,
+    > ConstraintChecker<V> for Release<T, ID>
+{
+    type Error = ConstraintCheckerError;
+    type InherentHooks = ();
+
+    fn check(
+        &self,
+        inputs: &[Output<V>],
+        _peeks: &[Output<V>],
+        outputs: &[Output<V>],
+        _context: &CheckerContext,
+    ) -> Result<TransactionPriority, Self::Error> {
+        ensure!(inputs.len() == 2, ConstraintCheckerError::WrongNumberInputs);
+        let old_predecessor = inputs[0]
+            .payload
+            .extract::<NameRecord<ID>>()
+            .map_err(|_| ConstraintCheckerError::BadlyTypedInput)?;
+        let record = inputs[1]
+            .payload
+            .extract::<NameRecord<ID>>()
+            .map_err(|_| ConstraintCheckerError::BadlyTypedInput)?;
+        let new_predecessor = extract_record::<V, ID>(
+            outputs
+                .first()
+                .ok_or(ConstraintCheckerError::WrongNumberOutputs)?,
+        )?;
+
+        // Only the owner may release the name before the grace period has passed.
+        if !record.is_releasable::<T>(T::block_height()) {
+            let signature = self
+                .owner_signature
+                .as_ref()
+                .ok_or(ConstraintCheckerError::NotReleasable)?;
+            ensure!(
+                check_signature(record.owner, &record.release_message(), signature),
+                ConstraintCheckerError::BadSignature
+            );
+        }
+
+        // Unlink the record from the list
+        ensure!(
+            !record.is_root() && old_predecessor.next.as_ref() == Some(&record.name),
+            ConstraintCheckerError::NotAdjacent
+        );
+        ensure!(
+            new_predecessor
+                == NameRecord {
+                    next: record.next.clone(),
+                    ..old_predecessor
+                },
+            ConstraintCheckerError::PredecessorModified
+        );
+
+        // Refund the deposit
+        let owner: V = SigCheck::new(record.owner).into();
+        ensure!(
+            outputs[1..].iter().all(|output| output.verifier == owner),
+            ConstraintCheckerError::PaidToWrongVerifier
+        );
+        let refunded = output_value::<V, ID>(&outputs[1..])?;
+        ensure!(
+            refunded <= record.deposit,
+            ConstraintCheckerError::OutputsExceedInputs
+        );
+
+        let burned = record.deposit - refunded;
+        Ok(burned.try_into().unwrap_or(TransactionPriority::MAX))
+    }
+
+    fn is_inherent(&self) -> bool {
+        false
+    }
+}
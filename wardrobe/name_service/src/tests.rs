@@ -0,0 +1,570 @@
+//! Unit tests for the Name Service piece
+
+use super::*;
+use sp_core::{crypto::Pair as _, sr25519::Pair};
+use tuxedo_core::{dynamic_typing::testing::Bogus, tuxedo_verifier};
+
+/// A verifier that can represent name records as well as their owners.
+#[derive(Serialize, Deserialize, Encode, Decode, Debug, PartialEq, Eq, Clone, TypeInfo)]
+#[tuxedo_verifier]
+pub enum TestVerifier {
+    SigCheck(SigCheck),
+    UpForGrabs(UpForGrabs),
+}
+
+/// A mock config that always reports the same block height and uses short periods.
+pub struct AtHeight<const H: u32>;
+
+impl<const H: u32> NameServiceConfig for AtHeight<H> {
+    fn block_height() -> u32 {
+        H
+    }
+
+    const REGISTRATION_PERIOD: u32 = 1000;
+    const GRACE_PERIOD: u32 = 100;
+    const MAX_COMMITMENT_AGE: u32 = 10;
+}
+
+fn alice() -> Pair {
+    Pair::from_seed(&[1u8; 32])
+}
+
+fn bob() -> Pair {
+    Pair::from_seed(&[2u8; 32])
+}
+
+fn owned_by(pair: &Pair) -> TestVerifier {
+    SigCheck::new(pair.public()).into()
+}
+
+fn up_for_grabs() -> TestVerifier {
+    UpForGrabs.into()
+}
+
+fn salt() -> H256 {
+    H256::repeat_byte(7)
+}
+
+fn coin(value: u128, verifier: TestVerifier) -> Output<TestVerifier> {
+    (Coin::<0>(value), verifier).into()
+}
+
+fn record_output(record: NameRecord<0>) -> Output<TestVerifier> {
+    (record, up_for_grabs()).into()
+}
+
+/// A record for the given name owned by alice that expires at block 1000.
+fn record(name: &[u8]) -> NameRecord<0> {
+    NameRecord {
+        name: name.to_vec(),
+        next: None,
+        owner: alice().public().into(),
+        target: alice().public().into(),
+        deposit: 100,
+        expires_at: 1000,
+    }
+}
+
+fn check<C: ConstraintChecker<TestVerifier>>(
+    checker: C,
+    inputs: Vec<Output<TestVerifier>>,
+    outputs: Vec<Output<TestVerifier>>,
+) -> Result<TransactionPriority, C::Error> {
+    checker.check(&inputs, &[], &outputs, &Default::default())
+}
+
+#[test]
+fn commit_works() {
+    let commitment = NameCommitment::<0> {
+        commitment: commitment(b"alice", alice().public().into(), salt()),
+        committed_at: 3,
+    };
+
+    assert_eq!(
+        SimpleConstraintChecker::check(
+            &Commit::<AtHeight<3>, 0>::default(),
+            &[],
+            &[],
+            &[commitment.into()],
+            &Default::default()
+        ),
+        Ok(0)
+    );
+}
+
+#[test]
+fn commit_backdated_fails() {
+    let commitment = NameCommitment::<0> {
+        commitment: commitment(b"alice", alice().public().into(), salt()),
+        committed_at: 2,
+    };
+
+    assert_eq!(
+        SimpleConstraintChecker::check(
+            &Commit::<AtHeight<3>, 0>::default(),
+            &[],
+            &[],
+            &[commitment.into()],
+            &Default::default()
+        ),
+        Err(ConstraintCheckerError::WrongCommitmentHeight)
+    );
+}
+
+#[test]
+fn commit_with_bogus_output_fails() {
+    assert_eq!(
+        SimpleConstraintChecker::check(
+            &Commit::<AtHeight<3>, 0>::default(),
+            &[],
+            &[],
+            &[Bogus.into()],
+            &Default::default()
+        ),
+        Err(ConstraintCheckerError::BadlyTypedOutput)
+    );
+}
+
+/// Register `name` for alice at block `H`, after a commitment at block 3 to `committed_name`,
+/// between `predecessor` and its successor.
+fn register<const H: u32>(
+    committed_name: &[u8],
+    predecessor: NameRecord<0>,
+    mut new: NameRecord<0>,
+    payment: u128,
+) -> Result<TransactionPriority, ConstraintCheckerError> {
+    let commitment = NameCommitment::<0> {
+        commitment: commitment(committed_name, alice().public().into(), salt()),
+        committed_at: 3,
+    };
+    new.next = predecessor.next.clone();
+    let linked = NameRecord {
+        next: Some(new.name.clone()),
+        ..predecessor.clone()
+    };
+
+    check(
+        Register::<AtHeight<H>, 0>::new(salt()),
+        vec![
+            (commitment, owned_by(&alice())).into(),
+            record_output(predecessor),
+            coin(payment, owned_by(&alice())),
+        ],
+        vec![
+            record_output(linked),
+            record_output(new),
+            coin(payment - 100, owned_by(&alice())),
+        ],
+    )
+}
+
+/// A record for alice registered at block 4.
+fn fresh(name: &[u8]) -> NameRecord<0> {
+    NameRecord {
+        expires_at: 1004,
+        ..record(name)
+    }
+}
+
+#[test]
+fn register_after_root_works() {
+    assert_eq!(
+        register::<4>(b"alice", NameRecord::root(), fresh(b"alice"), 150),
+        Ok(0)
+    );
+}
+
+#[test]
+fn register_between_names_works() {
+    let predecessor = NameRecord {
+        next: Some(b"carol".to_vec()),
+        ..record(b"alice")
+    };
+
+    assert_eq!(
+        register::<4>(b"bob", predecessor, fresh(b"bob"), 150),
+        Ok(0)
+    );
+}
+
+#[test]
+fn register_taken_name_fails() {
+    assert_eq!(
+        register::<4>(b"alice", record(b"alice"), fresh(b"alice"), 150),
+        Err(ConstraintCheckerError::NotAdjacent)
+    );
+}
+
+#[test]
+fn register_skipping_ahead_in_list_fails() {
+    let predecessor = NameRecord {
+        next: Some(b"bob".to_vec()),
+        ..NameRecord::root()
+    };
+
+    assert_eq!(
+        register::<4>(b"carol", predecessor, fresh(b"carol"), 150),
+        Err(ConstraintCheckerError::NotAdjacent)
+    );
+}
+
+#[test]
+fn register_in_commitment_block_fails() {
+    let new = NameRecord {
+        expires_at: 1003,
+        ..record(b"alice")
+    };
+
+    assert_eq!(
+        register::<3>(b"alice", NameRecord::root(), new, 150),
+        Err(ConstraintCheckerError::CommitmentTooNew)
+    );
+}
+
+#[test]
+fn register_with_stale_commitment_fails() {
+    let new = NameRecord {
+        expires_at: 1014,
+        ..record(b"alice")
+    };
+
+    assert_eq!(
+        register::<14>(b"alice", NameRecord::root(), new, 150),
+        Err(ConstraintCheckerError::CommitmentTooOld)
+    );
+}
+
+#[test]
+fn register_other_name_than_committed_fails() {
+    assert_eq!(
+        register::<4>(b"alicia", NameRecord::root(), fresh(b"alice"), 150),
+        Err(ConstraintCheckerError::CommitmentMismatch)
+    );
+}
+
+#[test]
+fn register_invalid_name_fails() {
+    assert_eq!(
+        register::<4>(b"Alice", NameRecord::root(), fresh(b"Alice"), 150),
+        Err(ConstraintCheckerError::InvalidName)
+    );
+}
+
+#[test]
+fn register_with_long_expiry_fails() {
+    let new = NameRecord {
+        expires_at: 2000,
+        ..record(b"alice")
+    };
+
+    assert_eq!(
+        register::<4>(b"alice", NameRecord::root(), new, 150),
+        Err(ConstraintCheckerError::WrongExpiry)
+    );
+}
+
+#[test]
+fn register_with_small_deposit_fails() {
+    let new = NameRecord {
+        deposit: 99,
+        ..fresh(b"alice")
+    };
+
+    assert_eq!(
+        register::<4>(b"alice", NameRecord::root(), new, 150),
+        Err(ConstraintCheckerError::DepositTooLow)
+    );
+}
+
+#[test]
+fn register_underpaid_fails() {
+    let new = NameRecord {
+        deposit: 200,
+        ..fresh(b"alice")
+    };
+
+    assert_eq!(
+        register::<4>(b"alice", NameRecord::root(), new, 150),
+        Err(ConstraintCheckerError::OutputsExceedInputs)
+    );
+}
+
+#[test]
+fn register_modifying_predecessor_fails() {
+    let commitment = NameCommitment::<0> {
+        commitment: commitment(b"bob", alice().public().into(), salt()),
+        committed_at: 3,
+    };
+    let predecessor = record(b"alice");
+    let hijacked = NameRecord {
+        next: Some(b"bob".to_vec()),
+        owner: bob().public().into(),
+        ..predecessor.clone()
+    };
+
+    assert_eq!(
+        check(
+            Register::<AtHeight<4>, 0>::new(salt()),
+            vec![
+                (commitment, owned_by(&alice())).into(),
+                record_output(predecessor),
+                coin(100, owned_by(&alice())),
+            ],
+            vec![record_output(hijacked), record_output(fresh(b"bob"))],
+        ),
+        Err(ConstraintCheckerError::PredecessorModified)
+    );
+}
+
+#[test]
+fn register_record_locked_to_owner_fails() {
+    let commitment = NameCommitment::<0> {
+        commitment: commitment(b"alice", alice().public().into(), salt()),
+        committed_at: 3,
+    };
+    let linked = NameRecord {
+        next: Some(b"alice".to_vec()),
+        ..NameRecord::root()
+    };
+
+    assert_eq!(
+        check(
+            Register::<AtHeight<4>, 0>::new(salt()),
+            vec![
+                (commitment, owned_by(&alice())).into(),
+                record_output(NameRecord::root()),
+                coin(100, owned_by(&alice())),
+            ],
+            vec![
+                record_output(linked),
+                (fresh(b"alice"), owned_by(&alice())).into()
+            ],
+        ),
+        Err(ConstraintCheckerError::RecordNotUpForGrabs)
+    );
+}
+
+fn renew<const H: u32>(
+    old: NameRecord<0>,
+    new_expiry: u32,
+    payment: u128,
+) -> Result<TransactionPriority, ConstraintCheckerError> {
+    let new = NameRecord {
+        expires_at: new_expiry,
+        ..old.clone()
+    };
+
+    check(
+        Renew::<AtHeight<H>, 0>::default(),
+        vec![record_output(old), coin(payment, owned_by(&bob()))],
+        vec![record_output(new)],
+    )
+}
+
+#[test]
+fn renew_works() {
+    assert_eq!(renew::<500>(record(b"alice"), 2000, 10), Ok(10));
+}
+
+#[test]
+fn renew_during_grace_period_works() {
+    assert_eq!(renew::<1050>(record(b"alice"), 2050, 10), Ok(10));
+}
+
+#[test]
+fn renew_too_early_fails() {
+    let old = NameRecord {
+        expires_at: 1600,
+        ..record(b"alice")
+    };
+
+    assert_eq!(
+        renew::<500>(old, 2600, 10),
+        Err(ConstraintCheckerError::WrongExpiry)
+    );
+}
+
+#[test]
+fn renew_with_extra_time_fails() {
+    assert_eq!(
+        renew::<500>(record(b"alice"), 2001, 10),
+        Err(ConstraintCheckerError::ImmutableFieldChanged)
+    );
+}
+
+#[test]
+fn renew_without_fee_fails() {
+    assert_eq!(
+        renew::<500>(record(b"alice"), 2000, 9),
+        Err(ConstraintCheckerError::InsufficientFee)
+    );
+}
+
+fn transfer<const H: u32>(
+    old: NameRecord<0>,
+    new: NameRecord<0>,
+    signer: &Pair,
+) -> Result<TransactionPriority, ConstraintCheckerError> {
+    let signature = signer.sign(&old.transfer_message(&new));
+    check(
+        Transfer::<AtHeight<H>, 0>::new(signature),
+        vec![record_output(old)],
+        vec![record_output(new)],
+    )
+}
+
+#[test]
+fn transfer_works() {
+    let new = NameRecord {
+        owner: bob().public().into(),
+        target: bob().public().into(),
+        ..record(b"alice")
+    };
+
+    assert_eq!(transfer::<500>(record(b"alice"), new, &alice()), Ok(0));
+}
+
+#[test]
+fn retarget_works() {
+    let new = NameRecord {
+        target: bob().public().into(),
+        ..record(b"alice")
+    };
+
+    assert_eq!(transfer::<500>(record(b"alice"), new, &alice()), Ok(0));
+}
+
+#[test]
+fn transfer_signed_by_someone_else_fails() {
+    let new = NameRecord {
+        owner: bob().public().into(),
+        ..record(b"alice")
+    };
+
+    assert_eq!(
+        transfer::<500>(record(b"alice"), new, &bob()),
+        Err(ConstraintCheckerError::BadSignature)
+    );
+}
+
+#[test]
+fn transfer_extending_expiry_fails() {
+    let new = NameRecord {
+        expires_at: 5000,
+        ..record(b"alice")
+    };
+
+    assert_eq!(
+        transfer::<500>(record(b"alice"), new, &alice()),
+        Err(ConstraintCheckerError::ImmutableFieldChanged)
+    );
+}
+
+#[test]
+fn transfer_expired_name_fails() {
+    let new = NameRecord {
+        owner: bob().public().into(),
+        ..record(b"alice")
+    };
+
+    assert_eq!(
+        transfer::<1000>(record(b"alice"), new, &alice()),
+        Err(ConstraintCheckerError::Expired)
+    );
+}
+
+fn release<const H: u32>(
+    signer: Option<&Pair>,
+    refund_to: &Pair,
+) -> Result<TransactionPriority, ConstraintCheckerError> {
+    let released = NameRecord {
+        next: Some(b"carol".to_vec()),
+        ..record(b"bob")
+    };
+    let predecessor = NameRecord {
+        next: Some(b"bob".to_vec()),
+        ..NameRecord::root()
+    };
+    let unlinked = NameRecord {
+        next: Some(b"carol".to_vec()),
+        ..NameRecord::root()
+    };
+    let signature = signer.map(|pair| pair.sign(&released.release_message()));
+
+    check(
+        Release::<AtHeight<H>, 0>::new(signature),
+        vec![record_output(predecessor), record_output(released)],
+        vec![record_output(unlinked), coin(100, owned_by(refund_to))],
+    )
+}
+
+#[test]
+fn release_after_grace_period_works() {
+    assert_eq!(release::<1100>(None, &alice()), Ok(0));
+}
+
+#[test]
+fn release_during_grace_period_fails() {
+    assert_eq!(
+        release::<1099>(None, &alice()),
+        Err(ConstraintCheckerError::NotReleasable)
+    );
+}
+
+#[test]
+fn early_release_by_owner_works() {
+    assert_eq!(release::<500>(Some(&alice()), &alice()), Ok(0));
+}
+
+#[test]
+fn early_release_by_someone_else_fails() {
+    assert_eq!(
+        release::<500>(Some(&bob()), &bob()),
+        Err(ConstraintCheckerError::BadSignature)
+    );
+}
+
+#[test]
+fn release_refunding_someone_else_fails() {
+    assert_eq!(
+        release::<1100>(None, &bob()),
+        Err(ConstraintCheckerError::PaidToWrongVerifier)
+    );
+}
+
+#[test]
+fn release_root_fails() {
+    let predecessor = NameRecord::root();
+
+    assert_eq!(
+        check(
+            Release::<AtHeight<500>, 0>::default(),
+            vec![
+                record_output(predecessor.clone()),
+                record_output(predecessor.clone())
+            ],
+            vec![record_output(predecessor)],
+        ),
+        Err(ConstraintCheckerError::NotReleasable)
+    );
+}
+
+#[test]
+fn release_unlinked_record_fails() {
+    let predecessor = NameRecord {
+        next: Some(b"carol".to_vec()),
+        ..NameRecord::root()
+    };
+
+    assert_eq!(
+        check(
+            Release::<AtHeight<1100>, 0>::default(),
+            vec![
+                record_output(predecessor.clone()),
+                record_output(record(b"bob"))
+            ],
+            vec![record_output(predecessor)],
+        ),
+        Err(ConstraintCheckerError::NotAdjacent)
+    );
+}
@@ -0,0 +1,532 @@
+//! General purpose non-fungible tokens with metadata and optional creator royalties.
+//!
+//! A creator first opens a [`Collection`], which is locked to their own key. Items are minted by
+//! consuming and re-creating the collection, so only the creator can mint, and each [`Nft`] gets
+//! a serial number that is unique within its collection. Items point at their metadata with a URI
+//! and commit to its content with a hash.
+//!
+//! Items can be transferred and burned freely. When an item is sold through the [`Sell`]
+//! checker, a share of the price, set by the collection, is paid to the creator as a royalty.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use money::Coin;
+use parity_scale_codec::{Decode, Encode};
+use scale_info::TypeInfo;
+use serde::{Deserialize, Serialize};
+use sp_core::H256;
+use sp_runtime::{
+    traits::{BlakeTwo256, Hash},
+    transaction_validity::TransactionPriority,
+};
+use sp_std::prelude::*;
+use tuxedo_core::{
+    dynamic_typing::{DynamicallyTypedData, UtxoData},
+    ensure,
+    support_macros::{CloneNoBound, DebugNoBound, DefaultNoBound},
+    types::Output,
+    verifier::SigCheck,
+    CheckerContext, ConstraintChecker, SimpleConstraintChecker, Verifier,
+};
+
+#[cfg(test)]
+mod tests;
+
+/// The maximum length of a metadata URI, in bytes.
+pub const MAX_URI_LENGTH: usize = 256;
+
+/// The maximum royalty, in thousandths of the sale price. Royalties above this are not allowed.
+pub const MAX_ROYALTY_PER_THOUSAND: u16 = 1000;
+
+/// A collection of NFTs that share a creator and royalty terms.
+///
+/// The collection UTXO is locked to the creator, and must be consumed to mint new items.
+#[derive(
+    Serialize,
+    Deserialize,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Clone,
+    Encode,
+    Decode,
+    Hash,
+    Debug,
+    TypeInfo,
+)]
+pub struct Collection<const ID: u8> {
+    /// The key of the creator, who mints the items and receives the royalties.
+    pub creator: H256,
+    /// A number chosen by the creator to tell their collections apart.
+    pub nonce: u64,
+    /// The maximum number of items that may ever be minted, if limited.
+    pub max_supply: Option<u32>,
+    /// The number of items that have been minted so far.
+    pub minted: u32,
+    /// The share of every sale that is paid to the creator, in thousandths of the price.
+    pub royalty_per_thousand: u16,
+    /// A URI pointing at metadata about the collection as a whole.
+    pub metadata_uri: Vec<u8>,
+}
+
+impl<const ID: u8> Collection<ID> {
+    /// The id of this collection, which its items refer to.
+    ///
+    /// Because the collection must be locked to the creator, nobody else can create a
+    /// collection with the same id.
+    pub fn id(&self) -> H256 {
+        BlakeTwo256::hash_of(&(self.creator, self.nonce))
+    }
+}
+
+impl<const ID: u8> UtxoData for Collection<ID> {
+    const TYPE_ID: [u8; 4] = [b'c', b'o', b'l', ID];
+}
+
+/// A single non-fungible token.
+#[derive(
+    Serialize,
+    Deserialize,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Clone,
+    Encode,
+    Decode,
+    Hash,
+    Debug,
+    TypeInfo,
+)]
+pub struct Nft<const ID: u8> {
+    /// The id of the collection that this item belongs to.
+    pub collection: H256,
+    /// The serial number of this item within its collection.
+    pub serial: u32,
+    /// The key of the creator, copied from the collection for use in royalty payments.
+    pub creator: H256,
+    /// The royalty owed on sales, copied from the collection.
+    pub royalty_per_thousand: u16,
+    /// A URI pointing at this item's metadata.
+    pub metadata_uri: Vec<u8>,
+    /// The hash of this item's metadata, so that holders can tell if it has changed.
+    pub metadata_hash: H256,
+}
+
+impl<const ID: u8> Nft<ID> {
+    /// The royalty owed to the creator when this item is sold for the given price.
+    pub fn royalty(&self, price: u128) -> u128 {
+        price.saturating_mul(self.royalty_per_thousand.into()) / 1000
+    }
+}
+
+impl<const ID: u8> UtxoData for Nft<ID> {
+    const TYPE_ID: [u8; 4] = [b'n', b'f', b't', ID];
+}
+
+/// Errors that can occur when checking NFT transactions.
+#[derive(
+    Serialize,
+    Deserialize,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Clone,
+    Encode,
+    Decode,
+    Hash,
+    Debug,
+    TypeInfo,
+)]
+pub enum ConstraintCheckerError {
+    /// An input data has the wrong type.
+    BadlyTypedInput,
+    /// An output data has the wrong type.
+    BadlyTypedOutput,
+    /// Wrong number of inputs were provided to the constraint checker.
+    WrongNumberInputs,
+    /// Wrong number of outputs were provided to the constraint checker.
+    WrongNumberOutputs,
+    /// A coin with zero value was created. This is not allowed because it wastes state space.
+    ZeroValue,
+    /// The value consumed or created by this transaction overflows the value type.
+    ValueOverflow,
+    /// More value was created than was consumed.
+    OutputsExceedInputs,
+    /// A new collection has already minted items.
+    MintedNotZero,
+    /// The royalty is more than the whole sale price.
+    RoyaltyTooHigh,
+    /// A metadata URI is longer than allowed.
+    UriTooLong,
+    /// The collection was locked to a verifier other than the creator's.
+    CollectionNotOwnedByCreator,
+    /// Minting must create at least one item.
+    NothingMinted,
+    /// Minting would exceed the collection's maximum supply.
+    SupplyExceeded,
+    /// The collection was changed in some way other than counting the minted items.
+    CollectionModified,
+    /// A minted item does not match its collection or has the wrong serial number.
+    ItemMismatch,
+    /// The transferred items do not match the items that were consumed.
+    ItemsModified,
+    /// The seller was not paid the price minus the royalty.
+    SellerNotPaid,
+    /// The creator was not paid the royalty.
+    RoyaltyNotPaid,
+}
+
+/// Sum the values of some `Coin<ID>` inputs.
+fn input_value<V, const ID: u8>(inputs: &[Output<V>]) -> Result<u128, ConstraintCheckerError> {
+    inputs.iter().try_fold(0u128, |total, input| {
+        let coin = input
+            .payload
+            .extract::<Coin<ID>>()
+            .map_err(|_| ConstraintCheckerError::BadlyTypedInput)?;
+        total
+            .checked_add(coin.0)
+            .ok_or(ConstraintCheckerError::ValueOverflow)
+    })
+}
+
+/// Sum the values of some `Coin<ID>` outputs, none of which may be zero.
+fn output_value<V, const ID: u8>(outputs: &[Output<V>]) -> Result<u128, ConstraintCheckerError> {
+    outputs.iter().try_fold(0u128, |total, output| {
+        let coin = output
+            .payload
+            .extract::<Coin<ID>>()
+            .map_err(|_| ConstraintCheckerError::BadlyTypedOutput)?;
+        ensure!(coin.0 > 0, ConstraintCheckerError::ZeroValue);
+        total
+            .checked_add(coin.0)
+            .ok_or(ConstraintCheckerError::ValueOverflow)
+    })
+}
+
+/// A constraint checker that creates a new collection.
+///
+/// There are no inputs, and the only output is the collection, which must be locked to the
+/// creator's key and have nothing minted yet.
+#[derive(
+    Serialize,
+    Deserialize,
+    Encode,
+    Decode,
+    DebugNoBound,
+    DefaultNoBound,
+    PartialEq,
+    Eq,
+    CloneNoBound,
+    TypeInfo,
+)]
+pub struct CreateCollection<const ID: u8>;
+
+impl<V: Verifier + PartialEq + From<SigCheck>, const ID: u8> ConstraintChecker<V>
+    for CreateCollection<ID>
+{
+    type Error = ConstraintCheckerError;
+    type InherentHooks = ();
+
+    fn check(
+        &self,
+        inputs: &[Output<V>],
+        _peeks: &[Output<V>],
+        outputs: &[Output<V>],
+        _context: &CheckerContext,
+    ) -> Result<TransactionPriority, Self::Error> {
+        ensure!(inputs.is_empty(), ConstraintCheckerError::WrongNumberInputs);
+        ensure!(
+            outputs.len() == 1,
+            ConstraintCheckerError::WrongNumberOutputs
+        );
+        let collection = outputs[0]
+            .payload
+            .extract::<Collection<ID>>()
+            .map_err(|_| ConstraintCheckerError::BadlyTypedOutput)?;
+
+        ensure!(
+            outputs[0].verifier == SigCheck::new(collection.creator).into(),
+            ConstraintCheckerError::CollectionNotOwnedByCreator
+        );
+        ensure!(
+            collection.minted == 0,
+            ConstraintCheckerError::MintedNotZero
+        );
+        ensure!(
+            collection.royalty_per_thousand <= MAX_ROYALTY_PER_THOUSAND,
+            ConstraintCheckerError::RoyaltyTooHigh
+        );
+        ensure!(
+            collection.metadata_uri.len() <= MAX_URI_LENGTH,
+            ConstraintCheckerError::UriTooLong
+        );
+
+        Ok(0)
+    }
+
+    fn is_inherent(&self) -> bool {
+        false
+    }
+}
+
+/// A constraint checker that mints new items in a collection.
+///
+/// The only input is the collection. The first output is the collection with the new items
+/// counted, and the remaining outputs are the new items with consecutive serial numbers.
+#[derive(
+    Serialize,
+    Deserialize,
+    Encode,
+    Decode,
+    DebugNoBound,
+    DefaultNoBound,
+    PartialEq,
+    Eq,
+    CloneNoBound,
+    TypeInfo,
+)]
+pub struct Mint<const ID: u8>;
+
+impl<const ID: u8> SimpleConstraintChecker for Mint<ID> {
+    type Error = ConstraintCheckerError;
+
+    fn check(
+        &self,
+        input_data: &[DynamicallyTypedData],
+        _peeks: &[DynamicallyTypedData],
+        output_data: &[DynamicallyTypedData],
+        _context: &CheckerContext,
+    ) -> Result<TransactionPriority, Self::Error> {
+        ensure!(
+            input_data.len() == 1,
+            ConstraintCheckerError::WrongNumberInputs
+        );
+        let old = input_data[0]
+            .extract::<Collection<ID>>()
+            .map_err(|_| ConstraintCheckerError::BadlyTypedInput)?;
+        let new = output_data
+            .first()
+            .ok_or(ConstraintCheckerError::WrongNumberOutputs)?
+            .extract::<Collection<ID>>()
+            .map_err(|_| ConstraintCheckerError::BadlyTypedOutput)?;
+
+        let items = &output_data[1..];
+        ensure!(!items.is_empty(), ConstraintCheckerError::NothingMinted);
+        let minted = u32::try_from(items.len())
+            .ok()
+            .and_then(|count| old.minted.checked_add(count))
+            .ok_or(ConstraintCheckerError::SupplyExceeded)?;
+        ensure!(
+            old.max_supply.map_or(true, |max| minted <= max),
+            ConstraintCheckerError::SupplyExceeded
+        );
+        ensure!(
+            new == Collection {
+                minted,
+                ..old.clone()
+            },
+            ConstraintCheckerError::CollectionModified
+        );
+
+        let collection = old.id();
+        for (serial, item) in (old.minted..).zip(items) {
+            let item = item
+                .extract::<Nft<ID>>()
+                .map_err(|_| ConstraintCheckerError::BadlyTypedOutput)?;
+            ensure!(
+                item.collection == collection
+                    && item.serial == serial
+                    && item.creator == old.creator
+                    && item.royalty_per_thousand == old.royalty_per_thousand,
+                ConstraintCheckerError::ItemMismatch
+            );
+            ensure!(
+                item.metadata_uri.len() <= MAX_URI_LENGTH,
+                ConstraintCheckerError::UriTooLong
+            );
+        }
+
+        Ok(0)
+    }
+}
+
+/// A constraint checker that transfers items without changing them.
+///
+/// The outputs must be exactly the inputs, in the same order. Only their verifiers may change.
+/// Creators are not paid royalties on transfers. Use [`Sell`] to trade items for coins.
+#[derive(
+    Serialize,
+    Deserialize,
+    Encode,
+    Decode,
+    DebugNoBound,
+    DefaultNoBound,
+    PartialEq,
+    Eq,
+    CloneNoBound,
+    TypeInfo,
+)]
+pub struct Transfer<const ID: u8>;
+
+impl<const ID: u8> SimpleConstraintChecker for Transfer<ID> {
+    type Error = ConstraintCheckerError;
+
+    fn check(
+        &self,
+        input_data: &[DynamicallyTypedData],
+        _peeks: &[DynamicallyTypedData],
+        output_data: &[DynamicallyTypedData],
+        _context: &CheckerContext,
+    ) -> Result<TransactionPriority, Self::Error> {
+        ensure!(
+            !input_data.is_empty(),
+            ConstraintCheckerError::WrongNumberInputs
+        );
+        ensure!(
+            input_data.len() == output_data.len(),
+            ConstraintCheckerError::WrongNumberOutputs
+        );
+        for (input, output) in input_data.iter().zip(output_data) {
+            let input = input
+                .extract::<Nft<ID>>()
+                .map_err(|_| ConstraintCheckerError::BadlyTypedInput)?;
+            let output = output
+                .extract::<Nft<ID>>()
+                .map_err(|_| ConstraintCheckerError::BadlyTypedOutput)?;
+            ensure!(input == output, ConstraintCheckerError::ItemsModified);
+        }
+
+        Ok(0)
+    }
+}
+
+/// A constraint checker that destroys items.
+///
+/// All inputs are items and there are no outputs.
+#[derive(
+    Serialize,
+    Deserialize,
+    Encode,
+    Decode,
+    DebugNoBound,
+    DefaultNoBound,
+    PartialEq,
+    Eq,
+    CloneNoBound,
+    TypeInfo,
+)]
+pub struct Burn<const ID: u8>;
+
+impl<const ID: u8> SimpleConstraintChecker for Burn<ID> {
+    type Error = ConstraintCheckerError;
+
+    fn check(
+        &self,
+        input_data: &[DynamicallyTypedData],
+        _peeks: &[DynamicallyTypedData],
+        output_data: &[DynamicallyTypedData],
+        _context: &CheckerContext,
+    ) -> Result<TransactionPriority, Self::Error> {
+        ensure!(
+            !input_data.is_empty(),
+            ConstraintCheckerError::WrongNumberInputs
+        );
+        ensure!(
+            output_data.is_empty(),
+            ConstraintCheckerError::WrongNumberOutputs
+        );
+        for input in input_data {
+            input
+                .extract::<Nft<ID>>()
+                .map_err(|_| ConstraintCheckerError::BadlyTypedInput)?;
+        }
+
+        Ok(0)
+    }
+}
+
+/// A constraint checker that sells an item for coins and pays the creator's royalty.
+///
+/// The first input is the item and the remaining inputs are the buyer's `Coin<ID>`s. The first
+/// output is the unchanged item, locked to whatever verifier the buyer chooses. The second output
+/// pays the price minus the royalty to the seller. If the royalty is not zero, the third output
+/// pays it to the creator. The remaining outputs are optionally `Coin<ID>` change for the buyer.
+/// The buyer pays the transaction's fee and tip with whatever coins are left over.
+///
+/// The seller authorizes the sale by satisfying the item's verifier, which also
+/// commits them to the price and the seller key in this checker.
+#[derive(Serialize, Deserialize, Encode, Decode, Debug, PartialEq, Eq, Clone, TypeInfo)]
+pub struct Sell<const ID: u8> {
+    /// The price the buyer pays, including the royalty.
+    pub price: u128,
+    /// The key that receives the proceeds of the sale.
+    pub seller: H256,
+}
+
+impl<V: Verifier + PartialEq + From<SigCheck>, const ID: u8> ConstraintChecker<V> for Sell<ID> {
+    type Error = ConstraintCheckerError;
+    type InherentHooks = ();
+
+    fn check(
+        &self,
+        inputs: &[Output<V>],
+        _peeks: &[Output<V>],
+        outputs: &[Output<V>],
+        _context: &CheckerContext,
+    ) -> Result<TransactionPriority, Self::Error> {
+        let item = inputs
+            .first()
+            .ok_or(ConstraintCheckerError::WrongNumberInputs)?
+            .payload
+            .extract::<Nft<ID>>()
+            .map_err(|_| ConstraintCheckerError::BadlyTypedInput)?;
+        let sold = outputs
+            .first()
+            .ok_or(ConstraintCheckerError::WrongNumberOutputs)?
+            .payload
+            .extract::<Nft<ID>>()
+            .map_err(|_| ConstraintCheckerError::BadlyTypedOutput)?;
+        ensure!(item == sold, ConstraintCheckerError::ItemsModified);
+
+        // Check that the seller and creator are paid
+        let royalty = item.royalty(self.price);
+        let proceeds = self.price - royalty;
+        let payments = if royalty == 0 { 1 } else { 2 };
+        ensure!(
+            outputs.len() > payments,
+            ConstraintCheckerError::WrongNumberOutputs
+        );
+        let paid = |output: &Output<V>, amount: u128, recipient: H256| {
+            output.payload.extract::<Coin<ID>>() == Ok(Coin(amount))
+                && output.verifier == SigCheck::new(recipient).into()
+        };
+        ensure!(
+            paid(&outputs[1], proceeds, self.seller),
+            ConstraintCheckerError::SellerNotPaid
+        );
+        if royalty > 0 {
+            ensure!(
+                paid(&outputs[2], royalty, item.creator),
+                ConstraintCheckerError::RoyaltyNotPaid
+            );
+        }
+
+        // Check that the buyer's coins cover the payments and change
+        let total_input = input_value::<V, ID>(&inputs[1..])?;
+        let total_output = output_value::<V, ID>(&outputs[1..])?;
+        ensure!(
+            total_output <= total_input,
+            ConstraintCheckerError::OutputsExceedInputs
+        );
+
+        let burned = total_input - total_output;
+        Ok(burned.try_into().unwrap_or(TransactionPriority::MAX))
+    }
+
+    fn is_inherent(&self) -> bool {
+        false
+    }
+}
@@ -0,0 +1,437 @@
+//! Unit tests for the NFT piece
+
+use super::*;
+use sp_core::{crypto::Pair as _, sr25519::Pair};
+use tuxedo_core::dynamic_typing::testing::Bogus;
+
+fn creator() -> H256 {
+    Pair::from_seed(&[1u8; 32]).public().into()
+}
+
+fn seller() -> H256 {
+    Pair::from_seed(&[2u8; 32]).public().into()
+}
+
+fn buyer() -> H256 {
+    Pair::from_seed(&[3u8; 32]).public().into()
+}
+
+/// A collection of at most 3 items with a 5% royalty.
+fn collection(minted: u32) -> Collection<0> {
+    Collection {
+        creator: creator(),
+        nonce: 0,
+        max_supply: Some(3),
+        minted,
+        royalty_per_thousand: 50,
+        metadata_uri: b"ipfs://collection".to_vec(),
+    }
+}
+
+fn item(serial: u32) -> Nft<0> {
+    Nft {
+        collection: collection(0).id(),
+        serial,
+        creator: creator(),
+        royalty_per_thousand: 50,
+        metadata_uri: b"ipfs://item".to_vec(),
+        metadata_hash: H256::repeat_byte(serial as u8),
+    }
+}
+
+fn coin(value: u128, owner: H256) -> Output<SigCheck> {
+    (Coin::<0>(value), SigCheck::new(owner)).into()
+}
+
+fn check<C: ConstraintChecker<SigCheck>>(
+    checker: C,
+    inputs: Vec<Output<SigCheck>>,
+    outputs: Vec<Output<SigCheck>>,
+) -> Result<TransactionPriority, C::Error> {
+    checker.check(&inputs, &[], &outputs, &Default::default())
+}
+
+#[test]
+fn create_collection_works() {
+    let output = (collection(0), SigCheck::new(creator())).into();
+
+    assert_eq!(check(CreateCollection::<0>, vec![], vec![output]), Ok(0));
+}
+
+#[test]
+fn create_collection_for_someone_else_fails() {
+    let output = (collection(0), SigCheck::new(seller())).into();
+
+    assert_eq!(
+        check(CreateCollection::<0>, vec![], vec![output]),
+        Err(ConstraintCheckerError::CollectionNotOwnedByCreator)
+    );
+}
+
+#[test]
+fn create_collection_with_items_fails() {
+    let output = (collection(1), SigCheck::new(creator())).into();
+
+    assert_eq!(
+        check(CreateCollection::<0>, vec![], vec![output]),
+        Err(ConstraintCheckerError::MintedNotZero)
+    );
+}
+
+#[test]
+fn create_collection_with_excessive_royalty_fails() {
+    let greedy = Collection {
+        royalty_per_thousand: 1001,
+        ..collection(0)
+    };
+    let output = (greedy, SigCheck::new(creator())).into();
+
+    assert_eq!(
+        check(CreateCollection::<0>, vec![], vec![output]),
+        Err(ConstraintCheckerError::RoyaltyTooHigh)
+    );
+}
+
+#[test]
+fn create_collection_with_long_uri_fails() {
+    let verbose = Collection {
+        metadata_uri: vec![b'a'; MAX_URI_LENGTH + 1],
+        ..collection(0)
+    };
+    let output = (verbose, SigCheck::new(creator())).into();
+
+    assert_eq!(
+        check(CreateCollection::<0>, vec![], vec![output]),
+        Err(ConstraintCheckerError::UriTooLong)
+    );
+}
+
+#[test]
+fn mint_works() {
+    let outputs = vec![collection(2).into(), item(0).into(), item(1).into()];
+
+    assert_eq!(
+        SimpleConstraintChecker::check(
+            &Mint::<0>,
+            &[collection(0).into()],
+            &[],
+            &outputs,
+            &Default::default()
+        ),
+        Ok(0)
+    );
+}
+
+#[test]
+fn mint_continues_serials() {
+    let outputs = vec![collection(2).into(), item(1).into()];
+
+    assert_eq!(
+        SimpleConstraintChecker::check(
+            &Mint::<0>,
+            &[collection(1).into()],
+            &[],
+            &outputs,
+            &Default::default()
+        ),
+        Ok(0)
+    );
+}
+
+#[test]
+fn mint_nothing_fails() {
+    assert_eq!(
+        SimpleConstraintChecker::check(
+            &Mint::<0>,
+            &[collection(0).into()],
+            &[],
+            &[collection(0).into()],
+            &Default::default()
+        ),
+        Err(ConstraintCheckerError::NothingMinted)
+    );
+}
+
+#[test]
+fn mint_beyond_max_supply_fails() {
+    let outputs = vec![collection(4).into(), item(2).into(), item(3).into()];
+
+    assert_eq!(
+        SimpleConstraintChecker::check(
+            &Mint::<0>,
+            &[collection(2).into()],
+            &[],
+            &outputs,
+            &Default::default()
+        ),
+        Err(ConstraintCheckerError::SupplyExceeded)
+    );
+}
+
+#[test]
+fn mint_without_counting_fails() {
+    let outputs = vec![collection(0).into(), item(0).into()];
+
+    assert_eq!(
+        SimpleConstraintChecker::check(
+            &Mint::<0>,
+            &[collection(0).into()],
+            &[],
+            &outputs,
+            &Default::default()
+        ),
+        Err(ConstraintCheckerError::CollectionModified)
+    );
+}
+
+#[test]
+fn mint_duplicate_serial_fails() {
+    let outputs = vec![collection(2).into(), item(0).into(), item(0).into()];
+
+    assert_eq!(
+        SimpleConstraintChecker::check(
+            &Mint::<0>,
+            &[collection(0).into()],
+            &[],
+            &outputs,
+            &Default::default()
+        ),
+        Err(ConstraintCheckerError::ItemMismatch)
+    );
+}
+
+#[test]
+fn mint_without_royalty_fails() {
+    let free = Nft {
+        royalty_per_thousand: 0,
+        ..item(0)
+    };
+    let outputs = vec![collection(1).into(), free.into()];
+
+    assert_eq!(
+        SimpleConstraintChecker::check(
+            &Mint::<0>,
+            &[collection(0).into()],
+            &[],
+            &outputs,
+            &Default::default()
+        ),
+        Err(ConstraintCheckerError::ItemMismatch)
+    );
+}
+
+#[test]
+fn mint_into_other_collection_fails() {
+    let stray = Nft {
+        collection: H256::zero(),
+        ..item(0)
+    };
+    let outputs = vec![collection(1).into(), stray.into()];
+
+    assert_eq!(
+        SimpleConstraintChecker::check(
+            &Mint::<0>,
+            &[collection(0).into()],
+            &[],
+            &outputs,
+            &Default::default()
+        ),
+        Err(ConstraintCheckerError::ItemMismatch)
+    );
+}
+
+#[test]
+fn transfer_works() {
+    assert_eq!(
+        SimpleConstraintChecker::check(
+            &Transfer::<0>,
+            &[item(0).into(), item(1).into()],
+            &[],
+            &[item(0).into(), item(1).into()],
+            &Default::default()
+        ),
+        Ok(0)
+    );
+}
+
+#[test]
+fn transfer_changing_metadata_fails() {
+    let altered = Nft {
+        metadata_uri: b"ipfs://other".to_vec(),
+        ..item(0)
+    };
+
+    assert_eq!(
+        SimpleConstraintChecker::check(
+            &Transfer::<0>,
+            &[item(0).into()],
+            &[],
+            &[altered.into()],
+            &Default::default()
+        ),
+        Err(ConstraintCheckerError::ItemsModified)
+    );
+}
+
+#[test]
+fn transfer_dropping_item_fails() {
+    assert_eq!(
+        SimpleConstraintChecker::check(
+            &Transfer::<0>,
+            &[item(0).into(), item(1).into()],
+            &[],
+            &[item(0).into()],
+            &Default::default()
+        ),
+        Err(ConstraintCheckerError::WrongNumberOutputs)
+    );
+}
+
+#[test]
+fn burn_works() {
+    assert_eq!(
+        SimpleConstraintChecker::check(
+            &Burn::<0>,
+            &[item(0).into()],
+            &[],
+            &[],
+            &Default::default()
+        ),
+        Ok(0)
+    );
+}
+
+#[test]
+fn burn_bogus_fails() {
+    assert_eq!(
+        SimpleConstraintChecker::check(&Burn::<0>, &[Bogus.into()], &[], &[], &Default::default()),
+        Err(ConstraintCheckerError::BadlyTypedInput)
+    );
+}
+
+fn sale() -> Sell<0> {
+    Sell {
+        price: 1000,
+        seller: seller(),
+    }
+}
+
+#[test]
+fn sell_works() {
+    let inputs = vec![
+        (item(0), SigCheck::new(seller())).into(),
+        coin(1200, buyer()),
+    ];
+    let outputs = vec![
+        (item(0), SigCheck::new(buyer())).into(),
+        coin(950, seller()),
+        coin(50, creator()),
+        coin(190, buyer()),
+    ];
+
+    assert_eq!(check(sale(), inputs, outputs), Ok(10));
+}
+
+#[test]
+fn sell_royalty_free_item_works() {
+    let free = Nft {
+        royalty_per_thousand: 0,
+        ..item(0)
+    };
+    let inputs = vec![
+        (free.clone(), SigCheck::new(seller())).into(),
+        coin(1000, buyer()),
+    ];
+    let outputs = vec![(free, SigCheck::new(buyer())).into(), coin(1000, seller())];
+
+    assert_eq!(check(sale(), inputs, outputs), Ok(0));
+}
+
+#[test]
+fn sell_without_royalty_fails() {
+    let inputs = vec![
+        (item(0), SigCheck::new(seller())).into(),
+        coin(1000, buyer()),
+    ];
+    let outputs = vec![
+        (item(0), SigCheck::new(buyer())).into(),
+        coin(1000, seller()),
+        coin(0, creator()),
+    ];
+
+    assert_eq!(
+        check(sale(), inputs, outputs),
+        Err(ConstraintCheckerError::SellerNotPaid)
+    );
+}
+
+#[test]
+fn sell_paying_royalty_to_someone_else_fails() {
+    let inputs = vec![
+        (item(0), SigCheck::new(seller())).into(),
+        coin(1000, buyer()),
+    ];
+    let outputs = vec![
+        (item(0), SigCheck::new(buyer())).into(),
+        coin(950, seller()),
+        coin(50, seller()),
+    ];
+
+    assert_eq!(
+        check(sale(), inputs, outputs),
+        Err(ConstraintCheckerError::RoyaltyNotPaid)
+    );
+}
+
+#[test]
+fn sell_with_missing_royalty_output_fails() {
+    let inputs = vec![
+        (item(0), SigCheck::new(seller())).into(),
+        coin(1000, buyer()),
+    ];
+    let outputs = vec![
+        (item(0), SigCheck::new(buyer())).into(),
+        coin(950, seller()),
+    ];
+
+    assert_eq!(
+        check(sale(), inputs, outputs),
+        Err(ConstraintCheckerError::WrongNumberOutputs)
+    );
+}
+
+#[test]
+fn sell_underfunded_fails() {
+    let inputs = vec![
+        (item(0), SigCheck::new(seller())).into(),
+        coin(900, buyer()),
+    ];
+    let outputs = vec![
+        (item(0), SigCheck::new(buyer())).into(),
+        coin(950, seller()),
+        coin(50, creator()),
+    ];
+
+    assert_eq!(
+        check(sale(), inputs, outputs),
+        Err(ConstraintCheckerError::OutputsExceedInputs)
+    );
+}
+
+#[test]
+fn sell_modified_item_fails() {
+    let inputs = vec![
+        (item(0), SigCheck::new(seller())).into(),
+        coin(1000, buyer()),
+    ];
+    let outputs = vec![
+        (item(1), SigCheck::new(buyer())).into(),
+        coin(950, seller()),
+        coin(50, creator()),
+    ];
+
+    assert_eq!(
+        check(sale(), inputs, outputs),
+        Err(ConstraintCheckerError::ItemsModified)
+    );
+}
@@ -0,0 +1,329 @@
+//! Price feeds reported by whitelisted oracle feeders.
+//!
+//! Each asset has a single [`PriceFeed`] UTXO holding the latest aggregated price. Other pieces,
+//! such as an AMM or a lending market, learn the price by peeking at the feed.
+//!
+//! Feeders are configured through [`OracleConfig`]. They sign [`PriceReport`]s off chain, and
+//! anyone may collect these signed reports into an [`UpdatePrice`] transaction that consumes the
+//! old feed and creates a new one. The checker makes sure that every report is signed by a
+//! distinct whitelisted feeder, is newer than the current feed, and is fresh with respect to a
+//! peeked `Timestamp` from the timestamp piece. The new feed holds the median of the reported
+//! prices, so that a minority of dishonest feeders can not move the price arbitrarily.
+//!
+//! Initial feeds are expected to be placed in the genesis block.
+//!
+//! ## Hack Warning
+//!
+//! Price feeds must be protected by the `UpForGrabs` verifier so that anyone may submit updates.
+//! The feeders' signatures, rather than the verifier, authorize the update. Signatures are passed
+//! as fields of the constraint checker.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use core::marker::PhantomData;
+
+use parity_scale_codec::{Decode, Encode};
+use scale_info::TypeInfo;
+use serde::{Deserialize, Serialize};
+use sp_core::{
+    sr25519::{Public, Signature},
+    H256,
+};
+use sp_runtime::transaction_validity::TransactionPriority;
+use sp_std::prelude::*;
+use timestamp::Timestamp;
+use tuxedo_core::{
+    dynamic_typing::UtxoData,
+    ensure,
+    support_macros::{CloneNoBound, DebugNoBound, DefaultNoBound},
+    types::Output,
+    verifier::UpForGrabs,
+    CheckerContext, ConstraintChecker, Verifier,
+};
+
+#[cfg(test)]
+mod tests;
+
+/// Configuration items for the oracle piece when it is
+/// instantiated in a concrete runtime.
+pub trait OracleConfig {
+    /// The keys of the feeders who may report prices.
+    fn feeders() -> Vec<H256>;
+
+    /// The minimum number of reports from distinct feeders needed to update a feed.
+    const MIN_REPORTS: usize = 1;
+
+    /// The maximum distance, in milliseconds, between the time of a report
+    /// and the peeked timestamp.
+    const MAX_REPORT_AGE: u64 = 60_000;
+}
+
+/// The latest aggregated price of an asset.
+#[derive(
+    Serialize,
+    Deserialize,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Clone,
+    Copy,
+    Encode,
+    Decode,
+    Hash,
+    Debug,
+    TypeInfo,
+)]
+pub struct PriceFeed {
+    /// The asset being priced.
+    pub asset: u32,
+    /// The aggregated price of the asset.
+    pub price: u128,
+    /// The time, in milliseconds since the unix epoch, of the oldest report
+    /// that contributed to this price.
+    pub time: u64,
+}
+
+impl PriceFeed {
+    pub fn new(asset: u32, price: u128, time: u64) -> Self {
+        Self { asset, price, time }
+    }
+}
+
+impl UtxoData for PriceFeed {
+    const TYPE_ID: [u8; 4] = *b"orcl";
+}
+
+/// A price observed by a single feeder.
+#[derive(
+    Serialize,
+    Deserialize,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Clone,
+    Copy,
+    Encode,
+    Decode,
+    Hash,
+    Debug,
+    TypeInfo,
+)]
+pub struct PriceReport {
+    /// The asset being priced.
+    pub asset: u32,
+    /// The observed price of the asset.
+    pub price: u128,
+    /// The time, in milliseconds since the unix epoch, at which the price was observed.
+    pub time: u64,
+}
+
+impl PriceReport {
+    pub fn new(asset: u32, price: u128, time: u64) -> Self {
+        Self { asset, price, time }
+    }
+
+    /// The message that the feeder must sign to make this report.
+    pub fn signing_message(&self) -> Vec<u8> {
+        (b"price_report", self).encode()
+    }
+}
+
+/// A price report along with the feeder who made it and their signature.
+#[derive(Serialize, Deserialize, PartialEq, Eq, Clone, Encode, Decode, Debug, TypeInfo)]
+pub struct SignedReport {
+    pub report: PriceReport,
+    pub feeder: H256,
+    pub signature: Signature,
+}
+
+/// Errors that can occur when updating a price feed.
+#[derive(
+    Serialize,
+    Deserialize,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Clone,
+    Encode,
+    Decode,
+    Hash,
+    Debug,
+    TypeInfo,
+)]
+pub enum ConstraintCheckerError {
+    /// An input data has the wrong type.
+    BadlyTypedInput,
+    /// An output data has the wrong type.
+    BadlyTypedOutput,
+    /// The peeked data is not a timestamp.
+    BadlyTypedPeek,
+    /// An update consumes exactly one price feed.
+    WrongNumberInputs,
+    /// An update creates exactly one price feed.
+    WrongNumberOutputs,
+    /// An update peeks at exactly one timestamp.
+    WrongNumberPeeks,
+    /// The new price feed is not protected by the `UpForGrabs` verifier.
+    FeedNotUpForGrabs,
+    /// Fewer reports were supplied than the configured minimum.
+    NotEnoughReports,
+    /// A report is for a different asset than the feed.
+    WrongAsset,
+    /// A report is signed by a key that is not a whitelisted feeder.
+    UnknownFeeder,
+    /// Two reports come from the same feeder.
+    DuplicateFeeder,
+    /// A report's signature is invalid.
+    BadSignature,
+    /// A report is not newer than the current feed.
+    StaleReport,
+    /// A report's time is too far from the peeked timestamp.
+    ReportNotFresh,
+    /// The new feed does not hold the aggregate of the reports.
+    WrongAggregate,
+}
+
+fn check_signature(signer: H256, message: &[u8], signature: &Signature) -> bool {
+    sp_io::crypto::sr25519_verify(signature, message, &Public::from_h256(signer))
+}
+
+/// Aggregates the reports into a new price feed.
+///
+/// The price is the median of the reported prices, taking the lower one when there is an even
+/// number of reports. The time is that of the oldest report. Returns `None` if there are no
+/// reports.
+pub fn aggregate(reports: &[PriceReport]) -> Option<PriceFeed> {
+    let first = reports.first()?;
+    let mut prices: Vec<u128> = reports.iter().map(|r| r.price).collect();
+    prices.sort_unstable();
+    let time = reports.iter().map(|r| r.time).min()?;
+
+    Some(PriceFeed::new(
+        first.asset,
+        prices[(prices.len() - 1) / 2],
+        time,
+    ))
+}
+
+/// A constraint checker that updates a price feed from signed feeder reports.
+///
+/// The only input is the old feed, the only peek is a `Timestamp`, and the only output is the new
+/// feed, which must be protected by the `UpForGrabs` verifier.
+#[derive(
+    Serialize,
+    Deserialize,
+    Encode,
+    Decode,
+    DebugNoBound,
+    DefaultNoBound,
+    PartialEq,
+    Eq,
+    CloneNoBound,
+    TypeInfo,
+)]
+#[scale_info(skip_type_params(T))]
+pub struct UpdatePrice<T> {
+    /// The signed reports to aggregate.
+    pub reports: Vec<SignedReport>,
+    _phantom: PhantomData<T>,
+}
+
+impl<T> UpdatePrice<T> {
+    pub fn new(reports: Vec<SignedReport>) -> Self {
+        Self {
+            reports,
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<T: OracleConfig, V: Verifier + PartialEq + From<UpForGrabs>> ConstraintChecker<V>
+    for UpdatePrice<T>
+{
+    type Error = ConstraintCheckerError;
+    type InherentHooks = ();
+
+    fn check(
+        &self,
+        inputs: &[Output<V>],
+        peeks: &[Output<V>],
+        outputs: &[Output<V>],
+        _context: &CheckerContext,
+    ) -> Result<TransactionPriority, Self::Error> {
+        ensure!(inputs.len() == 1, ConstraintCheckerError::WrongNumberInputs);
+        ensure!(peeks.len() == 1, ConstraintCheckerError::WrongNumberPeeks);
+        ensure!(
+            outputs.len() == 1,
+            ConstraintCheckerError::WrongNumberOutputs
+        );
+        let old = inputs[0]
+            .payload
+            .extract::<PriceFeed>()
+            .map_err(|_| ConstraintCheckerError::BadlyTypedInput)?;
+        let now = peeks[0]
+            .payload
+            .extract::<Timestamp>()
+            .map_err(|_| ConstraintCheckerError::BadlyTypedPeek)?
+            .time;
+        let new = outputs[0]
+            .payload
+            .extract::<PriceFeed>()
+            .map_err(|_| ConstraintCheckerError::BadlyTypedOutput)?;
+        ensure!(
+            outputs[0].verifier == UpForGrabs.into(),
+            ConstraintCheckerError::FeedNotUpForGrabs
+        );
+
+        ensure!(
+            !self.reports.is_empty() && self.reports.len() >= T::MIN_REPORTS,
+            ConstraintCheckerError::NotEnoughReports
+        );
+
+        let feeders = T::feeders();
+        let mut seen = Vec::with_capacity(self.reports.len());
+        for SignedReport {
+            report,
+            feeder,
+            signature,
+        } in &self.reports
+        {
+            ensure!(
+                report.asset == old.asset,
+                ConstraintCheckerError::WrongAsset
+            );
+            ensure!(
+                feeders.contains(feeder),
+                ConstraintCheckerError::UnknownFeeder
+            );
+            ensure!(
+                !seen.contains(feeder),
+                ConstraintCheckerError::DuplicateFeeder
+            );
+            seen.push(*feeder);
+            ensure!(report.time > old.time, ConstraintCheckerError::StaleReport);
+            ensure!(
+                report.time.abs_diff(now) <= T::MAX_REPORT_AGE,
+                ConstraintCheckerError::ReportNotFresh
+            );
+            ensure!(
+                check_signature(*feeder, &report.signing_message(), signature),
+                ConstraintCheckerError::BadSignature
+            );
+        }
+
+        let reports: Vec<PriceReport> = self.reports.iter().map(|r| r.report).collect();
+        ensure!(
+            aggregate(&reports) == Some(new),
+            ConstraintCheckerError::WrongAggregate
+        );
+
+        Ok(0)
+    }
+
+    fn is_inherent(&self) -> bool {
+        false
+    }
+}
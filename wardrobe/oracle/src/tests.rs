@@ -0,0 +1,238 @@
+//! Unit tests for the Oracle piece
+
+use super::*;
+use sp_core::{crypto::Pair as _, sr25519::Pair};
+use tuxedo_core::{dynamic_typing::testing::Bogus, tuxedo_verifier, verifier::SigCheck};
+
+/// A verifier that can represent price feeds as well as ordinary users.
+#[derive(Serialize, Deserialize, Encode, Decode, Debug, PartialEq, Eq, Clone, TypeInfo)]
+#[tuxedo_verifier]
+pub enum TestVerifier {
+    SigCheck(SigCheck),
+    UpForGrabs(UpForGrabs),
+}
+
+/// A mock config with three feeders, only the first two of which are whitelisted.
+/// Updates need at least two reports that are at most ten seconds old.
+pub struct TestConfig;
+
+impl OracleConfig for TestConfig {
+    fn feeders() -> Vec<H256> {
+        vec![feeder(1).public().into(), feeder(2).public().into()]
+    }
+
+    const MIN_REPORTS: usize = 2;
+    const MAX_REPORT_AGE: u64 = 10_000;
+}
+
+fn feeder(seed: u8) -> Pair {
+    Pair::from_seed(&[seed; 32])
+}
+
+fn signed(seed: u8, report: PriceReport) -> SignedReport {
+    let pair = feeder(seed);
+    SignedReport {
+        report,
+        feeder: pair.public().into(),
+        signature: pair.sign(&report.signing_message()),
+    }
+}
+
+fn feed_output(feed: PriceFeed) -> Output<TestVerifier> {
+    (feed, UpForGrabs.into()).into()
+}
+
+/// A timestamp noted at the given time.
+fn time(time: u64) -> Output<TestVerifier> {
+    (Timestamp::new(time, 10), UpForGrabs.into()).into()
+}
+
+fn update(
+    reports: Vec<SignedReport>,
+    new: PriceFeed,
+) -> Result<TransactionPriority, ConstraintCheckerError> {
+    UpdatePrice::<TestConfig>::new(reports).check(
+        &[feed_output(PriceFeed::new(0, 100, 1_000))],
+        &[time(20_000)],
+        &[feed_output(new)],
+        &Default::default(),
+    )
+}
+
+#[test]
+fn update_price_works() {
+    let reports = vec![
+        signed(1, PriceReport::new(0, 120, 19_000)),
+        signed(2, PriceReport::new(0, 110, 18_000)),
+    ];
+
+    assert_eq!(update(reports, PriceFeed::new(0, 110, 18_000)), Ok(0));
+}
+
+#[test]
+fn aggregate_takes_median_and_oldest_time() {
+    let reports = [
+        PriceReport::new(0, 300, 5),
+        PriceReport::new(0, 100, 7),
+        PriceReport::new(0, 200, 6),
+    ];
+
+    assert_eq!(aggregate(&reports), Some(PriceFeed::new(0, 200, 5)));
+    assert_eq!(aggregate(&reports[..2]), Some(PriceFeed::new(0, 100, 5)));
+    assert_eq!(aggregate(&[]), None);
+}
+
+#[test]
+fn wrong_aggregate_fails() {
+    let reports = vec![
+        signed(1, PriceReport::new(0, 120, 19_000)),
+        signed(2, PriceReport::new(0, 110, 18_000)),
+    ];
+
+    assert_eq!(
+        update(reports, PriceFeed::new(0, 120, 18_000)),
+        Err(ConstraintCheckerError::WrongAggregate)
+    );
+}
+
+#[test]
+fn not_enough_reports_fails() {
+    let reports = vec![signed(1, PriceReport::new(0, 120, 19_000))];
+
+    assert_eq!(
+        update(reports, PriceFeed::new(0, 120, 19_000)),
+        Err(ConstraintCheckerError::NotEnoughReports)
+    );
+}
+
+#[test]
+fn unknown_feeder_fails() {
+    let reports = vec![
+        signed(1, PriceReport::new(0, 120, 19_000)),
+        signed(3, PriceReport::new(0, 110, 18_000)),
+    ];
+
+    assert_eq!(
+        update(reports, PriceFeed::new(0, 110, 18_000)),
+        Err(ConstraintCheckerError::UnknownFeeder)
+    );
+}
+
+#[test]
+fn duplicate_feeder_fails() {
+    let reports = vec![
+        signed(1, PriceReport::new(0, 120, 19_000)),
+        signed(1, PriceReport::new(0, 110, 18_000)),
+    ];
+
+    assert_eq!(
+        update(reports, PriceFeed::new(0, 110, 18_000)),
+        Err(ConstraintCheckerError::DuplicateFeeder)
+    );
+}
+
+#[test]
+fn bad_signature_fails() {
+    let mut forged = signed(2, PriceReport::new(0, 110, 18_000));
+    forged.report.price = 1;
+    let reports = vec![signed(1, PriceReport::new(0, 120, 19_000)), forged];
+
+    assert_eq!(
+        update(reports, PriceFeed::new(0, 1, 18_000)),
+        Err(ConstraintCheckerError::BadSignature)
+    );
+}
+
+#[test]
+fn report_for_other_asset_fails() {
+    let reports = vec![
+        signed(1, PriceReport::new(0, 120, 19_000)),
+        signed(2, PriceReport::new(1, 110, 18_000)),
+    ];
+
+    assert_eq!(
+        update(reports, PriceFeed::new(0, 110, 18_000)),
+        Err(ConstraintCheckerError::WrongAsset)
+    );
+}
+
+#[test]
+fn report_older_than_feed_fails() {
+    let reports = vec![
+        signed(1, PriceReport::new(0, 120, 19_000)),
+        signed(2, PriceReport::new(0, 110, 1_000)),
+    ];
+
+    assert_eq!(
+        update(reports, PriceFeed::new(0, 110, 1_000)),
+        Err(ConstraintCheckerError::StaleReport)
+    );
+}
+
+#[test]
+fn report_too_far_from_timestamp_fails() {
+    let reports = vec![
+        signed(1, PriceReport::new(0, 120, 19_000)),
+        signed(2, PriceReport::new(0, 110, 9_000)),
+    ];
+
+    assert_eq!(
+        update(reports, PriceFeed::new(0, 110, 9_000)),
+        Err(ConstraintCheckerError::ReportNotFresh)
+    );
+}
+
+#[test]
+fn feed_not_up_for_grabs_fails() {
+    let reports = vec![
+        signed(1, PriceReport::new(0, 120, 19_000)),
+        signed(2, PriceReport::new(0, 110, 18_000)),
+    ];
+    let owner: H256 = feeder(1).public().into();
+
+    assert_eq!(
+        UpdatePrice::<TestConfig>::new(reports).check(
+            &[feed_output(PriceFeed::new(0, 100, 1_000))],
+            &[time(20_000)],
+            &[(PriceFeed::new(0, 110, 18_000), SigCheck::new(owner).into()).into()],
+            &Default::default(),
+        ),
+        Err(ConstraintCheckerError::FeedNotUpForGrabs)
+    );
+}
+
+#[test]
+fn peeking_non_timestamp_fails() {
+    let reports = vec![
+        signed(1, PriceReport::new(0, 120, 19_000)),
+        signed(2, PriceReport::new(0, 110, 18_000)),
+    ];
+
+    assert_eq!(
+        UpdatePrice::<TestConfig>::new(reports).check(
+            &[feed_output(PriceFeed::new(0, 100, 1_000))],
+            &[(Bogus, UpForGrabs.into()).into()],
+            &[feed_output(PriceFeed::new(0, 110, 18_000))],
+            &Default::default(),
+        ),
+        Err(ConstraintCheckerError::BadlyTypedPeek)
+    );
+}
+
+#[test]
+fn consuming_something_else_fails() {
+    let reports = vec![
+        signed(1, PriceReport::new(0, 120, 19_000)),
+        signed(2, PriceReport::new(0, 110, 18_000)),
+    ];
+
+    assert_eq!(
+        UpdatePrice::<TestConfig>::new(reports).check(
+            &[(Bogus, UpForGrabs.into()).into()],
+            &[time(20_000)],
+            &[feed_output(PriceFeed::new(0, 110, 18_000))],
+            &Default::default(),
+        ),
+        Err(ConstraintCheckerError::BadlyTypedInput)
+    );
+}
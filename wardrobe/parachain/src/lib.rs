@@ -7,6 +7,27 @@
 //! This is quite similar to how the timestamp inherent works, except that in this case we are consuming the previous
 //! input directly instead of peeking. This decision may be revisitied if keeping the info around would be useful.
 //!
+//! ## Relay Chain State Peeks
+//!
+//! The parachain inherent carries a proof of part of the relay parent's state. Constraint checkers can
+//! read verified entries of that state, such as the relay chain's randomness, by being wrapped in
+//! [`PeekRelayState`]. The wrapper reads the entries that the transaction names, and passes each one
+//! to the wrapped checker as an extra [`RelayStorageEntry`] peek after the transaction's own peeks.
+//! These peeks are synthetic. They are not in the UTXO set, and the transaction does not reference them.
+//!
+//! ## Async Backing
+//!
+//! With async backing, collators may build a block before its parent is included in the relay chain,
+//! and may build several blocks on the same relay parent. This piece tracks the blocks that are not yet
+//! included in the unincluded segment, and rejects any block that would make the segment longer than
+//! [`ParachainPieceConfig::UNINCLUDED_SEGMENT_CAPACITY`], or that would put more than
+//! [`ParachainPieceConfig::BLOCK_PROCESSING_VELOCITY`] blocks on one relay parent. The defaults of
+//! one and one are synchronous backing.
+//!
+//! The collator node still authors with the basic collator, which only builds on included blocks.
+//! Raising these constants lets the runtime accept blocks from a lookahead collator, but the node
+//! does not yet use one.
+//!
 //! ## Comparison with Cumulus Pallet Parachain System
 //!
 //! This is similar to FRAME's pallet parachain system, although this piece is only responsible for the inherent flow
@@ -19,7 +40,10 @@
 
 #![cfg_attr(not(feature = "std"), no_std)]
 
-use core::marker::PhantomData;
+use core::{
+    fmt::{Debug, Formatter},
+    marker::PhantomData,
+};
 
 use cumulus_primitives_parachain_inherent::{ParachainInherentData, INHERENT_IDENTIFIER};
 use parity_scale_codec::{Decode, Encode};
@@ -27,20 +51,28 @@ use scale_info::TypeInfo;
 use serde::{Deserialize, Serialize};
 use sp_core::H256;
 use sp_inherents::{CheckInherentsResult, InherentData};
-use sp_runtime::transaction_validity::TransactionPriority;
+use sp_runtime::{
+    traits::{BlakeTwo256, Hash},
+    transaction_validity::TransactionPriority,
+};
 use sp_std::{vec, vec::Vec};
 // We get all the Tuxedo core stuff through the re-export so we don't risk crossed versions.
 use tuxedo_parachain_core::ParachainInherentDataUtxo;
 use tuxedo_parachain_core::{
+    read_egress_channels, read_included_head_hash,
     tuxedo_core::{
+        dynamic_typing::UtxoData,
         ensure,
         inherents::{TuxedoInherent, TuxedoInherentAdapter},
         support_macros::{CloneNoBound, DebugNoBound, DefaultNoBound},
-        types::{Input, Output, OutputRef, Transaction},
+        types::{Input, InputMode, Output, OutputRef, Transaction, TransactionVersion},
         verifier::UpForGrabs,
-        ConstraintChecker, Verifier,
+        weights::{Weight, DEFAULT_CHECKER_WEIGHT},
+        CheckerContext, ConstraintChecker, Verifier,
     },
-    SetRelayParentNumberStorage,
+    Ancestor, GetUnincludedSegmentStorage, HorizontalMessagingState, SetDownwardMessagesStorage,
+    SetHorizontalMessagingStorage, SetRelayParentNumberStorage, SetUnincludedSegmentStorage,
+    UnincludedSegmentError,
 };
 
 #[cfg(test)]
@@ -62,10 +94,43 @@ pub trait ParachainPieceConfig {
     /// The default value is set to 2_000 to match the first available id in the rococo-local runtime.
     const PARA_ID: u32 = 2_000;
 
+    /// The most parachain blocks that may be built on a single relay parent.
+    ///
+    /// The default value of one means that every block must have a newer relay parent than its parent.
+    const BLOCK_PROCESSING_VELOCITY: u32 = 1;
+
+    /// The most parachain blocks that may be pending inclusion in the relay chain, including the
+    /// block being built.
+    ///
+    /// The default value of one is synchronous backing, where a block's parent must already be included.
+    const UNINCLUDED_SEGMENT_CAPACITY: u32 = 1;
+
     /// A means of setting an ambiently available relay parent number. This value WILL be used when
     /// the collator calls the collation API after the block is authored and also in validate_block.
     /// Additionally, it MAY be used by any other pieces in the runtime who have access to it.
     type SetRelayParentNumberStorage: SetRelayParentNumberStorage;
+
+    /// A means of setting the ambiently available downward messages. These WILL be counted as processed
+    /// when the collator calls the collation API and also in validate_block, so some piece in the runtime
+    /// is expected to process them, such as the xcm transfer piece.
+    type SetDownwardMessagesStorage: SetDownwardMessagesStorage;
+
+    /// A means of setting the ambiently available horizontal messaging state. Its inbound messages
+    /// are expected to be processed by some piece in the runtime, such as the hrmp messaging piece,
+    /// and its egress channels limit the messages that the block can send.
+    type SetHorizontalMessagingStorage: SetHorizontalMessagingStorage;
+
+    /// A means of setting the ambiently available relay chain state proof. Checkers that are wrapped
+    /// in [`PeekRelayState`] read from it through [`ReadRelayState`](Self::ReadRelayState).
+    type SetRelayStateProofStorage: SetRelayStateProofStorage;
+
+    /// A means of reading entries of the relay parent's state.
+    /// Probably this will be `tuxedo_parachain_core::RelayStateProofStorage`.
+    type ReadRelayState: ReadRelayState;
+
+    /// A means of reading and advancing the unincluded segment from block to block.
+    /// Probably this will be `tuxedo_parachain_core::UnincludedSegmentStorage`.
+    type UnincludedSegmentStorage: GetUnincludedSegmentStorage + SetUnincludedSegmentStorage;
 }
 
 /// Reasons that setting or cleaning up the parachain info may go wrong.
@@ -82,7 +147,10 @@ pub enum ParachainError {
     /// Multiple inputs were specified while setting the parachain info, but exactly one is required.
     ExtraInputs,
     /// The new relay chain block number is expected to be higher than the previous, but that is not the case.
+    /// It may only equal the previous when the block processing velocity allows several blocks per relay parent.
     RelayBlockNotIncreasing,
+    /// The block does not fit in the unincluded segment.
+    UnincludedSegment(UnincludedSegmentError),
 }
 
 /// A constraint checker for the simple act of including new parachain information.
@@ -117,6 +185,7 @@ impl<T: ParachainPieceConfig + 'static, V: Verifier + From<UpForGrabs>> Constrai
         input_data: &[Output<V>],
         _peek_data: &[Output<V>],
         output_data: &[Output<V>],
+        _context: &CheckerContext,
     ) -> Result<TransactionPriority, Self::Error> {
         log::debug!(
             target: LOG_TARGET,
@@ -135,6 +204,22 @@ impl<T: ParachainPieceConfig + 'static, V: Verifier + From<UpForGrabs>> Constrai
         // SIDE EFFECT: Write the relay parent block number to storage to use later in the collation info api
         T::SetRelayParentNumberStorage::set(current.validation_data.relay_parent_number);
 
+        // SIDE EFFECT: Write the relay chain state proof to storage so that other pieces can peek at the relay state
+        T::SetRelayStateProofStorage::set(
+            current.validation_data.relay_parent_storage_root,
+            current.relay_chain_state.clone(),
+        );
+
+        // SIDE EFFECT: Write the downward messages to storage so that other pieces can process them
+        T::SetDownwardMessagesStorage::set(current.downward_messages.clone());
+
+        // SIDE EFFECT: Write the horizontal messaging state to storage so that other pieces can
+        // process the inbound messages and send messages through the open channels
+        T::SetHorizontalMessagingStorage::set(HorizontalMessagingState {
+            inbound: current.horizontal_messages.clone(),
+            egress_channels: read_egress_channels(T::PARA_ID, &current),
+        });
+
         // Make sure there is exactly one input which is the previous parachain info
         ensure!(!input_data.is_empty(), Self::Error::MissingPreviousInfo);
         ensure!(input_data.len() == 1, Self::Error::ExtraInputs);
@@ -144,23 +229,40 @@ impl<T: ParachainPieceConfig + 'static, V: Verifier + From<UpForGrabs>> Constrai
             .map_err(|_| Self::Error::BadlyTyped)?
             .into();
 
-        // Make sure the relay chain block height is strictly increasing.
-        // In frame this logic is generic and it doesn't have to be so strict.
-        // But for now I'll start simple.
+        // Make sure the relay chain block height is increasing. It may stay the same when
+        // several blocks are allowed per relay parent.
+        let current_relay_parent = current.validation_data.relay_parent_number;
+        let previous_relay_parent = previous.validation_data.relay_parent_number;
         ensure!(
-            current.validation_data.relay_parent_number
-                > previous.validation_data.relay_parent_number,
+            current_relay_parent > previous_relay_parent
+                || (current_relay_parent == previous_relay_parent
+                    && T::BLOCK_PROCESSING_VELOCITY > 1),
             Self::Error::RelayBlockNotIncreasing,
         );
 
+        // Make sure the block fits in the unincluded segment, after pruning the blocks that
+        // the relay chain has included.
+        let mut segment = T::UnincludedSegmentStorage::get();
+        segment
+            .advance(
+                Ancestor {
+                    relay_parent_number: current_relay_parent,
+                    parent_head_hash: BlakeTwo256::hash(&current.validation_data.parent_head.0),
+                },
+                read_included_head_hash(T::PARA_ID, &current),
+                T::UNINCLUDED_SEGMENT_CAPACITY,
+                T::BLOCK_PROCESSING_VELOCITY,
+            )
+            .map_err(Self::Error::UnincludedSegment)?;
+
+        // SIDE EFFECT: Write the advanced unincluded segment to storage for the next block. It also
+        // identifies the current block to the upward and horizontal message queues.
+        T::UnincludedSegmentStorage::set(segment);
+
         // We may need to put a log on the block header at some point.
         // Frame does this. However, it seems this design is not fully fleshed out in cumulus itself.
         // FIXME https://github.com/Off-Narrative-Labs/Tuxedo/issues/147 for more context and info.
 
-        // When we support async backing, we will need to validate that the parent is included
-        // and perform consensus checks as well. For now, we rely on synchronous backing.
-        // FIXME https://github.com/Off-Narrative-Labs/Tuxedo/issues/148
-
         Ok(0)
     }
 
@@ -202,6 +304,7 @@ impl<V: Verifier + From<UpForGrabs>, T: ParachainPieceConfig + 'static> TuxedoIn
         let input = Input {
             output_ref,
             redeemer: Vec::new(),
+            mode: InputMode::Consume,
         };
 
         let new_output = Output {
@@ -210,10 +313,13 @@ impl<V: Verifier + From<UpForGrabs>, T: ParachainPieceConfig + 'static> TuxedoIn
         };
 
         let t = Transaction {
+            version: TransactionVersion::LATEST,
             inputs: vec![input],
             peeks: Vec::new(),
             outputs: vec![new_output],
             checker: Self::default(),
+            memo: None,
+            valid_until: None,
         };
 
         log::debug!(
@@ -243,6 +349,7 @@ impl<V: Verifier + From<UpForGrabs>, T: ParachainPieceConfig + 'static> TuxedoIn
         let payload = new_data_from_relay_parent_number(0).into();
 
         vec![Transaction {
+            version: TransactionVersion::LATEST,
             inputs: Vec::new(),
             peeks: Vec::new(),
             outputs: vec![Output {
@@ -250,10 +357,131 @@ impl<V: Verifier + From<UpForGrabs>, T: ParachainPieceConfig + 'static> TuxedoIn
                 verifier: UpForGrabs.into(),
             }],
             checker: Self::default(),
+            memo: None,
+            valid_until: None,
         }]
     }
 }
 
+/// An entry of the relay parent's state, as a [`PeekRelayState`] wrapper passes it to the wrapped
+/// constraint checker.
+#[derive(Debug, Encode, Decode, PartialEq, Eq, Clone, TypeInfo)]
+pub struct RelayStorageEntry {
+    /// The relay chain storage key.
+    pub key: Vec<u8>,
+    /// The raw value stored under the key, or `None` if the entry is absent on the relay chain.
+    pub value: Option<Vec<u8>>,
+}
+
+impl UtxoData for RelayStorageEntry {
+    const TYPE_ID: [u8; 4] = *b"rlys";
+}
+
+impl RelayStorageEntry {
+    /// Decode the value of the entry, if it is present and properly encoded.
+    pub fn decode_value<D: Decode>(&self) -> Option<D> {
+        D::decode(&mut &self.value.as_ref()?[..]).ok()
+    }
+}
+
+/// Reasons that a constraint checker that peeks at the relay chain state may fail.
+#[derive(Debug, Eq, PartialEq)]
+pub enum PeekRelayStateError<E> {
+    /// One of the named entries of the relay parent's state could not be read.
+    RelayState(RelayStateError),
+    /// The wrapped constraint checker failed.
+    Checker(E),
+}
+
+/// A constraint checker that passes entries of the relay parent's state to another constraint
+/// checker as extra peeks.
+///
+/// The wrapped checker sees the transaction's own peeks first, followed by one [`RelayStorageEntry`]
+/// for each key, in the order of the keys. The transaction fails if any of the entries can not be
+/// read from the relay chain state proof.
+#[derive(Serialize, Deserialize, Encode, Decode, PartialEq, Eq, TypeInfo)]
+#[scale_info(skip_type_params(T))]
+pub struct PeekRelayState<T, C> {
+    /// The relay chain storage keys of the entries to peek at.
+    pub keys: Vec<Vec<u8>>,
+    /// The wrapped constraint checker.
+    pub checker: C,
+    _phantom: PhantomData<T>,
+}
+
+// Debug and Clone are implemented by hand, because the derives would bound the runtime by them,
+// and the `NoBound` derives would not bound the wrapped checker.
+impl<T, C: Debug> Debug for PeekRelayState<T, C> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("PeekRelayState")
+            .field("keys", &self.keys)
+            .field("checker", &self.checker)
+            .finish()
+    }
+}
+
+impl<T, C: Clone> Clone for PeekRelayState<T, C> {
+    fn clone(&self) -> Self {
+        Self::new(self.keys.clone(), self.checker.clone())
+    }
+}
+
+impl<T, C> PeekRelayState<T, C> {
+    pub fn new(keys: Vec<Vec<u8>>, checker: C) -> Self {
+        Self {
+            keys,
+            checker,
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<T, V, C> ConstraintChecker<V> for PeekRelayState<T, C>
+where
+    T: ParachainPieceConfig + 'static,
+    V: Verifier + From<UpForGrabs>,
+    C: ConstraintChecker<V>,
+{
+    type Error = PeekRelayStateError<C::Error>;
+    type InherentHooks = ();
+
+    fn check(
+        &self,
+        input_data: &[Output<V>],
+        peek_data: &[Output<V>],
+        output_data: &[Output<V>],
+        context: &CheckerContext,
+    ) -> Result<TransactionPriority, Self::Error> {
+        let mut peeks = peek_data.to_vec();
+        for key in &self.keys {
+            let value = T::ReadRelayState::read(key).map_err(PeekRelayStateError::RelayState)?;
+            peeks.push(Output {
+                payload: RelayStorageEntry {
+                    key: key.clone(),
+                    value,
+                }
+                .into(),
+                verifier: UpForGrabs.into(),
+            });
+        }
+
+        self.checker
+            .check(input_data, &peeks, output_data, context)
+            .map_err(PeekRelayStateError::Checker)
+    }
+
+    fn is_inherent(&self) -> bool {
+        false
+    }
+
+    fn weight(&self) -> Weight {
+        // Each entry is read from the proof, which is about as much work as a simple check.
+        DEFAULT_CHECKER_WEIGHT
+            .saturating_mul(self.keys.len() as Weight)
+            .saturating_add(self.checker.weight())
+    }
+}
+
 #[cfg(feature = "std")]
 fn new_data_from_relay_parent_number(relay_parent_number: u32) -> ParachainInherentDataUtxo {
     let sproof_builder = cumulus_test_relay_sproof_builder::RelayStateSproofBuilder::default();
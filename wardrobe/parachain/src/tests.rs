@@ -2,18 +2,79 @@
 
 use super::*;
 use tuxedo_parachain_core::{
-    tuxedo_core::dynamic_typing::{testing::Bogus, DynamicallyTypedData},
-    MockRelayParentNumberStorage,
+    tuxedo_core::{
+        dynamic_typing::{testing::Bogus, DynamicallyTypedData},
+        SimpleConstraintChecker,
+    },
+    MockDownwardMessagesStorage, MockHorizontalMessagingStorage, MockRelayParentNumberStorage,
+    MockRelayStateProofStorage, MockUnincludedSegmentStorage,
 };
 use ParachainError::*;
 
-/// The mock config ignores the set relay parent storage number.
+/// The mock config ignores the set relay parent storage number, relay state proof, and messaging state.
+/// Its relay state has randomness seven, has no staking value, and the proof covers nothing else.
 pub struct MockConfig;
 
 impl ParachainPieceConfig for MockConfig {
     type SetRelayParentNumberStorage = MockRelayParentNumberStorage;
+    type SetDownwardMessagesStorage = MockDownwardMessagesStorage;
+    type SetHorizontalMessagingStorage = MockHorizontalMessagingStorage;
+    type SetRelayStateProofStorage = MockRelayStateProofStorage;
+    type ReadRelayState = Self;
+    type UnincludedSegmentStorage = MockUnincludedSegmentStorage;
 }
 
+/// A mock config for async backing, which allows two blocks per relay parent and three unincluded blocks.
+pub struct AsyncBackingConfig;
+
+impl ParachainPieceConfig for AsyncBackingConfig {
+    const BLOCK_PROCESSING_VELOCITY: u32 = 2;
+    const UNINCLUDED_SEGMENT_CAPACITY: u32 = 3;
+
+    type SetRelayParentNumberStorage = MockRelayParentNumberStorage;
+    type SetDownwardMessagesStorage = MockDownwardMessagesStorage;
+    type SetHorizontalMessagingStorage = MockHorizontalMessagingStorage;
+    type SetRelayStateProofStorage = MockRelayStateProofStorage;
+    type ReadRelayState = MockConfig;
+    type UnincludedSegmentStorage = MockUnincludedSegmentStorage;
+}
+
+impl ReadRelayState for MockConfig {
+    fn read(key: &[u8]) -> Result<Option<Vec<u8>>, RelayStateError> {
+        match key {
+            b"randomness" => Ok(Some(7u8.encode())),
+            b"staking" => Ok(None),
+            _ => Err(RelayStateError::NotInProof),
+        }
+    }
+}
+
+/// A constraint checker that passes when its last peek is relay chain randomness of seven.
+#[derive(Encode, Decode, Debug, PartialEq, Eq, Clone)]
+pub struct LuckySeven;
+
+impl SimpleConstraintChecker for LuckySeven {
+    type Error = ();
+
+    fn check(
+        &self,
+        _input_data: &[DynamicallyTypedData],
+        peek_data: &[DynamicallyTypedData],
+        _output_data: &[DynamicallyTypedData],
+        _context: &CheckerContext,
+    ) -> Result<TransactionPriority, ()> {
+        let entry = peek_data
+            .last()
+            .ok_or(())?
+            .extract::<RelayStorageEntry>()
+            .map_err(|_| ())?;
+        ensure!(entry.decode_value::<u8>() == Some(7), ());
+        Ok(0)
+    }
+}
+
+type LuckyPeek = PeekRelayState<MockConfig, LuckySeven>;
+
 #[test]
 fn update_parachain_info_happy_path() {
     let old: DynamicallyTypedData = new_data_from_relay_parent_number(3).into();
@@ -22,7 +83,12 @@ fn update_parachain_info_happy_path() {
     let outputs: Vec<Output<UpForGrabs>> = vec![new.into()];
 
     assert_eq!(
-        SetParachainInfo::<MockConfig>(Default::default()).check(&inputs, &[], &outputs),
+        SetParachainInfo::<MockConfig>(Default::default()).check(
+            &inputs,
+            &[],
+            &outputs,
+            &Default::default()
+        ),
         Ok(0),
     );
 }
@@ -35,7 +101,48 @@ fn update_parachain_info_relay_block_not_increasing() {
     let outputs: Vec<Output<UpForGrabs>> = vec![new.into()];
 
     assert_eq!(
-        SetParachainInfo::<MockConfig>(Default::default()).check(&inputs, &[], &outputs),
+        SetParachainInfo::<MockConfig>(Default::default()).check(
+            &inputs,
+            &[],
+            &outputs,
+            &Default::default()
+        ),
+        Err(RelayBlockNotIncreasing),
+    );
+}
+
+#[test]
+fn update_parachain_info_async_backing_same_relay_block() {
+    let old: DynamicallyTypedData = new_data_from_relay_parent_number(3).into();
+    let inputs: Vec<Output<UpForGrabs>> = vec![old.into()];
+    let new: DynamicallyTypedData = new_data_from_relay_parent_number(3).into();
+    let outputs: Vec<Output<UpForGrabs>> = vec![new.into()];
+
+    assert_eq!(
+        SetParachainInfo::<AsyncBackingConfig>(Default::default()).check(
+            &inputs,
+            &[],
+            &outputs,
+            &Default::default()
+        ),
+        Ok(0),
+    );
+}
+
+#[test]
+fn update_parachain_info_async_backing_relay_block_decreasing() {
+    let old: DynamicallyTypedData = new_data_from_relay_parent_number(3).into();
+    let inputs: Vec<Output<UpForGrabs>> = vec![old.into()];
+    let new: DynamicallyTypedData = new_data_from_relay_parent_number(2).into();
+    let outputs: Vec<Output<UpForGrabs>> = vec![new.into()];
+
+    assert_eq!(
+        SetParachainInfo::<AsyncBackingConfig>(Default::default()).check(
+            &inputs,
+            &[],
+            &outputs,
+            &Default::default()
+        ),
         Err(RelayBlockNotIncreasing),
     );
 }
@@ -49,7 +156,12 @@ fn update_parachain_info_extra_inputs() {
     let outputs: Vec<Output<UpForGrabs>> = vec![new.into()];
 
     assert_eq!(
-        SetParachainInfo::<MockConfig>(Default::default()).check(&inputs, &[], &outputs),
+        SetParachainInfo::<MockConfig>(Default::default()).check(
+            &inputs,
+            &[],
+            &outputs,
+            &Default::default()
+        ),
         Err(ExtraInputs)
     );
 }
@@ -61,7 +173,12 @@ fn update_parachain_info_missing_input() {
     let outputs: Vec<Output<UpForGrabs>> = vec![new.into()];
 
     assert_eq!(
-        SetParachainInfo::<MockConfig>(Default::default()).check(&inputs, &[], &outputs),
+        SetParachainInfo::<MockConfig>(Default::default()).check(
+            &inputs,
+            &[],
+            &outputs,
+            &Default::default()
+        ),
         Err(MissingPreviousInfo)
     );
 }
@@ -74,7 +191,12 @@ fn update_parachain_info_bogus_input() {
     let outputs: Vec<Output<UpForGrabs>> = vec![new.into()];
 
     assert_eq!(
-        SetParachainInfo::<MockConfig>(Default::default()).check(&inputs, &[], &outputs),
+        SetParachainInfo::<MockConfig>(Default::default()).check(
+            &inputs,
+            &[],
+            &outputs,
+            &Default::default()
+        ),
         Err(BadlyTyped)
     );
 }
@@ -88,7 +210,12 @@ fn update_parachain_info_extra_outputs() {
     let outputs: Vec<Output<UpForGrabs>> = vec![new1.into(), new2.into()];
 
     assert_eq!(
-        SetParachainInfo::<MockConfig>(Default::default()).check(&inputs, &[], &outputs),
+        SetParachainInfo::<MockConfig>(Default::default()).check(
+            &inputs,
+            &[],
+            &outputs,
+            &Default::default()
+        ),
         Err(ExtraOutputs)
     );
 }
@@ -100,7 +227,12 @@ fn update_parachain_info_missing_output() {
     let outputs: Vec<Output<UpForGrabs>> = vec![];
 
     assert_eq!(
-        SetParachainInfo::<MockConfig>(Default::default()).check(&inputs, &[], &outputs),
+        SetParachainInfo::<MockConfig>(Default::default()).check(
+            &inputs,
+            &[],
+            &outputs,
+            &Default::default()
+        ),
         Err(MissingNewInfo)
     );
 }
@@ -113,7 +245,67 @@ fn update_parachain_info_bogus_output() {
     let outputs: Vec<Output<UpForGrabs>> = vec![new.into()];
 
     assert_eq!(
-        SetParachainInfo::<MockConfig>(Default::default()).check(&inputs, &[], &outputs),
+        SetParachainInfo::<MockConfig>(Default::default()).check(
+            &inputs,
+            &[],
+            &outputs,
+            &Default::default()
+        ),
         Err(BadlyTyped)
     );
 }
+
+#[test]
+fn peek_relay_state_passes_entries_to_checker() {
+    let checker = LuckyPeek::new(vec![b"randomness".to_vec()], LuckySeven);
+
+    assert_eq!(
+        ConstraintChecker::<UpForGrabs>::check(&checker, &[], &[], &[], &Default::default()),
+        Ok(0),
+    );
+}
+
+#[test]
+fn peek_relay_state_appends_entries_after_real_peeks() {
+    let checker = LuckyPeek::new(vec![b"randomness".to_vec()], LuckySeven);
+    let real: DynamicallyTypedData = Bogus.into();
+    let peeks: Vec<Output<UpForGrabs>> = vec![real.into()];
+
+    assert_eq!(checker.check(&[], &peeks, &[], &Default::default()), Ok(0),);
+}
+
+#[test]
+fn peek_relay_state_passes_absent_entries() {
+    let checker = LuckyPeek::new(vec![b"staking".to_vec()], LuckySeven);
+
+    assert_eq!(
+        ConstraintChecker::<UpForGrabs>::check(&checker, &[], &[], &[], &Default::default()),
+        Err(PeekRelayStateError::Checker(())),
+    );
+}
+
+#[test]
+fn peek_relay_state_entry_not_in_proof() {
+    let checker = LuckyPeek::new(
+        vec![b"randomness".to_vec(), b"unproven".to_vec()],
+        LuckySeven,
+    );
+
+    assert_eq!(
+        ConstraintChecker::<UpForGrabs>::check(&checker, &[], &[], &[], &Default::default()),
+        Err(PeekRelayStateError::RelayState(RelayStateError::NotInProof)),
+    );
+}
+
+#[test]
+fn peek_relay_state_weighs_reads() {
+    let checker = LuckyPeek::new(
+        vec![b"randomness".to_vec(), b"staking".to_vec()],
+        LuckySeven,
+    );
+
+    assert_eq!(
+        ConstraintChecker::<UpForGrabs>::weight(&checker),
+        3 * DEFAULT_CHECKER_WEIGHT,
+    );
+}
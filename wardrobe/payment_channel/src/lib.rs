@@ -0,0 +1,635 @@
+//! Payment channels in the tokens provided by the money piece.
+//!
+//! Two parties lock funds into a [`Channel`] on chain and then pay each other any number of
+//! times off chain by exchanging signed [`ChannelState`]s. Each state records both parties'
+//! balances and a sequence number that increases with every update. Payments may flow in
+//! either direction, so unidirectional channels are just the special case where only one
+//! balance ever decreases.
+//!
+//! A channel is closed in one of two ways.
+//! 1. Cooperatively. Both parties sign the final balances and the funds are paid out at once.
+//! 2. Unilaterally. Either party submits the latest state that both parties signed. This starts
+//!    a challenge period during which the counterparty may submit any newer signed state. Once
+//!    the challenge period ends, the funds are paid out according to the newest state submitted.
+//!
+//! Before funding a channel, the parties should both sign the initial state with sequence
+//! number zero, so that either party can recover their funds if the other disappears.
+//!
+//! Channel outputs should be protected by the `UpForGrabs` verifier. The constraint checkers,
+//! rather than the verifier, check the relevant signatures and guarantee that funds are paid to
+//! the right parties. The signatures are passed as fields of the constraint checkers.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use core::marker::PhantomData;
+
+use money::Coin;
+use parity_scale_codec::{Decode, Encode};
+use scale_info::TypeInfo;
+use serde::{Deserialize, Serialize};
+use sp_core::{
+    sr25519::{Public, Signature},
+    H256,
+};
+use sp_runtime::transaction_validity::TransactionPriority;
+use sp_std::prelude::*;
+use tuxedo_core::{
+    dynamic_typing::{DynamicallyTypedData, UtxoData},
+    ensure,
+    support_macros::{CloneNoBound, DebugNoBound, DefaultNoBound},
+    types::Output,
+    verifier::SigCheck,
+    CheckerContext, ConstraintChecker, SimpleConstraintChecker, Verifier,
+};
+
+#[cfg(test)]
+mod tests;
+
+/// Configuration items for the Payment Channel piece when it is
+/// instantiated in a concrete runtime.
+pub trait PaymentChannelConfig {
+    /// A means of getting the current block height.
+    /// Probably this will be the Tuxedo Executive
+    fn block_height() -> u32;
+}
+
+/// The fixed terms of a channel that are agreed when it is opened.
+#[derive(
+    Serialize,
+    Deserialize,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Clone,
+    Encode,
+    Decode,
+    Hash,
+    Debug,
+    TypeInfo,
+)]
+pub struct ChannelTerms {
+    /// The first party's key.
+    pub party_a: H256,
+    /// The second party's key.
+    pub party_b: H256,
+    /// The total value locked in the channel.
+    pub capacity: u128,
+    /// How many blocks the counterparty has to challenge a unilateral close.
+    pub challenge_period: u32,
+    /// Distinguishes channels that otherwise have identical terms so that
+    /// states signed for one cannot be replayed on another.
+    pub nonce: u32,
+}
+
+/// A snapshot of the balances in a channel that both parties sign off chain.
+#[derive(
+    Serialize,
+    Deserialize,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Clone,
+    Encode,
+    Decode,
+    Hash,
+    Debug,
+    TypeInfo,
+)]
+pub struct ChannelState {
+    /// Increases with every update. Newer states supersede older ones.
+    pub sequence: u64,
+    /// The first party's balance.
+    pub balance_a: u128,
+    /// The second party's balance.
+    pub balance_b: u128,
+}
+
+/// A channel state along with both parties' signatures over it.
+#[derive(Serialize, Deserialize, Encode, Decode, Debug, PartialEq, Eq, Clone, TypeInfo)]
+pub struct SignedChannelState {
+    pub state: ChannelState,
+    pub signature_a: Signature,
+    pub signature_b: Signature,
+}
+
+/// A unilateral close that is waiting out its challenge period.
+#[derive(
+    Serialize,
+    Deserialize,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Clone,
+    Encode,
+    Decode,
+    Hash,
+    Debug,
+    TypeInfo,
+)]
+pub struct PendingClose {
+    /// The newest state submitted so far.
+    pub state: ChannelState,
+    /// The block height from which the channel may be finalized.
+    pub closes_at: u32,
+}
+
+/// A payment channel holding `Coin<ID>` value.
+#[derive(
+    Serialize,
+    Deserialize,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Clone,
+    Encode,
+    Decode,
+    Hash,
+    Debug,
+    TypeInfo,
+)]
+pub struct Channel<const ID: u8> {
+    /// The terms agreed when opening the channel.
+    pub terms: ChannelTerms,
+    /// The unilateral close in progress, if any.
+    pub closing: Option<PendingClose>,
+}
+
+impl<const ID: u8> UtxoData for Channel<ID> {
+    const TYPE_ID: [u8; 4] = [b'c', b'h', b'n', ID];
+}
+
+impl ChannelTerms {
+    /// The message that both parties sign to agree on an off-chain state.
+    pub fn state_message(&self, state: &ChannelState) -> Vec<u8> {
+        (b"chan_state", self, state).encode()
+    }
+
+    /// The message that both parties sign to cooperatively close with the given balances.
+    pub fn close_message(&self, balance_a: u128, balance_b: u128) -> Vec<u8> {
+        (b"chan_close", self, balance_a, balance_b).encode()
+    }
+
+    /// Check that both parties signed the given message.
+    fn check_signatures(
+        &self,
+        message: &[u8],
+        signature_a: &Signature,
+        signature_b: &Signature,
+    ) -> bool {
+        sp_io::crypto::sr25519_verify(signature_a, message, &Public::from_h256(self.party_a))
+            && sp_io::crypto::sr25519_verify(signature_b, message, &Public::from_h256(self.party_b))
+    }
+
+    /// Check that a signed state is valid for this channel and return the state.
+    fn check_state(
+        &self,
+        signed: &SignedChannelState,
+    ) -> Result<ChannelState, ConstraintCheckerError> {
+        ensure!(
+            self.check_signatures(
+                &self.state_message(&signed.state),
+                &signed.signature_a,
+                &signed.signature_b
+            ),
+            ConstraintCheckerError::BadSignature
+        );
+        ensure!(
+            signed.state.balance_a.checked_add(signed.state.balance_b) == Some(self.capacity),
+            ConstraintCheckerError::BalancesDoNotMatchCapacity
+        );
+
+        Ok(signed.state.clone())
+    }
+}
+
+/// Errors that can occur when checking payment channel transactions.
+#[derive(
+    Serialize,
+    Deserialize,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Clone,
+    Encode,
+    Decode,
+    Hash,
+    Debug,
+    TypeInfo,
+)]
+pub enum ConstraintCheckerError {
+    /// An input data has the wrong type.
+    BadlyTypedInput,
+    /// An output data has the wrong type.
+    BadlyTypedOutput,
+    /// Wrong number of inputs were provided to the constraint checker.
+    WrongNumberInputs,
+    /// Wrong number of outputs were provided to the constraint checker.
+    WrongNumberOutputs,
+    /// A channel or coin with zero value was created. This is not allowed because it wastes state space.
+    ZeroValue,
+    /// The value consumed or created by this transaction overflows the value type.
+    ValueOverflow,
+    /// More value was created than was consumed.
+    OutputsExceedInputs,
+    /// A new channel is already closing.
+    OpenedClosing,
+    /// A required signature is missing or invalid.
+    BadSignature,
+    /// The balances in a state or close do not add up to the channel's capacity.
+    BalancesDoNotMatchCapacity,
+    /// A party was paid more than their balance.
+    Overpaid,
+    /// Coins were locked to some verifier other than the parties'.
+    PaidToWrongVerifier,
+    /// Attempted to start a unilateral close on a channel that is already closing.
+    AlreadyClosing,
+    /// Attempted to challenge or finalize a channel that is not closing.
+    NotClosing,
+    /// The challenge period is over, so the close can no longer be challenged.
+    ChallengePeriodOver,
+    /// The challenge period is not over, so the close cannot be finalized.
+    ChallengePeriodNotOver,
+    /// The submitted state is not newer than the one already submitted.
+    StaleState,
+    /// The updated channel differs from the original in some way other than the expected update.
+    ChannelMismatch,
+}
+
+/// Consume the single channel input and make sure the outputs pay each party no more than their balance.
+/// Returns the channel along with the amount that the parties leave to pay the fee and tip.
+fn pay_out<V, const ID: u8>(
+    inputs: &[Output<V>],
+    outputs: &[Output<V>],
+    balance_of: impl Fn(&Channel<ID>) -> Result<(u128, u128), ConstraintCheckerError>,
+) -> Result<u128, ConstraintCheckerError>
+where
+    V: Verifier + PartialEq + From<SigCheck>,
+{
+    let channel = extract_channel::<V, ID>(inputs)?;
+    let (balance_a, balance_b) = balance_of(&channel)?;
+
+    let party_a: V = SigCheck::new(channel.terms.party_a).into();
+    let party_b: V = SigCheck::new(channel.terms.party_b).into();
+    let mut paid_a: u128 = 0;
+    let mut paid_b: u128 = 0;
+    for output in outputs {
+        let value = output
+            .payload
+            .extract::<Coin<ID>>()
+            .map_err(|_| ConstraintCheckerError::BadlyTypedOutput)?
+            .0;
+        ensure!(value > 0, ConstraintCheckerError::ZeroValue);
+        let paid = if output.verifier == party_a {
+            &mut paid_a
+        } else if output.verifier == party_b {
+            &mut paid_b
+        } else {
+            return Err(ConstraintCheckerError::PaidToWrongVerifier);
+        };
+        *paid = paid
+            .checked_add(value)
+            .ok_or(ConstraintCheckerError::ValueOverflow)?;
+    }
+    ensure!(
+        paid_a <= balance_a && paid_b <= balance_b,
+        ConstraintCheckerError::Overpaid
+    );
+
+    // The balances were already checked to add up to the capacity, so this can't underflow.
+    Ok(channel.terms.capacity - paid_a - paid_b)
+}
+
+/// Extract the channel from the single input.
+fn extract_channel<V, const ID: u8>(
+    inputs: &[Output<V>],
+) -> Result<Channel<ID>, ConstraintCheckerError> {
+    ensure!(inputs.len() == 1, ConstraintCheckerError::WrongNumberInputs);
+    inputs[0]
+        .payload
+        .extract::<Channel<ID>>()
+        .map_err(|_| ConstraintCheckerError::BadlyTypedInput)
+}
+
+/// Extract the updated channel from the single output and make sure its terms are unchanged.
+fn extract_updated_channel<V, const ID: u8>(
+    outputs: &[Output<V>],
+    terms: &ChannelTerms,
+) -> Result<Channel<ID>, ConstraintCheckerError> {
+    ensure!(
+        outputs.len() == 1,
+        ConstraintCheckerError::WrongNumberOutputs
+    );
+    let updated = outputs[0]
+        .payload
+        .extract::<Channel<ID>>()
+        .map_err(|_| ConstraintCheckerError::BadlyTypedOutput)?;
+    ensure!(
+        &updated.terms == terms,
+        ConstraintCheckerError::ChannelMismatch
+    );
+
+    Ok(updated)
+}
+
+/// A constraint checker that opens new channels.
+///
+/// All inputs are `Coin<ID>`s. Outputs are new channels, and optionally `Coin<ID>` change.
+/// Whatever the opener does not put into a channel or take back as change pays the transaction's
+/// fee and tip.
+#[derive(
+    Serialize, Deserialize, Encode, Decode, Debug, Default, PartialEq, Eq, Clone, TypeInfo,
+)]
+pub struct OpenChannel<const ID: u8>;
+
+impl<const ID: u8> SimpleConstraintChecker for OpenChannel<ID> {
+    type Error = ConstraintCheckerError;
+
+    fn check(
+        &self,
+        input_data: &[DynamicallyTypedData],
+        _peeks: &[DynamicallyTypedData],
+        output_data: &[DynamicallyTypedData],
+        _context: &CheckerContext,
+    ) -> Result<TransactionPriority, Self::Error> {
+        let mut total_input: u128 = 0;
+        for input in input_data {
+            let value = input
+                .extract::<Coin<ID>>()
+                .map_err(|_| ConstraintCheckerError::BadlyTypedInput)?
+                .0;
+            total_input = total_input
+                .checked_add(value)
+                .ok_or(ConstraintCheckerError::ValueOverflow)?;
+        }
+
+        let mut total_output: u128 = 0;
+        let mut opened_any = false;
+        for output in output_data {
+            let value = if let Ok(channel) = output.extract::<Channel<ID>>() {
+                ensure!(
+                    channel.closing.is_none(),
+                    ConstraintCheckerError::OpenedClosing
+                );
+                opened_any = true;
+                channel.terms.capacity
+            } else {
+                output
+                    .extract::<Coin<ID>>()
+                    .map_err(|_| ConstraintCheckerError::BadlyTypedOutput)?
+                    .0
+            };
+            ensure!(value > 0, ConstraintCheckerError::ZeroValue);
+            total_output = total_output
+                .checked_add(value)
+                .ok_or(ConstraintCheckerError::ValueOverflow)?;
+        }
+        ensure!(opened_any, ConstraintCheckerError::WrongNumberOutputs);
+        ensure!(
+            total_output <= total_input,
+            ConstraintCheckerError::OutputsExceedInputs
+        );
+
+        let burned = total_input - total_output;
+        Ok(burned.try_into().unwrap_or(TransactionPriority::MAX))
+    }
+}
+
+/// A constraint checker that closes a channel immediately with both parties' agreement.
+///
+/// The only input is the channel. All outputs are `Coin<ID>`s locked to one of the parties, and
+/// each party is paid no more than their agreed balance. This works whether or not a unilateral
+/// close is in progress.
+#[derive(Serialize, Deserialize, Encode, Decode, Debug, PartialEq, Eq, Clone, TypeInfo)]
+pub struct CooperativeClose<const ID: u8> {
+    /// The first party's final balance.
+    pub balance_a: u128,
+    /// The second party's final balance.
+    pub balance_b: u128,
+    /// The first party's signature over the close message.
+    pub signature_a: Signature,
+    /// The second party's signature over the close message.
+    pub signature_b: Signature,
+}
+
+impl<V, const ID: u8> ConstraintChecker<V> for CooperativeClose<ID>
+where
+    V: Verifier + PartialEq + From<SigCheck>,
+{
+    type Error = ConstraintCheckerError;
+    type InherentHooks = ();
+
+    fn check(
+        &self,
+        inputs: &[Output<V>],
+        _peeks: &[Output<V>],
+        outputs: &[Output<V>],
+        _context: &CheckerContext,
+    ) -> Result<TransactionPriority, Self::Error> {
+        let burned = pay_out::<V, ID>(inputs, outputs, |channel| {
+            let terms = &channel.terms;
+            ensure!(
+                terms.check_signatures(
+                    &terms.close_message(self.balance_a, self.balance_b),
+                    &self.signature_a,
+                    &self.signature_b,
+                ),
+                ConstraintCheckerError::BadSignature
+            );
+            ensure!(
+                self.balance_a.checked_add(self.balance_b) == Some(terms.capacity),
+                ConstraintCheckerError::BalancesDoNotMatchCapacity
+            );
+            Ok((self.balance_a, self.balance_b))
+        })?;
+
+        Ok(burned.try_into().unwrap_or(TransactionPriority::MAX))
+    }
+
+    fn is_inherent(&self) -> bool {
+        false
+    }
+}
+
+/// A constraint checker that starts a unilateral close by submitting the latest signed state.
+///
+/// The only input is an open channel and the only output is the same channel, now closing with
+/// the submitted state, and a challenge period starting at the current block.
+#[derive(
+    Serialize, Deserialize, Encode, Decode, DebugNoBound, PartialEq, Eq, CloneNoBound, TypeInfo,
+)]
+#[scale_info(skip_type_params(T))]
+pub struct StartClose<T, const ID: u8> {
+    /// The latest state signed by both parties.
+    pub state: SignedChannelState,
+    _phantom: PhantomData<T>,
+}
+
+impl<T, const ID: u8> StartClose<T, ID> {
+    pub fn new(state: SignedChannelState) -> Self {
+        Self {
+            state,
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<T: PaymentChannelConfig, V: Verifier, const ID: u8> ConstraintChecker<V>
+    for StartClose<T, ID>
+{
+    type Error = ConstraintCheckerError;
+    type InherentHooks = ();
+
+    fn check(
+        &self,
+        inputs: &[Output<V>],
+        _peeks: &[Output<V>],
+        outputs: &[Output<V>],
+        _context: &CheckerContext,
+    ) -> Result<TransactionPriority, Self::Error> {
+        let channel = extract_channel::<V, ID>(inputs)?;
+        ensure!(
+            channel.closing.is_none(),
+            ConstraintCheckerError::AlreadyClosing
+        );
+        let state = channel.terms.check_state(&self.state)?;
+
+        let updated = extract_updated_channel::<V, ID>(outputs, &channel.terms)?;
+        let expected = PendingClose {
+            state,
+            closes_at: T::block_height().saturating_add(channel.terms.challenge_period),
+        };
+        ensure!(
+            updated.closing == Some(expected),
+            ConstraintCheckerError::ChannelMismatch
+        );
+
+        Ok(0)
+    }
+
+    fn is_inherent(&self) -> bool {
+        false
+    }
+}
+
+/// A constraint checker that challenges a unilateral close by submitting a newer signed state.
+///
+/// The only input is a closing channel and the only output is the same channel with the newer
+/// state. The challenge period is not extended.
+#[derive(
+    Serialize, Deserialize, Encode, Decode, DebugNoBound, PartialEq, Eq, CloneNoBound, TypeInfo,
+)]
+#[scale_info(skip_type_params(T))]
+pub struct Challenge<T, const ID: u8> {
+    /// A state signed by both parties that is newer than the one already submitted.
+    pub state: SignedChannelState,
+    _phantom: PhantomData<T>,
+}
+
+impl<T, const ID: u8> Challenge<T, ID> {
+    pub fn new(state: SignedChannelState) -> Self {
+        Self {
+            state,
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<T: PaymentChannelConfig, V: Verifier, const ID: u8> ConstraintChecker<V> for Challenge<T, ID> {
+    type Error = ConstraintCheckerError;
+    type InherentHooks = ();
+
+    fn check(
+        &self,
+        inputs: &[Output<V>],
+        _peeks: &[Output<V>],
+        outputs: &[Output<V>],
+        _context: &CheckerContext,
+    ) -> Result<TransactionPriority, Self::Error> {
+        let channel = extract_channel::<V, ID>(inputs)?;
+        let closing = channel.closing.ok_or(ConstraintCheckerError::NotClosing)?;
+        ensure!(
+            T::block_height() < closing.closes_at,
+            ConstraintCheckerError::ChallengePeriodOver
+        );
+        let state = channel.terms.check_state(&self.state)?;
+        ensure!(
+            state.sequence > closing.state.sequence,
+            ConstraintCheckerError::StaleState
+        );
+
+        let updated = extract_updated_channel::<V, ID>(outputs, &channel.terms)?;
+        let expected = PendingClose {
+            state,
+            closes_at: closing.closes_at,
+        };
+        ensure!(
+            updated.closing == Some(expected),
+            ConstraintCheckerError::ChannelMismatch
+        );
+
+        Ok(0)
+    }
+
+    fn is_inherent(&self) -> bool {
+        false
+    }
+}
+
+/// A constraint checker that pays out a unilaterally closed channel once its challenge period is over.
+///
+/// The only input is the closing channel. All outputs are `Coin<ID>`s locked to one of the parties,
+/// and each party is paid no more than their balance in the newest submitted state.
+#[derive(
+    Serialize,
+    Deserialize,
+    Encode,
+    Decode,
+    DebugNoBound,
+    DefaultNoBound,
+    PartialEq,
+    Eq,
+    CloneNoBound,
+    TypeInfo,
+)]
+#[scale_info(skip_type_params(T))]
+pub struct FinalizeClose<T, const ID: u8>(PhantomData<T>);
+
+impl<T, V, const ID: u8> ConstraintChecker<V> for FinalizeClose<T, ID>
+where
+    T: PaymentChannelConfig,
+    V: Verifier + PartialEq + From<SigCheck>,
+{
+    type Error = ConstraintCheckerError;
+    type InherentHooks = ();
+
+    fn check(
+        &self,
+        inputs: &[Output<V>],
+        _peeks: &[Output<V>],
+        outputs: &[Output<V>],
+        _context: &CheckerContext,
+    ) -> Result<TransactionPriority, Self::Error> {
+        let burned = pay_out::<V, ID>(inputs, outputs, |channel| {
+            let closing = channel
+                .closing
+                .as_ref()
+                .ok_or(ConstraintCheckerError::NotClosing)?;
+            ensure!(
+                T::block_height() >= closing.closes_at,
+                ConstraintCheckerError::ChallengePeriodNotOver
+            );
+            Ok((closing.state.balance_a, closing.state.balance_b))
+        })?;
+
+        Ok(burned.try_into().unwrap_or(TransactionPriority::MAX))
+    }
+
+    fn is_inherent(&self) -> bool {
+        false
+    }
+}
@@ -0,0 +1,368 @@
+//! Unit tests for the Payment Channel piece
+
+use super::*;
+use sp_core::{crypto::Pair as _, sr25519::Pair};
+use tuxedo_core::dynamic_typing::testing::Bogus;
+
+/// A mock config that always reports the same block height.
+pub struct AtHeight<const H: u32>;
+
+impl<const H: u32> PaymentChannelConfig for AtHeight<H> {
+    fn block_height() -> u32 {
+        H
+    }
+}
+
+fn alice() -> Pair {
+    Pair::from_seed(&[1u8; 32])
+}
+
+fn bob() -> Pair {
+    Pair::from_seed(&[2u8; 32])
+}
+
+fn owned_by(pair: &Pair) -> SigCheck {
+    SigCheck::new(pair.public())
+}
+
+/// A channel between alice and bob with a capacity of 100 and a challenge period of 10 blocks.
+fn terms() -> ChannelTerms {
+    ChannelTerms {
+        party_a: alice().public().into(),
+        party_b: bob().public().into(),
+        capacity: 100,
+        challenge_period: 10,
+        nonce: 0,
+    }
+}
+
+fn open_channel() -> Channel<0> {
+    Channel {
+        terms: terms(),
+        closing: None,
+    }
+}
+
+fn closing_channel(state: ChannelState, closes_at: u32) -> Channel<0> {
+    Channel {
+        terms: terms(),
+        closing: Some(PendingClose { state, closes_at }),
+    }
+}
+
+fn state(sequence: u64, balance_a: u128, balance_b: u128) -> ChannelState {
+    ChannelState {
+        sequence,
+        balance_a,
+        balance_b,
+    }
+}
+
+fn sign_state(state: ChannelState) -> SignedChannelState {
+    let message = terms().state_message(&state);
+    SignedChannelState {
+        state,
+        signature_a: alice().sign(&message),
+        signature_b: bob().sign(&message),
+    }
+}
+
+fn check<C: ConstraintChecker<SigCheck>>(
+    checker: C,
+    inputs: Vec<Output<SigCheck>>,
+    outputs: Vec<Output<SigCheck>>,
+) -> Result<TransactionPriority, C::Error> {
+    checker.check(&inputs, &[], &outputs, &Default::default())
+}
+
+#[test]
+fn open_channel_works() {
+    let inputs = vec![
+        (Coin::<0>(70), owned_by(&alice())).into(),
+        (Coin::<0>(40), owned_by(&bob())).into(),
+    ];
+    let outputs = vec![
+        (open_channel(), owned_by(&alice())).into(),
+        (Coin::<0>(5), owned_by(&bob())).into(),
+    ];
+
+    assert_eq!(check(OpenChannel::<0>, inputs, outputs), Ok(5));
+}
+
+#[test]
+fn open_channel_exceeding_inputs_fails() {
+    let inputs = vec![(Coin::<0>(70), owned_by(&alice())).into()];
+    let outputs = vec![(open_channel(), owned_by(&alice())).into()];
+
+    assert_eq!(
+        check(OpenChannel::<0>, inputs, outputs),
+        Err(ConstraintCheckerError::OutputsExceedInputs)
+    );
+}
+
+#[test]
+fn open_channel_already_closing_fails() {
+    let inputs = vec![(Coin::<0>(100), owned_by(&alice())).into()];
+    let outputs = vec![(closing_channel(state(0, 100, 0), 10), owned_by(&alice())).into()];
+
+    assert_eq!(
+        check(OpenChannel::<0>, inputs, outputs),
+        Err(ConstraintCheckerError::OpenedClosing)
+    );
+}
+
+#[test]
+fn cooperative_close_works() {
+    let message = terms().close_message(30, 70);
+    let checker = CooperativeClose::<0> {
+        balance_a: 30,
+        balance_b: 70,
+        signature_a: alice().sign(&message),
+        signature_b: bob().sign(&message),
+    };
+    let inputs = vec![(open_channel(), owned_by(&alice())).into()];
+    let outputs = vec![
+        (Coin::<0>(30), owned_by(&alice())).into(),
+        (Coin::<0>(69), owned_by(&bob())).into(),
+    ];
+
+    assert_eq!(check(checker, inputs, outputs), Ok(1));
+}
+
+#[test]
+fn cooperative_close_with_one_signature_fails() {
+    let message = terms().close_message(30, 70);
+    let checker = CooperativeClose::<0> {
+        balance_a: 30,
+        balance_b: 70,
+        signature_a: alice().sign(&message),
+        signature_b: alice().sign(&message),
+    };
+    let inputs = vec![(open_channel(), owned_by(&alice())).into()];
+    let outputs = vec![
+        (Coin::<0>(30), owned_by(&alice())).into(),
+        (Coin::<0>(70), owned_by(&bob())).into(),
+    ];
+
+    assert_eq!(
+        check(checker, inputs, outputs),
+        Err(ConstraintCheckerError::BadSignature)
+    );
+}
+
+#[test]
+fn cooperative_close_overpaying_fails() {
+    let message = terms().close_message(30, 70);
+    let checker = CooperativeClose::<0> {
+        balance_a: 30,
+        balance_b: 70,
+        signature_a: alice().sign(&message),
+        signature_b: bob().sign(&message),
+    };
+    let inputs = vec![(open_channel(), owned_by(&alice())).into()];
+    let outputs = vec![(Coin::<0>(31), owned_by(&alice())).into()];
+
+    assert_eq!(
+        check(checker, inputs, outputs),
+        Err(ConstraintCheckerError::Overpaid)
+    );
+}
+
+#[test]
+fn cooperative_close_to_outsider_fails() {
+    let message = terms().close_message(30, 70);
+    let checker = CooperativeClose::<0> {
+        balance_a: 30,
+        balance_b: 70,
+        signature_a: alice().sign(&message),
+        signature_b: bob().sign(&message),
+    };
+    let inputs = vec![(open_channel(), owned_by(&alice())).into()];
+    let outputs = vec![(Coin::<0>(30), SigCheck::new(H256::zero())).into()];
+
+    assert_eq!(
+        check(checker, inputs, outputs),
+        Err(ConstraintCheckerError::PaidToWrongVerifier)
+    );
+}
+
+#[test]
+fn start_close_works() {
+    let checker = StartClose::<AtHeight<50>, 0>::new(sign_state(state(3, 40, 60)));
+    let inputs = vec![(open_channel(), owned_by(&alice())).into()];
+    let outputs = vec![(closing_channel(state(3, 40, 60), 60), owned_by(&alice())).into()];
+
+    assert_eq!(check(checker, inputs, outputs), Ok(0));
+}
+
+#[test]
+fn start_close_with_wrong_closing_height_fails() {
+    let checker = StartClose::<AtHeight<50>, 0>::new(sign_state(state(3, 40, 60)));
+    let inputs = vec![(open_channel(), owned_by(&alice())).into()];
+    let outputs = vec![(closing_channel(state(3, 40, 60), 51), owned_by(&alice())).into()];
+
+    assert_eq!(
+        check(checker, inputs, outputs),
+        Err(ConstraintCheckerError::ChannelMismatch)
+    );
+}
+
+#[test]
+fn start_close_with_unsigned_state_fails() {
+    let mut signed = sign_state(state(3, 40, 60));
+    signed.state.balance_a = 60;
+    signed.state.balance_b = 40;
+    let checker = StartClose::<AtHeight<50>, 0>::new(signed);
+    let inputs = vec![(open_channel(), owned_by(&alice())).into()];
+    let outputs = vec![(closing_channel(state(3, 60, 40), 60), owned_by(&alice())).into()];
+
+    assert_eq!(
+        check(checker, inputs, outputs),
+        Err(ConstraintCheckerError::BadSignature)
+    );
+}
+
+#[test]
+fn start_close_with_wrong_total_fails() {
+    let checker = StartClose::<AtHeight<50>, 0>::new(sign_state(state(3, 40, 70)));
+    let inputs = vec![(open_channel(), owned_by(&alice())).into()];
+    let outputs = vec![(closing_channel(state(3, 40, 70), 60), owned_by(&alice())).into()];
+
+    assert_eq!(
+        check(checker, inputs, outputs),
+        Err(ConstraintCheckerError::BalancesDoNotMatchCapacity)
+    );
+}
+
+#[test]
+fn start_close_twice_fails() {
+    let checker = StartClose::<AtHeight<50>, 0>::new(sign_state(state(3, 40, 60)));
+    let inputs = vec![(closing_channel(state(1, 100, 0), 55), owned_by(&alice())).into()];
+    let outputs = vec![(closing_channel(state(3, 40, 60), 60), owned_by(&alice())).into()];
+
+    assert_eq!(
+        check(checker, inputs, outputs),
+        Err(ConstraintCheckerError::AlreadyClosing)
+    );
+}
+
+#[test]
+fn challenge_works() {
+    let checker = Challenge::<AtHeight<55>, 0>::new(sign_state(state(5, 20, 80)));
+    let inputs = vec![(closing_channel(state(3, 40, 60), 60), owned_by(&alice())).into()];
+    let outputs = vec![(closing_channel(state(5, 20, 80), 60), owned_by(&alice())).into()];
+
+    assert_eq!(check(checker, inputs, outputs), Ok(0));
+}
+
+#[test]
+fn challenge_with_stale_state_fails() {
+    let checker = Challenge::<AtHeight<55>, 0>::new(sign_state(state(2, 20, 80)));
+    let inputs = vec![(closing_channel(state(3, 40, 60), 60), owned_by(&alice())).into()];
+    let outputs = vec![(closing_channel(state(2, 20, 80), 60), owned_by(&alice())).into()];
+
+    assert_eq!(
+        check(checker, inputs, outputs),
+        Err(ConstraintCheckerError::StaleState)
+    );
+}
+
+#[test]
+fn challenge_after_period_fails() {
+    let checker = Challenge::<AtHeight<60>, 0>::new(sign_state(state(5, 20, 80)));
+    let inputs = vec![(closing_channel(state(3, 40, 60), 60), owned_by(&alice())).into()];
+    let outputs = vec![(closing_channel(state(5, 20, 80), 60), owned_by(&alice())).into()];
+
+    assert_eq!(
+        check(checker, inputs, outputs),
+        Err(ConstraintCheckerError::ChallengePeriodOver)
+    );
+}
+
+#[test]
+fn challenge_open_channel_fails() {
+    let checker = Challenge::<AtHeight<55>, 0>::new(sign_state(state(5, 20, 80)));
+    let inputs = vec![(open_channel(), owned_by(&alice())).into()];
+    let outputs = vec![(closing_channel(state(5, 20, 80), 60), owned_by(&alice())).into()];
+
+    assert_eq!(
+        check(checker, inputs, outputs),
+        Err(ConstraintCheckerError::NotClosing)
+    );
+}
+
+#[test]
+fn challenge_extending_period_fails() {
+    let checker = Challenge::<AtHeight<55>, 0>::new(sign_state(state(5, 20, 80)));
+    let inputs = vec![(closing_channel(state(3, 40, 60), 60), owned_by(&alice())).into()];
+    let outputs = vec![(closing_channel(state(5, 20, 80), 65), owned_by(&alice())).into()];
+
+    assert_eq!(
+        check(checker, inputs, outputs),
+        Err(ConstraintCheckerError::ChannelMismatch)
+    );
+}
+
+#[test]
+fn finalize_close_works() {
+    let inputs = vec![(closing_channel(state(5, 20, 80), 60), owned_by(&alice())).into()];
+    let outputs = vec![
+        (Coin::<0>(20), owned_by(&alice())).into(),
+        (Coin::<0>(80), owned_by(&bob())).into(),
+    ];
+
+    assert_eq!(
+        check(FinalizeClose::<AtHeight<60>, 0>::default(), inputs, outputs),
+        Ok(0)
+    );
+}
+
+#[test]
+fn finalize_close_during_challenge_period_fails() {
+    let inputs = vec![(closing_channel(state(5, 20, 80), 60), owned_by(&alice())).into()];
+    let outputs = vec![
+        (Coin::<0>(20), owned_by(&alice())).into(),
+        (Coin::<0>(80), owned_by(&bob())).into(),
+    ];
+
+    assert_eq!(
+        check(FinalizeClose::<AtHeight<59>, 0>::default(), inputs, outputs),
+        Err(ConstraintCheckerError::ChallengePeriodNotOver)
+    );
+}
+
+#[test]
+fn finalize_close_overpaying_fails() {
+    let inputs = vec![(closing_channel(state(5, 20, 80), 60), owned_by(&alice())).into()];
+    let outputs = vec![
+        (Coin::<0>(21), owned_by(&alice())).into(),
+        (Coin::<0>(79), owned_by(&bob())).into(),
+    ];
+
+    assert_eq!(
+        check(FinalizeClose::<AtHeight<60>, 0>::default(), inputs, outputs),
+        Err(ConstraintCheckerError::Overpaid)
+    );
+}
+
+#[test]
+fn finalize_open_channel_fails() {
+    let inputs = vec![(open_channel(), owned_by(&alice())).into()];
+    let outputs = vec![(Coin::<0>(20), owned_by(&alice())).into()];
+
+    assert_eq!(
+        check(FinalizeClose::<AtHeight<60>, 0>::default(), inputs, outputs),
+        Err(ConstraintCheckerError::NotClosing)
+    );
+}
+
+#[test]
+fn finalize_non_channel_fails() {
+    let inputs = vec![(Bogus, owned_by(&alice())).into()];
+    let outputs = vec![(Coin::<0>(20), owned_by(&alice())).into()];
+
+    assert_eq!(
+        check(FinalizeClose::<AtHeight<60>, 0>::default(), inputs, outputs),
+        Err(ConstraintCheckerError::BadlyTypedInput)
+    );
+}
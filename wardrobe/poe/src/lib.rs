@@ -14,40 +14,183 @@
 //! to boot subsequent redundant claims when they are discovered. This difference is analogous to
 //! the difference between recorded and registered land
 //! https://cannerlaw.com/blog/the-difference-of-recorded-and-registered-land/
+//!
+//! Runtimes may also configure claims to expire some number of blocks after their effective height.
+//! Expired claims can be cleaned up from the state with the `PoeExpire` constraint checker, and
+//! whoever does so may be rewarded with newly minted `Coin<ID>`s from the money piece.
+//!
+//! Documents that change over time can be revised with `PoeRevise`, which replaces a claim with a
+//! claim to the new revision that references the hash of the previous one. The chain of revisions
+//! remains in the history of the blockchain.
+//!
+//! Many documents can be notarized at once by claiming the root of a Merkle tree over their hashes
+//! with `PoeBatchClaim`. Anyone holding a document from the batch can then prove that it existed
+//! with a [`MerkleProof`], which is checked by [`verify_inclusion`], or through the [`PoeApi`]
+//! runtime API.
 
 #![cfg_attr(not(feature = "std"), no_std)]
 
 use core::marker::PhantomData;
 
+use money::Coin;
 use parity_scale_codec::{Decode, Encode};
 use scale_info::TypeInfo;
 use serde::{Deserialize, Serialize};
 use sp_core::H256;
-use sp_runtime::transaction_validity::TransactionPriority;
-use sp_std::fmt::Debug;
+use sp_runtime::{
+    traits::{BlakeTwo256, Hash as HashT},
+    transaction_validity::TransactionPriority,
+};
+use sp_std::{fmt::Debug, vec, vec::Vec};
 use tuxedo_core::{
     dynamic_typing::{DynamicallyTypedData, UtxoData},
     ensure,
+    metadata::MetadataBuilder,
     support_macros::{CloneNoBound, DebugNoBound},
-    SimpleConstraintChecker,
+    CheckerContext, SimpleConstraintChecker,
 };
 
 #[cfg(test)]
 mod tests;
 
 // Notice this type doesn't have to be public. Cool.
-#[derive(Serialize, Deserialize, Encode, Decode, Debug, PartialEq, Eq, Clone)]
+// It is still described in the runtime metadata through `describe_utxo_types`.
+#[derive(Serialize, Deserialize, Encode, Decode, Debug, PartialEq, Eq, Clone, TypeInfo)]
 struct ClaimData {
     /// The hash of the data whose existence is being proven.
     claim: H256,
     /// The time (in block height) at which the claim becomes valid.
     effective_height: u32, //TODO get the generic block height type
+    /// The hash of the previous revision of the document, if this claim is a revision.
+    previous: Option<H256>,
 }
 
 impl UtxoData for ClaimData {
     const TYPE_ID: [u8; 4] = *b"poe_";
 }
 
+/// A claim to the existence of many documents at once.
+#[derive(Serialize, Deserialize, Encode, Decode, Debug, PartialEq, Eq, Clone, TypeInfo)]
+struct BatchClaimData {
+    /// The root of the Merkle tree over the hashes of the documents whose existence is being proven.
+    root: H256,
+    /// The number of documents in the tree.
+    leaves: u32,
+    /// The time (in block height) at which the claim becomes valid.
+    effective_height: u32,
+}
+
+impl UtxoData for BatchClaimData {
+    const TYPE_ID: [u8; 4] = *b"poeb";
+}
+
+/// Describe the claims that this piece stores in the runtime metadata.
+pub fn describe_utxo_types(builder: MetadataBuilder) -> MetadataBuilder {
+    builder
+        .with_utxo_type::<ClaimData>()
+        .with_utxo_type::<BatchClaimData>()
+}
+
+/// A proof that a document is included in a batch claim.
+#[derive(Serialize, Deserialize, Encode, Decode, Debug, PartialEq, Eq, Clone, TypeInfo)]
+pub struct MerkleProof {
+    /// The position of the document in the batch.
+    pub index: u32,
+    /// The sibling of each node on the path from the document up to the root.
+    pub siblings: Vec<H256>,
+}
+
+/// The number of levels between the leaves and the root of a Merkle tree with the given number
+/// of leaves. This is also the length of every valid inclusion proof.
+fn merkle_depth(leaves: u32) -> usize {
+    let mut depth = 0;
+    let mut width = leaves;
+    while width > 1 {
+        width = (width + 1) / 2;
+        depth += 1;
+    }
+    depth
+}
+
+/// Hash two sibling nodes into their parent.
+fn merkle_parent(left: &H256, right: &H256) -> H256 {
+    BlakeTwo256::hash_of(&(left, right))
+}
+
+/// Compute each level of the Merkle tree over the given document hashes, starting with the leaves.
+///
+/// A node without a sibling is paired with itself.
+fn merkle_levels(documents: &[H256]) -> Vec<Vec<H256>> {
+    let mut levels = vec![documents.to_vec()];
+    while levels[levels.len() - 1].len() > 1 {
+        let level = &levels[levels.len() - 1];
+        let next = level
+            .chunks(2)
+            .map(|pair| merkle_parent(&pair[0], pair.last().expect("chunks are never empty")))
+            .collect();
+        levels.push(next);
+    }
+    levels
+}
+
+/// The Merkle root over the given document hashes, to be claimed in a batch claim.
+///
+/// Returns `None` if there are no documents.
+pub fn merkle_root(documents: &[H256]) -> Option<H256> {
+    merkle_levels(documents).pop()?.first().copied()
+}
+
+/// A proof that the document at the given index is included in the batch of the given documents.
+///
+/// Returns `None` if the index is out of bounds.
+pub fn merkle_proof(documents: &[H256], index: u32) -> Option<MerkleProof> {
+    if index as usize >= documents.len() {
+        return None;
+    }
+
+    let levels = merkle_levels(documents);
+    let mut position = index as usize;
+    let mut siblings = Vec::new();
+    for level in &levels[..levels.len() - 1] {
+        let sibling = position ^ 1;
+        siblings.push(*level.get(sibling).unwrap_or(&level[position]));
+        position /= 2;
+    }
+
+    Some(MerkleProof { index, siblings })
+}
+
+/// Check that a document is included in the batch with the given Merkle root and number of leaves.
+///
+/// The proof must have exactly one sibling per level of the tree, so an inner node of the tree
+/// can never pass for a document.
+pub fn verify_inclusion(root: H256, leaves: u32, document: H256, proof: &MerkleProof) -> bool {
+    if proof.index >= leaves || proof.siblings.len() != merkle_depth(leaves) {
+        return false;
+    }
+
+    let mut position = proof.index;
+    let mut node = document;
+    for sibling in &proof.siblings {
+        node = if position % 2 == 0 {
+            merkle_parent(&node, sibling)
+        } else {
+            merkle_parent(sibling, &node)
+        };
+        position /= 2;
+    }
+
+    node == root
+}
+
+sp_api::decl_runtime_apis! {
+    /// An API that lets anyone check proofs against batch claims without reimplementing the tree.
+    pub trait PoeApi {
+        /// Whether the document is included in the batch with the given Merkle root and number of leaves.
+        fn verify_inclusion(root: H256, leaves: u32, document: H256, proof: MerkleProof) -> bool;
+    }
+}
+
 /// Errors that can occur when checking PoE Transactions
 #[derive(Serialize, Deserialize, Encode, Decode, Debug, PartialEq, Eq, Clone)]
 pub enum ConstraintCheckerError {
@@ -65,14 +208,46 @@ pub enum ConstraintCheckerError {
     /// The effective height of this claim is in the past,
     /// So the claim cannot be created.
     EffectiveHeightInPast,
+    /// A transferred claim does not have the same hash and effective height as the original.
+    TransferredClaimMismatch,
+    /// The runtime does not configure a claim TTL, so claims never expire.
+    ClaimsNeverExpire,
+    /// A claim was cleaned up before its TTL had passed.
+    ClaimNotExpired,
+    /// The reward for cleaning up expired claims is not worth the configured amount.
+    WrongRewardAmount,
+    /// The reward for cleaning up expired claims overflows the value type.
+    RewardOverflow,
+    /// A batch claim does not contain any documents.
+    EmptyBatch,
+    /// A new claim references a previous revision. Only revisions may do that.
+    UnexpectedPredecessor,
+    /// A revision does not reference the hash of the claim it revises.
+    WrongPredecessor,
+    /// A revision claims the same hash as the claim it revises.
+    RevisionUnchanged,
+    /// There must be exactly one winning claim in a dispute.
+    WrongNumberPeeks,
+    /// A losing claim in a dispute does not claim the same hash as the winning claim.
+    DisputedClaimMismatch,
+    /// A losing claim in a dispute belongs to a different revision chain than the winning claim.
+    DifferentLineage,
+    /// A losing claim in a dispute did not become effective strictly after the winning claim.
+    LoserNotLater,
 }
 
 /// Configuration items for the Proof of Existence piece when it is
 /// instantiated in a concrete runtime.
 pub trait PoeConfig {
-    /// A means of getting the current block height.
-    /// Probably this will be the Tuxedo Executive
-    fn block_height() -> u32;
+    /// The number of blocks after its effective height that a claim expires, if claims expire at all.
+    ///
+    /// Default is `None`, so claims never expire.
+    const CLAIM_TTL: Option<u32> = None;
+
+    /// The value that is minted to whoever cleans up an expired claim, for each claim.
+    ///
+    /// Default is zero, so cleaning up is not rewarded.
+    const EXPIRY_REWARD: u128 = 0;
 }
 
 /// A constraint checker to create claims.
@@ -93,6 +268,7 @@ impl<T: PoeConfig> SimpleConstraintChecker for PoeClaim<T> {
         input_data: &[DynamicallyTypedData],
         _peeks: &[DynamicallyTypedData],
         output_data: &[DynamicallyTypedData],
+        context: &CheckerContext,
     ) -> Result<TransactionPriority, Self::Error> {
         // Make sure there are no inputs
         ensure!(
@@ -113,15 +289,63 @@ impl<T: PoeConfig> SimpleConstraintChecker for PoeClaim<T> {
                 //TODO we're grabbing the block height function directly from
                 // the runtime level. This needs to be made available through some
                 // kind of config.
-                output.effective_height >= T::block_height(),
+                output.effective_height >= context.block_height,
                 ConstraintCheckerError::EffectiveHeightInPast
             );
+            ensure!(
+                output.previous.is_none(),
+                ConstraintCheckerError::UnexpectedPredecessor
+            );
         }
 
         Ok(0)
     }
 }
 
+/// A constraint checker to create a batch claim.
+///
+/// Unlike the regular claim constraint checker, this creates exactly one claim, whose Merkle root
+/// commits to every document in the batch.
+#[derive(
+    Serialize, Deserialize, Encode, Decode, DebugNoBound, CloneNoBound, PartialEq, Eq, TypeInfo,
+)]
+pub struct PoeBatchClaim<T>(PhantomData<T>);
+
+impl<T: PoeConfig> SimpleConstraintChecker for PoeBatchClaim<T> {
+    type Error = ConstraintCheckerError;
+
+    fn check(
+        &self,
+        input_data: &[DynamicallyTypedData],
+        _peeks: &[DynamicallyTypedData],
+        output_data: &[DynamicallyTypedData],
+        context: &CheckerContext,
+    ) -> Result<TransactionPriority, Self::Error> {
+        // Make sure there are no inputs, and a single batch claim
+        ensure!(
+            input_data.is_empty(),
+            ConstraintCheckerError::WrongNumberInputs
+        );
+        ensure!(
+            output_data.len() == 1,
+            ConstraintCheckerError::WrongNumberOutputs
+        );
+
+        // The batch must have at least one document, and the same relaxed height rule
+        // as regular claims applies.
+        let output = output_data[0]
+            .extract::<BatchClaimData>()
+            .map_err(|_| ConstraintCheckerError::BadlyTypedOutput)?;
+        ensure!(output.leaves > 0, ConstraintCheckerError::EmptyBatch);
+        ensure!(
+            output.effective_height >= context.block_height,
+            ConstraintCheckerError::EffectiveHeightInPast
+        );
+
+        Ok(0)
+    }
+}
+
 /// A constraint checker to revoke claims.
 ///
 /// Like the creation constraint checker, this allows batch revocation.
@@ -136,6 +360,7 @@ impl SimpleConstraintChecker for PoeRevoke {
         input_data: &[DynamicallyTypedData],
         _peeks: &[DynamicallyTypedData],
         output_data: &[DynamicallyTypedData],
+        _context: &CheckerContext,
     ) -> Result<TransactionPriority, Self::Error> {
         // Make sure there are no outputs
         ensure!(
@@ -154,10 +379,182 @@ impl SimpleConstraintChecker for PoeRevoke {
     }
 }
 
+/// A constraint checker to revise claims.
+///
+/// Each input is a claim, and each output is a claim to the next revision of the document at the
+/// same index. The revision must reference the hash of the claim it replaces, and follows the same
+/// relaxed height rule as new claims.
+///
+/// Like the other constraint checkers, this allows batch revisions.
+#[derive(
+    Serialize, Deserialize, Encode, Decode, DebugNoBound, CloneNoBound, PartialEq, Eq, TypeInfo,
+)]
+pub struct PoeRevise<T>(PhantomData<T>);
+
+impl<T: PoeConfig> SimpleConstraintChecker for PoeRevise<T> {
+    type Error = ConstraintCheckerError;
+
+    fn check(
+        &self,
+        input_data: &[DynamicallyTypedData],
+        _peeks: &[DynamicallyTypedData],
+        output_data: &[DynamicallyTypedData],
+        context: &CheckerContext,
+    ) -> Result<TransactionPriority, Self::Error> {
+        // Make sure there is something to revise, and that every claim is revised
+        ensure!(
+            !input_data.is_empty(),
+            ConstraintCheckerError::WrongNumberInputs
+        );
+        ensure!(
+            input_data.len() == output_data.len(),
+            ConstraintCheckerError::WrongNumberOutputs
+        );
+
+        for (untyped_input, untyped_output) in input_data.iter().zip(output_data) {
+            let input = untyped_input
+                .extract::<ClaimData>()
+                .map_err(|_| ConstraintCheckerError::BadlyTypedInput)?;
+            let output = untyped_output
+                .extract::<ClaimData>()
+                .map_err(|_| ConstraintCheckerError::BadlyTypedOutput)?;
+            ensure!(
+                output.previous == Some(input.claim),
+                ConstraintCheckerError::WrongPredecessor
+            );
+            ensure!(
+                output.claim != input.claim,
+                ConstraintCheckerError::RevisionUnchanged
+            );
+            ensure!(
+                output.effective_height >= context.block_height,
+                ConstraintCheckerError::EffectiveHeightInPast
+            );
+        }
+
+        Ok(0)
+    }
+}
+
+/// A constraint checker to transfer claims to new owners.
+///
+/// Each input is a claim, and each output is the claim at the same index, re-created under
+/// whichever verifier the new owner chooses. The claimed hash and the effective height must
+/// not change, so the claim keeps its original priority in any later dispute.
+///
+/// Like the other constraint checkers, this allows batch transfers.
+#[derive(Serialize, Deserialize, Encode, Decode, Debug, PartialEq, Eq, Clone, TypeInfo)]
+pub struct PoeTransfer;
+
+impl SimpleConstraintChecker for PoeTransfer {
+    type Error = ConstraintCheckerError;
+
+    fn check(
+        &self,
+        input_data: &[DynamicallyTypedData],
+        _peeks: &[DynamicallyTypedData],
+        output_data: &[DynamicallyTypedData],
+        _context: &CheckerContext,
+    ) -> Result<TransactionPriority, Self::Error> {
+        // Make sure there is something to transfer, and that every claim is re-created
+        ensure!(
+            !input_data.is_empty(),
+            ConstraintCheckerError::WrongNumberInputs
+        );
+        ensure!(
+            input_data.len() == output_data.len(),
+            ConstraintCheckerError::WrongNumberOutputs
+        );
+
+        for (untyped_input, untyped_output) in input_data.iter().zip(output_data) {
+            let input = untyped_input
+                .extract::<ClaimData>()
+                .map_err(|_| ConstraintCheckerError::BadlyTypedInput)?;
+            let output = untyped_output
+                .extract::<ClaimData>()
+                .map_err(|_| ConstraintCheckerError::BadlyTypedOutput)?;
+            ensure!(
+                output == input,
+                ConstraintCheckerError::TransferredClaimMismatch
+            );
+        }
+
+        Ok(0)
+    }
+}
+
+/// A constraint checker to clean up expired claims.
+///
+/// Each input is a claim whose TTL has passed. If the runtime configures a reward, there may be
+/// a single output: a `Coin<ID>` worth the reward for each claim, locked to whichever verifier
+/// the cleaner chooses. Otherwise there are no outputs.
+///
+/// TODO Claims can only be cleaned up by whoever satisfies their verifiers, so for now only the
+/// claimers themselves, or anyone at all for claims that are up for grabs, can clean them up.
+/// This will become a true public cleanup once there are evictions (see the `brainstorm` below).
+#[derive(
+    Serialize, Deserialize, Encode, Decode, DebugNoBound, CloneNoBound, PartialEq, Eq, TypeInfo,
+)]
+pub struct PoeExpire<T, const ID: u8>(PhantomData<T>);
+
+impl<T: PoeConfig, const ID: u8> SimpleConstraintChecker for PoeExpire<T, ID> {
+    type Error = ConstraintCheckerError;
+
+    fn check(
+        &self,
+        input_data: &[DynamicallyTypedData],
+        _peeks: &[DynamicallyTypedData],
+        output_data: &[DynamicallyTypedData],
+        context: &CheckerContext,
+    ) -> Result<TransactionPriority, Self::Error> {
+        let ttl = T::CLAIM_TTL.ok_or(ConstraintCheckerError::ClaimsNeverExpire)?;
+        ensure!(
+            !input_data.is_empty(),
+            ConstraintCheckerError::WrongNumberInputs
+        );
+
+        // Make sure every claim has outlived its TTL
+        for untyped_input in input_data {
+            let input = untyped_input
+                .extract::<ClaimData>()
+                .map_err(|_| ConstraintCheckerError::BadlyTypedInput)?;
+            ensure!(
+                context.block_height.saturating_sub(input.effective_height) >= ttl,
+                ConstraintCheckerError::ClaimNotExpired
+            );
+        }
+
+        // The cleaner may claim the reward, but doesn't have to
+        let reward = T::EXPIRY_REWARD
+            .checked_mul(input_data.len() as u128)
+            .ok_or(ConstraintCheckerError::RewardOverflow)?;
+        match output_data {
+            [] => (),
+            [untyped_output] if reward > 0 => {
+                let output = untyped_output
+                    .extract::<Coin<ID>>()
+                    .map_err(|_| ConstraintCheckerError::BadlyTypedOutput)?;
+                ensure!(
+                    output.0 == reward,
+                    ConstraintCheckerError::WrongRewardAmount
+                );
+            }
+            _ => return Err(ConstraintCheckerError::WrongNumberOutputs),
+        }
+
+        Ok(0)
+    }
+}
+
 /// A constraint checker that resolves claim disputes by keeping whichever claim came first.
 ///
-/// TODO this will work much more elegantly once peek is implemented. We only need to peek at the
-/// older winning claim because it will remain in state afterwards.
+/// The winning claim is the only peek, and remains in state afterwards. Each input is a losing
+/// claim, which is removed. Losing claims must claim the same hash as the winner, and must have
+/// become effective strictly after it.
+///
+/// Claims only compete within the same revision chain. A document that is reached as a revision
+/// of one document, and independently as a revision of another, or as a fresh claim, has a
+/// different history in each case, so none of those claims can boot the others.
 ///
 /// TODO what shall we do about the verifier? Each claimer may have given their claim a verifier
 /// such that their own private signature. Perhaps there should be a way for a constraint checker to override
@@ -172,20 +569,47 @@ impl SimpleConstraintChecker for PoeDispute {
 
     fn check(
         &self,
-        _input_data: &[DynamicallyTypedData],
-        _peeks: &[DynamicallyTypedData],
-        _output_data: &[DynamicallyTypedData],
+        input_data: &[DynamicallyTypedData],
+        peeks: &[DynamicallyTypedData],
+        output_data: &[DynamicallyTypedData],
+        _context: &CheckerContext,
     ) -> Result<TransactionPriority, Self::Error> {
-        todo!("implement this once we have at least peeks and maybe evictions")
-
-        // Make sure there is at least one input (once peek is ready, it will become a peek)
-        // This first input (or only peek) is the claim that will be retained.
+        // Make sure there is one winner, at least one loser, and no outputs
+        ensure!(peeks.len() == 1, ConstraintCheckerError::WrongNumberPeeks);
+        ensure!(
+            !input_data.is_empty(),
+            ConstraintCheckerError::WrongNumberInputs
+        );
+        ensure!(
+            output_data.is_empty(),
+            ConstraintCheckerError::WrongNumberOutputs
+        );
 
-        // Make sure that all other inputs, claim the same hash as the winner.
+        let winner = peeks[0]
+            .extract::<ClaimData>()
+            .map_err(|_| ConstraintCheckerError::BadlyTypedInput)?;
 
-        // Make sure that all other claims have block heights strictly greater than the winner.
+        // Make sure that all the losers claim the same hash in the same revision chain,
+        // and came strictly later than the winner.
+        for untyped_input in input_data {
+            let loser = untyped_input
+                .extract::<ClaimData>()
+                .map_err(|_| ConstraintCheckerError::BadlyTypedInput)?;
+            ensure!(
+                loser.claim == winner.claim,
+                ConstraintCheckerError::DisputedClaimMismatch
+            );
+            ensure!(
+                loser.previous == winner.previous,
+                ConstraintCheckerError::DifferentLineage
+            );
+            ensure!(
+                loser.effective_height > winner.effective_height,
+                ConstraintCheckerError::LoserNotLater
+            );
+        }
 
-        //TODO what to do about the verifiers on those losing claims.
+        Ok(0)
     }
 }
 
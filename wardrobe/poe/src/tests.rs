@@ -1 +1,426 @@
-//! Tests for the Proof of Existence Piece are TODO
+//! Tests for the Proof of Existence Piece
+
+use super::*;
+
+/// A bogus data type used in tests for type validation
+#[derive(Encode, Decode)]
+struct Bogus;
+
+impl UtxoData for Bogus {
+    const TYPE_ID: [u8; 4] = *b"bogs";
+}
+
+fn claim(byte: u8, effective_height: u32) -> DynamicallyTypedData {
+    ClaimData {
+        claim: H256::repeat_byte(byte),
+        effective_height,
+        previous: None,
+    }
+    .into()
+}
+
+fn revision(byte: u8, effective_height: u32, previous: u8) -> DynamicallyTypedData {
+    ClaimData {
+        claim: H256::repeat_byte(byte),
+        effective_height,
+        previous: Some(H256::repeat_byte(previous)),
+    }
+    .into()
+}
+
+#[test]
+fn transfer_claim_works() {
+    let result = PoeTransfer.check(&[claim(1, 5)], &[], &[claim(1, 5)], &context());
+    assert_eq!(result, Ok(0));
+}
+
+#[test]
+fn transfer_many_claims_works() {
+    let claims = vec![claim(1, 5), claim(2, 6)];
+    let result = PoeTransfer.check(&claims, &[], &claims, &context());
+    assert_eq!(result, Ok(0));
+}
+
+#[test]
+fn transfer_nothing_fails() {
+    let result = PoeTransfer.check(&[], &[], &[], &context());
+    assert_eq!(result, Err(ConstraintCheckerError::WrongNumberInputs));
+}
+
+#[test]
+fn transfer_dropping_claim_fails() {
+    let result = PoeTransfer.check(&[claim(1, 5), claim(2, 6)], &[], &[claim(1, 5)], &context());
+    assert_eq!(result, Err(ConstraintCheckerError::WrongNumberOutputs));
+}
+
+#[test]
+fn transfer_changing_hash_fails() {
+    let result = PoeTransfer.check(&[claim(1, 5)], &[], &[claim(2, 5)], &context());
+    assert_eq!(
+        result,
+        Err(ConstraintCheckerError::TransferredClaimMismatch)
+    );
+}
+
+#[test]
+fn transfer_changing_height_fails() {
+    let result = PoeTransfer.check(&[claim(1, 5)], &[], &[claim(1, 4)], &context());
+    assert_eq!(
+        result,
+        Err(ConstraintCheckerError::TransferredClaimMismatch)
+    );
+}
+
+#[test]
+fn transfer_badly_typed_input_fails() {
+    let result = PoeTransfer.check(&[Bogus.into()], &[], &[claim(1, 5)], &context());
+    assert_eq!(result, Err(ConstraintCheckerError::BadlyTypedInput));
+}
+
+#[test]
+fn transfer_badly_typed_output_fails() {
+    let result = PoeTransfer.check(&[claim(1, 5)], &[], &[Bogus.into()], &context());
+    assert_eq!(result, Err(ConstraintCheckerError::BadlyTypedOutput));
+}
+
+/// The mock config says claims expire after ten blocks,
+/// and cleaning each one up is worth five coins.
+pub struct TestConfig;
+
+impl PoeConfig for TestConfig {
+    const CLAIM_TTL: Option<u32> = Some(10);
+    const EXPIRY_REWARD: u128 = 5;
+}
+
+/// Like the test config, but claims never expire.
+pub struct NeverExpire;
+
+impl PoeConfig for NeverExpire {}
+
+/// Like the test config, but cleaning up is not rewarded.
+pub struct NoReward;
+
+impl PoeConfig for NoReward {
+    const CLAIM_TTL: Option<u32> = Some(10);
+}
+
+/// The checkers in these tests run at block twenty.
+fn context() -> CheckerContext {
+    CheckerContext {
+        block_height: 20,
+        ..Default::default()
+    }
+}
+
+type Expire = PoeExpire<TestConfig, 0>;
+
+fn expire() -> Expire {
+    PoeExpire(PhantomData)
+}
+
+#[test]
+fn expire_claims_works() {
+    let result = expire().check(&[claim(1, 10), claim(2, 3)], &[], &[], &context());
+    assert_eq!(result, Ok(0));
+}
+
+#[test]
+fn expire_claims_with_reward_works() {
+    let result = expire().check(
+        &[claim(1, 10), claim(2, 3)],
+        &[],
+        &[Coin::<0>(10).into()],
+        &context(),
+    );
+    assert_eq!(result, Ok(0));
+}
+
+#[test]
+fn expire_nothing_fails() {
+    let result = expire().check(&[], &[], &[], &context());
+    assert_eq!(result, Err(ConstraintCheckerError::WrongNumberInputs));
+}
+
+#[test]
+fn expire_fresh_claim_fails() {
+    let result = expire().check(&[claim(1, 10), claim(2, 11)], &[], &[], &context());
+    assert_eq!(result, Err(ConstraintCheckerError::ClaimNotExpired));
+}
+
+#[test]
+fn expire_future_claim_fails() {
+    let result = expire().check(&[claim(1, 25)], &[], &[], &context());
+    assert_eq!(result, Err(ConstraintCheckerError::ClaimNotExpired));
+}
+
+#[test]
+fn expire_when_claims_never_expire_fails() {
+    let result =
+        PoeExpire::<NeverExpire, 0>(PhantomData).check(&[claim(1, 0)], &[], &[], &context());
+    assert_eq!(result, Err(ConstraintCheckerError::ClaimsNeverExpire));
+}
+
+#[test]
+fn expire_with_wrong_reward_fails() {
+    let result = expire().check(
+        &[claim(1, 10), claim(2, 3)],
+        &[],
+        &[Coin::<0>(11).into()],
+        &context(),
+    );
+    assert_eq!(result, Err(ConstraintCheckerError::WrongRewardAmount));
+}
+
+#[test]
+fn expire_with_badly_typed_reward_fails() {
+    let result = expire().check(&[claim(1, 10)], &[], &[Coin::<1>(5).into()], &context());
+    assert_eq!(result, Err(ConstraintCheckerError::BadlyTypedOutput));
+}
+
+#[test]
+fn expire_with_two_rewards_fails() {
+    let reward: DynamicallyTypedData = Coin::<0>(5).into();
+    let result = expire().check(&[claim(1, 10)], &[], &[reward.clone(), reward], &context());
+    assert_eq!(result, Err(ConstraintCheckerError::WrongNumberOutputs));
+}
+
+#[test]
+fn expire_with_unconfigured_reward_fails() {
+    let result = PoeExpire::<NoReward, 0>(PhantomData).check(
+        &[claim(1, 10)],
+        &[],
+        &[Coin::<0>(5).into()],
+        &context(),
+    );
+    assert_eq!(result, Err(ConstraintCheckerError::WrongNumberOutputs));
+}
+
+fn documents(count: u8) -> Vec<H256> {
+    (0..count).map(H256::repeat_byte).collect()
+}
+
+fn batch(leaves: u32, effective_height: u32) -> DynamicallyTypedData {
+    BatchClaimData {
+        root: merkle_root(&documents(leaves as u8)).unwrap_or_default(),
+        leaves,
+        effective_height,
+    }
+    .into()
+}
+
+fn batch_claim() -> PoeBatchClaim<TestConfig> {
+    PoeBatchClaim(PhantomData)
+}
+
+#[test]
+fn batch_claim_works() {
+    let result = batch_claim().check(&[], &[], &[batch(5, 20)], &context());
+    assert_eq!(result, Ok(0));
+}
+
+#[test]
+fn batch_claim_with_inputs_fails() {
+    let result = batch_claim().check(&[claim(1, 5)], &[], &[batch(5, 20)], &context());
+    assert_eq!(result, Err(ConstraintCheckerError::WrongNumberInputs));
+}
+
+#[test]
+fn batch_claim_many_batches_fails() {
+    let result = batch_claim().check(&[], &[], &[batch(5, 20), batch(3, 20)], &context());
+    assert_eq!(result, Err(ConstraintCheckerError::WrongNumberOutputs));
+}
+
+#[test]
+fn batch_claim_empty_batch_fails() {
+    let result = batch_claim().check(&[], &[], &[batch(0, 20)], &context());
+    assert_eq!(result, Err(ConstraintCheckerError::EmptyBatch));
+}
+
+#[test]
+fn batch_claim_in_past_fails() {
+    let result = batch_claim().check(&[], &[], &[batch(5, 19)], &context());
+    assert_eq!(result, Err(ConstraintCheckerError::EffectiveHeightInPast));
+}
+
+#[test]
+fn batch_claim_badly_typed_fails() {
+    let result = batch_claim().check(&[], &[], &[claim(1, 20)], &context());
+    assert_eq!(result, Err(ConstraintCheckerError::BadlyTypedOutput));
+}
+
+#[test]
+fn merkle_root_of_nothing_is_none() {
+    assert_eq!(merkle_root(&[]), None);
+}
+
+#[test]
+fn merkle_root_of_one_document_is_the_document() {
+    assert_eq!(merkle_root(&documents(1)), Some(H256::repeat_byte(0)));
+}
+
+#[test]
+fn every_document_is_provably_included() {
+    for count in 1..=9 {
+        let documents = documents(count);
+        let root = merkle_root(&documents).unwrap();
+
+        for (index, document) in documents.iter().enumerate() {
+            let proof = merkle_proof(&documents, index as u32).unwrap();
+            assert!(verify_inclusion(root, count as u32, *document, &proof));
+        }
+    }
+}
+
+#[test]
+fn proof_out_of_bounds_is_none() {
+    assert_eq!(merkle_proof(&documents(5), 5), None);
+}
+
+#[test]
+fn wrong_document_is_not_included() {
+    let documents = documents(5);
+    let root = merkle_root(&documents).unwrap();
+    let proof = merkle_proof(&documents, 2).unwrap();
+
+    assert!(!verify_inclusion(root, 5, H256::repeat_byte(9), &proof));
+    assert!(!verify_inclusion(root, 5, documents[3], &proof));
+}
+
+#[test]
+fn proof_at_wrong_index_fails() {
+    let documents = documents(5);
+    let root = merkle_root(&documents).unwrap();
+    let mut proof = merkle_proof(&documents, 2).unwrap();
+    proof.index = 3;
+
+    assert!(!verify_inclusion(root, 5, documents[2], &proof));
+}
+
+#[test]
+fn inner_node_is_not_a_document() {
+    let documents = documents(4);
+    let root = merkle_root(&documents).unwrap();
+    let inner = merkle_parent(&documents[0], &documents[1]);
+    let mut proof = merkle_proof(&documents, 0).unwrap();
+    proof.siblings.remove(0);
+
+    assert!(!verify_inclusion(root, 4, inner, &proof));
+}
+
+#[test]
+fn claim_with_predecessor_fails() {
+    let result =
+        PoeClaim::<TestConfig>(PhantomData).check(&[], &[], &[revision(2, 20, 1)], &context());
+    assert_eq!(result, Err(ConstraintCheckerError::UnexpectedPredecessor));
+}
+
+fn revise() -> PoeRevise<TestConfig> {
+    PoeRevise(PhantomData)
+}
+
+#[test]
+fn revise_claim_works() {
+    let result = revise().check(&[claim(1, 5)], &[], &[revision(2, 20, 1)], &context());
+    assert_eq!(result, Ok(0));
+}
+
+#[test]
+fn revise_revision_works() {
+    let result = revise().check(
+        &[revision(2, 20, 1)],
+        &[],
+        &[revision(3, 21, 2)],
+        &context(),
+    );
+    assert_eq!(result, Ok(0));
+}
+
+#[test]
+fn revise_nothing_fails() {
+    let result = revise().check(&[], &[], &[], &context());
+    assert_eq!(result, Err(ConstraintCheckerError::WrongNumberInputs));
+}
+
+#[test]
+fn revise_dropping_claim_fails() {
+    let result = revise().check(&[claim(1, 5)], &[], &[], &context());
+    assert_eq!(result, Err(ConstraintCheckerError::WrongNumberOutputs));
+}
+
+#[test]
+fn revise_without_predecessor_fails() {
+    let result = revise().check(&[claim(1, 5)], &[], &[claim(2, 20)], &context());
+    assert_eq!(result, Err(ConstraintCheckerError::WrongPredecessor));
+}
+
+#[test]
+fn revise_with_wrong_predecessor_fails() {
+    let result = revise().check(&[claim(1, 5)], &[], &[revision(2, 20, 3)], &context());
+    assert_eq!(result, Err(ConstraintCheckerError::WrongPredecessor));
+}
+
+#[test]
+fn revise_to_same_document_fails() {
+    let result = revise().check(&[claim(1, 5)], &[], &[revision(1, 20, 1)], &context());
+    assert_eq!(result, Err(ConstraintCheckerError::RevisionUnchanged));
+}
+
+#[test]
+fn revise_in_past_fails() {
+    let result = revise().check(&[claim(1, 5)], &[], &[revision(2, 19, 1)], &context());
+    assert_eq!(result, Err(ConstraintCheckerError::EffectiveHeightInPast));
+}
+
+#[test]
+fn dispute_works() {
+    let result = PoeDispute.check(&[claim(1, 6), claim(1, 9)], &[claim(1, 5)], &[], &context());
+    assert_eq!(result, Ok(0));
+}
+
+#[test]
+fn dispute_within_revision_chain_works() {
+    let result = PoeDispute.check(&[revision(2, 9, 1)], &[revision(2, 5, 1)], &[], &context());
+    assert_eq!(result, Ok(0));
+}
+
+#[test]
+fn dispute_without_winner_fails() {
+    let result = PoeDispute.check(&[claim(1, 6)], &[], &[], &context());
+    assert_eq!(result, Err(ConstraintCheckerError::WrongNumberPeeks));
+}
+
+#[test]
+fn dispute_without_losers_fails() {
+    let result = PoeDispute.check(&[], &[claim(1, 5)], &[], &context());
+    assert_eq!(result, Err(ConstraintCheckerError::WrongNumberInputs));
+}
+
+#[test]
+fn dispute_with_outputs_fails() {
+    let result = PoeDispute.check(&[claim(1, 6)], &[claim(1, 5)], &[claim(1, 5)], &context());
+    assert_eq!(result, Err(ConstraintCheckerError::WrongNumberOutputs));
+}
+
+#[test]
+fn dispute_different_claims_fails() {
+    let result = PoeDispute.check(&[claim(2, 6)], &[claim(1, 5)], &[], &context());
+    assert_eq!(result, Err(ConstraintCheckerError::DisputedClaimMismatch));
+}
+
+#[test]
+fn dispute_simultaneous_claims_fails() {
+    let result = PoeDispute.check(&[claim(1, 5)], &[claim(1, 5)], &[], &context());
+    assert_eq!(result, Err(ConstraintCheckerError::LoserNotLater));
+}
+
+#[test]
+fn dispute_fresh_claim_against_revision_fails() {
+    let result = PoeDispute.check(&[revision(2, 9, 1)], &[claim(2, 5)], &[], &context());
+    assert_eq!(result, Err(ConstraintCheckerError::DifferentLineage));
+}
+
+#[test]
+fn dispute_across_revision_chains_fails() {
+    let result = PoeDispute.check(&[revision(2, 9, 3)], &[revision(2, 5, 1)], &[], &context());
+    assert_eq!(result, Err(ConstraintCheckerError::DifferentLineage));
+}
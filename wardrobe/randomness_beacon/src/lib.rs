@@ -0,0 +1,477 @@
+//! An on-chain randomness beacon fed by the block authors' VRF outputs.
+//!
+//! In each block, the author includes a single [`RevealRandomness`] inherent that peeks at the
+//! [`Beacon`] UTXO that was created in the previous block, and creates a new one. The author signs
+//! the slot and the parent block hash with the VRF of their Aura key. The VRF output is
+//! unpredictable to everyone else, yet the author can not choose it, so mixing it into the
+//! previous randomness gives other pieces, such as lotteries and games, a source of randomness
+//! that is much harder to bias than the parent block hash.
+//!
+//! Other pieces use the randomness by peeking at a beacon. Old beacons stay in the UTXO set, so
+//! that transactions peeking at them are not immediately invalidated, until anyone removes them
+//! with a [`CleanUpBeacons`] transaction once they are old enough.
+//!
+//! Blocks that are not authored with Aura, such as instantly sealed ones, carry the previous
+//! randomness over unchanged.
+//!
+//! ## Hack Warning
+//!
+//! Like the timestamp piece, this piece abuses the UpForGrabs verifier to protect the beacons,
+//! and relies on the constraint checkers to make sure that only the inherent creates them.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use core::marker::PhantomData;
+
+use parity_scale_codec::{Decode, Encode};
+use scale_info::TypeInfo;
+use serde::{Deserialize, Serialize};
+use sp_core::{
+    crypto::VrfPublic,
+    sr25519::{
+        vrf::{VrfSignData, VrfSignature, VrfTranscript},
+        Public,
+    },
+    H256,
+};
+use sp_inherents::{CheckInherentsResult, InherentData, InherentIdentifier};
+use sp_runtime::transaction_validity::TransactionPriority;
+use sp_std::{vec, vec::Vec};
+use tuxedo_core::{
+    dynamic_typing::{DynamicallyTypedData, UtxoData},
+    ensure,
+    inherents::{TuxedoInherent, TuxedoInherentAdapter},
+    support_macros::{CloneNoBound, DebugNoBound, DefaultNoBound},
+    types::{Output, OutputRef, Transaction, TransactionVersion},
+    verifier::UpForGrabs,
+    CheckerContext, ConstraintChecker, SimpleConstraintChecker, Verifier,
+};
+
+#[cfg(test)]
+mod tests;
+
+/// The identifier under which the block author's VRF signature is passed as inherent data.
+pub const INHERENT_IDENTIFIER: InherentIdentifier = *b"rnd_bcn_";
+
+/// The label of the VRF transcript that block authors sign.
+const VRF_LABEL: &[u8] = b"tuxedo-randomness-beacon";
+
+/// Configuration items for the randomness beacon piece when it is
+/// instantiated in a concrete runtime.
+pub trait RandomnessBeaconConfig {
+    /// The sr25519 public key of the current block's author, along with the slot it was authored
+    /// in. Blocks for which this is `None` carry the previous randomness over unchanged.
+    /// Probably this comes from the Aura pre-runtime digest.
+    fn author_and_slot() -> Option<(H256, u64)>;
+
+    /// The hash of the current block's parent.
+    /// Probably this will be the Tuxedo Executive
+    fn parent_hash() -> H256;
+
+    /// The minimum number of blocks that must have passed before an old beacon
+    /// may be cleaned up.
+    ///
+    /// Default is 15 thousand which is roughly equivalent to 1 day with 6 second block times.
+    const MIN_BLOCKS_BEFORE_CLEANUP: u32 = 15_000;
+}
+
+/// The randomness of the beacon as of some block.
+#[derive(
+    Serialize,
+    Deserialize,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Clone,
+    Copy,
+    Encode,
+    Decode,
+    Hash,
+    Debug,
+    Default,
+    TypeInfo,
+)]
+pub struct Beacon {
+    /// The randomness accumulated up to and including this block.
+    pub randomness: H256,
+    /// The block number in which this randomness was revealed.
+    pub block: u32,
+}
+
+impl UtxoData for Beacon {
+    const TYPE_ID: [u8; 4] = *b"rand";
+}
+
+impl Beacon {
+    pub fn new(randomness: H256, block: u32) -> Self {
+        Self { randomness, block }
+    }
+
+    /// Randomness derived from the beacon for a specific purpose, such as a particular lottery
+    /// round. Different subjects get independent randomness from the same beacon.
+    pub fn derive(&self, subject: &[u8]) -> H256 {
+        H256(sp_io::hashing::blake2_256(
+            &(self.randomness, subject).encode(),
+        ))
+    }
+
+    /// The beacon in the next block, which mixes in the given VRF signature if there is one.
+    pub fn next(&self, vrf_signature: Option<&VrfSignature>) -> Self {
+        let randomness = match vrf_signature {
+            // Only the output goes in. The proof is not unique, so the author could grind it.
+            Some(signature) => H256(sp_io::hashing::blake2_256(
+                &(self.randomness, &signature.output).encode(),
+            )),
+            None => self.randomness,
+        };
+        Self::new(randomness, self.block.saturating_add(1))
+    }
+}
+
+/// The data that a block author signs with their VRF key in the given slot on top of the given parent.
+pub fn vrf_sign_data(slot: u64, parent_hash: H256) -> VrfSignData {
+    VrfTranscript::new(
+        VRF_LABEL,
+        &[
+            (b"slot", &slot.to_le_bytes()),
+            (b"parent hash", parent_hash.as_ref()),
+        ],
+    )
+    .into()
+}
+
+/// Errors that can occur when revealing randomness or cleaning up old beacons.
+#[derive(
+    Serialize,
+    Deserialize,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Clone,
+    Encode,
+    Decode,
+    Hash,
+    Debug,
+    TypeInfo,
+)]
+pub enum ConstraintCheckerError {
+    /// UTXO data has an unexpected type.
+    BadlyTyped,
+    /// Inputs were specified while revealing randomness, but none are allowed.
+    InputsWhileRevealing,
+    /// No previous beacon was peeked at, but exactly one is required.
+    MissingPreviousBeacon,
+    /// Revealing randomness creates exactly one new beacon.
+    WrongNumberOutputs,
+    /// The new beacon is not protected by the `UpForGrabs` verifier, so it could not be peeked at freely.
+    BeaconNotUpForGrabs,
+    /// The previous beacon that is peeked at must be from the immediate ancestor block, but this one is not.
+    PreviousBeaconWrongHeight,
+    /// The block height reported in the new beacon does not match the block into which it was inserted.
+    NewBeaconWrongHeight,
+    /// The block has an author, but the author did not include a VRF signature.
+    MissingVrfSignature,
+    /// The block has no author who could have made the included VRF signature.
+    UnexpectedVrfSignature,
+    /// The VRF signature does not decode, or is not the author's signature of this slot and parent.
+    InvalidVrfSignature,
+    /// The new randomness does not follow from the previous randomness and the VRF output.
+    WrongRandomness,
+    /// When cleaning up old beacons, you may not create any new state at all.
+    CleanupCannotCreateState,
+    /// You may not clean up old beacons until they are at least the configured number of blocks old.
+    DontBeSoHasty,
+}
+
+/// A constraint checker that reveals the randomness of a new block.
+///
+/// This is expected to be performed through an inherent, and to happen exactly once per block.
+///
+/// There are no inputs. The only peek is the beacon from the previous block, and the only output
+/// is the beacon for this block, which must be up for grabs. The block author's VRF signature is
+/// carried in the checker itself.
+#[derive(
+    Serialize,
+    Deserialize,
+    Encode,
+    Decode,
+    DebugNoBound,
+    DefaultNoBound,
+    PartialEq,
+    Eq,
+    CloneNoBound,
+    TypeInfo,
+)]
+#[scale_info(skip_type_params(T))]
+pub struct RevealRandomness<T> {
+    /// The SCALE encoded VRF signature of the block's author, if the block has an author.
+    pub vrf_signature: Option<Vec<u8>>,
+    _config: PhantomData<T>,
+}
+
+impl<T> RevealRandomness<T> {
+    pub fn new(vrf_signature: Option<Vec<u8>>) -> Self {
+        Self {
+            vrf_signature,
+            _config: PhantomData,
+        }
+    }
+}
+
+impl<T: RandomnessBeaconConfig + 'static, V: Verifier + PartialEq + From<UpForGrabs>>
+    ConstraintChecker<V> for RevealRandomness<T>
+{
+    type Error = ConstraintCheckerError;
+    type InherentHooks = TuxedoInherentAdapter<Self>;
+
+    fn check(
+        &self,
+        input_data: &[Output<V>],
+        peek_data: &[Output<V>],
+        output_data: &[Output<V>],
+        context: &CheckerContext,
+    ) -> Result<TransactionPriority, Self::Error> {
+        ensure!(
+            input_data.is_empty(),
+            ConstraintCheckerError::InputsWhileRevealing
+        );
+
+        ensure!(
+            peek_data.len() == 1,
+            ConstraintCheckerError::MissingPreviousBeacon
+        );
+        let previous = peek_data[0]
+            .payload
+            .extract::<Beacon>()
+            .map_err(|_| ConstraintCheckerError::BadlyTyped)?;
+        ensure!(
+            previous.block.saturating_add(1) == context.block_height,
+            ConstraintCheckerError::PreviousBeaconWrongHeight
+        );
+
+        ensure!(
+            output_data.len() == 1,
+            ConstraintCheckerError::WrongNumberOutputs
+        );
+        let new = output_data[0]
+            .payload
+            .extract::<Beacon>()
+            .map_err(|_| ConstraintCheckerError::BadlyTyped)?;
+        ensure!(
+            output_data[0].verifier == UpForGrabs.into(),
+            ConstraintCheckerError::BeaconNotUpForGrabs
+        );
+        ensure!(
+            new.block == context.block_height,
+            ConstraintCheckerError::NewBeaconWrongHeight
+        );
+
+        // Make sure the VRF signature, if any, is the author's signature of this slot and parent.
+        let vrf_signature = match (T::author_and_slot(), &self.vrf_signature) {
+            (Some((author, slot)), Some(encoded)) => {
+                let signature = VrfSignature::decode(&mut &encoded[..])
+                    .map_err(|_| ConstraintCheckerError::InvalidVrfSignature)?;
+                ensure!(
+                    Public::from_h256(author)
+                        .vrf_verify(&vrf_sign_data(slot, T::parent_hash()), &signature),
+                    ConstraintCheckerError::InvalidVrfSignature
+                );
+                Some(signature)
+            }
+            (Some(_), None) => return Err(ConstraintCheckerError::MissingVrfSignature),
+            (None, Some(_)) => return Err(ConstraintCheckerError::UnexpectedVrfSignature),
+            (None, None) => None,
+        };
+
+        ensure!(
+            new == previous.next(vrf_signature.as_ref()),
+            ConstraintCheckerError::WrongRandomness
+        );
+
+        Ok(0)
+    }
+
+    fn is_inherent(&self) -> bool {
+        true
+    }
+}
+
+impl<V: Verifier + PartialEq + From<UpForGrabs>, T: RandomnessBeaconConfig + 'static>
+    TuxedoInherent<V, Self> for RevealRandomness<T>
+{
+    type Error = sp_inherents::MakeFatalError<()>;
+    const INHERENT_IDENTIFIER: InherentIdentifier = INHERENT_IDENTIFIER;
+
+    fn create_inherent(
+        authoring_inherent_data: &InherentData,
+        (previous_inherent, previous_id): (Transaction<V, Self>, H256),
+    ) -> Transaction<V, Self> {
+        let previous = previous_inherent.outputs[0]
+            .payload
+            .extract::<Beacon>()
+            .expect("The previous beacon inherent should have an output that decodes as a Beacon.");
+
+        // Only blocks with an author carry a VRF signature, even if the client provided one.
+        let vrf_signature = T::author_and_slot().and(
+            authoring_inherent_data
+                .get_data::<Vec<u8>>(&INHERENT_IDENTIFIER)
+                .expect("Inherent data should decode properly"),
+        );
+        let signature = vrf_signature
+            .as_ref()
+            .and_then(|encoded| VrfSignature::decode(&mut &encoded[..]).ok());
+
+        // There is always 1 output, so we know right where to find the previous beacon.
+        let previous_beacon = OutputRef {
+            tx_hash: previous_id,
+            index: 0,
+        };
+
+        Transaction {
+            version: TransactionVersion::LATEST,
+            inputs: Vec::new(),
+            peeks: vec![previous_beacon],
+            outputs: vec![Output {
+                payload: previous.next(signature.as_ref()).into(),
+                verifier: UpForGrabs.into(),
+            }],
+            checker: Self::new(vrf_signature),
+            memo: None,
+            valid_until: None,
+        }
+    }
+
+    fn check_inherent(
+        _importing_inherent_data: &InherentData,
+        _inherent: Transaction<V, Self>,
+        _result: &mut CheckInherentsResult,
+    ) {
+        // The VRF signature can be verified by anyone, so the constraint checker does all the checks.
+    }
+
+    #[cfg(feature = "std")]
+    fn genesis_transactions() -> Vec<Transaction<V, Self>> {
+        // The inherent must appear in every block, including genesis, so that
+        // block authors always find a previous beacon to peek at.
+        vec![Transaction {
+            version: TransactionVersion::LATEST,
+            inputs: Vec::new(),
+            peeks: Vec::new(),
+            outputs: vec![Output {
+                payload: Beacon::default().into(),
+                verifier: UpForGrabs.into(),
+            }],
+            checker: Self::default(),
+            memo: None,
+            valid_until: None,
+        }]
+    }
+}
+
+/// Allows users to voluntarily clean up old beacons once they are at least the configured
+/// number of blocks old.
+///
+/// You can clean up multiple beacons at once. There are no peeks and no outputs.
+#[derive(
+    Serialize,
+    Deserialize,
+    Encode,
+    Decode,
+    DebugNoBound,
+    DefaultNoBound,
+    PartialEq,
+    Eq,
+    CloneNoBound,
+    TypeInfo,
+)]
+#[scale_info(skip_type_params(T))]
+pub struct CleanUpBeacons<T>(PhantomData<T>);
+
+impl<T: RandomnessBeaconConfig> SimpleConstraintChecker for CleanUpBeacons<T> {
+    type Error = ConstraintCheckerError;
+
+    fn check(
+        &self,
+        input_data: &[DynamicallyTypedData],
+        _peeks: &[DynamicallyTypedData],
+        output_data: &[DynamicallyTypedData],
+        context: &CheckerContext,
+    ) -> Result<TransactionPriority, Self::Error> {
+        ensure!(
+            output_data.is_empty(),
+            ConstraintCheckerError::CleanupCannotCreateState
+        );
+
+        for input in input_data {
+            let beacon = input
+                .extract::<Beacon>()
+                .map_err(|_| ConstraintCheckerError::BadlyTyped)?;
+            ensure!(
+                beacon.block.saturating_add(T::MIN_BLOCKS_BEFORE_CLEANUP) < context.block_height,
+                ConstraintCheckerError::DontBeSoHasty
+            );
+        }
+
+        Ok(0)
+    }
+}
+
+/// An inherent data provider that signs the VRF input of the block being authored with the
+/// author's key, so that the author can reveal new randomness.
+///
+/// If the keystore does not hold the author's key, no signature is provided. Such a block
+/// could not be sealed by this node anyway.
+#[cfg(feature = "std")]
+pub struct InherentDataProvider(Option<VrfSignature>);
+
+#[cfg(feature = "std")]
+impl InherentDataProvider {
+    /// Sign the VRF input of the given slot on top of the given parent with the given author's
+    /// key of the given key type.
+    pub fn new(
+        keystore: &dyn sp_keystore::Keystore,
+        key_type: sp_core::crypto::KeyTypeId,
+        author: H256,
+        slot: u64,
+        parent_hash: H256,
+    ) -> Self {
+        let signature = keystore
+            .sr25519_vrf_sign(
+                key_type,
+                &Public::from_h256(author),
+                &vrf_sign_data(slot, parent_hash),
+            )
+            .ok()
+            .flatten();
+        Self(signature)
+    }
+
+    /// A provider that does not provide a signature, for blocks without an author.
+    pub fn empty() -> Self {
+        Self(None)
+    }
+}
+
+#[cfg(feature = "std")]
+#[async_trait::async_trait]
+impl sp_inherents::InherentDataProvider for InherentDataProvider {
+    async fn provide_inherent_data(
+        &self,
+        inherent_data: &mut InherentData,
+    ) -> Result<(), sp_inherents::Error> {
+        match &self.0 {
+            Some(signature) => inherent_data.put_data(INHERENT_IDENTIFIER, &signature.encode()),
+            None => Ok(()),
+        }
+    }
+
+    async fn try_handle_error(
+        &self,
+        _identifier: &InherentIdentifier,
+        _error: &[u8],
+    ) -> Option<Result<(), sp_inherents::Error>> {
+        None
+    }
+}
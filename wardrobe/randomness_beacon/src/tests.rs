@@ -0,0 +1,354 @@
+//! Unit tests for the Randomness Beacon piece
+
+use super::*;
+use sp_core::{crypto::VrfSecret, sr25519::Pair, Pair as _};
+use sp_io::TestExternalities;
+use std::cell::RefCell;
+use tuxedo_core::{dynamic_typing::testing::Bogus, tuxedo_verifier, verifier::SigCheck};
+
+/// A verifier that can represent the beacons and ordinary users.
+#[derive(Serialize, Deserialize, Encode, Decode, Debug, PartialEq, Eq, Clone, TypeInfo)]
+#[tuxedo_verifier]
+pub enum TestVerifier {
+    SigCheck(SigCheck),
+    UpForGrabs(UpForGrabs),
+}
+
+thread_local! {
+    static AUTHOR_AND_SLOT: RefCell<Option<(H256, u64)>> = RefCell::new(Some((alice(), 7)));
+}
+
+/// The mock config reads the author and slot from a thread local. Unless a test says
+/// otherwise, Alice authors the block in slot seven. Beacons may be cleaned up after ten blocks.
+pub struct TestConfig;
+
+impl RandomnessBeaconConfig for TestConfig {
+    fn author_and_slot() -> Option<(H256, u64)> {
+        AUTHOR_AND_SLOT.with(|a| *a.borrow())
+    }
+
+    fn parent_hash() -> H256 {
+        H256::repeat_byte(5)
+    }
+
+    const MIN_BLOCKS_BEFORE_CLEANUP: u32 = 10;
+}
+
+fn alice_pair() -> Pair {
+    Pair::from_seed(&[1; 32])
+}
+
+fn alice() -> H256 {
+    alice_pair().public().into()
+}
+
+fn set_author_and_slot(author_and_slot: Option<(H256, u64)>) {
+    AUTHOR_AND_SLOT.with(|a| *a.borrow_mut() = author_and_slot);
+}
+
+fn at_height(block_height: u32) -> CheckerContext {
+    CheckerContext {
+        block_height,
+        ..Default::default()
+    }
+}
+
+/// Alice's signature of the VRF input in the given slot on top of the mock parent.
+fn alice_signature(slot: u64) -> VrfSignature {
+    alice_pair().vrf_sign(&vrf_sign_data(slot, TestConfig::parent_hash()))
+}
+
+fn beacon_output(beacon: Beacon) -> Output<TestVerifier> {
+    (beacon, UpForGrabs.into()).into()
+}
+
+fn reveal(
+    signature: Option<&VrfSignature>,
+    previous: Beacon,
+    new: Beacon,
+) -> Result<TransactionPriority, ConstraintCheckerError> {
+    TestExternalities::default().execute_with(|| {
+        RevealRandomness::<TestConfig>::new(signature.map(Encode::encode)).check(
+            &[],
+            &[beacon_output(previous)],
+            &[beacon_output(new)],
+            &at_height(new.block),
+        )
+    })
+}
+
+#[test]
+fn reveal_randomness_works() {
+    set_author_and_slot(Some((alice(), 7)));
+    let signature = alice_signature(7);
+    let previous = Beacon::new(H256::repeat_byte(9), 1);
+    let new = TestExternalities::default().execute_with(|| previous.next(Some(&signature)));
+
+    assert_eq!(new.block, 2);
+    assert_ne!(new.randomness, previous.randomness);
+    assert_eq!(reveal(Some(&signature), previous, new), Ok(0));
+}
+
+#[test]
+fn vrf_output_is_unique_per_slot() {
+    let previous = Beacon::new(H256::repeat_byte(9), 1);
+
+    TestExternalities::default().execute_with(|| {
+        assert_eq!(
+            previous.next(Some(&alice_signature(7))),
+            previous.next(Some(&alice_signature(7)))
+        );
+        assert_ne!(
+            previous.next(Some(&alice_signature(7))),
+            previous.next(Some(&alice_signature(8)))
+        );
+    });
+}
+
+#[test]
+fn block_without_author_carries_randomness_over() {
+    set_author_and_slot(None);
+    let previous = Beacon::new(H256::repeat_byte(9), 1);
+    let new = Beacon::new(H256::repeat_byte(9), 2);
+
+    assert_eq!(reveal(None, previous, new), Ok(0));
+}
+
+#[test]
+fn missing_signature_fails() {
+    set_author_and_slot(Some((alice(), 7)));
+    let previous = Beacon::new(H256::repeat_byte(9), 1);
+    let new = Beacon::new(H256::repeat_byte(9), 2);
+
+    assert_eq!(
+        reveal(None, previous, new),
+        Err(ConstraintCheckerError::MissingVrfSignature)
+    );
+}
+
+#[test]
+fn unexpected_signature_fails() {
+    set_author_and_slot(None);
+    let signature = alice_signature(7);
+    let previous = Beacon::new(H256::repeat_byte(9), 1);
+    let new = TestExternalities::default().execute_with(|| previous.next(Some(&signature)));
+
+    assert_eq!(
+        reveal(Some(&signature), previous, new),
+        Err(ConstraintCheckerError::UnexpectedVrfSignature)
+    );
+}
+
+#[test]
+fn signature_for_other_slot_fails() {
+    set_author_and_slot(Some((alice(), 7)));
+    let signature = alice_signature(8);
+    let previous = Beacon::new(H256::repeat_byte(9), 1);
+    let new = TestExternalities::default().execute_with(|| previous.next(Some(&signature)));
+
+    assert_eq!(
+        reveal(Some(&signature), previous, new),
+        Err(ConstraintCheckerError::InvalidVrfSignature)
+    );
+}
+
+#[test]
+fn signature_by_other_key_fails() {
+    let bob = Pair::from_seed(&[2; 32]);
+    set_author_and_slot(Some((alice(), 7)));
+    let signature = bob.vrf_sign(&vrf_sign_data(7, TestConfig::parent_hash()));
+    let previous = Beacon::new(H256::repeat_byte(9), 1);
+    let new = TestExternalities::default().execute_with(|| previous.next(Some(&signature)));
+
+    assert_eq!(
+        reveal(Some(&signature), previous, new),
+        Err(ConstraintCheckerError::InvalidVrfSignature)
+    );
+}
+
+#[test]
+fn chosen_randomness_fails() {
+    set_author_and_slot(Some((alice(), 7)));
+    let signature = alice_signature(7);
+    let previous = Beacon::new(H256::repeat_byte(9), 1);
+    let new = Beacon::new(H256::repeat_byte(4), 2);
+
+    assert_eq!(
+        reveal(Some(&signature), previous, new),
+        Err(ConstraintCheckerError::WrongRandomness)
+    );
+}
+
+#[test]
+fn previous_beacon_wrong_height_fails() {
+    set_author_and_slot(None);
+    let previous = Beacon::new(H256::repeat_byte(9), 0);
+    let new = Beacon::new(H256::repeat_byte(9), 2);
+
+    assert_eq!(
+        reveal(None, previous, new),
+        Err(ConstraintCheckerError::PreviousBeaconWrongHeight)
+    );
+}
+
+#[test]
+fn new_beacon_wrong_height_fails() {
+    set_author_and_slot(None);
+    let previous = Beacon::new(H256::repeat_byte(9), 1);
+    let new = Beacon::new(H256::repeat_byte(9), 2);
+
+    let result = TestExternalities::default().execute_with(|| {
+        RevealRandomness::<TestConfig>::default().check(
+            &[],
+            &[beacon_output(previous)],
+            &[beacon_output(new)],
+            &at_height(3),
+        )
+    });
+    assert_eq!(
+        result,
+        Err(ConstraintCheckerError::PreviousBeaconWrongHeight)
+    );
+
+    let result = TestExternalities::default().execute_with(|| {
+        RevealRandomness::<TestConfig>::default().check(
+            &[],
+            &[beacon_output(Beacon::new(H256::repeat_byte(9), 2))],
+            &[beacon_output(new)],
+            &at_height(3),
+        )
+    });
+    assert_eq!(result, Err(ConstraintCheckerError::NewBeaconWrongHeight));
+}
+
+#[test]
+fn beacon_not_up_for_grabs_fails() {
+    set_author_and_slot(None);
+    let previous = Beacon::new(H256::repeat_byte(9), 1);
+    let new = Beacon::new(H256::repeat_byte(9), 2);
+
+    let result = RevealRandomness::<TestConfig>::default().check(
+        &[],
+        &[beacon_output(previous)],
+        &[(new, SigCheck::new(alice()).into()).into()],
+        &at_height(2),
+    );
+    assert_eq!(result, Err(ConstraintCheckerError::BeaconNotUpForGrabs));
+}
+
+#[test]
+fn reveal_without_previous_beacon_fails() {
+    set_author_and_slot(None);
+    let new = Beacon::new(H256::repeat_byte(9), 2);
+
+    let result = RevealRandomness::<TestConfig>::default().check(
+        &[],
+        &[],
+        &[beacon_output(new)],
+        &at_height(2),
+    );
+    assert_eq!(result, Err(ConstraintCheckerError::MissingPreviousBeacon));
+}
+
+#[test]
+fn create_inherent_reveals_randomness() {
+    set_author_and_slot(Some((alice(), 7)));
+    let signature = alice_signature(7);
+    let previous = Beacon::new(H256::repeat_byte(9), 1);
+    let previous_tx = Transaction::<TestVerifier, RevealRandomness<TestConfig>> {
+        version: TransactionVersion::LATEST,
+        inputs: Vec::new(),
+        peeks: Vec::new(),
+        outputs: vec![beacon_output(previous)],
+        checker: Default::default(),
+        memo: None,
+        valid_until: None,
+    };
+    let mut data = InherentData::new();
+    data.put_data(INHERENT_IDENTIFIER, &signature.encode())
+        .unwrap();
+
+    TestExternalities::default().execute_with(|| {
+        let inherent = <RevealRandomness<TestConfig> as TuxedoInherent<
+            TestVerifier,
+            RevealRandomness<TestConfig>,
+        >>::create_inherent(&data, (previous_tx, H256::repeat_byte(3)));
+
+        assert_eq!(inherent.peeks[0].tx_hash, H256::repeat_byte(3));
+        assert_eq!(
+            inherent.outputs[0].payload.extract::<Beacon>(),
+            Ok(previous.next(Some(&signature)))
+        );
+        assert_eq!(
+            inherent.checker.check(
+                &[],
+                &[beacon_output(previous)],
+                &inherent.outputs,
+                &at_height(2)
+            ),
+            Ok(0)
+        );
+    });
+}
+
+#[test]
+fn derived_randomness_depends_on_subject() {
+    let beacon = Beacon::new(H256::repeat_byte(9), 1);
+
+    TestExternalities::default().execute_with(|| {
+        assert_eq!(beacon.derive(b"lottery"), beacon.derive(b"lottery"));
+        assert_ne!(beacon.derive(b"lottery"), beacon.derive(b"kitties"));
+    });
+}
+
+#[test]
+fn clean_up_old_beacons_works() {
+    let input_data = vec![
+        Beacon::new(H256::zero(), 1).into(),
+        Beacon::new(H256::zero(), 5).into(),
+    ];
+
+    assert_eq!(
+        CleanUpBeacons::<TestConfig>::default().check(&input_data, &[], &[], &at_height(16)),
+        Ok(0)
+    );
+}
+
+#[test]
+fn clean_up_young_beacon_fails() {
+    let input_data = vec![
+        Beacon::new(H256::zero(), 1).into(),
+        Beacon::new(H256::zero(), 6).into(),
+    ];
+
+    assert_eq!(
+        CleanUpBeacons::<TestConfig>::default().check(&input_data, &[], &[], &at_height(16)),
+        Err(ConstraintCheckerError::DontBeSoHasty)
+    );
+}
+
+#[test]
+fn clean_up_creating_state_fails() {
+    let input_data = vec![Beacon::new(H256::zero(), 1).into()];
+    let output_data = vec![Beacon::new(H256::zero(), 1).into()];
+
+    assert_eq!(
+        CleanUpBeacons::<TestConfig>::default().check(
+            &input_data,
+            &[],
+            &output_data,
+            &at_height(16)
+        ),
+        Err(ConstraintCheckerError::CleanupCannotCreateState)
+    );
+}
+
+#[test]
+fn clean_up_something_else_fails() {
+    let input_data = vec![Bogus.into()];
+
+    assert_eq!(
+        CleanUpBeacons::<TestConfig>::default().check(&input_data, &[], &[], &at_height(16)),
+        Err(ConstraintCheckerError::BadlyTyped)
+    );
+}
@@ -0,0 +1,603 @@
+//! Rock-paper-scissors games that are played for `Coin<ID>`s from the money piece.
+//!
+//! This piece is a compact example of three patterns that come up again and again in Tuxedo
+//! pieces: hiding a choice behind a commitment until everyone has chosen, giving up on players who
+//! stop responding once a deadline passes, and locking up and paying out coins from another piece.
+//!
+//! A game goes through these transactions:
+//! 1. [`OpenGame`]: The challenger locks their wager in a new [`Game`], along with a commitment
+//!    to their hand, and a deadline by which an opponent must join.
+//! 2. [`JoinGame`]: An opponent locks the same wager in the game, along with their own
+//!    commitment. From then on, the players have [`RockPaperScissorsConfig::REVEAL_PERIOD`]
+//!    blocks to reveal their hands.
+//! 3. [`RevealHand`]: Each player reveals their hand, and the salt that hid it.
+//! 4. [`SettleGame`]: Once both hands are revealed, the winner takes the whole pot, or each player
+//!    takes back their wager on a draw. If the deadline passes first, a player who revealed
+//!    takes the whole pot from one who did not, and players who both failed to reveal, or a
+//!    challenger whom nobody joined, are refunded.
+//!
+//! A commitment is the hash of the hand, a random salt, and the verifier that the player wants to
+//! be paid to. See [`commitment`]. Including the verifier means that an opponent gains nothing
+//! by copying the challenger's commitment, because they could only ever reveal it as the
+//! challenger and pay the challenger.
+//!
+//! Games should be protected by the `UpForGrabs` verifier, and the constraint checkers make sure
+//! that every step is played honestly. Open games are open challenges that anyone may join.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use core::marker::PhantomData;
+
+use money::Coin;
+use parity_scale_codec::{Decode, Encode};
+use scale_info::TypeInfo;
+use serde::{Deserialize, Serialize};
+use sp_core::H256;
+use sp_runtime::{
+    traits::{BlakeTwo256, Hash as HashT},
+    transaction_validity::TransactionPriority,
+};
+use sp_std::prelude::*;
+use tuxedo_core::{
+    dynamic_typing::UtxoData,
+    ensure,
+    support_macros::{CloneNoBound, DebugNoBound, DefaultNoBound},
+    types::Output,
+    verifier::UpForGrabs,
+    CheckerContext, ConstraintChecker, Verifier,
+};
+
+#[cfg(test)]
+mod tests;
+
+/// Configuration items for the rock-paper-scissors piece when it is
+/// instantiated in a concrete runtime.
+pub trait RockPaperScissorsConfig {
+    /// A means of getting the current block height.
+    /// Probably this will be the Tuxedo Executive
+    fn block_height() -> u32;
+
+    /// The number of blocks that players have to reveal their hands once an opponent joins.
+    ///
+    /// Default is roughly ten minutes with 6 second block times.
+    const REVEAL_PERIOD: u32 = 100;
+}
+
+/// The hands that a player may play.
+#[derive(
+    Serialize,
+    Deserialize,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Clone,
+    Copy,
+    Encode,
+    Decode,
+    Hash,
+    Debug,
+    TypeInfo,
+)]
+pub enum Hand {
+    Rock,
+    Paper,
+    Scissors,
+}
+
+impl Hand {
+    /// Whether this hand beats the other one.
+    pub fn beats(&self, other: &Hand) -> bool {
+        matches!(
+            (self, other),
+            (Hand::Rock, Hand::Scissors)
+                | (Hand::Paper, Hand::Rock)
+                | (Hand::Scissors, Hand::Paper)
+        )
+    }
+}
+
+/// The commitment to a hand, hidden by a salt, that pays out to the given verifier.
+///
+/// The salt must be random and kept secret until the hand is revealed. Otherwise the opponent can
+/// simply try the three possible hands.
+pub fn commitment<V: Encode>(hand: Hand, salt: H256, payout: &V) -> H256 {
+    BlakeTwo256::hash_of(&(hand, salt, payout))
+}
+
+/// A player in a game.
+#[derive(
+    Serialize,
+    Deserialize,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Clone,
+    Encode,
+    Decode,
+    Hash,
+    Debug,
+    TypeInfo,
+)]
+pub struct Player<V> {
+    /// The verifier that the player's winnings or refund must be locked to.
+    pub payout: V,
+    /// The commitment to the player's hand.
+    pub commitment: H256,
+    /// The player's hand, once they revealed it.
+    pub revealed: Option<Hand>,
+}
+
+impl<V> Player<V> {
+    /// A player who has committed to a hand, but not revealed it.
+    pub fn new(payout: V, commitment: H256) -> Self {
+        Self {
+            payout,
+            commitment,
+            revealed: None,
+        }
+    }
+}
+
+/// A game of rock-paper-scissors, and the wagers that are locked in it.
+#[derive(
+    Serialize,
+    Deserialize,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Clone,
+    Encode,
+    Decode,
+    Hash,
+    Debug,
+    TypeInfo,
+)]
+pub struct Game<V, const ID: u8> {
+    /// The value that each player wagers. Once the opponent joins, the pot is twice this.
+    pub wager: u128,
+    /// The player who opened the game.
+    pub challenger: Player<V>,
+    /// The player who joined the game, if anybody has yet.
+    pub opponent: Option<Player<V>>,
+    /// Until the opponent joins, the block height from which nobody can join anymore.
+    /// Afterwards, the block height from which players can no longer reveal their hands.
+    pub deadline: u32,
+}
+
+impl<V: Encode + Decode, const ID: u8> UtxoData for Game<V, ID> {
+    const TYPE_ID: [u8; 4] = [b'r', b'p', b's', ID];
+}
+
+/// Errors that can occur when checking rock-paper-scissors transactions.
+#[derive(
+    Serialize,
+    Deserialize,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Clone,
+    Encode,
+    Decode,
+    Hash,
+    Debug,
+    TypeInfo,
+)]
+pub enum ConstraintCheckerError {
+    /// An input data has the wrong type.
+    BadlyTypedInput,
+    /// An output data has the wrong type.
+    BadlyTypedOutput,
+    /// Wrong number of inputs were provided to the constraint checker.
+    WrongNumberInputs,
+    /// Wrong number of outputs were provided to the constraint checker.
+    WrongNumberOutputs,
+    /// A game was opened with no wager, or a coin with zero value was created.
+    ZeroValue,
+    /// The value consumed or created by this transaction overflows the value type.
+    ValueOverflow,
+    /// More value was created than was consumed.
+    OutputsExceedInputs,
+    /// A game was locked to a verifier other than `UpForGrabs`, so the next step could not consume it.
+    GameNotUpForGrabs,
+    /// A new game already has an opponent or a revealed hand.
+    NotANewGame,
+    /// A new game has a deadline that has already passed.
+    DeadlineInPast,
+    /// The game already has an opponent.
+    AlreadyJoined,
+    /// The game has no opponent yet.
+    NotJoined,
+    /// The deadline of the game has passed, so it is too late for this step.
+    DeadlinePassed,
+    /// The game can not be settled yet, because hands are missing and the deadline has not passed.
+    NotFinished,
+    /// The re-created game differs from the consumed one by more than this step allows.
+    GameMismatch,
+    /// The revealed hand and salt do not match the commitment of any player who has not revealed.
+    WrongReveal,
+    /// The payouts were locked to the wrong verifiers.
+    PaidToWrongVerifier,
+}
+
+/// Sum the value of some outputs, making sure they are all `Coin<ID>`s with non-zero value.
+fn sum_coins<'a, V: 'a, const ID: u8>(
+    coins: impl IntoIterator<Item = &'a Output<V>>,
+    error: ConstraintCheckerError,
+) -> Result<u128, ConstraintCheckerError> {
+    coins.into_iter().try_fold(0u128, |total, coin| {
+        let value = coin
+            .payload
+            .extract::<Coin<ID>>()
+            .map_err(|_| error.clone())?
+            .0;
+        ensure!(value > 0, ConstraintCheckerError::ZeroValue);
+        total
+            .checked_add(value)
+            .ok_or(ConstraintCheckerError::ValueOverflow)
+    })
+}
+
+/// Extract the game from an output that must be locked up for grabs.
+fn game_output<V: Verifier + PartialEq + From<UpForGrabs>, const ID: u8>(
+    output: &Output<V>,
+) -> Result<Game<V, ID>, ConstraintCheckerError> {
+    ensure!(
+        output.verifier == UpForGrabs.into(),
+        ConstraintCheckerError::GameNotUpForGrabs
+    );
+    output
+        .payload
+        .extract::<Game<V, ID>>()
+        .map_err(|_| ConstraintCheckerError::BadlyTypedOutput)
+}
+
+/// Extract the game from the single input of a transaction.
+fn game_input<V: Verifier, const ID: u8>(
+    inputs: &[Output<V>],
+) -> Result<Game<V, ID>, ConstraintCheckerError> {
+    ensure!(inputs.len() == 1, ConstraintCheckerError::WrongNumberInputs);
+    inputs[0]
+        .payload
+        .extract::<Game<V, ID>>()
+        .map_err(|_| ConstraintCheckerError::BadlyTypedInput)
+}
+
+/// The surplus of a transaction that left the given value over for its fee and tip.
+fn priority(burned: u128) -> TransactionPriority {
+    burned.try_into().unwrap_or(TransactionPriority::MAX)
+}
+
+/// A constraint checker that opens a new game with the challenger's wager and commitment.
+///
+/// All inputs are `Coin<ID>`s. The first output is the game, and any other outputs are `Coin<ID>`
+/// change. The challenger pays the transaction's fee and tip with whatever coins are neither
+/// wagered nor returned as change.
+#[derive(
+    Serialize,
+    Deserialize,
+    Encode,
+    Decode,
+    DebugNoBound,
+    DefaultNoBound,
+    PartialEq,
+    Eq,
+    CloneNoBound,
+    TypeInfo,
+)]
+#[scale_info(skip_type_params(T))]
+pub struct OpenGame<T, const ID: u8>(PhantomData<T>);
+
+impl<T, V, const ID: u8> ConstraintChecker<V> for OpenGame<T, ID>
+where
+    T: RockPaperScissorsConfig,
+    V: Verifier + PartialEq + From<UpForGrabs>,
+{
+    type Error = ConstraintCheckerError;
+    type InherentHooks = ();
+
+    fn check(
+        &self,
+        inputs: &[Output<V>],
+        _peeks: &[Output<V>],
+        outputs: &[Output<V>],
+        _context: &CheckerContext,
+    ) -> Result<TransactionPriority, Self::Error> {
+        let total_input = sum_coins::<V, ID>(inputs, ConstraintCheckerError::BadlyTypedInput)?;
+
+        let (game, change) = outputs
+            .split_first()
+            .ok_or(ConstraintCheckerError::WrongNumberOutputs)?;
+        let game = game_output::<V, ID>(game)?;
+        ensure!(game.wager > 0, ConstraintCheckerError::ZeroValue);
+        ensure!(
+            game.opponent.is_none() && game.challenger.revealed.is_none(),
+            ConstraintCheckerError::NotANewGame
+        );
+        ensure!(
+            game.deadline > T::block_height(),
+            ConstraintCheckerError::DeadlineInPast
+        );
+
+        let total_output = sum_coins::<V, ID>(change, ConstraintCheckerError::BadlyTypedOutput)?
+            .checked_add(game.wager)
+            .ok_or(ConstraintCheckerError::ValueOverflow)?;
+        ensure!(
+            total_output <= total_input,
+            ConstraintCheckerError::OutputsExceedInputs
+        );
+
+        Ok(priority(total_input - total_output))
+    }
+
+    fn is_inherent(&self) -> bool {
+        false
+    }
+}
+
+/// A constraint checker that joins an open game with the opponent's wager and commitment.
+///
+/// The first input is the game, and any other inputs are `Coin<ID>`s. The first output is the same
+/// game with the opponent added and the reveal deadline set, and any other outputs are `Coin<ID>`
+/// change. The opponent pays the transaction's fee and tip with whatever coins are neither
+/// wagered nor returned as change.
+#[derive(
+    Serialize,
+    Deserialize,
+    Encode,
+    Decode,
+    DebugNoBound,
+    DefaultNoBound,
+    PartialEq,
+    Eq,
+    CloneNoBound,
+    TypeInfo,
+)]
+#[scale_info(skip_type_params(T))]
+pub struct JoinGame<T, const ID: u8>(PhantomData<T>);
+
+impl<T, V, const ID: u8> ConstraintChecker<V> for JoinGame<T, ID>
+where
+    T: RockPaperScissorsConfig,
+    V: Verifier + PartialEq + From<UpForGrabs>,
+{
+    type Error = ConstraintCheckerError;
+    type InherentHooks = ();
+
+    fn check(
+        &self,
+        inputs: &[Output<V>],
+        _peeks: &[Output<V>],
+        outputs: &[Output<V>],
+        _context: &CheckerContext,
+    ) -> Result<TransactionPriority, Self::Error> {
+        let (old_game, coins) = inputs
+            .split_first()
+            .ok_or(ConstraintCheckerError::WrongNumberInputs)?;
+        let old_game = old_game
+            .payload
+            .extract::<Game<V, ID>>()
+            .map_err(|_| ConstraintCheckerError::BadlyTypedInput)?;
+        ensure!(
+            old_game.opponent.is_none(),
+            ConstraintCheckerError::AlreadyJoined
+        );
+        ensure!(
+            T::block_height() < old_game.deadline,
+            ConstraintCheckerError::DeadlinePassed
+        );
+
+        let (new_game, change) = outputs
+            .split_first()
+            .ok_or(ConstraintCheckerError::WrongNumberOutputs)?;
+        let new_game = game_output::<V, ID>(new_game)?;
+        let opponent = new_game
+            .opponent
+            .as_ref()
+            .ok_or(ConstraintCheckerError::NotJoined)?;
+        ensure!(
+            opponent.revealed.is_none(),
+            ConstraintCheckerError::NotANewGame
+        );
+        ensure!(
+            new_game.wager == old_game.wager
+                && new_game.challenger == old_game.challenger
+                && new_game.deadline == T::block_height().saturating_add(T::REVEAL_PERIOD),
+            ConstraintCheckerError::GameMismatch
+        );
+        // The pot must be payable to a single winner.
+        old_game
+            .wager
+            .checked_mul(2)
+            .ok_or(ConstraintCheckerError::ValueOverflow)?;
+
+        let total_input = sum_coins::<V, ID>(coins, ConstraintCheckerError::BadlyTypedInput)?;
+        let total_output = sum_coins::<V, ID>(change, ConstraintCheckerError::BadlyTypedOutput)?
+            .checked_add(old_game.wager)
+            .ok_or(ConstraintCheckerError::ValueOverflow)?;
+        ensure!(
+            total_output <= total_input,
+            ConstraintCheckerError::OutputsExceedInputs
+        );
+
+        Ok(priority(total_input - total_output))
+    }
+
+    fn is_inherent(&self) -> bool {
+        false
+    }
+}
+
+/// A constraint checker that reveals one player's hand before the deadline.
+///
+/// The only input is the game, and the only output is the same game with the hand revealed. The
+/// player is the one whose commitment the hand and salt match, so anyone may submit the reveal.
+#[derive(
+    Serialize, Deserialize, Encode, Decode, DebugNoBound, PartialEq, Eq, CloneNoBound, TypeInfo,
+)]
+#[scale_info(skip_type_params(T))]
+pub struct RevealHand<T, const ID: u8> {
+    /// The hand that the player committed to.
+    pub hand: Hand,
+    /// The salt that hid the hand in the commitment.
+    pub salt: H256,
+    _phantom: PhantomData<T>,
+}
+
+impl<T, const ID: u8> RevealHand<T, ID> {
+    pub fn new(hand: Hand, salt: H256) -> Self {
+        Self {
+            hand,
+            salt,
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<T, V, const ID: u8> ConstraintChecker<V> for RevealHand<T, ID>
+where
+    T: RockPaperScissorsConfig,
+    V: Verifier + PartialEq + From<UpForGrabs>,
+{
+    type Error = ConstraintCheckerError;
+    type InherentHooks = ();
+
+    fn check(
+        &self,
+        inputs: &[Output<V>],
+        _peeks: &[Output<V>],
+        outputs: &[Output<V>],
+        _context: &CheckerContext,
+    ) -> Result<TransactionPriority, Self::Error> {
+        let old_game = game_input::<V, ID>(inputs)?;
+        ensure!(
+            old_game.opponent.is_some(),
+            ConstraintCheckerError::NotJoined
+        );
+        ensure!(
+            T::block_height() < old_game.deadline,
+            ConstraintCheckerError::DeadlinePassed
+        );
+
+        ensure!(
+            outputs.len() == 1,
+            ConstraintCheckerError::WrongNumberOutputs
+        );
+        let new_game = game_output::<V, ID>(&outputs[0])?;
+
+        // Reveal the hand of whichever player it matches, and expect nothing else to change.
+        let mut expected = old_game;
+        let player = core::iter::once(&mut expected.challenger)
+            .chain(expected.opponent.as_mut())
+            .find(|player| {
+                player.revealed.is_none()
+                    && player.commitment == commitment(self.hand, self.salt, &player.payout)
+            })
+            .ok_or(ConstraintCheckerError::WrongReveal)?;
+        player.revealed = Some(self.hand);
+        ensure!(new_game == expected, ConstraintCheckerError::GameMismatch);
+
+        Ok(0)
+    }
+
+    fn is_inherent(&self) -> bool {
+        false
+    }
+}
+
+/// A constraint checker that pays out a finished game.
+///
+/// The only input is the game. Each output is a `Coin<ID>` for one player who is owed something,
+/// in the order that the players joined the game. A payout may be less than what the player is
+/// owed, and the difference pays the transaction's fee and tip, so whoever settles the game can
+/// be paid for doing so out of the winnings.
+///
+/// A game is finished once both hands are revealed, or once its deadline has passed.
+#[derive(
+    Serialize,
+    Deserialize,
+    Encode,
+    Decode,
+    DebugNoBound,
+    DefaultNoBound,
+    PartialEq,
+    Eq,
+    CloneNoBound,
+    TypeInfo,
+)]
+#[scale_info(skip_type_params(T))]
+pub struct SettleGame<T, const ID: u8>(PhantomData<T>);
+
+impl<T, V, const ID: u8> ConstraintChecker<V> for SettleGame<T, ID>
+where
+    T: RockPaperScissorsConfig,
+    V: Verifier + PartialEq,
+{
+    type Error = ConstraintCheckerError;
+    type InherentHooks = ();
+
+    fn check(
+        &self,
+        inputs: &[Output<V>],
+        _peeks: &[Output<V>],
+        outputs: &[Output<V>],
+        _context: &CheckerContext,
+    ) -> Result<TransactionPriority, Self::Error> {
+        let game = game_input::<V, ID>(inputs)?;
+        let deadline_passed = T::block_height() >= game.deadline;
+        let challenger = &game.challenger;
+
+        // Who is owed what. Every case pays out exactly the wagers that were locked.
+        let owed = match &game.opponent {
+            None => {
+                ensure!(deadline_passed, ConstraintCheckerError::NotFinished);
+                vec![(&challenger.payout, game.wager)]
+            }
+            Some(opponent) => {
+                let pot = game
+                    .wager
+                    .checked_mul(2)
+                    .ok_or(ConstraintCheckerError::ValueOverflow)?;
+                match (challenger.revealed, opponent.revealed) {
+                    (Some(a), Some(b)) if a.beats(&b) => vec![(&challenger.payout, pot)],
+                    (Some(a), Some(b)) if b.beats(&a) => vec![(&opponent.payout, pot)],
+                    (Some(_), Some(_)) => vec![
+                        (&challenger.payout, game.wager),
+                        (&opponent.payout, game.wager),
+                    ],
+                    _ if !deadline_passed => return Err(ConstraintCheckerError::NotFinished),
+                    (Some(_), None) => vec![(&challenger.payout, pot)],
+                    (None, Some(_)) => vec![(&opponent.payout, pot)],
+                    (None, None) => vec![
+                        (&challenger.payout, game.wager),
+                        (&opponent.payout, game.wager),
+                    ],
+                }
+            }
+        };
+
+        ensure!(
+            outputs.len() == owed.len(),
+            ConstraintCheckerError::WrongNumberOutputs
+        );
+        let mut burned: u128 = 0;
+        for (output, (payee, amount)) in outputs.iter().zip(owed) {
+            ensure!(
+                &output.verifier == payee,
+                ConstraintCheckerError::PaidToWrongVerifier
+            );
+            let paid = sum_coins::<V, ID>([output], ConstraintCheckerError::BadlyTypedOutput)?;
+            ensure!(paid <= amount, ConstraintCheckerError::OutputsExceedInputs);
+            burned = burned.saturating_add(amount - paid);
+        }
+
+        Ok(priority(burned))
+    }
+
+    fn is_inherent(&self) -> bool {
+        false
+    }
+}
@@ -0,0 +1,489 @@
+//! Unit tests for the rock-paper-scissors piece
+
+use super::*;
+use tuxedo_core::dynamic_typing::testing::Bogus;
+
+/// A mock config that always reports the same block height, and lets players reveal for ten blocks.
+pub struct AtHeight<const H: u32>;
+
+impl<const H: u32> RockPaperScissorsConfig for AtHeight<H> {
+    fn block_height() -> u32 {
+        H
+    }
+
+    const REVEAL_PERIOD: u32 = 10;
+}
+
+/// A verifier that can represent players and games.
+#[derive(Serialize, Deserialize, Encode, Decode, Debug, PartialEq, Eq, Clone, TypeInfo)]
+#[tuxedo_core::tuxedo_verifier]
+pub enum TestVerifier {
+    SigCheck(tuxedo_core::verifier::SigCheck),
+    UpForGrabs(UpForGrabs),
+}
+
+fn alice() -> TestVerifier {
+    tuxedo_core::verifier::SigCheck::new(H256::repeat_byte(1)).into()
+}
+
+fn bob() -> TestVerifier {
+    tuxedo_core::verifier::SigCheck::new(H256::repeat_byte(2)).into()
+}
+
+const ALICE_SALT: H256 = H256::repeat_byte(3);
+const BOB_SALT: H256 = H256::repeat_byte(4);
+
+/// Alice challenges with rock and a wager of 100. Anyone may join until block 50.
+fn open_game() -> Game<TestVerifier, 0> {
+    Game {
+        wager: 100,
+        challenger: Player::new(alice(), commitment(Hand::Rock, ALICE_SALT, &alice())),
+        opponent: None,
+        deadline: 50,
+    }
+}
+
+/// Bob joins Alice's game at block 40 with the given hand, so they may reveal until block 50.
+fn joined_game(bob_hand: Hand) -> Game<TestVerifier, 0> {
+    Game {
+        opponent: Some(Player::new(bob(), commitment(bob_hand, BOB_SALT, &bob()))),
+        ..open_game()
+    }
+}
+
+/// Bob's game against Alice, with the given hands revealed.
+fn revealed_game(
+    alice_hand: Option<Hand>,
+    bob_hand: Hand,
+    bob_revealed: bool,
+) -> Game<TestVerifier, 0> {
+    let mut game = joined_game(bob_hand);
+    game.challenger.revealed = alice_hand;
+    if bob_revealed {
+        game.opponent.as_mut().unwrap().revealed = Some(bob_hand);
+    }
+    game
+}
+
+fn up_for_grabs(game: Game<TestVerifier, 0>) -> Output<TestVerifier> {
+    (game, UpForGrabs.into()).into()
+}
+
+fn coin(value: u128, verifier: TestVerifier) -> Output<TestVerifier> {
+    (Coin::<0>(value), verifier).into()
+}
+
+fn check<C: ConstraintChecker<TestVerifier>>(
+    checker: C,
+    inputs: Vec<Output<TestVerifier>>,
+    outputs: Vec<Output<TestVerifier>>,
+) -> Result<TransactionPriority, C::Error> {
+    checker.check(&inputs, &[], &outputs, &Default::default())
+}
+
+#[test]
+fn rock_paper_scissors_rules() {
+    assert!(Hand::Rock.beats(&Hand::Scissors));
+    assert!(Hand::Paper.beats(&Hand::Rock));
+    assert!(Hand::Scissors.beats(&Hand::Paper));
+    assert!(!Hand::Rock.beats(&Hand::Paper));
+    assert!(!Hand::Rock.beats(&Hand::Rock));
+}
+
+#[test]
+fn open_works() {
+    let inputs = vec![coin(150, alice())];
+    let outputs = vec![up_for_grabs(open_game()), coin(49, alice())];
+
+    assert_eq!(
+        check(OpenGame::<AtHeight<30>, 0>::default(), inputs, outputs),
+        Ok(1)
+    );
+}
+
+#[test]
+fn open_exceeding_inputs_fails() {
+    let inputs = vec![coin(99, alice())];
+    let outputs = vec![up_for_grabs(open_game())];
+
+    assert_eq!(
+        check(OpenGame::<AtHeight<30>, 0>::default(), inputs, outputs),
+        Err(ConstraintCheckerError::OutputsExceedInputs)
+    );
+}
+
+#[test]
+fn open_with_deadline_in_past_fails() {
+    let inputs = vec![coin(100, alice())];
+    let outputs = vec![up_for_grabs(open_game())];
+
+    assert_eq!(
+        check(OpenGame::<AtHeight<50>, 0>::default(), inputs, outputs),
+        Err(ConstraintCheckerError::DeadlineInPast)
+    );
+}
+
+#[test]
+fn open_already_joined_game_fails() {
+    let inputs = vec![coin(100, alice())];
+    let outputs = vec![up_for_grabs(joined_game(Hand::Paper))];
+
+    assert_eq!(
+        check(OpenGame::<AtHeight<30>, 0>::default(), inputs, outputs),
+        Err(ConstraintCheckerError::NotANewGame)
+    );
+}
+
+#[test]
+fn open_game_not_up_for_grabs_fails() {
+    let inputs = vec![coin(100, alice())];
+    let outputs = vec![(open_game(), alice()).into()];
+
+    assert_eq!(
+        check(OpenGame::<AtHeight<30>, 0>::default(), inputs, outputs),
+        Err(ConstraintCheckerError::GameNotUpForGrabs)
+    );
+}
+
+#[test]
+fn join_works() {
+    let inputs = vec![up_for_grabs(open_game()), coin(120, bob())];
+    let outputs = vec![up_for_grabs(joined_game(Hand::Paper)), coin(20, bob())];
+
+    assert_eq!(
+        check(JoinGame::<AtHeight<40>, 0>::default(), inputs, outputs),
+        Ok(0)
+    );
+}
+
+#[test]
+fn join_without_wager_fails() {
+    let inputs = vec![up_for_grabs(open_game()), coin(99, bob())];
+    let outputs = vec![up_for_grabs(joined_game(Hand::Paper))];
+
+    assert_eq!(
+        check(JoinGame::<AtHeight<40>, 0>::default(), inputs, outputs),
+        Err(ConstraintCheckerError::OutputsExceedInputs)
+    );
+}
+
+#[test]
+fn join_after_deadline_fails() {
+    let inputs = vec![up_for_grabs(open_game()), coin(100, bob())];
+    let mut joined = joined_game(Hand::Paper);
+    joined.deadline = 60;
+    let outputs = vec![up_for_grabs(joined)];
+
+    assert_eq!(
+        check(JoinGame::<AtHeight<50>, 0>::default(), inputs, outputs),
+        Err(ConstraintCheckerError::DeadlinePassed)
+    );
+}
+
+#[test]
+fn join_joined_game_fails() {
+    let inputs = vec![up_for_grabs(joined_game(Hand::Paper)), coin(100, alice())];
+    let outputs = vec![up_for_grabs(joined_game(Hand::Paper))];
+
+    assert_eq!(
+        check(JoinGame::<AtHeight<40>, 0>::default(), inputs, outputs),
+        Err(ConstraintCheckerError::AlreadyJoined)
+    );
+}
+
+#[test]
+fn join_with_wrong_deadline_fails() {
+    let inputs = vec![up_for_grabs(open_game()), coin(100, bob())];
+    let outputs = vec![up_for_grabs(joined_game(Hand::Paper))];
+
+    assert_eq!(
+        check(JoinGame::<AtHeight<30>, 0>::default(), inputs, outputs),
+        Err(ConstraintCheckerError::GameMismatch)
+    );
+}
+
+#[test]
+fn join_changing_wager_fails() {
+    let inputs = vec![up_for_grabs(open_game()), coin(100, bob())];
+    let mut joined = joined_game(Hand::Paper);
+    joined.wager = 1;
+    let outputs = vec![up_for_grabs(joined)];
+
+    assert_eq!(
+        check(JoinGame::<AtHeight<40>, 0>::default(), inputs, outputs),
+        Err(ConstraintCheckerError::GameMismatch)
+    );
+}
+
+#[test]
+fn join_without_opponent_fails() {
+    let inputs = vec![up_for_grabs(open_game()), coin(100, bob())];
+    let outputs = vec![up_for_grabs(open_game())];
+
+    assert_eq!(
+        check(JoinGame::<AtHeight<40>, 0>::default(), inputs, outputs),
+        Err(ConstraintCheckerError::NotJoined)
+    );
+}
+
+#[test]
+fn reveal_challenger_works() {
+    let inputs = vec![up_for_grabs(joined_game(Hand::Paper))];
+    let outputs = vec![up_for_grabs(revealed_game(
+        Some(Hand::Rock),
+        Hand::Paper,
+        false,
+    ))];
+
+    assert_eq!(
+        check(
+            RevealHand::<AtHeight<45>, 0>::new(Hand::Rock, ALICE_SALT),
+            inputs,
+            outputs
+        ),
+        Ok(0)
+    );
+}
+
+#[test]
+fn reveal_opponent_works() {
+    let inputs = vec![up_for_grabs(joined_game(Hand::Paper))];
+    let outputs = vec![up_for_grabs(revealed_game(None, Hand::Paper, true))];
+
+    assert_eq!(
+        check(
+            RevealHand::<AtHeight<45>, 0>::new(Hand::Paper, BOB_SALT),
+            inputs,
+            outputs
+        ),
+        Ok(0)
+    );
+}
+
+#[test]
+fn reveal_other_hand_fails() {
+    let inputs = vec![up_for_grabs(joined_game(Hand::Paper))];
+    let outputs = vec![up_for_grabs(revealed_game(
+        Some(Hand::Scissors),
+        Hand::Paper,
+        false,
+    ))];
+
+    assert_eq!(
+        check(
+            RevealHand::<AtHeight<45>, 0>::new(Hand::Scissors, ALICE_SALT),
+            inputs,
+            outputs
+        ),
+        Err(ConstraintCheckerError::WrongReveal)
+    );
+}
+
+#[test]
+fn reveal_copied_commitment_fails() {
+    // Bob copies Alice's commitment, hoping to reveal the same hand once she does and force a draw.
+    let mut game = open_game();
+    game.opponent = Some(Player::new(bob(), game.challenger.commitment));
+    game.challenger.revealed = Some(Hand::Rock);
+    let inputs = vec![up_for_grabs(game.clone())];
+
+    let mut revealed = game;
+    revealed.opponent.as_mut().unwrap().revealed = Some(Hand::Rock);
+    let outputs = vec![up_for_grabs(revealed)];
+
+    assert_eq!(
+        check(
+            RevealHand::<AtHeight<45>, 0>::new(Hand::Rock, ALICE_SALT),
+            inputs,
+            outputs
+        ),
+        Err(ConstraintCheckerError::WrongReveal)
+    );
+}
+
+#[test]
+fn reveal_after_deadline_fails() {
+    let inputs = vec![up_for_grabs(joined_game(Hand::Paper))];
+    let outputs = vec![up_for_grabs(revealed_game(
+        Some(Hand::Rock),
+        Hand::Paper,
+        false,
+    ))];
+
+    assert_eq!(
+        check(
+            RevealHand::<AtHeight<50>, 0>::new(Hand::Rock, ALICE_SALT),
+            inputs,
+            outputs
+        ),
+        Err(ConstraintCheckerError::DeadlinePassed)
+    );
+}
+
+#[test]
+fn reveal_before_opponent_joins_fails() {
+    let inputs = vec![up_for_grabs(open_game())];
+    let mut revealed = open_game();
+    revealed.challenger.revealed = Some(Hand::Rock);
+    let outputs = vec![up_for_grabs(revealed)];
+
+    assert_eq!(
+        check(
+            RevealHand::<AtHeight<45>, 0>::new(Hand::Rock, ALICE_SALT),
+            inputs,
+            outputs
+        ),
+        Err(ConstraintCheckerError::NotJoined)
+    );
+}
+
+#[test]
+fn reveal_changing_game_fails() {
+    let inputs = vec![up_for_grabs(joined_game(Hand::Paper))];
+    let mut revealed = revealed_game(Some(Hand::Rock), Hand::Paper, false);
+    revealed.deadline = 1000;
+    let outputs = vec![up_for_grabs(revealed)];
+
+    assert_eq!(
+        check(
+            RevealHand::<AtHeight<45>, 0>::new(Hand::Rock, ALICE_SALT),
+            inputs,
+            outputs
+        ),
+        Err(ConstraintCheckerError::GameMismatch)
+    );
+}
+
+#[test]
+fn settle_pays_winner() {
+    let inputs = vec![up_for_grabs(revealed_game(
+        Some(Hand::Rock),
+        Hand::Paper,
+        true,
+    ))];
+    let outputs = vec![coin(195, bob())];
+
+    assert_eq!(
+        check(SettleGame::<AtHeight<45>, 0>::default(), inputs, outputs),
+        Ok(5)
+    );
+}
+
+#[test]
+fn settle_paying_loser_fails() {
+    let inputs = vec![up_for_grabs(revealed_game(
+        Some(Hand::Rock),
+        Hand::Paper,
+        true,
+    ))];
+    let outputs = vec![coin(200, alice())];
+
+    assert_eq!(
+        check(SettleGame::<AtHeight<45>, 0>::default(), inputs, outputs),
+        Err(ConstraintCheckerError::PaidToWrongVerifier)
+    );
+}
+
+#[test]
+fn settle_paying_too_much_fails() {
+    let inputs = vec![up_for_grabs(revealed_game(
+        Some(Hand::Rock),
+        Hand::Paper,
+        true,
+    ))];
+    let outputs = vec![coin(201, bob())];
+
+    assert_eq!(
+        check(SettleGame::<AtHeight<45>, 0>::default(), inputs, outputs),
+        Err(ConstraintCheckerError::OutputsExceedInputs)
+    );
+}
+
+#[test]
+fn settle_draw_refunds_both() {
+    let inputs = vec![up_for_grabs(revealed_game(
+        Some(Hand::Rock),
+        Hand::Rock,
+        true,
+    ))];
+    let outputs = vec![coin(100, alice()), coin(100, bob())];
+
+    assert_eq!(
+        check(SettleGame::<AtHeight<45>, 0>::default(), inputs, outputs),
+        Ok(0)
+    );
+}
+
+#[test]
+fn settle_before_reveals_fails() {
+    let inputs = vec![up_for_grabs(revealed_game(
+        Some(Hand::Rock),
+        Hand::Paper,
+        false,
+    ))];
+    let outputs = vec![coin(200, alice())];
+
+    assert_eq!(
+        check(SettleGame::<AtHeight<49>, 0>::default(), inputs, outputs),
+        Err(ConstraintCheckerError::NotFinished)
+    );
+}
+
+#[test]
+fn settle_after_deadline_pays_the_only_revealer() {
+    let inputs = vec![up_for_grabs(revealed_game(
+        Some(Hand::Rock),
+        Hand::Paper,
+        false,
+    ))];
+    let outputs = vec![coin(200, alice())];
+
+    assert_eq!(
+        check(SettleGame::<AtHeight<50>, 0>::default(), inputs, outputs),
+        Ok(0)
+    );
+}
+
+#[test]
+fn settle_after_deadline_without_reveals_refunds_both() {
+    let inputs = vec![up_for_grabs(joined_game(Hand::Paper))];
+    let outputs = vec![coin(100, alice()), coin(100, bob())];
+
+    assert_eq!(
+        check(SettleGame::<AtHeight<50>, 0>::default(), inputs, outputs),
+        Ok(0)
+    );
+}
+
+#[test]
+fn settle_unjoined_game_refunds_challenger() {
+    let inputs = vec![up_for_grabs(open_game())];
+    let outputs = vec![coin(100, alice())];
+
+    assert_eq!(
+        check(SettleGame::<AtHeight<50>, 0>::default(), inputs, outputs),
+        Ok(0)
+    );
+}
+
+#[test]
+fn settle_unjoined_game_before_deadline_fails() {
+    let inputs = vec![up_for_grabs(open_game())];
+    let outputs = vec![coin(100, alice())];
+
+    assert_eq!(
+        check(SettleGame::<AtHeight<49>, 0>::default(), inputs, outputs),
+        Err(ConstraintCheckerError::NotFinished)
+    );
+}
+
+#[test]
+fn settle_non_game_fails() {
+    let inputs = vec![(Bogus, alice()).into()];
+    let outputs = vec![coin(100, alice())];
+
+    assert_eq!(
+        check(SettleGame::<AtHeight<50>, 0>::default(), inputs, outputs),
+        Err(ConstraintCheckerError::BadlyTypedInput)
+    );
+}
@@ -1,9 +1,18 @@
 //! This is a small pallet that handles runtime upgrades in chains that want
 //! to support them.
 //!
-//! Right now this method is entirely unprotected (except by the verifier) which
-//! may not be realistic enough for public production chains. It should be composed
-//! with some governance mechanism when one is available.
+//! On a live network, arbitrary users must not be able to push new wasm. Each runtime
+//! chooses how upgrades are authorized through its [`RuntimeUpgradeConfig`]:
+//!
+//! * With an upgrade key, the transaction must consume an [`UpgradeKey`] UTXO and re-create it.
+//!   The key is typically placed in the genesis state behind a `ThresholdMultiSignature`, so
+//!   that a quorum of its holders has to sign every upgrade. Whoever consumes the key also
+//!   chooses the verifier of the re-created key, so the holders can rotate themselves.
+//! * With governance, the transaction must consume an `Enacted<ID>` UTXO from the governance
+//!   piece whose payload is the hash of the new wasm. The referendum is then the authorization.
+//!
+//! If a runtime enables neither, upgrades are only protected by the verifier of the runtime
+//! reference, which is rarely what a public chain wants.
 //!
 //! It is not possible to adhere perfectly to the UTXO model here, because the
 //! wasm code must be stored in the well-known `:code` key. We stick as closely
@@ -13,22 +22,29 @@
 
 #![cfg_attr(not(feature = "std"), no_std)]
 
+use core::marker::PhantomData;
+
+use governance::Enacted;
 use parity_scale_codec::{Decode, Encode};
 use scale_info::TypeInfo;
 use serde::{Deserialize, Serialize};
+use sp_core::H256;
 use sp_runtime::transaction_validity::TransactionPriority;
 use sp_std::vec::Vec;
 use sp_storage::well_known_keys::CODE;
 use tuxedo_core::{
     dynamic_typing::{DynamicallyTypedData, UtxoData},
-    ensure, SimpleConstraintChecker,
+    ensure,
+    metadata::MetadataBuilder,
+    support_macros::{CloneNoBound, DebugNoBound},
+    CheckerContext, SimpleConstraintChecker,
 };
 
 #[cfg(test)]
 mod tests;
 
 /// A reference to a runtime wasm blob. It is just a hash.
-#[derive(Serialize, Deserialize, Encode, Decode, Debug, PartialEq, Eq, Clone)]
+#[derive(Serialize, Deserialize, Encode, Decode, Debug, PartialEq, Eq, Clone, TypeInfo)]
 struct RuntimeRef {
     hash: [u8; 32],
 }
@@ -37,8 +53,36 @@ impl UtxoData for RuntimeRef {
     const TYPE_ID: [u8; 4] = *b"upgd";
 }
 
+/// The right to upgrade the runtime. Its verifier decides who may upgrade.
+#[derive(
+    Serialize, Deserialize, Encode, Decode, Debug, Default, PartialEq, Eq, Clone, TypeInfo,
+)]
+pub struct UpgradeKey;
+
+impl UtxoData for UpgradeKey {
+    const TYPE_ID: [u8; 4] = *b"upky";
+}
+
+/// Configuration items for the runtime upgrade piece when it is
+/// instantiated in a concrete runtime.
+pub trait RuntimeUpgradeConfig {
+    /// Whether consuming and re-creating an [`UpgradeKey`] authorizes an upgrade.
+    const UPGRADE_KEY: bool = false;
+
+    /// Whether consuming an `Enacted` governance outcome whose payload is the hash of the new
+    /// wasm authorizes an upgrade.
+    const GOVERNANCE: bool = false;
+}
+
+/// Describe the runtime references and upgrade keys that this piece stores in the runtime metadata.
+pub fn describe_utxo_types(builder: MetadataBuilder) -> MetadataBuilder {
+    builder
+        .with_utxo_type::<RuntimeRef>()
+        .with_utxo_type::<UpgradeKey>()
+}
+
 /// Reasons that the RuntimeUpgrade constraint checker may fail
-#[derive(Debug)]
+#[derive(Debug, PartialEq, Eq)]
 pub enum ConstraintCheckerError {
     // Again we're duplicating these common errors. Probably going to want a
     // better way to handle these.
@@ -57,21 +101,44 @@ pub enum ConstraintCheckerError {
     InputMismatch,
     /// The created output does not match the provided new runtime wasm.
     OutputMismatch,
+    /// The runtime requires authorization, but the transaction does not consume an upgrade key
+    /// or governance outcome that the runtime accepts.
+    Unauthorized,
+    /// The consumed governance outcome was enacted for some other payload than the new wasm.
+    EnactedForOtherPayload,
+    /// The consumed upgrade key was not re-created.
+    UpgradeKeyNotRecreated,
 }
 
 /// The sole constraint checker for the runtime upgrade. It confirms that the UTXO
 /// being consumed points to the correct current wasm and creates a new
 /// UTXO for the new wasm.
 ///
+/// When the runtime requires authorization, the second input is the upgrade key or governance
+/// outcome. An upgrade key must be re-created as the second output.
+///
 /// This constraint checker is somewhat non-standard in that it has a side-effect that
 /// writes the full wasm code to the well-known `:code` storage key. This is
 /// necessary to satisfy Substrate's assumptions that this will happen.
-#[derive(Serialize, Deserialize, Encode, Decode, Debug, PartialEq, Eq, Clone, TypeInfo)]
-pub struct RuntimeUpgrade {
+#[derive(
+    Serialize, Deserialize, Encode, Decode, DebugNoBound, PartialEq, Eq, CloneNoBound, TypeInfo,
+)]
+#[scale_info(skip_type_params(T))]
+pub struct RuntimeUpgrade<T, const ID: u8> {
     full_wasm: Vec<u8>,
+    _config: PhantomData<T>,
+}
+
+impl<T, const ID: u8> RuntimeUpgrade<T, ID> {
+    pub fn new(full_wasm: Vec<u8>) -> Self {
+        Self {
+            full_wasm,
+            _config: PhantomData,
+        }
+    }
 }
 
-impl SimpleConstraintChecker for RuntimeUpgrade {
+impl<T: RuntimeUpgradeConfig, const ID: u8> SimpleConstraintChecker for RuntimeUpgrade<T, ID> {
     type Error = ConstraintCheckerError;
 
     fn check(
@@ -79,10 +146,14 @@ impl SimpleConstraintChecker for RuntimeUpgrade {
         input_data: &[DynamicallyTypedData],
         _peeks: &[DynamicallyTypedData],
         output_data: &[DynamicallyTypedData],
+        _context: &CheckerContext,
     ) -> Result<TransactionPriority, Self::Error> {
-        // Make sure there is a single input that matches the hash of the previous runtime logic
+        let authorized = T::UPGRADE_KEY || T::GOVERNANCE;
+        let expected_inputs = if authorized { 2 } else { 1 };
+
+        // Make sure the first input matches the hash of the previous runtime logic
         ensure!(
-            input_data.len() == 1,
+            input_data.len() == expected_inputs,
             ConstraintCheckerError::WrongNumberInputs
         );
         let consumed = input_data[0]
@@ -96,15 +167,37 @@ impl SimpleConstraintChecker for RuntimeUpgrade {
             ConstraintCheckerError::InputMismatch
         );
 
-        // Make sure there is a single output that matches the has of the incoming runtime logic
+        // Make sure the upgrade is authorized. Upgrade keys live on, governance outcomes are used up.
+        let incoming_hash = sp_io::hashing::blake2_256(&self.full_wasm);
+        let mut expected_outputs = 1;
+        if authorized {
+            if T::UPGRADE_KEY && input_data[1].extract::<UpgradeKey>().is_ok() {
+                ensure!(
+                    output_data.len() == 2 && output_data[1].extract::<UpgradeKey>().is_ok(),
+                    ConstraintCheckerError::UpgradeKeyNotRecreated
+                );
+                expected_outputs = 2;
+            } else if T::GOVERNANCE {
+                let enacted = input_data[1]
+                    .extract::<Enacted<ID>>()
+                    .map_err(|_| ConstraintCheckerError::Unauthorized)?;
+                ensure!(
+                    enacted.payload == H256(incoming_hash),
+                    ConstraintCheckerError::EnactedForOtherPayload
+                );
+            } else {
+                return Err(ConstraintCheckerError::Unauthorized);
+            }
+        }
+
+        // Make sure the first output matches the hash of the incoming runtime logic
         ensure!(
-            output_data.len() == 1,
+            output_data.len() == expected_outputs,
             ConstraintCheckerError::WrongNumberOutputs
         );
         let created = output_data[0]
             .extract::<RuntimeRef>()
             .map_err(|_| ConstraintCheckerError::BadlyTypedOutput)?;
-        let incoming_hash = sp_io::hashing::blake2_256(&self.full_wasm);
         ensure!(
             created.hash == incoming_hash,
             ConstraintCheckerError::OutputMismatch
@@ -1 +1,156 @@
-//! Tests for the runtime upgrade piece are TODO
+//! Unit tests for the runtime upgrade piece
+
+use super::*;
+use sp_io::TestExternalities;
+use tuxedo_core::dynamic_typing::testing::Bogus;
+
+const OLD_WASM: &[u8] = b"old wasm";
+const NEW_WASM: &[u8] = b"new wasm";
+
+/// A mock config that protects upgrades with nothing but the verifier.
+pub struct Unprotected;
+
+impl RuntimeUpgradeConfig for Unprotected {}
+
+/// A mock config that protects upgrades with an upgrade key.
+pub struct KeyOnly;
+
+impl RuntimeUpgradeConfig for KeyOnly {
+    const UPGRADE_KEY: bool = true;
+}
+
+/// A mock config that protects upgrades with governance.
+pub struct GovernanceOnly;
+
+impl RuntimeUpgradeConfig for GovernanceOnly {
+    const GOVERNANCE: bool = true;
+}
+
+fn runtime_ref(wasm: &[u8]) -> DynamicallyTypedData {
+    RuntimeRef {
+        hash: sp_io::hashing::blake2_256(wasm),
+    }
+    .into()
+}
+
+fn enacted(wasm: &[u8]) -> DynamicallyTypedData {
+    Enacted::<0> {
+        payload: H256(sp_io::hashing::blake2_256(wasm)),
+    }
+    .into()
+}
+
+/// Run the check with the old wasm in storage, and return the stored wasm afterwards.
+fn check<T: RuntimeUpgradeConfig>(
+    inputs: Vec<DynamicallyTypedData>,
+    outputs: Vec<DynamicallyTypedData>,
+) -> (Result<TransactionPriority, ConstraintCheckerError>, Vec<u8>) {
+    TestExternalities::default().execute_with(|| {
+        sp_io::storage::set(CODE, OLD_WASM);
+        let result = RuntimeUpgrade::<T, 0>::new(NEW_WASM.to_vec()).check(
+            &inputs,
+            &[],
+            &outputs,
+            &Default::default(),
+        );
+        (result, sp_io::storage::get(CODE).unwrap().to_vec())
+    })
+}
+
+#[test]
+fn unprotected_upgrade_works() {
+    let (result, code) =
+        check::<Unprotected>(vec![runtime_ref(OLD_WASM)], vec![runtime_ref(NEW_WASM)]);
+
+    assert_eq!(result, Ok(0));
+    assert_eq!(code, NEW_WASM);
+}
+
+#[test]
+fn upgrade_with_stale_reference_fails() {
+    let (result, code) =
+        check::<Unprotected>(vec![runtime_ref(b"other")], vec![runtime_ref(NEW_WASM)]);
+
+    assert_eq!(result, Err(ConstraintCheckerError::InputMismatch));
+    assert_eq!(code, OLD_WASM);
+}
+
+#[test]
+fn upgrade_with_wrong_new_reference_fails() {
+    let (result, _) =
+        check::<Unprotected>(vec![runtime_ref(OLD_WASM)], vec![runtime_ref(b"other")]);
+
+    assert_eq!(result, Err(ConstraintCheckerError::OutputMismatch));
+}
+
+#[test]
+fn upgrade_with_key_works() {
+    let (result, code) = check::<KeyOnly>(
+        vec![runtime_ref(OLD_WASM), UpgradeKey.into()],
+        vec![runtime_ref(NEW_WASM), UpgradeKey.into()],
+    );
+
+    assert_eq!(result, Ok(0));
+    assert_eq!(code, NEW_WASM);
+}
+
+#[test]
+fn upgrade_without_key_fails() {
+    let (result, code) = check::<KeyOnly>(vec![runtime_ref(OLD_WASM)], vec![runtime_ref(NEW_WASM)]);
+
+    assert_eq!(result, Err(ConstraintCheckerError::WrongNumberInputs));
+    assert_eq!(code, OLD_WASM);
+}
+
+#[test]
+fn upgrade_with_something_other_than_key_fails() {
+    let (result, _) = check::<KeyOnly>(
+        vec![runtime_ref(OLD_WASM), Bogus.into()],
+        vec![runtime_ref(NEW_WASM), UpgradeKey.into()],
+    );
+
+    assert_eq!(result, Err(ConstraintCheckerError::Unauthorized));
+}
+
+#[test]
+fn upgrade_that_does_not_recreate_key_fails() {
+    let (result, code) = check::<KeyOnly>(
+        vec![runtime_ref(OLD_WASM), UpgradeKey.into()],
+        vec![runtime_ref(NEW_WASM)],
+    );
+
+    assert_eq!(result, Err(ConstraintCheckerError::UpgradeKeyNotRecreated));
+    assert_eq!(code, OLD_WASM);
+}
+
+#[test]
+fn upgrade_with_governance_works() {
+    let (result, code) = check::<GovernanceOnly>(
+        vec![runtime_ref(OLD_WASM), enacted(NEW_WASM)],
+        vec![runtime_ref(NEW_WASM)],
+    );
+
+    assert_eq!(result, Ok(0));
+    assert_eq!(code, NEW_WASM);
+}
+
+#[test]
+fn upgrade_with_governance_for_other_wasm_fails() {
+    let (result, code) = check::<GovernanceOnly>(
+        vec![runtime_ref(OLD_WASM), enacted(b"other")],
+        vec![runtime_ref(NEW_WASM)],
+    );
+
+    assert_eq!(result, Err(ConstraintCheckerError::EnactedForOtherPayload));
+    assert_eq!(code, OLD_WASM);
+}
+
+#[test]
+fn governance_config_rejects_upgrade_key() {
+    let (result, _) = check::<GovernanceOnly>(
+        vec![runtime_ref(OLD_WASM), UpgradeKey.into()],
+        vec![runtime_ref(NEW_WASM), UpgradeKey.into()],
+    );
+
+    assert_eq!(result, Err(ConstraintCheckerError::Unauthorized));
+}
@@ -0,0 +1,385 @@
+//! Session keys that consensus authorities publish on chain.
+//!
+//! An authority is identified by the key that the Aura or GRANDPA authority set lists for it. Rather
+//! than keeping that key on the node that authors or votes, the authority can generate fresh session
+//! keys on the node, for example through the `author_rotateKeys` RPC method, and publish them in a
+//! [`PublishSessionKey`] transaction signed by its identity key. From then on the runtime's
+//! consensus APIs report the session key in place of the identity, until the authority publishes
+//! another one. Each engine's key is published separately, because the identity keys of the two
+//! engines differ: Aura identities are sr25519 keys, while GRANDPA identities are ed25519 keys.
+//!
+//! The current key of each authority lives in a [`Registration`] UTXO. The first registration of an
+//! authority consumes nothing, and every later one consumes the current registration and increments
+//! its nonce, so that old signed registrations can not be replayed. Like the authorities pieces, the
+//! checker also writes each registration to a well-known storage key as a side effect, so that the
+//! runtime APIs can find it through [`active_key`].
+//!
+//! A new Aura key takes effect from the block after the one that publishes it, because Aura reads
+//! the authorities from the parent block's state. The GRANDPA voters do not read the runtime's state,
+//! so publishing a GRANDPA key also schedules a change to the same voters with their new keys in the
+//! block's digest. Each such change counts as a new GRANDPA set, which is why the runtime adds
+//! [`grandpa_key_changes`] to the set id of the GRANDPA authorities piece.
+//!
+//! ## Hack Warning
+//!
+//! Like the authorities pieces, this piece abuses the UpForGrabs verifier to protect the
+//! registrations, and relies on the constraint checker to make sure that only the authority can
+//! replace its own. GRANDPA only reads one scheduled change from each block, so the checker allows
+//! one new GRANDPA key per block, and none in a block that the runtime reports to
+//! [already schedule a change](SessionKeysConfig::grandpa_change_scheduled).
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use core::marker::PhantomData;
+
+use parity_scale_codec::{Decode, Encode};
+use scale_info::TypeInfo;
+use serde::{Deserialize, Serialize};
+use sp_consensus_grandpa::{
+    AuthorityId, AuthorityList, AuthorityWeight, ConsensusLog, ScheduledChange, GRANDPA_ENGINE_ID,
+};
+use sp_core::{ed25519, sr25519, H256};
+use sp_runtime::{transaction_validity::TransactionPriority, DigestItem};
+use sp_std::vec::Vec;
+use tuxedo_core::{
+    digest,
+    dynamic_typing::UtxoData,
+    ensure,
+    support_macros::{CloneNoBound, DebugNoBound},
+    types::Output,
+    verifier::UpForGrabs,
+    CheckerContext, ConstraintChecker, Verifier,
+};
+
+#[cfg(test)]
+mod tests;
+
+/// The storage key that counts the GRANDPA key changes scheduled so far.
+const GRANDPA_CHANGES_KEY: &[u8] = b"session_keys_grandpa_changes";
+
+/// The storage key that holds the height of the last block that scheduled a GRANDPA key change.
+const LAST_GRANDPA_CHANGE_KEY: &[u8] = b"session_keys_last_grandpa_change";
+
+/// Configuration items for the session keys piece when it is
+/// instantiated in a concrete runtime.
+pub trait SessionKeysConfig {
+    /// The current block height.
+    /// Probably this will be the Tuxedo Executive
+    fn block_height() -> u32;
+
+    /// The sr25519 identity keys of the current Aura authorities, in the order that they author in.
+    fn aura_authorities() -> Vec<H256>;
+
+    /// The ed25519 identity keys and weights of the current GRANDPA authorities.
+    fn grandpa_authorities() -> Vec<(H256, AuthorityWeight)>;
+
+    /// Whether another piece, such as the GRANDPA authorities piece, already scheduled a change to
+    /// the GRANDPA voters in the current block. New GRANDPA keys are refused in such blocks.
+    fn grandpa_change_scheduled() -> bool {
+        false
+    }
+}
+
+/// The consensus engines that authorities can publish session keys for.
+#[derive(
+    Serialize,
+    Deserialize,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Clone,
+    Copy,
+    Encode,
+    Decode,
+    Hash,
+    Debug,
+    TypeInfo,
+)]
+pub enum Engine {
+    /// Block authoring with sr25519 keys
+    Aura,
+    /// Finality with ed25519 keys
+    Grandpa,
+}
+
+/// An authority's current session key for one consensus engine.
+#[derive(
+    Serialize,
+    Deserialize,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Clone,
+    Encode,
+    Decode,
+    Hash,
+    Debug,
+    TypeInfo,
+)]
+pub struct Registration {
+    /// The engine that the key is for
+    pub engine: Engine,
+    /// The identity key that the engine's authority set lists
+    pub authority: H256,
+    /// The session key that the authority uses in place of its identity
+    pub session_key: H256,
+    /// The number of keys that the authority published for this engine before this one
+    pub nonce: u32,
+}
+
+impl Registration {
+    /// The message that the authority's identity key signs to publish this registration.
+    pub fn signing_message(&self) -> Vec<u8> {
+        (b"session_keys", self).encode()
+    }
+
+    /// Whether the authority's identity key signed this registration.
+    fn is_signed_by_authority(&self, signature: &AuthoritySignature) -> bool {
+        let message = self.signing_message();
+        match (self.engine, signature) {
+            (Engine::Aura, AuthoritySignature::Sr25519(signature)) => {
+                sp_io::crypto::sr25519_verify(
+                    signature,
+                    &message,
+                    &sr25519::Public::from_h256(self.authority),
+                )
+            }
+            (Engine::Grandpa, AuthoritySignature::Ed25519(signature)) => {
+                sp_io::crypto::ed25519_verify(
+                    signature,
+                    &message,
+                    &ed25519::Public::from_raw(self.authority.0),
+                )
+            }
+            _ => false,
+        }
+    }
+}
+
+impl UtxoData for Registration {
+    const TYPE_ID: [u8; 4] = *b"sess";
+}
+
+/// A signature by an authority's identity key, of the kind that its engine uses.
+#[derive(Serialize, Deserialize, PartialEq, Eq, Clone, Encode, Decode, Debug, TypeInfo)]
+pub enum AuthoritySignature {
+    /// A signature by an Aura identity
+    Sr25519(sr25519::Signature),
+    /// A signature by a GRANDPA identity
+    Ed25519(ed25519::Signature),
+}
+
+/// The storage key that holds an authority's current registration for the given engine.
+fn registration_key(engine: Engine, authority: &H256) -> Vec<u8> {
+    (b"session_keys", engine, authority).encode()
+}
+
+/// The authority's current registration for the given engine, if it published a key.
+fn registration(engine: Engine, authority: &H256) -> Option<Registration> {
+    sp_io::storage::get(&registration_key(engine, authority))
+        .and_then(|d| Registration::decode(&mut &*d).ok())
+}
+
+/// The key that the authority currently uses for the given engine. This is the session key that it
+/// published last, or its identity key if it has not published one.
+pub fn active_key(engine: Engine, authority: H256) -> H256 {
+    registration(engine, &authority)
+        .map(|registration| registration.session_key)
+        .unwrap_or(authority)
+}
+
+/// The number of GRANDPA key changes that the piece scheduled so far.
+pub fn grandpa_key_changes() -> u64 {
+    sp_io::storage::get(GRANDPA_CHANGES_KEY)
+        .and_then(|d| u64::decode(&mut &*d).ok())
+        .unwrap_or(0)
+}
+
+/// The current Aura authorities, each with its active key.
+pub fn aura_authorities<T: SessionKeysConfig>() -> Vec<H256> {
+    T::aura_authorities()
+        .into_iter()
+        .map(|authority| active_key(Engine::Aura, authority))
+        .collect()
+}
+
+/// The current GRANDPA voters, each with its active key, in the form that GRANDPA expects.
+pub fn grandpa_voters<T: SessionKeysConfig>() -> AuthorityList {
+    T::grandpa_authorities()
+        .into_iter()
+        .map(|(authority, weight)| {
+            let key = active_key(Engine::Grandpa, authority);
+            (AuthorityId::from(ed25519::Public::from_raw(key.0)), weight)
+        })
+        .collect()
+}
+
+/// Errors that can occur when publishing a session key.
+#[derive(
+    Serialize,
+    Deserialize,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Clone,
+    Encode,
+    Decode,
+    Hash,
+    Debug,
+    TypeInfo,
+)]
+pub enum ConstraintCheckerError {
+    /// An input data has the wrong type.
+    BadlyTypedInput,
+    /// An output data has the wrong type.
+    BadlyTypedOutput,
+    /// Wrong number of inputs were provided to the constraint checker.
+    WrongNumberInputs,
+    /// Wrong number of outputs were provided to the constraint checker.
+    WrongNumberOutputs,
+    /// The new registration is not protected by the `UpForGrabs` verifier, so it could not be replaced.
+    RegistrationNotUpForGrabs,
+    /// The key is not the identity of an authority in the engine's current set.
+    NotAnAuthority,
+    /// The consumed registration is not the authority's current one.
+    InputMismatch,
+    /// The new registration's nonce does not follow the current one.
+    WrongNonce,
+    /// The session key is already the active key of another authority.
+    KeyInUse,
+    /// The authority's identity key did not sign the registration.
+    BadSignature,
+    /// The block already scheduled a GRANDPA key change, or another change to the GRANDPA voters.
+    GrandpaChangeAlreadyScheduled,
+}
+
+/// A constraint checker that publishes a new session key for an authority.
+///
+/// The only input is the authority's current registration for the engine, unless this is its
+/// first. The only output is the new registration, which must be up for grabs. The authority's
+/// identity key signs the new registration.
+#[derive(
+    Serialize, Deserialize, Encode, Decode, DebugNoBound, PartialEq, Eq, CloneNoBound, TypeInfo,
+)]
+#[scale_info(skip_type_params(T))]
+pub struct PublishSessionKey<T> {
+    /// The identity key's signature over the new registration.
+    pub signature: AuthoritySignature,
+    _phantom: PhantomData<T>,
+}
+
+impl<T> PublishSessionKey<T> {
+    pub fn new(signature: AuthoritySignature) -> Self {
+        Self {
+            signature,
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<T: SessionKeysConfig, V: Verifier + PartialEq + From<UpForGrabs>> ConstraintChecker<V>
+    for PublishSessionKey<T>
+{
+    type Error = ConstraintCheckerError;
+    type InherentHooks = ();
+
+    fn check(
+        &self,
+        inputs: &[Output<V>],
+        _peeks: &[Output<V>],
+        outputs: &[Output<V>],
+        _context: &CheckerContext,
+    ) -> Result<TransactionPriority, Self::Error> {
+        ensure!(
+            outputs.len() == 1,
+            ConstraintCheckerError::WrongNumberOutputs
+        );
+        let new = outputs[0]
+            .payload
+            .extract::<Registration>()
+            .map_err(|_| ConstraintCheckerError::BadlyTypedOutput)?;
+        ensure!(
+            outputs[0].verifier == UpForGrabs.into(),
+            ConstraintCheckerError::RegistrationNotUpForGrabs
+        );
+
+        let authorities: Vec<H256> = match new.engine {
+            Engine::Aura => T::aura_authorities(),
+            Engine::Grandpa => T::grandpa_authorities()
+                .into_iter()
+                .map(|(authority, _)| authority)
+                .collect(),
+        };
+        ensure!(
+            authorities.contains(&new.authority),
+            ConstraintCheckerError::NotAnAuthority
+        );
+        ensure!(
+            authorities
+                .iter()
+                .filter(|authority| **authority != new.authority)
+                .all(|authority| active_key(new.engine, *authority) != new.session_key),
+            ConstraintCheckerError::KeyInUse
+        );
+
+        // Once the authority has published a key, its current registration must be consumed.
+        match registration(new.engine, &new.authority) {
+            Some(current) => {
+                ensure!(inputs.len() == 1, ConstraintCheckerError::WrongNumberInputs);
+                let consumed = inputs[0]
+                    .payload
+                    .extract::<Registration>()
+                    .map_err(|_| ConstraintCheckerError::BadlyTypedInput)?;
+                ensure!(consumed == current, ConstraintCheckerError::InputMismatch);
+                ensure!(
+                    Some(new.nonce) == current.nonce.checked_add(1),
+                    ConstraintCheckerError::WrongNonce
+                );
+            }
+            None => {
+                ensure!(inputs.is_empty(), ConstraintCheckerError::WrongNumberInputs);
+                ensure!(new.nonce == 0, ConstraintCheckerError::WrongNonce);
+            }
+        }
+
+        ensure!(
+            new.is_signed_by_authority(&self.signature),
+            ConstraintCheckerError::BadSignature
+        );
+
+        let height = T::block_height();
+        if new.engine == Engine::Grandpa {
+            let last_change = sp_io::storage::get(LAST_GRANDPA_CHANGE_KEY)
+                .and_then(|d| u32::decode(&mut &*d).ok());
+            ensure!(
+                last_change != Some(height) && !T::grandpa_change_scheduled(),
+                ConstraintCheckerError::GrandpaChangeAlreadyScheduled
+            );
+        }
+
+        // SIDE EFFECT: Write the registration to storage so that the runtime APIs can find it
+        sp_io::storage::set(&registration_key(new.engine, &new.authority), &new.encode());
+
+        if new.engine == Engine::Grandpa {
+            // SIDE EFFECT: Schedule the new keys for the GRANDPA voters in the block's digest,
+            // and count the change so that the runtime can report the voters' set id.
+            // Like the rest of the wardrobe, this assumes that block numbers are u32.
+            let change = ConsensusLog::<u32>::ScheduledChange(ScheduledChange {
+                next_authorities: grandpa_voters::<T>(),
+                delay: 0,
+            });
+            digest::deposit_log(DigestItem::Consensus(GRANDPA_ENGINE_ID, change.encode()));
+            sp_io::storage::set(LAST_GRANDPA_CHANGE_KEY, &height.encode());
+            sp_io::storage::set(GRANDPA_CHANGES_KEY, &(grandpa_key_changes() + 1).encode());
+        }
+
+        Ok(0)
+    }
+
+    fn is_inherent(&self) -> bool {
+        false
+    }
+}
@@ -0,0 +1,403 @@
+//! Unit tests for the Session Keys piece
+
+use super::*;
+use sp_core::Pair;
+use sp_io::TestExternalities;
+use tuxedo_core::{dynamic_typing::testing::Bogus, tuxedo_verifier, verifier::SigCheck};
+
+/// A verifier that can represent the registrations and ordinary users.
+#[derive(Serialize, Deserialize, Encode, Decode, Debug, PartialEq, Eq, Clone, TypeInfo)]
+#[tuxedo_verifier]
+pub enum TestVerifier {
+    SigCheck(SigCheck),
+    UpForGrabs(UpForGrabs),
+}
+
+/// A mock config at the given block height, where the authorities of both engines are
+/// the keys generated from seeds one and two.
+pub struct AtHeight<const H: u32>;
+
+impl<const H: u32> SessionKeysConfig for AtHeight<H> {
+    fn block_height() -> u32 {
+        H
+    }
+
+    fn aura_authorities() -> Vec<H256> {
+        vec![aura_identity(1), aura_identity(2)]
+    }
+
+    fn grandpa_authorities() -> Vec<(H256, AuthorityWeight)> {
+        vec![(grandpa_identity(1), 1), (grandpa_identity(2), 1)]
+    }
+}
+
+fn aura_pair(seed: u8) -> sr25519::Pair {
+    sr25519::Pair::from_seed(&[seed; 32])
+}
+
+fn grandpa_pair(seed: u8) -> ed25519::Pair {
+    ed25519::Pair::from_seed(&[seed; 32])
+}
+
+fn aura_identity(seed: u8) -> H256 {
+    aura_pair(seed).public().into()
+}
+
+fn grandpa_identity(seed: u8) -> H256 {
+    H256(grandpa_pair(seed).public().0)
+}
+
+fn aura(seed: u8, session_key: H256, nonce: u32) -> Registration {
+    Registration {
+        engine: Engine::Aura,
+        authority: aura_identity(seed),
+        session_key,
+        nonce,
+    }
+}
+
+fn grandpa(seed: u8, session_key: H256, nonce: u32) -> Registration {
+    Registration {
+        engine: Engine::Grandpa,
+        authority: grandpa_identity(seed),
+        session_key,
+        nonce,
+    }
+}
+
+fn aura_signature(seed: u8, registration: &Registration) -> AuthoritySignature {
+    AuthoritySignature::Sr25519(aura_pair(seed).sign(&registration.signing_message()))
+}
+
+fn grandpa_signature(seed: u8, registration: &Registration) -> AuthoritySignature {
+    AuthoritySignature::Ed25519(grandpa_pair(seed).sign(&registration.signing_message()))
+}
+
+fn up_for_grabs(registration: Registration) -> Output<TestVerifier> {
+    (registration, UpForGrabs.into()).into()
+}
+
+fn publish<const H: u32>(
+    signature: AuthoritySignature,
+    inputs: Vec<Output<TestVerifier>>,
+    outputs: Vec<Output<TestVerifier>>,
+) -> Result<TransactionPriority, ConstraintCheckerError> {
+    PublishSessionKey::<AtHeight<H>>::new(signature).check(
+        &inputs,
+        &[],
+        &outputs,
+        &Default::default(),
+    )
+}
+
+/// The log that schedules a change to the given voters, each with weight one.
+fn scheduled_change(voters: Vec<H256>) -> DigestItem {
+    let change = ConsensusLog::<u32>::ScheduledChange(ScheduledChange {
+        next_authorities: voters
+            .into_iter()
+            .map(|key| (AuthorityId::from(ed25519::Public::from_raw(key.0)), 1))
+            .collect(),
+        delay: 0,
+    });
+    DigestItem::Consensus(GRANDPA_ENGINE_ID, change.encode())
+}
+
+#[test]
+fn identities_are_active_before_publishing() {
+    TestExternalities::default().execute_with(|| {
+        assert_eq!(
+            aura_authorities::<AtHeight<1>>(),
+            vec![aura_identity(1), aura_identity(2)]
+        );
+        assert_eq!(grandpa_key_changes(), 0);
+    });
+}
+
+#[test]
+fn first_aura_key_works() {
+    TestExternalities::default().execute_with(|| {
+        let new = aura(1, aura_identity(5), 0);
+
+        assert_eq!(
+            publish::<1>(aura_signature(1, &new), vec![], vec![up_for_grabs(new)]),
+            Ok(0)
+        );
+        assert_eq!(
+            aura_authorities::<AtHeight<1>>(),
+            vec![aura_identity(5), aura_identity(2)]
+        );
+        assert!(digest::deposited_logs().is_empty());
+    });
+}
+
+#[test]
+fn later_aura_key_consumes_current_registration() {
+    TestExternalities::default().execute_with(|| {
+        let first = aura(1, aura_identity(5), 0);
+        publish::<1>(
+            aura_signature(1, &first),
+            vec![],
+            vec![up_for_grabs(first.clone())],
+        )
+        .unwrap();
+        let second = aura(1, aura_identity(6), 1);
+
+        assert_eq!(
+            publish::<2>(
+                aura_signature(1, &second),
+                vec![up_for_grabs(first)],
+                vec![up_for_grabs(second)]
+            ),
+            Ok(0)
+        );
+        assert_eq!(active_key(Engine::Aura, aura_identity(1)), aura_identity(6));
+    });
+}
+
+#[test]
+fn replaying_first_registration_fails() {
+    TestExternalities::default().execute_with(|| {
+        let first = aura(1, aura_identity(5), 0);
+        let signature = aura_signature(1, &first);
+        publish::<1>(signature.clone(), vec![], vec![up_for_grabs(first.clone())]).unwrap();
+
+        assert_eq!(
+            publish::<2>(signature, vec![], vec![up_for_grabs(first)]),
+            Err(ConstraintCheckerError::WrongNumberInputs)
+        );
+    });
+}
+
+#[test]
+fn later_registration_with_wrong_nonce_fails() {
+    TestExternalities::default().execute_with(|| {
+        let first = aura(1, aura_identity(5), 0);
+        publish::<1>(
+            aura_signature(1, &first),
+            vec![],
+            vec![up_for_grabs(first.clone())],
+        )
+        .unwrap();
+        let second = aura(1, aura_identity(6), 0);
+
+        assert_eq!(
+            publish::<2>(
+                aura_signature(1, &second),
+                vec![up_for_grabs(first)],
+                vec![up_for_grabs(second)]
+            ),
+            Err(ConstraintCheckerError::WrongNonce)
+        );
+    });
+}
+
+#[test]
+fn consuming_stale_registration_fails() {
+    TestExternalities::default().execute_with(|| {
+        let first = aura(1, aura_identity(5), 0);
+        publish::<1>(
+            aura_signature(1, &first),
+            vec![],
+            vec![up_for_grabs(first.clone())],
+        )
+        .unwrap();
+        let second = aura(1, aura_identity(6), 1);
+
+        assert_eq!(
+            publish::<2>(
+                aura_signature(1, &second),
+                vec![up_for_grabs(aura(1, aura_identity(7), 0))],
+                vec![up_for_grabs(second)]
+            ),
+            Err(ConstraintCheckerError::InputMismatch)
+        );
+    });
+}
+
+#[test]
+fn consuming_something_else_fails() {
+    TestExternalities::default().execute_with(|| {
+        let first = aura(1, aura_identity(5), 0);
+        publish::<1>(aura_signature(1, &first), vec![], vec![up_for_grabs(first)]).unwrap();
+        let second = aura(1, aura_identity(6), 1);
+
+        assert_eq!(
+            publish::<2>(
+                aura_signature(1, &second),
+                vec![(Bogus, UpForGrabs.into()).into()],
+                vec![up_for_grabs(second)]
+            ),
+            Err(ConstraintCheckerError::BadlyTypedInput)
+        );
+    });
+}
+
+#[test]
+fn publishing_for_non_authority_fails() {
+    TestExternalities::default().execute_with(|| {
+        let new = aura(3, aura_identity(5), 0);
+
+        assert_eq!(
+            publish::<1>(aura_signature(3, &new), vec![], vec![up_for_grabs(new)]),
+            Err(ConstraintCheckerError::NotAnAuthority)
+        );
+    });
+}
+
+#[test]
+fn publishing_another_authoritys_key_fails() {
+    TestExternalities::default().execute_with(|| {
+        let new = aura(1, aura_identity(2), 0);
+
+        assert_eq!(
+            publish::<1>(aura_signature(1, &new), vec![], vec![up_for_grabs(new)]),
+            Err(ConstraintCheckerError::KeyInUse)
+        );
+    });
+}
+
+#[test]
+fn publishing_signed_by_someone_else_fails() {
+    TestExternalities::default().execute_with(|| {
+        let new = aura(1, aura_identity(5), 0);
+
+        assert_eq!(
+            publish::<1>(aura_signature(2, &new), vec![], vec![up_for_grabs(new)]),
+            Err(ConstraintCheckerError::BadSignature)
+        );
+    });
+}
+
+#[test]
+fn publishing_with_other_engines_signature_fails() {
+    TestExternalities::default().execute_with(|| {
+        let new = grandpa(1, grandpa_identity(5), 0);
+        let signature = AuthoritySignature::Sr25519(aura_pair(1).sign(&new.signing_message()));
+
+        assert_eq!(
+            publish::<1>(signature, vec![], vec![up_for_grabs(new)]),
+            Err(ConstraintCheckerError::BadSignature)
+        );
+    });
+}
+
+#[test]
+fn owned_registration_fails() {
+    TestExternalities::default().execute_with(|| {
+        let new = aura(1, aura_identity(5), 0);
+        let owned = (new.clone(), SigCheck::new(aura_identity(1)).into()).into();
+
+        assert_eq!(
+            publish::<1>(aura_signature(1, &new), vec![], vec![owned]),
+            Err(ConstraintCheckerError::RegistrationNotUpForGrabs)
+        );
+    });
+}
+
+#[test]
+fn grandpa_key_schedules_change() {
+    TestExternalities::default().execute_with(|| {
+        let new = grandpa(2, grandpa_identity(5), 0);
+
+        assert_eq!(
+            publish::<1>(grandpa_signature(2, &new), vec![], vec![up_for_grabs(new)]),
+            Ok(0)
+        );
+        assert_eq!(grandpa_key_changes(), 1);
+        assert_eq!(
+            digest::deposited_logs(),
+            vec![scheduled_change(vec![
+                grandpa_identity(1),
+                grandpa_identity(5)
+            ])]
+        );
+    });
+}
+
+#[test]
+fn second_grandpa_key_in_block_fails() {
+    TestExternalities::default().execute_with(|| {
+        let first = grandpa(1, grandpa_identity(5), 0);
+        publish::<1>(
+            grandpa_signature(1, &first),
+            vec![],
+            vec![up_for_grabs(first)],
+        )
+        .unwrap();
+        let second = grandpa(2, grandpa_identity(6), 0);
+
+        assert_eq!(
+            publish::<1>(
+                grandpa_signature(2, &second),
+                vec![],
+                vec![up_for_grabs(second.clone())]
+            ),
+            Err(ConstraintCheckerError::GrandpaChangeAlreadyScheduled)
+        );
+        assert_eq!(
+            publish::<2>(
+                grandpa_signature(2, &second),
+                vec![],
+                vec![up_for_grabs(second)]
+            ),
+            Ok(0)
+        );
+        assert_eq!(grandpa_key_changes(), 2);
+    });
+}
+
+/// A mock config at height one, in a block where another piece already scheduled a GRANDPA change.
+pub struct ChangeScheduled;
+
+impl SessionKeysConfig for ChangeScheduled {
+    fn block_height() -> u32 {
+        1
+    }
+
+    fn aura_authorities() -> Vec<H256> {
+        AtHeight::<1>::aura_authorities()
+    }
+
+    fn grandpa_authorities() -> Vec<(H256, AuthorityWeight)> {
+        AtHeight::<1>::grandpa_authorities()
+    }
+
+    fn grandpa_change_scheduled() -> bool {
+        true
+    }
+}
+
+#[test]
+fn grandpa_key_in_block_with_scheduled_change_fails() {
+    TestExternalities::default().execute_with(|| {
+        let new = grandpa(1, grandpa_identity(5), 0);
+
+        assert_eq!(
+            PublishSessionKey::<ChangeScheduled>::new(grandpa_signature(1, &new)).check(
+                &[],
+                &[],
+                &[up_for_grabs(new)],
+                &Default::default()
+            ),
+            Err(ConstraintCheckerError::GrandpaChangeAlreadyScheduled)
+        );
+        assert_eq!(grandpa_key_changes(), 0);
+    });
+}
+
+#[test]
+fn aura_key_in_block_with_scheduled_change_works() {
+    TestExternalities::default().execute_with(|| {
+        let new = aura(1, aura_identity(5), 0);
+
+        assert_eq!(
+            PublishSessionKey::<ChangeScheduled>::new(aura_signature(1, &new)).check(
+                &[],
+                &[],
+                &[up_for_grabs(new)],
+                &Default::default()
+            ),
+            Ok(0)
+        );
+    });
+}
@@ -0,0 +1,183 @@
+//! Social recovery of outputs whose owner has lost their key.
+//!
+//! An owner who wants to be able to recover their outputs locks them with the [`Recoverable`]
+//! verifier instead of a plain signature check. Besides the owner's key, it names some guardians,
+//! the number of them that must agree on a recovery, and a challenge delay.
+//!
+//! When the owner loses their key, they generate a new one and ask their guardians to sign a
+//! [`RecoveryCertificate`] naming the new key and the current block height. Once a threshold of
+//! guardians have signed and the challenge delay has passed, the new key can spend the outputs,
+//! typically re-keying them to an ordinary verifier.
+//!
+//! Verifiers can not read storage, so there is no on-chain record of a recovery in progress.
+//! Instead the owner objects to a recovery they did not ask for by spending their outputs with
+//! the original key before the delay is over. Moving them to a [`Recoverable`] with a new nonce
+//! invalidates every certificate the guardians have signed so far, while keeping the same
+//! guardians. Because the certificate's height is attested by the guardians themselves, honest
+//! guardians must only sign certificates for the current height and should tell the owner about
+//! them through every channel they have, so that the delay actually gives the owner a chance to
+//! object.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use parity_scale_codec::{Decode, Encode};
+use scale_info::TypeInfo;
+use serde::{Deserialize, Serialize};
+use sp_core::{
+    sr25519::{Public, Signature},
+    H256,
+};
+use sp_std::{collections::btree_set::BTreeSet, prelude::*};
+use tuxedo_core::{
+    verifier::{SignatureAndIndex, VerifierContext},
+    weights::{Weight, SIGNATURE_WEIGHT},
+    Verifier,
+};
+
+#[cfg(test)]
+mod tests;
+
+/// A verifier for outputs that can be spent by the owner, or re-keyed by a threshold of guardians
+/// after a challenge delay.
+#[derive(Serialize, Deserialize, Encode, Decode, Debug, PartialEq, Eq, Clone, TypeInfo)]
+pub struct Recoverable {
+    /// The owner's sr25519 public key
+    pub owner: H256,
+    /// The guardians' sr25519 public keys. This should include no duplicates
+    pub guardians: Vec<H256>,
+    /// The minimum number of guardians that must sign a recovery certificate.
+    /// A threshold of zero disables recovery.
+    pub threshold: u8,
+    /// The number of blocks that must pass between the height in a certificate and the recovery
+    pub delay: u32,
+    /// Changing the nonce invalidates all certificates signed for this verifier so far
+    pub nonce: u32,
+}
+
+impl Recoverable {
+    pub fn new(owner: H256, guardians: Vec<H256>, threshold: u8, delay: u32) -> Self {
+        Recoverable {
+            owner,
+            guardians,
+            threshold,
+            delay,
+            nonce: 0,
+        }
+    }
+
+    pub fn has_duplicate_guardians(&self) -> bool {
+        let set: BTreeSet<_> = self.guardians.iter().collect();
+        set.len() < self.guardians.len()
+    }
+
+    /// The message that guardians sign to approve a recovery of outputs locked by this verifier.
+    pub fn certificate_message(&self, certificate: &RecoveryCertificate) -> Vec<u8> {
+        (b"social_recovery", self, certificate).encode()
+    }
+
+    /// Whether the guardian signatures approve the certificate. Each guardian counts once.
+    fn is_approved(
+        &self,
+        certificate: &RecoveryCertificate,
+        guardian_signatures: &[SignatureAndIndex],
+    ) -> bool {
+        if self.threshold == 0 || self.has_duplicate_guardians() {
+            return false;
+        }
+
+        let indices: BTreeSet<u8> = guardian_signatures.iter().map(|sig| sig.index).collect();
+        if indices.len() < guardian_signatures.len()
+            || guardian_signatures.len() < self.threshold.into()
+        {
+            return false;
+        }
+
+        let message = self.certificate_message(certificate);
+        guardian_signatures.iter().all(|sig| {
+            self.guardians
+                .get(sig.index as usize)
+                .is_some_and(|guardian| {
+                    sp_io::crypto::sr25519_verify(
+                        &sig.signature,
+                        &message,
+                        &Public::from_h256(*guardian),
+                    )
+                })
+        })
+    }
+}
+
+/// The guardians' approval to hand control of the outputs to a new key.
+#[derive(Serialize, Deserialize, Encode, Decode, Debug, PartialEq, Eq, Clone, TypeInfo)]
+pub struct RecoveryCertificate {
+    /// The sr25519 public key that takes over the outputs
+    pub new_key: H256,
+    /// The block height at which the guardians signed the certificate.
+    /// The challenge delay starts here.
+    pub initiated_at: u32,
+}
+
+/// The redeemer for a [`Recoverable`] verifier.
+#[derive(Serialize, Deserialize, Encode, Decode, Debug, PartialEq, Eq, Clone, TypeInfo)]
+pub enum RecoverableRedeemer {
+    /// The owner's signature over the transaction.
+    Owner(Signature),
+    /// A recovery approved by the guardians, along with the new key's signature over the
+    /// transaction.
+    Recovery {
+        certificate: RecoveryCertificate,
+        guardian_signatures: Vec<SignatureAndIndex>,
+        signature: Signature,
+    },
+}
+
+impl Verifier for Recoverable {
+    fn verify(&self, simplified_tx: &[u8], redeemer: &[u8], context: &VerifierContext) -> bool {
+        let redeemer = match RecoverableRedeemer::decode(&mut &redeemer[..]) {
+            Ok(r) => r,
+            Err(_) => return false,
+        };
+
+        match redeemer {
+            RecoverableRedeemer::Owner(signature) => sp_io::crypto::sr25519_verify(
+                &signature,
+                simplified_tx,
+                &Public::from_h256(self.owner),
+            ),
+            RecoverableRedeemer::Recovery {
+                certificate,
+                guardian_signatures,
+                signature,
+            } => {
+                context.block_height >= certificate.initiated_at.saturating_add(self.delay)
+                    && self.is_approved(&certificate, &guardian_signatures)
+                    && sp_io::crypto::sr25519_verify(
+                        &signature,
+                        simplified_tx,
+                        &Public::from_h256(certificate.new_key),
+                    )
+            }
+        }
+    }
+
+    fn weight(&self) -> Weight {
+        SIGNATURE_WEIGHT.saturating_mul(self.guardians.len() as Weight + 1)
+    }
+}
+
+// Partially signed transactions only collect the owner's signature. Recoveries need a
+// certificate from the guardians, which the new key's wallet assembles on its own.
+#[cfg(feature = "std")]
+impl tuxedo_core::pstt::SignatureRedeemer for Recoverable {
+    fn signers(&self) -> Vec<H256> {
+        vec![self.owner]
+    }
+
+    fn assemble_redeemer(
+        &self,
+        signatures: &std::collections::BTreeMap<H256, Vec<u8>>,
+    ) -> Option<Vec<u8>> {
+        let signature = Signature::try_from(&signatures.get(&self.owner)?[..]).ok()?;
+        Some(RecoverableRedeemer::Owner(signature).encode())
+    }
+}
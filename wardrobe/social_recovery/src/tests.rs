@@ -0,0 +1,184 @@
+//! Unit tests for the Social Recovery piece
+
+use super::*;
+use sp_core::{crypto::Pair as _, sr25519::Pair};
+
+fn pair(seed: u8) -> Pair {
+    Pair::from_seed(&[seed; 32])
+}
+
+fn key(seed: u8) -> H256 {
+    pair(seed).public().into()
+}
+
+/// Owned by key 1, with guardians 2, 3, and 4, two of whom must agree. The delay is ten blocks.
+fn recoverable() -> Recoverable {
+    Recoverable::new(key(1), vec![key(2), key(3), key(4)], 2, 10)
+}
+
+/// A certificate handing the outputs to key 5, signed at block 20.
+fn certificate() -> RecoveryCertificate {
+    RecoveryCertificate {
+        new_key: key(5),
+        initiated_at: 20,
+    }
+}
+
+fn at_height(block_height: u32) -> VerifierContext {
+    VerifierContext {
+        block_height,
+        ..Default::default()
+    }
+}
+
+/// Signatures by the guardians at the given indices over the certificate for the verifier.
+fn guardian_signatures(
+    verifier: &Recoverable,
+    certificate: &RecoveryCertificate,
+    indices: &[u8],
+) -> Vec<SignatureAndIndex> {
+    let message = verifier.certificate_message(certificate);
+    indices
+        .iter()
+        .map(|&index| SignatureAndIndex {
+            signature: pair(index + 2).sign(&message),
+            index,
+        })
+        .collect()
+}
+
+fn recovery_redeemer(simplified_tx: &[u8], guardian_signatures: Vec<SignatureAndIndex>) -> Vec<u8> {
+    RecoverableRedeemer::Recovery {
+        certificate: certificate(),
+        guardian_signatures,
+        signature: pair(5).sign(simplified_tx),
+    }
+    .encode()
+}
+
+#[test]
+fn owner_spend_works() {
+    let simplified_tx = b"hello world".as_slice();
+    let redeemer = RecoverableRedeemer::Owner(pair(1).sign(simplified_tx)).encode();
+
+    assert!(recoverable().verify(simplified_tx, &redeemer, &at_height(0)));
+}
+
+#[test]
+fn owner_spend_with_wrong_key_fails() {
+    let simplified_tx = b"hello world".as_slice();
+    let redeemer = RecoverableRedeemer::Owner(pair(5).sign(simplified_tx)).encode();
+
+    assert!(!recoverable().verify(simplified_tx, &redeemer, &at_height(0)));
+}
+
+#[test]
+fn recovery_after_delay_works() {
+    let simplified_tx = b"hello world".as_slice();
+    let verifier = recoverable();
+    let signatures = guardian_signatures(&verifier, &certificate(), &[0, 2]);
+    let redeemer = recovery_redeemer(simplified_tx, signatures);
+
+    assert!(verifier.verify(simplified_tx, &redeemer, &at_height(30)));
+}
+
+#[test]
+fn recovery_before_delay_fails() {
+    let simplified_tx = b"hello world".as_slice();
+    let verifier = recoverable();
+    let signatures = guardian_signatures(&verifier, &certificate(), &[0, 2]);
+    let redeemer = recovery_redeemer(simplified_tx, signatures);
+
+    assert!(!verifier.verify(simplified_tx, &redeemer, &at_height(29)));
+}
+
+#[test]
+fn recovery_below_threshold_fails() {
+    let simplified_tx = b"hello world".as_slice();
+    let verifier = recoverable();
+    let signatures = guardian_signatures(&verifier, &certificate(), &[1]);
+    let redeemer = recovery_redeemer(simplified_tx, signatures);
+
+    assert!(!verifier.verify(simplified_tx, &redeemer, &at_height(30)));
+}
+
+#[test]
+fn recovery_counting_guardian_twice_fails() {
+    let simplified_tx = b"hello world".as_slice();
+    let verifier = recoverable();
+    let signatures = guardian_signatures(&verifier, &certificate(), &[1, 1]);
+    let redeemer = recovery_redeemer(simplified_tx, signatures);
+
+    assert!(!verifier.verify(simplified_tx, &redeemer, &at_height(30)));
+}
+
+#[test]
+fn recovery_with_guardian_out_of_range_fails() {
+    let simplified_tx = b"hello world".as_slice();
+    let verifier = recoverable();
+    let signatures = guardian_signatures(&verifier, &certificate(), &[0, 3]);
+    let redeemer = recovery_redeemer(simplified_tx, signatures);
+
+    assert!(!verifier.verify(simplified_tx, &redeemer, &at_height(30)));
+}
+
+#[test]
+fn recovery_signed_by_other_new_key_fails() {
+    let simplified_tx = b"hello world".as_slice();
+    let verifier = recoverable();
+    let redeemer = RecoverableRedeemer::Recovery {
+        certificate: certificate(),
+        guardian_signatures: guardian_signatures(&verifier, &certificate(), &[0, 1]),
+        signature: pair(6).sign(simplified_tx),
+    }
+    .encode();
+
+    assert!(!verifier.verify(simplified_tx, &redeemer, &at_height(30)));
+}
+
+#[test]
+fn certificate_for_old_nonce_fails() {
+    let simplified_tx = b"hello world".as_slice();
+    let signatures = guardian_signatures(&recoverable(), &certificate(), &[0, 1]);
+    let redeemer = recovery_redeemer(simplified_tx, signatures);
+    let objected = Recoverable {
+        nonce: 1,
+        ..recoverable()
+    };
+
+    assert!(!objected.verify(simplified_tx, &redeemer, &at_height(30)));
+}
+
+#[test]
+fn recovery_disabled_with_zero_threshold() {
+    let simplified_tx = b"hello world".as_slice();
+    let verifier = Recoverable::new(key(1), vec![key(2)], 0, 10);
+    let redeemer = recovery_redeemer(simplified_tx, Vec::new());
+
+    assert!(!verifier.verify(simplified_tx, &redeemer, &at_height(30)));
+}
+
+#[test]
+fn recovery_with_duplicate_guardians_fails() {
+    let simplified_tx = b"hello world".as_slice();
+    let verifier = Recoverable::new(key(1), vec![key(2), key(2)], 2, 10);
+    let message = verifier.certificate_message(&certificate());
+    let signatures = vec![
+        SignatureAndIndex {
+            signature: pair(2).sign(&message),
+            index: 0,
+        },
+        SignatureAndIndex {
+            signature: pair(2).sign(&message),
+            index: 1,
+        },
+    ];
+    let redeemer = recovery_redeemer(simplified_tx, signatures);
+
+    assert!(!verifier.verify(simplified_tx, &redeemer, &at_height(30)));
+}
+
+#[test]
+fn bogus_redeemer_fails() {
+    assert!(!recoverable().verify(b"hello world", b"bogus", &at_height(30)));
+}
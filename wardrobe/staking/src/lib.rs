@@ -0,0 +1,689 @@
+//! Bond `Coin<ID>`s to a validator and earn staking rewards.
+//!
+//! Users bond their coins into [`Stake`] UTXOs that name the validator they are backing. Stakes
+//! earn rewards for every block they remain bonded. To stop staking, a stake is unbonded into an
+//! [`Unbonding`] UTXO which only becomes withdrawable as regular coins after the configured
+//! unbonding period has passed.
+//!
+//! Rewards are tracked with a global [`RewardIndex`] which the block author advances once per
+//! block via an inherent, similar to the timestamp piece. The index accumulates the reward earned
+//! by every [`REWARD_PRECISION`] bonded tokens since genesis. Each stake remembers the index at
+//! which it last claimed, so the reward it is owed is its amount multiplied by the growth of the
+//! index since then. Claiming mints the reward as new coins and resets the stake's index.
+//!
+//! Like the timestamp piece, reward indices are peeked rather than consumed so that transactions
+//! referring to them are not invalidated every block. Old indices can be cleaned up later.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use core::marker::PhantomData;
+
+use money::Coin;
+use parity_scale_codec::{Decode, Encode};
+use scale_info::TypeInfo;
+use serde::{Deserialize, Serialize};
+use sp_core::{H256, U256};
+use sp_inherents::{CheckInherentsResult, InherentData, MakeFatalError};
+use sp_runtime::transaction_validity::TransactionPriority;
+use sp_std::{vec, vec::Vec};
+use tuxedo_core::{
+    dynamic_typing::{DynamicallyTypedData, UtxoData},
+    ensure,
+    inherents::{TuxedoInherent, TuxedoInherentAdapter},
+    support_macros::{CloneNoBound, DebugNoBound, DefaultNoBound},
+    types::{Output, OutputRef, Transaction, TransactionVersion},
+    verifier::UpForGrabs,
+    CheckerContext, ConstraintChecker, SimpleConstraintChecker, Verifier,
+};
+
+#[cfg(test)]
+mod tests;
+
+/// The number of bonded tokens that earn [`StakingConfig::REWARD_RATE`] each block.
+///
+/// Rewards are expressed per this many tokens so that the rate can be a fraction of a token.
+pub const REWARD_PRECISION: u128 = 1_000_000_000;
+
+/// Configuration items for the staking piece when it is
+/// instantiated in a concrete runtime.
+pub trait StakingConfig {
+    /// A means of getting the current block height.
+    /// Probably this will be the Tuxedo Executive
+    fn block_height() -> u32;
+
+    /// The reward earned each block by every [`REWARD_PRECISION`] bonded tokens.
+    const REWARD_RATE: u128;
+
+    /// The number of blocks an unbonding stake must wait before it can be withdrawn.
+    ///
+    /// Default is roughly one week with 6 second block times.
+    const UNBONDING_PERIOD: u32 = 100_800;
+
+    /// The minimum number of blocks that must have passed before an old reward index
+    /// may be cleaned up.
+    ///
+    /// Default is roughly one day with 6 second block times.
+    const MIN_BLOCKS_BEFORE_CLEANUP: u32 = 15_000;
+}
+
+/// Some `Coin<ID>` value bonded to a validator.
+#[derive(
+    Serialize,
+    Deserialize,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Clone,
+    Encode,
+    Decode,
+    Hash,
+    Debug,
+    TypeInfo,
+)]
+pub struct Stake<const ID: u8> {
+    /// The bonded value.
+    pub amount: u128,
+    /// The key of the validator this stake backs.
+    pub validator: H256,
+    /// The cumulative reward index as of when this stake last claimed its rewards.
+    pub reward_index: u128,
+}
+
+impl<const ID: u8> Stake<ID> {
+    /// The reward this stake has earned since it last claimed, given the current index.
+    pub fn pending_reward(&self, index: &RewardIndex) -> Result<u128, ConstraintCheckerError> {
+        let growth = index
+            .cumulative
+            .checked_sub(self.reward_index)
+            .ok_or(ConstraintCheckerError::StaleRewardIndex)?;
+        (U256::from(self.amount) * U256::from(growth) / U256::from(REWARD_PRECISION))
+            .try_into()
+            .map_err(|_| ConstraintCheckerError::ValueOverflow)
+    }
+}
+
+impl<const ID: u8> UtxoData for Stake<ID> {
+    const TYPE_ID: [u8; 4] = [b's', b't', b'k', ID];
+}
+
+/// Some `Coin<ID>` value that has been unbonded and is waiting out the unbonding period.
+#[derive(
+    Serialize,
+    Deserialize,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Clone,
+    Encode,
+    Decode,
+    Hash,
+    Debug,
+    TypeInfo,
+)]
+pub struct Unbonding<const ID: u8> {
+    /// The unbonded value.
+    pub amount: u128,
+    /// The block height from which the value may be withdrawn.
+    pub unlocks_at: u32,
+}
+
+impl<const ID: u8> UtxoData for Unbonding<ID> {
+    const TYPE_ID: [u8; 4] = [b'u', b'n', b'b', ID];
+}
+
+/// The cumulative staking reward, noted at some block in the history of the chain.
+#[derive(
+    Serialize,
+    Deserialize,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Clone,
+    Copy,
+    Default,
+    Encode,
+    Decode,
+    Hash,
+    Debug,
+    TypeInfo,
+)]
+pub struct RewardIndex {
+    /// The block number in which this index was noted.
+    pub block: u32,
+    /// The total reward earned by every [`REWARD_PRECISION`] tokens bonded since genesis.
+    pub cumulative: u128,
+}
+
+impl UtxoData for RewardIndex {
+    const TYPE_ID: [u8; 4] = *b"rwdx";
+}
+
+/// Errors that can occur when checking staking transactions.
+#[derive(
+    Serialize,
+    Deserialize,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Clone,
+    Encode,
+    Decode,
+    Hash,
+    Debug,
+    TypeInfo,
+)]
+pub enum ConstraintCheckerError {
+    /// An input data has the wrong type.
+    BadlyTypedInput,
+    /// An output data has the wrong type.
+    BadlyTypedOutput,
+    /// A peeked data has the wrong type.
+    BadlyTypedPeek,
+    /// No reward index was peeked at, but one is required.
+    MissingRewardIndex,
+    /// The transaction does not bond anything.
+    NothingBonded,
+    /// A stake, unbonding, or coin with zero value was created. This is not allowed because it wastes state space.
+    ZeroValue,
+    /// The value consumed or created by this transaction overflows the value type.
+    ValueOverflow,
+    /// More value was created than was consumed.
+    OutputsExceedInputs,
+    /// The peeked reward index is older than the one the transaction must be based on.
+    StaleRewardIndex,
+    /// A new stake does not start from the peeked reward index.
+    WrongRewardIndex,
+    /// A stake that claimed rewards was not re-created with only its reward index updated.
+    StakeNotPreserved,
+    /// An unbonding output unlocks before the unbonding period has passed.
+    UnbondingTooShort,
+    /// The unbonding period has not passed yet, so the value cannot be withdrawn.
+    StillUnbonding,
+    /// Inputs were specified while distributing rewards, but none are allowed.
+    InputsWhileDistributingRewards,
+    /// Distributing rewards must create exactly one new reward index.
+    WrongNumberOutputs,
+    /// The block height reported in the new reward index does not match the block into which it was inserted.
+    NewIndexWrongHeight,
+    /// The previous reward index that is peeked at must be from the immediate ancestor block, but this one is not.
+    PreviousIndexWrongHeight,
+    /// The new reward index does not grow by exactly the reward rate.
+    WrongRewardAmount,
+    /// When cleaning up old reward indices, you may not create any new state at all.
+    CleanupCannotCreateState,
+    /// You may not clean up old reward indices until they are old enough.
+    DontBeSoHasty,
+}
+
+/// Extract the reward index that a transaction is based on, which is always the first peek.
+fn reward_index(peeks: &[DynamicallyTypedData]) -> Result<RewardIndex, ConstraintCheckerError> {
+    peeks
+        .first()
+        .ok_or(ConstraintCheckerError::MissingRewardIndex)?
+        .extract::<RewardIndex>()
+        .map_err(|_| ConstraintCheckerError::BadlyTypedPeek)
+}
+
+/// Add a value to a running total, making sure it is non-zero and does not overflow.
+fn accumulate(total: u128, value: u128) -> Result<u128, ConstraintCheckerError> {
+    ensure!(value > 0, ConstraintCheckerError::ZeroValue);
+    total
+        .checked_add(value)
+        .ok_or(ConstraintCheckerError::ValueOverflow)
+}
+
+/// A constraint checker that bonds `Coin<ID>`s into new stakes.
+///
+/// All inputs are `Coin<ID>`s. Outputs are stakes, and optionally `Coin<ID>` change. The first
+/// peek is the reward index from the current or previous block, and new stakes start from it
+/// so that they do not earn rewards for blocks before they were bonded.
+/// Coins that are neither bonded nor returned as change pay the transaction's fee and tip.
+#[derive(
+    Serialize,
+    Deserialize,
+    Encode,
+    Decode,
+    DebugNoBound,
+    DefaultNoBound,
+    PartialEq,
+    Eq,
+    CloneNoBound,
+    TypeInfo,
+)]
+#[scale_info(skip_type_params(T))]
+pub struct Bond<T, const ID: u8>(PhantomData<T>);
+
+impl<T: StakingConfig, const ID: u8> SimpleConstraintChecker for Bond<T, ID> {
+    type Error = ConstraintCheckerError;
+
+    fn check(
+        &self,
+        input_data: &[DynamicallyTypedData],
+        peek_data: &[DynamicallyTypedData],
+        output_data: &[DynamicallyTypedData],
+        _context: &CheckerContext,
+    ) -> Result<TransactionPriority, Self::Error> {
+        let index = reward_index(peek_data)?;
+        ensure!(
+            index.block.saturating_add(1) >= T::block_height(),
+            ConstraintCheckerError::StaleRewardIndex
+        );
+
+        let mut total_input: u128 = 0;
+        for input in input_data {
+            let coin = input
+                .extract::<Coin<ID>>()
+                .map_err(|_| ConstraintCheckerError::BadlyTypedInput)?;
+            total_input = accumulate(total_input, coin.0)?;
+        }
+
+        let mut bonded: u128 = 0;
+        let mut total_output: u128 = 0;
+        for output in output_data {
+            if let Ok(stake) = output.extract::<Stake<ID>>() {
+                ensure!(
+                    stake.reward_index == index.cumulative,
+                    ConstraintCheckerError::WrongRewardIndex
+                );
+                bonded = accumulate(bonded, stake.amount)?;
+                total_output = accumulate(total_output, stake.amount)?;
+            } else {
+                let coin = output
+                    .extract::<Coin<ID>>()
+                    .map_err(|_| ConstraintCheckerError::BadlyTypedOutput)?;
+                total_output = accumulate(total_output, coin.0)?;
+            }
+        }
+        ensure!(bonded > 0, ConstraintCheckerError::NothingBonded);
+        ensure!(
+            total_output <= total_input,
+            ConstraintCheckerError::OutputsExceedInputs
+        );
+
+        let burned = total_input - total_output;
+        Ok(burned.try_into().unwrap_or(TransactionPriority::MAX))
+    }
+}
+
+/// A constraint checker that claims the rewards earned by some stakes.
+///
+/// All inputs are stakes. The first outputs re-create each input stake, in the same order, with
+/// its reward index updated to the peeked reward index. The remaining outputs are `Coin<ID>`s
+/// minted as rewards, and their total may not exceed the rewards earned.
+#[derive(
+    Serialize,
+    Deserialize,
+    Encode,
+    Decode,
+    DebugNoBound,
+    DefaultNoBound,
+    PartialEq,
+    Eq,
+    CloneNoBound,
+    TypeInfo,
+)]
+pub struct ClaimRewards<const ID: u8>;
+
+impl<const ID: u8> SimpleConstraintChecker for ClaimRewards<ID> {
+    type Error = ConstraintCheckerError;
+
+    fn check(
+        &self,
+        input_data: &[DynamicallyTypedData],
+        peek_data: &[DynamicallyTypedData],
+        output_data: &[DynamicallyTypedData],
+        _context: &CheckerContext,
+    ) -> Result<TransactionPriority, Self::Error> {
+        let index = reward_index(peek_data)?;
+        ensure!(
+            output_data.len() >= input_data.len(),
+            ConstraintCheckerError::StakeNotPreserved
+        );
+        let (restaked, payouts) = output_data.split_at(input_data.len());
+
+        let mut earned: u128 = 0;
+        for (input, output) in input_data.iter().zip(restaked) {
+            let stake = input
+                .extract::<Stake<ID>>()
+                .map_err(|_| ConstraintCheckerError::BadlyTypedInput)?;
+            earned = earned
+                .checked_add(stake.pending_reward(&index)?)
+                .ok_or(ConstraintCheckerError::ValueOverflow)?;
+
+            let expected = Stake::<ID> {
+                reward_index: index.cumulative,
+                ..stake
+            };
+            ensure!(
+                output.extract::<Stake<ID>>() == Ok(expected),
+                ConstraintCheckerError::StakeNotPreserved
+            );
+        }
+
+        let mut paid: u128 = 0;
+        for payout in payouts {
+            let coin = payout
+                .extract::<Coin<ID>>()
+                .map_err(|_| ConstraintCheckerError::BadlyTypedOutput)?;
+            paid = accumulate(paid, coin.0)?;
+        }
+        ensure!(paid <= earned, ConstraintCheckerError::OutputsExceedInputs);
+
+        Ok(0)
+    }
+}
+
+/// A constraint checker that unbonds some stakes so they can be withdrawn later.
+///
+/// All inputs are stakes, and all outputs are unbondings that unlock no sooner than the
+/// unbonding period from now. Any rewards the stakes have not claimed are forfeited, so they
+/// should be claimed first. Any stake that is not carried into an unbonding pays the
+/// transaction's fee and tip.
+#[derive(
+    Serialize,
+    Deserialize,
+    Encode,
+    Decode,
+    DebugNoBound,
+    DefaultNoBound,
+    PartialEq,
+    Eq,
+    CloneNoBound,
+    TypeInfo,
+)]
+#[scale_info(skip_type_params(T))]
+pub struct Unbond<T, const ID: u8>(PhantomData<T>);
+
+impl<T: StakingConfig, const ID: u8> SimpleConstraintChecker for Unbond<T, ID> {
+    type Error = ConstraintCheckerError;
+
+    fn check(
+        &self,
+        input_data: &[DynamicallyTypedData],
+        _peeks: &[DynamicallyTypedData],
+        output_data: &[DynamicallyTypedData],
+        _context: &CheckerContext,
+    ) -> Result<TransactionPriority, Self::Error> {
+        let mut total_input: u128 = 0;
+        for input in input_data {
+            let stake = input
+                .extract::<Stake<ID>>()
+                .map_err(|_| ConstraintCheckerError::BadlyTypedInput)?;
+            total_input = accumulate(total_input, stake.amount)?;
+        }
+
+        let earliest_unlock = T::block_height().saturating_add(T::UNBONDING_PERIOD);
+        let mut total_output: u128 = 0;
+        for output in output_data {
+            let unbonding = output
+                .extract::<Unbonding<ID>>()
+                .map_err(|_| ConstraintCheckerError::BadlyTypedOutput)?;
+            ensure!(
+                unbonding.unlocks_at >= earliest_unlock,
+                ConstraintCheckerError::UnbondingTooShort
+            );
+            total_output = accumulate(total_output, unbonding.amount)?;
+        }
+        ensure!(
+            total_output <= total_input,
+            ConstraintCheckerError::OutputsExceedInputs
+        );
+
+        let burned = total_input - total_output;
+        Ok(burned.try_into().unwrap_or(TransactionPriority::MAX))
+    }
+}
+
+/// A constraint checker that withdraws unbonded value once the unbonding period has passed.
+///
+/// All inputs are unbondings that have unlocked, and all outputs are `Coin<ID>`s.
+/// Whatever is not withdrawn into coins pays the transaction's fee and tip.
+#[derive(
+    Serialize,
+    Deserialize,
+    Encode,
+    Decode,
+    DebugNoBound,
+    DefaultNoBound,
+    PartialEq,
+    Eq,
+    CloneNoBound,
+    TypeInfo,
+)]
+#[scale_info(skip_type_params(T))]
+pub struct Withdraw<T, const ID: u8>(PhantomData<T>);
+
+impl<T: StakingConfig, const ID: u8> SimpleConstraintChecker for Withdraw<T, ID> {
+    type Error = ConstraintCheckerError;
+
+    fn check(
+        &self,
+        input_data: &[DynamicallyTypedData],
+        _peeks: &[DynamicallyTypedData],
+        output_data: &[DynamicallyTypedData],
+        _context: &CheckerContext,
+    ) -> Result<TransactionPriority, Self::Error> {
+        let mut total_input: u128 = 0;
+        for input in input_data {
+            let unbonding = input
+                .extract::<Unbonding<ID>>()
+                .map_err(|_| ConstraintCheckerError::BadlyTypedInput)?;
+            ensure!(
+                T::block_height() >= unbonding.unlocks_at,
+                ConstraintCheckerError::StillUnbonding
+            );
+            total_input = accumulate(total_input, unbonding.amount)?;
+        }
+
+        let mut total_output: u128 = 0;
+        for output in output_data {
+            let coin = output
+                .extract::<Coin<ID>>()
+                .map_err(|_| ConstraintCheckerError::BadlyTypedOutput)?;
+            total_output = accumulate(total_output, coin.0)?;
+        }
+        ensure!(
+            total_output <= total_input,
+            ConstraintCheckerError::OutputsExceedInputs
+        );
+
+        let burned = total_input - total_output;
+        Ok(burned.try_into().unwrap_or(TransactionPriority::MAX))
+    }
+}
+
+/// A constraint checker for advancing the reward index by one block's worth of rewards.
+///
+/// This is expected to be performed through an inherent, and to happen exactly once per block.
+///
+/// This transaction peeks at the reward index created in the previous block and creates a
+/// single new one for the current block whose cumulative reward has grown by the reward rate.
+#[derive(
+    Serialize,
+    Deserialize,
+    Encode,
+    Decode,
+    DebugNoBound,
+    DefaultNoBound,
+    PartialEq,
+    Eq,
+    CloneNoBound,
+    TypeInfo,
+)]
+#[scale_info(skip_type_params(T))]
+pub struct DistributeRewards<T>(PhantomData<T>);
+
+impl<T: StakingConfig + 'static, V: Verifier + From<UpForGrabs>> ConstraintChecker<V>
+    for DistributeRewards<T>
+{
+    type Error = ConstraintCheckerError;
+    type InherentHooks = TuxedoInherentAdapter<Self>;
+
+    fn check(
+        &self,
+        input_data: &[Output<V>],
+        peek_data: &[Output<V>],
+        output_data: &[Output<V>],
+        _context: &CheckerContext,
+    ) -> Result<TransactionPriority, Self::Error> {
+        // Distributing rewards does not consume anything.
+        ensure!(
+            input_data.is_empty(),
+            ConstraintCheckerError::InputsWhileDistributingRewards
+        );
+
+        ensure!(
+            output_data.len() == 1,
+            ConstraintCheckerError::WrongNumberOutputs
+        );
+        let new_index = output_data[0]
+            .payload
+            .extract::<RewardIndex>()
+            .map_err(|_| ConstraintCheckerError::BadlyTypedOutput)?;
+        ensure!(
+            new_index.block == T::block_height(),
+            ConstraintCheckerError::NewIndexWrongHeight
+        );
+
+        let old_index = peek_data
+            .first()
+            .ok_or(ConstraintCheckerError::MissingRewardIndex)?
+            .payload
+            .extract::<RewardIndex>()
+            .map_err(|_| ConstraintCheckerError::BadlyTypedPeek)?;
+        ensure!(
+            new_index.block == old_index.block + 1,
+            ConstraintCheckerError::PreviousIndexWrongHeight
+        );
+        ensure!(
+            old_index.cumulative.checked_add(T::REWARD_RATE) == Some(new_index.cumulative),
+            ConstraintCheckerError::WrongRewardAmount
+        );
+
+        Ok(0)
+    }
+
+    fn is_inherent(&self) -> bool {
+        true
+    }
+}
+
+impl<V: Verifier + From<UpForGrabs>, T: StakingConfig + 'static> TuxedoInherent<V, Self>
+    for DistributeRewards<T>
+{
+    type Error = MakeFatalError<()>;
+    const INHERENT_IDENTIFIER: sp_inherents::InherentIdentifier = *b"stkrewrd";
+
+    fn create_inherent(
+        _authoring_inherent_data: &InherentData,
+        previous_inherent: (Transaction<V, Self>, H256),
+    ) -> Transaction<V, Self> {
+        // There is always exactly 1 output, so we know right where to find it.
+        let old_index = previous_inherent.0.outputs[0]
+            .payload
+            .extract::<RewardIndex>()
+            .expect(
+                "DistributeRewards extrinsic should have an output that decodes as a RewardIndex.",
+            );
+        let old_output = OutputRef {
+            tx_hash: previous_inherent.1,
+            index: 0,
+        };
+
+        let new_index = RewardIndex {
+            block: T::block_height(),
+            cumulative: old_index.cumulative.saturating_add(T::REWARD_RATE),
+        };
+        let new_output = Output {
+            payload: new_index.into(),
+            verifier: UpForGrabs.into(),
+        };
+
+        Transaction {
+            version: TransactionVersion::LATEST,
+            inputs: Vec::new(),
+            peeks: vec![old_output],
+            outputs: vec![new_output],
+            checker: Self::default(),
+            memo: None,
+            valid_until: None,
+        }
+    }
+
+    fn check_inherent(
+        _importing_inherent_data: &InherentData,
+        _inherent: Transaction<V, Self>,
+        _result: &mut CheckInherentsResult,
+    ) {
+        // The reward index depends only on on-chain data, so there is nothing to check
+        // off-chain. The constraint checker makes all the necessary checks during execution.
+    }
+
+    #[cfg(feature = "std")]
+    fn genesis_transactions() -> Vec<Transaction<V, Self>> {
+        vec![Transaction {
+            version: TransactionVersion::LATEST,
+            inputs: Vec::new(),
+            peeks: Vec::new(),
+            outputs: vec![Output {
+                payload: RewardIndex::default().into(),
+                verifier: UpForGrabs.into(),
+            }],
+            checker: Self::default(),
+            memo: None,
+            valid_until: None,
+        }]
+    }
+}
+
+/// Allows users to voluntarily clean up old reward indices once they are old enough
+/// that no reasonable transaction would still want to peek at them.
+///
+/// All inputs are reward indices, and there are no outputs.
+#[derive(
+    Serialize,
+    Deserialize,
+    Encode,
+    Decode,
+    DebugNoBound,
+    DefaultNoBound,
+    PartialEq,
+    Eq,
+    CloneNoBound,
+    TypeInfo,
+)]
+#[scale_info(skip_type_params(T))]
+pub struct CleanUpRewardIndex<T>(PhantomData<T>);
+
+impl<T: StakingConfig> SimpleConstraintChecker for CleanUpRewardIndex<T> {
+    type Error = ConstraintCheckerError;
+
+    fn check(
+        &self,
+        input_data: &[DynamicallyTypedData],
+        _peeks: &[DynamicallyTypedData],
+        output_data: &[DynamicallyTypedData],
+        _context: &CheckerContext,
+    ) -> Result<TransactionPriority, Self::Error> {
+        ensure!(
+            output_data.is_empty(),
+            ConstraintCheckerError::CleanupCannotCreateState
+        );
+
+        for input in input_data {
+            let old_index = input
+                .extract::<RewardIndex>()
+                .map_err(|_| ConstraintCheckerError::BadlyTypedInput)?;
+            ensure!(
+                old_index.block.saturating_add(T::MIN_BLOCKS_BEFORE_CLEANUP) < T::block_height(),
+                ConstraintCheckerError::DontBeSoHasty
+            );
+        }
+
+        Ok(0)
+    }
+}
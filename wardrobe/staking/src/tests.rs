@@ -0,0 +1,421 @@
+//! Unit tests for the Staking piece
+
+use super::*;
+use tuxedo_core::dynamic_typing::testing::Bogus;
+use ConstraintCheckerError::*;
+
+/// The mock config always says the block number is ten.
+pub struct AlwaysBlockTen;
+
+impl StakingConfig for AlwaysBlockTen {
+    fn block_height() -> u32 {
+        10
+    }
+
+    const REWARD_RATE: u128 = REWARD_PRECISION / 100;
+    const UNBONDING_PERIOD: u32 = 5;
+    const MIN_BLOCKS_BEFORE_CLEANUP: u32 = 3;
+}
+
+const VALIDATOR: H256 = H256::repeat_byte(7);
+
+fn stake(amount: u128, reward_index: u128) -> DynamicallyTypedData {
+    Stake::<0> {
+        amount,
+        validator: VALIDATOR,
+        reward_index,
+    }
+    .into()
+}
+
+fn index(block: u32, cumulative: u128) -> DynamicallyTypedData {
+    RewardIndex { block, cumulative }.into()
+}
+
+fn coin(value: u128) -> DynamicallyTypedData {
+    Coin::<0>(value).into()
+}
+
+fn unbonding(amount: u128, unlocks_at: u32) -> DynamicallyTypedData {
+    Unbonding::<0> { amount, unlocks_at }.into()
+}
+
+#[test]
+fn bond_works() {
+    let checker = Bond::<AlwaysBlockTen, 0>::default();
+    let inputs = vec![coin(100)];
+    let peeks = vec![index(9, 500)];
+    let outputs = vec![stake(80, 500), coin(15)];
+
+    assert_eq!(
+        SimpleConstraintChecker::check(&checker, &inputs, &peeks, &outputs, &Default::default()),
+        Ok(5)
+    );
+}
+
+#[test]
+fn bond_without_reward_index_fails() {
+    let checker = Bond::<AlwaysBlockTen, 0>::default();
+    let inputs = vec![coin(100)];
+    let outputs = vec![stake(100, 500)];
+
+    assert_eq!(
+        SimpleConstraintChecker::check(&checker, &inputs, &[], &outputs, &Default::default()),
+        Err(MissingRewardIndex)
+    );
+}
+
+#[test]
+fn bond_with_stale_reward_index_fails() {
+    let checker = Bond::<AlwaysBlockTen, 0>::default();
+    let inputs = vec![coin(100)];
+    let peeks = vec![index(8, 490)];
+    let outputs = vec![stake(100, 490)];
+
+    assert_eq!(
+        SimpleConstraintChecker::check(&checker, &inputs, &peeks, &outputs, &Default::default()),
+        Err(StaleRewardIndex)
+    );
+}
+
+#[test]
+fn bond_backdating_reward_index_fails() {
+    let checker = Bond::<AlwaysBlockTen, 0>::default();
+    let inputs = vec![coin(100)];
+    let peeks = vec![index(9, 500)];
+    let outputs = vec![stake(100, 0)];
+
+    assert_eq!(
+        SimpleConstraintChecker::check(&checker, &inputs, &peeks, &outputs, &Default::default()),
+        Err(WrongRewardIndex)
+    );
+}
+
+#[test]
+fn bond_nothing_fails() {
+    let checker = Bond::<AlwaysBlockTen, 0>::default();
+    let inputs = vec![coin(100)];
+    let peeks = vec![index(9, 500)];
+    let outputs = vec![coin(100)];
+
+    assert_eq!(
+        SimpleConstraintChecker::check(&checker, &inputs, &peeks, &outputs, &Default::default()),
+        Err(NothingBonded)
+    );
+}
+
+#[test]
+fn bond_exceeding_inputs_fails() {
+    let checker = Bond::<AlwaysBlockTen, 0>::default();
+    let inputs = vec![coin(100)];
+    let peeks = vec![index(9, 500)];
+    let outputs = vec![stake(101, 500)];
+
+    assert_eq!(
+        SimpleConstraintChecker::check(&checker, &inputs, &peeks, &outputs, &Default::default()),
+        Err(OutputsExceedInputs)
+    );
+}
+
+#[test]
+fn bond_bogus_input_fails() {
+    let checker = Bond::<AlwaysBlockTen, 0>::default();
+    let inputs = vec![Bogus.into()];
+    let peeks = vec![index(9, 500)];
+    let outputs = vec![stake(100, 500)];
+
+    assert_eq!(
+        SimpleConstraintChecker::check(&checker, &inputs, &peeks, &outputs, &Default::default()),
+        Err(BadlyTypedInput)
+    );
+}
+
+#[test]
+fn claim_rewards_works() {
+    let checker = ClaimRewards::<0>;
+    // Each stake earns a reward equal to its amount times the index growth over the precision.
+    let inputs = vec![stake(1_000, 0), stake(2_000, REWARD_PRECISION / 2)];
+    let peeks = vec![index(10, REWARD_PRECISION)];
+    let outputs = vec![
+        stake(1_000, REWARD_PRECISION),
+        stake(2_000, REWARD_PRECISION),
+        coin(1_500),
+        coin(500),
+    ];
+
+    assert_eq!(
+        SimpleConstraintChecker::check(&checker, &inputs, &peeks, &outputs, &Default::default()),
+        Ok(0)
+    );
+}
+
+#[test]
+fn claim_too_much_reward_fails() {
+    let checker = ClaimRewards::<0>;
+    let inputs = vec![stake(1_000, 0)];
+    let peeks = vec![index(10, REWARD_PRECISION)];
+    let outputs = vec![stake(1_000, REWARD_PRECISION), coin(1_001)];
+
+    assert_eq!(
+        SimpleConstraintChecker::check(&checker, &inputs, &peeks, &outputs, &Default::default()),
+        Err(OutputsExceedInputs)
+    );
+}
+
+#[test]
+fn claim_without_resetting_index_fails() {
+    let checker = ClaimRewards::<0>;
+    let inputs = vec![stake(1_000, 0)];
+    let peeks = vec![index(10, REWARD_PRECISION)];
+    let outputs = vec![stake(1_000, 0), coin(1_000)];
+
+    assert_eq!(
+        SimpleConstraintChecker::check(&checker, &inputs, &peeks, &outputs, &Default::default()),
+        Err(StakeNotPreserved)
+    );
+}
+
+#[test]
+fn claim_changing_stake_amount_fails() {
+    let checker = ClaimRewards::<0>;
+    let inputs = vec![stake(1_000, 0)];
+    let peeks = vec![index(10, REWARD_PRECISION)];
+    let outputs = vec![stake(2_000, REWARD_PRECISION)];
+
+    assert_eq!(
+        SimpleConstraintChecker::check(&checker, &inputs, &peeks, &outputs, &Default::default()),
+        Err(StakeNotPreserved)
+    );
+}
+
+#[test]
+fn claim_dropping_stake_fails() {
+    let checker = ClaimRewards::<0>;
+    let inputs = vec![stake(1_000, 0)];
+    let peeks = vec![index(10, REWARD_PRECISION)];
+
+    assert_eq!(
+        SimpleConstraintChecker::check(&checker, &inputs, &peeks, &[], &Default::default()),
+        Err(StakeNotPreserved)
+    );
+}
+
+#[test]
+fn claim_with_older_index_than_stake_fails() {
+    let checker = ClaimRewards::<0>;
+    let inputs = vec![stake(1_000, 500)];
+    let peeks = vec![index(10, 400)];
+    let outputs = vec![stake(1_000, 400)];
+
+    assert_eq!(
+        SimpleConstraintChecker::check(&checker, &inputs, &peeks, &outputs, &Default::default()),
+        Err(StaleRewardIndex)
+    );
+}
+
+#[test]
+fn unbond_works() {
+    let checker = Unbond::<AlwaysBlockTen, 0>::default();
+    let inputs = vec![stake(60, 0), stake(40, 0)];
+    let outputs = vec![unbonding(70, 15), unbonding(30, 20)];
+
+    assert_eq!(
+        SimpleConstraintChecker::check(&checker, &inputs, &[], &outputs, &Default::default()),
+        Ok(0)
+    );
+}
+
+#[test]
+fn unbond_too_short_fails() {
+    let checker = Unbond::<AlwaysBlockTen, 0>::default();
+    let inputs = vec![stake(100, 0)];
+    let outputs = vec![unbonding(100, 14)];
+
+    assert_eq!(
+        SimpleConstraintChecker::check(&checker, &inputs, &[], &outputs, &Default::default()),
+        Err(UnbondingTooShort)
+    );
+}
+
+#[test]
+fn unbond_straight_to_coins_fails() {
+    let checker = Unbond::<AlwaysBlockTen, 0>::default();
+    let inputs = vec![stake(100, 0)];
+    let outputs = vec![coin(100)];
+
+    assert_eq!(
+        SimpleConstraintChecker::check(&checker, &inputs, &[], &outputs, &Default::default()),
+        Err(BadlyTypedOutput)
+    );
+}
+
+#[test]
+fn unbond_exceeding_inputs_fails() {
+    let checker = Unbond::<AlwaysBlockTen, 0>::default();
+    let inputs = vec![stake(100, 0)];
+    let outputs = vec![unbonding(101, 15)];
+
+    assert_eq!(
+        SimpleConstraintChecker::check(&checker, &inputs, &[], &outputs, &Default::default()),
+        Err(OutputsExceedInputs)
+    );
+}
+
+#[test]
+fn withdraw_works() {
+    let checker = Withdraw::<AlwaysBlockTen, 0>::default();
+    let inputs = vec![unbonding(100, 10)];
+    let outputs = vec![coin(90)];
+
+    assert_eq!(
+        SimpleConstraintChecker::check(&checker, &inputs, &[], &outputs, &Default::default()),
+        Ok(10)
+    );
+}
+
+#[test]
+fn withdraw_while_unbonding_fails() {
+    let checker = Withdraw::<AlwaysBlockTen, 0>::default();
+    let inputs = vec![unbonding(100, 11)];
+    let outputs = vec![coin(100)];
+
+    assert_eq!(
+        SimpleConstraintChecker::check(&checker, &inputs, &[], &outputs, &Default::default()),
+        Err(StillUnbonding)
+    );
+}
+
+#[test]
+fn withdraw_bonded_stake_fails() {
+    let checker = Withdraw::<AlwaysBlockTen, 0>::default();
+    let inputs = vec![stake(100, 0)];
+    let outputs = vec![coin(100)];
+
+    assert_eq!(
+        SimpleConstraintChecker::check(&checker, &inputs, &[], &outputs, &Default::default()),
+        Err(BadlyTypedInput)
+    );
+}
+
+fn distribute(
+    inputs: Vec<DynamicallyTypedData>,
+    peeks: Vec<DynamicallyTypedData>,
+    outputs: Vec<DynamicallyTypedData>,
+) -> Result<TransactionPriority, ConstraintCheckerError> {
+    let checker = DistributeRewards::<AlwaysBlockTen>::default();
+    let wrap = |data: Vec<DynamicallyTypedData>| -> Vec<Output<UpForGrabs>> {
+        data.into_iter().map(Into::into).collect()
+    };
+    ConstraintChecker::<UpForGrabs>::check(
+        &checker,
+        &wrap(inputs),
+        &wrap(peeks),
+        &wrap(outputs),
+        &Default::default(),
+    )
+}
+
+#[test]
+fn distribute_rewards_works() {
+    let rate = AlwaysBlockTen::REWARD_RATE;
+    assert_eq!(
+        distribute(vec![], vec![index(9, 3 * rate)], vec![index(10, 4 * rate)]),
+        Ok(0)
+    );
+}
+
+#[test]
+fn distribute_too_much_reward_fails() {
+    let rate = AlwaysBlockTen::REWARD_RATE;
+    assert_eq!(
+        distribute(vec![], vec![index(9, 3 * rate)], vec![index(10, 5 * rate)]),
+        Err(WrongRewardAmount)
+    );
+}
+
+#[test]
+fn distribute_with_input_fails() {
+    let rate = AlwaysBlockTen::REWARD_RATE;
+    assert_eq!(
+        distribute(
+            vec![index(9, 3 * rate)],
+            vec![index(9, 3 * rate)],
+            vec![index(10, 4 * rate)]
+        ),
+        Err(InputsWhileDistributingRewards)
+    );
+}
+
+#[test]
+fn distribute_without_previous_index_fails() {
+    let rate = AlwaysBlockTen::REWARD_RATE;
+    assert_eq!(
+        distribute(vec![], vec![], vec![index(10, rate)]),
+        Err(MissingRewardIndex)
+    );
+}
+
+#[test]
+fn distribute_skipping_block_fails() {
+    let rate = AlwaysBlockTen::REWARD_RATE;
+    assert_eq!(
+        distribute(vec![], vec![index(8, 3 * rate)], vec![index(10, 4 * rate)]),
+        Err(PreviousIndexWrongHeight)
+    );
+}
+
+#[test]
+fn distribute_wrong_height_fails() {
+    let rate = AlwaysBlockTen::REWARD_RATE;
+    assert_eq!(
+        distribute(vec![], vec![index(10, 3 * rate)], vec![index(11, 4 * rate)]),
+        Err(NewIndexWrongHeight)
+    );
+}
+
+#[test]
+fn distribute_extra_output_fails() {
+    let rate = AlwaysBlockTen::REWARD_RATE;
+    assert_eq!(
+        distribute(
+            vec![],
+            vec![index(9, 3 * rate)],
+            vec![index(10, 4 * rate), coin(100)]
+        ),
+        Err(WrongNumberOutputs)
+    );
+}
+
+#[test]
+fn clean_up_reward_index_works() {
+    let checker = CleanUpRewardIndex::<AlwaysBlockTen>::default();
+    let inputs = vec![index(5, 0), index(6, 0)];
+
+    assert_eq!(
+        SimpleConstraintChecker::check(&checker, &inputs, &[], &[], &Default::default()),
+        Ok(0)
+    );
+}
+
+#[test]
+fn clean_up_recent_reward_index_fails() {
+    let checker = CleanUpRewardIndex::<AlwaysBlockTen>::default();
+    let inputs = vec![index(7, 0)];
+
+    assert_eq!(
+        SimpleConstraintChecker::check(&checker, &inputs, &[], &[], &Default::default()),
+        Err(DontBeSoHasty)
+    );
+}
+
+#[test]
+fn clean_up_creating_state_fails() {
+    let checker = CleanUpRewardIndex::<AlwaysBlockTen>::default();
+    let inputs = vec![index(5, 0)];
+    let outputs = vec![index(5, 0)];
+
+    assert_eq!(
+        SimpleConstraintChecker::check(&checker, &inputs, &[], &outputs, &Default::default()),
+        Err(CleanupCannotCreateState)
+    );
+}
@@ -0,0 +1,912 @@
+//! Two-party state channels for turn-based games that are played for `Coin<ID>`s from the money piece.
+//!
+//! Two players lock their stakes into a [`GameChannel`] on chain, along with the hash of the
+//! initial game state, and then play any number of turns off chain. After every turn, both players
+//! sign a new [`GameState`] that records the hash of the game state, the balances that each player
+//! would be paid if the game ended there, and a nonce that increases with every update. The piece
+//! never sees the game itself, only its hash, so the same channel works for chess, tic-tac-toe, or
+//! any other game that two players can agree on turn by turn.
+//!
+//! A channel is closed in one of two ways.
+//! 1. Cooperatively with [`CooperativeClose`]. Both players sign the final balances and the stakes
+//!    are paid out at once.
+//! 2. Through a dispute, when a player stops responding or disagrees. Either player submits the
+//!    latest state that both players signed with [`StartDispute`]. This starts a dispute window
+//!    of [`GameTerms::dispute_window`] blocks during which any newer signed state may be submitted
+//!    with [`Challenge`]. Once the window ends, [`SettleChannel`] pays out the stakes according to
+//!    the newest state submitted.
+//!
+//! A player who expects to be offline during a dispute can post their latest state on chain in
+//! advance as a [`Checkpoint`]. Disputes and challenges may peek at checkpoints instead of carrying
+//! a signed state, so anyone, such as a watchtower, can challenge a stale state on a player's
+//! behalf without ever holding their signatures. Checkpoints that are superseded by the channel's
+//! state are removed by [`DiscardCheckpoints`], and the remaining ones are removed along with the
+//! channel when it closes. Because several transactions may race to remove the same checkpoint,
+//! checkpoints should always be spent as evicted inputs, so that a close never fails because a
+//! checkpoint was removed first.
+//!
+//! Channels and checkpoints should be protected by the `UpForGrabs` verifier. The constraint
+//! checkers, rather than the verifier, check the relevant signatures and guarantee that the stakes
+//! are paid to the right players.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use core::marker::PhantomData;
+
+use money::Coin;
+use parity_scale_codec::{Decode, Encode};
+use scale_info::TypeInfo;
+use serde::{Deserialize, Serialize};
+use sp_core::{
+    sr25519::{Public, Signature},
+    H256,
+};
+use sp_runtime::transaction_validity::TransactionPriority;
+use sp_std::prelude::*;
+use tuxedo_core::{
+    dynamic_typing::UtxoData,
+    ensure,
+    support_macros::{CloneNoBound, DebugNoBound, DefaultNoBound},
+    types::Output,
+    verifier::SigCheck,
+    CheckerContext, ConstraintChecker, Verifier,
+};
+
+#[cfg(test)]
+mod tests;
+
+/// Configuration items for the State Channel piece when it is
+/// instantiated in a concrete runtime.
+pub trait StateChannelConfig {
+    /// A means of getting the current block height.
+    /// Probably this will be the Tuxedo Executive
+    fn block_height() -> u32;
+}
+
+/// The fixed terms of a channel that are agreed when it is opened.
+#[derive(
+    Serialize,
+    Deserialize,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Clone,
+    Encode,
+    Decode,
+    Hash,
+    Debug,
+    TypeInfo,
+)]
+pub struct GameTerms {
+    /// The first player's key.
+    pub player_a: H256,
+    /// The second player's key.
+    pub player_b: H256,
+    /// The value that the first player locks in the channel.
+    pub stake_a: u128,
+    /// The value that the second player locks in the channel.
+    pub stake_b: u128,
+    /// How many blocks a dispute stays open for newer states to be submitted.
+    pub dispute_window: u32,
+    /// Distinguishes channels that otherwise have identical terms so that
+    /// states signed for one cannot be replayed on another.
+    pub salt: u32,
+}
+
+/// A snapshot of a game that both players sign off chain after every turn.
+#[derive(
+    Serialize,
+    Deserialize,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Clone,
+    Encode,
+    Decode,
+    Hash,
+    Debug,
+    TypeInfo,
+)]
+pub struct GameState {
+    /// Increases with every update. Newer states supersede older ones.
+    pub nonce: u64,
+    /// The hash of the game state, in whatever encoding the players agreed on.
+    pub state_hash: H256,
+    /// The first player's balance if the game ended in this state.
+    pub balance_a: u128,
+    /// The second player's balance if the game ended in this state.
+    pub balance_b: u128,
+}
+
+/// A game state along with both players' signatures over it.
+#[derive(Serialize, Deserialize, Encode, Decode, Debug, PartialEq, Eq, Clone, TypeInfo)]
+pub struct SignedGameState {
+    pub state: GameState,
+    pub signature_a: Signature,
+    pub signature_b: Signature,
+}
+
+/// A state channel holding both players' stakes in `Coin<ID>`.
+#[derive(
+    Serialize,
+    Deserialize,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Clone,
+    Encode,
+    Decode,
+    Hash,
+    Debug,
+    TypeInfo,
+)]
+pub struct GameChannel<const ID: u8> {
+    /// The terms agreed when opening the channel.
+    pub terms: GameTerms,
+    /// The newest state known on chain. When the channel is opened, this is the initial state.
+    pub state: GameState,
+    /// The block height from which the channel may be settled, if a dispute is in progress.
+    pub dispute_ends: Option<u32>,
+}
+
+impl<const ID: u8> UtxoData for GameChannel<ID> {
+    const TYPE_ID: [u8; 4] = [b'g', b'c', b'h', ID];
+}
+
+/// A signed state that was posted on chain so that disputes and challenges can use it later.
+#[derive(
+    Serialize,
+    Deserialize,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Clone,
+    Encode,
+    Decode,
+    Hash,
+    Debug,
+    TypeInfo,
+)]
+pub struct Checkpoint<const ID: u8> {
+    /// The terms of the channel that the state belongs to.
+    pub terms: GameTerms,
+    /// The state, whose signatures were checked when it was posted.
+    pub state: GameState,
+}
+
+impl<const ID: u8> UtxoData for Checkpoint<ID> {
+    const TYPE_ID: [u8; 4] = [b'g', b'c', b'p', ID];
+}
+
+impl GameTerms {
+    /// The message that both players sign to agree on an off-chain state.
+    pub fn state_message(&self, state: &GameState) -> Vec<u8> {
+        (b"game_state", self, state).encode()
+    }
+
+    /// The message that both players sign to cooperatively close with the given balances.
+    pub fn close_message(&self, balance_a: u128, balance_b: u128) -> Vec<u8> {
+        (b"game_close", self, balance_a, balance_b).encode()
+    }
+
+    /// Check that both players signed the given message.
+    fn check_signatures(
+        &self,
+        message: &[u8],
+        signature_a: &Signature,
+        signature_b: &Signature,
+    ) -> bool {
+        sp_io::crypto::sr25519_verify(signature_a, message, &Public::from_h256(self.player_a))
+            && sp_io::crypto::sr25519_verify(
+                signature_b,
+                message,
+                &Public::from_h256(self.player_b),
+            )
+    }
+
+    /// Check that the given balances add up to both stakes together.
+    fn check_balances(
+        &self,
+        balance_a: u128,
+        balance_b: u128,
+    ) -> Result<(), ConstraintCheckerError> {
+        let total_stake = self
+            .stake_a
+            .checked_add(self.stake_b)
+            .ok_or(ConstraintCheckerError::ValueOverflow)?;
+        ensure!(
+            balance_a.checked_add(balance_b) == Some(total_stake),
+            ConstraintCheckerError::BalancesDoNotMatchStakes
+        );
+        Ok(())
+    }
+
+    /// Check that a signed state is valid for this channel and return the state.
+    fn check_state(&self, signed: &SignedGameState) -> Result<GameState, ConstraintCheckerError> {
+        ensure!(
+            self.check_signatures(
+                &self.state_message(&signed.state),
+                &signed.signature_a,
+                &signed.signature_b
+            ),
+            ConstraintCheckerError::BadSignature
+        );
+        self.check_balances(signed.state.balance_a, signed.state.balance_b)?;
+
+        Ok(signed.state.clone())
+    }
+}
+
+/// Errors that can occur when checking state channel transactions.
+#[derive(
+    Serialize,
+    Deserialize,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Clone,
+    Encode,
+    Decode,
+    Hash,
+    Debug,
+    TypeInfo,
+)]
+pub enum ConstraintCheckerError {
+    /// An input data has the wrong type.
+    BadlyTypedInput,
+    /// A peeked data has the wrong type.
+    BadlyTypedPeek,
+    /// An output data has the wrong type.
+    BadlyTypedOutput,
+    /// Wrong number of inputs were provided to the constraint checker.
+    WrongNumberInputs,
+    /// Wrong number of peeks were provided to the constraint checker.
+    WrongNumberPeeks,
+    /// Wrong number of outputs were provided to the constraint checker.
+    WrongNumberOutputs,
+    /// A channel or coin with zero value was created. This is not allowed because it wastes state space.
+    ZeroValue,
+    /// The value consumed or created by this transaction overflows the value type.
+    ValueOverflow,
+    /// A channel was funded with coins that belong to neither player.
+    FundedByStranger,
+    /// A player contributed less than their stake to a new channel.
+    Underfunded,
+    /// A new channel is already in dispute.
+    OpenedInDispute,
+    /// A new channel's initial state does not have nonce zero and balances equal to the stakes.
+    BadInitialState,
+    /// A required signature is missing or invalid.
+    BadSignature,
+    /// The balances in a state or close do not add up to both stakes together.
+    BalancesDoNotMatchStakes,
+    /// A player was paid more than their balance.
+    Overpaid,
+    /// Coins were locked to some verifier other than the players'.
+    PaidToWrongVerifier,
+    /// Attempted to start a dispute on a channel that is already in dispute.
+    AlreadyInDispute,
+    /// Attempted to challenge or settle a channel that is not in dispute.
+    NotInDispute,
+    /// The dispute window is over, so the dispute can no longer be challenged.
+    DisputeWindowOver,
+    /// The dispute window is not over, so the channel cannot be settled.
+    DisputeWindowNotOver,
+    /// No state was submitted, either directly or from a checkpoint.
+    NoStateSubmitted,
+    /// The submitted state is not newer than the one already on chain.
+    StaleState,
+    /// The updated channel differs from the original in some way other than the expected update.
+    ChannelMismatch,
+    /// A checkpoint belongs to a different channel.
+    CheckpointMismatch,
+    /// A checkpoint that is not yet superseded by the channel's state was discarded.
+    CheckpointNotStale,
+}
+
+/// Extract the channel from the first input, and make sure every other input is a checkpoint of
+/// the same channel. Checkpoints that another transaction already removed are simply not there.
+fn extract_channel<V, const ID: u8>(
+    inputs: &[Output<V>],
+) -> Result<GameChannel<ID>, ConstraintCheckerError> {
+    let (first, checkpoints) = inputs
+        .split_first()
+        .ok_or(ConstraintCheckerError::WrongNumberInputs)?;
+    let channel = first
+        .payload
+        .extract::<GameChannel<ID>>()
+        .map_err(|_| ConstraintCheckerError::BadlyTypedInput)?;
+    for checkpoint in checkpoints {
+        let checkpoint = checkpoint
+            .payload
+            .extract::<Checkpoint<ID>>()
+            .map_err(|_| ConstraintCheckerError::BadlyTypedInput)?;
+        ensure!(
+            checkpoint.terms == channel.terms,
+            ConstraintCheckerError::CheckpointMismatch
+        );
+    }
+
+    Ok(channel)
+}
+
+/// Extract the updated channel from the single output and make sure its terms are unchanged.
+fn extract_updated_channel<V, const ID: u8>(
+    outputs: &[Output<V>],
+    terms: &GameTerms,
+) -> Result<GameChannel<ID>, ConstraintCheckerError> {
+    ensure!(
+        outputs.len() == 1,
+        ConstraintCheckerError::WrongNumberOutputs
+    );
+    let updated = outputs[0]
+        .payload
+        .extract::<GameChannel<ID>>()
+        .map_err(|_| ConstraintCheckerError::BadlyTypedOutput)?;
+    ensure!(
+        &updated.terms == terms,
+        ConstraintCheckerError::ChannelMismatch
+    );
+
+    Ok(updated)
+}
+
+/// The state submitted to a dispute or challenge. It is either passed in the constraint checker,
+/// or it is the newest of the peeked checkpoints, but not both. Returns `None` if neither was given.
+fn submitted_state<V, const ID: u8>(
+    signed: &Option<SignedGameState>,
+    peeks: &[Output<V>],
+    terms: &GameTerms,
+) -> Result<Option<GameState>, ConstraintCheckerError> {
+    if let Some(signed) = signed {
+        ensure!(peeks.is_empty(), ConstraintCheckerError::WrongNumberPeeks);
+        return terms.check_state(signed).map(Some);
+    }
+
+    let mut newest: Option<GameState> = None;
+    for peek in peeks {
+        let checkpoint = peek
+            .payload
+            .extract::<Checkpoint<ID>>()
+            .map_err(|_| ConstraintCheckerError::BadlyTypedPeek)?;
+        ensure!(
+            &checkpoint.terms == terms,
+            ConstraintCheckerError::CheckpointMismatch
+        );
+        if newest
+            .as_ref()
+            .map_or(true, |newest| checkpoint.state.nonce > newest.nonce)
+        {
+            newest = Some(checkpoint.state);
+        }
+    }
+
+    Ok(newest)
+}
+
+/// Consume the channel and make sure the outputs pay each player no more than their balance.
+/// Returns the amount that the players leave to pay the fee and tip.
+fn pay_out<V, const ID: u8>(
+    inputs: &[Output<V>],
+    outputs: &[Output<V>],
+    balance_of: impl Fn(&GameChannel<ID>) -> Result<(u128, u128), ConstraintCheckerError>,
+) -> Result<u128, ConstraintCheckerError>
+where
+    V: Verifier + PartialEq + From<SigCheck>,
+{
+    let channel = extract_channel::<V, ID>(inputs)?;
+    let (balance_a, balance_b) = balance_of(&channel)?;
+
+    let player_a: V = SigCheck::new(channel.terms.player_a).into();
+    let player_b: V = SigCheck::new(channel.terms.player_b).into();
+    let mut paid_a: u128 = 0;
+    let mut paid_b: u128 = 0;
+    for output in outputs {
+        let value = output
+            .payload
+            .extract::<Coin<ID>>()
+            .map_err(|_| ConstraintCheckerError::BadlyTypedOutput)?
+            .0;
+        ensure!(value > 0, ConstraintCheckerError::ZeroValue);
+        let paid = if output.verifier == player_a {
+            &mut paid_a
+        } else if output.verifier == player_b {
+            &mut paid_b
+        } else {
+            return Err(ConstraintCheckerError::PaidToWrongVerifier);
+        };
+        *paid = paid
+            .checked_add(value)
+            .ok_or(ConstraintCheckerError::ValueOverflow)?;
+    }
+    ensure!(
+        paid_a <= balance_a && paid_b <= balance_b,
+        ConstraintCheckerError::Overpaid
+    );
+
+    // The balances were already checked to add up to the stakes, so this can't underflow.
+    Ok(balance_a - paid_a + balance_b - paid_b)
+}
+
+/// A constraint checker that opens a new channel.
+///
+/// All inputs are `Coin<ID>`s belonging to either player. The first output is the new channel,
+/// and any other outputs are `Coin<ID>` change for either player. Each player must contribute at
+/// least their stake, net of their change, so neither player can be made to fund the other's
+/// stake. The players pay the transaction's fee and tip with whatever they contribute beyond
+/// their stakes and change.
+#[derive(
+    Serialize, Deserialize, Encode, Decode, Debug, Default, PartialEq, Eq, Clone, TypeInfo,
+)]
+pub struct OpenGameChannel<const ID: u8>;
+
+impl<V, const ID: u8> ConstraintChecker<V> for OpenGameChannel<ID>
+where
+    V: Verifier + PartialEq + From<SigCheck>,
+{
+    type Error = ConstraintCheckerError;
+    type InherentHooks = ();
+
+    fn check(
+        &self,
+        inputs: &[Output<V>],
+        _peeks: &[Output<V>],
+        outputs: &[Output<V>],
+        _context: &CheckerContext,
+    ) -> Result<TransactionPriority, Self::Error> {
+        let (channel, change) = outputs
+            .split_first()
+            .ok_or(ConstraintCheckerError::WrongNumberOutputs)?;
+        let channel = channel
+            .payload
+            .extract::<GameChannel<ID>>()
+            .map_err(|_| ConstraintCheckerError::BadlyTypedOutput)?;
+        let terms = &channel.terms;
+        ensure!(
+            channel.dispute_ends.is_none(),
+            ConstraintCheckerError::OpenedInDispute
+        );
+        ensure!(
+            channel.state.nonce == 0
+                && channel.state.balance_a == terms.stake_a
+                && channel.state.balance_b == terms.stake_b,
+            ConstraintCheckerError::BadInitialState
+        );
+        let total_stake = terms
+            .stake_a
+            .checked_add(terms.stake_b)
+            .ok_or(ConstraintCheckerError::ValueOverflow)?;
+        ensure!(total_stake > 0, ConstraintCheckerError::ZeroValue);
+
+        let player_a: V = SigCheck::new(terms.player_a).into();
+        let player_b: V = SigCheck::new(terms.player_b).into();
+        let mut funded_a: u128 = 0;
+        let mut funded_b: u128 = 0;
+        for input in inputs {
+            let value = input
+                .payload
+                .extract::<Coin<ID>>()
+                .map_err(|_| ConstraintCheckerError::BadlyTypedInput)?
+                .0;
+            let funded = if input.verifier == player_a {
+                &mut funded_a
+            } else if input.verifier == player_b {
+                &mut funded_b
+            } else {
+                return Err(ConstraintCheckerError::FundedByStranger);
+            };
+            *funded = funded
+                .checked_add(value)
+                .ok_or(ConstraintCheckerError::ValueOverflow)?;
+        }
+
+        for output in change {
+            let value = output
+                .payload
+                .extract::<Coin<ID>>()
+                .map_err(|_| ConstraintCheckerError::BadlyTypedOutput)?
+                .0;
+            ensure!(value > 0, ConstraintCheckerError::ZeroValue);
+            let funded = if output.verifier == player_a {
+                &mut funded_a
+            } else if output.verifier == player_b {
+                &mut funded_b
+            } else {
+                return Err(ConstraintCheckerError::PaidToWrongVerifier);
+            };
+            *funded = funded
+                .checked_sub(value)
+                .ok_or(ConstraintCheckerError::Underfunded)?;
+        }
+        ensure!(
+            funded_a >= terms.stake_a && funded_b >= terms.stake_b,
+            ConstraintCheckerError::Underfunded
+        );
+
+        let burned = (funded_a - terms.stake_a).saturating_add(funded_b - terms.stake_b);
+        Ok(burned.try_into().unwrap_or(TransactionPriority::MAX))
+    }
+
+    fn is_inherent(&self) -> bool {
+        false
+    }
+}
+
+/// A constraint checker that posts a signed state on chain as a [`Checkpoint`].
+///
+/// There are no inputs, the only peek is the channel that the state belongs to, and the only output
+/// is the checkpoint. The state must be newer than the one already in the channel.
+#[derive(Serialize, Deserialize, Encode, Decode, Debug, PartialEq, Eq, Clone, TypeInfo)]
+pub struct PostCheckpoint<const ID: u8> {
+    /// A state signed by both players.
+    pub state: SignedGameState,
+}
+
+impl<V: Verifier, const ID: u8> ConstraintChecker<V> for PostCheckpoint<ID> {
+    type Error = ConstraintCheckerError;
+    type InherentHooks = ();
+
+    fn check(
+        &self,
+        inputs: &[Output<V>],
+        peeks: &[Output<V>],
+        outputs: &[Output<V>],
+        _context: &CheckerContext,
+    ) -> Result<TransactionPriority, Self::Error> {
+        ensure!(inputs.is_empty(), ConstraintCheckerError::WrongNumberInputs);
+        ensure!(peeks.len() == 1, ConstraintCheckerError::WrongNumberPeeks);
+        let channel = peeks[0]
+            .payload
+            .extract::<GameChannel<ID>>()
+            .map_err(|_| ConstraintCheckerError::BadlyTypedPeek)?;
+        let state = channel.terms.check_state(&self.state)?;
+        ensure!(
+            state.nonce > channel.state.nonce,
+            ConstraintCheckerError::StaleState
+        );
+
+        ensure!(
+            outputs.len() == 1,
+            ConstraintCheckerError::WrongNumberOutputs
+        );
+        let checkpoint = outputs[0]
+            .payload
+            .extract::<Checkpoint<ID>>()
+            .map_err(|_| ConstraintCheckerError::BadlyTypedOutput)?;
+        ensure!(
+            checkpoint
+                == Checkpoint {
+                    terms: channel.terms,
+                    state,
+                },
+            ConstraintCheckerError::CheckpointMismatch
+        );
+
+        Ok(0)
+    }
+
+    fn is_inherent(&self) -> bool {
+        false
+    }
+}
+
+/// A constraint checker that removes checkpoints that the channel's state already supersedes.
+///
+/// All inputs are checkpoints, the only peek is their channel, and there are no outputs. Anyone
+/// may discard stale checkpoints, and they should be spent as evicted inputs.
+#[derive(
+    Serialize, Deserialize, Encode, Decode, Debug, Default, PartialEq, Eq, Clone, TypeInfo,
+)]
+pub struct DiscardCheckpoints<const ID: u8>;
+
+impl<V: Verifier, const ID: u8> ConstraintChecker<V> for DiscardCheckpoints<ID> {
+    type Error = ConstraintCheckerError;
+    type InherentHooks = ();
+
+    fn check(
+        &self,
+        inputs: &[Output<V>],
+        peeks: &[Output<V>],
+        outputs: &[Output<V>],
+        _context: &CheckerContext,
+    ) -> Result<TransactionPriority, Self::Error> {
+        ensure!(peeks.len() == 1, ConstraintCheckerError::WrongNumberPeeks);
+        ensure!(
+            outputs.is_empty(),
+            ConstraintCheckerError::WrongNumberOutputs
+        );
+        let channel = peeks[0]
+            .payload
+            .extract::<GameChannel<ID>>()
+            .map_err(|_| ConstraintCheckerError::BadlyTypedPeek)?;
+
+        for input in inputs {
+            let checkpoint = input
+                .payload
+                .extract::<Checkpoint<ID>>()
+                .map_err(|_| ConstraintCheckerError::BadlyTypedInput)?;
+            ensure!(
+                checkpoint.terms == channel.terms,
+                ConstraintCheckerError::CheckpointMismatch
+            );
+            ensure!(
+                checkpoint.state.nonce <= channel.state.nonce,
+                ConstraintCheckerError::CheckpointNotStale
+            );
+        }
+
+        Ok(0)
+    }
+
+    fn is_inherent(&self) -> bool {
+        false
+    }
+}
+
+/// A constraint checker that closes a channel immediately with both players' agreement.
+///
+/// The first input is the channel, and any other inputs are its checkpoints, which are removed
+/// along with it. All outputs are `Coin<ID>`s locked to one of the players, and each player is
+/// paid no more than their agreed balance. This works whether or not a dispute is in progress.
+#[derive(Serialize, Deserialize, Encode, Decode, Debug, PartialEq, Eq, Clone, TypeInfo)]
+pub struct CooperativeClose<const ID: u8> {
+    /// The first player's final balance.
+    pub balance_a: u128,
+    /// The second player's final balance.
+    pub balance_b: u128,
+    /// The first player's signature over the close message.
+    pub signature_a: Signature,
+    /// The second player's signature over the close message.
+    pub signature_b: Signature,
+}
+
+impl<V, const ID: u8> ConstraintChecker<V> for CooperativeClose<ID>
+where
+    V: Verifier + PartialEq + From<SigCheck>,
+{
+    type Error = ConstraintCheckerError;
+    type InherentHooks = ();
+
+    fn check(
+        &self,
+        inputs: &[Output<V>],
+        _peeks: &[Output<V>],
+        outputs: &[Output<V>],
+        _context: &CheckerContext,
+    ) -> Result<TransactionPriority, Self::Error> {
+        let burned = pay_out::<V, ID>(inputs, outputs, |channel| {
+            let terms = &channel.terms;
+            ensure!(
+                terms.check_signatures(
+                    &terms.close_message(self.balance_a, self.balance_b),
+                    &self.signature_a,
+                    &self.signature_b,
+                ),
+                ConstraintCheckerError::BadSignature
+            );
+            terms.check_balances(self.balance_a, self.balance_b)?;
+            Ok((self.balance_a, self.balance_b))
+        })?;
+
+        Ok(burned.try_into().unwrap_or(TransactionPriority::MAX))
+    }
+
+    fn is_inherent(&self) -> bool {
+        false
+    }
+}
+
+/// A constraint checker that starts a dispute with the latest state that both players signed.
+///
+/// The only input is a channel that is not in dispute, and the only output is the same channel,
+/// now in dispute until the dispute window after the current block has passed. The state is either
+/// passed in the checker or taken from the newest peeked checkpoint. If neither is given, the
+/// dispute starts from the state already in the channel, which is how a player gives up on an
+/// opponent who never made a move.
+#[derive(
+    Serialize,
+    Deserialize,
+    Encode,
+    Decode,
+    DebugNoBound,
+    DefaultNoBound,
+    PartialEq,
+    Eq,
+    CloneNoBound,
+    TypeInfo,
+)]
+#[scale_info(skip_type_params(T))]
+pub struct StartDispute<T, const ID: u8> {
+    /// The latest state signed by both players, unless it comes from a checkpoint.
+    pub state: Option<SignedGameState>,
+    _phantom: PhantomData<T>,
+}
+
+impl<T, const ID: u8> StartDispute<T, ID> {
+    pub fn new(state: Option<SignedGameState>) -> Self {
+        Self {
+            state,
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<T: StateChannelConfig, V: Verifier, const ID: u8> ConstraintChecker<V>
+    for StartDispute<T, ID>
+{
+    type Error = ConstraintCheckerError;
+    type InherentHooks = ();
+
+    fn check(
+        &self,
+        inputs: &[Output<V>],
+        peeks: &[Output<V>],
+        outputs: &[Output<V>],
+        _context: &CheckerContext,
+    ) -> Result<TransactionPriority, Self::Error> {
+        ensure!(inputs.len() == 1, ConstraintCheckerError::WrongNumberInputs);
+        let channel = extract_channel::<V, ID>(inputs)?;
+        ensure!(
+            channel.dispute_ends.is_none(),
+            ConstraintCheckerError::AlreadyInDispute
+        );
+        let state = match submitted_state::<V, ID>(&self.state, peeks, &channel.terms)? {
+            Some(state) => {
+                ensure!(
+                    state.nonce > channel.state.nonce || state == channel.state,
+                    ConstraintCheckerError::StaleState
+                );
+                state
+            }
+            None => channel.state,
+        };
+
+        let updated = extract_updated_channel::<V, ID>(outputs, &channel.terms)?;
+        ensure!(
+            updated.state == state
+                && updated.dispute_ends
+                    == Some(T::block_height().saturating_add(channel.terms.dispute_window)),
+            ConstraintCheckerError::ChannelMismatch
+        );
+
+        Ok(0)
+    }
+
+    fn is_inherent(&self) -> bool {
+        false
+    }
+}
+
+/// A constraint checker that challenges a dispute with a newer signed state.
+///
+/// The only input is a channel in dispute and the only output is the same channel with the newer
+/// state. The state is either passed in the checker or taken from the newest peeked checkpoint.
+/// The dispute window is not extended.
+#[derive(
+    Serialize,
+    Deserialize,
+    Encode,
+    Decode,
+    DebugNoBound,
+    DefaultNoBound,
+    PartialEq,
+    Eq,
+    CloneNoBound,
+    TypeInfo,
+)]
+#[scale_info(skip_type_params(T))]
+pub struct Challenge<T, const ID: u8> {
+    /// A state signed by both players that is newer than the one in the channel, unless it comes
+    /// from a checkpoint.
+    pub state: Option<SignedGameState>,
+    _phantom: PhantomData<T>,
+}
+
+impl<T, const ID: u8> Challenge<T, ID> {
+    pub fn new(state: Option<SignedGameState>) -> Self {
+        Self {
+            state,
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<T: StateChannelConfig, V: Verifier, const ID: u8> ConstraintChecker<V> for Challenge<T, ID> {
+    type Error = ConstraintCheckerError;
+    type InherentHooks = ();
+
+    fn check(
+        &self,
+        inputs: &[Output<V>],
+        peeks: &[Output<V>],
+        outputs: &[Output<V>],
+        _context: &CheckerContext,
+    ) -> Result<TransactionPriority, Self::Error> {
+        ensure!(inputs.len() == 1, ConstraintCheckerError::WrongNumberInputs);
+        let channel = extract_channel::<V, ID>(inputs)?;
+        let dispute_ends = channel
+            .dispute_ends
+            .ok_or(ConstraintCheckerError::NotInDispute)?;
+        ensure!(
+            T::block_height() < dispute_ends,
+            ConstraintCheckerError::DisputeWindowOver
+        );
+        let state = submitted_state::<V, ID>(&self.state, peeks, &channel.terms)?
+            .ok_or(ConstraintCheckerError::NoStateSubmitted)?;
+        ensure!(
+            state.nonce > channel.state.nonce,
+            ConstraintCheckerError::StaleState
+        );
+
+        let updated = extract_updated_channel::<V, ID>(outputs, &channel.terms)?;
+        ensure!(
+            updated.state == state && updated.dispute_ends == Some(dispute_ends),
+            ConstraintCheckerError::ChannelMismatch
+        );
+
+        Ok(0)
+    }
+
+    fn is_inherent(&self) -> bool {
+        false
+    }
+}
+
+/// A constraint checker that pays out a disputed channel once its dispute window is over.
+///
+/// The first input is the channel in dispute, and any other inputs are its checkpoints, which are
+/// removed along with it. All outputs are `Coin<ID>`s locked to one of the players, and each player
+/// is paid no more than their balance in the newest submitted state.
+#[derive(
+    Serialize,
+    Deserialize,
+    Encode,
+    Decode,
+    DebugNoBound,
+    DefaultNoBound,
+    PartialEq,
+    Eq,
+    CloneNoBound,
+    TypeInfo,
+)]
+#[scale_info(skip_type_params(T))]
+pub struct SettleChannel<T, const ID: u8>(PhantomData<T>);
+
+impl<T, V, const ID: u8> ConstraintChecker<V> for SettleChannel<T, ID>
+where
+    T: StateChannelConfig,
+    V: Verifier + PartialEq + From<SigCheck>,
+{
+    type Error = ConstraintCheckerError;
+    type InherentHooks = ();
+
+    fn check(
+        &self,
+        inputs: &[Output<V>],
+        _peeks: &[Output<V>],
+        outputs: &[Output<V>],
+        _context: &CheckerContext,
+    ) -> Result<TransactionPriority, Self::Error> {
+        let burned = pay_out::<V, ID>(inputs, outputs, |channel| {
+            let dispute_ends = channel
+                .dispute_ends
+                .ok_or(ConstraintCheckerError::NotInDispute)?;
+            ensure!(
+                T::block_height() >= dispute_ends,
+                ConstraintCheckerError::DisputeWindowNotOver
+            );
+            Ok((channel.state.balance_a, channel.state.balance_b))
+        })?;
+
+        Ok(burned.try_into().unwrap_or(TransactionPriority::MAX))
+    }
+
+    fn is_inherent(&self) -> bool {
+        false
+    }
+}
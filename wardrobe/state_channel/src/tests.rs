@@ -0,0 +1,692 @@
+//! Unit tests for the State Channel piece
+
+use super::*;
+use sp_core::{crypto::Pair as _, sr25519::Pair};
+use tuxedo_core::dynamic_typing::{testing::Bogus, DynamicallyTypedData};
+
+/// A mock config that always reports the same block height.
+pub struct AtHeight<const H: u32>;
+
+impl<const H: u32> StateChannelConfig for AtHeight<H> {
+    fn block_height() -> u32 {
+        H
+    }
+}
+
+fn alice() -> Pair {
+    Pair::from_seed(&[1u8; 32])
+}
+
+fn bob() -> Pair {
+    Pair::from_seed(&[2u8; 32])
+}
+
+fn charlie() -> Pair {
+    Pair::from_seed(&[3u8; 32])
+}
+
+fn owned_by(pair: &Pair) -> SigCheck {
+    SigCheck::new(pair.public())
+}
+
+/// A channel where alice stakes 60 and bob stakes 40, with a dispute window of 10 blocks.
+fn terms() -> GameTerms {
+    GameTerms {
+        player_a: alice().public().into(),
+        player_b: bob().public().into(),
+        stake_a: 60,
+        stake_b: 40,
+        dispute_window: 10,
+        salt: 0,
+    }
+}
+
+fn state(nonce: u64, balance_a: u128, balance_b: u128) -> GameState {
+    GameState {
+        nonce,
+        state_hash: H256::repeat_byte(nonce as u8),
+        balance_a,
+        balance_b,
+    }
+}
+
+fn initial_state() -> GameState {
+    state(0, 60, 40)
+}
+
+fn channel(state: GameState, dispute_ends: Option<u32>) -> GameChannel<0> {
+    GameChannel {
+        terms: terms(),
+        state,
+        dispute_ends,
+    }
+}
+
+fn open_channel() -> GameChannel<0> {
+    channel(initial_state(), None)
+}
+
+fn checkpoint(state: GameState) -> Checkpoint<0> {
+    Checkpoint {
+        terms: terms(),
+        state,
+    }
+}
+
+fn sign_state(state: GameState) -> SignedGameState {
+    let message = terms().state_message(&state);
+    SignedGameState {
+        state,
+        signature_a: alice().sign(&message),
+        signature_b: bob().sign(&message),
+    }
+}
+
+/// A channel or checkpoint utxo. The constraint checkers never look at its verifier.
+fn utxo(data: impl Into<DynamicallyTypedData>) -> Output<SigCheck> {
+    (data, owned_by(&charlie())).into()
+}
+
+fn check<C: ConstraintChecker<SigCheck>>(
+    checker: C,
+    inputs: Vec<Output<SigCheck>>,
+    peeks: Vec<Output<SigCheck>>,
+    outputs: Vec<Output<SigCheck>>,
+) -> Result<TransactionPriority, C::Error> {
+    checker.check(&inputs, &peeks, &outputs, &Default::default())
+}
+
+#[test]
+fn open_channel_works() {
+    let inputs = vec![
+        (Coin::<0>(70), owned_by(&alice())).into(),
+        (Coin::<0>(45), owned_by(&bob())).into(),
+    ];
+    let outputs = vec![
+        utxo(open_channel()),
+        (Coin::<0>(5), owned_by(&alice())).into(),
+        (Coin::<0>(3), owned_by(&bob())).into(),
+    ];
+
+    assert_eq!(check(OpenGameChannel::<0>, inputs, vec![], outputs), Ok(7));
+}
+
+#[test]
+fn open_channel_with_one_player_funding_both_stakes_fails() {
+    let inputs = vec![(Coin::<0>(100), owned_by(&alice())).into()];
+    let outputs = vec![utxo(open_channel())];
+
+    assert_eq!(
+        check(OpenGameChannel::<0>, inputs, vec![], outputs),
+        Err(ConstraintCheckerError::Underfunded)
+    );
+}
+
+#[test]
+fn open_channel_taking_too_much_change_fails() {
+    let inputs = vec![
+        (Coin::<0>(60), owned_by(&alice())).into(),
+        (Coin::<0>(50), owned_by(&bob())).into(),
+    ];
+    let outputs = vec![
+        utxo(open_channel()),
+        (Coin::<0>(20), owned_by(&bob())).into(),
+    ];
+
+    assert_eq!(
+        check(OpenGameChannel::<0>, inputs, vec![], outputs),
+        Err(ConstraintCheckerError::Underfunded)
+    );
+}
+
+#[test]
+fn open_channel_funded_by_stranger_fails() {
+    let inputs = vec![
+        (Coin::<0>(60), owned_by(&alice())).into(),
+        (Coin::<0>(40), owned_by(&charlie())).into(),
+    ];
+    let outputs = vec![utxo(open_channel())];
+
+    assert_eq!(
+        check(OpenGameChannel::<0>, inputs, vec![], outputs),
+        Err(ConstraintCheckerError::FundedByStranger)
+    );
+}
+
+#[test]
+fn open_channel_change_to_stranger_fails() {
+    let inputs = vec![
+        (Coin::<0>(70), owned_by(&alice())).into(),
+        (Coin::<0>(40), owned_by(&bob())).into(),
+    ];
+    let outputs = vec![
+        utxo(open_channel()),
+        (Coin::<0>(10), owned_by(&charlie())).into(),
+    ];
+
+    assert_eq!(
+        check(OpenGameChannel::<0>, inputs, vec![], outputs),
+        Err(ConstraintCheckerError::PaidToWrongVerifier)
+    );
+}
+
+#[test]
+fn open_channel_with_bad_initial_state_fails() {
+    let inputs = vec![
+        (Coin::<0>(60), owned_by(&alice())).into(),
+        (Coin::<0>(40), owned_by(&bob())).into(),
+    ];
+    let outputs = vec![utxo(channel(state(0, 100, 0), None))];
+
+    assert_eq!(
+        check(OpenGameChannel::<0>, inputs, vec![], outputs),
+        Err(ConstraintCheckerError::BadInitialState)
+    );
+}
+
+#[test]
+fn open_channel_in_dispute_fails() {
+    let inputs = vec![
+        (Coin::<0>(60), owned_by(&alice())).into(),
+        (Coin::<0>(40), owned_by(&bob())).into(),
+    ];
+    let outputs = vec![utxo(channel(initial_state(), Some(10)))];
+
+    assert_eq!(
+        check(OpenGameChannel::<0>, inputs, vec![], outputs),
+        Err(ConstraintCheckerError::OpenedInDispute)
+    );
+}
+
+#[test]
+fn open_channel_without_channel_fails() {
+    let inputs = vec![(Coin::<0>(60), owned_by(&alice())).into()];
+    let outputs = vec![(Coin::<0>(60), owned_by(&alice())).into()];
+
+    assert_eq!(
+        check(OpenGameChannel::<0>, inputs, vec![], outputs),
+        Err(ConstraintCheckerError::BadlyTypedOutput)
+    );
+}
+
+#[test]
+fn post_checkpoint_works() {
+    let checker = PostCheckpoint::<0> {
+        state: sign_state(state(3, 50, 50)),
+    };
+    let peeks = vec![utxo(open_channel())];
+    let outputs = vec![utxo(checkpoint(state(3, 50, 50)))];
+
+    assert_eq!(check(checker, vec![], peeks, outputs), Ok(0));
+}
+
+#[test]
+fn post_stale_checkpoint_fails() {
+    let checker = PostCheckpoint::<0> {
+        state: sign_state(state(3, 50, 50)),
+    };
+    let peeks = vec![utxo(channel(state(4, 70, 30), Some(10)))];
+    let outputs = vec![utxo(checkpoint(state(3, 50, 50)))];
+
+    assert_eq!(
+        check(checker, vec![], peeks, outputs),
+        Err(ConstraintCheckerError::StaleState)
+    );
+}
+
+#[test]
+fn post_checkpoint_with_different_state_fails() {
+    let checker = PostCheckpoint::<0> {
+        state: sign_state(state(3, 50, 50)),
+    };
+    let peeks = vec![utxo(open_channel())];
+    let outputs = vec![utxo(checkpoint(state(3, 100, 0)))];
+
+    assert_eq!(
+        check(checker, vec![], peeks, outputs),
+        Err(ConstraintCheckerError::CheckpointMismatch)
+    );
+}
+
+#[test]
+fn post_checkpoint_signed_by_one_player_fails() {
+    let message = terms().state_message(&state(3, 100, 0));
+    let checker = PostCheckpoint::<0> {
+        state: SignedGameState {
+            state: state(3, 100, 0),
+            signature_a: alice().sign(&message),
+            signature_b: alice().sign(&message),
+        },
+    };
+    let peeks = vec![utxo(open_channel())];
+    let outputs = vec![utxo(checkpoint(state(3, 100, 0)))];
+
+    assert_eq!(
+        check(checker, vec![], peeks, outputs),
+        Err(ConstraintCheckerError::BadSignature)
+    );
+}
+
+#[test]
+fn post_checkpoint_without_channel_fails() {
+    let checker = PostCheckpoint::<0> {
+        state: sign_state(state(3, 50, 50)),
+    };
+    let outputs = vec![utxo(checkpoint(state(3, 50, 50)))];
+
+    assert_eq!(
+        check(checker, vec![], vec![], outputs),
+        Err(ConstraintCheckerError::WrongNumberPeeks)
+    );
+}
+
+#[test]
+fn start_dispute_works() {
+    let checker = StartDispute::<AtHeight<5>, 0>::new(Some(sign_state(state(3, 50, 50))));
+    let inputs = vec![utxo(open_channel())];
+    let outputs = vec![utxo(channel(state(3, 50, 50), Some(15)))];
+
+    assert_eq!(check(checker, inputs, vec![], outputs), Ok(0));
+}
+
+#[test]
+fn start_dispute_from_initial_state_works() {
+    let checker = StartDispute::<AtHeight<5>, 0>::new(None);
+    let inputs = vec![utxo(open_channel())];
+    let outputs = vec![utxo(channel(initial_state(), Some(15)))];
+
+    assert_eq!(check(checker, inputs, vec![], outputs), Ok(0));
+}
+
+#[test]
+fn start_dispute_from_newest_checkpoint_works() {
+    let checker = StartDispute::<AtHeight<5>, 0>::new(None);
+    let inputs = vec![utxo(open_channel())];
+    let peeks = vec![
+        utxo(checkpoint(state(2, 30, 70))),
+        utxo(checkpoint(state(4, 90, 10))),
+        utxo(checkpoint(state(3, 50, 50))),
+    ];
+    let outputs = vec![utxo(channel(state(4, 90, 10), Some(15)))];
+
+    assert_eq!(check(checker, inputs, peeks, outputs), Ok(0));
+}
+
+#[test]
+fn start_dispute_with_state_and_checkpoint_fails() {
+    let checker = StartDispute::<AtHeight<5>, 0>::new(Some(sign_state(state(3, 50, 50))));
+    let inputs = vec![utxo(open_channel())];
+    let peeks = vec![utxo(checkpoint(state(4, 90, 10)))];
+    let outputs = vec![utxo(channel(state(3, 50, 50), Some(15)))];
+
+    assert_eq!(
+        check(checker, inputs, peeks, outputs),
+        Err(ConstraintCheckerError::WrongNumberPeeks)
+    );
+}
+
+#[test]
+fn start_dispute_from_other_channels_checkpoint_fails() {
+    let checker = StartDispute::<AtHeight<5>, 0>::new(None);
+    let inputs = vec![utxo(open_channel())];
+    let other = Checkpoint::<0> {
+        terms: GameTerms { salt: 1, ..terms() },
+        state: state(4, 90, 10),
+    };
+    let outputs = vec![utxo(channel(state(4, 90, 10), Some(15)))];
+
+    assert_eq!(
+        check(checker, inputs, vec![utxo(other)], outputs),
+        Err(ConstraintCheckerError::CheckpointMismatch)
+    );
+}
+
+#[test]
+fn start_dispute_peeking_at_bogus_fails() {
+    let checker = StartDispute::<AtHeight<5>, 0>::new(None);
+    let inputs = vec![utxo(open_channel())];
+    let outputs = vec![utxo(channel(initial_state(), Some(15)))];
+
+    assert_eq!(
+        check(checker, inputs, vec![utxo(Bogus)], outputs),
+        Err(ConstraintCheckerError::BadlyTypedPeek)
+    );
+}
+
+#[test]
+fn start_dispute_with_wrong_deadline_fails() {
+    let checker = StartDispute::<AtHeight<5>, 0>::new(Some(sign_state(state(3, 50, 50))));
+    let inputs = vec![utxo(open_channel())];
+    let outputs = vec![utxo(channel(state(3, 50, 50), Some(100)))];
+
+    assert_eq!(
+        check(checker, inputs, vec![], outputs),
+        Err(ConstraintCheckerError::ChannelMismatch)
+    );
+}
+
+#[test]
+fn start_dispute_with_unbalanced_state_fails() {
+    let checker = StartDispute::<AtHeight<5>, 0>::new(Some(sign_state(state(3, 80, 40))));
+    let inputs = vec![utxo(open_channel())];
+    let outputs = vec![utxo(channel(state(3, 80, 40), Some(15)))];
+
+    assert_eq!(
+        check(checker, inputs, vec![], outputs),
+        Err(ConstraintCheckerError::BalancesDoNotMatchStakes)
+    );
+}
+
+#[test]
+fn start_dispute_twice_fails() {
+    let checker = StartDispute::<AtHeight<5>, 0>::new(Some(sign_state(state(3, 50, 50))));
+    let inputs = vec![utxo(channel(state(2, 30, 70), Some(12)))];
+    let outputs = vec![utxo(channel(state(3, 50, 50), Some(15)))];
+
+    assert_eq!(
+        check(checker, inputs, vec![], outputs),
+        Err(ConstraintCheckerError::AlreadyInDispute)
+    );
+}
+
+#[test]
+fn challenge_works() {
+    let checker = Challenge::<AtHeight<14>, 0>::new(Some(sign_state(state(5, 20, 80))));
+    let inputs = vec![utxo(channel(state(3, 50, 50), Some(15)))];
+    let outputs = vec![utxo(channel(state(5, 20, 80), Some(15)))];
+
+    assert_eq!(check(checker, inputs, vec![], outputs), Ok(0));
+}
+
+#[test]
+fn challenge_from_checkpoint_works() {
+    let checker = Challenge::<AtHeight<14>, 0>::new(None);
+    let inputs = vec![utxo(channel(state(3, 50, 50), Some(15)))];
+    let peeks = vec![utxo(checkpoint(state(5, 20, 80)))];
+    let outputs = vec![utxo(channel(state(5, 20, 80), Some(15)))];
+
+    assert_eq!(check(checker, inputs, peeks, outputs), Ok(0));
+}
+
+#[test]
+fn challenge_without_state_fails() {
+    let checker = Challenge::<AtHeight<14>, 0>::new(None);
+    let inputs = vec![utxo(channel(state(3, 50, 50), Some(15)))];
+    let outputs = vec![utxo(channel(state(3, 50, 50), Some(15)))];
+
+    assert_eq!(
+        check(checker, inputs, vec![], outputs),
+        Err(ConstraintCheckerError::NoStateSubmitted)
+    );
+}
+
+#[test]
+fn challenge_with_stale_state_fails() {
+    let checker = Challenge::<AtHeight<14>, 0>::new(Some(sign_state(state(3, 20, 80))));
+    let inputs = vec![utxo(channel(state(3, 50, 50), Some(15)))];
+    let outputs = vec![utxo(channel(state(3, 20, 80), Some(15)))];
+
+    assert_eq!(
+        check(checker, inputs, vec![], outputs),
+        Err(ConstraintCheckerError::StaleState)
+    );
+}
+
+#[test]
+fn challenge_after_dispute_window_fails() {
+    let checker = Challenge::<AtHeight<15>, 0>::new(Some(sign_state(state(5, 20, 80))));
+    let inputs = vec![utxo(channel(state(3, 50, 50), Some(15)))];
+    let outputs = vec![utxo(channel(state(5, 20, 80), Some(15)))];
+
+    assert_eq!(
+        check(checker, inputs, vec![], outputs),
+        Err(ConstraintCheckerError::DisputeWindowOver)
+    );
+}
+
+#[test]
+fn challenge_extending_dispute_window_fails() {
+    let checker = Challenge::<AtHeight<14>, 0>::new(Some(sign_state(state(5, 20, 80))));
+    let inputs = vec![utxo(channel(state(3, 50, 50), Some(15)))];
+    let outputs = vec![utxo(channel(state(5, 20, 80), Some(24)))];
+
+    assert_eq!(
+        check(checker, inputs, vec![], outputs),
+        Err(ConstraintCheckerError::ChannelMismatch)
+    );
+}
+
+#[test]
+fn challenge_open_channel_fails() {
+    let checker = Challenge::<AtHeight<14>, 0>::new(Some(sign_state(state(5, 20, 80))));
+    let inputs = vec![utxo(open_channel())];
+    let outputs = vec![utxo(channel(state(5, 20, 80), None))];
+
+    assert_eq!(
+        check(checker, inputs, vec![], outputs),
+        Err(ConstraintCheckerError::NotInDispute)
+    );
+}
+
+#[test]
+fn settle_works() {
+    let inputs = vec![utxo(channel(state(5, 20, 80), Some(15)))];
+    let outputs = vec![
+        (Coin::<0>(20), owned_by(&alice())).into(),
+        (Coin::<0>(80), owned_by(&bob())).into(),
+    ];
+
+    assert_eq!(
+        check(
+            SettleChannel::<AtHeight<15>, 0>::default(),
+            inputs,
+            vec![],
+            outputs
+        ),
+        Ok(0)
+    );
+}
+
+#[test]
+fn settle_removing_checkpoints_works() {
+    let inputs = vec![
+        utxo(channel(state(5, 20, 80), Some(15))),
+        utxo(checkpoint(state(5, 20, 80))),
+        utxo(checkpoint(state(4, 90, 10))),
+    ];
+    let outputs = vec![(Coin::<0>(80), owned_by(&bob())).into()];
+
+    assert_eq!(
+        check(
+            SettleChannel::<AtHeight<15>, 0>::default(),
+            inputs,
+            vec![],
+            outputs
+        ),
+        Ok(20)
+    );
+}
+
+#[test]
+fn settle_removing_other_channels_checkpoint_fails() {
+    let other = Checkpoint::<0> {
+        terms: GameTerms { salt: 1, ..terms() },
+        state: state(4, 90, 10),
+    };
+    let inputs = vec![utxo(channel(state(5, 20, 80), Some(15))), utxo(other)];
+    let outputs = vec![(Coin::<0>(80), owned_by(&bob())).into()];
+
+    assert_eq!(
+        check(
+            SettleChannel::<AtHeight<15>, 0>::default(),
+            inputs,
+            vec![],
+            outputs
+        ),
+        Err(ConstraintCheckerError::CheckpointMismatch)
+    );
+}
+
+#[test]
+fn settle_during_dispute_window_fails() {
+    let inputs = vec![utxo(channel(state(5, 20, 80), Some(15)))];
+    let outputs = vec![(Coin::<0>(80), owned_by(&bob())).into()];
+
+    assert_eq!(
+        check(
+            SettleChannel::<AtHeight<14>, 0>::default(),
+            inputs,
+            vec![],
+            outputs
+        ),
+        Err(ConstraintCheckerError::DisputeWindowNotOver)
+    );
+}
+
+#[test]
+fn settle_open_channel_fails() {
+    let inputs = vec![utxo(open_channel())];
+    let outputs = vec![(Coin::<0>(60), owned_by(&alice())).into()];
+
+    assert_eq!(
+        check(
+            SettleChannel::<AtHeight<15>, 0>::default(),
+            inputs,
+            vec![],
+            outputs
+        ),
+        Err(ConstraintCheckerError::NotInDispute)
+    );
+}
+
+#[test]
+fn settle_overpaying_fails() {
+    let inputs = vec![utxo(channel(state(5, 20, 80), Some(15)))];
+    let outputs = vec![(Coin::<0>(21), owned_by(&alice())).into()];
+
+    assert_eq!(
+        check(
+            SettleChannel::<AtHeight<15>, 0>::default(),
+            inputs,
+            vec![],
+            outputs
+        ),
+        Err(ConstraintCheckerError::Overpaid)
+    );
+}
+
+#[test]
+fn settle_paying_stranger_fails() {
+    let inputs = vec![utxo(channel(state(5, 20, 80), Some(15)))];
+    let outputs = vec![(Coin::<0>(20), owned_by(&charlie())).into()];
+
+    assert_eq!(
+        check(
+            SettleChannel::<AtHeight<15>, 0>::default(),
+            inputs,
+            vec![],
+            outputs
+        ),
+        Err(ConstraintCheckerError::PaidToWrongVerifier)
+    );
+}
+
+#[test]
+fn cooperative_close_works() {
+    let message = terms().close_message(30, 70);
+    let checker = CooperativeClose::<0> {
+        balance_a: 30,
+        balance_b: 70,
+        signature_a: alice().sign(&message),
+        signature_b: bob().sign(&message),
+    };
+    let inputs = vec![
+        utxo(channel(state(3, 50, 50), Some(15))),
+        utxo(checkpoint(state(4, 90, 10))),
+    ];
+    let outputs = vec![
+        (Coin::<0>(30), owned_by(&alice())).into(),
+        (Coin::<0>(70), owned_by(&bob())).into(),
+    ];
+
+    assert_eq!(check(checker, inputs, vec![], outputs), Ok(0));
+}
+
+#[test]
+fn cooperative_close_with_one_signature_fails() {
+    let message = terms().close_message(100, 0);
+    let checker = CooperativeClose::<0> {
+        balance_a: 100,
+        balance_b: 0,
+        signature_a: alice().sign(&message),
+        signature_b: alice().sign(&message),
+    };
+    let inputs = vec![utxo(open_channel())];
+    let outputs = vec![(Coin::<0>(100), owned_by(&alice())).into()];
+
+    assert_eq!(
+        check(checker, inputs, vec![], outputs),
+        Err(ConstraintCheckerError::BadSignature)
+    );
+}
+
+#[test]
+fn cooperative_close_with_unbalanced_balances_fails() {
+    let message = terms().close_message(100, 100);
+    let checker = CooperativeClose::<0> {
+        balance_a: 100,
+        balance_b: 100,
+        signature_a: alice().sign(&message),
+        signature_b: bob().sign(&message),
+    };
+    let inputs = vec![utxo(open_channel())];
+    let outputs = vec![(Coin::<0>(100), owned_by(&alice())).into()];
+
+    assert_eq!(
+        check(checker, inputs, vec![], outputs),
+        Err(ConstraintCheckerError::BalancesDoNotMatchStakes)
+    );
+}
+
+#[test]
+fn discard_stale_checkpoints_works() {
+    let inputs = vec![
+        utxo(checkpoint(state(3, 50, 50))),
+        utxo(checkpoint(state(5, 20, 80))),
+    ];
+    let peeks = vec![utxo(channel(state(5, 20, 80), Some(15)))];
+
+    assert_eq!(check(DiscardCheckpoints::<0>, inputs, peeks, vec![]), Ok(0));
+}
+
+#[test]
+fn discard_newer_checkpoint_fails() {
+    let inputs = vec![utxo(checkpoint(state(6, 50, 50)))];
+    let peeks = vec![utxo(channel(state(5, 20, 80), Some(15)))];
+
+    assert_eq!(
+        check(DiscardCheckpoints::<0>, inputs, peeks, vec![]),
+        Err(ConstraintCheckerError::CheckpointNotStale)
+    );
+}
+
+#[test]
+fn discard_checkpoint_without_channel_fails() {
+    let inputs = vec![utxo(checkpoint(state(3, 50, 50)))];
+
+    assert_eq!(
+        check(DiscardCheckpoints::<0>, inputs, vec![], vec![]),
+        Err(ConstraintCheckerError::WrongNumberPeeks)
+    );
+}
+
+#[test]
+fn discard_channel_fails() {
+    let inputs = vec![utxo(open_channel())];
+    let peeks = vec![utxo(open_channel())];
+
+    assert_eq!(
+        check(DiscardCheckpoints::<0>, inputs, peeks, vec![]),
+        Err(ConstraintCheckerError::BadlyTypedInput)
+    );
+}
@@ -0,0 +1,221 @@
+//! Wall-clock timelocks for the tokens provided by the money piece.
+//!
+//! A [`TimeLockedCoin`] holds some amount of `Coin<ID>` value that can not be spent until a
+//! specific time, in milliseconds since the unix epoch. This complements the core
+//! [`HeightLock`](tuxedo_core::verifier::HeightLock) verifier for schedules that users think of in
+//! calendar time rather than block heights.
+//!
+//! Coins are locked up with the [`LockCoins`] constraint checker. They are later turned back into
+//! ordinary spendable coins with the [`UnlockCoins`] constraint checker, which peeks at a
+//! `Timestamp` UTXO created by the timestamp piece's `SetTimestamp` inherent to learn the time.
+//! Any noted timestamp that is at or after the unlock time proves that the lock has expired, so
+//! the unlocking transaction may peek at whichever timestamp is convenient, typically the latest.
+//!
+//! The ownership of the locked coins is governed by their verifier as usual.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use money::Coin;
+use parity_scale_codec::{Decode, Encode};
+use scale_info::TypeInfo;
+use serde::{Deserialize, Serialize};
+use sp_runtime::transaction_validity::TransactionPriority;
+use sp_std::prelude::*;
+use timestamp::Timestamp;
+use tuxedo_core::{
+    dynamic_typing::{DynamicallyTypedData, UtxoData},
+    ensure, CheckerContext, SimpleConstraintChecker,
+};
+
+#[cfg(test)]
+mod tests;
+
+/// Some value that can not be spent before a given time.
+#[derive(
+    Serialize,
+    Deserialize,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Clone,
+    Encode,
+    Decode,
+    Hash,
+    Debug,
+    TypeInfo,
+)]
+pub struct TimeLockedCoin<const ID: u8> {
+    /// The value that is locked.
+    pub value: u128,
+    /// The time, in milliseconds since the unix epoch, from which the value may be unlocked.
+    pub unlock_time: u64,
+}
+
+impl<const ID: u8> TimeLockedCoin<ID> {
+    pub fn new(value: u128, unlock_time: u64) -> Self {
+        Self { value, unlock_time }
+    }
+}
+
+impl<const ID: u8> UtxoData for TimeLockedCoin<ID> {
+    const TYPE_ID: [u8; 4] = [b't', b'l', b'k', ID];
+}
+
+/// Errors that can occur when checking timelock transactions.
+#[derive(
+    Serialize,
+    Deserialize,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Clone,
+    Encode,
+    Decode,
+    Hash,
+    Debug,
+    TypeInfo,
+)]
+pub enum ConstraintCheckerError {
+    /// An input data has the wrong type.
+    BadlyTypedInput,
+    /// An output data has the wrong type.
+    BadlyTypedOutput,
+    /// The peeked data is not a timestamp.
+    BadlyTypedPeek,
+    /// The transaction does not consume anything.
+    NoInputs,
+    /// The transaction does not create anything, so it is a waste of processing power.
+    NothingCreated,
+    /// Unlocking requires peeking at exactly one timestamp.
+    WrongNumberPeeks,
+    /// The transaction attempted to create a coin or locked coin with zero value.
+    /// This is not allowed because it wastes state space.
+    ZeroValue,
+    /// The value consumed or created by this transaction overflows the value type.
+    ValueOverflow,
+    /// The value of the outputs exceeds the value of the inputs.
+    OutputsExceedInputs,
+    /// The peeked timestamp is before the unlock time of one of the locked coins.
+    StillLocked,
+}
+
+/// A constraint checker that locks up coins until a given time.
+///
+/// All inputs must be `Coin<ID>`s and all outputs must be `TimeLockedCoin<ID>`s. As with
+/// ordinary spends, any value that is not locked pays the transaction's fee and tip.
+#[derive(
+    Serialize, Deserialize, Encode, Decode, Debug, Default, PartialEq, Eq, Clone, TypeInfo,
+)]
+pub struct LockCoins<const ID: u8>;
+
+impl<const ID: u8> SimpleConstraintChecker for LockCoins<ID> {
+    type Error = ConstraintCheckerError;
+
+    fn check(
+        &self,
+        input_data: &[DynamicallyTypedData],
+        _peeks: &[DynamicallyTypedData],
+        output_data: &[DynamicallyTypedData],
+        _context: &CheckerContext,
+    ) -> Result<TransactionPriority, Self::Error> {
+        ensure!(
+            !output_data.is_empty(),
+            ConstraintCheckerError::NothingCreated
+        );
+
+        let mut total_input_value: u128 = 0;
+        for input in input_data {
+            let value = input
+                .extract::<Coin<ID>>()
+                .map_err(|_| ConstraintCheckerError::BadlyTypedInput)?
+                .0;
+            total_input_value = total_input_value
+                .checked_add(value)
+                .ok_or(ConstraintCheckerError::ValueOverflow)?;
+        }
+
+        let mut total_output_value: u128 = 0;
+        for output in output_data {
+            let locked = output
+                .extract::<TimeLockedCoin<ID>>()
+                .map_err(|_| ConstraintCheckerError::BadlyTypedOutput)?;
+            ensure!(locked.value > 0, ConstraintCheckerError::ZeroValue);
+            total_output_value = total_output_value
+                .checked_add(locked.value)
+                .ok_or(ConstraintCheckerError::ValueOverflow)?;
+        }
+
+        ensure!(
+            total_output_value <= total_input_value,
+            ConstraintCheckerError::OutputsExceedInputs
+        );
+
+        let burned = total_input_value - total_output_value;
+        Ok(burned.try_into().unwrap_or(TransactionPriority::MAX))
+    }
+}
+
+/// A constraint checker that turns expired time locked coins back into spendable coins.
+///
+/// All inputs must be `TimeLockedCoin<ID>`s and all outputs must be `Coin<ID>`s. There must be
+/// exactly one peek, which is a `Timestamp` at or after the unlock time of every input. Any
+/// locked value that is not turned back into coins pays the transaction's fee and tip.
+#[derive(
+    Serialize, Deserialize, Encode, Decode, Debug, Default, PartialEq, Eq, Clone, TypeInfo,
+)]
+pub struct UnlockCoins<const ID: u8>;
+
+impl<const ID: u8> SimpleConstraintChecker for UnlockCoins<ID> {
+    type Error = ConstraintCheckerError;
+
+    fn check(
+        &self,
+        input_data: &[DynamicallyTypedData],
+        peeks: &[DynamicallyTypedData],
+        output_data: &[DynamicallyTypedData],
+        _context: &CheckerContext,
+    ) -> Result<TransactionPriority, Self::Error> {
+        ensure!(!input_data.is_empty(), ConstraintCheckerError::NoInputs);
+        ensure!(peeks.len() == 1, ConstraintCheckerError::WrongNumberPeeks);
+        let now = peeks[0]
+            .extract::<Timestamp>()
+            .map_err(|_| ConstraintCheckerError::BadlyTypedPeek)?
+            .time;
+
+        let mut total_input_value: u128 = 0;
+        for input in input_data {
+            let locked = input
+                .extract::<TimeLockedCoin<ID>>()
+                .map_err(|_| ConstraintCheckerError::BadlyTypedInput)?;
+            ensure!(
+                locked.unlock_time <= now,
+                ConstraintCheckerError::StillLocked
+            );
+            total_input_value = total_input_value
+                .checked_add(locked.value)
+                .ok_or(ConstraintCheckerError::ValueOverflow)?;
+        }
+
+        let mut total_output_value: u128 = 0;
+        for output in output_data {
+            let value = output
+                .extract::<Coin<ID>>()
+                .map_err(|_| ConstraintCheckerError::BadlyTypedOutput)?
+                .0;
+            ensure!(value > 0, ConstraintCheckerError::ZeroValue);
+            total_output_value = total_output_value
+                .checked_add(value)
+                .ok_or(ConstraintCheckerError::ValueOverflow)?;
+        }
+
+        ensure!(
+            total_output_value <= total_input_value,
+            ConstraintCheckerError::OutputsExceedInputs
+        );
+
+        let burned = total_input_value - total_output_value;
+        Ok(burned.try_into().unwrap_or(TransactionPriority::MAX))
+    }
+}
@@ -0,0 +1,186 @@
+//! Unit tests for the Time Lock piece
+
+use super::*;
+use tuxedo_core::dynamic_typing::testing::Bogus;
+
+/// A timestamp noted at the given time.
+fn time(time: u64) -> DynamicallyTypedData {
+    Timestamp::new(time, 10).into()
+}
+
+#[test]
+fn lock_coins_works() {
+    let input_data = vec![Coin::<0>(700).into(), Coin::<0>(400).into()];
+    let output_data = vec![TimeLockedCoin::<0>::new(1000, 5_000).into()];
+
+    assert_eq!(
+        LockCoins::<0>.check(&input_data, &[], &output_data, &Default::default()),
+        Ok(100)
+    );
+}
+
+#[test]
+fn lock_coins_exceeding_inputs_fails() {
+    let input_data = vec![Coin::<0>(700).into()];
+    let output_data = vec![TimeLockedCoin::<0>::new(1000, 5_000).into()];
+
+    assert_eq!(
+        LockCoins::<0>.check(&input_data, &[], &output_data, &Default::default()),
+        Err(ConstraintCheckerError::OutputsExceedInputs)
+    );
+}
+
+#[test]
+fn lock_coins_zero_value_fails() {
+    let input_data = vec![Coin::<0>(700).into()];
+    let output_data = vec![TimeLockedCoin::<0>::new(0, 5_000).into()];
+
+    assert_eq!(
+        LockCoins::<0>.check(&input_data, &[], &output_data, &Default::default()),
+        Err(ConstraintCheckerError::ZeroValue)
+    );
+}
+
+#[test]
+fn lock_coins_wrong_input_type_fails() {
+    let input_data = vec![Bogus.into()];
+    let output_data = vec![TimeLockedCoin::<0>::new(1000, 5_000).into()];
+
+    assert_eq!(
+        LockCoins::<0>.check(&input_data, &[], &output_data, &Default::default()),
+        Err(ConstraintCheckerError::BadlyTypedInput)
+    );
+}
+
+#[test]
+fn lock_coins_nothing_created_fails() {
+    let input_data = vec![Coin::<0>(700).into()];
+
+    assert_eq!(
+        LockCoins::<0>.check(&input_data, &[], &[], &Default::default()),
+        Err(ConstraintCheckerError::NothingCreated)
+    );
+}
+
+#[test]
+fn unlock_coins_works() {
+    let input_data = vec![
+        TimeLockedCoin::<0>::new(600, 5_000).into(),
+        TimeLockedCoin::<0>::new(400, 4_000).into(),
+    ];
+    let output_data = vec![Coin::<0>(900).into()];
+
+    assert_eq!(
+        UnlockCoins::<0>.check(
+            &input_data,
+            &[time(6_000)],
+            &output_data,
+            &Default::default()
+        ),
+        Ok(100)
+    );
+}
+
+#[test]
+fn unlock_coins_exactly_at_unlock_time_works() {
+    let input_data = vec![TimeLockedCoin::<0>::new(1000, 5_000).into()];
+    let output_data = vec![Coin::<0>(1000).into()];
+
+    assert_eq!(
+        UnlockCoins::<0>.check(
+            &input_data,
+            &[time(5_000)],
+            &output_data,
+            &Default::default()
+        ),
+        Ok(0)
+    );
+}
+
+#[test]
+fn unlock_coins_too_early_fails() {
+    let input_data = vec![
+        TimeLockedCoin::<0>::new(600, 5_000).into(),
+        TimeLockedCoin::<0>::new(400, 7_000).into(),
+    ];
+    let output_data = vec![Coin::<0>(1000).into()];
+
+    assert_eq!(
+        UnlockCoins::<0>.check(
+            &input_data,
+            &[time(6_000)],
+            &output_data,
+            &Default::default()
+        ),
+        Err(ConstraintCheckerError::StillLocked)
+    );
+}
+
+#[test]
+fn unlock_coins_without_timestamp_fails() {
+    let input_data = vec![TimeLockedCoin::<0>::new(1000, 5_000).into()];
+    let output_data = vec![Coin::<0>(1000).into()];
+
+    assert_eq!(
+        UnlockCoins::<0>.check(&input_data, &[], &output_data, &Default::default()),
+        Err(ConstraintCheckerError::WrongNumberPeeks)
+    );
+}
+
+#[test]
+fn unlock_coins_peeking_non_timestamp_fails() {
+    let input_data = vec![TimeLockedCoin::<0>::new(1000, 5_000).into()];
+    let output_data = vec![Coin::<0>(1000).into()];
+
+    assert_eq!(
+        UnlockCoins::<0>.check(
+            &input_data,
+            &[Coin::<0>(6_000).into()],
+            &output_data,
+            &Default::default()
+        ),
+        Err(ConstraintCheckerError::BadlyTypedPeek)
+    );
+}
+
+#[test]
+fn unlock_coins_exceeding_inputs_fails() {
+    let input_data = vec![TimeLockedCoin::<0>::new(1000, 5_000).into()];
+    let output_data = vec![Coin::<0>(1001).into()];
+
+    assert_eq!(
+        UnlockCoins::<0>.check(
+            &input_data,
+            &[time(6_000)],
+            &output_data,
+            &Default::default()
+        ),
+        Err(ConstraintCheckerError::OutputsExceedInputs)
+    );
+}
+
+#[test]
+fn unlock_coins_wrong_input_type_fails() {
+    let input_data = vec![Coin::<0>(1000).into()];
+    let output_data = vec![Coin::<0>(1000).into()];
+
+    assert_eq!(
+        UnlockCoins::<0>.check(
+            &input_data,
+            &[time(6_000)],
+            &output_data,
+            &Default::default()
+        ),
+        Err(ConstraintCheckerError::BadlyTypedInput)
+    );
+}
+
+#[test]
+fn unlock_coins_no_inputs_fails() {
+    let output_data = vec![Coin::<0>(1000).into()];
+
+    assert_eq!(
+        UnlockCoins::<0>.check(&[], &[time(6_000)], &output_data, &Default::default()),
+        Err(ConstraintCheckerError::NoInputs)
+    );
+}
@@ -2,17 +2,21 @@
 //! This module tests the secondary flow of cleaning up old timestamps.
 
 use super::{
-    CleanUpTimestamp, SimpleConstraintChecker, Timestamp, TimestampConfig, TimestampError,
+    CheckerContext, CleanUpTimestamp, SimpleConstraintChecker, Timestamp, TimestampConfig,
+    TimestampError,
 };
 use tuxedo_core::dynamic_typing::testing::Bogus;
 use TimestampError::*;
 
-/// The mock config always says the block number is one million.
+/// The mock config. The checkers in these tests always run at block one million.
 pub struct AlwaysBlockMillion;
 
-impl TimestampConfig for AlwaysBlockMillion {
-    fn block_height() -> u32 {
-        1_000_000
+impl TimestampConfig for AlwaysBlockMillion {}
+
+fn block_million() -> CheckerContext {
+    CheckerContext {
+        block_height: 1_000_000,
+        ..Default::default()
     }
 }
 
@@ -28,7 +32,7 @@ fn cleanup_timestamp_happy_path() {
     let peek = vec![newer.into()];
 
     assert_eq!(
-        CleanUpTimestamp::<AlwaysBlockMillion>::default().check(&inp, &peek, &[]),
+        CleanUpTimestamp::<AlwaysBlockMillion>::default().check(&inp, &peek, &[], &block_million()),
         Ok(0),
     );
 }
@@ -39,7 +43,7 @@ fn cleanup_timestamp_no_peek() {
     let inp = vec![old.into()];
 
     assert_eq!(
-        CleanUpTimestamp::<AlwaysBlockMillion>::default().check(&inp, &[], &[]),
+        CleanUpTimestamp::<AlwaysBlockMillion>::default().check(&inp, &[], &[], &block_million()),
         Err(CleanupRequiresOneReference)
     );
 }
@@ -56,7 +60,7 @@ fn cleanup_timestamp_input_newer_than_reference() {
     let peek = vec![old.into()];
 
     assert_eq!(
-        CleanUpTimestamp::<AlwaysBlockMillion>::default().check(&inp, &peek, &[]),
+        CleanUpTimestamp::<AlwaysBlockMillion>::default().check(&inp, &peek, &[], &block_million()),
         Err(DontBeSoHasty)
     );
 }
@@ -73,7 +77,7 @@ fn cleanup_timestamp_input_not_yet_ripe_for_cleaning() {
     let peek = vec![newer.into()];
 
     assert_eq!(
-        CleanUpTimestamp::<AlwaysBlockMillion>::default().check(&inp, &peek, &[]),
+        CleanUpTimestamp::<AlwaysBlockMillion>::default().check(&inp, &peek, &[], &block_million()),
         Err(DontBeSoHasty)
     );
 }
@@ -91,7 +95,7 @@ fn cleanup_timestamp_multiple_happy_path() {
     let peek = vec![newer.into()];
 
     assert_eq!(
-        CleanUpTimestamp::<AlwaysBlockMillion>::default().check(&inp, &peek, &[]),
+        CleanUpTimestamp::<AlwaysBlockMillion>::default().check(&inp, &peek, &[], &block_million()),
         Ok(0),
     );
 }
@@ -110,7 +114,7 @@ fn cleanup_timestamp_missing_input() {
     let peek = vec![newer.into()];
 
     assert_eq!(
-        CleanUpTimestamp::<AlwaysBlockMillion>::default().check(&[], &peek, &[]),
+        CleanUpTimestamp::<AlwaysBlockMillion>::default().check(&[], &peek, &[], &block_million()),
         Ok(0),
     );
 }
@@ -131,7 +135,7 @@ fn cleanup_timestamp_multiple_first_valid_second_invalid() {
     let peek = vec![newer.into()];
 
     assert_eq!(
-        CleanUpTimestamp::<AlwaysBlockMillion>::default().check(&inp, &peek, &[]),
+        CleanUpTimestamp::<AlwaysBlockMillion>::default().check(&inp, &peek, &[], &block_million()),
         Err(DontBeSoHasty)
     );
 }
@@ -148,7 +152,7 @@ fn cleanup_timestamp_input_is_wrong_type() {
     let peek = vec![newer.into()];
 
     assert_eq!(
-        CleanUpTimestamp::<AlwaysBlockMillion>::default().check(&inp, &peek, &[]),
+        CleanUpTimestamp::<AlwaysBlockMillion>::default().check(&inp, &peek, &[], &block_million()),
         Err(BadlyTyped)
     );
 }
@@ -161,7 +165,7 @@ fn cleanup_timestamp_reference_is_wrong_type() {
     let peek = vec![Bogus.into()];
 
     assert_eq!(
-        CleanUpTimestamp::<AlwaysBlockMillion>::default().check(&inp, &peek, &[]),
+        CleanUpTimestamp::<AlwaysBlockMillion>::default().check(&inp, &peek, &[], &block_million()),
         Err(BadlyTyped)
     );
 }
@@ -179,7 +183,12 @@ fn cleanup_timestamp_cannot_create_state() {
     let out = vec![Bogus.into()];
 
     assert_eq!(
-        CleanUpTimestamp::<AlwaysBlockMillion>::default().check(&inp, &peek, &out),
+        CleanUpTimestamp::<AlwaysBlockMillion>::default().check(
+            &inp,
+            &peek,
+            &out,
+            &block_million()
+        ),
         Err(CleanupCannotCreateState)
     );
 }
@@ -5,6 +5,11 @@
 //!
 //! In each block, the block author must include a single `SetTimestamp` transaction that peeks at the
 //! Timestamp UTXO that was created in the previous block, and creates a new one with an updated timestamp.
+//! The runtime configures how far the new timestamp must, and may, be ahead of the previous one.
+//!
+//! The timestamps of old blocks stay in the UTXO set until anyone removes them with a
+//! `CleanUpTimestamp` transaction, which is allowed once they are older than a configured age
+//! and depth. This keeps the number of stored timestamps bounded.
 //!
 //! This piece currently features a prominent hack which will need to be cleaned up in due course.
 //! It abuses the UpForGrabs verifier. This should be replaced with an Unspendable verifier and an eviction workflow.
@@ -26,9 +31,9 @@ use tuxedo_core::{
     ensure,
     inherents::{TuxedoInherent, TuxedoInherentAdapter},
     support_macros::{CloneNoBound, DebugNoBound, DefaultNoBound},
-    types::{Output, OutputRef, Transaction},
-    verifier::UpForGrabs,
-    ConstraintChecker, SimpleConstraintChecker, Verifier,
+    types::{Output, OutputRef, Transaction, TransactionVersion},
+    verifier::{note_time, UpForGrabs},
+    CheckerContext, ConstraintChecker, SimpleConstraintChecker, Verifier,
 };
 
 #[cfg(test)]
@@ -41,7 +46,7 @@ const LOG_TARGET: &str = "timestamp-piece";
 
 /// A timestamp, since the unix epoch, noted at some point in the history of the chain.
 /// It also records the block height in which it was included.
-#[derive(Debug, Encode, Decode, PartialEq, Eq, Clone, Copy, Default, PartialOrd, Ord)]
+#[derive(Debug, Encode, Decode, PartialEq, Eq, Clone, Copy, Default, PartialOrd, Ord, TypeInfo)]
 pub struct Timestamp {
     /// The time, in milliseconds, since the unix epoch.
     pub time: u64,
@@ -60,17 +65,23 @@ impl Timestamp {
 }
 
 /// Options to configure the timestamp piece in your runtime.
-/// Currently we only need access to a block number.
 pub trait TimestampConfig {
-    /// A means of getting the current block height.
-    /// Probably this will be the Tuxedo Executive
-    fn block_height() -> u32;
-
     /// The minimum amount of time by which the timestamp may be updated.
     ///
     /// The default is 2 seconds which should be slightly lower than most chains' block times.
     const MINIMUM_TIME_INTERVAL: u64 = 2_000;
 
+    /// The maximum amount of time by which the timestamp may be updated.
+    ///
+    /// Unlike `MAX_DRIFT`, which each importing node checks against its own clock, this is checked
+    /// on chain, so it bounds how far any author can push the chain's time ahead in a single block.
+    /// Authors whose clocks are further ahead note the latest time that is allowed instead. Keep it
+    /// well above the block time, or a chain that stalls will lag behind real time for a long while
+    /// after it resumes.
+    ///
+    /// The default is no limit.
+    const MAXIMUM_TIME_INTERVAL: u64 = u64::MAX;
+
     /// The maximum amount by which a valid block's timestamp may be ahead of an importing
     /// node's current local time.
     ///
@@ -110,6 +121,8 @@ pub enum TimestampError {
     InputsWhileSettingTimestamp,
     /// The new timestamp is not sufficiently far after the previous (or may even be before it).
     TimestampTooOld,
+    /// The new timestamp is further after the previous one than the maximum interval allows.
+    TimestampTooFarAhead,
     /// When cleaning up old timestamps, you must supply exactly one peek input which is the "new time reference"
     /// All the timestamps that will be cleaned up must be at least the CLEANUP_AGE older than this reference.
     CleanupRequiresOneReference,
@@ -158,6 +171,7 @@ impl<T: TimestampConfig + 'static, V: Verifier + From<UpForGrabs>> ConstraintChe
         input_data: &[tuxedo_core::types::Output<V>],
         peek_data: &[tuxedo_core::types::Output<V>],
         output_data: &[tuxedo_core::types::Output<V>],
+        context: &CheckerContext,
     ) -> Result<TransactionPriority, Self::Error> {
         log::debug!(
             target: LOG_TARGET,
@@ -183,7 +197,7 @@ impl<T: TimestampConfig + 'static, V: Verifier + From<UpForGrabs>> ConstraintChe
 
         // Make sure the block height from this timestamp matches the current block height.
         ensure!(
-            new_timestamp.block == T::block_height(),
+            new_timestamp.block == context.block_height,
             Self::Error::NewTimestampWrongHeight,
         );
 
@@ -197,9 +211,13 @@ impl<T: TimestampConfig + 'static, V: Verifier + From<UpForGrabs>> ConstraintChe
 
         // Compare the new timestamp to the previous timestamp
         ensure!(
-            new_timestamp.time >= old_timestamp.time + T::MINIMUM_TIME_INTERVAL,
+            new_timestamp.time >= old_timestamp.time.saturating_add(T::MINIMUM_TIME_INTERVAL),
             Self::Error::TimestampTooOld
         );
+        ensure!(
+            new_timestamp.time <= old_timestamp.time.saturating_add(T::MAXIMUM_TIME_INTERVAL),
+            Self::Error::TimestampTooFarAhead
+        );
 
         // Make sure the block height from the previous timestamp matches the previous block height.
         ensure!(
@@ -207,6 +225,9 @@ impl<T: TimestampConfig + 'static, V: Verifier + From<UpForGrabs>> ConstraintChe
             Self::Error::PreviousTimestampWrongHeight,
         );
 
+        // Make the new time available to verifiers.
+        note_time(new_timestamp.time, new_timestamp.block);
+
         Ok(0)
     }
 
@@ -229,9 +250,18 @@ impl<V: Verifier + From<UpForGrabs>, T: TimestampConfig + 'static> TuxedoInheren
             .get_data(&sp_timestamp::INHERENT_IDENTIFIER)
             .expect("Inherent data should decode properly")
             .expect("Timestamp inherent data should be present.");
+        // The timestamp is updated in every block, so the new block is the one after the previous timestamp's.
+        let previous_timestamp = previous_inherent.0.outputs[0]
+            .payload
+            .extract::<Timestamp>()
+            .expect("The previous timestamp inherent should have an output that decodes as a Timestamp.");
+        // A clock that runs too far ahead of the previous timestamp would make the block invalid.
+        let latest_allowed = previous_timestamp
+            .time
+            .saturating_add(T::MAXIMUM_TIME_INTERVAL);
         let new_timestamp = Timestamp {
-            time: current_timestamp,
-            block: T::block_height(),
+            time: current_timestamp.min(latest_allowed),
+            block: previous_timestamp.block + 1,
         };
 
         log::debug!(
@@ -253,10 +283,13 @@ impl<V: Verifier + From<UpForGrabs>, T: TimestampConfig + 'static> TuxedoInheren
         };
 
         Transaction {
+            version: TransactionVersion::LATEST,
             inputs: Vec::new(),
             peeks: vec![old_output],
             outputs: vec![new_output],
             checker: Self::default(),
+            memo: None,
+            valid_until: None,
         }
     }
 
@@ -312,6 +345,7 @@ impl<V: Verifier + From<UpForGrabs>, T: TimestampConfig + 'static> TuxedoInheren
             .as_millis() as u64;
 
         vec![Transaction {
+            version: TransactionVersion::LATEST,
             inputs: Vec::new(),
             peeks: Vec::new(),
             outputs: vec![Output {
@@ -319,6 +353,8 @@ impl<V: Verifier + From<UpForGrabs>, T: TimestampConfig + 'static> TuxedoInheren
                 verifier: UpForGrabs.into(),
             }],
             checker: Self::default(),
+            memo: None,
+            valid_until: None,
         }]
     }
 }
@@ -341,6 +377,7 @@ impl<V: Verifier + From<UpForGrabs>, T: TimestampConfig + 'static> TuxedoInheren
     CloneNoBound,
     TypeInfo,
 )]
+#[scale_info(skip_type_params(T))]
 pub struct CleanUpTimestamp<T>(PhantomData<T>);
 
 impl<T: TimestampConfig> SimpleConstraintChecker for CleanUpTimestamp<T> {
@@ -351,6 +388,7 @@ impl<T: TimestampConfig> SimpleConstraintChecker for CleanUpTimestamp<T> {
         input_data: &[DynamicallyTypedData],
         peek_data: &[DynamicallyTypedData],
         output_data: &[DynamicallyTypedData],
+        context: &CheckerContext,
     ) -> Result<TransactionPriority, Self::Error> {
         // Make sure there at least one peek that is the new reference time.
         // We don't expect any additional peeks typically, but as above, they are harmless.
@@ -376,11 +414,17 @@ impl<T: TimestampConfig> SimpleConstraintChecker for CleanUpTimestamp<T> {
                 .map_err(|_| Self::Error::BadlyTyped)?;
 
             ensure!(
-                old_timestamp.time + T::MIN_TIME_BEFORE_CLEANUP < new_reference_timestamp.time,
+                old_timestamp
+                    .time
+                    .saturating_add(T::MIN_TIME_BEFORE_CLEANUP)
+                    < new_reference_timestamp.time,
                 Self::Error::DontBeSoHasty
             );
             ensure!(
-                old_timestamp.block + T::MIN_BLOCKS_BEFORE_CLEANUP < T::block_height(),
+                old_timestamp
+                    .block
+                    .saturating_add(T::MIN_BLOCKS_BEFORE_CLEANUP)
+                    < context.block_height,
                 Self::Error::DontBeSoHasty
             );
         }
@@ -2,15 +2,19 @@
 //! This module tests the primary flow of updating the timestamp via an inherent after it has been initialized.
 
 use super::*;
-use tuxedo_core::dynamic_typing::testing::Bogus;
+use sp_io::TestExternalities;
+use tuxedo_core::{dynamic_typing::testing::Bogus, verifier::best_time};
 use TimestampError::*;
 
-/// The mock config always says the block number is two.
+/// The mock config. The checkers in these tests always run at block two.
 pub struct AlwaysBlockTwo;
 
-impl TimestampConfig for AlwaysBlockTwo {
-    fn block_height() -> u32 {
-        2
+impl TimestampConfig for AlwaysBlockTwo {}
+
+fn block_two() -> CheckerContext {
+    CheckerContext {
+        block_height: 2,
+        ..Default::default()
     }
 }
 
@@ -23,7 +27,11 @@ fn update_timestamp_happy_path() {
     let new: DynamicallyTypedData = Timestamp::new(3_000, 2).into();
     let out: Vec<Output<UpForGrabs>> = vec![new.into()];
 
-    assert_eq!(checker.check(&[], &peek, &out), Ok(0));
+    TestExternalities::default().execute_with(|| {
+        assert_eq!(checker.check(&[], &peek, &out, &block_two()), Ok(0));
+        // The new time is available to verifiers
+        assert_eq!(best_time(), Some((3_000, 2)));
+    });
 }
 
 #[test]
@@ -38,7 +46,7 @@ fn update_timestamp_with_input() {
     let out: Vec<Output<UpForGrabs>> = vec![new.into()];
 
     assert_eq!(
-        checker.check(&inp, &peek, &out),
+        checker.check(&inp, &peek, &out, &block_two()),
         Err(InputsWhileSettingTimestamp)
     );
 }
@@ -52,7 +60,10 @@ fn update_timestamp_bogus_peek() {
     let new: DynamicallyTypedData = Timestamp::new(3_000, 2).into();
     let out: Vec<Output<UpForGrabs>> = vec![new.into()];
 
-    assert_eq!(checker.check(&[], &peek, &out), Err(BadlyTyped));
+    assert_eq!(
+        checker.check(&[], &peek, &out, &block_two()),
+        Err(BadlyTyped)
+    );
 }
 
 #[test]
@@ -62,7 +73,10 @@ fn update_timestamp_no_peek() {
     let new: DynamicallyTypedData = Timestamp::new(3_000, 2).into();
     let out: Vec<Output<UpForGrabs>> = vec![new.into()];
 
-    assert_eq!(checker.check(&[], &[], &out), Err(MissingPreviousTimestamp));
+    assert_eq!(
+        checker.check(&[], &[], &out, &block_two()),
+        Err(MissingPreviousTimestamp)
+    );
 }
 
 #[test]
@@ -72,7 +86,10 @@ fn update_timestamp_no_output() {
     let old: DynamicallyTypedData = Timestamp::new(1_000, 1).into();
     let peek: Vec<Output<UpForGrabs>> = vec![old.into()];
 
-    assert_eq!(checker.check(&[], &peek, &[]), Err(MissingNewTimestamp));
+    assert_eq!(
+        checker.check(&[], &peek, &[], &block_two()),
+        Err(MissingNewTimestamp)
+    );
 }
 
 #[test]
@@ -86,7 +103,7 @@ fn update_timestamp_too_many_outputs() {
     let out: Vec<Output<UpForGrabs>> = vec![new.into(), bogus.into()];
 
     assert_eq!(
-        checker.check(&[], &peek, &out),
+        checker.check(&[], &peek, &out, &block_two()),
         Err(TooManyOutputsWhileSettingTimestamp)
     );
 }
@@ -101,7 +118,7 @@ fn update_timestamp_wrong_height() {
     let out: Vec<Output<UpForGrabs>> = vec![new.into()];
 
     assert_eq!(
-        checker.check(&[], &peek, &out),
+        checker.check(&[], &peek, &out, &block_two()),
         Err(NewTimestampWrongHeight)
     );
 }
@@ -115,7 +132,10 @@ fn update_timestamp_output_earlier_than_input() {
     let new: DynamicallyTypedData = Timestamp::new(1_000, 2).into();
     let out: Vec<Output<UpForGrabs>> = vec![new.into()];
 
-    assert_eq!(checker.check(&[], &peek, &out), Err(TimestampTooOld));
+    assert_eq!(
+        checker.check(&[], &peek, &out, &block_two()),
+        Err(TimestampTooOld)
+    );
 }
 
 #[test]
@@ -127,7 +147,10 @@ fn update_timestamp_output_newer_than_previous_best_nut_not_enough_to_meet_thres
     let new: DynamicallyTypedData = Timestamp::new(2_000, 2).into();
     let out: Vec<Output<UpForGrabs>> = vec![new.into()];
 
-    assert_eq!(checker.check(&[], &peek, &out), Err(TimestampTooOld));
+    assert_eq!(
+        checker.check(&[], &peek, &out, &block_two()),
+        Err(TimestampTooOld)
+    );
 }
 
 #[test]
@@ -140,7 +163,72 @@ fn update_timestamp_previous_timestamp_wrong_height() {
     let out: Vec<Output<UpForGrabs>> = vec![new.into()];
 
     assert_eq!(
-        checker.check(&[], &peek, &out),
+        checker.check(&[], &peek, &out, &block_two()),
         Err(PreviousTimestampWrongHeight)
     );
 }
+
+/// A mock config that lets the timestamp move ahead by at most five seconds per block.
+pub struct FiveSecondsAtMost;
+
+impl TimestampConfig for FiveSecondsAtMost {
+    const MAXIMUM_TIME_INTERVAL: u64 = 5_000;
+}
+
+#[test]
+fn update_timestamp_by_maximum_interval_works() {
+    let checker = SetTimestamp::<FiveSecondsAtMost>(Default::default());
+
+    let old: DynamicallyTypedData = Timestamp::new(1_000, 1).into();
+    let peek: Vec<Output<UpForGrabs>> = vec![old.into()];
+    let new: DynamicallyTypedData = Timestamp::new(6_000, 2).into();
+    let out: Vec<Output<UpForGrabs>> = vec![new.into()];
+
+    TestExternalities::default().execute_with(|| {
+        assert_eq!(checker.check(&[], &peek, &out, &block_two()), Ok(0));
+    });
+}
+
+#[test]
+fn update_timestamp_beyond_maximum_interval_fails() {
+    let checker = SetTimestamp::<FiveSecondsAtMost>(Default::default());
+
+    let old: DynamicallyTypedData = Timestamp::new(1_000, 1).into();
+    let peek: Vec<Output<UpForGrabs>> = vec![old.into()];
+    let new: DynamicallyTypedData = Timestamp::new(6_001, 2).into();
+    let out: Vec<Output<UpForGrabs>> = vec![new.into()];
+
+    assert_eq!(
+        checker.check(&[], &peek, &out, &block_two()),
+        Err(TimestampTooFarAhead)
+    );
+}
+
+#[test]
+fn inherent_caps_a_clock_that_runs_ahead() {
+    let previous = Transaction::<UpForGrabs, SetTimestamp<FiveSecondsAtMost>> {
+        version: TransactionVersion::LATEST,
+        inputs: Vec::new(),
+        peeks: Vec::new(),
+        outputs: vec![Output {
+            payload: Timestamp::new(1_000, 1).into(),
+            verifier: UpForGrabs,
+        }],
+        checker: Default::default(),
+        memo: None,
+        valid_until: None,
+    };
+    let mut data = InherentData::new();
+    data.put_data(sp_timestamp::INHERENT_IDENTIFIER, &60_000u64)
+        .unwrap();
+
+    let inherent = <SetTimestamp<FiveSecondsAtMost> as TuxedoInherent<
+        UpForGrabs,
+        SetTimestamp<FiveSecondsAtMost>,
+    >>::create_inherent(&data, (previous, H256::zero()));
+
+    assert_eq!(
+        inherent.outputs[0].payload.extract::<Timestamp>(),
+        Ok(Timestamp::new(6_000, 2))
+    );
+}
@@ -0,0 +1,493 @@
+//! An on-chain treasury funded by a share of the fees, and optionally the tips, that transactions
+//! pay.
+//!
+//! Once per block, the block author includes a `FundTreasury` inherent that collects the configured
+//! share of the fees and tips paid in the previous block (see `tuxedo_core::fees`) and deposits it into a new
+//! [`TreasuryFunds`] UTXO. The rest of the fees go to other collectors, or remain burned. Creating a new UTXO
+//! each block, rather than updating a single treasury UTXO, means that spends from the treasury
+//! never conflict with the inherent.
+//!
+//! By default only fees are shared with the treasury, so it only makes sense in a runtime that
+//! charges them through its weight configuration. A runtime that charges no fees, like the
+//! template, may give the treasury a share of the tips instead, and lower the author reward's
+//! share of them to match.
+//!
+//! Anyone may propose a spend from the treasury by creating a [`SpendProposal`]. A proposal is
+//! approved in one of two ways:
+//! * It is locked to the configured council's threshold multisignature, so consuming it requires
+//!   the council's signatures.
+//! * It is consumed together with an `Enacted` outcome from the governance piece whose payload is
+//!   the hash of the proposal.
+//!
+//! Treasury funds are protected by the `UpForGrabs` verifier. The constraint checkers, rather than
+//! the verifier, guarantee that they are only ever spent on approved proposals.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use core::marker::PhantomData;
+
+use governance::Enacted;
+use money::Coin;
+use parity_scale_codec::{Decode, Encode};
+use scale_info::TypeInfo;
+use serde::{Deserialize, Serialize};
+use sp_core::H256;
+use sp_inherents::{CheckInherentsResult, InherentData, MakeFatalError};
+use sp_runtime::{
+    traits::{BlakeTwo256, Hash},
+    transaction_validity::TransactionPriority,
+    Percent,
+};
+use sp_std::{vec, vec::Vec};
+use tuxedo_core::{
+    dynamic_typing::UtxoData,
+    ensure, fees,
+    inherents::{TuxedoInherent, TuxedoInherentAdapter},
+    support_macros::{CloneNoBound, DebugNoBound, DefaultNoBound},
+    types::{Output, Transaction, TransactionVersion},
+    verifier::{ThresholdMultiSignature, UpForGrabs},
+    CheckerContext, ConstraintChecker, Verifier,
+};
+
+#[cfg(test)]
+mod tests;
+
+/// Configuration items for the treasury piece when it is
+/// instantiated in a concrete runtime.
+pub trait TreasuryConfig {
+    /// A means of getting the current block height.
+    /// Probably this will be the Tuxedo Executive
+    fn block_height() -> u32;
+
+    /// The council whose signatures can approve spends from the treasury.
+    fn council() -> ThresholdMultiSignature;
+
+    /// The share of the fees burned by transactions that is deposited into the treasury.
+    ///
+    /// Default is 20 percent.
+    const FEE_SHARE: Percent = Percent::from_percent(20);
+
+    /// The share of the tips paid by transactions that is deposited into the treasury.
+    ///
+    /// Default is none, because tips are meant for the block author. Whatever share the treasury
+    /// takes must be left out of the author reward's, or the tips are paid out twice.
+    const TIP_SHARE: Percent = Percent::from_percent(0);
+}
+
+/// Some `Coin<ID>` value held by the treasury.
+#[derive(
+    Serialize,
+    Deserialize,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Clone,
+    Encode,
+    Decode,
+    Hash,
+    Debug,
+    TypeInfo,
+)]
+pub struct TreasuryFunds<const ID: u8>(pub u128);
+
+impl<const ID: u8> UtxoData for TreasuryFunds<ID> {
+    const TYPE_ID: [u8; 4] = [b't', b'r', b's', ID];
+}
+
+/// A proposal to pay some `Coin<ID>` value from the treasury to a beneficiary.
+#[derive(
+    Serialize,
+    Deserialize,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Clone,
+    Encode,
+    Decode,
+    Hash,
+    Debug,
+    TypeInfo,
+)]
+pub struct SpendProposal<V, const ID: u8> {
+    /// The value to pay.
+    pub amount: u128,
+    /// The verifier that the paid coins will be locked to.
+    pub beneficiary: V,
+}
+
+impl<V: Encode, const ID: u8> SpendProposal<V, ID> {
+    /// The payload that a governance referendum must enact to approve this proposal.
+    pub fn governance_payload(&self) -> H256 {
+        BlakeTwo256::hash_of(self)
+    }
+}
+
+impl<V: Encode + Decode, const ID: u8> UtxoData for SpendProposal<V, ID> {
+    const TYPE_ID: [u8; 4] = [b's', b'p', b'n', ID];
+}
+
+/// Errors that can occur when checking treasury transactions.
+#[derive(
+    Serialize,
+    Deserialize,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Clone,
+    Encode,
+    Decode,
+    Hash,
+    Debug,
+    TypeInfo,
+)]
+pub enum ConstraintCheckerError {
+    /// An input data has the wrong type.
+    BadlyTypedInput,
+    /// An output data has the wrong type.
+    BadlyTypedOutput,
+    /// Wrong number of inputs were provided to the constraint checker.
+    WrongNumberInputs,
+    /// Wrong number of outputs were provided to the constraint checker.
+    WrongNumberOutputs,
+    /// The block height noted in the inherent does not match the block into which it was inserted.
+    WrongHeight,
+    /// The treasury was funded with a different amount than its share of the pending fees.
+    WrongFundingAmount,
+    /// Treasury funds were locked to a verifier other than `UpForGrabs`.
+    FundsNotUpForGrabs,
+    /// A proposal, treasury funds, or coin with zero value was created. This is not allowed because it wastes state space.
+    ZeroValue,
+    /// The value consumed or created by this transaction overflows the value type.
+    ValueOverflow,
+    /// More value was created than was consumed.
+    OutputsExceedInputs,
+    /// The spend proposal was not consumed as the first input.
+    MissingProposal,
+    /// The spend proposal has been approved by neither the council nor a referendum.
+    NotApproved,
+    /// The governance outcome does not approve this proposal.
+    WrongGovernanceOutcome,
+    /// The approved spend was not paid to the beneficiary as the first output.
+    BeneficiaryNotPaid,
+}
+
+/// A constraint checker that deposits the treasury's share of the previous block's fees and tips.
+///
+/// This is expected to be performed through an inherent, and to happen exactly once per block.
+///
+/// There are no inputs. If the treasury's share of the collectible fees and tips is zero there are no outputs.
+/// Otherwise the only output is the new treasury funds. The block height is part of the checker so that two
+/// deposits of the same amount in different blocks do not have the same output references.
+#[derive(
+    Serialize,
+    Deserialize,
+    Encode,
+    Decode,
+    DebugNoBound,
+    DefaultNoBound,
+    PartialEq,
+    Eq,
+    CloneNoBound,
+    TypeInfo,
+)]
+#[scale_info(skip_type_params(T))]
+pub struct FundTreasury<T, const ID: u8> {
+    /// The block in which the fees were collected.
+    pub block: u32,
+    _phantom: PhantomData<T>,
+}
+
+impl<T, const ID: u8> FundTreasury<T, ID> {
+    pub fn new(block: u32) -> Self {
+        Self {
+            block,
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<T: TreasuryConfig, const ID: u8> FundTreasury<T, ID> {
+    /// The treasury's share of the fees and tips that are currently collectible.
+    pub fn expected_funding() -> u128 {
+        (T::FEE_SHARE * fees::collectible()).saturating_add(T::TIP_SHARE * fees::collectible_tips())
+    }
+}
+
+impl<T: TreasuryConfig + 'static, V: Verifier + PartialEq + From<UpForGrabs>, const ID: u8>
+    ConstraintChecker<V> for FundTreasury<T, ID>
+{
+    type Error = ConstraintCheckerError;
+    type InherentHooks = TuxedoInherentAdapter<Self>;
+
+    fn check(
+        &self,
+        input_data: &[Output<V>],
+        _peek_data: &[Output<V>],
+        output_data: &[Output<V>],
+        _context: &CheckerContext,
+    ) -> Result<TransactionPriority, Self::Error> {
+        ensure!(
+            input_data.is_empty(),
+            ConstraintCheckerError::WrongNumberInputs
+        );
+        ensure!(
+            self.block == T::block_height(),
+            ConstraintCheckerError::WrongHeight
+        );
+
+        let expected = Self::expected_funding();
+        if expected == 0 {
+            ensure!(
+                output_data.is_empty(),
+                ConstraintCheckerError::WrongNumberOutputs
+            );
+        } else {
+            ensure!(
+                output_data.len() == 1,
+                ConstraintCheckerError::WrongNumberOutputs
+            );
+            let funds = output_data[0]
+                .payload
+                .extract::<TreasuryFunds<ID>>()
+                .map_err(|_| ConstraintCheckerError::BadlyTypedOutput)?;
+            ensure!(
+                funds.0 == expected,
+                ConstraintCheckerError::WrongFundingAmount
+            );
+            ensure!(
+                output_data[0].verifier == UpForGrabs.into(),
+                ConstraintCheckerError::FundsNotUpForGrabs
+            );
+        }
+
+        Ok(0)
+    }
+
+    fn is_inherent(&self) -> bool {
+        true
+    }
+}
+
+impl<V: Verifier + PartialEq + From<UpForGrabs>, T: TreasuryConfig + 'static, const ID: u8>
+    TuxedoInherent<V, Self> for FundTreasury<T, ID>
+{
+    type Error = MakeFatalError<()>;
+    const INHERENT_IDENTIFIER: sp_inherents::InherentIdentifier = *b"treasury";
+
+    fn create_inherent(
+        _authoring_inherent_data: &InherentData,
+        _previous_inherent: (Transaction<V, Self>, H256),
+    ) -> Transaction<V, Self> {
+        // Inherents are applied at the beginning of the block, so the collectible fees and tips
+        // are the same now as they will be when this transaction is applied.
+        let amount = Self::expected_funding();
+        let outputs = if amount == 0 {
+            Vec::new()
+        } else {
+            vec![Output {
+                payload: TreasuryFunds::<ID>(amount).into(),
+                verifier: UpForGrabs.into(),
+            }]
+        };
+
+        Transaction {
+            version: TransactionVersion::LATEST,
+            inputs: Vec::new(),
+            peeks: Vec::new(),
+            outputs,
+            checker: Self::new(T::block_height()),
+            memo: None,
+            valid_until: None,
+        }
+    }
+
+    fn check_inherent(
+        _importing_inherent_data: &InherentData,
+        _inherent: Transaction<V, Self>,
+        _result: &mut CheckInherentsResult,
+    ) {
+        // The funding depends only on on-chain data, so there is nothing to check
+        // off-chain. The constraint checker makes all the necessary checks during execution.
+    }
+
+    #[cfg(feature = "std")]
+    fn genesis_transactions() -> Vec<Transaction<V, Self>> {
+        // The inherent must appear in every block, including genesis, so that
+        // block authors always find a previous inherent to build on.
+        vec![Transaction {
+            version: TransactionVersion::LATEST,
+            inputs: Vec::new(),
+            peeks: Vec::new(),
+            outputs: Vec::new(),
+            checker: Self::default(),
+            memo: None,
+            valid_until: None,
+        }]
+    }
+}
+
+/// A constraint checker that proposes spends from the treasury.
+///
+/// There are no inputs, and all outputs are spend proposals. Proposals locked to the council's
+/// verifier can be approved by the council. Others must be approved by a referendum.
+#[derive(
+    Serialize,
+    Deserialize,
+    Encode,
+    Decode,
+    DebugNoBound,
+    DefaultNoBound,
+    PartialEq,
+    Eq,
+    CloneNoBound,
+    TypeInfo,
+)]
+pub struct ProposeSpend<const ID: u8>;
+
+impl<V: Verifier, const ID: u8> ConstraintChecker<V> for ProposeSpend<ID> {
+    type Error = ConstraintCheckerError;
+    type InherentHooks = ();
+
+    fn check(
+        &self,
+        inputs: &[Output<V>],
+        _peeks: &[Output<V>],
+        outputs: &[Output<V>],
+        _context: &CheckerContext,
+    ) -> Result<TransactionPriority, Self::Error> {
+        ensure!(inputs.is_empty(), ConstraintCheckerError::WrongNumberInputs);
+        ensure!(
+            !outputs.is_empty(),
+            ConstraintCheckerError::WrongNumberOutputs
+        );
+
+        for output in outputs {
+            let proposal = output
+                .payload
+                .extract::<SpendProposal<V, ID>>()
+                .map_err(|_| ConstraintCheckerError::BadlyTypedOutput)?;
+            ensure!(proposal.amount > 0, ConstraintCheckerError::ZeroValue);
+        }
+
+        Ok(0)
+    }
+
+    fn is_inherent(&self) -> bool {
+        false
+    }
+}
+
+/// A constraint checker that pays an approved spend proposal out of the treasury.
+///
+/// The first input is the spend proposal. If the proposal is not locked to the council, the
+/// second input must be the governance outcome that enacted it. The remaining inputs are treasury
+/// funds. The first output pays the proposed amount to the beneficiary, and the remaining outputs
+/// are treasury funds change. Any treasury funds left over pay the transaction's fee and tip, so
+/// whoever submits an approved spend need not pay for it out of their own coins.
+#[derive(
+    Serialize,
+    Deserialize,
+    Encode,
+    Decode,
+    DebugNoBound,
+    DefaultNoBound,
+    PartialEq,
+    Eq,
+    CloneNoBound,
+    TypeInfo,
+)]
+#[scale_info(skip_type_params(T))]
+pub struct ApproveSpend<T, const ID: u8>(PhantomData<T>);
+
+impl<
+        T: TreasuryConfig,
+        V: Verifier + PartialEq + From<ThresholdMultiSignature> + From<UpForGrabs>,
+        const ID: u8,
+    > ConstraintChecker<V> for ApproveSpend<T, ID>
+{
+    type Error = ConstraintCheckerError;
+    type InherentHooks = ();
+
+    fn check(
+        &self,
+        inputs: &[Output<V>],
+        _peeks: &[Output<V>],
+        outputs: &[Output<V>],
+        _context: &CheckerContext,
+    ) -> Result<TransactionPriority, Self::Error> {
+        let proposal_input = inputs
+            .first()
+            .ok_or(ConstraintCheckerError::MissingProposal)?;
+        let proposal = proposal_input
+            .payload
+            .extract::<SpendProposal<V, ID>>()
+            .map_err(|_| ConstraintCheckerError::MissingProposal)?;
+
+        // The council approves by signing for the proposal input itself.
+        // Otherwise there must be a referendum outcome for this exact proposal.
+        let mut funds = &inputs[1..];
+        if proposal_input.verifier != T::council().into() {
+            let outcome = funds
+                .first()
+                .and_then(|input| input.payload.extract::<Enacted<ID>>().ok())
+                .ok_or(ConstraintCheckerError::NotApproved)?;
+            ensure!(
+                outcome.payload == proposal.governance_payload(),
+                ConstraintCheckerError::WrongGovernanceOutcome
+            );
+            funds = &funds[1..];
+        }
+
+        let mut total_input: u128 = 0;
+        for input in funds {
+            let value = input
+                .payload
+                .extract::<TreasuryFunds<ID>>()
+                .map_err(|_| ConstraintCheckerError::BadlyTypedInput)?
+                .0;
+            total_input = total_input
+                .checked_add(value)
+                .ok_or(ConstraintCheckerError::ValueOverflow)?;
+        }
+
+        let payment = outputs
+            .first()
+            .ok_or(ConstraintCheckerError::BeneficiaryNotPaid)?;
+        ensure!(
+            payment.payload.extract::<Coin<ID>>() == Ok(Coin(proposal.amount))
+                && payment.verifier == proposal.beneficiary,
+            ConstraintCheckerError::BeneficiaryNotPaid
+        );
+
+        let mut total_output = proposal.amount;
+        for output in &outputs[1..] {
+            let value = output
+                .payload
+                .extract::<TreasuryFunds<ID>>()
+                .map_err(|_| ConstraintCheckerError::BadlyTypedOutput)?
+                .0;
+            ensure!(value > 0, ConstraintCheckerError::ZeroValue);
+            ensure!(
+                output.verifier == UpForGrabs.into(),
+                ConstraintCheckerError::FundsNotUpForGrabs
+            );
+            total_output = total_output
+                .checked_add(value)
+                .ok_or(ConstraintCheckerError::ValueOverflow)?;
+        }
+        ensure!(
+            total_output <= total_input,
+            ConstraintCheckerError::OutputsExceedInputs
+        );
+
+        let burned = total_input - total_output;
+        Ok(burned.try_into().unwrap_or(TransactionPriority::MAX))
+    }
+
+    fn is_inherent(&self) -> bool {
+        false
+    }
+}
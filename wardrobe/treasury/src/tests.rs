@@ -0,0 +1,344 @@
+//! Unit tests for the Treasury piece
+
+use super::*;
+use sp_io::TestExternalities;
+use tuxedo_core::{tuxedo_verifier, verifier::SigCheck};
+
+/// A verifier that can represent the council, the treasury, and ordinary users.
+#[derive(Serialize, Deserialize, Encode, Decode, Debug, PartialEq, Eq, Clone, TypeInfo)]
+#[tuxedo_verifier]
+pub enum TestVerifier {
+    SigCheck(SigCheck),
+    UpForGrabs(UpForGrabs),
+    ThresholdMultiSignature(ThresholdMultiSignature),
+}
+
+/// The mock config always says the block number is seven, and the council is two of two.
+pub struct TestConfig;
+
+impl TreasuryConfig for TestConfig {
+    fn block_height() -> u32 {
+        7
+    }
+
+    fn council() -> ThresholdMultiSignature {
+        ThresholdMultiSignature::new(2, vec![H256::repeat_byte(1), H256::repeat_byte(2)])
+    }
+}
+
+/// Like the mock config, but the treasury also takes a fifth of the tips.
+pub struct SharesTips;
+
+impl TreasuryConfig for SharesTips {
+    fn block_height() -> u32 {
+        7
+    }
+
+    fn council() -> ThresholdMultiSignature {
+        TestConfig::council()
+    }
+
+    const TIP_SHARE: Percent = Percent::from_percent(20);
+}
+
+fn council() -> TestVerifier {
+    TestConfig::council().into()
+}
+
+fn treasury() -> TestVerifier {
+    UpForGrabs.into()
+}
+
+fn bob() -> TestVerifier {
+    SigCheck::new(H256::repeat_byte(3)).into()
+}
+
+fn proposal(amount: u128) -> SpendProposal<TestVerifier, 0> {
+    SpendProposal {
+        amount,
+        beneficiary: bob(),
+    }
+}
+
+fn funds(value: u128) -> Output<TestVerifier> {
+    (TreasuryFunds::<0>(value), treasury()).into()
+}
+
+fn payment(value: u128) -> Output<TestVerifier> {
+    (Coin::<0>(value), bob()).into()
+}
+
+/// Pay some fees in the previous block, so that they are collectible in this one.
+fn pay_fees(fee: u128) {
+    fees::note(fee);
+    fees::roll_over();
+}
+
+fn fund(outputs: Vec<Output<TestVerifier>>) -> Result<TransactionPriority, ConstraintCheckerError> {
+    FundTreasury::<TestConfig, 0>::new(7).check(&[], &[], &outputs, &Default::default())
+}
+
+#[test]
+fn fund_treasury_works() {
+    TestExternalities::default().execute_with(|| {
+        pay_fees(100);
+
+        assert_eq!(fund(vec![funds(20)]), Ok(0));
+        // The rest of the fees are left for other collectors
+        assert_eq!(fees::collectible(), 100);
+    });
+}
+
+#[test]
+fn fund_treasury_leaves_tips_by_default() {
+    TestExternalities::default().execute_with(|| {
+        fees::note_tip(100);
+        fees::roll_over();
+
+        assert_eq!(fund(vec![]), Ok(0));
+    });
+}
+
+#[test]
+fn fund_treasury_with_tip_share_works() {
+    TestExternalities::default().execute_with(|| {
+        fees::note(100);
+        fees::note_tip(50);
+        fees::roll_over();
+
+        assert_eq!(
+            FundTreasury::<SharesTips, 0>::new(7).check(
+                &[],
+                &[],
+                &[funds(30)],
+                &Default::default()
+            ),
+            Ok(0)
+        );
+    });
+}
+
+#[test]
+fn fund_treasury_without_fees_works() {
+    TestExternalities::default().execute_with(|| {
+        assert_eq!(fund(vec![]), Ok(0));
+    });
+}
+
+#[test]
+fn fund_treasury_with_wrong_amount_fails() {
+    TestExternalities::default().execute_with(|| {
+        pay_fees(100);
+
+        assert_eq!(
+            fund(vec![funds(21)]),
+            Err(ConstraintCheckerError::WrongFundingAmount)
+        );
+    });
+}
+
+#[test]
+fn fund_treasury_without_output_fails() {
+    TestExternalities::default().execute_with(|| {
+        pay_fees(100);
+
+        assert_eq!(
+            fund(vec![]),
+            Err(ConstraintCheckerError::WrongNumberOutputs)
+        );
+    });
+}
+
+#[test]
+fn fund_treasury_locked_to_author_fails() {
+    TestExternalities::default().execute_with(|| {
+        pay_fees(100);
+
+        assert_eq!(
+            fund(vec![(TreasuryFunds::<0>(20), bob()).into()]),
+            Err(ConstraintCheckerError::FundsNotUpForGrabs)
+        );
+    });
+}
+
+#[test]
+fn fund_treasury_at_wrong_height_fails() {
+    TestExternalities::default().execute_with(|| {
+        let checker = FundTreasury::<TestConfig, 0>::new(6);
+
+        assert_eq!(
+            ConstraintChecker::<TestVerifier>::check(&checker, &[], &[], &[], &Default::default()),
+            Err(ConstraintCheckerError::WrongHeight)
+        );
+    });
+}
+
+#[test]
+fn created_inherent_is_valid() {
+    TestExternalities::default().execute_with(|| {
+        pay_fees(1_000);
+
+        let previous = Transaction {
+            version: TransactionVersion::LATEST,
+            inputs: Vec::new(),
+            peeks: Vec::new(),
+            outputs: Vec::new(),
+            checker: FundTreasury::<TestConfig, 0>::new(6),
+            memo: None,
+            valid_until: None,
+        };
+        let tx = <FundTreasury<TestConfig, 0> as TuxedoInherent<TestVerifier, _>>::create_inherent(
+            &InherentData::new(),
+            (previous, H256::zero()),
+        );
+
+        assert_eq!(tx.outputs, vec![funds(200)]);
+        assert_eq!(
+            tx.checker.check(&[], &[], &tx.outputs, &Default::default()),
+            Ok(0)
+        );
+    });
+}
+
+#[test]
+fn propose_spend_works() {
+    let outputs = vec![(proposal(50), council()).into()];
+
+    assert_eq!(
+        ProposeSpend::<0>.check(&[], &[], &outputs, &Default::default()),
+        Ok(0)
+    );
+}
+
+#[test]
+fn propose_zero_spend_fails() {
+    let outputs = vec![(proposal(0), council()).into()];
+
+    assert_eq!(
+        ProposeSpend::<0>.check(&[], &[], &outputs, &Default::default()),
+        Err(ConstraintCheckerError::ZeroValue)
+    );
+}
+
+#[test]
+fn propose_spend_with_inputs_fails() {
+    let inputs = vec![funds(50)];
+    let outputs = vec![(proposal(50), council()).into()];
+
+    assert_eq!(
+        ProposeSpend::<0>.check(&inputs, &[], &outputs, &Default::default()),
+        Err(ConstraintCheckerError::WrongNumberInputs)
+    );
+}
+
+fn approve(
+    inputs: Vec<Output<TestVerifier>>,
+    outputs: Vec<Output<TestVerifier>>,
+) -> Result<TransactionPriority, ConstraintCheckerError> {
+    ApproveSpend::<TestConfig, 0>::default().check(&inputs, &[], &outputs, &Default::default())
+}
+
+fn enacted(payload: H256) -> Output<TestVerifier> {
+    (Enacted::<0> { payload }, treasury()).into()
+}
+
+#[test]
+fn council_approved_spend_works() {
+    let inputs = vec![(proposal(60), council()).into(), funds(30), funds(50)];
+    let outputs = vec![payment(60), funds(15)];
+
+    assert_eq!(approve(inputs, outputs), Ok(5));
+}
+
+#[test]
+fn referendum_approved_spend_works() {
+    let inputs = vec![
+        (proposal(100), treasury()).into(),
+        enacted(proposal(100).governance_payload()),
+        funds(100),
+    ];
+    let outputs = vec![payment(100)];
+
+    assert_eq!(approve(inputs, outputs), Ok(0));
+}
+
+#[test]
+fn unapproved_spend_fails() {
+    let inputs = vec![(proposal(100), treasury()).into(), funds(100)];
+    let outputs = vec![payment(100)];
+
+    assert_eq!(
+        approve(inputs, outputs),
+        Err(ConstraintCheckerError::NotApproved)
+    );
+}
+
+#[test]
+fn spend_with_other_referendum_outcome_fails() {
+    let inputs = vec![
+        (proposal(100), treasury()).into(),
+        enacted(proposal(99).governance_payload()),
+        funds(100),
+    ];
+    let outputs = vec![payment(100)];
+
+    assert_eq!(
+        approve(inputs, outputs),
+        Err(ConstraintCheckerError::WrongGovernanceOutcome)
+    );
+}
+
+#[test]
+fn spend_paying_wrong_beneficiary_fails() {
+    let inputs = vec![(proposal(60), council()).into(), funds(60)];
+    let outputs = vec![(Coin::<0>(60), council()).into()];
+
+    assert_eq!(
+        approve(inputs, outputs),
+        Err(ConstraintCheckerError::BeneficiaryNotPaid)
+    );
+}
+
+#[test]
+fn spend_paying_wrong_amount_fails() {
+    let inputs = vec![(proposal(60), council()).into(), funds(100)];
+    let outputs = vec![payment(70), funds(30)];
+
+    assert_eq!(
+        approve(inputs, outputs),
+        Err(ConstraintCheckerError::BeneficiaryNotPaid)
+    );
+}
+
+#[test]
+fn spend_exceeding_funds_fails() {
+    let inputs = vec![(proposal(60), council()).into(), funds(50)];
+    let outputs = vec![payment(60)];
+
+    assert_eq!(
+        approve(inputs, outputs),
+        Err(ConstraintCheckerError::OutputsExceedInputs)
+    );
+}
+
+#[test]
+fn spend_with_change_locked_away_fails() {
+    let inputs = vec![(proposal(60), council()).into(), funds(100)];
+    let outputs = vec![payment(60), (TreasuryFunds::<0>(40), bob()).into()];
+
+    assert_eq!(
+        approve(inputs, outputs),
+        Err(ConstraintCheckerError::FundsNotUpForGrabs)
+    );
+}
+
+#[test]
+fn spend_with_bogus_funds_fails() {
+    let inputs = vec![(proposal(60), council()).into(), payment(100)];
+    let outputs = vec![payment(60)];
+
+    assert_eq!(
+        approve(inputs, outputs),
+        Err(ConstraintCheckerError::BadlyTypedInput)
+    );
+}
@@ -0,0 +1,453 @@
+//! Multisig vaults that let a single signer make small withdrawals.
+//!
+//! A [`Vault`] holds `Coin<ID>`s on behalf of a group of signatories. Withdrawals normally need
+//! signatures from a threshold of the signatories, like the `ThresholdMultiSignature` verifier.
+//! But to keep day to day spending convenient, any single signatory may withdraw on their own
+//! as long as the total withdrawn by single signers in the current period stays within the
+//! vault's limit. The vault tracks how much has been withdrawn this period, and a new period
+//! begins with the first withdrawal after the current one has elapsed.
+//!
+//! Vaults are protected by the `UpForGrabs` verifier so that anyone may deposit into them.
+//! The constraint checkers, rather than the verifier, check the signatories' signatures on
+//! withdrawals. Signatures are passed as fields of the constraint checker.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use core::marker::PhantomData;
+
+use money::Coin;
+use parity_scale_codec::{Decode, Encode};
+use scale_info::TypeInfo;
+use serde::{Deserialize, Serialize};
+use sp_core::{sr25519::Public, H256};
+use sp_runtime::transaction_validity::TransactionPriority;
+use sp_std::{collections::btree_set::BTreeSet, prelude::*};
+use tuxedo_core::{
+    dynamic_typing::UtxoData,
+    ensure,
+    support_macros::{CloneNoBound, DebugNoBound, DefaultNoBound},
+    types::Output,
+    verifier::{SignatureAndIndex, UpForGrabs},
+    CheckerContext, ConstraintChecker, Verifier,
+};
+
+#[cfg(test)]
+mod tests;
+
+/// Configuration items for the vault piece when it is
+/// instantiated in a concrete runtime.
+pub trait VaultConfig {
+    /// A means of getting the current block height.
+    /// Probably this will be the Tuxedo Executive
+    fn block_height() -> u32;
+}
+
+/// Coins held jointly by a group of signatories.
+#[derive(
+    Serialize,
+    Deserialize,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Clone,
+    Encode,
+    Decode,
+    Hash,
+    Debug,
+    TypeInfo,
+)]
+pub struct Vault<const ID: u8> {
+    /// The keys that may authorize withdrawals.
+    pub signatories: Vec<H256>,
+    /// The number of signatures needed to withdraw more than the single signer limit.
+    pub threshold: u8,
+    /// The value held in the vault.
+    pub balance: u128,
+    /// The total that single signers may withdraw in each period.
+    pub period_limit: u128,
+    /// The number of blocks in each period.
+    pub period_length: u32,
+    /// The block height at which the current period began.
+    pub period_start: u32,
+    /// The total withdrawn by single signers in the current period.
+    pub spent_this_period: u128,
+    /// The number of withdrawals so far, so that signatures cannot be replayed.
+    pub nonce: u64,
+}
+
+impl<const ID: u8> Vault<ID> {
+    /// The message that signatories must sign to authorize a withdrawal from this vault
+    /// that creates the given outputs.
+    pub fn withdrawal_message<V: Encode>(&self, outputs: &[Output<V>]) -> Vec<u8> {
+        (b"vault", self, outputs).encode()
+    }
+
+    /// The total withdrawn by single signers in the period that is current at the given height.
+    pub fn spent_at(&self, height: u32) -> u128 {
+        if height >= self.period_start.saturating_add(self.period_length) {
+            0
+        } else {
+            self.spent_this_period
+        }
+    }
+
+    /// The start of the period that is current at the given height.
+    fn period_start_at(&self, height: u32) -> u32 {
+        if height >= self.period_start.saturating_add(self.period_length) {
+            height
+        } else {
+            self.period_start
+        }
+    }
+
+    /// Count the distinct signatories that validly signed the given message.
+    fn count_signers(&self, message: &[u8], signatures: &[SignatureAndIndex]) -> usize {
+        signatures
+            .iter()
+            .filter(|sig| {
+                self.signatories
+                    .get(sig.index as usize)
+                    .is_some_and(|signer| {
+                        sp_io::crypto::sr25519_verify(
+                            &sig.signature,
+                            message,
+                            &Public::from_h256(*signer),
+                        )
+                    })
+            })
+            .map(|sig| sig.index)
+            .collect::<BTreeSet<_>>()
+            .len()
+    }
+}
+
+impl<const ID: u8> UtxoData for Vault<ID> {
+    const TYPE_ID: [u8; 4] = [b'v', b'l', b't', ID];
+}
+
+/// Errors that can occur when checking vault transactions.
+#[derive(
+    Serialize,
+    Deserialize,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Clone,
+    Encode,
+    Decode,
+    Hash,
+    Debug,
+    TypeInfo,
+)]
+pub enum ConstraintCheckerError {
+    /// An input data has the wrong type.
+    BadlyTypedInput,
+    /// An output data has the wrong type.
+    BadlyTypedOutput,
+    /// Wrong number of inputs were provided to the constraint checker.
+    WrongNumberInputs,
+    /// Wrong number of outputs were provided to the constraint checker.
+    WrongNumberOutputs,
+    /// A coin with zero value was created. This is not allowed because it wastes state space.
+    ZeroValue,
+    /// The value consumed or created by this transaction overflows the value type.
+    ValueOverflow,
+    /// More value was created than was consumed.
+    OutputsExceedInputs,
+    /// The vault was locked to a verifier other than `UpForGrabs`.
+    VaultNotUpForGrabs,
+    /// The threshold is zero or greater than the number of signatories.
+    InvalidThreshold,
+    /// The same key appears more than once among the signatories.
+    DuplicateSignatories,
+    /// A new vault has already spent, has a nonzero nonce, or does not start its period now.
+    InvalidInitialState,
+    /// A period must last at least one block.
+    ZeroPeriod,
+    /// The vault was changed in some way other than the deposit or withdrawal allows.
+    VaultModified,
+    /// Nothing was withdrawn.
+    NothingWithdrawn,
+    /// The withdrawal was not signed by any signatory.
+    NotSigned,
+    /// The withdrawal exceeds the single signer limit and was signed by fewer
+    /// signatories than the threshold.
+    ThresholdNotMet,
+}
+
+/// Sum the values of some `Coin<ID>` inputs.
+fn input_value<V, const ID: u8>(inputs: &[Output<V>]) -> Result<u128, ConstraintCheckerError> {
+    inputs.iter().try_fold(0u128, |total, input| {
+        let coin = input
+            .payload
+            .extract::<Coin<ID>>()
+            .map_err(|_| ConstraintCheckerError::BadlyTypedInput)?;
+        total
+            .checked_add(coin.0)
+            .ok_or(ConstraintCheckerError::ValueOverflow)
+    })
+}
+
+/// Sum the values of some `Coin<ID>` outputs, none of which may be zero.
+fn output_value<V, const ID: u8>(outputs: &[Output<V>]) -> Result<u128, ConstraintCheckerError> {
+    outputs.iter().try_fold(0u128, |total, output| {
+        let coin = output
+            .payload
+            .extract::<Coin<ID>>()
+            .map_err(|_| ConstraintCheckerError::BadlyTypedOutput)?;
+        ensure!(coin.0 > 0, ConstraintCheckerError::ZeroValue);
+        total
+            .checked_add(coin.0)
+            .ok_or(ConstraintCheckerError::ValueOverflow)
+    })
+}
+
+/// Extract a vault from an output and make sure it is protected by `UpForGrabs`.
+fn extract_vault<V: Verifier + PartialEq + From<UpForGrabs>, const ID: u8>(
+    output: Option<&Output<V>>,
+) -> Result<Vault<ID>, ConstraintCheckerError> {
+    let output = output.ok_or(ConstraintCheckerError::WrongNumberOutputs)?;
+    let vault = output
+        .payload
+        .extract::<Vault<ID>>()
+        .map_err(|_| ConstraintCheckerError::BadlyTypedOutput)?;
+    ensure!(
+        output.verifier == UpForGrabs.into(),
+        ConstraintCheckerError::VaultNotUpForGrabs
+    );
+    Ok(vault)
+}
+
+/// A constraint checker that creates a new vault and funds it.
+///
+/// All inputs are `Coin<ID>`s. The first output is the vault, and the remaining outputs are
+/// optionally `Coin<ID>` change. Coins that are neither put into the vault nor returned as change
+/// pay the transaction's fee and tip.
+#[derive(
+    Serialize,
+    Deserialize,
+    Encode,
+    Decode,
+    DebugNoBound,
+    DefaultNoBound,
+    PartialEq,
+    Eq,
+    CloneNoBound,
+    TypeInfo,
+)]
+#[scale_info(skip_type_params(T))]
+pub struct CreateVault<T, const ID: u8>(PhantomData<T>);
+
+impl<T: VaultConfig, V: Verifier + PartialEq + From<UpForGrabs>, const ID: u8> ConstraintChecker<V>
+    for CreateVault<T, ID>
+{
+    type Error = ConstraintCheckerError;
+    type InherentHooks = ();
+
+    fn check(
+        &self,
+        inputs: &[Output<V>],
+        _peeks: &[Output<V>],
+        outputs: &[Output<V>],
+        _context: &CheckerContext,
+    ) -> Result<TransactionPriority, Self::Error> {
+        let vault = extract_vault::<V, ID>(outputs.first())?;
+
+        ensure!(
+            vault.threshold > 0 && vault.signatories.len() >= vault.threshold.into(),
+            ConstraintCheckerError::InvalidThreshold
+        );
+        ensure!(
+            vault.signatories.iter().collect::<BTreeSet<_>>().len() == vault.signatories.len(),
+            ConstraintCheckerError::DuplicateSignatories
+        );
+        ensure!(vault.period_length > 0, ConstraintCheckerError::ZeroPeriod);
+        ensure!(
+            vault.spent_this_period == 0
+                && vault.nonce == 0
+                && vault.period_start == T::block_height(),
+            ConstraintCheckerError::InvalidInitialState
+        );
+
+        let total_input = input_value::<V, ID>(inputs)?;
+        let total_output = output_value::<V, ID>(&outputs[1..])?
+            .checked_add(vault.balance)
+            .ok_or(ConstraintCheckerError::ValueOverflow)?;
+        ensure!(
+            total_output <= total_input,
+            ConstraintCheckerError::OutputsExceedInputs
+        );
+
+        let burned = total_input - total_output;
+        Ok(burned.try_into().unwrap_or(TransactionPriority::MAX))
+    }
+
+    fn is_inherent(&self) -> bool {
+        false
+    }
+}
+
+/// A constraint checker that deposits coins into an existing vault.
+///
+/// Anyone may deposit. The first input is the vault and the remaining inputs are `Coin<ID>`s.
+/// The first output is the vault with its balance increased, and the remaining outputs are
+/// optionally `Coin<ID>` change. The depositor pays the transaction's fee and tip with whatever
+/// coins are left over.
+#[derive(
+    Serialize,
+    Deserialize,
+    Encode,
+    Decode,
+    DebugNoBound,
+    DefaultNoBound,
+    PartialEq,
+    Eq,
+    CloneNoBound,
+    TypeInfo,
+)]
+pub struct Deposit<const ID: u8>;
+
+impl<V: Verifier + PartialEq + From<UpForGrabs>, const ID: u8> ConstraintChecker<V>
+    for Deposit<ID>
+{
+    type Error = ConstraintCheckerError;
+    type InherentHooks = ();
+
+    fn check(
+        &self,
+        inputs: &[Output<V>],
+        _peeks: &[Output<V>],
+        outputs: &[Output<V>],
+        _context: &CheckerContext,
+    ) -> Result<TransactionPriority, Self::Error> {
+        let old = inputs
+            .first()
+            .ok_or(ConstraintCheckerError::WrongNumberInputs)?
+            .payload
+            .extract::<Vault<ID>>()
+            .map_err(|_| ConstraintCheckerError::BadlyTypedInput)?;
+        let new = extract_vault::<V, ID>(outputs.first())?;
+        ensure!(
+            new.balance > old.balance
+                && new
+                    == Vault {
+                        balance: new.balance,
+                        ..old.clone()
+                    },
+            ConstraintCheckerError::VaultModified
+        );
+        let deposited = new.balance - old.balance;
+
+        let total_input = input_value::<V, ID>(&inputs[1..])?;
+        let total_output = output_value::<V, ID>(&outputs[1..])?
+            .checked_add(deposited)
+            .ok_or(ConstraintCheckerError::ValueOverflow)?;
+        ensure!(
+            total_output <= total_input,
+            ConstraintCheckerError::OutputsExceedInputs
+        );
+
+        let burned = total_input - total_output;
+        Ok(burned.try_into().unwrap_or(TransactionPriority::MAX))
+    }
+
+    fn is_inherent(&self) -> bool {
+        false
+    }
+}
+
+/// A constraint checker that withdraws coins from a vault.
+///
+/// The only input is the vault. The first output is the vault with its balance reduced, and the
+/// remaining outputs are the withdrawn `Coin<ID>`s, locked to whatever verifiers the signers
+/// choose. Signatories sign the old vault along with all the outputs. See
+/// [`Vault::withdrawal_message`].
+///
+/// If the withdrawal fits within what is left of the single signer limit for the current period,
+/// one signature is enough, and the withdrawal counts toward the limit. Otherwise, signatures from
+/// the threshold of signatories are needed, and the withdrawal does not count toward the limit.
+#[derive(
+    Serialize, Deserialize, Encode, Decode, DebugNoBound, PartialEq, Eq, CloneNoBound, TypeInfo,
+)]
+#[scale_info(skip_type_params(T))]
+pub struct Withdraw<T, const ID: u8> {
+    /// The signatories' signatures over the withdrawal.
+    pub signatures: Vec<SignatureAndIndex>,
+    _phantom: PhantomData<T>,
+}
+
+impl<T, const ID: u8> Withdraw<T, ID> {
+    pub fn new(signatures: Vec<SignatureAndIndex>) -> Self {
+        Self {
+            signatures,
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<T: VaultConfig, V: Verifier + PartialEq + From<UpForGrabs>, const ID: u8> ConstraintChecker<V>
+    for Withdraw<T, ID>
+{
+    type Error = ConstraintCheckerError;
+    type InherentHooks = ();
+
+    fn check(
+        &self,
+        inputs: &[Output<V>],
+        _peeks: &[Output<V>],
+        outputs: &[Output<V>],
+        _context: &CheckerContext,
+    ) -> Result<TransactionPriority, Self::Error> {
+        ensure!(inputs.len() == 1, ConstraintCheckerError::WrongNumberInputs);
+        let old = inputs[0]
+            .payload
+            .extract::<Vault<ID>>()
+            .map_err(|_| ConstraintCheckerError::BadlyTypedInput)?;
+        let new = extract_vault::<V, ID>(outputs.first())?;
+
+        let withdrawn = output_value::<V, ID>(&outputs[1..])?;
+        ensure!(withdrawn > 0, ConstraintCheckerError::NothingWithdrawn);
+        let balance = old
+            .balance
+            .checked_sub(withdrawn)
+            .ok_or(ConstraintCheckerError::OutputsExceedInputs)?;
+
+        // Decide which kind of authorization is needed
+        let height = T::block_height();
+        let spent = old.spent_at(height);
+        let within_limit = spent
+            .checked_add(withdrawn)
+            .filter(|total| *total <= old.period_limit);
+        let signers = old.count_signers(&old.withdrawal_message(outputs), &self.signatures);
+        ensure!(signers > 0, ConstraintCheckerError::NotSigned);
+        let spent_this_period = match within_limit {
+            Some(total) => total,
+            None => {
+                ensure!(
+                    signers >= old.threshold.into(),
+                    ConstraintCheckerError::ThresholdNotMet
+                );
+                spent
+            }
+        };
+
+        ensure!(
+            new == Vault {
+                balance,
+                period_start: old.period_start_at(height),
+                spent_this_period,
+                nonce: old.nonce.wrapping_add(1),
+                ..old
+            },
+            ConstraintCheckerError::VaultModified
+        );
+
+        Ok(0)
+    }
+
+    fn is_inherent(&self) -> bool {
+        false
+    }
+}
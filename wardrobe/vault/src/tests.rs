@@ -0,0 +1,383 @@
+//! Unit tests for the Vault piece
+
+use super::*;
+use sp_core::{crypto::Pair as _, sr25519::Pair};
+use tuxedo_core::{dynamic_typing::testing::Bogus, tuxedo_verifier, verifier::SigCheck};
+
+/// A verifier that can represent vaults as well as the recipients of withdrawals.
+#[derive(Serialize, Deserialize, Encode, Decode, Debug, PartialEq, Eq, Clone, TypeInfo)]
+#[tuxedo_verifier]
+pub enum TestVerifier {
+    SigCheck(SigCheck),
+    UpForGrabs(UpForGrabs),
+}
+
+/// A mock config that always reports the same block height.
+pub struct AtHeight<const H: u32>;
+
+impl<const H: u32> VaultConfig for AtHeight<H> {
+    fn block_height() -> u32 {
+        H
+    }
+}
+
+fn signatory(i: u8) -> Pair {
+    Pair::from_seed(&[i; 32])
+}
+
+fn recipient() -> H256 {
+    signatory(9).public().into()
+}
+
+fn coin(value: u128, owner: H256) -> Output<TestVerifier> {
+    (Coin::<0>(value), TestVerifier::from(SigCheck::new(owner))).into()
+}
+
+fn grabbable(vault: Vault<0>) -> Output<TestVerifier> {
+    (vault, TestVerifier::from(UpForGrabs)).into()
+}
+
+/// A 2 of 3 vault holding 1000, whose signers may withdraw 100 every 10 blocks on their own.
+/// The current period started at block 10 and 30 has been spent so far.
+fn vault() -> Vault<0> {
+    Vault {
+        signatories: (0..3).map(|i| signatory(i).public().into()).collect(),
+        threshold: 2,
+        balance: 1000,
+        period_limit: 100,
+        period_length: 10,
+        period_start: 10,
+        spent_this_period: 30,
+        nonce: 0,
+    }
+}
+
+fn check<C: ConstraintChecker<TestVerifier>>(
+    checker: C,
+    inputs: Vec<Output<TestVerifier>>,
+    outputs: Vec<Output<TestVerifier>>,
+) -> Result<TransactionPriority, C::Error> {
+    checker.check(&inputs, &[], &outputs, &Default::default())
+}
+
+#[test]
+fn create_vault_works() {
+    let new = Vault {
+        spent_this_period: 0,
+        ..vault()
+    };
+
+    assert_eq!(
+        check(
+            CreateVault::<AtHeight<10>, 0>::default(),
+            vec![coin(1200, recipient())],
+            vec![grabbable(new), coin(150, recipient())],
+        ),
+        Ok(50)
+    );
+}
+
+#[test]
+fn create_vault_with_high_threshold_fails() {
+    let new = Vault {
+        threshold: 4,
+        spent_this_period: 0,
+        ..vault()
+    };
+
+    assert_eq!(
+        check(
+            CreateVault::<AtHeight<10>, 0>::default(),
+            vec![coin(1000, recipient())],
+            vec![grabbable(new)],
+        ),
+        Err(ConstraintCheckerError::InvalidThreshold)
+    );
+}
+
+#[test]
+fn create_vault_with_duplicate_signatories_fails() {
+    let mut new = Vault {
+        spent_this_period: 0,
+        ..vault()
+    };
+    new.signatories[2] = new.signatories[0];
+
+    assert_eq!(
+        check(
+            CreateVault::<AtHeight<10>, 0>::default(),
+            vec![coin(1000, recipient())],
+            vec![grabbable(new)],
+        ),
+        Err(ConstraintCheckerError::DuplicateSignatories)
+    );
+}
+
+#[test]
+fn create_vault_with_spending_fails() {
+    assert_eq!(
+        check(
+            CreateVault::<AtHeight<10>, 0>::default(),
+            vec![coin(1000, recipient())],
+            vec![grabbable(vault())],
+        ),
+        Err(ConstraintCheckerError::InvalidInitialState)
+    );
+}
+
+#[test]
+fn create_underfunded_vault_fails() {
+    let new = Vault {
+        spent_this_period: 0,
+        ..vault()
+    };
+
+    assert_eq!(
+        check(
+            CreateVault::<AtHeight<10>, 0>::default(),
+            vec![coin(999, recipient())],
+            vec![grabbable(new)],
+        ),
+        Err(ConstraintCheckerError::OutputsExceedInputs)
+    );
+}
+
+#[test]
+fn create_vault_locked_to_key_fails() {
+    let new = Vault {
+        spent_this_period: 0,
+        ..vault()
+    };
+
+    assert_eq!(
+        check(
+            CreateVault::<AtHeight<10>, 0>::default(),
+            vec![coin(1000, recipient())],
+            vec![(new, TestVerifier::from(SigCheck::new(recipient()))).into()],
+        ),
+        Err(ConstraintCheckerError::VaultNotUpForGrabs)
+    );
+}
+
+#[test]
+fn deposit_works() {
+    let new = Vault {
+        balance: 1100,
+        ..vault()
+    };
+
+    assert_eq!(
+        check(
+            Deposit::<0>,
+            vec![grabbable(vault()), coin(150, recipient())],
+            vec![grabbable(new), coin(50, recipient())],
+        ),
+        Ok(0)
+    );
+}
+
+#[test]
+fn deposit_resetting_spending_fails() {
+    let new = Vault {
+        balance: 1100,
+        spent_this_period: 0,
+        ..vault()
+    };
+
+    assert_eq!(
+        check(
+            Deposit::<0>,
+            vec![grabbable(vault()), coin(100, recipient())],
+            vec![grabbable(new)],
+        ),
+        Err(ConstraintCheckerError::VaultModified)
+    );
+}
+
+#[test]
+fn deposit_without_coins_fails() {
+    let new = Vault {
+        balance: 1100,
+        ..vault()
+    };
+
+    assert_eq!(
+        check(Deposit::<0>, vec![grabbable(vault())], vec![grabbable(new)]),
+        Err(ConstraintCheckerError::OutputsExceedInputs)
+    );
+}
+
+#[test]
+fn deposit_with_bogus_input_fails() {
+    let new = Vault {
+        balance: 1100,
+        ..vault()
+    };
+
+    assert_eq!(
+        check(
+            Deposit::<0>,
+            vec![
+                grabbable(vault()),
+                (Bogus, TestVerifier::from(UpForGrabs)).into()
+            ],
+            vec![grabbable(new)],
+        ),
+        Err(ConstraintCheckerError::BadlyTypedInput)
+    );
+}
+
+/// Withdraw `amount` at block `H`, signed by the given signatories, expecting the vault to
+/// end up with the given single signer spending and period start.
+fn withdraw<const H: u32>(
+    amount: u128,
+    signers: &[u8],
+    spent_this_period: u128,
+    period_start: u32,
+) -> Result<TransactionPriority, ConstraintCheckerError> {
+    let old = vault();
+    let new = Vault {
+        balance: old.balance - amount,
+        period_start,
+        spent_this_period,
+        nonce: 1,
+        ..old.clone()
+    };
+    let outputs = vec![grabbable(new), coin(amount, recipient())];
+    let message = old.withdrawal_message(&outputs);
+    let signatures = signers
+        .iter()
+        .map(|i| SignatureAndIndex {
+            signature: signatory(*i).sign(&message),
+            index: *i,
+        })
+        .collect();
+
+    check(
+        Withdraw::<AtHeight<H>, 0>::new(signatures),
+        vec![grabbable(old)],
+        outputs,
+    )
+}
+
+#[test]
+fn single_signer_within_limit_works() {
+    assert_eq!(withdraw::<15>(70, &[1], 100, 10), Ok(0));
+}
+
+#[test]
+fn single_signer_over_limit_fails() {
+    assert_eq!(
+        withdraw::<15>(71, &[1], 101, 10),
+        Err(ConstraintCheckerError::ThresholdNotMet)
+    );
+}
+
+#[test]
+fn threshold_over_limit_works() {
+    assert_eq!(withdraw::<15>(500, &[0, 2], 30, 10), Ok(0));
+}
+
+#[test]
+fn repeated_signature_counts_once() {
+    assert_eq!(
+        withdraw::<15>(500, &[2, 2], 30, 10),
+        Err(ConstraintCheckerError::ThresholdNotMet)
+    );
+}
+
+#[test]
+fn single_signer_in_new_period_works() {
+    assert_eq!(withdraw::<20>(100, &[0], 100, 20), Ok(0));
+}
+
+#[test]
+fn new_period_must_reset_spending() {
+    assert_eq!(
+        withdraw::<20>(50, &[0], 80, 10),
+        Err(ConstraintCheckerError::VaultModified)
+    );
+}
+
+#[test]
+fn unsigned_withdrawal_fails() {
+    assert_eq!(
+        withdraw::<15>(10, &[], 40, 10),
+        Err(ConstraintCheckerError::NotSigned)
+    );
+}
+
+#[test]
+fn withdrawal_signed_by_outsider_fails() {
+    assert_eq!(
+        withdraw::<15>(10, &[5], 40, 10),
+        Err(ConstraintCheckerError::NotSigned)
+    );
+}
+
+#[test]
+fn withdrawal_without_counting_spending_fails() {
+    assert_eq!(
+        withdraw::<15>(10, &[1], 30, 10),
+        Err(ConstraintCheckerError::VaultModified)
+    );
+}
+
+#[test]
+fn overdrawing_fails() {
+    let old = vault();
+    let new = Vault {
+        balance: 0,
+        nonce: 1,
+        ..old.clone()
+    };
+    let outputs = vec![grabbable(new), coin(1001, recipient())];
+    let message = old.withdrawal_message(&outputs);
+    let signatures = (0..3)
+        .map(|i| SignatureAndIndex {
+            signature: signatory(i).sign(&message),
+            index: i,
+        })
+        .collect();
+
+    assert_eq!(
+        check(
+            Withdraw::<AtHeight<15>, 0>::new(signatures),
+            vec![grabbable(old)],
+            outputs,
+        ),
+        Err(ConstraintCheckerError::OutputsExceedInputs)
+    );
+}
+
+#[test]
+fn replayed_signature_fails() {
+    let old = vault();
+    let new = Vault {
+        balance: 990,
+        spent_this_period: 40,
+        nonce: 1,
+        ..old.clone()
+    };
+    let outputs = vec![grabbable(new.clone()), coin(10, recipient())];
+    let signature = signatory(0).sign(&old.withdrawal_message(&outputs));
+
+    // Replay the same signature against the vault that the first withdrawal produced.
+    let replayed = Vault {
+        balance: 980,
+        spent_this_period: 50,
+        nonce: 2,
+        ..old
+    };
+    assert_eq!(
+        check(
+            Withdraw::<AtHeight<15>, 0>::new(vec![SignatureAndIndex {
+                signature,
+                index: 0
+            }]),
+            vec![grabbable(new)],
+            vec![grabbable(replayed), coin(10, recipient())],
+        ),
+        Err(ConstraintCheckerError::NotSigned)
+    );
+}
@@ -0,0 +1,326 @@
+//! Vesting for the tokens provided by the money piece.
+//!
+//! A [`VestedCoin`] holds some amount of `Coin<ID>` value that unlocks gradually according to a
+//! [`VestingSchedule`]. Nothing is unlocked before the cliff. After the cliff, the value unlocks
+//! linearly from the start height until the end of the vesting duration.
+//!
+//! Coins are locked up with the [`VestCoins`] constraint checker, or by placing vested coins
+//! directly in the genesis state. The unlocked portion is later converted into ordinary spendable
+//! coins with the [`ClaimVested`] constraint checker. Any value that is still locked is carried
+//! forward in a new vested coin with the same schedule.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use core::marker::PhantomData;
+
+use money::Coin;
+use parity_scale_codec::{Decode, Encode};
+use scale_info::TypeInfo;
+use serde::{Deserialize, Serialize};
+use sp_runtime::transaction_validity::TransactionPriority;
+use sp_std::prelude::*;
+use tuxedo_core::{
+    dynamic_typing::{DynamicallyTypedData, UtxoData},
+    ensure,
+    support_macros::{CloneNoBound, DebugNoBound, DefaultNoBound},
+    CheckerContext, SimpleConstraintChecker,
+};
+
+#[cfg(test)]
+mod tests;
+
+/// Configuration items for the Vesting piece when it is
+/// instantiated in a concrete runtime.
+pub trait VestingConfig {
+    /// A means of getting the current block height.
+    /// Probably this will be the Tuxedo Executive
+    fn block_height() -> u32;
+}
+
+/// When and how quickly the value in a vested coin unlocks.
+#[derive(
+    Serialize,
+    Deserialize,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Clone,
+    Encode,
+    Decode,
+    Hash,
+    Debug,
+    TypeInfo,
+)]
+pub struct VestingSchedule {
+    /// The block height at which linear vesting begins.
+    pub start: u32,
+    /// The number of blocks after the start before anything can be claimed.
+    /// Once the cliff is reached, everything that vested since the start is claimable at once.
+    pub cliff: u32,
+    /// The number of blocks after the start until the entire value is unlocked.
+    pub duration: u32,
+}
+
+impl VestingSchedule {
+    /// The portion of `total` that has unlocked by the given block height.
+    pub fn vested_at(&self, total: u128, height: u32) -> u128 {
+        let elapsed = height.saturating_sub(self.start);
+        if elapsed < self.cliff {
+            return 0;
+        }
+        if elapsed >= self.duration {
+            return total;
+        }
+
+        let elapsed = elapsed as u128;
+        let duration = self.duration as u128;
+        total
+            .checked_mul(elapsed)
+            .map(|scaled| scaled / duration)
+            .unwrap_or_else(|| total / duration * elapsed)
+    }
+}
+
+/// Some value that unlocks over time according to a vesting schedule.
+#[derive(
+    Serialize,
+    Deserialize,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Clone,
+    Encode,
+    Decode,
+    Hash,
+    Debug,
+    TypeInfo,
+)]
+pub struct VestedCoin<const ID: u8> {
+    /// The total value locked under the schedule, including what has already been claimed.
+    pub total: u128,
+    /// How much of the total has already been claimed as spendable coins.
+    pub claimed: u128,
+    /// The schedule by which the total unlocks.
+    pub schedule: VestingSchedule,
+}
+
+impl<const ID: u8> VestedCoin<ID> {
+    /// The value that is still held in this vested coin.
+    pub fn remaining(&self) -> u128 {
+        self.total.saturating_sub(self.claimed)
+    }
+}
+
+impl<const ID: u8> UtxoData for VestedCoin<ID> {
+    const TYPE_ID: [u8; 4] = [b'v', b's', b't', ID];
+}
+
+/// Errors that can occur when checking vesting transactions.
+#[derive(
+    Serialize,
+    Deserialize,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Clone,
+    Encode,
+    Decode,
+    Hash,
+    Debug,
+    TypeInfo,
+)]
+pub enum ConstraintCheckerError {
+    /// An input data has the wrong type.
+    BadlyTypedInput,
+    /// An output data has the wrong type.
+    BadlyTypedOutput,
+    /// Wrong number of inputs were provided to the constraint checker.
+    WrongNumberInputs,
+    /// The transaction does not create anything, so it is a waste of processing power.
+    NothingCreated,
+    /// The transaction attempted to create a coin or vested coin with zero value.
+    /// This is not allowed because it wastes state space.
+    ZeroValue,
+    /// The value consumed or created by this transaction overflows the value type.
+    ValueOverflow,
+    /// The value of the vested coins created exceeds the value of the coins consumed.
+    OutputsExceedInputs,
+    /// A newly vested coin claims that some of its value has already been claimed.
+    AlreadyClaimed,
+    /// A vesting schedule's cliff comes after the end of its duration.
+    CliffAfterEnd,
+    /// More than one vested coin was created when claiming.
+    /// All the remaining value must be carried forward in a single vested coin.
+    MultipleRemainders,
+    /// The vested coin carrying the remaining value does not match the consumed vested coin.
+    /// Its total and schedule must be unchanged.
+    RemainderMismatch,
+    /// The amount claimed does not match the claimed coins that were created.
+    ClaimMismatch,
+    /// Attempted to claim more value than has unlocked so far.
+    ClaimExceedsVested,
+    /// Value is still locked, but no vested coin was created to carry it forward.
+    RemainderMissing,
+}
+
+/// A constraint checker that locks up coins under vesting schedules.
+///
+/// All inputs must be `Coin<ID>`s and all outputs must be fresh `VestedCoin<ID>`s. As with
+/// ordinary spends, any value that is not locked up pays the transaction's fee and tip.
+#[derive(
+    Serialize, Deserialize, Encode, Decode, Debug, Default, PartialEq, Eq, Clone, TypeInfo,
+)]
+pub struct VestCoins<const ID: u8>;
+
+impl<const ID: u8> SimpleConstraintChecker for VestCoins<ID> {
+    type Error = ConstraintCheckerError;
+
+    fn check(
+        &self,
+        input_data: &[DynamicallyTypedData],
+        _peeks: &[DynamicallyTypedData],
+        output_data: &[DynamicallyTypedData],
+        _context: &CheckerContext,
+    ) -> Result<TransactionPriority, Self::Error> {
+        ensure!(
+            !output_data.is_empty(),
+            ConstraintCheckerError::NothingCreated
+        );
+
+        let mut total_input_value: u128 = 0;
+        for input in input_data {
+            let value = input
+                .extract::<Coin<ID>>()
+                .map_err(|_| ConstraintCheckerError::BadlyTypedInput)?
+                .0;
+            total_input_value = total_input_value
+                .checked_add(value)
+                .ok_or(ConstraintCheckerError::ValueOverflow)?;
+        }
+
+        let mut total_output_value: u128 = 0;
+        for output in output_data {
+            let vested = output
+                .extract::<VestedCoin<ID>>()
+                .map_err(|_| ConstraintCheckerError::BadlyTypedOutput)?;
+            ensure!(vested.total > 0, ConstraintCheckerError::ZeroValue);
+            ensure!(vested.claimed == 0, ConstraintCheckerError::AlreadyClaimed);
+            ensure!(
+                vested.schedule.cliff <= vested.schedule.duration,
+                ConstraintCheckerError::CliffAfterEnd
+            );
+            total_output_value = total_output_value
+                .checked_add(vested.total)
+                .ok_or(ConstraintCheckerError::ValueOverflow)?;
+        }
+
+        ensure!(
+            total_output_value <= total_input_value,
+            ConstraintCheckerError::OutputsExceedInputs
+        );
+
+        let burned = total_input_value - total_output_value;
+        Ok(burned.try_into().unwrap_or(TransactionPriority::MAX))
+    }
+}
+
+/// A constraint checker that converts the unlocked portion of a vested coin into spendable coins.
+///
+/// There must be exactly one input which is the vested coin. The outputs are any number of
+/// `Coin<ID>`s containing the claimed value, and, if there is still locked value, exactly one
+/// `VestedCoin<ID>` with the same total and schedule and an updated claimed amount.
+#[derive(
+    Serialize,
+    Deserialize,
+    Encode,
+    Decode,
+    DebugNoBound,
+    DefaultNoBound,
+    PartialEq,
+    Eq,
+    CloneNoBound,
+    TypeInfo,
+)]
+#[scale_info(skip_type_params(T))]
+pub struct ClaimVested<T, const ID: u8>(PhantomData<T>);
+
+impl<T: VestingConfig, const ID: u8> SimpleConstraintChecker for ClaimVested<T, ID> {
+    type Error = ConstraintCheckerError;
+
+    fn check(
+        &self,
+        input_data: &[DynamicallyTypedData],
+        _peeks: &[DynamicallyTypedData],
+        output_data: &[DynamicallyTypedData],
+        _context: &CheckerContext,
+    ) -> Result<TransactionPriority, Self::Error> {
+        ensure!(
+            input_data.len() == 1,
+            ConstraintCheckerError::WrongNumberInputs
+        );
+        let vested = input_data[0]
+            .extract::<VestedCoin<ID>>()
+            .map_err(|_| ConstraintCheckerError::BadlyTypedInput)?;
+
+        // Sort the outputs into claimed coins and the remainder.
+        let mut claimed_now: u128 = 0;
+        let mut remainder = None;
+        for output in output_data {
+            if let Ok(coin) = output.extract::<Coin<ID>>() {
+                ensure!(coin.0 > 0, ConstraintCheckerError::ZeroValue);
+                claimed_now = claimed_now
+                    .checked_add(coin.0)
+                    .ok_or(ConstraintCheckerError::ValueOverflow)?;
+            } else {
+                let new_vested = output
+                    .extract::<VestedCoin<ID>>()
+                    .map_err(|_| ConstraintCheckerError::BadlyTypedOutput)?;
+                ensure!(
+                    remainder.is_none(),
+                    ConstraintCheckerError::MultipleRemainders
+                );
+                remainder = Some(new_vested);
+            }
+        }
+        ensure!(claimed_now > 0, ConstraintCheckerError::NothingCreated);
+
+        // Make sure the claim is covered by what has unlocked so far.
+        let claimed_total = vested
+            .claimed
+            .checked_add(claimed_now)
+            .ok_or(ConstraintCheckerError::ValueOverflow)?;
+        ensure!(
+            claimed_total <= vested.schedule.vested_at(vested.total, T::block_height()),
+            ConstraintCheckerError::ClaimExceedsVested
+        );
+
+        // Make sure whatever is still locked is carried forward.
+        match remainder {
+            Some(new_vested) => {
+                ensure!(
+                    new_vested.total == vested.total && new_vested.schedule == vested.schedule,
+                    ConstraintCheckerError::RemainderMismatch
+                );
+                ensure!(
+                    new_vested.claimed == claimed_total,
+                    ConstraintCheckerError::ClaimMismatch
+                );
+                ensure!(
+                    new_vested.remaining() > 0,
+                    ConstraintCheckerError::ZeroValue
+                );
+            }
+            None => {
+                ensure!(
+                    claimed_total == vested.total,
+                    ConstraintCheckerError::RemainderMissing
+                );
+            }
+        }
+
+        Ok(0)
+    }
+}
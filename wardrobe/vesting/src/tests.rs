@@ -0,0 +1,293 @@
+//! Unit tests for the Vesting piece
+
+use super::*;
+use tuxedo_core::dynamic_typing::testing::Bogus;
+
+/// A mock config that always reports the same block height.
+pub struct AtHeight<const H: u32>;
+
+impl<const H: u32> VestingConfig for AtHeight<H> {
+    fn block_height() -> u32 {
+        H
+    }
+}
+
+/// Starts at block 100, claimable from block 110, fully vested at block 200.
+const SCHEDULE: VestingSchedule = VestingSchedule {
+    start: 100,
+    cliff: 10,
+    duration: 100,
+};
+
+fn vested(total: u128, claimed: u128) -> VestedCoin<0> {
+    VestedCoin {
+        total,
+        claimed,
+        schedule: SCHEDULE,
+    }
+}
+
+#[test]
+fn vested_at_follows_schedule() {
+    assert_eq!(SCHEDULE.vested_at(1000, 50), 0);
+    assert_eq!(SCHEDULE.vested_at(1000, 109), 0);
+    assert_eq!(SCHEDULE.vested_at(1000, 110), 100);
+    assert_eq!(SCHEDULE.vested_at(1000, 150), 500);
+    assert_eq!(SCHEDULE.vested_at(1000, 200), 1000);
+    assert_eq!(SCHEDULE.vested_at(1000, 5000), 1000);
+}
+
+#[test]
+fn vested_at_does_not_overflow() {
+    assert_eq!(SCHEDULE.vested_at(u128::MAX, 150), u128::MAX / 100 * 50);
+}
+
+#[test]
+fn vest_coins_works() {
+    let input_data = vec![Coin::<0>(700).into(), Coin::<0>(400).into()];
+    let output_data = vec![vested(1000, 0).into()];
+
+    assert_eq!(
+        VestCoins::<0>.check(&input_data, &[], &output_data, &Default::default()),
+        Ok(100)
+    );
+}
+
+#[test]
+fn vest_coins_exceeding_inputs_fails() {
+    let input_data = vec![Coin::<0>(700).into()];
+    let output_data = vec![vested(1000, 0).into()];
+
+    assert_eq!(
+        VestCoins::<0>.check(&input_data, &[], &output_data, &Default::default()),
+        Err(ConstraintCheckerError::OutputsExceedInputs)
+    );
+}
+
+#[test]
+fn vest_coins_already_claimed_fails() {
+    let input_data = vec![Coin::<0>(1000).into()];
+    let output_data = vec![vested(1000, 1).into()];
+
+    assert_eq!(
+        VestCoins::<0>.check(&input_data, &[], &output_data, &Default::default()),
+        Err(ConstraintCheckerError::AlreadyClaimed)
+    );
+}
+
+#[test]
+fn vest_coins_cliff_after_end_fails() {
+    let input_data = vec![Coin::<0>(1000).into()];
+    let output_data = vec![VestedCoin::<0> {
+        total: 1000,
+        claimed: 0,
+        schedule: VestingSchedule {
+            start: 0,
+            cliff: 20,
+            duration: 10,
+        },
+    }
+    .into()];
+
+    assert_eq!(
+        VestCoins::<0>.check(&input_data, &[], &output_data, &Default::default()),
+        Err(ConstraintCheckerError::CliffAfterEnd)
+    );
+}
+
+#[test]
+fn vest_coins_wrong_input_type_fails() {
+    let input_data = vec![Bogus.into()];
+    let output_data = vec![vested(1000, 0).into()];
+
+    assert_eq!(
+        VestCoins::<0>.check(&input_data, &[], &output_data, &Default::default()),
+        Err(ConstraintCheckerError::BadlyTypedInput)
+    );
+}
+
+#[test]
+fn claim_partial_works() {
+    let input_data = vec![vested(1000, 0).into()];
+    let output_data = vec![Coin::<0>(500).into(), vested(1000, 500).into()];
+
+    assert_eq!(
+        ClaimVested::<AtHeight<150>, 0>::default().check(
+            &input_data,
+            &[],
+            &output_data,
+            &Default::default()
+        ),
+        Ok(0)
+    );
+}
+
+#[test]
+fn claim_after_previous_claim_works() {
+    let input_data = vec![vested(1000, 500).into()];
+    let output_data = vec![
+        Coin::<0>(100).into(),
+        Coin::<0>(100).into(),
+        vested(1000, 700).into(),
+    ];
+
+    assert_eq!(
+        ClaimVested::<AtHeight<170>, 0>::default().check(
+            &input_data,
+            &[],
+            &output_data,
+            &Default::default()
+        ),
+        Ok(0)
+    );
+}
+
+#[test]
+fn claim_everything_after_end_works() {
+    let input_data = vec![vested(1000, 500).into()];
+    let output_data = vec![Coin::<0>(500).into()];
+
+    assert_eq!(
+        ClaimVested::<AtHeight<200>, 0>::default().check(
+            &input_data,
+            &[],
+            &output_data,
+            &Default::default()
+        ),
+        Ok(0)
+    );
+}
+
+#[test]
+fn claim_before_cliff_fails() {
+    let input_data = vec![vested(1000, 0).into()];
+    let output_data = vec![Coin::<0>(1).into(), vested(1000, 1).into()];
+
+    assert_eq!(
+        ClaimVested::<AtHeight<105>, 0>::default().check(
+            &input_data,
+            &[],
+            &output_data,
+            &Default::default()
+        ),
+        Err(ConstraintCheckerError::ClaimExceedsVested)
+    );
+}
+
+#[test]
+fn claim_more_than_vested_fails() {
+    let input_data = vec![vested(1000, 0).into()];
+    let output_data = vec![Coin::<0>(501).into(), vested(1000, 501).into()];
+
+    assert_eq!(
+        ClaimVested::<AtHeight<150>, 0>::default().check(
+            &input_data,
+            &[],
+            &output_data,
+            &Default::default()
+        ),
+        Err(ConstraintCheckerError::ClaimExceedsVested)
+    );
+}
+
+#[test]
+fn claim_without_remainder_fails() {
+    let input_data = vec![vested(1000, 0).into()];
+    let output_data = vec![Coin::<0>(500).into()];
+
+    assert_eq!(
+        ClaimVested::<AtHeight<150>, 0>::default().check(
+            &input_data,
+            &[],
+            &output_data,
+            &Default::default()
+        ),
+        Err(ConstraintCheckerError::RemainderMissing)
+    );
+}
+
+#[test]
+fn claim_with_wrong_claimed_amount_fails() {
+    let input_data = vec![vested(1000, 0).into()];
+    let output_data = vec![Coin::<0>(500).into(), vested(1000, 400).into()];
+
+    assert_eq!(
+        ClaimVested::<AtHeight<150>, 0>::default().check(
+            &input_data,
+            &[],
+            &output_data,
+            &Default::default()
+        ),
+        Err(ConstraintCheckerError::ClaimMismatch)
+    );
+}
+
+#[test]
+fn claim_changing_schedule_fails() {
+    let input_data = vec![vested(1000, 0).into()];
+    let mut new_vested = vested(1000, 500);
+    new_vested.schedule.duration = 50;
+    let output_data = vec![Coin::<0>(500).into(), new_vested.into()];
+
+    assert_eq!(
+        ClaimVested::<AtHeight<150>, 0>::default().check(
+            &input_data,
+            &[],
+            &output_data,
+            &Default::default()
+        ),
+        Err(ConstraintCheckerError::RemainderMismatch)
+    );
+}
+
+#[test]
+fn claim_with_multiple_remainders_fails() {
+    let input_data = vec![vested(1000, 0).into()];
+    let output_data = vec![
+        Coin::<0>(500).into(),
+        vested(1000, 500).into(),
+        vested(1000, 500).into(),
+    ];
+
+    assert_eq!(
+        ClaimVested::<AtHeight<150>, 0>::default().check(
+            &input_data,
+            &[],
+            &output_data,
+            &Default::default()
+        ),
+        Err(ConstraintCheckerError::MultipleRemainders)
+    );
+}
+
+#[test]
+fn claim_nothing_fails() {
+    let input_data = vec![vested(1000, 0).into()];
+    let output_data = vec![vested(1000, 0).into()];
+
+    assert_eq!(
+        ClaimVested::<AtHeight<150>, 0>::default().check(
+            &input_data,
+            &[],
+            &output_data,
+            &Default::default()
+        ),
+        Err(ConstraintCheckerError::NothingCreated)
+    );
+}
+
+#[test]
+fn claim_wrong_output_type_fails() {
+    let input_data = vec![vested(1000, 0).into()];
+    let output_data = vec![Coin::<0>(500).into(), Bogus.into()];
+
+    assert_eq!(
+        ClaimVested::<AtHeight<150>, 0>::default().check(
+            &input_data,
+            &[],
+            &output_data,
+            &Default::default()
+        ),
+        Err(ConstraintCheckerError::BadlyTypedOutput)
+    );
+}
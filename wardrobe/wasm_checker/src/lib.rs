@@ -0,0 +1,377 @@
+//! Constraint checkers that are small Wasm programs, so that new application logic can be deployed
+//! without a runtime upgrade.
+//!
+//! Governance approves a program by enacting a referendum whose payload is the hash of its code.
+//! Then an [`UploadProgram`] transaction consumes the enacted outcome and creates a [`Program`]
+//! output that holds the code. Programs are locked by the [`Unspendable`] verifier, so they stay in
+//! the state for good, and transactions read them by peeking.
+//!
+//! A [`RunProgram`] transaction names the program that checks it by the hash of its code, and
+//! peeks at the program's output. The program then decides whether the transaction is valid. It
+//! runs in the [`wasm_sandbox`], so the work it may do is bounded by [`WasmCheckerConfig::FUEL`],
+//! and its memory and tables are limited too.
+//!
+//! An application built this way keeps its state in [`ProgramState`] outputs, each of which belongs
+//! to one program. A transaction that runs a program may only consume and create that program's
+//! state. Programs can not create data for other pieces, so a faulty program can not mint coins or
+//! tamper with other applications. They may peek at any data, for example to read an oracle.
+//!
+//! ## Program Interface
+//!
+//! A program must export a function `check` that takes no parameters and returns an `i32`, and its
+//! `memory`. The transaction is valid if `check` returns `0`. Any other value rejects it, and is
+//! reported in [`ConstraintCheckerError::Rejected`] to help the application's users. To read the
+//! transaction, the program may import these functions from the `env` module:
+//! * `inputs_len() -> i32` and `read_inputs(ptr: i32)`, which writes the data of the consumed
+//!   states at `ptr`, encoded as a SCALE `Vec<Vec<u8>>`.
+//! * `outputs_len() -> i32` and `read_outputs(ptr: i32)`, which does the same for the new states.
+//! * `peeks_len() -> i32` and `read_peeks(ptr: i32)`, which writes all the peeked data, including
+//!   the program itself, encoded as a SCALE `Vec<DynamicallyTypedData>`.
+//! * `block_height() -> i32`, which is the height of the block that includes the transaction.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use core::marker::PhantomData;
+
+use governance::Enacted;
+use parity_scale_codec::{Decode, Encode};
+use scale_info::TypeInfo;
+use serde::{Deserialize, Serialize};
+use sp_core::H256;
+use sp_runtime::{
+    traits::{BlakeTwo256, Hash},
+    transaction_validity::TransactionPriority,
+};
+use sp_std::prelude::*;
+use tuxedo_core::{
+    dynamic_typing::{DynamicallyTypedData, UtxoData},
+    ensure,
+    support_macros::{CloneNoBound, DebugNoBound, DefaultNoBound},
+    types::Output,
+    verifier::Unspendable,
+    wasm_sandbox::{self, Host},
+    weights::{Weight, DEFAULT_CHECKER_WEIGHT},
+    CheckerContext, ConstraintChecker, SimpleConstraintChecker, Verifier,
+};
+
+#[cfg(test)]
+mod tests;
+
+/// Configuration items for the Wasm checker piece when it is
+/// instantiated in a concrete runtime.
+pub trait WasmCheckerConfig {
+    /// The largest program, in bytes, that may be uploaded or run.
+    const MAX_CODE_SIZE: usize = 64 * 1024;
+
+    /// The most fuel that a program may consume while checking a transaction. Roughly, each
+    /// executed Wasm instruction consumes one unit of fuel.
+    const FUEL: u64 = 1_000_000;
+}
+
+/// A Wasm program that governance approved to check transactions.
+#[derive(Serialize, Deserialize, Encode, Decode, Debug, PartialEq, Eq, Clone, TypeInfo)]
+pub struct Program {
+    /// The Wasm code of the program.
+    pub code: Vec<u8>,
+}
+
+impl Program {
+    /// The hash of the program's code, which is how transactions and governance refer to it.
+    pub fn hash(&self) -> H256 {
+        BlakeTwo256::hash(&self.code)
+    }
+}
+
+impl UtxoData for Program {
+    const TYPE_ID: [u8; 4] = *b"wasm";
+}
+
+/// A piece of state that belongs to the application of a single program.
+#[derive(Serialize, Deserialize, Encode, Decode, Debug, PartialEq, Eq, Clone, TypeInfo)]
+pub struct ProgramState {
+    /// The hash of the program that the state belongs to.
+    pub program: H256,
+    /// The state itself, which only the program interprets.
+    pub data: Vec<u8>,
+}
+
+impl UtxoData for ProgramState {
+    const TYPE_ID: [u8; 4] = *b"wsta";
+}
+
+/// Errors that can occur when uploading or running programs.
+#[derive(
+    Serialize,
+    Deserialize,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Clone,
+    Encode,
+    Decode,
+    Hash,
+    Debug,
+    TypeInfo,
+)]
+pub enum ConstraintCheckerError {
+    /// An input data has the wrong type.
+    BadlyTypedInput,
+    /// An output data has the wrong type.
+    BadlyTypedOutput,
+    /// Wrong number of inputs were provided to the constraint checker.
+    WrongNumberInputs,
+    /// Wrong number of outputs were provided to the constraint checker.
+    WrongNumberOutputs,
+    /// The upload is not backed by an enacted referendum outcome.
+    NotApproved,
+    /// The enacted referendum outcome approves some other payload than the uploaded program.
+    WrongGovernanceOutcome,
+    /// The uploaded program is not locked by the `Unspendable` verifier.
+    ProgramNotUnspendable,
+    /// The uploaded program is larger than the runtime allows.
+    ProgramTooLarge,
+    /// The uploaded program is not valid Wasm.
+    InvalidProgram,
+    /// The transaction does not peek at the program that it names.
+    ProgramNotPeeked,
+    /// The transaction consumes or creates state that belongs to another program.
+    ForeignState,
+    /// The program trapped, ran out of fuel, or does not follow the program interface.
+    ProgramFailed,
+    /// The program rejected the transaction with the given code.
+    Rejected(i32),
+}
+
+/// A constraint checker that uploads a program with the approval of governance.
+///
+/// The single input is an enacted referendum outcome whose payload is the hash of the program.
+/// The single output is the program, which must be unspendable.
+#[derive(
+    Serialize,
+    Deserialize,
+    Encode,
+    Decode,
+    DebugNoBound,
+    DefaultNoBound,
+    PartialEq,
+    Eq,
+    CloneNoBound,
+    TypeInfo,
+)]
+#[scale_info(skip_type_params(T))]
+pub struct UploadProgram<T, const ID: u8>(PhantomData<T>);
+
+impl<T: WasmCheckerConfig, V: Verifier + PartialEq + From<Unspendable>, const ID: u8>
+    ConstraintChecker<V> for UploadProgram<T, ID>
+{
+    type Error = ConstraintCheckerError;
+    type InherentHooks = ();
+
+    fn check(
+        &self,
+        inputs: &[Output<V>],
+        _peeks: &[Output<V>],
+        outputs: &[Output<V>],
+        _context: &CheckerContext,
+    ) -> Result<TransactionPriority, Self::Error> {
+        ensure!(inputs.len() == 1, ConstraintCheckerError::WrongNumberInputs);
+        ensure!(
+            outputs.len() == 1,
+            ConstraintCheckerError::WrongNumberOutputs
+        );
+
+        let program = outputs[0]
+            .payload
+            .extract::<Program>()
+            .map_err(|_| ConstraintCheckerError::BadlyTypedOutput)?;
+        ensure!(
+            outputs[0].verifier == Unspendable.into(),
+            ConstraintCheckerError::ProgramNotUnspendable
+        );
+        ensure!(
+            program.code.len() <= T::MAX_CODE_SIZE,
+            ConstraintCheckerError::ProgramTooLarge
+        );
+        ensure!(
+            wasm_sandbox::compile(&wasm_sandbox::engine(), &program.code, T::MAX_CODE_SIZE)
+                .is_some(),
+            ConstraintCheckerError::InvalidProgram
+        );
+
+        let outcome = inputs[0]
+            .payload
+            .extract::<Enacted<ID>>()
+            .map_err(|_| ConstraintCheckerError::NotApproved)?;
+        ensure!(
+            outcome.payload == program.hash(),
+            ConstraintCheckerError::WrongGovernanceOutcome
+        );
+
+        Ok(0)
+    }
+
+    fn is_inherent(&self) -> bool {
+        false
+    }
+}
+
+/// A constraint checker that lets an uploaded program check the transaction.
+///
+/// The transaction must peek at the program. Its inputs and outputs must all be the program's state.
+#[derive(
+    Serialize, Deserialize, Encode, Decode, DebugNoBound, PartialEq, Eq, CloneNoBound, TypeInfo,
+)]
+#[scale_info(skip_type_params(T))]
+pub struct RunProgram<T> {
+    /// The hash of the program's code.
+    pub program: H256,
+    _config: PhantomData<T>,
+}
+
+impl<T> RunProgram<T> {
+    pub fn new(program: H256) -> Self {
+        Self {
+            program,
+            _config: PhantomData,
+        }
+    }
+
+    /// The data of the given states, after making sure that they all belong to this program.
+    fn own_states(
+        &self,
+        data: &[DynamicallyTypedData],
+        badly_typed: ConstraintCheckerError,
+    ) -> Result<Vec<Vec<u8>>, ConstraintCheckerError> {
+        data.iter()
+            .map(|d| -> Result<Vec<u8>, ConstraintCheckerError> {
+                let state = d
+                    .extract::<ProgramState>()
+                    .map_err(|_| badly_typed.clone())?;
+                ensure!(
+                    state.program == self.program,
+                    ConstraintCheckerError::ForeignState
+                );
+                Ok(state.data)
+            })
+            .collect()
+    }
+}
+
+/// The parts of the transaction that the program can read through the host functions
+struct TransactionData {
+    inputs: Vec<u8>,
+    outputs: Vec<u8>,
+    peeks: Vec<u8>,
+    block_height: u32,
+}
+
+/// Run the program over the transaction. Returns `None` if it could not be run to completion.
+fn run<T: WasmCheckerConfig>(code: &[u8], transaction: TransactionData) -> Option<i32> {
+    use wasmi::Caller;
+
+    wasm_sandbox::run(
+        code,
+        T::MAX_CODE_SIZE,
+        T::FUEL,
+        transaction,
+        "check",
+        |linker| {
+            linker
+                .func_wrap(
+                    "env",
+                    "inputs_len",
+                    |caller: Caller<'_, Host<TransactionData>>| {
+                        caller.data().data.inputs.len() as i32
+                    },
+                )
+                .ok()?
+                .func_wrap(
+                    "env",
+                    "read_inputs",
+                    |mut caller: Caller<'_, Host<TransactionData>>, ptr: i32| {
+                        wasm_sandbox::write(&mut caller, ptr, |tx| &tx.inputs)
+                    },
+                )
+                .ok()?
+                .func_wrap(
+                    "env",
+                    "outputs_len",
+                    |caller: Caller<'_, Host<TransactionData>>| {
+                        caller.data().data.outputs.len() as i32
+                    },
+                )
+                .ok()?
+                .func_wrap(
+                    "env",
+                    "read_outputs",
+                    |mut caller: Caller<'_, Host<TransactionData>>, ptr: i32| {
+                        wasm_sandbox::write(&mut caller, ptr, |tx| &tx.outputs)
+                    },
+                )
+                .ok()?
+                .func_wrap(
+                    "env",
+                    "peeks_len",
+                    |caller: Caller<'_, Host<TransactionData>>| {
+                        caller.data().data.peeks.len() as i32
+                    },
+                )
+                .ok()?
+                .func_wrap(
+                    "env",
+                    "read_peeks",
+                    |mut caller: Caller<'_, Host<TransactionData>>, ptr: i32| {
+                        wasm_sandbox::write(&mut caller, ptr, |tx| &tx.peeks)
+                    },
+                )
+                .ok()?
+                .func_wrap(
+                    "env",
+                    "block_height",
+                    |caller: Caller<'_, Host<TransactionData>>| {
+                        caller.data().data.block_height as i32
+                    },
+                )
+                .ok()?;
+            Some(())
+        },
+    )
+}
+
+impl<T: WasmCheckerConfig> SimpleConstraintChecker for RunProgram<T> {
+    type Error = ConstraintCheckerError;
+
+    fn check(
+        &self,
+        input_data: &[DynamicallyTypedData],
+        peek_data: &[DynamicallyTypedData],
+        output_data: &[DynamicallyTypedData],
+        context: &CheckerContext,
+    ) -> Result<TransactionPriority, Self::Error> {
+        let program = peek_data
+            .iter()
+            .filter_map(|d| d.extract::<Program>().ok())
+            .find(|program| program.hash() == self.program)
+            .ok_or(ConstraintCheckerError::ProgramNotPeeked)?;
+
+        let inputs = self.own_states(input_data, ConstraintCheckerError::BadlyTypedInput)?;
+        let outputs = self.own_states(output_data, ConstraintCheckerError::BadlyTypedOutput)?;
+
+        let transaction = TransactionData {
+            inputs: inputs.encode(),
+            outputs: outputs.encode(),
+            peeks: peek_data.encode(),
+            block_height: context.block_height,
+        };
+        match run::<T>(&program.code, transaction) {
+            Some(0) => Ok(0),
+            Some(code) => Err(ConstraintCheckerError::Rejected(code)),
+            None => Err(ConstraintCheckerError::ProgramFailed),
+        }
+    }
+
+    fn weight(&self) -> Weight {
+        DEFAULT_CHECKER_WEIGHT.saturating_add(wasm_sandbox::weight(T::MAX_CODE_SIZE, T::FUEL))
+    }
+}
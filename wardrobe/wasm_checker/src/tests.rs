@@ -0,0 +1,389 @@
+//! Unit tests for the Wasm Checker piece
+
+use super::*;
+use tuxedo_core::{tuxedo_verifier, verifier::UpForGrabs};
+
+/// A verifier that can represent programs and ordinary outputs.
+#[derive(Serialize, Deserialize, Encode, Decode, Debug, PartialEq, Eq, Clone, TypeInfo)]
+#[tuxedo_verifier]
+pub enum TestVerifier {
+    Unspendable(Unspendable),
+    UpForGrabs(UpForGrabs),
+}
+
+/// The mock config uses the default limits.
+pub struct TestConfig;
+
+impl WasmCheckerConfig for TestConfig {}
+
+/// A mock config with tight limits.
+pub struct TinyConfig;
+
+impl WasmCheckerConfig for TinyConfig {
+    const MAX_CODE_SIZE: usize = 16;
+    const FUEL: u64 = 100;
+}
+
+/// A program that accepts every transaction.
+const ACCEPT: &str = r#"
+    (module
+        (memory (export "memory") 1)
+        (func (export "check") (result i32) i32.const 0))
+"#;
+
+/// A program that rejects every transaction with code 7.
+const REJECT: &str = r#"
+    (module
+        (memory (export "memory") 1)
+        (func (export "check") (result i32) i32.const 7))
+"#;
+
+/// A program that never finishes.
+const SPIN: &str = r#"
+    (module
+        (memory (export "memory") 1)
+        (func (export "check") (result i32)
+            (loop $forever (br $forever))
+            i32.const 0))
+"#;
+
+/// A program that accepts transactions that consume a single state, which it recognizes from the
+/// compact length prefix of the encoded inputs, and rejects the rest with code 1.
+const SINGLE_INPUT: &str = r#"
+    (module
+        (import "env" "inputs_len" (func $inputs_len (result i32)))
+        (import "env" "read_inputs" (func $read_inputs (param i32)))
+        (memory (export "memory") 1)
+        (func (export "check") (result i32)
+            (if (i32.eqz (call $inputs_len))
+                (then (return (i32.const 1))))
+            (call $read_inputs (i32.const 0))
+            (if (result i32) (i32.eq (i32.load8_u (i32.const 0)) (i32.const 4))
+                (then (i32.const 0))
+                (else (i32.const 1)))))
+"#;
+
+/// A program that accepts transactions from height ten onward, and rejects the rest with code 1.
+const FROM_TEN: &str = r#"
+    (module
+        (import "env" "block_height" (func $block_height (result i32)))
+        (memory (export "memory") 1)
+        (func (export "check") (result i32)
+            (if (result i32) (i32.ge_u (call $block_height) (i32.const 10))
+                (then (i32.const 0))
+                (else (i32.const 1)))))
+"#;
+
+fn program(wat: &str) -> Program {
+    Program {
+        code: wat::parse_str(wat).unwrap(),
+    }
+}
+
+fn state(program: &Program, data: Vec<u8>) -> DynamicallyTypedData {
+    ProgramState {
+        program: program.hash(),
+        data,
+    }
+    .into()
+}
+
+fn approval(program: &Program) -> Output<TestVerifier> {
+    let payload = program.hash();
+    (Enacted::<0> { payload }, UpForGrabs).into()
+}
+
+fn upload<T: WasmCheckerConfig>(
+    inputs: Vec<Output<TestVerifier>>,
+    outputs: Vec<Output<TestVerifier>>,
+) -> Result<TransactionPriority, ConstraintCheckerError> {
+    UploadProgram::<T, 0>::default().check(&inputs, &[], &outputs, &Default::default())
+}
+
+fn check_with<T: WasmCheckerConfig>(
+    program: &Program,
+    inputs: Vec<DynamicallyTypedData>,
+    peeks: Vec<DynamicallyTypedData>,
+    outputs: Vec<DynamicallyTypedData>,
+    block_height: u32,
+) -> Result<TransactionPriority, ConstraintCheckerError> {
+    let context = CheckerContext {
+        block_height,
+        ..Default::default()
+    };
+    SimpleConstraintChecker::check(
+        &RunProgram::<T>::new(program.hash()),
+        &inputs,
+        &peeks,
+        &outputs,
+        &context,
+    )
+}
+
+#[test]
+fn upload_works() {
+    let accept = program(ACCEPT);
+
+    assert_eq!(
+        upload::<TestConfig>(vec![approval(&accept)], vec![(accept, Unspendable).into()]),
+        Ok(0)
+    );
+}
+
+#[test]
+fn upload_without_approval_fails() {
+    let accept = program(ACCEPT);
+
+    assert_eq!(
+        upload::<TestConfig>(
+            vec![(accept.clone(), UpForGrabs).into()],
+            vec![(accept, Unspendable).into()]
+        ),
+        Err(ConstraintCheckerError::NotApproved)
+    );
+}
+
+#[test]
+fn upload_with_wrong_outcome_fails() {
+    let accept = program(ACCEPT);
+
+    assert_eq!(
+        upload::<TestConfig>(
+            vec![approval(&program(REJECT))],
+            vec![(accept, Unspendable).into()]
+        ),
+        Err(ConstraintCheckerError::WrongGovernanceOutcome)
+    );
+}
+
+#[test]
+fn upload_with_extra_input_fails() {
+    let accept = program(ACCEPT);
+
+    assert_eq!(
+        upload::<TestConfig>(
+            vec![approval(&accept), approval(&accept)],
+            vec![(accept, Unspendable).into()]
+        ),
+        Err(ConstraintCheckerError::WrongNumberInputs)
+    );
+}
+
+#[test]
+fn upload_of_spendable_program_fails() {
+    let accept = program(ACCEPT);
+
+    assert_eq!(
+        upload::<TestConfig>(vec![approval(&accept)], vec![(accept, UpForGrabs).into()]),
+        Err(ConstraintCheckerError::ProgramNotUnspendable)
+    );
+}
+
+#[test]
+fn upload_of_large_program_fails() {
+    let accept = program(ACCEPT);
+
+    assert_eq!(
+        upload::<TinyConfig>(vec![approval(&accept)], vec![(accept, Unspendable).into()]),
+        Err(ConstraintCheckerError::ProgramTooLarge)
+    );
+}
+
+#[test]
+fn upload_of_invalid_wasm_fails() {
+    let bogus = Program {
+        code: b"bogus".to_vec(),
+    };
+
+    assert_eq!(
+        upload::<TestConfig>(vec![approval(&bogus)], vec![(bogus, Unspendable).into()]),
+        Err(ConstraintCheckerError::InvalidProgram)
+    );
+}
+
+#[test]
+fn accepting_program_works() {
+    let accept = program(ACCEPT);
+
+    assert_eq!(
+        check_with::<TestConfig>(
+            &accept,
+            vec![state(&accept, vec![1])],
+            vec![accept.clone().into()],
+            vec![state(&accept, vec![2]), state(&accept, vec![3])],
+            0
+        ),
+        Ok(0)
+    );
+}
+
+#[test]
+fn rejecting_program_reports_code() {
+    let reject = program(REJECT);
+
+    assert_eq!(
+        check_with::<TestConfig>(&reject, vec![], vec![reject.clone().into()], vec![], 0),
+        Err(ConstraintCheckerError::Rejected(7))
+    );
+}
+
+#[test]
+fn running_without_peeking_program_fails() {
+    let accept = program(ACCEPT);
+
+    assert_eq!(
+        check_with::<TestConfig>(&accept, vec![], vec![program(REJECT).into()], vec![], 0),
+        Err(ConstraintCheckerError::ProgramNotPeeked)
+    );
+}
+
+#[test]
+fn consuming_foreign_state_fails() {
+    let accept = program(ACCEPT);
+    let reject = program(REJECT);
+
+    assert_eq!(
+        check_with::<TestConfig>(
+            &accept,
+            vec![state(&reject, vec![1])],
+            vec![accept.clone().into()],
+            vec![],
+            0
+        ),
+        Err(ConstraintCheckerError::ForeignState)
+    );
+}
+
+#[test]
+fn creating_foreign_state_fails() {
+    let accept = program(ACCEPT);
+    let reject = program(REJECT);
+
+    assert_eq!(
+        check_with::<TestConfig>(
+            &accept,
+            vec![],
+            vec![accept.clone().into()],
+            vec![state(&reject, vec![1])],
+            0
+        ),
+        Err(ConstraintCheckerError::ForeignState)
+    );
+}
+
+#[test]
+fn creating_other_data_fails() {
+    let accept = program(ACCEPT);
+
+    assert_eq!(
+        check_with::<TestConfig>(
+            &accept,
+            vec![],
+            vec![accept.clone().into()],
+            vec![accept.clone().into()],
+            0
+        ),
+        Err(ConstraintCheckerError::BadlyTypedOutput)
+    );
+}
+
+#[test]
+fn program_out_of_fuel_fails() {
+    let spin = program(SPIN);
+
+    assert_eq!(
+        check_with::<TinyConfig>(&spin, vec![], vec![spin.clone().into()], vec![], 0),
+        Err(ConstraintCheckerError::ProgramFailed)
+    );
+}
+
+#[test]
+fn program_without_check_function_fails() {
+    let verifier = program(r#"(module (func (export "verify") (result i32) i32.const 0))"#);
+
+    assert_eq!(
+        check_with::<TestConfig>(&verifier, vec![], vec![verifier.clone().into()], vec![], 0),
+        Err(ConstraintCheckerError::ProgramFailed)
+    );
+}
+
+#[test]
+fn program_with_large_memory_fails() {
+    let hog = program(
+        r#"(module
+            (memory (export "memory") 65536)
+            (func (export "check") (result i32) i32.const 0))"#,
+    );
+
+    assert_eq!(
+        check_with::<TestConfig>(&hog, vec![], vec![hog.clone().into()], vec![], 0),
+        Err(ConstraintCheckerError::ProgramFailed)
+    );
+}
+
+#[test]
+fn program_growing_memory_past_limit_fails() {
+    let hog = program(
+        r#"(module
+            (memory (export "memory") 1)
+            (func (export "check") (result i32)
+                (i32.eq (memory.grow (i32.const 1024)) (i32.const -1))))"#,
+    );
+
+    assert_eq!(
+        check_with::<TestConfig>(&hog, vec![], vec![hog.clone().into()], vec![], 0),
+        Err(ConstraintCheckerError::Rejected(1))
+    );
+}
+
+#[test]
+fn program_larger_than_limit_fails() {
+    let accept = program(ACCEPT);
+
+    assert_eq!(
+        check_with::<TinyConfig>(&accept, vec![], vec![accept.clone().into()], vec![], 0),
+        Err(ConstraintCheckerError::ProgramFailed)
+    );
+}
+
+#[test]
+fn program_reads_inputs() {
+    let single = program(SINGLE_INPUT);
+    let peeks = vec![single.clone().into()];
+
+    assert_eq!(
+        check_with::<TestConfig>(
+            &single,
+            vec![state(&single, vec![1])],
+            peeks.clone(),
+            vec![],
+            0
+        ),
+        Ok(0)
+    );
+    assert_eq!(
+        check_with::<TestConfig>(
+            &single,
+            vec![state(&single, vec![1]), state(&single, vec![2])],
+            peeks,
+            vec![],
+            0
+        ),
+        Err(ConstraintCheckerError::Rejected(1))
+    );
+}
+
+#[test]
+fn program_reads_block_height() {
+    let from_ten = program(FROM_TEN);
+    let peeks = vec![from_ten.clone().into()];
+
+    assert_eq!(
+        check_with::<TestConfig>(&from_ten, vec![], peeks.clone(), vec![], 9),
+        Err(ConstraintCheckerError::Rejected(1))
+    );
+    assert_eq!(
+        check_with::<TestConfig>(&from_ten, vec![], peeks, vec![], 10),
+        Ok(0)
+    );
+}
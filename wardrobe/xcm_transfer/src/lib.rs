@@ -0,0 +1,403 @@
+//! Moves the relay chain's token between the relay chain and this parachain with XCM reserve transfers.
+//!
+//! The relay chain is the reserve. Tokens that somebody sends to this parachain stay locked in the
+//! parachain's sovereign account on the relay chain, and the parachain mints an equal amount of
+//! [`Coin<RELAY>`](Coin) to represent them. Burning those coins again releases the locked tokens.
+//!
+//! * Incoming transfers arrive as downward messages. Once per block, the block author includes a
+//!   [`ReceiveReserveTransfers`] inherent that mints a coin to the beneficiary of each reserve asset
+//!   deposit among the block's downward messages. Other downward messages are ignored.
+//! * Outgoing transfers use the [`BurnAndSend`] constraint checker. It burns relay coins and sends
+//!   an upward message that withdraws the same amount from the sovereign account to a beneficiary
+//!   on the relay chain.
+//!
+//! Beneficiaries are sr25519 public keys on both sides, so a minted coin is locked with a [`SigCheck`].
+//!
+//! ## Trust Warning
+//!
+//! The downward messages are not yet checked against the message queue chain in the relay chain
+//! state proof, so this piece trusts the collator to report them faithfully. Messages are also
+//! not charged for the XCM execution that they buy, so the whole deposited amount is minted.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use core::marker::PhantomData;
+
+use cumulus_primitives_core::InboundDownwardMessage;
+use cumulus_primitives_parachain_inherent::{
+    ParachainInherentData, INHERENT_IDENTIFIER as PARACHAIN_INHERENT_IDENTIFIER,
+};
+use money::{Coin, MoneyConfig};
+use parity_scale_codec::{Decode, DecodeLimit, Encode};
+use scale_info::TypeInfo;
+use serde::{Deserialize, Serialize};
+use sp_core::H256;
+use sp_inherents::{CheckInherentsResult, InherentData, MakeFatalError};
+use sp_runtime::transaction_validity::TransactionPriority;
+use sp_std::{vec, vec::Vec};
+// We get all the Tuxedo core stuff through the re-export so we don't risk crossed versions.
+use tuxedo_parachain_core::{
+    tuxedo_core::{
+        dynamic_typing::DynamicallyTypedData,
+        ensure,
+        inherents::{TuxedoInherent, TuxedoInherentAdapter},
+        support_macros::{CloneNoBound, DebugNoBound, DefaultNoBound},
+        types::{Output, Transaction, TransactionVersion},
+        verifier::SigCheck,
+        CheckerContext, ConstraintChecker, SimpleConstraintChecker, Verifier,
+    },
+    GetDownwardMessagesStorage, GetRelayParentNumberStorage, SendUpwardMessage,
+};
+use xcm::{
+    v3::{
+        AssetId::Concrete, Fungibility::Fungible, Instruction, Junction::AccountId32,
+        Junctions::X1, MultiAsset, MultiAssetFilter, MultiLocation, WeightLimit,
+        WildMultiAsset::AllCounted, Xcm,
+    },
+    VersionedXcm, MAX_XCM_DECODE_DEPTH,
+};
+
+#[cfg(test)]
+mod tests;
+
+/// A piece-wide target for logging
+const LOG_TARGET: &str = "xcm-transfer";
+
+/// The coin id that a runtime is expected to use for the relay chain's token.
+pub const RELAY: u8 = 1;
+
+/// Configuration items for the xcm transfer piece when it is
+/// instantiated in a concrete runtime.
+pub trait XcmTransferConfig: MoneyConfig {
+    /// A means of getting the relay parent number of the current block.
+    /// Probably this will be `tuxedo_parachain_core::RelayParentNumberStorage`.
+    type GetRelayParentNumber: GetRelayParentNumberStorage;
+
+    /// A means of getting the downward messages that the relay chain delivered to the current block.
+    /// Probably this will be `tuxedo_parachain_core::DownwardMessagesStorage`.
+    type GetDownwardMessages: GetDownwardMessagesStorage;
+
+    /// A means of sending messages up to the relay chain.
+    /// Probably this will be `tuxedo_parachain_core::UpwardMessages`.
+    type SendUpwardMessage: SendUpwardMessage;
+}
+
+/// The amount and beneficiary of a reserve transfer of the relay chain's token, if the given
+/// downward message is one.
+///
+/// The message must deposit the relay chain's token as a reserve asset, and then deposit it to an
+/// account on this parachain. Any other message gives `None`.
+pub fn parse_reserve_deposit(message: &[u8]) -> Option<(u128, H256)> {
+    let versioned =
+        VersionedXcm::<()>::decode_all_with_depth_limit(MAX_XCM_DECODE_DEPTH, &mut &message[..])
+            .ok()?;
+    let Xcm(instructions) = Xcm::<()>::try_from(versioned).ok()?;
+
+    let mut instructions = instructions.into_iter();
+    let Some(Instruction::ReserveAssetDeposited(assets)) = instructions.next() else {
+        return None;
+    };
+    let mut amount: u128 = 0;
+    for asset in assets.into_inner() {
+        if let MultiAsset {
+            id: Concrete(location),
+            fun: Fungible(value),
+        } = asset
+        {
+            if location == MultiLocation::parent() {
+                amount = amount.checked_add(value)?;
+            }
+        }
+    }
+
+    let beneficiary = instructions.find_map(|instruction| match instruction {
+        Instruction::DepositAsset {
+            beneficiary:
+                MultiLocation {
+                    parents: 0,
+                    interior: X1(AccountId32 { id, .. }),
+                },
+            ..
+        } => Some(H256(id)),
+        _ => None,
+    })?;
+
+    (amount > 0).then_some((amount, beneficiary))
+}
+
+/// The coins that the given downward messages mint, in the order of the messages.
+pub fn deposits<V: From<SigCheck>>(messages: &[InboundDownwardMessage]) -> Vec<Output<V>> {
+    messages
+        .iter()
+        .filter_map(|message| parse_reserve_deposit(&message.msg))
+        .map(|(amount, beneficiary)| Output {
+            payload: Coin::<RELAY>(amount).into(),
+            verifier: SigCheck::new(beneficiary).into(),
+        })
+        .collect()
+}
+
+/// The upward message that withdraws the given amount of the relay chain's token from this
+/// parachain's sovereign account, and deposits it to the beneficiary on the relay chain.
+pub fn withdraw_message(amount: u128, beneficiary: H256) -> Vec<u8> {
+    let asset = MultiAsset {
+        id: Concrete(MultiLocation::here()),
+        fun: Fungible(amount),
+    };
+
+    VersionedXcm::<()>::V3(Xcm(vec![
+        Instruction::WithdrawAsset(asset.clone().into()),
+        Instruction::BuyExecution {
+            fees: asset,
+            weight_limit: WeightLimit::Unlimited,
+        },
+        Instruction::DepositAsset {
+            assets: MultiAssetFilter::Wild(AllCounted(1)),
+            beneficiary: MultiLocation::new(
+                0,
+                X1(AccountId32 {
+                    network: None,
+                    id: beneficiary.0,
+                }),
+            ),
+        },
+    ]))
+    .encode()
+}
+
+/// Errors that can occur when moving the relay chain's token.
+#[derive(
+    Serialize,
+    Deserialize,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Clone,
+    Encode,
+    Decode,
+    Hash,
+    Debug,
+    TypeInfo,
+)]
+pub enum ConstraintCheckerError {
+    /// Inputs or peeks were provided while receiving transfers. Received coins are minted from the void.
+    WrongNumberInputs,
+    /// The relay parent noted in the inherent does not match the block into which it was inserted.
+    WrongRelayParent,
+    /// The minted coins do not match the reserve transfers among the block's downward messages.
+    WrongDeposits,
+    /// No coins were consumed while sending a transfer.
+    SpendingNothing,
+    /// An input or output is not a relay chain coin.
+    BadlyTyped,
+    /// Nothing was sent to the relay chain.
+    ZeroTransfer,
+    /// The value of the consumed coins does not cover the outputs and the transfer.
+    InsufficientInputs,
+    /// The value of the coins overflowed while being added up.
+    ValueOverflow,
+}
+
+/// A constraint checker that mints the relay chain coins that reserve transfers to this parachain
+/// deposited.
+///
+/// This is expected to be performed through an inherent, and to happen exactly once per block.
+///
+/// There are no inputs. There is one output for each reserve transfer among the downward messages,
+/// in the order of the messages. The relay parent number is part of the checker so that two
+/// identical transfers in different blocks do not have the same output references.
+#[derive(
+    Serialize,
+    Deserialize,
+    Encode,
+    Decode,
+    DebugNoBound,
+    DefaultNoBound,
+    PartialEq,
+    Eq,
+    CloneNoBound,
+    TypeInfo,
+)]
+#[scale_info(skip_type_params(T))]
+pub struct ReceiveReserveTransfers<T> {
+    /// The relay parent of the block whose downward messages are received.
+    pub relay_parent_number: u32,
+    _phantom: PhantomData<T>,
+}
+
+impl<T> ReceiveReserveTransfers<T> {
+    pub fn new(relay_parent_number: u32) -> Self {
+        Self {
+            relay_parent_number,
+            _phantom: PhantomData,
+        }
+    }
+}
+
+impl<T: XcmTransferConfig + 'static, V: Verifier + PartialEq + From<SigCheck>> ConstraintChecker<V>
+    for ReceiveReserveTransfers<T>
+{
+    type Error = ConstraintCheckerError;
+    type InherentHooks = TuxedoInherentAdapter<Self>;
+
+    fn check(
+        &self,
+        input_data: &[Output<V>],
+        peek_data: &[Output<V>],
+        output_data: &[Output<V>],
+        _context: &CheckerContext,
+    ) -> Result<TransactionPriority, Self::Error> {
+        ensure!(
+            input_data.is_empty() && peek_data.is_empty(),
+            ConstraintCheckerError::WrongNumberInputs
+        );
+        ensure!(
+            self.relay_parent_number == T::GetRelayParentNumber::get(),
+            ConstraintCheckerError::WrongRelayParent
+        );
+        ensure!(
+            output_data == deposits::<V>(&T::GetDownwardMessages::get()),
+            ConstraintCheckerError::WrongDeposits
+        );
+
+        Ok(0)
+    }
+
+    fn is_inherent(&self) -> bool {
+        true
+    }
+}
+
+impl<V: Verifier + PartialEq + From<SigCheck>, T: XcmTransferConfig + 'static>
+    TuxedoInherent<V, Self> for ReceiveReserveTransfers<T>
+{
+    type Error = MakeFatalError<ConstraintCheckerError>;
+    // This sorts after the parachain inherent, so the downward messages are in storage by the time this runs.
+    const INHERENT_IDENTIFIER: sp_inherents::InherentIdentifier = *b"xcm_recv";
+
+    fn create_inherent(
+        authoring_inherent_data: &InherentData,
+        _previous_inherent: (Transaction<V, Self>, H256),
+    ) -> Transaction<V, Self> {
+        // The parachain inherent has not been applied yet, so read the messages from the inherent data.
+        let parachain_data: ParachainInherentData = authoring_inherent_data
+            .get_data(&PARACHAIN_INHERENT_IDENTIFIER)
+            .expect("Inherent data should decode properly")
+            .expect("Parachain inherent data should be present.");
+
+        let outputs = deposits(&parachain_data.downward_messages);
+        log::debug!(
+            target: LOG_TARGET,
+            "Receiving {} reserve transfers from {} downward messages.",
+            outputs.len(),
+            parachain_data.downward_messages.len()
+        );
+
+        Transaction {
+            version: TransactionVersion::LATEST,
+            inputs: Vec::new(),
+            peeks: Vec::new(),
+            outputs,
+            checker: Self::new(parachain_data.validation_data.relay_parent_number),
+            memo: None,
+            valid_until: None,
+        }
+    }
+
+    fn check_inherent(
+        _importing_inherent_data: &InherentData,
+        _inherent: Transaction<V, Self>,
+        _result: &mut CheckInherentsResult,
+    ) {
+        // The deposits are checked against the downward messages on-chain,
+        // so no off-chain checks are required.
+    }
+
+    #[cfg(feature = "std")]
+    fn genesis_transactions() -> Vec<Transaction<V, Self>> {
+        // The inherent must appear in every block, including genesis, so that
+        // block authors always find a previous inherent to build on.
+        vec![Transaction {
+            version: TransactionVersion::LATEST,
+            inputs: Vec::new(),
+            peeks: Vec::new(),
+            outputs: Vec::new(),
+            checker: Self::default(),
+            memo: None,
+            valid_until: None,
+        }]
+    }
+}
+
+/// A constraint checker that burns relay chain coins and sends the same amount to a beneficiary
+/// on the relay chain.
+///
+/// The inputs are the burned coins, and the outputs are any change. Whatever value is left over
+/// after the outputs and the transfer is burned too. Relay chain coins are not the native coin
+/// that tips are paid in, so it does not count towards the transaction's fee and tip.
+#[derive(
+    Serialize, Deserialize, Encode, Decode, DebugNoBound, PartialEq, Eq, CloneNoBound, TypeInfo,
+)]
+#[scale_info(skip_type_params(T))]
+pub struct BurnAndSend<T> {
+    /// The sr25519 public key of the account on the relay chain that receives the transfer.
+    pub beneficiary: H256,
+    /// The amount that is sent to the relay chain.
+    pub amount: u128,
+    _phantom: PhantomData<T>,
+}
+
+impl<T> BurnAndSend<T> {
+    pub fn new(beneficiary: H256, amount: u128) -> Self {
+        Self {
+            beneficiary,
+            amount,
+            _phantom: PhantomData,
+        }
+    }
+}
+
+/// The total value of the given relay chain coins.
+fn total_value(data: &[DynamicallyTypedData]) -> Result<u128, ConstraintCheckerError> {
+    data.iter().try_fold(0u128, |total, coin| {
+        let value = coin
+            .extract::<Coin<RELAY>>()
+            .map_err(|_| ConstraintCheckerError::BadlyTyped)?
+            .0;
+        total
+            .checked_add(value)
+            .ok_or(ConstraintCheckerError::ValueOverflow)
+    })
+}
+
+impl<T: XcmTransferConfig> SimpleConstraintChecker for BurnAndSend<T> {
+    type Error = ConstraintCheckerError;
+
+    fn check(
+        &self,
+        input_data: &[DynamicallyTypedData],
+        _peek_data: &[DynamicallyTypedData],
+        output_data: &[DynamicallyTypedData],
+        _context: &CheckerContext,
+    ) -> Result<TransactionPriority, Self::Error> {
+        ensure!(
+            !input_data.is_empty(),
+            ConstraintCheckerError::SpendingNothing
+        );
+        ensure!(self.amount > 0, ConstraintCheckerError::ZeroTransfer);
+
+        let total_input_value = total_value(input_data)?;
+        let spent = total_value(output_data)?
+            .checked_add(self.amount)
+            .ok_or(ConstraintCheckerError::ValueOverflow)?;
+        let burned = total_input_value
+            .checked_sub(spent)
+            .ok_or(ConstraintCheckerError::InsufficientInputs)?;
+
+        // SIDE EFFECT: Withdraw the transfer from the sovereign account on the relay chain
+        T::SendUpwardMessage::send(withdraw_message(self.amount, self.beneficiary));
+
+        Ok(money::surplus::<RELAY, T>(burned))
+    }
+}
@@ -0,0 +1,341 @@
+//! Unit tests for the XCM Transfer piece
+
+use super::*;
+use std::cell::RefCell;
+use tuxedo_parachain_core::tuxedo_core::{tuxedo_verifier, verifier::UpForGrabs};
+use xcm::v3::{Junction::Parachain, Junctions::Here};
+
+/// A verifier that can represent beneficiaries and the void.
+#[derive(Serialize, Deserialize, Encode, Decode, Debug, PartialEq, Eq, Clone, TypeInfo)]
+#[tuxedo_verifier]
+pub enum TestVerifier {
+    SigCheck(SigCheck),
+    UpForGrabs(UpForGrabs),
+}
+
+thread_local! {
+    static UPWARD_MESSAGES: RefCell<Vec<Vec<u8>>> = RefCell::new(Vec::new());
+}
+
+/// The mock config says the relay parent is number seven, and that the relay chain delivered
+/// one transfer of 100 to Alice, a message that is not a transfer, and a transfer of 50 to Bob.
+/// Upward messages are collected in a thread local.
+pub struct TestConfig;
+
+impl GetRelayParentNumberStorage for TestConfig {
+    fn get() -> u32 {
+        7
+    }
+}
+
+impl GetDownwardMessagesStorage for TestConfig {
+    fn get() -> Vec<InboundDownwardMessage> {
+        vec![
+            downward(reserve_transfer(100, alice())),
+            downward(vec![1, 2, 3]),
+            downward(reserve_transfer(50, bob())),
+        ]
+    }
+}
+
+impl SendUpwardMessage for TestConfig {
+    fn send(message: Vec<u8>) {
+        UPWARD_MESSAGES.with(|messages| messages.borrow_mut().push(message));
+    }
+}
+
+impl MoneyConfig for TestConfig {}
+
+impl XcmTransferConfig for TestConfig {
+    type GetRelayParentNumber = Self;
+    type GetDownwardMessages = Self;
+    type SendUpwardMessage = Self;
+}
+
+type Receive = ReceiveReserveTransfers<TestConfig>;
+type Burn = BurnAndSend<TestConfig>;
+
+fn alice() -> H256 {
+    H256::repeat_byte(1)
+}
+
+fn bob() -> H256 {
+    H256::repeat_byte(2)
+}
+
+fn downward(msg: Vec<u8>) -> InboundDownwardMessage {
+    InboundDownwardMessage { sent_at: 7, msg }
+}
+
+/// The message that the relay chain sends when somebody reserve transfers its token to us.
+fn reserve_transfer(amount: u128, beneficiary: H256) -> Vec<u8> {
+    let asset = MultiAsset {
+        id: Concrete(MultiLocation::parent()),
+        fun: Fungible(amount),
+    };
+
+    VersionedXcm::<()>::V3(Xcm(vec![
+        Instruction::ReserveAssetDeposited(asset.clone().into()),
+        Instruction::ClearOrigin,
+        Instruction::BuyExecution {
+            fees: asset,
+            weight_limit: WeightLimit::Unlimited,
+        },
+        Instruction::DepositAsset {
+            assets: MultiAssetFilter::Wild(AllCounted(1)),
+            beneficiary: MultiLocation::new(
+                0,
+                X1(AccountId32 {
+                    network: None,
+                    id: beneficiary.0,
+                }),
+            ),
+        },
+    ]))
+    .encode()
+}
+
+fn coin(value: u128, owner: H256) -> Output<TestVerifier> {
+    Output {
+        payload: Coin::<RELAY>(value).into(),
+        verifier: SigCheck::new(owner).into(),
+    }
+}
+
+fn take_upward_messages() -> Vec<Vec<u8>> {
+    UPWARD_MESSAGES.with(|messages| messages.take())
+}
+
+#[test]
+fn parse_reserve_transfer_works() {
+    assert_eq!(
+        parse_reserve_deposit(&reserve_transfer(100, alice())),
+        Some((100, alice()))
+    );
+}
+
+#[test]
+fn parse_ignores_garbage() {
+    assert_eq!(parse_reserve_deposit(&[1, 2, 3]), None);
+}
+
+#[test]
+fn parse_ignores_other_assets() {
+    let asset = MultiAsset {
+        id: Concrete(MultiLocation::new(1, X1(Parachain(1000)))),
+        fun: Fungible(100),
+    };
+    let message = VersionedXcm::<()>::V3(Xcm(vec![
+        Instruction::ReserveAssetDeposited(asset.into()),
+        Instruction::DepositAsset {
+            assets: MultiAssetFilter::Wild(AllCounted(1)),
+            beneficiary: MultiLocation::new(
+                0,
+                X1(AccountId32 {
+                    network: None,
+                    id: alice().0,
+                }),
+            ),
+        },
+    ]))
+    .encode();
+
+    assert_eq!(parse_reserve_deposit(&message), None);
+}
+
+#[test]
+fn parse_ignores_missing_beneficiary() {
+    let asset = MultiAsset {
+        id: Concrete(MultiLocation::parent()),
+        fun: Fungible(100),
+    };
+    let message =
+        VersionedXcm::<()>::V3(Xcm(vec![Instruction::ReserveAssetDeposited(asset.into())]))
+            .encode();
+
+    assert_eq!(parse_reserve_deposit(&message), None);
+}
+
+#[test]
+fn receive_happy_path() {
+    assert_eq!(
+        Receive::new(7).check(
+            &[],
+            &[],
+            &[coin(100, alice()), coin(50, bob())],
+            &CheckerContext::default()
+        ),
+        Ok(0)
+    );
+}
+
+#[test]
+fn receive_with_inputs_fails() {
+    assert_eq!(
+        Receive::new(7).check(
+            &[coin(1, alice())],
+            &[],
+            &[coin(100, alice()), coin(50, bob())],
+            &CheckerContext::default()
+        ),
+        Err(ConstraintCheckerError::WrongNumberInputs)
+    );
+}
+
+#[test]
+fn receive_at_wrong_relay_parent_fails() {
+    assert_eq!(
+        Receive::new(6).check(
+            &[],
+            &[],
+            &[coin(100, alice()), coin(50, bob())],
+            &CheckerContext::default()
+        ),
+        Err(ConstraintCheckerError::WrongRelayParent)
+    );
+}
+
+#[test]
+fn receive_missing_deposit_fails() {
+    assert_eq!(
+        Receive::new(7).check(&[], &[], &[coin(100, alice())], &CheckerContext::default()),
+        Err(ConstraintCheckerError::WrongDeposits)
+    );
+}
+
+#[test]
+fn receive_inflated_deposit_fails() {
+    assert_eq!(
+        Receive::new(7).check(
+            &[],
+            &[],
+            &[coin(100, alice()), coin(500, bob())],
+            &CheckerContext::default()
+        ),
+        Err(ConstraintCheckerError::WrongDeposits)
+    );
+}
+
+#[test]
+fn receive_to_wrong_beneficiary_fails() {
+    assert_eq!(
+        Receive::new(7).check(
+            &[],
+            &[],
+            &[coin(100, bob()), coin(50, bob())],
+            &CheckerContext::default()
+        ),
+        Err(ConstraintCheckerError::WrongDeposits)
+    );
+}
+
+#[test]
+fn send_happy_path() {
+    take_upward_messages();
+    let input = Coin::<RELAY>(100).into();
+    let change = Coin::<RELAY>(30).into();
+
+    assert_eq!(
+        SimpleConstraintChecker::check(
+            &Burn::new(bob(), 60),
+            &[input],
+            &[],
+            &[change],
+            &CheckerContext::default()
+        ),
+        // The leftover relay chain coins are burned without tipping in native coins
+        Ok(0)
+    );
+    assert_eq!(take_upward_messages(), vec![withdraw_message(60, bob())]);
+}
+
+#[test]
+fn send_withdraws_relay_token_to_beneficiary() {
+    let message = VersionedXcm::<()>::decode(&mut &withdraw_message(60, bob())[..]).unwrap();
+    let Ok(Xcm(instructions)) = Xcm::<()>::try_from(message) else {
+        panic!("withdraw message should be v3");
+    };
+
+    let here = MultiAsset {
+        id: Concrete(MultiLocation::new(0, Here)),
+        fun: Fungible(60),
+    };
+    assert_eq!(instructions[0], Instruction::WithdrawAsset(here.into()));
+    assert!(matches!(
+        instructions[2],
+        Instruction::DepositAsset {
+            beneficiary: MultiLocation {
+                parents: 0,
+                interior: X1(AccountId32 { id, .. }),
+            },
+            ..
+        } if id == bob().0
+    ));
+}
+
+#[test]
+fn send_nothing_fails() {
+    take_upward_messages();
+    let input = Coin::<RELAY>(100).into();
+
+    assert_eq!(
+        SimpleConstraintChecker::check(
+            &Burn::new(bob(), 0),
+            &[input],
+            &[],
+            &[],
+            &CheckerContext::default()
+        ),
+        Err(ConstraintCheckerError::ZeroTransfer)
+    );
+    assert!(take_upward_messages().is_empty());
+}
+
+#[test]
+fn send_without_inputs_fails() {
+    assert_eq!(
+        SimpleConstraintChecker::check(
+            &Burn::new(bob(), 60),
+            &[],
+            &[],
+            &[],
+            &CheckerContext::default()
+        ),
+        Err(ConstraintCheckerError::SpendingNothing)
+    );
+}
+
+#[test]
+fn send_more_than_inputs_fails() {
+    take_upward_messages();
+    let input = Coin::<RELAY>(100).into();
+    let change = Coin::<RELAY>(50).into();
+
+    assert_eq!(
+        SimpleConstraintChecker::check(
+            &Burn::new(bob(), 60),
+            &[input],
+            &[],
+            &[change],
+            &CheckerContext::default()
+        ),
+        Err(ConstraintCheckerError::InsufficientInputs)
+    );
+    assert!(take_upward_messages().is_empty());
+}
+
+#[test]
+fn send_native_coins_fails() {
+    let input = Coin::<0>(100).into();
+
+    assert_eq!(
+        SimpleConstraintChecker::check(
+            &Burn::new(bob(), 60),
+            &[input],
+            &[],
+            &[],
+            &CheckerContext::default()
+        ),
+        Err(ConstraintCheckerError::BadlyTyped)
+    );
+}